@@ -0,0 +1,194 @@
+//! Decoding of compressed NOTIFY bodies.
+//!
+//! Some firmware compresses large NOTIFY bodies (typically ZoneGroupTopology)
+//! and says so in `Content-Encoding`. Bodies are decompressed before routing
+//! so consumers always receive XML text.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::Read;
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+
+/// Compression a NOTIFY body arrived with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Gzip,
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Parse a `Content-Encoding` header value.
+    ///
+    /// Returns `Ok(None)` for an absent or `identity` encoding, and the
+    /// unrecognised value as the error.
+    pub fn from_header(value: Option<&str>) -> Result<Option<Self>, String> {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+        match value.to_ascii_lowercase().as_str() {
+            "identity" => Ok(None),
+            "gzip" | "x-gzip" => Ok(Some(Self::Gzip)),
+            "deflate" => Ok(Some(Self::Deflate)),
+            _ => Err(value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ContentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        })
+    }
+}
+
+/// Why a NOTIFY body could not be turned into event XML.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DecodeError {
+    /// `Content-Encoding` names a compression this server can't undo
+    #[error("unsupported Content-Encoding {0:?}")]
+    UnsupportedEncoding(String),
+    /// The body decompresses to more than the configured limit
+    #[error("{encoding} body exceeds {limit} bytes when decompressed")]
+    TooLarge {
+        encoding: ContentEncoding,
+        limit: usize,
+    },
+    /// The body isn't valid data for its encoding
+    #[error("corrupt {encoding} body: {reason}")]
+    Corrupt {
+        encoding: ContentEncoding,
+        reason: String,
+    },
+}
+
+/// Decompress `body` according to `encoding`, reading at most `limit` bytes
+/// of output.
+pub(crate) fn decode_body(
+    body: &[u8],
+    encoding: Option<ContentEncoding>,
+    limit: usize,
+) -> Result<Cow<'_, [u8]>, DecodeError> {
+    let Some(encoding) = encoding else {
+        return Ok(Cow::Borrowed(body));
+    };
+    let reader: Box<dyn Read + '_> = match encoding {
+        ContentEncoding::Gzip => Box::new(GzDecoder::new(body)),
+        // HTTP deflate is zlib-wrapped, but some servers send raw deflate
+        ContentEncoding::Deflate if is_zlib(body) => Box::new(ZlibDecoder::new(body)),
+        ContentEncoding::Deflate => Box::new(DeflateDecoder::new(body)),
+    };
+
+    let mut decoded = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| DecodeError::Corrupt {
+            encoding,
+            reason: e.to_string(),
+        })?;
+    if decoded.len() > limit {
+        return Err(DecodeError::TooLarge { encoding, limit });
+    }
+    Ok(Cow::Owned(decoded))
+}
+
+/// Whether `body` starts with a zlib header (RFC 1950)
+fn is_zlib(body: &[u8]) -> bool {
+    match body {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    const XML: &[u8] = b"<e:propertyset><ZoneGroupState/></e:propertyset>";
+
+    fn compress<W: Write>(mut encoder: W, finish: impl FnOnce(W) -> Vec<u8>) -> Vec<u8> {
+        encoder.write_all(XML).unwrap();
+        finish(encoder)
+    }
+
+    #[test]
+    fn test_header_parsing() {
+        assert_eq!(ContentEncoding::from_header(None), Ok(None));
+        assert_eq!(ContentEncoding::from_header(Some("identity")), Ok(None));
+        assert_eq!(
+            ContentEncoding::from_header(Some(" GZIP ")),
+            Ok(Some(ContentEncoding::Gzip))
+        );
+        assert_eq!(
+            ContentEncoding::from_header(Some("br")),
+            Err("br".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decodes_every_deflate_flavour() {
+        let gzip = compress(GzEncoder::new(Vec::new(), Compression::default()), |e| {
+            e.finish().unwrap()
+        });
+        let zlib = compress(ZlibEncoder::new(Vec::new(), Compression::default()), |e| {
+            e.finish().unwrap()
+        });
+        let raw = compress(
+            DeflateEncoder::new(Vec::new(), Compression::default()),
+            |e| e.finish().unwrap(),
+        );
+        for (body, encoding) in [
+            (gzip, ContentEncoding::Gzip),
+            (zlib, ContentEncoding::Deflate),
+            (raw, ContentEncoding::Deflate),
+        ] {
+            assert_eq!(decode_body(&body, Some(encoding), 1024).unwrap(), XML);
+        }
+        assert!(matches!(
+            decode_body(XML, Some(ContentEncoding::Gzip), 1024),
+            Err(DecodeError::Corrupt { .. })
+        ));
+    }
+
+    /// Bodies that start like a real gzip or zlib stream and then go wrong
+    fn hostile_body() -> impl proptest::strategy::Strategy<Value = Vec<u8>> {
+        use proptest::prelude::*;
+        let header = prop_oneof![
+            Just(vec![]),
+            Just(vec![0x1f, 0x8b, 0x08, 0x00]),
+            Just(vec![0x78, 0x9c]),
+        ];
+        (header, proptest::collection::vec(any::<u8>(), 0..256)).prop_map(|(mut body, rest)| {
+            body.extend(rest);
+            body
+        })
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn fuzz_decode_body(
+            body in hostile_body(),
+            header in "(?i)gzip|x-gzip|deflate|identity|\\PC{0,8}",
+        ) {
+            if let Ok(encoding) = ContentEncoding::from_header(Some(&header)) {
+                if let Ok(xml) = decode_body(&body, encoding, 64) {
+                    proptest::prop_assert!(xml.len() <= 64 || encoding.is_none());
+                }
+            }
+        }
+    }
+}
@@ -7,7 +7,7 @@
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, RwLock};
 use tracing::{debug, info, warn};
 
@@ -51,8 +51,8 @@ impl Default for FirewallDetectionConfig {
 pub struct DeviceFirewallState {
     pub device_ip: IpAddr,
     pub status: FirewallStatus,
-    pub first_subscription_time: SystemTime,
-    pub first_event_time: Option<SystemTime>,
+    pub first_subscription_time: Instant,
+    pub first_event_time: Option<Instant>,
     pub detection_completed: bool,
     pub timeout_duration: Duration,
 }
@@ -202,13 +202,11 @@ impl FirewallDetectionCoordinator {
 
             if !state.detection_completed {
                 // First event received - mark as accessible
-                state.first_event_time = Some(SystemTime::now());
+                state.first_event_time = Some(Instant::now());
                 state.status = FirewallStatus::Accessible;
                 state.detection_completed = true;
 
-                let elapsed = SystemTime::now()
-                    .duration_since(state.first_subscription_time)
-                    .unwrap_or(Duration::ZERO);
+                let elapsed = state.first_subscription_time.elapsed();
 
                 // Notify completion
                 let _ = self.detection_complete_tx.send(DetectionResult {
@@ -257,7 +255,7 @@ impl FirewallDetectionCoordinator {
         let new_state = Arc::new(RwLock::new(DeviceFirewallState {
             device_ip,
             status: FirewallStatus::Unknown,
-            first_subscription_time: SystemTime::now(),
+            first_subscription_time: Instant::now(),
             first_event_time: None,
             detection_completed: false,
             timeout_duration: self.config.event_wait_timeout,
@@ -295,9 +293,7 @@ impl FirewallDetectionCoordinator {
                 let mut state = state_arc.write().await;
 
                 if !state.detection_completed {
-                    let elapsed = SystemTime::now()
-                        .duration_since(state.first_subscription_time)
-                        .unwrap_or(Duration::ZERO);
+                    let elapsed = state.first_subscription_time.elapsed();
 
                     if elapsed >= state.timeout_duration {
                         // Timeout reached - mark as blocked
@@ -137,13 +137,15 @@
 //! This crate is intended for internal use within the workspace and is not published
 //! to crates.io. It provides the foundation for device-specific event handling layers.
 
+pub mod encoding;
 pub mod firewall_detection;
 pub mod router;
 mod server;
 
+pub use encoding::{ContentEncoding, DecodeError};
 pub use firewall_detection::{
     CoordinatorStats, DetectionReason, DetectionResult, DeviceFirewallState,
     FirewallDetectionConfig, FirewallDetectionCoordinator, FirewallStatus,
 };
 pub use router::{EventRouter, NotificationPayload};
-pub use server::CallbackServer;
+pub use server::{CallbackServer, ConnectionLimits, PortSelection, StartupInfo, SERVER_HEADER};
@@ -6,12 +6,15 @@
 //! registration completes, preventing the race between SUBSCRIBE response
 //! and initial NOTIFY delivery.
 
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, RwLock};
 use tracing::debug;
 
+use crate::encoding::{ContentEncoding, DecodeError};
+
 /// Maximum time a buffered event is kept before being discarded.
 /// The race window is typically microseconds; 5 seconds handles any
 /// pathological scheduling delay.
@@ -20,23 +23,40 @@ const BUFFER_TTL: Duration = Duration::from_secs(5);
 /// Generic notification payload for UPnP event notifications.
 ///
 /// This represents an unparsed UPnP event notification that has been received
-/// via HTTP callback. It contains only the subscription ID and raw XML body,
+/// via HTTP callback. It contains only the subscription ID and XML body,
 /// with no device-specific context.
+///
+/// It is the workspace's only raw-event type; `sonos-stream` consumes it
+/// as-is, without an intermediate adapter struct.
 #[derive(Debug, Clone)]
 pub struct NotificationPayload {
     /// The subscription ID from the UPnP SID header
     pub subscription_id: String,
-    /// The raw XML event body
+    /// The XML event body, already decompressed
     pub event_xml: String,
+    /// The event sequence number from the UPnP SEQ header, if present
+    pub seq: Option<u32>,
+    /// Compression the body arrived with, for diagnostics
+    pub content_encoding: Option<ContentEncoding>,
+    /// Why the body couldn't be decoded; `event_xml` is empty when set
+    pub decode_error: Option<DecodeError>,
+    /// Address of the device that sent the NOTIFY, when known
+    ///
+    /// SIDs are only unique per device: some firmware reissues the same SIDs
+    /// after a reboot, so the sender is needed to tell which subscription an
+    /// event belongs to.
+    pub sender: Option<IpAddr>,
 }
 
 /// Internal state protected by a single lock to eliminate TOCTOU gaps.
 struct RouterState {
-    subscriptions: HashSet<String>,
-    /// Flat buffer of (subscription_id, event_xml, buffered_at).
+    /// Registered SIDs, counted: devices can hand out the same SID, and one
+    /// of them unregistering mustn't stop events for the others
+    subscriptions: HashMap<String, usize>,
+    /// Flat buffer of (payload, buffered_at).
     /// Expected size: 0-5 entries. Only populated during the microsecond
     /// race window between SUBSCRIBE response and register() call.
-    pending: Vec<(String, String, Instant)>,
+    pending: Vec<(NotificationPayload, Instant)>,
 }
 
 /// Routes events from HTTP callbacks to a channel.
@@ -74,7 +94,7 @@ impl EventRouter {
     pub fn new(event_sender: mpsc::UnboundedSender<NotificationPayload>) -> Self {
         Self {
             state: Arc::new(RwLock::new(RouterState {
-                subscriptions: HashSet::new(),
+                subscriptions: HashMap::new(),
                 pending: Vec::new(),
             })),
             event_sender,
@@ -86,22 +106,24 @@ impl EventRouter {
     /// Adds the SID to the active set and replays any buffered events that
     /// arrived before registration (the SUBSCRIBE/NOTIFY race window).
     /// Also cleans up stale buffer entries older than `BUFFER_TTL`.
+    ///
+    /// Registering a SID that is already registered counts it again; it
+    /// stays active until unregistered as many times.
     pub async fn register(&self, subscription_id: String) {
         let mut state = self.state.write().await;
-        state.subscriptions.insert(subscription_id.clone());
+        *state
+            .subscriptions
+            .entry(subscription_id.clone())
+            .or_default() += 1;
 
         // Replay buffered events for this SID and remove stale entries.
         let now = Instant::now();
         let mut i = 0;
         while i < state.pending.len() {
-            let (ref sid, _, buffered_at) = state.pending[i];
-            if sid == &subscription_id {
-                let (_, xml, _) = state.pending.swap_remove(i);
+            let (ref payload, buffered_at) = state.pending[i];
+            if payload.subscription_id == subscription_id {
+                let (payload, _) = state.pending.swap_remove(i);
                 debug!(sid = %subscription_id, "Replayed buffered event");
-                let payload = NotificationPayload {
-                    subscription_id: subscription_id.clone(),
-                    event_xml: xml,
-                };
                 let _ = self.event_sender.send(payload);
                 // Don't increment i — swap_remove moved the last element here
             } else if now.duration_since(buffered_at) > BUFFER_TTL {
@@ -115,12 +137,24 @@ impl EventRouter {
 
     /// Unregister a subscription ID.
     ///
-    /// Removes the SID from the active set and drains any buffered events
-    /// for it, preventing stale replays on future re-registration.
+    /// Removes the SID from the active set once its last registration is
+    /// gone, and drains any buffered events for it, preventing stale
+    /// replays on future re-registration.
     pub async fn unregister(&self, subscription_id: &str) {
         let mut state = self.state.write().await;
-        state.subscriptions.remove(subscription_id);
-        state.pending.retain(|(sid, _, _)| sid != subscription_id);
+        match state.subscriptions.get_mut(subscription_id) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                return;
+            }
+            Some(_) => {
+                state.subscriptions.remove(subscription_id);
+            }
+            None => {}
+        }
+        state
+            .pending
+            .retain(|(payload, _)| payload.subscription_id != subscription_id);
     }
 
     /// Route an incoming event to the unified event stream.
@@ -129,19 +163,29 @@ impl EventRouter {
     /// If not, the event is buffered for replay when `register()` is called.
     /// The caller should always return HTTP 200 OK — buffered events are
     /// accepted for processing, not rejected.
-    pub async fn route_event(&self, subscription_id: String, event_xml: String) {
+    pub async fn route_event(&self, subscription_id: String, event_xml: String, seq: Option<u32>) {
+        self.route(NotificationPayload {
+            subscription_id,
+            event_xml,
+            seq,
+            content_encoding: None,
+            decode_error: None,
+            sender: None,
+        })
+        .await;
+    }
+
+    /// Route a complete payload, such as one carrying a decode error.
+    ///
+    /// Buffers like [`route_event()`](Self::route_event) when the SID isn't
+    /// registered yet.
+    pub async fn route(&self, payload: NotificationPayload) {
         let mut state = self.state.write().await;
-        if state.subscriptions.contains(&subscription_id) {
-            let payload = NotificationPayload {
-                subscription_id,
-                event_xml,
-            };
+        if state.subscriptions.contains_key(&payload.subscription_id) {
             let _ = self.event_sender.send(payload);
         } else {
-            debug!(sid = %subscription_id, "Buffered event for pending SID");
-            state
-                .pending
-                .push((subscription_id, event_xml, Instant::now()));
+            debug!(sid = %payload.subscription_id, "Buffered event for pending SID");
+            state.pending.push((payload, Instant::now()));
         }
     }
 }
@@ -162,12 +206,15 @@ mod tests {
 
         // Route an event
         let event_xml = "<event>test</event>".to_string();
-        router.route_event(sub_id.clone(), event_xml.clone()).await;
+        router
+            .route_event(sub_id.clone(), event_xml.clone(), Some(3))
+            .await;
 
         // Verify payload was sent
         let payload = rx.recv().await.unwrap();
         assert_eq!(payload.subscription_id, sub_id);
         assert_eq!(payload.event_xml, event_xml);
+        assert_eq!(payload.seq, Some(3));
     }
 
     #[tokio::test]
@@ -183,7 +230,7 @@ mod tests {
 
         // Route an event — should be buffered (not delivered), since SID is unregistered
         let event_xml = "<event>test</event>".to_string();
-        router.route_event(sub_id, event_xml).await;
+        router.route_event(sub_id, event_xml, None).await;
 
         // No immediate payload — event was buffered, not routed
         assert!(rx.try_recv().is_err());
@@ -196,7 +243,11 @@ mod tests {
 
         // Route event for unknown subscription — should be buffered, not dropped
         router
-            .route_event("unknown-sub".to_string(), "<event>test</event>".to_string())
+            .route_event(
+                "unknown-sub".to_string(),
+                "<event>test</event>".to_string(),
+                None,
+            )
             .await;
 
         // No immediate payload — event was buffered
@@ -215,7 +266,9 @@ mod tests {
             "<e:propertyset><CurrentPlayMode>NORMAL</CurrentPlayMode></e:propertyset>".to_string();
 
         // 1. Event arrives BEFORE register (the race condition)
-        router.route_event(sub_id.clone(), event_xml.clone()).await;
+        router
+            .route_event(sub_id.clone(), event_xml.clone(), None)
+            .await;
 
         // 2. Register happens moments later
         router.register(sub_id.clone()).await;
@@ -236,8 +289,14 @@ mod tests {
         {
             let mut state = router.state.write().await;
             state.pending.push((
-                "uuid:stale-sid".to_string(),
-                "<event>stale</event>".to_string(),
+                NotificationPayload {
+                    subscription_id: "uuid:stale-sid".to_string(),
+                    event_xml: "<event>stale</event>".to_string(),
+                    seq: None,
+                    content_encoding: None,
+                    decode_error: None,
+                    sender: None,
+                },
                 Instant::now() - Duration::from_secs(10), // 10s ago, well past TTL
             ));
         }
@@ -263,7 +322,7 @@ mod tests {
 
         // Buffer an event
         router
-            .route_event(sub_id.clone(), "<event>buffered</event>".to_string())
+            .route_event(sub_id.clone(), "<event>buffered</event>".to_string(), None)
             .await;
 
         // Unregister — should drain the buffered event
@@ -286,10 +345,10 @@ mod tests {
 
         // Buffer two events before registering
         router
-            .route_event(sub_id.clone(), "<event>first</event>".to_string())
+            .route_event(sub_id.clone(), "<event>first</event>".to_string(), None)
             .await;
         router
-            .route_event(sub_id.clone(), "<event>second</event>".to_string())
+            .route_event(sub_id.clone(), "<event>second</event>".to_string(), None)
             .await;
 
         // Register — both events should be replayed
@@ -313,10 +372,18 @@ mod tests {
 
         // Buffer events for two different SIDs
         router
-            .route_event("uuid:sid-a".to_string(), "<event>a</event>".to_string())
+            .route_event(
+                "uuid:sid-a".to_string(),
+                "<event>a</event>".to_string(),
+                None,
+            )
             .await;
         router
-            .route_event("uuid:sid-b".to_string(), "<event>b</event>".to_string())
+            .route_event(
+                "uuid:sid-b".to_string(),
+                "<event>b</event>".to_string(),
+                None,
+            )
             .await;
 
         // Register only SID-A
@@ -337,4 +404,29 @@ mod tests {
         assert_eq!(p2.subscription_id, "uuid:sid-b");
         assert!(p2.event_xml.contains("b"));
     }
+
+    /// Two devices holding the same SID: one unregistering leaves the
+    /// other's events flowing.
+    #[tokio::test]
+    async fn test_shared_sid_stays_registered_until_last_unregister() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let router = EventRouter::new(tx);
+
+        let sub_id = "uuid:recycled-1".to_string();
+        router.register(sub_id.clone()).await;
+        router.register(sub_id.clone()).await;
+        router.unregister(&sub_id).await;
+
+        router
+            .route_event(sub_id.clone(), "<event>still</event>".to_string(), None)
+            .await;
+        let p = rx.try_recv().expect("SID should still be registered");
+        assert!(p.event_xml.contains("still"));
+
+        router.unregister(&sub_id).await;
+        router
+            .route_event(sub_id, "<event>gone</event>".to_string(), None)
+            .await;
+        assert!(rx.try_recv().is_err());
+    }
 }
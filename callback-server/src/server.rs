@@ -1,13 +1,104 @@
 //! HTTP server for receiving UPnP event notifications.
-
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
+
+use std::collections::hash_map::RandomState;
+use std::convert::Infallible;
+use std::hash::BuildHasher;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info, trace};
+use std::time::{Duration, Instant};
+
+use hyper::server::conn::Http;
+use hyper::service::{service_fn, Service};
+use hyper::{Body, Request, Response};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch, OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, error, info, trace, warn};
 use warp::Filter;
 
+use super::encoding::{decode_body, ContentEncoding, DecodeError};
 use super::router::{EventRouter, NotificationPayload};
 
+/// How long shutdown waits for open connections to finish their request
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// `SERVER` header of every response, naming the SDK like its requests do
+pub const SERVER_HEADER: &str = concat!("sonos-sdk/", env!("CARGO_PKG_VERSION"), " UPnP/1.0");
+
+/// Connection handling limits for the callback server.
+///
+/// Sonos devices keep one HTTP/1.1 connection open and deliver many NOTIFYs
+/// over it. These limits bound how long such connections live and how many
+/// can be open at once.
+#[derive(Debug, Clone)]
+pub struct ConnectionLimits {
+    /// Maximum concurrently open connections; extra connections get `503`
+    pub max_connections: usize,
+    /// Close a keep-alive connection after this long without a request
+    pub idle_timeout: Duration,
+    /// Close a connection this long after it was accepted (after its
+    /// in-flight request completes); the device reconnects for the next NOTIFY
+    pub max_lifetime: Duration,
+    /// Largest NOTIFY body accepted after decompression; a compressed body
+    /// that inflates past this gets `413`
+    pub max_decoded_body: usize,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_connections: 128,
+            idle_timeout: Duration::from_secs(60),
+            max_lifetime: Duration::from_secs(600),
+            max_decoded_body: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// How [`CallbackServer`] picks its port within the range it is given.
+///
+/// Whatever the order, the port the process bound last is tried first when
+/// it is in the range. A port in use fails its bind at once and is never
+/// retried.
+#[derive(Debug, Clone)]
+pub struct PortSelection {
+    /// Start the scan at a random port of the range and wrap around, so
+    /// processes given the same range rarely try the same ports
+    pub randomize: bool,
+    /// Ports bound at once while scanning
+    pub max_concurrent_probes: usize,
+}
+
+impl Default for PortSelection {
+    fn default() -> Self {
+        Self {
+            randomize: false,
+            max_concurrent_probes: 8,
+        }
+    }
+}
+
+/// How a [`CallbackServer`] found its port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartupInfo {
+    /// Port the server is bound to
+    pub port: u16,
+    /// Ports a bind was attempted on, the chosen one included
+    pub ports_probed: usize,
+    /// Time spent finding the port
+    pub probe_time: Duration,
+}
+
+/// Port the process bound last, tried first by the next server; 0 for none
+static LAST_PORT: AtomicU16 = AtomicU16::new(0);
+
 /// HTTP callback server for receiving UPnP event notifications.
 ///
 /// The `CallbackServer` binds to a local port and provides an HTTP endpoint
@@ -39,6 +130,8 @@ use super::router::{EventRouter, NotificationPayload};
 pub struct CallbackServer {
     /// The port the server is bound to
     port: u16,
+    /// How the port was found
+    startup: StartupInfo,
     /// The base URL for callback registration
     base_url: String,
     /// Event router for handling incoming events
@@ -92,13 +185,41 @@ impl CallbackServer {
         port_range: (u16, u16),
         event_sender: mpsc::UnboundedSender<NotificationPayload>,
     ) -> Result<Self, String> {
-        // Find an available port in the range
-        let port = Self::find_available_port(port_range.0, port_range.1).ok_or_else(|| {
-            format!(
-                "No available port found in range {}-{}",
-                port_range.0, port_range.1
-            )
-        })?;
+        Self::with_limits(port_range, event_sender, ConnectionLimits::default()).await
+    }
+
+    /// Create and start a callback server with custom [`ConnectionLimits`].
+    ///
+    /// Behaves like [`new()`](Self::new) otherwise.
+    pub async fn with_limits(
+        port_range: (u16, u16),
+        event_sender: mpsc::UnboundedSender<NotificationPayload>,
+        limits: ConnectionLimits,
+    ) -> Result<Self, String> {
+        Self::with_options(port_range, event_sender, limits, PortSelection::default()).await
+    }
+
+    /// Create and start a callback server with custom [`ConnectionLimits`]
+    /// and [`PortSelection`].
+    ///
+    /// Behaves like [`new()`](Self::new) otherwise.
+    pub async fn with_options(
+        port_range: (u16, u16),
+        event_sender: mpsc::UnboundedSender<NotificationPayload>,
+        limits: ConnectionLimits,
+        selection: PortSelection,
+    ) -> Result<Self, String> {
+        // Find an available port in the range, keeping it bound
+        let (listener, startup) =
+            Self::bind_in_range(port_range.0, port_range.1, &selection, &LAST_PORT).ok_or_else(
+                || {
+                    format!(
+                        "No available port found in range {}-{}",
+                        port_range.0, port_range.1
+                    )
+                },
+            )?;
+        let port = startup.port;
 
         // Detect local IP address
         let local_ip = Self::detect_local_ip()
@@ -116,7 +237,14 @@ impl CallbackServer {
         let (ready_tx, mut ready_rx) = mpsc::channel::<()>(1);
 
         // Start the HTTP server
-        let server_handle = Self::start_server(port, event_router.clone(), shutdown_rx, ready_tx);
+        let server_handle = Self::start_server(
+            listener,
+            startup,
+            event_router.clone(),
+            limits,
+            shutdown_rx,
+            ready_tx,
+        );
 
         // Wait for server to be ready
         ready_rx
@@ -126,6 +254,7 @@ impl CallbackServer {
 
         Ok(Self {
             port,
+            startup,
             base_url,
             event_router,
             shutdown_tx: Some(shutdown_tx),
@@ -164,6 +293,11 @@ impl CallbackServer {
         self.port
     }
 
+    /// How the port was found: ports probed and time spent.
+    pub fn startup_info(&self) -> StartupInfo {
+        self.startup
+    }
+
     /// Get a reference to the event router.
     ///
     /// The router can be used to register and unregister subscription IDs
@@ -216,14 +350,77 @@ impl CallbackServer {
         Ok(())
     }
 
-    /// Find an available port in the given range.
-    fn find_available_port(start: u16, end: u16) -> Option<u16> {
-        (start..=end).find(|&port| Self::is_port_available(port))
+    /// Bind the first free port of `start..=end` in `selection`'s order,
+    /// after the port recorded in `last_port`, and record the one bound.
+    ///
+    /// Ports are bound `max_concurrent_probes` at a time; the first free
+    /// one in scan order is kept and the others are released.
+    fn bind_in_range(
+        start: u16,
+        end: u16,
+        selection: &PortSelection,
+        last_port: &AtomicU16,
+    ) -> Option<(TcpListener, StartupInfo)> {
+        let started = Instant::now();
+        if start > end {
+            return None;
+        }
+        let found = |listener: TcpListener, ports_probed| {
+            let port = listener.local_addr().ok()?.port();
+            last_port.store(port, Ordering::Relaxed);
+            Some((
+                listener,
+                StartupInfo {
+                    port,
+                    ports_probed,
+                    probe_time: started.elapsed(),
+                },
+            ))
+        };
+
+        let last = last_port.load(Ordering::Relaxed);
+        let mut probed = 0;
+        if last != 0 && (start..=end).contains(&last) {
+            probed += 1;
+            if let Some(listener) = Self::try_bind(last) {
+                return found(listener, probed);
+            }
+        }
+
+        let len = usize::from(end - start) + 1;
+        let offset = if selection.randomize {
+            let seed = RandomState::new().hash_one(started);
+            usize::try_from(seed % len as u64).unwrap_or(0)
+        } else {
+            0
+        };
+        let candidates: Vec<u16> = (0..len)
+            .map(|i| start.wrapping_add(((offset + i) % len) as u16))
+            .filter(|&port| port != last)
+            .collect();
+
+        for chunk in candidates.chunks(selection.max_concurrent_probes.max(1)) {
+            probed += chunk.len();
+            let bound: Vec<Option<TcpListener>> = std::thread::scope(|scope| {
+                let probes: Vec<_> = chunk
+                    .iter()
+                    .map(|&port| scope.spawn(move || Self::try_bind(port)))
+                    .collect();
+                probes
+                    .into_iter()
+                    .map(|probe| probe.join().ok().flatten())
+                    .collect()
+            });
+            if let Some(listener) = bound.into_iter().flatten().next() {
+                return found(listener, probed);
+            }
+        }
+        None
     }
 
-    /// Check if a port is available for binding.
-    fn is_port_available(port: u16) -> bool {
-        TcpListener::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port)).is_ok()
+    /// Bind `port` on every interface; a port in use fails at once.
+    fn try_bind(port: u16) -> Option<TcpListener> {
+        TcpListener::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port)).ok()
     }
 
     /// Detect the local IP address for callback URLs.
@@ -239,10 +436,12 @@ impl CallbackServer {
         Some(local_addr.ip())
     }
 
-    /// Start the HTTP server on the given port.
+    /// Start the HTTP server on the listener found by `bind_in_range()`.
     fn start_server(
-        port: u16,
+        listener: TcpListener,
+        startup: StartupInfo,
         event_router: Arc<EventRouter>,
+        limits: ConnectionLimits,
         mut shutdown_rx: mpsc::Receiver<()>,
         ready_tx: mpsc::Sender<()>,
     ) -> tokio::task::JoinHandle<()> {
@@ -253,19 +452,26 @@ impl CallbackServer {
                 .and(warp::header::optional::<String>("sid"))
                 .and(warp::header::optional::<String>("nt"))
                 .and(warp::header::optional::<String>("nts"))
+                .and(warp::header::optional::<String>("seq"))
+                .and(warp::header::optional::<String>("content-encoding"))
+                .and(warp::ext::optional::<Peer>())
                 .and(warp::body::bytes())
                 .and_then({
                     let router = event_router.clone();
+                    let max_decoded_body = limits.max_decoded_body;
                     move |method: warp::http::Method,
                           path: warp::path::FullPath,
                           sid: Option<String>,
                           nt: Option<String>,
                           nts: Option<String>,
+                          seq: Option<String>,
+                          content_encoding: Option<String>,
+                          peer: Option<Peer>,
                           body: bytes::Bytes| {
                         let router = router.clone();
                         async move {
                             // Only handle NOTIFY method
-                            if method != warp::http::Method::from_bytes(b"NOTIFY").unwrap() {
+                            if method.as_str() != "NOTIFY" {
                                 return Err(warp::reject::not_found());
                             }
 
@@ -277,24 +483,10 @@ impl CallbackServer {
                                 sid = ?sid,
                                 nt = ?nt,
                                 nts = ?nts,
+                                content_encoding = ?content_encoding,
                                 "Received UPnP NOTIFY event"
                             );
 
-                            // Convert body to string and log content at trace level only
-                            let event_xml = String::from_utf8_lossy(&body).to_string();
-                            if event_xml.len() > 200 {
-                                trace!(
-                                    event_xml_preview = %&event_xml[..200],
-                                    total_length = event_xml.len(),
-                                    "UPnP event XML content (truncated)"
-                                );
-                            } else {
-                                trace!(
-                                    event_xml = %event_xml,
-                                    "UPnP event XML content (full)"
-                                );
-                            }
-
                             // Validate UPnP headers
                             if !Self::validate_upnp_headers(&sid, &nt, &nts) {
                                 error!(
@@ -307,15 +499,69 @@ impl CallbackServer {
                             }
 
                             // Extract subscription ID from SID header (required for UPnP events)
-                            let sub_id = sid.ok_or_else(|| {
+                            let sub_id = sid.as_deref().map(normalize_sid).ok_or_else(|| {
                                 error!("Missing required SID header in UPnP NOTIFY request");
                                 warp::reject::custom(InvalidUpnpHeaders)
                             })?;
 
+                            let seq = seq.and_then(|s| s.trim().parse().ok());
+                            let encoding = ContentEncoding::from_header(content_encoding.as_deref());
+                            let decoded = encoding
+                                .clone()
+                                .map_err(DecodeError::UnsupportedEncoding)
+                                .and_then(|encoding| decode_body(&body, encoding, max_decoded_body));
+                            let mut payload = NotificationPayload {
+                                subscription_id: sub_id.clone(),
+                                event_xml: String::new(),
+                                seq,
+                                content_encoding: encoding.ok().flatten(),
+                                decode_error: None,
+                                sender: peer.map(|p| p.0.ip()),
+                            };
+
+                            // An undecodable body is still routed, so the consumer
+                            // learns about it instead of seeing the event vanish
+                            let xml = match decoded {
+                                Ok(xml) => xml,
+                                Err(e) => {
+                                    warn!(subscription_id = %sub_id, error = %e, "Undecodable UPnP NOTIFY body");
+                                    let code = match e {
+                                        DecodeError::UnsupportedEncoding(_) => {
+                                            warp::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+                                        }
+                                        DecodeError::TooLarge { .. } => {
+                                            warp::http::StatusCode::PAYLOAD_TOO_LARGE
+                                        }
+                                        DecodeError::Corrupt { .. } => {
+                                            warp::http::StatusCode::BAD_REQUEST
+                                        }
+                                    };
+                                    payload.decode_error = Some(e);
+                                    router.route(payload).await;
+                                    return Ok(warp::reply::with_status("", code));
+                                }
+                            };
+
+                            // Convert body to string and log content at trace level only
+                            payload.event_xml = String::from_utf8_lossy(&xml).into_owned();
+                            let event_xml = &payload.event_xml;
+                            if let Some(preview) = xml_preview(event_xml) {
+                                trace!(
+                                    event_xml_preview = %preview,
+                                    total_length = event_xml.len(),
+                                    "UPnP event XML content (truncated)"
+                                );
+                            } else {
+                                trace!(
+                                    event_xml = %event_xml,
+                                    "UPnP event XML content (full)"
+                                );
+                            }
+
                             // Route the event through the unified event stream.
                             // Events are either delivered immediately (registered SID)
                             // or buffered for replay when register() is called.
-                            router.route_event(sub_id.clone(), event_xml).await;
+                            router.route(payload).await;
 
                             debug!(
                                 subscription_id = %sub_id,
@@ -332,23 +578,82 @@ impl CallbackServer {
                 });
 
             // Configure routes with just the NOTIFY endpoint
-            let routes = notify_route.recover(handle_rejection);
-
-            // Create server with graceful shutdown
-            let (addr, server) = warp::serve(routes).bind_with_graceful_shutdown(
-                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port),
-                async move {
-                    shutdown_rx.recv().await;
-                },
-            );
+            let routes = notify_route
+                .recover(handle_rejection)
+                .with(warp::reply::with::header("server", SERVER_HEADER));
+            let service = warp::service(routes);
+
+            let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), startup.port);
+            let listener = match listener
+                .set_nonblocking(true)
+                .and_then(|()| tokio::net::TcpListener::from_std(listener))
+            {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!(address = %addr, error = %e, "CallbackServer failed to listen");
+                    return;
+                }
+            };
 
             info!(
                 address = %addr,
+                ports_probed = startup.ports_probed,
+                probe_time_ms = startup.probe_time.as_millis() as u64,
+                max_connections = limits.max_connections,
                 "CallbackServer listening - ready to process UPnP events"
             );
             // Signal that server is ready
             let _ = ready_tx.send(()).await;
-            server.await;
+
+            let permits = Arc::new(Semaphore::new(limits.max_connections));
+            let (closing_tx, closing_rx) = watch::channel(false);
+
+            loop {
+                let (stream, peer) = tokio::select! {
+                    _ = shutdown_rx.recv() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            // Usually fd exhaustion; back off instead of spinning
+                            warn!(error = %e, "Failed to accept callback connection");
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            continue;
+                        }
+                    },
+                };
+
+                let Ok(permit) = Arc::clone(&permits).try_acquire_owned() else {
+                    warn!(
+                        peer = %peer,
+                        max_connections = limits.max_connections,
+                        "Callback connection limit reached, rejecting connection"
+                    );
+                    tokio::spawn(reject_connection(stream));
+                    continue;
+                };
+
+                // Devices send the next NOTIFY on a keep-alive connection only
+                // after reading the previous response; with Nagle enabled that
+                // response can sit in the send buffer waiting for a delayed ACK.
+                let _ = stream.set_nodelay(true);
+                trace!(peer = %peer, "Accepted callback connection");
+                tokio::spawn(serve_connection(
+                    stream,
+                    peer,
+                    service.clone(),
+                    limits.clone(),
+                    closing_rx.clone(),
+                    permit,
+                ));
+            }
+
+            // Let open connections finish their current request, then stop
+            let _ = closing_tx.send(true);
+            let _ = tokio::time::timeout(
+                SHUTDOWN_GRACE,
+                permits.acquire_many(limits.max_connections as u32),
+            )
+            .await;
         })
     }
 
@@ -367,9 +672,12 @@ impl CallbackServer {
         }
 
         // For UPnP events, NT and NTS headers are typically present
-        // If present, validate they have expected values
+        // If present, validate they have expected values; firmware differs
+        // in case and padding
         if let (Some(nt_val), Some(nts_val)) = (nt, nts) {
-            if nt_val != "upnp:event" || nts_val != "upnp:propchange" {
+            if !nt_val.trim().eq_ignore_ascii_case("upnp:event")
+                || !nts_val.trim().eq_ignore_ascii_case("upnp:propchange")
+            {
                 return false;
             }
         }
@@ -378,6 +686,128 @@ impl CallbackServer {
     }
 }
 
+/// The first 200 characters of a NOTIFY body for trace logs, or `None` if
+/// the body is no longer than that
+fn xml_preview(xml: &str) -> Option<&str> {
+    // Count chars, not bytes: a byte cut could land inside a multi-byte character
+    xml.char_indices()
+        .nth(200)
+        .and_then(|(cut, _)| xml.get(..cut))
+}
+
+/// A NOTIFY's SID in the form the subscriber registered it: trimmed, with
+/// a lowercase `uuid:` prefix whether or not the device sent one
+fn normalize_sid(sid: &str) -> String {
+    let sid = sid.trim();
+    match sid.split_at_checked(5) {
+        Some((prefix, rest)) if prefix.eq_ignore_ascii_case("uuid:") => format!("uuid:{rest}"),
+        _ => format!("uuid:{sid}"),
+    }
+}
+
+/// Remote address of a connection, attached to each of its requests
+#[derive(Debug, Clone, Copy)]
+struct Peer(SocketAddr);
+
+/// Serve sequential requests on one keep-alive connection until the client
+/// closes it, it sits idle past `idle_timeout`, it outlives `max_lifetime`, or
+/// the server shuts down. The permit is released when the connection ends.
+async fn serve_connection<S>(
+    stream: TcpStream,
+    peer: SocketAddr,
+    service: S,
+    limits: ConnectionLimits,
+    mut closing: watch::Receiver<bool>,
+    _permit: OwnedSemaphorePermit,
+) where
+    S: Service<Request<Body>, Response = Response<Body>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+{
+    let accepted = Instant::now();
+    // Milliseconds after `accepted` at which the latest request arrived
+    let last_request = Arc::new(AtomicU64::new(0));
+    let served_any = Arc::new(AtomicBool::new(false));
+
+    let svc = {
+        let last_request = Arc::clone(&last_request);
+        let served_any = Arc::clone(&served_any);
+        service_fn(move |mut req: Request<Body>| {
+            last_request.store(accepted.elapsed().as_millis() as u64, Ordering::Relaxed);
+            served_any.store(true, Ordering::Relaxed);
+            req.extensions_mut().insert(Peer(peer));
+            let mut service = service.clone();
+            async move { service.call(req).await }
+        })
+    };
+
+    let conn = Http::new()
+        .http1_only(true)
+        .http1_keep_alive(true)
+        .serve_connection(stream, svc);
+    tokio::pin!(conn);
+
+    let lifetime = tokio::time::sleep(limits.max_lifetime);
+    tokio::pin!(lifetime);
+    let hard_stop = tokio::time::sleep(limits.max_lifetime + SHUTDOWN_GRACE);
+    tokio::pin!(hard_stop);
+    let mut idle_check = tokio::time::interval(
+        limits
+            .idle_timeout
+            .clamp(Duration::from_millis(10), Duration::from_secs(1)),
+    );
+    let mut draining = false;
+
+    loop {
+        let reason = tokio::select! {
+            result = conn.as_mut() => {
+                if let Err(e) = result {
+                    debug!(error = %e, "Callback connection closed with error");
+                }
+                return;
+            }
+            _ = &mut hard_stop, if draining => {
+                debug!("Callback connection did not drain in time, dropping it");
+                return;
+            }
+            _ = &mut lifetime, if !draining => "lifetime",
+            _ = closing.changed(), if !draining => "shutdown",
+            _ = idle_check.tick(), if !draining => {
+                let last = Duration::from_millis(last_request.load(Ordering::Relaxed));
+                if accepted.elapsed().saturating_sub(last) < limits.idle_timeout {
+                    continue;
+                }
+                "idle"
+            }
+        };
+
+        trace!(reason, "Closing callback connection");
+        // hyper 0.14 ignores graceful shutdown on a connection that never
+        // sent a request, so those are simply dropped. Otherwise an in-flight
+        // request is answered before the connection closes.
+        if !served_any.load(Ordering::Relaxed) {
+            return;
+        }
+        conn.as_mut().graceful_shutdown();
+        hard_stop
+            .as_mut()
+            .reset(tokio::time::Instant::now() + SHUTDOWN_GRACE);
+        draining = true;
+    }
+}
+
+/// Refuse a connection over the limit with `503` so the device retries
+/// later instead of waiting for a response that never comes.
+async fn reject_connection(mut stream: TcpStream) {
+    let response = format!(
+        "HTTP/1.1 503 Service Unavailable\r\nServer: {SERVER_HEADER}\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
 /// Custom rejection for invalid UPnP headers.
 #[derive(Debug)]
 struct InvalidUpnpHeaders;
@@ -410,25 +840,40 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_is_port_available() {
+    fn test_try_bind() {
         // Port 0 should always be available (OS assigns a free port)
-        assert!(CallbackServer::is_port_available(0));
+        assert!(CallbackServer::try_bind(0).is_some());
 
         // Bind to a port and verify it's no longer available
-        let _listener = TcpListener::bind("0.0.0.0:0").unwrap();
-        let port = _listener.local_addr().unwrap().port();
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
         // While the listener is held, the port should not be available
-        assert!(!CallbackServer::is_port_available(port));
-        // Keep listener alive for the assertion
-        drop(_listener);
+        assert!(CallbackServer::try_bind(port).is_none());
+        drop(listener);
     }
 
     #[test]
-    fn test_find_available_port() {
-        // Should find a port in a reasonable range
-        let port = CallbackServer::find_available_port(50000, 50100);
-        assert!(port.is_some());
-        assert!(port.unwrap() >= 50000 && port.unwrap() <= 50100);
+    fn test_bind_in_range() {
+        // Should find a port in a reasonable range, and keep it bound
+        let selection = PortSelection::default();
+        let last = AtomicU16::new(0);
+        let (listener, info) =
+            CallbackServer::bind_in_range(52100, 52200, &selection, &last).unwrap();
+        assert!((52100..=52200).contains(&info.port));
+        assert_eq!(listener.local_addr().unwrap().port(), info.port);
+        assert_eq!(last.load(Ordering::Relaxed), info.port);
+        assert!(CallbackServer::try_bind(info.port).is_none());
+        assert!(CallbackServer::bind_in_range(52200, 52100, &selection, &last).is_none());
+
+        // The port bound last is tried first once free again
+        drop(listener);
+        let randomized = PortSelection {
+            randomize: true,
+            ..PortSelection::default()
+        };
+        let (_listener, again) =
+            CallbackServer::bind_in_range(52100, 52200, &randomized, &last).unwrap();
+        assert_eq!((again.port, again.ports_probed), (info.port, 1));
     }
 
     #[test]
@@ -478,6 +923,39 @@ mod tests {
             &Some("upnp:event".to_string()),
             &Some("wrong".to_string()),
         ));
+
+        // Valid: case and padding vary across firmware
+        assert!(CallbackServer::validate_upnp_headers(
+            &Some("uuid:123".to_string()),
+            &Some(" UPnP:Event".to_string()),
+            &Some("upnp:propchange ".to_string()),
+        ));
+    }
+
+    #[test]
+    fn test_normalize_sid() {
+        assert_eq!(normalize_sid("uuid:RINCON_1_sub1"), "uuid:RINCON_1_sub1");
+        assert_eq!(normalize_sid(" UUID:RINCON_1_sub1"), "uuid:RINCON_1_sub1");
+        assert_eq!(normalize_sid("RINCON_1_sub1"), "uuid:RINCON_1_sub1");
+    }
+
+    #[test]
+    fn test_xml_preview() {
+        assert_eq!(xml_preview("<e:propertyset/>"), None);
+        // Found by fuzz_xml_preview: byte 200 falls inside the 67th "€"
+        let body = "€".repeat(250);
+        assert_eq!(xml_preview(&body), Some("€".repeat(200).as_str()));
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn fuzz_xml_preview(xml in "\\PC{150,250}") {
+            if let Some(preview) = xml_preview(&xml) {
+                proptest::prop_assert!(xml.starts_with(preview));
+            }
+        }
     }
 
     #[tokio::test]
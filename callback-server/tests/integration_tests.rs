@@ -3,8 +3,16 @@
 //! These tests start a real HTTP server, send actual HTTP requests,
 //! and verify end-to-end functionality.
 
-use callback_server::{CallbackServer, NotificationPayload};
-use std::time::Duration;
+use callback_server::{
+    CallbackServer, ConnectionLimits, ContentEncoding, DecodeError, NotificationPayload,
+    PortSelection, SERVER_HEADER,
+};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tokio::time::timeout;
 
@@ -56,6 +64,8 @@ async fn test_callback_server_end_to_end() {
         .expect("Failed to send HTTP request");
 
     assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["server"], SERVER_HEADER);
+    assert!(SERVER_HEADER.starts_with(concat!("sonos-sdk/", env!("CARGO_PKG_VERSION"))));
 
     // Verify we received the notification
     let notification = timeout(Duration::from_secs(1), rx.recv())
@@ -69,6 +79,12 @@ async fn test_callback_server_end_to_end() {
     );
     assert!(notification.event_xml.contains("TransportState"));
     assert!(notification.event_xml.contains("PLAYING"));
+    // The sender is the connecting address, here this host's own
+    let host = base_url
+        .trim_start_matches("http://")
+        .rsplit_once(':')
+        .map(|(host, _)| host.parse().unwrap());
+    assert_eq!(notification.sender, host);
 
     // Test 2: Send event with SID header only (no NT/NTS)
     let event_xml2 = r#"<?xml version="1.0"?>
@@ -420,6 +436,7 @@ async fn test_error_handling() {
 
     // Should return an error status (not 200)
     assert_ne!(response1.status(), 200);
+    assert_eq!(response1.headers()["server"], SERVER_HEADER);
 
     // 2. Invalid NT header value
     let response2 = client
@@ -506,3 +523,236 @@ async fn test_notify_before_register_is_replayed() {
 
     server.shutdown().await.expect("Failed to shutdown server");
 }
+
+/// Read one HTTP response head from `stream`, returning the status line.
+/// Responses from the callback server carry no body.
+async fn read_response_head(stream: &mut TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte).await.expect("read response") == 0 {
+            break;
+        }
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8_lossy(&head).to_string();
+    head.lines().next().unwrap_or_default().to_string()
+}
+
+/// Devices deliver many NOTIFYs over one keep-alive connection; each must be
+/// answered promptly or the device considers the callback dead.
+#[tokio::test]
+async fn test_keep_alive_connection_delivers_every_notify() {
+    let (tx, mut rx) = mpsc::unbounded_channel::<NotificationPayload>();
+    let server = CallbackServer::new((51400, 51500), tx)
+        .await
+        .expect("Failed to create callback server");
+    let sub_id = "uuid:keep-alive";
+    server.router().register(sub_id.to_string()).await;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+        .await
+        .expect("Failed to connect");
+    let per_request = Duration::from_millis(200);
+
+    for seq in 0..50 {
+        let body = format!("<e:propertyset><Volume>{seq}</Volume></e:propertyset>");
+        let request = format!(
+            "NOTIFY /notify HTTP/1.1\r\nHost: 127.0.0.1\r\nSID: {sub_id}\r\nNT: upnp:event\r\n\
+             NTS: upnp:propchange\r\nSEQ: {seq}\r\nContent-Type: text/xml\r\n\
+             Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let started = Instant::now();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let status = timeout(per_request, read_response_head(&mut stream))
+            .await
+            .unwrap_or_else(|_| panic!("NOTIFY {seq} not answered within {per_request:?}"));
+        assert_eq!(status, "HTTP/1.1 200 OK", "NOTIFY {seq}");
+        assert!(started.elapsed() < per_request);
+    }
+
+    for seq in 0..50 {
+        let payload = timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("Timeout waiting for routed event")
+            .expect("Channel closed");
+        assert_eq!(payload.subscription_id, sub_id);
+        assert!(payload
+            .event_xml
+            .contains(&format!("<Volume>{seq}</Volume>")));
+    }
+
+    server.shutdown().await.expect("Failed to shutdown server");
+}
+
+/// Connections beyond `max_connections` are refused with 503, and idle
+/// keep-alive connections are closed so their slot is freed.
+#[tokio::test]
+async fn test_connection_cap_and_idle_timeout() {
+    let (tx, _rx) = mpsc::unbounded_channel::<NotificationPayload>();
+    let limits = ConnectionLimits {
+        max_connections: 1,
+        idle_timeout: Duration::from_millis(200),
+        ..ConnectionLimits::default()
+    };
+    let server = CallbackServer::with_limits((51600, 51700), tx, limits)
+        .await
+        .expect("Failed to create callback server");
+    let addr = ("127.0.0.1", server.port());
+
+    let notify = b"NOTIFY / HTTP/1.1\r\nHost: x\r\nSID: uuid:x\r\nContent-Length: 0\r\n\r\n";
+    let mut first = TcpStream::connect(addr).await.unwrap();
+    first.write_all(notify).await.unwrap();
+    assert_eq!(read_response_head(&mut first).await, "HTTP/1.1 200 OK");
+
+    let mut second = TcpStream::connect(addr).await.unwrap();
+    let status = timeout(Duration::from_secs(1), read_response_head(&mut second))
+        .await
+        .expect("Rejected connection should be answered");
+    assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+
+    // The idle first connection is closed by the server
+    let mut buf = [0u8; 1];
+    let read = timeout(Duration::from_secs(2), first.read(&mut buf))
+        .await
+        .expect("Idle connection should be closed");
+    assert_eq!(read.unwrap_or(0), 0);
+
+    // Its slot is free again
+    let mut third = TcpStream::connect(addr).await.unwrap();
+    third.write_all(notify).await.unwrap();
+    let status = timeout(Duration::from_secs(1), read_response_head(&mut third))
+        .await
+        .unwrap();
+    assert_eq!(status, "HTTP/1.1 200 OK");
+
+    server.shutdown().await.expect("Failed to shutdown server");
+}
+
+/// Ports held by other sockets are skipped without delay, and the server
+/// reports the port it chose and how many it tried.
+#[tokio::test]
+async fn test_port_probe_skips_occupied_block() {
+    let (start, end) = (51800, 51840);
+    let occupied: Vec<_> = (start..start + 24)
+        .filter_map(|port| std::net::TcpListener::bind(("0.0.0.0", port)).ok())
+        .collect();
+    assert!(!occupied.is_empty());
+
+    let started = Instant::now();
+    let (tx, _rx) = mpsc::unbounded_channel::<NotificationPayload>();
+    let server = CallbackServer::new((start, end), tx)
+        .await
+        .expect("Failed to create callback server");
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    let info = server.startup_info();
+    assert_eq!(info.port, server.port());
+    assert!(server.base_url().ends_with(&format!(":{}", info.port)));
+    assert!((start + 24..=end).contains(&info.port));
+    assert!(info.ports_probed > usize::from(info.port - start));
+    assert!(info.probe_time <= started.elapsed());
+
+    // The chosen port is held: a second server in the range gets another
+    let (tx, _rx) = mpsc::unbounded_channel::<NotificationPayload>();
+    let selection = PortSelection {
+        randomize: true,
+        ..PortSelection::default()
+    };
+    let second =
+        CallbackServer::with_options((start, end), tx, ConnectionLimits::default(), selection)
+            .await
+            .expect("Failed to create second callback server");
+    assert_ne!(second.port(), server.port());
+    assert!((start + 24..=end).contains(&second.port()));
+
+    second.shutdown().await.unwrap();
+    server.shutdown().await.unwrap();
+    drop(occupied);
+}
+
+fn compress<W: Write>(mut encoder: W, body: &[u8], finish: impl FnOnce(W) -> Vec<u8>) -> Vec<u8> {
+    encoder.write_all(body).unwrap();
+    finish(encoder)
+}
+
+/// Compressed NOTIFY bodies are decoded before routing; bodies that can't be
+/// decoded are rejected and routed as errors.
+#[tokio::test]
+async fn test_compressed_notify_bodies() {
+    let (tx, mut rx) = mpsc::unbounded_channel::<NotificationPayload>();
+    let limits = ConnectionLimits {
+        max_decoded_body: 64 * 1024,
+        ..ConnectionLimits::default()
+    };
+    let server = CallbackServer::with_limits((51800, 51900), tx, limits)
+        .await
+        .expect("Failed to create callback server");
+    let sid = "uuid:compressed-sub";
+    server.router().register(sid.to_string()).await;
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/notify", server.base_url());
+    let notify = |encoding: &'static str, body: Vec<u8>| {
+        client
+            .request(reqwest::Method::from_bytes(b"NOTIFY").unwrap(), &url)
+            .header("SID", sid)
+            .header("NT", "upnp:event")
+            .header("NTS", "upnp:propchange")
+            .header("Content-Encoding", encoding)
+            .body(body)
+            .send()
+    };
+    let event_xml = "<e:propertyset><ZoneGroupState>big</ZoneGroupState></e:propertyset>";
+    let gzip = |body: &[u8]| {
+        compress(
+            GzEncoder::new(Vec::new(), Compression::default()),
+            body,
+            |e| e.finish().unwrap(),
+        )
+    };
+    let deflate = compress(
+        ZlibEncoder::new(Vec::new(), Compression::default()),
+        event_xml.as_bytes(),
+        |e| e.finish().unwrap(),
+    );
+
+    for (encoding, body, expected) in [
+        ("gzip", gzip(event_xml.as_bytes()), ContentEncoding::Gzip),
+        ("deflate", deflate, ContentEncoding::Deflate),
+    ] {
+        let response = notify(encoding, body).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let payload = rx.recv().await.unwrap();
+        assert_eq!(payload.event_xml, event_xml);
+        assert_eq!(payload.content_encoding, Some(expected));
+        assert_eq!(payload.decode_error, None);
+    }
+
+    // 1 MiB of zeros compresses to about 1 KiB but inflates past the cap
+    let bomb = gzip(&vec![0; 1024 * 1024]);
+    assert!(bomb.len() < 64 * 1024);
+    let response = notify("gzip", bomb).await.unwrap();
+    assert_eq!(response.status(), 413);
+    let payload = rx.recv().await.unwrap();
+    assert!(payload.event_xml.is_empty());
+    assert_eq!(
+        payload.decode_error,
+        Some(DecodeError::TooLarge {
+            encoding: ContentEncoding::Gzip,
+            limit: 64 * 1024
+        })
+    );
+
+    let response = notify("br", event_xml.into()).await.unwrap();
+    assert_eq!(response.status(), 415);
+    let payload = rx.recv().await.unwrap();
+    assert_eq!(payload.subscription_id, sid);
+    assert_eq!(
+        payload.decode_error,
+        Some(DecodeError::UnsupportedEncoding("br".to_string()))
+    );
+
+    server.shutdown().await.expect("Failed to shutdown server");
+}
@@ -0,0 +1,431 @@
+//! Non-blocking counterpart of [`SoapClient`](crate::SoapClient)
+//!
+//! Sends the same envelopes and reads responses, faults and subscription
+//! headers with the same code as the blocking client; only the HTTP goes
+//! through a `reqwest` client instead of a `ureq` agent. Devices are spoken
+//! to over plain HTTP: [`DeviceTransport`](crate::DeviceTransport)s are a
+//! blocking-client feature.
+
+use std::sync::Arc;
+
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use xmltree::Element;
+
+use crate::{
+    action_response, envelope, fault_response, granted_timeout, response_too_large, soap_action,
+    split_host_port, ClientIdentity, EventingConnection, Failed, RetryPolicy, SoapClientConfig,
+    SoapError, SubscriptionResponse, MAX_RESPONSE_BYTES,
+};
+
+/// A SOAP client for UPnP devices whose requests are futures
+///
+/// Cloning is cheap and shares the connection pool.
+#[derive(Debug, Clone)]
+pub struct AsyncSoapClient {
+    http: reqwest::Client,
+    /// Connection use of eventing requests
+    eventing: EventingConnection,
+    /// `USER-AGENT` of every request
+    user_agent: Arc<str>,
+}
+
+impl AsyncSoapClient {
+    /// A client with the default [`SoapClientConfig`]
+    pub fn new() -> Self {
+        Self::with_config(SoapClientConfig::default())
+    }
+
+    /// A client with `config`'s timeouts, `USER-AGENT` and pool limits
+    ///
+    /// A request may take the connect and read timeouts together, as
+    /// `reqwest` only bounds whole requests.
+    pub fn with_config(config: SoapClientConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.connect_timeout + config.read_timeout)
+            .pool_max_idle_per_host(config.max_connections_per_host)
+            .pool_idle_timeout(config.idle_timeout)
+            .build()
+            .expect("a plain HTTP client always builds");
+        let user_agent = match config.user_agent {
+            Some(user_agent) => user_agent.into(),
+            None => ClientIdentity::default().user_agent().into(),
+        };
+        Self {
+            http,
+            eventing: config.eventing_connection,
+            user_agent,
+        }
+    }
+
+    /// Identify as `identity` instead of the default `sonos-sdk/{version}`
+    ///
+    /// The connection pool stays shared with the client this was called on.
+    pub fn with_identity(mut self, identity: &ClientIdentity) -> Self {
+        self.user_agent = identity.user_agent().into();
+        self
+    }
+
+    /// The `USER-AGENT` header this client sends
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Send a SOAP request and return the parsed response element
+    ///
+    /// Like [`SoapClient::call()`](crate::SoapClient::call); `address` is an
+    /// IP or `ip:port`.
+    pub async fn call(
+        &self,
+        address: &str,
+        endpoint: &str,
+        service_uri: &str,
+        action: &str,
+        payload: &str,
+    ) -> Result<Element, SoapError> {
+        self.post(address, endpoint, service_uri, action, payload)
+            .await
+            .map_err(|failed| failed.error)
+    }
+
+    /// Like [`call()`](Self::call), retrying transient failures as `policy`
+    /// allows
+    ///
+    /// As for [`SoapClient::call_with_retry()`](crate::SoapClient::call_with_retry),
+    /// only requests that got no response are retried.
+    pub async fn call_with_retry(
+        &self,
+        policy: &RetryPolicy,
+        address: &str,
+        endpoint: &str,
+        service_uri: &str,
+        action: &str,
+        payload: &str,
+    ) -> Result<Element, SoapError> {
+        let mut attempt = 1;
+        loop {
+            match self
+                .post(address, endpoint, service_uri, action, payload)
+                .await
+            {
+                Err(failed) if failed.retryable && attempt < policy.max_attempts => {
+                    tokio::time::sleep(policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result.map_err(|failed| failed.error),
+            }
+        }
+    }
+
+    /// One attempt of a SOAP request
+    async fn post(
+        &self,
+        address: &str,
+        endpoint: &str,
+        service_uri: &str,
+        action: &str,
+        payload: &str,
+    ) -> Result<Element, Failed> {
+        let (ip, port) = split_host_port(address);
+        let response = self
+            .http
+            .post(format!("http://{ip}:{port}/{endpoint}"))
+            .header("Content-Type", "text/xml; charset=\"utf-8\"")
+            .header("SOAPACTION", soap_action(service_uri, action))
+            .header("USER-AGENT", &*self.user_agent)
+            .body(envelope(service_uri, action, payload))
+            .send()
+            .await
+            // Nothing of a response arrived: refused, reset or timed out
+            .map_err(|e| Failed {
+                error: SoapError::Network(e.to_string()),
+                retryable: true,
+            })?;
+
+        let status = response.status();
+        let body = read_response(response).await?;
+        match status.as_u16() {
+            200..=299 => Ok(action_response(&body, action)?),
+            500 => Err(fault_response(Some(&body), format!("{address}: status code 500")).into()),
+            code => Err(SoapError::Network(format!("{address}: status code {code}")).into()),
+        }
+    }
+
+    /// Subscribe to UPnP events for a service endpoint
+    ///
+    /// Like [`SoapClient::subscribe()`](crate::SoapClient::subscribe).
+    pub async fn subscribe(
+        &self,
+        ip: &str,
+        port: u16,
+        event_endpoint: &str,
+        callback_url: &str,
+        timeout_seconds: u32,
+    ) -> Result<SubscriptionResponse, SoapError> {
+        let response = self
+            .eventing_request("SUBSCRIBE", ip, port, event_endpoint)
+            .header("CALLBACK", format!("<{callback_url}>"))
+            .header("NT", "upnp:event")
+            .header("TIMEOUT", format!("Second-{timeout_seconds}"))
+            .send()
+            .await
+            .map_err(|e| SoapError::Network(e.to_string()))?;
+
+        if response.status().as_u16() != 200 {
+            return Err(SoapError::Network(format!(
+                "SUBSCRIBE failed: HTTP {}",
+                response.status().as_u16()
+            )));
+        }
+
+        let headers = header_pairs(response.headers());
+        SubscriptionResponse::from_headers(
+            headers.iter().map(|(n, v)| (n.as_str(), v.as_str())),
+            timeout_seconds,
+        )
+    }
+
+    /// Renew an existing UPnP subscription
+    ///
+    /// Like [`SoapClient::renew_subscription()`](crate::SoapClient::renew_subscription):
+    /// the granted timeout, or [`SoapError::PreconditionFailed`] if the
+    /// device no longer knows `sid`.
+    pub async fn renew_subscription(
+        &self,
+        ip: &str,
+        port: u16,
+        event_endpoint: &str,
+        sid: &str,
+        timeout_seconds: u32,
+    ) -> Result<u32, SoapError> {
+        let response = self
+            .eventing_request("SUBSCRIBE", ip, port, event_endpoint)
+            .header("SID", sid)
+            .header("TIMEOUT", format!("Second-{timeout_seconds}"))
+            .send()
+            .await
+            .map_err(|e| SoapError::Network(e.to_string()))?;
+
+        match response.status().as_u16() {
+            200 => Ok(granted_timeout(
+                response
+                    .headers()
+                    .get_all("TIMEOUT")
+                    .iter()
+                    .filter_map(|value| value.to_str().ok()),
+                timeout_seconds,
+            )),
+            412 => Err(SoapError::PreconditionFailed),
+            code => Err(SoapError::Network(format!(
+                "SUBSCRIBE renewal failed: HTTP {code}"
+            ))),
+        }
+    }
+
+    /// Unsubscribe from UPnP events
+    pub async fn unsubscribe(
+        &self,
+        ip: &str,
+        port: u16,
+        event_endpoint: &str,
+        sid: &str,
+    ) -> Result<(), SoapError> {
+        let response = self
+            .eventing_request("UNSUBSCRIBE", ip, port, event_endpoint)
+            .header("SID", sid)
+            .send()
+            .await
+            .map_err(|e| SoapError::Network(e.to_string()))?;
+
+        if response.status().as_u16() != 200 {
+            return Err(SoapError::Network(format!(
+                "UNSUBSCRIBE failed: HTTP {}",
+                response.status().as_u16()
+            )));
+        }
+        Ok(())
+    }
+
+    /// A `method` request for an eventing `path` on the device at
+    /// `ip:port`, asking the device to close the connection unless this
+    /// client keeps eventing connections alive
+    fn eventing_request(
+        &self,
+        method: &str,
+        ip: &str,
+        port: u16,
+        path: &str,
+    ) -> reqwest::RequestBuilder {
+        let method = Method::from_bytes(method.as_bytes()).expect("a valid method token");
+        let request = self
+            .http
+            .request(method, format!("http://{ip}:{port}/{path}"))
+            .header("USER-AGENT", &*self.user_agent);
+        match self.eventing {
+            EventingConnection::Close => request.header("CONNECTION", "close"),
+            EventingConnection::KeepAlive => request,
+        }
+    }
+}
+
+impl Default for AsyncSoapClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read a SOAP response body chunk by chunk, rejecting one over
+/// [`MAX_RESPONSE_BYTES`] as soon as it passes the limit
+async fn read_response(mut response: reqwest::Response) -> Result<String, SoapError> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| SoapError::Network(e.to_string()))?
+    {
+        if body.len() + chunk.len() > MAX_RESPONSE_BYTES {
+            return Err(response_too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    String::from_utf8(body).map_err(|e| SoapError::Network(e.to_string()))
+}
+
+/// Every header of a response, repeated headers included
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    const AVT: &str = "urn:schemas-upnp-org:service:AVTransport:1";
+
+    /// Answer one request with `response`; the handle yields the raw request
+    fn serve_once(response: String) -> (u16, std::thread::JoinHandle<String>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let (mut request, mut length) = (String::new(), 0);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+            stream.write_all(response.as_bytes()).unwrap();
+            request.to_ascii_lowercase()
+        });
+        (port, handle)
+    }
+
+    fn http(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    #[tokio::test]
+    async fn test_call_shares_envelope_and_fault_parsing() {
+        let client = AsyncSoapClient::new();
+        let (port, server) = serve_once(http(
+            "200 OK",
+            "",
+            &format!(
+                r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><u:GetTransportInfoResponse xmlns:u="{AVT}"><CurrentTransportState>PLAYING</CurrentTransportState></u:GetTransportInfoResponse></s:Body></s:Envelope>"#
+            ),
+        ));
+        let address = format!("127.0.0.1:{port}");
+        let response = client
+            .call(
+                &address,
+                "Control",
+                AVT,
+                "GetTransportInfo",
+                "<InstanceID>0</InstanceID>",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response
+                .get_child("CurrentTransportState")
+                .and_then(|e| e.get_text()),
+            Some("PLAYING".into())
+        );
+        let request = server.join().unwrap();
+        let envelope = envelope(AVT, "GetTransportInfo", "<InstanceID>0</InstanceID>");
+        assert!(request.ends_with(&envelope.to_ascii_lowercase()));
+        assert!(request.contains(
+            "\r\nsoapaction: \"urn:schemas-upnp-org:service:avtransport:1#gettransportinfo\"\r\n"
+        ));
+
+        let (port, _server) = serve_once(http(
+            "500 Internal Server Error",
+            "",
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><s:Fault><detail><UPnPError><errorCode>701</errorCode></UPnPError></detail></s:Fault></s:Body></s:Envelope>"#,
+        ));
+        let error = client
+            .call(&format!("127.0.0.1:{port}"), "Control", AVT, "Play", "")
+            .await
+            .unwrap_err();
+        assert_eq!(error.fault_code(), Some(701));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_response_is_rejected() {
+        let client = AsyncSoapClient::new();
+        let (port, _server) = serve_once(http("200 OK", "", &"x".repeat(MAX_RESPONSE_BYTES + 1)));
+        let error = client
+            .call(&format!("127.0.0.1:{port}"), "Control", AVT, "Play", "")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("response exceeds"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_subscription_round_trip() {
+        let client = AsyncSoapClient::new();
+        let (port, server) = serve_once(http(
+            "200 OK",
+            "SID: uuid:RINCON_1_sub7\r\nTIMEOUT: Second-900\r\n",
+            "",
+        ));
+        let subscription = client
+            .subscribe("127.0.0.1", port, "Event", "http://10.0.0.2:3400/cb", 1800)
+            .await
+            .unwrap();
+        assert_eq!(subscription.sid, "uuid:RINCON_1_sub7");
+        assert_eq!(subscription.timeout_seconds, 900);
+        let request = server.join().unwrap();
+        assert!(request.starts_with("subscribe /event http/1.1\r\n"));
+        assert!(request.contains("\r\ncallback: <http://10.0.0.2:3400/cb>\r\n"));
+        assert!(request.contains("\r\nconnection: close\r\n"));
+
+        let (port, _server) = serve_once(http("412 Precondition Failed", "", ""));
+        assert!(matches!(
+            client
+                .renew_subscription("127.0.0.1", port, "Event", "uuid:RINCON_1_sub7", 1800)
+                .await,
+            Err(SoapError::PreconditionFailed)
+        ));
+    }
+}
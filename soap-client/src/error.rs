@@ -1,7 +1,11 @@
 //! Error types for the SOAP client
 
+use std::fmt;
+
 use thiserror::Error;
 
+use crate::xml::XmlViolation;
+
 /// Errors that can occur during SOAP communication
 #[derive(Debug, Error)]
 pub enum SoapError {
@@ -14,6 +18,62 @@ pub enum SoapError {
     Parse(String),
 
     /// SOAP fault returned by the server
-    #[error("SOAP fault: error code {0}")]
-    Fault(u16),
+    #[error("SOAP fault: {0}")]
+    Fault(SoapFault),
+
+    /// Response refused before parsing, see [`xml`](crate::xml)
+    #[error("XML rejected: {0}")]
+    XmlRejected(#[from] XmlViolation),
+
+    /// A subscription renewal got HTTP 412 Precondition Failed
+    ///
+    /// The device doesn't know the SID, usually because the subscription
+    /// lapsed or the device rebooted. Only a fresh SUBSCRIBE helps.
+    #[error("HTTP 412 Precondition Failed: subscription unknown to the device")]
+    PreconditionFailed,
+}
+
+impl SoapError {
+    /// UPnP error code of a [`Fault`](Self::Fault)
+    pub fn fault_code(&self) -> Option<u16> {
+        match self {
+            Self::Fault(fault) => Some(fault.code),
+            _ => None,
+        }
+    }
+}
+
+/// A SOAP fault as the device reported it
+///
+/// Displays as the code followed by the description, e.g.
+/// `error code 701: Transition not available`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoapFault {
+    /// UPnP `errorCode`; 500 when the fault carries none
+    pub code: u16,
+    /// UPnP `errorDescription`, which Sonos often leaves out
+    pub description: Option<String>,
+    /// SOAP `faultstring`, usually just `UPnPError`
+    pub fault_string: Option<String>,
+}
+
+impl SoapFault {
+    /// A fault with only a code
+    pub fn new(code: u16) -> Self {
+        Self {
+            code,
+            description: None,
+            fault_string: None,
+        }
+    }
+}
+
+impl fmt::Display for SoapFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error code {}", self.code)?;
+        if let Some(description) = &self.description {
+            write!(f, ": {description}")?;
+        }
+        Ok(())
+    }
 }
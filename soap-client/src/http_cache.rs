@@ -0,0 +1,313 @@
+//! Conditional-GET cache for device documents
+//!
+//! Device descriptions are fetched again and again (each discovery run,
+//! each rediscovery) although they rarely change. [`HttpCache`] keeps the
+//! last body of each URL with its `ETag` / `Last-Modified` validators, so
+//! a repeat fetch is a conditional GET that a 304 answers without a body.
+//! When the device can't be reached, a body validated within the cache's
+//! stale limit is served instead of the error.
+//!
+//! The cache doesn't do the HTTP itself: [`HttpCache::fetch()`] hands the
+//! validators to a closure, so any client can use it.
+//! [`SoapClient::fetch_resource_cached()`](crate::SoapClient::fetch_resource_cached)
+//! is the one for the shared agent.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::{HttpResource, SoapError};
+
+/// Entries kept by [`HttpCache::default()`]
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// How long after its last validation [`HttpCache::default()`] still
+/// serves a body when the device can't be reached
+pub const DEFAULT_MAX_STALE: Duration = Duration::from_secs(600);
+
+/// Validators to send with a conditional GET
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    /// For `If-None-Match`
+    pub etag: Option<String>,
+    /// For `If-Modified-Since`
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    /// Whether there is nothing to send, so the GET is unconditional
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// What a (conditional) GET returned
+#[derive(Debug, Clone)]
+pub enum Revalidation {
+    /// A new body, with the validators it came with
+    Modified(HttpResource, Validators),
+    /// 304: the cached body is still current
+    NotModified,
+}
+
+#[derive(Debug)]
+struct Entry {
+    resource: HttpResource,
+    validators: Validators,
+    validated_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    by_url: HashMap<String, Entry>,
+    /// Use counter for least-recently-used eviction
+    tick: u64,
+}
+
+/// Bounded cache of HTTP bodies keyed by URL
+///
+/// Share one through an `Arc` ([`HttpCache::shared()`] is the process-wide
+/// one discovery uses by default). When full, the least recently used
+/// entry makes room.
+#[derive(Debug)]
+pub struct HttpCache {
+    entries: Mutex<Entries>,
+    capacity: usize,
+    max_stale: Duration,
+}
+
+impl Default for HttpCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl HttpCache {
+    /// A cache holding at most `capacity` URLs (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            capacity: capacity.max(1),
+            max_stale: DEFAULT_MAX_STALE,
+        }
+    }
+
+    /// Serve a cached body on network errors only within `max_stale` of
+    /// its last validation (default [`DEFAULT_MAX_STALE`]); zero never
+    /// serves one
+    pub fn with_max_stale(mut self, max_stale: Duration) -> Self {
+        self.max_stale = max_stale;
+        self
+    }
+
+    /// The process-wide cache
+    pub fn shared() -> Arc<HttpCache> {
+        static SHARED: LazyLock<Arc<HttpCache>> = LazyLock::new(|| Arc::new(HttpCache::default()));
+        Arc::clone(&SHARED)
+    }
+
+    /// Fetch `url` through the cache
+    ///
+    /// `get` performs the GET, sending the validators it is given (empty
+    /// for an uncached URL) and reporting a 304 as
+    /// [`Revalidation::NotModified`]. A new body replaces the entry; a 304
+    /// returns the cached one. If `get` fails with
+    /// [`SoapError::Network`] and the entry was validated within the stale
+    /// limit, the cached body is returned instead. Other errors, and 304s
+    /// for a URL with nothing cached, fail.
+    pub fn fetch(
+        &self,
+        url: &str,
+        get: impl FnOnce(&Validators) -> Result<Revalidation, SoapError>,
+    ) -> Result<HttpResource, SoapError> {
+        // Copied out so a concurrent eviction can't lose the body mid-request
+        let cached = self.lock().by_url.get(url).map(|entry| {
+            (
+                entry.resource.clone(),
+                entry.validators.clone(),
+                entry.validated_at,
+            )
+        });
+        let validators = cached
+            .as_ref()
+            .map(|(_, validators, _)| validators.clone())
+            .unwrap_or_default();
+
+        match get(&validators) {
+            Ok(Revalidation::Modified(resource, validators)) => {
+                self.store(url, resource.clone(), validators);
+                Ok(resource)
+            }
+            Ok(Revalidation::NotModified) => {
+                let Some((resource, validators, _)) = cached else {
+                    return Err(SoapError::Parse(format!("304 for uncached {url}")));
+                };
+                self.store(url, resource.clone(), validators);
+                Ok(resource)
+            }
+            Err(SoapError::Network(message)) => match cached {
+                Some((resource, _, validated_at)) if validated_at.elapsed() < self.max_stale => {
+                    Ok(resource)
+                }
+                _ => Err(SoapError::Network(message)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Number of cached URLs
+    pub fn len(&self) -> usize {
+        self.lock().by_url.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether `url` is cached
+    pub fn contains(&self, url: &str) -> bool {
+        self.lock().by_url.contains_key(url)
+    }
+
+    /// Forget every entry
+    pub fn clear(&self) {
+        self.lock().by_url.clear();
+    }
+
+    fn store(&self, url: &str, resource: HttpResource, validators: Validators) {
+        let mut entries = self.lock();
+        entries.tick += 1;
+        let tick = entries.tick;
+        if !entries.by_url.contains_key(url) && entries.by_url.len() >= self.capacity {
+            let oldest = entries
+                .by_url
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(url, _)| url.clone());
+            if let Some(oldest) = oldest {
+                entries.by_url.remove(&oldest);
+            }
+        }
+        entries.by_url.insert(
+            url.to_string(),
+            Entry {
+                resource,
+                validators,
+                validated_at: Instant::now(),
+                last_used: tick,
+            },
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(body: &str) -> HttpResource {
+        HttpResource {
+            body: body.as_bytes().to_vec(),
+            content_type: Some("text/xml".to_string()),
+        }
+    }
+
+    fn etag(tag: &str) -> Validators {
+        Validators {
+            etag: Some(tag.to_string()),
+            last_modified: None,
+        }
+    }
+
+    #[test]
+    fn test_revalidates_with_stored_validators() {
+        let cache = HttpCache::new(4);
+        let first = cache
+            .fetch("http://a/desc.xml", |validators| {
+                assert!(validators.is_empty());
+                Ok(Revalidation::Modified(resource("v1"), etag("\"1\"")))
+            })
+            .unwrap();
+        assert_eq!(first.body, b"v1");
+
+        let second = cache
+            .fetch("http://a/desc.xml", |validators| {
+                assert_eq!(*validators, etag("\"1\""));
+                Ok(Revalidation::NotModified)
+            })
+            .unwrap();
+        assert_eq!(second.body, b"v1");
+        assert_eq!(second.content_type.as_deref(), Some("text/xml"));
+    }
+
+    #[test]
+    fn test_stale_body_on_network_error_only_within_limit() {
+        let cache = HttpCache::new(4);
+        cache
+            .fetch("http://a/desc.xml", |_| {
+                Ok(Revalidation::Modified(resource("v1"), etag("\"1\"")))
+            })
+            .unwrap();
+
+        let stale = cache
+            .fetch("http://a/desc.xml", |_| {
+                Err(SoapError::Network("connection reset".to_string()))
+            })
+            .unwrap();
+        assert_eq!(stale.body, b"v1");
+
+        // Not a network error, so not papered over
+        let parse = cache.fetch("http://a/desc.xml", |_| {
+            Err(SoapError::Parse("bad".to_string()))
+        });
+        assert!(matches!(parse, Err(SoapError::Parse(_))));
+
+        let strict = HttpCache::new(4).with_max_stale(Duration::ZERO);
+        strict
+            .fetch("http://a/desc.xml", |_| {
+                Ok(Revalidation::Modified(resource("v1"), etag("\"1\"")))
+            })
+            .unwrap();
+        let err = strict.fetch("http://a/desc.xml", |_| {
+            Err(SoapError::Network("timed out".to_string()))
+        });
+        assert!(matches!(err, Err(SoapError::Network(_))));
+    }
+
+    #[test]
+    fn test_not_modified_without_entry_fails() {
+        let cache = HttpCache::new(4);
+        let result = cache.fetch("http://a/desc.xml", |_| Ok(Revalidation::NotModified));
+        assert!(matches!(result, Err(SoapError::Parse(_))));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_least_recently_used_entry_is_evicted() {
+        let cache = HttpCache::new(2);
+        let fill = |url: &str| {
+            cache
+                .fetch(url, |_| {
+                    Ok(Revalidation::Modified(resource(url), etag(url)))
+                })
+                .unwrap();
+        };
+        fill("http://a");
+        fill("http://b");
+        // Revalidating `a` makes `b` the least recently used
+        cache
+            .fetch("http://a", |_| Ok(Revalidation::NotModified))
+            .unwrap();
+        fill("http://c");
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains("http://a"));
+        assert!(!cache.contains("http://b"));
+        assert!(cache.contains("http://c"));
+    }
+}
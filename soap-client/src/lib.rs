@@ -6,21 +6,449 @@
 //! communicating with UPnP devices like Sonos speakers. It also supports
 //! UPnP event subscriptions using SUBSCRIBE/UNSUBSCRIBE methods.
 
+#[cfg(feature = "async")]
+mod async_client;
 mod error;
+pub mod http_cache;
+mod pool;
+pub mod transport;
+pub mod xml;
 
-pub use error::SoapError;
+#[cfg(feature = "async")]
+pub use async_client::AsyncSoapClient;
+pub use error::{SoapError, SoapFault};
+pub use http_cache::{HttpCache, Revalidation, Validators};
+pub use pool::PoolStats;
+pub use transport::{
+    CertFingerprint, CertificateTrust, DeviceTransport, EventingConnection, Scheme, HTTPS_PORT,
+};
+pub use xml::{XmlLimits, XmlViolation};
 
-use std::sync::{Arc, LazyLock};
+use pool::{DevicePool, Lease};
+use transport::Transports;
+
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
 use std::time::Duration;
 use xmltree::Element;
 
 /// Response from a UPnP subscription request
 #[derive(Debug, Clone)]
 pub struct SubscriptionResponse {
-    /// Subscription ID returned by the device
+    /// Subscription ID returned by the device, normalized by [`normalize_sid`]
     pub sid: String,
     /// Actual timeout granted by the device (in seconds)
     pub timeout_seconds: u32,
+    /// Headers this client doesn't interpret, names lowercased
+    extra_headers: Vec<(String, String)>,
+}
+
+/// Headers of any HTTP response; not worth reporting as extras
+const TRANSPORT_HEADERS: [&str; 6] = [
+    "connection",
+    "content-length",
+    "content-type",
+    "date",
+    "server",
+    "transfer-encoding",
+];
+
+impl SubscriptionResponse {
+    /// Read a SUBSCRIBE response's headers
+    ///
+    /// The first non-empty `SID` is the subscription ID and the first valid
+    /// `TIMEOUT` the granted timeout, falling back to `requested_timeout`.
+    /// Header names are matched case-insensitively and headers the client
+    /// doesn't know are kept as [`extra_headers()`](Self::extra_headers)
+    /// rather than rejected.
+    pub fn from_headers<'a>(
+        headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+        requested_timeout: u32,
+    ) -> Result<Self, SoapError> {
+        let mut sid = None;
+        let mut timeout = None;
+        let mut extra_headers = Vec::new();
+        for (name, value) in headers {
+            let name = name.trim().to_ascii_lowercase();
+            match name.as_str() {
+                "sid" => {
+                    if sid.is_none() && !value.trim().is_empty() {
+                        sid = Some(normalize_sid(value));
+                    }
+                }
+                "timeout" => timeout = timeout.or_else(|| parse_timeout(value)),
+                _ if TRANSPORT_HEADERS.contains(&name.as_str()) => {}
+                _ => extra_headers.push((name, value.trim().to_string())),
+            }
+        }
+        Ok(Self {
+            sid: sid.ok_or_else(|| {
+                SoapError::Parse("Missing SID header in SUBSCRIBE response".to_string())
+            })?,
+            timeout_seconds: timeout.unwrap_or(requested_timeout),
+            extra_headers,
+        })
+    }
+
+    /// Headers beyond `SID`, `TIMEOUT` and plain HTTP ones, in the order
+    /// received
+    ///
+    /// Firmware with UPnP 2.0 multicast eventing adds headers such as
+    /// `ACCEPTED-STATEVAR` or `BOOTID.UPNP.ORG`; their presence is how
+    /// support for it can be detected.
+    pub fn extra_headers(&self) -> &[(String, String)] {
+        &self.extra_headers
+    }
+
+    /// First extra header named `name`, matched case-insensitively
+    pub fn extra_header(&self, name: &str) -> Option<&str> {
+        self.extra_headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Canonical form of a subscription ID: trimmed, with a lowercase `uuid:`
+/// prefix
+///
+/// Devices send SIDs with or without the prefix; normalizing both the
+/// SUBSCRIBE response and NOTIFY headers keeps them comparable.
+pub fn normalize_sid(sid: &str) -> String {
+    let sid = sid.trim();
+    match sid.split_at_checked(5) {
+        Some((prefix, rest)) if prefix.eq_ignore_ascii_case("uuid:") => format!("uuid:{rest}"),
+        _ => format!("uuid:{sid}"),
+    }
+}
+
+/// Seconds in a `Second-N` timeout value; `None` for anything else,
+/// including `infinite`
+fn parse_timeout(value: &str) -> Option<u32> {
+    value.split(',').find_map(|part| {
+        let (unit, seconds) = part.trim().split_once('-')?;
+        if !unit.eq_ignore_ascii_case("second") {
+            return None;
+        }
+        seconds.trim().parse().ok()
+    })
+}
+
+/// Every header of a response, repeated headers included
+fn response_headers(response: &ureq::Response) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for name in response.headers_names() {
+        if headers.iter().any(|(n, _)| *n == name) {
+            continue;
+        }
+        let values = response.all(&name);
+        headers.extend(values.into_iter().map(|v| (name.clone(), v.to_string())));
+    }
+    headers
+}
+
+/// A plain HTTP resource fetched from a device (e.g. album art)
+#[derive(Debug, Clone)]
+pub struct HttpResource {
+    /// Response body
+    pub body: Vec<u8>,
+    /// `Content-Type` header, if present
+    pub content_type: Option<String>,
+}
+
+/// Largest body accepted by [`SoapClient::fetch_resource`]
+const MAX_RESOURCE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Largest SOAP response body either client reads, as `ureq`'s
+/// `into_string()` allows
+const MAX_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Port Sonos devices listen on unless an address says otherwise
+pub const DEFAULT_PORT: u16 = 1400;
+
+/// Split a device address into host and port
+///
+/// Accepts a bare IP (`"192.168.1.100"`, port [`DEFAULT_PORT`]) or a socket
+/// address (`"192.168.1.100:1401"`, `"[fe80::1]:1401"`).
+pub fn split_host_port(address: &str) -> (&str, u16) {
+    if address.parse::<std::net::SocketAddr>().is_ok() {
+        if let Some((host, port)) = address.rsplit_once(':') {
+            if let Ok(port) = port.parse() {
+                return (host, port);
+            }
+        }
+    }
+    (address, DEFAULT_PORT)
+}
+
+/// How the SDK identifies itself to devices
+///
+/// Sent as the `USER-AGENT` header of every request, so SDK traffic can be
+/// told apart from other clients in device diagnostics and network logs.
+/// The default is `sonos-sdk/{version}`, the version being this crate's at
+/// build time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    /// Product name, e.g. `my-controller`
+    pub product: String,
+    /// Product version
+    pub version: String,
+    /// Where to find out about the product, appended as a comment
+    pub contact_url: Option<String>,
+}
+
+impl ClientIdentity {
+    /// Identify as `product/version`
+    pub fn new(product: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            product: product.into(),
+            version: version.into(),
+            contact_url: None,
+        }
+    }
+
+    /// Add a contact URL, sent as `(+url)` after the product token
+    pub fn with_contact_url(mut self, url: impl Into<String>) -> Self {
+        self.contact_url = Some(url.into());
+        self
+    }
+
+    /// The `USER-AGENT` header value
+    pub fn user_agent(&self) -> String {
+        match &self.contact_url {
+            Some(url) => format!("{}/{} (+{url})", self.product, self.version),
+            None => format!("{}/{}", self.product, self.version),
+        }
+    }
+}
+
+impl Default for ClientIdentity {
+    fn default() -> Self {
+        Self::new("sonos-sdk", env!("CARGO_PKG_VERSION"))
+    }
+}
+
+/// TCP connections opened per device, by the agents this crate builds
+///
+/// Each device's agent keeps its idle keep-alive connections and reuses
+/// them for the next requests to that device, so a count that keeps pace
+/// with the requests sent means the device defeats the reuse.
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    opened: Mutex<HashMap<String, u64>>,
+}
+
+impl ConnectionStats {
+    /// Connections opened to `address` (an IP or `ip:port`; see
+    /// [`split_host_port`])
+    pub fn opened(&self, address: &str) -> u64 {
+        let (ip, port) = split_host_port(address);
+        self.lock()
+            .get(&format!("{ip}:{port}"))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Connections opened so far, by `host:port`
+    pub fn snapshot(&self) -> HashMap<String, u64> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, u64>> {
+        self.opened.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Counts connections: the agent resolves an address only when it has no
+/// pooled connection to reuse
+///
+/// Per device into the stats, and in total into `opened` if set.
+struct CountingResolver {
+    stats: Arc<ConnectionStats>,
+    opened: Option<Arc<AtomicU64>>,
+}
+
+impl ureq::Resolver for CountingResolver {
+    fn resolve(&self, netloc: &str) -> std::io::Result<Vec<SocketAddr>> {
+        *self.stats.lock().entry(netloc.to_string()).or_insert(0) += 1;
+        if let Some(opened) = &self.opened {
+            opened.fetch_add(1, Ordering::Relaxed);
+        }
+        netloc.to_socket_addrs().map(Iterator::collect)
+    }
+}
+
+/// Timeouts, identity and pooling for a [`SoapClient`] with its own agents
+///
+/// The defaults (5s to connect, 10s to read, `sonos-sdk/{version}`, 4
+/// connections per device, idle agents dropped after 60s) are those of the
+/// shared client from [`SoapClient::get()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoapClientConfig {
+    /// Longest wait for a TCP connection to a device
+    pub connect_timeout: Duration,
+    /// Longest wait for each read of a response; large responses such as
+    /// `GetZoneGroupState` on a big household may need more
+    pub read_timeout: Duration,
+    /// `USER-AGENT` header, instead of the default [`ClientIdentity`]
+    pub user_agent: Option<String>,
+    /// Connection use of SUBSCRIBE, renewal and UNSUBSCRIBE requests to
+    /// devices without their own [`DeviceTransport::eventing`]
+    pub eventing_connection: EventingConnection,
+    /// Most requests running against one device at once, and most idle
+    /// connections kept to it; further requests wait for one to finish
+    pub max_connections_per_host: usize,
+    /// How long a device's agent, with its idle connections, is kept
+    /// unused before it is dropped
+    pub idle_timeout: Duration,
+}
+
+impl SoapClientConfig {
+    /// Set the connect timeout
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set the read timeout
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Send `user_agent` as the `USER-AGENT` header
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Set how eventing requests use connections
+    pub fn with_eventing_connection(mut self, eventing: EventingConnection) -> Self {
+        self.eventing_connection = eventing;
+        self
+    }
+
+    /// Set the most requests running against one device at once (at
+    /// least 1)
+    pub fn with_max_connections_per_host(mut self, max: usize) -> Self {
+        self.max_connections_per_host = max.max(1);
+        self
+    }
+
+    /// Set how long an unused device keeps its agent and idle connections
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+}
+
+impl Default for SoapClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(10),
+            user_agent: None,
+            eventing_connection: EventingConnection::Close,
+            max_connections_per_host: 4,
+            idle_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Retries of requests that failed before any response arrived
+///
+/// For [`SoapClient::call_with_retry()`]. Only connection failures, resets
+/// and timeouts that happen before the device sent a response head are
+/// retried; an HTTP status (SOAP faults included) or a failure while reading
+/// a response body ends the call. A timed-out request may still have been
+/// carried out, so use it only for actions that are safe to repeat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first included
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled before each following one
+    pub initial_backoff: Duration,
+    /// Longest wait between two attempts
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Make at most `max_attempts` attempts (at least one)
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the wait before the first retry
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the longest wait between two attempts
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Wait before retry number `retry` (1 for the second attempt)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// A failed attempt, and whether another may follow
+struct Failed {
+    error: SoapError,
+    retryable: bool,
+}
+
+impl From<SoapError> for Failed {
+    fn from(error: SoapError) -> Self {
+        Self {
+            error,
+            retryable: false,
+        }
+    }
+}
+
+/// Agent with `config`'s timeouts, keeping up to `idle_per_host`
+/// connections to each device for reuse, counting the connections it opens
+/// into `stats` (and `opened`) and checking HTTPS devices' certificates
+/// against `transports`
+fn build_agent(
+    config: &SoapClientConfig,
+    idle_per_host: usize,
+    stats: &Arc<ConnectionStats>,
+    opened: Option<&Arc<AtomicU64>>,
+    transports: &Arc<Transports>,
+) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(config.connect_timeout)
+        .timeout_read(config.read_timeout)
+        .max_idle_connections(idle_per_host)
+        .max_idle_connections_per_host(idle_per_host)
+        .resolver(CountingResolver {
+            stats: Arc::clone(stats),
+            opened: opened.cloned(),
+        })
+        .tls_config(transport::tls_config(Arc::clone(transports)))
+        .build()
 }
 
 /// A minimal SOAP client for UPnP device communication
@@ -29,18 +457,27 @@ pub struct SubscriptionResponse {
 /// and connection pool across multiple instances.
 #[derive(Debug, Clone)]
 pub struct SoapClient {
-    agent: Arc<ureq::Agent>,
+    /// An agent per device, each with its own keep-alive connections
+    pool: Arc<DevicePool>,
+    /// Agent that never reuses a connection, for
+    /// [`EventingConnection::Close`]
+    fresh_agent: Arc<ureq::Agent>,
+    /// Connection use of eventing requests to devices without their own
+    eventing: EventingConnection,
+    /// Deadline of a whole eventing request; ureq clears the read timeout
+    /// of connections it pools, so a reused one would otherwise wait for
+    /// an answer forever
+    eventing_deadline: Option<Duration>,
+    /// `USER-AGENT` of every request, from a [`ClientIdentity`]
+    user_agent: Arc<str>,
+    /// Connections the pool's agents opened, if this crate built them
+    connections: Arc<ConnectionStats>,
+    /// How to reach each device; hosts not in it get plain HTTP
+    transports: Arc<Transports>,
 }
 
 /// Global shared SOAP client instance for maximum resource efficiency
-static SHARED_SOAP_CLIENT: LazyLock<SoapClient> = LazyLock::new(|| SoapClient {
-    agent: Arc::new(
-        ureq::AgentBuilder::new()
-            .timeout_connect(Duration::from_secs(5))
-            .timeout_read(Duration::from_secs(10))
-            .build(),
-    ),
-});
+static SHARED_SOAP_CLIENT: LazyLock<SoapClient> = LazyLock::new(SoapClient::pooled);
 
 impl SoapClient {
     /// Get the global shared SOAP client instance
@@ -55,74 +492,331 @@ impl SoapClient {
     /// Create a SOAP client with a custom agent (for advanced use cases only)
     ///
     /// Most applications should use `SoapClient::get()` instead for better
-    /// resource efficiency. This method is provided for cases where custom
-    /// timeout values or other HTTP client configuration is needed.
+    /// resource efficiency, or [`with_config()`](Self::with_config) for
+    /// other timeouts. This method is provided for cases where other HTTP
+    /// client configuration is needed.
+    ///
+    /// Every device shares the agent, but requests to each are still
+    /// limited to the default
+    /// [`max_connections_per_host`](SoapClientConfig::max_connections_per_host).
+    /// Connections made by a custom agent aren't counted in
+    /// [`connections()`](Self::connections), so
+    /// [`pool_stats()`](Self::pool_stats) reports no hits or misses. Transports set with
+    /// [`set_transport()`](Self::set_transport) still pick the scheme and
+    /// port, but certificates are checked by the agent's own TLS settings,
+    /// which know nothing of [`CertificateTrust`]. Eventing requests
+    /// with [`EventingConnection::Close`] go through the agent too, so
+    /// they ask the device to close the connection but may start on a
+    /// pooled one.
     pub fn with_agent(agent: Arc<ureq::Agent>) -> Self {
-        Self { agent }
+        let defaults = SoapClientConfig::default();
+        let shared = ureq::Agent::clone(&agent);
+        Self {
+            pool: Arc::new(DevicePool::new(
+                Box::new(move || shared.clone()),
+                defaults.max_connections_per_host,
+                defaults.idle_timeout,
+                None,
+            )),
+            fresh_agent: agent,
+            eventing: EventingConnection::default(),
+            eventing_deadline: None,
+            user_agent: ClientIdentity::default().user_agent().into(),
+            connections: Arc::default(),
+            transports: Arc::default(),
+        }
+    }
+
+    /// Create a SOAP client with its own agents and connection pool, using
+    /// `config`'s timeouts, `USER-AGENT` and pool limits
+    ///
+    /// Connections are counted in [`connections()`](Self::connections) as
+    /// for the shared client.
+    pub fn with_config(config: SoapClientConfig) -> Self {
+        let connections: Arc<ConnectionStats> = Arc::default();
+        let transports: Arc<Transports> = Arc::default();
+        let opened = Arc::new(AtomicU64::new(0));
+        let user_agent = match config.user_agent {
+            Some(ref user_agent) => user_agent.as_str().into(),
+            None => ClientIdentity::default().user_agent().into(),
+        };
+        let build = {
+            let (config, connections, transports, opened) = (
+                config.clone(),
+                Arc::clone(&connections),
+                Arc::clone(&transports),
+                Arc::clone(&opened),
+            );
+            move || {
+                build_agent(
+                    &config,
+                    config.max_connections_per_host,
+                    &connections,
+                    Some(&opened),
+                    &transports,
+                )
+            }
+        };
+        Self {
+            pool: Arc::new(DevicePool::new(
+                Box::new(build),
+                config.max_connections_per_host,
+                config.idle_timeout,
+                Some(opened),
+            )),
+            fresh_agent: Arc::new(build_agent(&config, 0, &connections, None, &transports)),
+            eventing: config.eventing_connection,
+            eventing_deadline: Some(config.connect_timeout + config.read_timeout),
+            user_agent,
+            connections,
+            transports,
+        }
+    }
+
+    fn pooled() -> Self {
+        Self::with_config(SoapClientConfig::default())
+    }
+
+    /// Connections this client's agents have opened, per device
+    ///
+    /// Shared by every client using the same pool.
+    pub fn connections(&self) -> &ConnectionStats {
+        &self.connections
+    }
+
+    /// Hits, misses and requests in flight of the per-device connection
+    /// pool, shared by every client using it
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+
+    /// Identify as `identity` instead of the default `sonos-sdk/{version}`
+    ///
+    /// The connection pool stays shared with the client this was called on.
+    pub fn with_identity(mut self, identity: &ClientIdentity) -> Self {
+        self.user_agent = identity.user_agent().into();
+        self
+    }
+
+    /// Use connections for eventing requests as `eventing` says, for
+    /// devices whose transport doesn't say otherwise
+    ///
+    /// The connection pool stays shared with the client this was called on.
+    pub fn with_eventing_connection(mut self, eventing: EventingConnection) -> Self {
+        self.eventing = eventing;
+        self
+    }
+
+    /// How eventing requests to `host` use connections
+    pub fn eventing_connection(&self, host: &str) -> EventingConnection {
+        self.transports
+            .get(host)
+            .and_then(|transport| transport.eventing)
+            .unwrap_or(self.eventing)
+    }
+
+    /// The `USER-AGENT` header this client sends
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Reach the device at `host` (an IP, without port) through `transport`
+    ///
+    /// Applies to every request to the host: SOAP calls and event
+    /// (un)subscriptions, whatever port their address names. Shared by
+    /// every client using the same agent, so setting it on a clone of
+    /// [`SoapClient::get()`] configures the shared client.
+    pub fn set_transport(&self, host: &str, transport: DeviceTransport) {
+        self.transports.set(host, transport);
+    }
+
+    /// The transport set for `host`, if any
+    pub fn transport(&self, host: &str) -> Option<DeviceTransport> {
+        self.transports.get(host)
+    }
+
+    /// Go back to plain HTTP for `host`, returning the transport it had
+    pub fn clear_transport(&self, host: &str) -> Option<DeviceTransport> {
+        self.transports.remove(host)
+    }
+
+    /// URL of `path` on the device at `ip:port` and the `HOST` header to
+    /// send, after the host's transport picked scheme and port
+    fn endpoint(&self, ip: &str, port: u16, path: &str) -> (String, String) {
+        let (scheme, port) = self
+            .transports
+            .get(ip)
+            .map_or((Scheme::Http, port), |transport| transport.endpoint(port));
+        (
+            format!("{scheme}://{ip}:{port}/{path}"),
+            format!("{ip}:{port}"),
+        )
+    }
+
+    /// A `method` request for an eventing `path` on the device at
+    /// `ip:port`, on a fresh connection unless the device keeps them alive
+    ///
+    /// Kept-alive requests come with the lease of the device's pool slot,
+    /// to hold until the response has been read.
+    fn eventing_request(
+        &self,
+        method: &str,
+        ip: &str,
+        port: u16,
+        path: &str,
+    ) -> (Option<Lease<'_>>, ureq::Request) {
+        let (url, host) = self.endpoint(ip, port, path);
+        let (lease, request) = match self.eventing_connection(ip) {
+            EventingConnection::Close => (
+                None,
+                self.fresh_agent
+                    .request(method, &url)
+                    .set("CONNECTION", "close"),
+            ),
+            EventingConnection::KeepAlive => {
+                let agent = self.pool.checkout(&host);
+                let request = agent.request(method, &url);
+                (Some(agent), request)
+            }
+        };
+        let request = match self.eventing_deadline {
+            Some(deadline) => request.timeout(deadline),
+            None => request,
+        };
+        (
+            lease,
+            request
+                .set("HOST", &host)
+                .set("USER-AGENT", &self.user_agent),
+        )
     }
 
     /// Create a new SOAP client with default configuration
     ///
     /// **DEPRECATED**: Use `SoapClient::get()` instead for better resource efficiency.
-    /// This method creates a separate HTTP agent instance, which wastes resources
+    /// This method creates a separate connection pool, which wastes resources
     /// when multiple SOAP clients are used.
     #[deprecated(since = "0.1.0", note = "Use SoapClient::get() for shared resources")]
     pub fn new() -> Self {
-        Self::with_agent(Arc::new(
-            ureq::AgentBuilder::new()
-                .timeout_connect(Duration::from_secs(5))
-                .timeout_read(Duration::from_secs(10))
-                .build(),
-        ))
+        Self::pooled()
     }
 
     /// Send a SOAP request and return the parsed response element
+    ///
+    /// `address` is an IP or `ip:port`; see [`split_host_port`].
     pub fn call(
+        &self,
+        address: &str,
+        endpoint: &str,
+        service_uri: &str,
+        action: &str,
+        payload: &str,
+    ) -> Result<Element, SoapError> {
+        let (ip, port) = split_host_port(address);
+        self.call_with_port(ip, port, endpoint, service_uri, action, payload)
+    }
+
+    /// Like [`call()`](Self::call), retrying transient failures as `policy`
+    /// allows
+    ///
+    /// See [`RetryPolicy`] for which failures are retried; the error of the
+    /// last attempt is returned.
+    pub fn call_with_retry(
+        &self,
+        policy: &RetryPolicy,
+        address: &str,
+        endpoint: &str,
+        service_uri: &str,
+        action: &str,
+        payload: &str,
+    ) -> Result<Element, SoapError> {
+        let (ip, port) = split_host_port(address);
+        let mut attempt = 1;
+        loop {
+            match self.post(ip, port, endpoint, service_uri, action, payload) {
+                Err(failed) if failed.retryable && attempt < policy.max_attempts => {
+                    std::thread::sleep(policy.backoff(attempt));
+                    attempt += 1;
+                }
+                result => return result.map_err(|failed| failed.error),
+            }
+        }
+    }
+
+    /// Send a SOAP request to a device on an explicit port
+    ///
+    /// # Arguments
+    /// * `ip` - Device IP address
+    /// * `port` - Device port (typically 1400; a transport set for `ip` may
+    ///   replace it)
+    /// * `endpoint` - Control endpoint path (e.g., "MediaRenderer/AVTransport/Control")
+    /// * `service_uri` - Service type URN
+    /// * `action` - Action name
+    /// * `payload` - Action arguments, already XML-escaped
+    pub fn call_with_port(
         &self,
         ip: &str,
+        port: u16,
         endpoint: &str,
         service_uri: &str,
         action: &str,
         payload: &str,
     ) -> Result<Element, SoapError> {
-        // Inline SOAP envelope construction - no separate module needed
-        let body = format!(
-            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
-                <s:Body>
-                    <u:{action} xmlns:u="{service_uri}">
-                        {payload}
-                    </u:{action}>
-                </s:Body>
-            </s:Envelope>"#
-        );
+        self.post(ip, port, endpoint, service_uri, action, payload)
+            .map_err(|failed| failed.error)
+    }
 
-        let url = format!("http://{ip}:1400/{endpoint}");
-        let soap_action = format!("\"{service_uri}#{action}\"");
+    /// One attempt of a SOAP request
+    fn post(
+        &self,
+        ip: &str,
+        port: u16,
+        endpoint: &str,
+        service_uri: &str,
+        action: &str,
+        payload: &str,
+    ) -> Result<Element, Failed> {
+        let body = envelope(service_uri, action, payload);
+        let (url, host) = self.endpoint(ip, port, endpoint);
+        let soap_action = soap_action(service_uri, action);
 
-        let response = self
-            .agent
+        let agent = self.pool.checkout(&host);
+        let response = agent
             .post(&url)
+            .set("HOST", &host)
             .set("Content-Type", "text/xml; charset=\"utf-8\"")
             .set("SOAPACTION", &soap_action)
+            .set("USER-AGENT", &self.user_agent)
             .send_string(&body)
-            .map_err(|e| SoapError::Network(e.to_string()))?;
-
-        let xml_text = response
-            .into_string()
-            .map_err(|e| SoapError::Network(e.to_string()))?;
+            .map_err(|e| {
+                let message = e.to_string();
+                match e {
+                    ureq::Error::Status(500, response) => {
+                        fault_response(read_response(response).ok().as_deref(), message).into()
+                    }
+                    // Nothing of a response arrived: refused, reset or timed out
+                    ureq::Error::Transport(transport) => Failed {
+                        retryable: matches!(
+                            transport.kind(),
+                            ureq::ErrorKind::ConnectionFailed | ureq::ErrorKind::Io
+                        ),
+                        error: SoapError::Network(message),
+                    },
+                    ureq::Error::Status(..) => SoapError::Network(message).into(),
+                }
+            })?;
 
-        let xml =
-            Element::parse(xml_text.as_bytes()).map_err(|e| SoapError::Parse(e.to_string()))?;
+        let xml_text = read_response(response)?;
 
-        // Extract response or handle SOAP fault
-        self.extract_response(&xml, action)
+        Ok(action_response(&xml_text, action)?)
     }
 
     /// Subscribe to UPnP events for a specific service endpoint
     ///
     /// # Arguments
     /// * `ip` - Device IP address
-    /// * `port` - Device port (typically 1400)
+    /// * `port` - Device port (typically 1400; a transport set for `ip` may
+    ///   replace it)
     /// * `event_endpoint` - Event endpoint path (e.g., "MediaRenderer/AVTransport/Event")
     /// * `callback_url` - URL where events should be sent
     /// * `timeout_seconds` - Requested subscription timeout in seconds
@@ -137,13 +831,8 @@ impl SoapClient {
         callback_url: &str,
         timeout_seconds: u32,
     ) -> Result<SubscriptionResponse, SoapError> {
-        let url = format!("http://{ip}:{port}/{event_endpoint}");
-        let host = format!("{ip}:{port}");
-
-        let response = self
-            .agent
-            .request("SUBSCRIBE", &url)
-            .set("HOST", &host)
+        let (_lease, request) = self.eventing_request("SUBSCRIBE", ip, port, event_endpoint);
+        let response = request
             .set("CALLBACK", &format!("<{callback_url}>"))
             .set("NT", "upnp:event")
             .set("TIMEOUT", &format!("Second-{timeout_seconds}"))
@@ -157,44 +846,26 @@ impl SoapClient {
             )));
         }
 
-        // Extract SID from response headers
-        let sid = response
-            .header("SID")
-            .ok_or_else(|| {
-                SoapError::Parse("Missing SID header in SUBSCRIBE response".to_string())
-            })?
-            .to_string();
-
-        // Extract timeout from response headers (optional, fallback to requested timeout)
-        let actual_timeout_seconds = response
-            .header("TIMEOUT")
-            .and_then(|s| {
-                // Parse "Second-1800" format
-                if s.starts_with("Second-") {
-                    s.strip_prefix("Second-")?.parse::<u32>().ok()
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(timeout_seconds);
-
-        Ok(SubscriptionResponse {
-            sid,
-            timeout_seconds: actual_timeout_seconds,
-        })
+        let headers = response_headers(&response);
+        SubscriptionResponse::from_headers(
+            headers.iter().map(|(n, v)| (n.as_str(), v.as_str())),
+            timeout_seconds,
+        )
     }
 
     /// Renew an existing UPnP subscription
     ///
     /// # Arguments
     /// * `ip` - Device IP address
-    /// * `port` - Device port (typically 1400)
+    /// * `port` - Device port (typically 1400; a transport set for `ip` may
+    ///   replace it)
     /// * `event_endpoint` - Event endpoint path
     /// * `sid` - Subscription ID to renew
     /// * `timeout_seconds` - Requested renewal timeout in seconds
     ///
     /// # Returns
-    /// The actual timeout granted by the device
+    /// The actual timeout granted by the device, or
+    /// [`SoapError::PreconditionFailed`] if it no longer knows `sid`
     pub fn renew_subscription(
         &self,
         ip: &str,
@@ -203,17 +874,15 @@ impl SoapClient {
         sid: &str,
         timeout_seconds: u32,
     ) -> Result<u32, SoapError> {
-        let url = format!("http://{ip}:{port}/{event_endpoint}");
-        let host = format!("{ip}:{port}");
-
-        let response = self
-            .agent
-            .request("SUBSCRIBE", &url)
-            .set("HOST", &host)
+        let (_lease, request) = self.eventing_request("SUBSCRIBE", ip, port, event_endpoint);
+        let response = request
             .set("SID", sid)
             .set("TIMEOUT", &format!("Second-{timeout_seconds}"))
             .call()
-            .map_err(|e| SoapError::Network(e.to_string()))?;
+            .map_err(|e| match e {
+                ureq::Error::Status(412, _) => SoapError::PreconditionFailed,
+                e => SoapError::Network(e.to_string()),
+            })?;
 
         if response.status() != 200 {
             return Err(SoapError::Network(format!(
@@ -222,26 +891,15 @@ impl SoapClient {
             )));
         }
 
-        // Extract timeout from response headers
-        let actual_timeout_seconds = response
-            .header("TIMEOUT")
-            .and_then(|s| {
-                if s.starts_with("Second-") {
-                    s.strip_prefix("Second-")?.parse::<u32>().ok()
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(timeout_seconds);
-
-        Ok(actual_timeout_seconds)
+        Ok(granted_timeout(response.all("TIMEOUT"), timeout_seconds))
     }
 
     /// Unsubscribe from UPnP events
     ///
     /// # Arguments
     /// * `ip` - Device IP address
-    /// * `port` - Device port (typically 1400)
+    /// * `port` - Device port (typically 1400; a transport set for `ip` may
+    ///   replace it)
     /// * `event_endpoint` - Event endpoint path
     /// * `sid` - Subscription ID to cancel
     pub fn unsubscribe(
@@ -251,13 +909,8 @@ impl SoapClient {
         event_endpoint: &str,
         sid: &str,
     ) -> Result<(), SoapError> {
-        let url = format!("http://{ip}:{port}/{event_endpoint}");
-        let host = format!("{ip}:{port}");
-
-        let response = self
-            .agent
-            .request("UNSUBSCRIBE", &url)
-            .set("HOST", &host)
+        let (_lease, request) = self.eventing_request("UNSUBSCRIBE", ip, port, event_endpoint);
+        let response = request
             .set("SID", sid)
             .call()
             .map_err(|e| SoapError::Network(e.to_string()))?;
@@ -272,28 +925,59 @@ impl SoapClient {
         Ok(())
     }
 
-    fn extract_response(&self, xml: &Element, action: &str) -> Result<Element, SoapError> {
-        let body = xml
-            .get_child("Body")
-            .ok_or_else(|| SoapError::Parse("Missing SOAP Body".to_string()))?;
-
-        // Check for SOAP fault first
-        if let Some(fault) = body.get_child("Fault") {
-            let error_code = fault
-                .get_child("detail")
-                .and_then(|d| d.get_child("UpnPError"))
-                .and_then(|e| e.get_child("errorCode"))
-                .and_then(|c| c.get_text())
-                .and_then(|t| t.parse::<u16>().ok())
-                .unwrap_or(500);
-            return Err(SoapError::Fault(error_code));
-        }
+    /// Fetch a non-SOAP resource with a plain GET over the pooled agent of
+    /// the URL's host
+    ///
+    /// # Arguments
+    /// * `url` - Absolute URL (e.g. `http://192.168.1.100:1400/getaa?...`)
+    ///
+    /// Bodies larger than 16 MiB are rejected.
+    pub fn fetch_resource(&self, url: &str) -> Result<HttpResource, SoapError> {
+        let agent = self.pool.checkout(authority(url));
+        let response = agent
+            .get(url)
+            .set("USER-AGENT", &self.user_agent)
+            .call()
+            .map_err(|e| SoapError::Network(e.to_string()))?;
+
+        read_resource(response, url)
+    }
 
-        // Extract the action response
-        let response_name = format!("{action}Response");
-        body.get_child(response_name.as_str())
-            .cloned()
-            .ok_or_else(|| SoapError::Parse(format!("Missing {response_name} element")))
+    /// Fetch a non-SOAP resource through `cache`
+    ///
+    /// Like [`fetch_resource()`](Self::fetch_resource), but a cached URL is
+    /// fetched with `If-None-Match` / `If-Modified-Since` and a 304 answered
+    /// from the cache; see [`HttpCache::fetch()`] for when a cached body
+    /// stands in for a network error.
+    pub fn fetch_resource_cached(
+        &self,
+        url: &str,
+        cache: &HttpCache,
+    ) -> Result<HttpResource, SoapError> {
+        cache.fetch(url, |validators| {
+            let agent = self.pool.checkout(authority(url));
+            let mut request = agent.get(url).set("USER-AGENT", &self.user_agent);
+            if let Some(etag) = &validators.etag {
+                request = request.set("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.set("If-Modified-Since", last_modified);
+            }
+            let response = request
+                .call()
+                .map_err(|e| SoapError::Network(e.to_string()))?;
+            if response.status() == 304 {
+                return Ok(Revalidation::NotModified);
+            }
+            let validators = Validators {
+                etag: response.header("ETag").map(str::to_string),
+                last_modified: response.header("Last-Modified").map(str::to_string),
+            };
+            Ok(Revalidation::Modified(
+                read_resource(response, url)?,
+                validators,
+            ))
+        })
     }
 }
 
@@ -303,10 +987,160 @@ impl Default for SoapClient {
     }
 }
 
+/// The `host[:port]` of an absolute URL, keying its device in the pool
+fn authority(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+/// SOAP envelope calling `action` of `service_uri` with `payload`
+///
+/// `payload` holds the action's arguments, already XML-escaped.
+pub(crate) fn envelope(service_uri: &str, action: &str, payload: &str) -> String {
+    format!(
+        r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+                <s:Body>
+                    <u:{action} xmlns:u="{service_uri}">
+                        {payload}
+                    </u:{action}>
+                </s:Body>
+            </s:Envelope>"#
+    )
+}
+
+/// `SOAPACTION` header value of `action`
+pub(crate) fn soap_action(service_uri: &str, action: &str) -> String {
+    format!("\"{service_uri}#{action}\"")
+}
+
+/// The `<{action}Response>` element of a 200 response body
+pub(crate) fn action_response(body: &str, action: &str) -> Result<Element, SoapError> {
+    let xml = xml::parse_element(body)?;
+    extract_response(&xml, action)
+}
+
+/// Error of an HTTP 500 answer to a SOAP call
+///
+/// UPnP faults arrive as HTTP 500 with the fault in the body; without a
+/// readable one it is a network error with `message`.
+pub(crate) fn fault_response(body: Option<&str>, message: String) -> SoapError {
+    body.and_then(|text| xml::parse_element(text).ok())
+        .and_then(|xml| parse_fault(&xml))
+        .map_or(SoapError::Network(message), SoapError::Fault)
+}
+
+/// Timeout a device granted in its `TIMEOUT` headers, or `requested` if
+/// none is readable
+pub(crate) fn granted_timeout<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+    requested: u32,
+) -> u32 {
+    values
+        .into_iter()
+        .find_map(parse_timeout)
+        .unwrap_or(requested)
+}
+
+/// The action's response element in a SOAP envelope, or the fault it holds
+fn extract_response(xml: &Element, action: &str) -> Result<Element, SoapError> {
+    let body = xml
+        .get_child("Body")
+        .ok_or_else(|| SoapError::Parse("Missing SOAP Body".to_string()))?;
+
+    // Check for SOAP fault first
+    if let Some(fault) = parse_fault(xml) {
+        return Err(SoapError::Fault(fault));
+    }
+
+    // Extract the action response
+    let response_name = format!("{action}Response");
+    body.get_child(response_name.as_str())
+        .cloned()
+        .ok_or_else(|| SoapError::Parse(format!("Missing {response_name} element")))
+}
+
+/// Read a SOAP response body, rejecting one over [`MAX_RESPONSE_BYTES`]
+fn read_response(response: ureq::Response) -> Result<String, SoapError> {
+    use std::io::Read;
+
+    let mut body = String::new();
+    response
+        .into_reader()
+        .take(MAX_RESPONSE_BYTES as u64 + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| SoapError::Network(e.to_string()))?;
+    if body.len() > MAX_RESPONSE_BYTES {
+        return Err(response_too_large());
+    }
+    Ok(body)
+}
+
+/// Error for a SOAP response over [`MAX_RESPONSE_BYTES`]
+fn response_too_large() -> SoapError {
+    SoapError::Network(format!("response exceeds {MAX_RESPONSE_BYTES} bytes"))
+}
+
+/// Read a resource body, rejecting one over 16 MiB
+fn read_resource(response: ureq::Response, url: &str) -> Result<HttpResource, SoapError> {
+    use std::io::Read;
+
+    let content_type = response.header("Content-Type").map(str::to_string);
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_RESOURCE_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| SoapError::Network(e.to_string()))?;
+    if body.len() as u64 > MAX_RESOURCE_BYTES {
+        return Err(SoapError::Network(format!(
+            "resource exceeds {MAX_RESOURCE_BYTES} bytes: {url}"
+        )));
+    }
+
+    Ok(HttpResource { body, content_type })
+}
+
+/// The fault in a SOAP envelope, if it holds one
+///
+/// Faults without a readable code count as 500. The detail element is
+/// `UPnPError` per the UPnP spec; `UpnPError` is accepted too. Empty
+/// descriptions and fault strings are treated as absent.
+fn parse_fault(envelope: &Element) -> Option<SoapFault> {
+    let fault = envelope.get_child("Body")?.get_child("Fault")?;
+    let text = |element: Option<&Element>| {
+        element
+            .and_then(|e| e.get_text())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+    };
+    let upnp_error = fault.get_child("detail").and_then(|d| {
+        d.get_child("UPnPError")
+            .or_else(|| d.get_child("UpnPError"))
+    });
+    let code = text(upnp_error.and_then(|e| e.get_child("errorCode")))
+        .and_then(|t| t.parse::<u16>().ok())
+        .unwrap_or(500);
+    Some(SoapFault {
+        code,
+        description: text(upnp_error.and_then(|e| e.get_child("errorDescription"))),
+        fault_string: text(fault.get_child("faultstring")),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(split_host_port("192.168.1.100"), ("192.168.1.100", 1400));
+        assert_eq!(
+            split_host_port("192.168.1.100:1401"),
+            ("192.168.1.100", 1401)
+        );
+        assert_eq!(split_host_port("[fe80::1]:1402"), ("[fe80::1]", 1402));
+    }
+
     #[test]
     fn test_soap_client_creation() {
         // Test singleton pattern
@@ -335,14 +1169,37 @@ mod tests {
         let cloned1 = client1.clone();
         let cloned2 = client2.clone();
 
-        // All clones should share the same underlying agent
-        assert!(Arc::ptr_eq(&cloned1.agent, &cloned2.agent));
+        // All clones should share the same connection pool
+        assert!(Arc::ptr_eq(&cloned1.pool, &cloned2.pool));
     }
 
     #[test]
-    fn test_extract_response_with_valid_response() {
-        let client = SoapClient::get();
+    fn test_with_identity_keeps_shared_agent() {
+        assert_eq!(
+            SoapClient::get().user_agent(),
+            format!("sonos-sdk/{}", env!("CARGO_PKG_VERSION"))
+        );
+        let client = SoapClient::get()
+            .clone()
+            .with_identity(&ClientIdentity::new("my-controller", "2.1"));
+        assert_eq!(client.user_agent(), "my-controller/2.1");
+        assert!(Arc::ptr_eq(&client.pool, &SoapClient::get().pool));
+    }
+
+    #[test]
+    fn test_with_config_builds_own_agent() {
+        let client = SoapClient::with_config(SoapClientConfig::default());
+        assert!(!Arc::ptr_eq(&client.pool, &SoapClient::get().pool));
+        assert_eq!(client.user_agent(), SoapClient::get().user_agent());
+
+        let client = SoapClient::with_config(
+            SoapClientConfig::default().with_user_agent("kiosk/1.0 (lobby)"),
+        );
+        assert_eq!(client.user_agent(), "kiosk/1.0 (lobby)");
+    }
 
+    #[test]
+    fn test_extract_response_with_valid_response() {
         let xml_str = r#"
             <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
                 <s:Body>
@@ -353,7 +1210,7 @@ mod tests {
         "#;
 
         let xml = Element::parse(xml_str.as_bytes()).unwrap();
-        let result = client.extract_response(&xml, "Play");
+        let result = extract_response(&xml, "Play");
 
         assert!(result.is_ok());
         let response = result.unwrap();
@@ -362,8 +1219,6 @@ mod tests {
 
     #[test]
     fn test_extract_response_with_soap_fault() {
-        let client = SoapClient::get();
-
         let xml_str = r#"
             <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
                 <s:Body>
@@ -382,26 +1237,129 @@ mod tests {
         "#;
 
         let xml = Element::parse(xml_str.as_bytes()).unwrap();
-        let result = client.extract_response(&xml, "Play");
+        let result = extract_response(&xml, "Play");
 
         assert!(result.is_err());
         match result.unwrap_err() {
-            SoapError::Fault(code) => assert_eq!(code, 401),
+            SoapError::Fault(fault) => {
+                assert_eq!(fault.code, 401);
+                assert_eq!(fault.description.as_deref(), Some("Invalid Action"));
+                assert_eq!(fault.fault_string.as_deref(), Some("UPnPError"));
+            }
             _ => panic!("Expected SoapError::Fault"),
         }
     }
 
     #[test]
-    fn test_extract_response_missing_body() {
-        let client = SoapClient::get();
+    fn test_fault_code_accepts_spec_casing() {
+        let xml = Element::parse(
+            br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><s:Fault>
+                <detail><UPnPError xmlns="urn:schemas-upnp-org:control-1-0"><errorCode> 602 </errorCode></UPnPError></detail>
+            </s:Fault></s:Body></s:Envelope>"#
+                .as_slice(),
+        )
+        .unwrap();
+        let fault = parse_fault(&xml).unwrap();
+        assert_eq!(fault, SoapFault::new(602));
+        assert_eq!(fault.to_string(), "error code 602");
+    }
+
+    #[test]
+    fn test_fault_with_description() {
+        let xml = Element::parse(
+            br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><s:Fault>
+                <faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring>
+                <detail><UPnPError xmlns="urn:schemas-upnp-org:control-1-0"><errorCode>701</errorCode><errorDescription> Transition not available </errorDescription></UPnPError></detail>
+            </s:Fault></s:Body></s:Envelope>"#
+                .as_slice(),
+        )
+        .unwrap();
+        let error = SoapError::Fault(parse_fault(&xml).unwrap());
+        assert_eq!(error.fault_code(), Some(701));
+        assert_eq!(
+            error.to_string(),
+            "SOAP fault: error code 701: Transition not available"
+        );
+        assert_eq!(SoapError::Parse("x".to_string()).fault_code(), None);
+    }
+
+    #[test]
+    fn test_fault_with_empty_description() {
+        let xml = Element::parse(
+            br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><s:Fault>
+                <faultstring></faultstring>
+                <detail><UPnPError><errorCode>714</errorCode><errorDescription/></UPnPError></detail>
+            </s:Fault></s:Body></s:Envelope>"#
+                .as_slice(),
+        )
+        .unwrap();
+        assert_eq!(parse_fault(&xml), Some(SoapFault::new(714)));
+    }
+
+    #[test]
+    fn test_subscription_headers_from_classic_and_new_firmware() {
+        let classic = SubscriptionResponse::from_headers(
+            [
+                ("server", "Linux UPnP/1.0 Sonos/70.3-35220 (ZPS1)"),
+                ("sid", "uuid:RINCON_000E58A0B1C201400_sub0000000123"),
+                ("timeout", "Second-1800"),
+                ("content-length", "0"),
+                ("connection", "close"),
+            ],
+            3600,
+        )
+        .unwrap();
+        assert_eq!(classic.sid, "uuid:RINCON_000E58A0B1C201400_sub0000000123");
+        assert_eq!(classic.timeout_seconds, 1800);
+        assert!(classic.extra_headers().is_empty());
+
+        // UPnP 2.0 firmware: bare SID, a second TIMEOUT, multicast headers
+        let multicast = SubscriptionResponse::from_headers(
+            [
+                ("server", "Linux UPnP/2.0 Sonos/80.1-55240 (ZPS27)"),
+                ("SID", "RINCON_000E58A0B1C201400_sub0000000456"),
+                ("TIMEOUT", "Second-infinite"),
+                ("TIMEOUT", "Second-900"),
+                ("ACCEPTED-STATEVAR", "LastChange,GroupVolume"),
+                ("BOOTID.UPNP.ORG", "17"),
+                ("content-length", "0"),
+            ],
+            3600,
+        )
+        .unwrap();
+        assert_eq!(multicast.sid, "uuid:RINCON_000E58A0B1C201400_sub0000000456");
+        assert_eq!(multicast.timeout_seconds, 900);
+        assert_eq!(
+            multicast.extra_header("Accepted-StateVar"),
+            Some("LastChange,GroupVolume")
+        );
+        assert_eq!(multicast.extra_headers().len(), 2);
+
+        let no_timeout =
+            SubscriptionResponse::from_headers([("sid", "uuid:x"), ("timeout", "")], 3600);
+        assert_eq!(no_timeout.unwrap().timeout_seconds, 3600);
+        assert!(matches!(
+            SubscriptionResponse::from_headers([("timeout", "Second-1800")], 3600),
+            Err(SoapError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_normalize_sid() {
+        assert_eq!(normalize_sid("uuid:RINCON_1_sub1"), "uuid:RINCON_1_sub1");
+        assert_eq!(normalize_sid(" UUID:RINCON_1_sub1 "), "uuid:RINCON_1_sub1");
+        assert_eq!(normalize_sid("RINCON_1_sub1"), "uuid:RINCON_1_sub1");
+    }
 
+    #[test]
+    fn test_extract_response_missing_body() {
         let xml_str = r#"
             <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
             </s:Envelope>
         "#;
 
         let xml = Element::parse(xml_str.as_bytes()).unwrap();
-        let result = client.extract_response(&xml, "Play");
+        let result = extract_response(&xml, "Play");
 
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -412,8 +1370,6 @@ mod tests {
 
     #[test]
     fn test_extract_response_missing_action_response() {
-        let client = SoapClient::get();
-
         let xml_str = r#"
             <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
                 <s:Body>
@@ -422,7 +1378,7 @@ mod tests {
         "#;
 
         let xml = Element::parse(xml_str.as_bytes()).unwrap();
-        let result = client.extract_response(&xml, "Play");
+        let result = extract_response(&xml, "Play");
 
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -433,8 +1389,6 @@ mod tests {
 
     #[test]
     fn test_soap_fault_with_default_error_code() {
-        let client = SoapClient::get();
-
         let xml_str = r#"
             <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
                 <s:Body>
@@ -447,12 +1401,582 @@ mod tests {
         "#;
 
         let xml = Element::parse(xml_str.as_bytes()).unwrap();
-        let result = client.extract_response(&xml, "Play");
+        let result = extract_response(&xml, "Play");
 
         assert!(result.is_err());
         match result.unwrap_err() {
-            SoapError::Fault(code) => assert_eq!(code, 500), // Default error code
+            SoapError::Fault(fault) => {
+                assert_eq!(fault.code, 500); // Default error code
+                assert_eq!(fault.description, None);
+                assert_eq!(fault.fault_string.as_deref(), Some("Internal Error"));
+            }
             _ => panic!("Expected SoapError::Fault"),
         }
     }
+
+    /// Serve one request on an ephemeral local port, answering with
+    /// `status` and `body`; the handle yields the raw request
+    fn serve_once(status: &str, body: &str) -> (u16, std::thread::JoinHandle<String>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+            stream.write_all(response.as_bytes()).unwrap();
+            request
+        });
+        (port, handle)
+    }
+
+    /// Serve `requests` GETs of a document tagged `etag`, answering 304
+    /// to those that send it back; the handle yields each raw request
+    fn serve_tagged(
+        etag: &'static str,
+        body: &'static str,
+        requests: usize,
+    ) -> (u16, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let mut seen = Vec::new();
+            for _ in 0..requests {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let revalidated = request
+                    .to_ascii_lowercase()
+                    .contains(&format!("if-none-match: {etag}"));
+                let response = if revalidated {
+                    format!(
+                        "HTTP/1.1 304 Not Modified\r\nETag: {etag}\r\nConnection: close\r\n\r\n"
+                    )
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nETag: {etag}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+                seen.push(request);
+            }
+            seen
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_fetch_resource_cached_revalidates_and_serves_stale() {
+        let (port, server) = serve_tagged("\"v1\"", "<root/>", 2);
+        let url = format!("http://127.0.0.1:{port}/xml/device_description.xml");
+        let cache = HttpCache::new(4);
+        let client = SoapClient::get();
+
+        let first = client.fetch_resource_cached(&url, &cache).unwrap();
+        let second = client.fetch_resource_cached(&url, &cache).unwrap();
+        assert_eq!(first.body, b"<root/>");
+        assert_eq!(second.body, b"<root/>");
+        assert_eq!(second.content_type.as_deref(), Some("text/xml"));
+
+        let requests = server.join().unwrap();
+        assert!(!requests[0].to_ascii_lowercase().contains("if-none-match"));
+        assert!(requests[1]
+            .to_ascii_lowercase()
+            .contains("\r\nif-none-match: \"v1\"\r\n"));
+
+        // The device is gone; the body validated a moment ago still serves
+        let stale = client.fetch_resource_cached(&url, &cache).unwrap();
+        assert_eq!(stale.body, b"<root/>");
+        let uncached = client.fetch_resource_cached(&url, &HttpCache::new(4));
+        assert!(matches!(uncached, Err(SoapError::Network(_))));
+    }
+
+    const AVT: &str = "urn:schemas-upnp-org:service:AVTransport:1";
+
+    /// Quick retries for the flaky-server tests
+    fn fast_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::default()
+            .with_max_attempts(max_attempts)
+            .with_initial_backoff(Duration::from_millis(5))
+    }
+
+    /// Serve forever, closing the first `failures` connections after reading
+    /// the request and answering later ones with `response`; the counter
+    /// tracks connections accepted
+    fn serve_flaky(
+        failures: usize,
+        response: String,
+    ) -> (u16, Arc<std::sync::atomic::AtomicUsize>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { return };
+                let seen = counter.fetch_add(1, Ordering::SeqCst);
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                let _ = reader.read_exact(&mut body);
+                if seen >= failures {
+                    let _ = stream.write_all(response.as_bytes());
+                }
+            }
+        });
+        (port, accepted)
+    }
+
+    fn http_response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    const TRANSPORT_INFO: &str = r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><u:GetTransportInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><CurrentTransportState>PLAYING</CurrentTransportState></u:GetTransportInfoResponse></s:Body></s:Envelope>"#;
+
+    fn get_transport_info(port: u16, policy: Option<&RetryPolicy>) -> Result<Element, SoapError> {
+        let address = format!("127.0.0.1:{port}");
+        let (endpoint, payload) = (
+            "MediaRenderer/AVTransport/Control",
+            "<InstanceID>0</InstanceID>",
+        );
+        match policy {
+            Some(policy) => SoapClient::get().call_with_retry(
+                policy,
+                &address,
+                endpoint,
+                AVT,
+                "GetTransportInfo",
+                payload,
+            ),
+            None => SoapClient::get().call(&address, endpoint, AVT, "GetTransportInfo", payload),
+        }
+    }
+
+    #[test]
+    fn test_call_with_retry_recovers_from_dropped_connections() {
+        use std::sync::atomic::Ordering;
+
+        let (port, accepted) = serve_flaky(2, http_response("200 OK", TRANSPORT_INFO));
+        let response = get_transport_info(port, Some(&fast_retries(3))).unwrap();
+        assert_eq!(response.name, "GetTransportInfoResponse");
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_call_with_retry_gives_up_after_max_attempts() {
+        use std::sync::atomic::Ordering;
+
+        let (port, accepted) = serve_flaky(3, http_response("200 OK", TRANSPORT_INFO));
+        let result = get_transport_info(port, Some(&fast_retries(2)));
+        assert!(matches!(result, Err(SoapError::Network(_))), "{result:?}");
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+
+        // A plain call never retries
+        let (port, accepted) = serve_flaky(1, http_response("200 OK", TRANSPORT_INFO));
+        assert!(get_transport_info(port, None).is_err());
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_call_with_retry_stops_once_a_response_arrives() {
+        use std::sync::atomic::Ordering;
+
+        let fault = r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring><detail><UPnPError><errorCode>701</errorCode></UPnPError></detail></s:Fault></s:Body></s:Envelope>"#;
+        let (port, accepted) = serve_flaky(0, http_response("500 Internal Server Error", fault));
+        let result = get_transport_info(port, Some(&fast_retries(3)));
+        assert_eq!(result.unwrap_err().fault_code(), Some(701));
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        // The head arrived but the body was cut short
+        let truncated = "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: 500\r\nConnection: close\r\n\r\n<s:Envelope".to_string();
+        let (port, accepted) = serve_flaky(0, truncated);
+        let result = get_transport_info(port, Some(&fast_retries(3)));
+        assert!(matches!(result, Err(SoapError::Network(_))), "{result:?}");
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_retry_backoff_doubles_up_to_max() {
+        let policy = RetryPolicy::default()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(350));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+        assert_eq!(RetryPolicy::default().with_max_attempts(0).max_attempts, 1);
+    }
+
+    #[test]
+    fn test_call_with_port_round_trip() {
+        let (port, server) = serve_once(
+            "200 OK",
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><u:GetTransportInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><CurrentTransportState>PLAYING</CurrentTransportState></u:GetTransportInfoResponse></s:Body></s:Envelope>"#,
+        );
+
+        let response = SoapClient::get()
+            .call_with_port(
+                "127.0.0.1",
+                port,
+                "MediaRenderer/AVTransport/Control",
+                AVT,
+                "GetTransportInfo",
+                "<InstanceID>0</InstanceID>",
+            )
+            .unwrap();
+        assert_eq!(
+            response
+                .get_child("CurrentTransportState")
+                .and_then(|e| e.get_text()),
+            Some("PLAYING".into())
+        );
+
+        let request = server.join().unwrap().to_ascii_lowercase();
+        assert!(request.starts_with("post /mediarenderer/avtransport/control http/1.1\r\n"));
+        assert!(request.contains(&format!("\r\nhost: 127.0.0.1:{port}\r\n")));
+        assert!(request.contains(
+            "\r\nsoapaction: \"urn:schemas-upnp-org:service:avtransport:1#gettransportinfo\"\r\n"
+        ));
+        assert!(request.contains("<instanceid>0</instanceid>"));
+    }
+
+    #[test]
+    fn test_call_reads_port_from_address() {
+        let (port, server) = serve_once(
+            "500 Internal Server Error",
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring><detail><UPnPError xmlns="urn:schemas-upnp-org:control-1-0"><errorCode>701</errorCode></UPnPError></detail></s:Fault></s:Body></s:Envelope>"#,
+        );
+
+        let result = SoapClient::get().call(
+            &format!("127.0.0.1:{port}"),
+            "MediaRenderer/AVTransport/Control",
+            AVT,
+            "Play",
+            "<InstanceID>0</InstanceID><Speed>1</Speed>",
+        );
+        assert_eq!(result.unwrap_err().fault_code(), Some(701));
+
+        let request = server.join().unwrap().to_ascii_lowercase();
+        assert!(request.contains(&format!("\r\nhost: 127.0.0.1:{port}\r\n")));
+    }
+
+    #[test]
+    fn test_eventing_requests_close_connections_unless_kept_alive() {
+        let client = SoapClient::with_config(SoapClientConfig::default());
+        assert_eq!(
+            client.eventing_connection("127.0.0.1"),
+            EventingConnection::Close
+        );
+
+        let (port, server) = serve_once("200 OK", "");
+        client
+            .unsubscribe(
+                "127.0.0.1",
+                port,
+                "MediaRenderer/AVTransport/Event",
+                "uuid:1",
+            )
+            .unwrap();
+        let request = server.join().unwrap().to_ascii_lowercase();
+        assert!(request.starts_with("unsubscribe /mediarenderer/avtransport/event http/1.1\r\n"));
+        assert!(request.contains("\r\nconnection: close\r\n"));
+
+        client.set_transport(
+            "127.0.0.1",
+            DeviceTransport::http().with_eventing_connection(EventingConnection::KeepAlive),
+        );
+        assert_eq!(
+            client.eventing_connection("[127.0.0.1]"),
+            EventingConnection::KeepAlive
+        );
+        let (port, server) = serve_once("200 OK", "");
+        client
+            .unsubscribe(
+                "127.0.0.1",
+                port,
+                "MediaRenderer/AVTransport/Event",
+                "uuid:1",
+            )
+            .unwrap();
+        let request = server.join().unwrap().to_ascii_lowercase();
+        assert!(!request.contains("connection: close"));
+
+        let keep_alive = SoapClient::with_config(
+            SoapClientConfig::default().with_eventing_connection(EventingConnection::KeepAlive),
+        );
+        assert_eq!(
+            keep_alive.eventing_connection("127.0.0.1"),
+            EventingConnection::KeepAlive
+        );
+    }
+
+    /// Serve one TLS connection per entry of `responses` with a freshly
+    /// made self-signed certificate; the handle yields each raw request,
+    /// empty where the client gave up during the handshake
+    fn serve_tls(
+        responses: Vec<String>,
+    ) -> (u16, CertFingerprint, std::thread::JoinHandle<Vec<String>>) {
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+        use std::io::{BufRead, BufReader, Write};
+
+        let certified = rcgen::generate_simple_self_signed(vec!["127.0.0.1".to_string()]).unwrap();
+        let cert = CertificateDer::from(certified.cert.der().to_vec());
+        let fingerprint = CertFingerprint::of_der(&cert);
+        let key =
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()));
+        let config = Arc::new(
+            rustls::ServerConfig::builder_with_provider(Arc::new(
+                rustls::crypto::ring::default_provider(),
+            ))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap(),
+        );
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let mut seen = Vec::new();
+            for response in responses {
+                let (tcp, _) = listener.accept().unwrap();
+                let connection = rustls::ServerConnection::new(Arc::clone(&config)).unwrap();
+                let mut stream = rustls::StreamOwned::new(connection, tcp);
+                let mut request = String::new();
+                let mut reader = BufReader::new(&mut stream);
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        break;
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                if !request.is_empty() {
+                    stream.write_all(response.as_bytes()).unwrap();
+                    stream.conn.send_close_notify();
+                    let _ = stream.flush();
+                }
+                seen.push(request);
+            }
+            seen
+        });
+        (port, fingerprint, handle)
+    }
+
+    #[test]
+    fn test_https_transport_checks_pinned_certificate() {
+        let subscribed = "HTTP/1.1 200 OK\r\nSID: uuid:RINCON_1_sub1\r\nTIMEOUT: Second-1800\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string();
+        let (port, fingerprint, server) = serve_tls(vec![
+            String::new(),
+            subscribed,
+            http_response("200 OK", TRANSPORT_INFO),
+        ]);
+        let client = SoapClient::with_config(SoapClientConfig::default());
+        let subscribe = || {
+            // The address names the plain HTTP port; the transport replaces it
+            client.subscribe(
+                "127.0.0.1",
+                DEFAULT_PORT,
+                "MediaRenderer/AVTransport/Event",
+                "http://127.0.0.1:3400/callback",
+                1800,
+            )
+        };
+
+        let stranger = CertFingerprint::of_der(b"another device");
+        client.set_transport(
+            "127.0.0.1",
+            DeviceTransport::https().with_port(port).pinned(stranger),
+        );
+        assert!(matches!(subscribe(), Err(SoapError::Network(_))));
+
+        client.set_transport(
+            "127.0.0.1",
+            DeviceTransport::https().with_port(port).pinned(fingerprint),
+        );
+        let subscription = subscribe().unwrap();
+        assert_eq!(subscription.sid, "uuid:RINCON_1_sub1");
+
+        client.set_transport("127.0.0.1", DeviceTransport::https().with_port(port));
+        let response = client
+            .call(
+                "127.0.0.1",
+                "MediaRenderer/AVTransport/Control",
+                AVT,
+                "GetTransportInfo",
+                "<InstanceID>0</InstanceID>",
+            )
+            .unwrap();
+        assert_eq!(response.name, "GetTransportInfoResponse");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].is_empty());
+        let subscribe_request = requests[1].to_ascii_lowercase();
+        assert!(subscribe_request
+            .starts_with("subscribe /mediarenderer/avtransport/event http/1.1\r\n"));
+        assert!(subscribe_request.contains(&format!("\r\nhost: 127.0.0.1:{port}\r\n")));
+        assert!(requests[2].starts_with("POST /MediaRenderer/AVTransport/Control"));
+
+        // Other clients, the shared one included, keep plain HTTP
+        assert_eq!(SoapClient::get().transport("127.0.0.1"), None);
+        assert!(client.clear_transport("127.0.0.1").is_some());
+    }
+
+    #[test]
+    fn test_read_timeout_from_config() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Accept and never answer
+        let server = std::thread::spawn(move || listener.accept().unwrap());
+
+        let client = SoapClient::with_config(
+            SoapClientConfig::default().with_read_timeout(Duration::from_millis(100)),
+        );
+        let started = std::time::Instant::now();
+        let result = client.call_with_port(
+            "127.0.0.1",
+            port,
+            "MediaRenderer/AVTransport/Control",
+            AVT,
+            "Play",
+            "<InstanceID>0</InstanceID><Speed>1</Speed>",
+        );
+        assert!(matches!(result, Err(SoapError::Network(_))), "{result:?}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        drop(server.join().unwrap());
+    }
+
+    /// Answer every request with an empty PlayResponse, keeping each
+    /// connection open; the count is of connections accepted
+    fn serve_keep_alive() -> (u16, Arc<std::sync::atomic::AtomicUsize>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accepted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        let body = format!(
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><u:PlayResponse xmlns:u="{AVT}"/></s:Body></s:Envelope>"#
+        );
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                counter.fetch_add(1, Ordering::Relaxed);
+                let body = body.clone();
+                std::thread::spawn(move || {
+                    let mut stream = stream;
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    loop {
+                        let mut length = 0;
+                        loop {
+                            let mut line = String::new();
+                            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                                return;
+                            }
+                            if let Some((name, value)) = line.split_once(':') {
+                                if name.eq_ignore_ascii_case("content-length") {
+                                    length = value.trim().parse().unwrap();
+                                }
+                            }
+                            if line == "\r\n" {
+                                break;
+                            }
+                        }
+                        let mut request = vec![0; length];
+                        reader.read_exact(&mut request).unwrap();
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\n\r\n{body}",
+                            body.len()
+                        );
+                        stream.write_all(response.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+        (port, accepted)
+    }
+
+    #[test]
+    fn test_pool_reuses_connections_until_idle() {
+        let client = SoapClient::with_config(
+            SoapClientConfig::default().with_idle_timeout(Duration::from_millis(100)),
+        );
+        let (port, accepted) = serve_keep_alive();
+        let play = || {
+            client
+                .call_with_port("127.0.0.1", port, "Control", AVT, "Play", "")
+                .unwrap();
+        };
+
+        for _ in 0..3 {
+            play();
+        }
+        let stats = client.pool_stats();
+        assert_eq!((stats.hits, stats.misses), (Some(2), Some(1)));
+        assert_eq!((stats.active, stats.devices), (0, 1));
+        assert_eq!(accepted.load(Ordering::Relaxed), 1);
+
+        std::thread::sleep(Duration::from_millis(150));
+        play();
+        let stats = client.pool_stats();
+        assert_eq!((stats.misses, stats.evicted), (Some(2), 1));
+        assert_eq!(accepted.load(Ordering::Relaxed), 2);
+        assert_eq!(client.connections().opened(&format!("127.0.0.1:{port}")), 2);
+    }
 }
@@ -0,0 +1,239 @@
+//! HTTP agents per device
+//!
+//! Each device (`host:port`) gets its own agent, so requests to one device
+//! never wait on another's connections. At most `max_per_host` requests run
+//! against a device at once; further callers wait for one to finish. A
+//! device whose agent sat unused for `idle_timeout` loses it, closing the
+//! keep-alive connections it held.
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Use of a [`SoapClient`](crate::SoapClient)'s per-device connection pool
+///
+/// Counts are shared by every client on the same pool and cover SOAP calls,
+/// eventing requests kept alive and resource fetches; eventing requests
+/// that close their connection bypass the pool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Requests sent on a connection kept from an earlier one; `None` when
+    /// the pool's agents don't count the connections they open (an agent
+    /// from [`with_agent()`](crate::SoapClient::with_agent))
+    pub hits: Option<u64>,
+    /// Connections opened because the device had none idle; `None` when
+    /// they aren't counted
+    pub misses: Option<u64>,
+    /// Requests in flight, over all devices
+    pub active: usize,
+    /// Devices with an agent, busy or idle
+    pub devices: usize,
+    /// Agents dropped after sitting unused for the idle timeout
+    pub evicted: u64,
+}
+
+type BuildAgent = Box<dyn Fn() -> ureq::Agent + Send + Sync>;
+
+/// The agent of each device, with its in-flight requests
+pub(crate) struct DevicePool {
+    build: BuildAgent,
+    max_per_host: usize,
+    idle_timeout: Duration,
+    devices: Mutex<HashMap<String, Device>>,
+    /// Signalled when a request finishes
+    released: Condvar,
+    requests: AtomicU64,
+    /// Connections opened by the agents built here; `None` when they
+    /// aren't counted
+    opened: Option<Arc<AtomicU64>>,
+    evicted: AtomicU64,
+}
+
+struct Device {
+    agent: ureq::Agent,
+    active: usize,
+    idle_since: Instant,
+}
+
+impl DevicePool {
+    /// A pool building each device's agent with `build`
+    ///
+    /// `opened` is the count of connections `build`'s agents open, if they
+    /// count them.
+    pub(crate) fn new(
+        build: BuildAgent,
+        max_per_host: usize,
+        idle_timeout: Duration,
+        opened: Option<Arc<AtomicU64>>,
+    ) -> Self {
+        Self {
+            build,
+            max_per_host: max_per_host.max(1),
+            idle_timeout,
+            devices: Mutex::default(),
+            released: Condvar::new(),
+            requests: AtomicU64::new(0),
+            opened,
+            evicted: AtomicU64::new(0),
+        }
+    }
+
+    /// The agent of the device at `host` (`ip:port`), once fewer than
+    /// `max_per_host` requests are running against it
+    ///
+    /// The slot is held until the lease is dropped, so keep it until the
+    /// response has been read.
+    pub(crate) fn checkout(&self, host: &str) -> Lease<'_> {
+        let mut devices = self.lock();
+        self.evict_idle(&mut devices);
+        loop {
+            let device = devices.entry(host.to_string()).or_insert_with(|| Device {
+                agent: (self.build)(),
+                active: 0,
+                idle_since: Instant::now(),
+            });
+            if device.active < self.max_per_host {
+                device.active += 1;
+                self.requests.fetch_add(1, Ordering::Relaxed);
+                return Lease {
+                    pool: self,
+                    host: host.to_string(),
+                    agent: device.agent.clone(),
+                };
+            }
+            devices = self
+                .released
+                .wait(devices)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> PoolStats {
+        let mut devices = self.lock();
+        self.evict_idle(&mut devices);
+        let misses = self
+            .opened
+            .as_ref()
+            .map(|opened| opened.load(Ordering::Relaxed));
+        PoolStats {
+            hits: misses.map(|misses| self.requests.load(Ordering::Relaxed).saturating_sub(misses)),
+            misses,
+            active: devices.values().map(|device| device.active).sum(),
+            devices: devices.len(),
+            evicted: self.evicted.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drop the agents of devices unused for `idle_timeout`
+    fn evict_idle(&self, devices: &mut HashMap<String, Device>) {
+        let before = devices.len();
+        devices.retain(|_, device| {
+            device.active > 0 || device.idle_since.elapsed() < self.idle_timeout
+        });
+        let evicted = before - devices.len();
+        if evicted > 0 {
+            self.evicted.fetch_add(evicted as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn release(&self, host: &str) {
+        let mut devices = self.lock();
+        if let Some(device) = devices.get_mut(host) {
+            device.active -= 1;
+            device.idle_since = Instant::now();
+        }
+        drop(devices);
+        self.released.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Device>> {
+        self.devices.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl std::fmt::Debug for DevicePool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DevicePool")
+            .field("max_per_host", &self.max_per_host)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// A device's agent, holding one of its request slots until dropped
+pub(crate) struct Lease<'a> {
+    pool: &'a DevicePool,
+    host: String,
+    agent: ureq::Agent,
+}
+
+impl Deref for Lease<'_> {
+    type Target = ureq::Agent;
+
+    fn deref(&self) -> &ureq::Agent {
+        &self.agent
+    }
+}
+
+impl Drop for Lease<'_> {
+    fn drop(&mut self) {
+        self.pool.release(&self.host);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn pool(max_per_host: usize, idle_timeout: Duration) -> DevicePool {
+        DevicePool::new(
+            Box::new(|| ureq::AgentBuilder::new().build()),
+            max_per_host,
+            idle_timeout,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_checkout_waits_for_a_free_slot() {
+        let pool = Arc::new(pool(1, Duration::from_secs(60)));
+        let first = pool.checkout("10.0.0.1:1400");
+        let _other = pool.checkout("10.0.0.2:1400");
+        assert_eq!(pool.stats().active, 2);
+
+        let waiter = {
+            let pool = Arc::clone(&pool);
+            thread::spawn(move || {
+                let _lease = pool.checkout("10.0.0.1:1400");
+            })
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiter.is_finished(), "second request to the device waited");
+        drop(first);
+        waiter.join().unwrap();
+        assert_eq!(pool.stats().active, 1);
+    }
+
+    #[test]
+    fn test_idle_devices_are_evicted() {
+        let pool = pool(2, Duration::from_millis(20));
+        let busy = pool.checkout("10.0.0.1:1400");
+        drop(pool.checkout("10.0.0.2:1400"));
+        assert_eq!(pool.stats().devices, 2);
+
+        thread::sleep(Duration::from_millis(40));
+        let stats = pool.stats();
+        assert_eq!((stats.devices, stats.evicted), (1, 1), "busy device kept");
+        drop(busy);
+        let stats = pool.stats();
+        assert_eq!(
+            (stats.hits, stats.misses),
+            (None, None),
+            "uncounted agents can't tell hits from misses"
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//! Per-device transport: plain HTTP or HTTPS
+//!
+//! Firmware with the secure local API enforced answers control and event
+//! requests only over HTTPS on port 1443, with a certificate the device
+//! signed itself. No certificate authority vouches for it, so it is
+//! accepted by configuration instead: [`CertificateTrust::AcceptSelfSigned`]
+//! takes whatever the device presents (encryption without authentication),
+//! [`CertificateTrust::Pinned`] only a certificate with a known SHA-256
+//! fingerprint.
+//!
+//! A [`SoapClient`](crate::SoapClient) keeps one [`DeviceTransport`] per
+//! host; hosts without one are spoken to over plain HTTP on the port their
+//! address names. HTTPS hosts the table doesn't know (e.g. in a URL given to
+//! [`fetch_resource()`](crate::SoapClient::fetch_resource)) are verified
+//! against the usual web roots.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+/// Port of the secure local API
+pub const HTTPS_PORT: u16 = 1443;
+
+/// URL scheme of a device's endpoints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Scheme {
+    #[default]
+    Http,
+    Https,
+}
+
+impl Scheme {
+    /// `http` or `https`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scheme::Http => "http",
+            Scheme::Https => "https",
+        }
+    }
+
+    /// Port devices serve this scheme on
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Scheme::Http => crate::DEFAULT_PORT,
+            Scheme::Https => HTTPS_PORT,
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// SHA-256 fingerprint of a DER-encoded certificate
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CertFingerprint([u8; 32]);
+
+impl CertFingerprint {
+    /// Fingerprint of `der`, a certificate in DER form
+    pub fn of_der(der: &[u8]) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, der);
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(digest.as_ref());
+        Self(bytes)
+    }
+
+    /// Parse 64 hex digits, optionally separated by colons as
+    /// `openssl x509 -fingerprint -sha256` prints them
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits: Vec<u8> = hex.bytes().filter(|b| *b != b':').collect();
+        if digits.len() != 64 {
+            return None;
+        }
+        let mut bytes = [0; 32];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            let pair = std::str::from_utf8(pair).ok()?;
+            *byte = u8::from_str_radix(pair, 16).ok()?;
+        }
+        Some(Self(bytes))
+    }
+
+    /// The raw digest
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{byte:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CertFingerprint({self})")
+    }
+}
+
+/// Which certificates an HTTPS device may present
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CertificateTrust {
+    /// Any certificate: the connection is encrypted, but whoever answers
+    /// at the device's address is believed to be it
+    #[default]
+    AcceptSelfSigned,
+    /// Only a certificate with one of these fingerprints
+    Pinned(Vec<CertFingerprint>),
+}
+
+impl CertificateTrust {
+    /// Whether a certificate with fingerprint `presented` is acceptable
+    pub fn accepts(&self, presented: &CertFingerprint) -> bool {
+        match self {
+            CertificateTrust::AcceptSelfSigned => true,
+            CertificateTrust::Pinned(pins) => pins.contains(presented),
+        }
+    }
+}
+
+/// How SUBSCRIBE, renewal and UNSUBSCRIBE requests use connections
+///
+/// Some older ZonePlayers leave a SUBSCRIBE that arrives over a reused
+/// keep-alive connection unanswered until the read timeout, but answer at
+/// once on a fresh one; Sonos' own controllers close every eventing
+/// connection. SOAP control calls keep pooling either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EventingConnection {
+    /// A fresh connection per request, sent with `Connection: close`
+    #[default]
+    Close,
+    /// Pooled keep-alive connections, shared with SOAP control calls
+    KeepAlive,
+}
+
+/// How to reach one device
+///
+/// The default is plain HTTP on the port the address names.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceTransport {
+    pub scheme: Scheme,
+    /// Port replacing the one the address names; `None` keeps it
+    pub port: Option<u16>,
+    /// Certificates accepted when `scheme` is HTTPS
+    pub trust: CertificateTrust,
+    /// Connection use of eventing requests; `None` leaves it to the
+    /// client's default
+    pub eventing: Option<EventingConnection>,
+}
+
+impl DeviceTransport {
+    /// Plain HTTP on the port the address names
+    pub fn http() -> Self {
+        Self::default()
+    }
+
+    /// HTTPS on [`HTTPS_PORT`], accepting the device's self-signed
+    /// certificate
+    pub fn https() -> Self {
+        Self {
+            scheme: Scheme::Https,
+            port: Some(HTTPS_PORT),
+            trust: CertificateTrust::AcceptSelfSigned,
+            eventing: None,
+        }
+    }
+
+    /// Use `port` whatever port the address names
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Accept only a certificate with fingerprint `fingerprint` (may be
+    /// called again to allow several, e.g. across a certificate renewal)
+    pub fn pinned(mut self, fingerprint: CertFingerprint) -> Self {
+        match &mut self.trust {
+            CertificateTrust::Pinned(pins) => pins.push(fingerprint),
+            trust => *trust = CertificateTrust::Pinned(vec![fingerprint]),
+        }
+        self
+    }
+
+    /// Use connections for eventing requests as `eventing` says, whatever
+    /// the client's default
+    pub fn with_eventing_connection(mut self, eventing: EventingConnection) -> Self {
+        self.eventing = Some(eventing);
+        self
+    }
+
+    /// Scheme and port for a request to an address naming `port`
+    pub fn endpoint(&self, port: u16) -> (Scheme, u16) {
+        (self.scheme, self.port.unwrap_or(port))
+    }
+}
+
+/// Transports by host, shared by the clients using one agent
+#[derive(Debug, Default)]
+pub(crate) struct Transports {
+    by_host: RwLock<HashMap<String, DeviceTransport>>,
+}
+
+impl Transports {
+    pub fn get(&self, host: &str) -> Option<DeviceTransport> {
+        self.by_host
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(host_key(host))
+            .cloned()
+    }
+
+    pub fn set(&self, host: &str, transport: DeviceTransport) {
+        self.by_host
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(host_key(host).to_string(), transport);
+    }
+
+    pub fn remove(&self, host: &str) -> Option<DeviceTransport> {
+        self.by_host
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(host_key(host))
+    }
+}
+
+/// An IPv6 host is looked up without the brackets a URL puts around it
+fn host_key(host: &str) -> &str {
+    host.strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
+/// TLS settings for an agent: device hosts are checked against their
+/// [`CertificateTrust`], any other host against the web roots
+pub(crate) fn tls_config(transports: Arc<Transports>) -> Arc<rustls::ClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let web = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), Arc::clone(&provider))
+        .build()
+        .expect("the bundled web roots are valid trust anchors");
+    let verifier = DeviceVerifier {
+        transports,
+        provider: Arc::clone(&provider),
+        web,
+    };
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .expect("the ring provider supports the default protocol versions")
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    Arc::new(config)
+}
+
+#[derive(Debug)]
+struct DeviceVerifier {
+    transports: Arc<Transports>,
+    provider: Arc<CryptoProvider>,
+    web: Arc<WebPkiServerVerifier>,
+}
+
+impl DeviceVerifier {
+    /// The trust configured for the host `server_name` names, if any
+    fn trust_for(&self, server_name: &ServerName<'_>) -> Option<CertificateTrust> {
+        let host = match server_name {
+            ServerName::IpAddress(ip) => IpAddr::from(*ip).to_string(),
+            ServerName::DnsName(name) => name.as_ref().to_string(),
+            _ => return None,
+        };
+        self.transports
+            .get(&host)
+            .filter(|transport| transport.scheme == Scheme::Https)
+            .map(|transport| transport.trust)
+    }
+}
+
+impl ServerCertVerifier for DeviceVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let Some(trust) = self.trust_for(server_name) else {
+            return self.web.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            );
+        };
+        let presented = CertFingerprint::of_der(end_entity);
+        if trust.accepts(&presented) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "certificate {presented} of {server_name:?} is not pinned"
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_hex_round_trip() {
+        let fingerprint = CertFingerprint::of_der(b"not really a certificate");
+        let printed = fingerprint.to_string();
+        assert_eq!(printed.len(), 32 * 3 - 1);
+        assert_eq!(CertFingerprint::from_hex(&printed), Some(fingerprint));
+        assert_eq!(
+            CertFingerprint::from_hex(&printed.replace(':', "").to_lowercase()),
+            Some(fingerprint)
+        );
+        assert_eq!(CertFingerprint::from_hex("AB:CD"), None);
+        assert_eq!(CertFingerprint::from_hex(&"zz".repeat(32)), None);
+    }
+
+    #[test]
+    fn test_transport_endpoint_and_pins() {
+        assert_eq!(DeviceTransport::http().endpoint(1401), (Scheme::Http, 1401));
+        assert_eq!(
+            DeviceTransport::https().endpoint(1400),
+            (Scheme::Https, HTTPS_PORT)
+        );
+
+        let old = CertFingerprint::of_der(b"old");
+        let new = CertFingerprint::of_der(b"new");
+        let transport = DeviceTransport::https().pinned(old).pinned(new);
+        assert_eq!(transport.trust, CertificateTrust::Pinned(vec![old, new]));
+        assert!(transport.trust.accepts(&new));
+        assert!(!transport
+            .trust
+            .accepts(&CertFingerprint::of_der(b"impostor")));
+        assert!(CertificateTrust::AcceptSelfSigned.accepts(&old));
+    }
+
+    #[test]
+    fn test_ipv6_hosts_match_with_or_without_brackets() {
+        let transports = Transports::default();
+        transports.set("[fe80::1]", DeviceTransport::https());
+        assert_eq!(transports.get("fe80::1"), Some(DeviceTransport::https()));
+        assert!(transports.remove("fe80::1").is_some());
+        assert_eq!(transports.get("[fe80::1]"), None);
+    }
+}
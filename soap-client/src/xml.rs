@@ -0,0 +1,207 @@
+//! Limits on XML that arrives from the network
+//!
+//! Device responses, NOTIFY bodies and device descriptions are scanned with
+//! [`check()`] before they reach a parser. The scan streams through the
+//! document without building it, so a hostile document is turned away
+//! before it costs more memory than its own bytes:
+//!
+//! - any `<!DOCTYPE>`, and with it entity declarations (XXE, billion laughs)
+//! - more than [`XmlLimits::max_bytes`] in all
+//! - elements nested deeper than [`XmlLimits::max_depth`]
+//! - more than [`XmlLimits::max_elements`] elements
+//! - more than [`XmlLimits::max_attribute_bytes`] of attributes on one element
+//!
+//! A rejection is an [`XmlViolation`] rather than a parse error, so callers
+//! can tell an attack or a misbehaving device from a merely malformed reply.
+//! Malformed XML that breaks none of the limits is left to the parser to
+//! report.
+
+use std::sync::{PoisonError, RwLock};
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use thiserror::Error;
+use xmltree::Element;
+
+use crate::SoapError;
+
+/// Bounds on XML accepted from the network
+///
+/// The defaults leave room for the largest documents Sonos sends, such as
+/// `ZoneGroupState` of a big household or a page of 100 queue entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XmlLimits {
+    /// Size of the whole document
+    pub max_bytes: usize,
+    /// Depth of element nesting, the root element being 1
+    pub max_depth: usize,
+    /// Elements in the whole document
+    pub max_elements: usize,
+    /// Bytes of attributes, names and quoting included, on any one element
+    pub max_attribute_bytes: usize,
+}
+
+impl XmlLimits {
+    /// The limits used unless [`set_global()`](Self::set_global) replaced them
+    pub const DEFAULT: Self = Self {
+        max_bytes: 8 * 1024 * 1024,
+        max_depth: 64,
+        max_elements: 100_000,
+        max_attribute_bytes: 64 * 1024,
+    };
+
+    /// Limits every network-facing parse in the process checks against
+    pub fn global() -> Self {
+        *LIMITS.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Check every later network-facing parse in the process against `self`
+    pub fn set_global(self) {
+        *LIMITS.write().unwrap_or_else(PoisonError::into_inner) = self;
+    }
+
+    /// Scan `xml` against these limits
+    pub fn check(&self, xml: &str) -> Result<(), XmlViolation> {
+        if xml.len() > self.max_bytes {
+            return Err(XmlViolation::TooLarge {
+                bytes: xml.len(),
+                limit: self.max_bytes,
+            });
+        }
+        let mut reader = Reader::from_str(xml);
+        // Depth is counted here; remembering open names would cost memory
+        reader.check_end_names(false);
+        let (mut depth, mut elements) = (0usize, 0usize);
+        loop {
+            let (start, empty) = match reader.read_event() {
+                Ok(Event::Start(start)) => (start, false),
+                Ok(Event::Empty(start)) => (start, true),
+                Ok(Event::End(_)) => {
+                    depth = depth.saturating_sub(1);
+                    continue;
+                }
+                Ok(Event::DocType(_)) => return Err(XmlViolation::Doctype),
+                // Left for the parser to report as malformed
+                Ok(Event::Eof) | Err(_) => return Ok(()),
+                Ok(_) => continue,
+            };
+            elements += 1;
+            if elements > self.max_elements {
+                return Err(XmlViolation::TooManyElements {
+                    limit: self.max_elements,
+                });
+            }
+            if depth + 1 > self.max_depth {
+                return Err(XmlViolation::TooDeep {
+                    limit: self.max_depth,
+                });
+            }
+            // The tag's bytes past its name are its attributes
+            let attribute_bytes = start.len() - start.name().as_ref().len();
+            if attribute_bytes > self.max_attribute_bytes {
+                return Err(XmlViolation::AttributeTooLarge {
+                    bytes: attribute_bytes,
+                    limit: self.max_attribute_bytes,
+                });
+            }
+            depth += usize::from(!empty);
+        }
+    }
+}
+
+impl Default for XmlLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static LIMITS: RwLock<XmlLimits> = RwLock::new(XmlLimits::DEFAULT);
+
+/// Why a document from the network was refused before parsing
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum XmlViolation {
+    /// The document declares a DTD, which may define or fetch entities
+    #[error("document type declarations are not accepted")]
+    Doctype,
+
+    /// The document is larger than [`XmlLimits::max_bytes`]
+    #[error("document of {bytes} bytes exceeds the limit of {limit}")]
+    TooLarge { bytes: usize, limit: usize },
+
+    /// Elements nest deeper than [`XmlLimits::max_depth`]
+    #[error("elements nest deeper than {limit}")]
+    TooDeep { limit: usize },
+
+    /// The document has more than [`XmlLimits::max_elements`] elements
+    #[error("more than {limit} elements")]
+    TooManyElements { limit: usize },
+
+    /// An element has more than [`XmlLimits::max_attribute_bytes`] of
+    /// attributes
+    #[error("{bytes} bytes of attributes on one element exceed the limit of {limit}")]
+    AttributeTooLarge { bytes: usize, limit: usize },
+}
+
+/// Scan `xml` against the [global](XmlLimits::global) limits
+pub fn check(xml: &str) -> Result<(), XmlViolation> {
+    XmlLimits::global().check(xml)
+}
+
+/// Parse a document from the network into an [`Element`], after [`check()`]
+pub fn parse_element(xml: &str) -> Result<Element, SoapError> {
+    check(xml)?;
+    Element::parse(xml.as_bytes()).map_err(|e| SoapError::Parse(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: XmlLimits = XmlLimits {
+        max_bytes: 1024,
+        max_depth: 4,
+        max_elements: 10,
+        max_attribute_bytes: 32,
+    };
+
+    #[test]
+    fn test_ordinary_documents_pass() {
+        let xml = r#"<?xml version="1.0"?><a x="1"><b><c/><c>text</c></b></a>"#;
+        assert_eq!(LIMITS.check(xml), Ok(()));
+        // Malformed XML is the parser's to report
+        assert_eq!(LIMITS.check("<a><b></a"), Ok(()));
+    }
+
+    #[test]
+    fn test_violations() {
+        let doctype = r#"<!DOCTYPE a [<!ENTITY x SYSTEM "file:///etc/passwd">]><a>&x;</a>"#;
+        assert_eq!(LIMITS.check(doctype), Err(XmlViolation::Doctype));
+        assert!(matches!(
+            LIMITS.check(&"<a/>".repeat(300)),
+            Err(XmlViolation::TooLarge { bytes: 1200, .. })
+        ));
+        assert_eq!(
+            LIMITS.check("<a><a><a><a><a/></a></a></a></a>"),
+            Err(XmlViolation::TooDeep { limit: 4 })
+        );
+        // Siblings don't add depth
+        assert_eq!(
+            LIMITS.check(&format!("<a>{}</a>", "<b></b>".repeat(10))),
+            Err(XmlViolation::TooManyElements { limit: 10 })
+        );
+        assert!(matches!(
+            LIMITS.check(r#"<a b="0123456789" c="0123456789" d="x"/>"#),
+            Err(XmlViolation::AttributeTooLarge { limit: 32, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_element_reports_violations_apart_from_parse_errors() {
+        assert!(matches!(
+            parse_element("<!DOCTYPE a><a/>"),
+            Err(SoapError::XmlRejected(XmlViolation::Doctype))
+        ));
+        assert!(matches!(parse_element("<a>"), Err(SoapError::Parse(_))));
+        assert_eq!(parse_element("<a><b/></a>").unwrap().children.len(), 1);
+    }
+}
@@ -0,0 +1,81 @@
+//! Allocations and time to parse an AVTransport NOTIFY body
+//!
+//! Compares the previous serde mapping with the owned and borrowed parsers
+//! on a full event from a speaker playing a streaming queue. Run with:
+//!
+//! ```bash
+//! cargo bench -p sonos-api --bench av_transport_event
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use sonos_api::events::xml_utils;
+use sonos_api::services::av_transport::{AVTransportEvent, AVTransportEventRef};
+
+const FIXTURE: &str = include_str!("../tests/fixtures/av_transport_event.xml");
+const ITERATIONS: u32 = 20_000;
+
+/// Counts every allocation the process makes
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn bench(name: &str, parse: impl Fn(&str)) {
+    parse(FIXTURE);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    parse(FIXTURE);
+    let per_parse = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        parse(black_box(FIXTURE));
+    }
+    let elapsed = started.elapsed() / ITERATIONS;
+    println!("{name:<28} {per_parse:>5} allocations {elapsed:>10.2?} per event");
+}
+
+fn main() {
+    bench("serde mapping + state", |xml| {
+        let event: AVTransportEvent =
+            quick_xml::de::from_str(&xml_utils::strip_namespaces(xml)).unwrap();
+        black_box(event.into_state());
+    });
+    bench("owned event + state", |xml| {
+        black_box(AVTransportEvent::from_xml(xml).unwrap().into_state());
+    });
+    bench("borrowed event + state", |xml| {
+        let last_change = xml_utils::last_change(xml).unwrap();
+        black_box(AVTransportEventRef::parse(&last_change).unwrap().to_state());
+    });
+    bench("borrowed event, 3 fields", |xml| {
+        let last_change = xml_utils::last_change(xml).unwrap();
+        let event = AVTransportEventRef::parse(&last_change).unwrap();
+        black_box((
+            event.transport_state(),
+            event.current_track_uri(),
+            event.track_duration(),
+        ));
+    });
+}
@@ -3,7 +3,7 @@
 //! This example shows how to use the new ManagedSubscription API that provides
 //! automatic lifecycle management for UPnP subscriptions.
 
-use sonos_api::{Result, Service, SonosClient};
+use sonos_api::{Renewal, Result, Service, SonosClient};
 use std::thread;
 use std::time::Duration;
 
@@ -21,7 +21,7 @@ fn main() -> Result<()> {
         "http://192.168.1.50:8080/callback",
         1800, // 30 minutes
     ) {
-        Ok(subscription) => {
+        Ok(mut subscription) => {
             println!(
                 "✅ Created subscription: {}",
                 subscription.subscription_id()
@@ -36,7 +36,11 @@ fn main() -> Result<()> {
 
                 // Renew it
                 match subscription.renew() {
-                    Ok(()) => println!("✅ Subscription renewed successfully"),
+                    Ok(Renewal::Renewed) => println!("✅ Subscription renewed successfully"),
+                    Ok(Renewal::Resubscribed(fresh)) => {
+                        println!("🔁 Re-subscribed as {}", fresh.subscription_id());
+                        subscription = fresh;
+                    }
                     Err(e) => println!("❌ Failed to renew subscription: {e}"),
                 }
             } else {
@@ -0,0 +1,275 @@
+//! Non-blocking counterpart of [`SonosClient`](crate::SonosClient)
+//!
+//! Runs the same [`UPnPOperation`]s, building payloads and parsing responses
+//! with the same code and failing with the same [`ApiError`]s; only the HTTP
+//! is async. Needs the `async-client` feature and a Tokio runtime.
+
+use soap_client::{AsyncSoapClient, ClientIdentity, SoapClientConfig};
+use xmltree::Element;
+
+use crate::client::raw_payload;
+use crate::operation::{ComposableOperation, UPnPOperation};
+use crate::services::events::{
+    RenewOperation, RenewRequest, RenewResponse, SubscribeOperation, SubscribeRequest,
+    SubscribeResponse, UnsubscribeOperation, UnsubscribeRequest,
+};
+use crate::{ApiError, Result, Service};
+
+/// A client for executing Sonos operations from async code
+///
+/// Like [`SonosClient`](crate::SonosClient), methods that take a device `ip`
+/// also accept `ip:port`. Devices are reached over plain HTTP.
+///
+/// ```rust,no_run
+/// use sonos_api::AsyncSonosClient;
+/// use sonos_api::services::rendering_control;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = AsyncSonosClient::new();
+/// let volume = client
+///     .execute_enhanced("192.168.1.100", rendering_control::get_volume("Master".to_string()).build()?)
+///     .await?;
+/// println!("volume: {}", volume.current_volume);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AsyncSonosClient {
+    soap_client: AsyncSoapClient,
+}
+
+impl AsyncSonosClient {
+    /// Create a client with the default [`SoapClientConfig`]
+    pub fn new() -> Self {
+        Self::with_soap_client(AsyncSoapClient::new())
+    }
+
+    /// Create a client around `soap_client`, sharing its connection pool
+    pub fn with_soap_client(soap_client: AsyncSoapClient) -> Self {
+        Self { soap_client }
+    }
+
+    /// Create a client with its own connection pool, using `config`'s
+    /// timeouts, `USER-AGENT` and pool limits
+    pub fn with_soap_config(config: SoapClientConfig) -> Self {
+        Self::with_soap_client(AsyncSoapClient::with_config(config))
+    }
+
+    /// Identify as `identity` on every request, subscriptions included
+    pub fn with_identity(mut self, identity: &ClientIdentity) -> Self {
+        self.soap_client = self.soap_client.with_identity(identity);
+        self
+    }
+
+    /// The `USER-AGENT` header this client sends
+    pub fn user_agent(&self) -> &str {
+        self.soap_client.user_agent()
+    }
+
+    /// Execute `Op` with `request`
+    ///
+    /// The async [`SonosClient::execute()`](crate::SonosClient::execute).
+    pub async fn execute<Op: UPnPOperation>(
+        &self,
+        ip: &str,
+        request: &Op::Request,
+    ) -> Result<Op::Response> {
+        let payload = Op::build_payload(request)
+            .map_err(|e| ApiError::ParseError(format!("Validation error: {e}")))?;
+        let service_info = Op::SERVICE.info();
+        let xml = self
+            .soap_client
+            .call(
+                ip,
+                service_info.endpoint,
+                service_info.service_uri,
+                Op::ACTION,
+                &payload,
+            )
+            .await
+            .map_err(|e| ApiError::from_soap(Op::SERVICE, e))?;
+
+        Op::parse_response(&xml)
+    }
+
+    /// Execute a built operation, retrying dropped connections if it opted in
+    ///
+    /// The async [`SonosClient::execute_enhanced()`](crate::SonosClient::execute_enhanced).
+    pub async fn execute_enhanced<Op: UPnPOperation>(
+        &self,
+        ip: &str,
+        operation: ComposableOperation<Op>,
+    ) -> Result<Op::Response> {
+        let payload = operation
+            .build_payload()
+            .map_err(|e| ApiError::ParseError(format!("Validation error: {e}")))?;
+        let service_info = Op::SERVICE.info();
+        let xml = match operation.retry() {
+            Some(policy) => {
+                self.soap_client
+                    .call_with_retry(
+                        policy,
+                        ip,
+                        service_info.endpoint,
+                        service_info.service_uri,
+                        Op::ACTION,
+                        &payload,
+                    )
+                    .await
+            }
+            None => {
+                self.soap_client
+                    .call(
+                        ip,
+                        service_info.endpoint,
+                        service_info.service_uri,
+                        Op::ACTION,
+                        &payload,
+                    )
+                    .await
+            }
+        }
+        .map_err(|e| ApiError::from_soap(Op::SERVICE, e))?;
+
+        operation.parse_response(&xml)
+    }
+
+    /// Call `action` on `service` with string arguments and return the raw
+    /// response element
+    ///
+    /// The async [`SonosClient::call_raw()`](crate::SonosClient::call_raw).
+    pub async fn call_raw(
+        &self,
+        ip: &str,
+        service: Service,
+        action: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Element> {
+        let payload = raw_payload(action, params)?;
+        let service_info = service.info();
+        self.soap_client
+            .call(
+                ip,
+                service_info.endpoint,
+                service_info.service_uri,
+                action,
+                &payload,
+            )
+            .await
+            .map_err(|e| ApiError::from_soap(service, e))
+    }
+
+    /// Subscribe to `service`'s events, sent to `callback_url`
+    ///
+    /// Unlike [`SonosClient::subscribe()`](crate::SonosClient::subscribe)
+    /// this returns the bare subscription: renew it with
+    /// [`renew_subscription()`](Self::renew_subscription) before the granted
+    /// timeout runs out, and end it with [`unsubscribe()`](Self::unsubscribe).
+    pub async fn subscribe(
+        &self,
+        ip: &str,
+        service: Service,
+        callback_url: &str,
+        timeout_seconds: u32,
+    ) -> Result<SubscribeResponse> {
+        let request = SubscribeRequest {
+            callback_url: callback_url.to_string(),
+            timeout_seconds,
+        };
+        SubscribeOperation::execute_async(&self.soap_client, ip, service, &request).await
+    }
+
+    /// Renew the subscription `sid` to `service`'s events
+    ///
+    /// Fails with [`ApiError::SubscriptionUnknown`] if the device no longer
+    /// knows `sid`; subscribe again in that case.
+    pub async fn renew_subscription(
+        &self,
+        ip: &str,
+        service: Service,
+        sid: &str,
+        timeout_seconds: u32,
+    ) -> Result<RenewResponse> {
+        let request = RenewRequest {
+            sid: sid.to_string(),
+            timeout_seconds,
+        };
+        RenewOperation::execute_async(&self.soap_client, ip, service, &request).await
+    }
+
+    /// End the subscription `sid` to `service`'s events
+    pub async fn unsubscribe(&self, ip: &str, service: Service, sid: &str) -> Result<()> {
+        let request = UnsubscribeRequest {
+            sid: sid.to_string(),
+        };
+        UnsubscribeOperation::execute_async(&self.soap_client, ip, service, &request).await?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use super::*;
+    use crate::mock::{Action, MockDevice, Scenario};
+    use crate::services::rendering_control::{self, GetVolumeOperation, GetVolumeOperationRequest};
+
+    #[tokio::test]
+    async fn test_operations_run_through_shared_payloads_and_parsers() {
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(31));
+        let addr = device.addr().to_string();
+        let client = AsyncSonosClient::new();
+
+        let request = GetVolumeOperationRequest {
+            instance_id: 0,
+            channel: "Master".to_string(),
+        };
+        let response = client.execute::<GetVolumeOperation>(&addr, &request).await;
+        assert_eq!(response.unwrap().current_volume, 31);
+
+        let set = rendering_control::set_volume("Master".to_string(), 40)
+            .build()
+            .unwrap();
+        client.execute_enhanced(&addr, set).await.unwrap();
+        assert_eq!(device.volume(), 40);
+
+        let error = client
+            .call_raw(&addr, Service::RenderingControl, "Bad Action", &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::InvalidParameter(_)));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_lifecycle() {
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let addr = device.addr().to_string();
+        let client = AsyncSonosClient::new();
+        let service = Service::RenderingControl;
+
+        let subscription = client
+            .subscribe(&addr, service, "http://127.0.0.1:3400/cb", 1800)
+            .await
+            .unwrap();
+        assert_eq!(device.sids(service), vec![subscription.sid.clone()]);
+        client
+            .renew_subscription(&addr, service, &subscription.sid, 1800)
+            .await
+            .unwrap();
+        client
+            .unsubscribe(&addr, service, &subscription.sid)
+            .await
+            .unwrap();
+        assert_eq!(device.live_subscriptions(), 0);
+
+        let expired = client
+            .subscribe(&addr, service, "http://127.0.0.1:3400/cb", 1800)
+            .await
+            .unwrap();
+        device.perform(Action::Expire(service));
+        let error = client
+            .renew_subscription(&addr, service, &expired.sid, 1800)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::SubscriptionUnknown(sid) if sid == expired.sid));
+    }
+}
@@ -1,7 +1,22 @@
-use crate::operation::{ComposableOperation, UPnPOperation};
+use crate::clock::{SharedClock, SystemClock};
+use crate::operation::{
+    BatchItem, BatchResult, ComposableOperation, Conditional, ConditionalResult, Sequence,
+    SequenceResult, UPnPOperation,
+};
+use crate::rate_limit::WriteLimiter;
+use crate::subscription::SubscriptionDirectory;
 use crate::{ApiError, ManagedSubscription, Result, Service, SonosOperation};
-use soap_client::SoapClient;
+use soap_client::{split_host_port, SoapClient};
+
+pub use soap_client::{
+    CertFingerprint, CertificateTrust, ClientIdentity, DeviceTransport, EventingConnection,
+    HttpCache, HttpResource, PoolStats, RetryPolicy, Revalidation, Scheme, SoapClientConfig,
+    Validators, XmlLimits, HTTPS_PORT,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
+use xmltree::Element;
 
 /// A client for executing Sonos operations against actual devices
 ///
@@ -9,6 +24,9 @@ use std::time::Instant;
 /// and actual network requests to Sonos speakers. It uses the soap-client
 /// crate to handle the underlying SOAP communication.
 ///
+/// Methods that take a device `ip` also accept `ip:port`, for devices that
+/// are not on the standard port 1400 (port forwards, test fixtures).
+///
 /// # Subscription Management
 ///
 /// The primary API for managing UPnP event subscriptions is `create_managed_subscription()`,
@@ -33,6 +51,10 @@ use std::time::Instant;
 #[derive(Debug, Clone)]
 pub struct SonosClient {
     soap_client: SoapClient,
+    clock: SharedClock,
+    subscription_directory: Option<SubscriptionDirectory>,
+    subscription_owner: Option<String>,
+    write_limiter: Arc<WriteLimiter>,
 }
 
 impl SonosClient {
@@ -42,9 +64,7 @@ impl SonosClient {
     /// All SonosClient instances created this way share the same underlying HTTP client
     /// and connection pool, reducing memory usage and improving performance.
     pub fn new() -> Self {
-        Self {
-            soap_client: SoapClient::get().clone(),
-        }
+        Self::with_soap_client(SoapClient::get().clone())
     }
 
     /// Create a Sonos client with a custom SOAP client (for advanced use cases)
@@ -52,7 +72,167 @@ impl SonosClient {
     /// Most applications should use `SonosClient::new()` instead. This method is
     /// provided for cases where custom SOAP client configuration is needed.
     pub fn with_soap_client(soap_client: SoapClient) -> Self {
-        Self { soap_client }
+        let clock: SharedClock = Arc::new(SystemClock);
+        Self {
+            soap_client,
+            write_limiter: Arc::new(WriteLimiter::new(Arc::clone(&clock))),
+            clock,
+            subscription_directory: None,
+            subscription_owner: None,
+        }
+    }
+
+    /// Create a Sonos client with its own connection pool, using `config`'s
+    /// connect / read timeouts and `USER-AGENT`
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use sonos_api::{SoapClientConfig, SonosClient};
+    ///
+    /// // Large topologies on slow Wi-Fi
+    /// let client = SonosClient::with_soap_config(
+    ///     SoapClientConfig::default().with_read_timeout(Duration::from_secs(30)),
+    /// );
+    /// ```
+    pub fn with_soap_config(config: SoapClientConfig) -> Self {
+        Self::with_soap_client(SoapClient::with_config(config))
+    }
+
+    /// Identify as `identity` on every request, subscriptions included
+    ///
+    /// Without this the client sends `USER-AGENT: sonos-sdk/{version}`.
+    pub fn with_identity(mut self, identity: &ClientIdentity) -> Self {
+        self.soap_client = self.soap_client.with_identity(identity);
+        self
+    }
+
+    /// The `USER-AGENT` header this client sends
+    pub fn user_agent(&self) -> &str {
+        self.soap_client.user_agent()
+    }
+
+    /// TCP connections opened to `address` (an IP or `ip:port`) by the
+    /// HTTP agent behind this client, SOAP calls and subscriptions alike
+    ///
+    /// Requests to one device reuse a keep-alive connection when they don't
+    /// overlap, so this grows much slower than the request count.
+    pub fn connections_opened(&self, address: &str) -> u64 {
+        self.soap_client.connections().opened(address)
+    }
+
+    /// Use of the per-device connection pool behind this client
+    ///
+    /// Each device has its own agent, running at most
+    /// [`max_connections_per_host`](SoapClientConfig::max_connections_per_host)
+    /// requests at once, so calls to different speakers never queue behind
+    /// each other. `hits` counts requests that reused a kept-alive
+    /// connection, `misses` connections opened and `active` requests in
+    /// flight; clients sharing a pool share the counts.
+    pub fn pool_stats(&self) -> PoolStats {
+        self.soap_client.pool_stats()
+    }
+
+    /// Reach the device at `ip` through `transport`, e.g. HTTPS for
+    /// firmware with the secure local API enforced
+    ///
+    /// Applies to calls and subscriptions alike, whatever port an address
+    /// names. Clients created with [`new()`](Self::new) share one table, so
+    /// this also covers the subscriptions an event manager makes.
+    ///
+    /// ```rust,no_run
+    /// use sonos_api::{CertFingerprint, DeviceTransport, SonosClient};
+    ///
+    /// let client = SonosClient::new();
+    /// // Port 1443, accepting the speaker's self-signed certificate...
+    /// client.set_device_transport("192.168.1.100", DeviceTransport::https());
+    /// // ...or only the certificate it had when first paired
+    /// let pin = CertFingerprint::from_hex("AB:CD:…").expect("a SHA-256 fingerprint");
+    /// client.set_device_transport("192.168.1.101", DeviceTransport::https().pinned(pin));
+    /// ```
+    pub fn set_device_transport(&self, ip: &str, transport: DeviceTransport) {
+        let (host, _) = split_host_port(ip);
+        self.soap_client.set_transport(host, transport);
+    }
+
+    /// The transport set for the device at `ip`, if any (otherwise plain
+    /// HTTP)
+    pub fn device_transport(&self, ip: &str) -> Option<DeviceTransport> {
+        let (host, _) = split_host_port(ip);
+        self.soap_client.transport(host)
+    }
+
+    /// Use connections for SUBSCRIBE, renewal and UNSUBSCRIBE requests as
+    /// `eventing` says, for devices whose transport doesn't say otherwise
+    ///
+    /// The default, [`EventingConnection::Close`], opens a fresh connection
+    /// per request; SOAP calls keep pooling either way.
+    pub fn with_eventing_connection(mut self, eventing: EventingConnection) -> Self {
+        self.soap_client = self.soap_client.with_eventing_connection(eventing);
+        self
+    }
+
+    /// How eventing requests to the device at `ip` use connections
+    pub fn eventing_connection(&self, ip: &str) -> EventingConnection {
+        let (host, _) = split_host_port(ip);
+        self.soap_client.eventing_connection(host)
+    }
+
+    /// Measure subscription expiry on `clock` instead of the system clock
+    ///
+    /// Tests use a [`ManualClock`](crate::clock::ManualClock) to simulate
+    /// the host sleeping.
+    ///
+    /// The write limiter measures on it too; clones made before this call
+    /// keep the old limiter.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.write_limiter = Arc::new(self.write_limiter.with_clock(Arc::clone(&clock)));
+        self.clock = clock;
+        self
+    }
+
+    /// Limiter for the writes sent through this client and its clones
+    ///
+    /// Off until [`WriteLimiter::set_limit()`] is called. Writes made with
+    /// [`execute_enhanced()`](Self::execute_enhanced) aren't limited; callers
+    /// that know an operation is a write, like the SDK, send it through
+    /// [`WriteLimiter::send()`].
+    pub fn write_limiter(&self) -> &WriteLimiter {
+        &self.write_limiter
+    }
+
+    /// Register the subscriptions this client creates in `directory`
+    ///
+    /// Lets an event broker sharing the directory find them instead of
+    /// subscribing to the same service a second time; see
+    /// [`SubscriptionDirectory`].
+    pub fn with_subscription_directory(mut self, directory: SubscriptionDirectory) -> Self {
+        self.subscription_directory = Some(directory);
+        self
+    }
+
+    /// Tag the subscriptions this client creates with the component that owns them
+    ///
+    /// Reported by [`ManagedSubscription::owner()`], e.g. in a broker's
+    /// conflict error.
+    pub fn with_subscription_owner(mut self, owner: impl Into<String>) -> Self {
+        self.subscription_owner = Some(owner.into());
+        self
+    }
+
+    /// Fetch a plain HTTP resource from a device (e.g. album art)
+    ///
+    /// Uses the same shared HTTP agent as SOAP calls.
+    pub fn fetch_resource(&self, url: &str) -> Result<HttpResource> {
+        Ok(self.soap_client.fetch_resource(url)?)
+    }
+
+    /// Fetch a plain HTTP resource through `cache`
+    ///
+    /// A URL fetched before is revalidated with its `ETag` /
+    /// `Last-Modified`; see [`HttpCache::fetch()`] for when the cached body
+    /// is returned instead of an error.
+    pub fn fetch_resource_cached(&self, url: &str, cache: &HttpCache) -> Result<HttpResource> {
+        Ok(self.soap_client.fetch_resource_cached(url, cache)?)
     }
 
     /// Execute a Sonos operation against a device
@@ -94,11 +274,51 @@ impl SonosClient {
                 Op::ACTION,
                 &payload,
             )
-            .map_err(|e| match e {
-                soap_client::SoapError::Network(msg) => ApiError::NetworkError(msg),
-                soap_client::SoapError::Parse(msg) => ApiError::ParseError(msg),
-                soap_client::SoapError::Fault(code) => ApiError::SoapFault(code),
-            })?;
+            .map_err(|e| ApiError::from_soap(Op::SERVICE, e))?;
+
+        Op::parse_response(&xml)
+    }
+
+    /// Execute a Sonos operation, retrying dropped connections
+    ///
+    /// Like [`execute()`](Self::execute), but a connection that fails before
+    /// any response arrives is retried with backoff per `policy` (see
+    /// [`SoapClient::call_with_retry()`](soap_client::SoapClient::call_with_retry)).
+    /// Meant for reads and other actions that are safe to repeat.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use sonos_api::{RetryPolicy, SonosClient};
+    /// use sonos_api::services::av_transport::{GetTransportInfoOperation, GetTransportInfoRequest};
+    ///
+    /// let client = SonosClient::new();
+    /// let request = GetTransportInfoRequest { instance_id: 0 };
+    /// let response = client.execute_with_retry::<GetTransportInfoOperation>(
+    ///     "192.168.1.100",
+    ///     &request,
+    ///     &RetryPolicy::default(),
+    /// )?;
+    /// ```
+    pub fn execute_with_retry<Op: SonosOperation>(
+        &self,
+        ip: &str,
+        request: &Op::Request,
+        policy: &RetryPolicy,
+    ) -> Result<Op::Response> {
+        let service_info = Op::SERVICE.info();
+        let payload = Op::build_payload(request);
+
+        let xml = self
+            .soap_client
+            .call_with_retry(
+                policy,
+                ip,
+                service_info.endpoint,
+                service_info.service_uri,
+                Op::ACTION,
+                &payload,
+            )
+            .map_err(|e| ApiError::from_soap(Op::SERVICE, e))?;
 
         Op::parse_response(&xml)
     }
@@ -149,25 +369,205 @@ impl SonosClient {
             }
         }
 
-        // Execute SOAP call
-        let xml = self
-            .soap_client
-            .call(
+        // Execute SOAP call, retrying dropped connections if the operation opted in
+        let xml = match operation.retry() {
+            Some(policy) => self.soap_client.call_with_retry(
+                policy,
                 ip,
                 service_info.endpoint,
                 service_info.service_uri,
                 Op::ACTION,
                 &payload,
-            )
-            .map_err(|e| match e {
-                soap_client::SoapError::Network(msg) => ApiError::NetworkError(msg),
-                soap_client::SoapError::Parse(msg) => ApiError::ParseError(msg),
-                soap_client::SoapError::Fault(code) => ApiError::SoapFault(code),
-            })?;
+            ),
+            None => self.soap_client.call(
+                ip,
+                service_info.endpoint,
+                service_info.service_uri,
+                Op::ACTION,
+                &payload,
+            ),
+        }
+        .map_err(|e| ApiError::from_soap(Op::SERVICE, e))?;
 
         operation.parse_response(&xml)
     }
 
+    /// Execute operations on several devices at once
+    ///
+    /// Each `(ip, operation)` pair is sent on its own thread, so setting the
+    /// volume on eight speakers takes about one round trip rather than
+    /// eight. Operations whose type can't be batched with itself (see
+    /// [`UPnPOperation::can_batch_with()`]) still run concurrently across
+    /// devices, but one after another on the same device, in the order
+    /// given. One failure doesn't stop the rest; the result has an item per
+    /// pair, in order.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use sonos_api::services::rendering_control;
+    ///
+    /// let result = client.execute_batch(speakers.iter().map(|ip| {
+    ///     let op = rendering_control::set_volume("Master".to_string(), 30).build()?;
+    ///     Ok((ip.to_string(), op))
+    /// }).collect::<Result<Vec<_>, sonos_api::ValidationError>>()?);
+    /// for failed in result.failures() {
+    ///     eprintln!("{}: {:?}", failed.ip, failed.result);
+    /// }
+    /// ```
+    pub fn execute_batch<Op>(
+        &self,
+        operations: impl IntoIterator<Item = (String, ComposableOperation<Op>)>,
+    ) -> BatchResult<Op::Response>
+    where
+        Op: UPnPOperation,
+        ComposableOperation<Op>: Send,
+        Op::Response: Send,
+    {
+        let operations: Vec<_> = operations.into_iter().collect();
+        // Operations that run one after another, each lane on its own thread
+        let mut lanes: Vec<Vec<(usize, String, ComposableOperation<Op>)>> = Vec::new();
+        let parallel = Op::can_batch_with::<Op>();
+        let mut lane_of_ip: HashMap<String, usize> = HashMap::new();
+        for (index, (ip, operation)) in operations.into_iter().enumerate() {
+            let lane = match lane_of_ip.get(&ip) {
+                Some(&lane) if !parallel => lane,
+                _ => {
+                    lane_of_ip.insert(ip.clone(), lanes.len());
+                    lanes.push(Vec::new());
+                    lanes.len() - 1
+                }
+            };
+            lanes[lane].push((index, ip, operation));
+        }
+
+        let mut items: Vec<(usize, BatchItem<Op::Response>)> = std::thread::scope(|scope| {
+            let workers: Vec<_> = lanes
+                .into_iter()
+                .map(|lane| {
+                    scope.spawn(move || {
+                        lane.into_iter()
+                            .map(|(index, ip, operation)| {
+                                let result = self.execute_enhanced(&ip, operation);
+                                (index, BatchItem { ip, result })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("batch worker panicked"))
+                .collect()
+        });
+        items.sort_by_key(|(index, _)| *index);
+        BatchResult {
+            items: items.into_iter().map(|(_, item)| item).collect(),
+        }
+    }
+
+    /// Execute the steps of `sequence` on one device, one after another
+    ///
+    /// Steps run in the order they were added, except that a step whose
+    /// operation declares [`dependencies()`](UPnPOperation::dependencies)
+    /// waits for the steps with those actions. The first failure stops the
+    /// sequence: the result says which step failed and holds the responses
+    /// of the steps before it. A dependency cycle fails before anything is
+    /// sent.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use sonos_api::services::av_transport::{play, set_av_transport_uri, stop};
+    /// use sonos_api::Sequence;
+    ///
+    /// let sequence = Sequence::new()
+    ///     .then(stop().build()?)
+    ///     .then(set_av_transport_uri(uri, metadata).build()?)
+    ///     .then(play("1".to_string()).build()?);
+    /// if let SequenceResult::Aborted { action, error, .. } = client.execute_sequence(ip, sequence) {
+    ///     eprintln!("{action} failed: {error}");
+    /// }
+    /// ```
+    pub fn execute_sequence(&self, ip: &str, sequence: Sequence) -> SequenceResult {
+        sequence.run(self, ip)
+    }
+
+    /// Execute the probe of `conditional`, then its target only if the
+    /// predicate holds for the probe's response
+    ///
+    /// A failed probe is returned as the error and the target isn't sent;
+    /// so is a failed target. The probe's response is in the result either
+    /// way.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use sonos_api::services::rendering_control::{get_mute, set_mute};
+    /// use sonos_api::Conditional;
+    ///
+    /// // Unmute only if muted
+    /// let unmute = Conditional::new(
+    ///     get_mute("Master".to_string()).build()?,
+    ///     |mute| mute.current_mute,
+    ///     set_mute("Master".to_string(), false).build()?,
+    /// );
+    /// client.execute_conditional(ip, unmute)?;
+    /// ```
+    pub fn execute_conditional<P, T, F>(
+        &self,
+        ip: &str,
+        conditional: Conditional<P, T, F>,
+    ) -> Result<ConditionalResult<P::Response, T::Response>>
+    where
+        P: UPnPOperation,
+        T: UPnPOperation,
+        F: FnOnce(&P::Response) -> bool,
+    {
+        conditional.run(self, ip)
+    }
+
+    /// Call an action this crate has no typed operation for
+    ///
+    /// An escape hatch for actions added by new firmware. Prefer the typed
+    /// operations ([`execute()`](Self::execute), [`execute_enhanced()`](Self::execute_enhanced))
+    /// where one exists: they validate their input and parse the response.
+    ///
+    /// `params` become the action's arguments, in order, with values XML
+    /// escaped. The control path and SOAPACTION come from `service`, and
+    /// faults are translated exactly as for typed operations. Returns the
+    /// `<{action}Response>` element; see [`extract_values()`] to read it.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use sonos_api::{extract_values, Service, SonosClient};
+    ///
+    /// let client = SonosClient::new();
+    /// let response = client.call_raw(
+    ///     "192.168.1.100",
+    ///     Service::RenderingControl,
+    ///     "GetVolume",
+    ///     &[("InstanceID", "0"), ("Channel", "Master")],
+    /// )?;
+    /// let values = extract_values(&response, &["CurrentVolume"]);
+    /// ```
+    pub fn call_raw(
+        &self,
+        ip: &str,
+        service: Service,
+        action: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Element> {
+        let payload = raw_payload(action, params)?;
+        let service_info = service.info();
+        self.soap_client
+            .call(
+                ip,
+                service_info.endpoint,
+                service_info.service_uri,
+                action,
+                &payload,
+            )
+            .map_err(|e| ApiError::from_soap(service, e))
+    }
+
     /// Subscribe to UPnP events from a service
     ///
     /// This creates a subscription to the specified service's event endpoint.
@@ -283,6 +683,9 @@ impl SonosClient {
             callback_url.to_string(),
             timeout_seconds,
             self.soap_client.clone(),
+            Arc::clone(&self.clock),
+            self.subscription_owner.clone(),
+            self.subscription_directory.clone(),
         )
     }
 }
@@ -293,9 +696,462 @@ impl Default for SonosClient {
     }
 }
 
+/// The payload of `action` with `params`, checking that the names are XML
+/// names and escaping the values
+pub(crate) fn raw_payload(action: &str, params: &[(&str, &str)]) -> Result<String> {
+    if !is_xml_name(action) {
+        return Err(ApiError::InvalidParameter(format!(
+            "invalid action name: {action:?}"
+        )));
+    }
+    let mut payload = String::new();
+    for (name, value) in params {
+        if !is_xml_name(name) {
+            return Err(ApiError::InvalidParameter(format!(
+                "invalid argument name: {name:?}"
+            )));
+        }
+        let value = crate::operation::xml_escape(value);
+        payload.push_str(&format!("<{name}>{value}</{name}>"));
+    }
+    Ok(payload)
+}
+
+/// Read named child values from a response element
+///
+/// Companion to [`SonosClient::call_raw()`]. Children that are missing are
+/// left out of the map; empty ones map to an empty string.
+pub fn extract_values(element: &Element, names: &[&str]) -> HashMap<String, String> {
+    names
+        .iter()
+        .filter_map(|name| {
+            crate::operation::opt_child_text(element, name).map(|text| (name.to_string(), text))
+        })
+        .collect()
+}
+
+/// Whether `s` can be used as an unprefixed XML element name
+fn is_xml_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde::Serialize;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Mutex, OnceLock};
+    use std::thread;
+
+    /// Request line, SOAPACTION and body of the last request per action
+    type Requests = Mutex<HashMap<String, (String, String, String)>>;
+
+    fn requests() -> &'static Requests {
+        static REQUESTS: OnceLock<Requests> = OnceLock::new();
+        REQUESTS.get_or_init(Default::default)
+    }
+
+    /// Start (once) a mock device that answers `MadeUpAction` and faults
+    /// every other action with UPnP error 401; returns its address
+    fn start_mock() -> &'static str {
+        static ADDR: OnceLock<String> = OnceLock::new();
+        ADDR.get_or_init(|| {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock device");
+            let addr = listener.local_addr().unwrap().to_string();
+            thread::spawn(move || {
+                for mut stream in listener.incoming().flatten() {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request_line = String::new();
+                    let _ = reader.read_line(&mut request_line);
+                    let (mut soap_action, mut content_length) = (String::new(), 0);
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            match name.to_ascii_lowercase().as_str() {
+                                "soapaction" => soap_action = value.trim().to_string(),
+                                "content-length" => {
+                                    content_length = value.trim().parse().unwrap_or(0)
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    let _ = reader.read_exact(&mut body);
+                    let action = soap_action
+                        .rsplit('#')
+                        .next()
+                        .unwrap_or_default()
+                        .trim_end_matches('"')
+                        .to_string();
+                    requests().lock().unwrap().insert(
+                        action.clone(),
+                        (
+                            request_line.trim().to_string(),
+                            soap_action,
+                            String::from_utf8_lossy(&body).into_owned(),
+                        ),
+                    );
+
+                    let (status, inner) = if action == "MadeUpAction" {
+                        (
+                            "200 OK",
+                            r#"<u:MadeUpActionResponse xmlns:u="urn:mock"><Answer>42</Answer><Empty/></u:MadeUpActionResponse>"#,
+                        )
+                    } else {
+                        (
+                            "500 Internal Server Error",
+                            "<s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring>\
+                             <detail><UPnPError><errorCode>401</errorCode></UPnPError></detail></s:Fault>",
+                        )
+                    };
+                    let envelope = format!(
+                        r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body>{inner}</s:Body></s:Envelope>"#
+                    );
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 {status}\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{envelope}",
+                        envelope.len()
+                    );
+                }
+            });
+            addr
+        })
+    }
+
+    #[test]
+    fn test_call_raw_builds_escaped_envelope() {
+        let mock_addr = start_mock();
+        let client = SonosClient::new();
+        let note = "<a & 'b'>";
+        let response = client
+            .call_raw(
+                mock_addr,
+                Service::RenderingControl,
+                "MadeUpAction",
+                &[("InstanceID", "0"), ("Note", note)],
+            )
+            .unwrap();
+
+        let (request_line, soap_action, body) = requests().lock().unwrap()["MadeUpAction"].clone();
+        assert!(request_line.starts_with("POST /MediaRenderer/RenderingControl/Control "));
+        assert_eq!(
+            soap_action,
+            r#""urn:schemas-upnp-org:service:RenderingControl:1#MadeUpAction""#
+        );
+        assert!(
+            body.contains("<InstanceID>0</InstanceID><Note>&lt;a &amp; &apos;b&apos;&gt;</Note>")
+        );
+        let envelope = Element::parse(body.as_bytes()).unwrap();
+        let call = envelope
+            .get_child("Body")
+            .and_then(|b| b.get_child("MadeUpAction"))
+            .unwrap();
+        assert_eq!(
+            call.namespace.as_deref(),
+            Some("urn:schemas-upnp-org:service:RenderingControl:1")
+        );
+        assert_eq!(call.get_child("Note").unwrap().get_text().unwrap(), note);
+
+        let values = extract_values(&response, &["Answer", "Empty", "Missing"]);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values["Answer"], "42");
+        assert_eq!(values["Empty"], "");
+    }
+
+    #[test]
+    fn test_call_raw_translates_faults_like_typed_operations() {
+        let mock_addr = start_mock();
+        let client = SonosClient::new();
+        let raw = client
+            .call_raw(mock_addr, Service::RenderingControl, "GetVolume", &[])
+            .unwrap_err();
+        let typed = client
+            .execute_enhanced(
+                mock_addr,
+                crate::services::rendering_control::get_volume("Master".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(format!("{raw:?}"), format!("{typed:?}"));
+
+        assert!(matches!(
+            client.call_raw(mock_addr, Service::RenderingControl, "Bad Action", &[]),
+            Err(ApiError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            client.call_raw(mock_addr, Service::RenderingControl, "Ok", &[("a><b", "1")]),
+            Err(ApiError::InvalidParameter(_))
+        ));
+    }
+
+    /// A device that drops the first `failures` connections after reading
+    /// the request, then answers GetVolume with 30
+    fn serve_flaky(failures: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            for (seen, stream) in listener.incoming().enumerate() {
+                let Ok(mut stream) = stream else { return };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap_or(0);
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                let _ = reader.read_exact(&mut body);
+                if seen < failures {
+                    continue;
+                }
+                let envelope = r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><u:GetVolumeResponse xmlns:u="urn:schemas-upnp-org:service:RenderingControl:1"><CurrentVolume>30</CurrentVolume></u:GetVolumeResponse></s:Body></s:Envelope>"#;
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{envelope}",
+                    envelope.len()
+                );
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn test_execute_enhanced_retries_only_when_opted_in() {
+        let client = SonosClient::new();
+        let get_volume = || crate::services::rendering_control::get_volume("Master".to_string());
+        let policy = RetryPolicy::default()
+            .with_initial_backoff(std::time::Duration::from_millis(1))
+            .with_max_attempts(3);
+
+        let address = serve_flaky(2);
+        let response = client
+            .execute_enhanced(&address, get_volume().with_retry(policy).build().unwrap())
+            .unwrap();
+        assert_eq!(response.current_volume, 30);
+
+        let address = serve_flaky(1);
+        assert!(matches!(
+            client.execute_enhanced(&address, get_volume().build().unwrap()),
+            Err(ApiError::NetworkError(_))
+        ));
+    }
+
+    /// When a request arrived and when it was answered
+    type Spans = Arc<Mutex<Vec<(Instant, Instant)>>>;
+
+    /// A device that answers any action after `delay`, serving connections
+    /// concurrently; returns its address and the span of each request
+    fn serve_slowly(delay: std::time::Duration) -> (String, Spans) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let spans = Spans::default();
+        let served = Arc::clone(&spans);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { return };
+                let served = Arc::clone(&served);
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let (mut action, mut content_length) = (String::new(), 0);
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                            break;
+                        }
+                        let Some((name, value)) = line.split_once(':') else {
+                            continue;
+                        };
+                        match name.to_ascii_lowercase().as_str() {
+                            "soapaction" => {
+                                let value = value.trim().trim_matches('"');
+                                action = value.rsplit('#').next().unwrap_or_default().to_string();
+                            }
+                            "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                            _ => {}
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    let _ = reader.read_exact(&mut body);
+                    let arrived = Instant::now();
+                    thread::sleep(delay);
+                    served.lock().unwrap().push((arrived, Instant::now()));
+                    let envelope = format!(
+                        r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><u:{action}Response xmlns:u="urn:mock"/></s:Body></s:Envelope>"#
+                    );
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{envelope}",
+                        envelope.len()
+                    );
+                });
+            }
+        });
+        (address, spans)
+    }
+
+    fn overlap(a: (Instant, Instant), b: (Instant, Instant)) -> bool {
+        a.0 < b.1 && b.0 < a.1
+    }
+
+    #[test]
+    fn test_execute_batch_overlaps_requests() {
+        let client = SonosClient::new();
+        let delay = std::time::Duration::from_millis(200);
+        let devices: Vec<_> = (0..4).map(|_| serve_slowly(delay)).collect();
+        let unreachable = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let set_volume = |volume| {
+            crate::services::rendering_control::set_volume("Master".to_string(), volume)
+                .build()
+                .unwrap()
+        };
+        let mut batch: Vec<_> = devices
+            .iter()
+            .map(|(address, _)| (address.clone(), set_volume(30)))
+            .collect();
+        batch.insert(2, (unreachable.clone(), set_volume(30)));
+
+        let result = client.execute_batch(batch);
+        assert_eq!(result.len(), 5);
+        assert_eq!(result.successes().count(), 4);
+        assert!(!result.all_succeeded());
+        assert_eq!(result.items[2].ip, unreachable);
+        assert!(result.items[2].result.is_err());
+        assert_eq!(result.items[3].ip, devices[2].0, "input order kept");
+
+        let spans: Vec<_> = devices
+            .iter()
+            .map(|(_, spans)| spans.lock().unwrap()[0])
+            .collect();
+        for (i, a) in spans.iter().enumerate() {
+            for b in &spans[i + 1..] {
+                assert!(overlap(*a, *b), "requests ran one after another");
+            }
+        }
+    }
+
+    #[test]
+    fn test_concurrent_calls_to_distinct_devices_overlap() {
+        let client = SonosClient::with_soap_config(SoapClientConfig::default());
+        let delay = std::time::Duration::from_millis(200);
+        let devices: Vec<_> = (0..12).map(|_| serve_slowly(delay)).collect();
+
+        let started = Instant::now();
+        thread::scope(|scope| {
+            for (address, _) in &devices {
+                let client = &client;
+                scope.spawn(move || {
+                    client
+                        .call_raw(
+                            address,
+                            Service::RenderingControl,
+                            "GetVolume",
+                            &[("InstanceID", "0"), ("Channel", "Master")],
+                        )
+                        .unwrap();
+                });
+            }
+        });
+        assert!(started.elapsed() < delay * 4, "calls ran one after another");
+
+        let spans: Vec<_> = devices
+            .iter()
+            .map(|(_, spans)| spans.lock().unwrap()[0])
+            .collect();
+        assert!(spans.iter().all(|span| overlap(*span, spans[0])));
+        let stats = client.pool_stats();
+        assert_eq!(
+            (stats.misses, stats.devices, stats.active),
+            (Some(12), 12, 0)
+        );
+    }
+
+    #[test]
+    fn test_calls_to_one_device_wait_for_a_free_connection() {
+        let client = SonosClient::with_soap_config(
+            SoapClientConfig::default().with_max_connections_per_host(1),
+        );
+        let (address, spans) = serve_slowly(std::time::Duration::from_millis(100));
+        thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    let _ = client.call_raw(&address, Service::AVTransport, "Play", &[]);
+                });
+            }
+        });
+        let spans = spans.lock().unwrap();
+        assert_eq!(spans.len(), 2);
+        assert!(!overlap(spans[0], spans[1]), "the second call waited");
+    }
+
+    #[test]
+    fn test_execute_batch_serializes_conflicting_operations_per_device() {
+        #[derive(Serialize)]
+        struct NoRequest;
+        impl crate::operation::Validate for NoRequest {}
+
+        /// An action that mustn't overlap itself on one device
+        struct Exclusive;
+        impl UPnPOperation for Exclusive {
+            type Request = NoRequest;
+            type Response = ();
+            const SERVICE: Service = Service::AVTransport;
+            const ACTION: &'static str = "Exclusive";
+
+            fn build_payload(_: &NoRequest) -> std::result::Result<String, crate::ValidationError> {
+                Ok(String::new())
+            }
+
+            fn parse_response(_: &Element) -> Result<()> {
+                Ok(())
+            }
+
+            fn can_batch_with<T: UPnPOperation>() -> bool {
+                false
+            }
+        }
+
+        let client = SonosClient::new();
+        let delay = std::time::Duration::from_millis(150);
+        let (first, first_spans) = serve_slowly(delay);
+        let (second, second_spans) = serve_slowly(delay);
+        let exclusive = || {
+            crate::OperationBuilder::<Exclusive>::new(NoRequest)
+                .build()
+                .unwrap()
+        };
+        let result = client.execute_batch([
+            (first.clone(), exclusive()),
+            (second, exclusive()),
+            (first, exclusive()),
+        ]);
+        assert!(result.all_succeeded());
+
+        let first_spans = first_spans.lock().unwrap().clone();
+        let second_spans = second_spans.lock().unwrap().clone();
+        assert_eq!(first_spans.len(), 2);
+        assert!(!overlap(first_spans[0], first_spans[1]));
+        assert!(overlap(first_spans[0], second_spans[0]));
+    }
 
     #[test]
     fn test_client_creation() {
@@ -0,0 +1,443 @@
+//! Time source for every time-based feature
+//!
+//! Subscription deadlines are measured on the monotonic clock, so NTP steps
+//! and manual clock changes can't make a renewal fire early or years late.
+//! A [`Clock`] also reports wall time: on most platforms the monotonic clock
+//! stops while the host sleeps, and comparing the two is how callers notice
+//! a sleep happened.
+//!
+//! The same clock also does the waiting: [`Clock::sleep()`] for threads and
+//! [`Clock::timer()`] for async tasks. Components that take a
+//! [`SharedClock`] (the client, the event broker's renewal, polling and
+//! event-timeout timers, the state manager and the SDK) can share one
+//! [`ManualClock`], so a test moves all of them through virtual time
+//! together.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError, Weak};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of the current time
+pub trait Clock: Debug + Send + Sync {
+    /// Monotonic time, used for all deadlines
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, only used to detect host sleep
+    fn wall(&self) -> SystemTime;
+
+    /// Block the calling thread until `duration` has passed on this clock
+    ///
+    /// The default sleeps in real time.
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    /// A future that completes once this clock reaches `at`
+    ///
+    /// The default fires in real time, from a timer thread shared by every
+    /// such timer, so it works under any async runtime.
+    fn timer(&self, at: Instant) -> Timer {
+        Timer::new(at, Arc::clone(real_timers()))
+    }
+}
+
+/// A clock shared between a client and the subscriptions it creates
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to, for simulating sleep in tests.
+///
+/// Clones share the same time. [`advance()`](Self::advance) wakes every
+/// sleeper and timer whose deadline it passes; with
+/// [`auto_advance()`](Self::auto_advance) the clock also jumps to the next
+/// deadline by itself once nobody has moved it for a while.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    wall_start: SystemTime,
+    timers: Arc<Timers>,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at the current time
+    pub fn new() -> Self {
+        Self {
+            wall_start: SystemTime::now(),
+            timers: Arc::new(Timers::new(Some(Instant::now()))),
+        }
+    }
+
+    /// Move both monotonic and wall time forward
+    pub fn advance(&self, by: Duration) {
+        let mut queue = self.timers.lock();
+        queue.elapsed += by;
+        queue.advances += 1;
+        self.timers.wake_due(&mut queue);
+    }
+
+    /// Jump to the earliest pending deadline whenever `idle` of real time
+    /// passes without the clock moving
+    ///
+    /// Lets a test run time-based code to completion without knowing its
+    /// deadlines. The helper thread stops when the last clone is dropped.
+    pub fn auto_advance(self, idle: Duration) -> Self {
+        let timers = Arc::downgrade(&self.timers);
+        thread::Builder::new()
+            .name("manual-clock-auto-advance".to_string())
+            .spawn(move || auto_advance(timers, idle))
+            .expect("failed to spawn auto-advance thread");
+        self
+    }
+
+    /// Number of sleepers and timers waiting on this clock
+    pub fn pending(&self) -> usize {
+        self.timers.lock().entries.len()
+    }
+
+    /// Earliest deadline anybody is waiting for
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.timers.lock().earliest()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.timers.lock().elapsed
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.timers.now(&self.timers.lock())
+    }
+
+    fn wall(&self) -> SystemTime {
+        self.wall_start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let mut queue = self.timers.lock();
+        let at = self.timers.now(&queue) + duration;
+        let id = queue.insert(at, None);
+        while self.timers.now(&queue) < at {
+            queue = self
+                .timers
+                .changed
+                .wait(queue)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        queue.entries.remove(&id);
+    }
+
+    fn timer(&self, at: Instant) -> Timer {
+        Timer::new(at, Arc::clone(&self.timers))
+    }
+}
+
+fn auto_advance(timers: Weak<Timers>, idle: Duration) {
+    let mut seen = None;
+    loop {
+        thread::sleep(idle);
+        let Some(timers) = timers.upgrade() else {
+            return;
+        };
+        let mut queue = timers.lock();
+        if seen == Some(queue.advances) {
+            let now = timers.now(&queue);
+            let next = queue
+                .entries
+                .values()
+                .map(|(at, _)| *at)
+                .filter(|at| *at > now)
+                .min();
+            if let Some(next) = next {
+                queue.elapsed += next - now;
+                queue.advances += 1;
+                timers.wake_due(&mut queue);
+            }
+        }
+        seen = Some(queue.advances);
+    }
+}
+
+/// Future returned by [`Clock::timer()`]; completes once its clock reaches
+/// the deadline. Dropping it cancels the wait.
+#[must_use = "timers do nothing unless awaited"]
+#[derive(Debug)]
+pub struct Timer {
+    at: Instant,
+    timers: Arc<Timers>,
+    id: Option<u64>,
+}
+
+impl Timer {
+    fn new(at: Instant, timers: Arc<Timers>) -> Self {
+        Self {
+            at,
+            timers,
+            id: None,
+        }
+    }
+
+    /// When the timer fires
+    pub fn deadline(&self) -> Instant {
+        self.at
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let timers = Arc::clone(&self.timers);
+        let mut queue = timers.lock();
+        if timers.now(&queue) >= self.at {
+            if let Some(id) = self.id.take() {
+                queue.entries.remove(&id);
+            }
+            return Poll::Ready(());
+        }
+        match self.id {
+            Some(id) => {
+                queue
+                    .entries
+                    .insert(id, (self.at, Some(cx.waker().clone())));
+            }
+            None => {
+                self.id = Some(queue.insert(self.at, Some(cx.waker().clone())));
+                // The real-time driver may be waiting for a later deadline
+                timers.changed.notify_all();
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.timers.lock().entries.remove(&id);
+        }
+    }
+}
+
+/// Deadlines waited for on one clock
+#[derive(Debug)]
+struct Timers {
+    /// Start of a manual clock; `None` for real time
+    start: Option<Instant>,
+    queue: Mutex<Queue>,
+    /// Signalled when the time or the set of deadlines changes
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    /// Deadline of each sleeper, with the waker of async ones
+    entries: HashMap<u64, (Instant, Option<Waker>)>,
+    next_id: u64,
+    /// Time a manual clock has been advanced by
+    elapsed: Duration,
+    /// Number of times a manual clock was advanced
+    advances: u64,
+}
+
+impl Queue {
+    fn insert(&mut self, at: Instant, waker: Option<Waker>) -> u64 {
+        self.next_id += 1;
+        self.entries.insert(self.next_id, (at, waker));
+        self.next_id
+    }
+
+    fn earliest(&self) -> Option<Instant> {
+        self.entries.values().map(|(at, _)| *at).min()
+    }
+}
+
+impl Timers {
+    fn new(start: Option<Instant>) -> Self {
+        Self {
+            start,
+            queue: Mutex::new(Queue::default()),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn now(&self, queue: &Queue) -> Instant {
+        match self.start {
+            Some(start) => start + queue.elapsed,
+            None => Instant::now(),
+        }
+    }
+
+    /// Wake the async timers that are due, and every blocked sleeper so it
+    /// can check its own deadline
+    fn wake_due(&self, queue: &mut Queue) {
+        let now = self.now(queue);
+        for (at, waker) in queue.entries.values_mut() {
+            if *at <= now {
+                if let Some(waker) = waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+        self.changed.notify_all();
+    }
+}
+
+/// Timers of the real clock, driven by one background thread
+fn real_timers() -> &'static Arc<Timers> {
+    static REAL: OnceLock<Arc<Timers>> = OnceLock::new();
+    REAL.get_or_init(|| {
+        let timers = Arc::new(Timers::new(None));
+        let driven = Arc::clone(&timers);
+        thread::Builder::new()
+            .name("clock-timers".to_string())
+            .spawn(move || drive_real_timers(&driven))
+            .expect("failed to spawn clock timer thread");
+        timers
+    })
+}
+
+fn drive_real_timers(timers: &Timers) {
+    let mut queue = timers.lock();
+    loop {
+        let now = Instant::now();
+        for (at, waker) in queue.entries.values_mut() {
+            if *at <= now {
+                if let Some(waker) = waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+        // Entries already woken stay until their timer is polled or dropped
+        let next = queue
+            .entries
+            .values()
+            .filter(|(_, waker)| waker.is_some())
+            .map(|(at, _)| *at)
+            .min();
+        queue = match next {
+            Some(next) => {
+                timers
+                    .changed
+                    .wait_timeout(queue, next.saturating_duration_since(now))
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0
+            }
+            None => timers
+                .changed
+                .wait(queue)
+                .unwrap_or_else(PoisonError::into_inner),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_manual_sleep_returns_when_advanced_past_deadline() {
+        let clock = ManualClock::new();
+        let sleeper = clock.clone();
+        let (done, finished) = mpsc::channel();
+        thread::spawn(move || {
+            sleeper.sleep(Duration::from_secs(30));
+            done.send(()).unwrap();
+        });
+        while clock.pending() == 0 {
+            thread::yield_now();
+        }
+
+        clock.advance(Duration::from_secs(29));
+        assert!(finished.recv_timeout(Duration::from_millis(50)).is_err());
+        clock.advance(Duration::from_secs(1));
+        finished.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(clock.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_manual_timer_fires_on_advance() {
+        let clock = ManualClock::new();
+        let fired = Arc::new(AtomicBool::new(false));
+        let timer = clock.timer(clock.now() + Duration::from_secs(60));
+        let flag = Arc::clone(&fired);
+        let task = tokio::spawn(async move {
+            timer.await;
+            flag.store(true, Ordering::SeqCst);
+        });
+        while clock.pending() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        clock.advance(Duration::from_secs(59));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!fired.load(Ordering::SeqCst));
+        clock.advance(Duration::from_secs(1));
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(fired.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_dropped_timer_stops_waiting() {
+        let clock = ManualClock::new();
+        let timer = clock.timer(clock.now() + Duration::from_secs(1));
+        struct Noop;
+        impl std::task::Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+        let mut timer = Box::pin(timer);
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = Context::from_waker(&waker);
+        assert!(timer.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(clock.pending(), 1);
+        drop(timer);
+        assert_eq!(clock.pending(), 0);
+    }
+
+    #[test]
+    fn test_auto_advance_jumps_to_next_deadline() {
+        let clock = ManualClock::new().auto_advance(Duration::from_millis(5));
+        let started = clock.now();
+        clock.sleep(Duration::from_secs(3600));
+        assert_eq!(clock.now() - started, Duration::from_secs(3600));
+    }
+
+    #[tokio::test]
+    async fn test_system_timer_fires_in_real_time() {
+        let started = Instant::now();
+        SystemClock.timer(started + Duration::from_millis(30)).await;
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+}
@@ -1,12 +1,15 @@
 use soap_client::SoapError;
+pub use soap_client::{SoapFault, XmlViolation};
 use thiserror::Error;
 
+use crate::Service;
+
 /// High-level API errors for Sonos operations
 ///
 /// This enum provides domain-specific error types that abstract away the underlying
 /// SOAP communication details and provide meaningful error information for common
 /// failure scenarios when controlling Sonos devices.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum ApiError {
     /// Network communication error
     ///
@@ -24,12 +27,43 @@ pub enum ApiError {
     #[error("Parse error: {0}")]
     ParseError(String),
 
+    /// XML from the device refused before parsing
+    ///
+    /// The document declared a DTD or broke one of the
+    /// [`XmlLimits`](soap_client::XmlLimits) on size, depth, element count or
+    /// attribute size. Kept apart from `ParseError` as it points to a hostile
+    /// or badly misbehaving device rather than an unexpected format.
+    #[error("XML rejected: {0}")]
+    XmlRejected(XmlViolation),
+
     /// SOAP fault returned by device
     ///
     /// This error occurs when the device returns a SOAP fault response,
     /// indicating that the request was malformed or the operation failed.
-    #[error("SOAP fault: error code {0}")]
-    SoapFault(u16),
+    /// `fault` carries the raw UPnP code and, when the device sent one, its
+    /// description; `code` is the same code as an [`UpnpErrorCode`].
+    #[error("SOAP fault: {fault}")]
+    SoapFault {
+        code: UpnpErrorCode,
+        fault: SoapFault,
+    },
+
+    /// The device doesn't implement the action
+    ///
+    /// Translated from UPnP faults 401 (Invalid Action) and 602 (Optional
+    /// Action Not Implemented), which is how models lacking a feature such as
+    /// the button lock reject it. Carries the fault code.
+    #[error("Operation not supported by this device (error code {0})")]
+    NotSupported(u16),
+
+    /// The speaker isn't its group's coordinator
+    ///
+    /// Translated from UPnP fault 701 on GroupRenderingControl, whose actions
+    /// only the coordinator accepts; send the action to the coordinator
+    /// instead. Other services use 701 for other things (AVTransport's
+    /// "transition not available"), so they keep it as a `SoapFault`.
+    #[error("Speaker is not the group coordinator (error code 701)")]
+    NotCoordinator,
 
     /// Invalid parameter value
     ///
@@ -45,12 +79,48 @@ pub enum ApiError {
     #[error("Subscription error: {0}")]
     SubscriptionError(String),
 
+    /// Subscription was already cancelled
+    ///
+    /// Returned by `ManagedSubscription::unsubscribe()` and `renew()` once any
+    /// clone of the subscription has unsubscribed. Carries the SID.
+    #[error("Subscription {0} already unsubscribed")]
+    AlreadyUnsubscribed(String),
+
+    /// The device refused a renewal with HTTP 412 Precondition Failed
+    ///
+    /// It no longer knows the SID, typically because the subscription lapsed
+    /// or the device rebooted; only a fresh SUBSCRIBE helps. Carries the SID.
+    #[error("Subscription {0} is unknown to the device (HTTP 412)")]
+    SubscriptionUnknown(String),
+
+    /// A lapsed subscription couldn't be replaced
+    ///
+    /// Returned by `ManagedSubscription::renew()` when the device refused the
+    /// renewal with HTTP 412 and the fresh SUBSCRIBE sent in its place failed
+    /// too. Carries the old SID and the error of the SUBSCRIBE.
+    #[error("Subscription {sid} lapsed and re-subscribing failed: {source}")]
+    ResubscribeFailed {
+        sid: String,
+        #[source]
+        source: Box<ApiError>,
+    },
+
     /// Device operation error
     ///
     /// This error covers device-specific issues like not being a group coordinator,
     /// unsupported operations, or invalid device states.
     #[error("Device error: {0}")]
     DeviceError(String),
+
+    /// The write rate limit refused the write; nothing was sent
+    ///
+    /// Returned by a [`WriteLimiter`](crate::rate_limit::WriteLimiter) under
+    /// [`ExceedPolicy::Reject`](crate::rate_limit::ExceedPolicy::Reject), or
+    /// when a queued write would wait longer than allowed. A write made
+    /// `retry_after` from now would be accepted, unless other writes take
+    /// the tokens first.
+    #[error("Write rate limit exceeded; retry in {retry_after:?}")]
+    RateLimited { retry_after: std::time::Duration },
 }
 
 impl ApiError {
@@ -58,6 +128,46 @@ impl ApiError {
     pub fn subscription_expired() -> Self {
         Self::SubscriptionError("Subscription expired".to_string())
     }
+
+    /// Wrap a SOAP fault as-is, classifying its code
+    pub fn soap_fault(fault: SoapFault) -> Self {
+        Self::SoapFault {
+            code: fault.code.into(),
+            fault,
+        }
+    }
+
+    /// UPnP error code of a [`SoapFault`](Self::SoapFault),
+    /// [`NotSupported`](Self::NotSupported) or
+    /// [`NotCoordinator`](Self::NotCoordinator) error
+    pub fn fault_code(&self) -> Option<u16> {
+        match self {
+            Self::SoapFault { fault, .. } => Some(fault.code),
+            Self::NotSupported(code) => Some(*code),
+            Self::NotCoordinator => Some(701),
+            _ => None,
+        }
+    }
+
+    /// [`fault_code()`](Self::fault_code) as an [`UpnpErrorCode`]
+    pub fn upnp_error(&self) -> Option<UpnpErrorCode> {
+        self.fault_code().map(UpnpErrorCode::from)
+    }
+
+    /// Translate an error from a call to one of `service`'s actions
+    ///
+    /// Like the `From<SoapError>` conversion, plus the faults whose meaning
+    /// depends on the service.
+    pub(crate) fn from_soap(service: Service, error: SoapError) -> Self {
+        match error {
+            SoapError::Fault(SoapFault { code: 701, .. })
+                if service == Service::GroupRenderingControl =>
+            {
+                ApiError::NotCoordinator
+            }
+            error => error.into(),
+        }
+    }
 }
 
 /// Type alias for results that can return an ApiError
@@ -69,11 +179,106 @@ impl From<SoapError> for ApiError {
         match error {
             SoapError::Network(msg) => ApiError::NetworkError(msg),
             SoapError::Parse(msg) => ApiError::ParseError(msg),
-            SoapError::Fault(code) => ApiError::SoapFault(code),
+            SoapError::XmlRejected(violation) => ApiError::XmlRejected(violation),
+            error @ SoapError::PreconditionFailed => ApiError::SubscriptionError(error.to_string()),
+            SoapError::Fault(SoapFault {
+                code: code @ (401 | 602),
+                ..
+            }) => ApiError::NotSupported(code),
+            SoapError::Fault(fault) => ApiError::soap_fault(fault),
         }
     }
 }
 
+/// UPnP error codes Sonos devices answer with
+///
+/// Built from the raw code of a fault with `From<u16>`; codes without a
+/// variant are kept as `Unknown`. The meaning of some codes depends on the
+/// service (701 is both AVTransport's "transition not available" and
+/// GroupRenderingControl's "not coordinator"), so the predicates name what a
+/// code can mean rather than what it does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpnpErrorCode {
+    /// 401: the service has no such action
+    InvalidAction,
+    /// 402: missing, extra or malformed arguments
+    InvalidArgs,
+    /// 602: the device doesn't implement this optional action
+    OptionalActionNotImplemented,
+    /// 701: the action isn't allowed in the current state, or the speaker
+    /// isn't its group's coordinator
+    TransitionNotAvailable,
+    /// 711: the seek target is out of range or of the wrong unit
+    IllegalSeekTarget,
+    /// 714: the media type isn't one the speaker plays
+    IllegalMimeType,
+    /// 718: the InstanceID isn't 0
+    InvalidInstanceId,
+    /// Any other code
+    Unknown(u16),
+}
+
+impl UpnpErrorCode {
+    /// The numeric code
+    pub fn code(self) -> u16 {
+        match self {
+            Self::InvalidAction => 401,
+            Self::InvalidArgs => 402,
+            Self::OptionalActionNotImplemented => 602,
+            Self::TransitionNotAvailable => 701,
+            Self::IllegalSeekTarget => 711,
+            Self::IllegalMimeType => 714,
+            Self::InvalidInstanceId => 718,
+            Self::Unknown(code) => code,
+        }
+    }
+
+    /// Whether the code can mean the speaker isn't the group coordinator (701)
+    pub fn is_not_coordinator(self) -> bool {
+        self == Self::TransitionNotAvailable
+    }
+
+    /// Whether the code can mean the transport can't make this transition
+    /// in its current state (701)
+    pub fn is_invalid_transition(self) -> bool {
+        self == Self::TransitionNotAvailable
+    }
+
+    /// Whether the device rejected the arguments (402)
+    pub fn is_invalid_args(self) -> bool {
+        self == Self::InvalidArgs
+    }
+
+    /// Whether the device doesn't implement the action (401 or 602)
+    pub fn is_not_supported(self) -> bool {
+        matches!(
+            self,
+            Self::InvalidAction | Self::OptionalActionNotImplemented
+        )
+    }
+}
+
+impl From<u16> for UpnpErrorCode {
+    fn from(code: u16) -> Self {
+        match code {
+            401 => Self::InvalidAction,
+            402 => Self::InvalidArgs,
+            602 => Self::OptionalActionNotImplemented,
+            701 => Self::TransitionNotAvailable,
+            711 => Self::IllegalSeekTarget,
+            714 => Self::IllegalMimeType,
+            718 => Self::InvalidInstanceId,
+            code => Self::Unknown(code),
+        }
+    }
+}
+
+impl std::fmt::Display for UpnpErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
 /// Convert from ValidationError to ApiError
 impl From<crate::operation::ValidationError> for ApiError {
     fn from(validation_error: crate::operation::ValidationError) -> Self {
@@ -125,9 +330,44 @@ mod tests {
         let api_error: ApiError = soap_error.into();
         assert!(matches!(api_error, ApiError::ParseError(_)));
 
-        let soap_error = SoapError::Fault(500);
+        let soap_error = SoapError::Fault(SoapFault::new(500));
         let api_error: ApiError = soap_error.into();
-        assert!(matches!(api_error, ApiError::SoapFault(500)));
+        assert_eq!(api_error.fault_code(), Some(500));
+        assert!(matches!(
+            api_error,
+            ApiError::SoapFault {
+                code: UpnpErrorCode::Unknown(500),
+                ..
+            }
+        ));
+
+        for code in [401, 602] {
+            let api_error: ApiError = SoapError::Fault(SoapFault::new(code)).into();
+            assert!(matches!(api_error, ApiError::NotSupported(c) if c == code));
+            assert_eq!(api_error.fault_code(), Some(code));
+        }
+        assert_eq!(ApiError::subscription_expired().fault_code(), None);
+    }
+
+    #[test]
+    fn test_not_coordinator_only_for_group_rendering_control() {
+        let fault = || SoapError::Fault(SoapFault::new(701));
+        let error = ApiError::from_soap(Service::GroupRenderingControl, fault());
+        assert!(matches!(error, ApiError::NotCoordinator));
+        assert_eq!(error.fault_code(), Some(701));
+
+        for service in [Service::AVTransport, Service::RenderingControl] {
+            let error = ApiError::from_soap(service, fault());
+            assert!(matches!(error, ApiError::SoapFault { ref fault, code }
+                if fault.code == 701 && code.is_invalid_transition()));
+        }
+        assert!(matches!(
+            ApiError::from_soap(
+                Service::GroupRenderingControl,
+                SoapError::Fault(SoapFault::new(401))
+            ),
+            ApiError::NotSupported(401)
+        ));
     }
 
     #[test]
@@ -138,7 +378,47 @@ mod tests {
         let parse_err = ApiError::ParseError("invalid XML".to_string());
         assert_eq!(format!("{parse_err}"), "Parse error: invalid XML");
 
-        let soap_fault = ApiError::SoapFault(500);
+        let soap_fault = ApiError::soap_fault(SoapFault::new(500));
         assert_eq!(format!("{soap_fault}"), "SOAP fault: error code 500");
+
+        let described: ApiError = SoapError::Fault(SoapFault {
+            code: 701,
+            description: Some("Transition not available".to_string()),
+            fault_string: Some("UPnPError".to_string()),
+        })
+        .into();
+        assert_eq!(
+            format!("{described}"),
+            "SOAP fault: error code 701: Transition not available"
+        );
+    }
+
+    #[test]
+    fn test_upnp_error_codes() {
+        for code in [401, 402, 602, 701, 711, 714, 718] {
+            let upnp = UpnpErrorCode::from(code);
+            assert!(!matches!(upnp, UpnpErrorCode::Unknown(_)), "{code}");
+            assert_eq!(upnp.code(), code);
+        }
+        for code in [0, 500, 702, 800] {
+            assert_eq!(UpnpErrorCode::from(code), UpnpErrorCode::Unknown(code));
+            assert_eq!(UpnpErrorCode::from(code).code(), code);
+        }
+
+        let upnp = UpnpErrorCode::from(701);
+        assert!(upnp.is_not_coordinator() && upnp.is_invalid_transition());
+        assert!(!UpnpErrorCode::from(711).is_not_coordinator());
+        assert!(UpnpErrorCode::from(402).is_invalid_args());
+        assert!(UpnpErrorCode::from(602).is_not_supported());
+
+        assert_eq!(
+            ApiError::NotCoordinator.upnp_error(),
+            Some(UpnpErrorCode::TransitionNotAvailable)
+        );
+        assert_eq!(
+            ApiError::NotSupported(401).upnp_error(),
+            Some(UpnpErrorCode::InvalidAction)
+        );
+        assert_eq!(ApiError::subscription_expired().upnp_error(), None);
     }
 }
@@ -55,6 +55,6 @@ pub use types::{
     extract_xml_value, EnrichedEvent, EventParser, EventParserDyn, EventParserRegistry, EventSource,
 };
 pub use xml_utils::{
-    deserialize_nested, parse, strip_namespaces, DidlItem, DidlLite, DidlResource, NestedAttribute,
-    ValueAttribute,
+    deserialize_nested, last_change, parse, strip_namespaces, DidlItem, DidlLite, DidlResource,
+    NestedAttribute, ValueAttribute,
 };
@@ -4,7 +4,7 @@
 //! events from any Sonos UPnP service using direct self-parsing methods.
 
 use super::types::{EnrichedEvent, EventSource};
-use crate::{Result, Service};
+use crate::{ApiError, Result, Service};
 use std::net::IpAddr;
 
 /// Generic event processor that can handle events from any service
@@ -119,6 +119,20 @@ impl EventProcessor {
                     crate::services::group_management::GroupManagementEvent::from_xml(event_xml)?;
                 Ok(Box::new(event))
             }
+            Service::DeviceProperties => {
+                let event =
+                    crate::services::device_properties::DevicePropertiesEvent::from_xml(event_xml)?;
+                Ok(Box::new(event))
+            }
+            Service::ConnectionManager => Err(ApiError::ParseError(
+                "ConnectionManager events are not supported".to_string(),
+            )),
+            Service::AlarmClock => Err(ApiError::ParseError(
+                "AlarmClock events are not supported".to_string(),
+            )),
+            Service::ContentDirectory => Err(ApiError::ParseError(
+                "ContentDirectory events are not supported".to_string(),
+            )),
         }
     }
 
@@ -131,6 +145,7 @@ impl EventProcessor {
                 | Service::GroupRenderingControl
                 | Service::ZoneGroupTopology
                 | Service::GroupManagement
+                | Service::DeviceProperties
         )
     }
 
@@ -142,6 +157,7 @@ impl EventProcessor {
             Service::GroupRenderingControl,
             Service::ZoneGroupTopology,
             Service::GroupManagement,
+            Service::DeviceProperties,
         ]
     }
 }
@@ -210,7 +226,7 @@ mod tests {
         let processor = EventProcessor::new();
 
         // Should support all implemented services
-        assert_eq!(processor.supported_services().len(), 5); // AVTransport, RenderingControl, GroupRenderingControl, ZoneGroupTopology, GroupManagement
+        assert_eq!(processor.supported_services().len(), 6); // AVTransport, RenderingControl, GroupRenderingControl, ZoneGroupTopology, GroupManagement, DeviceProperties
     }
 
     #[test]
@@ -219,12 +235,13 @@ mod tests {
 
         // Should be created without error
         // Should have parsers for all available services
-        assert_eq!(processor.supported_services().len(), 5); // AVTransport, RenderingControl, GroupRenderingControl, ZoneGroupTopology, GroupManagement
+        assert_eq!(processor.supported_services().len(), 6); // AVTransport, RenderingControl, GroupRenderingControl, ZoneGroupTopology, GroupManagement, DeviceProperties
         assert!(processor.supports_service(&Service::AVTransport));
         assert!(processor.supports_service(&Service::RenderingControl));
         assert!(processor.supports_service(&Service::GroupRenderingControl));
         assert!(processor.supports_service(&Service::ZoneGroupTopology));
         assert!(processor.supports_service(&Service::GroupManagement));
+        assert!(processor.supports_service(&Service::DeviceProperties));
     }
 
     #[test]
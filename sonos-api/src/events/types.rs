@@ -2,6 +2,12 @@
 //!
 //! This module provides the core event infrastructure that is service-agnostic.
 //! Service-specific event types are defined in their respective service modules.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
 
 use crate::{Result, Service};
 use serde::{Deserialize, Serialize};
@@ -197,16 +203,10 @@ pub fn extract_xml_value(xml: &str, tag: &str) -> Option<String> {
     let start_tag = format!("<{tag}>");
     let end_tag = format!("</{tag}>");
 
-    if let Some(start_pos) = xml.find(&start_tag) {
-        let content_start = start_pos + start_tag.len();
-        if let Some(end_pos) = xml[content_start..].find(&end_tag) {
-            let value = xml[content_start..content_start + end_pos].trim();
-            if !value.is_empty() {
-                return Some(value.to_string());
-            }
-        }
-    }
-    None
+    let (_, rest) = xml.split_once(&start_tag)?;
+    let (value, _) = rest.split_once(&end_tag)?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
 }
 
 #[cfg(test)]
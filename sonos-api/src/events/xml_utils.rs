@@ -3,8 +3,18 @@
 //! This module provides reusable XML parsing components that were consolidated
 //! from the sonos-parser crate. It includes namespace stripping, attribute parsing,
 //! and DIDL-Lite metadata structures.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
+
+use std::borrow::Cow;
 
 use crate::{ApiError, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
 use serde::de::{DeserializeOwned, Deserializer};
 use serde::{Deserialize, Serialize};
 
@@ -22,11 +32,22 @@ use serde::{Deserialize, Serialize};
 ///
 /// The parsed value of type `T`, or an error if parsing fails.
 pub fn parse<T: DeserializeOwned>(xml: &str) -> Result<T> {
+    check(xml)?;
     let stripped = strip_namespaces(xml);
     quick_xml::de::from_str(&stripped)
         .map_err(|e| ApiError::ParseError(format!("XML deserialization failed: {e}")))
 }
 
+/// Scan XML from a device against the [`XmlLimits`](crate::XmlLimits).
+///
+/// Refuses DTDs and documents too large, too deep, with too many elements
+/// or with oversized attributes as [`ApiError::XmlRejected`], before any of
+/// the document is built. Every parser in this crate that reads network XML
+/// calls it first.
+pub fn check(xml: &str) -> Result<()> {
+    soap_client::xml::check(xml).map_err(ApiError::XmlRejected)
+}
+
 /// Strip namespace prefixes from XML content to simplify parsing.
 ///
 /// UPnP XML often contains namespace prefixes like `e:`, `dc:`, `upnp:`, etc.
@@ -45,9 +66,8 @@ pub fn strip_namespaces(xml: &str) -> String {
             result.push(c);
 
             // Check for closing tag or special tags
-            let is_closing = chars.peek() == Some(&'/');
-            if is_closing {
-                result.push(chars.next().unwrap());
+            if let Some(slash) = chars.next_if_eq(&'/') {
+                result.push(slash);
             }
 
             // Check for special tags (?, !)
@@ -66,42 +86,35 @@ pub fn strip_namespaces(xml: &str) -> String {
 
             // Read the tag name (possibly with namespace prefix)
             let mut tag_name = String::new();
-            while let Some(&ch) = chars.peek() {
-                if ch.is_whitespace() || ch == '>' || ch == '/' {
-                    break;
-                }
-                tag_name.push(chars.next().unwrap());
+            while let Some(ch) =
+                chars.next_if(|&ch| !(ch.is_whitespace() || ch == '>' || ch == '/'))
+            {
+                tag_name.push(ch);
             }
 
             // Strip namespace prefix from tag name
-            if let Some(pos) = tag_name.find(':') {
-                result.push_str(&tag_name[pos + 1..]);
-            } else {
-                result.push_str(&tag_name);
+            match tag_name.split_once(':') {
+                Some((_, local)) => result.push_str(local),
+                None => result.push_str(&tag_name),
             }
 
             // Process attributes
             while let Some(&ch) = chars.peek() {
-                if ch == '>' {
-                    result.push(chars.next().unwrap());
-                    break;
-                }
-                if ch == '/' {
-                    result.push(chars.next().unwrap());
-                    continue;
-                }
-                if ch.is_whitespace() {
-                    result.push(chars.next().unwrap());
+                if ch == '>' || ch == '/' || ch.is_whitespace() {
+                    chars.next();
+                    result.push(ch);
+                    if ch == '>' {
+                        break;
+                    }
                     continue;
                 }
 
                 // Read attribute name
                 let mut attr_name = String::new();
-                while let Some(&ach) = chars.peek() {
-                    if ach == '=' || ach.is_whitespace() || ach == '>' || ach == '/' {
-                        break;
-                    }
-                    attr_name.push(chars.next().unwrap());
+                while let Some(ach) = chars.next_if(|&ach| {
+                    !(ach == '=' || ach.is_whitespace() || ach == '>' || ach == '/')
+                }) {
+                    attr_name.push(ach);
                 }
 
                 // Strip namespace prefix from attribute name (but keep xmlns declarations)
@@ -124,10 +137,9 @@ pub fn strip_namespaces(xml: &str) -> String {
                     }
                 } else {
                     // Keep the attribute, stripping namespace prefix
-                    if let Some(pos) = attr_name.find(':') {
-                        result.push_str(&attr_name[pos + 1..]);
-                    } else {
-                        result.push_str(&attr_name);
+                    match attr_name.split_once(':') {
+                        Some((_, local)) => result.push_str(local),
+                        None => result.push_str(&attr_name),
                     }
 
                     // Copy '=' and value
@@ -135,18 +147,17 @@ pub fn strip_namespaces(xml: &str) -> String {
                         if ach == '>' || ach == '/' {
                             break;
                         }
+                        chars.next();
+                        result.push(ach);
                         if ach == '"' || ach == '\'' {
-                            let quote = chars.next().unwrap();
-                            result.push(quote);
                             for ch in chars.by_ref() {
                                 result.push(ch);
-                                if ch == quote {
+                                if ch == ach {
                                     break;
                                 }
                             }
                             break;
                         }
-                        result.push(chars.next().unwrap());
                     }
                 }
             }
@@ -201,6 +212,73 @@ where
     Ok(Some(parsed))
 }
 
+/// Text of the `LastChange` property in a NOTIFY body, unescaped.
+///
+/// The property holds an escaped `<Event>` document, so the result is
+/// usually owned; it borrows from `xml` only when the text needs no
+/// unescaping (a CDATA section, say). Borrowing parsers such as
+/// `AVTransportEventRef` then read the document without further copies.
+pub fn last_change(xml: &str) -> Result<Cow<'_, str>> {
+    check(xml)?;
+    let error =
+        |e: &dyn std::fmt::Display| ApiError::ParseError(format!("Invalid NOTIFY body: {e}"));
+    let mut reader = Reader::from_str(xml);
+    // Element depth: 1 inside `propertyset`, 2 inside a `property`
+    let mut depth = 0usize;
+    let mut text: Option<Cow<'_, str>> = None;
+    let mut inside = false;
+    loop {
+        let (start, empty) = match reader.read_event().map_err(|e| error(&e))? {
+            Event::Start(start) => (start, false),
+            Event::Empty(start) => (start, true),
+            Event::End(_) => {
+                inside &= depth != 3;
+                depth = depth.saturating_sub(1);
+                continue;
+            }
+            Event::Text(chunk) if inside && depth == 3 => {
+                let chunk = chunk.unescape().map_err(|e| error(&e))?;
+                append(&mut text, chunk);
+                continue;
+            }
+            Event::CData(chunk) if inside && depth == 3 => {
+                let chunk = match chunk.into_inner() {
+                    Cow::Borrowed(bytes) => {
+                        Cow::Borrowed(std::str::from_utf8(bytes).map_err(|e| error(&e))?)
+                    }
+                    Cow::Owned(bytes) => {
+                        Cow::Owned(String::from_utf8(bytes).map_err(|e| error(&e))?)
+                    }
+                };
+                append(&mut text, chunk);
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        if depth == 2 && start.local_name().as_ref() == b"LastChange" {
+            if text.is_some() {
+                return Err(error(&"duplicate LastChange property"));
+            }
+            text = Some(Cow::Borrowed(""));
+            inside = !empty;
+        }
+        depth += usize::from(!empty);
+    }
+    if depth != 0 {
+        return Err(error(&"unexpected end of document"));
+    }
+    text.ok_or_else(|| error(&"missing LastChange property"))
+}
+
+/// Join text split across events, borrowing while there is only one piece
+fn append<'a>(text: &mut Option<Cow<'a, str>>, chunk: Cow<'a, str>) {
+    match text {
+        Some(Cow::Borrowed("")) | None => *text = Some(chunk),
+        Some(existing) => existing.to_mut().push_str(&chunk),
+    }
+}
+
 /// Represents an XML element with a `val` attribute.
 ///
 /// Many UPnP state variables are represented as empty elements with a `val` attribute:
@@ -275,6 +353,10 @@ pub struct DidlLite {
     /// The item elements containing track metadata
     #[serde(rename = "item", default)]
     pub items: Vec<DidlItem>,
+
+    /// Container elements, such as saved queues in a Browse result
+    #[serde(rename = "container", default)]
+    pub containers: Vec<DidlItem>,
 }
 
 impl DidlLite {
@@ -294,6 +376,7 @@ impl DidlLite {
 
 /// Individual item in DIDL-Lite metadata containing track information.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "DidlItemXml")]
 pub struct DidlItem {
     /// Item ID
     #[serde(rename = "@id", default)]
@@ -311,7 +394,8 @@ pub struct DidlItem {
     #[serde(rename = "res", default)]
     pub resources: Vec<DidlResource>,
 
-    /// Album art URI
+    /// Album art URI; the first one when there are several, as saved
+    /// queues have
     #[serde(rename = "albumArtURI", default)]
     pub album_art_uri: Option<String>,
 
@@ -334,6 +418,71 @@ pub struct DidlItem {
     /// Stream info
     #[serde(rename = "streamInfo", default)]
     pub stream_info: Option<String>,
+
+    /// Position among the favorites (`r:ordinal`)
+    #[serde(rename = "ordinal", default)]
+    pub ordinal: Option<String>,
+
+    /// Short description, such as the service a favorite comes from
+    /// (`r:description`)
+    #[serde(rename = "description", default)]
+    pub description: Option<String>,
+
+    /// DIDL-Lite metadata of the favorite's target (`r:resMD`), unescaped
+    /// once so it is itself a DIDL-Lite document
+    #[serde(rename = "resMD", default)]
+    pub res_md: Option<String>,
+}
+
+/// [`DidlItem`] as it is read, accepting repeated `albumArtURI` elements
+#[derive(Deserialize)]
+struct DidlItemXml {
+    #[serde(rename = "@id", default)]
+    id: String,
+    #[serde(rename = "@parentID", default)]
+    parent_id: String,
+    #[serde(rename = "@restricted", default)]
+    restricted: Option<String>,
+    #[serde(rename = "res", default)]
+    resources: Vec<DidlResource>,
+    #[serde(rename = "albumArtURI", default)]
+    album_art_uris: Vec<String>,
+    #[serde(rename = "class", default)]
+    class: Option<String>,
+    #[serde(rename = "title", default)]
+    title: Option<String>,
+    #[serde(rename = "creator", default)]
+    creator: Option<String>,
+    #[serde(rename = "album", default)]
+    album: Option<String>,
+    #[serde(rename = "streamInfo", default)]
+    stream_info: Option<String>,
+    #[serde(rename = "ordinal", default)]
+    ordinal: Option<String>,
+    #[serde(rename = "description", default)]
+    description: Option<String>,
+    #[serde(rename = "resMD", default)]
+    res_md: Option<String>,
+}
+
+impl From<DidlItemXml> for DidlItem {
+    fn from(xml: DidlItemXml) -> Self {
+        DidlItem {
+            id: xml.id,
+            parent_id: xml.parent_id,
+            restricted: xml.restricted,
+            resources: xml.resources,
+            album_art_uri: xml.album_art_uris.into_iter().next(),
+            class: xml.class,
+            title: xml.title,
+            creator: xml.creator,
+            album: xml.album,
+            stream_info: xml.stream_info,
+            ordinal: xml.ordinal,
+            description: xml.description,
+            res_md: xml.res_md,
+        }
+    }
 }
 
 /// Resource element in DIDL-Lite containing media resource information.
@@ -484,4 +633,91 @@ mod tests {
         assert_eq!(item.creator, None);
         assert_eq!(item.album, None);
     }
+
+    #[test]
+    fn test_parse_didl_lite_favorite_and_containers() {
+        let didl_xml = r#"<DIDL-Lite xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/"><item id="FV:2/4" parentID="FV:2"><dc:title>Radio</dc:title><r:ordinal>2</r:ordinal><r:description>TuneIn</r:description><r:resMD>&lt;DIDL-Lite&gt;&lt;item id="s1"/&gt;&lt;/DIDL-Lite&gt;</r:resMD></item><container id="SQ:3" parentID="SQ:"><upnp:albumArtURI>/a</upnp:albumArtURI><upnp:albumArtURI>/b</upnp:albumArtURI></container></DIDL-Lite>"#;
+
+        let didl = DidlLite::from_xml(didl_xml).unwrap();
+        let favorite = &didl.items[0];
+        assert_eq!(favorite.ordinal.as_deref(), Some("2"));
+        assert_eq!(favorite.description.as_deref(), Some("TuneIn"));
+        assert_eq!(
+            favorite.res_md.as_deref(),
+            Some(r#"<DIDL-Lite><item id="s1"/></DIDL-Lite>"#)
+        );
+        assert_eq!(didl.containers[0].id, "SQ:3");
+        assert_eq!(didl.containers[0].album_art_uri.as_deref(), Some("/a"));
+    }
+
+    #[test]
+    fn test_last_change() {
+        let escaped = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event/&gt;</LastChange></e:property></e:propertyset>"#;
+        assert_eq!(last_change(escaped).unwrap(), "<Event/>");
+
+        let cdata = r#"<e:propertyset><e:property><LastChange><![CDATA[<Event/>]]></LastChange></e:property></e:propertyset>"#;
+        assert!(matches!(
+            last_change(cdata).unwrap(),
+            Cow::Borrowed("<Event/>")
+        ));
+
+        let empty = r#"<e:propertyset><e:property><LastChange/></e:property></e:propertyset>"#;
+        assert_eq!(last_change(empty).unwrap(), "");
+
+        for invalid in [
+            r#"<e:propertyset><e:property><Volume>1</Volume></e:property></e:propertyset>"#,
+            r#"<e:propertyset><e:property><LastChange>&lt;Event/&gt;</LastChange>"#,
+            r#"<e:propertyset><e:property><LastChange/></e:property><e:property><LastChange/></e:property></e:propertyset>"#,
+        ] {
+            assert!(last_change(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    const DIDL: &str = r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/"><item id="-1" parentID="-1"><dc:title>Song</dc:title><dc:creator>Artist</dc:creator><upnp:album>Album</upnp:album><res duration="0:03:58" protocolInfo="http-get:*:audio/mpeg:*">http://example.com/song.mp3</res></item></DIDL-Lite>"#;
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn fuzz_strip_namespaces(xml in fuzz::xmlish()) {
+            strip_namespaces(&xml);
+        }
+
+        #[test]
+        fn fuzz_last_change(xml in fuzz::xmlish()) {
+            let _ = last_change(&xml);
+        }
+
+        #[test]
+        fn fuzz_didl_lite(xml in fuzz::mutated(DIDL)) {
+            let _ = DidlLite::from_xml(&xml);
+        }
+    }
+}
+
+/// Proptest strategies for fuzzing the parsers that take device input.
+///
+/// Every case must come back as `Ok` or `Err`; a panic fails the test.
+#[cfg(test)]
+pub(crate) mod fuzz {
+    use proptest::prelude::*;
+
+    /// Short strings dense in XML syntax, plus arbitrary text
+    pub(crate) fn xmlish() -> impl Strategy<Value = String> {
+        prop_oneof!["[<>/=\"'&;:!? a-zA-Z]{0,48}", "\\PC{0,48}"]
+    }
+
+    /// `fixture` with a run of characters cut out and [`xmlish`] text spliced
+    /// in at the same place, so the parser gets well past the first byte
+    pub(crate) fn mutated(fixture: &'static str) -> impl Strategy<Value = String> {
+        (any::<prop::sample::Index>(), 0..32usize, xmlish()).prop_map(move |(at, cut, junk)| {
+            let chars: Vec<char> = fixture.chars().collect();
+            let start = at.index(chars.len() + 1);
+            let end = (start + cut).min(chars.len());
+            let mut xml: String = chars[..start].iter().collect();
+            xml.push_str(&junk);
+            xml.extend(&chars[end..]);
+            xml
+        })
+    }
 }
@@ -154,10 +154,16 @@
 //! // caused by the control operations
 //! ```
 
+#[cfg(feature = "async-client")]
+pub mod async_client;
 pub mod client;
+pub mod clock;
 pub mod error;
 pub mod events;
+#[cfg(feature = "test-support")]
+pub mod mock;
 pub mod operation; // Enhanced operation framework
+pub mod rate_limit;
 pub mod service;
 pub mod services; // Enhanced services
 pub mod subscription; // New event handling framework
@@ -167,15 +173,28 @@ pub mod types;
 pub use types::{GroupId, SpeakerId};
 
 // Legacy exports for backward compatibility
-pub use client::SonosClient;
-pub use error::{ApiError, Result};
+pub use client::{
+    extract_values, CertFingerprint, CertificateTrust, ClientIdentity, DeviceTransport,
+    EventingConnection, HttpCache, HttpResource, PoolStats, RetryPolicy, Revalidation, Scheme,
+    SoapClientConfig, SonosClient, Validators, XmlLimits, HTTPS_PORT,
+};
+
+#[cfg(feature = "async-client")]
+pub use async_client::AsyncSonosClient;
+
+// Response type of SonosClient::call_raw()
+pub use clock::{Clock, ManualClock, SharedClock, SystemClock, Timer};
+pub use error::{ApiError, Result, SoapFault, UpnpErrorCode, XmlViolation};
 pub use operation::SonosOperation; // Legacy trait
 pub use service::{Service, ServiceInfo, ServiceScope};
-pub use subscription::ManagedSubscription;
+pub use subscription::{ManagedSubscription, Renewal, SubscriptionDirectory};
+pub use xmltree::Element;
 
 // New enhanced operation framework exports
 pub use operation::{
-    OperationBuilder, OperationMetadata, UPnPOperation, Validate, ValidationError, ValidationLevel,
+    BatchItem, BatchResult, Conditional, ConditionalResult, OperationBuilder, OperationMetadata,
+    Sequence, SequenceResponses, SequenceResult, SequenceStepId, UPnPOperation, Validate,
+    ValidationError, ValidationLevel,
 };
 
 // New event handling framework exports
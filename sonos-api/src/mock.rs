@@ -0,0 +1,1872 @@
+//! Scriptable mock Sonos device for tests
+//!
+//! [`MockDevice`] serves SOAP control requests and SUBSCRIBE / renewal /
+//! UNSUBSCRIBE on a local address and sends NOTIFYs to its subscribers. A
+//! [`Scenario`] scripts it: how the next requests are answered (normally,
+//! with an HTTP error, after a delay, by dropping or hanging the
+//! connection), and what the device does at points on its virtual timeline
+//! (change volume, forget subscriptions, reboot). Actions can also be tied
+//! to a request instead of a time, to play another controller acting at the
+//! same moment ([`Scenario::after_action`]).
+//!
+//! Time is a [`ManualClock`] that only moves when the test calls
+//! [`MockDevice::advance()`], so scenarios run the same way every time. Pass
+//! [`MockDevice::clock()`] to `SonosClient::with_clock()` (or a broker's
+//! config) to put subscription expiry on the same timeline.
+//!
+//! Only available with the `test-support` feature.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use sonos_api::mock::{Action, Behavior, MockDevice, Scenario};
+//! use sonos_api::Service;
+//!
+//! // Respond normally 3 times, then 503 twice, then delay 5s, then drop the
+//! // connection, then answer normally again. Change the volume (notifying
+//! // subscribers) at t+2s and reboot, losing every SID, at t+10s.
+//! let scenario = Scenario::new()
+//!     .respond(3)
+//!     .status(503, 2)
+//!     .delay(Duration::from_secs(5))
+//!     .step(Behavior::Drop, 1)
+//!     .at(Duration::from_secs(2), Action::SetVolume(30))
+//!     .at(Duration::from_secs(10), Action::Reboot);
+//! let device = MockDevice::start("127.0.0.1:0", scenario);
+//!
+//! // ... exercise the code under test against device.addr() ...
+//! device.advance(Duration::from_secs(10));
+//! assert_eq!(device.live_subscriptions(), 0);
+//!
+//! // The same scenario as JSON (durations in milliseconds)
+//! let scenario = Scenario::from_json(r#"{
+//!     "requests": [
+//!         { "behavior": "respond", "times": 3 },
+//!         { "behavior": { "status": 503 }, "times": 2 },
+//!         { "behavior": { "delay": 5000 } },
+//!         { "behavior": "drop" }
+//!     ],
+//!     "timeline": [
+//!         { "at_ms": 2000, "action": { "set_volume": 30 } },
+//!         { "at_ms": 10000, "action": "reboot" }
+//!     ]
+//! }"#).unwrap();
+//! # let _ = Service::RenderingControl;
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Socket, Type};
+
+use crate::clock::{Clock, ManualClock};
+use crate::services::device_properties::{
+    button_lock_state_str, led_state_str, parse_button_lock_state, parse_led_state,
+    BLUETOOTH_STATUS_PATH,
+};
+use crate::services::rendering_control::FACTORY_DEFAULTS_PRESET;
+use crate::Service;
+
+/// How the device answers one request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Behavior {
+    /// Answer normally
+    Respond,
+    /// Answer with this HTTP status and an empty body
+    Status(u16),
+    /// Answer normally once the timeline has moved this far past the
+    /// request (milliseconds in JSON)
+    Delay(#[serde(with = "millis")] Duration),
+    /// Read the request, then close the connection without answering
+    Drop,
+    /// Read the request and hold the connection open without answering
+    Hang,
+}
+
+/// A behavior applied to the next `times` requests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestStep {
+    pub behavior: Behavior,
+    #[serde(default = "one")]
+    pub times: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// Something the device does on its own at a point on the timeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Change the volume and notify RenderingControl subscribers
+    SetVolume(u8),
+    /// Change the mute state and notify RenderingControl subscribers
+    SetMute(bool),
+    /// Change the transport state (`PLAYING`, `PAUSED_PLAYBACK`, ...) and
+    /// notify AVTransport subscribers
+    SetTransportState(String),
+    /// Turn the status light on or off and notify DeviceProperties
+    /// subscribers
+    SetStatusLight(bool),
+    /// Lock or unlock the buttons and notify DeviceProperties subscribers
+    SetButtonLock(bool),
+    /// Send a raw `<e:propertyset>` NOTIFY to the subscribers of a service
+    Notify { service: Service, body: String },
+    /// Forget every subscription to one service, so renewals get 412
+    Expire(Service),
+    /// Power cycle: forget every subscription and drop connections held
+    /// by [`Behavior::Hang`]; with [`Scenario::recycle_sids`] the next SIDs
+    /// repeat the ones granted before
+    Reboot,
+}
+
+/// An action scheduled on the device's timeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimedAction {
+    /// Offset from the start of the scenario (`at_ms` in JSON)
+    #[serde(rename = "at_ms", with = "millis")]
+    pub at: Duration,
+    pub action: Action,
+}
+
+/// An action run right after the device answers a SOAP action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reaction {
+    /// SOAP action that triggers it, such as `SetMute`
+    pub after: String,
+    pub action: Action,
+}
+
+/// Script for a [`MockDevice`]
+///
+/// Requests (SOAP and subscription alike) consume `requests` in order; once
+/// they run out every request gets `then`. Timeline actions run in order of
+/// `at` as the device's clock passes them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    /// Volume the device starts at
+    pub volume: u8,
+    /// Mute state the device starts in
+    pub mute: bool,
+    /// Transport state the device starts in
+    pub transport_state: String,
+    /// AVTransport URI the device starts with; while it is `x-rincon:` (a
+    /// group member) Play, Pause, Stop, Next and Previous fault with UPnP
+    /// error 701
+    pub transport_uri: String,
+    pub requests: Vec<RequestStep>,
+    /// Behavior once `requests` is used up
+    pub then: Behavior,
+    pub timeline: Vec<TimedAction>,
+    /// One-shot actions run after the next request for their SOAP action
+    pub reactions: Vec<Reaction>,
+    /// Topology XML served as `GetZoneGroupState`'s `ZoneGroupState`; the action
+    /// faults when unset
+    pub zone_group_state: Option<String>,
+    /// Button lock state; the button lock actions fault with UPnP error 401
+    /// (not supported) when unset
+    pub button_lock: Option<bool>,
+    /// Headphone state served by `GetHeadphoneConnected`; the action faults
+    /// with UPnP error 401 (not supported) when unset
+    pub headphone_connected: Option<bool>,
+    /// Document served at the Bluetooth status page; 404 when unset
+    pub bluetooth_status: Option<String>,
+    /// Household served as `GetHouseholdID`'s `CurrentHouseholdID`; the
+    /// action faults when unset
+    pub household_id: Option<String>,
+    /// Sink list served as `GetProtocolInfo`'s `Sink`; the action faults when
+    /// unset
+    pub protocol_info: Option<String>,
+    /// `<Alarms>` document served as `ListAlarms`' `CurrentAlarmList` and
+    /// edited by `UpdateAlarm`; both actions fault when unset
+    pub alarm_list: Option<String>,
+    /// Remaining sleep timer (`H:MM:SS`, empty for none) served by
+    /// `GetRemainingSleepTimerDuration`; the action faults when unset
+    pub sleep_timer: Option<String>,
+    /// Titles of the queue entries served by ContentDirectory `Browse` of
+    /// `Q:0`, at most 100 per call, emptied by `RemoveAllTracksFromQueue`
+    /// and added to by `AddURIToQueue`; empty by default
+    pub queue: Vec<String>,
+    /// DIDL-Lite documents served whole by `Browse` of other object IDs,
+    /// such as `FV:2` or `SQ:`; unknown IDs fault with UPnP error 701
+    pub browse_results: HashMap<String, String>,
+    /// Room served by `GetAutoplayRoomUUID`; empty (autoplay off) by default
+    pub autoplay_room_uuid: String,
+    /// Volume served by `GetAutoplayVolume`
+    pub autoplay_volume: u8,
+    /// Room name served by `GetZoneAttributes` and changed by
+    /// `SetZoneAttributes`; both fault when unset
+    pub zone_name: Option<String>,
+    /// Keep connections open for further requests, as HTTP/1.1 keep-alive
+    /// allows; otherwise every response closes its connection
+    pub keep_alive: bool,
+    /// Leave SUBSCRIBE and UNSUBSCRIBE requests unanswered when they arrive
+    /// on a connection that already served a request, as some older
+    /// ZonePlayers do; implies `keep_alive`
+    pub stall_reused_connections: bool,
+    /// Grant SIDs the way some firmware does: numbered from 1 again after
+    /// every reboot, and the same on every device using this setting
+    pub recycle_sids: bool,
+    /// Speaker ID the device coordinates a group as; GroupManagement
+    /// `AddMember` / `RemoveMember` fault when unset
+    pub coordinator_id: Option<String>,
+    /// Group volume served by `GetGroupVolume` and changed by the group
+    /// volume actions; every GroupRenderingControl action faults with UPnP
+    /// error 701 (not the coordinator) when unset
+    pub group_volume: Option<u16>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            volume: 25,
+            mute: false,
+            transport_state: "PLAYING".to_string(),
+            transport_uri: String::new(),
+            requests: Vec::new(),
+            then: Behavior::Respond,
+            timeline: Vec::new(),
+            reactions: Vec::new(),
+            zone_group_state: None,
+            button_lock: None,
+            headphone_connected: None,
+            bluetooth_status: None,
+            household_id: None,
+            protocol_info: None,
+            alarm_list: None,
+            sleep_timer: None,
+            queue: Vec::new(),
+            browse_results: HashMap::new(),
+            autoplay_room_uuid: String::new(),
+            autoplay_volume: 0,
+            zone_name: None,
+            keep_alive: false,
+            stall_reused_connections: false,
+            recycle_sids: false,
+            coordinator_id: None,
+            group_volume: None,
+        }
+    }
+}
+
+impl Scenario {
+    /// A device that answers every request normally
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a scenario from JSON
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn with_volume(mut self, volume: u8) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_mute(mut self, muted: bool) -> Self {
+        self.mute = muted;
+        self
+    }
+
+    pub fn with_transport_state(mut self, state: impl Into<String>) -> Self {
+        self.transport_state = state.into();
+        self
+    }
+
+    /// Start as a speaker playing `uri`; `x-rincon:RINCON_...` makes it a
+    /// group member that refuses transport commands
+    pub fn with_transport_uri(mut self, uri: impl Into<String>) -> Self {
+        self.transport_uri = uri.into();
+        self
+    }
+
+    pub fn with_zone_group_state(mut self, xml: impl Into<String>) -> Self {
+        self.zone_group_state = Some(xml.into());
+        self
+    }
+
+    pub fn with_button_lock(mut self, locked: bool) -> Self {
+        self.button_lock = Some(locked);
+        self
+    }
+
+    pub fn with_headphone_connected(mut self, connected: bool) -> Self {
+        self.headphone_connected = Some(connected);
+        self
+    }
+
+    pub fn with_bluetooth_status(mut self, xml: impl Into<String>) -> Self {
+        self.bluetooth_status = Some(xml.into());
+        self
+    }
+
+    pub fn with_household(mut self, household_id: impl Into<String>) -> Self {
+        self.household_id = Some(household_id.into());
+        self
+    }
+
+    pub fn with_protocol_info(mut self, sink: impl Into<String>) -> Self {
+        self.protocol_info = Some(sink.into());
+        self
+    }
+
+    pub fn with_alarm_list(mut self, xml: impl Into<String>) -> Self {
+        self.alarm_list = Some(xml.into());
+        self
+    }
+
+    pub fn with_sleep_timer(mut self, remaining: impl Into<String>) -> Self {
+        self.sleep_timer = Some(remaining.into());
+        self
+    }
+
+    /// Queue entries with these titles, playing `x-file:track{n}.mp3`
+    pub fn with_queue<T: Into<String>>(mut self, titles: impl IntoIterator<Item = T>) -> Self {
+        self.queue = titles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Answer `Browse` of `object_id` with `didl` in a single page
+    pub fn with_browse_result(
+        mut self,
+        object_id: impl Into<String>,
+        didl: impl Into<String>,
+    ) -> Self {
+        self.browse_results.insert(object_id.into(), didl.into());
+        self
+    }
+
+    /// Autoplay line-in in `room_uuid` at `volume`
+    pub fn with_autoplay(mut self, room_uuid: impl Into<String>, volume: u8) -> Self {
+        self.autoplay_room_uuid = room_uuid.into();
+        self.autoplay_volume = volume;
+        self
+    }
+
+    /// Answer `GetZoneAttributes` with room `name`, and accept
+    /// `SetZoneAttributes`
+    pub fn with_zone_name(mut self, name: impl Into<String>) -> Self {
+        self.zone_name = Some(name.into());
+        self
+    }
+
+    /// Coordinate a group as `id`, accepting GroupManagement `AddMember`
+    /// (UPnP error 402 for a speaker already added) and `RemoveMember`
+    pub fn with_coordinator_id(mut self, id: impl Into<String>) -> Self {
+        self.coordinator_id = Some(id.into());
+        self
+    }
+
+    /// Answer GroupRenderingControl as a coordinator whose group is at
+    /// `volume`
+    pub fn with_group_volume(mut self, volume: u16) -> Self {
+        self.group_volume = Some(volume);
+        self
+    }
+
+    /// Serve further requests on a connection instead of closing it
+    pub fn keep_alive(mut self) -> Self {
+        self.keep_alive = true;
+        self
+    }
+
+    /// Stall eventing requests on reused connections; see
+    /// [`stall_reused_connections`](Self::stall_reused_connections)
+    pub fn stall_reused_connections(mut self) -> Self {
+        self.keep_alive = true;
+        self.stall_reused_connections = true;
+        self
+    }
+
+    /// Reissue SIDs after a reboot and share them with other devices; see
+    /// [`recycle_sids`](Self::recycle_sids)
+    pub fn recycle_sids(mut self) -> Self {
+        self.recycle_sids = true;
+        self
+    }
+
+    /// Apply `behavior` to the next `times` requests
+    pub fn step(mut self, behavior: Behavior, times: u32) -> Self {
+        self.requests.push(RequestStep { behavior, times });
+        self
+    }
+
+    pub fn respond(self, times: u32) -> Self {
+        self.step(Behavior::Respond, times)
+    }
+
+    pub fn status(self, code: u16, times: u32) -> Self {
+        self.step(Behavior::Status(code), times)
+    }
+
+    /// Delay the next request by `by` of virtual time
+    pub fn delay(self, by: Duration) -> Self {
+        self.step(Behavior::Delay(by), 1)
+    }
+
+    /// Behavior once the request steps are used up
+    pub fn then(mut self, behavior: Behavior) -> Self {
+        self.then = behavior;
+        self
+    }
+
+    /// Schedule `action` at `at` on the device's timeline
+    pub fn at(mut self, at: Duration, action: Action) -> Self {
+        self.timeline.push(TimedAction { at, action });
+        self
+    }
+
+    /// Run `action` once, right after answering the next `soap_action`
+    /// request, as if another controller acted at the same moment
+    pub fn after_action(mut self, soap_action: impl Into<String>, action: Action) -> Self {
+        self.reactions.push(Reaction {
+            after: soap_action.into(),
+            action,
+        });
+        self
+    }
+}
+
+/// A mock Sonos device running a [`Scenario`]
+///
+/// Clones share the same device.
+#[derive(Debug, Clone)]
+pub struct MockDevice {
+    inner: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    addr: SocketAddr,
+    clock: ManualClock,
+    start: Instant,
+    state: Mutex<DeviceState>,
+    /// Signalled whenever the clock moves, for delayed responses
+    tick: Condvar,
+}
+
+#[derive(Debug)]
+struct DeviceState {
+    requests: VecDeque<RequestStep>,
+    then: Behavior,
+    timeline: VecDeque<TimedAction>,
+    reactions: Vec<Reaction>,
+    volume: u8,
+    mute: bool,
+    transport_state: String,
+    /// Set by `SetAVTransportURI`, read back by `GetMediaInfo`
+    transport_uri: String,
+    /// Set by `SetPlayMode`, read back by `GetTransportSettings`
+    play_mode: String,
+    bass: i8,
+    treble: i8,
+    loudness: bool,
+    zone_group_state: Option<String>,
+    button_lock: Option<bool>,
+    headphone_connected: Option<bool>,
+    bluetooth_status: Option<String>,
+    household_id: Option<String>,
+    protocol_info: Option<String>,
+    alarm_list: Option<String>,
+    sleep_timer: Option<String>,
+    queue: Vec<String>,
+    browse_results: HashMap<String, String>,
+    autoplay_room_uuid: String,
+    autoplay_volume: u8,
+    zone_name: Option<String>,
+    zone_icon: String,
+    /// Status LED, on until `SetLEDState` turns it off
+    led: bool,
+    keep_alive: bool,
+    stall_reused_connections: bool,
+    subscribers: HashMap<String, Subscriber>,
+    /// Start of every SID this device grants; it includes the address so
+    /// SIDs are unique across mocks sharing one callback server, unless
+    /// the scenario recycles SIDs
+    sid_prefix: String,
+    next_sid: usize,
+    recycle_sids: bool,
+    coordinator_id: Option<String>,
+    group_volume: Option<u16>,
+    group_mute: bool,
+    /// Speakers added with `AddMember` and not removed since
+    members: Vec<String>,
+    /// SOAP action and request body of every control request, in arrival
+    /// order
+    calls: Vec<(String, String)>,
+    counts: Counts,
+    /// Method and `USER-AGENT` of every request, in arrival order
+    user_agents: Vec<(String, String)>,
+    /// Connections held open by [`Behavior::Hang`] and stalled requests
+    held: Vec<TcpStream>,
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    service: Service,
+    callback: String,
+    seq: u32,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    connections: usize,
+    requests: usize,
+    stalled: usize,
+    subscriptions: usize,
+    renewals: usize,
+    unsubscriptions: usize,
+}
+
+/// A NOTIFY to send once the state lock is released
+struct Notify {
+    callback: String,
+    sid: String,
+    seq: u32,
+    body: String,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+impl MockDevice {
+    /// Bind `addr` and start serving `scenario` on a fresh clock
+    ///
+    /// # Panics
+    ///
+    /// If `addr` can't be bound.
+    pub fn start(addr: &str, scenario: Scenario) -> Self {
+        Self::start_with_clock(addr, scenario, ManualClock::new())
+    }
+
+    /// Like [`start()`](Self::start), with the timeline on `clock`
+    ///
+    /// Devices sharing a clock share a timeline. Each runs its own due
+    /// actions when advanced through it, or on its next request.
+    pub fn start_with_clock(addr: &str, scenario: Scenario, clock: ManualClock) -> Self {
+        let listener = TcpListener::bind(addr).expect("bind mock device");
+        let mut timeline = scenario.timeline;
+        timeline.sort_by_key(|t| t.at);
+        let addr = listener.local_addr().expect("mock device address");
+        let device = MockDevice {
+            inner: Arc::new(Shared {
+                addr,
+                start: clock.now(),
+                clock,
+                state: Mutex::new(DeviceState {
+                    requests: scenario.requests.into(),
+                    then: scenario.then,
+                    timeline: timeline.into(),
+                    reactions: scenario.reactions,
+                    volume: scenario.volume,
+                    mute: scenario.mute,
+                    transport_state: scenario.transport_state,
+                    transport_uri: scenario.transport_uri,
+                    play_mode: "NORMAL".to_string(),
+                    bass: 0,
+                    treble: 0,
+                    loudness: false,
+                    zone_group_state: scenario.zone_group_state,
+                    button_lock: scenario.button_lock,
+                    headphone_connected: scenario.headphone_connected,
+                    bluetooth_status: scenario.bluetooth_status,
+                    household_id: scenario.household_id,
+                    protocol_info: scenario.protocol_info,
+                    alarm_list: scenario.alarm_list,
+                    sleep_timer: scenario.sleep_timer,
+                    queue: scenario.queue,
+                    browse_results: scenario.browse_results,
+                    autoplay_room_uuid: scenario.autoplay_room_uuid,
+                    autoplay_volume: scenario.autoplay_volume,
+                    zone_name: scenario.zone_name,
+                    zone_icon: "x-rincon-roomicon:living".to_string(),
+                    led: true,
+                    keep_alive: scenario.keep_alive || scenario.stall_reused_connections,
+                    stall_reused_connections: scenario.stall_reused_connections,
+                    subscribers: HashMap::new(),
+                    sid_prefix: if scenario.recycle_sids {
+                        "uuid:mock-".to_string()
+                    } else {
+                        format!("uuid:mock-{addr}-")
+                    },
+                    next_sid: 0,
+                    recycle_sids: scenario.recycle_sids,
+                    coordinator_id: scenario.coordinator_id,
+                    group_volume: scenario.group_volume,
+                    group_mute: false,
+                    members: Vec::new(),
+                    calls: Vec::new(),
+                    counts: Counts::default(),
+                    user_agents: Vec::new(),
+                    held: Vec::new(),
+                }),
+                tick: Condvar::new(),
+            }),
+        };
+        let server = device.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = server.clone();
+                thread::spawn(move || server.serve(stream));
+            }
+        });
+        device.catch_up();
+        device
+    }
+
+    /// The address the device listens on, e.g. the port picked when
+    /// started on port 0
+    pub fn addr(&self) -> SocketAddr {
+        self.inner.addr
+    }
+
+    /// The device's clock, to share with clients under test
+    pub fn clock(&self) -> ManualClock {
+        self.inner.clock.clone()
+    }
+
+    /// Virtual time since the scenario started
+    pub fn elapsed(&self) -> Duration {
+        self.inner.clock.now() - self.inner.start
+    }
+
+    /// Move the clock forward, running timeline actions that fall due and
+    /// releasing delayed responses
+    pub fn advance(&self, by: Duration) {
+        self.inner.clock.advance(by);
+        self.catch_up();
+    }
+
+    /// Run an action now, outside the timeline
+    pub fn perform(&self, action: Action) {
+        let notifies = self.lock().apply(action);
+        send_notifies(self.inner.addr.ip(), notifies);
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.lock().volume
+    }
+
+    pub fn mute(&self) -> bool {
+        self.lock().mute
+    }
+
+    /// Transport state, as set by SOAP requests and actions
+    pub fn transport_state(&self) -> String {
+        self.lock().transport_state.clone()
+    }
+
+    /// Serve `xml` as the topology from now on
+    ///
+    /// For topologies that name the device's own address, which is known
+    /// only once it has started on port 0.
+    pub fn set_zone_group_state(&self, xml: impl Into<String>) {
+        self.lock().zone_group_state = Some(xml.into());
+    }
+
+    /// Room name, as set by `SetZoneAttributes`
+    pub fn zone_name(&self) -> Option<String> {
+        self.lock().zone_name.clone()
+    }
+
+    /// Titles of the queue entries, as changed by the queue actions
+    pub fn queue(&self) -> Vec<String> {
+        self.lock().queue.clone()
+    }
+
+    /// Whether the status LED is on, as set by `SetLEDState`
+    pub fn led(&self) -> bool {
+        self.lock().led
+    }
+
+    /// Bass, treble and loudness, as set by SOAP requests
+    pub fn eq(&self) -> (i8, i8, bool) {
+        let state = self.lock();
+        (state.bass, state.treble, state.loudness)
+    }
+
+    /// TCP connections accepted
+    pub fn connections(&self) -> usize {
+        self.lock().counts.connections
+    }
+
+    /// HTTP requests received
+    pub fn requests(&self) -> usize {
+        self.lock().counts.requests
+    }
+
+    /// Requests left unanswered by
+    /// [`Scenario::stall_reused_connections`]
+    pub fn stalled(&self) -> usize {
+        self.lock().counts.stalled
+    }
+
+    /// Method and `USER-AGENT` (empty if absent) of every request received
+    pub fn user_agents(&self) -> Vec<(String, String)> {
+        self.lock().user_agents.clone()
+    }
+
+    /// SOAP action and request body of every control request received, in
+    /// order
+    pub fn calls(&self) -> Vec<(String, String)> {
+        self.lock().calls.clone()
+    }
+
+    /// SOAP actions received, in order
+    pub fn actions(&self) -> Vec<String> {
+        self.lock().calls.iter().map(|(a, _)| a.clone()).collect()
+    }
+
+    /// Speakers currently added to the group with `AddMember`
+    pub fn members(&self) -> Vec<String> {
+        self.lock().members.clone()
+    }
+
+    /// New subscriptions granted
+    pub fn subscriptions(&self) -> usize {
+        self.lock().counts.subscriptions
+    }
+
+    /// Renewals granted
+    pub fn renewals(&self) -> usize {
+        self.lock().counts.renewals
+    }
+
+    /// Subscriptions cancelled by UNSUBSCRIBE
+    pub fn unsubscriptions(&self) -> usize {
+        self.lock().counts.unsubscriptions
+    }
+
+    /// Subscriptions the device currently knows
+    pub fn live_subscriptions(&self) -> usize {
+        self.lock().subscribers.len()
+    }
+
+    /// SIDs of the current subscriptions to `service`
+    pub fn sids(&self, service: Service) -> Vec<String> {
+        let mut sids: Vec<String> = self
+            .lock()
+            .subscribers
+            .iter()
+            .filter(|(_, s)| s.service == service)
+            .map(|(sid, _)| sid.clone())
+            .collect();
+        sids.sort();
+        sids
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DeviceState> {
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run timeline actions that are due and wake delayed responses
+    fn catch_up(&self) {
+        let elapsed = self.elapsed();
+        let mut notifies = Vec::new();
+        {
+            let mut state = self.lock();
+            while state.timeline.front().is_some_and(|t| t.at <= elapsed) {
+                let due = state.timeline.pop_front().unwrap();
+                notifies.extend(state.apply(due.action));
+            }
+        }
+        self.inner.tick.notify_all();
+        send_notifies(self.inner.addr.ip(), notifies);
+    }
+
+    fn serve(&self, mut stream: TcpStream) {
+        let keep_alive = {
+            let mut state = self.lock();
+            state.counts.connections += 1;
+            state.keep_alive
+        };
+        let mut reused = false;
+        while self.serve_request(&mut stream, keep_alive, reused) && keep_alive {
+            reused = true;
+        }
+    }
+
+    /// Answer one request; `false` once the connection is closed or held
+    fn serve_request(&self, stream: &mut TcpStream, keep_alive: bool, reused: bool) -> bool {
+        let Some(request) = read_request(stream) else {
+            return false;
+        };
+        let eventing = matches!(request.method.as_str(), "SUBSCRIBE" | "UNSUBSCRIBE");
+        if reused && eventing && self.lock().stall_reused_connections {
+            let mut state = self.lock();
+            state.counts.requests += 1;
+            state.counts.stalled += 1;
+            if let Ok(held) = stream.try_clone() {
+                state.held.push(held);
+            }
+            return false;
+        }
+        // A client asking to close gets its connection closed
+        let keep_alive = keep_alive
+            && !request
+                .headers
+                .get("connection")
+                .is_some_and(|c| c.eq_ignore_ascii_case("close"));
+        self.catch_up();
+        let arrived = self.elapsed();
+
+        let behavior = {
+            let mut state = self.lock();
+            state.counts.requests += 1;
+            state.user_agents.push((
+                request.method.clone(),
+                request
+                    .headers
+                    .get("user-agent")
+                    .cloned()
+                    .unwrap_or_default(),
+            ));
+            state.next_behavior()
+        };
+        let response = match behavior {
+            Behavior::Respond => self.respond(&request),
+            Behavior::Status(code) => {
+                format!(
+                    "HTTP/1.1 {code} {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    reason(code)
+                )
+            }
+            Behavior::Delay(by) => {
+                let mut state = self.lock();
+                while self.elapsed() < arrived + by {
+                    state = self
+                        .inner
+                        .tick
+                        .wait(state)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+                drop(state);
+                self.respond(&request)
+            }
+            Behavior::Drop => {
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+                return false;
+            }
+            Behavior::Hang => {
+                if let Ok(held) = stream.try_clone() {
+                    self.lock().held.push(held);
+                }
+                return false;
+            }
+        };
+        let response = if keep_alive {
+            response.replacen("Connection: close", "Connection: keep-alive", 1)
+        } else {
+            response
+        };
+        stream.write_all(response.as_bytes()).is_ok() && keep_alive
+    }
+
+    fn respond(&self, request: &Request) -> String {
+        match request.method.as_str() {
+            "SUBSCRIBE" | "UNSUBSCRIBE" => self.lock().subscription(request),
+            "GET" => self.lock().resource(request),
+            _ => {
+                let (response, notifies) = {
+                    let mut state = self.lock();
+                    let response = state.soap(request);
+                    (response, state.react())
+                };
+                send_notifies(self.inner.addr.ip(), notifies);
+                response
+            }
+        }
+    }
+}
+
+impl DeviceState {
+    fn next_behavior(&mut self) -> Behavior {
+        let Some(step) = self.requests.front_mut() else {
+            return self.then.clone();
+        };
+        let behavior = step.behavior.clone();
+        step.times = step.times.saturating_sub(1);
+        if step.times == 0 {
+            self.requests.pop_front();
+        }
+        behavior
+    }
+
+    fn apply(&mut self, action: Action) -> Vec<Notify> {
+        match action {
+            Action::SetVolume(volume) => {
+                self.volume = volume;
+                let last_change = format!(
+                    r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/RCS/"><InstanceID val="0"><Volume channel="Master" val="{volume}"/></InstanceID></Event>"#
+                );
+                self.last_change(Service::RenderingControl, &last_change)
+            }
+            Action::SetMute(muted) => {
+                self.mute = muted;
+                let last_change = format!(
+                    r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/RCS/"><InstanceID val="0"><Mute channel="Master" val="{}"/></InstanceID></Event>"#,
+                    u8::from(muted)
+                );
+                self.last_change(Service::RenderingControl, &last_change)
+            }
+            Action::SetTransportState(state) => {
+                let last_change = format!(
+                    r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"><InstanceID val="0"><TransportState val="{}"/></InstanceID></Event>"#,
+                    escape(&state)
+                );
+                self.transport_state = state;
+                self.last_change(Service::AVTransport, &last_change)
+            }
+            Action::SetStatusLight(on) => {
+                self.led = on;
+                self.device_property("LEDState", led_state_str(on))
+            }
+            Action::SetButtonLock(locked) => {
+                self.button_lock = Some(locked);
+                self.device_property("ButtonLockState", button_lock_state_str(locked))
+            }
+            Action::Notify { service, body } => self.notifies(service, &body),
+            Action::Expire(service) => {
+                self.subscribers.retain(|_, s| s.service != service);
+                Vec::new()
+            }
+            Action::Reboot => {
+                self.subscribers.clear();
+                self.held.clear();
+                if self.recycle_sids {
+                    self.next_sid = 0;
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// Run the reaction waiting for the SOAP action just answered, if any
+    fn react(&mut self) -> Vec<Notify> {
+        let Some((action, _)) = self.calls.last() else {
+            return Vec::new();
+        };
+        match self.reactions.iter().position(|r| r.after == *action) {
+            Some(pos) => {
+                let reaction = self.reactions.remove(pos);
+                self.apply(reaction.action)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// NOTIFYs carrying `last_change` as a service's `LastChange`
+    fn last_change(&mut self, service: Service, last_change: &str) -> Vec<Notify> {
+        let body = format!(
+            r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>{}</LastChange></e:property></e:propertyset>"#,
+            escape(last_change)
+        );
+        self.notifies(service, &body)
+    }
+
+    /// A DeviceProperties NOTIFY of one variable
+    fn device_property(&mut self, name: &str, value: &str) -> Vec<Notify> {
+        let body = format!(
+            r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><{name}>{value}</{name}></e:property></e:propertyset>"#
+        );
+        self.notifies(Service::DeviceProperties, &body)
+    }
+
+    fn notifies(&mut self, service: Service, body: &str) -> Vec<Notify> {
+        self.subscribers
+            .iter_mut()
+            .filter(|(_, s)| s.service == service)
+            .map(|(sid, s)| {
+                let notify = Notify {
+                    callback: s.callback.clone(),
+                    sid: sid.clone(),
+                    seq: s.seq,
+                    body: body.to_string(),
+                };
+                s.seq += 1;
+                notify
+            })
+            .collect()
+    }
+
+    /// Plain HTTP GET: the Bluetooth status page, or 404
+    fn resource(&self, request: &Request) -> String {
+        match (&self.bluetooth_status, request.path.as_str()) {
+            (Some(xml), BLUETOOTH_STATUS_PATH) => format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{xml}",
+                xml.len()
+            ),
+            _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+        }
+    }
+
+    fn subscription(&mut self, request: &Request) -> String {
+        let sid = request.headers.get("sid");
+        let granted = match (request.method.as_str(), sid) {
+            ("SUBSCRIBE", None) => {
+                let service = [
+                    Service::AVTransport,
+                    Service::RenderingControl,
+                    Service::GroupRenderingControl,
+                    Service::ZoneGroupTopology,
+                    Service::GroupManagement,
+                    Service::DeviceProperties,
+                ]
+                .into_iter()
+                .find(|s| request.path.trim_start_matches('/') == s.info().event_endpoint);
+                let callback = request
+                    .headers
+                    .get("callback")
+                    .map(|c| c.trim_matches(|c| c == '<' || c == '>').to_string());
+                match (service, callback) {
+                    (Some(service), Some(callback)) => {
+                        self.next_sid += 1;
+                        self.counts.subscriptions += 1;
+                        let sid = format!("{}{}", self.sid_prefix, self.next_sid);
+                        self.subscribers.insert(
+                            sid.clone(),
+                            Subscriber {
+                                service,
+                                callback,
+                                seq: 0,
+                            },
+                        );
+                        Some(sid)
+                    }
+                    _ => None,
+                }
+            }
+            ("SUBSCRIBE", Some(sid)) => {
+                let live = self.subscribers.contains_key(sid);
+                if live {
+                    self.counts.renewals += 1;
+                }
+                live.then(|| sid.clone())
+            }
+            (_, Some(sid)) => {
+                let live = self.subscribers.remove(sid).is_some();
+                if live {
+                    self.counts.unsubscriptions += 1;
+                }
+                live.then(|| sid.clone())
+            }
+            _ => None,
+        };
+        match granted {
+            Some(sid) => format!(
+                "HTTP/1.1 200 OK\r\nSID: {sid}\r\nTIMEOUT: Second-1800\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            ),
+            None => "HTTP/1.1 412 Precondition Failed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+        }
+    }
+
+    fn soap(&mut self, request: &Request) -> String {
+        let action = request
+            .headers
+            .get("soapaction")
+            .and_then(|a| a.rsplit('#').next())
+            .unwrap_or_default()
+            .trim_end_matches('"')
+            .to_string();
+        self.calls.push((action.clone(), request.body.clone()));
+        // UPnP error code for the fault, when the action is refused
+        let mut fault = ((action.ends_with("ButtonLockState") && self.button_lock.is_none())
+            || (action == "GetHeadphoneConnected" && self.headphone_connected.is_none()))
+        .then_some(401)
+        .or_else(|| {
+            let control = request.path.trim_start_matches('/');
+            let group_rendering = control == Service::GroupRenderingControl.info().endpoint;
+            (group_rendering && self.group_volume.is_none()).then_some(701)
+        })
+        .or_else(|| {
+            let transport = matches!(
+                action.as_str(),
+                "Play" | "Pause" | "Stop" | "Next" | "Previous"
+            );
+            (transport && self.transport_uri.starts_with("x-rincon:")).then_some(701)
+        });
+        let fields = match action.as_str() {
+            _ if fault.is_some() => None,
+            "GetVolume" => Some(format!("<CurrentVolume>{}</CurrentVolume>", self.volume)),
+            "SetVolume" => {
+                if let Some(volume) = request
+                    .body
+                    .split("<DesiredVolume>")
+                    .nth(1)
+                    .and_then(|rest| rest.split('<').next())
+                    .and_then(|v| v.parse().ok())
+                {
+                    self.volume = volume;
+                }
+                Some(String::new())
+            }
+            "GetMute" => Some(format!(
+                "<CurrentMute>{}</CurrentMute>",
+                u8::from(self.mute)
+            )),
+            "SetMute" => {
+                if let Some(muted) = arg(&request.body, "DesiredMute") {
+                    self.mute = muted == "1";
+                }
+                Some(String::new())
+            }
+            "GetBass" => Some(format!("<CurrentBass>{}</CurrentBass>", self.bass)),
+            "GetTreble" => Some(format!("<CurrentTreble>{}</CurrentTreble>", self.treble)),
+            "GetLoudness" => Some(format!(
+                "<CurrentLoudness>{}</CurrentLoudness>",
+                u8::from(self.loudness)
+            )),
+            "SetBass" => {
+                if let Some(bass) = arg(&request.body, "DesiredBass").and_then(|v| v.parse().ok()) {
+                    self.bass = bass;
+                }
+                Some(String::new())
+            }
+            "SetTreble" => {
+                if let Some(treble) =
+                    arg(&request.body, "DesiredTreble").and_then(|v| v.parse().ok())
+                {
+                    self.treble = treble;
+                }
+                Some(String::new())
+            }
+            "SetLoudness" => {
+                if let Some(loudness) = arg(&request.body, "DesiredLoudness") {
+                    self.loudness = loudness == "1";
+                }
+                Some(String::new())
+            }
+            "GetOutputFixed" => Some("<CurrentFixed>0</CurrentFixed>".to_string()),
+            "GetHeadphoneConnected" => self.headphone_connected.map(|connected| {
+                format!(
+                    "<CurrentHeadphoneConnected>{}</CurrentHeadphoneConnected>",
+                    u8::from(connected)
+                )
+            }),
+            "ListPresets" => Some(format!(
+                "<CurrentPresetNameList>{FACTORY_DEFAULTS_PRESET}</CurrentPresetNameList>"
+            )),
+            "SelectPreset" => {
+                if arg(&request.body, "PresetName") == Some(FACTORY_DEFAULTS_PRESET) {
+                    self.bass = 0;
+                    self.treble = 0;
+                    self.loudness = true;
+                    Some(String::new())
+                } else {
+                    fault = Some(701);
+                    None
+                }
+            }
+            "SetAVTransportURI" => {
+                self.transport_uri = arg(&request.body, "CurrentURI")
+                    .unwrap_or_default()
+                    .to_string();
+                Some(String::new())
+            }
+            "BecomeCoordinatorOfStandaloneGroup" => {
+                let left = self
+                    .transport_uri
+                    .strip_prefix("x-rincon:")
+                    .unwrap_or_default()
+                    .to_string();
+                self.transport_uri.clear();
+                Some(format!(
+                    "<DelegatedGroupCoordinatorID>{}</DelegatedGroupCoordinatorID><NewGroupID></NewGroupID>",
+                    escape(&left)
+                ))
+            }
+            "GetMediaInfo" => Some(format!(
+                "<NrTracks>{}</NrTracks><MediaDuration>NOT_IMPLEMENTED</MediaDuration>\
+                 <CurrentURI>{}</CurrentURI><CurrentURIMetaData></CurrentURIMetaData>\
+                 <NextURI></NextURI><NextURIMetaData></NextURIMetaData>\
+                 <PlayMedium>NETWORK</PlayMedium><RecordMedium>NOT_IMPLEMENTED</RecordMedium>\
+                 <WriteStatus>NOT_IMPLEMENTED</WriteStatus>",
+                u8::from(!self.transport_uri.is_empty()),
+                escape(&self.transport_uri)
+            )),
+            "SetPlayMode" => {
+                if let Some(mode) = arg(&request.body, "NewPlayMode") {
+                    self.play_mode = mode.to_string();
+                }
+                Some(String::new())
+            }
+            "GetTransportSettings" => Some(format!(
+                "<PlayMode>{}</PlayMode><RecQualityMode>NOT_IMPLEMENTED</RecQualityMode>",
+                self.play_mode
+            )),
+            "GetGroupVolume" => self
+                .group_volume
+                .map(|volume| format!("<CurrentVolume>{volume}</CurrentVolume>")),
+            "SetGroupVolume" => {
+                if let Some(volume) =
+                    arg(&request.body, "DesiredVolume").and_then(|v| v.parse().ok())
+                {
+                    self.group_volume = Some(volume);
+                }
+                Some(String::new())
+            }
+            "SetRelativeGroupVolume" => {
+                let adjustment: i32 = arg(&request.body, "Adjustment")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0);
+                let volume = (i32::from(self.group_volume.unwrap_or(0)) + adjustment).clamp(0, 100);
+                self.group_volume = Some(volume as u16);
+                Some(format!("<NewVolume>{volume}</NewVolume>"))
+            }
+            "GetGroupMute" => Some(format!(
+                "<CurrentMute>{}</CurrentMute>",
+                u8::from(self.group_mute)
+            )),
+            "SetGroupMute" => {
+                if let Some(muted) = arg(&request.body, "DesiredMute") {
+                    self.group_mute = muted == "1";
+                }
+                Some(String::new())
+            }
+            "SnapshotGroupVolume" => Some(String::new()),
+            "AddMember" => match (&self.coordinator_id, arg(&request.body, "MemberID")) {
+                (Some(id), Some(member)) if !self.members.iter().any(|m| m == member) => {
+                    self.members.push(member.to_string());
+                    Some(format!(
+                        "<CurrentTransportSettings></CurrentTransportSettings>\
+                         <CurrentURI>x-rincon-queue:{id}#0</CurrentURI>\
+                         <GroupUUIDJoined>{id}:1</GroupUUIDJoined>\
+                         <ResetVolumeAfter>0</ResetVolumeAfter>\
+                         <VolumeAVTransportURI></VolumeAVTransportURI>"
+                    ))
+                }
+                (Some(_), Some(_)) => {
+                    fault = Some(402);
+                    None
+                }
+                _ => None,
+            },
+            "RemoveMember" => {
+                let before = self.members.len();
+                if let Some(member) = arg(&request.body, "MemberID") {
+                    self.members.retain(|m| m != member);
+                }
+                if self.members.len() < before {
+                    Some(String::new())
+                } else {
+                    fault = Some(402);
+                    None
+                }
+            }
+            "ReportTrackBufferingResult" => Some(String::new()),
+            "GetTransportInfo" => Some(format!(
+                "<CurrentTransportState>{}</CurrentTransportState>\
+                 <CurrentTransportStatus>OK</CurrentTransportStatus><CurrentSpeed>1</CurrentSpeed>",
+                self.transport_state
+            )),
+            "Play" | "Pause" | "Stop" => {
+                self.transport_state = match action.as_str() {
+                    "Play" => "PLAYING",
+                    "Pause" => "PAUSED_PLAYBACK",
+                    _ => "STOPPED",
+                }
+                .to_string();
+                Some(String::new())
+            }
+            "GetPositionInfo" => Some(
+                "<Track>1</Track><TrackDuration>0:03:00</TrackDuration>\
+                 <TrackURI>x-file:song.mp3</TrackURI><RelTime>0:00:10</RelTime>"
+                    .to_string(),
+            ),
+            "GetZoneGroupState" => self
+                .zone_group_state
+                .as_deref()
+                .map(|xml| format!("<ZoneGroupState>{}</ZoneGroupState>", escape(xml))),
+            "GetHouseholdID" => self
+                .household_id
+                .as_deref()
+                .map(|id| format!("<CurrentHouseholdID>{}</CurrentHouseholdID>", escape(id))),
+            "GetProtocolInfo" => self
+                .protocol_info
+                .as_deref()
+                .map(|sink| format!("<Source></Source><Sink>{}</Sink>", escape(sink))),
+            "ListAlarms" => self.alarm_list.as_deref().map(|xml| {
+                format!(
+                    "<CurrentAlarmList>{}</CurrentAlarmList><CurrentAlarmListVersion>mock:1</CurrentAlarmListVersion>",
+                    escape(xml)
+                )
+            }),
+            "UpdateAlarm" => match self
+                .alarm_list
+                .as_deref()
+                .and_then(|list| update_alarm(list, &request.body))
+            {
+                Some(list) => {
+                    self.alarm_list = Some(list);
+                    Some(String::new())
+                }
+                None => {
+                    fault = Some(402);
+                    None
+                }
+            },
+            "Browse" if arg(&request.body, "ObjectID") == Some("Q:0") => {
+                let arg_u32 = |name| {
+                    arg(&request.body, name)
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .unwrap_or(0)
+                };
+                let start = arg_u32("StartingIndex").min(self.queue.len());
+                let count = match arg_u32("RequestedCount") {
+                    0 => 100,
+                    count => count.min(100),
+                };
+                let page = &self.queue[start..(start + count).min(self.queue.len())];
+                let items: String = page
+                    .iter()
+                    .enumerate()
+                    .map(|(i, title)| {
+                        let n = start + i + 1;
+                        format!(
+                            r#"<item id="Q:0/{n}" parentID="Q:0" restricted="true"><res duration="0:03:00">x-file:track{n}.mp3</res><dc:title>{}</dc:title></item>"#,
+                            escape(title)
+                        )
+                    })
+                    .collect();
+                let didl = format!(
+                    r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/">{items}</DIDL-Lite>"#
+                );
+                Some(format!(
+                    "<Result>{}</Result><NumberReturned>{}</NumberReturned><TotalMatches>{}</TotalMatches><UpdateID>1</UpdateID>",
+                    escape(&didl),
+                    page.len(),
+                    self.queue.len()
+                ))
+            }
+            "Browse" => {
+                let object_id = arg(&request.body, "ObjectID").map(unescape);
+                match object_id.and_then(|id| self.browse_results.get(&id)) {
+                    Some(didl) => {
+                        // Everything on the first page, nothing after it
+                        let first = arg(&request.body, "StartingIndex") == Some("0");
+                        let count = if first {
+                            didl.matches("<item ").count() + didl.matches("<container ").count()
+                        } else {
+                            0
+                        };
+                        let result = if first { didl.as_str() } else { "" };
+                        Some(format!(
+                            "<Result>{}</Result><NumberReturned>{count}</NumberReturned><TotalMatches>{count}</TotalMatches><UpdateID>1</UpdateID>",
+                            escape(result)
+                        ))
+                    }
+                    None => {
+                        fault = Some(701);
+                        None
+                    }
+                }
+            }
+            "RemoveAllTracksFromQueue" => {
+                self.queue.clear();
+                Some(String::new())
+            }
+            "AddURIToQueue" => {
+                let metadata = arg(&request.body, "EnqueuedURIMetaData")
+                    .map(unescape)
+                    .unwrap_or_default();
+                let title = arg(&metadata, "dc:title")
+                    .map(unescape)
+                    .or_else(|| arg(&request.body, "EnqueuedURI").map(unescape))
+                    .unwrap_or_default();
+                self.queue.push(title);
+                Some(format!(
+                    "<FirstTrackNumberEnqueued>{len}</FirstTrackNumberEnqueued><NumTracksAdded>1</NumTracksAdded><NewQueueLength>{len}</NewQueueLength>",
+                    len = self.queue.len()
+                ))
+            }
+            "GetRemainingSleepTimerDuration" => self.sleep_timer.as_deref().map(|remaining| {
+                format!(
+                    "<RemainingSleepTimerDuration>{remaining}</RemainingSleepTimerDuration><CurrentSleepTimerGeneration>1</CurrentSleepTimerGeneration>"
+                )
+            }),
+            "GetZoneAttributes" => self.zone_name.as_deref().map(|name| {
+                format!(
+                    "<CurrentZoneName>{}</CurrentZoneName><CurrentIcon>{}</CurrentIcon><CurrentConfiguration>1</CurrentConfiguration>",
+                    escape(name),
+                    escape(&self.zone_icon)
+                )
+            }),
+            "SetZoneAttributes" if self.zone_name.is_some() => {
+                self.zone_name = arg(&request.body, "DesiredZoneName").map(unescape);
+                self.zone_icon = arg(&request.body, "DesiredIcon")
+                    .map(unescape)
+                    .unwrap_or_default();
+                Some(String::new())
+            }
+            "GetLEDState" => Some(format!(
+                "<CurrentLEDState>{}</CurrentLEDState>",
+                led_state_str(self.led)
+            )),
+            "SetLEDState" => {
+                if let Some(on) = arg(&request.body, "DesiredLEDState").and_then(parse_led_state) {
+                    self.led = on;
+                }
+                Some(String::new())
+            }
+            "GetAutoplayRoomUUID" => Some(format!(
+                "<RoomUUID>{}</RoomUUID>",
+                escape(&self.autoplay_room_uuid)
+            )),
+            "GetAutoplayVolume" => Some(format!(
+                "<CurrentVolume>{}</CurrentVolume>",
+                self.autoplay_volume
+            )),
+            "GetButtonLockState" => self.button_lock.map(|locked| {
+                format!(
+                    "<CurrentButtonLockState>{}</CurrentButtonLockState>",
+                    button_lock_state_str(locked)
+                )
+            }),
+            "SetButtonLockState" => {
+                if let Some(locked) = request
+                    .body
+                    .split("<DesiredButtonLockState>")
+                    .nth(1)
+                    .and_then(|rest| rest.split('<').next())
+                    .and_then(parse_button_lock_state)
+                {
+                    self.button_lock = Some(locked);
+                }
+                Some(String::new())
+            }
+            _ => None,
+        };
+        let (status, inner) = match fields {
+            Some(fields) => (
+                "200 OK",
+                format!(r#"<u:{action}Response xmlns:u="urn:mock">{fields}</u:{action}Response>"#),
+            ),
+            None => (
+                "500 Internal Server Error",
+                format!(
+                    "<s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring>{}</s:Fault>",
+                    fault
+                        .map(|code| format!(
+                            "<detail><UPnPError><errorCode>{code}</errorCode></UPnPError></detail>"
+                        ))
+                        .unwrap_or_default()
+                ),
+            ),
+        };
+        let envelope = format!(
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body>{inner}</s:Body></s:Envelope>"#
+        );
+        format!(
+            "HTTP/1.1 {status}\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{envelope}",
+            envelope.len()
+        )
+    }
+}
+
+/// Text of the first `<name>` element in a request body
+fn arg<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    body.split(&format!("<{name}>"))
+        .nth(1)
+        .and_then(|rest| rest.split('<').next())
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream.try_clone().ok()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    let content_length = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    let _ = reader.read_exact(&mut body);
+    Some(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+/// Deliver NOTIFYs in order from the device's own address, so the callback
+/// server sees which device sent them; delivery failures are ignored like a
+/// real device would
+fn send_notifies(from: IpAddr, notifies: Vec<Notify>) {
+    for notify in notifies {
+        let Some(rest) = notify.callback.strip_prefix("http://") else {
+            continue;
+        };
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let Some(addr) = host.to_socket_addrs().ok().and_then(|mut a| a.next()) else {
+            continue;
+        };
+        let Ok(mut stream) = connect_from(from, addr) else {
+            continue;
+        };
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+        let request = format!(
+            "NOTIFY /{path} HTTP/1.1\r\nHOST: {host}\r\nCONTENT-TYPE: text/xml; charset=\"utf-8\"\r\nNT: upnp:event\r\nNTS: upnp:propchange\r\nSID: {}\r\nSEQ: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            notify.sid,
+            notify.seq,
+            notify.body.len(),
+            notify.body
+        );
+        if stream.write_all(request.as_bytes()).is_ok() {
+            let mut status = String::new();
+            let _ = BufReader::new(stream).read_line(&mut status);
+        }
+    }
+}
+
+fn connect_from(from: IpAddr, to: SocketAddr) -> std::io::Result<TcpStream> {
+    let socket = Socket::new(Domain::for_address(to), Type::STREAM, None)?;
+    if from.is_ipv4() == to.is_ipv4() {
+        socket.bind(&SocketAddr::new(from, 0).into())?;
+    }
+    socket.connect_timeout(&to.into(), Duration::from_secs(2))?;
+    Ok(socket.into())
+}
+
+fn escape(xml: &str) -> String {
+    xml.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `list` with the alarm `UpdateAlarm`'s `body` names replaced by its
+/// arguments; `None` when no alarm has that ID
+fn update_alarm(list: &str, body: &str) -> Option<String> {
+    let id = arg(body, "ID")?;
+    let mut alarms = xmltree::Element::parse(list.as_bytes()).ok()?;
+    let alarm = alarms
+        .children
+        .iter_mut()
+        .filter_map(|node| node.as_mut_element())
+        .find(|alarm| alarm.attributes.get("ID").map(String::as_str) == Some(id))?;
+    for (argument, attribute) in [
+        ("StartLocalTime", "StartTime"),
+        ("Duration", "Duration"),
+        ("Recurrence", "Recurrence"),
+        ("Enabled", "Enabled"),
+        ("RoomUUID", "RoomUUID"),
+        ("ProgramURI", "ProgramURI"),
+        ("ProgramMetaData", "ProgramMetaData"),
+        ("PlayMode", "PlayMode"),
+        ("Volume", "Volume"),
+        ("IncludeLinkedZones", "IncludeLinkedZones"),
+    ] {
+        let value = arg(body, argument).map(unescape).unwrap_or_default();
+        alarm.attributes.insert(attribute.to_string(), value);
+    }
+    let mut xml = Vec::new();
+    alarms
+        .write_with_config(
+            &mut xml,
+            xmltree::EmitterConfig::new().write_document_declaration(false),
+        )
+        .ok()?;
+    String::from_utf8(xml).ok()
+}
+
+fn unescape(xml: &str) -> String {
+    xml.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn reason(code: u16) -> &'static str {
+    match code {
+        400 => "Bad Request",
+        404 => "Not Found",
+        412 => "Precondition Failed",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+/// `Duration` as whole milliseconds
+mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SonosClient;
+
+    #[test]
+    fn test_scenario_json_matches_builder() {
+        let built = Scenario::new()
+            .with_volume(40)
+            .respond(3)
+            .status(503, 2)
+            .delay(Duration::from_secs(5))
+            .step(Behavior::Drop, 1)
+            .then(Behavior::Hang)
+            .at(Duration::from_secs(2), Action::SetVolume(30))
+            .at(
+                Duration::from_secs(10),
+                Action::Expire(Service::AVTransport),
+            );
+        let loaded = Scenario::from_json(
+            r#"{
+                "volume": 40,
+                "requests": [
+                    { "behavior": "respond", "times": 3 },
+                    { "behavior": { "status": 503 }, "times": 2 },
+                    { "behavior": { "delay": 5000 } },
+                    { "behavior": "drop" }
+                ],
+                "then": "hang",
+                "timeline": [
+                    { "at_ms": 2000, "action": { "set_volume": 30 } },
+                    { "at_ms": 10000, "action": { "expire": "AVTransport" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(loaded, built);
+    }
+
+    #[test]
+    fn test_request_steps_run_in_order_on_virtual_time() {
+        let device = MockDevice::start(
+            "127.0.0.1:0",
+            Scenario::new()
+                .respond(1)
+                .status(503, 1)
+                .delay(Duration::from_secs(5))
+                .step(Behavior::Drop, 1)
+                .at(Duration::from_secs(2), Action::SetVolume(60)),
+        );
+        let addr = device.addr().to_string();
+        let client = SonosClient::new();
+        let volume = || {
+            client.execute_enhanced(
+                &addr,
+                crate::services::rendering_control::get_volume("Master".to_string())
+                    .build()
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(volume().unwrap().current_volume, 25);
+        assert!(volume().is_err(), "503");
+
+        // Released only once the timeline passes the delay; t+2s runs first
+        let delayed = thread::scope(|scope| {
+            let call = scope.spawn(volume);
+            while device.requests() < 3 {
+                thread::sleep(Duration::from_millis(5));
+            }
+            device.advance(Duration::from_secs(4));
+            thread::sleep(Duration::from_millis(50));
+            assert!(!call.is_finished());
+            device.advance(Duration::from_secs(1));
+            call.join().unwrap()
+        });
+        assert_eq!(delayed.unwrap().current_volume, 60);
+
+        assert!(volume().is_err(), "dropped connection");
+        assert_eq!(volume().unwrap().current_volume, 60);
+        assert_eq!(device.requests(), 5);
+    }
+
+    #[test]
+    fn test_every_request_carries_the_client_identity() {
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let addr = device.addr().to_string();
+        let get_volume = || {
+            crate::services::rendering_control::get_volume("Master".to_string())
+                .build()
+                .unwrap()
+        };
+        let default_agent = concat!("sonos-sdk/", env!("CARGO_PKG_VERSION"));
+        assert_eq!(SonosClient::new().user_agent(), default_agent);
+        SonosClient::new()
+            .execute_enhanced(&addr, get_volume())
+            .unwrap();
+
+        let identity = crate::ClientIdentity::new("my-controller", "2.1")
+            .with_contact_url("https://example.com");
+        let client = SonosClient::new().with_identity(&identity);
+        client.execute_enhanced(&addr, get_volume()).unwrap();
+        let subscription = client
+            .subscribe(&addr, Service::RenderingControl, "http://127.0.0.1:9/cb")
+            .unwrap();
+        subscription.renew().unwrap();
+        subscription.unsubscribe().unwrap();
+        // Not a resource the mock serves; only the request matters
+        let _ = client.fetch_resource(&format!("http://{addr}/status/info"));
+
+        let custom = "my-controller/2.1 (+https://example.com)";
+        let expected: Vec<(String, String)> = [
+            ("POST", default_agent),
+            ("POST", custom),
+            ("SUBSCRIBE", custom),
+            ("SUBSCRIBE", custom),
+            ("UNSUBSCRIBE", custom),
+            ("GET", custom),
+        ]
+        .into_iter()
+        .map(|(m, a)| (m.to_string(), a.to_string()))
+        .collect();
+        assert_eq!(device.user_agents(), expected);
+    }
+
+    #[test]
+    fn test_queue_pages_read_past_the_browse_limit() {
+        let titles: Vec<String> = (1..=230).map(|n| format!("Song {n}")).collect();
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().with_queue(titles));
+        let addr = device.addr().to_string();
+        let client = SonosClient::new();
+
+        let pages: Vec<_> =
+            crate::services::content_directory::browse_pages(&client, &addr, "Q:0", 150)
+                .collect::<Result<_, _>>()
+                .unwrap();
+        let sizes: Vec<_> = pages.iter().map(|p| p.number_returned).collect();
+        assert_eq!(sizes, [100, 100, 30]);
+        let items: Vec<_> = pages.into_iter().flat_map(|p| p.items).collect();
+        assert_eq!(items[0].title.as_deref(), Some("Song 1"));
+        assert_eq!(items[229].id, "Q:0/230");
+        assert_eq!(items[229].uri.as_deref(), Some("x-file:track230.mp3"));
+        assert_eq!(device.requests(), 3);
+
+        // An empty queue is one empty page; an error ends the iteration
+        let empty = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let pages: Vec<_> =
+            crate::services::content_directory::queue_pages(&client, &empty.addr().to_string())
+                .collect();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].as_ref().unwrap().total_matches, 0);
+        let mut missing = crate::services::content_directory::browse_pages(
+            &client,
+            &empty.addr().to_string(),
+            "SQ:",
+            10,
+        );
+        assert!(missing.next().unwrap().is_err());
+        assert!(missing.next().is_none());
+    }
+
+    #[test]
+    fn test_reaction_runs_once_after_its_action() {
+        let device = MockDevice::start(
+            "127.0.0.1:0",
+            Scenario::new()
+                .with_transport_state("PAUSED_PLAYBACK")
+                .after_action("SetMute", Action::SetMute(false)),
+        );
+        let addr = device.addr().to_string();
+        let client = SonosClient::new();
+        let set_mute = |muted| {
+            client
+                .execute_enhanced(
+                    &addr,
+                    crate::services::rendering_control::set_mute("Master".to_string(), muted)
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap();
+        };
+
+        // Another controller unmutes right after the first SetMute
+        set_mute(true);
+        assert!(!device.mute());
+        set_mute(true);
+        assert!(device.mute());
+
+        assert_eq!(device.transport_state(), "PAUSED_PLAYBACK");
+        client
+            .execute_enhanced(
+                &addr,
+                crate::services::av_transport::play("1".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(device.transport_state(), "PLAYING");
+    }
+
+    #[test]
+    fn test_fresh_eventing_connections_avoid_reuse_stalls() {
+        let config = crate::SoapClientConfig::default()
+            .with_connect_timeout(Duration::from_millis(300))
+            .with_read_timeout(Duration::from_millis(300));
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().stall_reused_connections());
+        let addr = device.addr().to_string();
+        let client = SonosClient::with_soap_config(config.clone());
+        assert_eq!(
+            client.eventing_connection(&addr),
+            crate::EventingConnection::Close
+        );
+        let subscription = client
+            .subscribe(&addr, Service::AVTransport, "http://127.0.0.1:9/cb")
+            .unwrap();
+        subscription.renew().unwrap();
+        subscription.renew().unwrap();
+        subscription.unsubscribe().unwrap();
+        assert_eq!(device.stalled(), 0);
+        assert_eq!((device.subscriptions(), device.renewals()), (1, 2));
+
+        // Reusing the subscription's connection for its renewal stalls
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().stall_reused_connections());
+        let addr = device.addr().to_string();
+        let client = SonosClient::with_soap_config(config)
+            .with_eventing_connection(crate::EventingConnection::KeepAlive);
+        let subscription = client
+            .subscribe(&addr, Service::AVTransport, "http://127.0.0.1:9/cb")
+            .unwrap();
+        assert!(subscription.renew().is_err());
+        assert_eq!(device.stalled(), 1);
+        assert_eq!(device.renewals(), 0);
+    }
+}
@@ -0,0 +1,45 @@
+//! Results of operations sent to several devices at once
+//!
+//! See [`SonosClient::execute_batch()`](crate::SonosClient::execute_batch).
+
+use crate::error::ApiError;
+
+/// One operation of a batch and how it went
+#[derive(Debug)]
+pub struct BatchItem<R> {
+    /// Device the operation was sent to
+    pub ip: String,
+    pub result: Result<R, ApiError>,
+}
+
+/// Outcome of [`SonosClient::execute_batch()`](crate::SonosClient::execute_batch),
+/// one item per operation in the order they were given
+#[derive(Debug)]
+pub struct BatchResult<R> {
+    pub items: Vec<BatchItem<R>>,
+}
+
+impl<R> BatchResult<R> {
+    /// Whether every operation succeeded
+    pub fn all_succeeded(&self) -> bool {
+        self.items.iter().all(|item| item.result.is_ok())
+    }
+
+    /// Operations that succeeded
+    pub fn successes(&self) -> impl Iterator<Item = &BatchItem<R>> {
+        self.items.iter().filter(|item| item.result.is_ok())
+    }
+
+    /// Operations that failed
+    pub fn failures(&self) -> impl Iterator<Item = &BatchItem<R>> {
+        self.items.iter().filter(|item| item.result.is_err())
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
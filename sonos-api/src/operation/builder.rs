@@ -4,6 +4,7 @@
 //! with validation, timeout, and retry configuration.
 
 use super::{OperationMetadata, UPnPOperation, Validate, ValidationError, ValidationLevel};
+use soap_client::RetryPolicy;
 use std::marker::PhantomData;
 use std::time::Duration;
 
@@ -18,6 +19,7 @@ pub struct OperationBuilder<Op: UPnPOperation> {
     request: Op::Request,
     validation: ValidationLevel,
     timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
     _phantom: PhantomData<Op>,
 }
 
@@ -34,6 +36,7 @@ impl<Op: UPnPOperation> OperationBuilder<Op> {
             request,
             validation: ValidationLevel::default(),
             timeout: None,
+            retry: None,
             _phantom: PhantomData,
         }
     }
@@ -62,6 +65,22 @@ impl<Op: UPnPOperation> OperationBuilder<Op> {
         self
     }
 
+    /// Retry the operation on dropped connections
+    ///
+    /// Only failures before any response arrived are retried (see
+    /// [`SoapClient::call_with_retry()`](soap_client::SoapClient::call_with_retry)).
+    /// Leave it off for actions that must not run twice.
+    ///
+    /// # Arguments
+    /// * `policy` - Attempts and backoff to use
+    ///
+    /// # Returns
+    /// The builder for method chaining
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
     /// Build the final composable operation
     ///
     /// This validates the request according to the configured validation level
@@ -77,6 +96,7 @@ impl<Op: UPnPOperation> OperationBuilder<Op> {
             request: self.request,
             validation: self.validation,
             timeout: self.timeout,
+            retry: self.retry,
             metadata: Op::metadata(),
             _phantom: PhantomData,
         })
@@ -94,6 +114,7 @@ impl<Op: UPnPOperation> OperationBuilder<Op> {
             request: self.request,
             validation: ValidationLevel::None,
             timeout: self.timeout,
+            retry: self.retry,
             metadata: Op::metadata(),
             _phantom: PhantomData,
         }
@@ -108,6 +129,11 @@ impl<Op: UPnPOperation> OperationBuilder<Op> {
     pub fn timeout(&self) -> Option<Duration> {
         self.timeout
     }
+
+    /// Get the current retry policy, if retries are enabled
+    pub fn retry(&self) -> Option<&RetryPolicy> {
+        self.retry.as_ref()
+    }
 }
 
 /// A composable operation ready for execution
@@ -121,6 +147,7 @@ pub struct ComposableOperation<Op: UPnPOperation> {
     pub(crate) request: Op::Request,
     pub(crate) validation: ValidationLevel,
     pub(crate) timeout: Option<Duration>,
+    pub(crate) retry: Option<RetryPolicy>,
     pub(crate) metadata: OperationMetadata,
     _phantom: PhantomData<Op>,
 }
@@ -141,6 +168,11 @@ impl<Op: UPnPOperation> ComposableOperation<Op> {
         self.timeout
     }
 
+    /// Get the retry policy for this operation, if retries are enabled
+    pub fn retry(&self) -> Option<&RetryPolicy> {
+        self.retry.as_ref()
+    }
+
     /// Get the operation metadata
     pub fn metadata(&self) -> &OperationMetadata {
         &self.metadata
@@ -154,6 +186,32 @@ impl<Op: UPnPOperation> ComposableOperation<Op> {
         Op::build_payload(&self.request)
     }
 
+    /// The action's arguments as (name, value) pairs, in payload order
+    ///
+    /// Values are unescaped, so they can be passed straight to
+    /// [`SonosClient::call_raw()`](crate::SonosClient::call_raw).
+    pub fn arguments(&self) -> Result<Vec<(String, String)>, ValidationError> {
+        let payload = self.build_payload()?;
+        let wrapped = format!("<Arguments>{payload}</Arguments>");
+        let root =
+            xmltree::Element::parse(wrapped.as_bytes()).map_err(|e| ValidationError::Custom {
+                parameter: Op::ACTION.to_string(),
+                message: format!("unparseable payload: {e}"),
+            })?;
+        Ok(root
+            .children
+            .iter()
+            .filter_map(|node| node.as_element())
+            .map(|element| {
+                let value = element
+                    .get_text()
+                    .map(|t| t.into_owned())
+                    .unwrap_or_default();
+                (element.name.clone(), value)
+            })
+            .collect())
+    }
+
     /// Parse a response for this operation
     ///
     /// # Arguments
@@ -176,6 +234,7 @@ impl<Op: UPnPOperation> std::fmt::Debug for ComposableOperation<Op> {
             .field("action", &self.metadata.action)
             .field("validation", &self.validation)
             .field("timeout", &self.timeout)
+            .field("retry", &self.retry)
             .finish()
     }
 }
@@ -189,6 +248,7 @@ where
             request: self.request.clone(),
             validation: self.validation,
             timeout: self.timeout,
+            retry: self.retry.clone(),
             metadata: self.metadata.clone(),
             _phantom: PhantomData,
         }
@@ -272,6 +332,20 @@ mod tests {
         assert_eq!(builder.timeout(), Some(Duration::from_secs(30)));
     }
 
+    #[test]
+    fn test_operation_builder_retry() {
+        let request = TestRequest { value: 50 };
+        let builder = OperationBuilder::<TestOperation>::new(request);
+        assert!(builder.retry().is_none());
+
+        let operation = builder
+            .with_retry(RetryPolicy::default().with_max_attempts(5))
+            .build()
+            .expect("Should build successfully");
+        assert_eq!(operation.retry().map(|policy| policy.max_attempts), Some(5));
+        assert_eq!(operation.clone().retry(), operation.retry());
+    }
+
     #[test]
     fn test_operation_builder_build_success() {
         let request = TestRequest { value: 50 };
@@ -342,4 +416,19 @@ mod tests {
         assert_eq!(operation.request().value, cloned.request().value);
         assert_eq!(operation.validation_level(), cloned.validation_level());
     }
+
+    #[test]
+    fn test_composable_operation_arguments_are_unescaped() {
+        let operation = crate::services::av_transport::set_av_transport_uri(
+            "x-rincon:RINCON_1".to_string(),
+            "<DIDL-Lite/>".to_string(),
+        )
+        .build()
+        .unwrap();
+
+        let arguments = operation.arguments().unwrap();
+        let names: Vec<&str> = arguments.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["InstanceID", "CurrentURI", "CurrentURIMetaData"]);
+        assert_eq!(arguments[2].1, "<DIDL-Lite/>");
+    }
 }
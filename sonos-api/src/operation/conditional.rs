@@ -0,0 +1,177 @@
+//! An operation sent only when a probe of the device's state allows it
+//!
+//! See [`SonosClient::execute_conditional()`](crate::SonosClient::execute_conditional).
+
+use std::fmt;
+
+use super::{ComposableOperation, UPnPOperation};
+use crate::error::ApiError;
+use crate::SonosClient;
+
+/// A probe operation, a predicate on its response and a target operation
+/// sent only when the predicate holds
+///
+/// The probe and the target are two requests to the same device, one right
+/// after the other. A change made by another controller between them isn't
+/// seen; the device has no way to make the pair atomic.
+///
+/// ```rust,ignore
+/// use sonos_api::operation::Conditional;
+/// use sonos_api::services::av_transport::{get_transport_info, pause};
+///
+/// // Pause only if currently playing
+/// let pause_if_playing = Conditional::new(
+///     get_transport_info().build()?,
+///     |info| info.current_transport_state == "PLAYING",
+///     pause().build()?,
+/// );
+/// if client.execute_conditional(ip, pause_if_playing)?.is_skipped() {
+///     println!("not playing");
+/// }
+/// ```
+pub struct Conditional<P: UPnPOperation, T: UPnPOperation, F> {
+    probe: ComposableOperation<P>,
+    predicate: F,
+    target: ComposableOperation<T>,
+}
+
+impl<P, T, F> Conditional<P, T, F>
+where
+    P: UPnPOperation,
+    T: UPnPOperation,
+    F: FnOnce(&P::Response) -> bool,
+{
+    pub fn new(
+        probe: ComposableOperation<P>,
+        predicate: F,
+        target: ComposableOperation<T>,
+    ) -> Self {
+        Self {
+            probe,
+            predicate,
+            target,
+        }
+    }
+
+    pub(crate) fn run(
+        self,
+        client: &SonosClient,
+        ip: &str,
+    ) -> Result<ConditionalResult<P::Response, T::Response>, ApiError> {
+        let probe = client.execute_enhanced(ip, self.probe)?;
+        if !(self.predicate)(&probe) {
+            return Ok(ConditionalResult::Skipped { probe });
+        }
+        let response = client.execute_enhanced(ip, self.target)?;
+        Ok(ConditionalResult::Executed { probe, response })
+    }
+}
+
+impl<P: UPnPOperation, T: UPnPOperation, F> fmt::Debug for Conditional<P, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Conditional")
+            .field("probe", &P::ACTION)
+            .field("target", &T::ACTION)
+            .finish()
+    }
+}
+
+/// Outcome of [`SonosClient::execute_conditional()`](crate::SonosClient::execute_conditional)
+/// whose probe succeeded
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionalResult<P, T> {
+    /// The predicate held and the target was sent
+    Executed { probe: P, response: T },
+    /// The predicate didn't hold; the target wasn't sent
+    Skipped { probe: P },
+}
+
+impl<P, T> ConditionalResult<P, T> {
+    pub fn is_executed(&self) -> bool {
+        matches!(self, Self::Executed { .. })
+    }
+
+    pub fn is_skipped(&self) -> bool {
+        matches!(self, Self::Skipped { .. })
+    }
+
+    /// Response of the probe the decision was made on
+    pub fn probe(&self) -> &P {
+        match self {
+            Self::Executed { probe, .. } | Self::Skipped { probe } => probe,
+        }
+    }
+
+    /// Response of the target, if it was sent
+    pub fn response(&self) -> Option<&T> {
+        match self {
+            Self::Executed { response, .. } => Some(response),
+            Self::Skipped { .. } => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDevice, Scenario};
+    use crate::services::av_transport::{
+        get_transport_info, pause, GetTransportInfoOperation, GetTransportInfoResponse,
+        PauseOperation,
+    };
+    use crate::services::rendering_control::{get_mute, set_mute};
+
+    fn pause_if_playing() -> Conditional<
+        GetTransportInfoOperation,
+        PauseOperation,
+        impl FnOnce(&GetTransportInfoResponse) -> bool,
+    > {
+        Conditional::new(
+            get_transport_info().build().unwrap(),
+            |info| info.current_transport_state == "PLAYING",
+            pause().build().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_conditional_executes_when_predicate_holds() {
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().with_mute(true));
+        let result = SonosClient::new()
+            .execute_conditional(
+                &device.addr().to_string(),
+                Conditional::new(
+                    get_mute("Master".to_string()).build().unwrap(),
+                    |mute| mute.current_mute,
+                    set_mute("Master".to_string(), false).build().unwrap(),
+                ),
+            )
+            .unwrap();
+        assert!(result.is_executed());
+        assert!(result.probe().current_mute);
+        assert_eq!(result.response(), Some(&()));
+        assert_eq!(device.actions(), ["GetMute", "SetMute"]);
+    }
+
+    #[test]
+    fn test_conditional_skips_when_predicate_fails() {
+        let device = MockDevice::start(
+            "127.0.0.1:0",
+            Scenario::new().with_transport_state("STOPPED"),
+        );
+        let result = SonosClient::new()
+            .execute_conditional(&device.addr().to_string(), pause_if_playing())
+            .unwrap();
+        assert!(result.is_skipped());
+        assert_eq!(result.probe().current_transport_state, "STOPPED");
+        assert_eq!(device.actions(), ["GetTransportInfo"]);
+    }
+
+    #[test]
+    fn test_conditional_probe_failure_sends_nothing_else() {
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().status(500, 1));
+        let result =
+            SonosClient::new().execute_conditional(&device.addr().to_string(), pause_if_playing());
+        assert!(result.is_err());
+        assert_eq!(device.requests(), 1);
+    }
+}
@@ -101,6 +101,14 @@ macro_rules! define_upnp_operation {
 ///     },
 /// }
 /// ```
+///
+/// Fields are read with [`opt_child_text`](crate::operation::opt_child_text):
+/// plain fields default when the element is missing, `Option` fields are
+/// `None`. Append `=> non_empty` to a mapping to also treat an empty element
+/// as `None`.
+///
+/// An optional `dependencies: ["SnapshotGroupVolume"]` after `xml_mapping`
+/// lists actions a [`Sequence`](crate::operation::Sequence) runs first.
 #[macro_export]
 macro_rules! define_operation_with_response {
     (
@@ -114,8 +122,9 @@ macro_rules! define_operation_with_response {
             $($resp_field:ident: $resp_type:ty),* $(,)?
         },
         xml_mapping: {
-            $($xml_field:ident: $xml_path:literal),* $(,)?
-        } $(,)?
+            $($xml_field:ident: $xml_path:literal $(=> $filter:ident)?),* $(,)?
+        }
+        $(, dependencies: [$($dependency:literal),* $(,)?])? $(,)?
     ) => {
         paste! {
             #[derive(serde::Serialize, Clone, Debug, PartialEq)]
@@ -169,16 +178,23 @@ macro_rules! define_operation_with_response {
 
                 fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, $crate::error::ApiError> {
                     // Create a temporary mapping from field names to XML paths
-                    $(let $xml_field = xml
-                        .get_child($xml_path)
-                        .and_then(|e| e.get_text())
-                        .and_then(|s| s.parse().ok())
-                        .unwrap_or_default();)*
+                    $(
+                        let text = $crate::operation::opt_child_text(xml, $xml_path);
+                        $(let text = $crate::operation::$filter(text);)?
+                        let $xml_field =
+                            $crate::operation::FromResponseField::from_response_field(text);
+                    )*
 
                     Ok($response_struct {
                         $($resp_field: $xml_field,)*
                     })
                 }
+
+                $(
+                    fn dependencies() -> &'static [&'static str] {
+                        &[$($dependency),*]
+                    }
+                )?
             }
 
             // Generate convenience function
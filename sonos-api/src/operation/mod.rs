@@ -6,10 +6,16 @@
 //! - Fluent builder pattern for operation construction
 //! - Strong type safety with minimal boilerplate
 
+mod batch;
 mod builder;
+mod conditional;
 pub mod macros;
+mod sequence;
 
+pub use batch::{BatchItem, BatchResult};
 pub use builder::*;
+pub use conditional::{Conditional, ConditionalResult};
+pub use sequence::{Sequence, SequenceResponses, SequenceResult, SequenceStepId};
 
 // Legacy SonosOperation trait for backward compatibility
 use serde::{Deserialize, Serialize};
@@ -241,15 +247,82 @@ pub struct OperationMetadata {
 ///
 /// Returns `false` if the child element is missing or empty.
 pub fn parse_sonos_bool(xml: &Element, child_name: &str) -> bool {
-    xml.get_child(child_name)
-        .and_then(|e| e.get_text())
+    opt_child_text(xml, child_name)
         .map(|s| s.trim() == "1" || s.trim().eq_ignore_ascii_case("true"))
         .unwrap_or(false)
 }
 
+/// Read the text of an optional child element of a response.
+///
+/// This is the one place response parsers look at child text, so they all
+/// agree on what "optional" means:
+///
+/// - a missing element is `None` (older firmware omits fields it doesn't know)
+/// - a present element is `Some(text)`, and an empty one (`<X></X>` or `<X/>`)
+///   is `Some("")`
+///
+/// A field whose empty value carries no information (DIDL-Lite metadata, for
+/// instance) collapses it to `None` with [`non_empty`], spelled
+/// `=> non_empty` in a `define_operation_with_response!` `xml_mapping`. Each
+/// response type documents which of its fields do so.
+pub fn opt_child_text(xml: &Element, child_name: &str) -> Option<String> {
+    xml.get_child(child_name)
+        .map(|e| e.get_text().map(|t| t.into_owned()).unwrap_or_default())
+}
+
+/// Treat an empty optional field as absent.
+pub fn non_empty(text: Option<String>) -> Option<String> {
+    text.filter(|s| !s.is_empty())
+}
+
+/// Conversion from an optional child's text (see [`opt_child_text`]) into a
+/// response field, used by `define_operation_with_response!`.
+///
+/// Plain fields stay lenient: a missing or unparsable value becomes the type's
+/// default. `Option` fields keep `None` for a missing element, so callers can
+/// tell an omitted field from a zero or empty one.
+pub trait FromResponseField: Sized {
+    fn from_response_field(text: Option<String>) -> Self;
+}
+
+impl FromResponseField for String {
+    fn from_response_field(text: Option<String>) -> Self {
+        text.unwrap_or_default()
+    }
+}
+
+impl FromResponseField for Option<String> {
+    fn from_response_field(text: Option<String>) -> Self {
+        text
+    }
+}
+
+macro_rules! impl_numeric_response_field {
+    ($($ty:ty),*) => {
+        $(
+            impl FromResponseField for $ty {
+                fn from_response_field(text: Option<String>) -> Self {
+                    text.and_then(|s| s.parse().ok()).unwrap_or_default()
+                }
+            }
+
+            impl FromResponseField for Option<$ty> {
+                fn from_response_field(text: Option<String>) -> Self {
+                    text.and_then(|s| s.parse().ok())
+                }
+            }
+        )*
+    };
+}
+
+impl_numeric_response_field!(u8, u16, u32, u64, i8, i16, i32, i64);
+
 /// Escape XML special characters in a string for safe SOAP payload interpolation.
 ///
-/// Replaces `&`, `<`, `>`, `"`, and `'` with their XML entity equivalents.
+/// Replaces `&`, `<`, `>`, `"`, and `'` with their XML entity equivalents, so
+/// the result is safe as element text and as a quoted attribute value.
+/// Other characters, non-ASCII included, pass through; payloads are sent as
+/// UTF-8.
 pub fn xml_escape(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     for c in s.chars() {
@@ -325,6 +398,25 @@ mod tests {
         assert!(negative_request.validate(ValidationLevel::Basic).is_err());
     }
 
+    #[test]
+    fn test_opt_child_text_distinguishes_missing_from_empty() {
+        let xml =
+            Element::parse("<R><Full>a &amp; b</Full><Empty></Empty><SelfClosed/></R>".as_bytes())
+                .unwrap();
+        assert_eq!(opt_child_text(&xml, "Full").as_deref(), Some("a & b"));
+        assert_eq!(opt_child_text(&xml, "Empty").as_deref(), Some(""));
+        assert_eq!(opt_child_text(&xml, "SelfClosed").as_deref(), Some(""));
+        assert_eq!(opt_child_text(&xml, "Missing"), None);
+        assert_eq!(non_empty(opt_child_text(&xml, "Empty")), None);
+
+        assert_eq!(u32::from_response_field(None), 0);
+        assert_eq!(Option::<u32>::from_response_field(None), None);
+        assert_eq!(
+            Option::<i32>::from_response_field(Some("-1".into())),
+            Some(-1)
+        );
+    }
+
     #[test]
     fn test_xml_escape() {
         assert_eq!(xml_escape("hello"), "hello");
@@ -337,5 +429,10 @@ mod tests {
             "&lt;/CurrentURI&gt;&lt;Injected&gt;"
         );
         assert_eq!(xml_escape(""), "");
+        assert_eq!(xml_escape("Café — 東京"), "Café — 東京");
+        assert_eq!(
+            xml_escape(r#"<a title="x">"#),
+            "&lt;a title=&quot;x&quot;&gt;"
+        );
     }
 }
@@ -0,0 +1,350 @@
+//! Operations sent to one device in order, stopping at the first failure
+//!
+//! See [`SonosClient::execute_sequence()`](crate::SonosClient::execute_sequence).
+
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+
+use super::{ComposableOperation, UPnPOperation};
+use crate::error::ApiError;
+use crate::SonosClient;
+
+type RunStep = Box<dyn FnOnce(&SonosClient, &str) -> Result<Box<dyn Any + Send>, ApiError> + Send>;
+
+struct SequenceStep {
+    action: &'static str,
+    dependencies: &'static [&'static str],
+    run: RunStep,
+}
+
+/// Operations for one device, run in the order they were added except
+/// where an operation's [`dependencies()`](UPnPOperation::dependencies)
+/// need another first
+///
+/// A dependency on an action the sequence doesn't contain is ignored.
+///
+/// ```rust,ignore
+/// use sonos_api::operation::Sequence;
+/// use sonos_api::services::group_rendering_control::{set_relative_group_volume, snapshot_group_volume};
+///
+/// let mut sequence = Sequence::new();
+/// // Runs second: it depends on SnapshotGroupVolume
+/// let louder = sequence.push(set_relative_group_volume(5).build()?);
+/// sequence.push(snapshot_group_volume().build()?);
+/// let responses = client.execute_sequence("192.168.1.100", sequence).into_result()?;
+/// println!("group volume is now {}", responses.get(louder).unwrap().new_volume);
+/// ```
+#[derive(Default)]
+pub struct Sequence {
+    steps: Vec<SequenceStep>,
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `operation`, for when its response isn't needed
+    pub fn then<Op>(mut self, operation: ComposableOperation<Op>) -> Self
+    where
+        Op: UPnPOperation + 'static,
+        ComposableOperation<Op>: Send,
+        Op::Response: Send,
+    {
+        self.push(operation);
+        self
+    }
+
+    /// Add `operation`; the returned step reads its response from the result
+    pub fn push<Op>(&mut self, operation: ComposableOperation<Op>) -> SequenceStepId<Op>
+    where
+        Op: UPnPOperation + 'static,
+        ComposableOperation<Op>: Send,
+        Op::Response: Send,
+    {
+        let index = self.steps.len();
+        self.steps.push(SequenceStep {
+            action: Op::ACTION,
+            dependencies: Op::dependencies(),
+            run: Box::new(move |client, ip| {
+                let response = client.execute_enhanced(ip, operation)?;
+                Ok(Box::new(response) as Box<dyn Any + Send>)
+            }),
+        });
+        SequenceStepId {
+            index,
+            _op: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Indices of the steps in the order they run: the earliest added step
+    /// whose dependencies have run goes next
+    ///
+    /// Fails with the index of a step on a dependency cycle.
+    pub fn order(&self) -> Result<Vec<usize>, (usize, ApiError)> {
+        let mut done = vec![false; self.steps.len()];
+        let mut order = Vec::with_capacity(self.steps.len());
+        while order.len() < self.steps.len() {
+            let ready = (0..self.steps.len()).find(|&index| {
+                !done[index]
+                    && self.steps[index].dependencies.iter().all(|dependency| {
+                        self.steps
+                            .iter()
+                            .zip(&done)
+                            .all(|(step, done)| *done || step.action != *dependency)
+                    })
+            });
+            let Some(index) = ready else {
+                let index = done.iter().position(|done| !done).unwrap_or_default();
+                let step = &self.steps[index];
+                return Err((
+                    index,
+                    ApiError::InvalidParameter(format!(
+                        "{} depends on {:?}, which depend on it",
+                        step.action, step.dependencies
+                    )),
+                ));
+            };
+            done[index] = true;
+            order.push(index);
+        }
+        Ok(order)
+    }
+
+    pub(crate) fn run(self, client: &SonosClient, ip: &str) -> SequenceResult {
+        let actions: Vec<_> = self.steps.iter().map(|step| step.action).collect();
+        let mut completed = SequenceResponses {
+            responses: actions.iter().map(|_| None).collect(),
+            actions,
+        };
+        let order = match self.order() {
+            Ok(order) => order,
+            Err((index, error)) => {
+                return SequenceResult::Aborted {
+                    index,
+                    action: completed.actions[index],
+                    error,
+                    completed,
+                }
+            }
+        };
+        let mut steps: Vec<_> = self.steps.into_iter().map(Some).collect();
+        for index in order {
+            let Some(step) = steps[index].take() else {
+                continue;
+            };
+            match (step.run)(client, ip) {
+                Ok(response) => completed.responses[index] = Some(response),
+                Err(error) => {
+                    return SequenceResult::Aborted {
+                        index,
+                        action: step.action,
+                        error,
+                        completed,
+                    }
+                }
+            }
+        }
+        SequenceResult::Success(completed)
+    }
+}
+
+impl fmt::Debug for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.steps.iter().map(|step| step.action))
+            .finish()
+    }
+}
+
+/// A step added with [`Sequence::push()`], for reading its typed response
+pub struct SequenceStepId<Op> {
+    index: usize,
+    _op: PhantomData<fn() -> Op>,
+}
+
+impl<Op> SequenceStepId<Op> {
+    /// Position of the step in the order it was added
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<Op> Clone for SequenceStepId<Op> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Op> Copy for SequenceStepId<Op> {}
+
+impl<Op> fmt::Debug for SequenceStepId<Op> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SequenceStepId").field(&self.index).finish()
+    }
+}
+
+/// Responses of the steps of a sequence that ran
+pub struct SequenceResponses {
+    actions: Vec<&'static str>,
+    responses: Vec<Option<Box<dyn Any + Send>>>,
+}
+
+impl SequenceResponses {
+    /// Response of `step`, if it ran
+    pub fn get<Op: UPnPOperation + 'static>(
+        &self,
+        step: SequenceStepId<Op>,
+    ) -> Option<&Op::Response> {
+        self.responses
+            .get(step.index)?
+            .as_ref()?
+            .downcast_ref::<Op::Response>()
+    }
+
+    /// Take the response of `step` out, if it ran
+    pub fn take<Op: UPnPOperation + 'static>(
+        &mut self,
+        step: SequenceStepId<Op>,
+    ) -> Option<Op::Response> {
+        let response = self.responses.get_mut(step.index)?.take()?;
+        response
+            .downcast::<Op::Response>()
+            .ok()
+            .map(|response| *response)
+    }
+
+    /// Whether the step at `index` ran
+    pub fn ran(&self, index: usize) -> bool {
+        self.responses.get(index).is_some_and(Option::is_some)
+    }
+
+    /// Number of steps that ran
+    pub fn len(&self) -> usize {
+        self.responses.iter().filter(|r| r.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for SequenceResponses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.actions
+                    .iter()
+                    .zip(&self.responses)
+                    .filter(|(_, response)| response.is_some())
+                    .map(|(action, _)| action),
+            )
+            .finish()
+    }
+}
+
+/// Outcome of [`SonosClient::execute_sequence()`](crate::SonosClient::execute_sequence)
+#[derive(Debug)]
+pub enum SequenceResult {
+    /// Every step succeeded
+    Success(SequenceResponses),
+    /// A step failed and the steps after it weren't sent
+    Aborted {
+        /// Position of the failed step in the order it was added
+        index: usize,
+        action: &'static str,
+        error: ApiError,
+        /// Responses of the steps that ran before it
+        completed: SequenceResponses,
+    },
+}
+
+impl SequenceResult {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success(_))
+    }
+
+    /// Responses of the steps that ran
+    pub fn responses(&self) -> &SequenceResponses {
+        match self {
+            Self::Success(responses) => responses,
+            Self::Aborted { completed, .. } => completed,
+        }
+    }
+
+    /// The responses, or the error that aborted the sequence
+    pub fn into_result(self) -> Result<SequenceResponses, ApiError> {
+        match self {
+            Self::Success(responses) => Ok(responses),
+            Self::Aborted { error, .. } => Err(error),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDevice, Scenario};
+    use crate::services::av_transport::{play, set_av_transport_uri, stop};
+    use crate::services::group_rendering_control::{
+        set_relative_group_volume, snapshot_group_volume,
+    };
+
+    #[test]
+    fn test_sequence_runs_dependencies_first() {
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().with_group_volume(20));
+        let mut sequence = Sequence::new();
+        let louder = sequence.push(set_relative_group_volume(5).build().unwrap());
+        let snapshot = sequence.push(snapshot_group_volume().build().unwrap());
+        assert_eq!(sequence.order().unwrap(), [1, 0]);
+
+        let result = SonosClient::new().execute_sequence(&device.addr().to_string(), sequence);
+        assert!(result.is_success());
+        assert_eq!(
+            device.actions(),
+            ["SnapshotGroupVolume", "SetRelativeGroupVolume"]
+        );
+        let mut responses = result.into_result().unwrap();
+        assert_eq!(responses.get(louder).unwrap().new_volume, 25);
+        assert_eq!(responses.take(snapshot), Some(()));
+        assert!(responses.get(snapshot).is_none());
+    }
+
+    #[test]
+    fn test_sequence_aborts_at_first_failure() {
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().respond(1).status(500, 1));
+        let sequence = Sequence::new()
+            .then(stop().build().unwrap())
+            .then(
+                set_av_transport_uri("x-rincon-mp3radio://a".to_string(), String::new())
+                    .build()
+                    .unwrap(),
+            )
+            .then(play("1".to_string()).build().unwrap());
+
+        match SonosClient::new().execute_sequence(&device.addr().to_string(), sequence) {
+            SequenceResult::Aborted {
+                index,
+                action,
+                completed,
+                ..
+            } => {
+                assert_eq!((index, action), (1, "SetAVTransportURI"));
+                assert!(completed.ran(0));
+                assert_eq!(completed.len(), 1);
+            }
+            SequenceResult::Success(_) => panic!("sequence should abort"),
+        }
+        // Play was never sent
+        assert_eq!(device.requests(), 2);
+    }
+}
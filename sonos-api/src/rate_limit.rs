@@ -0,0 +1,669 @@
+//! Rate limiting of the writes sent to speakers
+//!
+//! A [`WriteLimiter`] meters writes with token buckets: one for the whole
+//! household and one per device, each refilling at a sustained rate up to a
+//! burst size. A write takes a token from every configured bucket. Buckets
+//! hand out tokens in arrival order, so writes queued for one device go out
+//! in the order they were made, spaced by its rate, rather than racing each
+//! other for the next token.
+//!
+//! What happens to a write that finds a bucket empty is the
+//! [`ExceedPolicy`]'s choice: wait for its token, fail with
+//! [`ApiError::RateLimited`], or merge with a write to the same target that
+//! is already waiting.
+//!
+//! Only writes go through the limiter; reads and subscriptions are never
+//! delayed. Every clone of a [`SonosClient`](crate::SonosClient) shares one
+//! limiter, which measures time on the client's clock.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use xmltree::Element;
+
+use crate::clock::SharedClock;
+use crate::{ApiError, Result, Service};
+
+/// Sustained rate and burst size of a token bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    per_second: f64,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// `per_second` writes sustained, and up to `burst` at once after a
+    /// quiet spell
+    ///
+    /// # Panics
+    /// If `per_second` isn't positive and finite, or `burst` is 0.
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        assert!(
+            per_second.is_finite() && per_second > 0.0,
+            "rate must be positive, got {per_second}"
+        );
+        assert!(burst > 0, "burst must be at least 1");
+        Self { per_second, burst }
+    }
+
+    pub fn per_second(&self) -> f64 {
+        self.per_second
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Time for `tokens` tokens to refill
+    fn refill_time(&self, tokens: f64) -> Duration {
+        Duration::from_secs_f64(tokens.max(0.0) / self.per_second)
+    }
+}
+
+/// What a write does when a bucket has no token for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceedPolicy {
+    /// Wait for its token, in arrival order; fails with
+    /// [`ApiError::RateLimited`] instead if that would take longer than
+    /// `max_wait`
+    Queue { max_wait: Duration },
+    /// Fail with [`ApiError::RateLimited`] at once
+    Reject,
+    /// Like `Queue`, but a `Set` action (not a relative one) replaces the
+    /// value of the same action on the same target still waiting for its
+    /// token, instead of queueing behind it: one request goes out, with the
+    /// latest value, and every caller gets its answer
+    ///
+    /// The target is the device, the service and the arguments other than
+    /// the value (those named `Desired*` or `New*`), e.g. the channel of a
+    /// `SetVolume`.
+    Coalesce { max_wait: Duration },
+}
+
+/// Limits to apply to writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WriteRateLimit {
+    global: Option<RateLimit>,
+    per_device: Option<RateLimit>,
+    policy: ExceedPolicy,
+}
+
+impl WriteRateLimit {
+    /// No limit yet, handling writes over a limit by `policy`
+    pub fn new(policy: ExceedPolicy) -> Self {
+        Self {
+            global: None,
+            per_device: None,
+            policy,
+        }
+    }
+
+    /// Limit writes to all devices together
+    pub fn global(mut self, limit: RateLimit) -> Self {
+        self.global = Some(limit);
+        self
+    }
+
+    /// Limit writes to each device
+    pub fn per_device(mut self, limit: RateLimit) -> Self {
+        self.per_device = Some(limit);
+        self
+    }
+
+    pub fn policy(&self) -> ExceedPolicy {
+        self.policy
+    }
+}
+
+/// Tokens left in each bucket, for diagnostics
+///
+/// Negative while writes are queued: `-2.0` means two writes are waiting
+/// for tokens beyond the one being refilled.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenLevels {
+    /// The household-wide bucket, when there is a global limit
+    pub global: Option<f64>,
+    /// By device address, for devices written to since the limit was set
+    pub devices: BTreeMap<String, f64>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            updated: now,
+        }
+    }
+
+    fn level(&self, limit: &RateLimit, now: Instant) -> f64 {
+        let refilled = now.saturating_duration_since(self.updated).as_secs_f64() * limit.per_second;
+        (self.tokens + refilled).min(f64::from(limit.burst))
+    }
+
+    /// How long until the next token is free
+    fn wait(&self, limit: &RateLimit, now: Instant) -> Duration {
+        limit.refill_time(1.0 - self.level(limit, now))
+    }
+
+    /// Take the next token, free or not
+    fn take(&mut self, limit: &RateLimit, now: Instant) {
+        self.tokens = self.level(limit, now) - 1.0;
+        self.updated = now;
+    }
+}
+
+/// Unescaped (name, value) arguments of a write, in wire order
+type Args = Vec<(String, String)>;
+
+/// A coalescable write's device, service, action and non-value arguments
+type Target = (String, Service, String, Args);
+
+/// A queued write later writes to the same target merge into
+#[derive(Debug)]
+struct Pending {
+    /// Arguments of the latest write merged in
+    args: Args,
+    slot: Arc<Slot>,
+}
+
+/// Where the write that goes out leaves its answer, and the arguments it
+/// sent, for the writes merged into it
+#[derive(Debug, Default)]
+struct Slot {
+    sent: Mutex<Option<(Result<Element>, Args)>>,
+    ready: Condvar,
+}
+
+/// Answer to a write sent through a [`WriteLimiter`]
+#[derive(Debug, Clone)]
+pub struct LimitedWrite {
+    pub response: Element,
+    /// A later write to the same target merged into this one, so the
+    /// speaker got that write's value instead of this one's
+    pub superseded: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    limit: Option<WriteRateLimit>,
+    global: Option<Bucket>,
+    devices: HashMap<String, Bucket>,
+    pending: HashMap<Target, Pending>,
+}
+
+/// Token-bucket limiter for writes; see the [module docs](self)
+#[derive(Debug)]
+pub struct WriteLimiter {
+    clock: SharedClock,
+    state: Mutex<State>,
+}
+
+impl WriteLimiter {
+    /// A limiter with no limit, measuring time on `clock`
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            clock,
+            state: Mutex::default(),
+        }
+    }
+
+    /// A limiter with this one's limit, measuring time on `clock`
+    pub(crate) fn with_clock(&self, clock: SharedClock) -> Self {
+        let limiter = Self::new(clock);
+        limiter.set_limit(self.state().limit);
+        limiter
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Apply `limit` to writes from now on, with full buckets; `None` lifts
+    /// the limit
+    ///
+    /// Writes already waiting keep their place.
+    pub fn set_limit(&self, limit: Option<WriteRateLimit>) {
+        let now = self.clock.now();
+        let mut state = self.state();
+        state.global = limit
+            .and_then(|limit| limit.global)
+            .map(|global| Bucket::full(&global, now));
+        state.devices.clear();
+        state.limit = limit;
+    }
+
+    pub fn limit(&self) -> Option<WriteRateLimit> {
+        self.state().limit
+    }
+
+    /// Whether writes are limited at all
+    pub fn is_enabled(&self) -> bool {
+        self.state()
+            .limit
+            .is_some_and(|limit| limit.global.is_some() || limit.per_device.is_some())
+    }
+
+    /// Tokens left in each bucket now
+    pub fn levels(&self) -> TokenLevels {
+        let now = self.clock.now();
+        let state = self.state();
+        let Some(limit) = state.limit else {
+            return TokenLevels::default();
+        };
+        TokenLevels {
+            global: limit
+                .global
+                .zip(state.global.as_ref())
+                .map(|(global, bucket)| bucket.level(&global, now)),
+            devices: limit
+                .per_device
+                .map(|per_device| {
+                    state
+                        .devices
+                        .iter()
+                        .map(|(device, bucket)| (device.clone(), bucket.level(&per_device, now)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Send a write of `action` with `args` to `device` with `send`, once
+    /// the limit allows
+    ///
+    /// `send` gets the arguments to send: `args`, or under
+    /// [`ExceedPolicy::Coalesce`] those of a later write to the same target
+    /// that merged into this one. Returns [`ApiError::RateLimited`] without
+    /// calling `send` when the policy refuses the write.
+    ///
+    /// Writes merged into another don't call `send`; they return the
+    /// answer to the write that went out.
+    pub fn send(
+        &self,
+        device: &str,
+        service: Service,
+        action: &str,
+        args: Vec<(String, String)>,
+        send: impl FnOnce(&[(String, String)]) -> Result<Element>,
+    ) -> Result<LimitedWrite> {
+        let sent = |response| LimitedWrite {
+            response,
+            superseded: false,
+        };
+        let now = self.clock.now();
+        let mut state = self.state();
+        let Some(limit) = state.limit else {
+            drop(state);
+            return send(&args).map(sent);
+        };
+
+        let target = match limit.policy {
+            ExceedPolicy::Coalesce { .. } if is_idempotent(action) => Some((
+                device.to_string(),
+                service,
+                action.to_string(),
+                args.iter()
+                    .filter(|(name, _)| !is_value(name))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            )),
+            _ => None,
+        };
+        if let Some(pending) = target.as_ref().and_then(|t| state.pending.get_mut(t)) {
+            pending.args = args.clone();
+            let slot = Arc::clone(&pending.slot);
+            drop(state);
+            return slot.wait(&args);
+        }
+
+        let State {
+            global, devices, ..
+        } = &mut *state;
+        let device_bucket = limit.per_device.map(|per_device| {
+            devices
+                .entry(device.to_string())
+                .or_insert_with(|| Bucket::full(&per_device, now))
+        });
+        let wait = [
+            limit.global.zip(global.as_ref()),
+            limit.per_device.zip(device_bucket.as_deref()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|(limit, bucket)| bucket.wait(&limit, now))
+        .max()
+        .unwrap_or_default();
+
+        let max_wait = match limit.policy {
+            ExceedPolicy::Reject => Duration::ZERO,
+            ExceedPolicy::Queue { max_wait } | ExceedPolicy::Coalesce { max_wait } => max_wait,
+        };
+        if wait > max_wait {
+            return Err(ApiError::RateLimited {
+                retry_after: wait - max_wait,
+            });
+        }
+        if let (Some(global_limit), Some(bucket)) = (limit.global, global.as_mut()) {
+            bucket.take(&global_limit, now);
+        }
+        if let (Some(per_device), Some(bucket)) = (limit.per_device, device_bucket) {
+            bucket.take(&per_device, now);
+        }
+
+        // Only a write that has to wait can be merged into
+        let target = target.filter(|_| !wait.is_zero());
+        let slot = target.as_ref().map(|target| {
+            let slot = Arc::new(Slot::default());
+            state.pending.insert(
+                target.clone(),
+                Pending {
+                    args: args.clone(),
+                    slot: Arc::clone(&slot),
+                },
+            );
+            slot
+        });
+        drop(state);
+
+        if !wait.is_zero() {
+            // The reservation was made at `now`; time may have moved since
+            let left = (now + wait).saturating_duration_since(self.clock.now());
+            self.clock.sleep(left);
+        }
+        let (Some(target), Some(slot)) = (target, slot) else {
+            return send(&args).map(sent);
+        };
+        let latest = self
+            .state()
+            .pending
+            .remove(&target)
+            .map_or_else(|| args.clone(), |pending| pending.args);
+        let unwinding = Unfilled {
+            slot: &slot,
+            args: &latest,
+        };
+        let result = send(&latest);
+        std::mem::forget(unwinding);
+        slot.fill(result, latest);
+        slot.wait(&args)
+    }
+}
+
+/// Answers the writes merged into one whose `send` panicked, which
+/// would otherwise wait on its slot forever
+struct Unfilled<'a> {
+    slot: &'a Slot,
+    args: &'a Args,
+}
+
+impl Drop for Unfilled<'_> {
+    fn drop(&mut self) {
+        let error = ApiError::NetworkError("the write they were merged into panicked".to_string());
+        self.slot.fill(Err(error), self.args.clone());
+    }
+}
+
+impl Slot {
+    fn fill(&self, result: Result<Element>, args: Args) {
+        *self.sent.lock().unwrap_or_else(PoisonError::into_inner) = Some((result, args));
+        self.ready.notify_all();
+    }
+
+    /// The answer, once the write has gone out, for a caller that asked
+    /// for `args`
+    fn wait(&self, args: &[(String, String)]) -> Result<LimitedWrite> {
+        let mut sent = self.sent.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some((result, sent_args)) = sent.as_ref() {
+                return result.clone().map(|response| LimitedWrite {
+                    response,
+                    superseded: sent_args.as_slice() != args,
+                });
+            }
+            sent = self
+                .ready
+                .wait(sent)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+/// Whether sending `action` twice leaves the speaker as sending it once
+fn is_idempotent(action: &str) -> bool {
+    action.starts_with("Set") && !action.contains("Relative")
+}
+
+/// Whether argument `name` carries the value a write sets
+fn is_value(name: &str) -> bool {
+    name.starts_with("Desired") || name.starts_with("New")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use std::thread;
+
+    fn limiter(clock: &ManualClock, limit: WriteRateLimit) -> WriteLimiter {
+        let limiter = WriteLimiter::new(Arc::new(clock.clone()));
+        limiter.set_limit(Some(limit));
+        limiter
+    }
+
+    fn volume(value: u8) -> Vec<(String, String)> {
+        vec![
+            ("InstanceID".to_string(), "0".to_string()),
+            ("Channel".to_string(), "Master".to_string()),
+            ("DesiredVolume".to_string(), value.to_string()),
+        ]
+    }
+
+    fn ok(_: &[(String, String)]) -> Result<Element> {
+        Ok(Element::new("SetVolumeResponse"))
+    }
+
+    #[test]
+    fn test_reject_reports_time_to_next_token() {
+        let clock = ManualClock::new();
+        let limiter = limiter(
+            &clock,
+            WriteRateLimit::new(ExceedPolicy::Reject).per_device(RateLimit::new(2.0, 3)),
+        );
+        let send = || limiter.send("den", Service::RenderingControl, "SetVolume", volume(1), ok);
+
+        for _ in 0..3 {
+            send().unwrap();
+        }
+        assert!(matches!(
+            send(),
+            Err(ApiError::RateLimited { retry_after }) if retry_after == Duration::from_millis(500)
+        ));
+        clock.advance(Duration::from_millis(200));
+        assert!(matches!(
+            send(),
+            Err(ApiError::RateLimited { retry_after }) if retry_after == Duration::from_millis(300)
+        ));
+        clock.advance(Duration::from_millis(300));
+        send().unwrap();
+
+        // Another device has its own bucket
+        limiter
+            .send(
+                "hall",
+                Service::RenderingControl,
+                "SetVolume",
+                volume(1),
+                ok,
+            )
+            .unwrap();
+        let levels = limiter.levels();
+        assert_eq!(levels.global, None);
+        assert!(levels.devices["den"].abs() < 1e-9);
+        assert!((levels.devices["hall"] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_global_limit_spans_devices_and_queue_bounds_the_wait() {
+        let clock = ManualClock::new();
+        let limiter = limiter(
+            &clock,
+            WriteRateLimit::new(ExceedPolicy::Queue {
+                max_wait: Duration::from_secs(1),
+            })
+            .global(RateLimit::new(1.0, 1)),
+        );
+        let send = |device: &str| {
+            limiter.send(device, Service::RenderingControl, "SetMute", volume(1), ok)
+        };
+
+        send("den").unwrap();
+        let started = clock.now();
+        thread::scope(|scope| {
+            let queued = scope.spawn(|| send("hall"));
+            while limiter.levels().global.unwrap() > -0.5 {
+                thread::yield_now();
+            }
+            // A second queued write would wait 2s, over the bound
+            assert!(matches!(
+                send("loft"),
+                Err(ApiError::RateLimited { retry_after }) if retry_after == Duration::from_secs(1)
+            ));
+            clock.advance(Duration::from_secs(1));
+            queued.join().unwrap().unwrap();
+        });
+        assert_eq!(clock.now() - started, Duration::from_secs(1));
+        assert!(limiter.levels().global.unwrap().abs() < 1e-9);
+
+        limiter.set_limit(None);
+        assert!(!limiter.is_enabled());
+        for _ in 0..10 {
+            send("den").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_coalesced_writes_send_the_latest_value_once() {
+        let clock = ManualClock::new();
+        let limiter = limiter(
+            &clock,
+            WriteRateLimit::new(ExceedPolicy::Coalesce {
+                max_wait: Duration::from_secs(5),
+            })
+            .per_device(RateLimit::new(1.0, 1)),
+        );
+        let sent = Mutex::new(Vec::new());
+        let send = |value: u8| {
+            limiter.send(
+                "den",
+                Service::RenderingControl,
+                "SetVolume",
+                volume(value),
+                |args: &[(String, String)]| {
+                    sent.lock().unwrap().push(args[2].1.clone());
+                    let mut response = Element::new("SetVolumeResponse");
+                    response
+                        .attributes
+                        .insert("sent".to_string(), args[2].1.clone());
+                    Ok(response)
+                },
+            )
+        };
+
+        send(10).unwrap();
+        let responses: Vec<LimitedWrite> = thread::scope(|scope| {
+            let first = scope.spawn(|| send(20));
+            while limiter.levels().devices["den"] > -0.5 {
+                thread::yield_now();
+            }
+            let merged: Vec<_> = (30..=40)
+                .step_by(5)
+                .map(|value| {
+                    let handle = scope.spawn(move || send(value));
+                    // Merge in order, before the token refills
+                    thread::sleep(Duration::from_millis(20));
+                    handle
+                })
+                .collect();
+            // Relative changes are never merged; this one queues behind
+            let relative = scope.spawn(|| {
+                limiter.send(
+                    "den",
+                    Service::RenderingControl,
+                    "SetRelativeVolume",
+                    volume(1),
+                    ok,
+                )
+            });
+            while limiter.levels().devices["den"] > -1.5 {
+                thread::yield_now();
+            }
+            clock.advance(Duration::from_secs(1));
+            let mut responses = vec![first.join().unwrap().unwrap()];
+            responses.extend(merged.into_iter().map(|h| h.join().unwrap().unwrap()));
+            clock.advance(Duration::from_secs(1));
+            relative.join().unwrap().unwrap();
+            responses
+        });
+
+        assert_eq!(*sent.lock().unwrap(), ["10", "40"]);
+        assert!(responses
+            .iter()
+            .all(|write| write.response.attributes["sent"] == "40"));
+        let superseded: Vec<_> = responses.iter().map(|write| write.superseded).collect();
+        assert_eq!(superseded, [true, true, true, false]);
+    }
+
+    #[test]
+    fn test_panicking_write_releases_merged_writes() {
+        let clock = ManualClock::new();
+        let limiter = limiter(
+            &clock,
+            WriteRateLimit::new(ExceedPolicy::Coalesce {
+                max_wait: Duration::from_secs(5),
+            })
+            .per_device(RateLimit::new(1.0, 1)),
+        );
+        let send = |value: u8| {
+            limiter.send(
+                "den",
+                Service::RenderingControl,
+                "SetVolume",
+                volume(value),
+                |args: &[(String, String)]| -> Result<Element> {
+                    assert_ne!(args[2].1, "30", "transport blew up");
+                    ok(args)
+                },
+            )
+        };
+
+        send(10).unwrap();
+        thread::scope(|scope| {
+            let first = scope.spawn(|| send(20));
+            while limiter.levels().devices["den"] > -0.5 {
+                thread::yield_now();
+            }
+            let merged = scope.spawn(|| send(30));
+            while limiter
+                .state()
+                .pending
+                .values()
+                .all(|p| p.args[2].1 != "30")
+            {
+                thread::yield_now();
+            }
+            clock.advance(Duration::from_secs(1));
+            assert!(first.join().is_err());
+            assert!(matches!(
+                merged.join().unwrap(),
+                Err(ApiError::NetworkError(_))
+            ));
+        });
+    }
+}
@@ -1,8 +1,10 @@
+use serde::{Deserialize, Serialize};
+
 /// Represents the different UPnP services exposed by Sonos devices
 ///
 /// Each service provides a specific set of operations for controlling different
 /// aspects of the Sonos device functionality.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Service {
     /// AVTransport service - Controls playback (play, pause, stop, seek, etc.)
     AVTransport,
@@ -18,6 +20,18 @@ pub enum Service {
 
     /// GroupManagement service - Manages speaker group membership operations
     GroupManagement,
+
+    /// DeviceProperties service - Per-device settings such as the button lock
+    DeviceProperties,
+
+    /// ConnectionManager service - Media formats the renderer accepts
+    ConnectionManager,
+
+    /// AlarmClock service - Household-wide alarms
+    AlarmClock,
+
+    /// ContentDirectory service - Browsing the queue and other containers
+    ContentDirectory,
 }
 
 /// Contains the endpoint and service URI information for a UPnP service
@@ -56,6 +70,10 @@ impl Service {
             Service::GroupRenderingControl => "GroupRenderingControl",
             Service::ZoneGroupTopology => "ZoneGroupTopology",
             Service::GroupManagement => "GroupManagement",
+            Service::DeviceProperties => "DeviceProperties",
+            Service::ConnectionManager => "ConnectionManager",
+            Service::AlarmClock => "AlarmClock",
+            Service::ContentDirectory => "ContentDirectory",
         }
     }
 
@@ -90,6 +108,26 @@ impl Service {
                 service_uri: "urn:schemas-upnp-org:service:GroupManagement:1",
                 event_endpoint: "GroupManagement/Event",
             },
+            Service::DeviceProperties => ServiceInfo {
+                endpoint: "DeviceProperties/Control",
+                service_uri: "urn:schemas-upnp-org:service:DeviceProperties:1",
+                event_endpoint: "DeviceProperties/Event",
+            },
+            Service::ConnectionManager => ServiceInfo {
+                endpoint: "MediaRenderer/ConnectionManager/Control",
+                service_uri: "urn:schemas-upnp-org:service:ConnectionManager:1",
+                event_endpoint: "MediaRenderer/ConnectionManager/Event",
+            },
+            Service::AlarmClock => ServiceInfo {
+                endpoint: "AlarmClock/Control",
+                service_uri: "urn:schemas-upnp-org:service:AlarmClock:1",
+                event_endpoint: "AlarmClock/Event",
+            },
+            Service::ContentDirectory => ServiceInfo {
+                endpoint: "MediaServer/ContentDirectory/Control",
+                service_uri: "urn:schemas-upnp-org:service:ContentDirectory:1",
+                event_endpoint: "MediaServer/ContentDirectory/Event",
+            },
         }
     }
 
@@ -105,6 +143,10 @@ impl Service {
             Service::GroupRenderingControl => ServiceScope::PerCoordinator,
             Service::ZoneGroupTopology => ServiceScope::PerNetwork,
             Service::GroupManagement => ServiceScope::PerCoordinator,
+            Service::DeviceProperties => ServiceScope::PerSpeaker,
+            Service::ConnectionManager => ServiceScope::PerSpeaker,
+            Service::AlarmClock => ServiceScope::PerNetwork,
+            Service::ContentDirectory => ServiceScope::PerSpeaker,
         }
     }
 }
@@ -126,6 +168,10 @@ mod scope_tests {
             Service::GroupManagement.scope(),
             ServiceScope::PerCoordinator
         );
+        assert_eq!(Service::DeviceProperties.scope(), ServiceScope::PerSpeaker);
+        assert_eq!(Service::ConnectionManager.scope(), ServiceScope::PerSpeaker);
+        assert_eq!(Service::AlarmClock.scope(), ServiceScope::PerNetwork);
+        assert_eq!(Service::ContentDirectory.scope(), ServiceScope::PerSpeaker);
     }
 
     #[test]
@@ -137,6 +183,10 @@ mod scope_tests {
             Service::GroupRenderingControl,
             Service::ZoneGroupTopology,
             Service::GroupManagement,
+            Service::DeviceProperties,
+            Service::ConnectionManager,
+            Service::AlarmClock,
+            Service::ContentDirectory,
         ];
 
         for service in services {
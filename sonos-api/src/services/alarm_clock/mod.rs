@@ -0,0 +1,30 @@
+//! AlarmClock service for the household's alarms
+//!
+//! Alarms are stored household-wide, so any speaker answers `ListAlarms`
+//! with every alarm, whichever room it plays in.
+//!
+//! # Control Operations
+//! ```rust,ignore
+//! use sonos_api::services::alarm_clock;
+//!
+//! let op = alarm_clock::list_alarms().build()?;
+//! let list = client.execute_enhanced("192.168.1.100", op)?;
+//! for alarm in list.alarms.iter().filter(|a| a.enabled) {
+//!     println!("{} {} in {}", alarm.start_time, alarm.recurrence, alarm.room_uuid);
+//! }
+//!
+//! // Switch the first alarm off; UpdateAlarm replaces every setting
+//! let op = alarm_clock::set_alarm_enabled(&list.alarms[0], false).build()?;
+//! client.execute_enhanced("192.168.1.100", op)?;
+//! ```
+//!
+//! # Important Notes
+//! - AlarmClock events aren't parsed; subscribing is not supported
+
+pub mod operations;
+
+// Re-export operations for convenience
+pub use operations::*;
+
+/// Service constant for AlarmClock
+pub const SERVICE: crate::Service = crate::Service::AlarmClock;
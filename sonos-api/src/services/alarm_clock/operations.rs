@@ -0,0 +1,495 @@
+//! AlarmClock service operations
+//!
+//! # Operations
+//! - `list_alarms` - Read every alarm of the household
+//! - `update_alarm` - Replace an alarm's settings
+//! - `set_alarm_enabled` - Switch an alarm on or off, keeping its settings
+//!
+//! AlarmClock actions take no `InstanceID`, so these are implemented by hand
+//! rather than with the operation macros.
+
+use std::fmt;
+
+use crate::operation::{opt_child_text, OperationBuilder, UPnPOperation, ValidationError};
+use crate::{ApiError, Service, Validate};
+use serde::{Deserialize, Serialize};
+
+/// Days an alarm fires on
+///
+/// On the wire: `ONCE`, `DAILY`, `WEEKDAYS`, `WEEKENDS`, or `ON_` followed
+/// by day digits, `0` for Sunday through `6` for Saturday (`ON_135` is
+/// Monday, Wednesday and Friday).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Recurrence {
+    /// The next time the start time comes round, then never again
+    Once,
+    Daily,
+    /// Monday to Friday
+    Weekdays,
+    /// Saturday and Sunday
+    Weekends,
+    /// Days as a bit mask, bit 0 for Sunday through bit 6 for Saturday
+    On(u8),
+}
+
+impl Recurrence {
+    /// Parse a recurrence as sent on the wire, in any case
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_uppercase();
+        match value.as_str() {
+            "ONCE" => Some(Recurrence::Once),
+            "DAILY" => Some(Recurrence::Daily),
+            "WEEKDAYS" => Some(Recurrence::Weekdays),
+            "WEEKENDS" => Some(Recurrence::Weekends),
+            _ => {
+                let days = value.strip_prefix("ON_")?;
+                days.chars()
+                    .try_fold(0u8, |mask, day| match day {
+                        '0'..='6' => Some(mask | 1 << (day as u8 - b'0')),
+                        _ => None,
+                    })
+                    .map(Recurrence::On)
+            }
+        }
+    }
+
+    /// Whether the alarm fires on `weekday`, counted from Sunday (0) to
+    /// Saturday (6)
+    ///
+    /// [`Once`](Recurrence::Once) fires on whichever day comes first.
+    pub fn fires_on(&self, weekday: u8) -> bool {
+        let mask = match *self {
+            Recurrence::Once | Recurrence::Daily => 0b111_1111,
+            Recurrence::Weekdays => 0b011_1110,
+            Recurrence::Weekends => 0b100_0001,
+            Recurrence::On(mask) => mask,
+        };
+        weekday < 7 && mask & (1 << weekday) != 0
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Recurrence::Once => f.write_str("ONCE"),
+            Recurrence::Daily => f.write_str("DAILY"),
+            Recurrence::Weekdays => f.write_str("WEEKDAYS"),
+            Recurrence::Weekends => f.write_str("WEEKENDS"),
+            Recurrence::On(mask) => {
+                f.write_str("ON_")?;
+                for day in (0..7).filter(|day| mask & (1 << day) != 0) {
+                    write!(f, "{day}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Recurrence::parse(&value).ok_or_else(|| format!("invalid recurrence {value:?}"))
+    }
+}
+
+/// One alarm of the household
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Alarm {
+    pub id: String,
+    /// Local time of day, `HH:MM:SS`
+    pub start_time: String,
+    /// How long it plays, `HH:MM:SS`
+    pub duration: String,
+    pub recurrence: Recurrence,
+    pub enabled: bool,
+    /// Speaker ID of the room it plays in
+    pub room_uuid: String,
+    pub program_uri: String,
+    pub program_metadata: String,
+    pub play_mode: String,
+    pub volume: u8,
+    /// Whether the room's group plays it too
+    pub include_linked_zones: bool,
+}
+
+impl Alarm {
+    fn from_xml(element: &xmltree::Element) -> Result<Self, ApiError> {
+        let attr = |name: &str| element.attributes.get(name).cloned().unwrap_or_default();
+        let id = attr("ID");
+        let recurrence = Recurrence::parse(&attr("Recurrence")).ok_or_else(|| {
+            ApiError::ParseError(format!(
+                "Invalid Recurrence {:?} of alarm {id}",
+                attr("Recurrence")
+            ))
+        })?;
+        Ok(Alarm {
+            start_time: attr("StartTime"),
+            duration: attr("Duration"),
+            recurrence,
+            enabled: attr("Enabled") == "1",
+            room_uuid: attr("RoomUUID"),
+            program_uri: attr("ProgramURI"),
+            program_metadata: attr("ProgramMetaData"),
+            play_mode: attr("PlayMode"),
+            volume: attr("Volume").parse().unwrap_or_default(),
+            include_linked_zones: attr("IncludeLinkedZones") == "1",
+            id,
+        })
+    }
+}
+
+/// Parse the `<Alarms>` document of a `ListAlarms` response
+pub fn parse_alarm_list(xml: &str) -> Result<Vec<Alarm>, ApiError> {
+    if xml.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    crate::events::xml_utils::check(xml)?;
+    let root = xmltree::Element::parse(xml.as_bytes())
+        .map_err(|e| ApiError::ParseError(format!("Invalid alarm list: {e}")))?;
+    root.children
+        .iter()
+        .filter_map(|node| node.as_element())
+        .filter(|element| element.name == "Alarm")
+        .map(Alarm::from_xml)
+        .collect()
+}
+
+// =============================================================================
+// LIST ALARMS OPERATION
+// =============================================================================
+
+/// Request to read the household's alarms
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ListAlarmsOperationRequest {}
+
+impl Validate for ListAlarmsOperationRequest {}
+
+/// Response carrying every alarm of the household
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ListAlarmsResponse {
+    pub alarms: Vec<Alarm>,
+    /// Changes whenever an alarm is added, edited or removed
+    pub version: String,
+}
+
+/// Operation to read every alarm of the household
+pub struct ListAlarmsOperation;
+
+impl UPnPOperation for ListAlarmsOperation {
+    type Request = ListAlarmsOperationRequest;
+    type Response = ListAlarmsResponse;
+
+    const SERVICE: Service = Service::AlarmClock;
+    const ACTION: &'static str = "ListAlarms";
+
+    fn build_payload(_request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(String::new())
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        let list = opt_child_text(xml, "CurrentAlarmList")
+            .ok_or_else(|| ApiError::ParseError("Missing CurrentAlarmList element".to_string()))?;
+        Ok(ListAlarmsResponse {
+            alarms: parse_alarm_list(&list)?,
+            version: opt_child_text(xml, "CurrentAlarmListVersion").unwrap_or_default(),
+        })
+    }
+}
+
+/// Create a ListAlarms operation builder
+pub fn list_alarms_operation() -> OperationBuilder<ListAlarmsOperation> {
+    OperationBuilder::new(ListAlarmsOperationRequest {})
+}
+
+// =============================================================================
+// UPDATE ALARM OPERATION
+// =============================================================================
+
+/// `HH:MM:SS`, as alarm start times and durations are written
+fn is_clock_time(value: &str) -> bool {
+    let parts: Vec<&str> = value.split(':').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| part.len() == 2 && part.bytes().all(|b| b.is_ascii_digit()))
+        && parts[0] < "24"
+        && parts[1] < "60"
+        && parts[2] < "60"
+}
+
+/// Request to replace an alarm's settings
+///
+/// The speaker replaces every setting, so start from the alarm as
+/// [`ListAlarmsOperation`] returned it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpdateAlarmOperationRequest {
+    pub alarm: Alarm,
+}
+
+impl Validate for UpdateAlarmOperationRequest {
+    fn validate_basic(&self) -> Result<(), ValidationError> {
+        let alarm = &self.alarm;
+        if alarm.id.trim().is_empty() {
+            return Err(ValidationError::MissingParameter {
+                parameter: "id".to_string(),
+            });
+        }
+        for (parameter, value) in [
+            ("start_time", &alarm.start_time),
+            ("duration", &alarm.duration),
+        ] {
+            if !is_clock_time(value) {
+                return Err(ValidationError::InvalidValue {
+                    parameter: parameter.to_string(),
+                    value: value.clone(),
+                    reason: "expected HH:MM:SS".to_string(),
+                });
+            }
+        }
+        if alarm.volume > 100 {
+            return Err(ValidationError::range_error("volume", 0, 100, alarm.volume));
+        }
+        Ok(())
+    }
+}
+
+/// Operation to replace an alarm's settings
+pub struct UpdateAlarmOperation;
+
+impl UPnPOperation for UpdateAlarmOperation {
+    type Request = UpdateAlarmOperationRequest;
+    type Response = ();
+
+    const SERVICE: Service = Service::AlarmClock;
+    const ACTION: &'static str = "UpdateAlarm";
+
+    fn build_payload(request: &Self::Request) -> Result<String, ValidationError> {
+        request.validate(crate::operation::ValidationLevel::Basic)?;
+        let alarm = &request.alarm;
+        let escape = crate::operation::xml_escape;
+        Ok(format!(
+            "<ID>{}</ID><StartLocalTime>{}</StartLocalTime><Duration>{}</Duration>\
+             <Recurrence>{}</Recurrence><Enabled>{}</Enabled><RoomUUID>{}</RoomUUID>\
+             <ProgramURI>{}</ProgramURI><ProgramMetaData>{}</ProgramMetaData>\
+             <PlayMode>{}</PlayMode><Volume>{}</Volume><IncludeLinkedZones>{}</IncludeLinkedZones>",
+            escape(&alarm.id),
+            alarm.start_time,
+            alarm.duration,
+            alarm.recurrence,
+            u8::from(alarm.enabled),
+            escape(&alarm.room_uuid),
+            escape(&alarm.program_uri),
+            escape(&alarm.program_metadata),
+            escape(&alarm.play_mode),
+            alarm.volume,
+            u8::from(alarm.include_linked_zones),
+        ))
+    }
+
+    fn parse_response(_xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        Ok(())
+    }
+}
+
+/// Create an UpdateAlarm operation builder
+pub fn update_alarm_operation(alarm: Alarm) -> OperationBuilder<UpdateAlarmOperation> {
+    OperationBuilder::new(UpdateAlarmOperationRequest { alarm })
+}
+
+/// Create an UpdateAlarm operation builder that only switches `alarm` on or
+/// off
+pub fn set_alarm_enabled_operation(
+    alarm: &Alarm,
+    enabled: bool,
+) -> OperationBuilder<UpdateAlarmOperation> {
+    update_alarm_operation(Alarm {
+        enabled,
+        ..alarm.clone()
+    })
+}
+
+// =============================================================================
+// LEGACY ALIASES
+// =============================================================================
+
+pub use list_alarms_operation as list_alarms;
+pub use set_alarm_enabled_operation as set_alarm_enabled;
+pub use update_alarm_operation as update_alarm;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recurrence_round_trip() {
+        for (wire, recurrence) in [
+            ("ONCE", Recurrence::Once),
+            ("DAILY", Recurrence::Daily),
+            ("WEEKDAYS", Recurrence::Weekdays),
+            ("WEEKENDS", Recurrence::Weekends),
+            ("ON_135", Recurrence::On(0b010_1010)),
+            ("ON_06", Recurrence::On(0b100_0001)),
+        ] {
+            assert_eq!(Recurrence::parse(wire), Some(recurrence));
+            assert_eq!(recurrence.to_string(), wire);
+        }
+        assert_eq!(Recurrence::parse(" daily "), Some(Recurrence::Daily));
+        assert_eq!(
+            Recurrence::parse("ON_531"),
+            Some(Recurrence::On(0b010_1010))
+        );
+        assert_eq!(Recurrence::parse("ON_7"), None);
+        assert_eq!(Recurrence::parse("HOURLY"), None);
+
+        assert!(Recurrence::Weekdays.fires_on(1) && !Recurrence::Weekdays.fires_on(0));
+        assert!(Recurrence::Weekends.fires_on(6) && !Recurrence::Weekends.fires_on(5));
+        assert!(Recurrence::On(0b000_0100).fires_on(2));
+        assert!(!Recurrence::On(0).fires_on(2));
+        assert!(!Recurrence::Daily.fires_on(7));
+
+        let json = serde_json::to_string(&Recurrence::On(0b000_0011)).unwrap();
+        assert_eq!(json, r#""ON_01""#);
+        assert_eq!(
+            serde_json::from_str::<Recurrence>(&json).unwrap(),
+            Recurrence::On(0b000_0011)
+        );
+    }
+
+    #[test]
+    fn test_list_alarms_response() {
+        let op = list_alarms_operation().build().unwrap();
+        assert_eq!(op.metadata().action, "ListAlarms");
+        assert_eq!(op.metadata().service, "AlarmClock");
+
+        let xml = xmltree::Element::parse(
+            r#"<u:ListAlarmsResponse xmlns:u="urn:schemas-upnp-org:service:AlarmClock:1"><CurrentAlarmList>&lt;Alarms&gt;&lt;Alarm ID="4" StartTime="07:00:00" Duration="02:00:00" Recurrence="WEEKDAYS" Enabled="1" RoomUUID="RINCON_DEN" ProgramURI="x-rincon-buzzer:0" ProgramMetaData="" PlayMode="SHUFFLE_NOREPEAT" Volume="25" IncludeLinkedZones="0"/&gt;&lt;Alarm ID="9" StartTime="09:30:00" Duration="01:00:00" Recurrence="ON_06" Enabled="0" RoomUUID="RINCON_HALL" ProgramURI="" ProgramMetaData="" PlayMode="NORMAL" Volume="10" IncludeLinkedZones="1"/&gt;&lt;/Alarms&gt;</CurrentAlarmList><CurrentAlarmListVersion>RINCON_DEN:12</CurrentAlarmListVersion></u:ListAlarmsResponse>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let response = ListAlarmsOperation::parse_response(&xml).unwrap();
+        assert_eq!(response.version, "RINCON_DEN:12");
+        assert_eq!(response.alarms.len(), 2);
+        let wake = &response.alarms[0];
+        assert_eq!(
+            (wake.id.as_str(), wake.start_time.as_str()),
+            ("4", "07:00:00")
+        );
+        assert_eq!(wake.recurrence, Recurrence::Weekdays);
+        assert!(wake.enabled && !wake.include_linked_zones);
+        assert_eq!((wake.room_uuid.as_str(), wake.volume), ("RINCON_DEN", 25));
+        let weekend = &response.alarms[1];
+        assert_eq!(weekend.recurrence, Recurrence::On(0b100_0001));
+        assert!(!weekend.enabled && weekend.include_linked_zones);
+
+        let empty = xmltree::Element::parse(
+            r#"<u:ListAlarmsResponse xmlns:u="urn:mock"><CurrentAlarmList></CurrentAlarmList></u:ListAlarmsResponse>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        assert!(ListAlarmsOperation::parse_response(&empty)
+            .unwrap()
+            .alarms
+            .is_empty());
+        assert!(
+            parse_alarm_list(r#"<Alarms><Alarm ID="1" Recurrence="HOURLY"/></Alarms>"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_list_alarms_fixture() {
+        let envelope = xmltree::Element::parse(
+            include_str!("../../../tests/fixtures/list_alarms_response.xml").as_bytes(),
+        )
+        .unwrap();
+        let response = envelope
+            .get_child("Body")
+            .and_then(|body| body.get_child("ListAlarmsResponse"))
+            .unwrap();
+        let list = ListAlarmsOperation::parse_response(response).unwrap();
+        assert_eq!(list.version, "RINCON_B8E9372C8A9001400:124");
+        let ids: Vec<_> = list.alarms.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["15", "16", "22"]);
+
+        // Attribute values are unescaped once; the metadata is itself XML
+        let radio = &list.alarms[0];
+        assert_eq!(
+            radio.program_uri,
+            "x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0"
+        );
+        assert!(radio
+            .program_metadata
+            .starts_with(r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/""#));
+        assert!(radio
+            .program_metadata
+            .contains("<dc:title>BBC Radio 4 &amp; World</dc:title>"));
+        assert_eq!(
+            (radio.start_time.as_str(), radio.duration.as_str()),
+            ("06:45:00", "01:00:00")
+        );
+        assert_eq!(radio.recurrence, Recurrence::Weekdays);
+        assert_eq!(radio.room_uuid, "RINCON_B8E9372C8A9001400");
+        assert_eq!((radio.volume, radio.enabled), (20, true));
+
+        assert_eq!(list.alarms[1].recurrence, Recurrence::On(0b100_0001));
+        assert!(!list.alarms[1].enabled);
+        assert_eq!(list.alarms[2].recurrence, Recurrence::Once);
+    }
+
+    #[test]
+    fn test_update_alarm_payload() {
+        let xml = include_str!("../../../tests/fixtures/list_alarms_response.xml");
+        let envelope = xmltree::Element::parse(xml.as_bytes()).unwrap();
+        let response = envelope
+            .get_child("Body")
+            .and_then(|body| body.get_child("ListAlarmsResponse"))
+            .unwrap();
+        let radio = ListAlarmsOperation::parse_response(response)
+            .unwrap()
+            .alarms
+            .remove(0);
+
+        let op = set_alarm_enabled_operation(&radio, false).build().unwrap();
+        assert_eq!(op.metadata().action, "UpdateAlarm");
+        assert!(!op.request().alarm.enabled);
+        let payload = UpdateAlarmOperation::build_payload(op.request()).unwrap();
+        assert!(payload.starts_with(
+            "<ID>15</ID><StartLocalTime>06:45:00</StartLocalTime><Duration>01:00:00</Duration>\
+             <Recurrence>WEEKDAYS</Recurrence><Enabled>0</Enabled>"
+        ));
+        assert!(payload.contains(
+            "<ProgramURI>x-sonosapi-stream:s17077?sid=254&amp;flags=8224&amp;sn=0</ProgramURI>"
+        ));
+        assert!(payload.contains("<ProgramMetaData>&lt;DIDL-Lite "));
+        assert!(payload.ends_with("<Volume>20</Volume><IncludeLinkedZones>0</IncludeLinkedZones>"));
+
+        for alarm in [
+            Alarm {
+                start_time: "7:00".to_string(),
+                ..radio.clone()
+            },
+            Alarm {
+                duration: "00:60:00".to_string(),
+                ..radio.clone()
+            },
+            Alarm {
+                volume: 101,
+                ..radio.clone()
+            },
+            Alarm {
+                id: String::new(),
+                ..radio.clone()
+            },
+        ] {
+            assert!(update_alarm_operation(alarm).build().is_err());
+        }
+    }
+}
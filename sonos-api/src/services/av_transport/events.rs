@@ -1,9 +1,23 @@
 //! AVTransport service event types and parsing
 //!
-//! Provides direct serde-based XML parsing with no business logic,
-//! replicating exactly what Sonos produces for sonos-stream consumption.
-
+//! Provides direct XML parsing with no business logic, replicating exactly
+//! what Sonos produces for sonos-stream consumption.
+//!
+//! [`AVTransportEventRef`] reads the `LastChange` document in place, with
+//! values borrowed from it; [`AVTransportEvent`] is built on top of it for
+//! callers that keep the event.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
+
+use quick_xml::events::attributes::Attributes;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::net::IpAddr;
 
 use crate::events::{xml_utils, EnrichedEvent, EventParser, EventSource};
@@ -73,6 +87,9 @@ struct AVTransportInstance {
 
     #[serde(rename = "NumberOfTracks", default)]
     pub queue_length: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "CurrentTransportActions", default)]
+    pub current_transport_actions: Option<xml_utils::ValueAttribute>,
 }
 
 impl AVTransportEvent {
@@ -211,6 +228,16 @@ impl AVTransportEvent {
             .and_then(|v| v.val.parse().ok())
     }
 
+    /// Get currently allowed transport actions (comma-separated)
+    pub fn current_transport_actions(&self) -> Option<String> {
+        self.property
+            .last_change
+            .instance
+            .current_transport_actions
+            .as_ref()
+            .map(|v| v.val.clone())
+    }
+
     /// Convert parsed UPnP event to canonical state representation.
     pub fn into_state(&self) -> super::state::AVTransportState {
         super::state::AVTransportState {
@@ -228,17 +255,265 @@ impl AVTransportEvent {
             next_track_uri: self.next_track_uri(),
             next_track_metadata: self.next_track_metadata(),
             queue_length: self.queue_length(),
+            transport_actions: self.current_transport_actions(),
         }
     }
 
-    /// Parse from UPnP event XML using serde
+    /// Parse from UPnP event XML
     pub fn from_xml(xml: &str) -> Result<Self> {
-        let clean_xml = xml_utils::strip_namespaces(xml);
-        quick_xml::de::from_str(&clean_xml)
-            .map_err(|e| ApiError::ParseError(format!("Failed to parse AVTransport XML: {e}")))
+        let last_change = xml_utils::last_change(xml)?;
+        Ok(AVTransportEventRef::parse(&last_change)?.into_owned())
     }
 }
 
+/// AVTransport event borrowed from its `LastChange` document
+///
+/// Values without entities point into the document; the rest, such as the
+/// escaped DIDL-Lite metadata, are unescaped into owned strings. Accessors
+/// match [`AVTransportEvent`]'s.
+///
+/// ```rust
+/// use sonos_api::events::xml_utils;
+/// use sonos_api::services::av_transport::AVTransportEventRef;
+///
+/// # fn main() -> sonos_api::Result<()> {
+/// let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event&gt;&lt;InstanceID val="0"&gt;&lt;TransportState val="PLAYING"/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#;
+/// let last_change = xml_utils::last_change(xml)?;
+/// let event = AVTransportEventRef::parse(&last_change)?;
+/// assert_eq!(event.transport_state(), Some("PLAYING"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AVTransportEventRef<'a> {
+    transport_state: Option<Cow<'a, str>>,
+    transport_status: Option<Cow<'a, str>>,
+    speed: Option<Cow<'a, str>>,
+    current_track_uri: Option<Cow<'a, str>>,
+    track_duration: Option<Cow<'a, str>>,
+    rel_time: Option<Cow<'a, str>>,
+    abs_time: Option<Cow<'a, str>>,
+    rel_count: Option<Cow<'a, str>>,
+    play_mode: Option<Cow<'a, str>>,
+    track_metadata: Option<Cow<'a, str>>,
+    next_track_uri: Option<Cow<'a, str>>,
+    next_track_metadata: Option<Cow<'a, str>>,
+    queue_length: Option<Cow<'a, str>>,
+    current_transport_actions: Option<Cow<'a, str>>,
+}
+
+impl<'a> AVTransportEventRef<'a> {
+    /// Parse the `<Event>` document carried by `LastChange`
+    ///
+    /// Take it out of a NOTIFY body with [`xml_utils::last_change`].
+    /// Variables are read from the first `InstanceID`'s children, without
+    /// their namespace prefix; unknown ones are skipped.
+    pub fn parse(last_change: &'a str) -> Result<Self> {
+        xml_utils::check(last_change)?;
+        let mut reader = Reader::from_str(last_change);
+        let mut event = AVTransportEventRef::default();
+        // Element depth: 1 inside `Event`, 2 inside `InstanceID`
+        let mut depth = 0usize;
+        let mut instances = 0;
+        loop {
+            let (start, empty) = match reader.read_event().map_err(parse_error)? {
+                Event::Start(start) => (start, false),
+                Event::Empty(start) => (start, true),
+                Event::End(_) => {
+                    depth = depth.saturating_sub(1);
+                    continue;
+                }
+                Event::Eof => break,
+                _ => continue,
+            };
+            match depth {
+                1 if start.local_name().as_ref() == b"InstanceID" => instances += 1,
+                2 if instances == 1 => {
+                    // The tag text sits just before `>` (`/>` when empty);
+                    // reading attributes from the input keeps them borrowed
+                    let tag_end = reader.buffer_position() - 1 - usize::from(empty);
+                    let tag = tag_end
+                        .checked_sub(start.len())
+                        .and_then(|tag_start| last_change.get(tag_start..tag_end))
+                        .ok_or_else(|| parse_error("tag outside the document"))?;
+                    event.set(&start, Attributes::new(tag, start.name().as_ref().len()))?;
+                }
+                _ => {}
+            }
+            depth += usize::from(!empty);
+        }
+        if depth != 0 {
+            return Err(parse_error("unexpected end of document"));
+        }
+        match instances {
+            0 => Err(parse_error("missing field `InstanceID`")),
+            1 => Ok(event),
+            _ => Err(parse_error("duplicate field `InstanceID`")),
+        }
+    }
+
+    /// Record one state variable element
+    fn set(&mut self, element: &BytesStart<'_>, attributes: Attributes<'a>) -> Result<()> {
+        let slot = match element.local_name().as_ref() {
+            b"TransportState" => &mut self.transport_state,
+            b"TransportStatus" => &mut self.transport_status,
+            b"TransportPlaySpeed" => &mut self.speed,
+            b"CurrentTrackURI" => &mut self.current_track_uri,
+            b"CurrentTrackDuration" => &mut self.track_duration,
+            b"RelativeTimePosition" => &mut self.rel_time,
+            b"AbsoluteTimePosition" => &mut self.abs_time,
+            b"CurrentTrack" => &mut self.rel_count,
+            b"CurrentPlayMode" => &mut self.play_mode,
+            b"CurrentTrackMetaData" => &mut self.track_metadata,
+            b"NextTrackURI" => &mut self.next_track_uri,
+            b"NextTrackMetaData" => &mut self.next_track_metadata,
+            b"NumberOfTracks" => &mut self.queue_length,
+            b"CurrentTransportActions" => &mut self.current_transport_actions,
+            _ => return Ok(()),
+        };
+        if slot.is_some() {
+            let name = String::from_utf8_lossy(element.local_name().into_inner()).into_owned();
+            return Err(parse_error(format!("duplicate field `{name}`")));
+        }
+        let mut val = Cow::Borrowed("");
+        for attribute in attributes {
+            let attribute = attribute.map_err(parse_error)?;
+            if attribute.key.local_name().as_ref() == b"val" {
+                val = attribute.unescape_value().map_err(parse_error)?;
+            }
+        }
+        *slot = Some(val);
+        Ok(())
+    }
+
+    /// Get transport state
+    pub fn transport_state(&self) -> Option<&str> {
+        self.transport_state.as_deref()
+    }
+
+    /// Get transport status
+    pub fn transport_status(&self) -> Option<&str> {
+        self.transport_status.as_deref()
+    }
+
+    /// Get speed
+    pub fn speed(&self) -> Option<&str> {
+        self.speed.as_deref()
+    }
+
+    /// Get current track URI
+    pub fn current_track_uri(&self) -> Option<&str> {
+        self.current_track_uri.as_deref()
+    }
+
+    /// Get track duration
+    pub fn track_duration(&self) -> Option<&str> {
+        self.track_duration.as_deref()
+    }
+
+    /// Get relative time
+    pub fn rel_time(&self) -> Option<&str> {
+        self.rel_time.as_deref()
+    }
+
+    /// Get absolute time
+    pub fn abs_time(&self) -> Option<&str> {
+        self.abs_time.as_deref()
+    }
+
+    /// Get relative count
+    pub fn rel_count(&self) -> Option<u32> {
+        self.rel_count.as_deref().and_then(|v| v.parse().ok())
+    }
+
+    /// Get play mode
+    pub fn play_mode(&self) -> Option<&str> {
+        self.play_mode.as_deref()
+    }
+
+    /// Get track metadata
+    pub fn track_metadata(&self) -> Option<&str> {
+        self.track_metadata.as_deref()
+    }
+
+    /// Get next track URI
+    pub fn next_track_uri(&self) -> Option<&str> {
+        self.next_track_uri.as_deref()
+    }
+
+    /// Get next track metadata
+    pub fn next_track_metadata(&self) -> Option<&str> {
+        self.next_track_metadata.as_deref()
+    }
+
+    /// Get queue length
+    pub fn queue_length(&self) -> Option<u32> {
+        self.queue_length.as_deref().and_then(|v| v.parse().ok())
+    }
+
+    /// Get currently allowed transport actions (comma-separated)
+    pub fn current_transport_actions(&self) -> Option<&str> {
+        self.current_transport_actions.as_deref()
+    }
+
+    /// Convert to canonical state, copying only the values present
+    pub fn to_state(&self) -> super::state::AVTransportState {
+        let owned = |value: Option<&str>| value.map(str::to_string);
+        super::state::AVTransportState {
+            transport_state: owned(self.transport_state()),
+            transport_status: owned(self.transport_status()),
+            speed: owned(self.speed()),
+            current_track_uri: owned(self.current_track_uri()),
+            track_duration: owned(self.track_duration()),
+            track_metadata: owned(self.track_metadata()),
+            rel_time: owned(self.rel_time()),
+            abs_time: owned(self.abs_time()),
+            rel_count: self.rel_count(),
+            abs_count: None,
+            play_mode: owned(self.play_mode()),
+            next_track_uri: owned(self.next_track_uri()),
+            next_track_metadata: owned(self.next_track_metadata()),
+            queue_length: self.queue_length(),
+            transport_actions: owned(self.current_transport_actions()),
+        }
+    }
+
+    /// Copy into an owned [`AVTransportEvent`]
+    pub fn into_owned(self) -> AVTransportEvent {
+        let owned = |value: Option<Cow<'_, str>>| {
+            value.map(|val| xml_utils::ValueAttribute {
+                val: val.into_owned(),
+            })
+        };
+        AVTransportEvent {
+            property: AVTransportProperty {
+                last_change: AVTransportEventData {
+                    instance: AVTransportInstance {
+                        transport_state: owned(self.transport_state),
+                        transport_status: owned(self.transport_status),
+                        speed: owned(self.speed),
+                        current_track_uri: owned(self.current_track_uri),
+                        track_duration: owned(self.track_duration),
+                        rel_time: owned(self.rel_time),
+                        abs_time: owned(self.abs_time),
+                        rel_count: owned(self.rel_count),
+                        play_mode: owned(self.play_mode),
+                        track_metadata: owned(self.track_metadata),
+                        next_track_uri: owned(self.next_track_uri),
+                        next_track_metadata: owned(self.next_track_metadata),
+                        queue_length: owned(self.queue_length),
+                        current_transport_actions: owned(self.current_transport_actions),
+                    },
+                },
+            },
+        }
+    }
+}
+
+fn parse_error(e: impl std::fmt::Display) -> ApiError {
+    ApiError::ParseError(format!("Failed to parse AVTransport XML: {e}"))
+}
+
 /// Minimal parser implementation
 pub struct AVTransportEventParser;
 
@@ -312,6 +587,7 @@ mod tests {
                 next_track_uri: None,
                 next_track_metadata: None,
                 queue_length: None,
+                current_transport_actions: None,
             },
         };
 
@@ -350,6 +626,7 @@ mod tests {
                         next_track_uri: None,
                         next_track_metadata: None,
                         queue_length: None,
+                        current_transport_actions: None,
                     },
                 },
             },
@@ -387,6 +664,7 @@ mod tests {
                         next_track_uri: None,
                         next_track_metadata: None,
                         queue_length: None,
+                        current_transport_actions: None,
                     },
                 },
             },
@@ -456,6 +734,7 @@ mod tests {
                         queue_length: Some(xml_utils::ValueAttribute {
                             val: "5".to_string(),
                         }),
+                        current_transport_actions: None,
                     },
                 },
             },
@@ -500,4 +779,155 @@ mod tests {
         assert_eq!(state.rel_count, Some(1));
         assert_eq!(state.queue_length, Some(5));
     }
+
+    #[test]
+    fn test_current_transport_actions_from_xml() {
+        let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0">
+            <e:property>
+                <LastChange>&lt;Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"&gt;
+                    &lt;InstanceID val="0"&gt;
+                        &lt;TransportState val="PLAYING"/&gt;
+                        &lt;CurrentTransportActions val="Play, Stop, Pause, Next"/&gt;
+                    &lt;/InstanceID&gt;
+                &lt;/Event&gt;</LastChange>
+            </e:property>
+        </e:propertyset>"#;
+
+        let state = AVTransportEvent::from_xml(xml).unwrap().into_state();
+        assert_eq!(
+            state.transport_actions,
+            Some("Play, Stop, Pause, Next".to_string())
+        );
+    }
+
+    const FIXTURE: &str = include_str!("../../../tests/fixtures/av_transport_event.xml");
+
+    /// The serde mapping `from_xml` used before the borrowing parser
+    fn serde_from_xml(xml: &str) -> Result<AVTransportEvent> {
+        quick_xml::de::from_str(&xml_utils::strip_namespaces(xml))
+            .map_err(|e| ApiError::ParseError(e.to_string()))
+    }
+
+    /// Element names the parsers map, plus ones they skip
+    const VARIABLES: &[&str] = &[
+        "TransportState",
+        "TransportStatus",
+        "TransportPlaySpeed",
+        "CurrentTrackURI",
+        "CurrentTrackDuration",
+        "RelativeTimePosition",
+        "AbsoluteTimePosition",
+        "CurrentTrack",
+        "CurrentPlayMode",
+        "CurrentTrackMetaData",
+        "NextTrackURI",
+        "NextTrackMetaData",
+        "NumberOfTracks",
+        "CurrentTransportActions",
+        "CurrentCrossfadeMode",
+        "SleepTimerGeneration",
+    ];
+
+    fn xml_escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    #[test]
+    fn test_event_ref_borrows_plain_values() {
+        let last_change = xml_utils::last_change(FIXTURE).unwrap();
+        let event = AVTransportEventRef::parse(&last_change).unwrap();
+
+        assert_eq!(event.transport_state(), Some("PLAYING"));
+        assert_eq!(event.play_mode(), Some("SHUFFLE_NOREPEAT"));
+        assert_eq!(event.rel_count(), Some(1));
+        assert_eq!(event.queue_length(), Some(10));
+        assert_eq!(event.rel_time(), None);
+        assert!(matches!(event.transport_state, Some(Cow::Borrowed(_))));
+        assert!(matches!(event.track_duration, Some(Cow::Borrowed(_))));
+        // Prefixed in the document (`r:NextTrackURI`), with `&amp;` inside
+        assert_eq!(
+            event.next_track_uri(),
+            Some("x-sonos-spotify:spotify%3atrack%3a2ctvdKmETyOzPb2GiJJT53?sid=12&flags=8224&sn=1")
+        );
+        let metadata = event.track_metadata().unwrap();
+        assert!(metadata.starts_with("<DIDL-Lite"), "{metadata}");
+        assert!(metadata.contains("<dc:title>Speak to Me</dc:title>"));
+    }
+
+    #[test]
+    fn test_owned_event_matches_serde_mapping() {
+        let event = AVTransportEvent::from_xml(FIXTURE).unwrap();
+        let legacy = serde_from_xml(FIXTURE).unwrap();
+        assert_eq!(event.into_state(), legacy.into_state());
+
+        let last_change = xml_utils::last_change(FIXTURE).unwrap();
+        let borrowed = AVTransportEventRef::parse(&last_change).unwrap();
+        assert_eq!(borrowed.to_state(), event.into_state());
+    }
+
+    #[test]
+    fn test_event_ref_rejects_what_serde_rejects() {
+        let wrap = |inner: &str| {
+            format!(
+                r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>{}</LastChange></e:property></e:propertyset>"#,
+                inner.replace('<', "&lt;").replace('>', "&gt;")
+            )
+        };
+        for xml in [
+            wrap(r#"<Event><Other val="0"/></Event>"#),
+            wrap(
+                r#"<Event><InstanceID val="0"><TransportState val="PLAYING"/><TransportState val="STOPPED"/></InstanceID></Event>"#,
+            ),
+            wrap(r#"<Event><InstanceID val="0"><TransportState val="PLAYING"/></Event>"#),
+            r#"<e:propertyset><e:property></e:property></e:propertyset>"#.to_string(),
+        ] {
+            assert!(serde_from_xml(&xml).is_err(), "serde accepted {xml}");
+            assert!(AVTransportEvent::from_xml(&xml).is_err(), "{xml}");
+        }
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn prop_event_ref_matches_serde_mapping(
+            variables in proptest::collection::vec(
+                (
+                    proptest::sample::select(VARIABLES),
+                    proptest::option::of("[a-z]{1,3}"),
+                    "[ a-zA-Z0-9:,%&<>\"']{0,24}",
+                ),
+                0..12,
+            ),
+        ) {
+            // Unique names: a repeated variable is an error either way
+            let mut seen = std::collections::HashSet::new();
+            let inner: String = variables
+                .into_iter()
+                .filter(|(name, _, _)| seen.insert(*name))
+                .map(|(name, prefix, val)| {
+                    let name = prefix.map_or(name.to_string(), |p| format!("{p}:{name}"));
+                    format!(r#"<{name} val="{}"/>"#, xml_escape(&val))
+                })
+                .collect();
+            let xml = format!(
+                r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>{}</LastChange></e:property></e:propertyset>"#,
+                xml_escape(&format!(r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"><InstanceID val="0">{inner}</InstanceID></Event>"#))
+            );
+            let legacy = serde_from_xml(&xml).map(|e| e.into_state());
+            let event = AVTransportEvent::from_xml(&xml).map(|e| e.into_state());
+            proptest::prop_assert_eq!(event.ok(), legacy.ok());
+        }
+
+        #[test]
+        fn fuzz_av_transport_event(xml in xml_utils::fuzz::mutated(r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"&gt;&lt;InstanceID val="0"&gt;&lt;TransportState val="PLAYING"/&gt;&lt;CurrentTrack val="1"/&gt;&lt;CurrentTrackDuration val="0:03:58"/&gt;&lt;CurrentTrackMetaData val="&amp;lt;DIDL-Lite&amp;gt;&amp;lt;item id=&amp;quot;-1&amp;quot;&amp;gt;&amp;lt;dc:title&amp;gt;Song&amp;lt;/dc:title&amp;gt;&amp;lt;/item&amp;gt;&amp;lt;/DIDL-Lite&amp;gt;"/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#)) {
+            if let Ok(event) = AVTransportEvent::from_xml(&xml) {
+                event.into_state();
+            }
+        }
+    }
 }
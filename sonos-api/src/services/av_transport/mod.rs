@@ -9,6 +9,10 @@
 //!
 //! let play_op = av_transport::play("1".to_string()).build()?;
 //! client.execute("192.168.1.100", play_op)?;
+//!
+//! // A TuneIn station, URI and metadata built for you
+//! let station = av_transport::Station::tunein("s25419", "BBC Radio 4");
+//! av_transport::play_station(&client, "192.168.1.100", &station)?;
 //! ```
 //!
 //! # Event Subscriptions
@@ -28,6 +32,7 @@
 
 pub mod events;
 pub mod operations;
+pub mod radio;
 pub mod state;
 
 // Re-export operations for convenience
@@ -36,6 +41,7 @@ pub use operations::*;
 // Re-export event types and parsers
 pub use events::{
     create_enriched_event, create_enriched_event_with_registration_id, AVTransportEvent,
-    AVTransportEventParser,
+    AVTransportEventParser, AVTransportEventRef,
 };
+pub use radio::{play_station, Station, TUNEIN_SERVICE_ID};
 pub use state::AVTransportState;
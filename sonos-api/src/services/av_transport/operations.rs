@@ -3,6 +3,8 @@
 //! This module contains all UPnP operations for the AVTransport service,
 //! which controls playback, queue management, and transport settings.
 
+use std::time::Duration;
+
 use crate::{define_operation_with_response, define_upnp_operation, Validate};
 use paste::paste;
 
@@ -122,18 +124,54 @@ define_upnp_operation! {
 
 impl Validate for SeekOperationRequest {
     fn validate_basic(&self) -> Result<(), crate::operation::ValidationError> {
-        match self.unit.as_str() {
-            "TRACK_NR" | "REL_TIME" | "TIME_DELTA" => Ok(()),
-            other => Err(crate::operation::ValidationError::Custom {
-                parameter: "unit".to_string(),
-                message: format!(
-                    "Invalid unit '{other}'. Must be 'TRACK_NR', 'REL_TIME', or 'TIME_DELTA'"
-                ),
-            }),
+        let valid = match self.unit.as_str() {
+            "TRACK_NR" => self.target.parse::<u32>().is_ok_and(|track| track > 0),
+            "REL_TIME" => is_seek_time(&self.target),
+            "TIME_DELTA" => {
+                is_seek_time(self.target.strip_prefix(['+', '-']).unwrap_or(&self.target))
+            }
+            other => {
+                return Err(crate::operation::ValidationError::Custom {
+                    parameter: "unit".to_string(),
+                    message: format!(
+                        "Invalid unit '{other}'. Must be 'TRACK_NR', 'REL_TIME', or 'TIME_DELTA'"
+                    ),
+                })
+            }
+        };
+        if valid {
+            return Ok(());
         }
+        let expected = match self.unit.as_str() {
+            "TRACK_NR" => "a track number from 1",
+            "REL_TIME" => "a time as h:mm:ss",
+            _ => "a time as h:mm:ss, optionally signed",
+        };
+        Err(crate::operation::ValidationError::InvalidValue {
+            parameter: "target".to_string(),
+            value: self.target.clone(),
+            reason: format!("{} targets must be {expected}", self.unit),
+        })
     }
 }
 
+/// Whether `value` is `h:mm:ss`: any number of hour digits, then two-digit
+/// minutes and seconds below 60
+fn is_seek_time(value: &str) -> bool {
+    let mut parts = value.split(':');
+    let (Some(hours), Some(minutes), Some(seconds), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let sexagesimal = |part: &str| part.len() == 2 && digits(part) && part < "60";
+    digits(hours) && sexagesimal(minutes) && sexagesimal(seconds)
+}
+
+// Optional fields (see `opt_child_text`): `track_uri` is `Some("")` when
+// nothing is loaded, `track_meta_data` is `None` when empty, and the counters
+// are `None` on firmware that omits them.
 define_operation_with_response! {
     operation: GetPositionInfoOperation,
     action: "GetPositionInfo",
@@ -142,17 +180,17 @@ define_operation_with_response! {
     response: GetPositionInfoResponse {
         track: u32,
         track_duration: String,
-        track_meta_data: String,
-        track_uri: String,
+        track_meta_data: Option<String>,
+        track_uri: Option<String>,
         rel_time: String,
         abs_time: String,
-        rel_count: i32,
-        abs_count: i32,
+        rel_count: Option<i32>,
+        abs_count: Option<i32>,
     },
     xml_mapping: {
         track: "Track",
         track_duration: "TrackDuration",
-        track_meta_data: "TrackMetaData",
+        track_meta_data: "TrackMetaData" => non_empty,
         track_uri: "TrackURI",
         rel_time: "RelTime",
         abs_time: "AbsTime",
@@ -163,6 +201,59 @@ define_operation_with_response! {
 
 impl Validate for GetPositionInfoOperationRequest {}
 
+impl GetPositionInfoResponse {
+    /// Length of the current track, `None` when the source has none (line-in,
+    /// TV, radio streams)
+    pub fn track_length(&self) -> Option<Duration> {
+        parse_time(&self.track_duration)
+    }
+
+    /// Position within the current track
+    pub fn rel_position(&self) -> Option<Duration> {
+        parse_time(&self.rel_time)
+    }
+
+    /// Position within the whole queue; Sonos reports `NOT_IMPLEMENTED`
+    pub fn abs_position(&self) -> Option<Duration> {
+        parse_time(&self.abs_time)
+    }
+}
+
+/// Parse an AVTransport time, `h:mm:ss` with optional fractional seconds
+///
+/// Returns `None` for the sentinels sources without a timeline report
+/// (`NOT_IMPLEMENTED`, `-1:-1:-1`, an empty string) and for anything else
+/// that is not a time.
+pub fn parse_time(value: &str) -> Option<Duration> {
+    let mut parts = value.trim().split(':');
+    let (Some(hours), Some(minutes), Some(seconds), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    let number = |part: &str| -> Option<u64> {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        part.parse().ok()
+    };
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let (hours, minutes, seconds) = (number(hours)?, number(minutes)?, number(seconds)?);
+    if minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    let nanos = if fraction.is_empty() {
+        0
+    } else {
+        let digits = fraction.get(..fraction.len().min(9))?;
+        number(digits)? * 10u64.pow(9 - digits.len() as u32)
+    };
+    let whole = hours
+        .checked_mul(3600)?
+        .checked_add(minutes * 60 + seconds)?;
+    Some(Duration::new(whole, nanos as u32))
+}
+
 // =============================================================================
 // TRANSPORT INFO AND SETTINGS
 // =============================================================================
@@ -203,6 +294,126 @@ define_operation_with_response! {
 
 impl Validate for GetTransportSettingsOperationRequest {}
 
+impl GetTransportSettingsResponse {
+    /// The play mode, `None` for a value this crate doesn't know
+    pub fn mode(&self) -> Option<PlayMode> {
+        PlayMode::parse(&self.play_mode)
+    }
+}
+
+/// Order and repetition of queue playback
+///
+/// On the wire: `NORMAL`, `REPEAT_ALL`, `REPEAT_ONE`, `SHUFFLE_NOREPEAT`,
+/// `SHUFFLE` and `SHUFFLE_REPEAT_ONE`, as read by `GetTransportSettings` and
+/// `CurrentPlayMode` events and written by `SetPlayMode`. Strings from users
+/// go through [`str::parse()`], which rejects anything else with a
+/// `ValidationError` before a request can be built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum PlayMode {
+    /// Normal sequential playback
+    Normal,
+    /// Repeat all tracks
+    RepeatAll,
+    /// Repeat current track
+    RepeatOne,
+    /// Shuffle without repeat
+    ShuffleNoRepeat,
+    /// Shuffle with repeat
+    Shuffle,
+    /// Shuffle and repeat current track
+    ShuffleRepeatOne,
+}
+
+impl PlayMode {
+    /// Every play mode, in wire order
+    pub const ALL: [PlayMode; 6] = [
+        PlayMode::Normal,
+        PlayMode::RepeatAll,
+        PlayMode::RepeatOne,
+        PlayMode::ShuffleNoRepeat,
+        PlayMode::Shuffle,
+        PlayMode::ShuffleRepeatOne,
+    ];
+
+    /// Parse a play mode as sent on the wire, in any case
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.as_str().eq_ignore_ascii_case(value))
+    }
+
+    /// The wire value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlayMode::Normal => "NORMAL",
+            PlayMode::RepeatAll => "REPEAT_ALL",
+            PlayMode::RepeatOne => "REPEAT_ONE",
+            PlayMode::ShuffleNoRepeat => "SHUFFLE_NOREPEAT",
+            PlayMode::Shuffle => "SHUFFLE",
+            PlayMode::ShuffleRepeatOne => "SHUFFLE_REPEAT_ONE",
+        }
+    }
+
+    /// Whether tracks play in random order
+    pub fn is_shuffle(&self) -> bool {
+        matches!(
+            self,
+            PlayMode::ShuffleNoRepeat | PlayMode::Shuffle | PlayMode::ShuffleRepeatOne
+        )
+    }
+
+    /// The same repeat setting with shuffle turned on or off
+    ///
+    /// `NORMAL` pairs with `SHUFFLE_NOREPEAT`, `REPEAT_ALL` with `SHUFFLE`
+    /// and `REPEAT_ONE` with `SHUFFLE_REPEAT_ONE`.
+    pub fn with_shuffle(self, shuffle: bool) -> Self {
+        match (self, shuffle) {
+            (PlayMode::Normal | PlayMode::ShuffleNoRepeat, false) => PlayMode::Normal,
+            (PlayMode::Normal | PlayMode::ShuffleNoRepeat, true) => PlayMode::ShuffleNoRepeat,
+            (PlayMode::RepeatAll | PlayMode::Shuffle, false) => PlayMode::RepeatAll,
+            (PlayMode::RepeatAll | PlayMode::Shuffle, true) => PlayMode::Shuffle,
+            (PlayMode::RepeatOne | PlayMode::ShuffleRepeatOne, false) => PlayMode::RepeatOne,
+            (PlayMode::RepeatOne | PlayMode::ShuffleRepeatOne, true) => PlayMode::ShuffleRepeatOne,
+        }
+    }
+}
+
+impl std::fmt::Display for PlayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PlayMode {
+    type Err = crate::operation::ValidationError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        PlayMode::parse(value).ok_or_else(|| {
+            crate::operation::ValidationError::InvalidValue {
+                parameter: "play_mode".to_string(),
+                value: value.to_string(),
+                reason: "must be NORMAL, REPEAT_ALL, REPEAT_ONE, SHUFFLE_NOREPEAT, SHUFFLE or SHUFFLE_REPEAT_ONE".to_string(),
+            }
+        })
+    }
+}
+
+impl From<PlayMode> for String {
+    fn from(mode: PlayMode) -> Self {
+        mode.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for PlayMode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        PlayMode::parse(&value).ok_or_else(|| format!("invalid play mode {value:?}"))
+    }
+}
+
 define_operation_with_response! {
     operation: GetCurrentTransportActionsOperation,
     action: "GetCurrentTransportActions",
@@ -249,21 +460,21 @@ define_operation_with_response! {
     response: GetMediaInfoResponse {
         nr_tracks: u32,
         media_duration: String,
-        current_uri: String,
-        current_uri_meta_data: String,
-        next_uri: String,
-        next_uri_meta_data: String,
-        play_medium: String,
-        record_medium: String,
-        write_status: String,
+        current_uri: Option<String>,
+        current_uri_meta_data: Option<String>,
+        next_uri: Option<String>,
+        next_uri_meta_data: Option<String>,
+        play_medium: Option<String>,
+        record_medium: Option<String>,
+        write_status: Option<String>,
     },
     xml_mapping: {
         nr_tracks: "NrTracks",
         media_duration: "MediaDuration",
         current_uri: "CurrentURI",
-        current_uri_meta_data: "CurrentURIMetaData",
+        current_uri_meta_data: "CurrentURIMetaData" => non_empty,
         next_uri: "NextURI",
-        next_uri_meta_data: "NextURIMetaData",
+        next_uri_meta_data: "NextURIMetaData" => non_empty,
         play_medium: "PlayMedium",
         record_medium: "RecordMedium",
         write_status: "WriteStatus",
@@ -360,33 +571,21 @@ define_upnp_operation! {
     action: "SetPlayMode",
     service: AVTransport,
     request: {
-        new_play_mode: String,
+        new_play_mode: PlayMode,
     },
     response: (),
     payload: |req| {
         format!(
             "<InstanceID>{}</InstanceID><NewPlayMode>{}</NewPlayMode>",
             req.instance_id,
-            crate::operation::xml_escape(&req.new_play_mode)
+            req.new_play_mode.as_str()
         )
     },
     parse: |_xml| Ok(()),
 }
 
-impl Validate for SetPlayModeOperationRequest {
-    fn validate_basic(&self) -> Result<(), crate::operation::ValidationError> {
-        match self.new_play_mode.as_str() {
-            "NORMAL" | "REPEAT_ALL" | "REPEAT_ONE" | "SHUFFLE_NOREPEAT" | "SHUFFLE"
-            | "SHUFFLE_REPEAT_ONE" => Ok(()),
-            other => Err(crate::operation::ValidationError::Custom {
-                parameter: "new_play_mode".to_string(),
-                message: format!(
-                    "Invalid play mode '{other}'. Must be NORMAL, REPEAT_ALL, REPEAT_ONE, SHUFFLE_NOREPEAT, SHUFFLE, or SHUFFLE_REPEAT_ONE"
-                ),
-            }),
-        }
-    }
-}
+/// Always valid: a `PlayMode` can only hold a wire value
+impl Validate for SetPlayModeOperationRequest {}
 
 // =============================================================================
 // SLEEP TIMER
@@ -469,30 +668,23 @@ impl crate::operation::UPnPOperation for AddURIToQueueOperation {
         Ok(format!(
             "<InstanceID>{}</InstanceID><EnqueuedURI>{}</EnqueuedURI><EnqueuedURIMetaData>{}</EnqueuedURIMetaData><DesiredFirstTrackNumberEnqueued>{}</DesiredFirstTrackNumberEnqueued><EnqueueAsNext>{}</EnqueueAsNext>",
             request.instance_id,
-            request.enqueued_uri,
-            request.enqueued_uri_meta_data,
+            crate::operation::xml_escape(&request.enqueued_uri),
+            crate::operation::xml_escape(&request.enqueued_uri_meta_data),
             request.desired_first_track_number_enqueued,
             if request.enqueue_as_next { "1" } else { "0" }
         ))
     }
 
     fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, crate::error::ApiError> {
-        Ok(AddURIToQueueResponse {
-            first_track_number_enqueued: xml
-                .get_child("FirstTrackNumberEnqueued")
-                .and_then(|e| e.get_text())
-                .and_then(|s| s.parse().ok())
-                .unwrap_or_default(),
-            num_tracks_added: xml
-                .get_child("NumTracksAdded")
-                .and_then(|e| e.get_text())
+        let count = |name| {
+            crate::operation::opt_child_text(xml, name)
                 .and_then(|s| s.parse().ok())
-                .unwrap_or_default(),
-            new_queue_length: xml
-                .get_child("NewQueueLength")
-                .and_then(|e| e.get_text())
-                .and_then(|s| s.parse().ok())
-                .unwrap_or_default(),
+                .unwrap_or_default()
+        };
+        Ok(AddURIToQueueResponse {
+            first_track_number_enqueued: count("FirstTrackNumberEnqueued"),
+            num_tracks_added: count("NumTracksAdded"),
+            new_queue_length: count("NewQueueLength"),
         })
     }
 }
@@ -880,6 +1072,43 @@ mod tests {
             target: "0:01:30".to_string(),
         };
         assert!(request.validate_basic().is_ok());
+
+        let check = |unit: &str, target: &str| {
+            SeekOperationRequest {
+                instance_id: 0,
+                unit: unit.to_string(),
+                target: target.to_string(),
+            }
+            .validate_basic()
+        };
+        for (unit, target) in [
+            ("REL_TIME", "12:00:59"),
+            ("TIME_DELTA", "+0:00:30"),
+            ("TIME_DELTA", "-0:00:10"),
+            ("TIME_DELTA", "0:01:00"),
+            ("TRACK_NR", "1"),
+        ] {
+            assert!(check(unit, target).is_ok(), "{unit} {target}");
+        }
+        for (unit, target) in [
+            ("REL_TIME", "1:30"),
+            ("REL_TIME", "0:1:30"),
+            ("REL_TIME", "0:60:00"),
+            ("REL_TIME", "0:00:75"),
+            ("REL_TIME", "+0:00:30"),
+            ("REL_TIME", ":00:30"),
+            ("TIME_DELTA", "+-0:00:30"),
+            ("TRACK_NR", "0"),
+            ("TRACK_NR", "three"),
+        ] {
+            assert!(
+                matches!(
+                    check(unit, target),
+                    Err(crate::operation::ValidationError::InvalidValue { .. })
+                ),
+                "{unit} {target}"
+            );
+        }
     }
 
     #[test]
@@ -914,6 +1143,88 @@ mod tests {
         assert_eq!(op.metadata().action, "GetMediaInfo");
     }
 
+    // Old firmware (ZP100) omits fields that newer firmware returns empty
+    const POSITION_INFO_OLD_FIRMWARE: &str = r#"<u:GetPositionInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><Track>0</Track><TrackDuration>NOT_IMPLEMENTED</TrackDuration><RelTime>NOT_IMPLEMENTED</RelTime><AbsTime>NOT_IMPLEMENTED</AbsTime></u:GetPositionInfoResponse>"#;
+    const POSITION_INFO_NEW_FIRMWARE: &str = r#"<u:GetPositionInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><Track>0</Track><TrackDuration>0:00:00</TrackDuration><TrackMetaData></TrackMetaData><TrackURI></TrackURI><RelTime>0:00:00</RelTime><AbsTime>NOT_IMPLEMENTED</AbsTime><RelCount>2147483647</RelCount><AbsCount>2147483647</AbsCount></u:GetPositionInfoResponse>"#;
+    const MEDIA_INFO_OLD_FIRMWARE: &str = r#"<u:GetMediaInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><NrTracks>0</NrTracks><MediaDuration>NOT_IMPLEMENTED</MediaDuration><PlayMedium>NONE</PlayMedium></u:GetMediaInfoResponse>"#;
+    const MEDIA_INFO_NEW_FIRMWARE: &str = r#"<u:GetMediaInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><NrTracks>0</NrTracks><MediaDuration>NOT_IMPLEMENTED</MediaDuration><CurrentURI></CurrentURI><CurrentURIMetaData></CurrentURIMetaData><NextURI></NextURI><NextURIMetaData></NextURIMetaData><PlayMedium>NONE</PlayMedium><RecordMedium>NOT_IMPLEMENTED</RecordMedium><WriteStatus>NOT_IMPLEMENTED</WriteStatus></u:GetMediaInfoResponse>"#;
+
+    fn parse<Op: UPnPOperation>(xml: &str) -> Op::Response {
+        let xml = xmltree::Element::parse(xml.as_bytes()).unwrap();
+        Op::parse_response(&xml).unwrap()
+    }
+
+    #[test]
+    fn test_get_position_info_parses_omitted_and_empty_fields() {
+        let old = parse::<GetPositionInfoOperation>(POSITION_INFO_OLD_FIRMWARE);
+        assert_eq!(old.track_uri, None);
+        assert_eq!(old.track_meta_data, None);
+        assert_eq!((old.rel_count, old.abs_count), (None, None));
+        assert_eq!(old.rel_time, "NOT_IMPLEMENTED");
+
+        let new = parse::<GetPositionInfoOperation>(POSITION_INFO_NEW_FIRMWARE);
+        assert_eq!(new.track_uri.as_deref(), Some(""));
+        assert_eq!(new.track_meta_data, None);
+        assert_eq!(new.rel_count, Some(i32::MAX));
+        assert_eq!(new.track_duration, "0:00:00");
+        assert_eq!(new.track_length(), Some(Duration::ZERO));
+        assert_eq!(new.abs_position(), None);
+    }
+
+    #[test]
+    fn test_get_position_info_times() {
+        let playing = parse::<GetPositionInfoOperation>(
+            r#"<u:GetPositionInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><Track>3</Track><TrackDuration>0:03:58</TrackDuration><TrackMetaData>&lt;DIDL-Lite/&gt;</TrackMetaData><TrackURI>x-file-cifs://nas/song.flac</TrackURI><RelTime>0:01:07</RelTime><AbsTime>NOT_IMPLEMENTED</AbsTime></u:GetPositionInfoResponse>"#,
+        );
+        assert_eq!(playing.track, 3);
+        assert_eq!(playing.track_length(), Some(Duration::from_secs(238)));
+        assert_eq!(playing.rel_position(), Some(Duration::from_secs(67)));
+        assert_eq!(playing.abs_position(), None);
+
+        let line_in = parse::<GetPositionInfoOperation>(
+            r#"<u:GetPositionInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><Track>1</Track><TrackDuration>-1:-1:-1</TrackDuration><TrackURI>x-rincon-stream:RINCON_DEN</TrackURI><RelTime>NOT_IMPLEMENTED</RelTime><AbsTime>-1:-1:-1</AbsTime></u:GetPositionInfoResponse>"#,
+        );
+        assert_eq!(line_in.track_length(), None);
+        assert_eq!(line_in.rel_position(), None);
+        assert_eq!(line_in.abs_position(), None);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("0:00:00"), Some(Duration::ZERO));
+        assert_eq!(parse_time("1:02:03"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse_time("12:00:01"), Some(Duration::from_secs(43201)));
+        assert_eq!(parse_time("0:00:01.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_time("0:00:01.250"), Some(Duration::from_millis(1250)));
+        for sentinel in [
+            "NOT_IMPLEMENTED",
+            "-1:-1:-1",
+            "",
+            "1:60:00",
+            "1:00",
+            "+0:00:01",
+        ] {
+            assert_eq!(parse_time(sentinel), None, "{sentinel:?}");
+        }
+        assert_eq!(parse_time("99999999999999999:00:00"), None);
+    }
+
+    #[test]
+    fn test_get_media_info_parses_omitted_and_empty_fields() {
+        let old = parse::<GetMediaInfoOperation>(MEDIA_INFO_OLD_FIRMWARE);
+        assert_eq!((old.current_uri, old.next_uri), (None, None));
+        assert_eq!(old.current_uri_meta_data, None);
+        assert_eq!((old.record_medium, old.write_status), (None, None));
+        assert_eq!(old.play_medium.as_deref(), Some("NONE"));
+
+        let new = parse::<GetMediaInfoOperation>(MEDIA_INFO_NEW_FIRMWARE);
+        assert_eq!(new.current_uri.as_deref(), Some(""));
+        assert_eq!(new.next_uri.as_deref(), Some(""));
+        assert_eq!(new.current_uri_meta_data, None);
+        assert_eq!(new.next_uri_meta_data, None);
+        assert_eq!(new.write_status.as_deref(), Some("NOT_IMPLEMENTED"));
+    }
+
     #[test]
     fn test_get_transport_settings_builder() {
         let op = get_transport_settings_operation().build().unwrap();
@@ -954,26 +1265,56 @@ mod tests {
 
     #[test]
     fn test_set_play_mode_builder() {
-        let op = set_play_mode_operation("SHUFFLE".to_string())
-            .build()
-            .unwrap();
-        assert_eq!(op.request().new_play_mode, "SHUFFLE");
+        let op = set_play_mode_operation(PlayMode::Shuffle).build().unwrap();
+        assert_eq!(op.request().new_play_mode, PlayMode::Shuffle);
         assert_eq!(op.metadata().action, "SetPlayMode");
+        let payload = SetPlayModeOperation::build_payload(op.request()).unwrap();
+        assert!(payload.contains("<NewPlayMode>SHUFFLE</NewPlayMode>"));
     }
 
     #[test]
-    fn test_set_play_mode_validation() {
-        let request = SetPlayModeOperationRequest {
-            instance_id: 0,
-            new_play_mode: "INVALID".to_string(),
-        };
-        assert!(request.validate_basic().is_err());
+    fn test_play_mode_round_trip() {
+        for mode in PlayMode::ALL {
+            assert_eq!(PlayMode::parse(&mode.to_string()), Some(mode));
+            assert_eq!(mode.as_str().parse::<PlayMode>().unwrap(), mode);
+            let json = serde_json::to_string(&mode).unwrap();
+            assert_eq!(json, format!("\"{mode}\""));
+            assert_eq!(serde_json::from_str::<PlayMode>(&json).unwrap(), mode);
+        }
+        assert_eq!(
+            PlayMode::parse(" shuffle_norepeat "),
+            Some(PlayMode::ShuffleNoRepeat)
+        );
+        assert_eq!(PlayMode::parse("SHUFFLE_REPEAT"), None);
+        assert!(PlayMode::ShuffleRepeatOne.is_shuffle() && !PlayMode::RepeatAll.is_shuffle());
+        for mode in PlayMode::ALL {
+            assert!(mode.with_shuffle(true).is_shuffle(), "{mode}");
+            assert!(!mode.with_shuffle(false).is_shuffle(), "{mode}");
+            assert_eq!(mode.with_shuffle(mode.is_shuffle()), mode);
+        }
+        assert_eq!(PlayMode::RepeatAll.with_shuffle(true), PlayMode::Shuffle);
 
-        let request = SetPlayModeOperationRequest {
-            instance_id: 0,
-            new_play_mode: "REPEAT_ALL".to_string(),
-        };
-        assert!(request.validate_basic().is_ok());
+        let settings = parse::<GetTransportSettingsOperation>(
+            r#"<u:GetTransportSettingsResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><PlayMode>REPEAT_ONE</PlayMode><RecQualityMode>NOT_IMPLEMENTED</RecQualityMode></u:GetTransportSettingsResponse>"#,
+        );
+        assert_eq!(settings.mode(), Some(PlayMode::RepeatOne));
+    }
+
+    #[test]
+    fn test_set_play_mode_validation() {
+        for invalid in ["INVALID", "", "SHUFFLE_REPEAT", "REPEAT ALL"] {
+            let err = invalid.parse::<PlayMode>().unwrap_err();
+            assert!(
+                matches!(
+                    &err,
+                    crate::operation::ValidationError::InvalidValue { parameter, value, .. }
+                        if parameter == "play_mode" && value == invalid
+                ),
+                "{err}"
+            );
+        }
+        let mode: PlayMode = "repeat_all".parse().unwrap();
+        assert!(set_play_mode_operation(mode).build().is_ok());
     }
 
     // --- Sleep Timer Tests ---
@@ -1038,6 +1379,80 @@ mod tests {
 
     // --- Service Tests ---
 
+    // --- Escaping ---
+
+    /// A radio stream URL with query parameters
+    const STREAM_URI: &str = "x-rincon-mp3radio://radio.example.com/live?station=kexp&format=aac";
+
+    /// DIDL-Lite with quoted attributes and non-ASCII text
+    const STREAM_DIDL: &str = r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/"><item id="-1" parentID="-1"><dc:title>Café del Mar — 東京 & "Friends" > 'Others'</dc:title></item></DIDL-Lite>"#;
+
+    /// Each argument's value after the payload is parsed as XML
+    fn round_trip<Op: UPnPOperation>(
+        builder: crate::operation::OperationBuilder<Op>,
+    ) -> Vec<(String, String)> {
+        let operation = builder.build().unwrap();
+        let payload = operation.build_payload().unwrap();
+        assert!(!payload.contains("&format"), "{payload}");
+        assert!(!payload.contains("<DIDL-Lite"), "{payload}");
+        operation.arguments().unwrap()
+    }
+
+    fn value<'a>(arguments: &'a [(String, String)], name: &str) -> &'a str {
+        arguments
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_uri_and_metadata_payloads_are_escaped() {
+        let arguments = round_trip(set_av_transport_uri(
+            STREAM_URI.to_string(),
+            STREAM_DIDL.to_string(),
+        ));
+        assert_eq!(value(&arguments, "CurrentURI"), STREAM_URI);
+        assert_eq!(value(&arguments, "CurrentURIMetaData"), STREAM_DIDL);
+
+        let arguments = round_trip(set_next_av_transport_uri(
+            STREAM_URI.to_string(),
+            STREAM_DIDL.to_string(),
+        ));
+        assert_eq!(value(&arguments, "NextURI"), STREAM_URI);
+        assert_eq!(value(&arguments, "NextURIMetaData"), STREAM_DIDL);
+
+        let arguments = round_trip(add_uri_to_queue_operation(
+            STREAM_URI.to_string(),
+            STREAM_DIDL.to_string(),
+            0,
+            true,
+        ));
+        assert_eq!(value(&arguments, "EnqueuedURI"), STREAM_URI);
+        assert_eq!(value(&arguments, "EnqueuedURIMetaData"), STREAM_DIDL);
+
+        let arguments = round_trip(create_saved_queue_operation(
+            "Mix <B-sides> & 'rarities'".to_string(),
+            STREAM_URI.to_string(),
+            STREAM_DIDL.to_string(),
+        ));
+        assert_eq!(value(&arguments, "Title"), "Mix <B-sides> & 'rarities'");
+        assert!(arguments.iter().any(|(_, v)| v == STREAM_DIDL));
+    }
+
+    #[test]
+    fn test_add_uri_to_queue_payload_escapes_ampersand() {
+        let request = AddURIToQueueOperationRequest {
+            instance_id: 0,
+            enqueued_uri: "http://example.com/a?b=1&c=2".to_string(),
+            enqueued_uri_meta_data: String::new(),
+            desired_first_track_number_enqueued: 0,
+            enqueue_as_next: false,
+        };
+        let payload = AddURIToQueueOperation::build_payload(&request).unwrap();
+        assert!(payload.contains("<EnqueuedURI>http://example.com/a?b=1&amp;c=2</EnqueuedURI>"));
+    }
+
     #[test]
     fn test_service_constant() {
         assert_eq!(SERVICE, crate::Service::AVTransport);
@@ -0,0 +1,128 @@
+//! Radio stations of music services, such as TuneIn
+//!
+//! A station plays by setting its `x-sonosapi-stream:` URI as the transport
+//! URI, together with DIDL-Lite metadata whose `cdudn` desc names the service
+//! (`SA_RINCON<type>_`). Without that desc speakers refuse the stream or play
+//! it without a title. [`Station`] builds both from the station and service
+//! IDs, so callers don't write the XML themselves.
+
+use super::operations::{play, set_av_transport_uri};
+use crate::operation::xml_escape;
+use crate::{ApiError, SonosClient};
+
+/// Music service ID of TuneIn
+pub const TUNEIN_SERVICE_ID: u32 = 254;
+
+/// A station of a music service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Station {
+    /// The service's ID for the station, e.g. `s25419` for TuneIn's BBC
+    /// Radio 4
+    pub station_id: String,
+    /// Music service ID, e.g. [`TUNEIN_SERVICE_ID`]
+    pub service_id: u32,
+    /// Title the speaker shows until the stream sends its own
+    pub title: String,
+}
+
+impl Station {
+    /// A station of the service with ID `service_id`
+    pub fn new(service_id: u32, station_id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            station_id: station_id.into(),
+            service_id,
+            title: title.into(),
+        }
+    }
+
+    /// A TuneIn station, `station_id` being TuneIn's (`s25419`)
+    pub fn tunein(station_id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self::new(TUNEIN_SERVICE_ID, station_id, title)
+    }
+
+    /// Service type in the `cdudn` desc, `service_id * 256 + 7`
+    pub fn service_type(&self) -> u32 {
+        self.service_id * 256 + 7
+    }
+
+    /// Transport URI, unescaped; the SOAP envelope escapes it
+    pub fn uri(&self) -> String {
+        format!(
+            "x-sonosapi-stream:{}?sid={}&flags=8224&sn=0",
+            self.station_id, self.service_id
+        )
+    }
+
+    /// DIDL-Lite metadata to send with [`uri()`](Self::uri)
+    pub fn metadata(&self) -> String {
+        format!(
+            r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="F00092020{}" parentID="L" restricted="true"><dc:title>{}</dc:title><upnp:class>object.item.audioItem.audioBroadcast</upnp:class><desc id="cdudn" nameSpace="urn:schemas-rinconnetworks-com:metadata-1-0/">SA_RINCON{}_</desc></item></DIDL-Lite>"#,
+            xml_escape(&self.station_id),
+            xml_escape(&self.title),
+            self.service_type()
+        )
+    }
+}
+
+/// Play `station` on the speaker at `ip`: `SetAVTransportURI`, then `Play`
+///
+/// The station replaces what was playing; the queue is left as it was.
+pub fn play_station(client: &SonosClient, ip: &str, station: &Station) -> Result<(), ApiError> {
+    let set_uri = set_av_transport_uri(station.uri(), station.metadata()).build()?;
+    client.execute_enhanced(ip, set_uri)?;
+    client.execute_enhanced(ip, play("1".to_string()).build()?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::content_directory::parse_favorites;
+
+    #[test]
+    fn test_tunein_station_matches_a_saved_favorite() {
+        let station = Station::tunein("s25419", "BBC Radio 4 & World");
+        assert_eq!(
+            station.uri(),
+            "x-sonosapi-stream:s25419?sid=254&flags=8224&sn=0"
+        );
+
+        // The favorite the Sonos app saved for the same station
+        let envelope = xmltree::Element::parse(
+            include_str!("../../../tests/fixtures/browse_favorites_response.xml").as_bytes(),
+        )
+        .unwrap();
+        let didl = envelope
+            .get_child("Body")
+            .and_then(|body| body.get_child("BrowseResponse"))
+            .and_then(|response| response.get_child("Result"))
+            .and_then(|result| result.get_text())
+            .unwrap();
+        let favorite = parse_favorites(&didl, 0)
+            .unwrap()
+            .into_iter()
+            .find(|favorite| favorite.title == "BBC Radio 4 & World")
+            .unwrap();
+        assert_eq!(station.uri(), favorite.uri);
+        assert_eq!(station.metadata(), favorite.metadata);
+    }
+
+    #[test]
+    fn test_station_metadata() {
+        let station = Station::tunein("s24940", "Radio Paradise");
+        assert_eq!(
+            station.metadata(),
+            r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="F00092020s24940" parentID="L" restricted="true"><dc:title>Radio Paradise</dc:title><upnp:class>object.item.audioItem.audioBroadcast</upnp:class><desc id="cdudn" nameSpace="urn:schemas-rinconnetworks-com:metadata-1-0/">SA_RINCON65031_</desc></item></DIDL-Lite>"#
+        );
+
+        // Sonos Radio, with a title needing escapes
+        let station = Station::new(303, "sonos:hits", "Hits <1> & More");
+        assert_eq!(
+            station.uri(),
+            "x-sonosapi-stream:sonos:hits?sid=303&flags=8224&sn=0"
+        );
+        let metadata = station.metadata();
+        assert!(metadata.contains("<dc:title>Hits &lt;1&gt; &amp; More</dc:title>"));
+        assert!(metadata.contains(">SA_RINCON77575_</desc>"));
+    }
+}
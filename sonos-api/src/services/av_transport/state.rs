@@ -55,12 +55,16 @@ pub struct AVTransportState {
 
     /// Queue size/length
     pub queue_length: Option<u32>,
+
+    /// Currently allowed transport actions, comma-separated (e.g. "Play, Stop, Next")
+    #[serde(default)]
+    pub transport_actions: Option<String>,
 }
 
 /// Poll a speaker for complete AVTransport state.
 ///
 /// Calls GetTransportInfo (required), GetPositionInfo, GetTransportSettings,
-/// and GetMediaInfo (optional — fall back to None on failure).
+/// GetMediaInfo and GetCurrentTransportActions (optional — fall back to None on failure).
 pub fn poll(client: &SonosClient, ip: &str) -> crate::Result<AVTransportState> {
     let transport = client.execute_enhanced(
         ip,
@@ -81,25 +85,32 @@ pub fn poll(client: &SonosClient, ip: &str) -> crate::Result<AVTransportState> {
         .build()
         .ok()
         .and_then(|op| client.execute_enhanced(ip, op).ok());
+    let actions = super::get_current_transport_actions_operation()
+        .build()
+        .ok()
+        .and_then(|op| client.execute_enhanced(ip, op).ok());
 
     Ok(AVTransportState {
         transport_state: Some(transport.current_transport_state),
         transport_status: Some(transport.current_transport_status),
         speed: Some(transport.current_speed),
-        current_track_uri: position.as_ref().map(|p| p.track_uri.clone()),
+        current_track_uri: position.as_ref().and_then(|p| p.track_uri.clone()),
         track_duration: position.as_ref().map(|p| p.track_duration.clone()),
-        track_metadata: position.as_ref().map(|p| p.track_meta_data.clone()),
+        track_metadata: position.as_ref().and_then(|p| p.track_meta_data.clone()),
         rel_time: position.as_ref().map(|p| p.rel_time.clone()),
         abs_time: position.as_ref().map(|p| p.abs_time.clone()),
         rel_count: position
             .as_ref()
-            .and_then(|p| u32::try_from(p.rel_count).ok()),
+            .and_then(|p| p.rel_count)
+            .and_then(|n| u32::try_from(n).ok()),
         abs_count: position
             .as_ref()
-            .and_then(|p| u32::try_from(p.abs_count).ok()),
+            .and_then(|p| p.abs_count)
+            .and_then(|n| u32::try_from(n).ok()),
         play_mode: settings.map(|s| s.play_mode),
-        next_track_uri: media.as_ref().map(|m| m.next_uri.clone()),
-        next_track_metadata: media.as_ref().map(|m| m.next_uri_meta_data.clone()),
+        next_track_uri: media.as_ref().and_then(|m| m.next_uri.clone()),
+        next_track_metadata: media.as_ref().and_then(|m| m.next_uri_meta_data.clone()),
         queue_length: media.map(|m| m.nr_tracks),
+        transport_actions: actions.map(|a| a.actions),
     })
 }
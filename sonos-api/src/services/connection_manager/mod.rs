@@ -0,0 +1,30 @@
+//! ConnectionManager service for the media formats a speaker can render
+//!
+//! Only `GetProtocolInfo` is covered: its sink list says which transport
+//! protocols and content types the renderer accepts, which lets callers
+//! catch a URI the speaker can't play before sending it.
+//!
+//! # Control Operations
+//! ```rust,ignore
+//! use sonos_api::services::connection_manager::{self, parse_protocol_info_list};
+//!
+//! let op = connection_manager::get_protocol_info().build()?;
+//! let info = client.execute_enhanced("192.168.1.100", op)?;
+//! let sinks = parse_protocol_info_list(&info.sink);
+//! ```
+//!
+//! # Important Notes
+//! - ConnectionManager events aren't parsed; subscribing is not supported
+
+pub mod operations;
+pub mod protocol_info;
+
+// Re-export operations for convenience
+pub use operations::*;
+
+pub use protocol_info::{
+    parse_protocol_info_list, sink_supports_uri, uri_content_formats, uri_protocol, ProtocolInfo,
+};
+
+/// Service constant for ConnectionManager
+pub const SERVICE: crate::Service = crate::Service::ConnectionManager;
@@ -0,0 +1,106 @@
+//! ConnectionManager service operations
+//!
+//! # Operations
+//! - `get_protocol_info` - Read the source and sink protocolInfo lists
+//!
+//! ConnectionManager actions take no `InstanceID`, so these are implemented by
+//! hand rather than with the operation macros.
+
+use crate::operation::{opt_child_text, OperationBuilder, UPnPOperation, ValidationError};
+use crate::{ApiError, Service, Validate};
+use serde::{Deserialize, Serialize};
+
+// =============================================================================
+// GET PROTOCOL INFO OPERATION
+// =============================================================================
+
+/// Request to read the protocolInfo lists
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GetProtocolInfoOperationRequest {}
+
+impl Validate for GetProtocolInfoOperationRequest {}
+
+/// Response carrying the raw protocolInfo lists
+///
+/// Both are comma-separated `protocol:network:contentFormat:additionalInfo`
+/// entries; parse them with
+/// [`parse_protocol_info_list`](super::parse_protocol_info_list). A missing
+/// element is returned as an empty list.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetProtocolInfoResponse {
+    /// Formats the speaker can serve (empty on Sonos speakers)
+    pub source: String,
+    /// Formats the speaker can render
+    pub sink: String,
+}
+
+/// Operation to read the formats the speaker can serve and render
+pub struct GetProtocolInfoOperation;
+
+impl UPnPOperation for GetProtocolInfoOperation {
+    type Request = GetProtocolInfoOperationRequest;
+    type Response = GetProtocolInfoResponse;
+
+    const SERVICE: Service = Service::ConnectionManager;
+    const ACTION: &'static str = "GetProtocolInfo";
+
+    fn build_payload(_request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(String::new())
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        Ok(GetProtocolInfoResponse {
+            source: opt_child_text(xml, "Source").unwrap_or_default(),
+            sink: opt_child_text(xml, "Sink").unwrap_or_default(),
+        })
+    }
+}
+
+/// Create a GetProtocolInfo operation builder
+pub fn get_protocol_info_operation() -> OperationBuilder<GetProtocolInfoOperation> {
+    OperationBuilder::new(GetProtocolInfoOperationRequest {})
+}
+
+// =============================================================================
+// LEGACY ALIASES
+// =============================================================================
+
+pub use get_protocol_info_operation as get_protocol_info;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_protocol_info_payload() {
+        let op = get_protocol_info_operation().build().unwrap();
+        assert_eq!(op.metadata().action, "GetProtocolInfo");
+        assert_eq!(op.metadata().service, "ConnectionManager");
+        assert_eq!(
+            GetProtocolInfoOperation::build_payload(op.request()).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_get_protocol_info_response() {
+        let xml = xmltree::Element::parse(
+            r#"<u:GetProtocolInfoResponse xmlns:u="urn:schemas-upnp-org:service:ConnectionManager:1"><Source></Source><Sink>http-get:*:audio/mpeg:*,x-rincon-mp3radio:*:*:*</Sink></u:GetProtocolInfoResponse>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let response = GetProtocolInfoOperation::parse_response(&xml).unwrap();
+        assert_eq!(response.source, "");
+        assert_eq!(
+            response.sink,
+            "http-get:*:audio/mpeg:*,x-rincon-mp3radio:*:*:*"
+        );
+
+        let empty = xmltree::Element::parse(
+            r#"<u:GetProtocolInfoResponse xmlns:u="urn:mock"/>"#.as_bytes(),
+        )
+        .unwrap();
+        let response = GetProtocolInfoOperation::parse_response(&empty).unwrap();
+        assert!(response.sink.is_empty());
+    }
+}
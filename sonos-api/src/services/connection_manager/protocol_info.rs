@@ -0,0 +1,294 @@
+//! Typed protocolInfo entries and URI matching
+//!
+//! A protocolInfo entry is `protocol:network:contentFormat:additionalInfo`,
+//! for example `http-get:*:audio/mpeg:*`, where `*` matches anything. Lists
+//! separate entries with commas; a comma inside an entry is escaped as `\,`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ApiError;
+
+/// One `protocol:network:contentFormat:additionalInfo` entry
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProtocolInfo {
+    /// Transport protocol, e.g. `http-get` or `x-rincon-mp3radio`
+    pub protocol: String,
+    /// Network the protocol applies to; `*` on every Sonos entry
+    pub network: String,
+    /// MIME type such as `audio/flac`, or `*` for any
+    pub content_format: String,
+    /// Protocol-specific details such as DLNA flags; `*` when there are none
+    pub additional_info: String,
+}
+
+impl ProtocolInfo {
+    /// Whether this entry accepts `protocol` (compared exactly) with any
+    /// of `content_formats` (compared ignoring ASCII case and MIME parameters)
+    pub fn accepts(&self, protocol: &str, content_formats: &[&str]) -> bool {
+        if self.protocol != "*" && self.protocol != protocol {
+            return false;
+        }
+        if self.content_format == "*" {
+            return true;
+        }
+        let essence = self
+            .content_format
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim();
+        content_formats
+            .iter()
+            .any(|format| essence.eq_ignore_ascii_case(format))
+    }
+}
+
+impl FromStr for ProtocolInfo {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.trim().splitn(4, ':').map(str::trim);
+        match (fields.next(), fields.next(), fields.next(), fields.next()) {
+            (Some(protocol), Some(network), Some(content_format), Some(additional_info))
+                if !protocol.is_empty() && !content_format.is_empty() =>
+            {
+                Ok(Self {
+                    protocol: protocol.to_string(),
+                    network: network.to_string(),
+                    content_format: content_format.to_string(),
+                    additional_info: additional_info.to_string(),
+                })
+            }
+            _ => Err(ApiError::ParseError(format!(
+                "Invalid protocolInfo entry: {s:?}"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ProtocolInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.protocol, self.network, self.content_format, self.additional_info
+        )
+    }
+}
+
+/// Parse a comma-separated protocolInfo list
+///
+/// Empty and malformed entries are skipped, so a partly garbled list still
+/// yields the entries that could be read.
+pub fn parse_protocol_info_list(list: &str) -> Vec<ProtocolInfo> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut chars = list.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(',') => current.push(','),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            ',' => entries.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    entries.push(current);
+    entries
+        .iter()
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| entry.parse().ok())
+        .collect()
+}
+
+/// The protocolInfo protocol a URI is played over
+///
+/// `http`/`https` map to `http-get`; Sonos schemes such as
+/// `x-rincon-mp3radio` are their own protocol. `None` when the URI has no
+/// scheme.
+pub fn uri_protocol(uri: &str) -> Option<String> {
+    let (scheme, _) = uri.trim().split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid {
+        return None;
+    }
+    let scheme = scheme.to_ascii_lowercase();
+    Some(match scheme.as_str() {
+        "http" | "https" => "http-get".to_string(),
+        _ => scheme,
+    })
+}
+
+/// Content formats a URI's file extension stands for
+///
+/// Several MIME types are in use for most formats, so all of them are
+/// returned, most common first. `None` when the extension is missing or not
+/// an audio format this crate knows.
+pub fn uri_content_formats(uri: &str) -> Option<&'static [&'static str]> {
+    let path = uri.split(['?', '#']).next().unwrap_or_default();
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let (_, extension) = name.rsplit_once('.')?;
+    Some(match extension.to_ascii_lowercase().as_str() {
+        "mp3" => &["audio/mpeg", "audio/mp3"],
+        "m4a" | "mp4" | "aac" => &["audio/mp4", "audio/x-m4a", "audio/aac"],
+        "flac" => &["audio/flac", "audio/x-flac"],
+        "ogg" | "oga" => &["application/ogg", "audio/ogg"],
+        "wav" => &["audio/wav", "audio/x-wav"],
+        "aif" | "aiff" => &["audio/aiff", "audio/x-aiff"],
+        "wma" => &["audio/x-ms-wma"],
+        "m3u" => &["audio/mpegurl", "audio/x-mpegurl"],
+        _ => return None,
+    })
+}
+
+/// Whether a sink list accepts `uri`
+///
+/// `None` when the list can't tell: the URI's scheme or content type can't
+/// be worked out, or no entry covers its protocol at all (Sonos leaves many
+/// of its own schemes off the list but plays them anyway).
+pub fn sink_supports_uri(sinks: &[ProtocolInfo], uri: &str) -> Option<bool> {
+    let protocol = uri_protocol(uri)?;
+    let formats = uri_content_formats(uri)?;
+    let mut covering = sinks
+        .iter()
+        .filter(|sink| sink.protocol == "*" || sink.protocol == protocol)
+        .peekable();
+    covering.peek()?;
+    Some(covering.any(|sink| sink.accepts(&protocol, formats)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sink list reported by a Sonos One (S2)
+    const SONOS_ONE_SINK: &str = "http-get:*:audio/mpegurl:*,x-file-cifs:*:audio/mpegurl:*,\
+        x-rincon-mp3radio:*:*:*,http-get:*:audio/mp4:*,x-file-cifs:*:audio/mp4:*,\
+        http-get:*:audio/mpeg:*,x-file-cifs:*:audio/mpeg:*,http-get:*:audio/x-ms-wma:*,\
+        x-file-cifs:*:audio/x-ms-wma:*,http-get:*:application/ogg:*,\
+        x-file-cifs:*:application/ogg:*,http-get:*:audio/flac:*,x-file-cifs:*:audio/flac:*,\
+        http-get:*:audio/x-flac:*,http-get:*:audio/wav:*,http-get:*:audio/x-wav:*,\
+        http-get:*:audio/aiff:*,http-get:*:audio/x-aiff:*,x-rincon:*:*:*,\
+        x-rincon-queue:*:*:*,x-rincon-stream:*:*:*,x-sonosapi-stream:*:*:*,\
+        x-sonosapi-radio:*:*:*,x-sonosapi-hls:*:*:*,x-sonos-http:*:*:*,\
+        aac:*:application/octet-stream:*,x-rincon-playlist:*:*:*";
+
+    /// Sink list of a DLNA renderer, with MIME parameters and DLNA flags
+    const DLNA_SINK: &str = "http-get:*:audio/L16;rate=44100;channels=2:DLNA.ORG_PN=LPCM,\
+        http-get:*:audio/mpeg:DLNA.ORG_PN=MP3;DLNA.ORG_OP=01;DLNA.ORG_FLAGS=01700000000000000000000000000000,\
+        http-get:*:audio/vnd.dlna.adts:DLNA.ORG_PN=AAC_ADTS";
+
+    #[test]
+    fn test_parse_sonos_sink_list() {
+        let sinks = parse_protocol_info_list(SONOS_ONE_SINK);
+        assert_eq!(sinks.len(), 27);
+        assert_eq!(
+            sinks[0],
+            ProtocolInfo {
+                protocol: "http-get".to_string(),
+                network: "*".to_string(),
+                content_format: "audio/mpegurl".to_string(),
+                additional_info: "*".to_string(),
+            }
+        );
+        assert_eq!(sinks[2].protocol, "x-rincon-mp3radio");
+        assert_eq!(sinks[2].content_format, "*");
+        assert_eq!(sinks[25].to_string(), "aac:*:application/octet-stream:*");
+    }
+
+    #[test]
+    fn test_parse_dlna_sink_list() {
+        let sinks = parse_protocol_info_list(DLNA_SINK);
+        assert_eq!(sinks.len(), 3);
+        assert_eq!(sinks[0].content_format, "audio/L16;rate=44100;channels=2");
+        assert_eq!(sinks[0].additional_info, "DLNA.ORG_PN=LPCM");
+        assert_eq!(
+            sinks[1].additional_info,
+            "DLNA.ORG_PN=MP3;DLNA.ORG_OP=01;DLNA.ORG_FLAGS=01700000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_parse_tolerates_malformed_lists() {
+        let sinks = parse_protocol_info_list(
+            " http-get:*:audio/mpeg:* , ,garbage,http-get:*:audio/flac:*,",
+        );
+        assert_eq!(sinks.len(), 2);
+        assert_eq!(sinks[1].content_format, "audio/flac");
+
+        let escaped = parse_protocol_info_list(r"http-get:*:audio/mpeg:a\,b,x-rincon:*:*:*");
+        assert_eq!(escaped.len(), 2);
+        assert_eq!(escaped[0].additional_info, "a,b");
+
+        for list in ["", ",", "\\", ":::", "a:b", "http-get:*:audio/mpeg:*\\"] {
+            let _ = parse_protocol_info_list(list);
+        }
+        assert!(parse_protocol_info_list(":::").is_empty());
+        assert!("http-get:*:audio/mpeg".parse::<ProtocolInfo>().is_err());
+    }
+
+    #[test]
+    fn test_uri_protocol_and_formats() {
+        assert_eq!(
+            uri_protocol("http://host/a.mp3").as_deref(),
+            Some("http-get")
+        );
+        assert_eq!(
+            uri_protocol("HTTPS://host/a.mp3").as_deref(),
+            Some("http-get")
+        );
+        assert_eq!(
+            uri_protocol("x-rincon-mp3radio://stream.example.com/live").as_deref(),
+            Some("x-rincon-mp3radio")
+        );
+        assert_eq!(uri_protocol("no scheme"), None);
+        assert_eq!(uri_protocol("/local/path:colon"), None);
+
+        assert_eq!(
+            uri_content_formats("http://host/Song.FLAC?token=1#t=5"),
+            Some(&["audio/flac", "audio/x-flac"][..])
+        );
+        assert_eq!(uri_content_formats("http://host.example.com/stream"), None);
+        assert_eq!(uri_content_formats("http://host/video.mkv"), None);
+    }
+
+    #[test]
+    fn test_sink_supports_uri() {
+        let sonos = parse_protocol_info_list(SONOS_ONE_SINK);
+        assert_eq!(
+            sink_supports_uri(&sonos, "http://nas/music/a.flac"),
+            Some(true)
+        );
+        assert_eq!(sink_supports_uri(&sonos, "https://cdn/a.m4a"), Some(true));
+        assert_eq!(sink_supports_uri(&sonos, "http://nas/a.wma"), Some(true));
+        assert_eq!(
+            sink_supports_uri(&sonos, "x-file-cifs://nas/share/a.mp3"),
+            Some(true)
+        );
+        assert_eq!(
+            sink_supports_uri(&sonos, "x-rincon-mp3radio://radio/a.mp3"),
+            Some(true)
+        );
+        // No extension, unknown extension, or a scheme the list doesn't cover
+        assert_eq!(sink_supports_uri(&sonos, "http://radio/live"), None);
+        assert_eq!(sink_supports_uri(&sonos, "http://nas/a.mkv"), None);
+        assert_eq!(
+            sink_supports_uri(&sonos, "x-sonos-vli:RINCON_1:2.mp3"),
+            None
+        );
+
+        let dlna = parse_protocol_info_list(DLNA_SINK);
+        assert_eq!(sink_supports_uri(&dlna, "http://nas/a.mp3"), Some(true));
+        assert_eq!(sink_supports_uri(&dlna, "http://nas/a.flac"), Some(false));
+        assert_eq!(sink_supports_uri(&[], "http://nas/a.mp3"), None);
+    }
+}
@@ -0,0 +1,47 @@
+//! ContentDirectory service for browsing what a speaker holds
+//!
+//! Only `Browse` is covered, enough to read the speaker's queue (`Q:0`),
+//! the household's Sonos Favorites (`FV:2`) and Sonos playlists (`SQ:`).
+//! Results arrive as DIDL-Lite and are parsed into [`QueueItem`]s,
+//! [`Favorite`]s and [`Playlist`]s.
+//!
+//! # Control Operations
+//! ```rust,ignore
+//! use sonos_api::services::content_directory;
+//!
+//! // One page: the first 20 queue entries
+//! let op = content_directory::browse_queue(0, 20).build()?;
+//! let page = client.execute_enhanced("192.168.1.100", op)?;
+//! println!("{} of {} entries", page.number_returned, page.total_matches);
+//!
+//! // The whole queue, however long
+//! let queue: Vec<_> = content_directory::queue_pages(&client, "192.168.1.100")
+//!     .collect::<Result<Vec<_>, _>>()?
+//!     .into_iter()
+//!     .flat_map(|page| page.items)
+//!     .collect();
+//!
+//! // Favorites carry the URI and metadata to play them with
+//! for favorite in content_directory::list_favorites(&client, "192.168.1.100")? {
+//!     println!("{}: {}", favorite.ordinal, favorite.title);
+//! }
+//! ```
+//!
+//! # Important Notes
+//! - Speakers return at most [`MAX_BROWSE_COUNT`] entries per Browse; see
+//!   [`pages`] for reading past that
+//! - Favorites that are containers (albums, service playlists) and Sonos
+//!   playlists play by adding them to the queue; see
+//!   [`Favorite::plays_from_queue()`]
+//! - ContentDirectory events aren't parsed; subscribing is not supported
+
+pub mod operations;
+pub mod pages;
+
+// Re-export operations for convenience
+pub use operations::*;
+
+pub use pages::{browse_pages, list_favorites, list_playlists, queue_pages, BrowsePages};
+
+/// Service constant for ContentDirectory
+pub const SERVICE: crate::Service = crate::Service::ContentDirectory;
@@ -0,0 +1,472 @@
+//! ContentDirectory service operations
+//!
+//! # Operations
+//! - `browse` - Read a page of a container's children, or one object's
+//!   metadata
+//! - `browse_queue` - Read a page of the speaker's queue (`Q:0`)
+//!
+//! Favorites (`FV:2`) and saved queues (`SQ:`) are browsed with `browse`
+//! and parsed with [`parse_favorites`] and [`parse_playlists`].
+//!
+//! ContentDirectory actions take no `InstanceID`, so these are implemented
+//! by hand rather than with the operation macros.
+
+use std::fmt;
+
+use crate::events::{DidlItem, DidlLite};
+use crate::operation::{opt_child_text, OperationBuilder, UPnPOperation, ValidationError};
+use crate::{ApiError, Service, Validate};
+use serde::{Deserialize, Serialize};
+
+/// Object ID of the speaker's queue
+pub const QUEUE_OBJECT_ID: &str = "Q:0";
+
+/// Object ID of the household's Sonos Favorites
+pub const FAVORITES_OBJECT_ID: &str = "FV:2";
+
+/// Object ID of the household's Sonos playlists (saved queues)
+pub const SAVED_QUEUES_OBJECT_ID: &str = "SQ:";
+
+/// Most entries a speaker returns from one Browse, whatever was requested
+pub const MAX_BROWSE_COUNT: u32 = 100;
+
+/// What a Browse returns for its object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BrowseFlag {
+    /// The object itself
+    Metadata,
+    /// The object's children, in order
+    DirectChildren,
+}
+
+impl fmt::Display for BrowseFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrowseFlag::Metadata => f.write_str("BrowseMetadata"),
+            BrowseFlag::DirectChildren => f.write_str("BrowseDirectChildren"),
+        }
+    }
+}
+
+/// One entry of the queue, or any other browsed item
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct QueueItem {
+    /// Object ID, `Q:0/1` for the first queue entry
+    pub id: String,
+    pub title: Option<String>,
+    /// `dc:creator`
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Often relative to the speaker, e.g. `/getaa?s=1&u=...`
+    pub album_art_uri: Option<String>,
+    /// `H:MM:SS`, absent for streams
+    pub duration: Option<String>,
+    /// URI the speaker plays
+    pub uri: Option<String>,
+}
+
+impl From<DidlItem> for QueueItem {
+    fn from(item: DidlItem) -> Self {
+        let resource = item.resources.into_iter().next().unwrap_or_default();
+        QueueItem {
+            id: item.id,
+            title: item.title,
+            artist: item.creator,
+            album: item.album,
+            album_art_uri: item.album_art_uri,
+            duration: resource.duration,
+            uri: resource.uri,
+        }
+    }
+}
+
+/// Parse the DIDL-Lite `Result` of a Browse response into its items
+pub fn parse_browse_result(didl: &str) -> Result<Vec<QueueItem>, ApiError> {
+    Ok(parse_didl(didl)?
+        .items
+        .into_iter()
+        .map(QueueItem::from)
+        .collect())
+}
+
+/// One of the household's Sonos Favorites
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Favorite {
+    /// Object ID, e.g. `FV:2/13`
+    pub id: String,
+    /// Position the Sonos app lists it at (`r:ordinal`), or its position in
+    /// the Browse result, from 0, when the speaker doesn't say
+    pub ordinal: u32,
+    pub title: String,
+    /// URI to play, for `SetAVTransportURI` or, when
+    /// [`plays_from_queue()`](Self::plays_from_queue), `AddURIToQueue`
+    pub uri: String,
+    /// DIDL-Lite metadata to send along with `uri` (`r:resMD`)
+    pub metadata: String,
+    /// Where it comes from, e.g. `TuneIn` or `Spotify Album`
+    pub description: Option<String>,
+    pub album_art_uri: Option<String>,
+}
+
+impl Favorite {
+    fn from_didl(item: DidlItem, position: u32) -> Self {
+        Favorite {
+            ordinal: item
+                .ordinal
+                .as_deref()
+                .and_then(|ordinal| ordinal.trim().parse().ok())
+                .unwrap_or(position),
+            uri: item
+                .resources
+                .into_iter()
+                .next()
+                .and_then(|res| res.uri)
+                .unwrap_or_default(),
+            id: item.id,
+            title: item.title.unwrap_or_default(),
+            metadata: item.res_md.unwrap_or_default(),
+            description: item.description,
+            album_art_uri: item.album_art_uri,
+        }
+    }
+
+    /// Whether it is a container, such as an album or a service playlist,
+    /// which plays by adding it to the queue rather than as the transport
+    /// URI
+    pub fn plays_from_queue(&self) -> bool {
+        self.uri.starts_with("x-rincon-cpcontainer:")
+            || self.uri.starts_with("file:")
+            || DidlLite::from_xml(&self.metadata)
+                .ok()
+                .and_then(|didl| didl.items.into_iter().next())
+                .and_then(|item| item.class)
+                .is_some_and(|class| class.starts_with("object.container"))
+    }
+}
+
+/// One of the household's Sonos playlists (a saved queue)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Playlist {
+    /// Object ID, e.g. `SQ:3`
+    pub id: String,
+    /// Position in the Browse result, from 0
+    pub ordinal: u32,
+    pub title: String,
+    /// URI to add to the queue, e.g. `file:///jffs/settings/savedqueues.rsq#3`
+    pub uri: String,
+    /// DIDL-Lite metadata to send along with `uri`
+    pub metadata: String,
+    pub album_art_uri: Option<String>,
+}
+
+impl Playlist {
+    fn from_didl(item: DidlItem, position: u32) -> Self {
+        let escape = crate::operation::xml_escape;
+        let metadata = format!(
+            r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="{}" parentID="{}" restricted="true"><dc:title>{}</dc:title><upnp:class>object.container.playlistContainer</upnp:class><desc id="cdudn" nameSpace="urn:schemas-rinconnetworks-com:metadata-1-0/">RINCON_AssociatedZPUDN</desc></item></DIDL-Lite>"#,
+            escape(&item.id),
+            escape(&item.parent_id),
+            escape(item.title.as_deref().unwrap_or_default()),
+        );
+        Playlist {
+            ordinal: position,
+            uri: item
+                .resources
+                .into_iter()
+                .next()
+                .and_then(|res| res.uri)
+                .unwrap_or_default(),
+            id: item.id,
+            title: item.title.unwrap_or_default(),
+            metadata,
+            album_art_uri: item.album_art_uri,
+        }
+    }
+}
+
+fn parse_didl(didl: &str) -> Result<DidlLite, ApiError> {
+    if didl.trim().is_empty() {
+        return Ok(DidlLite {
+            items: Vec::new(),
+            containers: Vec::new(),
+        });
+    }
+    DidlLite::from_xml(didl)
+        .map_err(|e| ApiError::ParseError(format!("Invalid Browse result: {e}")))
+}
+
+/// Parse the DIDL-Lite `Result` of a `FV:2` Browse into favorites, counting
+/// positions from `first_index`
+pub fn parse_favorites(didl: &str, first_index: u32) -> Result<Vec<Favorite>, ApiError> {
+    Ok(parse_didl(didl)?
+        .items
+        .into_iter()
+        .zip(first_index..)
+        .map(|(item, position)| Favorite::from_didl(item, position))
+        .collect())
+}
+
+/// Parse the DIDL-Lite `Result` of an `SQ:` Browse into playlists, counting
+/// positions from `first_index`
+pub fn parse_playlists(didl: &str, first_index: u32) -> Result<Vec<Playlist>, ApiError> {
+    Ok(parse_didl(didl)?
+        .containers
+        .into_iter()
+        .zip(first_index..)
+        .map(|(item, position)| Playlist::from_didl(item, position))
+        .collect())
+}
+
+// =============================================================================
+// BROWSE OPERATION
+// =============================================================================
+
+/// Request to read a page of a ContentDirectory object
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BrowseOperationRequest {
+    /// `Q:0` for the queue
+    pub object_id: String,
+    pub browse_flag: BrowseFlag,
+    /// Comma-separated properties to include, `*` for all
+    pub filter: String,
+    /// Index of the first child to return, from 0
+    pub starting_index: u32,
+    /// How many children to return, 0 for as many as the speaker allows
+    /// (at most [`MAX_BROWSE_COUNT`])
+    pub requested_count: u32,
+    pub sort_criteria: String,
+}
+
+impl Validate for BrowseOperationRequest {
+    fn validate_basic(&self) -> Result<(), ValidationError> {
+        if self.object_id.trim().is_empty() {
+            return Err(ValidationError::MissingParameter {
+                parameter: "object_id".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Response carrying a page of a ContentDirectory object
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BrowseResponse {
+    /// Items of the page, parsed from `result`
+    pub items: Vec<QueueItem>,
+    /// The DIDL-Lite document as sent, containers included
+    pub result: String,
+    pub number_returned: u32,
+    /// Children the object has in all, across pages
+    pub total_matches: u32,
+    /// Changes whenever the object's children change
+    pub update_id: u32,
+}
+
+/// Operation to read a page of a ContentDirectory object
+pub struct BrowseOperation;
+
+impl UPnPOperation for BrowseOperation {
+    type Request = BrowseOperationRequest;
+    type Response = BrowseResponse;
+
+    const SERVICE: Service = Service::ContentDirectory;
+    const ACTION: &'static str = "Browse";
+
+    fn build_payload(request: &Self::Request) -> Result<String, ValidationError> {
+        request.validate(crate::operation::ValidationLevel::Basic)?;
+        let escape = crate::operation::xml_escape;
+        Ok(format!(
+            "<ObjectID>{}</ObjectID><BrowseFlag>{}</BrowseFlag><Filter>{}</Filter>\
+             <StartingIndex>{}</StartingIndex><RequestedCount>{}</RequestedCount>\
+             <SortCriteria>{}</SortCriteria>",
+            escape(&request.object_id),
+            request.browse_flag,
+            escape(&request.filter),
+            request.starting_index,
+            request.requested_count,
+            escape(&request.sort_criteria),
+        ))
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        let result = opt_child_text(xml, "Result")
+            .ok_or_else(|| ApiError::ParseError("Missing Result element".to_string()))?;
+        let number = |name: &str| {
+            opt_child_text(xml, name)
+                .and_then(|text| text.trim().parse().ok())
+                .unwrap_or_default()
+        };
+        Ok(BrowseResponse {
+            items: parse_browse_result(&result)?,
+            number_returned: number("NumberReturned"),
+            total_matches: number("TotalMatches"),
+            update_id: number("UpdateID"),
+            result,
+        })
+    }
+}
+
+/// Create a Browse operation builder for every property of `object_id`'s
+/// children from `starting_index`
+pub fn browse_operation(
+    object_id: String,
+    starting_index: u32,
+    requested_count: u32,
+) -> OperationBuilder<BrowseOperation> {
+    OperationBuilder::new(BrowseOperationRequest {
+        object_id,
+        browse_flag: BrowseFlag::DirectChildren,
+        filter: "*".to_string(),
+        starting_index,
+        requested_count,
+        sort_criteria: String::new(),
+    })
+}
+
+/// Create a Browse operation builder for a page of the queue
+pub fn browse_queue_operation(
+    starting_index: u32,
+    requested_count: u32,
+) -> OperationBuilder<BrowseOperation> {
+    browse_operation(QUEUE_OBJECT_ID.to_string(), starting_index, requested_count)
+}
+
+// =============================================================================
+// LEGACY ALIASES
+// =============================================================================
+
+pub use browse_operation as browse;
+pub use browse_queue_operation as browse_queue;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_browse_payload() {
+        let op = browse_queue_operation(100, 50).build().unwrap();
+        assert_eq!(op.metadata().action, "Browse");
+        assert_eq!(op.metadata().service, "ContentDirectory");
+        assert_eq!(
+            BrowseOperation::build_payload(op.request()).unwrap(),
+            "<ObjectID>Q:0</ObjectID><BrowseFlag>BrowseDirectChildren</BrowseFlag><Filter>*</Filter>\
+             <StartingIndex>100</StartingIndex><RequestedCount>50</RequestedCount>\
+             <SortCriteria></SortCriteria>"
+        );
+        assert!(browse_operation(" ".to_string(), 0, 0).build().is_err());
+    }
+
+    #[test]
+    fn test_browse_queue_response() {
+        let xml = xmltree::Element::parse(
+            r#"<u:BrowseResponse xmlns:u="urn:schemas-upnp-org:service:ContentDirectory:1"><Result>&lt;DIDL-Lite xmlns:dc=&quot;http://purl.org/dc/elements/1.1/&quot; xmlns:upnp=&quot;urn:schemas-upnp-org:metadata-1-0/upnp/&quot; xmlns:r=&quot;urn:schemas-rinconnetworks-com:metadata-1-0/&quot; xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/&quot;&gt;&lt;item id=&quot;Q:0/1&quot; parentID=&quot;Q:0&quot; restricted=&quot;true&quot;&gt;&lt;res protocolInfo=&quot;sonos.com-http:*:audio/mp4:*&quot; duration=&quot;0:04:12&quot;&gt;x-sonos-http:track%3a1.mp4?sid=204&amp;amp;flags=8224&lt;/res&gt;&lt;upnp:albumArtURI&gt;/getaa?s=1&amp;amp;u=x-sonos-http%3atrack%253a1.mp4&lt;/upnp:albumArtURI&gt;&lt;dc:title&gt;Song &amp;amp; Dance&lt;/dc:title&gt;&lt;upnp:class&gt;object.item.audioItem.musicTrack&lt;/upnp:class&gt;&lt;dc:creator&gt;The Artist&lt;/dc:creator&gt;&lt;upnp:album&gt;The Album&lt;/upnp:album&gt;&lt;/item&gt;&lt;item id=&quot;Q:0/2&quot; parentID=&quot;Q:0&quot; restricted=&quot;true&quot;&gt;&lt;res protocolInfo=&quot;x-rincon-mp3radio:*:*:*&quot;&gt;x-rincon-mp3radio://radio.example/live&lt;/res&gt;&lt;dc:title&gt;Live&lt;/dc:title&gt;&lt;/item&gt;&lt;/DIDL-Lite&gt;</Result><NumberReturned>2</NumberReturned><TotalMatches>7</TotalMatches><UpdateID>13</UpdateID></u:BrowseResponse>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let page = BrowseOperation::parse_response(&xml).unwrap();
+        assert_eq!(
+            (page.number_returned, page.total_matches, page.update_id),
+            (2, 7, 13)
+        );
+        assert_eq!(
+            page.items[0],
+            QueueItem {
+                id: "Q:0/1".to_string(),
+                title: Some("Song & Dance".to_string()),
+                artist: Some("The Artist".to_string()),
+                album: Some("The Album".to_string()),
+                album_art_uri: Some("/getaa?s=1&u=x-sonos-http%3atrack%253a1.mp4".to_string()),
+                duration: Some("0:04:12".to_string()),
+                uri: Some("x-sonos-http:track%3a1.mp4?sid=204&flags=8224".to_string()),
+            }
+        );
+        let radio = &page.items[1];
+        assert_eq!(radio.title.as_deref(), Some("Live"));
+        assert_eq!(
+            (radio.artist.as_ref(), radio.duration.as_ref()),
+            (None, None)
+        );
+
+        let empty = xmltree::Element::parse(
+            r#"<u:BrowseResponse xmlns:u="urn:mock"><Result></Result><NumberReturned>0</NumberReturned><TotalMatches>0</TotalMatches><UpdateID>1</UpdateID></u:BrowseResponse>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        assert!(BrowseOperation::parse_response(&empty)
+            .unwrap()
+            .items
+            .is_empty());
+        assert!(parse_browse_result("<DIDL-Lite><item").is_err());
+    }
+
+    fn fixture_result(xml: &str) -> BrowseResponse {
+        let envelope = xmltree::Element::parse(xml.as_bytes()).unwrap();
+        let response = envelope
+            .get_child("Body")
+            .and_then(|body| body.get_child("BrowseResponse"))
+            .unwrap();
+        BrowseOperation::parse_response(response).unwrap()
+    }
+
+    #[test]
+    fn test_favorites_fixture() {
+        let page = fixture_result(include_str!(
+            "../../../tests/fixtures/browse_favorites_response.xml"
+        ));
+        let favorites = parse_favorites(&page.result, 0).unwrap();
+        let titles: Vec<_> = favorites.iter().map(|f| f.title.as_str()).collect();
+        assert_eq!(titles, ["BBC Radio 4 & World", "Blue Lines", "Teardrop"]);
+
+        let radio = &favorites[0];
+        assert_eq!((radio.id.as_str(), radio.ordinal), ("FV:2/13", 1));
+        assert_eq!(
+            radio.uri,
+            "x-sonosapi-stream:s25419?sid=254&flags=8224&sn=0"
+        );
+        assert_eq!(radio.description.as_deref(), Some("TuneIn"));
+        // r:resMD is unescaped once, leaving a DIDL-Lite document to send
+        assert!(radio
+            .metadata
+            .starts_with(r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/""#));
+        assert!(radio
+            .metadata
+            .contains("<dc:title>BBC Radio 4 &amp; World</dc:title>"));
+        assert!(!radio.plays_from_queue());
+
+        let album = &favorites[1];
+        assert_eq!(album.ordinal, 0);
+        assert!(album.plays_from_queue());
+        assert!(!favorites[2].plays_from_queue());
+
+        // Without r:ordinal, the position counts from the page's first index
+        let bare = r#"<DIDL-Lite><item id="FV:2/1" parentID="FV:2"><dc:title>Bare</dc:title></item></DIDL-Lite>"#;
+        let favorite = &parse_favorites(bare, 100).unwrap()[0];
+        assert_eq!((favorite.ordinal, favorite.uri.as_str()), (100, ""));
+    }
+
+    #[test]
+    fn test_playlists_fixture() {
+        let page = fixture_result(include_str!(
+            "../../../tests/fixtures/browse_saved_queues_response.xml"
+        ));
+        assert!(page.items.is_empty());
+        let playlists = parse_playlists(&page.result, 0).unwrap();
+        assert_eq!(playlists.len(), 2);
+        let dinner = &playlists[0];
+        assert_eq!(
+            (dinner.id.as_str(), dinner.ordinal, dinner.title.as_str()),
+            ("SQ:3", 0, "Dinner Party")
+        );
+        assert_eq!(dinner.uri, "file:///jffs/settings/savedqueues.rsq#3");
+        assert!(dinner
+            .album_art_uri
+            .as_deref()
+            .unwrap()
+            .starts_with("/getaa?"));
+        assert!(dinner.metadata.contains(
+            r#"<item id="SQ:3" parentID="SQ:" restricted="true"><dc:title>Dinner Party</dc:title>"#
+        ));
+        assert_eq!(playlists[1].ordinal, 1);
+        assert_eq!(playlists[1].album_art_uri, None);
+    }
+}
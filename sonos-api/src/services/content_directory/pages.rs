@@ -0,0 +1,120 @@
+//! Reading a whole container page by page
+//!
+//! A speaker returns at most [`MAX_BROWSE_COUNT`] children per Browse, so
+//! long queues take several calls. [`BrowsePages`] makes them in turn,
+//! starting each page where the last one ended, until `TotalMatches` is
+//! reached.
+
+use super::operations::{
+    browse_operation, parse_favorites, parse_playlists, BrowseResponse, Favorite, Playlist,
+    FAVORITES_OBJECT_ID, MAX_BROWSE_COUNT, QUEUE_OBJECT_ID, SAVED_QUEUES_OBJECT_ID,
+};
+use crate::{ApiError, SonosClient};
+
+/// Iterator over the pages of a container, one Browse per page
+///
+/// Stops after the page that reaches the last reported `TotalMatches`, after
+/// an empty page, or after the first error, which it yields. If the container
+/// changes between pages, later pages reflect the change; compare
+/// [`update_id`](BrowseResponse::update_id) across pages to notice.
+pub struct BrowsePages<'a> {
+    client: &'a SonosClient,
+    ip: String,
+    object_id: String,
+    page_size: u32,
+    next_index: u32,
+    total_matches: Option<u32>,
+    done: bool,
+}
+
+impl BrowsePages<'_> {
+    /// Children the container reported on the last page read, if any
+    pub fn total_matches(&self) -> Option<u32> {
+        self.total_matches
+    }
+}
+
+impl Iterator for BrowsePages<'_> {
+    type Item = Result<BrowseResponse, ApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done
+            || self
+                .total_matches
+                .is_some_and(|total| self.next_index >= total)
+        {
+            return None;
+        }
+        let page = browse_operation(self.object_id.clone(), self.next_index, self.page_size)
+            .build()
+            .map_err(ApiError::from)
+            .and_then(|op| self.client.execute_enhanced(&self.ip, op));
+        match &page {
+            Ok(page) => {
+                self.next_index = self.next_index.saturating_add(page.number_returned);
+                self.total_matches = Some(page.total_matches);
+                self.done = page.number_returned == 0;
+            }
+            Err(_) => self.done = true,
+        }
+        Some(page)
+    }
+}
+
+/// Read `object_id`'s children on the speaker at `ip`, `page_size` per
+/// Browse (capped by the speaker at [`MAX_BROWSE_COUNT`])
+pub fn browse_pages<'a>(
+    client: &'a SonosClient,
+    ip: &str,
+    object_id: &str,
+    page_size: u32,
+) -> BrowsePages<'a> {
+    BrowsePages {
+        client,
+        ip: ip.to_string(),
+        object_id: object_id.to_string(),
+        page_size,
+        next_index: 0,
+        total_matches: None,
+        done: false,
+    }
+}
+
+/// Read the queue of the speaker at `ip`, as many entries per Browse as it
+/// allows
+///
+/// ```rust,ignore
+/// use sonos_api::services::content_directory;
+///
+/// for page in content_directory::queue_pages(&client, "192.168.1.100") {
+///     for item in page?.items {
+///         println!("{}", item.title.unwrap_or_default());
+///     }
+/// }
+/// ```
+pub fn queue_pages<'a>(client: &'a SonosClient, ip: &str) -> BrowsePages<'a> {
+    browse_pages(client, ip, QUEUE_OBJECT_ID, MAX_BROWSE_COUNT)
+}
+
+/// Read every Sonos Favorite of the household from the speaker at `ip`,
+/// ordered by [`ordinal`](Favorite::ordinal)
+pub fn list_favorites(client: &SonosClient, ip: &str) -> Result<Vec<Favorite>, ApiError> {
+    let mut favorites = Vec::new();
+    for page in browse_pages(client, ip, FAVORITES_OBJECT_ID, MAX_BROWSE_COUNT) {
+        let first_index = favorites.len() as u32;
+        favorites.extend(parse_favorites(&page?.result, first_index)?);
+    }
+    favorites.sort_by_key(|favorite| favorite.ordinal);
+    Ok(favorites)
+}
+
+/// Read every Sonos playlist (saved queue) of the household from the
+/// speaker at `ip`
+pub fn list_playlists(client: &SonosClient, ip: &str) -> Result<Vec<Playlist>, ApiError> {
+    let mut playlists = Vec::new();
+    for page in browse_pages(client, ip, SAVED_QUEUES_OBJECT_ID, MAX_BROWSE_COUNT) {
+        let first_index = playlists.len() as u32;
+        playlists.extend(parse_playlists(&page?.result, first_index)?);
+    }
+    Ok(playlists)
+}
@@ -0,0 +1,144 @@
+//! Where a portable speaker's audio is going
+//!
+//! Roam and Move play through their own driver, headphones, a Bluetooth
+//! device or line-out. They event the route from DeviceProperties as
+//! `AudioOutput` (see [`DevicePropertiesEvent::audio_output()`]);
+//! [`read_audio_output()`] asks for it directly by combining the Bluetooth
+//! status page with RenderingControl `GetHeadphoneConnected`.
+//!
+//! Other models have none of this and don't serve the status page, so check
+//! [`supports_audio_output()`] before asking: an unsupported model fails
+//! with a 404 network error instead of a clean `NotSupported`.
+//!
+//! [`DevicePropertiesEvent::audio_output()`]: super::DevicePropertiesEvent::audio_output
+
+use crate::events::xml_utils;
+use crate::operation::{non_empty, opt_child_text, parse_sonos_bool};
+use crate::services::rendering_control;
+use crate::{ApiError, Result, SonosClient};
+
+/// Path of the Bluetooth status page on portable models
+pub const BLUETOOTH_STATUS_PATH: &str = "/status/bluetooth";
+
+/// `AudioOutput` value: the speaker's own driver
+pub const AUDIO_OUTPUT_SPEAKER: &str = "SPEAKER";
+/// `AudioOutput` value: wired headphones
+pub const AUDIO_OUTPUT_HEADPHONE: &str = "HEADPHONE";
+/// `AudioOutput` value: a connected Bluetooth device
+pub const AUDIO_OUTPUT_BLUETOOTH: &str = "BLUETOOTH";
+/// `AudioOutput` value: line-out
+pub const AUDIO_OUTPUT_LINE_OUT: &str = "LINE_OUT";
+
+/// Whether speakers of this model report their audio output
+///
+/// True for the portables (Roam, Roam SL, Roam 2, Move, Move 2), matched on
+/// the discovery model name with or without the `Sonos ` prefix.
+pub fn supports_audio_output(model_name: &str) -> bool {
+    let model = model_name.trim().to_ascii_lowercase();
+    let model = model.strip_prefix("sonos ").unwrap_or(&model);
+    model.starts_with("roam") || model.starts_with("move")
+}
+
+/// The Bluetooth status page
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BluetoothStatus {
+    /// A Bluetooth device is connected
+    pub connected: bool,
+    /// Audio is going to the connected device instead of the speaker
+    pub active: bool,
+    /// Name the connected device advertises
+    pub device_name: Option<String>,
+}
+
+impl BluetoothStatus {
+    /// Parse the status page (`<BluetoothStatus><Active>1</Active>...`)
+    pub fn from_xml(xml: &[u8]) -> Result<Self> {
+        xml_utils::check(&String::from_utf8_lossy(xml))?;
+        let root = xmltree::Element::parse(xml)
+            .map_err(|e| ApiError::ParseError(format!("Invalid Bluetooth status page: {e}")))?;
+        if root.name != "BluetoothStatus" {
+            return Err(ApiError::ParseError(format!(
+                "Expected BluetoothStatus, got {}",
+                root.name
+            )));
+        }
+        Ok(Self {
+            connected: parse_sonos_bool(&root, "Connected"),
+            active: parse_sonos_bool(&root, "Active"),
+            device_name: non_empty(opt_child_text(&root, "DeviceName")),
+        })
+    }
+}
+
+/// Read a portable speaker's audio output as an `AudioOutput` value
+///
+/// Bluetooth when it's active, otherwise headphones when they're plugged
+/// in, otherwise the speaker. A model that faults `GetHeadphoneConnected`
+/// as not supported has no headphone output. Line-out is only known from
+/// events.
+pub fn read_audio_output(client: &SonosClient, ip: &str) -> Result<&'static str> {
+    let (host, port) = soap_client::split_host_port(ip);
+    let page = client.fetch_resource(&format!("http://{host}:{port}{BLUETOOTH_STATUS_PATH}"))?;
+    if BluetoothStatus::from_xml(&page.body)?.active {
+        return Ok(AUDIO_OUTPUT_BLUETOOTH);
+    }
+
+    let operation = rendering_control::get_headphone_connected_operation()
+        .build()
+        .map_err(|e| ApiError::ParseError(e.to_string()))?;
+    match client.execute_enhanced(ip, operation) {
+        Ok(response) if response.current_headphone_connected => Ok(AUDIO_OUTPUT_HEADPHONE),
+        Ok(_) | Err(ApiError::NotSupported(_)) => Ok(AUDIO_OUTPUT_SPEAKER),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROAM_BLUETOOTH: &str = include_str!("../../../tests/fixtures/roam_bluetooth_status.xml");
+
+    #[test]
+    fn test_supported_models() {
+        for model in [
+            "Sonos Roam 2",
+            "Roam",
+            "Sonos Roam SL",
+            "Sonos Move",
+            "Move 2",
+        ] {
+            assert!(supports_audio_output(model), "{model}");
+        }
+        for model in ["Sonos One", "Sonos Arc", "Five", "Sonos Era 100", ""] {
+            assert!(!supports_audio_output(model), "{model}");
+        }
+    }
+
+    #[test]
+    fn test_bluetooth_status_page() {
+        assert_eq!(
+            BluetoothStatus::from_xml(ROAM_BLUETOOTH.as_bytes()).unwrap(),
+            BluetoothStatus {
+                connected: true,
+                active: true,
+                device_name: Some("Pixel 8".to_string()),
+            }
+        );
+        assert_eq!(
+            BluetoothStatus::from_xml(
+                b"<BluetoothStatus><Connected>0</Connected><DeviceName/></BluetoothStatus>"
+            )
+            .unwrap(),
+            BluetoothStatus::default()
+        );
+        assert!(matches!(
+            BluetoothStatus::from_xml(b"<html>Not Found</html>"),
+            Err(ApiError::ParseError(_))
+        ));
+        assert!(matches!(
+            BluetoothStatus::from_xml(b"not xml"),
+            Err(ApiError::ParseError(_))
+        ));
+    }
+}
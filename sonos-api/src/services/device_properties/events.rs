@@ -0,0 +1,194 @@
+//! DeviceProperties service event types and parsing
+//!
+//! Provides direct serde-based XML parsing with no business logic,
+//! replicating exactly what Sonos produces for sonos-stream consumption.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
+
+use serde::{Deserialize, Serialize};
+
+use super::operations::{parse_button_lock_state, parse_led_state};
+use crate::events::{xml_utils, EventParser};
+use crate::{ApiError, Result, Service};
+
+/// DeviceProperties event - direct serde mapping from UPnP event XML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "propertyset")]
+pub struct DevicePropertiesEvent {
+    /// Multiple property elements can exist in a single event
+    #[serde(rename = "property", default)]
+    properties: Vec<DevicePropertiesProperty>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DevicePropertiesProperty {
+    #[serde(rename = "ZoneName", default)]
+    zone_name: Option<String>,
+
+    #[serde(rename = "Icon", default)]
+    icon: Option<String>,
+
+    #[serde(rename = "Configuration", default)]
+    configuration: Option<String>,
+
+    #[serde(rename = "ButtonLockState", default)]
+    button_lock_state: Option<String>,
+
+    #[serde(rename = "LEDState", default)]
+    led_state: Option<String>,
+
+    #[serde(rename = "AudioOutput", default)]
+    audio_output: Option<String>,
+}
+
+impl DevicePropertiesEvent {
+    /// Get the room name
+    pub fn zone_name(&self) -> Option<String> {
+        self.properties.iter().find_map(|p| p.zone_name.clone())
+    }
+
+    /// Get the room icon
+    pub fn icon(&self) -> Option<String> {
+        self.properties.iter().find_map(|p| p.icon.clone())
+    }
+
+    /// Get the configuration value
+    pub fn configuration(&self) -> Option<String> {
+        self.properties.iter().find_map(|p| p.configuration.clone())
+    }
+
+    /// Get whether the buttons/touch controls are locked
+    ///
+    /// `None` when the event doesn't carry the state or it isn't a
+    /// recognisable on/off value.
+    pub fn button_lock(&self) -> Option<bool> {
+        self.properties
+            .iter()
+            .find_map(|p| p.button_lock_state.as_deref())
+            .and_then(parse_button_lock_state)
+    }
+
+    /// Get whether the white status light is on
+    ///
+    /// `None` when the event doesn't carry the state or it isn't a
+    /// recognisable on/off value.
+    pub fn status_light(&self) -> Option<bool> {
+        self.properties
+            .iter()
+            .find_map(|p| p.led_state.as_deref())
+            .and_then(parse_led_state)
+    }
+
+    /// Get where a portable speaker's audio is going (`SPEAKER`, `HEADPHONE`,
+    /// `BLUETOOTH` or `LINE_OUT`)
+    ///
+    /// Only portable models event this; see [`super::audio_output`].
+    pub fn audio_output(&self) -> Option<String> {
+        self.properties
+            .iter()
+            .find_map(|p| p.audio_output.as_deref())
+            .map(|output| output.trim().to_string())
+            .filter(|output| !output.is_empty())
+    }
+
+    /// Convert parsed UPnP event to canonical state representation.
+    pub fn into_state(&self) -> super::state::DevicePropertiesState {
+        super::state::DevicePropertiesState {
+            zone_name: self.zone_name(),
+            icon: self.icon(),
+            configuration: self.configuration(),
+            button_lock: self.button_lock(),
+            status_light: self.status_light(),
+            audio_output: self.audio_output(),
+        }
+    }
+
+    /// Parse from UPnP event XML using serde
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        xml_utils::check(xml)?;
+        let clean_xml = xml_utils::strip_namespaces(xml);
+        quick_xml::de::from_str(&clean_xml)
+            .map_err(|e| ApiError::ParseError(format!("Failed to parse DeviceProperties XML: {e}")))
+    }
+}
+
+/// Parser implementation for DeviceProperties events
+pub struct DevicePropertiesEventParser;
+
+impl EventParser for DevicePropertiesEventParser {
+    type EventData = DevicePropertiesEvent;
+
+    fn parse_upnp_event(&self, xml: &str) -> Result<Self::EventData> {
+        DevicePropertiesEvent::from_xml(xml)
+    }
+
+    fn service_type(&self) -> Service {
+        Service::DeviceProperties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml_parsing() {
+        let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0">
+            <e:property><ZoneName>Kids Room</ZoneName></e:property>
+            <e:property><Icon>x-rincon-roomicon:bedroom</Icon></e:property>
+            <e:property><ButtonLockState>On</ButtonLockState></e:property>
+            <e:property><LEDState>Off</LEDState></e:property>
+        </e:propertyset>"#;
+
+        let state = DevicePropertiesEvent::from_xml(xml).unwrap().into_state();
+        assert_eq!(state.zone_name.as_deref(), Some("Kids Room"));
+        assert_eq!(state.icon.as_deref(), Some("x-rincon-roomicon:bedroom"));
+        assert_eq!(state.configuration, None);
+        assert_eq!(state.button_lock, Some(true));
+        assert_eq!(state.status_light, Some(false));
+        assert_eq!(state.audio_output, None);
+    }
+
+    #[test]
+    fn test_audio_output_fixtures() {
+        let roam = DevicePropertiesEvent::from_xml(include_str!(
+            "../../../tests/fixtures/roam_device_properties_event.xml"
+        ))
+        .unwrap();
+        assert_eq!(roam.audio_output().as_deref(), Some("BLUETOOTH"));
+        assert_eq!(roam.button_lock(), Some(false));
+
+        let one = DevicePropertiesEvent::from_xml(include_str!(
+            "../../../tests/fixtures/one_device_properties_event.xml"
+        ))
+        .unwrap();
+        assert_eq!(one.audio_output(), None);
+        assert_eq!(one.zone_name().as_deref(), Some("Kitchen"));
+    }
+
+    #[test]
+    fn test_missing_or_garbled_on_off_states() {
+        let event = |inner: &str| {
+            DevicePropertiesEvent::from_xml(&format!(
+                r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property>{inner}</e:property></e:propertyset>"#
+            ))
+            .unwrap()
+        };
+        assert_eq!(event("<ZoneName>Den</ZoneName>").button_lock(), None);
+        assert_eq!(
+            event("<ButtonLockState>Sideways</ButtonLockState>").button_lock(),
+            None
+        );
+        assert_eq!(
+            event("<ButtonLockState>off</ButtonLockState>").button_lock(),
+            Some(false)
+        );
+        assert_eq!(event("<ZoneName>Den</ZoneName>").status_light(), None);
+        assert_eq!(event("<LEDState>Dim</LEDState>").status_light(), None);
+        assert_eq!(event("<LEDState>On</LEDState>").status_light(), Some(true));
+    }
+}
@@ -0,0 +1,64 @@
+//! DeviceProperties service for per-speaker device settings
+//!
+//! Covers settings that belong to one physical speaker rather than its group,
+//! such as its room name and icon, the status LED and locking the on-device
+//! buttons and touch controls.
+//!
+//! # Control Operations
+//! ```rust,ignore
+//! use sonos_api::services::device_properties;
+//!
+//! let lock_op = device_properties::set_button_lock_state(true).build()?;
+//! client.execute_enhanced("192.168.1.100", lock_op)?;
+//!
+//! let rename_op = device_properties::set_zone_attributes(
+//!     "Kitchen".to_string(),
+//!     "x-rincon-roomicon:kitchen".to_string(),
+//!     String::new(),
+//! ).build()?;
+//! client.execute_enhanced("192.168.1.100", rename_op)?;
+//! ```
+//!
+//! # Important Notes
+//! - SetZoneAttributes rejects an empty room name before anything is sent
+//! - Models without button lock support fault with UPnP error 401 or 602,
+//!   which surfaces as [`ApiError::NotSupported`](crate::ApiError::NotSupported)
+//! - Only portable models report their audio output (speaker, headphones,
+//!   Bluetooth or line-out); see [`audio_output`]
+
+pub mod audio_output;
+pub mod events;
+pub mod operations;
+pub mod state;
+
+// Re-export operations for convenience
+pub use operations::*;
+
+pub use audio_output::{
+    read_audio_output, supports_audio_output, BluetoothStatus, AUDIO_OUTPUT_BLUETOOTH,
+    AUDIO_OUTPUT_HEADPHONE, AUDIO_OUTPUT_LINE_OUT, AUDIO_OUTPUT_SPEAKER, BLUETOOTH_STATUS_PATH,
+};
+pub use events::{DevicePropertiesEvent, DevicePropertiesEventParser};
+pub use state::DevicePropertiesState;
+
+/// Service constant for DeviceProperties
+pub const SERVICE: crate::Service = crate::Service::DeviceProperties;
+
+/// Subscribe to DeviceProperties events
+pub fn subscribe(
+    client: &crate::SonosClient,
+    ip: &str,
+    callback_url: &str,
+) -> crate::Result<crate::ManagedSubscription> {
+    client.subscribe(ip, SERVICE, callback_url)
+}
+
+/// Subscribe to DeviceProperties events with custom timeout
+pub fn subscribe_with_timeout(
+    client: &crate::SonosClient,
+    ip: &str,
+    callback_url: &str,
+    timeout_seconds: u32,
+) -> crate::Result<crate::ManagedSubscription> {
+    client.subscribe_with_timeout(ip, SERVICE, callback_url, timeout_seconds)
+}
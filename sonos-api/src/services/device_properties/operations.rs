@@ -0,0 +1,740 @@
+//! DeviceProperties service operations
+//!
+//! # Operations
+//! - `get_button_lock_state` - Read whether the buttons/touch controls are locked
+//! - `set_button_lock_state` - Lock or unlock the buttons/touch controls
+//! - `get_household_id` - Read the household the speaker belongs to
+//! - `get_autoplay_room_uuid` - Read the room a line-in source starts playing in
+//! - `get_autoplay_volume` - Read the volume autoplay starts at
+//! - `get_zone_attributes` - Read the room name and icon
+//! - `set_zone_attributes` - Rename the room or change its icon
+//! - `get_led_state` - Read whether the white status LED is on
+//! - `set_led_state` - Turn the white status LED on or off
+//!
+//! DeviceProperties actions take no `InstanceID`, so these are implemented by
+//! hand rather than with the operation macros.
+
+use crate::operation::{opt_child_text, OperationBuilder, UPnPOperation, ValidationError};
+use crate::{ApiError, Service, Validate};
+use serde::{Deserialize, Serialize};
+
+/// Parse a button lock state as sent on the wire
+///
+/// Sonos sends `On`/`Off`; `1`/`0` and `true`/`false` are accepted too, in
+/// any case and with surrounding whitespace.
+pub fn parse_button_lock_state(value: &str) -> Option<bool> {
+    parse_on_off(value)
+}
+
+/// The wire string for a button lock state
+pub fn button_lock_state_str(locked: bool) -> &'static str {
+    on_off_str(locked)
+}
+
+/// Parse a status LED state as sent on the wire
+///
+/// Accepts the same spellings as [`parse_button_lock_state()`].
+pub fn parse_led_state(value: &str) -> Option<bool> {
+    parse_on_off(value)
+}
+
+/// The wire string for a status LED state
+pub fn led_state_str(on: bool) -> &'static str {
+    on_off_str(on)
+}
+
+fn parse_on_off(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "on" | "1" | "true" => Some(true),
+        "off" | "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn on_off_str(on: bool) -> &'static str {
+    if on {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+// =============================================================================
+// GET BUTTON LOCK STATE OPERATION
+// =============================================================================
+
+/// Request to read the button lock state
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GetButtonLockStateOperationRequest {}
+
+impl Validate for GetButtonLockStateOperationRequest {}
+
+/// Response carrying the button lock state
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetButtonLockStateResponse {
+    /// Whether the buttons/touch controls are locked
+    pub current_button_lock_state: bool,
+}
+
+/// Operation to read whether the buttons/touch controls are locked
+pub struct GetButtonLockStateOperation;
+
+impl UPnPOperation for GetButtonLockStateOperation {
+    type Request = GetButtonLockStateOperationRequest;
+    type Response = GetButtonLockStateResponse;
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "GetButtonLockState";
+
+    fn build_payload(_request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(String::new())
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        let state = opt_child_text(xml, "CurrentButtonLockState").ok_or_else(|| {
+            ApiError::ParseError("Missing CurrentButtonLockState element".to_string())
+        })?;
+        let current_button_lock_state = parse_button_lock_state(&state).ok_or_else(|| {
+            ApiError::ParseError(format!("Invalid CurrentButtonLockState: {state:?}"))
+        })?;
+        Ok(GetButtonLockStateResponse {
+            current_button_lock_state,
+        })
+    }
+}
+
+/// Create a GetButtonLockState operation builder
+pub fn get_button_lock_state_operation() -> OperationBuilder<GetButtonLockStateOperation> {
+    OperationBuilder::new(GetButtonLockStateOperationRequest {})
+}
+
+// =============================================================================
+// SET BUTTON LOCK STATE OPERATION
+// =============================================================================
+
+/// Request to lock or unlock the buttons/touch controls
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SetButtonLockStateOperationRequest {
+    /// `true` to lock, `false` to unlock
+    pub desired_button_lock_state: bool,
+}
+
+impl Validate for SetButtonLockStateOperationRequest {}
+
+/// Operation to lock or unlock the buttons/touch controls
+pub struct SetButtonLockStateOperation;
+
+impl UPnPOperation for SetButtonLockStateOperation {
+    type Request = SetButtonLockStateOperationRequest;
+    type Response = ();
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "SetButtonLockState";
+
+    fn build_payload(request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(format!(
+            "<DesiredButtonLockState>{}</DesiredButtonLockState>",
+            button_lock_state_str(request.desired_button_lock_state)
+        ))
+    }
+
+    fn parse_response(_xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        Ok(())
+    }
+}
+
+/// Create a SetButtonLockState operation builder
+pub fn set_button_lock_state_operation(
+    locked: bool,
+) -> OperationBuilder<SetButtonLockStateOperation> {
+    OperationBuilder::new(SetButtonLockStateOperationRequest {
+        desired_button_lock_state: locked,
+    })
+}
+
+// =============================================================================
+// GET HOUSEHOLD ID OPERATION
+// =============================================================================
+
+/// Request to read the speaker's household
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GetHouseholdIdOperationRequest {}
+
+impl Validate for GetHouseholdIdOperationRequest {}
+
+/// Response carrying the speaker's household
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetHouseholdIdResponse {
+    /// Household ID, e.g. `Sonos_a1b2c3`
+    pub current_household_id: String,
+}
+
+/// Operation to read the household the speaker belongs to
+pub struct GetHouseholdIdOperation;
+
+impl UPnPOperation for GetHouseholdIdOperation {
+    type Request = GetHouseholdIdOperationRequest;
+    type Response = GetHouseholdIdResponse;
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "GetHouseholdID";
+
+    fn build_payload(_request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(String::new())
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        let current_household_id = opt_child_text(xml, "CurrentHouseholdID")
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| {
+                ApiError::ParseError("Missing CurrentHouseholdID element".to_string())
+            })?;
+        Ok(GetHouseholdIdResponse {
+            current_household_id,
+        })
+    }
+}
+
+/// Create a GetHouseholdID operation builder
+pub fn get_household_id_operation() -> OperationBuilder<GetHouseholdIdOperation> {
+    OperationBuilder::new(GetHouseholdIdOperationRequest {})
+}
+
+// =============================================================================
+// GET AUTOPLAY ROOM UUID OPERATION
+// =============================================================================
+
+/// Request to read where a source plays when it starts receiving audio
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GetAutoplayRoomUuidOperationRequest {
+    /// Input the setting is for; empty for the speaker's own line-in
+    pub source: String,
+}
+
+impl Validate for GetAutoplayRoomUuidOperationRequest {}
+
+/// Response carrying the autoplay room
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetAutoplayRoomUuidResponse {
+    /// Speaker ID of the room autoplay starts in; empty when autoplay is off
+    pub room_uuid: String,
+}
+
+/// Operation to read the room a source autoplays in
+pub struct GetAutoplayRoomUuidOperation;
+
+impl UPnPOperation for GetAutoplayRoomUuidOperation {
+    type Request = GetAutoplayRoomUuidOperationRequest;
+    type Response = GetAutoplayRoomUuidResponse;
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "GetAutoplayRoomUUID";
+
+    fn build_payload(request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(format!(
+            "<Source>{}</Source>",
+            crate::operation::xml_escape(&request.source)
+        ))
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        let room_uuid = opt_child_text(xml, "RoomUUID")
+            .ok_or_else(|| ApiError::ParseError("Missing RoomUUID element".to_string()))?;
+        Ok(GetAutoplayRoomUuidResponse {
+            room_uuid: room_uuid.trim().to_string(),
+        })
+    }
+}
+
+/// Create a GetAutoplayRoomUUID operation builder
+pub fn get_autoplay_room_uuid_operation(
+    source: String,
+) -> OperationBuilder<GetAutoplayRoomUuidOperation> {
+    OperationBuilder::new(GetAutoplayRoomUuidOperationRequest { source })
+}
+
+// =============================================================================
+// GET AUTOPLAY VOLUME OPERATION
+// =============================================================================
+
+/// Request to read the volume a source autoplays at
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GetAutoplayVolumeOperationRequest {
+    /// Input the setting is for; empty for the speaker's own line-in
+    pub source: String,
+}
+
+impl Validate for GetAutoplayVolumeOperationRequest {}
+
+/// Response carrying the autoplay volume
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetAutoplayVolumeResponse {
+    /// Volume (0-100) autoplay starts at
+    pub current_volume: u8,
+}
+
+/// Operation to read the volume a source autoplays at
+pub struct GetAutoplayVolumeOperation;
+
+impl UPnPOperation for GetAutoplayVolumeOperation {
+    type Request = GetAutoplayVolumeOperationRequest;
+    type Response = GetAutoplayVolumeResponse;
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "GetAutoplayVolume";
+
+    fn build_payload(request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(format!(
+            "<Source>{}</Source>",
+            crate::operation::xml_escape(&request.source)
+        ))
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        let volume = opt_child_text(xml, "CurrentVolume")
+            .ok_or_else(|| ApiError::ParseError("Missing CurrentVolume element".to_string()))?;
+        let current_volume = volume
+            .trim()
+            .parse()
+            .map_err(|_| ApiError::ParseError(format!("Invalid CurrentVolume: {volume:?}")))?;
+        Ok(GetAutoplayVolumeResponse { current_volume })
+    }
+}
+
+/// Create a GetAutoplayVolume operation builder
+pub fn get_autoplay_volume_operation(
+    source: String,
+) -> OperationBuilder<GetAutoplayVolumeOperation> {
+    OperationBuilder::new(GetAutoplayVolumeOperationRequest { source })
+}
+
+// =============================================================================
+// GET ZONE ATTRIBUTES OPERATION
+// =============================================================================
+
+/// Request to read the room name and icon
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GetZoneAttributesOperationRequest {}
+
+impl Validate for GetZoneAttributesOperationRequest {}
+
+/// Response carrying the room name and icon
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetZoneAttributesResponse {
+    /// Room name, e.g. `Living Room`
+    pub current_zone_name: String,
+    /// Icon URI, e.g. `x-rincon-roomicon:living`
+    pub current_icon: String,
+    /// Opaque configuration value, sent back unchanged when renaming
+    pub current_configuration: String,
+}
+
+/// Operation to read the room name and icon
+pub struct GetZoneAttributesOperation;
+
+impl UPnPOperation for GetZoneAttributesOperation {
+    type Request = GetZoneAttributesOperationRequest;
+    type Response = GetZoneAttributesResponse;
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "GetZoneAttributes";
+
+    fn build_payload(_request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(String::new())
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        let current_zone_name = opt_child_text(xml, "CurrentZoneName")
+            .ok_or_else(|| ApiError::ParseError("Missing CurrentZoneName element".to_string()))?;
+        Ok(GetZoneAttributesResponse {
+            current_zone_name,
+            current_icon: opt_child_text(xml, "CurrentIcon").unwrap_or_default(),
+            current_configuration: opt_child_text(xml, "CurrentConfiguration").unwrap_or_default(),
+        })
+    }
+}
+
+/// Create a GetZoneAttributes operation builder
+pub fn get_zone_attributes_operation() -> OperationBuilder<GetZoneAttributesOperation> {
+    OperationBuilder::new(GetZoneAttributesOperationRequest {})
+}
+
+// =============================================================================
+// SET ZONE ATTRIBUTES OPERATION
+// =============================================================================
+
+/// Request to rename the room or change its icon
+///
+/// The speaker replaces all three values, so pass the current icon and
+/// configuration from [`GetZoneAttributesResponse`] to only rename.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SetZoneAttributesOperationRequest {
+    /// New room name; must not be blank
+    pub desired_zone_name: String,
+    /// New icon URI
+    pub desired_icon: String,
+    /// Configuration value to keep
+    pub desired_configuration: String,
+}
+
+impl Validate for SetZoneAttributesOperationRequest {
+    fn validate_basic(&self) -> Result<(), ValidationError> {
+        if self.desired_zone_name.trim().is_empty() {
+            return Err(ValidationError::Custom {
+                parameter: "desired_zone_name".to_string(),
+                message: "Zone name must not be empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Operation to rename the room or change its icon
+pub struct SetZoneAttributesOperation;
+
+impl UPnPOperation for SetZoneAttributesOperation {
+    type Request = SetZoneAttributesOperationRequest;
+    type Response = ();
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "SetZoneAttributes";
+
+    fn build_payload(request: &Self::Request) -> Result<String, ValidationError> {
+        request.validate(crate::operation::ValidationLevel::Basic)?;
+        Ok(format!(
+            "<DesiredZoneName>{}</DesiredZoneName><DesiredIcon>{}</DesiredIcon><DesiredConfiguration>{}</DesiredConfiguration>",
+            crate::operation::xml_escape(&request.desired_zone_name),
+            crate::operation::xml_escape(&request.desired_icon),
+            crate::operation::xml_escape(&request.desired_configuration)
+        ))
+    }
+
+    fn parse_response(_xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        Ok(())
+    }
+}
+
+/// Create a SetZoneAttributes operation builder
+pub fn set_zone_attributes_operation(
+    zone_name: String,
+    icon: String,
+    configuration: String,
+) -> OperationBuilder<SetZoneAttributesOperation> {
+    OperationBuilder::new(SetZoneAttributesOperationRequest {
+        desired_zone_name: zone_name,
+        desired_icon: icon,
+        desired_configuration: configuration,
+    })
+}
+
+// =============================================================================
+// GET LED STATE OPERATION
+// =============================================================================
+
+/// Request to read the status LED state
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GetLedStateOperationRequest {}
+
+impl Validate for GetLedStateOperationRequest {}
+
+/// Response carrying the status LED state
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GetLedStateResponse {
+    /// Whether the white status LED is on
+    pub current_led_state: bool,
+}
+
+/// Operation to read whether the white status LED is on
+pub struct GetLedStateOperation;
+
+impl UPnPOperation for GetLedStateOperation {
+    type Request = GetLedStateOperationRequest;
+    type Response = GetLedStateResponse;
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "GetLEDState";
+
+    fn build_payload(_request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(String::new())
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        let state = opt_child_text(xml, "CurrentLEDState")
+            .ok_or_else(|| ApiError::ParseError("Missing CurrentLEDState element".to_string()))?;
+        let current_led_state = parse_led_state(&state)
+            .ok_or_else(|| ApiError::ParseError(format!("Invalid CurrentLEDState: {state:?}")))?;
+        Ok(GetLedStateResponse { current_led_state })
+    }
+}
+
+/// Create a GetLEDState operation builder
+pub fn get_led_state_operation() -> OperationBuilder<GetLedStateOperation> {
+    OperationBuilder::new(GetLedStateOperationRequest {})
+}
+
+// =============================================================================
+// SET LED STATE OPERATION
+// =============================================================================
+
+/// Request to turn the status LED on or off
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SetLedStateOperationRequest {
+    /// `true` to turn the LED on
+    pub desired_led_state: bool,
+}
+
+impl Validate for SetLedStateOperationRequest {}
+
+/// Operation to turn the white status LED on or off
+pub struct SetLedStateOperation;
+
+impl UPnPOperation for SetLedStateOperation {
+    type Request = SetLedStateOperationRequest;
+    type Response = ();
+
+    const SERVICE: Service = Service::DeviceProperties;
+    const ACTION: &'static str = "SetLEDState";
+
+    fn build_payload(request: &Self::Request) -> Result<String, ValidationError> {
+        Ok(format!(
+            "<DesiredLEDState>{}</DesiredLEDState>",
+            led_state_str(request.desired_led_state)
+        ))
+    }
+
+    fn parse_response(_xml: &xmltree::Element) -> Result<Self::Response, ApiError> {
+        Ok(())
+    }
+}
+
+/// Create a SetLEDState operation builder
+pub fn set_led_state_operation(on: bool) -> OperationBuilder<SetLedStateOperation> {
+    OperationBuilder::new(SetLedStateOperationRequest {
+        desired_led_state: on,
+    })
+}
+
+// =============================================================================
+// LEGACY ALIASES
+// =============================================================================
+
+pub use get_autoplay_room_uuid_operation as get_autoplay_room_uuid;
+pub use get_autoplay_volume_operation as get_autoplay_volume;
+pub use get_button_lock_state_operation as get_button_lock_state;
+pub use get_household_id_operation as get_household_id;
+pub use get_led_state_operation as get_led_state;
+pub use get_zone_attributes_operation as get_zone_attributes;
+pub use set_button_lock_state_operation as set_button_lock_state;
+pub use set_led_state_operation as set_led_state;
+pub use set_zone_attributes_operation as set_zone_attributes;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_button_lock_payloads() {
+        let get = get_button_lock_state_operation().build().unwrap();
+        assert_eq!(get.metadata().action, "GetButtonLockState");
+        assert_eq!(get.metadata().service, "DeviceProperties");
+        assert_eq!(
+            GetButtonLockStateOperation::build_payload(get.request()).unwrap(),
+            ""
+        );
+
+        for (locked, wire) in [(true, "On"), (false, "Off")] {
+            let set = set_button_lock_state_operation(locked).build().unwrap();
+            assert_eq!(
+                SetButtonLockStateOperation::build_payload(set.request()).unwrap(),
+                format!("<DesiredButtonLockState>{wire}</DesiredButtonLockState>")
+            );
+        }
+    }
+
+    #[test]
+    fn test_button_lock_wire_strings() {
+        for (wire, expected) in [
+            ("On", true),
+            ("off", false),
+            (" ON ", true),
+            ("1", true),
+            ("0", false),
+            ("True", true),
+        ] {
+            assert_eq!(parse_button_lock_state(wire), Some(expected), "{wire:?}");
+        }
+        assert_eq!(parse_button_lock_state(""), None);
+        assert_eq!(parse_button_lock_state("Locked"), None);
+
+        for locked in [true, false] {
+            assert_eq!(
+                parse_button_lock_state(button_lock_state_str(locked)),
+                Some(locked)
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_button_lock_state_response() {
+        let parse = |inner: &str| {
+            let xml = format!("<GetButtonLockStateResponse>{inner}</GetButtonLockStateResponse>");
+            GetButtonLockStateOperation::parse_response(
+                &xmltree::Element::parse(xml.as_bytes()).unwrap(),
+            )
+        };
+        assert!(
+            parse("<CurrentButtonLockState>On</CurrentButtonLockState>")
+                .unwrap()
+                .current_button_lock_state
+        );
+        assert!(
+            !parse("<CurrentButtonLockState>Off</CurrentButtonLockState>")
+                .unwrap()
+                .current_button_lock_state
+        );
+        assert!(matches!(parse(""), Err(ApiError::ParseError(_))));
+        assert!(matches!(
+            parse("<CurrentButtonLockState>Maybe</CurrentButtonLockState>"),
+            Err(ApiError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_household_id_response() {
+        let parse = |inner: &str| {
+            let xml = format!("<GetHouseholdIDResponse>{inner}</GetHouseholdIDResponse>");
+            GetHouseholdIdOperation::parse_response(
+                &xmltree::Element::parse(xml.as_bytes()).unwrap(),
+            )
+        };
+        let op = get_household_id_operation().build().unwrap();
+        assert_eq!(op.metadata().action, "GetHouseholdID");
+        assert_eq!(
+            parse("<CurrentHouseholdID>Sonos_a1b2c3</CurrentHouseholdID>")
+                .unwrap()
+                .current_household_id,
+            "Sonos_a1b2c3"
+        );
+        assert!(matches!(
+            parse("<CurrentHouseholdID> </CurrentHouseholdID>"),
+            Err(ApiError::ParseError(_))
+        ));
+        assert!(matches!(parse(""), Err(ApiError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_autoplay_payloads_and_responses() {
+        let room = get_autoplay_room_uuid_operation(String::new())
+            .build()
+            .unwrap();
+        assert_eq!(room.metadata().action, "GetAutoplayRoomUUID");
+        assert_eq!(
+            GetAutoplayRoomUuidOperation::build_payload(room.request()).unwrap(),
+            "<Source></Source>"
+        );
+        let parse_room = |inner: &str| {
+            let xml = format!("<GetAutoplayRoomUUIDResponse>{inner}</GetAutoplayRoomUUIDResponse>");
+            GetAutoplayRoomUuidOperation::parse_response(
+                &xmltree::Element::parse(xml.as_bytes()).unwrap(),
+            )
+        };
+        assert_eq!(
+            parse_room("<RoomUUID>RINCON_DEN</RoomUUID>")
+                .unwrap()
+                .room_uuid,
+            "RINCON_DEN"
+        );
+        assert!(parse_room("<RoomUUID></RoomUUID>")
+            .unwrap()
+            .room_uuid
+            .is_empty());
+        assert!(matches!(parse_room(""), Err(ApiError::ParseError(_))));
+
+        let parse_volume = |inner: &str| {
+            let xml = format!("<GetAutoplayVolumeResponse>{inner}</GetAutoplayVolumeResponse>");
+            GetAutoplayVolumeOperation::parse_response(
+                &xmltree::Element::parse(xml.as_bytes()).unwrap(),
+            )
+        };
+        assert_eq!(
+            parse_volume("<CurrentVolume>35</CurrentVolume>")
+                .unwrap()
+                .current_volume,
+            35
+        );
+        assert!(matches!(
+            parse_volume("<CurrentVolume>loud</CurrentVolume>"),
+            Err(ApiError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_zone_attributes() {
+        let set = set_zone_attributes_operation(
+            "Kids & Guests".to_string(),
+            "x-rincon-roomicon:bedroom".to_string(),
+            "1".to_string(),
+        )
+        .build()
+        .unwrap();
+        assert_eq!(set.metadata().action, "SetZoneAttributes");
+        assert_eq!(
+            SetZoneAttributesOperation::build_payload(set.request()).unwrap(),
+            "<DesiredZoneName>Kids &amp; Guests</DesiredZoneName><DesiredIcon>x-rincon-roomicon:bedroom</DesiredIcon><DesiredConfiguration>1</DesiredConfiguration>"
+        );
+        for blank in ["", "  "] {
+            assert!(matches!(
+                set_zone_attributes_operation(blank.to_string(), String::new(), String::new())
+                    .build(),
+                Err(ValidationError::Custom { .. })
+            ));
+        }
+
+        let parse = |inner: &str| {
+            let xml = format!("<GetZoneAttributesResponse>{inner}</GetZoneAttributesResponse>");
+            GetZoneAttributesOperation::parse_response(
+                &xmltree::Element::parse(xml.as_bytes()).unwrap(),
+            )
+        };
+        let attributes = parse(
+            "<CurrentZoneName>Den</CurrentZoneName><CurrentIcon>x-rincon-roomicon:den</CurrentIcon><CurrentConfiguration>1</CurrentConfiguration>",
+        )
+        .unwrap();
+        assert_eq!(attributes.current_zone_name, "Den");
+        assert_eq!(attributes.current_icon, "x-rincon-roomicon:den");
+        assert_eq!(attributes.current_configuration, "1");
+        assert!(matches!(parse(""), Err(ApiError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_led_state() {
+        for (on, wire) in [(true, "On"), (false, "Off")] {
+            let set = set_led_state_operation(on).build().unwrap();
+            assert_eq!(set.metadata().action, "SetLEDState");
+            assert_eq!(
+                SetLedStateOperation::build_payload(set.request()).unwrap(),
+                format!("<DesiredLEDState>{wire}</DesiredLEDState>")
+            );
+        }
+
+        let parse = |inner: &str| {
+            let xml = format!("<GetLEDStateResponse>{inner}</GetLEDStateResponse>");
+            GetLedStateOperation::parse_response(&xmltree::Element::parse(xml.as_bytes()).unwrap())
+        };
+        assert!(
+            parse("<CurrentLEDState>On</CurrentLEDState>")
+                .unwrap()
+                .current_led_state
+        );
+        assert!(
+            !parse("<CurrentLEDState>Off</CurrentLEDState>")
+                .unwrap()
+                .current_led_state
+        );
+        assert!(matches!(
+            parse("<CurrentLEDState>Dim</CurrentLEDState>"),
+            Err(ApiError::ParseError(_))
+        ));
+    }
+}
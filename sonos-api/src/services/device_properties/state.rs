@@ -0,0 +1,57 @@
+//! Canonical DeviceProperties service state type.
+//!
+//! Used by both UPnP event streaming (via `into_state()`) and polling (via `poll()`).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ApiError, SonosClient};
+
+/// DeviceProperties service state.
+///
+/// Canonical type used by both UPnP event streaming and polling. Polling only
+/// fills in `button_lock`; the other fields come from events. Polling can't
+/// read `audio_output` since it doesn't know the model, which decides
+/// whether the speaker can be asked; see [`super::audio_output`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct DevicePropertiesState {
+    /// Room name
+    pub zone_name: Option<String>,
+
+    /// Room icon
+    pub icon: Option<String>,
+
+    /// Configuration value
+    pub configuration: Option<String>,
+
+    /// Whether the buttons/touch controls are locked
+    pub button_lock: Option<bool>,
+
+    /// Whether the white status light is on
+    #[serde(default)]
+    pub status_light: Option<bool>,
+
+    /// Where a portable speaker's audio is going (`SPEAKER`, `HEADPHONE`,
+    /// `BLUETOOTH` or `LINE_OUT`)
+    #[serde(default)]
+    pub audio_output: Option<String>,
+}
+
+/// Poll a speaker for DeviceProperties state.
+///
+/// Calls GetButtonLockState. Models without a button lock leave
+/// `button_lock` as `None` instead of failing the poll.
+pub fn poll(client: &SonosClient, ip: &str) -> crate::Result<DevicePropertiesState> {
+    let operation = super::get_button_lock_state_operation()
+        .build()
+        .map_err(|e| ApiError::ParseError(e.to_string()))?;
+    let button_lock = match client.execute_enhanced(ip, operation) {
+        Ok(response) => Some(response.current_button_lock_state),
+        Err(ApiError::NotSupported(_)) => None,
+        Err(e) => return Err(e),
+    };
+
+    Ok(DevicePropertiesState {
+        button_lock,
+        ..Default::default()
+    })
+}
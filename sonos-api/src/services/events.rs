@@ -43,7 +43,7 @@ impl SubscribeOperation {
     ///
     /// # Arguments
     /// * `soap_client` - The SOAP client to use for the request
-    /// * `ip` - Device IP address
+    /// * `ip` - Device IP, or `ip:port` for a device not on port 1400
     /// * `service` - The service to subscribe to
     /// * `request` - The subscription request parameters
     ///
@@ -56,20 +56,44 @@ impl SubscribeOperation {
         request: &SubscribeRequest,
     ) -> Result<SubscribeResponse> {
         let service_info = service.info();
+        let (ip, port) = soap_client::split_host_port(ip);
 
         let subscription_response = soap_client
             .subscribe(
                 ip,
-                1400, // Standard Sonos port
+                port,
                 service_info.event_endpoint,
                 &request.callback_url,
                 request.timeout_seconds,
             )
-            .map_err(|e| match e {
-                soap_client::SoapError::Network(msg) => ApiError::NetworkError(msg),
-                soap_client::SoapError::Parse(msg) => ApiError::ParseError(msg),
-                soap_client::SoapError::Fault(code) => ApiError::SoapFault(code),
-            })?;
+            .map_err(|e| eventing_error(e, None))?;
+
+        Ok(SubscribeResponse {
+            sid: subscription_response.sid,
+            timeout_seconds: subscription_response.timeout_seconds,
+        })
+    }
+
+    /// [`execute()`](Self::execute) through an
+    /// [`AsyncSoapClient`](soap_client::AsyncSoapClient)
+    #[cfg(feature = "async-client")]
+    pub async fn execute_async(
+        soap_client: &soap_client::AsyncSoapClient,
+        ip: &str,
+        service: Service,
+        request: &SubscribeRequest,
+    ) -> Result<SubscribeResponse> {
+        let (ip, port) = soap_client::split_host_port(ip);
+        let subscription_response = soap_client
+            .subscribe(
+                ip,
+                port,
+                service.info().event_endpoint,
+                &request.callback_url,
+                request.timeout_seconds,
+            )
+            .await
+            .map_err(|e| eventing_error(e, None))?;
 
         Ok(SubscribeResponse {
             sid: subscription_response.sid,
@@ -103,7 +127,7 @@ impl UnsubscribeOperation {
     ///
     /// # Arguments
     /// * `soap_client` - The SOAP client to use for the request
-    /// * `ip` - Device IP address
+    /// * `ip` - Device IP, or `ip:port` for a device not on port 1400
     /// * `service` - The service to unsubscribe from
     /// * `request` - The unsubscribe request parameters
     ///
@@ -116,19 +140,29 @@ impl UnsubscribeOperation {
         request: &UnsubscribeRequest,
     ) -> Result<UnsubscribeResponse> {
         let service_info = service.info();
+        let (ip, port) = soap_client::split_host_port(ip);
 
         soap_client
-            .unsubscribe(
-                ip,
-                1400, // Standard Sonos port
-                service_info.event_endpoint,
-                &request.sid,
-            )
-            .map_err(|e| match e {
-                soap_client::SoapError::Network(msg) => ApiError::NetworkError(msg),
-                soap_client::SoapError::Parse(msg) => ApiError::ParseError(msg),
-                soap_client::SoapError::Fault(code) => ApiError::SoapFault(code),
-            })?;
+            .unsubscribe(ip, port, service_info.event_endpoint, &request.sid)
+            .map_err(|e| eventing_error(e, None))?;
+
+        Ok(UnsubscribeResponse)
+    }
+
+    /// [`execute()`](Self::execute) through an
+    /// [`AsyncSoapClient`](soap_client::AsyncSoapClient)
+    #[cfg(feature = "async-client")]
+    pub async fn execute_async(
+        soap_client: &soap_client::AsyncSoapClient,
+        ip: &str,
+        service: Service,
+        request: &UnsubscribeRequest,
+    ) -> Result<UnsubscribeResponse> {
+        let (ip, port) = soap_client::split_host_port(ip);
+        soap_client
+            .unsubscribe(ip, port, service.info().event_endpoint, &request.sid)
+            .await
+            .map_err(|e| eventing_error(e, None))?;
 
         Ok(UnsubscribeResponse)
     }
@@ -164,12 +198,13 @@ impl RenewOperation {
     ///
     /// # Arguments
     /// * `soap_client` - The SOAP client to use for the request
-    /// * `ip` - Device IP address
+    /// * `ip` - Device IP, or `ip:port` for a device not on port 1400
     /// * `service` - The service to renew subscription for
     /// * `request` - The renewal request parameters
     ///
     /// # Returns
-    /// The renewal response containing the actual timeout granted
+    /// The renewal response containing the actual timeout granted, or
+    /// [`ApiError::SubscriptionUnknown`] if the device no longer knows the SID
     pub fn execute(
         soap_client: &soap_client::SoapClient,
         ip: &str,
@@ -177,25 +212,66 @@ impl RenewOperation {
         request: &RenewRequest,
     ) -> Result<RenewResponse> {
         let service_info = service.info();
+        let (ip, port) = soap_client::split_host_port(ip);
 
         let actual_timeout_seconds = soap_client
             .renew_subscription(
                 ip,
-                1400, // Standard Sonos port
+                port,
                 service_info.event_endpoint,
                 &request.sid,
                 request.timeout_seconds,
             )
-            .map_err(|e| match e {
-                soap_client::SoapError::Network(msg) => ApiError::NetworkError(msg),
-                soap_client::SoapError::Parse(msg) => ApiError::ParseError(msg),
-                soap_client::SoapError::Fault(code) => ApiError::SoapFault(code),
-            })?;
+            .map_err(|e| eventing_error(e, Some(&request.sid)))?;
 
         Ok(RenewResponse {
             timeout_seconds: actual_timeout_seconds,
         })
     }
+
+    /// [`execute()`](Self::execute) through an
+    /// [`AsyncSoapClient`](soap_client::AsyncSoapClient)
+    #[cfg(feature = "async-client")]
+    pub async fn execute_async(
+        soap_client: &soap_client::AsyncSoapClient,
+        ip: &str,
+        service: Service,
+        request: &RenewRequest,
+    ) -> Result<RenewResponse> {
+        let (ip, port) = soap_client::split_host_port(ip);
+        let actual_timeout_seconds = soap_client
+            .renew_subscription(
+                ip,
+                port,
+                service.info().event_endpoint,
+                &request.sid,
+                request.timeout_seconds,
+            )
+            .await
+            .map_err(|e| eventing_error(e, Some(&request.sid)))?;
+
+        Ok(RenewResponse {
+            timeout_seconds: actual_timeout_seconds,
+        })
+    }
+}
+
+/// The [`ApiError`] of a failed eventing request
+///
+/// A 412 means the device doesn't know the SID: for a renewal of `sid` that
+/// is [`ApiError::SubscriptionUnknown`], otherwise a subscription error.
+fn eventing_error(error: soap_client::SoapError, sid: Option<&str>) -> ApiError {
+    use soap_client::SoapError;
+    match error {
+        SoapError::Network(msg) => ApiError::NetworkError(msg),
+        SoapError::Parse(msg) => ApiError::ParseError(msg),
+        SoapError::Fault(fault) => ApiError::soap_fault(fault),
+        SoapError::XmlRejected(v) => ApiError::XmlRejected(v),
+        SoapError::PreconditionFailed => match sid {
+            Some(sid) => ApiError::SubscriptionUnknown(sid.to_string()),
+            None => ApiError::SubscriptionError(SoapError::PreconditionFailed.to_string()),
+        },
+    }
 }
 
 #[cfg(test)]
@@ -2,6 +2,12 @@
 //!
 //! Provides direct serde-based XML parsing with no business logic,
 //! replicating exactly what Sonos produces for sonos-stream consumption.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
 
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
@@ -91,6 +97,7 @@ impl GroupManagementEvent {
 
     /// Parse from UPnP event XML using serde
     pub fn from_xml(xml: &str) -> Result<Self> {
+        xml_utils::check(xml)?;
         let clean_xml = xml_utils::strip_namespaces(xml);
         quick_xml::de::from_str(&clean_xml)
             .map_err(|e| ApiError::ParseError(format!("Failed to parse GroupManagement XML: {e}")))
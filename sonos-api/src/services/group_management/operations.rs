@@ -9,7 +9,7 @@
 //! - `report_track_buffering_result` - Report track buffering status
 //! - `set_source_area_ids` - Set source area identifiers
 
-use crate::operation::parse_sonos_bool;
+use crate::operation::{opt_child_text, parse_sonos_bool};
 use crate::{define_upnp_operation, Validate};
 use paste::paste;
 use serde::{Deserialize, Serialize};
@@ -30,12 +30,20 @@ pub struct AddMemberOperationRequest {
 impl Validate for AddMemberOperationRequest {}
 
 /// Response from adding a member to the group
+///
+/// The coordinator answers with its own transport: what it plays, which
+/// group the member joined, and whether the member's volume should be
+/// reset. The member adopts it by pointing its transport at the coordinator
+/// ([`member_uri()`](Self::member_uri)).
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct AddMemberResponse {
     /// Current transport settings for the group
     pub current_transport_settings: String,
     /// Current URI being played
     pub current_uri: String,
+    /// DIDL-Lite metadata of `current_uri`, when the coordinator sends it
+    #[serde(default)]
+    pub current_uri_meta_data: String,
     /// UUID of the group that was joined
     pub group_uuid_joined: String,
     /// Whether to reset volume after joining
@@ -44,6 +52,82 @@ pub struct AddMemberResponse {
     pub volume_av_transport_uri: String,
 }
 
+impl AddMemberResponse {
+    /// The coordinator's transport, parsed from `current_uri`
+    pub fn transport(&self) -> GroupTransportUri {
+        GroupTransportUri::parse(&self.current_uri)
+    }
+
+    /// ID of the coordinator the member joined
+    ///
+    /// Taken from `group_uuid_joined` (`RINCON_…:N`), or from
+    /// `current_uri` when that names a speaker.
+    pub fn coordinator_id(&self) -> Option<&str> {
+        let from_group = self
+            .group_uuid_joined
+            .split(':')
+            .next()
+            .filter(|id| !id.is_empty());
+        from_group.or_else(|| match GroupTransportUri::parse(&self.current_uri) {
+            GroupTransportUri::Follow { .. } | GroupTransportUri::Queue { .. } => self
+                .current_uri
+                .split_once(':')
+                .map(|(_, rest)| rest.split('#').next().unwrap_or(rest)),
+            _ => None,
+        })
+    }
+
+    /// ID of the speaker whose queue the group plays, if it plays a queue
+    pub fn queue_owner_id(&self) -> Option<String> {
+        match self.transport() {
+            GroupTransportUri::Queue { owner_id, .. } => Some(owner_id),
+            _ => None,
+        }
+    }
+
+    /// URI the member sets with `SetAVTransportURI` to finish joining:
+    /// `x-rincon:{coordinator}`
+    pub fn member_uri(&self) -> Option<String> {
+        self.coordinator_id().map(|id| format!("x-rincon:{id}"))
+    }
+}
+
+/// A transport URI as it relates to grouping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupTransportUri {
+    /// `x-rincon:{id}`: following another speaker's stream as a group member
+    Follow { coordinator_id: String },
+    /// `x-rincon-queue:{id}#{instance}`: playing a speaker's queue
+    Queue { owner_id: String, instance: u32 },
+    /// Any other source (a stream, file or line-in), verbatim
+    Other(String),
+    /// No transport URI set
+    Empty,
+}
+
+impl GroupTransportUri {
+    /// Classify a transport URI
+    pub fn parse(uri: &str) -> Self {
+        let uri = uri.trim();
+        if uri.is_empty() {
+            return Self::Empty;
+        }
+        if let Some(id) = uri.strip_prefix("x-rincon:") {
+            return Self::Follow {
+                coordinator_id: id.to_string(),
+            };
+        }
+        if let Some(rest) = uri.strip_prefix("x-rincon-queue:") {
+            let (owner_id, instance) = rest.split_once('#').unwrap_or((rest, "0"));
+            return Self::Queue {
+                owner_id: owner_id.to_string(),
+                instance: instance.parse().unwrap_or(0),
+            };
+        }
+        Self::Other(uri.to_string())
+    }
+}
+
 /// Operation to add a member to a speaker group
 pub struct AddMemberOperation;
 
@@ -64,35 +148,24 @@ impl crate::operation::UPnPOperation for AddMemberOperation {
     }
 
     fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, crate::error::ApiError> {
-        let current_transport_settings = xml
-            .get_child("CurrentTransportSettings")
-            .and_then(|e| e.get_text())
-            .map(|s| s.to_string())
-            .unwrap_or_default();
-
-        let current_uri = xml
-            .get_child("CurrentURI")
-            .and_then(|e| e.get_text())
-            .map(|s| s.to_string())
-            .unwrap_or_default();
-
-        let group_uuid_joined = xml
-            .get_child("GroupUUIDJoined")
-            .and_then(|e| e.get_text())
-            .map(|s| s.to_string())
-            .unwrap_or_default();
+        let current_transport_settings =
+            opt_child_text(xml, "CurrentTransportSettings").unwrap_or_default();
+
+        let current_uri = opt_child_text(xml, "CurrentURI").unwrap_or_default();
+
+        let current_uri_meta_data = opt_child_text(xml, "CurrentURIMetaData").unwrap_or_default();
+
+        let group_uuid_joined = opt_child_text(xml, "GroupUUIDJoined").unwrap_or_default();
 
         let reset_volume_after = parse_sonos_bool(xml, "ResetVolumeAfter");
 
-        let volume_av_transport_uri = xml
-            .get_child("VolumeAVTransportURI")
-            .and_then(|e| e.get_text())
-            .map(|s| s.to_string())
-            .unwrap_or_default();
+        let volume_av_transport_uri =
+            opt_child_text(xml, "VolumeAVTransportURI").unwrap_or_default();
 
         Ok(AddMemberResponse {
             current_transport_settings,
             current_uri,
+            current_uri_meta_data,
             group_uuid_joined,
             reset_volume_after,
             volume_av_transport_uri,
@@ -274,6 +347,80 @@ mod tests {
         assert!(!response.reset_volume_after);
     }
 
+    #[test]
+    fn test_add_member_response_nested_uri_and_metadata() {
+        let xml_str = r#"<AddMemberResponse>
+            <CurrentTransportSettings></CurrentTransportSettings>
+            <CurrentURI>x-rincon-queue:RINCON_LIVING01400#0</CurrentURI>
+            <CurrentURIMetaData>&lt;DIDL-Lite&gt;&lt;item id=&quot;Q:0&quot;/&gt;&lt;/DIDL-Lite&gt;</CurrentURIMetaData>
+            <GroupUUIDJoined>RINCON_LIVING01400:1234</GroupUUIDJoined>
+            <ResetVolumeAfter>0</ResetVolumeAfter>
+            <VolumeAVTransportURI></VolumeAVTransportURI>
+        </AddMemberResponse>"#;
+        let xml = xmltree::Element::parse(xml_str.as_bytes()).unwrap();
+        let response = AddMemberOperation::parse_response(&xml).unwrap();
+
+        assert_eq!(
+            response.current_uri_meta_data,
+            r#"<DIDL-Lite><item id="Q:0"/></DIDL-Lite>"#
+        );
+        assert_eq!(
+            response.transport(),
+            GroupTransportUri::Queue {
+                owner_id: "RINCON_LIVING01400".to_string(),
+                instance: 0
+            }
+        );
+        assert_eq!(response.coordinator_id(), Some("RINCON_LIVING01400"));
+        assert_eq!(
+            response.queue_owner_id().as_deref(),
+            Some("RINCON_LIVING01400")
+        );
+        assert_eq!(
+            response.member_uri().as_deref(),
+            Some("x-rincon:RINCON_LIVING01400")
+        );
+    }
+
+    #[test]
+    fn test_add_member_response_coordinator_from_uri() {
+        let response = AddMemberResponse {
+            current_uri: "x-rincon:RINCON_DEN01400".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(response.coordinator_id(), Some("RINCON_DEN01400"));
+        assert_eq!(response.queue_owner_id(), None);
+
+        let streaming = AddMemberResponse {
+            current_uri: "x-sonosapi-stream:s12345?sid=254".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(streaming.coordinator_id(), None);
+        assert_eq!(streaming.member_uri(), None);
+    }
+
+    #[test]
+    fn test_group_transport_uri_parse() {
+        assert_eq!(GroupTransportUri::parse(""), GroupTransportUri::Empty);
+        assert_eq!(
+            GroupTransportUri::parse("x-rincon:RINCON_A"),
+            GroupTransportUri::Follow {
+                coordinator_id: "RINCON_A".to_string()
+            }
+        );
+        assert_eq!(
+            GroupTransportUri::parse("x-rincon-queue:RINCON_A#3"),
+            GroupTransportUri::Queue {
+                owner_id: "RINCON_A".to_string(),
+                instance: 3
+            }
+        );
+        assert_eq!(
+            GroupTransportUri::parse("x-rincon-stream:RINCON_A"),
+            GroupTransportUri::Other("x-rincon-stream:RINCON_A".to_string())
+        );
+    }
+
     // --- RemoveMember Tests ---
 
     #[test]
@@ -11,6 +11,12 @@
 //!   <e:property><GroupVolumeChangeable>1</GroupVolumeChangeable></e:property>
 //! </e:propertyset>
 //! ```
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
 
 use serde::{Deserialize, Serialize};
 
@@ -73,6 +79,7 @@ impl GroupRenderingControlEvent {
 
     /// Parse from UPnP event XML using serde
     pub fn from_xml(xml: &str) -> Result<Self> {
+        xml_utils::check(xml)?;
         let clean_xml = xml_utils::strip_namespaces(xml);
         quick_xml::de::from_str(&clean_xml).map_err(|e| {
             ApiError::ParseError(format!("Failed to parse GroupRenderingControl XML: {e}"))
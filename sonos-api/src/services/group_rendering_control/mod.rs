@@ -17,8 +17,15 @@
 //! ```
 //!
 //! # Important Notes
-//! - Operations should only be sent to the group coordinator
-//! - Sending to non-coordinator speakers will result in error code 701
+//! - Every action is coordinator-only. A member answers with UPnP error 701,
+//!   which the client returns as [`ApiError::NotCoordinator`] so callers can
+//!   look up the current coordinator and send it there
+//! - The group volume is the members' volumes scaled together, keeping their
+//!   ratios as of the last `SnapshotGroupVolume`. Take a snapshot before a
+//!   run of `SetGroupVolume` / `SetRelativeGroupVolume` calls (e.g. when a
+//!   slider drag starts) so member volumes changed since are respected
+//!
+//! [`ApiError::NotCoordinator`]: crate::ApiError::NotCoordinator
 
 pub mod events;
 pub mod operations;
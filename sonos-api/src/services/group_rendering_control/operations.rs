@@ -1,7 +1,8 @@
 //! GroupRenderingControl service operations
 //!
 //! This module provides operations for controlling group-wide audio rendering settings
-//! on Sonos speaker groups. All operations should be sent to the group coordinator only.
+//! on Sonos speaker groups. All operations should be sent to the group coordinator only;
+//! members fault with `ApiError::NotCoordinator`.
 //!
 //! # Operations
 //! - `get_group_volume` - Get the current group volume level
@@ -90,6 +91,8 @@ define_operation_with_response! {
     xml_mapping: {
         new_volume: "NewVolume",
     },
+    // Relative changes scale from the snapshot's volume ratios
+    dependencies: ["SnapshotGroupVolume"],
 }
 
 impl Validate for SetRelativeGroupVolumeOperationRequest {
@@ -256,6 +259,7 @@ mod tests {
         assert_eq!(op.request().adjustment, 10);
         assert_eq!(op.request().instance_id, 0);
         assert_eq!(op.metadata().action, "SetRelativeGroupVolume");
+        assert_eq!(op.metadata().dependencies, ["SnapshotGroupVolume"]);
     }
 
     #[test]
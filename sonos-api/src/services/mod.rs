@@ -20,7 +20,11 @@
 //! let rc_subscription = rendering_control::subscribe(&client, "192.168.1.100", "http://callback")?;
 //! ```
 
+pub mod alarm_clock;
 pub mod av_transport;
+pub mod connection_manager;
+pub mod content_directory;
+pub mod device_properties;
 pub mod events;
 pub mod group_management;
 pub mod group_rendering_control;
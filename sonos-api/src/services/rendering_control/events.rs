@@ -2,6 +2,12 @@
 //!
 //! Provides direct serde-based XML parsing with no business logic,
 //! replicating exactly what Sonos produces for sonos-stream consumption.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -34,7 +40,7 @@ pub struct RenderingControlEventData {
     instance: RenderingControlInstance,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct RenderingControlInstance {
     #[serde(rename = "Volume", default)]
     pub volumes: Vec<ChannelValueAttribute>,
@@ -53,6 +59,30 @@ struct RenderingControlInstance {
 
     #[serde(rename = "Balance", default)]
     pub balance: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "SubEnabled", default)]
+    pub sub_enabled: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "SubGain", default)]
+    pub sub_gain: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "SurroundEnabled", default)]
+    pub surround_enabled: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "SurroundMode", default)]
+    pub surround_mode: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "SurroundLevel", default)]
+    pub surround_level: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "MusicSurroundLevel", default)]
+    pub music_surround_level: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "OutputFixed", default)]
+    pub output_fixed: Option<xml_utils::ValueAttribute>,
+
+    #[serde(rename = "PresetNameList", default)]
+    pub preset_name_list: Option<xml_utils::ValueAttribute>,
 }
 
 /// Represents an XML element with both val and channel attributes
@@ -136,6 +166,50 @@ impl RenderingControlEvent {
             .map(|v| v.val.clone())
     }
 
+    /// Get Sub enabled ("0"/"1", home-theater primary only)
+    pub fn sub_enabled(&self) -> Option<String> {
+        Self::val(&self.property.last_change.instance.sub_enabled)
+    }
+
+    /// Get Sub gain (-15 to +15)
+    pub fn sub_gain(&self) -> Option<String> {
+        Self::val(&self.property.last_change.instance.sub_gain)
+    }
+
+    /// Get surround enabled ("0"/"1")
+    pub fn surround_enabled(&self) -> Option<String> {
+        Self::val(&self.property.last_change.instance.surround_enabled)
+    }
+
+    /// Get surround mode ("0" ambient, "1" full)
+    pub fn surround_mode(&self) -> Option<String> {
+        Self::val(&self.property.last_change.instance.surround_mode)
+    }
+
+    /// Get TV surround level (-15 to +15)
+    pub fn surround_level(&self) -> Option<String> {
+        Self::val(&self.property.last_change.instance.surround_level)
+    }
+
+    /// Get music surround level (-15 to +15)
+    pub fn music_surround_level(&self) -> Option<String> {
+        Self::val(&self.property.last_change.instance.music_surround_level)
+    }
+
+    /// Get output fixed ("0"/"1", line-out volume locked)
+    pub fn output_fixed(&self) -> Option<String> {
+        Self::val(&self.property.last_change.instance.output_fixed)
+    }
+
+    /// Get the comma-separated EQ preset names
+    pub fn preset_name_list(&self) -> Option<String> {
+        Self::val(&self.property.last_change.instance.preset_name_list)
+    }
+
+    fn val(attr: &Option<xml_utils::ValueAttribute>) -> Option<String> {
+        attr.as_ref().map(|v| v.val.clone())
+    }
+
     /// Get other channels as a map of all non-standard channels
     pub fn other_channels(&self) -> HashMap<String, String> {
         let mut channels = HashMap::new();
@@ -192,12 +266,21 @@ impl RenderingControlEvent {
             treble: self.treble(),
             loudness: self.loudness(),
             balance: self.balance(),
+            sub_enabled: self.sub_enabled(),
+            sub_gain: self.sub_gain(),
+            surround_enabled: self.surround_enabled(),
+            surround_mode: self.surround_mode(),
+            surround_level: self.surround_level(),
+            music_surround_level: self.music_surround_level(),
+            output_fixed: self.output_fixed(),
+            preset_name_list: self.preset_name_list(),
             other_channels: self.other_channels(),
         }
     }
 
     /// Parse from UPnP event XML using serde
     pub fn from_xml(xml: &str) -> Result<Self> {
+        xml_utils::check(xml)?;
         let clean_xml = xml_utils::strip_namespaces(xml);
         quick_xml::de::from_str(&clean_xml)
             .map_err(|e| ApiError::ParseError(format!("Failed to parse RenderingControl XML: {e}")))
@@ -285,6 +368,7 @@ mod tests {
                         balance: Some(xml_utils::ValueAttribute {
                             val: "0".to_string(),
                         }),
+                        ..Default::default()
                     },
                 },
             },
@@ -339,6 +423,16 @@ mod tests {
         assert_eq!(event.lf_mute(), Some("1".to_string()));
     }
 
+    #[test]
+    fn test_output_fixed_and_preset_list_parsing() {
+        // Initial LastChange from a Port (trimmed)
+        let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/RCS/&quot;&gt;&lt;InstanceID val=&quot;0&quot;&gt;&lt;Volume channel=&quot;Master&quot; val=&quot;30&quot;/&gt;&lt;OutputFixed val=&quot;1&quot;/&gt;&lt;PresetNameList val=&quot;FactoryDefaults&quot;/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#;
+
+        let state = RenderingControlEvent::from_xml(xml).unwrap().into_state();
+        assert_eq!(state.output_fixed.as_deref(), Some("1"));
+        assert_eq!(state.preset_name_list.as_deref(), Some("FactoryDefaults"));
+    }
+
     #[test]
     fn test_enriched_event_creation() {
         let ip: IpAddr = "192.168.1.100".parse().unwrap();
@@ -361,6 +455,7 @@ mod tests {
                         treble: None,
                         loudness: None,
                         balance: None,
+                        ..Default::default()
                     },
                 },
             },
@@ -395,6 +490,7 @@ mod tests {
                         treble: None,
                         loudness: None,
                         balance: None,
+                        ..Default::default()
                     },
                 },
             },
@@ -439,6 +535,7 @@ mod tests {
                             val: "1".to_string(),
                         }),
                         balance: None,
+                        ..Default::default()
                     },
                 },
             },
@@ -13,6 +13,10 @@
 //! | `get_bass` / `set_bass` | Get/set bass level (-10 to +10) |
 //! | `get_treble` / `set_treble` | Get/set treble level (-10 to +10) |
 //! | `get_loudness` / `set_loudness` | Get/set loudness compensation |
+//! | `get_eq` / `set_eq` | Get/set home-theater EQ (`SubGain`, `SurroundMode`, ...) |
+//! | `get_output_fixed` | Whether line-out volume is fixed |
+//! | `get_headphone_connected` | Whether headphones are plugged in (some models only) |
+//! | `list_presets` / `select_preset` | List/recall EQ presets (`FactoryDefaults` resets EQ) |
 //!
 //! # Examples
 //! ```rust,ignore
@@ -10,6 +10,10 @@
 //! - `get_bass` / `set_bass` - Get/set bass level (-10 to +10)
 //! - `get_treble` / `set_treble` - Get/set treble level (-10 to +10)
 //! - `get_loudness` / `set_loudness` - Get/set loudness compensation
+//! - `get_eq` / `set_eq` - Get/set home-theater EQ values (Sub, surround, ...)
+//! - `get_output_fixed` - Whether line-out volume is fixed
+//! - `get_headphone_connected` - Whether headphones are plugged in
+//! - `list_presets` / `select_preset` - List and recall EQ presets (`FactoryDefaults`)
 
 use crate::operation::{parse_sonos_bool, validate_channel};
 use crate::{define_operation_with_response, define_upnp_operation, Validate};
@@ -396,6 +400,255 @@ impl Validate for SetLoudnessOperationRequest {
 
 pub use set_loudness_operation as set_loudness;
 
+// =============================================================================
+// GET EQ / SET EQ
+// =============================================================================
+
+/// `EQType` values accepted by `GetEQ` / `SetEQ`, with the `SetEQ` value range
+///
+/// These settings exist on home-theater bonds and must be sent to the bond's
+/// primary (soundbar) device.
+pub const EQ_TYPES: [(&str, i16, i16); 8] = [
+    ("SubEnable", 0, 1),
+    ("SubGain", -15, 15),
+    ("SurroundEnable", 0, 1),
+    ("SurroundMode", 0, 1),
+    ("SurroundLevel", -15, 15),
+    ("MusicSurroundLevel", -15, 15),
+    ("DialogLevel", 0, 1),
+    ("NightMode", 0, 1),
+];
+
+fn validate_eq_type(eq_type: &str) -> Result<(i16, i16), crate::operation::ValidationError> {
+    EQ_TYPES
+        .iter()
+        .find(|(name, _, _)| *name == eq_type)
+        .map(|(_, min, max)| (*min, *max))
+        .ok_or_else(|| crate::operation::ValidationError::invalid_value("eq_type", eq_type))
+}
+
+define_operation_with_response! {
+    operation: GetEqOperation,
+    action: "GetEQ",
+    service: RenderingControl,
+    request: {
+        eq_type: String,
+    },
+    response: GetEqResponse {
+        current_value: i16,
+    },
+    xml_mapping: {
+        current_value: "CurrentValue",
+    },
+}
+
+impl Validate for GetEqOperationRequest {
+    fn validate_basic(&self) -> Result<(), crate::operation::ValidationError> {
+        validate_eq_type(&self.eq_type).map(|_| ())
+    }
+}
+
+pub use get_eq_operation as get_eq;
+
+define_upnp_operation! {
+    operation: SetEqOperation,
+    action: "SetEQ",
+    service: RenderingControl,
+    request: {
+        eq_type: String,
+        desired_value: i16,
+    },
+    response: (),
+    payload: |req| {
+        format!(
+            "<InstanceID>{}</InstanceID><EQType>{}</EQType><DesiredValue>{}</DesiredValue>",
+            req.instance_id, req.eq_type, req.desired_value
+        )
+    },
+    parse: |_xml| Ok(()),
+}
+
+impl Validate for SetEqOperationRequest {
+    fn validate_basic(&self) -> Result<(), crate::operation::ValidationError> {
+        let (min, max) = validate_eq_type(&self.eq_type)?;
+        if self.desired_value < min || self.desired_value > max {
+            return Err(crate::operation::ValidationError::range_error(
+                &self.eq_type,
+                min,
+                max,
+                self.desired_value,
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub use set_eq_operation as set_eq;
+
+// =============================================================================
+// GET OUTPUT FIXED
+// =============================================================================
+
+// Manual implementation for bool "0"/"1" parsing (same reason as GetMute).
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct GetOutputFixedOperationRequest {
+    pub instance_id: u32,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct GetOutputFixedResponse {
+    /// `true` when line-out is fixed and volume can't be changed
+    pub current_fixed: bool,
+}
+
+pub struct GetOutputFixedOperation;
+
+impl crate::operation::UPnPOperation for GetOutputFixedOperation {
+    type Request = GetOutputFixedOperationRequest;
+    type Response = GetOutputFixedResponse;
+
+    const SERVICE: crate::service::Service = crate::service::Service::RenderingControl;
+    const ACTION: &'static str = "GetOutputFixed";
+
+    fn build_payload(request: &Self::Request) -> Result<String, crate::operation::ValidationError> {
+        Ok(format!("<InstanceID>{}</InstanceID>", request.instance_id))
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, crate::error::ApiError> {
+        Ok(GetOutputFixedResponse {
+            current_fixed: parse_sonos_bool(xml, "CurrentFixed"),
+        })
+    }
+}
+
+pub fn get_output_fixed_operation() -> crate::operation::OperationBuilder<GetOutputFixedOperation> {
+    crate::operation::OperationBuilder::new(GetOutputFixedOperationRequest { instance_id: 0 })
+}
+
+impl Validate for GetOutputFixedOperationRequest {}
+
+pub use get_output_fixed_operation as get_output_fixed;
+
+// =============================================================================
+// GET HEADPHONE CONNECTED
+// =============================================================================
+
+// Manual implementation for bool "0"/"1" parsing (same reason as GetMute).
+// Only some models implement it; the rest fault with 401, surfacing as
+// `ApiError::NotSupported`.
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct GetHeadphoneConnectedOperationRequest {
+    pub instance_id: u32,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct GetHeadphoneConnectedResponse {
+    /// `true` when headphones are plugged in and playing instead of the speaker
+    pub current_headphone_connected: bool,
+}
+
+pub struct GetHeadphoneConnectedOperation;
+
+impl crate::operation::UPnPOperation for GetHeadphoneConnectedOperation {
+    type Request = GetHeadphoneConnectedOperationRequest;
+    type Response = GetHeadphoneConnectedResponse;
+
+    const SERVICE: crate::service::Service = crate::service::Service::RenderingControl;
+    const ACTION: &'static str = "GetHeadphoneConnected";
+
+    fn build_payload(request: &Self::Request) -> Result<String, crate::operation::ValidationError> {
+        Ok(format!("<InstanceID>{}</InstanceID>", request.instance_id))
+    }
+
+    fn parse_response(xml: &xmltree::Element) -> Result<Self::Response, crate::error::ApiError> {
+        Ok(GetHeadphoneConnectedResponse {
+            current_headphone_connected: parse_sonos_bool(xml, "CurrentHeadphoneConnected"),
+        })
+    }
+}
+
+pub fn get_headphone_connected_operation(
+) -> crate::operation::OperationBuilder<GetHeadphoneConnectedOperation> {
+    crate::operation::OperationBuilder::new(GetHeadphoneConnectedOperationRequest {
+        instance_id: 0,
+    })
+}
+
+impl Validate for GetHeadphoneConnectedOperationRequest {}
+
+pub use get_headphone_connected_operation as get_headphone_connected;
+
+// =============================================================================
+// LIST PRESETS / SELECT PRESET
+// =============================================================================
+
+/// Preset that resets bass, treble and loudness to their defaults
+pub const FACTORY_DEFAULTS_PRESET: &str = "FactoryDefaults";
+
+/// Split a `PresetNameList` (`"FactoryDefaults,..."`) into preset names
+pub fn parse_preset_name_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+define_operation_with_response! {
+    operation: ListPresetsOperation,
+    action: "ListPresets",
+    service: RenderingControl,
+    request: {},
+    response: ListPresetsResponse {
+        current_preset_name_list: String,
+    },
+    xml_mapping: {
+        current_preset_name_list: "CurrentPresetNameList",
+    },
+}
+
+impl Validate for ListPresetsOperationRequest {}
+
+impl ListPresetsResponse {
+    /// The preset names, split out of `current_preset_name_list`
+    pub fn preset_names(&self) -> Vec<String> {
+        parse_preset_name_list(&self.current_preset_name_list)
+    }
+}
+
+pub use list_presets_operation as list_presets;
+
+define_upnp_operation! {
+    operation: SelectPresetOperation,
+    action: "SelectPreset",
+    service: RenderingControl,
+    request: {
+        preset_name: String,
+    },
+    response: (),
+    payload: |req| {
+        format!(
+            "<InstanceID>{}</InstanceID><PresetName>{}</PresetName>",
+            req.instance_id,
+            crate::operation::xml_escape(&req.preset_name)
+        )
+    },
+    parse: |_xml| Ok(()),
+}
+
+impl Validate for SelectPresetOperationRequest {
+    fn validate_basic(&self) -> Result<(), crate::operation::ValidationError> {
+        if self.preset_name.trim().is_empty() {
+            return Err(crate::operation::ValidationError::MissingParameter {
+                parameter: "preset_name".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+pub use select_preset_operation as select_preset;
+
 // Legacy convenience functions for backward compatibility
 pub use get_volume_operation as get_volume;
 pub use set_relative_volume_operation as set_relative_volume;
@@ -863,4 +1116,117 @@ mod tests {
         };
         assert!(request.validate_basic().is_err());
     }
+
+    // =========================================================================
+    // EQ operation tests
+    // =========================================================================
+
+    #[test]
+    fn test_set_eq_payload_per_key() {
+        for (eq_type, value) in [
+            ("SubEnable", 1),
+            ("SubGain", -7),
+            ("SurroundEnable", 0),
+            ("SurroundMode", 1),
+            ("SurroundLevel", 12),
+        ] {
+            let op = set_eq_operation(eq_type.to_string(), value)
+                .build()
+                .unwrap();
+            assert_eq!(
+                SetEqOperation::build_payload(op.request()).unwrap(),
+                format!(
+                    "<InstanceID>0</InstanceID><EQType>{eq_type}</EQType><DesiredValue>{value}</DesiredValue>"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_eq_validates_ranges() {
+        assert!(set_eq_operation("SubGain".to_string(), 15).build().is_ok());
+        assert!(set_eq_operation("SubGain".to_string(), 16).build().is_err());
+        assert!(set_eq_operation("SurroundLevel".to_string(), -16)
+            .build()
+            .is_err());
+        assert!(set_eq_operation("SubEnable".to_string(), 2)
+            .build()
+            .is_err());
+        assert!(set_eq_operation("Bogus".to_string(), 0).build().is_err());
+        assert!(get_eq_operation("Bogus".to_string()).build().is_err());
+    }
+
+    #[test]
+    fn test_get_eq_parse_response() {
+        let xml_str = r#"<GetEQResponse><CurrentValue>-4</CurrentValue></GetEQResponse>"#;
+        let xml = xmltree::Element::parse(xml_str.as_bytes()).unwrap();
+        let response = GetEqOperation::parse_response(&xml).unwrap();
+        assert_eq!(response.current_value, -4);
+    }
+
+    // =========================================================================
+    // Output fixed / preset operation tests
+    // =========================================================================
+
+    #[test]
+    fn test_get_output_fixed_parse_response() {
+        let xml_str =
+            r#"<GetOutputFixedResponse><CurrentFixed>1</CurrentFixed></GetOutputFixedResponse>"#;
+        let xml = xmltree::Element::parse(xml_str.as_bytes()).unwrap();
+        assert!(
+            GetOutputFixedOperation::parse_response(&xml)
+                .unwrap()
+                .current_fixed
+        );
+    }
+
+    #[test]
+    fn test_get_headphone_connected() {
+        let op = get_headphone_connected_operation().build().unwrap();
+        assert_eq!(op.metadata().action, "GetHeadphoneConnected");
+        assert_eq!(
+            GetHeadphoneConnectedOperation::build_payload(op.request()).unwrap(),
+            "<InstanceID>0</InstanceID>"
+        );
+
+        for (wire, connected) in [("1", true), ("0", false)] {
+            let xml_str = format!(
+                "<GetHeadphoneConnectedResponse><CurrentHeadphoneConnected>{wire}</CurrentHeadphoneConnected></GetHeadphoneConnectedResponse>"
+            );
+            let xml = xmltree::Element::parse(xml_str.as_bytes()).unwrap();
+            assert_eq!(
+                GetHeadphoneConnectedOperation::parse_response(&xml)
+                    .unwrap()
+                    .current_headphone_connected,
+                connected
+            );
+        }
+    }
+
+    #[test]
+    fn test_list_presets_parse_response() {
+        let xml_str = r#"<ListPresetsResponse><CurrentPresetNameList>FactoryDefaults, Night</CurrentPresetNameList></ListPresetsResponse>"#;
+        let xml = xmltree::Element::parse(xml_str.as_bytes()).unwrap();
+        let response = ListPresetsOperation::parse_response(&xml).unwrap();
+        assert_eq!(response.preset_names(), ["FactoryDefaults", "Night"]);
+        assert!(parse_preset_name_list("").is_empty());
+    }
+
+    #[test]
+    fn test_select_preset_payload() {
+        let op = select_preset_operation(FACTORY_DEFAULTS_PRESET.to_string())
+            .build()
+            .unwrap();
+        assert_eq!(
+            SelectPresetOperation::build_payload(op.request()).unwrap(),
+            "<InstanceID>0</InstanceID><PresetName>FactoryDefaults</PresetName>"
+        );
+        let op = select_preset_operation("Rock & Roll".to_string())
+            .build()
+            .unwrap();
+        assert!(SelectPresetOperation::build_payload(op.request())
+            .unwrap()
+            .contains("<PresetName>Rock &amp; Roll</PresetName>"));
+        assert!(select_preset_operation(" ".to_string()).build().is_err());
+    }
 }
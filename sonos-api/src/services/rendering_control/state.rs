@@ -11,7 +11,7 @@ use crate::SonosClient;
 ///
 /// Canonical type used by both UPnP event streaming and polling.
 /// Fields match the UPnP RenderingControl event data 1:1.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct RenderingControlState {
     /// Current volume level (0-100) for Master channel
     pub master_volume: Option<String>,
@@ -43,6 +43,30 @@ pub struct RenderingControlState {
     /// Balance setting (-100 to +100)
     pub balance: Option<String>,
 
+    /// Sub enabled ("0"/"1"); reported by home-theater primaries only
+    pub sub_enabled: Option<String>,
+
+    /// Sub gain (-15 to +15)
+    pub sub_gain: Option<String>,
+
+    /// Surround speakers enabled ("0"/"1")
+    pub surround_enabled: Option<String>,
+
+    /// Surround mode ("0" ambient, "1" full)
+    pub surround_mode: Option<String>,
+
+    /// TV surround level (-15 to +15)
+    pub surround_level: Option<String>,
+
+    /// Music surround level (-15 to +15)
+    pub music_surround_level: Option<String>,
+
+    /// Line-out volume fixed ("0"/"1")
+    pub output_fixed: Option<String>,
+
+    /// Comma-separated EQ preset names (e.g. "FactoryDefaults")
+    pub preset_name_list: Option<String>,
+
     /// Additional channel configurations (can be extended)
     pub other_channels: HashMap<String, String>,
 }
@@ -87,6 +111,14 @@ pub fn poll(client: &SonosClient, ip: &str) -> crate::Result<RenderingControlSta
         lf_mute: None,
         rf_mute: None,
         balance: None,
+        sub_enabled: None,
+        sub_gain: None,
+        surround_enabled: None,
+        surround_mode: None,
+        surround_level: None,
+        music_surround_level: None,
+        output_fixed: None,
+        preset_name_list: None,
         other_channels: HashMap::new(),
     })
 }
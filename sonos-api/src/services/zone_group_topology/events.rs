@@ -2,6 +2,12 @@
 //!
 //! Provides direct serde-based XML parsing with no business logic,
 //! replicating exactly what Sonos produces for sonos-stream consumption.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
 
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
@@ -66,6 +72,9 @@ struct ZoneGroupMember {
     #[serde(rename = "@SoftwareVersion", default)]
     software_version: Option<String>,
 
+    #[serde(rename = "@SWGen", default)]
+    sw_gen: Option<String>,
+
     #[serde(rename = "@WirelessMode", default)]
     wireless_mode: Option<String>,
 
@@ -156,6 +165,9 @@ pub struct ZoneGroupMemberInfo {
     pub location: String,
     pub zone_name: String,
     pub software_version: String,
+    /// Software generation from `SWGen` (1 = S1, 2 = S2); absent on older firmware
+    #[serde(default)]
+    pub software_generation: Option<u8>,
     pub boot_seq: u32,
     pub network_info: NetworkInfo,
     pub satellites: Vec<SatelliteInfo>,
@@ -198,6 +210,7 @@ pub struct SatelliteInfo {
 /// Shared by UPnP event processing and polling for parity.
 /// The XML should be the inner `<ZoneGroupState>` content, e.g. from `GetZoneGroupState` response.
 pub fn parse_zone_group_state_xml(raw_xml: &str) -> Result<Vec<ZoneGroupInfo>> {
+    xml_utils::check(raw_xml)?;
     let clean_xml = xml_utils::strip_namespaces(raw_xml);
     let state: ZoneGroupState = quick_xml::de::from_str(&clean_xml)
         .map_err(|e| ApiError::ParseError(format!("ZoneGroupState parse error: {e}")))?;
@@ -221,6 +234,7 @@ fn convert_zone_groups(zone_group_state: &ZoneGroupState) -> Vec<ZoneGroupInfo>
                     location: member.location.clone(),
                     zone_name: member.zone_name.clone(),
                     software_version: member.software_version.clone().unwrap_or_default(),
+                    software_generation: member.sw_gen.as_deref().and_then(|s| s.parse().ok()),
                     boot_seq: member
                         .boot_seq
                         .as_deref()
@@ -286,6 +300,7 @@ impl ZoneGroupTopologyEvent {
 
     /// Parse from UPnP event XML using serde
     pub fn from_xml(xml: &str) -> Result<Self> {
+        xml_utils::check(xml)?;
         let clean_xml = xml_utils::strip_namespaces(xml);
         quick_xml::de::from_str(&clean_xml).map_err(|e| {
             ApiError::ParseError(format!("Failed to parse ZoneGroupTopology XML: {e}"))
@@ -355,6 +370,7 @@ mod tests {
             location: "http://192.168.1.100:1400/xml/device_description.xml".to_string(),
             zone_name: "Living Room".to_string(),
             software_version: "56.0-76060".to_string(),
+            software_generation: None,
             boot_seq: 0,
             network_info: NetworkInfo {
                 wireless_mode: "0".to_string(),
@@ -532,7 +548,7 @@ mod xml_parsing_tests {
             <ZoneGroups>
                 <ZoneGroup Coordinator="RINCON_111" ID="RINCON_111:0">
                     <ZoneGroupMember UUID="RINCON_111" Location="http://192.168.1.100:1400/xml/device_description.xml" ZoneName="Living Room"/>
-                    <ZoneGroupMember UUID="RINCON_222" Location="http://192.168.1.101:1400/xml/device_description.xml" ZoneName="Kitchen"/>
+                    <ZoneGroupMember UUID="RINCON_222" Location="http://192.168.1.101:1400/xml/device_description.xml" ZoneName="Kitchen" SoftwareVersion="85.0-64200" SWGen="2"/>
                 </ZoneGroup>
             </ZoneGroups>
         </ZoneGroupState>"#;
@@ -545,5 +561,25 @@ mod xml_parsing_tests {
         assert_eq!(groups[0].members.len(), 2);
         assert_eq!(groups[0].members[0].zone_name, "Living Room");
         assert_eq!(groups[0].members[1].zone_name, "Kitchen");
+        assert_eq!(groups[0].members[0].software_generation, None);
+        assert_eq!(groups[0].members[1].software_version, "85.0-64200");
+        assert_eq!(groups[0].members[1].software_generation, Some(2));
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn fuzz_zone_group_topology_event(xml in xml_utils::fuzz::mutated(r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><ZoneGroupState>&lt;ZoneGroupState&gt;&lt;ZoneGroups&gt;&lt;ZoneGroup Coordinator=&quot;RINCON_123&quot; ID=&quot;RINCON_123:0&quot;&gt;&lt;ZoneGroupMember UUID=&quot;RINCON_123&quot; Location=&quot;http://192.168.1.100:1400/xml/device_description.xml&quot; ZoneName=&quot;Living Room&quot; SWGen=&quot;2&quot;&gt;&lt;Satellite UUID=&quot;RINCON_456&quot; Location=&quot;http://192.168.1.101:1400/xml/device_description.xml&quot; ZoneName=&quot;Sub&quot;/&gt;&lt;/ZoneGroupMember&gt;&lt;/ZoneGroup&gt;&lt;/ZoneGroups&gt;&lt;VanishedDevices&gt;&lt;Device UUID=&quot;RINCON_789&quot; ZoneName=&quot;Den&quot; Reason=&quot;powered off&quot;/&gt;&lt;/VanishedDevices&gt;&lt;/ZoneGroupState&gt;</ZoneGroupState></e:property></e:propertyset>"#)) {
+            if let Ok(event) = ZoneGroupTopologyEvent::from_xml(&xml) {
+                event.into_state();
+                event.vanished_devices();
+            }
+        }
+
+        #[test]
+        fn fuzz_zone_group_state(xml in xml_utils::fuzz::mutated(r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_111" ID="RINCON_111:0"><ZoneGroupMember UUID="RINCON_111" Location="http://192.168.1.100:1400/xml/device_description.xml" ZoneName="Living Room" SoftwareVersion="85.0-64200" SWGen="2"/></ZoneGroup></ZoneGroups></ZoneGroupState>"#)) {
+            let _ = parse_zone_group_state_xml(&xml);
+        }
     }
 }
@@ -1,16 +1,23 @@
 //! Managed UPnP subscription with lifecycle management
 //!
 //! This module provides a higher-level subscription API that handles the complete
-//! lifecycle of UPnP subscriptions with manual renewal and proper cleanup.
+//! lifecycle of UPnP subscriptions with manual or background renewal and
+//! proper cleanup.
 
+use crate::clock::SharedClock;
 use crate::services::events::{
     RenewOperation, RenewRequest, RenewResponse, SubscribeOperation, SubscribeRequest,
     UnsubscribeOperation, UnsubscribeRequest, UnsubscribeResponse,
 };
-use crate::{ApiError, Result, Service};
-use soap_client::SoapClient;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
+use crate::{ApiError, Result, RetryPolicy, Service};
+use soap_client::{split_host_port, SoapClient};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock, Weak};
+use std::task::{Context, Wake, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// A managed UPnP subscription with lifecycle management
 ///
@@ -20,6 +27,16 @@ use std::time::{Duration, SystemTime};
 /// - Proper cleanup on drop
 /// - Thread-safe state management
 ///
+/// # Sharing
+///
+/// `ManagedSubscription` is a cheap handle: `clone()` shares the same SID,
+/// expiry and endpoint, so renewal logic, registries and diagnostics can
+/// each hold their own copy. Every clone observes renewals and the terminal
+/// unsubscribed state. [`unsubscribe()`](Self::unsubscribe) may be called
+/// from any clone; the first call sends UNSUBSCRIBE and later calls return
+/// [`ApiError::AlreadyUnsubscribed`]. Dropping the last clone sends a
+/// best-effort UNSUBSCRIBE unless [`detach()`](Self::detach) was called.
+///
 /// # Example
 /// ```rust,no_run
 /// use sonos_api::{SonosClient, Service};
@@ -43,59 +60,107 @@ use std::time::{Duration, SystemTime};
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ManagedSubscription {
+    inner: Arc<SharedSubscription>,
+}
+
+/// State shared by every clone of a [`ManagedSubscription`]
+#[derive(Debug)]
+struct SharedSubscription {
     /// UPnP subscription ID (SID) returned by the device
     sid: String,
     /// Device IP address
     device_ip: String,
     /// Service being subscribed to
     service: Service,
+    /// URL the device sends events to
+    callback_url: String,
+    /// Component that created the subscription, if it said
+    owner: Option<String>,
     /// Subscription state (protected by mutex)
-    state: Arc<Mutex<SubscriptionState>>,
+    state: Mutex<SubscriptionState>,
     /// SOAP client for making requests
     soap_client: SoapClient,
+    /// Time source for expiry
+    clock: SharedClock,
+    /// Directory the subscription, and any that replaces it, is listed in
+    directory: Option<SubscriptionDirectory>,
 }
 
 #[derive(Debug)]
 struct SubscriptionState {
-    /// When this subscription expires
-    expires_at: SystemTime,
-    /// Whether the subscription is currently active
+    /// When this subscription expires, on the monotonic clock
+    expires_at: Instant,
+    /// Whether the subscription is currently active (false once unsubscribed)
     active: bool,
     /// Timeout duration for this subscription
     timeout_seconds: u32,
+    /// Skip the UNSUBSCRIBE when the last clone is dropped
+    detached: bool,
+    /// Background renewal started by `spawn_auto_renew()`
+    renewer: Option<Renewer>,
+}
+
+/// Handle on a subscription's background renewal thread
+#[derive(Debug)]
+struct Renewer {
+    stopped: Arc<AtomicBool>,
+    thread: thread::Thread,
+}
+
+impl Renewer {
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.thread.unpark();
+    }
 }
 
 impl ManagedSubscription {
     /// Create a new managed subscription by performing the initial subscribe operation
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn create(
         device_ip: String,
         service: Service,
         callback_url: String,
         timeout_seconds: u32,
         soap_client: SoapClient,
+        clock: SharedClock,
+        owner: Option<String>,
+        directory: Option<SubscriptionDirectory>,
     ) -> Result<Self> {
         let request = SubscribeRequest {
-            callback_url,
+            callback_url: callback_url.clone(),
             timeout_seconds,
         };
 
         let response = SubscribeOperation::execute(&soap_client, &device_ip, service, &request)?;
 
         let state = SubscriptionState {
-            expires_at: SystemTime::now() + Duration::from_secs(response.timeout_seconds as u64),
+            expires_at: clock.now() + Duration::from_secs(response.timeout_seconds as u64),
             active: true,
             timeout_seconds: response.timeout_seconds,
+            detached: false,
+            renewer: None,
         };
 
-        Ok(Self {
-            sid: response.sid,
-            device_ip,
-            service,
-            state: Arc::new(Mutex::new(state)),
-            soap_client,
-        })
+        let subscription = Self {
+            inner: Arc::new(SharedSubscription {
+                sid: response.sid,
+                device_ip,
+                service,
+                callback_url,
+                owner,
+                state: Mutex::new(state),
+                soap_client,
+                clock,
+                directory,
+            }),
+        };
+        if let Some(directory) = &subscription.inner.directory {
+            directory.register(&subscription);
+        }
+        Ok(subscription)
     }
 
     /// Send a UPnP unsubscribe request (internal use only)
@@ -120,13 +185,41 @@ impl ManagedSubscription {
 
     /// Get the subscription ID
     pub fn subscription_id(&self) -> &str {
-        &self.sid
+        &self.inner.sid
+    }
+
+    /// Service the subscription is to
+    pub fn service(&self) -> Service {
+        self.inner.service
+    }
+
+    /// URL the device sends this subscription's events to
+    pub fn callback_url(&self) -> &str {
+        &self.inner.callback_url
+    }
+
+    /// Tag of the component that created the subscription
+    ///
+    /// Set with [`SonosClient::with_subscription_owner()`](crate::SonosClient::with_subscription_owner);
+    /// `None` when the creating client had no tag.
+    pub fn owner(&self) -> Option<&str> {
+        self.inner.owner.as_deref()
     }
 
     /// Check if the subscription is still active and not expired
     pub fn is_active(&self) -> bool {
-        let state = self.state.lock().unwrap();
-        state.active && SystemTime::now() < state.expires_at
+        let state = self.inner.state.lock().unwrap();
+        state.active && self.inner.clock.now() < state.expires_at
+    }
+
+    /// Check if any clone has unsubscribed (terminal state)
+    pub fn is_unsubscribed(&self) -> bool {
+        !self.inner.state.lock().unwrap().active
+    }
+
+    /// Number of live clones sharing this subscription
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
     }
 
     /// Check if the subscription needs renewal
@@ -142,18 +235,15 @@ impl ManagedSubscription {
     /// Returns `Some(duration)` if renewal is needed within the threshold,
     /// `None` if renewal is not needed or subscription is inactive.
     pub fn time_until_renewal(&self) -> Option<Duration> {
-        let state = self.state.lock().unwrap();
+        let state = self.inner.state.lock().unwrap();
 
         if !state.active {
             return None;
         }
 
-        let now = SystemTime::now();
-        if now >= state.expires_at {
-            return Some(Duration::ZERO);
-        }
-
-        let time_until_expiry = state.expires_at.duration_since(now).ok()?;
+        let time_until_expiry = state
+            .expires_at
+            .saturating_duration_since(self.inner.clock.now());
         let renewal_threshold = Duration::from_secs(300); // 5 minutes
 
         if time_until_expiry <= renewal_threshold {
@@ -163,82 +253,257 @@ impl ManagedSubscription {
         }
     }
 
-    /// Get when the subscription expires
-    pub fn expires_at(&self) -> SystemTime {
-        let state = self.state.lock().unwrap();
+    /// Timeout granted by the device on the last subscribe or renewal, in seconds
+    pub fn timeout_seconds(&self) -> u32 {
+        self.inner.state.lock().unwrap().timeout_seconds
+    }
+
+    /// Clock the subscription's expiry is measured on
+    pub fn clock(&self) -> &SharedClock {
+        &self.inner.clock
+    }
+
+    /// Get when the subscription expires, on the monotonic clock
+    pub fn expires_at(&self) -> Instant {
+        let state = self.inner.state.lock().unwrap();
         state.expires_at
     }
 
-    /// Manually renew the subscription
+    /// Time left until the subscription expires; zero once it has
+    pub fn remaining(&self) -> Duration {
+        self.expires_at()
+            .saturating_duration_since(self.inner.clock.now())
+    }
+
+    /// Check if the expiry granted last has passed
+    ///
+    /// Unlike [`is_active()`](Self::is_active), this ignores whether the
+    /// subscription was unsubscribed.
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// Manually renew the subscription, re-subscribing if the device forgot it
+    ///
+    /// Like [`renew_in_place()`](Self::renew_in_place), except that when the
+    /// device refuses the renewal with HTTP 412 Precondition Failed, a fresh
+    /// subscription to the same service and callback URL is created and
+    /// returned in [`Renewal::Resubscribed`]. This handle, and every clone of
+    /// it, is then unsubscribed without sending an UNSUBSCRIBE, since the
+    /// device has already dropped the SID. Background renewal started with
+    /// [`spawn_auto_renew()`](Self::spawn_auto_renew) isn't carried over.
+    ///
+    /// # Errors
+    /// - `ApiError::AlreadyUnsubscribed` if any clone has unsubscribed,
+    ///   including while the renewal was in flight
+    /// - `ApiError::ResubscribeFailed` if the device refused the renewal and
+    ///   the fresh SUBSCRIBE failed too; this handle is unsubscribed anyway
+    /// - Network or device errors from the renewal request
+    pub fn renew(&self) -> Result<Renewal> {
+        match self.renew_in_place() {
+            Ok(()) => Ok(Renewal::Renewed),
+            Err(ApiError::SubscriptionUnknown(_)) => self.resubscribe().map(Renewal::Resubscribed),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Replace a subscription the device no longer knows
+    fn resubscribe(&self) -> Result<ManagedSubscription> {
+        let inner = &self.inner;
+        let timeout_seconds = {
+            let mut state = inner.state.lock().unwrap();
+            if !state.active {
+                return Err(ApiError::AlreadyUnsubscribed(inner.sid.clone()));
+            }
+            state.active = false;
+            if let Some(renewer) = state.renewer.take() {
+                renewer.stop();
+            }
+            state.timeout_seconds
+        };
+
+        Self::create(
+            inner.device_ip.clone(),
+            inner.service,
+            inner.callback_url.clone(),
+            timeout_seconds,
+            inner.soap_client.clone(),
+            Arc::clone(&inner.clock),
+            inner.owner.clone(),
+            inner.directory.clone(),
+        )
+        .map_err(|source| ApiError::ResubscribeFailed {
+            sid: inner.sid.clone(),
+            source: Box::new(source),
+        })
+    }
+
+    /// Renew the subscription under its current SID
     ///
     /// This sends a renewal request to the device and updates the internal
-    /// expiration time based on the response.
+    /// expiration time based on the response. The new expiry is visible
+    /// from every clone.
     ///
     /// # Returns
     /// `Ok(())` if renewal succeeded, `Err(ApiError)` if it failed.
     ///
     /// # Errors
-    /// - `ApiError::SubscriptionExpired` if the subscription has already expired
+    /// - `ApiError::AlreadyUnsubscribed` if any clone has unsubscribed,
+    ///   including while the renewal was in flight
+    /// - `ApiError::SubscriptionUnknown` if the device no longer knows the SID
     /// - Network or device errors from the renewal request
-    pub fn renew(&self) -> Result<()> {
+    pub fn renew_in_place(&self) -> Result<()> {
+        let inner = &self.inner;
         let current_timeout = {
-            let state = self.state.lock().unwrap();
+            let state = inner.state.lock().unwrap();
             if !state.active {
-                return Err(ApiError::subscription_expired());
+                return Err(ApiError::AlreadyUnsubscribed(inner.sid.clone()));
             }
             state.timeout_seconds
         };
 
         let request = RenewRequest {
-            sid: self.sid.clone(),
+            sid: inner.sid.clone(),
             timeout_seconds: current_timeout,
         };
 
-        let response =
-            Self::renew_internal(&self.soap_client, &self.device_ip, self.service, &request)?;
+        let response = Self::renew_internal(
+            &inner.soap_client,
+            &inner.device_ip,
+            inner.service,
+            &request,
+        )?;
 
-        // Update state with new expiration time
+        // Update state with new expiration time, unless a clone unsubscribed meanwhile
         {
-            let mut state = self.state.lock().unwrap();
+            let mut state = inner.state.lock().unwrap();
+            if !state.active {
+                return Err(ApiError::AlreadyUnsubscribed(inner.sid.clone()));
+            }
             state.expires_at =
-                SystemTime::now() + Duration::from_secs(response.timeout_seconds as u64);
+                inner.clock.now() + Duration::from_secs(response.timeout_seconds as u64);
             state.timeout_seconds = response.timeout_seconds;
         }
 
         Ok(())
     }
 
+    /// Renew the subscription from a background thread until it is
+    /// unsubscribed or its last clone is dropped
+    ///
+    /// The thread sleeps on the subscription's clock until 80% of the
+    /// timeout granted last has passed, then renews. A failed renewal is
+    /// retried as `policy` says; once every attempt has failed, the error of
+    /// the last one is sent on the returned channel and the thread stops.
+    /// A renewal the device refuses with HTTP 412 isn't retried:
+    /// `ApiError::SubscriptionUnknown` is sent straight away, and
+    /// [`renew()`](Self::renew) replaces the subscription.
+    /// The channel disconnects without a message when the thread stops for
+    /// any other reason. The thread doesn't keep the subscription alive, so
+    /// dropping the last clone still sends the UNSUBSCRIBE.
+    ///
+    /// Replaces the thread of an earlier call. On a subscription that was
+    /// already unsubscribed, nothing is started.
+    ///
+    /// ```rust,no_run
+    /// use sonos_api::{RetryPolicy, Service, SonosClient};
+    ///
+    /// # fn main() -> sonos_api::Result<()> {
+    /// let client = SonosClient::new();
+    /// let subscription =
+    ///     client.subscribe("192.168.1.100", Service::AVTransport, "http://192.168.1.50:8080/callback")?;
+    /// let expired = subscription.spawn_auto_renew(RetryPolicy::default());
+    ///
+    /// // ... later, or on another thread
+    /// if let Ok(error) = expired.try_recv() {
+    ///     eprintln!("subscription lapsed: {error}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn_auto_renew(&self, policy: RetryPolicy) -> mpsc::Receiver<ApiError> {
+        let (expired_tx, expired) = mpsc::channel();
+        let mut state = self.inner.state.lock().unwrap();
+        if !state.active {
+            return expired;
+        }
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let subscription = Arc::downgrade(&self.inner);
+        let clock = Arc::clone(&self.inner.clock);
+        let flag = Arc::clone(&stopped);
+        let handle = thread::Builder::new()
+            .name(format!("renew-{}", self.inner.sid))
+            .spawn(move || auto_renew(subscription, clock, policy, flag, expired_tx))
+            .expect("failed to spawn subscription renewal thread");
+        let renewer = Renewer {
+            stopped,
+            thread: handle.thread().clone(),
+        };
+        if let Some(previous) = state.renewer.replace(renewer) {
+            previous.stop();
+        }
+        expired
+    }
+
     /// Unsubscribe and clean up the subscription
     ///
     /// This sends an unsubscribe request to the device and marks the
-    /// subscription as inactive. After calling this method, the subscription
-    /// should not be used for any further operations.
+    /// subscription as inactive for every clone. Only the first call, from
+    /// any clone, sends the request.
     ///
     /// # Returns
     /// `Ok(())` if unsubscribe succeeded, `Err(ApiError)` if it failed.
     /// Note that the subscription is marked inactive regardless of the result.
+    ///
+    /// # Errors
+    /// - `ApiError::AlreadyUnsubscribed` if any clone already unsubscribed
+    /// - Network or device errors from the unsubscribe request
     pub fn unsubscribe(&self) -> Result<()> {
-        // Mark as inactive first
+        let inner = &self.inner;
+        // Mark as inactive first; the flag flip decides which caller wins
         {
-            let mut state = self.state.lock().unwrap();
+            let mut state = inner.state.lock().unwrap();
+            if !state.active {
+                return Err(ApiError::AlreadyUnsubscribed(inner.sid.clone()));
+            }
             state.active = false;
+            if let Some(renewer) = state.renewer.take() {
+                renewer.stop();
+            }
         }
 
         // Send unsubscribe request
         let request = UnsubscribeRequest {
-            sid: self.sid.clone(),
+            sid: inner.sid.clone(),
         };
 
-        Self::unsubscribe_internal(&self.soap_client, &self.device_ip, self.service, &request)
-            .map(|_| ())
+        Self::unsubscribe_internal(
+            &inner.soap_client,
+            &inner.device_ip,
+            inner.service,
+            &request,
+        )
+        .map(|_| ())
+    }
+
+    /// Keep the subscription alive on the device after the last clone is dropped
+    ///
+    /// Affects every clone. The device lets the subscription lapse at its
+    /// expiry; an explicit [`unsubscribe()`](Self::unsubscribe) still works.
+    pub fn detach(&self) {
+        self.inner.state.lock().unwrap().detached = true;
     }
 }
 
-impl Drop for ManagedSubscription {
+impl Drop for SharedSubscription {
     fn drop(&mut self) {
-        // Mark as inactive
-        if let Ok(mut state) = self.state.lock() {
-            if state.active {
+        // Runs once, when the last ManagedSubscription clone is dropped
+        if let Ok(state) = self.state.get_mut() {
+            if let Some(renewer) = state.renewer.take() {
+                renewer.stop();
+            }
+            if state.active && !state.detached {
                 state.active = false;
 
                 // Attempt to unsubscribe, but don't panic if it fails
@@ -246,7 +511,7 @@ impl Drop for ManagedSubscription {
                     sid: self.sid.clone(),
                 };
 
-                if let Err(e) = Self::unsubscribe_internal(
+                if let Err(e) = ManagedSubscription::unsubscribe_internal(
                     &self.soap_client,
                     &self.device_ip,
                     self.service,
@@ -258,3 +523,508 @@ impl Drop for ManagedSubscription {
         }
     }
 }
+
+/// Outcome of a successful [`ManagedSubscription::renew()`]
+#[derive(Debug)]
+pub enum Renewal {
+    /// The device extended the subscription under its SID
+    Renewed,
+    /// The device had forgotten the SID, so this fresh subscription replaces it
+    Resubscribed(ManagedSubscription),
+}
+
+impl Renewal {
+    /// SID of the replacing subscription, if there is one
+    pub fn new_sid(&self) -> Option<&str> {
+        match self {
+            Self::Renewed => None,
+            Self::Resubscribed(subscription) => Some(subscription.subscription_id()),
+        }
+    }
+}
+
+/// Body of the thread started by [`ManagedSubscription::spawn_auto_renew()`]
+fn auto_renew(
+    subscription: Weak<SharedSubscription>,
+    clock: SharedClock,
+    policy: RetryPolicy,
+    stopped: Arc<AtomicBool>,
+    expired: mpsc::Sender<ApiError>,
+) {
+    loop {
+        let renew_at = {
+            let Some(inner) = subscription.upgrade() else {
+                return;
+            };
+            let state = inner.state.lock().unwrap();
+            let lead = Duration::from_secs(u64::from(state.timeout_seconds)) / 5;
+            state
+                .expires_at
+                .checked_sub(lead)
+                .unwrap_or(state.expires_at)
+        };
+        if !wait_until(&clock, renew_at, &stopped) {
+            return;
+        }
+
+        let mut attempt = 1;
+        loop {
+            // Held only while renewing, so a drop meanwhile unsubscribes from here
+            let Some(inner) = subscription.upgrade() else {
+                return;
+            };
+            match (ManagedSubscription { inner }).renew_in_place() {
+                Ok(()) => break,
+                Err(ApiError::AlreadyUnsubscribed(_)) => return,
+                // The device forgot the SID; retrying under it can't succeed
+                Err(error @ ApiError::SubscriptionUnknown(_)) => {
+                    let _ = expired.send(error);
+                    return;
+                }
+                Err(error) if attempt >= policy.max_attempts => {
+                    let _ = expired.send(error);
+                    return;
+                }
+                Err(_) => {
+                    if !wait_until(&clock, clock.now() + policy.backoff(attempt), &stopped) {
+                        return;
+                    }
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Block until `clock` reaches `at`; false if `stopped` is set first
+fn wait_until(clock: &SharedClock, at: Instant, stopped: &AtomicBool) -> bool {
+    struct Unpark(thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut timer = clock.timer(at);
+    loop {
+        if stopped.load(Ordering::SeqCst) {
+            return false;
+        }
+        if Pin::new(&mut timer).poll(&mut cx).is_ready() {
+            return true;
+        }
+        thread::park();
+    }
+}
+
+/// Process-local index of live subscriptions, by device and service
+///
+/// Opt-in: clients given a directory with
+/// [`SonosClient::with_subscription_directory()`](crate::SonosClient::with_subscription_directory)
+/// register every subscription they create, so another component in the
+/// process can find one before subscribing to the same service again.
+/// Clients without a directory register nothing. The directory doesn't keep
+/// subscriptions alive; one drops out once its last clone is dropped or it
+/// is unsubscribed.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionDirectory {
+    entries: Arc<Mutex<Vec<Weak<SharedSubscription>>>>,
+}
+
+impl SubscriptionDirectory {
+    /// Create an empty directory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The directory shared by everything in the process that asks for it
+    pub fn global() -> &'static SubscriptionDirectory {
+        static GLOBAL: OnceLock<SubscriptionDirectory> = OnceLock::new();
+        GLOBAL.get_or_init(SubscriptionDirectory::new)
+    }
+
+    pub(crate) fn register(&self, subscription: &ManagedSubscription) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.strong_count() > 0);
+        entries.push(Arc::downgrade(&subscription.inner));
+    }
+
+    /// The live subscription to `service` on the device at `ip`
+    ///
+    /// `ip` may carry a port, as for [`SonosClient`](crate::SonosClient)
+    /// methods; the standard port 1400 matches an address without one.
+    /// Subscriptions that were unsubscribed or have expired are skipped; the
+    /// most recent wins if there are several.
+    pub fn find(&self, ip: &str, service: Service) -> Option<ManagedSubscription> {
+        let device = split_host_port(ip);
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .rev()
+            .filter_map(Weak::upgrade)
+            .map(|inner| ManagedSubscription { inner })
+            .find(|subscription| {
+                subscription.inner.service == service
+                    && split_host_port(&subscription.inner.device_ip) == device
+                    && subscription.is_active()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock, SystemClock};
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    // ManagedSubscription is shared across threads by renewal and registry code
+    const _: fn() = || {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ManagedSubscription>();
+    };
+
+    /// UNSUBSCRIBE requests received by the mock device, keyed by SID
+    fn unsubscribes() -> &'static Mutex<HashMap<String, usize>> {
+        static COUNTS: OnceLock<Mutex<HashMap<String, usize>>> = OnceLock::new();
+        COUNTS.get_or_init(Default::default)
+    }
+
+    fn unsubscribe_count(sid: &str) -> usize {
+        unsubscribes()
+            .lock()
+            .unwrap()
+            .get(sid)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn subscribe_to_mock() -> ManagedSubscription {
+        subscribe_to_mock_with_clock(Arc::new(SystemClock))
+    }
+
+    fn subscribe_to_mock_with_clock(clock: SharedClock) -> ManagedSubscription {
+        ManagedSubscription::create(
+            mock_addr().to_string(),
+            Service::AVTransport,
+            "http://127.0.0.1:3400/callback".to_string(),
+            1800,
+            SoapClient::get().clone(),
+            clock,
+            None,
+            None,
+        )
+        .expect("mock SUBSCRIBE should succeed")
+    }
+
+    /// Start (once) a mock device that grants SUBSCRIBE/renewal with a
+    /// fresh SID and counts UNSUBSCRIBE requests; returns its address
+    fn mock_addr() -> &'static str {
+        static ADDR: OnceLock<String> = OnceLock::new();
+        ADDR.get_or_init(|| {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock device");
+            let addr = listener.local_addr().unwrap().to_string();
+            let next_sid = Arc::new(AtomicUsize::new(0));
+            thread::spawn(move || {
+                for mut stream in listener.incoming().flatten() {
+                    let next_sid = Arc::clone(&next_sid);
+                    thread::spawn(move || {
+                        let mut reader = BufReader::new(stream.try_clone().unwrap());
+                        let mut method = String::new();
+                        let mut sid = None;
+                        let mut line = String::new();
+                        while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                            if method.is_empty() {
+                                method = line.split(' ').next().unwrap_or_default().to_string();
+                            } else if let Some(value) = line.strip_prefix("sid:").or(line.strip_prefix("SID:")) {
+                                sid = Some(value.trim().to_string());
+                            }
+                            line.clear();
+                        }
+                        if method == "UNSUBSCRIBE" {
+                            *unsubscribes().lock().unwrap().entry(sid.clone().unwrap_or_default()).or_default() += 1;
+                        }
+                        let sid = sid.unwrap_or_else(|| {
+                            format!("uuid:mock-{}", next_sid.fetch_add(1, Ordering::SeqCst))
+                        });
+                        let _ = write!(
+                            stream,
+                            "HTTP/1.1 200 OK\r\nSID: {sid}\r\nTIMEOUT: Second-1800\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        );
+                    });
+                }
+            });
+            addr
+        })
+    }
+
+    #[test]
+    fn test_clones_share_renewal_and_unsubscribe_state() {
+        let subscription = subscribe_to_mock();
+        let observer = subscription.clone();
+        assert_eq!(observer.subscription_id(), subscription.subscription_id());
+        assert_eq!(subscription.handle_count(), 2);
+
+        // Force an early expiry, then renew from one clone
+        subscription.inner.state.lock().unwrap().expires_at = Instant::now();
+        subscription.renew().unwrap();
+        assert!(observer.is_active());
+        assert_eq!(observer.expires_at(), subscription.expires_at());
+
+        observer.unsubscribe().unwrap();
+        assert!(subscription.is_unsubscribed());
+        assert!(matches!(
+            subscription.unsubscribe(),
+            Err(ApiError::AlreadyUnsubscribed(_))
+        ));
+        assert!(matches!(
+            subscription.renew(),
+            Err(ApiError::AlreadyUnsubscribed(_))
+        ));
+
+        let sid = subscription.subscription_id().to_string();
+        drop(subscription);
+        drop(observer);
+        assert_eq!(unsubscribe_count(&sid), 1);
+    }
+
+    #[test]
+    fn test_concurrent_renew_and_unsubscribe_send_one_unsubscribe() {
+        let subscription = subscribe_to_mock();
+        let sid = subscription.subscription_id().to_string();
+
+        let workers: Vec<_> = (0..8)
+            .map(|i| {
+                let clone = subscription.clone();
+                thread::spawn(move || {
+                    if i % 2 == 0 {
+                        let _ = clone.renew();
+                    }
+                    clone.unsubscribe().is_ok()
+                })
+            })
+            .collect();
+        drop(subscription);
+
+        let winners = workers
+            .into_iter()
+            .map(|w| w.join().unwrap())
+            .filter(|won| *won)
+            .count();
+        assert_eq!(winners, 1);
+        assert_eq!(unsubscribe_count(&sid), 1);
+    }
+
+    #[test]
+    fn test_last_drop_unsubscribes_unless_detached() {
+        let subscription = subscribe_to_mock();
+        let sid = subscription.subscription_id().to_string();
+        let clone = subscription.clone();
+        drop(subscription);
+        assert_eq!(unsubscribe_count(&sid), 0);
+        drop(clone);
+        assert_eq!(unsubscribe_count(&sid), 1);
+
+        let detached = subscribe_to_mock();
+        let sid = detached.subscription_id().to_string();
+        detached.clone().detach();
+        drop(detached);
+        assert_eq!(unsubscribe_count(&sid), 0);
+    }
+
+    #[test]
+    fn test_clock_jump_leaves_subscription_renewable() {
+        let clock = ManualClock::new();
+        let subscription = subscribe_to_mock_with_clock(Arc::new(clock.clone()));
+        assert!(!subscription.needs_renewal());
+
+        // Two hours asleep: past expiry, but renewal is still attempted
+        clock.advance(Duration::from_secs(2 * 3600));
+        assert!(!subscription.is_active());
+        assert_eq!(subscription.time_until_renewal(), Some(Duration::ZERO));
+
+        subscription.renew().unwrap();
+        assert!(subscription.is_active());
+        assert_eq!(
+            subscription.expires_at() - clock.now(),
+            Duration::from_secs(1800)
+        );
+    }
+
+    #[test]
+    fn test_directory_finds_live_subscriptions() {
+        let directory = SubscriptionDirectory::new();
+        let subscription = subscribe_to_mock();
+        directory.register(&subscription);
+        assert_eq!(subscription.owner(), None);
+        assert_eq!(
+            subscription.callback_url(),
+            "http://127.0.0.1:3400/callback"
+        );
+
+        let found = directory.find(mock_addr(), Service::AVTransport).unwrap();
+        assert_eq!(found.subscription_id(), subscription.subscription_id());
+        assert!(directory
+            .find(mock_addr(), Service::RenderingControl)
+            .is_none());
+        let other_port = format!(
+            "127.0.0.1:{}",
+            split_host_port(mock_addr()).1.wrapping_add(1)
+        );
+        assert!(directory.find(&other_port, Service::AVTransport).is_none());
+
+        subscription.unsubscribe().unwrap();
+        assert!(directory.find(mock_addr(), Service::AVTransport).is_none());
+
+        // The directory doesn't hold dropped subscriptions alive
+        let dropped = subscribe_to_mock();
+        directory.register(&dropped);
+        let sid = dropped.subscription_id().to_string();
+        drop(dropped);
+        assert_eq!(unsubscribe_count(&sid), 1);
+        assert!(directory.find(mock_addr(), Service::AVTransport).is_none());
+    }
+
+    #[cfg(feature = "test-support")]
+    mod mock_device {
+        use super::*;
+        use crate::mock::{Action, Behavior, MockDevice, Scenario};
+        use crate::SonosClient;
+
+        fn subscribe(device: &MockDevice) -> ManagedSubscription {
+            SonosClient::new()
+                .with_clock(Arc::new(device.clock()))
+                .create_managed_subscription(
+                    &device.addr().to_string(),
+                    Service::AVTransport,
+                    "http://127.0.0.1:9/callback",
+                    1800,
+                )
+                .unwrap()
+        }
+
+        fn wait_for(condition: impl Fn() -> bool) {
+            let started = Instant::now();
+            while !condition() {
+                assert!(started.elapsed() < Duration::from_secs(5));
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+
+        #[test]
+        fn test_auto_renew_at_80_percent_and_stop_on_drop() {
+            let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+            let subscription = subscribe(&device);
+            let expired = subscription.spawn_auto_renew(RetryPolicy::default());
+            wait_for(|| device.clock().pending() == 1);
+
+            device.advance(Duration::from_secs(1439));
+            thread::sleep(Duration::from_millis(50));
+            assert_eq!(device.renewals(), 0);
+            device.advance(Duration::from_secs(1));
+            wait_for(|| device.renewals() == 1);
+            device.advance(Duration::from_secs(1440));
+            wait_for(|| device.renewals() == 2);
+
+            drop(subscription);
+            assert_eq!(device.unsubscriptions(), 1);
+            assert!(matches!(
+                expired.recv_timeout(Duration::from_secs(5)),
+                Err(mpsc::RecvTimeoutError::Disconnected)
+            ));
+        }
+
+        #[test]
+        fn test_auto_renew_reports_expiry_after_retries() {
+            // Every renewal fails with a server error
+            let scenario = Scenario::new().respond(1).then(Behavior::Status(500));
+            let device = MockDevice::start("127.0.0.1:0", scenario);
+            let subscription = subscribe(&device);
+            let policy = RetryPolicy::default()
+                .with_max_attempts(3)
+                .with_initial_backoff(Duration::from_secs(10));
+            let expired = subscription.spawn_auto_renew(policy);
+            wait_for(|| device.clock().pending() == 1);
+
+            device.advance(Duration::from_secs(1440));
+            for requests in 2..4 {
+                wait_for(|| device.requests() == requests && device.clock().pending() == 1);
+                device.advance(Duration::from_secs(20));
+            }
+            expired.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(device.requests(), 4);
+            assert_eq!(device.renewals(), 0);
+        }
+
+        #[test]
+        fn test_auto_renew_stops_at_forgotten_sid() {
+            let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+            let subscription = subscribe(&device);
+            let expired = subscription.spawn_auto_renew(RetryPolicy::default());
+            wait_for(|| device.clock().pending() == 1);
+
+            device.perform(Action::Expire(Service::AVTransport));
+            device.advance(Duration::from_secs(1440));
+            assert!(matches!(
+                expired.recv_timeout(Duration::from_secs(5)),
+                Ok(ApiError::SubscriptionUnknown(_))
+            ));
+            assert_eq!(device.requests(), 2);
+
+            // The handle is still live, so renew() can replace it
+            let renewal = subscription.renew().unwrap();
+            let new_sid = renewal.new_sid().unwrap().to_string();
+            assert_eq!(device.sids(Service::AVTransport), [new_sid]);
+        }
+
+        #[test]
+        fn test_renew_resubscribes_after_412() {
+            let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+            let subscription = subscribe(&device);
+            let old_sid = subscription.subscription_id().to_string();
+            device.advance(Duration::from_secs(600));
+            assert_eq!(subscription.remaining(), Duration::from_secs(1200));
+            assert!(matches!(subscription.renew(), Ok(Renewal::Renewed)));
+
+            device.perform(Action::Expire(Service::AVTransport));
+            device.advance(Duration::from_secs(1800));
+            assert!(subscription.is_expired());
+            let renewal = subscription.renew().unwrap();
+            let new_sid = renewal.new_sid().unwrap().to_string();
+            assert_ne!(new_sid, old_sid);
+            assert!(subscription.is_unsubscribed());
+            let Renewal::Resubscribed(fresh) = renewal else {
+                unreachable!()
+            };
+            assert_eq!(fresh.remaining(), Duration::from_secs(1800));
+            assert_eq!(device.sids(Service::AVTransport), [new_sid]);
+
+            // Nothing is sent for the forgotten SID
+            drop(subscription);
+            drop(fresh);
+            assert_eq!(device.unsubscriptions(), 1);
+        }
+
+        #[test]
+        fn test_failed_resubscribe_is_distinguishable() {
+            let device =
+                MockDevice::start("127.0.0.1:0", Scenario::new().respond(2).status(503, 1));
+            let subscription = subscribe(&device);
+            device.perform(Action::Expire(Service::AVTransport));
+
+            let error = subscription.renew().unwrap_err();
+            assert!(
+                matches!(&error, ApiError::ResubscribeFailed { sid, .. } if sid == subscription.subscription_id()),
+                "{error:?}"
+            );
+            assert!(subscription.is_unsubscribed());
+            assert_eq!(device.requests(), 3);
+        }
+    }
+}
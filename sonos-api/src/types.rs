@@ -28,17 +28,24 @@ macro_rules! impl_id_type {
 
 /// Unique identifier for a Sonos speaker
 ///
-/// Typically the UUID from the UPnP device description,
-/// normalized to strip the "uuid:" prefix if present.
+/// Always holds the bare `RINCON_…` form that ZoneGroupState uses, so it is
+/// the join key between discovery, topology and events. Every constructor
+/// (including deserialization) canonicalizes: discovery's UDN
+/// (`uuid:RINCON_…`) and a full SSDP USN
+/// (`uuid:RINCON_…::urn:schemas-upnp-org:device:ZonePlayer:1`) both become
+/// `RINCON_…`.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
 pub struct SpeakerId(String);
 
 impl SpeakerId {
-    /// Creates a new SpeakerId, normalizing the format
+    /// Creates a new SpeakerId in canonical form
     pub fn new(id: impl Into<String>) -> Self {
         let id = id.into();
-        let normalized = id.strip_prefix("uuid:").unwrap_or(&id);
-        Self(normalized.to_string())
+        let id = id.trim();
+        let id = id.strip_prefix("uuid:").unwrap_or(id);
+        let id = id.split_once("::").map_or(id, |(udn, _)| udn);
+        Self(id.to_string())
     }
 
     pub fn as_str(&self) -> &str {
@@ -82,6 +89,22 @@ mod tests {
         assert_eq!(id.as_str(), "RINCON_123456789");
     }
 
+    #[test]
+    fn test_speaker_id_canonicalizes_every_source() {
+        let canonical = SpeakerId::new("RINCON_000E58A0123456");
+        for raw in [
+            "uuid:RINCON_000E58A0123456",
+            "uuid:RINCON_000E58A0123456::urn:schemas-upnp-org:device:ZonePlayer:1",
+            " RINCON_000E58A0123456\n",
+        ] {
+            assert_eq!(SpeakerId::new(raw), canonical, "{raw:?}");
+        }
+        let udn = serde::de::value::StrDeserializer::<serde::de::value::Error>::new(
+            "uuid:RINCON_000E58A0123456",
+        );
+        assert_eq!(SpeakerId::deserialize(udn).unwrap(), canonical);
+    }
+
     #[test]
     fn test_speaker_id_equality() {
         let id1 = SpeakerId::new("uuid:RINCON_123");
@@ -0,0 +1,212 @@
+//! Sonos broadcast discovery on UDP port 6969
+//!
+//! Some meshed and guest networks drop SSDP multicast but still pass the
+//! subnet broadcast the Sonos app uses. [`search()`] sends that probe and
+//! collects the players that answer; the iterator then fetches their device
+//! descriptions exactly as it does for SSDP results.
+//!
+//! Both datagrams start with the 4-byte magic `SONS` and a type byte. All
+//! integers are big-endian:
+//!
+//! | Offset | Probe (`0x01`) | Reply (`0x02`)                       |
+//! |--------|----------------|--------------------------------------|
+//! | 0      | `SONS`         | `SONS`                               |
+//! | 4      | `0x01`         | `0x02`                               |
+//! | 5      |                | household ID length `n` (1 byte)     |
+//! | 6      |                | household ID, `n` bytes of UTF-8     |
+//! | 6 + n  |                | IPv4 address (4 bytes)               |
+//! | 10 + n |                | HTTP port (2 bytes)                  |
+//!
+//! Bytes after the port are ignored, so newer firmware can extend the reply.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
+
+use crate::error::{DiscoveryError, Result};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Port players listen on for the discovery broadcast
+pub(crate) const BROADCAST_PORT: u16 = 6969;
+
+/// Where [`search()`] sends the probe unless told otherwise
+pub(crate) const BROADCAST_ADDR: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::BROADCAST, BROADCAST_PORT));
+
+const MAGIC: &[u8; 4] = b"SONS";
+const PROBE: u8 = 0x01;
+const REPLY: u8 = 0x02;
+
+/// A player's answer to the discovery broadcast
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BroadcastReply {
+    /// Household the player belongs to, e.g. `Sonos_a1b2c3`
+    pub household_id: String,
+    pub ip: Ipv4Addr,
+    pub port: u16,
+}
+
+impl BroadcastReply {
+    /// URL of the player's device description, the same document SSDP's
+    /// `LOCATION` header points at
+    pub fn location(&self) -> String {
+        format!(
+            "http://{}:{}/xml/device_description.xml",
+            self.ip, self.port
+        )
+    }
+}
+
+/// The probe datagram
+pub(crate) fn probe() -> Vec<u8> {
+    let mut probe = MAGIC.to_vec();
+    probe.push(PROBE);
+    probe
+}
+
+/// Parse a reply datagram, or `None` if it's truncated, malformed or not a
+/// reply at all
+pub(crate) fn parse_reply(datagram: &[u8]) -> Option<BroadcastReply> {
+    let rest = datagram.strip_prefix(MAGIC)?;
+    let (&kind, rest) = rest.split_first()?;
+    if kind != REPLY {
+        return None;
+    }
+    let (&len, rest) = rest.split_first()?;
+    let (household_id, rest) = rest.split_at_checked(usize::from(len))?;
+    let household_id = std::str::from_utf8(household_id).ok()?.to_string();
+    let (ip, rest) = rest.split_first_chunk::<4>()?;
+    let (port, _) = rest.split_first_chunk::<2>()?;
+    let port = u16::from_be_bytes(*port);
+    if port == 0 {
+        return None;
+    }
+    Some(BroadcastReply {
+        household_id,
+        ip: Ipv4Addr::from(*ip),
+        port,
+    })
+}
+
+/// Broadcast the probe to `target` and collect replies until `timeout`
+///
+/// A reply carrying `0.0.0.0` is taken to mean the sender's own address.
+/// Datagrams that don't parse are skipped.
+pub(crate) fn search(target: SocketAddr, timeout: Duration) -> Result<Vec<BroadcastReply>> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .map_err(|e| DiscoveryError::NetworkError(format!("Failed to bind UDP socket: {e}")))?;
+    socket
+        .set_broadcast(true)
+        .map_err(|e| DiscoveryError::NetworkError(format!("Failed to enable broadcast: {e}")))?;
+    socket
+        .send_to(&probe(), target)
+        .map_err(|e| DiscoveryError::NetworkError(format!("Failed to send probe: {e}")))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buffer = [0; 512];
+    let mut replies = Vec::new();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(replies);
+        }
+        socket.set_read_timeout(Some(remaining)).map_err(|e| {
+            DiscoveryError::NetworkError(format!("Failed to set read timeout: {e}"))
+        })?;
+        match socket.recv_from(&mut buffer) {
+            Ok((size, from)) => {
+                let Some(mut reply) = buffer.get(..size).and_then(parse_reply) else {
+                    continue;
+                };
+                if reply.ip.is_unspecified() {
+                    if let SocketAddr::V4(from) = from {
+                        reply.ip = *from.ip();
+                    }
+                }
+                replies.push(reply);
+            }
+            Err(e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                return Ok(replies);
+            }
+            Err(e) => return Err(DiscoveryError::NetworkError(format!("Socket error: {e}"))),
+        }
+    }
+}
+
+/// A reply datagram for `household_id` at `ip:port`, as a player sends it
+#[cfg(test)]
+pub(crate) fn reply(household_id: &str, ip: Ipv4Addr, port: u16) -> Vec<u8> {
+    let mut reply = MAGIC.to_vec();
+    reply.push(REPLY);
+    reply.push(household_id.len() as u8);
+    reply.extend(household_id.as_bytes());
+    reply.extend(ip.octets());
+    reply.extend(port.to_be_bytes());
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        let datagram = reply("Sonos_a1b2c3", Ipv4Addr::new(192, 168, 1, 20), 1400);
+        let parsed = parse_reply(&datagram).unwrap();
+
+        assert_eq!(parsed.household_id, "Sonos_a1b2c3");
+        assert_eq!(
+            parsed.location(),
+            "http://192.168.1.20:1400/xml/device_description.xml"
+        );
+
+        // Trailing bytes are room for extensions
+        let mut extended = datagram.clone();
+        extended.extend(b"future");
+        assert_eq!(parse_reply(&extended), Some(parsed));
+
+        // Every truncation is rejected, not read past
+        for len in 0..datagram.len() {
+            assert_eq!(parse_reply(&datagram[..len]), None, "length {len}");
+        }
+    }
+
+    #[test]
+    fn test_parse_reply_rejects_malformed() {
+        let ip = Ipv4Addr::new(192, 168, 1, 20);
+        assert_eq!(parse_reply(&probe()), None);
+        assert_eq!(parse_reply(&reply("Sonos_a1b2c3", ip, 0)), None);
+
+        let mut bad_magic = reply("Sonos_a1b2c3", ip, 1400);
+        bad_magic[0] = b'X';
+        assert_eq!(parse_reply(&bad_magic), None);
+
+        // Household length pointing past the end of the datagram
+        let mut overlong = reply("", ip, 1400);
+        overlong[5] = 0xff;
+        assert_eq!(parse_reply(&overlong), None);
+
+        let mut not_utf8 = reply("ab", ip, 1400);
+        not_utf8[6] = 0xff;
+        assert_eq!(parse_reply(&not_utf8), None);
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn fuzz_parse_reply(datagram in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..64)) {
+            let mut framed = MAGIC.to_vec();
+            framed.push(REPLY);
+            framed.extend(&datagram);
+            parse_reply(&datagram);
+            parse_reply(&framed);
+        }
+    }
+}
@@ -2,6 +2,12 @@
 //!
 //! This module handles parsing UPnP device description XML and validating
 //! that devices are Sonos speakers.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
 
 use crate::error::{DiscoveryError, Result};
 use crate::Device;
@@ -43,8 +49,11 @@ impl DeviceDescription {
     ///
     /// # Errors
     ///
-    /// Returns `DiscoveryError::ParseError` if the XML is malformed or missing required fields.
+    /// Returns `DiscoveryError::ParseError` if the XML is malformed or missing required fields,
+    /// and `DiscoveryError::XmlRejected` if it declares a DTD or breaks the
+    /// [`XmlLimits`](soap_client::XmlLimits).
     pub fn from_xml(xml: &str) -> Result<Self> {
+        soap_client::xml::check(xml).map_err(DiscoveryError::XmlRejected)?;
         let root: Root = quick_xml::de::from_str(xml)
             .map_err(|e| DiscoveryError::ParseError(format!("Failed to parse device XML: {e}")))?;
 
@@ -67,6 +76,8 @@ impl DeviceDescription {
             ip_address,
             port: 1400,
             model_name: self.model_name.clone(),
+            household_id: None,
+            secure: false,
         }
     }
 
@@ -97,6 +108,29 @@ pub fn extract_ip_from_url(url: &str) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+/// Extract the port from a URL.
+///
+/// # Arguments
+///
+/// * `url` - URL string (e.g., "https://192.168.1.100:1443/xml/device_description.xml")
+///
+/// # Returns
+///
+/// The port the URL names, else the scheme's (80 for `http`, 443 for
+/// `https`), or `None` if the URL is malformed.
+pub fn extract_port_from_url(url: &str) -> Option<u16> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split('/').next()?;
+    match authority.rsplit_once(':') {
+        Some((_, port)) => port.parse().ok(),
+        None => match scheme {
+            "http" => Some(80),
+            "https" => Some(443),
+            _ => None,
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,6 +148,21 @@ mod tests {
         assert_eq!(extract_ip_from_url("invalid-url"), None);
     }
 
+    #[test]
+    fn test_extract_port_from_url() {
+        assert_eq!(
+            extract_port_from_url("http://192.168.1.100:1400/xml/device_description.xml"),
+            Some(1400)
+        );
+        assert_eq!(
+            extract_port_from_url("https://192.168.1.100:1443/xml/device_description.xml"),
+            Some(1443)
+        );
+        assert_eq!(extract_port_from_url("https://10.0.0.5/path"), Some(443));
+        assert_eq!(extract_port_from_url("http://10.0.0.5:http/path"), None);
+        assert_eq!(extract_port_from_url("invalid-url"), None);
+    }
+
     #[test]
     fn test_device_from_xml() {
         // Test with a minimal valid Sonos device XML
@@ -139,6 +188,20 @@ mod tests {
         assert!(device.is_sonos_device());
     }
 
+    #[test]
+    fn test_device_from_xml_rejects_dtd() {
+        let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE root [<!ENTITY name SYSTEM "file:///etc/hostname">]>
+<root><device><friendlyName>&name;</friendlyName></device></root>"#;
+
+        assert!(matches!(
+            DeviceDescription::from_xml(xml),
+            Err(DiscoveryError::XmlRejected(
+                soap_client::XmlViolation::Doctype
+            ))
+        ));
+    }
+
     #[test]
     fn test_is_sonos_device_by_manufacturer() {
         let xml = r#"<?xml version="1.0"?>
@@ -233,4 +296,21 @@ mod tests {
 
         assert_eq!(device.room_name, "Unknown");
     }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn fuzz_device_description(xml in "(<root>|<device>|</device>|</root>|<UDN>|</UDN>|<roomName>|</roomName>|\\PC){0,16}") {
+            if let Ok(description) = DeviceDescription::from_xml(&xml) {
+                description.to_device(String::new());
+            }
+        }
+
+        #[test]
+        fn fuzz_extract_ip_from_url(url in "(http://|:|/|\\PC){0,12}") {
+            extract_ip_from_url(&url);
+            extract_port_from_url(&url);
+        }
+    }
 }
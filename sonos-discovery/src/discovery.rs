@@ -3,21 +3,123 @@
 //! This module implements the discovery algorithm that:
 //! 1. Sends SSDP M-SEARCH requests for Sonos ZonePlayer devices
 //! 2. Receives and filters SSDP responses
-//! 3. Fetches device descriptions via HTTP
-//! 4. Parses and validates device information
-//! 5. Yields discovered devices as events
+//! 3. Falls back to the Sonos broadcast on port 6969 if SSDP found nothing
+//! 4. Fetches device descriptions via HTTP
+//! 5. Parses and validates device information
+//! 6. Yields discovered devices as events
 
-use crate::device::{extract_ip_from_url, DeviceDescription};
+use crate::broadcast::{self, BROADCAST_ADDR};
+use crate::device::{extract_ip_from_url, extract_port_from_url, DeviceDescription};
 use crate::error::Result;
-use crate::ssdp::{SsdpClient, SsdpResponse};
-use crate::DeviceEvent;
-use std::collections::HashSet;
+use crate::ssdp::{SsdpClient, SsdpResponse, SSDP_MULTICAST, ZONE_PLAYER_URN};
+use crate::{Device, DeviceEvent};
+use soap_client::{HttpCache, HttpResource, Revalidation, SoapError, Validators};
+use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Settings for [`get_with_options()`](crate::get_with_options) and
+/// [`get_iter_with_options()`](crate::get_iter_with_options).
+///
+/// The defaults are what [`get()`](crate::get) uses: a 3-second SSDP search,
+/// followed by the broadcast fallback if it finds no players.
+///
+/// ```no_run
+/// use sonos_discovery::{get_with_options, DiscoveryOptions};
+/// use std::time::Duration;
+///
+/// // Guest network that drops multicast: skip straight to the broadcast
+/// let devices = get_with_options(
+///     DiscoveryOptions::new()
+///         .with_timeout(Duration::from_secs(2))
+///         .with_ssdp(false),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    timeout: Duration,
+    ssdp: bool,
+    fallback: bool,
+    always_broadcast: bool,
+    broadcast_addr: SocketAddr,
+    pub(crate) ssdp_addr: SocketAddr,
+    http_cache: Arc<HttpCache>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(3),
+            ssdp: true,
+            fallback: true,
+            always_broadcast: false,
+            broadcast_addr: BROADCAST_ADDR,
+            ssdp_addr: SSDP_MULTICAST,
+            http_cache: HttpCache::shared(),
+        }
+    }
+}
+
+impl DiscoveryOptions {
+    /// The defaults, same as [`Default::default()`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long each phase waits for answers, and the timeout of each
+    /// description fetch
+    ///
+    /// When the fallback runs, discovery takes up to twice this long.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether to run the SSDP search (default `true`)
+    pub fn with_ssdp(mut self, ssdp: bool) -> Self {
+        self.ssdp = ssdp;
+        self
+    }
+
+    /// Send the Sonos discovery broadcast when SSDP finds no players
+    /// (default `true`)
+    pub fn with_fallback(mut self, fallback: bool) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Send the broadcast even when SSDP found players (default `false`)
+    ///
+    /// Players that answer both are reported once.
+    pub fn with_broadcast(mut self, always: bool) -> Self {
+        self.always_broadcast = always;
+        self
+    }
+
+    /// Send the broadcast to `addr` instead of `255.255.255.255:6969`, e.g.
+    /// a directed broadcast to another subnet
+    pub fn with_broadcast_addr(mut self, addr: SocketAddr) -> Self {
+        self.broadcast_addr = addr;
+        self
+    }
+
+    /// Fetch device descriptions through `cache` instead of the
+    /// process-wide [`HttpCache::shared()`]
+    ///
+    /// A description fetched before is revalidated with its `ETag` /
+    /// `Last-Modified`, and stands in for a failed fetch while fresh enough.
+    pub fn with_http_cache(mut self, cache: Arc<HttpCache>) -> Self {
+        self.http_cache = cache;
+        self
+    }
+}
+
 /// Iterator that discovers Sonos devices on the local network.
 ///
-/// This iterator performs network discovery using SSDP and yields `DeviceEvent::Found`
-/// for each discovered Sonos device. The iterator automatically handles deduplication,
+/// This iterator performs network discovery using SSDP, and the Sonos
+/// broadcast if configured, and yields `DeviceEvent::Found` for each
+/// discovered Sonos device. The iterator automatically handles deduplication,
 /// filtering of non-Sonos devices, and resource cleanup.
 ///
 /// # Examples
@@ -26,28 +128,44 @@ use std::time::Duration;
 /// use sonos_discovery::{get_iter, DeviceEvent};
 ///
 /// for event in get_iter() {
-///     match event {
-///         DeviceEvent::Found(device) => {
-///             println!("Found: {}", device.name);
-///         }
+///     if let DeviceEvent::Found(device) = event {
+///         println!("Found: {}", device.name);
 ///     }
 /// }
 /// ```
 pub struct DiscoveryIterator {
     ssdp_client: Option<SsdpClient>,
-    ssdp_buffer: Vec<SsdpResponse>,
-    buffer_index: usize,
+    options: DiscoveryOptions,
+    broadcast_pending: bool,
+    /// Description URLs still to resolve, with the household the player
+    /// announced alongside
+    locations: VecDeque<(String, Option<String>)>,
     seen_locations: HashSet<String>,
+    seen_ids: HashSet<String>,
     http_client: reqwest::blocking::Client,
+    user_agent: String,
     finished: bool,
 }
 
 impl DiscoveryIterator {
-    /// Create a new discovery iterator with the specified timeout
+    /// Create a new discovery iterator with the specified timeout and
+    /// otherwise default [`DiscoveryOptions`]
     pub fn new(timeout: Duration) -> Result<Self> {
-        let ssdp_client = SsdpClient::new(timeout)?;
+        Self::with_options(DiscoveryOptions::new().with_timeout(timeout))
+    }
+
+    /// Create a new discovery iterator with the given options
+    pub fn with_options(options: DiscoveryOptions) -> Result<Self> {
+        let ssdp_client = if options.ssdp {
+            Some(SsdpClient::new(options.timeout)?)
+        } else {
+            None
+        };
+        // Players with the secure local API enforced serve their description
+        // over HTTPS with a certificate they signed themselves
         let http_client = reqwest::blocking::Client::builder()
-            .timeout(timeout)
+            .timeout(options.timeout)
+            .danger_accept_invalid_certs(true)
             .build()
             .map_err(|e| {
                 crate::error::DiscoveryError::NetworkError(format!(
@@ -56,25 +174,38 @@ impl DiscoveryIterator {
             })?;
 
         Ok(Self {
-            ssdp_client: Some(ssdp_client),
-            ssdp_buffer: Vec::new(),
-            buffer_index: 0,
+            ssdp_client,
+            broadcast_pending: options.fallback || options.always_broadcast,
+            options,
+            locations: VecDeque::new(),
             seen_locations: HashSet::new(),
+            seen_ids: HashSet::new(),
             http_client,
+            user_agent: crate::USER_AGENT.to_string(),
             finished: false,
         })
     }
 
+    /// Send `user_agent` instead of [`USER_AGENT`](crate::USER_AGENT) when
+    /// fetching device descriptions
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
     /// Create an empty iterator that yields no results
     /// Used as a fallback when initialization fails
     pub(crate) fn empty() -> Self {
         let http_client = reqwest::blocking::Client::new();
         Self {
             ssdp_client: None,
-            ssdp_buffer: Vec::new(),
-            buffer_index: 0,
+            options: DiscoveryOptions::default(),
+            broadcast_pending: false,
+            locations: VecDeque::new(),
             seen_locations: HashSet::new(),
+            seen_ids: HashSet::new(),
             http_client,
+            user_agent: crate::USER_AGENT.to_string(),
             finished: true,
         }
     }
@@ -101,37 +232,128 @@ impl DiscoveryIterator {
         false
     }
 
-    /// Fetch and parse device description from a location URL
+    /// Fetch (through the options' cache) and parse device description
+    /// from a location URL
     fn fetch_device_description(&self, location: &str) -> Result<DeviceDescription> {
-        let response = self.http_client.get(location).send().map_err(|e| {
-            crate::error::DiscoveryError::NetworkError(format!(
-                "Failed to fetch device description: {e}"
-            ))
-        })?;
+        let resource = self
+            .options
+            .http_cache
+            .fetch(location, |validators| {
+                self.get_description(location, validators)
+            })
+            .map_err(|e| crate::error::DiscoveryError::NetworkError(e.to_string()))?;
 
-        let xml = response.text().map_err(|e| {
-            crate::error::DiscoveryError::NetworkError(format!("Failed to read response body: {e}"))
-        })?;
+        DeviceDescription::from_xml(&String::from_utf8_lossy(&resource.body))
+    }
+
+    /// One (conditional) GET of a device description
+    fn get_description(
+        &self,
+        location: &str,
+        validators: &Validators,
+    ) -> std::result::Result<Revalidation, SoapError> {
+        use reqwest::header;
+
+        let mut request = self
+            .http_client
+            .get(location)
+            .header(header::USER_AGENT, &self.user_agent);
+        if let Some(etag) = &validators.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = request
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SoapError::Network(format!("Failed to fetch device description: {e}")))?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Revalidation::NotModified);
+        }
 
-        DeviceDescription::from_xml(&xml)
+        let header = |name: header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validators = Validators {
+            etag: header(header::ETAG),
+            last_modified: header(header::LAST_MODIFIED),
+        };
+        let content_type = header(header::CONTENT_TYPE);
+        let body = response
+            .bytes()
+            .map_err(|e| SoapError::Network(format!("Failed to read response body: {e}")))?;
+        Ok(Revalidation::Modified(
+            HttpResource {
+                body: body.to_vec(),
+                content_type,
+            },
+            validators,
+        ))
     }
 
-    /// Fill the buffer with SSDP responses
-    fn fill_buffer(&mut self) {
+    /// Run the next discovery phase, queueing the locations it finds.
+    /// Returns `false` once there is nothing left to ask.
+    fn run_next_phase(&mut self) -> bool {
         if let Some(client) = self.ssdp_client.take() {
-            match client.search("urn:schemas-upnp-org:device:ZonePlayer:1") {
-                Ok(iter) => {
-                    // Collect all SSDP responses into buffer
-                    for response in iter.flatten() {
-                        self.ssdp_buffer.push(response);
-                    }
-                }
-                Err(_) => {
-                    // Failed to start search
-                }
+            if let Ok(iter) = client.search(self.options.ssdp_addr, ZONE_PLAYER_URN) {
+                let responses = iter.flatten().filter(Self::is_likely_sonos);
+                self.locations
+                    .extend(responses.map(|r| (r.location, r.household)));
+            }
+            return true;
+        }
+
+        // The fallback only runs if SSDP turned up no players
+        let wanted = self.options.always_broadcast || self.seen_ids.is_empty();
+        if std::mem::take(&mut self.broadcast_pending) && wanted {
+            if let Ok(replies) =
+                broadcast::search(self.options.broadcast_addr, self.options.timeout)
+            {
+                self.locations.extend(
+                    replies
+                        .into_iter()
+                        .map(|reply| (reply.location(), Some(reply.household_id))),
+                );
             }
-            self.finished = true;
+            return true;
+        }
+
+        self.finished = true;
+        false
+    }
+
+    /// Turn a description URL into a device, or `None` if it's a repeat or
+    /// not a Sonos player
+    fn resolve(&mut self, location: String, household: Option<String>) -> Option<Device> {
+        // Deduplicate by location
+        if !self.seen_locations.insert(location.clone()) {
+            return None;
         }
+
+        // Skip devices that fail to fetch or aren't Sonos
+        let device_desc = self.fetch_device_description(&location).ok()?;
+        if !device_desc.is_sonos_device() {
+            return None;
+        }
+
+        // The same player can answer at two URLs, e.g. once per discovery
+        // mechanism on a multi-homed host
+        if !self.seen_ids.insert(device_desc.udn.clone()) {
+            return None;
+        }
+
+        let ip_address = extract_ip_from_url(&location)?;
+        Some(Device {
+            household_id: household,
+            port: extract_port_from_url(&location)?,
+            secure: location.starts_with("https://"),
+            ..device_desc.to_device(ip_address)
+        })
     }
 }
 
@@ -139,54 +361,14 @@ impl Iterator for DiscoveryIterator {
     type Item = DeviceEvent;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Fill buffer on first call
-        if self.ssdp_client.is_some() {
-            self.fill_buffer();
-        }
-
-        // Process buffered SSDP responses
         loop {
-            // Check if we've processed all responses
-            if self.buffer_index >= self.ssdp_buffer.len() {
+            if let Some((location, household)) = self.locations.pop_front() {
+                if let Some(device) = self.resolve(location, household) {
+                    return Some(DeviceEvent::Found(device));
+                }
+            } else if self.finished || !self.run_next_phase() {
                 return None;
             }
-
-            let ssdp_response = &self.ssdp_buffer[self.buffer_index];
-            self.buffer_index += 1;
-
-            // Deduplicate by location
-            if self.seen_locations.contains(&ssdp_response.location) {
-                continue;
-            }
-            self.seen_locations.insert(ssdp_response.location.clone());
-
-            // Early filtering: skip non-Sonos devices
-            if !Self::is_likely_sonos(ssdp_response) {
-                continue;
-            }
-
-            // Fetch device description
-            let device_desc = match self.fetch_device_description(&ssdp_response.location) {
-                Ok(desc) => desc,
-                Err(_) => continue, // Skip devices that fail to fetch
-            };
-
-            // Validate it's a Sonos device
-            if !device_desc.is_sonos_device() {
-                continue;
-            }
-
-            // Extract IP address from location URL
-            let ip_address = match extract_ip_from_url(&ssdp_response.location) {
-                Some(ip) => ip,
-                None => continue, // Skip if we can't extract IP
-            };
-
-            // Convert to public Device type
-            let device = device_desc.to_device(ip_address);
-
-            // Yield the found device event
-            return Some(DeviceEvent::Found(device));
         }
     }
 }
@@ -202,3 +384,232 @@ impl Drop for DiscoveryIterator {
         // No additional cleanup needed for other fields
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, UdpSocket};
+    use std::thread::{self, JoinHandle};
+
+    const DESCRIPTION: &str = r#"<root xmlns="urn:schemas-upnp-org:device-1-0"><device>
+            <deviceType>urn:schemas-upnp-org:device:ZonePlayer:1</deviceType>
+            <friendlyName>Test Room</friendlyName><manufacturer>Sonos, Inc.</manufacturer>
+            <modelName>Sonos One</modelName><UDN>uuid:RINCON_TEST123456</UDN>
+        </device></root>"#;
+
+    /// Description server plus a broadcast responder that answers the probe
+    /// with a junk datagram and then a reply pointing at the server. The
+    /// handle yields whether a probe arrived.
+    fn broadcast_player(server: &mockito::Server) -> (SocketAddr, JoinHandle<bool>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let addr = socket.local_addr().unwrap();
+        let port = server.socket_address().port();
+        let handle = thread::spawn(move || {
+            let mut buffer = [0; 64];
+            let Ok((size, from)) = socket.recv_from(&mut buffer) else {
+                return false;
+            };
+            assert_eq!(&buffer[..size], broadcast::probe());
+            socket.send_to(b"SONS\x02\xff", from).unwrap();
+            let reply = broadcast::reply("Sonos_test", Ipv4Addr::LOCALHOST, port);
+            socket.send_to(&reply, from).unwrap();
+            true
+        });
+        (addr, handle)
+    }
+
+    /// SSDP responder that answers the first M-SEARCH with `location`
+    fn ssdp_player(location: String) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buffer = [0; 2048];
+            let (_, from) = socket.recv_from(&mut buffer).unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\n\
+                 LOCATION: {location}\r\n\
+                 ST: {ZONE_PLAYER_URN}\r\n\
+                 USN: uuid:RINCON_TEST123456::{ZONE_PLAYER_URN}\r\n\
+                 X-RINCON-HOUSEHOLD: Sonos_ssdp\r\n\
+                 \r\n"
+            );
+            socket.send_to(response.as_bytes(), from).unwrap();
+        });
+        addr
+    }
+
+    fn options(broadcast_addr: SocketAddr) -> DiscoveryOptions {
+        DiscoveryOptions::new()
+            .with_timeout(Duration::from_millis(500))
+            .with_broadcast_addr(broadcast_addr)
+    }
+
+    #[test]
+    fn test_broadcast_finds_devices_without_ssdp() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/xml/device_description.xml")
+            .with_body(DESCRIPTION)
+            .create();
+        let (addr, probed) = broadcast_player(&server);
+
+        let devices = crate::get_with_options(options(addr).with_ssdp(false));
+
+        assert!(probed.join().unwrap());
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].id, "uuid:RINCON_TEST123456");
+        assert_eq!(devices[0].ip_address, "127.0.0.1");
+        assert_eq!(devices[0].household_id.as_deref(), Some("Sonos_test"));
+    }
+
+    #[test]
+    fn test_broadcast_only_falls_back_when_ssdp_finds_nothing() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/xml/device_description.xml")
+            .with_body(DESCRIPTION)
+            .create();
+        let port = server.socket_address().port();
+        let location = format!("http://127.0.0.1:{port}/xml/device_description.xml");
+        let (addr, probed) = broadcast_player(&server);
+        let mut options = options(addr);
+        options.ssdp_addr = ssdp_player(location);
+
+        let devices = crate::get_with_options(options);
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].household_id.as_deref(), Some("Sonos_ssdp"));
+        assert!(!probed.join().unwrap());
+    }
+
+    #[test]
+    fn test_no_duplicates_when_ssdp_and_broadcast_answer() {
+        let mut server = mockito::Server::new();
+        let description = server
+            .mock("GET", "/xml/device_description.xml")
+            .with_body(DESCRIPTION)
+            .expect(2)
+            .create();
+        // SSDP names the host, the broadcast the address: two URLs, one player
+        let port = server.socket_address().port();
+        let location = format!("http://localhost:{port}/xml/device_description.xml");
+        let (addr, probed) = broadcast_player(&server);
+        let mut options = options(addr).with_broadcast(true);
+        options.ssdp_addr = ssdp_player(location);
+
+        let devices = crate::get_with_options(options);
+
+        assert!(probed.join().unwrap());
+        description.assert();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].ip_address, "localhost");
+    }
+
+    #[test]
+    fn test_description_fetch_sends_user_agent() {
+        let mut server = mockito::Server::new();
+        let xml = DESCRIPTION;
+        let default = server
+            .mock("GET", "/default.xml")
+            .match_header("user-agent", crate::USER_AGENT)
+            .with_body(xml)
+            .create();
+        let custom = server
+            .mock("GET", "/custom.xml")
+            .match_header("user-agent", "my-controller/2.1")
+            .with_body(xml)
+            .create();
+
+        let iter = DiscoveryIterator::empty();
+        iter.fetch_device_description(&format!("{}/default.xml", server.url()))
+            .unwrap();
+        let iter = iter.with_user_agent("my-controller/2.1");
+        iter.fetch_device_description(&format!("{}/custom.xml", server.url()))
+            .unwrap();
+
+        default.assert();
+        custom.assert();
+        assert!(crate::USER_AGENT.ends_with(env!("CARGO_PKG_VERSION")));
+    }
+
+    fn cached_iter(cache: &Arc<HttpCache>) -> DiscoveryIterator {
+        DiscoveryIterator::with_options(
+            DiscoveryOptions::new()
+                .with_ssdp(false)
+                .with_http_cache(Arc::clone(cache)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_description_is_revalidated_with_etag() {
+        let mut server = mockito::Server::new();
+        let full = server
+            .mock("GET", "/xml/device_description.xml")
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_header("etag", "\"desc-1\"")
+            .with_body(DESCRIPTION)
+            .expect(1)
+            .create();
+        let not_modified = server
+            .mock("GET", "/xml/device_description.xml")
+            .match_header("if-none-match", "\"desc-1\"")
+            .with_status(304)
+            .expect(2)
+            .create();
+        let location = format!("{}/xml/device_description.xml", server.url());
+        let cache = Arc::new(HttpCache::new(8));
+        let iter = cached_iter(&cache);
+
+        for _ in 0..3 {
+            let description = iter.fetch_device_description(&location).unwrap();
+            assert_eq!(description.udn, "uuid:RINCON_TEST123456");
+        }
+
+        full.assert();
+        not_modified.assert();
+    }
+
+    #[test]
+    fn test_cached_description_serves_while_device_unreachable() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/xml/device_description.xml")
+            .with_header("last-modified", "Tue, 01 Oct 2024 10:00:00 GMT")
+            .with_body(DESCRIPTION)
+            .create();
+        let location = format!("{}/xml/device_description.xml", server.url());
+        let cache = Arc::new(HttpCache::new(8));
+        let iter = cached_iter(&cache);
+        iter.fetch_device_description(&location).unwrap();
+        drop(server);
+
+        let stale = iter.fetch_device_description(&location).unwrap();
+        assert_eq!(stale.udn, "uuid:RINCON_TEST123456");
+
+        let strict = Arc::new(HttpCache::new(8).with_max_stale(Duration::ZERO));
+        assert!(cached_iter(&strict)
+            .fetch_device_description(&location)
+            .is_err());
+    }
+
+    #[test]
+    fn test_description_cache_is_bounded() {
+        let mut server = mockito::Server::new();
+        for path in ["/a.xml", "/b.xml", "/c.xml"] {
+            server.mock("GET", path).with_body(DESCRIPTION).create();
+        }
+        let cache = Arc::new(HttpCache::new(2));
+        let iter = cached_iter(&cache);
+        let url = |path: &str| format!("{}{path}", server.url());
+        for path in ["/a.xml", "/b.xml", "/c.xml"] {
+            iter.fetch_device_description(&url(path)).unwrap();
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&url("/a.xml")));
+        assert!(cache.contains(&url("/c.xml")));
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::fmt;
 
+use soap_client::XmlViolation;
+
 /// Error type for discovery operations.
 ///
 /// Represents various failure modes that can occur during device discovery,
@@ -16,6 +18,9 @@ pub enum DiscoveryError {
     Timeout,
     /// Invalid device data or non-Sonos device detected
     InvalidDevice(String),
+    /// Device description refused before parsing, for a DTD or for breaking
+    /// the [`XmlLimits`](soap_client::XmlLimits)
+    XmlRejected(XmlViolation),
 }
 
 impl fmt::Display for DiscoveryError {
@@ -25,6 +30,7 @@ impl fmt::Display for DiscoveryError {
             DiscoveryError::ParseError(msg) => write!(f, "Parse error: {msg}"),
             DiscoveryError::Timeout => write!(f, "Operation timed out"),
             DiscoveryError::InvalidDevice(msg) => write!(f, "Invalid device: {msg}"),
+            DiscoveryError::XmlRejected(violation) => write!(f, "XML rejected: {violation}"),
         }
     }
 }
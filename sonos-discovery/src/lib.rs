@@ -4,6 +4,8 @@
 //!
 //! This crate provides a simple API for discovering Sonos devices on a local network
 //! using SSDP (Simple Service Discovery Protocol) and UPnP device descriptions.
+//! Networks that drop SSDP multicast are covered by a fallback to the Sonos
+//! broadcast on UDP port 6969; see [`DiscoveryOptions`].
 //!
 //! # Quick Start
 //!
@@ -25,23 +27,26 @@
 //! use sonos_discovery::{get_iter, DeviceEvent};
 //!
 //! for event in get_iter() {
-//!     match event {
-//!         DeviceEvent::Found(device) => {
-//!             println!("Found: {}", device.name);
-//!             // Can break early if needed
-//!         }
+//!     if let DeviceEvent::Found(device) = event {
+//!         println!("Found: {}", device.name);
+//!         // Can break early if needed
 //!     }
 //! }
 //! ```
 
+mod broadcast;
 pub mod device;
 mod discovery;
 mod error;
-mod ssdp;
+pub mod ssdp;
 
-pub use discovery::DiscoveryIterator;
+pub use discovery::{DiscoveryIterator, DiscoveryOptions};
 pub use error::{DiscoveryError, Result};
 
+/// `USER-AGENT` of discovery traffic unless overridden, matching the SDK's
+/// other HTTP requests
+pub const USER_AGENT: &str = concat!("sonos-sdk/", env!("CARGO_PKG_VERSION"));
+
 /// Information about a discovered Sonos device.
 ///
 /// Contains all relevant metadata needed to identify and connect to a Sonos speaker.
@@ -55,20 +60,32 @@ pub struct Device {
     pub room_name: String,
     /// IP address of the device
     pub ip_address: String,
-    /// Port number (typically 1400)
+    /// Port number (typically 1400, or 1443 when `secure`)
     pub port: u16,
     /// Model name (e.g., "Sonos One", "Sonos Play:1")
     pub model_name: String,
+    /// Household the device belongs to, e.g. `Sonos_a1b2c3`, when discovery
+    /// reported one (SSDP `X-RINCON-HOUSEHOLD` or the UDP 6969 reply)
+    #[serde(default)]
+    pub household_id: Option<String>,
+    /// Whether the device serves its local API over HTTPS only (the secure
+    /// local API of newer firmware), as its description URL showed
+    #[serde(default)]
+    pub secure: bool,
 }
 
 /// Events emitted during device discovery.
 ///
-/// Currently only supports device found events. Future versions may add additional
-/// event types (e.g., device lost, device updated).
+/// One-shot scans ([`get_iter()`] and friends) only yield `Found`. `Lost` and
+/// `Updated` come from continuous watchers that track devices over time.
 #[derive(Debug, Clone)]
 pub enum DeviceEvent {
     /// A Sonos device was found on the network
     Found(Device),
+    /// The device with this ID (UDN without the `uuid:` prefix) left the network
+    Lost(String),
+    /// A known device changed address
+    Updated(Device),
 }
 
 use std::time::Duration;
@@ -76,6 +93,7 @@ use std::time::Duration;
 /// Discover all Sonos devices on the local network with a default 3-second timeout.
 ///
 /// This is a convenience function that collects all discovered devices into a Vec.
+/// If SSDP finds nothing, the Sonos broadcast is tried for another 3 seconds.
 /// For more control over the discovery process, use `get_iter()` instead.
 ///
 /// # Examples
@@ -113,9 +131,27 @@ pub fn get() -> Vec<Device> {
 /// }
 /// ```
 pub fn get_with_timeout(timeout: Duration) -> Vec<Device> {
-    get_iter_with_timeout(timeout)
-        .map(|event| match event {
-            DeviceEvent::Found(device) => device,
+    get_with_options(DiscoveryOptions::new().with_timeout(timeout))
+}
+
+/// Discover all Sonos devices on the local network with explicit options.
+///
+/// Use this to skip SSDP, turn the broadcast fallback off, or always send
+/// the broadcast alongside SSDP.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sonos_discovery::{get_with_options, DiscoveryOptions};
+///
+/// let devices = get_with_options(DiscoveryOptions::new().with_fallback(false));
+/// println!("SSDP found {} devices", devices.len());
+/// ```
+pub fn get_with_options(options: DiscoveryOptions) -> Vec<Device> {
+    get_iter_with_options(options)
+        .filter_map(|event| match event {
+            DeviceEvent::Found(device) => Some(device),
+            _ => None,
         })
         .collect()
 }
@@ -131,12 +167,10 @@ pub fn get_with_timeout(timeout: Duration) -> Vec<Device> {
 /// use sonos_discovery::{get_iter, DeviceEvent};
 ///
 /// for event in get_iter() {
-///     match event {
-///         DeviceEvent::Found(device) => {
-///             println!("Found: {} at {}", device.name, device.ip_address);
-///             // Can break early if needed
-///             break;
-///         }
+///     if let DeviceEvent::Found(device) = event {
+///         println!("Found: {} at {}", device.name, device.ip_address);
+///         // Can break early if needed
+///         break;
 ///     }
 /// }
 /// ```
@@ -161,15 +195,20 @@ pub fn get_iter() -> DiscoveryIterator {
 /// use std::time::Duration;
 ///
 /// for event in get_iter_with_timeout(Duration::from_secs(5)) {
-///     match event {
-///         DeviceEvent::Found(device) => {
-///             println!("Found: {} at {}", device.name, device.ip_address);
-///         }
+///     if let DeviceEvent::Found(device) = event {
+///         println!("Found: {} at {}", device.name, device.ip_address);
 ///     }
 /// }
 /// ```
 pub fn get_iter_with_timeout(timeout: Duration) -> DiscoveryIterator {
-    DiscoveryIterator::new(timeout).unwrap_or_else(|_| {
+    get_iter_with_options(DiscoveryOptions::new().with_timeout(timeout))
+}
+
+/// Get an iterator for discovering Sonos devices with explicit options.
+///
+/// See [`get_with_options()`].
+pub fn get_iter_with_options(options: DiscoveryOptions) -> DiscoveryIterator {
+    DiscoveryIterator::with_options(options).unwrap_or_else(|_| {
         // If we fail to create the iterator, return an empty one
         // This is better than panicking
         DiscoveryIterator::empty()
@@ -1,19 +1,118 @@
 //! SSDP (Simple Service Discovery Protocol) implementation for device discovery
 //!
-//! This module provides internal SSDP client functionality for discovering UPnP devices
-//! on the local network. It is not part of the public API.
+//! The multicast client behind [`get_iter()`](crate::get_iter) is internal.
+//! [`unicast_search()`] is public: it asks one known address who it is,
+//! which is much cheaper than a network-wide search when checking that a
+//! remembered speaker is still there.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
 
 use crate::error::{DiscoveryError, Result};
-use std::net::UdpSocket;
-use std::time::Duration;
+use crate::USER_AGENT;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Search target for Sonos players
+pub(crate) const ZONE_PLAYER_URN: &str = "urn:schemas-upnp-org:device:ZonePlayer:1";
+
+/// Port devices listen on for M-SEARCH requests
+const SSDP_PORT: u16 = 1900;
+
+/// Multicast group [`SsdpClient::search()`] sends to
+pub(crate) const SSDP_MULTICAST: SocketAddr = SocketAddr::V4(SocketAddrV4::new(
+    Ipv4Addr::new(239, 255, 255, 250),
+    SSDP_PORT,
+));
 
 /// SSDP response containing device information
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) struct SsdpResponse {
+pub struct SsdpResponse {
+    /// URL of the device description XML
     pub location: String,
+    /// `ST` header: the device type searched for
     pub urn: String,
+    /// `USN` header, e.g. `uuid:RINCON_…::urn:schemas-upnp-org:device:ZonePlayer:1`
     pub usn: String,
     pub server: Option<String>,
+    /// `X-RINCON-BOOTSEQ` header: Sonos bumps it on every boot, so a change
+    /// means the device restarted
+    pub bootseq: Option<u32>,
+    /// `X-RINCON-HOUSEHOLD` header: the household the player belongs to
+    pub household: Option<String>,
+}
+
+impl SsdpResponse {
+    /// Device ID from the USN, without the `uuid:` prefix or the service
+    /// suffix (`RINCON_…`, the form used by [`DeviceEvent::Lost`](crate::DeviceEvent::Lost))
+    pub fn uuid(&self) -> &str {
+        let udn = self.usn.split("::").next().unwrap_or_default();
+        udn.strip_prefix("uuid:").unwrap_or(udn)
+    }
+}
+
+/// Send an M-SEARCH straight to `ip` and return its answer
+///
+/// Confirms who is at an address without a multicast search: compare
+/// [`SsdpResponse::uuid()`] with the expected device and `bootseq` with the
+/// last one seen. Responses from other addresses are ignored.
+///
+/// # Errors
+///
+/// [`DiscoveryError::Timeout`] if `ip` doesn't answer within `timeout`, or
+/// a `NetworkError` if the request can't be sent.
+pub fn unicast_search(ip: IpAddr, timeout: Duration) -> Result<SsdpResponse> {
+    unicast_search_at(SocketAddr::new(ip, SSDP_PORT), timeout)
+}
+
+fn unicast_search_at(target: SocketAddr, timeout: Duration) -> Result<SsdpResponse> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .map_err(|e| DiscoveryError::NetworkError(format!("Failed to bind UDP socket: {e}")))?;
+    // Unicast searches carry the target's address and no MX
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\n\
+         HOST: {target}\r\n\
+         MAN: \"ssdp:discover\"\r\n\
+         ST: {ZONE_PLAYER_URN}\r\n\
+         USER-AGENT: {USER_AGENT} UPnP/1.0\r\n\
+         \r\n"
+    );
+    socket
+        .send_to(request.as_bytes(), target)
+        .map_err(|e| DiscoveryError::NetworkError(format!("Failed to send M-SEARCH: {e}")))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buffer = [0; 2048];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(DiscoveryError::Timeout);
+        }
+        socket.set_read_timeout(Some(remaining)).map_err(|e| {
+            DiscoveryError::NetworkError(format!("Failed to set read timeout: {e}"))
+        })?;
+        match socket.recv_from(&mut buffer) {
+            Ok((size, from)) if from.ip() == target.ip() => {
+                if let Some(response) = std::str::from_utf8(&buffer[..size])
+                    .ok()
+                    .and_then(parse_ssdp_response)
+                {
+                    return Ok(response);
+                }
+            }
+            Ok(_) => {}
+            Err(e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                return Err(DiscoveryError::Timeout);
+            }
+            Err(e) => return Err(DiscoveryError::NetworkError(format!("Socket error: {e}"))),
+        }
+    }
 }
 
 /// SSDP client for device discovery
@@ -38,20 +137,25 @@ impl SsdpClient {
         Ok(Self { socket })
     }
 
-    /// Send an M-SEARCH request and return an iterator of responses
-    pub fn search(&self, search_target: &str) -> Result<SsdpResponseIterator<'_>> {
+    /// Send an M-SEARCH request to `target` (normally [`SSDP_MULTICAST`])
+    /// and return an iterator of responses
+    pub fn search(
+        &self,
+        target: SocketAddr,
+        search_target: &str,
+    ) -> Result<SsdpResponseIterator<'_>> {
         let request = format!(
             "M-SEARCH * HTTP/1.1\r\n\
-             HOST: 239.255.255.250:1900\r\n\
+             HOST: {target}\r\n\
              MAN: \"ssdp:discover\"\r\n\
              MX: 2\r\n\
              ST: {search_target}\r\n\
-             USER-AGENT: sonos-rs/1.0 UPnP/1.0\r\n\
+             USER-AGENT: {USER_AGENT} UPnP/1.0\r\n\
              \r\n"
         );
 
         self.socket
-            .send_to(request.as_bytes(), "239.255.255.250:1900")
+            .send_to(request.as_bytes(), target)
             .map_err(|e| DiscoveryError::NetworkError(format!("Failed to send M-SEARCH: {e}")))?;
 
         Ok(SsdpResponseIterator::new(&self.socket))
@@ -79,41 +183,32 @@ impl<'a> Iterator for SsdpResponseIterator<'a> {
     type Item = Result<SsdpResponse>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.finished {
-            return None;
-        }
-
-        match self.socket.recv_from(&mut self.buffer) {
-            Ok((size, _)) => {
-                match std::str::from_utf8(&self.buffer[..size]) {
-                    Ok(response_text) => {
-                        match parse_ssdp_response(response_text) {
-                            Some(response) => Some(Ok(response)),
-                            None => {
-                                // Invalid response, try next one
-                                self.next()
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        // Invalid UTF-8, try next one
-                        self.next()
+        // Loop rather than recurse past junk: any host on the LAN can send
+        // us datagrams, and each one would otherwise cost a stack frame
+        while !self.finished {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((size, _)) => {
+                    let parsed = std::str::from_utf8(&self.buffer[..size])
+                        .ok()
+                        .and_then(parse_ssdp_response);
+                    if let Some(response) = parsed {
+                        return Some(Ok(response));
                     }
                 }
-            }
-            Err(e) => {
-                if e.kind() == std::io::ErrorKind::WouldBlock
-                    || e.kind() == std::io::ErrorKind::TimedOut
-                {
-                    self.finished = true;
-                    None
-                } else {
-                    Some(Err(DiscoveryError::NetworkError(format!(
-                        "Socket error: {e}"
-                    ))))
+                Err(e) => {
+                    if e.kind() == std::io::ErrorKind::WouldBlock
+                        || e.kind() == std::io::ErrorKind::TimedOut
+                    {
+                        self.finished = true;
+                    } else {
+                        return Some(Err(DiscoveryError::NetworkError(format!(
+                            "Socket error: {e}"
+                        ))));
+                    }
                 }
             }
         }
+        None
     }
 }
 
@@ -123,6 +218,8 @@ fn parse_ssdp_response(response: &str) -> Option<SsdpResponse> {
     let mut urn = None;
     let mut usn = None;
     let mut server = None;
+    let mut bootseq = None;
+    let mut household = None;
 
     for line in response.lines() {
         let line = line.trim();
@@ -135,6 +232,10 @@ fn parse_ssdp_response(response: &str) -> Option<SsdpResponse> {
             usn = Some(value);
         } else if let Some(value) = extract_header_value(line, "SERVER:") {
             server = Some(value);
+        } else if let Some(value) = extract_header_value(line, "X-RINCON-BOOTSEQ:") {
+            bootseq = value.parse().ok();
+        } else if let Some(value) = extract_header_value(line, "X-RINCON-HOUSEHOLD:") {
+            household = Some(value);
         }
     }
 
@@ -144,6 +245,8 @@ fn parse_ssdp_response(response: &str) -> Option<SsdpResponse> {
             urn,
             usn,
             server,
+            bootseq,
+            household,
         }),
         _ => None,
     }
@@ -151,16 +254,81 @@ fn parse_ssdp_response(response: &str) -> Option<SsdpResponse> {
 
 /// Extract header value from a line like "HEADER: value"
 fn extract_header_value(line: &str, header: &str) -> Option<String> {
-    if line.len() > header.len() && line[..header.len()].eq_ignore_ascii_case(header) {
-        Some(line[header.len()..].trim().to_string())
-    } else {
-        None
+    // `get` rather than slicing: a multi-byte character can straddle the cut
+    match (line.get(..header.len()), line.get(header.len()..)) {
+        (Some(name), Some(value)) if !value.is_empty() && name.eq_ignore_ascii_case(header) => {
+            Some(value.trim().to_string())
+        }
+        _ => None,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::thread;
+
+    const BEDROOM_USN: &str =
+        "uuid:RINCON_000E58A0123456::urn:schemas-upnp-org:device:ZonePlayer:1";
+
+    /// UDP responder on an ephemeral loopback port that answers the first
+    /// M-SEARCH with `usn`, or never answers if `usn` is `None`
+    fn responder(usn: Option<&'static str>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buffer = [0; 2048];
+            let (size, from) = socket.recv_from(&mut buffer).unwrap();
+            let request = std::str::from_utf8(&buffer[..size]).unwrap();
+            assert!(request.starts_with("M-SEARCH"));
+            assert!(request.contains(ZONE_PLAYER_URN));
+            let Some(usn) = usn else {
+                // Keep the socket open so the search times out
+                thread::sleep(Duration::from_secs(2));
+                return;
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\n\
+                 LOCATION: http://127.0.0.1:1400/xml/device_description.xml\r\n\
+                 ST: {ZONE_PLAYER_URN}\r\n\
+                 USN: {usn}\r\n\
+                 X-RINCON-BOOTSEQ: 42\r\n\
+                 \r\n"
+            );
+            socket.send_to(response.as_bytes(), from).unwrap();
+        });
+        addr
+    }
+
+    #[test]
+    fn test_unicast_search_returns_identity() {
+        let addr = responder(Some(BEDROOM_USN));
+        let response = unicast_search_at(addr, Duration::from_secs(2)).unwrap();
+
+        assert_eq!(response.uuid(), "RINCON_000E58A0123456");
+        assert_eq!(response.bootseq, Some(42));
+        assert_eq!(
+            response.location,
+            "http://127.0.0.1:1400/xml/device_description.xml"
+        );
+    }
+
+    #[test]
+    fn test_unicast_search_times_out() {
+        let addr = responder(None);
+        let result = unicast_search_at(addr, Duration::from_millis(200));
+        assert!(matches!(result, Err(DiscoveryError::Timeout)));
+    }
+
+    #[test]
+    fn test_unicast_search_reports_a_different_device() {
+        let addr = responder(Some(
+            "uuid:RINCON_B8E9378C0FFEE01400::urn:schemas-upnp-org:device:ZonePlayer:1",
+        ));
+        let response = unicast_search_at(addr, Duration::from_secs(2)).unwrap();
+        assert_ne!(response.uuid(), "RINCON_000E58A0123456");
+        assert_eq!(response.uuid(), "RINCON_B8E9378C0FFEE01400");
+    }
 
     #[test]
     fn test_parse_ssdp_response_valid() {
@@ -169,6 +337,7 @@ mod tests {
             ST: urn:schemas-upnp-org:device:ZonePlayer:1\r\n\
             USN: uuid:RINCON_000E58A0123456::urn:schemas-upnp-org:device:ZonePlayer:1\r\n\
             SERVER: Linux/3.14.0 UPnP/1.0 Sonos/70.3-35220\r\n\
+            X-RINCON-HOUSEHOLD: Sonos_a1b2c3\r\n\
             \r\n";
 
         let parsed = parse_ssdp_response(response).unwrap();
@@ -186,6 +355,7 @@ mod tests {
             parsed.server,
             Some("Linux/3.14.0 UPnP/1.0 Sonos/70.3-35220".to_string())
         );
+        assert_eq!(parsed.household.as_deref(), Some("Sonos_a1b2c3"));
     }
 
     #[test]
@@ -351,4 +521,34 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_extract_header_value_multibyte_boundary() {
+        // Found by fuzz_parse_ssdp_response: "ഒ" straddles the 7-byte cut for "SERVER:"
+        assert_eq!(extract_header_value("0AaA ഒ", "SERVER:"), None);
+        assert!(parse_ssdp_response("0AaA ഒ").is_none());
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn fuzz_parse_ssdp_response(response in ssdp_lines()) {
+            parse_ssdp_response(&response);
+        }
+    }
+
+    /// Header lines Sonos sends, in any case and with any value, mixed with junk
+    fn ssdp_lines() -> impl proptest::strategy::Strategy<Value = String> {
+        use proptest::prelude::*;
+        let line = prop_oneof![
+            (
+                "(?i)(LOCATION|ST|USN|SERVER|X-RINCON-BOOTSEQ|X-RINCON-HOUSEHOLD):",
+                "\\PC{0,24}"
+            )
+                .prop_map(|(header, value)| format!("{header}{value}")),
+            "\\PC{0,32}",
+        ];
+        proptest::collection::vec(line, 0..8).prop_map(|lines| lines.join("\r\n"))
+    }
 }
@@ -20,60 +20,56 @@ fn capture_real_device_data() {
     let mut device_count = 0;
 
     for event in get_iter_with_timeout(timeout) {
-        match event {
-            DeviceEvent::Found(device) => {
-                device_count += 1;
-
-                println!("--- Device {device_count} ---");
-                println!("ID:         {}", device.id);
-                println!("Name:       {}", device.name);
-                println!("Room:       {}", device.room_name);
-                println!("IP:         {}", device.ip_address);
-                println!("Port:       {}", device.port);
-                println!("Model:      {}", device.model_name);
-                println!();
-
-                // Fetch the device XML for this device
-                let url = format!(
-                    "http://{}:{}/xml/device_description.xml",
-                    device.ip_address, device.port
-                );
-
-                println!("Fetching device XML from: {url}");
-
-                match reqwest::blocking::get(&url) {
-                    Ok(response) => {
-                        match response.text() {
-                            Ok(xml) => {
-                                println!("Device XML:");
-                                println!("{xml}");
-                                println!();
-
-                                // Suggest fixture filename
-                                let model_slug = device
-                                    .model_name
-                                    .to_lowercase()
-                                    .replace(" ", "_")
-                                    .replace(":", "");
-                                let filename = format!("sonos_{model_slug}_device.xml");
-                                println!("Suggested fixture filename: {filename}");
-                                println!(
-                                    "Save this XML to: sonos-discovery/tests/fixtures/{filename}"
-                                );
-                                println!();
-                            }
-                            Err(e) => {
-                                println!("Failed to read XML response: {e}");
-                            }
+        if let DeviceEvent::Found(device) = event {
+            device_count += 1;
+
+            println!("--- Device {device_count} ---");
+            println!("ID:         {}", device.id);
+            println!("Name:       {}", device.name);
+            println!("Room:       {}", device.room_name);
+            println!("IP:         {}", device.ip_address);
+            println!("Port:       {}", device.port);
+            println!("Model:      {}", device.model_name);
+            println!();
+
+            // Fetch the device XML for this device
+            let url = format!(
+                "http://{}:{}/xml/device_description.xml",
+                device.ip_address, device.port
+            );
+
+            println!("Fetching device XML from: {url}");
+
+            match reqwest::blocking::get(&url) {
+                Ok(response) => {
+                    match response.text() {
+                        Ok(xml) => {
+                            println!("Device XML:");
+                            println!("{xml}");
+                            println!();
+
+                            // Suggest fixture filename
+                            let model_slug = device
+                                .model_name
+                                .to_lowercase()
+                                .replace(" ", "_")
+                                .replace(":", "");
+                            let filename = format!("sonos_{model_slug}_device.xml");
+                            println!("Suggested fixture filename: {filename}");
+                            println!("Save this XML to: sonos-discovery/tests/fixtures/{filename}");
+                            println!();
+                        }
+                        Err(e) => {
+                            println!("Failed to read XML response: {e}");
                         }
-                    }
-                    Err(e) => {
-                        println!("Failed to fetch device XML: {e}");
                     }
                 }
-
-                println!("========================================\n");
+                Err(e) => {
+                    println!("Failed to fetch device XML: {e}");
+                }
             }
+
+            println!("========================================\n");
         }
     }
 
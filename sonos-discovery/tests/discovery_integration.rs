@@ -19,35 +19,33 @@ fn test_full_discovery_flow_with_iterator() {
     let mut discovered_devices = Vec::new();
 
     for event in get_iter_with_timeout(timeout) {
-        match event {
-            DeviceEvent::Found(device) => {
-                // Validate device has required fields
-                assert!(!device.id.is_empty(), "Device ID should not be empty");
-                assert!(!device.name.is_empty(), "Device name should not be empty");
-                assert!(
-                    !device.ip_address.is_empty(),
-                    "Device IP should not be empty"
-                );
-                assert!(
-                    !device.model_name.is_empty(),
-                    "Device model should not be empty"
-                );
-                assert_eq!(device.port, 1400, "Sonos devices typically use port 1400");
-
-                // Verify ID format (should be a UUID)
-                assert!(
-                    device.id.starts_with("uuid:"),
-                    "Device ID should start with 'uuid:'"
-                );
-
-                // Verify IP address format (basic check)
-                assert!(
-                    device.ip_address.contains('.'),
-                    "IP address should contain dots"
-                );
-
-                discovered_devices.push(device);
-            }
+        if let DeviceEvent::Found(device) = event {
+            // Validate device has required fields
+            assert!(!device.id.is_empty(), "Device ID should not be empty");
+            assert!(!device.name.is_empty(), "Device name should not be empty");
+            assert!(
+                !device.ip_address.is_empty(),
+                "Device IP should not be empty"
+            );
+            assert!(
+                !device.model_name.is_empty(),
+                "Device model should not be empty"
+            );
+            assert_eq!(device.port, 1400, "Sonos devices typically use port 1400");
+
+            // Verify ID format (should be a UUID)
+            assert!(
+                device.id.starts_with("uuid:"),
+                "Device ID should start with 'uuid:'"
+            );
+
+            // Verify IP address format (basic check)
+            assert!(
+                device.ip_address.contains('.'),
+                "IP address should contain dots"
+            );
+
+            discovered_devices.push(device);
         }
     }
 
@@ -73,24 +71,22 @@ fn test_deduplication_logic() {
     let mut total_events = 0;
 
     for event in get_iter_with_timeout(timeout) {
-        match event {
-            DeviceEvent::Found(device) => {
-                total_events += 1;
-
-                // Check that we haven't seen this device ID before
-                assert!(
-                    device_ids.insert(device.id.clone()),
-                    "Device ID {} was reported multiple times - deduplication failed",
-                    device.id
-                );
-
-                // Check that we haven't seen this IP address before
-                assert!(
-                    device_ips.insert(device.ip_address.clone()),
-                    "Device IP {} was reported multiple times - deduplication failed",
-                    device.ip_address
-                );
-            }
+        if let DeviceEvent::Found(device) = event {
+            total_events += 1;
+
+            // Check that we haven't seen this device ID before
+            assert!(
+                device_ids.insert(device.id.clone()),
+                "Device ID {} was reported multiple times - deduplication failed",
+                device.id
+            );
+
+            // Check that we haven't seen this IP address before
+            assert!(
+                device_ips.insert(device.ip_address.clone()),
+                "Device IP {} was reported multiple times - deduplication failed",
+                device.ip_address
+            );
         }
     }
 
@@ -104,15 +100,13 @@ fn test_early_iterator_termination_with_discovery() {
     let mut count = 0;
 
     for event in get_iter_with_timeout(timeout) {
-        match event {
-            DeviceEvent::Found(device) => {
-                println!("Found device: {} at {}", device.name, device.ip_address);
-                count += 1;
-
-                // Break after finding first device (if any)
-                if count >= 1 {
-                    break;
-                }
+        if let DeviceEvent::Found(device) = event {
+            println!("Found device: {} at {}", device.name, device.ip_address);
+            count += 1;
+
+            // Break after finding first device (if any)
+            if count >= 1 {
+                break;
             }
         }
     }
@@ -162,10 +156,8 @@ fn test_get_iter_default_timeout() {
     let mut count = 0;
 
     for event in get_iter() {
-        match event {
-            DeviceEvent::Found(_device) => {
-                count += 1;
-            }
+        if let DeviceEvent::Found(_device) = event {
+            count += 1;
         }
     }
 
@@ -179,24 +171,22 @@ fn test_filtering_non_sonos_devices() {
     let timeout = Duration::from_secs(2);
 
     for event in get_iter_with_timeout(timeout) {
-        match event {
-            DeviceEvent::Found(device) => {
-                // Sonos device IDs contain RINCON
-                assert!(
-                    device.id.contains("RINCON") || device.id.contains("rincon"),
-                    "Device ID {} doesn't appear to be a Sonos device",
-                    device.id
-                );
-
-                // Sonos devices use port 1400
-                assert_eq!(
-                    device.port, 1400,
-                    "Non-Sonos device detected with port {}",
-                    device.port
-                );
-
-                println!("Validated Sonos device: {} ({})", device.name, device.id);
-            }
+        if let DeviceEvent::Found(device) = event {
+            // Sonos device IDs contain RINCON
+            assert!(
+                device.id.contains("RINCON") || device.id.contains("rincon"),
+                "Device ID {} doesn't appear to be a Sonos device",
+                device.id
+            );
+
+            // Sonos devices use port 1400
+            assert_eq!(
+                device.port, 1400,
+                "Non-Sonos device detected with port {}",
+                device.port
+            );
+
+            println!("Validated Sonos device: {} ({})", device.name, device.id);
         }
     }
 }
@@ -239,8 +229,9 @@ fn test_iterator_collect_matches_get() {
     std::thread::sleep(Duration::from_millis(100));
 
     let devices_from_iter: Vec<_> = get_iter_with_timeout(timeout)
-        .map(|event| match event {
-            DeviceEvent::Found(device) => device,
+        .filter_map(|event| match event {
+            DeviceEvent::Found(device) => Some(device),
+            _ => None,
         })
         .collect();
 
@@ -267,6 +258,7 @@ fn test_device_event_clone() {
                 assert_eq!(device1.name, device2.name);
                 assert_eq!(device1.ip_address, device2.ip_address);
             }
+            _ => panic!("clone changed the variant"),
         }
     }
 }
@@ -277,13 +269,11 @@ fn test_device_debug_format() {
     let timeout = Duration::from_millis(500);
 
     for event in get_iter_with_timeout(timeout).take(1) {
-        match event {
-            DeviceEvent::Found(device) => {
-                let debug_str = format!("{device:?}");
-                assert!(debug_str.contains("Device"));
-                assert!(debug_str.contains(&device.id));
-                println!("Device debug format: {debug_str}");
-            }
+        if let DeviceEvent::Found(device) = event {
+            let debug_str = format!("{device:?}");
+            assert!(debug_str.contains("Device"));
+            assert!(debug_str.contains(&device.id));
+            println!("Device debug format: {debug_str}");
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::net::IpAddr;
+use std::net::SocketAddr;
 use thiserror::Error;
 
 /// Errors that can occur in the Sonos Event Manager
@@ -9,38 +9,38 @@ pub enum EventManagerError {
     BrokerInitialization(#[from] sonos_stream::BrokerError),
 
     /// Error registering device with broker
-    #[error("Failed to register device {device_ip} for service {service:?}: {source}")]
+    #[error("Failed to register device {device_addr} for service {service:?}: {source}")]
     DeviceRegistration {
-        device_ip: IpAddr,
+        device_addr: SocketAddr,
         service: sonos_api::Service,
         #[source]
         source: sonos_stream::BrokerError,
     },
 
     /// Error unregistering device from broker
-    #[error("Failed to unregister device {device_ip} for service {service:?}: {source}")]
+    #[error("Failed to unregister device {device_addr} for service {service:?}: {source}")]
     DeviceUnregistration {
-        device_ip: IpAddr,
+        device_addr: SocketAddr,
         service: sonos_api::Service,
         #[source]
         source: sonos_stream::BrokerError,
     },
 
     /// Error creating event consumer
-    #[error("Failed to create event consumer for {device_ip} service {service:?}")]
+    #[error("Failed to create event consumer for {device_addr} service {service:?}")]
     ConsumerCreation {
-        device_ip: IpAddr,
+        device_addr: SocketAddr,
         service: sonos_api::Service,
     },
 
     /// Device not found
-    #[error("Device with IP {0} not found")]
-    DeviceNotFound(IpAddr),
+    #[error("Device with address {0} not found")]
+    DeviceNotFound(SocketAddr),
 
     /// Subscription not found
-    #[error("Subscription for device {device_ip} service {service:?} not found")]
+    #[error("Subscription for device {device_addr} service {service:?} not found")]
     SubscriptionNotFound {
-        device_ip: IpAddr,
+        device_addr: SocketAddr,
         service: sonos_api::Service,
     },
 
@@ -67,6 +67,14 @@ pub enum EventManagerError {
     /// Background worker disconnected
     #[error("Background worker has disconnected")]
     WorkerDisconnected,
+
+    /// Background worker did not answer in time
+    #[error("Timed out waiting for the background worker")]
+    WorkerTimeout,
+
+    /// The manager is draining for shutdown and takes no new subscriptions
+    #[error("Event manager is shutting down")]
+    ShuttingDown,
 }
 
 /// Result type for Event Manager operations
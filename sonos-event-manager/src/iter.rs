@@ -7,40 +7,66 @@ use std::sync::{mpsc, Arc, Mutex};
 use std::time::Duration;
 
 use sonos_stream::events::EnrichedEvent;
+use sonos_stream::SpilloverQueue;
+
+/// Consumer end of the worker's [`EventSink`](crate::worker::EventSink)
+#[derive(Clone)]
+enum EventFeed {
+    Channel(Arc<Mutex<mpsc::Receiver<EnrichedEvent>>>),
+    Spillover(Arc<SpilloverQueue>),
+}
 
 /// Blocking iterator over enriched events
 ///
 /// This iterator blocks on `next()` until an event is available or the
 /// channel is closed. Use `try_recv()` for non-blocking access.
 pub struct EventManagerIterator {
-    rx: Arc<Mutex<mpsc::Receiver<EnrichedEvent>>>,
+    source: EventFeed,
 }
 
 impl EventManagerIterator {
     /// Create a new iterator from a shared receiver
     pub(crate) fn new(rx: Arc<Mutex<mpsc::Receiver<EnrichedEvent>>>) -> Self {
-        Self { rx }
+        Self {
+            source: EventFeed::Channel(rx),
+        }
+    }
+
+    /// Create a new iterator reading from a spillover queue
+    pub(crate) fn spillover(queue: Arc<SpilloverQueue>) -> Self {
+        Self {
+            source: EventFeed::Spillover(queue),
+        }
     }
 
     /// Block until an event is available
     ///
     /// Returns `None` if the channel is closed.
     pub fn recv(&self) -> Option<EnrichedEvent> {
-        self.rx.lock().ok()?.recv().ok()
+        match &self.source {
+            EventFeed::Channel(rx) => rx.lock().ok()?.recv().ok(),
+            EventFeed::Spillover(queue) => queue.recv(),
+        }
     }
 
     /// Try to receive an event without blocking
     ///
     /// Returns `None` if no event is currently available or channel is closed.
     pub fn try_recv(&self) -> Option<EnrichedEvent> {
-        self.rx.lock().ok()?.try_recv().ok()
+        match &self.source {
+            EventFeed::Channel(rx) => rx.lock().ok()?.try_recv().ok(),
+            EventFeed::Spillover(queue) => queue.try_recv(),
+        }
     }
 
     /// Block until an event is available or timeout expires
     ///
     /// Returns `None` if the timeout expires or channel is closed.
     pub fn recv_timeout(&self, timeout: Duration) -> Option<EnrichedEvent> {
-        self.rx.lock().ok()?.recv_timeout(timeout).ok()
+        match &self.source {
+            EventFeed::Channel(rx) => rx.lock().ok()?.recv_timeout(timeout).ok(),
+            EventFeed::Spillover(queue) => queue.recv_timeout(timeout),
+        }
     }
 
     /// Get a non-blocking iterator over currently available events
@@ -73,7 +99,7 @@ impl Iterator for EventManagerIterator {
 impl Clone for EventManagerIterator {
     fn clone(&self) -> Self {
         Self {
-            rx: Arc::clone(&self.rx),
+            source: self.source.clone(),
         }
     }
 }
@@ -37,18 +37,18 @@
 //! // Get a device
 //! let devices = manager.devices();
 //! if let Some(device) = devices.first() {
-//!     let device_ip = device.ip_address.parse()?;
+//!     let device_addr = std::net::SocketAddr::new(device.ip_address.parse()?, device.port);
 //!
 //!     // Subscribe to events (sync, ref-counted)
-//!     manager.ensure_service_subscribed(device_ip, Service::RenderingControl)?;
+//!     manager.ensure_service_subscribed(device_addr, Service::RenderingControl)?;
 //!
 //!     // Iterate over events (blocking)
 //!     for event in manager.iter() {
-//!         println!("Event from {:?}: {:?}", event.speaker_ip, event.service);
+//!         println!("Event from {:?}: {:?}", event.speaker_addr, event.service);
 //!     }
 //!
 //!     // Release subscription when done
-//!     manager.release_service_subscription(device_ip, Service::RenderingControl)?;
+//!     manager.release_service_subscription(device_addr, Service::RenderingControl)?;
 //! }
 //! ```
 //!
@@ -77,8 +77,13 @@ pub use manager::{SonosEventManager, WatchGuard, WatchRegistry};
 // Re-export commonly used types from dependencies
 pub use sonos_api::Service;
 pub use sonos_discovery::Device;
+pub use sonos_stream::diagnostics::{
+    ExchangeKind, NotifyOutcome, NotifyReceipt, SubscriptionExchange,
+};
 pub use sonos_stream::events::EnrichedEvent;
-pub use sonos_stream::BrokerConfig;
+pub use sonos_stream::{
+    BrokerConfig, ProtocolObserver, ProtocolRecord, RedactionPolicy, SuspendPolicy,
+};
 
 /// Prelude module for convenient imports
 ///
@@ -5,27 +5,33 @@
 
 use std::collections::HashMap;
 use std::fmt;
-use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Mutex, OnceLock};
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use parking_lot::RwLock;
 use tokio::sync::mpsc as tokio_mpsc;
 
+use sonos_api::clock::SharedClock;
 use sonos_api::{Service, SpeakerId};
 use sonos_discovery::Device;
-use sonos_stream::events::EnrichedEvent;
-use sonos_stream::BrokerConfig;
+use sonos_stream::{BrokerConfig, SpilloverMetrics, SpilloverQueue, SuspendPolicy};
 
 use crate::error::{EventManagerError, Result};
 use crate::iter::EventManagerIterator;
-use crate::worker::{spawn_event_worker, Command};
+use crate::worker::{spawn_event_worker, Command, EventSink};
 
 /// Grace period duration before unsubscribing after last guard drops
 const GRACE_PERIOD: Duration = Duration::from_millis(50);
 
+/// Upper bound on waiting for the worker to finish a suspend or resume
+const SUSPEND_REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time past a drain deadline allowed for forwarding what was already queued
+const DRAIN_REPLY_SLACK: Duration = Duration::from_millis(100);
+
 // ============================================================================
 // WatchRegistry trait
 // ============================================================================
@@ -40,7 +46,7 @@ pub trait WatchRegistry: Send + Sync + 'static {
 
     /// Unregister all watched properties for a given service on a device.
     /// Called when the grace period expires and the subscription is actually torn down.
-    fn unregister_watches_for_service(&self, ip: IpAddr, service: Service);
+    fn unregister_watches_for_service(&self, addr: SocketAddr, service: Service);
 }
 
 // ============================================================================
@@ -62,8 +68,11 @@ pub struct WatchGuard {
     event_manager: Arc<SonosEventManager>,
     speaker_id: SpeakerId,
     property_key: &'static str,
-    ip: IpAddr,
+    addr: SocketAddr,
     service: Service,
+    /// `addr`'s release generation when acquired; see
+    /// [`SonosEventManager::release_device()`]
+    generation: u64,
 }
 
 // Compile-time assertion: WatchGuard must be Send
@@ -77,7 +86,7 @@ impl fmt::Debug for WatchGuard {
         f.debug_struct("WatchGuard")
             .field("speaker_id", &self.speaker_id)
             .field("property_key", &self.property_key)
-            .field("ip", &self.ip)
+            .field("addr", &self.addr)
             .field("service", &self.service)
             .finish()
     }
@@ -89,8 +98,9 @@ impl Drop for WatchGuard {
         self.event_manager.release_watch(
             &self.speaker_id,
             self.property_key,
-            self.ip,
+            self.addr,
             self.service,
+            self.generation,
         );
     }
 }
@@ -99,6 +109,22 @@ impl Drop for WatchGuard {
 // SonosEventManager
 // ============================================================================
 
+/// Grace-period cancel flags by (addr, service)
+type PendingUnsubscribes = HashMap<(SocketAddr, Service), Arc<AtomicBool>>;
+
+/// Holds a device had when released, by (addr, generation released)
+type CarriedHolds = HashMap<(SocketAddr, u64), Carried>;
+
+/// What became of the holds dropped by [`SonosEventManager::release_device()`]
+#[derive(Debug)]
+enum Carried {
+    /// Still outstanding, waiting for the device to come back
+    Held(HashMap<Service, usize>),
+    /// Moved by [`SonosEventManager::restore_device()`] to this addr and
+    /// generation
+    Restored { addr: SocketAddr, generation: u64 },
+}
+
 /// Sync-first event manager for Sonos devices
 ///
 /// Provides a fully synchronous API while managing async event subscriptions
@@ -118,8 +144,8 @@ impl Drop for WatchGuard {
 /// manager.add_devices(devices)?;
 ///
 /// // Subscribe to events (sync)
-/// let ip: std::net::IpAddr = "192.168.1.100".parse()?;
-/// manager.ensure_service_subscribed(ip, Service::RenderingControl)?;
+/// let addr: std::net::SocketAddr = "192.168.1.100:1400".parse()?;
+/// manager.ensure_service_subscribed(addr, Service::RenderingControl)?;
 ///
 /// // Iterate over events (blocking)
 /// for event in manager.iter() {
@@ -131,20 +157,38 @@ pub struct SonosEventManager {
     command_tx: tokio_mpsc::UnboundedSender<Command>,
 
     /// Receive events from background worker
-    event_rx: Arc<Mutex<mpsc::Receiver<EnrichedEvent>>>,
+    events: EventManagerIterator,
+
+    /// Disk-backed event queue, when `BrokerConfig::spillover` is set
+    spillover: Option<Arc<SpilloverQueue>>,
 
     /// Device info cache (sync access)
-    devices: Arc<RwLock<HashMap<IpAddr, Device>>>,
+    devices: Arc<RwLock<HashMap<SocketAddr, Device>>>,
 
     /// Service subscription ref counts (sync access)
-    service_refs: Arc<RwLock<HashMap<(IpAddr, Service), usize>>>,
+    service_refs: Arc<RwLock<HashMap<(SocketAddr, Service), usize>>>,
 
     /// Pending grace-period timers: cancelled via AtomicBool when re-acquired
-    pending_unsubscribes: parking_lot::Mutex<HashMap<(IpAddr, Service), Arc<AtomicBool>>>,
+    pending_unsubscribes: Arc<parking_lot::Mutex<PendingUnsubscribes>>,
+
+    /// Times each device was released; guards from an earlier generation
+    /// no longer hold a ref count. Locked after `service_refs`.
+    generations: parking_lot::Mutex<HashMap<SocketAddr, u64>>,
+
+    /// Holds outstanding when a device was released, until its guards drop
+    /// or [`restore_device()`](Self::restore_device) moves them. Locked after
+    /// `generations`.
+    carried: parking_lot::Mutex<CarriedHolds>,
 
     /// Watch registry for managing the watched-property set (set once)
     watch_registry: OnceLock<Arc<dyn WatchRegistry>>,
 
+    /// Set by [`drain()`](Self::drain); refuses new subscriptions
+    draining: AtomicBool,
+
+    /// Times the grace period (`BrokerConfig::clock`)
+    clock: SharedClock,
+
     /// Background worker handle (kept alive)
     _worker: JoinHandle<()>,
 }
@@ -163,18 +207,41 @@ impl SonosEventManager {
     pub fn with_config(config: BrokerConfig) -> Result<Self> {
         // Create channels for command/event communication
         let (command_tx, command_rx) = tokio_mpsc::unbounded_channel();
-        let (event_tx, event_rx) = mpsc::channel();
+        let spillover = config
+            .spillover
+            .clone()
+            .map(|spillover| Arc::new(SpilloverQueue::new(spillover)));
+        let (event_tx, events) = match &spillover {
+            Some(queue) => (
+                EventSink::Spillover(Arc::clone(queue)),
+                EventManagerIterator::spillover(Arc::clone(queue)),
+            ),
+            None => {
+                let (event_tx, event_rx) = mpsc::channel();
+                (
+                    EventSink::Channel(event_tx),
+                    EventManagerIterator::new(Arc::new(Mutex::new(event_rx))),
+                )
+            }
+        };
+
+        let clock = Arc::clone(&config.clock);
 
         // Spawn background worker with its own tokio runtime
         let worker = spawn_event_worker(config, command_rx, event_tx);
 
         Ok(Self {
             command_tx,
-            event_rx: Arc::new(Mutex::new(event_rx)),
+            events,
+            spillover,
             devices: Arc::new(RwLock::new(HashMap::new())),
             service_refs: Arc::new(RwLock::new(HashMap::new())),
-            pending_unsubscribes: parking_lot::Mutex::new(HashMap::new()),
+            pending_unsubscribes: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            generations: parking_lot::Mutex::new(HashMap::new()),
+            carried: parking_lot::Mutex::new(HashMap::new()),
             watch_registry: OnceLock::new(),
+            draining: AtomicBool::new(false),
+            clock,
             _worker: worker,
         })
     }
@@ -194,7 +261,7 @@ impl SonosEventManager {
     ///
     /// Increments the service ref count. If this is the first reference (and no
     /// grace period is pending), sends a Subscribe command to the worker. If a
-    /// grace period is active for this (ip, service), cancels it instead.
+    /// grace period is active for this (addr, service), cancels it instead.
     ///
     /// Also registers the (speaker_id, key) pair in the WatchRegistry so that
     /// change events are forwarded for this property.
@@ -202,72 +269,78 @@ impl SonosEventManager {
         self: &Arc<Self>,
         speaker_id: &SpeakerId,
         property_key: &'static str,
-        ip: IpAddr,
+        addr: SocketAddr,
         service: Service,
     ) -> Result<WatchGuard> {
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(EventManagerError::ShuttingDown);
+        }
+
         // 1. Register in watched set via WatchRegistry
         if let Some(registry) = self.watch_registry.get() {
             registry.register_watch(speaker_id, property_key, service);
         }
 
         // 2. Increment ref count + check if we need to subscribe
-        let should_subscribe = {
+        let (should_subscribe, generation) = {
             let mut refs = self.service_refs.write();
-            let count = refs.entry((ip, service)).or_insert(0);
+            let generation = self.generation(addr);
+            let count = refs.entry((addr, service)).or_insert(0);
             let was_zero = *count == 0;
             *count += 1;
 
             tracing::debug!(
                 "acquire_watch: ref count for {}:{:?}: {} -> {}",
-                ip,
+                addr,
                 service,
                 if was_zero { 0 } else { *count - 1 },
                 *count
             );
 
-            was_zero
+            (was_zero, generation)
         };
 
         if should_subscribe {
-            // 3. Check for pending grace period to cancel
-            let cancelled = self
-                .pending_unsubscribes
-                .lock()
-                .remove(&(ip, service))
-                .map(|flag| {
-                    flag.store(true, Ordering::SeqCst);
-                    true
-                })
-                .unwrap_or(false);
-
-            if cancelled {
-                tracing::debug!(
-                    "acquire_watch: cancelled grace period for {}:{:?}",
-                    ip,
-                    service
-                );
-            } else {
-                // No pending grace period — actually subscribe
-                tracing::debug!(
-                    "acquire_watch: sending Subscribe command for {}:{:?}",
-                    ip,
-                    service
-                );
-                self.command_tx
-                    .send(Command::Subscribe { ip, service })
-                    .map_err(|_| EventManagerError::WorkerDisconnected)?;
-            }
+            self.resume_subscription(addr, service)?;
         }
 
         Ok(WatchGuard {
             event_manager: Arc::clone(self),
             speaker_id: speaker_id.clone(),
             property_key,
-            ip,
+            addr,
             service,
+            generation,
         })
     }
 
+    /// Take up a subscription whose ref count just left zero
+    ///
+    /// Cancels a pending grace period if there is one, otherwise sends a
+    /// Subscribe command to the worker.
+    fn resume_subscription(&self, addr: SocketAddr, service: Service) -> Result<()> {
+        let cancelled = self
+            .pending_unsubscribes
+            .lock()
+            .remove(&(addr, service))
+            .map(|flag| {
+                flag.store(true, Ordering::SeqCst);
+                true
+            })
+            .unwrap_or(false);
+
+        if cancelled {
+            tracing::debug!("cancelled grace period for {}:{:?}", addr, service);
+        } else {
+            // No pending grace period — actually subscribe
+            tracing::debug!("sending Subscribe command for {}:{:?}", addr, service);
+            self.command_tx
+                .send(Command::Subscribe { addr, service })
+                .map_err(|_| EventManagerError::WorkerDisconnected)?;
+        }
+        Ok(())
+    }
+
     /// Release a watch (called from WatchGuard::Drop). Must never panic.
     ///
     /// Decrements the service ref count. If it hits zero, starts a grace period:
@@ -277,60 +350,86 @@ impl SonosEventManager {
         &self,
         _speaker_id: &SpeakerId,
         _property_key: &'static str,
-        ip: IpAddr,
+        addr: SocketAddr,
         service: Service,
+        generation: u64,
     ) {
-        let should_start_grace = {
+        let (should_start_grace, addr) = {
             let mut refs = self.service_refs.write();
+            let Some(holder) = self.current_holder(addr, generation, service) else {
+                tracing::debug!(
+                    "release_watch: {}:{:?} was released with its device",
+                    addr,
+                    service
+                );
+                return;
+            };
+            let addr = holder;
 
-            if let Some(count) = refs.get_mut(&(ip, service)) {
+            let should_start_grace = if let Some(count) = refs.get_mut(&(addr, service)) {
                 *count = count.saturating_sub(1);
 
                 tracing::debug!(
                     "release_watch: ref count for {}:{:?}: {} -> {}",
-                    ip,
+                    addr,
                     service,
                     *count + 1,
                     *count
                 );
 
                 if *count == 0 {
-                    refs.remove(&(ip, service));
+                    refs.remove(&(addr, service));
                     true
                 } else {
                     false
                 }
             } else {
-                tracing::warn!("release_watch: no ref count for {}:{:?}", ip, service);
+                tracing::warn!("release_watch: no ref count for {}:{:?}", addr, service);
                 false
-            }
+            };
+            (should_start_grace, addr)
         };
 
         if should_start_grace {
             let cancelled = Arc::new(AtomicBool::new(false));
             self.pending_unsubscribes
                 .lock()
-                .insert((ip, service), Arc::clone(&cancelled));
+                .insert((addr, service), Arc::clone(&cancelled));
 
             let tx = self.command_tx.clone();
             let registry = self.watch_registry.get().cloned();
+            let pending = Arc::clone(&self.pending_unsubscribes);
+            let clock = Arc::clone(&self.clock);
 
             std::thread::spawn(move || {
-                std::thread::sleep(GRACE_PERIOD);
+                clock.sleep(GRACE_PERIOD);
+
+                // Clear our timer so the next acquire subscribes again
+                // instead of "cancelling" a grace period that already ran
+                let expired = {
+                    let mut pending = pending.lock();
+                    let ours = pending
+                        .get(&(addr, service))
+                        .is_some_and(|flag| Arc::ptr_eq(flag, &cancelled));
+                    if ours {
+                        pending.remove(&(addr, service));
+                    }
+                    ours
+                };
 
-                if !cancelled.load(Ordering::SeqCst) {
+                if expired && !cancelled.load(Ordering::SeqCst) {
                     tracing::debug!(
                         "Grace period expired for {}:{:?}, unsubscribing",
-                        ip,
+                        addr,
                         service
                     );
 
                     // Unsubscribe from UPnP service
-                    let _ = tx.send(Command::Unsubscribe { ip, service });
+                    let _ = tx.send(Command::Unsubscribe { addr, service });
 
                     // Clean up watched set
                     if let Some(registry) = registry {
-                        registry.unregister_watches_for_service(ip, service);
+                        registry.unregister_watches_for_service(addr, service);
                     }
                 }
             });
@@ -343,31 +442,197 @@ impl SonosEventManager {
 
     /// Add discovered devices to the manager (sync)
     ///
-    /// Stores device information for later lookup. Does not automatically
-    /// subscribe to any services.
+    /// Stores device information for later lookup and tells the broker
+    /// each device's UUID, which its stable subscription IDs are keyed by.
+    /// Does not automatically subscribe to any services.
     pub fn add_devices(&self, devices: Vec<Device>) -> Result<()> {
         let mut device_map = self.devices.write();
 
         for device in devices {
-            let ip: IpAddr = device
+            let ip = device
                 .ip_address
                 .parse()
                 .map_err(|_| EventManagerError::InvalidIpAddress(device.ip_address.clone()))?;
+            let addr = SocketAddr::new(ip, device.port);
 
-            device_map.insert(ip, device);
+            // Before any subscription to it, so it gets its stable IDs
+            self.command_tx
+                .send(Command::Identify {
+                    addr,
+                    id: device.id.clone(),
+                })
+                .map_err(|_| EventManagerError::WorkerDisconnected)?;
+            device_map.insert(addr, device);
         }
 
         Ok(())
     }
 
+    /// Drop every subscription to a device, whatever holds it
+    ///
+    /// For a speaker removed from the system: each service with a ref count
+    /// or a pending grace period is unsubscribed now and its watched
+    /// properties unregistered. Guards acquired before the release no
+    /// longer count, so dropping them later is a no-op, unless
+    /// [`restore_device()`](Self::restore_device) takes their holds up
+    /// again. The device is also forgotten. Returns the services
+    /// unsubscribed.
+    pub fn release_device(&self, addr: SocketAddr) -> Result<Vec<Service>> {
+        let mut services: Vec<Service> = {
+            let mut refs = self.service_refs.write();
+            let released = {
+                let mut generations = self.generations.lock();
+                let generation = generations.entry(addr).or_insert(0);
+                *generation += 1;
+                *generation - 1
+            };
+            let counts: HashMap<Service, usize> = refs
+                .iter()
+                .filter(|((a, _), _)| *a == addr)
+                .map(|((_, service), count)| (*service, *count))
+                .collect();
+            refs.retain(|(a, _), _| *a != addr);
+            let held = counts.keys().copied().collect();
+            self.carried
+                .lock()
+                .insert((addr, released), Carried::Held(counts));
+            held
+        };
+        self.pending_unsubscribes
+            .lock()
+            .retain(|(a, service), cancelled| {
+                if *a != addr {
+                    return true;
+                }
+                cancelled.store(true, Ordering::SeqCst);
+                services.push(*service);
+                false
+            });
+        self.devices.write().remove(&addr);
+
+        tracing::debug!("release_device: unsubscribing {} from {:?}", addr, services);
+        let registry = self.watch_registry.get();
+        for service in &services {
+            self.command_tx
+                .send(Command::Unsubscribe {
+                    addr,
+                    service: *service,
+                })
+                .map_err(|_| EventManagerError::WorkerDisconnected)?;
+            if let Some(registry) = registry {
+                registry.unregister_watches_for_service(addr, *service);
+            }
+        }
+        Ok(services)
+    }
+
+    /// Move the holds `old_addr` had when released onto `new_addr`
+    ///
+    /// For a removed speaker that came back, possibly at a new address:
+    /// every ref count [`release_device()`](Self::release_device) dropped
+    /// from `old_addr` is added to `new_addr`, and services that had none
+    /// there are subscribed again. Guards acquired before the release count
+    /// once more and release at `new_addr` when dropped. Returns the
+    /// services restored; empty if `old_addr` was never released or was
+    /// already restored.
+    pub fn restore_device(
+        &self,
+        old_addr: SocketAddr,
+        new_addr: SocketAddr,
+    ) -> Result<Vec<Service>> {
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(EventManagerError::ShuttingDown);
+        }
+
+        let (restored, resubscribe) = {
+            let mut refs = self.service_refs.write();
+            let Some(released) = self.generation(old_addr).checked_sub(1) else {
+                return Ok(Vec::new());
+            };
+            let generation = self.generation(new_addr);
+            let mut carried = self.carried.lock();
+            let Some(Carried::Held(counts)) = carried.get(&(old_addr, released)) else {
+                return Ok(Vec::new());
+            };
+            let counts = counts.clone();
+            carried.insert(
+                (old_addr, released),
+                Carried::Restored {
+                    addr: new_addr,
+                    generation,
+                },
+            );
+
+            let mut restored = Vec::new();
+            let mut resubscribe = Vec::new();
+            for (service, held) in counts.into_iter().filter(|(_, held)| *held > 0) {
+                let count = refs.entry((new_addr, service)).or_insert(0);
+                if *count == 0 {
+                    resubscribe.push(service);
+                }
+                *count += held;
+                restored.push(service);
+            }
+            (restored, resubscribe)
+        };
+
+        tracing::debug!(
+            "restore_device: {} -> {}, restored {:?}",
+            old_addr,
+            new_addr,
+            restored
+        );
+        for service in resubscribe {
+            self.resume_subscription(new_addr, service)?;
+        }
+        Ok(restored)
+    }
+
+    /// Current release generation of a device
+    fn generation(&self, addr: SocketAddr) -> u64 {
+        self.generations.lock().get(&addr).copied().unwrap_or(0)
+    }
+
+    /// Where a hold acquired at `addr` in `generation` counts now
+    ///
+    /// `None` if it no longer counts anywhere. A hold still carried from a
+    /// released device is dropped from its carried count here.
+    fn current_holder(
+        &self,
+        mut addr: SocketAddr,
+        mut generation: u64,
+        service: Service,
+    ) -> Option<SocketAddr> {
+        loop {
+            if generation == self.generation(addr) {
+                return Some(addr);
+            }
+            match self.carried.lock().get_mut(&(addr, generation))? {
+                Carried::Held(counts) => {
+                    if let Some(count) = counts.get_mut(&service) {
+                        *count = count.saturating_sub(1);
+                    }
+                    return None;
+                }
+                Carried::Restored {
+                    addr: to,
+                    generation: at,
+                } => {
+                    addr = *to;
+                    generation = *at;
+                }
+            }
+        }
+    }
+
     /// Get all available devices (sync)
     pub fn devices(&self) -> Vec<Device> {
         self.devices.read().values().cloned().collect()
     }
 
-    /// Get a specific device by IP address (sync)
-    pub fn device_by_ip(&self, ip: IpAddr) -> Option<Device> {
-        self.devices.read().get(&ip).cloned()
+    /// Get a specific device by address (sync)
+    pub fn device_by_addr(&self, addr: SocketAddr) -> Option<Device> {
+        self.devices.read().get(&addr).cloned()
     }
 
     // ========================================================================
@@ -376,19 +641,27 @@ impl SonosEventManager {
 
     /// Ensure a service is subscribed for a device (sync, ref-counted)
     ///
-    /// Increments the reference count for the (device_ip, service) pair.
+    /// Increments the reference count for the (device_addr, service) pair.
     /// If this is the first reference, triggers a subscription via the background worker.
-    pub fn ensure_service_subscribed(&self, device_ip: IpAddr, service: Service) -> Result<()> {
+    pub fn ensure_service_subscribed(
+        &self,
+        device_addr: SocketAddr,
+        service: Service,
+    ) -> Result<()> {
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(EventManagerError::ShuttingDown);
+        }
+
         let should_subscribe = {
             let mut refs = self.service_refs.write();
 
-            let count = refs.entry((device_ip, service)).or_insert(0);
+            let count = refs.entry((device_addr, service)).or_insert(0);
             let was_zero = *count == 0;
             *count += 1;
 
             tracing::debug!(
                 "Service reference count for {}:{:?}: {} -> {}",
-                device_ip,
+                device_addr,
                 service,
                 if was_zero { 0 } else { *count - 1 },
                 *count
@@ -400,7 +673,7 @@ impl SonosEventManager {
         if should_subscribe {
             self.command_tx
                 .send(Command::Subscribe {
-                    ip: device_ip,
+                    addr: device_addr,
                     service,
                 })
                 .map_err(|_| EventManagerError::WorkerDisconnected)?;
@@ -411,26 +684,30 @@ impl SonosEventManager {
 
     /// Release a service subscription for a device (sync, ref-counted)
     ///
-    /// Decrements the reference count for the (device_ip, service) pair.
+    /// Decrements the reference count for the (device_addr, service) pair.
     /// If this reaches zero, triggers an unsubscription via the background worker.
-    pub fn release_service_subscription(&self, device_ip: IpAddr, service: Service) -> Result<()> {
+    pub fn release_service_subscription(
+        &self,
+        device_addr: SocketAddr,
+        service: Service,
+    ) -> Result<()> {
         let should_unsubscribe = {
             let mut refs = self.service_refs.write();
 
-            if let Some(count) = refs.get_mut(&(device_ip, service)) {
+            if let Some(count) = refs.get_mut(&(device_addr, service)) {
                 let old_count = *count;
                 *count = count.saturating_sub(1);
 
                 tracing::debug!(
                     "Service reference count for {}:{:?}: {} -> {}",
-                    device_ip,
+                    device_addr,
                     service,
                     old_count,
                     *count
                 );
 
                 if *count == 0 {
-                    refs.remove(&(device_ip, service));
+                    refs.remove(&(device_addr, service));
                     true
                 } else {
                     false
@@ -438,7 +715,7 @@ impl SonosEventManager {
             } else {
                 tracing::warn!(
                     "Attempted to release subscription for {}:{:?} but no references found",
-                    device_ip,
+                    device_addr,
                     service
                 );
                 false
@@ -448,7 +725,7 @@ impl SonosEventManager {
         if should_unsubscribe {
             self.command_tx
                 .send(Command::Unsubscribe {
-                    ip: device_ip,
+                    addr: device_addr,
                     service,
                 })
                 .map_err(|_| EventManagerError::WorkerDisconnected)?;
@@ -481,7 +758,13 @@ impl SonosEventManager {
     /// }
     /// ```
     pub fn iter(&self) -> EventManagerIterator {
-        EventManagerIterator::new(Arc::clone(&self.event_rx))
+        self.events.clone()
+    }
+
+    /// Spilled/drained/collapsed counts of the disk-backed event queue, or
+    /// `None` when `BrokerConfig::spillover` is not set
+    pub fn spillover_metrics(&self) -> Option<SpilloverMetrics> {
+        self.spillover.as_ref().map(|queue| queue.metrics())
     }
 
     // ========================================================================
@@ -489,40 +772,124 @@ impl SonosEventManager {
     // ========================================================================
 
     /// Get current service subscription statistics (sync)
-    pub fn service_subscription_stats(&self) -> HashMap<(IpAddr, Service), usize> {
+    pub fn service_subscription_stats(&self) -> HashMap<(SocketAddr, Service), usize> {
         self.service_refs.read().clone()
     }
 
     /// Check if a service is currently subscribed for a device (sync)
-    pub fn is_service_subscribed(&self, device_ip: IpAddr, service: Service) -> bool {
+    pub fn is_service_subscribed(&self, device_addr: SocketAddr, service: Service) -> bool {
         self.service_refs
             .read()
-            .get(&(device_ip, service))
+            .get(&(device_addr, service))
             .is_some_and(|&c| c > 0)
     }
 
     /// Get the current reference count for a service subscription
-    pub fn service_ref_count(&self, device_ip: IpAddr, service: Service) -> usize {
+    pub fn service_ref_count(&self, device_addr: SocketAddr, service: Service) -> usize {
         self.service_refs
             .read()
-            .get(&(device_ip, service))
+            .get(&(device_addr, service))
             .copied()
             .unwrap_or(0)
     }
 
+    // ========================================================================
+    // Suspend / resume
+    // ========================================================================
+
+    /// Suspend all event activity (renewals, polling, optionally subscriptions)
+    ///
+    /// Blocks until the worker has applied it. Safe to call from any thread,
+    /// e.g. an OS sleep hook. Returns `false` if already suspended.
+    pub fn suspend(&self, policy: SuspendPolicy) -> Result<bool> {
+        self.round_trip(|reply| Command::Suspend { policy, reply })
+    }
+
+    /// Re-establish every subscription after [`suspend()`](Self::suspend)
+    ///
+    /// Blocks until each registration is renewed, re-subscribed or polling.
+    /// Returns `false` if not suspended.
+    pub fn resume(&self) -> Result<bool> {
+        self.round_trip(|reply| Command::Resume { reply })
+    }
+
+    /// Report a device's UPnP boot sequence (`BOOTID.UPNP.ORG`, or
+    /// `BootSeq` in its topology), e.g. from an SSDP response
+    ///
+    /// A change from the last value seen means the device rebooted: it forgot
+    /// its subscriptions and may hand their SIDs to new ones, so they are all
+    /// replaced. Topology events report boot sequences on their own; call
+    /// this for other sources. Returns `true` when a reboot was detected.
+    pub fn observe_boot_seq(&self, addr: SocketAddr, boot_seq: u32) -> Result<bool> {
+        self.round_trip(|reply| Command::BootSeq {
+            addr,
+            boot_seq,
+            reply,
+        })
+    }
+
+    /// Send a command carrying a reply channel and wait for the answer
+    fn round_trip(&self, command: impl FnOnce(mpsc::Sender<bool>) -> Command) -> Result<bool> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.command_tx
+            .send(command(reply_tx))
+            .map_err(|_| EventManagerError::WorkerDisconnected)?;
+        reply_rx
+            .recv_timeout(SUSPEND_REPLY_TIMEOUT)
+            .map_err(|e| match e {
+                mpsc::RecvTimeoutError::Timeout => EventManagerError::WorkerTimeout,
+                mpsc::RecvTimeoutError::Disconnected => EventManagerError::WorkerDisconnected,
+            })
+    }
+
+    /// Drain the event pipeline ahead of shutdown, returning how many events
+    /// the worker forwarded while draining
+    ///
+    /// In order: new watches and subscriptions are refused, every
+    /// subscription is cancelled so devices stop sending, and each event
+    /// already received is forwarded to [`iter()`](Self::iter). The worker
+    /// then shuts down, ending the iterator once it's read to the end. Steps
+    /// still running at `deadline` are cut short (unsubscribed devices just
+    /// let their subscriptions expire).
+    pub fn drain(&self, deadline: Instant) -> Result<usize> {
+        self.draining.store(true, Ordering::SeqCst);
+        for (_, flag) in self.pending_unsubscribes.lock().drain() {
+            flag.store(true, Ordering::SeqCst);
+        }
+
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.command_tx
+            .send(Command::Drain {
+                deadline,
+                reply: reply_tx,
+            })
+            .map_err(|_| EventManagerError::WorkerDisconnected)?;
+        reply_rx
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()) + DRAIN_REPLY_SLACK)
+            .map_err(|e| match e {
+                mpsc::RecvTimeoutError::Timeout => EventManagerError::WorkerTimeout,
+                mpsc::RecvTimeoutError::Disconnected => EventManagerError::WorkerDisconnected,
+            })
+    }
+
+    /// Whether [`drain()`](Self::drain) has been called
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
     /// Shutdown the background worker
     ///
     /// Called automatically on drop, but can be called manually for graceful shutdown.
     pub fn shutdown(&self) {
         // Cancel all pending grace timers
         let pending: Vec<_> = self.pending_unsubscribes.lock().drain().collect();
-        for ((ip, service), flag) in pending {
+        for ((addr, service), flag) in pending {
             flag.store(true, Ordering::SeqCst);
             // Send unsubscribe immediately (no grace period on shutdown)
-            let _ = self.command_tx.send(Command::Unsubscribe { ip, service });
+            let _ = self.command_tx.send(Command::Unsubscribe { addr, service });
             // Clean up watched set
             if let Some(registry) = self.watch_registry.get() {
-                registry.unregister_watches_for_service(ip, service);
+                registry.unregister_watches_for_service(addr, service);
             }
         }
 
@@ -581,7 +948,7 @@ mod tests {
             self.register_count.fetch_add(1, Ordering::SeqCst);
         }
 
-        fn unregister_watches_for_service(&self, _ip: IpAddr, _service: Service) {
+        fn unregister_watches_for_service(&self, _addr: SocketAddr, _service: Service) {
             self.unregister_count.fetch_add(1, Ordering::SeqCst);
         }
     }
@@ -601,6 +968,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
             room_name: "Living Room".to_string(),
         }];
 
@@ -611,8 +980,8 @@ mod tests {
         assert_eq!(stored_devices.len(), 1);
 
         // Check specific device lookup
-        let device_ip: IpAddr = "192.168.1.100".parse().unwrap();
-        let device = manager.device_by_ip(device_ip).unwrap();
+        let device_addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
+        let device = manager.device_by_addr(device_addr).unwrap();
         assert_eq!(device.name, "Living Room");
     }
 
@@ -620,39 +989,39 @@ mod tests {
     fn test_reference_counting() {
         let config = BrokerConfig::default().with_callback_ports(4200, 4300);
         let manager = SonosEventManager::with_config(config).unwrap();
-        let device_ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let device_addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let service = Service::RenderingControl;
 
         // Initially not subscribed
-        assert!(!manager.is_service_subscribed(device_ip, service));
-        assert_eq!(manager.service_ref_count(device_ip, service), 0);
+        assert!(!manager.is_service_subscribed(device_addr, service));
+        assert_eq!(manager.service_ref_count(device_addr, service), 0);
 
         // First subscription
         manager
-            .ensure_service_subscribed(device_ip, service)
+            .ensure_service_subscribed(device_addr, service)
             .unwrap();
-        assert!(manager.is_service_subscribed(device_ip, service));
-        assert_eq!(manager.service_ref_count(device_ip, service), 1);
+        assert!(manager.is_service_subscribed(device_addr, service));
+        assert_eq!(manager.service_ref_count(device_addr, service), 1);
 
         // Second subscription (increments ref count)
         manager
-            .ensure_service_subscribed(device_ip, service)
+            .ensure_service_subscribed(device_addr, service)
             .unwrap();
-        assert_eq!(manager.service_ref_count(device_ip, service), 2);
+        assert_eq!(manager.service_ref_count(device_addr, service), 2);
 
         // Release one subscription
         manager
-            .release_service_subscription(device_ip, service)
+            .release_service_subscription(device_addr, service)
             .unwrap();
-        assert_eq!(manager.service_ref_count(device_ip, service), 1);
-        assert!(manager.is_service_subscribed(device_ip, service));
+        assert_eq!(manager.service_ref_count(device_addr, service), 1);
+        assert!(manager.is_service_subscribed(device_addr, service));
 
         // Release last subscription
         manager
-            .release_service_subscription(device_ip, service)
+            .release_service_subscription(device_addr, service)
             .unwrap();
-        assert_eq!(manager.service_ref_count(device_ip, service), 0);
-        assert!(!manager.is_service_subscribed(device_ip, service));
+        assert_eq!(manager.service_ref_count(device_addr, service), 0);
+        assert!(!manager.is_service_subscribed(device_addr, service));
     }
 
     #[test]
@@ -669,28 +1038,40 @@ mod tests {
     fn test_acquire_release_watch_ref_counting() {
         let config = BrokerConfig::default().with_callback_ports(4400, 4500);
         let manager = Arc::new(SonosEventManager::with_config(config).unwrap());
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let speaker_id = SpeakerId::new("RINCON_123");
 
         // Acquire first watch
         let guard1 = manager
-            .acquire_watch(&speaker_id, "volume", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
             .unwrap();
-        assert_eq!(manager.service_ref_count(ip, Service::RenderingControl), 1);
+        assert_eq!(
+            manager.service_ref_count(addr, Service::RenderingControl),
+            1
+        );
 
         // Acquire second watch (same service)
         let guard2 = manager
-            .acquire_watch(&speaker_id, "mute", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "mute", addr, Service::RenderingControl)
             .unwrap();
-        assert_eq!(manager.service_ref_count(ip, Service::RenderingControl), 2);
+        assert_eq!(
+            manager.service_ref_count(addr, Service::RenderingControl),
+            2
+        );
 
         // Drop first guard
         drop(guard1);
-        assert_eq!(manager.service_ref_count(ip, Service::RenderingControl), 1);
+        assert_eq!(
+            manager.service_ref_count(addr, Service::RenderingControl),
+            1
+        );
 
         // Drop second guard — ref count hits 0, grace period starts
         drop(guard2);
-        assert_eq!(manager.service_ref_count(ip, Service::RenderingControl), 0);
+        assert_eq!(
+            manager.service_ref_count(addr, Service::RenderingControl),
+            0
+        );
     }
 
     #[test]
@@ -700,12 +1081,12 @@ mod tests {
         let registry = MockRegistry::new();
         manager.set_watch_registry(registry.clone());
 
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let speaker_id = SpeakerId::new("RINCON_123");
 
         // Acquire and drop — starts grace period
         let guard = manager
-            .acquire_watch(&speaker_id, "volume", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
             .unwrap();
         drop(guard);
 
@@ -713,18 +1094,18 @@ mod tests {
         assert!(manager
             .pending_unsubscribes
             .lock()
-            .contains_key(&(ip, Service::RenderingControl)));
+            .contains_key(&(addr, Service::RenderingControl)));
 
         // Re-acquire within grace period — should cancel the timer
         let _guard2 = manager
-            .acquire_watch(&speaker_id, "volume", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
             .unwrap();
 
         // Pending should be cleared
         assert!(!manager
             .pending_unsubscribes
             .lock()
-            .contains_key(&(ip, Service::RenderingControl)));
+            .contains_key(&(addr, Service::RenderingControl)));
 
         // Registry should NOT have unregistered (grace period was cancelled)
         assert_eq!(registry.unregisters(), 0);
@@ -737,12 +1118,12 @@ mod tests {
         let registry = MockRegistry::new();
         manager.set_watch_registry(registry.clone());
 
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let speaker_id = SpeakerId::new("RINCON_123");
 
         // Acquire and drop
         let guard = manager
-            .acquire_watch(&speaker_id, "volume", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
             .unwrap();
         assert_eq!(registry.registers(), 1);
         drop(guard);
@@ -754,6 +1135,34 @@ mod tests {
         assert_eq!(registry.unregisters(), 1);
     }
 
+    #[test]
+    fn test_reacquire_after_grace_period_subscribes_again() {
+        let config = BrokerConfig::default().with_callback_ports(5000, 5100);
+        let manager = Arc::new(SonosEventManager::with_config(config).unwrap());
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
+        let speaker_id = SpeakerId::new("RINCON_123");
+
+        drop(
+            manager
+                .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
+                .unwrap(),
+        );
+        std::thread::sleep(Duration::from_millis(100));
+
+        // The expired timer is gone, so a new watch is a fresh subscription
+        assert!(manager.pending_unsubscribes.lock().is_empty());
+        let _guard = manager
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
+            .unwrap();
+        assert_eq!(
+            manager
+                .service_refs
+                .read()
+                .get(&(addr, Service::RenderingControl)),
+            Some(&1)
+        );
+    }
+
     #[test]
     fn test_watch_registry_integration() {
         let config = BrokerConfig::default().with_callback_ports(4700, 4800);
@@ -761,12 +1170,12 @@ mod tests {
         let registry = MockRegistry::new();
         manager.set_watch_registry(registry.clone());
 
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let speaker_id = SpeakerId::new("RINCON_123");
 
         // Acquire registers in watched set
         let _guard = manager
-            .acquire_watch(&speaker_id, "volume", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
             .unwrap();
         assert_eq!(registry.registers(), 1);
         assert_eq!(registry.unregisters(), 0);
@@ -776,11 +1185,11 @@ mod tests {
     fn test_guard_drop_with_disconnected_worker() {
         let config = BrokerConfig::default().with_callback_ports(4800, 4900);
         let manager = Arc::new(SonosEventManager::with_config(config).unwrap());
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let speaker_id = SpeakerId::new("RINCON_123");
 
         let guard = manager
-            .acquire_watch(&speaker_id, "volume", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
             .unwrap();
 
         // Shutdown the worker
@@ -795,15 +1204,15 @@ mod tests {
         // Use a different port range to avoid conflicts with other concurrent tests
         let config = BrokerConfig::default().with_callback_ports(4000, 4100);
         let manager = Arc::new(SonosEventManager::with_config(config).unwrap());
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let speaker_id = SpeakerId::new("RINCON_123");
 
         // Acquire watches on different services
         let guard_rc = manager
-            .acquire_watch(&speaker_id, "volume", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
             .unwrap();
         let guard_av = manager
-            .acquire_watch(&speaker_id, "playback_state", ip, Service::AVTransport)
+            .acquire_watch(&speaker_id, "playback_state", addr, Service::AVTransport)
             .unwrap();
 
         // Drop RC — starts grace period for RenderingControl only
@@ -811,14 +1220,14 @@ mod tests {
         assert!(manager
             .pending_unsubscribes
             .lock()
-            .contains_key(&(ip, Service::RenderingControl)));
+            .contains_key(&(addr, Service::RenderingControl)));
         assert!(!manager
             .pending_unsubscribes
             .lock()
-            .contains_key(&(ip, Service::AVTransport)));
+            .contains_key(&(addr, Service::AVTransport)));
 
         // AVTransport still has ref count 1
-        assert_eq!(manager.service_ref_count(ip, Service::AVTransport), 1);
+        assert_eq!(manager.service_ref_count(addr, Service::AVTransport), 1);
 
         drop(guard_av);
     }
@@ -830,12 +1239,12 @@ mod tests {
         let registry = MockRegistry::new();
         manager.set_watch_registry(registry.clone());
 
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let speaker_id = SpeakerId::new("RINCON_123");
 
         // Acquire and drop to start grace period
         let guard = manager
-            .acquire_watch(&speaker_id, "volume", ip, Service::RenderingControl)
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
             .unwrap();
         drop(guard);
 
@@ -843,7 +1252,7 @@ mod tests {
         assert!(manager
             .pending_unsubscribes
             .lock()
-            .contains_key(&(ip, Service::RenderingControl)));
+            .contains_key(&(addr, Service::RenderingControl)));
 
         // Shutdown should drain and cancel pending timers
         manager.shutdown();
@@ -851,4 +1260,100 @@ mod tests {
         // Pending should be cleared
         assert!(manager.pending_unsubscribes.lock().is_empty());
     }
+
+    #[test]
+    fn test_release_device_drops_refs_and_outstanding_guards() {
+        let config = BrokerConfig::default().with_callback_ports(5200, 5300);
+        let manager = Arc::new(SonosEventManager::with_config(config).unwrap());
+        let registry = MockRegistry::new();
+        manager.set_watch_registry(registry.clone());
+
+        let addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
+        let speaker_id = SpeakerId::new("RINCON_123");
+
+        let stale = manager
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
+            .unwrap();
+        // AVTransport is in its grace period when the device goes
+        drop(
+            manager
+                .acquire_watch(&speaker_id, "playback_state", addr, Service::AVTransport)
+                .unwrap(),
+        );
+
+        let mut released = manager.release_device(addr).unwrap();
+        released.sort_by_key(|service| format!("{service:?}"));
+        assert_eq!(released, [Service::AVTransport, Service::RenderingControl]);
+        assert_eq!(
+            manager.service_ref_count(addr, Service::RenderingControl),
+            0
+        );
+        assert!(manager.pending_unsubscribes.lock().is_empty());
+        assert_eq!(registry.unregisters(), 2);
+
+        // A watch taken after the release isn't undone by the stale guard
+        let _fresh = manager
+            .acquire_watch(&speaker_id, "volume", addr, Service::RenderingControl)
+            .unwrap();
+        drop(stale);
+        assert_eq!(
+            manager.service_ref_count(addr, Service::RenderingControl),
+            1
+        );
+    }
+
+    #[test]
+    fn test_restore_device_takes_up_outstanding_guards() {
+        let config = BrokerConfig::default().with_callback_ports(5300, 5400);
+        let manager = Arc::new(SonosEventManager::with_config(config).unwrap());
+        let old: SocketAddr = "192.168.1.100:1400".parse().unwrap();
+        let new: SocketAddr = "192.168.1.101:1400".parse().unwrap();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        let service = Service::RenderingControl;
+
+        let first = manager
+            .acquire_watch(&speaker_id, "volume", old, service)
+            .unwrap();
+        let second = manager
+            .acquire_watch(&speaker_id, "mute", old, service)
+            .unwrap();
+        manager.release_device(old).unwrap();
+        // One hold goes while the device is away
+        drop(first);
+
+        assert_eq!(manager.restore_device(old, new).unwrap(), [service]);
+        assert_eq!(manager.service_ref_count(new, service), 1);
+        assert!(manager.restore_device(old, new).unwrap().is_empty());
+
+        // The other releases where it was restored to
+        drop(second);
+        assert_eq!(manager.service_ref_count(new, service), 0);
+        assert!(manager
+            .pending_unsubscribes
+            .lock()
+            .contains_key(&(new, service)));
+    }
+
+    #[test]
+    fn test_spillover_is_opt_in_and_ends_with_worker() {
+        let config = BrokerConfig::default().with_callback_ports(5100, 5200);
+        let manager = SonosEventManager::with_config(config).unwrap();
+        assert_eq!(manager.spillover_metrics(), None);
+
+        let config = BrokerConfig::default()
+            .with_callback_ports(5100, 5200)
+            .with_spillover(sonos_stream::SpilloverConfig::default());
+        let manager = SonosEventManager::with_config(config).unwrap();
+        assert_eq!(
+            manager.spillover_metrics(),
+            Some(SpilloverMetrics::default())
+        );
+
+        // The stream ends once the worker drops its side of the queue
+        manager.shutdown();
+        assert!(manager
+            .iter()
+            .recv_timeout(Duration::from_secs(5))
+            .is_none());
+    }
 }
@@ -4,27 +4,78 @@
 //! while exposing a sync API to the parent SonosEventManager.
 
 use std::collections::HashMap;
-use std::net::IpAddr;
-use std::sync::mpsc;
+use std::net::SocketAddr;
+use std::sync::{mpsc, Arc};
 use std::thread::{self, JoinHandle};
+use std::time::Instant;
 
 use sonos_api::Service;
-use sonos_stream::events::EnrichedEvent;
+use sonos_stream::events::{EnrichedEvent, EventData, EventIterator};
 use sonos_stream::registry::RegistrationId;
-use sonos_stream::{BrokerConfig, EventBroker};
+use sonos_stream::{BrokerConfig, EventBroker, SpilloverQueue, SuspendPolicy};
 use tokio::sync::mpsc as tokio_mpsc;
 
 /// Commands sent from the sync SonosEventManager to the background worker
 #[derive(Debug)]
 pub enum Command {
     /// Subscribe to a service on a device
-    Subscribe { ip: IpAddr, service: Service },
+    Subscribe { addr: SocketAddr, service: Service },
     /// Unsubscribe from a service on a device
-    Unsubscribe { ip: IpAddr, service: Service },
+    Unsubscribe { addr: SocketAddr, service: Service },
+    /// The speaker with UUID `id` is at `addr`
+    Identify { addr: SocketAddr, id: String },
+    /// Suspend the broker; replies whether it was running
+    Suspend {
+        policy: SuspendPolicy,
+        reply: mpsc::Sender<bool>,
+    },
+    /// Resume the broker; replies whether it was suspended
+    Resume { reply: mpsc::Sender<bool> },
+    /// A device's boot sequence was observed; replies whether it means the
+    /// device rebooted
+    BootSeq {
+        addr: SocketAddr,
+        boot_seq: u32,
+        reply: mpsc::Sender<bool>,
+    },
+    /// Unsubscribe everything, forward the events already received, then
+    /// shut down; replies with the number forwarded
+    Drain {
+        deadline: Instant,
+        reply: mpsc::Sender<usize>,
+    },
     /// Shutdown the worker
     Shutdown,
 }
 
+/// Where the worker forwards broker events
+pub enum EventSink {
+    /// Unbounded in-memory channel (the default)
+    Channel(mpsc::Sender<EnrichedEvent>),
+    /// Disk-backed queue, when `BrokerConfig::spillover` is set
+    Spillover(Arc<SpilloverQueue>),
+}
+
+impl EventSink {
+    /// Forward an event; `false` once the consumer side is gone
+    fn send(&self, event: EnrichedEvent) -> bool {
+        match self {
+            EventSink::Channel(tx) => tx.send(event).is_ok(),
+            EventSink::Spillover(queue) => queue.push(event),
+        }
+    }
+}
+
+impl Drop for EventSink {
+    /// Mirror a dropped channel sender: the consumer sees the end of the
+    /// stream once it has received everything queued
+    fn drop(&mut self) {
+        if let EventSink::Spillover(queue) = self {
+            queue.close();
+        }
+    }
+}
+
 /// Spawns the background event worker thread
 ///
 /// The worker owns its own tokio runtime and manages:
@@ -34,7 +85,7 @@ pub enum Command {
 pub fn spawn_event_worker(
     config: BrokerConfig,
     command_rx: tokio_mpsc::UnboundedReceiver<Command>,
-    event_tx: mpsc::Sender<EnrichedEvent>,
+    event_tx: EventSink,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         // Create a new single-threaded tokio runtime for this worker
@@ -59,7 +110,7 @@ pub fn spawn_event_worker(
 async fn run_event_loop(
     config: BrokerConfig,
     mut command_rx: tokio_mpsc::UnboundedReceiver<Command>,
-    event_tx: mpsc::Sender<EnrichedEvent>,
+    event_tx: EventSink,
 ) {
     // Create EventBroker (async)
     let mut broker = match EventBroker::new(config).await {
@@ -79,8 +130,8 @@ async fn run_event_loop(
         }
     };
 
-    // Track registration IDs for each (ip, service) pair
-    let mut registration_ids: HashMap<(IpAddr, Service), RegistrationId> = HashMap::new();
+    // Track registration IDs for each (addr, service) pair
+    let mut registration_ids: HashMap<(SocketAddr, Service), RegistrationId> = HashMap::new();
 
     tracing::info!("Event worker started");
 
@@ -91,40 +142,72 @@ async fn run_event_loop(
             // Process commands first (deterministic priority)
             cmd = command_rx.recv() => {
                 match cmd {
-                    Some(Command::Subscribe { ip, service }) => {
-                        tracing::debug!("Worker: Subscribing to {}:{:?}", ip, service);
-                        match broker.register_speaker_service(ip, service).await {
+                    Some(Command::Subscribe { addr, service }) => {
+                        tracing::debug!("Worker: Subscribing to {}:{:?}", addr, service);
+                        match broker.register_speaker_service(addr, service).await {
                             Ok(result) => {
-                                registration_ids.insert((ip, service), result.registration_id);
+                                registration_ids.insert((addr, service), result.registration_id);
                                 tracing::debug!(
                                     "Registered speaker service {}:{:?} with ID {}",
-                                    ip, service, result.registration_id
+                                    addr, service, result.registration_id
                                 );
                             }
                             Err(e) => {
                                 tracing::warn!(
                                     "Failed to register speaker service {}:{:?}: {}",
-                                    ip, service, e
+                                    addr, service, e
                                 );
                             }
                         }
                     }
-                    Some(Command::Unsubscribe { ip, service }) => {
-                        tracing::debug!("Worker: Unsubscribing from {}:{:?}", ip, service);
-                        if let Some(reg_id) = registration_ids.remove(&(ip, service)) {
+                    Some(Command::Unsubscribe { addr, service }) => {
+                        tracing::debug!("Worker: Unsubscribing from {}:{:?}", addr, service);
+                        if let Some(reg_id) = registration_ids.remove(&(addr, service)) {
                             if let Err(e) = broker.unregister_speaker_service(reg_id).await {
                                 tracing::warn!(
                                     "Failed to unregister speaker service {}:{:?}: {}",
-                                    ip, service, e
+                                    addr, service, e
                                 );
                             }
                         } else {
                             tracing::warn!(
                                 "No registration ID found for {}:{:?}",
-                                ip, service
+                                addr, service
                             );
                         }
                     }
+                    Some(Command::Identify { addr, id }) => {
+                        broker.identify_speaker(addr, &id);
+                    }
+                    Some(Command::Suspend { policy, reply }) => {
+                        let changed = broker.suspend(policy).await.unwrap_or_else(|e| {
+                            tracing::warn!("Failed to suspend event broker: {}", e);
+                            false
+                        });
+                        let _ = reply.send(changed);
+                    }
+                    Some(Command::Resume { reply }) => {
+                        let changed = broker.resume().await.unwrap_or_else(|e| {
+                            tracing::warn!("Failed to resume event broker: {}", e);
+                            false
+                        });
+                        let _ = reply.send(changed);
+                    }
+                    Some(Command::BootSeq { addr, boot_seq, reply }) => {
+                        let _ = reply.send(broker.observe_boot_seq(addr, boot_seq).await);
+                    }
+                    Some(Command::Drain { deadline, reply }) => {
+                        tracing::info!("Worker draining for shutdown");
+                        let deadline = tokio::time::Instant::from_std(deadline);
+                        let forwarded =
+                            drain(&broker, &mut events, &mut registration_ids, &event_tx, deadline)
+                                .await;
+                        let _ = reply.send(forwarded);
+                        if let Err(e) = broker.shutdown().await {
+                            tracing::warn!("Failed to shut down event broker: {}", e);
+                        }
+                        return;
+                    }
                     Some(Command::Shutdown) => {
                         tracing::info!("Worker received shutdown command");
                         return;
@@ -140,7 +223,8 @@ async fn run_event_loop(
             event = events.next_async() => {
                 match event {
                     Some(e) => {
-                        if event_tx.send(e).is_err() {
+                        observe_topology(&broker, &e).await;
+                        if !event_tx.send(e) {
                             tracing::debug!("Event receiver dropped, shutting down worker");
                             break;
                         }
@@ -157,6 +241,73 @@ async fn run_event_loop(
     tracing::info!("Event worker shut down");
 }
 
+/// Pass a topology event to the broker, which moves group subscriptions
+/// whose coordinator changed, and the boot sequence of every speaker in it,
+/// which replaces a rebooted speaker's subscriptions
+async fn observe_topology(broker: &EventBroker, event: &EnrichedEvent) {
+    let EventData::ZoneGroupTopology(topology) = &event.event_data else {
+        return;
+    };
+    broker.observe_topology(topology).await;
+    let members = topology.zone_groups.iter().flat_map(|group| &group.members);
+    for member in members {
+        let ip = member
+            .location
+            .strip_prefix("http://")
+            .and_then(|rest| rest.split('/').next())
+            .and_then(|host| host.parse::<SocketAddr>().ok());
+        if let Some(addr) = ip {
+            broker.observe_boot_seq(addr, member.boot_seq).await;
+        }
+    }
+}
+
+/// The ordered part of a graceful shutdown, bounded by `deadline`
+///
+/// Notifications the callback server already acknowledged are processed
+/// first, while their SIDs still resolve. Then every registration is
+/// removed, so devices stop sending, and everything the broker has queued
+/// is forwarded. Returns how many events were forwarded.
+async fn drain(
+    broker: &EventBroker,
+    events: &mut EventIterator,
+    registration_ids: &mut HashMap<(SocketAddr, Service), RegistrationId>,
+    event_tx: &EventSink,
+    deadline: tokio::time::Instant,
+) -> usize {
+    if tokio::time::timeout_at(deadline, broker.flush_notifications())
+        .await
+        .is_err()
+    {
+        tracing::warn!("Drain deadline passed while flushing notifications");
+    }
+
+    for ((addr, service), reg_id) in registration_ids.drain() {
+        match tokio::time::timeout_at(deadline, broker.unregister_speaker_service(reg_id)).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::warn!(
+                "Failed to unregister speaker service {}:{:?}: {}",
+                addr,
+                service,
+                e
+            ),
+            Err(_) => {
+                tracing::warn!("Drain deadline passed, leaving remaining subscriptions to expire");
+                break;
+            }
+        }
+    }
+
+    let mut forwarded = 0;
+    while let Ok(Some(event)) = events.try_next() {
+        if !event_tx.send(event) {
+            break;
+        }
+        forwarded += 1;
+    }
+    forwarded
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,7 +315,7 @@ mod tests {
     #[test]
     fn test_command_debug() {
         let cmd = Command::Subscribe {
-            ip: "192.168.1.100".parse().unwrap(),
+            addr: "192.168.1.100:1400".parse().unwrap(),
             service: Service::RenderingControl,
         };
         assert!(format!("{cmd:?}").contains("Subscribe"));
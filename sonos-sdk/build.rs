@@ -0,0 +1,87 @@
+//! Captures the resolved versions of the sonos-sdk workspace crates for
+//! `sonos_sdk::build_info()`.
+//!
+//! Versions are read from the Cargo.lock of the build: the workspace's own
+//! when building here, or the consuming project's when sonos-sdk is a
+//! dependency. Crates missing from the lock file are left out; sonos-sdk
+//! itself falls back to `CARGO_PKG_VERSION`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Workspace crates reported by `build_info()`, in report order
+const WORKSPACE_CRATES: &[&str] = &[
+    "sonos-sdk",
+    "sonos-api",
+    "sonos-sdk-state",
+    "sonos-sdk-state-store",
+    "sonos-sdk-event-manager",
+    "sonos-sdk-stream",
+    "sonos-sdk-callback-server",
+    "sonos-sdk-discovery",
+    "sonos-sdk-soap-client",
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+
+    let lock_file = find_lock_file(&manifest_dir).or_else(|| find_lock_file(&out_dir));
+    let resolved = match &lock_file {
+        Some(path) => {
+            println!("cargo:rerun-if-changed={}", path.display());
+            locked_versions(path)
+        }
+        None => Vec::new(),
+    };
+
+    let mut versions = Vec::new();
+    for &name in WORKSPACE_CRATES {
+        let version = resolved
+            .iter()
+            .find(|(locked, _)| locked == name)
+            .map(|(_, version)| version.clone())
+            .or_else(|| (name == "sonos-sdk").then(|| env::var("CARGO_PKG_VERSION").unwrap()));
+        if let Some(version) = version {
+            versions.push(format!("    ({name:?}, {version:?}),\n"));
+        }
+    }
+
+    let source = format!(
+        "/// (crate, version) for each workspace crate in this build\n\
+         const CRATE_VERSIONS: &[(&str, &str)] = &[\n{}];\n",
+        versions.concat()
+    );
+    fs::write(out_dir.join("build_info.rs"), source).unwrap();
+}
+
+fn find_lock_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+/// (name, version) of every `[[package]]` entry in a Cargo.lock
+fn locked_versions(path: &Path) -> Vec<(String, String)> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    text.split("[[package]]")
+        .skip(1)
+        .filter_map(|package| {
+            let field = |key: &str| {
+                package.lines().find_map(|line| {
+                    line.strip_prefix(key)?
+                        .strip_prefix(" = \"")?
+                        .strip_suffix('"')
+                        .map(str::to_string)
+                })
+            };
+            Some((field("name")?, field("version")?))
+        })
+        .collect()
+}
@@ -0,0 +1,200 @@
+//! Resilience tour: how the SDK recovers from the usual failures
+//!
+//! Walks one system through each failure mode in turn, against loopback
+//! mock speakers on a virtual clock, and checks at every stage that the
+//! SDK does what its docs promise:
+//!
+//! 1. **Startup with a speaker offline**: `connect()` reports it degraded
+//!    instead of failing, and the reachable speaker is ready.
+//! 2. **Events flowing**: a watch subscribes and a change on the speaker
+//!    reaches the cache through a NOTIFY.
+//! 3. **Events stop arriving**: the speaker drops our subscription, as a
+//!    firewall blocking callbacks would look from here. Once the event
+//!    timeout passes the SDK polls, and the cache catches up.
+//! 4. **Renewal refused**: the speaker answers the renewal with 412, so the
+//!    SDK subscribes again under a fresh SID and events flow once more.
+//! 5. **Speaker reboot**: every SID is gone and a change made while it
+//!    restarted is never notified. Polling reconciles the cache and the
+//!    next renewal replaces the subscription.
+//! 6. **Speaker offline**: a read from the unreachable speaker fails fast
+//!    with a network error, and the other speaker carries on.
+//!
+//! Every stage asserts, so a change that breaks a recovery path fails the
+//! example's test. Run the tour:
+//!
+//! ```bash
+//! cargo run -p sonos-sdk --features test-support --example resilience_tour
+//! ```
+//!
+//! or as a test:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --example resilience_tour
+//! ```
+
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sonos_api::{ApiError, Service};
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::sonos_discovery::Device;
+use sonos_sdk::{ConnectOptions, ManualClock, SdkError, SonosSystem, Volume, WatchMode};
+
+/// How long a stage may take to settle, in real time
+const SETTLE: Duration = Duration::from_secs(5);
+
+fn main() {
+    tour();
+    println!("\nAll recovery paths behaved as documented.");
+}
+
+fn tour() {
+    let clock = ManualClock::new();
+    let mock = MockDevice::start_with_clock("127.0.0.1:0", Scenario::new(), clock.clone());
+    mock.set_zone_group_state(format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_STUDY" ID="RINCON_STUDY:1"><ZoneGroupMember UUID="RINCON_STUDY" Location="http://{}/xml/device_description.xml" ZoneName="Study"/></ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        mock.addr()
+    ));
+    let garage_addr = unreachable_addr();
+
+    stage(1, "Startup with a speaker offline");
+    let (system, report) = SonosSystem::connect(
+        ConnectOptions::default()
+            .devices(vec![
+                device("RINCON_STUDY", "Study", mock.addr()),
+                device("RINCON_GARAGE", "Garage", garage_addr),
+            ])
+            .subscribe(false)
+            .clock(Arc::new(clock.clone())),
+    )
+    .expect("connect succeeds with one speaker down");
+    let degraded: Vec<_> = report.degraded().map(|d| d.name.as_str()).collect();
+    narrate(format!("degraded: {degraded:?}"));
+    assert_eq!(degraded, ["Garage"]);
+    let study = system.speaker("Study").unwrap();
+    assert_eq!(study.volume.get(), Some(Volume(25)), "prefetched");
+    narrate("Study is ready with its values prefetched");
+
+    stage(2, "Events flowing");
+    let watch = study.volume.watch().unwrap();
+    assert_eq!(watch.mode(), WatchMode::Events);
+    settle("subscription", || mock.live_subscriptions() == 1);
+    mock.perform(Action::SetVolume(30));
+    settle("volume event", || study.volume.get() == Some(Volume(30)));
+    narrate("a NOTIFY carried volume 30 into the cache");
+
+    stage(3, "Events stop arriving");
+    mock.perform(Action::Expire(Service::RenderingControl));
+    mock.perform(Action::SetVolume(35));
+    assert_eq!(study.volume.get(), Some(Volume(30)), "no NOTIFY was sent");
+    let polls = gets(&mock);
+    step(&clock, Duration::from_secs(60));
+    settle("polling", || study.volume.get() == Some(Volume(35)));
+    narrate(format!(
+        "after the event timeout, polling read volume 35 ({} GetVolume)",
+        gets(&mock) - polls
+    ));
+
+    stage(4, "Renewal refused");
+    assert!(
+        mock.sids(Service::RenderingControl).is_empty(),
+        "the speaker forgot the SID"
+    );
+    step(&clock, Duration::from_secs(1500));
+    settle("resubscription", || mock.subscriptions() == 2);
+    assert_eq!(mock.live_subscriptions(), 1);
+    mock.perform(Action::SetVolume(38));
+    settle("volume event", || study.volume.get() == Some(Volume(38)));
+    narrate("the 412 was answered with a fresh SUBSCRIBE; events flow again");
+
+    stage(5, "Speaker reboot");
+    mock.perform(Action::Reboot);
+    mock.perform(Action::SetVolume(42));
+    assert_eq!(mock.live_subscriptions(), 0);
+    step(&clock, Duration::from_secs(60));
+    settle("polling", || study.volume.get() == Some(Volume(42)));
+    narrate("polling reconciled the change made while it restarted");
+    step(&clock, Duration::from_secs(1800));
+    settle("resubscription", || mock.subscriptions() == 3);
+    mock.perform(Action::SetVolume(45));
+    settle("volume event", || study.volume.get() == Some(Volume(45)));
+    narrate("the next renewal replaced the lost subscription");
+
+    stage(6, "Speaker offline");
+    let garage = system.speaker("Garage").unwrap();
+    let started = Instant::now();
+    let error = garage.volume.fetch().unwrap_err();
+    let elapsed = started.elapsed();
+    narrate(format!("fetch failed in {elapsed:?}: {error}"));
+    assert!(elapsed < Duration::from_secs(1), "fails fast");
+    assert!(matches!(
+        error,
+        SdkError::ApiError(ApiError::NetworkError(_))
+    ));
+    mock.perform(Action::SetVolume(20));
+    settle("volume event", || study.volume.get() == Some(Volume(20)));
+    assert_eq!(watch.mode(), WatchMode::Events);
+    narrate("Study kept receiving events throughout");
+}
+
+/// Advance `clock` by `total` in 5s steps, letting the background loops
+/// observe each one
+fn step(clock: &ManualClock, total: Duration) {
+    let mut elapsed = Duration::ZERO;
+    while elapsed < total {
+        clock.advance(Duration::from_secs(5));
+        elapsed += Duration::from_secs(5);
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Wait until `condition` holds, panicking after [`SETTLE`]
+fn settle(what: &str, condition: impl Fn() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(started.elapsed() < SETTLE, "timed out waiting for {what}");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn stage(number: u32, title: &str) {
+    println!("\n[{number}] {title}");
+}
+
+fn narrate(line: impl AsRef<str>) {
+    println!("    {}", line.as_ref());
+}
+
+fn gets(mock: &MockDevice) -> usize {
+    mock.actions().iter().filter(|a| *a == "GetVolume").count()
+}
+
+/// A loopback address nothing listens on
+fn unreachable_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("bind a free port")
+}
+
+fn device(id: &str, name: &str, addr: SocketAddr) -> Device {
+    Device {
+        id: id.to_string(),
+        name: name.to_string(),
+        room_name: name.to_string(),
+        ip_address: addr.ip().to_string(),
+        port: addr.port(),
+        model_name: "Sonos One".to_string(),
+        household_id: None,
+        secure: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_resilience_tour() {
+        super::tour();
+    }
+}
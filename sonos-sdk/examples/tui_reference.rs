@@ -0,0 +1,712 @@
+//! TUI reference implementation
+//!
+//! A small but complete terminal UI on the public SDK API:
+//!
+//! - **Speakers**: every group, coordinator first with its members indented
+//!   below it, and a marker for what each group is doing. Rebuilt when a
+//!   `group_membership` change arrives, keeping the selection on the same
+//!   speaker.
+//! - **Now playing**: track, artist, album, transport state and position of
+//!   the selected speaker.
+//! - **Volume**: a gauge for the selected speaker, changed from the keyboard
+//!   with `Speaker::set_volume()`.
+//! - **Status bar**: household totals (speakers, groups, groups playing),
+//!   how updates are arriving, and the outcome of the last command.
+//!
+//! Each widget watches only what it draws and holds the `WatchHandle`s while
+//! it is on screen: the list watches playback state and group membership of
+//! every speaker, the detail panes watch the selected speaker and move their
+//! watches with the selection. Each frame drains `system.iter()` without
+//! blocking and redraws only when something changed; values are read from
+//! the cache with `get()` at draw time.
+//!
+//! Keys: ↑/↓ select, ←/→ volume -5/+5, space play/pause, m mute, q quit.
+//!
+//! Run against the speakers on your network:
+//!
+//! ```bash
+//! cargo run -p sonos-sdk --example tui_reference
+//! ```
+//!
+//! or against three mock speakers on loopback:
+//!
+//! ```bash
+//! SONOS_TUI_MOCK=1 cargo run -p sonos-sdk --features test-support --example tui_reference
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+use sonos_sdk::{
+    ChangeIterator, CurrentTrack, GroupMembership, Mute, PlaybackState, Position, RerenderScope,
+    SdkError, SonosSystem, Speaker, SpeakerId, Volume, WatchHandle, WatchMode,
+};
+
+/// How long to wait for a key press before checking for changes again
+const TICK: Duration = Duration::from_millis(50);
+
+/// Volume step for ←/→
+const VOLUME_STEP: i16 = 5;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Mock devices stop when dropped, so hold them for the whole session
+    let (system, _household) = if std::env::var_os("SONOS_TUI_MOCK").is_some() {
+        let (system, household) = mock_system()?;
+        (system, Some(household))
+    } else {
+        (SonosSystem::new()?, None)
+    };
+    let mut app = App::new(system);
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let result = run(&mut terminal, &mut app);
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    Ok(result?)
+}
+
+#[cfg(feature = "test-support")]
+fn mock_system() -> Result<(SonosSystem, household::Household), Box<dyn Error>> {
+    let (system, household) = household::start()?;
+    household.play_once_subscribed();
+    Ok((system, household))
+}
+
+#[cfg(not(feature = "test-support"))]
+fn mock_system() -> Result<(SonosSystem, ()), Box<dyn Error>> {
+    Err("SONOS_TUI_MOCK needs --features test-support".into())
+}
+
+fn run<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    while !app.quit {
+        app.tick(terminal)?;
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.on_key(key.code);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One line of the speaker list
+struct Row {
+    speaker: Speaker,
+    /// Drawn indented under its group's coordinator
+    member: bool,
+}
+
+/// What the speaker list watches on each speaker
+struct ListWatches {
+    _playback: WatchHandle<PlaybackState>,
+    _membership: WatchHandle<GroupMembership>,
+}
+
+/// What the now-playing pane and volume gauge watch on the selected speaker
+struct DetailWatches {
+    speaker_id: SpeakerId,
+    volume: WatchHandle<Volume>,
+    _mute: WatchHandle<Mute>,
+    _track: WatchHandle<CurrentTrack>,
+    _position: WatchHandle<Position>,
+}
+
+impl DetailWatches {
+    fn new(speaker: &Speaker) -> Result<Self, SdkError> {
+        Ok(Self {
+            speaker_id: speaker.id.clone(),
+            volume: speaker.volume.watch_or_fetch()?,
+            _mute: speaker.mute.watch_or_fetch()?,
+            _track: speaker.current_track.watch_or_fetch()?,
+            _position: speaker.position.watch_or_fetch()?,
+        })
+    }
+}
+
+struct App {
+    system: SonosSystem,
+    changes: ChangeIterator,
+    rows: Vec<Row>,
+    selected: usize,
+    list_watches: HashMap<SpeakerId, ListWatches>,
+    detail: Option<DetailWatches>,
+    /// Outcome of the last command, or the last watch that failed
+    status: String,
+    redraw: bool,
+    quit: bool,
+}
+
+impl App {
+    fn new(system: SonosSystem) -> Self {
+        let changes = system.iter();
+        let mut app = Self {
+            system,
+            changes,
+            rows: Vec::new(),
+            selected: 0,
+            list_watches: HashMap::new(),
+            detail: None,
+            status: String::new(),
+            redraw: true,
+            quit: false,
+        };
+        app.rebuild_rows();
+        app
+    }
+
+    /// Apply pending changes and draw a frame if any widget is stale
+    ///
+    /// Returns whether a frame was drawn.
+    fn tick<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<bool> {
+        self.process_changes();
+        if !self.has_any_changes() {
+            return Ok(false);
+        }
+        terminal.draw(|frame| self.draw(frame))?;
+        self.redraw = false;
+        Ok(true)
+    }
+
+    /// Drain `system.iter()` without blocking
+    ///
+    /// Every event is for something a widget watches, so any event means a
+    /// redraw; only membership changes (or a full refresh) reshape the list.
+    fn process_changes(&mut self) {
+        let mut regroup = false;
+        for change in self.changes.try_iter() {
+            self.redraw = true;
+            regroup |= change.rerender_scope() == RerenderScope::Full
+                || change.property_key == "group_membership";
+        }
+        if regroup {
+            self.rebuild_rows();
+        }
+    }
+
+    fn has_any_changes(&self) -> bool {
+        self.redraw
+    }
+
+    /// Lay the list out from the current groups and move the watches to match
+    ///
+    /// Speakers that stay in the list keep their watches, so a regroup
+    /// doesn't resubscribe anything.
+    fn rebuild_rows(&mut self) {
+        let selected = self.selected_speaker().map(|s| s.id);
+        let mut groups = self.system.groups();
+        groups.sort_by_key(|g| g.coordinator().map(|c| c.name));
+        self.rows = groups
+            .iter()
+            .flat_map(|group| {
+                let coordinator = group.coordinator();
+                let coordinator_id = coordinator.as_ref().map(|c| c.id.clone());
+                let mut members: Vec<Speaker> = group
+                    .members()
+                    .into_iter()
+                    .filter(|m| Some(&m.id) != coordinator_id.as_ref())
+                    .collect();
+                members.sort_by(|a, b| a.name.cmp(&b.name));
+                coordinator
+                    .into_iter()
+                    .map(|speaker| Row {
+                        speaker,
+                        member: false,
+                    })
+                    .chain(members.into_iter().map(|speaker| Row {
+                        speaker,
+                        member: true,
+                    }))
+            })
+            .collect();
+
+        self.list_watches
+            .retain(|id, _| self.rows.iter().any(|row| &row.speaker.id == id));
+        for row in &self.rows {
+            if self.list_watches.contains_key(&row.speaker.id) {
+                continue;
+            }
+            let speaker = &row.speaker;
+            match (
+                speaker.playback_state.watch_or_fetch(),
+                speaker.group_membership.watch(),
+            ) {
+                (Ok(playback), Ok(membership)) => {
+                    self.list_watches.insert(
+                        speaker.id.clone(),
+                        ListWatches {
+                            _playback: playback,
+                            _membership: membership,
+                        },
+                    );
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    self.status = format!("watching {} failed: {e}", speaker.name);
+                }
+            }
+        }
+
+        let index = selected
+            .and_then(|id| self.rows.iter().position(|row| row.speaker.id == id))
+            .unwrap_or(0);
+        self.select(index);
+        self.redraw = true;
+    }
+
+    fn selected_speaker(&self) -> Option<Speaker> {
+        self.rows.get(self.selected).map(|row| row.speaker.clone())
+    }
+
+    /// Select a row and move the detail watches to its speaker
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.rows.len().saturating_sub(1));
+        let Some(speaker) = self.selected_speaker() else {
+            self.detail = None;
+            return;
+        };
+        if self
+            .detail
+            .as_ref()
+            .is_some_and(|d| d.speaker_id == speaker.id)
+        {
+            return;
+        }
+        // Drop the old speaker's watches before taking the new ones
+        self.detail = None;
+        match DetailWatches::new(&speaker) {
+            Ok(watches) => self.detail = Some(watches),
+            Err(e) => self.status = format!("watching {} failed: {e}", speaker.name),
+        }
+        self.redraw = true;
+    }
+
+    fn on_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Up => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down => self.select(self.selected + 1),
+            KeyCode::Left | KeyCode::Char('-') => self.nudge_volume(-VOLUME_STEP),
+            KeyCode::Right | KeyCode::Char('+') => self.nudge_volume(VOLUME_STEP),
+            KeyCode::Char(' ') => self.toggle_playback(),
+            KeyCode::Char('m') => self.toggle_mute(),
+            _ => {}
+        }
+    }
+
+    fn nudge_volume(&mut self, by: i16) {
+        let Some(speaker) = self.selected_speaker() else {
+            return;
+        };
+        let current = speaker.volume.get().map_or(0, |v| v.value());
+        let volume = (i16::from(current) + by).clamp(0, 100) as u8;
+        self.report(
+            speaker.set_volume(volume),
+            format!("{} volume {volume}", speaker.name),
+        );
+    }
+
+    fn toggle_playback(&mut self) {
+        let Some(speaker) = self.selected_speaker() else {
+            return;
+        };
+        if speaker.playback_state.get().is_some_and(|s| s.is_playing()) {
+            self.report(speaker.pause(), format!("paused {}", speaker.name));
+        } else {
+            self.report(speaker.play(), format!("playing {}", speaker.name));
+        }
+    }
+
+    fn toggle_mute(&mut self) {
+        let Some(speaker) = self.selected_speaker() else {
+            return;
+        };
+        let muted = !speaker.mute.get().is_some_and(|m| m.is_muted());
+        let done = format!(
+            "{} {}",
+            speaker.name,
+            if muted { "muted" } else { "unmuted" }
+        );
+        self.report(speaker.set_mute(muted), done);
+    }
+
+    /// Show the outcome of a command in the status bar
+    ///
+    /// A successful write updates the cache, and the change arrives through
+    /// `system.iter()` like any other.
+    fn report(&mut self, result: Result<(), SdkError>, done: String) {
+        self.status = match result {
+            Ok(()) => done,
+            Err(e) => format!("error: {e}"),
+        };
+        self.redraw = true;
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(frame.size());
+        let main = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Min(0)])
+            .split(outer[0]);
+        let detail = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)])
+            .split(main[1]);
+
+        self.draw_speakers(frame, main[0]);
+        let speaker = self.selected_speaker();
+        draw_now_playing(frame, detail[0], speaker.as_ref());
+        draw_volume(frame, detail[1], speaker.as_ref());
+        frame.render_widget(
+            Paragraph::new(self.summary()).style(Style::default().add_modifier(Modifier::REVERSED)),
+            outer[1],
+        );
+    }
+
+    fn draw_speakers(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let marker = match row.speaker.playback_state.get() {
+                    Some(PlaybackState::Playing) => '▶',
+                    Some(PlaybackState::Paused) => '‖',
+                    Some(PlaybackState::Transitioning) => '…',
+                    _ => ' ',
+                };
+                let indent = if row.member { "  " } else { "" };
+                ListItem::new(format!("{indent}{marker} {}", row.speaker.name))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Speakers"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    /// Household totals and the outcome of the last command
+    fn summary(&self) -> String {
+        let groups = self.rows.iter().filter(|row| !row.member);
+        let playing = groups
+            .clone()
+            .filter(|row| {
+                row.speaker
+                    .playback_state
+                    .get()
+                    .is_some_and(|s| s.is_playing())
+            })
+            .count();
+        let updates = match self.detail.as_ref().map(|d| d.volume.mode()) {
+            Some(WatchMode::Events) => "live",
+            Some(WatchMode::Polling) => "polling",
+            _ => "cached",
+        };
+        let mut summary = format!(
+            " {} speakers | {} groups | {playing} playing | {updates}",
+            self.rows.len(),
+            groups.count()
+        );
+        if !self.status.is_empty() {
+            summary.push_str(" | ");
+            summary.push_str(&self.status);
+        }
+        summary
+    }
+}
+
+fn draw_now_playing(frame: &mut Frame, area: Rect, speaker: Option<&Speaker>) {
+    let block = Block::default().borders(Borders::ALL).title("Now Playing");
+    let Some(speaker) = speaker else {
+        frame.render_widget(Paragraph::new("No speakers").block(block), area);
+        return;
+    };
+    let track = speaker.current_track.get();
+    let field = |get: fn(&CurrentTrack) -> &Option<String>| {
+        track
+            .as_ref()
+            .and_then(|t| get(t).clone())
+            .unwrap_or_default()
+    };
+    let title = track
+        .as_ref()
+        .and_then(|t| t.title.clone())
+        .unwrap_or_else(|| "Nothing playing".to_string());
+    let state = match speaker.playback_state.get() {
+        Some(PlaybackState::Playing) => "Playing",
+        Some(PlaybackState::Paused) => "Paused",
+        Some(PlaybackState::Stopped) => "Stopped",
+        Some(PlaybackState::Transitioning) => "Loading",
+        None => "-",
+    };
+    let position = speaker
+        .position
+        .get()
+        .filter(|p| p.duration_ms > 0)
+        .map(|p| format!("{} / {}", clock(p.position_ms), clock(p.duration_ms)))
+        .unwrap_or_default();
+    let lines = vec![
+        Line::styled(
+            speaker.name.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Line::raw(""),
+        Line::raw(title),
+        Line::raw(field(|t| &t.artist)),
+        Line::raw(field(|t| &t.album)),
+        Line::raw(""),
+        Line::raw(format!("{state}  {position}")),
+    ];
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_volume(frame: &mut Frame, area: Rect, speaker: Option<&Speaker>) {
+    let volume = speaker.and_then(|s| s.volume.get()).map(|v| v.value());
+    let muted = speaker
+        .and_then(|s| s.mute.get())
+        .is_some_and(|m| m.is_muted());
+    let label = match volume {
+        Some(volume) if muted => format!("{volume}% (muted)"),
+        Some(volume) => format!("{volume}%"),
+        None => "-".to_string(),
+    };
+    let gauge = Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title("Volume"))
+        .percent(volume.map_or(0, u16::from))
+        .label(label);
+    frame.render_widget(gauge, area);
+}
+
+/// `m:ss` for a track position
+fn clock(ms: u64) -> String {
+    format!("{}:{:02}", ms / 60_000, ms / 1000 % 60)
+}
+
+/// Three mock speakers on loopback: Living Room with Kitchen grouped under
+/// it, and Office on its own
+#[cfg(feature = "test-support")]
+mod household {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use sonos_api::Service;
+    use sonos_sdk::mock::{Action, MockDevice, Scenario};
+    use sonos_sdk::{Device, SdkError, SonosSystem};
+
+    /// (ID, room) in list order
+    pub const SPEAKERS: [(&str, &str); 3] = [
+        ("RINCON_00000000002301400", "Living Room"),
+        ("RINCON_00000000002401400", "Kitchen"),
+        ("RINCON_00000000002501400", "Office"),
+    ];
+
+    pub struct Household {
+        /// One per entry of [`SPEAKERS`]
+        pub devices: Vec<MockDevice>,
+    }
+
+    pub fn start() -> Result<(SonosSystem, Household), SdkError> {
+        let devices: Vec<MockDevice> = SPEAKERS
+            .iter()
+            .map(|_| MockDevice::start("127.0.0.1:0", Scenario::new()))
+            .collect();
+        for device in &devices {
+            device.set_zone_group_state(topology(&devices));
+        }
+        let system = SonosSystem::from_discovered_devices(
+            SPEAKERS
+                .iter()
+                .zip(&devices)
+                .map(|((id, name), device)| Device {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    room_name: name.to_string(),
+                    ip_address: device.addr().ip().to_string(),
+                    port: device.addr().port(),
+                    model_name: "Sonos One".to_string(),
+                    household_id: None,
+                    secure: false,
+                })
+                .collect(),
+        )?;
+        Ok((system, Household { devices }))
+    }
+
+    impl Household {
+        /// Wait until the UI has subscribed `service` on speaker `index`
+        pub fn wait_for_subscription(&self, index: usize, service: Service) -> bool {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while self.devices[index].sids(service).is_empty() {
+                if Instant::now() > deadline {
+                    return false;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+            true
+        }
+
+        /// Have the Living Room announce a track
+        pub fn play(&self, title: &str, artist: &str, album: &str) {
+            self.devices[0].perform(Action::Notify {
+                service: Service::AVTransport,
+                body: now_playing(title, artist, album),
+            });
+        }
+
+        /// Start the Living Room playing once the UI is listening, so the
+        /// interactive demo has something to show
+        pub fn play_once_subscribed(&self) {
+            let household = Household {
+                devices: self.devices.clone(),
+            };
+            thread::spawn(move || {
+                if household.wait_for_subscription(0, Service::AVTransport) {
+                    household.play("Morning Light", "The Placeholders", "Sample Sessions");
+                }
+            });
+        }
+    }
+
+    fn topology(devices: &[MockDevice]) -> String {
+        let member = |(id, name): (&str, &str), device: &MockDevice| {
+            format!(
+                r#"<ZoneGroupMember UUID="{id}" Location="http://{}/xml/device_description.xml" ZoneName="{name}" BootSeq="1"/>"#,
+                device.addr()
+            )
+        };
+        let [living_room, kitchen, office] = SPEAKERS;
+        format!(
+            r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="{lr}" ID="{lr}:1">{}{}</ZoneGroup><ZoneGroup Coordinator="{of}" ID="{of}:1">{}</ZoneGroup></ZoneGroups><VanishedDevices></VanishedDevices></ZoneGroupState>"#,
+            member(living_room, &devices[0]),
+            member(kitchen, &devices[1]),
+            member(office, &devices[2]),
+            lr = living_room.0,
+            of = office.0,
+        )
+    }
+
+    /// AVTransport NOTIFY for a track that is playing
+    fn now_playing(title: &str, artist: &str, album: &str) -> String {
+        let didl = format!(
+            r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="-1" parentID="-1" restricted="true"><upnp:class>object.item.audioItem.musicTrack</upnp:class><dc:title>{}</dc:title><dc:creator>{}</dc:creator><upnp:album>{}</upnp:album></item></DIDL-Lite>"#,
+            escape(title),
+            escape(artist),
+            escape(album)
+        );
+        let last_change = format!(
+            r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"><InstanceID val="0"><TransportState val="PLAYING"/><CurrentTrackURI val="x-file-cifs://nas/music/track.flac"/><CurrentTrackDuration val="0:03:30"/><CurrentTrackMetaData val="{}"/></InstanceID></Event>"#,
+            escape(&didl)
+        );
+        format!(
+            r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>{}</LastChange></e:property></e:propertyset>"#,
+            escape(&last_change)
+        )
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use sonos_api::Service;
+    use sonos_sdk::mock::Action;
+    use std::thread;
+    use std::time::Instant;
+
+    fn screen(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Run frames until the screen shows `text`
+    fn frames_until(app: &mut App, terminal: &mut Terminal<TestBackend>, text: &str) -> String {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            app.tick(terminal).unwrap();
+            let shown = screen(terminal);
+            if shown.contains(text) {
+                return shown;
+            }
+            assert!(Instant::now() < deadline, "never showed {text:?}:\n{shown}");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_renders_and_reacts_to_changes() {
+        let (system, household) = household::start().unwrap();
+        let mut app = App::new(system);
+        let mut terminal = Terminal::new(TestBackend::new(80, 16)).unwrap();
+
+        // Members are indented under their coordinator
+        let shown = frames_until(&mut app, &mut terminal, "Office");
+        let at = |name: &str| {
+            shown
+                .lines()
+                .enumerate()
+                .find_map(|(row, line)| Some((row, line[..line.find(name)?].chars().count())))
+                .unwrap()
+        };
+        assert!(at("Living Room").0 < at("Kitchen").0 && at("Kitchen").0 < at("Office").0);
+        assert!(
+            at("Kitchen").1 > at("Living Room").1,
+            "Kitchen not indented:\n{shown}"
+        );
+        assert!(
+            at("Office").1 == at("Living Room").1,
+            "Office indented:\n{shown}"
+        );
+        assert!(shown.contains("3 speakers | 2 groups"));
+        frames_until(&mut app, &mut terminal, "25%");
+
+        // A change made elsewhere reaches the gauge
+        assert!(household.wait_for_subscription(0, Service::RenderingControl));
+        household.devices[0].perform(Action::SetVolume(40));
+        frames_until(&mut app, &mut terminal, "40%");
+
+        // A key press writes to the device and the gauge follows
+        app.on_key(KeyCode::Right);
+        frames_until(&mut app, &mut terminal, "45%");
+        assert_eq!(household.devices[0].volume(), 45);
+
+        assert!(household.wait_for_subscription(0, Service::AVTransport));
+        household.play("Morning Light", "The Placeholders", "Sample Sessions");
+        frames_until(&mut app, &mut terminal, "Morning Light");
+
+        // The detail panes follow the selection down to Office
+        app.on_key(KeyCode::Down);
+        app.on_key(KeyCode::Down);
+        let shown = frames_until(&mut app, &mut terminal, "Nothing playing");
+        assert!(shown.contains("25%"), "{shown}");
+    }
+}
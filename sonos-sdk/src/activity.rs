@@ -0,0 +1,591 @@
+//! Scrolling activity log for debug panes
+//!
+//! [`ActivityFeed`] keeps a bounded ring of short, structured entries about
+//! what the SDK did on the wire: SUBSCRIBE, renewal and UNSUBSCRIBE requests
+//! and their results, NOTIFYs received, and writes sent. It is fed by the
+//! broker's protocol observer and a write interceptor, both installed by
+//! [`SonosSystem::activity_feed()`](crate::SonosSystem::activity_feed) the
+//! first time it's called; nothing earlier is recorded.
+//!
+//! Entries are numbered in order. A consumer keeps an [`ActivityCursor`] and
+//! reads what's new with [`ActivityFeed::since()`], which never repeats an
+//! entry and reports how many it missed when the ring overtook the cursor.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, Weak};
+use std::time::SystemTime;
+
+use sonos_api::Service;
+use sonos_event_manager::{
+    ExchangeKind, NotifyOutcome, ProtocolObserver, ProtocolRecord, RedactionPolicy,
+};
+use sonos_state::{ChangeEvent, InterceptDecision, SpeakerId, StateManager, WriteInterceptor};
+
+/// Write argument values are cut to this many characters
+const MAX_VALUE_CHARS: usize = 32;
+
+/// What an entry is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActivityCategory {
+    /// SUBSCRIBE, renewal or UNSUBSCRIBE, or a subscription dropped locally
+    Subscription,
+    /// A NOTIFY received for a subscription
+    Notify,
+    /// A mutating action sent to a speaker
+    Write,
+}
+
+impl fmt::Display for ActivityCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ActivityCategory::Subscription => "subscription",
+            ActivityCategory::Notify => "notify",
+            ActivityCategory::Write => "write",
+        })
+    }
+}
+
+/// How much attention an entry deserves, least first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ActivitySeverity {
+    Info,
+    /// Something was dropped or ignored, but nothing is broken yet
+    Warning,
+    /// A request failed
+    Error,
+}
+
+/// One line of the activity log
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityEntry {
+    /// Position in the feed, from 1, without gaps
+    pub seq: u64,
+    pub at: SystemTime,
+    pub category: ActivityCategory,
+    /// `None` when the address isn't a registered speaker
+    pub speaker: Option<SpeakerId>,
+    pub service: Service,
+    /// ID of the subscription that stays the same across restarts and SID
+    /// changes, when the broker has a `stable_id_file`; `None` for writes
+    pub stable_id: Option<String>,
+    /// Short human-readable description, redacted per
+    /// [`ActivityConfig::redaction`]
+    pub summary: String,
+    pub severity: ActivitySeverity,
+}
+
+impl ActivityEntry {
+    /// Cursor positioned just after this entry
+    pub fn cursor(&self) -> ActivityCursor {
+        ActivityCursor(self.seq)
+    }
+}
+
+/// Position in an [`ActivityFeed`]: everything up to it has been read
+///
+/// The default is the start of the feed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ActivityCursor(u64);
+
+/// Entries read with [`ActivityFeed::since()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityPage {
+    /// Oldest first
+    pub entries: Vec<ActivityEntry>,
+    /// Where the next read continues
+    pub next: ActivityCursor,
+    /// Entries after the cursor that the ring dropped before this read
+    pub missed: u64,
+}
+
+/// Size and redaction of an [`ActivityFeed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivityConfig {
+    /// Entries kept; the oldest are dropped beyond it
+    /// Default: 256
+    pub capacity: usize,
+    /// `Identifiers` replaces speaker IPs with speaker IDs and keeps only the
+    /// last 4 characters of SIDs
+    /// Default: `RedactionPolicy::Identifiers`
+    pub redaction: RedactionPolicy,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            redaction: RedactionPolicy::Identifiers,
+        }
+    }
+}
+
+impl ActivityConfig {
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn with_redaction(mut self, redaction: RedactionPolicy) -> Self {
+        self.redaction = redaction;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct Ring {
+    entries: VecDeque<ActivityEntry>,
+    /// Sequence number of the last entry recorded
+    last_seq: u64,
+}
+
+struct Inner {
+    config: ActivityConfig,
+    ring: Mutex<Ring>,
+    /// Resolves speakers and receives the change notifications; unset for
+    /// a feed not attached to a system
+    state: OnceLock<Weak<StateManager>>,
+    /// A change event went out and the feed hasn't been read since
+    announced: AtomicBool,
+}
+
+/// Bounded, shared log of recent subscription and write activity
+///
+/// Cloning gives another handle on the same log. When attached to a
+/// [`SonosSystem`](crate::SonosSystem), the first entry recorded after each
+/// read emits a [`ChangeEvent`] with `property_key ==`
+/// [`ActivityFeed::EVENT_KEY`] on `system.iter()` (system-scoped, so
+/// `speaker_id` is empty), so the activity pane is redrawn along with the
+/// rest of the UI. Further entries stay quiet until the feed is read with
+/// [`recent()`](Self::recent) or [`since()`](Self::since).
+#[derive(Clone)]
+pub struct ActivityFeed {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for ActivityFeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ActivityFeed")
+            .field("config", &self.inner.config)
+            .field("cursor", &self.cursor())
+            .finish()
+    }
+}
+
+impl ActivityFeed {
+    /// Property key of the change event announcing new entries
+    pub const EVENT_KEY: &'static str = "activity";
+
+    pub(crate) fn new(config: ActivityConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                ring: Mutex::new(Ring::default()),
+                state: OnceLock::new(),
+                announced: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Resolve speakers through `state_manager` and announce entries on it
+    pub(crate) fn attach(&self, state_manager: &Arc<StateManager>) {
+        let _ = self.inner.state.set(Arc::downgrade(state_manager));
+    }
+
+    pub fn config(&self) -> ActivityConfig {
+        self.inner.config
+    }
+
+    /// The last `n` entries, oldest first
+    pub fn recent(&self, n: usize) -> Vec<ActivityEntry> {
+        self.inner.announced.store(false, Ordering::SeqCst);
+        let ring = self.ring();
+        let skip = ring.entries.len().saturating_sub(n);
+        ring.entries.iter().skip(skip).cloned().collect()
+    }
+
+    /// Every entry after `cursor`, oldest first
+    pub fn since(&self, cursor: ActivityCursor) -> ActivityPage {
+        self.inner.announced.store(false, Ordering::SeqCst);
+        let ring = self.ring();
+        let oldest = ring.entries.front().map_or(ring.last_seq + 1, |e| e.seq);
+        let entries: Vec<_> = ring
+            .entries
+            .iter()
+            .filter(|entry| entry.seq > cursor.0)
+            .cloned()
+            .collect();
+        ActivityPage {
+            entries,
+            next: ActivityCursor(ring.last_seq.max(cursor.0)),
+            missed: oldest.saturating_sub(cursor.0 + 1),
+        }
+    }
+
+    /// Cursor just after the latest entry, to read only what comes next
+    pub fn cursor(&self) -> ActivityCursor {
+        ActivityCursor(self.ring().last_seq)
+    }
+
+    fn ring(&self) -> std::sync::MutexGuard<'_, Ring> {
+        self.inner
+            .ring
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn state(&self) -> Option<Arc<StateManager>> {
+        self.inner.state.get().and_then(Weak::upgrade)
+    }
+
+    pub(crate) fn record(
+        &self,
+        category: ActivityCategory,
+        speaker: Option<SpeakerId>,
+        service: Service,
+        stable_id: Option<String>,
+        severity: ActivitySeverity,
+        summary: String,
+    ) {
+        if self.inner.config.capacity == 0 {
+            return;
+        }
+        {
+            let mut ring = self.ring();
+            ring.last_seq += 1;
+            let entry = ActivityEntry {
+                seq: ring.last_seq,
+                at: SystemTime::now(),
+                category,
+                speaker,
+                service,
+                stable_id,
+                summary,
+                severity,
+            };
+            if ring.entries.len() == self.inner.config.capacity {
+                ring.entries.pop_front();
+            }
+            ring.entries.push_back(entry);
+        }
+        if self.inner.announced.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Some(state) = self.state() {
+            state.emit_change(ChangeEvent::new(
+                ChangeEvent::system_id(),
+                Self::EVENT_KEY,
+                service,
+            ));
+        }
+    }
+
+    /// Record a subscription exchange or NOTIFY receipt from the broker
+    pub(crate) fn record_protocol(
+        &self,
+        addr: SocketAddr,
+        service: Service,
+        record: &ProtocolRecord,
+    ) {
+        let speaker = self.state().and_then(|state| {
+            state
+                .speaker_infos()
+                .into_iter()
+                .find(|info| info.ip_address == addr.ip() && info.port == addr.port())
+                .map(|info| info.id)
+        });
+        let redact = |text: &str| self.redact(text, addr.ip(), speaker.as_ref());
+        let name = service.name();
+        let stable_id = match record {
+            ProtocolRecord::Exchange(exchange) => exchange.stable_id.clone(),
+            ProtocolRecord::Notify { stable_id, .. } => stable_id.clone(),
+        };
+
+        let (category, severity, summary) = match record {
+            ProtocolRecord::Exchange(exchange) => {
+                let sid = exchange
+                    .sid
+                    .as_deref()
+                    .map(|sid| self.redact_sid(sid))
+                    .unwrap_or_default();
+                let (severity, summary) = match (exchange.kind, &exchange.error) {
+                    (ExchangeKind::Subscribe, None) => (
+                        ActivitySeverity::Info,
+                        format!("SUBSCRIBE {name} {sid} for {}s", granted(exchange)),
+                    ),
+                    (ExchangeKind::Subscribe, Some(e)) => (
+                        ActivitySeverity::Error,
+                        format!("SUBSCRIBE {name} failed: {}", redact(e)),
+                    ),
+                    (ExchangeKind::Renew, None) => (
+                        ActivitySeverity::Info,
+                        format!("Renewed {name} {sid} for {}s", granted(exchange)),
+                    ),
+                    (ExchangeKind::Renew, Some(e)) => (
+                        ActivitySeverity::Error,
+                        format!("Renewal of {name} {sid} failed: {}", redact(e)),
+                    ),
+                    (ExchangeKind::Unsubscribe, None) => {
+                        (ActivitySeverity::Info, format!("UNSUBSCRIBE {name} {sid}"))
+                    }
+                    (ExchangeKind::Unsubscribe, Some(e)) => (
+                        ActivitySeverity::Warning,
+                        format!("UNSUBSCRIBE {name} {sid} failed: {}", redact(e)),
+                    ),
+                    (ExchangeKind::Retire, e) => (
+                        ActivitySeverity::Warning,
+                        format!(
+                            "Dropped {name} {sid}: {}",
+                            redact(e.as_deref().unwrap_or("retired"))
+                        ),
+                    ),
+                };
+                (ActivityCategory::Subscription, severity, summary)
+            }
+            ProtocolRecord::Notify { sid, receipt, .. } => {
+                let seq = receipt
+                    .seq
+                    .map(|seq| format!(" seq {seq}"))
+                    .unwrap_or_default();
+                let head = format!(
+                    "NOTIFY {name} {}{seq}, {} bytes",
+                    self.redact_sid(sid),
+                    receipt.size
+                );
+                let (severity, summary) = match receipt.outcome {
+                    NotifyOutcome::Delivered => (ActivitySeverity::Info, head),
+                    NotifyOutcome::ParseFailed => {
+                        (ActivitySeverity::Warning, format!("{head}, not parsed"))
+                    }
+                    NotifyOutcome::Undecodable => {
+                        (ActivitySeverity::Warning, format!("{head}, undecodable"))
+                    }
+                    NotifyOutcome::ChannelClosed => (
+                        ActivitySeverity::Warning,
+                        format!("{head}, dropped: event stream closed"),
+                    ),
+                };
+                (ActivityCategory::Notify, severity, summary)
+            }
+        };
+        self.record(category, speaker, service, stable_id, severity, summary);
+    }
+
+    /// Record a write as the interceptors before the feed's let it through
+    fn record_write(
+        &self,
+        speaker_id: &SpeakerId,
+        service: Service,
+        action: &str,
+        args: &[(String, String)],
+    ) {
+        let ip = self
+            .state()
+            .and_then(|state| state.get_speaker_ip(speaker_id));
+        let args: Vec<String> = args
+            .iter()
+            .filter(|(name, _)| name != "InstanceID")
+            .map(|(name, value)| {
+                let value = match ip {
+                    Some(ip) => self.redact(value, ip, Some(speaker_id)),
+                    None => value.clone(),
+                };
+                format!("{name}={}", shorten(&value))
+            })
+            .collect();
+        let summary = if args.is_empty() {
+            action.to_string()
+        } else {
+            format!("{action} {}", args.join(", "))
+        };
+        self.record(
+            ActivityCategory::Write,
+            Some(speaker_id.clone()),
+            service,
+            None,
+            ActivitySeverity::Info,
+            summary,
+        );
+    }
+
+    fn redact(&self, text: &str, ip: IpAddr, speaker: Option<&SpeakerId>) -> String {
+        match self.inner.config.redaction {
+            RedactionPolicy::Off => text.to_string(),
+            RedactionPolicy::Identifiers => {
+                text.replace(&ip.to_string(), speaker.map_or("speaker", |id| id.as_str()))
+            }
+        }
+    }
+
+    fn redact_sid(&self, sid: &str) -> String {
+        match self.inner.config.redaction {
+            RedactionPolicy::Off => sid.to_string(),
+            RedactionPolicy::Identifiers => {
+                let tail = sid.len().saturating_sub(4);
+                format!("…{}", sid.get(tail..).unwrap_or_default())
+            }
+        }
+    }
+}
+
+fn granted(exchange: &sonos_event_manager::SubscriptionExchange) -> u32 {
+    exchange.granted_timeout_secs.unwrap_or_default()
+}
+
+fn shorten(value: &str) -> String {
+    match value.char_indices().nth(MAX_VALUE_CHARS) {
+        Some((end, _)) => format!("{}…", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+/// Broker observer feeding the feed in `slot`, once there is one
+pub(crate) fn protocol_observer(slot: Arc<OnceLock<ActivityFeed>>) -> ProtocolObserver {
+    ProtocolObserver::new(move |pair, record| {
+        if let Some(feed) = slot.get() {
+            feed.record_protocol(pair.speaker_addr, pair.service, record);
+        }
+    })
+}
+
+/// Interceptor recording every write it sees, and allowing it
+pub(crate) fn write_recorder(feed: ActivityFeed) -> WriteInterceptor {
+    Arc::new(move |request| {
+        feed.record_write(
+            request.speaker_id(),
+            request.service(),
+            request.action(),
+            request.args(),
+        );
+        InterceptDecision::Allow
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sonos_event_manager::{NotifyReceipt, SubscriptionExchange};
+
+    fn note(feed: &ActivityFeed, n: u64) {
+        feed.record(
+            ActivityCategory::Write,
+            None,
+            Service::RenderingControl,
+            None,
+            ActivitySeverity::Info,
+            format!("entry {n}"),
+        );
+    }
+
+    #[test]
+    fn test_ring_keeps_the_latest_entries() {
+        let feed = ActivityFeed::new(ActivityConfig::default().with_capacity(3));
+        for n in 1..=5 {
+            note(&feed, n);
+        }
+        let seqs: Vec<_> = feed.recent(10).iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [3, 4, 5]);
+        let seqs: Vec<_> = feed.recent(2).iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [4, 5]);
+        assert_eq!(feed.cursor(), ActivityCursor(5));
+
+        let page = feed.since(ActivityCursor::default());
+        assert_eq!(page.missed, 2);
+        assert_eq!(page.entries.len(), 3);
+        assert_eq!(page.next, ActivityCursor(5));
+        let page = feed.since(page.next);
+        assert!(page.entries.is_empty());
+        assert_eq!((page.missed, page.next), (0, ActivityCursor(5)));
+    }
+
+    #[test]
+    fn test_pages_never_skip_or_repeat() {
+        let feed = ActivityFeed::new(ActivityConfig::default().with_capacity(8));
+        let mut cursor = ActivityCursor::default();
+        let mut read = Vec::new();
+        let mut n = 0;
+        for burst in [1, 5, 0, 8, 3, 7, 2] {
+            for _ in 0..burst {
+                n += 1;
+                note(&feed, n);
+            }
+            let page = feed.since(cursor);
+            assert_eq!(page.missed, 0);
+            read.extend(page.entries.iter().map(|e| e.seq));
+            cursor = page.next;
+        }
+        assert_eq!(read, (1..=n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_summaries_are_redacted() {
+        let addr: SocketAddr = "192.168.1.50:1400".parse().unwrap();
+        let feed = ActivityFeed::new(ActivityConfig::default());
+        feed.record_protocol(
+            addr,
+            Service::AVTransport,
+            &ProtocolRecord::Exchange(SubscriptionExchange {
+                at_ms: 0,
+                kind: ExchangeKind::Renew,
+                sid: Some("uuid:RINCON_1234".to_string()),
+                stable_id: Some("3f1c".to_string()),
+                granted_timeout_secs: None,
+                error: Some("connection to 192.168.1.50:1400 refused".to_string()),
+            }),
+        );
+        feed.record_protocol(
+            addr,
+            Service::AVTransport,
+            &ProtocolRecord::Notify {
+                sid: "uuid:RINCON_1234".to_string(),
+                stable_id: Some("3f1c".to_string()),
+                receipt: NotifyReceipt {
+                    at_ms: 0,
+                    seq: Some(7),
+                    size: 512,
+                    outcome: NotifyOutcome::ParseFailed,
+                },
+            },
+        );
+
+        let entries = feed.recent(2);
+        // Stable IDs identify the subscription and are never redacted
+        assert!(entries
+            .iter()
+            .all(|e| e.stable_id.as_deref() == Some("3f1c")));
+        assert_eq!(entries[0].category, ActivityCategory::Subscription);
+        assert_eq!(entries[0].severity, ActivitySeverity::Error);
+        assert_eq!(
+            entries[0].summary,
+            "Renewal of AVTransport …1234 failed: connection to speaker:1400 refused"
+        );
+        assert_eq!(entries[1].category, ActivityCategory::Notify);
+        assert_eq!(entries[1].severity, ActivitySeverity::Warning);
+        assert_eq!(
+            entries[1].summary,
+            "NOTIFY AVTransport …1234 seq 7, 512 bytes, not parsed"
+        );
+
+        let verbatim =
+            ActivityFeed::new(ActivityConfig::default().with_redaction(RedactionPolicy::Off));
+        verbatim.record_write(
+            &SpeakerId::new("RINCON_DEN"),
+            Service::AVTransport,
+            "SetAVTransportURI",
+            &[
+                ("InstanceID".to_string(), "0".to_string()),
+                (
+                    "CurrentURI".to_string(),
+                    "x-sonos-http:librarytrack%3ai.1234567890.mp4".to_string(),
+                ),
+                ("CurrentURIMetaData".to_string(), String::new()),
+            ],
+        );
+        assert_eq!(
+            verbatim.recent(1)[0].summary,
+            "SetAVTransportURI CurrentURI=x-sonos-http:librarytrack%3ai.12…, CurrentURIMetaData="
+        );
+    }
+}
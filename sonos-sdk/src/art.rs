@@ -0,0 +1,628 @@
+//! Album art cache
+//!
+//! Downloads album art over the shared HTTP agent and keeps the bytes in a
+//! size-bounded LRU cache. Keys are normalized so the same artwork is cached
+//! once even when its URL carries volatile tokens or comes from a different
+//! speaker. Concurrent requests for the same key share one download.
+//!
+//! No image decoding is done: [`ArtHandle`] exposes raw bytes, the
+//! `Content-Type` header and dimensions read from PNG/GIF/JPEG headers.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, Weak};
+use std::thread;
+
+use sonos_api::SonosClient;
+use sonos_state::{ChangeEvent, CurrentTrack, Property, StateManager};
+
+use crate::SdkError;
+
+/// Default cache budget: 32 MiB of image bytes
+const DEFAULT_CAPACITY_BYTES: usize = 32 * 1024 * 1024;
+
+/// Query parameters that change between requests for the same artwork
+/// (signed CDN URLs, cache busters) and are left out of the cache key.
+const VOLATILE_PARAMS: &[&str] = &[
+    "token",
+    "sig",
+    "signature",
+    "expires",
+    "exp",
+    "ts",
+    "timestamp",
+    "auth",
+    "key-pair-id",
+    "policy",
+];
+
+/// Cached album art bytes
+#[derive(Debug, Clone)]
+pub struct ArtHandle {
+    key: String,
+    bytes: Arc<[u8]>,
+    content_type: Option<String>,
+    dimensions: Option<(u32, u32)>,
+}
+
+impl ArtHandle {
+    /// Normalized cache key for this artwork
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Raw image bytes
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// `Content-Type` reported by the server, e.g. `"image/jpeg"`
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// `(width, height)` if the image header could be read (PNG, GIF, JPEG)
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        self.dimensions
+    }
+}
+
+/// A download in progress that other callers for the same key wait on
+#[derive(Default)]
+struct InFlight {
+    result: Mutex<Option<Result<ArtHandle, String>>>,
+    ready: Condvar,
+}
+
+/// The caller performing a download, which completes it for the waiters
+/// when dropped
+///
+/// Completing on drop means a download that panics still wakes its waiters,
+/// with an error, and clears its in-flight entry so the next request retries.
+struct Leader<'a> {
+    state: &'a Mutex<CacheState>,
+    key: String,
+    flight: Arc<InFlight>,
+    result: Option<Result<ArtHandle, String>>,
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        let result = self
+            .result
+            .take()
+            .unwrap_or_else(|| Err("album art download abandoned".to_string()));
+        {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            state.inflight.remove(&self.key);
+            if let Ok(handle) = &result {
+                state.insert(handle.clone());
+            }
+        }
+        *self
+            .flight
+            .result
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(result);
+        self.flight.ready.notify_all();
+    }
+}
+
+struct CacheState {
+    capacity: usize,
+    used: usize,
+    entries: HashMap<String, ArtHandle>,
+    /// Keys from least to most recently used
+    lru: VecDeque<String>,
+    inflight: HashMap<String, Arc<InFlight>>,
+}
+
+impl CacheState {
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.lru.iter().position(|k| k == key) {
+            let key = self.lru.remove(pos).unwrap_or_default();
+            self.lru.push_back(key);
+        }
+    }
+
+    fn insert(&mut self, handle: ArtHandle) {
+        let size = handle.bytes.len();
+        if size > self.capacity {
+            return;
+        }
+        if let Some(old) = self.entries.remove(&handle.key) {
+            self.used -= old.bytes.len();
+            self.lru.retain(|k| *k != handle.key);
+        }
+        self.used += size;
+        self.lru.push_back(handle.key.clone());
+        self.entries.insert(handle.key.clone(), handle);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.used > self.capacity {
+            let Some(key) = self.lru.pop_front() else {
+                break;
+            };
+            if let Some(old) = self.entries.remove(&key) {
+                self.used -= old.bytes.len();
+            }
+        }
+    }
+}
+
+/// In-memory LRU cache of album art, shared by a [`SonosSystem`](crate::SonosSystem)
+///
+/// Obtained via [`SonosSystem::album_art_cache()`](crate::SonosSystem::album_art_cache).
+pub struct ArtCache {
+    client: SonosClient,
+    state: Mutex<CacheState>,
+    prefetch: AtomicBool,
+}
+
+impl ArtCache {
+    pub(crate) fn new(client: SonosClient) -> Self {
+        Self {
+            client,
+            state: Mutex::new(CacheState {
+                capacity: DEFAULT_CAPACITY_BYTES,
+                used: 0,
+                entries: HashMap::new(),
+                lru: VecDeque::new(),
+                inflight: HashMap::new(),
+            }),
+            prefetch: AtomicBool::new(false),
+        }
+    }
+
+    /// Set the cache budget in bytes, evicting least recently used art as needed
+    pub fn set_capacity(&self, bytes: usize) {
+        if let Ok(mut state) = self.state.lock() {
+            state.capacity = bytes;
+            state.evict();
+        }
+    }
+
+    /// Cache budget in bytes
+    pub fn capacity(&self) -> usize {
+        self.state.lock().map(|s| s.capacity).unwrap_or(0)
+    }
+
+    /// Bytes currently cached
+    pub fn used(&self) -> usize {
+        self.state.lock().map(|s| s.used).unwrap_or(0)
+    }
+
+    /// Number of cached images
+    pub fn len(&self) -> usize {
+        self.state.lock().map(|s| s.entries.len()).unwrap_or(0)
+    }
+
+    /// Returns true if nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if art for this URL is cached
+    pub fn contains(&self, url: &str) -> bool {
+        let key = art_key(url);
+        self.state
+            .lock()
+            .map(|s| s.entries.contains_key(&key))
+            .unwrap_or(false)
+    }
+
+    /// Drop every cached image
+    pub fn clear(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.entries.clear();
+            state.lru.clear();
+            state.used = 0;
+        }
+    }
+
+    /// Warm the cache whenever a watched `current_track` changes (default: off)
+    ///
+    /// Only speakers whose `current_track` is being watched produce change
+    /// events, so pair this with `speaker.current_track.watch()` or
+    /// [`SonosSystem::connect()`](crate::SonosSystem::connect).
+    pub fn prefetch_on_track_change(&self, enabled: bool) {
+        self.prefetch.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Get art by absolute URL, downloading it on a cache miss
+    pub fn get(&self, url: &str) -> Result<ArtHandle, SdkError> {
+        let key = art_key(url);
+        let (flight, leader) = {
+            let mut state = self.state.lock().map_err(|_| SdkError::LockPoisoned)?;
+            if let Some(handle) = state.entries.get(&key).cloned() {
+                state.touch(&key);
+                return Ok(handle);
+            }
+            match state.inflight.get(&key) {
+                Some(flight) => (Arc::clone(flight), false),
+                None => {
+                    let flight = Arc::new(InFlight::default());
+                    state.inflight.insert(key.clone(), Arc::clone(&flight));
+                    (flight, true)
+                }
+            }
+        };
+
+        if leader {
+            let mut leader = Leader {
+                state: &self.state,
+                key: key.clone(),
+                flight,
+                result: None,
+            };
+            let result = self
+                .client
+                .fetch_resource(url)
+                .map(|resource| ArtHandle {
+                    key: key.clone(),
+                    dimensions: image_dimensions(&resource.body),
+                    bytes: resource.body.into(),
+                    content_type: resource.content_type,
+                })
+                .map_err(|e| e.to_string());
+            leader.result = Some(result.clone());
+            return result.map_err(SdkError::FetchFailed);
+        }
+
+        let mut slot = flight.result.lock().map_err(|_| SdkError::LockPoisoned)?;
+        while slot.is_none() {
+            slot = flight
+                .ready
+                .wait(slot)
+                .map_err(|_| SdkError::LockPoisoned)?;
+        }
+        slot.clone()
+            .unwrap_or_else(|| Err("album art download abandoned".to_string()))
+            .map_err(SdkError::FetchFailed)
+    }
+
+    /// Get the art for a track; relative URIs are resolved against `speaker_addr`
+    pub(crate) fn get_for_track(
+        &self,
+        track: &CurrentTrack,
+        speaker_addr: SocketAddr,
+    ) -> Result<ArtHandle, SdkError> {
+        let uri = track
+            .album_art_uri
+            .as_deref()
+            .filter(|uri| !uri.is_empty())
+            .ok_or_else(|| SdkError::FetchFailed("track has no album art".to_string()))?;
+        self.get(&resolve_art_url(uri, speaker_addr))
+    }
+}
+
+/// Warm `art` from `current_track` change events when prefetching is enabled.
+///
+/// Holds only weak references, so the observer stored on the state manager
+/// does not keep either side alive.
+pub(crate) fn observe_track_changes(art: &Arc<ArtCache>, state_manager: &Arc<StateManager>) {
+    let art = Arc::downgrade(art);
+    let manager: Weak<StateManager> = Arc::downgrade(state_manager);
+    state_manager.add_change_observer(Arc::new(move |event: &ChangeEvent| {
+        if event.property_key != CurrentTrack::KEY {
+            return;
+        }
+        let (Some(art), Some(manager)) = (art.upgrade(), manager.upgrade()) else {
+            return;
+        };
+        if !art.prefetch.load(Ordering::Relaxed) {
+            return;
+        }
+        let speaker_id = event.speaker_id.clone();
+        let (Some(track), Some(addr)) = (
+            manager.get_property::<CurrentTrack>(&speaker_id),
+            manager.get_speaker_addr(&speaker_id),
+        ) else {
+            return;
+        };
+        // Observers run on the event worker thread; download elsewhere
+        thread::spawn(move || {
+            if let Err(e) = art.get_for_track(&track, addr) {
+                tracing::debug!("album art prefetch failed for {}: {}", speaker_id, e);
+            }
+        });
+    }));
+}
+
+/// Turn a relative `/getaa?...` URI into an absolute URL on the speaker
+fn resolve_art_url(uri: &str, speaker_addr: SocketAddr) -> String {
+    if uri.starts_with("http://") || uri.starts_with("https://") {
+        uri.to_string()
+    } else {
+        let path = uri.trim_start_matches('/');
+        format!("http://{speaker_addr}/{path}")
+    }
+}
+
+/// Normalize an art URL into a cache key.
+///
+/// Speaker-served `/getaa` art is keyed by its `u` (track URI) parameter, so
+/// every speaker shares one entry. Other URLs drop the scheme and volatile
+/// query parameters and sort the rest.
+fn art_key(url: &str) -> String {
+    let without_scheme = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))
+        .unwrap_or(url);
+    let (location, query) = without_scheme
+        .split_once('?')
+        .unwrap_or((without_scheme, ""));
+    let (host, path) = location.split_once('/').unwrap_or((location, ""));
+    let path = path.trim_end_matches('/');
+
+    let params = query.split('&').filter(|p| !p.is_empty());
+    if path == "getaa" {
+        if let Some(track) = params.clone().find_map(|p| p.strip_prefix("u=")) {
+            return format!("getaa:{track}");
+        }
+    }
+
+    let mut kept: Vec<&str> = params
+        .filter(|p| {
+            let name = p.split('=').next().unwrap_or_default().to_ascii_lowercase();
+            !VOLATILE_PARAMS.contains(&name.as_str()) && !name.starts_with("x-amz-")
+        })
+        .collect();
+    kept.sort_unstable();
+
+    let mut key = format!("{}/{}", host.to_ascii_lowercase(), path);
+    if !kept.is_empty() {
+        key.push('?');
+        key.push_str(&kept.join("&"));
+    }
+    key
+}
+
+/// Read `(width, height)` from a PNG, GIF or JPEG header
+fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be32 = |b: &[u8]| u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+    let be16 = |b: &[u8]| u16::from_be_bytes([b[0], b[1]]) as u32;
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") && bytes.len() >= 24 {
+        return Some((be32(&bytes[16..20]), be32(&bytes[20..24])));
+    }
+    if (bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a")) && bytes.len() >= 10 {
+        let le16 = |b: &[u8]| u16::from_le_bytes([b[0], b[1]]) as u32;
+        return Some((le16(&bytes[6..8]), le16(&bytes[8..10])));
+    }
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        // Walk JPEG segments until a start-of-frame marker
+        let mut i = 2;
+        while i + 9 <= bytes.len() {
+            if bytes[i] != 0xFF {
+                return None;
+            }
+            let marker = bytes[i + 1];
+            let len = be16(&bytes[i + 2..i + 4]) as usize;
+            let is_sof = matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+            if is_sof {
+                return Some((be16(&bytes[i + 7..i + 9]), be16(&bytes[i + 5..i + 7])));
+            }
+            i += 2 + len;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::{Duration, Instant};
+
+    use sonos_state::SpeakerId;
+
+    /// PNG signature and IHDR header, followed by `pad` zero bytes
+    fn png(width: u32, height: u32, pad: usize) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.resize(bytes.len() + pad, 0);
+        bytes
+    }
+
+    /// Serve `body` for every GET after `delay`; returns (base URL, request counter)
+    fn serve_art(body: Vec<u8>, delay: Duration) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hits);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let body = body.clone();
+                let counter = Arc::clone(&counter);
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                        line.clear();
+                    }
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    thread::sleep(delay);
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = stream.write_all(&body);
+                });
+            }
+        });
+        (base, hits)
+    }
+
+    #[test]
+    fn test_art_key_normalization() {
+        assert_eq!(
+            art_key("http://192.168.1.10:1400/getaa?s=1&u=x-sonos-spotify%3atrack"),
+            art_key("http://192.168.1.11:1400/getaa?u=x-sonos-spotify%3atrack&s=1"),
+        );
+        assert_eq!(
+            art_key("https://CDN.example.com/a/b.jpg?size=640&token=abc&Expires=1"),
+            "cdn.example.com/a/b.jpg?size=640"
+        );
+        assert_ne!(
+            art_key("https://cdn.example.com/a.jpg?size=640"),
+            art_key("https://cdn.example.com/a.jpg?size=300")
+        );
+    }
+
+    #[test]
+    fn test_image_dimensions() {
+        assert_eq!(image_dimensions(&png(300, 200, 0)), Some((300, 200)));
+        assert_eq!(
+            image_dimensions(b"GIF89a\x40\x01\xc8\x00"),
+            Some((320, 200))
+        );
+        let jpeg = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, // APP0
+            0xFF, 0xC0, 0x00, 0x11, 0x08, 0x01, 0xE0, 0x02, 0x80, // SOF0 640x480
+        ];
+        assert_eq!(image_dimensions(&jpeg), Some((640, 480)));
+        assert_eq!(image_dimensions(b"not an image"), None);
+    }
+
+    #[test]
+    fn test_cache_hit_and_concurrent_dedup() {
+        let (base, hits) = serve_art(png(300, 200, 0), Duration::from_millis(100));
+        let cache = Arc::new(ArtCache::new(SonosClient::new()));
+        let url = format!("{base}/art.png?token=one");
+
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                let url = url.clone();
+                thread::spawn(move || cache.get(&url).unwrap())
+            })
+            .collect();
+        for worker in workers {
+            let handle = worker.join().unwrap();
+            assert_eq!(handle.content_type(), Some("image/png"));
+            assert_eq!(handle.dimensions(), Some((300, 200)));
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // A different volatile token maps to the same entry
+        cache.get(&format!("{base}/art.png?token=two")).unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_panicked_download_releases_waiters() {
+        let (base, hits) = serve_art(png(1, 1, 0), Duration::ZERO);
+        let cache = Arc::new(ArtCache::new(SonosClient::new()));
+        let url = format!("{base}/art.png");
+        let key = art_key(&url);
+        let flight = Arc::new(InFlight::default());
+        cache
+            .state
+            .lock()
+            .unwrap()
+            .inflight
+            .insert(key.clone(), Arc::clone(&flight));
+
+        let waiter = {
+            let (cache, url) = (Arc::clone(&cache), url.clone());
+            thread::spawn(move || cache.get(&url))
+        };
+        let started = Instant::now();
+        while Arc::strong_count(&flight) < 3 {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "waiter never joined"
+            );
+            thread::sleep(Duration::from_millis(5));
+        }
+        let leader = {
+            let (cache, flight) = (Arc::clone(&cache), Arc::clone(&flight));
+            thread::spawn(move || {
+                let _leader = Leader {
+                    state: &cache.state,
+                    key,
+                    flight,
+                    result: None,
+                };
+                panic!("download panicked");
+            })
+        };
+        assert!(leader.join().is_err());
+
+        let err = waiter.join().unwrap().unwrap_err();
+        assert!(matches!(err, SdkError::FetchFailed(_)), "{err:?}");
+        assert!(cache.state.lock().unwrap().inflight.is_empty());
+
+        // The next request downloads afresh
+        cache.get(&url).unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_lru_eviction_order() {
+        let (base, hits) = serve_art(png(1, 1, 100), Duration::ZERO);
+        let cache = ArtCache::new(SonosClient::new());
+        let size = png(1, 1, 100).len();
+        cache.set_capacity(size * 2);
+
+        let a = format!("{base}/a.png");
+        let b = format!("{base}/b.png");
+        let c = format!("{base}/c.png");
+        cache.get(&a).unwrap();
+        cache.get(&b).unwrap();
+        cache.get(&a).unwrap(); // a is now most recently used
+        cache.get(&c).unwrap(); // evicts b
+
+        assert!(cache.contains(&a) && cache.contains(&c));
+        assert!(!cache.contains(&b));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.used(), size * 2);
+    }
+
+    #[test]
+    fn test_prefetch_on_track_change() {
+        let (base, hits) = serve_art(png(10, 10, 0), Duration::ZERO);
+        let manager = Arc::new(StateManager::new().unwrap());
+        manager
+            .add_devices(vec![sonos_discovery::Device {
+                id: "RINCON_111".to_string(),
+                name: "Kitchen".to_string(),
+                room_name: "Kitchen".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                port: 1400,
+                model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
+            }])
+            .unwrap();
+        let cache = Arc::new(ArtCache::new(SonosClient::new()));
+        observe_track_changes(&cache, &manager);
+        cache.prefetch_on_track_change(true);
+
+        let speaker_id = SpeakerId::new("RINCON_111");
+        manager.register_watch(&speaker_id, CurrentTrack::KEY);
+        let url = format!("{base}/cover.png");
+        manager.set_property(
+            &speaker_id,
+            CurrentTrack {
+                album_art_uri: Some(url.clone()),
+                ..CurrentTrack::new()
+            },
+        );
+
+        let started = Instant::now();
+        while !cache.contains(&url) && started.elapsed() < Duration::from_secs(2) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(cache.contains(&url));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,434 @@
+//! Discovery-driven speaker lifecycle via [`SonosSystem::auto_subscribe()`](crate::SonosSystem::auto_subscribe)
+//!
+//! A worker thread applies [`DeviceEvent`]s from a continuous discovery
+//! source: new speakers are registered, prefetched and subscribed, lost
+//! speakers go offline (and lose their subscriptions after a grace period),
+//! and moved speakers are migrated to their new address. Speakers that keep
+//! dropping off and coming back are dampened with exponential backoff.
+//! Grace periods and backoff run on the system's clock
+//! ([`ConnectOptions::clock()`](crate::ConnectOptions::clock)).
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{mpsc, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sonos_api::clock::SharedClock;
+use sonos_discovery::{Device, DeviceEvent};
+use sonos_state::SpeakerId;
+
+use crate::SonosSystem;
+
+/// How often an idle worker checks whether the system is still alive, and
+/// the longest it goes without reading the clock
+const IDLE_TICK: Duration = Duration::from_secs(1);
+
+/// Reachability of a speaker, as reported by [`SonosSystem::presence()`].
+///
+/// Changes are announced on [`SonosSystem::iter()`] as a
+/// [`ChangeEvent`](sonos_state::ChangeEvent) with
+/// `property_key == Presence::EVENT_KEY`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Online,
+    Offline,
+}
+
+impl Presence {
+    /// Property key of the change event emitted when a speaker goes online or offline
+    pub const EVENT_KEY: &'static str = "presence";
+
+    /// Property key of the change event emitted when a speaker moves to a new address
+    pub const ADDRESS_EVENT_KEY: &'static str = "address";
+}
+
+/// Options for [`SonosSystem::auto_subscribe()`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let options = AutoSubscribeOptions::default()
+///     .teardown_after(Some(Duration::from_secs(60)))
+///     .backoff(Duration::from_secs(5), Duration::from_secs(300));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AutoSubscribeOptions {
+    pub(crate) prefetch: bool,
+    pub(crate) subscribe: bool,
+    pub(crate) prefetch_timeout: Duration,
+    pub(crate) teardown_after: Option<Duration>,
+    pub(crate) backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) flap_window: Duration,
+}
+
+impl Default for AutoSubscribeOptions {
+    fn default() -> Self {
+        Self {
+            prefetch: true,
+            subscribe: true,
+            prefetch_timeout: Duration::from_secs(5),
+            teardown_after: Some(Duration::from_secs(30)),
+            backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(120),
+            flap_window: Duration::from_secs(60),
+        }
+    }
+}
+
+impl AutoSubscribeOptions {
+    /// Fetch volume, mute, playback state and current track when a speaker
+    /// appears or comes back (default: `true`)
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Time budget for each prefetch (default: 5s)
+    pub fn prefetch_timeout(mut self, timeout: Duration) -> Self {
+        self.prefetch_timeout = timeout;
+        self
+    }
+
+    /// Watch the NowPlaying profile on speakers that appear (default: `true`)
+    pub fn subscribe(mut self, subscribe: bool) -> Self {
+        self.subscribe = subscribe;
+        self
+    }
+
+    /// Drop a lost speaker's profile watches after this long offline;
+    /// `None` keeps them (default: 30s)
+    pub fn teardown_after(mut self, grace: Option<Duration>) -> Self {
+        self.teardown_after = grace;
+        self
+    }
+
+    /// Delay for the second return within the flap window, doubling for
+    /// each further return up to `max` (default: 2s, 120s)
+    pub fn backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.backoff = base;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Returns further apart than this reset the backoff (default: 60s)
+    pub fn flap_window(mut self, window: Duration) -> Self {
+        self.flap_window = window;
+        self
+    }
+}
+
+enum Message {
+    Event(DeviceEvent),
+    Stop,
+}
+
+/// Keeps [`SonosSystem::auto_subscribe()`] running; dropping it stops the worker.
+///
+/// Speakers registered and watches taken so far stay in place.
+#[must_use = "dropping the handle stops auto-subscribe"]
+pub struct AutoSubscribe {
+    tx: mpsc::Sender<Message>,
+}
+
+impl Drop for AutoSubscribe {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Message::Stop);
+    }
+}
+
+pub(crate) fn spawn<I>(
+    system: Weak<SonosSystem>,
+    events: I,
+    options: AutoSubscribeOptions,
+    clock: SharedClock,
+) -> AutoSubscribe
+where
+    I: Iterator<Item = DeviceEvent> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    let feeder = tx.clone();
+    thread::spawn(move || {
+        for event in events {
+            if feeder.send(Message::Event(event)).is_err() {
+                break;
+            }
+        }
+    });
+    thread::spawn(move || Worker::new(system, options, clock).run(rx));
+    AutoSubscribe { tx }
+}
+
+/// Canonical ID of the device an event is about
+///
+/// `Found`/`Updated` carry the UDN (`uuid:RINCON_…`) while `Lost` carries
+/// the bare ID, so events are only ever matched through [`SpeakerId`].
+fn device_id(event: &DeviceEvent) -> SpeakerId {
+    match event {
+        DeviceEvent::Found(device) | DeviceEvent::Updated(device) => SpeakerId::new(&device.id),
+        DeviceEvent::Lost(id) => SpeakerId::new(id),
+    }
+}
+
+/// Per-device flap tracking
+#[derive(Debug, Default)]
+struct Flaps {
+    /// Consecutive returns, each within the flap window of the previous one
+    streak: u32,
+    last_return: Option<Instant>,
+    held_until: Option<Instant>,
+    /// Latest event received while held
+    pending: Option<DeviceEvent>,
+}
+
+/// Holds back returns of devices that keep dropping off the network
+#[derive(Debug)]
+struct Damper {
+    backoff: Duration,
+    max_backoff: Duration,
+    window: Duration,
+    devices: HashMap<SpeakerId, Flaps>,
+}
+
+impl Damper {
+    fn new(options: &AutoSubscribeOptions) -> Self {
+        Self {
+            backoff: options.backoff,
+            max_backoff: options.max_backoff,
+            window: options.flap_window,
+            devices: HashMap::new(),
+        }
+    }
+
+    /// Pass `event` through, or hold it if the device is backing off.
+    ///
+    /// `offline` says whether the device is currently offline, which makes
+    /// a `Found` or `Updated` a return.
+    fn admit(&mut self, event: DeviceEvent, offline: bool, now: Instant) -> Option<DeviceEvent> {
+        let flaps = self.devices.entry(device_id(&event)).or_default();
+        if flaps.held_until.is_some_and(|until| now < until) {
+            flaps.pending = Some(event);
+            return None;
+        }
+        if !offline || matches!(event, DeviceEvent::Lost(_)) {
+            return Some(event);
+        }
+
+        let recent = flaps
+            .last_return
+            .is_some_and(|last| now.duration_since(last) < self.window);
+        flaps.streak = if recent { flaps.streak + 1 } else { 1 };
+        flaps.last_return = Some(now);
+        if flaps.streak < 2 {
+            return Some(event);
+        }
+        let doublings = (flaps.streak - 2).min(16);
+        let delay = self
+            .backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff);
+        flaps.held_until = Some(now + delay);
+        flaps.pending = Some(event);
+        None
+    }
+
+    /// Events whose backoff has ended, to be applied as-is
+    fn release(&mut self, now: Instant) -> Vec<DeviceEvent> {
+        let mut released = Vec::new();
+        for flaps in self.devices.values_mut() {
+            if flaps.held_until.is_some_and(|until| until <= now) {
+                flaps.held_until = None;
+                released.extend(flaps.pending.take());
+            }
+        }
+        released
+    }
+
+    fn next_release(&self) -> Option<Instant> {
+        self.devices.values().filter_map(|f| f.held_until).min()
+    }
+}
+
+struct Worker {
+    system: Weak<SonosSystem>,
+    options: AutoSubscribeOptions,
+    damper: Damper,
+    /// Lost speakers whose watches are dropped at the given time
+    teardowns: HashMap<SpeakerId, Instant>,
+    clock: SharedClock,
+}
+
+impl Worker {
+    fn new(system: Weak<SonosSystem>, options: AutoSubscribeOptions, clock: SharedClock) -> Self {
+        Self {
+            damper: Damper::new(&options),
+            system,
+            options,
+            teardowns: HashMap::new(),
+            clock,
+        }
+    }
+
+    fn run(mut self, rx: mpsc::Receiver<Message>) {
+        loop {
+            let now = self.clock.now();
+            let next_timer = self
+                .damper
+                .next_release()
+                .into_iter()
+                .chain(self.teardowns.values().copied())
+                .min();
+            let wait = next_timer.map_or(IDLE_TICK, |at| at.saturating_duration_since(now));
+            let message = rx.recv_timeout(wait.min(IDLE_TICK));
+
+            let Some(system) = self.system.upgrade() else {
+                return;
+            };
+            match message {
+                Ok(Message::Event(event)) => {
+                    let offline = system
+                        .presence(&device_id(&event))
+                        .is_some_and(|p| p == Presence::Offline);
+                    if let Some(event) = self.damper.admit(event, offline, self.clock.now()) {
+                        self.apply(&system, event);
+                    }
+                }
+                Ok(Message::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+
+            let now = self.clock.now();
+            for event in self.damper.release(now) {
+                self.apply(&system, event);
+            }
+            let due: Vec<SpeakerId> = self
+                .teardowns
+                .iter()
+                .filter(|(_, at)| **at <= now)
+                .map(|(id, _)| id.clone())
+                .collect();
+            for speaker_id in due {
+                self.teardowns.remove(&speaker_id);
+                if system.unwatch_profile(&speaker_id) {
+                    tracing::debug!("dropped subscriptions of offline speaker {}", speaker_id);
+                }
+            }
+        }
+    }
+
+    fn apply(&mut self, system: &SonosSystem, event: DeviceEvent) {
+        match event {
+            DeviceEvent::Found(device) | DeviceEvent::Updated(device) => {
+                self.present(system, &device)
+            }
+            DeviceEvent::Lost(id) => {
+                let speaker_id = SpeakerId::new(id);
+                if system.speaker_by_id(&speaker_id).is_none() {
+                    return;
+                }
+                if system.set_presence(&speaker_id, Presence::Offline) {
+                    if let Some(grace) = self.options.teardown_after {
+                        self.teardowns.insert(speaker_id, self.clock.now() + grace);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bring a found or moved device online, registering it if unknown
+    fn present(&mut self, system: &SonosSystem, device: &Device) {
+        if !system.in_household(device) {
+            tracing::debug!("ignoring {} from another household", device.id);
+            return;
+        }
+        let speaker_id = SpeakerId::new(&device.id);
+        let Ok(ip) = device.ip_address.parse() else {
+            tracing::warn!(
+                "ignoring {} with invalid IP {}",
+                device.id,
+                device.ip_address
+            );
+            return;
+        };
+        let addr = SocketAddr::new(ip, device.port);
+        self.teardowns.remove(&speaker_id);
+
+        let (speaker, returned) = match system.speaker_by_id(&speaker_id) {
+            Some(known) => {
+                let speaker = system.migrate_speaker(&speaker_id, addr).unwrap_or(known);
+                (speaker, system.set_presence(&speaker_id, Presence::Online))
+            }
+            None => match system.register_device(device) {
+                Ok(speaker) => (speaker, true),
+                Err(e) => {
+                    tracing::warn!("failed to register {}: {}", device.id, e);
+                    return;
+                }
+            },
+        };
+        if !returned {
+            return;
+        }
+        if self.options.prefetch {
+            system.prefetch_speaker(&speaker, self.options.prefetch_timeout);
+        }
+        if self.options.subscribe {
+            if let Err(e) = system.watch_profile(&speaker) {
+                tracing::warn!("subscribing {} failed: {}", speaker_id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found() -> DeviceEvent {
+        DeviceEvent::Found(Device {
+            id: "uuid:RINCON_A".to_string(),
+            name: "Patio".to_string(),
+            room_name: "Patio".to_string(),
+            ip_address: "192.168.1.50".to_string(),
+            port: 1400,
+            model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
+        })
+    }
+
+    #[test]
+    fn test_damper_backs_off_repeated_returns() {
+        let options = AutoSubscribeOptions::default()
+            .backoff(Duration::from_secs(1), Duration::from_secs(3))
+            .flap_window(Duration::from_secs(10));
+        let mut damper = Damper::new(&options);
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        // First return passes; the second is held for the base backoff
+        assert!(damper.admit(found(), true, at(0)).is_some());
+        assert!(damper.admit(found(), true, at(1)).is_none());
+        assert_eq!(damper.next_release(), Some(at(2)));
+
+        // Events while held replace the pending one and come out together
+        assert!(damper
+            .admit(DeviceEvent::Lost("RINCON_A".into()), false, at(1))
+            .is_none());
+        assert!(damper.release(at(1)).is_empty());
+        let released = damper.release(at(2));
+        assert!(matches!(released[..], [DeviceEvent::Lost(_)]));
+
+        // Backoff doubles, then caps
+        assert!(damper.admit(found(), true, at(3)).is_none());
+        assert_eq!(damper.next_release(), Some(at(5)));
+        damper.release(at(5));
+        assert!(damper.admit(found(), true, at(6)).is_none());
+        assert_eq!(damper.next_release(), Some(at(9)));
+
+        // A quiet flap window resets the streak
+        damper.release(at(9));
+        assert!(damper.admit(found(), true, at(30)).is_some());
+    }
+}
@@ -0,0 +1,282 @@
+//! Build and device compatibility information for bug reports
+//!
+//! [`build_info()`] reports the workspace crate versions this binary was
+//! built with. [`SonosSystem::compatibility_report()`](crate::SonosSystem::compatibility_report)
+//! combines it with the model, firmware and software generation of every
+//! registered device and the per-device workarounds the SDK applies. The
+//! report serializes with serde and prints as a table.
+
+use std::fmt;
+
+use serde::Serialize;
+use sonos_state::{SpeakerId, SpeakerInfo};
+
+include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
+
+/// Workspace crate versions captured at build time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    /// Version of the `sonos-sdk` crate
+    pub sdk_version: &'static str,
+    /// Resolved workspace crates in this build, `sonos-sdk` first
+    pub crates: Vec<CrateVersion>,
+}
+
+/// A workspace crate and the version it resolved to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrateVersion {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+/// Get the versions of the sonos-sdk workspace crates in this build
+///
+/// # Example
+///
+/// ```rust
+/// let info = sonos_sdk::build_info();
+/// assert_eq!(info.sdk_version, env!("CARGO_PKG_VERSION"));
+/// println!("{info}");
+/// ```
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        sdk_version: env!("CARGO_PKG_VERSION"),
+        crates: CRATE_VERSIONS
+            .iter()
+            .map(|&(name, version)| CrateVersion { name, version })
+            .collect(),
+    }
+}
+
+impl fmt::Display for BuildInfo {
+    /// `sonos-sdk 0.5.2 (sonos-api 0.5.2, sonos-sdk-state 0.5.2, ...)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sonos-sdk {}", self.sdk_version)?;
+        let others: Vec<String> = self
+            .crates
+            .iter()
+            .filter(|c| c.name != "sonos-sdk")
+            .map(|c| format!("{} {}", c.name, c.version))
+            .collect();
+        if !others.is_empty() {
+            write!(f, " ({})", others.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A per-device workaround the SDK applies
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "quirk", rename_all = "snake_case")]
+pub enum Quirk {
+    /// Invisible home-theater satellite (sub or surround). It is hidden from
+    /// `speakers()`, and its settings are routed to the bond primary, or
+    /// rejected while the primary is unknown.
+    BondedSatellite { primary: Option<SpeakerId> },
+    /// Room name shared with another speaker; `speaker()` resolves the
+    /// disambiguated `label` to this device.
+    NameCollision { label: String },
+    /// Model that stalls subscription requests on reused keep-alive
+    /// connections; each one goes over a fresh connection, whatever the
+    /// broker's `eventing_connection`.
+    CloseEventingConnections,
+}
+
+impl fmt::Display for Quirk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quirk::BondedSatellite {
+                primary: Some(primary),
+            } => write!(f, "satellite of {}", primary.as_str()),
+            Quirk::BondedSatellite { primary: None } => write!(f, "satellite, primary unknown"),
+            Quirk::NameCollision { label } => write!(f, "shared name, use \"{label}\""),
+            Quirk::CloseEventingConnections => write!(f, "fresh connection per subscription"),
+        }
+    }
+}
+
+/// ZonePlayer models that leave a SUBSCRIBE on a reused connection
+/// unanswered, by model name without the `Sonos ` prefix
+const CLOSE_EVENTING_MODELS: &[&str] = &["ZP80", "ZP90", "ZP100", "ZP120"];
+
+/// Whether `model` needs [`Quirk::CloseEventingConnections`]
+pub(crate) fn closes_eventing_connections(model: &str) -> bool {
+    let model = model.trim();
+    let model = model
+        .get(..6)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("sonos "))
+        .map_or(model, |_| &model[6..]);
+    CLOSE_EVENTING_MODELS
+        .iter()
+        .any(|known| known.eq_ignore_ascii_case(model))
+}
+
+/// Model, firmware and applied quirks of one registered device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeviceCompatibility {
+    pub speaker_id: SpeakerId,
+    pub name: String,
+    pub model: String,
+    /// Firmware version from topology; `"unknown"` until topology is fetched
+    pub firmware: String,
+    /// Software generation (1 = S1, 2 = S2), if reported
+    pub generation: Option<u8>,
+    pub quirks: Vec<Quirk>,
+}
+
+impl DeviceCompatibility {
+    pub(crate) fn new(info: SpeakerInfo, quirks: Vec<Quirk>) -> Self {
+        Self {
+            speaker_id: info.id,
+            name: info.name,
+            model: info.model_name,
+            firmware: info.software_version,
+            generation: info.software_generation,
+            quirks,
+        }
+    }
+}
+
+/// SDK build and device compatibility matrix for bug reports
+///
+/// Serialize it (e.g. with `serde_json`) to attach to an issue, or print it
+/// for a table:
+///
+/// ```text
+/// sonos-sdk 0.5.2 (sonos-api 0.5.2, ...)
+///
+/// NAME         MODEL      FIRMWARE    GEN  ID        QUIRKS
+/// Living Room  Sonos Arc  85.0-64200  S2   RINCON_A  -
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompatibilityReport {
+    pub build: BuildInfo,
+    /// Every registered device, sorted by name then ID
+    pub devices: Vec<DeviceCompatibility>,
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.build)?;
+        writeln!(f)?;
+
+        let header = ["NAME", "MODEL", "FIRMWARE", "GEN", "ID", "QUIRKS"].map(str::to_string);
+        let rows: Vec<[String; 6]> = std::iter::once(header)
+            .chain(self.devices.iter().map(|device| {
+                let generation = match device.generation {
+                    Some(g @ (1 | 2)) => format!("S{g}"),
+                    Some(g) => g.to_string(),
+                    None => "?".to_string(),
+                };
+                let quirks = if device.quirks.is_empty() {
+                    "-".to_string()
+                } else {
+                    let quirks: Vec<String> = device.quirks.iter().map(Quirk::to_string).collect();
+                    quirks.join("; ")
+                };
+                [
+                    device.name.clone(),
+                    device.model.clone(),
+                    device.firmware.clone(),
+                    generation,
+                    device.speaker_id.as_str().to_string(),
+                    quirks,
+                ]
+            }))
+            .collect();
+
+        // Every column but the last is padded to its widest cell
+        let mut widths = [0; 5];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for row in &rows {
+            for (cell, width) in row.iter().zip(widths) {
+                write!(f, "{cell:<width$}  ")?;
+            }
+            writeln!(f, "{}", row[5])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_build() -> BuildInfo {
+        BuildInfo {
+            sdk_version: "1.2.3",
+            crates: vec![
+                CrateVersion {
+                    name: "sonos-sdk",
+                    version: "1.2.3",
+                },
+                CrateVersion {
+                    name: "sonos-api",
+                    version: "1.2.0",
+                },
+            ],
+        }
+    }
+
+    fn fixture_device(
+        id: &str,
+        name: &str,
+        generation: Option<u8>,
+        quirks: Vec<Quirk>,
+    ) -> DeviceCompatibility {
+        DeviceCompatibility {
+            speaker_id: SpeakerId::new(id),
+            name: name.to_string(),
+            model: "Sonos Arc".to_string(),
+            firmware: "85.0-64200".to_string(),
+            generation,
+            quirks,
+        }
+    }
+
+    #[test]
+    fn test_build_info_lists_this_crate_first() {
+        let info = build_info();
+        assert_eq!(info.crates[0].name, "sonos-sdk");
+        assert_eq!(info.crates[0].version, info.sdk_version);
+        assert!(info.crates.iter().any(|c| c.name == "sonos-api"));
+    }
+
+    #[test]
+    fn test_report_table_formatting_is_stable() {
+        let report = CompatibilityReport {
+            build: fixture_build(),
+            devices: vec![
+                fixture_device("RINCON_ARC", "Living Room", Some(2), vec![]),
+                fixture_device(
+                    "RINCON_SUB",
+                    "Living Room Sub",
+                    None,
+                    vec![Quirk::BondedSatellite {
+                        primary: Some(SpeakerId::new("RINCON_ARC")),
+                    }],
+                ),
+            ],
+        };
+
+        assert_eq!(
+            report.to_string(),
+            "sonos-sdk 1.2.3 (sonos-api 1.2.0)\n\
+             \n\
+             NAME             MODEL      FIRMWARE    GEN  ID          QUIRKS\n\
+             Living Room      Sonos Arc  85.0-64200  S2   RINCON_ARC  -\n\
+             Living Room Sub  Sonos Arc  85.0-64200  ?    RINCON_SUB  satellite of RINCON_ARC\n"
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["build"]["crates"][1]["name"], "sonos-api");
+        assert_eq!(
+            json["devices"][1]["quirks"][0],
+            serde_json::json!({"quirk": "bonded_satellite", "primary": "RINCON_ARC"})
+        );
+    }
+}
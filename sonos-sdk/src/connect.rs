@@ -0,0 +1,240 @@
+//! Zero-configuration startup via [`SonosSystem::connect()`](crate::SonosSystem::connect)
+//!
+//! `connect()` bundles the usual startup sequence — discovery, registration,
+//! prefetching the basic property set and subscribing to the NowPlaying
+//! profile — behind a single call with a total deadline. Devices that fail
+//! any step are reported as degraded instead of failing the whole call.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sonos_api::clock::{SharedClock, SystemClock};
+use sonos_api::DeviceTransport;
+use sonos_state::SpeakerId;
+
+use crate::HouseholdSelection;
+
+#[cfg(feature = "test-support")]
+use sonos_discovery::Device;
+
+/// Progress callback invoked from the thread running `connect()`.
+type ProgressCallback = Arc<dyn Fn(ConnectProgress) + Send + Sync>;
+
+/// Startup phase reported to [`ConnectOptions::on_progress()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectProgress {
+    /// Discovery (cache or SSDP) has started
+    DiscoveryStarted,
+    /// Discovery finished with this many devices
+    DeviceFound(usize),
+    /// A device finished prefetching (successfully or not)
+    Prefetching { done: usize, total: usize },
+    /// A device finished subscribing to the NowPlaying profile
+    Subscribing { done: usize, total: usize },
+    /// Startup finished; see the [`ConnectReport`] for per-device results
+    Ready,
+}
+
+/// Options for [`SonosSystem::connect()`](crate::SonosSystem::connect).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let options = ConnectOptions::default()
+///     .deadline(Duration::from_secs(5))
+///     .on_progress(|p| println!("{p:?}"));
+/// let (system, report) = SonosSystem::connect(options)?;
+/// ```
+#[derive(Clone)]
+pub struct ConnectOptions {
+    pub(crate) use_cache: bool,
+    pub(crate) discovery_timeout: Duration,
+    pub(crate) deadline: Duration,
+    pub(crate) prefetch: bool,
+    pub(crate) subscribe: bool,
+    pub(crate) on_progress: Option<ProgressCallback>,
+    pub(crate) household: HouseholdSelection,
+    pub(crate) clock: SharedClock,
+    pub(crate) transports: Vec<(String, DeviceTransport)>,
+    pub(crate) stable_id_file: Option<PathBuf>,
+    #[cfg(feature = "test-support")]
+    pub(crate) devices: Option<Vec<Device>>,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            use_cache: true,
+            discovery_timeout: Duration::from_secs(3),
+            deadline: Duration::from_secs(10),
+            prefetch: true,
+            subscribe: true,
+            on_progress: None,
+            household: HouseholdSelection::default(),
+            clock: Arc::new(SystemClock),
+            transports: Vec::new(),
+            stable_id_file: None,
+            #[cfg(feature = "test-support")]
+            devices: None,
+        }
+    }
+}
+
+impl ConnectOptions {
+    /// Use the on-disk discovery cache (default: `true`)
+    pub fn use_cache(mut self, use_cache: bool) -> Self {
+        self.use_cache = use_cache;
+        self
+    }
+
+    /// SSDP search window (default: 3s, capped by the deadline)
+    pub fn discovery_timeout(mut self, timeout: Duration) -> Self {
+        self.discovery_timeout = timeout;
+        self
+    }
+
+    /// Total time budget for the whole startup (default: 10s)
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Fetch volume, mute, playback state and current track (default: `true`)
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Watch the NowPlaying profile on every ready speaker (default: `true`)
+    pub fn subscribe(mut self, subscribe: bool) -> Self {
+        self.subscribe = subscribe;
+        self
+    }
+
+    /// Household to manage when the LAN has several (default: the one with
+    /// the most devices)
+    pub fn household(mut self, household: HouseholdSelection) -> Self {
+        self.household = household;
+        self
+    }
+
+    /// Time source for subscription renewal, polling, event timeouts,
+    /// change timestamps and `auto_subscribe()` timers (default: the system
+    /// clock)
+    ///
+    /// Tests pass a [`ManualClock`](crate::ManualClock) to step
+    /// all of them through virtual time together. The `deadline` budget is
+    /// always measured in real time.
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Reach the speaker at `ip` through `transport`, e.g. HTTPS accepting
+    /// only the certificate with a known fingerprint
+    ///
+    /// Speakers that discovery reports as secure (HTTPS only) and that have
+    /// no transport of their own are reached over HTTPS, accepting their
+    /// self-signed certificate.
+    pub fn device_transport(mut self, ip: impl Into<String>, transport: DeviceTransport) -> Self {
+        self.transports.push((ip.into(), transport));
+        self
+    }
+
+    /// Keep a stable ID for each subscription in `path`, so activity
+    /// entries name the same subscription across restarts and SID changes
+    /// (default: none)
+    pub fn stable_id_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.stable_id_file = Some(path.into());
+        self
+    }
+
+    /// Receive [`ConnectProgress`] updates while connecting
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(ConnectProgress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Skip discovery and use these devices instead.
+    ///
+    /// Only available when the `test-support` feature is enabled.
+    #[cfg(feature = "test-support")]
+    pub fn devices(mut self, devices: Vec<Device>) -> Self {
+        self.devices = Some(devices);
+        self
+    }
+
+    pub(crate) fn report(&self, progress: ConnectProgress) {
+        if let Some(callback) = &self.on_progress {
+            callback(progress);
+        }
+    }
+}
+
+impl fmt::Debug for ConnectOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectOptions")
+            .field("use_cache", &self.use_cache)
+            .field("discovery_timeout", &self.discovery_timeout)
+            .field("deadline", &self.deadline)
+            .field("prefetch", &self.prefetch)
+            .field("subscribe", &self.subscribe)
+            .field("on_progress", &self.on_progress.is_some())
+            .field("household", &self.household)
+            .field("clock", &self.clock)
+            .field("transports", &self.transports)
+            .finish()
+    }
+}
+
+/// Whether a device completed startup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Readiness {
+    /// Prefetch and subscription succeeded (or were disabled)
+    Ready,
+    /// A startup step failed; the speaker is registered but may have no
+    /// cached values or live events. The string describes the failure.
+    Degraded(String),
+}
+
+/// Startup result for one speaker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceReadiness {
+    pub speaker_id: SpeakerId,
+    pub name: String,
+    pub readiness: Readiness,
+}
+
+impl DeviceReadiness {
+    /// Returns true if the speaker completed every startup step.
+    pub fn is_ready(&self) -> bool {
+        self.readiness == Readiness::Ready
+    }
+}
+
+/// Per-device readiness returned by [`SonosSystem::connect()`](crate::SonosSystem::connect).
+#[derive(Debug, Clone)]
+pub struct ConnectReport {
+    /// One entry per registered speaker, sorted by name
+    pub devices: Vec<DeviceReadiness>,
+    /// Wall-clock time spent in `connect()`
+    pub elapsed: Duration,
+}
+
+impl ConnectReport {
+    /// Returns true if no speaker is degraded.
+    pub fn is_fully_ready(&self) -> bool {
+        self.devices.iter().all(DeviceReadiness::is_ready)
+    }
+
+    /// Iterate over speakers that failed a startup step.
+    pub fn degraded(&self) -> impl Iterator<Item = &DeviceReadiness> {
+        self.devices.iter().filter(|d| !d.is_ready())
+    }
+}
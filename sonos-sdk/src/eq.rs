@@ -0,0 +1,133 @@
+//! EQ presets applied as one action
+//!
+//! See [`Speaker::apply_eq()`](crate::Speaker::apply_eq).
+
+use std::fmt;
+
+use sonos_api::operation::ValidationError;
+use sonos_api::services::rendering_control;
+
+use crate::SdkError;
+
+/// EQ values for [`Speaker::apply_eq()`](crate::Speaker::apply_eq)
+///
+/// `None` leaves a setting as it is.
+///
+/// ```rust,ignore
+/// let preset = EqSettings { bass: Some(-2), treble: Some(1), loudness: Some(true) };
+/// let result = speaker.apply_eq(preset)?;
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EqSettings {
+    /// Bass level (-10 to +10)
+    pub bass: Option<i8>,
+    /// Treble level (-10 to +10)
+    pub treble: Option<i8>,
+    /// Loudness compensation
+    pub loudness: Option<bool>,
+}
+
+impl EqSettings {
+    /// The values set, in the order they are written
+    pub(crate) fn values(&self) -> Vec<EqValue> {
+        [
+            self.bass.map(EqValue::Bass),
+            self.treble.map(EqValue::Treble),
+            self.loudness.map(EqValue::Loudness),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// One setting of an [`EqSettings`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EqField {
+    Bass,
+    Treble,
+    Loudness,
+}
+
+impl fmt::Display for EqField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EqField::Bass => "bass",
+            EqField::Treble => "treble",
+            EqField::Loudness => "loudness",
+        })
+    }
+}
+
+/// A single EQ value to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EqValue {
+    Bass(i8),
+    Treble(i8),
+    Loudness(bool),
+}
+
+impl EqValue {
+    pub(crate) fn field(&self) -> EqField {
+        match self {
+            EqValue::Bass(_) => EqField::Bass,
+            EqValue::Treble(_) => EqField::Treble,
+            EqValue::Loudness(_) => EqField::Loudness,
+        }
+    }
+
+    /// Check the value as building its write would
+    pub(crate) fn validate(&self) -> Result<(), ValidationError> {
+        match *self {
+            EqValue::Bass(level) => rendering_control::set_bass(level).build().map(drop),
+            EqValue::Treble(level) => rendering_control::set_treble(level).build().map(drop),
+            EqValue::Loudness(enabled) => {
+                rendering_control::set_loudness("Master".to_string(), enabled)
+                    .build()
+                    .map(drop)
+            }
+        }
+    }
+}
+
+/// What happened to one field of an [`EqSettings`]
+#[derive(Debug)]
+pub enum EqOutcome {
+    /// Written and kept
+    Applied,
+    /// The write failed; the device may or may not hold the new value
+    Failed(SdkError),
+    /// Written, then restored to its earlier value after a later field failed
+    RolledBack,
+    /// Written, and restoring it after a later field failed didn't work;
+    /// the device holds the new value
+    RollbackFailed(SdkError),
+    /// Not sent because an earlier field failed
+    Skipped,
+}
+
+/// Per-field result of [`Speaker::apply_eq()`](crate::Speaker::apply_eq)
+///
+/// Fields appear in the order they were written; fields left as `None` in
+/// the settings are absent.
+#[derive(Debug)]
+pub struct EqResult {
+    pub fields: Vec<(EqField, EqOutcome)>,
+}
+
+impl EqResult {
+    /// Returns `true` if every field was applied
+    pub fn is_success(&self) -> bool {
+        self.fields
+            .iter()
+            .all(|(_, outcome)| matches!(outcome, EqOutcome::Applied))
+    }
+
+    /// Outcome for `field`, if it was part of the settings
+    pub fn outcome(&self, field: EqField) -> Option<&EqOutcome> {
+        self.fields
+            .iter()
+            .find(|(f, _)| *f == field)
+            .map(|(_, outcome)| outcome)
+    }
+}
@@ -35,4 +35,112 @@ pub enum SdkError {
 
     #[error("internal lock poisoned")]
     LockPoisoned,
+
+    /// The speaker does not currently allow this transport action
+    /// (e.g. `Pause` on a radio stream). `source` holds the device error
+    /// when the request was sent anyway under [`PreCheck::Advisory`](crate::PreCheck).
+    #[error("transport action {action} not available (allowed: {})", available.join(", "))]
+    NotAvailable {
+        action: String,
+        available: Vec<String>,
+        #[source]
+        source: Option<Box<SdkError>>,
+    },
+
+    /// The speaker has no EQ preset by this name
+    ///
+    /// Returned before sending when the cached preset list lacks the name,
+    /// and for the device's 701/702 faults. `available` is the cached list,
+    /// empty when none was cached.
+    #[error("no EQ preset named {name:?} (available: {})", available.join(", "))]
+    InvalidPreset {
+        name: String,
+        available: Vec<String>,
+    },
+
+    /// The speaker refused a group command as it isn't the coordinator, and
+    /// the SDK had no other speaker to redirect it to
+    ///
+    /// Also returned when a `Speaker` transport command is refused by a
+    /// speaker topology shows as a member of another coordinator's group;
+    /// that one is never redirected.
+    ///
+    /// `coordinator_hint` is the coordinator topology names for the speaker,
+    /// if that is another speaker: send the command there, or refresh the
+    /// topology and try again. `source` is the device's fault.
+    #[error(
+        "{} is not its group's coordinator{}",
+        speaker_id.as_str(),
+        coordinator_hint
+            .as_ref()
+            .map(|id| format!("; try {}", id.as_str()))
+            .unwrap_or_default()
+    )]
+    NotCoordinator {
+        speaker_id: sonos_state::SpeakerId,
+        coordinator_hint: Option<sonos_state::SpeakerId>,
+        #[source]
+        source: sonos_api::ApiError,
+    },
+
+    /// The speaker belongs to another household than the group; Sonos only
+    /// groups speakers within one household, so nothing was sent
+    #[error(
+        "{} is in household {speaker_household}, the group is in {group_household}",
+        speaker_id.as_str()
+    )]
+    CrossHousehold {
+        speaker_id: sonos_state::SpeakerId,
+        speaker_household: String,
+        group_household: String,
+    },
+
+    /// The speaker is already a member of the group; nothing was sent
+    #[error("{} is already in group {}", speaker_id.as_str(), group_id.as_str())]
+    AlreadyInGroup {
+        speaker_id: sonos_state::SpeakerId,
+        group_id: sonos_state::GroupId,
+    },
+
+    /// No discovered device belongs to the requested household
+    #[error("household not found: {0}")]
+    HouseholdNotFound(String),
+
+    /// The household has no alarm with this ID
+    #[error("alarm not found: {0}")]
+    AlarmNotFound(String),
+
+    /// The speaker's protocolInfo sink list has no entry for the URI's
+    /// format; returned without sending under
+    /// [`ProtocolCheck::Reject`](crate::ProtocolCheck)
+    #[error("{uri} is {content_type}, which the speaker can't play")]
+    UnsupportedMedia { uri: String, content_type: String },
+
+    /// The speaker's model doesn't have this property (e.g. the audio output
+    /// of a non-portable); nothing was sent
+    #[error("{model_name} does not support {property}")]
+    NotSupported {
+        property: &'static str,
+        model_name: String,
+    },
+
+    /// The write rate limit refused the write; nothing was sent
+    ///
+    /// See [`SonosSystem::set_write_rate_limit()`](crate::SonosSystem::set_write_rate_limit).
+    /// A write made `retry_after` from now would be accepted, unless other
+    /// writes take the tokens first.
+    #[error("{action} on {} rate limited; retry in {retry_after:?}", speaker_id.as_str())]
+    RateLimited {
+        speaker_id: sonos_state::SpeakerId,
+        action: String,
+        retry_after: std::time::Duration,
+    },
+
+    /// A write interceptor vetoed the request; nothing was sent
+    #[error("{action} on {} denied: {reason}", speaker_id.as_str())]
+    WriteDenied {
+        speaker_id: sonos_state::SpeakerId,
+        action: String,
+        reason: String,
+    },
 }
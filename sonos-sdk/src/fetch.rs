@@ -0,0 +1,305 @@
+//! Fetch coalescing
+//!
+//! Many widgets fetching the same property at startup would otherwise each
+//! send an identical request. [`FetchCoalescer`] lets concurrent `fetch()`
+//! calls for one (speaker, property) pair share a single request, and
+//! answers repeat fetches that arrive shortly after it completed from the
+//! state cache. Different properties on the same speaker never wait on each
+//! other.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use sonos_state::SpeakerId;
+
+use crate::SdkError;
+
+/// Default window in which a completed fetch answers repeat fetches
+const DEFAULT_WINDOW: Duration = Duration::from_millis(200);
+
+type FetchKey = (SpeakerId, &'static str);
+type SharedValue = Arc<dyn Any + Send + Sync>;
+
+/// A fetch in progress that other callers for the same key wait on
+#[derive(Default)]
+struct Flight {
+    result: Mutex<Option<Result<SharedValue, String>>>,
+    ready: Condvar,
+}
+
+impl Flight {
+    fn complete(&self, result: Result<SharedValue, String>) {
+        if let Ok(mut slot) = self.result.lock() {
+            *slot = Some(result);
+        }
+        self.ready.notify_all();
+    }
+}
+
+struct CoalescerState {
+    window: Duration,
+    inflight: HashMap<FetchKey, Arc<Flight>>,
+    /// When the last successful fetch for each key finished
+    completed: HashMap<FetchKey, Instant>,
+}
+
+/// Request counters of a [`FetchCoalescer`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FetchStats {
+    /// `fetch()` calls made
+    pub fetches: u64,
+    /// Requests actually sent to speakers
+    pub requests: u64,
+    /// Fetches that waited on a request another caller had in flight
+    pub joined: u64,
+    /// Fetches answered from the cache inside the post-completion window
+    pub served_recent: u64,
+}
+
+impl FetchStats {
+    /// Requests that coalescing avoided
+    pub fn saved(&self) -> u64 {
+        self.joined + self.served_recent
+    }
+}
+
+/// Single-flight coalescing of property fetches, shared by a [`SonosSystem`](crate::SonosSystem)
+///
+/// Obtained via [`SonosSystem::fetch_coalescer()`](crate::SonosSystem::fetch_coalescer).
+pub struct FetchCoalescer {
+    state: Mutex<CoalescerState>,
+    fetches: AtomicU64,
+    requests: AtomicU64,
+    joined: AtomicU64,
+    served_recent: AtomicU64,
+}
+
+impl Default for FetchCoalescer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FetchCoalescer {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(CoalescerState {
+                window: DEFAULT_WINDOW,
+                inflight: HashMap::new(),
+                completed: HashMap::new(),
+            }),
+            fetches: AtomicU64::new(0),
+            requests: AtomicU64::new(0),
+            joined: AtomicU64::new(0),
+            served_recent: AtomicU64::new(0),
+        }
+    }
+
+    /// Answer repeat fetches from the cache for `window` after a fetch
+    /// completes (default: 200ms). `Duration::ZERO` turns this off; in-flight
+    /// requests are still shared.
+    pub fn set_window(&self, window: Duration) {
+        if let Ok(mut state) = self.state.lock() {
+            state.window = window;
+            if window.is_zero() {
+                state.completed.clear();
+            }
+        }
+    }
+
+    /// Post-completion window
+    pub fn window(&self) -> Duration {
+        self.state
+            .lock()
+            .map(|s| s.window)
+            .unwrap_or(DEFAULT_WINDOW)
+    }
+
+    /// Make the next fetch of every property go to its speaker
+    pub(crate) fn forget_recent(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.completed.clear();
+        }
+    }
+
+    /// Counters since the system was created
+    pub fn stats(&self) -> FetchStats {
+        FetchStats {
+            fetches: self.fetches.load(Ordering::Relaxed),
+            requests: self.requests.load(Ordering::Relaxed),
+            joined: self.joined.load(Ordering::Relaxed),
+            served_recent: self.served_recent.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Run `fetch` for `(speaker, key)` unless another caller already is
+    ///
+    /// Inside the window `cached` is consulted first; it reads the state
+    /// store, so a write or event landing after the fetch is not hidden.
+    /// The leader gets its own error back; waiters get it as
+    /// [`SdkError::FetchFailed`]. Failures are never served from the window.
+    pub(crate) fn run<P>(
+        &self,
+        speaker: &SpeakerId,
+        key: &'static str,
+        cached: impl FnOnce() -> Option<P>,
+        fetch: impl FnOnce() -> Result<P, SdkError>,
+    ) -> Result<P, SdkError>
+    where
+        P: Clone + Send + Sync + 'static,
+    {
+        self.fetches.fetch_add(1, Ordering::Relaxed);
+        let fetch_key = (speaker.clone(), key);
+
+        let (flight, leader) = {
+            let mut state = self.state.lock().map_err(|_| SdkError::LockPoisoned)?;
+            let window = state.window;
+            let recent = state
+                .completed
+                .get(&fetch_key)
+                .is_some_and(|done| done.elapsed() < window);
+            if recent {
+                if let Some(value) = cached() {
+                    self.served_recent.fetch_add(1, Ordering::Relaxed);
+                    return Ok(value);
+                }
+            }
+            match state.inflight.get(&fetch_key) {
+                Some(flight) => (Arc::clone(flight), false),
+                None => {
+                    let flight = Arc::new(Flight::default());
+                    state
+                        .inflight
+                        .insert(fetch_key.clone(), Arc::clone(&flight));
+                    (flight, true)
+                }
+            }
+        };
+
+        if leader {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            // Wakes waiters with an error should `fetch` panic
+            let mut guard = FlightGuard {
+                coalescer: self,
+                key: Some(fetch_key),
+                flight: &flight,
+            };
+            let result = fetch();
+            let shared = match &result {
+                Ok(value) => Ok(Arc::new(value.clone()) as SharedValue),
+                Err(e) => Err(e.to_string()),
+            };
+            guard.finish(shared);
+            return result;
+        }
+
+        self.joined.fetch_add(1, Ordering::Relaxed);
+        let mut slot = flight.result.lock().map_err(|_| SdkError::LockPoisoned)?;
+        while slot.is_none() {
+            slot = flight
+                .ready
+                .wait(slot)
+                .map_err(|_| SdkError::LockPoisoned)?;
+        }
+        match slot.as_ref() {
+            Some(Ok(value)) => value
+                .downcast_ref::<P>()
+                .cloned()
+                .ok_or_else(|| SdkError::FetchFailed(format!("{key}: mismatched fetch result"))),
+            Some(Err(e)) => Err(SdkError::FetchFailed(e.clone())),
+            None => Err(SdkError::FetchFailed(format!("{key}: fetch abandoned"))),
+        }
+    }
+}
+
+/// Completes the leader's flight, even when the fetch unwinds
+struct FlightGuard<'a> {
+    coalescer: &'a FetchCoalescer,
+    key: Option<FetchKey>,
+    flight: &'a Flight,
+}
+
+impl FlightGuard<'_> {
+    fn finish(&mut self, result: Result<SharedValue, String>) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        if let Ok(mut state) = self.coalescer.state.lock() {
+            state.inflight.remove(&key);
+            if result.is_ok() && !state.window.is_zero() {
+                state.completed.insert(key, Instant::now());
+            } else {
+                state.completed.remove(&key);
+            }
+        }
+        self.flight.complete(result);
+    }
+}
+
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        self.finish(Err("fetch abandoned".to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn test_properties_do_not_share_flights() {
+        let coalescer = FetchCoalescer::new();
+        let id = SpeakerId::new("RINCON_1");
+        let volume = coalescer.run(&id, "volume", || None, || Ok(10u8));
+        let mute = coalescer.run(&id, "mute", || None, || Ok(true));
+        assert_eq!((volume.unwrap(), mute.unwrap()), (10, true));
+        assert_eq!(coalescer.stats().requests, 2);
+    }
+
+    #[test]
+    fn test_window_serves_cached_value() {
+        let coalescer = FetchCoalescer::new();
+        let id = SpeakerId::new("RINCON_1");
+        coalescer.run(&id, "volume", || None, || Ok(10u8)).unwrap();
+        let again = coalescer.run(&id, "volume", || Some(12u8), || Ok(99u8));
+        assert_eq!(again.unwrap(), 12, "cache is newer than the fetch");
+
+        coalescer.set_window(Duration::ZERO);
+        let fresh = coalescer.run(&id, "volume", || Some(12u8), || Ok(30u8));
+        assert_eq!(fresh.unwrap(), 30);
+        let stats = coalescer.stats();
+        assert_eq!((stats.requests, stats.served_recent), (2, 1));
+    }
+
+    #[test]
+    fn test_panicking_leader_releases_waiters() {
+        let coalescer = Arc::new(FetchCoalescer::new());
+        let id = SpeakerId::new("RINCON_1");
+        let started = Arc::new(Barrier::new(2));
+
+        let leader = {
+            let (coalescer, id, started) = (coalescer.clone(), id.clone(), started.clone());
+            thread::spawn(move || {
+                coalescer.run(
+                    &id,
+                    "volume",
+                    || None,
+                    || -> Result<u8, SdkError> {
+                        started.wait();
+                        thread::sleep(Duration::from_millis(50));
+                        panic!("decode failed")
+                    },
+                )
+            })
+        };
+        started.wait();
+        let waiter = coalescer.run(&id, "volume", || None, || Ok(1u8));
+        assert!(leader.join().is_err());
+        assert!(matches!(waiter, Err(SdkError::FetchFailed(_))));
+    }
+}
@@ -9,22 +9,25 @@
 //! optimistically after the SOAP call succeeds. The cached value may be stale
 //! if the coordinator rejects the command silently, until the next UPnP event
 //! corrects it.
+//! Writes rewritten by a write interceptor skip the optimistic update and
+//! wait for that event instead.
 
-use std::net::IpAddr;
 use std::sync::Arc;
 
 use sonos_api::operation::{ComposableOperation, UPnPOperation, ValidationError};
 use sonos_api::services::av_transport;
-use sonos_api::services::group_rendering_control::{self, SetRelativeGroupVolumeResponse};
+use sonos_api::services::group_management::{self, AddMemberResponse};
+use sonos_api::services::group_rendering_control::SetRelativeGroupVolumeResponse;
 use sonos_api::SonosClient;
-use sonos_state::{GroupId, GroupInfo, GroupMute, GroupVolume, SpeakerId, StateManager};
+use sonos_state::{GroupId, GroupInfo, SpeakerId, StateManager};
 
+use crate::intercept::{send_write, Sent};
 use crate::property::{
-    GroupContext, GroupMuteHandle, GroupPropertyHandle, GroupVolumeChangeableHandle,
-    GroupVolumeHandle,
+    GroupCompositionHandle, GroupContext, GroupMuteHandle, GroupPropertyHandle,
+    GroupVolumeChangeableHandle, GroupVolumeHandle,
 };
 use crate::SdkError;
-use crate::Speaker;
+use crate::{FetchCoalescer, Speaker};
 
 /// Result of a multi-speaker group operation (e.g., `dissolve()`, `create_group()`)
 ///
@@ -92,28 +95,36 @@ pub struct Group {
     /// Whether group volume can be changed (event-only, no fetch)
     pub volume_changeable: GroupVolumeChangeableHandle,
 
+    // ========================================================================
+    // ZoneGroupTopology properties
+    // ========================================================================
+    /// Coordinator and members; changes only when this group is reshaped
+    pub composition: GroupCompositionHandle,
+
     // Internal references
-    coordinator_ip: IpAddr,
+    context: Arc<GroupContext>,
     state_manager: Arc<StateManager>,
     api_client: SonosClient,
+    fetches: Arc<FetchCoalescer>,
 }
 
 impl Group {
     /// Create a new Group handle from GroupInfo
     ///
-    /// Returns `None` if the coordinator's IP address cannot be resolved
+    /// Returns `None` if the coordinator's address cannot be resolved
     /// (e.g., the coordinator speaker is not registered in state).
     pub(crate) fn from_info(
         info: GroupInfo,
         state_manager: Arc<StateManager>,
         api_client: SonosClient,
+        fetches: Arc<FetchCoalescer>,
     ) -> Option<Self> {
-        let coordinator_ip = state_manager.get_speaker_ip(&info.coordinator_id)?;
+        let coordinator_addr = state_manager.get_speaker_addr(&info.coordinator_id)?;
 
         let group_context = GroupContext::new(
             info.id.clone(),
             info.coordinator_id.clone(),
-            coordinator_ip,
+            coordinator_addr,
             Arc::clone(&state_manager),
             api_client.clone(),
         );
@@ -124,10 +135,12 @@ impl Group {
             member_ids: info.member_ids,
             volume: GroupPropertyHandle::new(Arc::clone(&group_context)),
             mute: GroupPropertyHandle::new(Arc::clone(&group_context)),
-            volume_changeable: GroupPropertyHandle::new(group_context),
-            coordinator_ip,
+            volume_changeable: GroupPropertyHandle::new(Arc::clone(&group_context)),
+            composition: GroupPropertyHandle::new(Arc::clone(&group_context)),
+            context: group_context,
             state_manager,
             api_client,
+            fetches,
         })
     }
 
@@ -147,13 +160,14 @@ impl Group {
     /// ```
     pub fn coordinator(&self) -> Option<Speaker> {
         let info = self.state_manager.speaker_info(&self.coordinator_id)?;
-        Some(Speaker::new(
+        Some(Speaker::with_fetches(
             self.coordinator_id.clone(),
-            info.name,
-            info.ip_address,
+            info.name.clone(),
+            info.socket_addr(),
             info.model_name,
             Arc::clone(&self.state_manager),
             self.api_client.clone(),
+            Arc::clone(&self.fetches),
         ))
     }
 
@@ -174,13 +188,14 @@ impl Group {
             .iter()
             .filter_map(|id| {
                 let info = self.state_manager.speaker_info(id)?;
-                Some(Speaker::new(
+                Some(Speaker::with_fetches(
                     id.clone(),
-                    info.name,
-                    info.ip_address,
+                    info.name.clone(),
+                    info.socket_addr(),
                     info.model_name,
                     Arc::clone(&self.state_manager),
                     self.api_client.clone(),
+                    Arc::clone(&self.fetches),
                 ))
             })
             .collect()
@@ -249,15 +264,16 @@ impl Group {
     // Private helpers
     // ========================================================================
 
-    /// Execute a UPnP operation against this group's coordinator
-    fn exec<Op: UPnPOperation>(
+    /// Send a mutating operation to this group's coordinator through the
+    /// write interceptors, following a coordinator handoff
+    fn write<Op: UPnPOperation>(
         &self,
         operation: Result<ComposableOperation<Op>, ValidationError>,
-    ) -> Result<Op::Response, SdkError> {
-        let op = operation?;
-        self.api_client
-            .execute_enhanced(&self.coordinator_ip.to_string(), op)
-            .map_err(SdkError::ApiError)
+    ) -> Result<Sent<Op::Response>, SdkError>
+    where
+        Op::Request: Clone,
+    {
+        self.context.write(operation)
     }
 
     // ========================================================================
@@ -269,20 +285,96 @@ impl Group {
     /// Sends `SetAVTransportURI` to the member speaker with `x-rincon:{coordinator_id}`
     /// to join the coordinator's audio stream. This is the standard Sonos grouping mechanism.
     /// After calling this, re-fetch groups via `system.groups()` to see updated membership.
+    ///
+    /// Returns [`SdkError::CrossHousehold`] without sending anything when the
+    /// speaker and the coordinator report different households.
     pub fn add_speaker(&self, speaker: &Speaker) -> Result<(), SdkError> {
+        self.check_joinable(speaker)?;
+        let rincon_uri = format!("x-rincon:{}", self.coordinator_id.as_str());
+        let op = av_transport::set_av_transport_uri(rincon_uri, String::new()).build()?;
+        send_write(
+            &self.state_manager,
+            &self.api_client,
+            &speaker.id,
+            speaker.addr(),
+            op,
+        )?;
+        Ok(())
+    }
+
+    /// Add a speaker to this group with the coordinator's handshake
+    ///
+    /// Sends GroupManagement `AddMember` (with the member's last known boot
+    /// sequence) to the coordinator first, then `SetAVTransportURI` with the
+    /// returned URI to the member. The coordinator has the member's slot
+    /// ready when it connects, so audio starts sooner than with
+    /// [`add_speaker()`](Self::add_speaker). If the member refuses the URI,
+    /// `RemoveMember` releases the slot again.
+    ///
+    /// Returns the coordinator's answer, including the group's transport and
+    /// queue owner. A speaker already in the group is refused with
+    /// [`SdkError::AlreadyInGroup`] without sending anything.
+    pub fn add_member_fast(&self, speaker: &Speaker) -> Result<AddMemberResponse, SdkError> {
+        self.check_joinable(speaker)?;
+        if self.member_ids.contains(&speaker.id) {
+            return Err(SdkError::AlreadyInGroup {
+                speaker_id: speaker.id.clone(),
+                group_id: self.id.clone(),
+            });
+        }
+        let member_id = speaker.id.as_str().to_string();
+        let boot_seq = self.state_manager.get_boot_seq(&speaker.id).unwrap_or(0);
+        let joined = self
+            .write(group_management::add_member_operation(member_id.clone(), boot_seq).build())?
+            .response;
+
+        let uri = joined
+            .member_uri()
+            .unwrap_or_else(|| format!("x-rincon:{}", self.coordinator_id.as_str()));
+        let op = av_transport::set_av_transport_uri(uri, String::new()).build()?;
+        if let Err(e) = send_write(
+            &self.state_manager,
+            &self.api_client,
+            &speaker.id,
+            speaker.addr(),
+            op,
+        ) {
+            if let Err(release) = self.write(group_management::remove_member(member_id).build()) {
+                tracing::warn!(
+                    "RemoveMember for {} after failed join: {}",
+                    speaker.id.as_str(),
+                    release
+                );
+            }
+            return Err(e);
+        }
+        Ok(joined)
+    }
+
+    /// Refuse speakers that can't join this group: its coordinator, or a
+    /// speaker of another household
+    fn check_joinable(&self, speaker: &Speaker) -> Result<(), SdkError> {
         if speaker.id == self.coordinator_id {
             return Err(SdkError::InvalidOperation(
                 "Cannot add coordinator to its own group".to_string(),
             ));
         }
-        let rincon_uri = format!("x-rincon:{}", self.coordinator_id.as_str());
-        let op = av_transport::set_av_transport_uri(rincon_uri, String::new()).build()?;
-        self.api_client
-            .execute_enhanced::<av_transport::SetAVTransportURIOperation>(
-                &speaker.ip.to_string(),
-                op,
-            )
-            .map_err(SdkError::ApiError)?;
+        let household = |id: &SpeakerId| {
+            self.state_manager
+                .speaker_info(id)
+                .and_then(|info| info.household_id)
+        };
+        if let (Some(speaker_household), Some(group_household)) =
+            (household(&speaker.id), household(&self.coordinator_id))
+        {
+            if speaker_household != group_household {
+                return Err(SdkError::CrossHousehold {
+                    speaker_id: speaker.id.clone(),
+                    speaker_household,
+                    group_household,
+                });
+            }
+        }
         Ok(())
     }
 
@@ -297,12 +389,13 @@ impl Group {
             ));
         }
         let op = av_transport::become_coordinator_of_standalone_group().build()?;
-        self.api_client
-            .execute_enhanced::<av_transport::BecomeCoordinatorOfStandaloneGroupOperation>(
-                &speaker.ip.to_string(),
-                op,
-            )
-            .map_err(SdkError::ApiError)?;
+        send_write(
+            &self.state_manager,
+            &self.api_client,
+            &speaker.id,
+            speaker.addr(),
+            op,
+        )?;
         Ok(())
     }
 
@@ -327,18 +420,39 @@ impl Group {
         GroupChangeResult { succeeded, failed }
     }
 
+    // ========================================================================
+    // AVTransport — Group playback
+    // ========================================================================
+
+    /// Start or resume playback of the group
+    ///
+    /// Sent to the coordinator once the group has settled, and rerouted
+    /// once if that speaker answers it no longer coordinates (see
+    /// [`SonosSystem::routing_stats()`](crate::SonosSystem::routing_stats)).
+    /// The cached `PlaybackState` is left to the coordinator's next event.
+    pub fn play(&self) -> Result<(), SdkError> {
+        self.write(av_transport::play("1".to_string()).build())?;
+        Ok(())
+    }
+
+    /// Pause playback of the group
+    ///
+    /// Routed like [`play()`](Self::play).
+    pub fn pause(&self) -> Result<(), SdkError> {
+        self.write(av_transport::pause().build())?;
+        Ok(())
+    }
+
     // ========================================================================
     // GroupRenderingControl — Volume and mute
     // ========================================================================
 
     /// Set group volume (0-100)
     ///
-    /// Updates the state cache to the new `GroupVolume` on success.
+    /// Updates the state cache to the new `GroupVolume` on success. Same as
+    /// `volume.set()`.
     pub fn set_volume(&self, volume: u16) -> Result<(), SdkError> {
-        self.exec(group_rendering_control::set_group_volume(volume).build())?;
-        self.state_manager
-            .set_group_property(&self.id, GroupVolume(volume));
-        Ok(())
+        self.volume.set(volume)
     }
 
     /// Adjust group volume relative to current level
@@ -348,27 +462,23 @@ impl Group {
         &self,
         adjustment: i16,
     ) -> Result<SetRelativeGroupVolumeResponse, SdkError> {
-        let response =
-            self.exec(group_rendering_control::set_relative_group_volume(adjustment).build())?;
-        self.state_manager
-            .set_group_property(&self.id, GroupVolume(response.new_volume));
-        Ok(response)
+        let volume = self.volume.set_relative(adjustment)?;
+        Ok(SetRelativeGroupVolumeResponse {
+            new_volume: volume.0,
+        })
     }
 
     /// Set group mute state
     ///
-    /// Updates the state cache to the new `GroupMute` value on success.
+    /// Updates the state cache to the new `GroupMute` value on success. Same
+    /// as `mute.set()`.
     pub fn set_mute(&self, muted: bool) -> Result<(), SdkError> {
-        self.exec(group_rendering_control::set_group_mute(muted).build())?;
-        self.state_manager
-            .set_group_property(&self.id, GroupMute(muted));
-        Ok(())
+        self.mute.set(muted)
     }
 
     /// Snapshot the current group volume (for later restore)
     pub fn snapshot_volume(&self) -> Result<(), SdkError> {
-        self.exec(group_rendering_control::snapshot_group_volume().build())?;
-        Ok(())
+        self.volume.snapshot()
     }
 }
 
@@ -390,6 +500,8 @@ mod tests {
                 ip_address: ip.to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             })
             .collect();
         manager.add_devices(devices).unwrap();
@@ -411,7 +523,8 @@ mod tests {
             vec![SpeakerId::new("RINCON_111")],
         );
 
-        let group = Group::from_info(group_info, state_manager, api_client).unwrap();
+        let group =
+            Group::from_info(group_info, state_manager, api_client, Default::default()).unwrap();
 
         assert_eq!(group.id.as_str(), "RINCON_111:1");
         assert_eq!(group.coordinator_id.as_str(), "RINCON_111");
@@ -434,7 +547,7 @@ mod tests {
             vec![SpeakerId::new("RINCON_UNKNOWN")],
         );
 
-        let group = Group::from_info(group_info, state_manager, api_client);
+        let group = Group::from_info(group_info, state_manager, api_client, Default::default());
         assert!(group.is_none());
     }
 
@@ -452,7 +565,8 @@ mod tests {
             vec![SpeakerId::new("RINCON_111"), SpeakerId::new("RINCON_222")],
         );
 
-        let group = Group::from_info(group_info, state_manager, api_client).unwrap();
+        let group =
+            Group::from_info(group_info, state_manager, api_client, Default::default()).unwrap();
 
         let coordinator = group.coordinator();
         assert!(coordinator.is_some());
@@ -475,7 +589,8 @@ mod tests {
             vec![SpeakerId::new("RINCON_111"), SpeakerId::new("RINCON_222")],
         );
 
-        let group = Group::from_info(group_info, state_manager, api_client).unwrap();
+        let group =
+            Group::from_info(group_info, state_manager, api_client, Default::default()).unwrap();
 
         let members = group.members();
         assert_eq!(members.len(), 2);
@@ -499,7 +614,8 @@ mod tests {
             vec![SpeakerId::new("RINCON_111"), SpeakerId::new("RINCON_222")],
         );
 
-        let group = Group::from_info(group_info, state_manager, api_client).unwrap();
+        let group =
+            Group::from_info(group_info, state_manager, api_client, Default::default()).unwrap();
 
         assert!(group.is_coordinator(&SpeakerId::new("RINCON_111")));
         assert!(!group.is_coordinator(&SpeakerId::new("RINCON_222")));
@@ -522,6 +638,7 @@ mod tests {
             ),
             Arc::clone(&state_manager),
             api_client.clone(),
+            Default::default(),
         )
         .unwrap();
         assert_eq!(single_group.member_count(), 1);
@@ -535,6 +652,7 @@ mod tests {
             ),
             state_manager,
             api_client,
+            Default::default(),
         )
         .unwrap();
         assert_eq!(multi_group.member_count(), 2);
@@ -557,6 +675,7 @@ mod tests {
             ),
             Arc::clone(&state_manager),
             api_client.clone(),
+            Default::default(),
         )
         .unwrap();
         assert!(standalone.is_standalone());
@@ -570,6 +689,7 @@ mod tests {
             ),
             state_manager,
             api_client,
+            Default::default(),
         )
         .unwrap();
         assert!(!grouped.is_standalone());
@@ -590,7 +710,8 @@ mod tests {
             vec![SpeakerId::new("RINCON_111")],
         );
 
-        let group = Group::from_info(group_info, state_manager, api_client).unwrap();
+        let group =
+            Group::from_info(group_info, state_manager, api_client, Default::default()).unwrap();
 
         // Volume handle should exist and return None initially
         assert!(group.volume.get().is_none());
@@ -611,7 +732,7 @@ mod tests {
             vec![SpeakerId::new("RINCON_111")],
         );
 
-        Group::from_info(group_info, state_manager, api_client).unwrap()
+        Group::from_info(group_info, state_manager, api_client, Default::default()).unwrap()
     }
 
     #[test]
@@ -648,12 +769,17 @@ mod tests {
             vec![SpeakerId::new("RINCON_111"), SpeakerId::new("RINCON_222")],
         );
 
-        let group =
-            Group::from_info(group_info, Arc::clone(&state_manager), api_client.clone()).unwrap();
+        let group = Group::from_info(
+            group_info,
+            Arc::clone(&state_manager),
+            api_client.clone(),
+            Default::default(),
+        )
+        .unwrap();
         let member = Speaker::new(
             SpeakerId::new("RINCON_222"),
             "Kitchen".to_string(),
-            "192.168.1.101".parse().unwrap(),
+            "192.168.1.101:1400".parse().unwrap(),
             "Sonos One".to_string(),
             state_manager,
             api_client,
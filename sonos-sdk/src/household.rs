@@ -0,0 +1,188 @@
+//! Household scoping
+//!
+//! A LAN can carry more than one Sonos household (a neighbour's system on a
+//! shared building network, say). Discovery sees all of them, but speakers
+//! only group and share topology within their own household. A
+//! [`SonosSystem`](crate::SonosSystem) is therefore scoped to one household
+//! by default: devices of other households are listed by
+//! [`households()`](crate::SonosSystem::households) but never registered,
+//! fetched from or subscribed to.
+
+use std::collections::BTreeMap;
+
+use sonos_api::services::device_properties;
+use sonos_api::SonosClient;
+use sonos_discovery::Device;
+use sonos_state::SpeakerId;
+
+use crate::SdkError;
+
+/// Which household a [`SonosSystem`](crate::SonosSystem) manages
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum HouseholdSelection {
+    /// The household with the most discovered devices; ties go to the
+    /// lowest household ID
+    #[default]
+    MostDevices,
+    /// Exactly this household
+    Id(String),
+    /// Every discovered device, whatever its household
+    All,
+}
+
+/// A household seen during discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Household {
+    /// Household ID, e.g. `Sonos_ab12...`
+    pub id: String,
+    /// Discovered devices reporting this household, sorted
+    pub speaker_ids: Vec<SpeakerId>,
+    /// Room names of those devices, sorted and deduplicated
+    pub room_names: Vec<String>,
+}
+
+impl Household {
+    /// Number of discovered devices in this household
+    pub fn device_count(&self) -> usize {
+        self.speaker_ids.len()
+    }
+}
+
+/// Ask devices without a household ID for it with `GetHouseholdID`
+///
+/// Only done when another device did report one (or a specific household
+/// was asked for): with no IDs at all there is nothing to tell apart, and
+/// startup stays at one request. Devices that do not answer keep `None`.
+pub(crate) fn resolve_missing(
+    devices: &mut [Device],
+    client: &SonosClient,
+    selection: &HouseholdSelection,
+) {
+    let missing = devices.iter().filter(|d| d.household_id.is_none()).count();
+    let needed = match selection {
+        HouseholdSelection::All => false,
+        HouseholdSelection::Id(_) => missing > 0,
+        HouseholdSelection::MostDevices => missing > 0 && missing < devices.len(),
+    };
+    if !needed {
+        return;
+    }
+    for device in devices.iter_mut().filter(|d| d.household_id.is_none()) {
+        let addr = format!("{}:{}", device.ip_address, device.port);
+        let response = device_properties::get_household_id()
+            .build()
+            .map_err(SdkError::from)
+            .and_then(|op| client.execute_enhanced(&addr, op).map_err(SdkError::from));
+        match response {
+            Ok(response) => device.household_id = Some(response.current_household_id),
+            Err(e) => tracing::debug!("GetHouseholdID failed for {}: {}", device.id, e),
+        }
+    }
+}
+
+/// Group devices by household, largest household first
+pub(crate) fn detect(devices: &[Device]) -> Vec<Household> {
+    let mut by_id: BTreeMap<&str, Vec<&Device>> = BTreeMap::new();
+    for device in devices {
+        if let Some(id) = device.household_id.as_deref() {
+            by_id.entry(id).or_default().push(device);
+        }
+    }
+    let mut households: Vec<Household> = by_id
+        .into_iter()
+        .map(|(id, members)| {
+            let mut speaker_ids: Vec<SpeakerId> =
+                members.iter().map(|d| SpeakerId::new(&d.id)).collect();
+            speaker_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            speaker_ids.dedup();
+            let mut room_names: Vec<String> = members.iter().map(|d| d.room_name.clone()).collect();
+            room_names.sort();
+            room_names.dedup();
+            Household {
+                id: id.to_string(),
+                speaker_ids,
+                room_names,
+            }
+        })
+        .collect();
+    // Stable sort keeps the BTreeMap's ID order among equal counts
+    households.sort_by_key(|h| std::cmp::Reverse(h.device_count()));
+    households
+}
+
+/// The household ID a selection resolves to; `None` means no scoping
+pub(crate) fn select(
+    households: &[Household],
+    selection: &HouseholdSelection,
+) -> Result<Option<String>, SdkError> {
+    match selection {
+        HouseholdSelection::All => Ok(None),
+        HouseholdSelection::MostDevices => Ok(households.first().map(|h| h.id.clone())),
+        HouseholdSelection::Id(id) => households
+            .iter()
+            .any(|h| &h.id == id)
+            .then(|| Some(id.clone()))
+            .ok_or_else(|| SdkError::HouseholdNotFound(id.clone())),
+    }
+}
+
+/// Whether a device belongs in a system scoped to `scope`
+///
+/// Devices that never reported a household are kept.
+pub(crate) fn in_scope(device: &Device, scope: Option<&str>) -> bool {
+    match (scope, device.household_id.as_deref()) {
+        (Some(scope), Some(theirs)) => scope == theirs,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, household: Option<&str>) -> Device {
+        Device {
+            id: id.to_string(),
+            name: id.to_string(),
+            room_name: id.to_string(),
+            ip_address: "192.168.1.100".to_string(),
+            port: 1400,
+            model_name: "Sonos One".to_string(),
+            household_id: household.map(str::to_string),
+            secure: false,
+        }
+    }
+
+    #[test]
+    fn test_most_devices_wins_and_ties_go_to_lowest_id() {
+        let devices = [
+            device("RINCON_1", Some("Sonos_B")),
+            device("RINCON_2", Some("Sonos_C")),
+            device("RINCON_3", Some("Sonos_C")),
+            device("RINCON_4", Some("Sonos_A")),
+            device("RINCON_5", None),
+        ];
+        let households = detect(&devices);
+        let ids: Vec<&str> = households.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, ["Sonos_C", "Sonos_A", "Sonos_B"]);
+        let chosen = select(&households, &HouseholdSelection::MostDevices).unwrap();
+        assert_eq!(chosen.as_deref(), Some("Sonos_C"));
+
+        let tied = detect(&[devices[0].clone(), devices[3].clone()]);
+        let chosen = select(&tied, &HouseholdSelection::MostDevices).unwrap();
+        assert_eq!(chosen.as_deref(), Some("Sonos_A"));
+    }
+
+    #[test]
+    fn test_select_unknown_household_fails() {
+        let households = detect(&[device("RINCON_1", Some("Sonos_A"))]);
+        let missing = select(&households, &HouseholdSelection::Id("Sonos_Z".into()));
+        assert!(matches!(missing, Err(SdkError::HouseholdNotFound(id)) if id == "Sonos_Z"));
+        assert_eq!(select(&households, &HouseholdSelection::All).unwrap(), None);
+        assert!(in_scope(&device("RINCON_2", None), Some("Sonos_A")));
+        assert!(!in_scope(
+            &device("RINCON_2", Some("Sonos_B")),
+            Some("Sonos_A")
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+//! Sending writes through the registered write interceptors
+//!
+//! Every mutating action on [`Speaker`](crate::Speaker) and
+//! [`Group`](crate::Group) goes through [`send_write()`]. With no
+//! interceptor registered and no write rate limit it is a plain
+//! `execute_enhanced()`; otherwise the operation is turned into a
+//! [`WriteRequest`] for the interceptors, and a rewritten or rate-limited
+//! request is sent with `call_raw()` and parsed as the original operation's
+//! response.
+
+use std::net::SocketAddr;
+
+use sonos_api::operation::{ComposableOperation, UPnPOperation};
+use sonos_api::{ApiError, SonosClient};
+use sonos_state::{InterceptDecision, SpeakerId, StateManager, WriteRequest};
+
+use crate::SdkError;
+
+/// Response of a write that went out
+pub(crate) struct Sent<R> {
+    pub(crate) response: R,
+    /// An interceptor rewrote the arguments, or a later coalesced write
+    /// replaced them, so the value the caller asked for is not what the
+    /// speaker received
+    pub(crate) modified: bool,
+}
+
+/// Send a mutating operation to `speaker_id` at `addr`, subject to the write
+/// interceptors registered on `state_manager` and the client's
+/// [write limiter](sonos_api::rate_limit)
+pub(crate) fn send_write<Op: UPnPOperation>(
+    state_manager: &StateManager,
+    api_client: &SonosClient,
+    speaker_id: &SpeakerId,
+    addr: SocketAddr,
+    operation: ComposableOperation<Op>,
+) -> Result<Sent<Op::Response>, SdkError> {
+    let ip = addr.to_string();
+    let limited = api_client.write_limiter().is_enabled();
+    if !state_manager.has_write_interceptors() && !limited {
+        let response = api_client.execute_enhanced(&ip, operation)?;
+        return Ok(Sent {
+            response,
+            modified: false,
+        });
+    }
+
+    let mut args = operation.arguments()?;
+    let mut modified = false;
+    if state_manager.has_write_interceptors() {
+        let request = WriteRequest::new(speaker_id.clone(), Op::SERVICE, Op::ACTION, args.clone());
+        match state_manager.intercept_write(request) {
+            InterceptDecision::Allow => {}
+            InterceptDecision::Deny(reason) => {
+                return Err(SdkError::WriteDenied {
+                    speaker_id: speaker_id.clone(),
+                    action: Op::ACTION.to_string(),
+                    reason,
+                })
+            }
+            InterceptDecision::Modify(request) => {
+                args = request.args().to_vec();
+                modified = true;
+            }
+        }
+    }
+    if !limited && !modified {
+        let response = api_client.execute_enhanced(&ip, operation)?;
+        return Ok(Sent { response, modified });
+    }
+
+    let call = |args: &[(String, String)]| {
+        let args: Vec<(&str, &str)> = args
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        api_client.call_raw(&ip, Op::SERVICE, Op::ACTION, &args)
+    };
+    let xml = if limited {
+        let write = api_client
+            .write_limiter()
+            .send(&ip, Op::SERVICE, Op::ACTION, args, call)
+            .map_err(|e| match e {
+                ApiError::RateLimited { retry_after } => SdkError::RateLimited {
+                    speaker_id: speaker_id.clone(),
+                    action: Op::ACTION.to_string(),
+                    retry_after,
+                },
+                e => e.into(),
+            })?;
+        // A superseded value never reached the speaker; don't cache it
+        modified |= write.superseded;
+        write.response
+    } else {
+        call(&args)?
+    };
+    Ok(Sent {
+        response: operation.parse_response(&xml)?,
+        modified,
+    })
+}
@@ -40,6 +40,19 @@
 //! - **Type safety**: All properties are strongly typed
 //! - **Resource efficiency**: Shared state management and HTTP connections
 //!
+//! ## Failure Recovery
+//!
+//! Startup reports unreachable speakers as degraded instead of failing,
+//! polling keeps the cache current when events stop arriving, subscriptions
+//! a speaker forgot (a 412 on renewal, or a reboot) are replaced at the next
+//! renewal, and calls to an offline speaker fail with a network error
+//! without waiting. The `resilience_tour` example walks through each case
+//! on mock speakers and asserts it:
+//!
+//! ```bash
+//! cargo run -p sonos-sdk --features test-support --example resilience_tour
+//! ```
+//!
 //! ## Available Properties
 //!
 //! Currently implemented:
@@ -47,8 +60,11 @@
 //! - `playback_state` - Current playback state (Playing/Paused/Stopped/Transitioning)
 //! - `mute` - Mute state
 //! - `bass`, `treble`, `loudness` - EQ settings
+//! - `presets`, `output_fixed` - EQ presets (see `Speaker::select_preset`) and fixed line-out
 //! - `position` - Current track position
 //! - `current_track` - Track metadata
+//! - `transport_actions` - Transport actions currently allowed (see `PreCheck`)
+//! - `play_mode` - Shuffle and repeat (`PlayMode`), settable
 //!
 //! ## Architecture
 //!
@@ -61,17 +77,38 @@
 //! ```
 
 // Main exports
+pub use activity::{
+    ActivityCategory, ActivityConfig, ActivityCursor, ActivityEntry, ActivityFeed, ActivityPage,
+    ActivitySeverity,
+};
+pub use art::{ArtCache, ArtHandle};
+pub use auto_subscribe::{AutoSubscribe, AutoSubscribeOptions, Presence};
+pub use compat::{
+    build_info, BuildInfo, CompatibilityReport, CrateVersion, DeviceCompatibility, Quirk,
+};
+pub use connect::{ConnectOptions, ConnectProgress, ConnectReport, DeviceReadiness, Readiness};
+pub use eq::{EqField, EqOutcome, EqResult, EqSettings};
 pub use error::SdkError;
+pub use fetch::{FetchCoalescer, FetchStats};
 pub use group::{Group, GroupChangeResult};
-pub use speaker::{PlayMode, SeekTarget, Speaker};
-pub use system::SonosSystem;
+pub use household::{Household, HouseholdSelection};
+pub use refresh::{
+    Correction, RefreshFailure, RefreshReport, SpeakerRefresh, DEFAULT_REFRESH_PARALLELISM,
+};
+pub use schedule::{
+    next_occurrence, AlarmEntry, AutoplayEntry, ScheduleFailure, ScheduleOverview, ScheduleQuery,
+    SleepTimerEntry,
+};
+pub use source::PlaybackSource;
+pub use speaker::{PlayMode, PreCheck, ProtocolCheck, SeekTarget, Speaker};
+pub use system::{NameCollision, SonosSystem};
 
 // Re-export the generic PropertyHandle, SpeakerContext, and watch types
 pub use property::{PropertyHandle, SpeakerContext, WatchHandle, WatchMode};
 
 // Re-export group property handle types
 pub use property::{
-    GroupContext, GroupFetchable, GroupMuteHandle, GroupPropertyHandle,
+    GroupCompositionHandle, GroupContext, GroupFetchable, GroupMuteHandle, GroupPropertyHandle,
     GroupVolumeChangeableHandle, GroupVolumeHandle,
 };
 
@@ -81,29 +118,77 @@ pub use sonos_api::services::av_transport::{
     GetCrossfadeModeResponse, GetCurrentTransportActionsResponse, GetDeviceCapabilitiesResponse,
     GetMediaInfoResponse, GetRemainingSleepTimerDurationResponse,
     GetRunningAlarmPropertiesResponse, GetTransportSettingsResponse,
-    RemoveTrackRangeFromQueueResponse, SaveQueueResponse,
+    RemoveTrackRangeFromQueueResponse, SaveQueueResponse, Station,
 };
+pub use sonos_api::services::connection_manager::ProtocolInfo;
+pub use sonos_api::services::content_directory::{Favorite, Playlist};
+pub use sonos_api::services::group_management::{AddMemberResponse, GroupTransportUri};
 pub use sonos_api::services::group_rendering_control::SetRelativeGroupVolumeResponse;
 pub use sonos_api::services::rendering_control::SetRelativeVolumeResponse;
 
+// Preset name for Speaker::select_preset() that resets EQ
+pub use sonos_api::services::rendering_control::FACTORY_DEFAULTS_PRESET;
+
+// Transports for ConnectOptions::device_transport()
+pub use sonos_api::{CertFingerprint, CertificateTrust, DeviceTransport, EventingConnection};
+
+// Write rate limiting
+pub use sonos_api::rate_limit::{ExceedPolicy, RateLimit, TokenLevels, WriteRateLimit};
+
+// Masking of identifiers in ActivityFeed summaries
+pub use sonos_event_manager::RedactionPolicy;
+
+// Time source for ConnectOptions::clock()
+pub use sonos_api::clock::{Clock, ManualClock, SharedClock, SystemClock};
+
 // sonos_discovery is internal — consumers use SonosSystem::new()
 // Re-exported under test-support for integration tests that need Device
 #[cfg(feature = "test-support")]
 pub use sonos_discovery;
 
+// Scriptable mock device for integration tests
+#[cfg(feature = "test-support")]
+pub use sonos_api::mock;
+
+// Discovery events consumed by SonosSystem::auto_subscribe()
+pub use sonos_discovery::{Device, DeviceEvent};
+
 // Re-export commonly used types from sonos-state
 pub use sonos_state::{
-    ChangeEvent, ChangeIterator, GroupId, GroupMute, GroupVolume, GroupVolumeChangeable,
-    PlaybackState, SpeakerId, Volume,
+    AudioOutput, Bass, ButtonLock, ChangeEvent, ChangeIterator, ChangeOrigin, CurrentPlayMode,
+    CurrentTrack, DynamicValue, GroupComposition, GroupId, GroupMembership, GroupMute, GroupVolume,
+    GroupVolumeChangeable, InterceptDecision, Loudness, Mute, OriginClassifier, OutputFixed,
+    PlaybackState, Position, Presets, RerenderScope, RoutingStats, ShutdownReport, SimulatedChange,
+    SimulatedTrack, SpeakerId, StatusLight, SubEnabled, SubGain, SurroundEnabled, SurroundLevel,
+    SurroundMode, SuspendPolicy, TransportActions, Treble, Volume, WriteRequest,
 };
 
+// Listening sessions, for scrobbling
+pub use sonos_state::{ListeningEvent, ListeningEvents, ListeningRules};
+
+// Synthetic events and scripted playback for development without speakers
+pub use sonos_state::simulation;
+
 // Public modules
 pub mod prelude;
 
 // Internal modules
+mod activity;
+mod art;
+mod auto_subscribe;
 mod cache;
+mod compat;
+mod connect;
+mod eq;
 mod error;
+mod fetch;
 mod group;
+mod household;
+mod intercept;
 pub mod property;
+mod refresh;
+mod schedule;
+mod source;
 mod speaker;
 mod system;
+mod toggle;
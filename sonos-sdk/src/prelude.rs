@@ -4,10 +4,14 @@
 //! use sonos_sdk::prelude::*;
 //! ```
 
+pub use crate::connect::{ConnectOptions, ConnectReport, Readiness};
 pub use crate::error::SdkError;
 pub use crate::group::Group;
-pub use crate::speaker::{PlayMode, SeekTarget, Speaker};
+pub use crate::speaker::{PlayMode, PreCheck, ProtocolCheck, SeekTarget, Speaker};
 pub use crate::system::SonosSystem;
 
 // Property value types
-pub use sonos_state::{GroupId, GroupMute, GroupVolume, PlaybackState, SpeakerId, Volume};
+pub use sonos_state::{
+    ChangeOrigin, GroupId, GroupMute, GroupVolume, PlaybackState, SpeakerId, SurroundMode,
+    SuspendPolicy, Volume,
+};
@@ -7,16 +7,19 @@
 
 use std::fmt;
 use std::marker::PhantomData;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
-use sonos_api::operation::{ComposableOperation, UPnPOperation};
-use sonos_api::{ServiceScope, SonosClient};
+use sonos_api::operation::{ComposableOperation, UPnPOperation, ValidationError};
+use sonos_api::services::connection_manager::ProtocolInfo;
+use sonos_api::{ApiError, ServiceScope, SonosClient};
 use sonos_event_manager::WatchGuard;
-use sonos_state::{property::SonosProperty, SpeakerId, StateManager};
+use sonos_state::{property::SonosProperty, CoordinatorRoute, Property, SpeakerId, StateManager};
 
-use crate::SdkError;
+use crate::intercept::{send_write, Sent};
+use crate::{toggle, FetchCoalescer, SdkError};
 
 /// Shared context for all property handles on a speaker
 ///
@@ -25,26 +28,96 @@ use crate::SdkError;
 #[derive(Clone)]
 pub struct SpeakerContext {
     pub(crate) speaker_id: SpeakerId,
-    pub(crate) speaker_ip: IpAddr,
+    pub(crate) speaker_addr: SocketAddr,
     pub(crate) state_manager: Arc<StateManager>,
     pub(crate) api_client: SonosClient,
+    pub(crate) fetches: Arc<FetchCoalescer>,
+    /// Sink protocolInfo list, fetched on first use
+    pub(crate) protocols: OnceLock<Vec<ProtocolInfo>>,
 }
 
 impl SpeakerContext {
     /// Create a new SpeakerContext
+    ///
+    /// Its fetches are coalesced only with each other; speakers of a
+    /// [`SonosSystem`](crate::SonosSystem) share one coalescer.
     pub fn new(
         speaker_id: SpeakerId,
-        speaker_ip: IpAddr,
+        speaker_addr: SocketAddr,
+        state_manager: Arc<StateManager>,
+        api_client: SonosClient,
+    ) -> Arc<Self> {
+        Self::with_fetches(
+            speaker_id,
+            speaker_addr,
+            state_manager,
+            api_client,
+            Arc::new(FetchCoalescer::new()),
+        )
+    }
+
+    pub(crate) fn with_fetches(
+        speaker_id: SpeakerId,
+        speaker_addr: SocketAddr,
         state_manager: Arc<StateManager>,
         api_client: SonosClient,
+        fetches: Arc<FetchCoalescer>,
     ) -> Arc<Self> {
         Arc::new(Self {
             speaker_id,
-            speaker_ip,
+            speaker_addr,
             state_manager,
             api_client,
+            fetches,
+            protocols: OnceLock::new(),
         })
     }
+
+    /// Refuse `P` when this speaker's model doesn't have it
+    /// ([`SonosProperty::supported_by()`]); speakers of unknown model pass
+    pub(crate) fn check_supported<P: SonosProperty>(&self) -> Result<(), SdkError> {
+        match self.state_manager.speaker_info(&self.speaker_id) {
+            Some(info) if !P::supported_by(&info.model_name) => Err(SdkError::NotSupported {
+                property: P::KEY,
+                model_name: info.model_name,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Speaker that holds `P` for this speaker, with its current address
+    ///
+    /// This is the speaker itself unless `P` is a home-theater setting
+    /// ([`SonosProperty::BOND_PRIMARY`]) and this speaker is a bonded
+    /// satellite, in which case calls go to the bond primary. A satellite
+    /// whose primary isn't known yet is rejected rather than queried, since
+    /// the satellite itself would answer with defaults.
+    pub(crate) fn property_owner<P: SonosProperty>(
+        &self,
+    ) -> Result<(SpeakerId, SocketAddr), SdkError> {
+        if !P::BOND_PRIMARY {
+            return Ok((self.speaker_id.clone(), self.speaker_addr));
+        }
+        if let Some(primary) = self.state_manager.bond_primary(&self.speaker_id) {
+            let addr = self
+                .state_manager
+                .get_speaker_addr(&primary)
+                .ok_or_else(|| SdkError::SpeakerNotFound(primary.as_str().to_string()))?;
+            return Ok((primary, addr));
+        }
+        if self
+            .state_manager
+            .get_satellite_ids()
+            .contains(&self.speaker_id)
+        {
+            return Err(SdkError::InvalidOperation(format!(
+                "{} is a bonded satellite and its primary is unknown; use the home-theater primary for {}",
+                self.speaker_id.as_str(),
+                P::KEY
+            )));
+        }
+        Ok((self.speaker_id.clone(), self.speaker_addr))
+    }
 }
 
 // ============================================================================
@@ -302,9 +375,8 @@ impl<P: SonosProperty> PropertyHandle<P> {
     /// ```
     #[must_use = "returns the cached property value"]
     pub fn get(&self) -> Option<P> {
-        self.context
-            .state_manager
-            .get_property::<P>(&self.context.speaker_id)
+        let (owner_id, _) = self.context.property_owner::<P>().ok()?;
+        self.context.state_manager.get_property::<P>(&owner_id)
     }
 
     /// Start watching this property for changes (sync)
@@ -313,6 +385,17 @@ impl<P: SonosProperty> PropertyHandle<P> {
     /// the handle for as long as you need updates — dropping it starts a
     /// 50ms grace period before the UPnP subscription is torn down.
     ///
+    /// Starting a watch sends one event with [`ChangeOrigin::Initial`] to
+    /// `system.iter()` for the current value, set or not; after that,
+    /// events mean real changes. Watching again while a handle (or its
+    /// grace period) is alive continues the same watch and sends nothing.
+    ///
+    /// A property the speaker's model doesn't have, such as
+    /// [`AudioOutput`] on a non-portable, fails with
+    /// [`SdkError::NotSupported`] before anything is subscribed.
+    ///
+    /// [`ChangeOrigin::Initial`]: sonos_state::ChangeOrigin::Initial
+    ///
     /// # Example
     ///
     /// ```rust,ignore
@@ -337,6 +420,7 @@ impl<P: SonosProperty> PropertyHandle<P> {
             P::SERVICE,
             self.context.speaker_id.as_str()
         );
+        self.context.check_supported::<P>()?;
 
         // Trigger lazy event manager init if needed
         if self.context.state_manager.event_manager().is_none() {
@@ -355,29 +439,34 @@ impl<P: SonosProperty> PropertyHandle<P> {
             }
         }
 
-        // Resolve subscription target: for PerCoordinator services, route to coordinator
-        let (sub_id, sub_ip) = self.context.state_manager.resolve_subscription_target(
-            &self.context.speaker_id,
-            self.context.speaker_ip,
+        // Resolve subscription target: home-theater settings live on the bond
+        // primary, and PerCoordinator services route to the coordinator
+        let (owner_id, owner_addr) = self.context.property_owner::<P>()?;
+        let (sub_id, sub_addr) = self.context.state_manager.resolve_subscription_target(
+            &owner_id,
+            owner_addr,
             P::SERVICE,
         );
-        let routed_to_coordinator = sub_id != self.context.speaker_id;
+        let routed_to_coordinator = sub_id != owner_id;
+
+        // Registers the watch and delivers its initial notification; a
+        // routed watch is notified under the member's ID
+        self.context
+            .state_manager
+            .begin_watch(&owner_id, P::KEY, P::SERVICE);
 
         let (mode, cleanup) = if let Some(em) = self.context.state_manager.event_manager() {
-            match em.acquire_watch(&sub_id, P::KEY, sub_ip, P::SERVICE) {
+            match em.acquire_watch(&sub_id, P::KEY, sub_addr, P::SERVICE) {
                 Ok(guard) => {
                     if routed_to_coordinator {
-                        // Register the member's watch for notification forwarding
-                        self.context
-                            .state_manager
-                            .register_watch(&self.context.speaker_id, P::KEY);
+                        // The member's watch receives forwarded notifications
                         (
                             WatchMode::Events,
                             WatchCleanup::CoordinatorGuard {
                                 _guard: guard,
                                 _member_cleanup: CacheOnlyGuard {
                                     state_manager: Arc::clone(&self.context.state_manager),
-                                    speaker_id: self.context.speaker_id.clone(),
+                                    speaker_id: owner_id.clone(),
                                     property_key: P::KEY,
                                 },
                             },
@@ -393,15 +482,11 @@ impl<P: SonosProperty> PropertyHandle<P> {
                         self.context.speaker_id.as_str(),
                         e
                     );
-                    // Register directly for polling fallback
-                    self.context
-                        .state_manager
-                        .register_watch(&self.context.speaker_id, P::KEY);
                     (
                         WatchMode::Polling,
                         WatchCleanup::CacheOnly(CacheOnlyGuard {
                             state_manager: Arc::clone(&self.context.state_manager),
-                            speaker_id: self.context.speaker_id.clone(),
+                            speaker_id: owner_id.clone(),
                             property_key: P::KEY,
                         }),
                     )
@@ -413,14 +498,11 @@ impl<P: SonosProperty> PropertyHandle<P> {
                 "No event manager available for {} — falling back to cache-only mode",
                 self.context.speaker_id.as_str()
             );
-            self.context
-                .state_manager
-                .register_watch(&self.context.speaker_id, P::KEY);
             (
                 WatchMode::CacheOnly,
                 WatchCleanup::CacheOnly(CacheOnlyGuard {
                     state_manager: Arc::clone(&self.context.state_manager),
-                    speaker_id: self.context.speaker_id.clone(),
+                    speaker_id: owner_id.clone(),
                     property_key: P::KEY,
                 }),
             )
@@ -457,8 +539,8 @@ impl<P: SonosProperty> PropertyHandle<P> {
     #[must_use = "returns whether the property is being watched"]
     pub fn is_watched(&self) -> bool {
         self.context
-            .state_manager
-            .is_watched(&self.context.speaker_id, P::KEY)
+            .property_owner::<P>()
+            .is_ok_and(|(owner_id, _)| self.context.state_manager.is_watched(&owner_id, P::KEY))
     }
 
     /// Get the speaker ID this handle is associated with
@@ -468,7 +550,38 @@ impl<P: SonosProperty> PropertyHandle<P> {
 
     /// Get the speaker IP address
     pub fn speaker_ip(&self) -> IpAddr {
-        self.context.speaker_ip
+        self.context.speaker_addr.ip()
+    }
+
+    /// Get the speaker address (IP and port)
+    pub fn speaker_addr(&self) -> SocketAddr {
+        self.context.speaker_addr
+    }
+
+    /// Send a write of this property to the speaker, then cache `value`
+    /// unless an interceptor changed the request
+    ///
+    /// The write is recorded, so its echo is not reported as an external
+    /// change.
+    fn write<Op: UPnPOperation>(
+        &self,
+        operation: Result<ComposableOperation<Op>, ValidationError>,
+        value: P,
+    ) -> Result<(), SdkError> {
+        let context = &self.context;
+        let sent = send_write(
+            &context.state_manager,
+            &context.api_client,
+            &context.speaker_id,
+            context.speaker_addr,
+            operation?,
+        )?;
+        if !sent.modified {
+            context
+                .state_manager
+                .apply_local_write(&context.speaker_id, value);
+        }
+        Ok(())
     }
 }
 
@@ -481,7 +594,8 @@ impl<P: Fetchable> PropertyHandle<P> {
     /// performs a one-time fetch to seed the value.
     ///
     /// Use this instead of `watch()` when you need a value on the first frame
-    /// without waiting for a UPnP event to arrive.
+    /// without waiting for a UPnP event to arrive. The seeding fetch is a
+    /// real change, so it follows the initial notification with its own.
     pub fn watch_or_fetch(&self) -> Result<WatchHandle<P>, SdkError> {
         let mut wh = self.watch()?;
         if wh.value.is_none() {
@@ -498,7 +612,9 @@ impl<P: Fetchable> PropertyHandle<P> {
     /// Fetch fresh value from device + update cache (sync)
     ///
     /// This makes a synchronous UPnP call to the device and updates
-    /// the local state cache with the result.
+    /// the local state cache with the result. Concurrent fetches of the same
+    /// property share one request, and a fetch right after one completed is
+    /// answered from the cache (see [`FetchCoalescer`]).
     ///
     /// # Example
     ///
@@ -512,28 +628,62 @@ impl<P: Fetchable> PropertyHandle<P> {
     /// ```
     #[must_use = "returns the fetched value from the device"]
     pub fn fetch(&self) -> Result<P, SdkError> {
-        let operation = P::build_operation()?;
+        let (target_id, target_addr) = self.fetch_target()?;
+        let state_manager = &self.context.state_manager;
+        self.context.fetches.run(
+            &target_id,
+            P::KEY,
+            || state_manager.get_property::<P>(&target_id),
+            || self.fetch_from(&target_id, target_addr),
+        )
+    }
 
-        // Resolve target: coordinator for PerCoordinator services, fresh IP for PerSpeaker
-        let (target_id, target_ip) = if P::SERVICE.scope() == ServiceScope::PerCoordinator {
+    /// The value to base a change on: the cached one while a watch keeps
+    /// the cache current through the event manager, otherwise fetched
+    pub(crate) fn fresh(&self) -> Result<P, SdkError> {
+        if self.is_watched() && self.context.state_manager.event_manager().is_some() {
+            if let Some(value) = self.get() {
+                return Ok(value);
+            }
+        }
+        self.fetch()
+    }
+
+    /// Read the value from the device and cache it, without coalescing
+    ///
+    /// For confirming a write: a coalesced fetch could be answered by the
+    /// cache from before it.
+    pub(crate) fn read_back(&self) -> Result<P, SdkError> {
+        let (target_id, target_addr) = self.fetch_target()?;
+        self.fetch_from(&target_id, target_addr)
+    }
+
+    /// Speaker to fetch from: the coordinator for PerCoordinator services,
+    /// the property owner at its current address otherwise
+    fn fetch_target(&self) -> Result<(SpeakerId, SocketAddr), SdkError> {
+        let (owner_id, owner_addr) = self.context.property_owner::<P>()?;
+        Ok(if P::SERVICE.scope() == ServiceScope::PerCoordinator {
             self.context.state_manager.resolve_subscription_target(
-                &self.context.speaker_id,
-                self.context.speaker_ip,
+                &owner_id,
+                owner_addr,
                 P::SERVICE,
             )
         } else {
-            let current_ip = self
+            let current_addr = self
                 .context
                 .state_manager
-                .get_speaker_ip(&self.context.speaker_id)
-                .unwrap_or(self.context.speaker_ip);
-            (self.context.speaker_id.clone(), current_ip)
-        };
+                .get_speaker_addr(&owner_id)
+                .unwrap_or(owner_addr);
+            (owner_id, current_addr)
+        })
+    }
 
+    fn fetch_from(&self, target_id: &SpeakerId, target_addr: SocketAddr) -> Result<P, SdkError> {
+        let operation = P::build_operation()?;
         let response = self
             .context
             .api_client
-            .execute_enhanced(&target_ip.to_string(), operation)
+            .execute_enhanced(&target_addr.to_string(), operation)
             .map_err(SdkError::ApiError)?;
 
         let property_value = P::from_response(response);
@@ -541,12 +691,105 @@ impl<P: Fetchable> PropertyHandle<P> {
         // Store under target_id (coordinator for PerCoordinator, self for PerSpeaker)
         self.context
             .state_manager
-            .set_property(&target_id, property_value.clone());
+            .set_property(target_id, property_value.clone());
 
         Ok(property_value)
     }
 }
 
+impl PropertyHandle<Mute> {
+    /// Flip mute and return the state the speaker confirmed
+    ///
+    /// Starts from the cached value while this property is watched, or a
+    /// fetched one otherwise. After the write the speaker is read back; if
+    /// another controller's change undid the write (the speaker shows the
+    /// starting state again), it is written once more from there. The
+    /// write is recorded like [`Speaker::set_mute()`](crate::Speaker::set_mute),
+    /// so its echo is not reported as an external change.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let muted = speaker.mute.toggle()?;
+    /// println!("Muted: {}", muted.0);
+    /// ```
+    pub fn toggle(&self) -> Result<Mute, SdkError> {
+        let baseline = self.fresh()?;
+        toggle::toggle(
+            Mute::KEY,
+            baseline,
+            |mute| mute.0,
+            |muted| {
+                self.write(
+                    rendering_control::set_mute("Master".to_string(), muted).build(),
+                    Mute(muted),
+                )
+            },
+            || self.read_back(),
+        )
+    }
+}
+
+impl PropertyHandle<Bass> {
+    /// Set the bass level (-10 to +10)
+    ///
+    /// Same as [`Speaker::set_bass()`](crate::Speaker::set_bass): out of
+    /// range levels fail validation without a request, and the cache holds
+    /// the new level once the speaker accepts it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// speaker.bass.set(-3)?;
+    /// assert_eq!(speaker.bass.get(), Some(Bass(-3)));
+    /// ```
+    pub fn set(&self, level: i8) -> Result<(), SdkError> {
+        self.write(rendering_control::set_bass(level).build(), Bass(level))
+    }
+}
+
+impl PropertyHandle<Treble> {
+    /// Set the treble level (-10 to +10)
+    ///
+    /// Same as [`Speaker::set_treble()`](crate::Speaker::set_treble).
+    pub fn set(&self, level: i8) -> Result<(), SdkError> {
+        self.write(rendering_control::set_treble(level).build(), Treble(level))
+    }
+}
+
+impl PropertyHandle<CurrentPlayMode> {
+    /// Set the play mode
+    ///
+    /// Same as [`Speaker::set_play_mode()`](crate::Speaker::set_play_mode).
+    /// To flip shuffle and keep the repeat setting, pass
+    /// `mode.with_shuffle(!mode.is_shuffle())`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mode = speaker.play_mode.fetch()?.mode();
+    /// speaker.play_mode.set(mode.with_shuffle(!mode.is_shuffle()))?;
+    /// ```
+    pub fn set(&self, mode: PlayMode) -> Result<(), SdkError> {
+        self.write(
+            av_transport::set_play_mode(mode).build(),
+            CurrentPlayMode(mode),
+        )
+    }
+}
+
+impl PropertyHandle<Loudness> {
+    /// Turn loudness compensation on or off (`Master` channel)
+    ///
+    /// Same as [`Speaker::set_loudness()`](crate::Speaker::set_loudness).
+    pub fn set(&self, enabled: bool) -> Result<(), SdkError> {
+        self.write(
+            rendering_control::set_loudness("Master".to_string(), enabled).build(),
+            Loudness(enabled),
+        )
+    }
+}
+
 // ============================================================================
 // Concrete fetch for FetchableWithContext properties
 // ============================================================================
@@ -563,27 +806,73 @@ impl PropertyHandle<GroupMembership> {
     #[must_use = "returns the fetched value from the device"]
     pub fn fetch(&self) -> Result<GroupMembership, SdkError> {
         let operation = <GroupMembership as FetchableWithContext>::build_operation()?;
+        let speaker_id = &self.context.speaker_id;
 
-        let response = self
-            .context
-            .api_client
-            .execute_enhanced(&self.context.speaker_ip.to_string(), operation)
-            .map_err(SdkError::ApiError)?;
-
-        let property_value =
-            GroupMembership::from_response_with_context(response, &self.context.speaker_id)
+        self.context.fetches.run(
+            speaker_id,
+            GroupMembership::KEY,
+            || self.context.state_manager.get_property(speaker_id),
+            || {
+                let response = self
+                    .context
+                    .api_client
+                    .execute_enhanced(&self.context.speaker_addr.to_string(), operation)
+                    .map_err(SdkError::ApiError)?;
+
+                let property_value = GroupMembership::from_response_with_context(
+                    response, speaker_id,
+                )
                 .ok_or_else(|| {
                     SdkError::FetchFailed(format!(
                         "Speaker {} not found in topology response",
-                        self.context.speaker_id.as_str()
+                        speaker_id.as_str()
                     ))
                 })?;
 
-        self.context
+                self.context
+                    .state_manager
+                    .set_property(speaker_id, property_value.clone());
+
+                Ok(property_value)
+            },
+        )
+    }
+}
+
+impl PropertyHandle<AudioOutput> {
+    /// Read where the speaker's audio is going + update cache (sync)
+    ///
+    /// Reads the Bluetooth status page and, unless Bluetooth is active,
+    /// `GetHeadphoneConnected`; see
+    /// [`read_audio_output()`](device_properties::read_audio_output).
+    /// Models other than the portables fail with [`SdkError::NotSupported`]
+    /// without a request.
+    #[must_use = "returns the fetched value from the device"]
+    pub fn fetch(&self) -> Result<AudioOutput, SdkError> {
+        self.context.check_supported::<AudioOutput>()?;
+        let speaker_id = &self.context.speaker_id;
+        let addr = self
+            .context
             .state_manager
-            .set_property(&self.context.speaker_id, property_value.clone());
+            .get_speaker_addr(speaker_id)
+            .unwrap_or(self.context.speaker_addr);
 
-        Ok(property_value)
+        self.context.fetches.run(
+            speaker_id,
+            AudioOutput::KEY,
+            || self.context.state_manager.get_property(speaker_id),
+            || {
+                let wire = device_properties::read_audio_output(
+                    &self.context.api_client,
+                    &addr.to_string(),
+                )?;
+                let output = AudioOutput::from_wire(wire).ok_or_else(|| {
+                    SdkError::FetchFailed(format!("Unknown audio output {wire:?}"))
+                })?;
+                self.context.state_manager.set_property(speaker_id, output);
+                Ok(output)
+            },
+        )
     }
 }
 
@@ -593,34 +882,62 @@ impl PropertyHandle<GroupMembership> {
 
 use sonos_api::services::{
     av_transport::{
-        self, GetPositionInfoOperation, GetPositionInfoResponse, GetTransportInfoOperation,
-        GetTransportInfoResponse,
+        self, GetCurrentTransportActionsOperation, GetCurrentTransportActionsResponse,
+        GetPositionInfoOperation, GetPositionInfoResponse, GetTransportInfoOperation,
+        GetTransportInfoResponse, GetTransportSettingsOperation, GetTransportSettingsResponse,
+        PlayMode,
+    },
+    device_properties::{
+        self, GetButtonLockStateOperation, GetButtonLockStateResponse, GetLedStateOperation,
+        GetLedStateResponse,
     },
     group_rendering_control::{
         self, GetGroupMuteOperation, GetGroupMuteResponse, GetGroupVolumeOperation,
         GetGroupVolumeResponse,
     },
     rendering_control::{
-        self, GetBassOperation, GetBassResponse, GetLoudnessOperation, GetLoudnessResponse,
-        GetMuteOperation, GetMuteResponse, GetTrebleOperation, GetTrebleResponse,
-        GetVolumeOperation, GetVolumeResponse,
+        self, GetBassOperation, GetBassResponse, GetEqOperation, GetEqResponse,
+        GetLoudnessOperation, GetLoudnessResponse, GetMuteOperation, GetMuteResponse,
+        GetOutputFixedOperation, GetOutputFixedResponse, GetTrebleOperation, GetTrebleResponse,
+        GetVolumeOperation, GetVolumeResponse, ListPresetsOperation, ListPresetsResponse,
     },
     zone_group_topology::{self, GetZoneGroupStateOperation, GetZoneGroupStateResponse},
 };
 use sonos_state::{
-    Bass, CurrentTrack, GroupId, GroupMembership, GroupMute, GroupVolume, GroupVolumeChangeable,
-    Loudness, Mute, PlaybackState, Position, Treble, Volume,
+    AudioOutput, Bass, ButtonLock, CurrentPlayMode, CurrentTrack, GroupComposition, GroupId,
+    GroupMembership, GroupMute, GroupVolume, GroupVolumeChangeable, Loudness, Mute, OutputFixed,
+    PlaybackState, Position, Presets, StatusLight, SubEnabled, SubGain, SurroundEnabled,
+    SurroundLevel, SurroundMode, TransportActions, Treble, Volume,
 };
 
 // ============================================================================
 // Helper functions
 // ============================================================================
 
+/// Whether the coordinator a group command went to answered as a member
+fn refused_as_member(error: &ApiError) -> bool {
+    error
+        .upnp_error()
+        .is_some_and(|code| code.is_not_coordinator())
+}
+
 /// Helper to create consistent error messages for operation build failures
 fn build_error<E: std::fmt::Display>(operation_name: &str, e: E) -> SdkError {
     SdkError::FetchFailed(format!("Failed to build {operation_name} operation: {e}"))
 }
 
+/// Build a `GetEQ` operation for one home-theater `EQType`
+fn get_eq_operation(eq_type: &str) -> Result<ComposableOperation<GetEqOperation>, SdkError> {
+    rendering_control::get_eq_operation(eq_type.to_string())
+        .build()
+        .map_err(|e| build_error("GetEQ", e))
+}
+
+/// Narrow a signed `GetEQ` level to the -15..=15 range
+fn eq_level(response: &GetEqResponse) -> i8 {
+    response.current_value.clamp(-15, 15) as i8
+}
+
 // ============================================================================
 // Fetchable implementations
 // ============================================================================
@@ -668,9 +985,12 @@ impl Fetchable for Position {
     }
 
     fn from_response(response: GetPositionInfoResponse) -> Self {
-        let position_ms = Position::parse_time_to_ms(&response.rel_time).unwrap_or(0);
-        let duration_ms = Position::parse_time_to_ms(&response.track_duration).unwrap_or(0);
-        Position::new(position_ms, duration_ms)
+        // Line-in and TV report no timeline: a zero position and duration
+        let millis = |time: Option<Duration>| time.map_or(0, |t| t.as_millis() as u64);
+        Position::new(
+            millis(response.rel_position()),
+            millis(response.track_length()),
+        )
     }
 }
 
@@ -730,6 +1050,122 @@ impl Fetchable for Loudness {
     }
 }
 
+impl Fetchable for OutputFixed {
+    type Operation = GetOutputFixedOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        rendering_control::get_output_fixed_operation()
+            .build()
+            .map_err(|e| build_error("GetOutputFixed", e))
+    }
+
+    fn from_response(response: GetOutputFixedResponse) -> Self {
+        OutputFixed(response.current_fixed)
+    }
+}
+
+impl Fetchable for Presets {
+    type Operation = ListPresetsOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        rendering_control::list_presets_operation()
+            .build()
+            .map_err(|e| build_error("ListPresets", e))
+    }
+
+    fn from_response(response: ListPresetsResponse) -> Self {
+        Presets(response.preset_names())
+    }
+}
+
+impl Fetchable for ButtonLock {
+    type Operation = GetButtonLockStateOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        device_properties::get_button_lock_state_operation()
+            .build()
+            .map_err(|e| build_error("GetButtonLockState", e))
+    }
+
+    fn from_response(response: GetButtonLockStateResponse) -> Self {
+        ButtonLock::new(response.current_button_lock_state)
+    }
+}
+
+impl Fetchable for StatusLight {
+    type Operation = GetLedStateOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        device_properties::get_led_state_operation()
+            .build()
+            .map_err(|e| build_error("GetLEDState", e))
+    }
+
+    fn from_response(response: GetLedStateResponse) -> Self {
+        StatusLight::new(response.current_led_state)
+    }
+}
+
+impl Fetchable for SubEnabled {
+    type Operation = GetEqOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        get_eq_operation("SubEnable")
+    }
+
+    fn from_response(response: GetEqResponse) -> Self {
+        SubEnabled(response.current_value == 1)
+    }
+}
+
+impl Fetchable for SubGain {
+    type Operation = GetEqOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        get_eq_operation("SubGain")
+    }
+
+    fn from_response(response: GetEqResponse) -> Self {
+        SubGain::new(eq_level(&response))
+    }
+}
+
+impl Fetchable for SurroundEnabled {
+    type Operation = GetEqOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        get_eq_operation("SurroundEnable")
+    }
+
+    fn from_response(response: GetEqResponse) -> Self {
+        SurroundEnabled(response.current_value == 1)
+    }
+}
+
+impl Fetchable for SurroundMode {
+    type Operation = GetEqOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        get_eq_operation("SurroundMode")
+    }
+
+    fn from_response(response: GetEqResponse) -> Self {
+        SurroundMode::from_eq_value(response.current_value)
+    }
+}
+
+impl Fetchable for SurroundLevel {
+    type Operation = GetEqOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        get_eq_operation("SurroundLevel")
+    }
+
+    fn from_response(response: GetEqResponse) -> Self {
+        SurroundLevel::new(eq_level(&response))
+    }
+}
+
 impl Fetchable for CurrentTrack {
     type Operation = GetPositionInfoOperation;
 
@@ -740,24 +1176,51 @@ impl Fetchable for CurrentTrack {
     }
 
     fn from_response(response: GetPositionInfoResponse) -> Self {
-        let metadata = if response.track_meta_data.is_empty()
-            || response.track_meta_data == "NOT_IMPLEMENTED"
-        {
-            None
-        } else {
-            Some(response.track_meta_data.as_str())
-        };
+        let metadata = response
+            .track_meta_data
+            .as_deref()
+            .filter(|m| *m != "NOT_IMPLEMENTED");
         let (title, artist, album, album_art_uri) = sonos_state::parse_track_metadata(metadata);
         CurrentTrack {
             title,
             artist,
             album,
             album_art_uri,
-            uri: Some(response.track_uri).filter(|s| !s.is_empty()),
+            uri: response.track_uri.filter(|s| !s.is_empty()),
         }
     }
 }
 
+impl Fetchable for TransportActions {
+    type Operation = GetCurrentTransportActionsOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        av_transport::get_current_transport_actions_operation()
+            .build()
+            .map_err(|e| build_error("GetCurrentTransportActions", e))
+    }
+
+    fn from_response(response: GetCurrentTransportActionsResponse) -> Self {
+        TransportActions::parse(&response.actions)
+    }
+}
+
+impl Fetchable for CurrentPlayMode {
+    type Operation = GetTransportSettingsOperation;
+
+    fn build_operation() -> Result<ComposableOperation<Self::Operation>, SdkError> {
+        av_transport::get_transport_settings_operation()
+            .build()
+            .map_err(|e| build_error("GetTransportSettings", e))
+    }
+
+    // A mode this crate doesn't know is read as NORMAL, like `PlaybackState`
+    // reads an unknown transport state as stopped
+    fn from_response(response: GetTransportSettingsResponse) -> Self {
+        CurrentPlayMode(response.mode().unwrap_or(PlayMode::Normal))
+    }
+}
+
 // ============================================================================
 // FetchableWithContext implementations
 // ============================================================================
@@ -779,9 +1242,12 @@ impl FetchableWithContext for GroupMembership {
             zone_group_topology::parse_zone_group_state_xml(&response.zone_group_state).ok()?;
 
         for group in &zone_groups {
-            let is_member = group.members.iter().any(|m| m.uuid == speaker_id.as_str());
+            let is_member = group
+                .members
+                .iter()
+                .any(|m| SpeakerId::new(&m.uuid) == *speaker_id);
             if is_member {
-                let is_coordinator = group.coordinator == speaker_id.as_str();
+                let is_coordinator = SpeakerId::new(&group.coordinator) == *speaker_id;
                 return Some(GroupMembership::new(
                     GroupId::new(&group.id),
                     is_coordinator,
@@ -826,12 +1292,48 @@ pub type TrebleHandle = PropertyHandle<Treble>;
 /// Handle for loudness compensation setting
 pub type LoudnessHandle = PropertyHandle<Loudness>;
 
+/// Handle for the fixed line-out volume setting
+pub type OutputFixedHandle = PropertyHandle<OutputFixed>;
+
+/// Handle for the EQ presets the speaker can recall
+pub type PresetsHandle = PropertyHandle<Presets>;
+
+/// Handle for the buttons/touch controls lock
+pub type ButtonLockHandle = PropertyHandle<ButtonLock>;
+
+/// Handle for the white status light
+pub type StatusLightHandle = PropertyHandle<StatusLight>;
+
+/// Handle for where a portable speaker's audio is going
+pub type AudioOutputHandle = PropertyHandle<AudioOutput>;
+
+/// Handle for the bonded Sub on/off setting
+pub type SubEnabledHandle = PropertyHandle<SubEnabled>;
+
+/// Handle for the bonded Sub level (-15 to +15)
+pub type SubGainHandle = PropertyHandle<SubGain>;
+
+/// Handle for the bonded surrounds on/off setting
+pub type SurroundEnabledHandle = PropertyHandle<SurroundEnabled>;
+
+/// Handle for the surround music playback mode (Ambient/Full)
+pub type SurroundModeHandle = PropertyHandle<SurroundMode>;
+
+/// Handle for the surround TV level (-15 to +15)
+pub type SurroundLevelHandle = PropertyHandle<SurroundLevel>;
+
 /// Handle for current playback position
 pub type PositionHandle = PropertyHandle<Position>;
 
 /// Handle for current track information
 pub type CurrentTrackHandle = PropertyHandle<CurrentTrack>;
 
+/// Handle for currently allowed transport actions
+pub type TransportActionsHandle = PropertyHandle<TransportActions>;
+
+/// Handle for the queue play mode (shuffle and repeat)
+pub type CurrentPlayModeHandle = PropertyHandle<CurrentPlayMode>;
+
 /// Handle for group membership information
 pub type GroupMembershipHandle = PropertyHandle<GroupMembership>;
 
@@ -847,7 +1349,7 @@ pub type GroupMembershipHandle = PropertyHandle<GroupMembership>;
 pub struct GroupContext {
     pub(crate) group_id: GroupId,
     pub(crate) coordinator_id: SpeakerId,
-    pub(crate) coordinator_ip: IpAddr,
+    pub(crate) coordinator_addr: SocketAddr,
     pub(crate) state_manager: Arc<StateManager>,
     pub(crate) api_client: SonosClient,
 }
@@ -857,18 +1359,121 @@ impl GroupContext {
     pub fn new(
         group_id: GroupId,
         coordinator_id: SpeakerId,
-        coordinator_ip: IpAddr,
+        coordinator_addr: SocketAddr,
         state_manager: Arc<StateManager>,
         api_client: SonosClient,
     ) -> Arc<Self> {
         Arc::new(Self {
             group_id,
             coordinator_id,
-            coordinator_ip,
+            coordinator_addr,
             state_manager,
             api_client,
         })
     }
+
+    /// Run `send` against the group's coordinator, and once more against a
+    /// new one if the speaker answers that it no longer coordinates
+    ///
+    /// The coordinator is picked by the state manager's
+    /// [`CoordinatorResolver`](sonos_state::CoordinatorResolver), which waits
+    /// for a group that was just reshaped to settle; the handle's own
+    /// coordinator is used if the store has no group for it. A speaker that
+    /// stopped coordinating faults with UPnP error 701
+    /// ([`ApiError::NotCoordinator`](sonos_api::ApiError::NotCoordinator)
+    /// for GroupRenderingControl). If the
+    /// topology has not changed since the route was read it is fetched from
+    /// that speaker first; the command is then sent to the coordinator the
+    /// store names. With none known, or the same one, the fault becomes
+    /// [`SdkError::NotCoordinator`]; so it does when that one refuses too,
+    /// after the topology is fetched from it to hint at the coordinator.
+    pub(crate) fn on_coordinator<T>(
+        &self,
+        mut send: impl FnMut(&SpeakerId, SocketAddr) -> Result<T, SdkError>,
+    ) -> Result<T, SdkError> {
+        let resolver = self.state_manager.coordinator_resolver();
+        let route = resolver
+            .route(&self.coordinator_id)
+            .unwrap_or_else(|| CoordinatorRoute {
+                coordinator_id: self.coordinator_id.clone(),
+                addr: self.coordinator_addr,
+                generation: self.state_manager.topology_generation(),
+            });
+        match send(&route.coordinator_id, route.addr) {
+            Err(SdkError::ApiError(e)) if refused_as_member(&e) => {
+                if self.state_manager.topology_generation() == route.generation {
+                    self.refresh_topology(route.addr);
+                }
+                let next = match resolver.current(&self.coordinator_id) {
+                    Some(next) if next.coordinator_id != route.coordinator_id => next,
+                    _ => return Err(self.not_coordinator(&route.coordinator_id, e)),
+                };
+                tracing::debug!(
+                    "{} is no longer coordinator of {}, redirecting to {}",
+                    route.coordinator_id.as_str(),
+                    self.group_id.as_str(),
+                    next.coordinator_id.as_str()
+                );
+                resolver.note_reroute();
+                match send(&next.coordinator_id, next.addr) {
+                    Err(SdkError::ApiError(e)) if refused_as_member(&e) => {
+                        self.refresh_topology(next.addr);
+                        Err(self.not_coordinator(&next.coordinator_id, e))
+                    }
+                    result => result,
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// [`SdkError::NotCoordinator`] for `speaker_id`'s refusal, hinting at
+    /// the coordinator topology now names for it
+    fn not_coordinator(&self, speaker_id: &SpeakerId, source: ApiError) -> SdkError {
+        let coordinator_hint = self
+            .state_manager
+            .coordinator_resolver()
+            .current(speaker_id)
+            .map(|route| route.coordinator_id)
+            .filter(|id| id != speaker_id);
+        SdkError::NotCoordinator {
+            speaker_id: speaker_id.clone(),
+            coordinator_hint,
+            source,
+        }
+    }
+
+    /// Fetch the topology from `addr` into the store
+    fn refresh_topology(&self, addr: SocketAddr) {
+        match zone_group_topology::state::poll(&self.api_client, &addr.to_string()) {
+            Ok(state) => {
+                self.state_manager.refresh_topology(addr, state);
+                self.state_manager.coordinator_resolver().note_refresh();
+            }
+            Err(e) => tracing::warn!("Topology refresh from {} failed: {}", addr, e),
+        }
+    }
+
+    /// Send a mutating operation to the coordinator through the write
+    /// interceptors, following a coordinator handoff
+    pub(crate) fn write<Op: UPnPOperation>(
+        &self,
+        operation: Result<ComposableOperation<Op>, ValidationError>,
+    ) -> Result<Sent<Op::Response>, SdkError>
+    where
+        Op::Request: Clone,
+    {
+        let operation = operation?;
+        self.on_coordinator(|coordinator_id, addr| {
+            send_write(
+                &self.state_manager,
+                &self.api_client,
+                coordinator_id,
+                addr,
+                operation.clone(),
+            )
+        })
+    }
 }
 
 /// Generic property handle for group-scoped properties
@@ -902,7 +1507,9 @@ impl<P: SonosProperty> GroupPropertyHandle<P> {
     /// Start watching this group property for changes (sync)
     ///
     /// Returns a [`WatchHandle`] scoped to the group coordinator.
-    /// Hold the handle to keep the subscription alive.
+    /// Hold the handle to keep the subscription alive. Like
+    /// [`PropertyHandle::watch()`], a new watch delivers one initial
+    /// notification, under the coordinator's ID.
     pub fn watch(&self) -> Result<WatchHandle<P>, SdkError> {
         // Trigger lazy event manager init if needed
         if self.context.state_manager.event_manager().is_none() {
@@ -921,11 +1528,17 @@ impl<P: SonosProperty> GroupPropertyHandle<P> {
             }
         }
 
+        // Group changes are keyed on the coordinator, and so is the initial
+        // notification
+        self.context
+            .state_manager
+            .begin_watch(&self.context.coordinator_id, P::KEY, P::SERVICE);
+
         let (mode, cleanup) = if let Some(em) = self.context.state_manager.event_manager() {
             match em.acquire_watch(
                 &self.context.coordinator_id,
                 P::KEY,
-                self.context.coordinator_ip,
+                self.context.coordinator_addr,
                 P::SERVICE,
             ) {
                 Ok(guard) => (WatchMode::Events, WatchCleanup::Guard(guard)),
@@ -936,9 +1549,6 @@ impl<P: SonosProperty> GroupPropertyHandle<P> {
                         self.context.group_id.as_str(),
                         e
                     );
-                    self.context
-                        .state_manager
-                        .register_watch(&self.context.coordinator_id, P::KEY);
                     (
                         WatchMode::Polling,
                         WatchCleanup::CacheOnly(CacheOnlyGuard {
@@ -950,9 +1560,6 @@ impl<P: SonosProperty> GroupPropertyHandle<P> {
                 }
             }
         } else {
-            self.context
-                .state_manager
-                .register_watch(&self.context.coordinator_id, P::KEY);
             (
                 WatchMode::CacheOnly,
                 WatchCleanup::CacheOnly(CacheOnlyGuard {
@@ -1017,15 +1624,17 @@ impl<P: GroupFetchable> GroupPropertyHandle<P> {
     }
 
     /// Fetch fresh value from coordinator + update group cache (sync)
+    ///
+    /// Follows a coordinator handoff like the group's writes: a coordinator
+    /// that answers it no longer is one is asked again at the current one.
     #[must_use = "returns the fetched value from the device"]
     pub fn fetch(&self) -> Result<P, SdkError> {
-        let operation = P::build_operation()?;
-
-        let response = self
-            .context
-            .api_client
-            .execute_enhanced(&self.context.coordinator_ip.to_string(), operation)
-            .map_err(SdkError::ApiError)?;
+        let response = self.context.on_coordinator(|_, addr| {
+            self.context
+                .api_client
+                .execute_enhanced(&addr.to_string(), P::build_operation()?)
+                .map_err(SdkError::ApiError)
+        })?;
 
         let property_value = P::from_response(response);
 
@@ -1037,6 +1646,75 @@ impl<P: GroupFetchable> GroupPropertyHandle<P> {
     }
 }
 
+impl GroupPropertyHandle<GroupVolume> {
+    /// Set the group volume (0-100) on the coordinator
+    ///
+    /// Same as [`Group::set_volume()`](crate::Group::set_volume): levels
+    /// above 100 fail validation without a request, and the cache holds the
+    /// new volume once the coordinator accepts it. Members keep their volume
+    /// ratios as of the last [`snapshot()`](Self::snapshot).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// group.volume.snapshot()?; // when the slider drag starts
+    /// group.volume.set(40)?;
+    /// ```
+    pub fn set(&self, volume: u16) -> Result<(), SdkError> {
+        let sent = self
+            .context
+            .write(group_rendering_control::set_group_volume(volume).build())?;
+        if !sent.modified {
+            self.context
+                .state_manager
+                .apply_local_group_write(&self.context.group_id, GroupVolume(volume));
+        }
+        Ok(())
+    }
+
+    /// Change the group volume by `adjustment` (-100 to +100), returning the
+    /// volume the coordinator settled on
+    pub fn set_relative(&self, adjustment: i16) -> Result<GroupVolume, SdkError> {
+        let response = self
+            .context
+            .write(group_rendering_control::set_relative_group_volume(adjustment).build())?
+            .response;
+        let volume = GroupVolume(response.new_volume);
+        self.context
+            .state_manager
+            .apply_local_group_write(&self.context.group_id, volume.clone());
+        Ok(volume)
+    }
+
+    /// Record the members' current volume ratios, which later group volume
+    /// changes scale by
+    ///
+    /// Take one before a run of changes, so member volumes adjusted
+    /// individually since the last snapshot are respected.
+    pub fn snapshot(&self) -> Result<(), SdkError> {
+        self.context
+            .write(group_rendering_control::snapshot_group_volume().build())?;
+        Ok(())
+    }
+}
+
+impl GroupPropertyHandle<GroupMute> {
+    /// Mute or unmute the whole group on the coordinator
+    ///
+    /// Same as [`Group::set_mute()`](crate::Group::set_mute).
+    pub fn set(&self, muted: bool) -> Result<(), SdkError> {
+        let sent = self
+            .context
+            .write(group_rendering_control::set_group_mute(muted).build())?;
+        if !sent.modified {
+            self.context
+                .state_manager
+                .apply_local_group_write(&self.context.group_id, GroupMute(muted));
+        }
+        Ok(())
+    }
+}
+
 // ============================================================================
 // GroupFetchable implementations
 // ============================================================================
@@ -1082,6 +1760,9 @@ pub type GroupMuteHandle = GroupPropertyHandle<GroupMute>;
 /// Handle for group volume changeable flag (event-only, no fetch)
 pub type GroupVolumeChangeableHandle = GroupPropertyHandle<GroupVolumeChangeable>;
 
+/// Handle for the group's coordinator and members (from topology, no fetch)
+pub type GroupCompositionHandle = GroupPropertyHandle<GroupComposition>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1096,6 +1777,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
         Arc::new(manager)
@@ -1104,7 +1787,7 @@ mod tests {
     fn create_test_context(state_manager: Arc<StateManager>) -> Arc<SpeakerContext> {
         SpeakerContext::new(
             SpeakerId::new("RINCON_TEST123"),
-            "192.168.1.100".parse().unwrap(),
+            "192.168.1.100:1400".parse().unwrap(),
             state_manager,
             SonosClient::new(),
         )
@@ -1114,12 +1797,13 @@ mod tests {
     fn test_property_handle_creation() {
         let state_manager = create_test_state_manager();
         let context = create_test_context(state_manager);
-        let speaker_ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let speaker_addr: SocketAddr = "192.168.1.100:1400".parse().unwrap();
 
         let handle: VolumeHandle = PropertyHandle::new(context);
 
         assert_eq!(handle.speaker_id().as_str(), "RINCON_TEST123");
-        assert_eq!(handle.speaker_ip(), speaker_ip);
+        assert_eq!(handle.speaker_addr(), speaker_addr);
+        assert_eq!(handle.speaker_ip(), speaker_addr.ip());
     }
 
     #[test]
@@ -1233,7 +1917,7 @@ mod tests {
         GroupContext::new(
             GroupId::new("RINCON_TEST123:1"),
             SpeakerId::new("RINCON_TEST123"),
-            "192.168.1.100".parse().unwrap(),
+            "192.168.1.100:1400".parse().unwrap(),
             state_manager,
             SonosClient::new(),
         )
@@ -1310,6 +1994,188 @@ mod tests {
         assert_eq!(handle.group_id().as_str(), "RINCON_TEST123:1");
     }
 
+    // ========================================================================
+    // Initial notification contract, shared by every way of starting a watch
+    // ========================================================================
+
+    use sonos_state::{ChangeOrigin, GroupInfo, Property, Topology};
+    use std::any::Any;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+
+    /// One way of starting a watch
+    struct Watcher {
+        /// Starts a watch that lasts as long as the returned guard
+        start: fn(&Arc<StateManager>) -> Box<dyn Any>,
+        set: fn(&StateManager, u8),
+        get: fn(&StateManager) -> Option<u8>,
+        key: &'static str,
+    }
+
+    fn test_group_id() -> GroupId {
+        GroupId::new("RINCON_TEST123:1")
+    }
+
+    fn create_grouped_state_manager() -> Arc<StateManager> {
+        let state_manager = create_test_state_manager();
+        let coordinator = SpeakerId::new("RINCON_TEST123");
+        let group = GroupInfo::new(test_group_id(), coordinator.clone(), vec![coordinator]);
+        state_manager.initialize(Topology::new(state_manager.speaker_infos(), vec![group]));
+        state_manager
+    }
+
+    /// Origins of the pending events for `key`
+    fn origins(state_manager: &StateManager, key: &str) -> Vec<ChangeOrigin> {
+        state_manager
+            .iter()
+            .try_iter()
+            .filter(|e| e.property_key == key)
+            .map(|e| e.origin)
+            .collect()
+    }
+
+    fn set_volume(state_manager: &StateManager, value: u8) {
+        state_manager.set_property(&SpeakerId::new("RINCON_TEST123"), Volume::new(value));
+    }
+
+    fn get_volume(state_manager: &StateManager) -> Option<u8> {
+        state_manager
+            .get_property::<Volume>(&SpeakerId::new("RINCON_TEST123"))
+            .map(|v| v.value())
+    }
+
+    const SPEAKER_WATCH: Watcher = Watcher {
+        start: |state_manager| {
+            let handle: VolumeHandle =
+                PropertyHandle::new(create_test_context(Arc::clone(state_manager)));
+            Box::new(handle.watch().unwrap())
+        },
+        set: set_volume,
+        get: get_volume,
+        key: Volume::KEY,
+    };
+
+    const GROUP_WATCH: Watcher = Watcher {
+        start: |state_manager| {
+            let handle: GroupVolumeHandle =
+                GroupPropertyHandle::new(create_test_group_context(Arc::clone(state_manager)));
+            Box::new(handle.watch().unwrap())
+        },
+        set: |state_manager, value| {
+            state_manager.set_group_property(&test_group_id(), GroupVolume::new(value.into()))
+        },
+        get: |state_manager| {
+            state_manager
+                .get_group_property::<GroupVolume>(&test_group_id())
+                .and_then(|v| u8::try_from(v.value()).ok())
+        },
+        key: GroupVolume::KEY,
+    };
+
+    /// `StateManager::watch_property_with_subscription()`, unwatched on drop
+    const STATE_MANAGER_WATCH: Watcher = Watcher {
+        start: |state_manager| {
+            struct Unwatch(Arc<StateManager>);
+            impl Drop for Unwatch {
+                fn drop(&mut self) {
+                    self.0
+                        .unwatch_property_with_subscription::<Volume>(&SpeakerId::new(
+                            "RINCON_TEST123",
+                        ));
+                }
+            }
+            state_manager
+                .watch_property_with_subscription::<Volume>(&SpeakerId::new("RINCON_TEST123"))
+                .unwrap();
+            Box::new(Unwatch(Arc::clone(state_manager)))
+        },
+        set: set_volume,
+        get: get_volume,
+        key: Volume::KEY,
+    };
+
+    fn assert_initial_notification_contract(watcher: &Watcher) {
+        let state_manager = create_grouped_state_manager();
+        let events = || origins(&state_manager, watcher.key);
+
+        // Announced even while unset
+        let first = (watcher.start)(&state_manager);
+        assert_eq!(events(), [ChangeOrigin::Initial]);
+        assert_eq!((watcher.get)(&state_manager), None);
+
+        // Watching again continues the same watch; only real changes follow
+        let second = (watcher.start)(&state_manager);
+        (watcher.set)(&state_manager, 40);
+        (watcher.set)(&state_manager, 40);
+        assert_eq!(events(), [ChangeOrigin::Unknown]);
+        drop((first, second));
+
+        // A fresh watch announces the value it finds
+        (watcher.set)(&state_manager, 50);
+        let _third = (watcher.start)(&state_manager);
+        assert_eq!(events(), [ChangeOrigin::Initial]);
+        assert_eq!((watcher.get)(&state_manager), Some(50));
+    }
+
+    fn assert_one_initial_notification_under_contention(watcher: &Watcher) {
+        const WATCHERS: usize = 4;
+        let state_manager = create_grouped_state_manager();
+        let done = AtomicBool::new(false);
+        let all_watching = Barrier::new(WATCHERS);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for value in (0..=100).cycle() {
+                    if done.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    (watcher.set)(&state_manager, value);
+                }
+            });
+            let starts: Vec<_> = (0..WATCHERS)
+                .map(|_| {
+                    s.spawn(|| {
+                        let _guard = (watcher.start)(&state_manager);
+                        all_watching.wait();
+                    })
+                })
+                .collect();
+            for start in starts {
+                start.join().unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        let events = origins(&state_manager, watcher.key);
+        let initial = events.iter().filter(|o| **o == ChangeOrigin::Initial);
+        assert_eq!(initial.count(), 1);
+    }
+
+    macro_rules! initial_notification_tests {
+        ($($kind:ident => $watcher:expr),* $(,)?) => {$(
+            mod $kind {
+                use super::*;
+
+                #[test]
+                fn test_initial_notification_contract() {
+                    assert_initial_notification_contract(&$watcher);
+                }
+
+                #[test]
+                fn test_one_initial_notification_under_contention() {
+                    assert_one_initial_notification_under_contention(&$watcher);
+                }
+            }
+        )*};
+    }
+
+    initial_notification_tests! {
+        speaker_watch => SPEAKER_WATCH,
+        group_watch => GROUP_WATCH,
+        state_manager_watch => STATE_MANAGER_WATCH,
+    }
+
     // ========================================================================
     // Trait implementation assertions
     // ========================================================================
@@ -1324,7 +2190,34 @@ mod tests {
         assert_fetchable::<Bass>();
         assert_fetchable::<Treble>();
         assert_fetchable::<Loudness>();
+        assert_fetchable::<OutputFixed>();
+        assert_fetchable::<Presets>();
+        assert_fetchable::<ButtonLock>();
+        assert_fetchable::<StatusLight>();
         assert_fetchable::<CurrentTrack>();
+        assert_fetchable::<CurrentPlayMode>();
+    }
+
+    #[test]
+    fn test_position_from_response() {
+        let response = |duration: &str, rel_time: &str| GetPositionInfoResponse {
+            track: 1,
+            track_duration: duration.to_string(),
+            track_meta_data: None,
+            track_uri: None,
+            rel_time: rel_time.to_string(),
+            abs_time: "NOT_IMPLEMENTED".to_string(),
+            rel_count: None,
+            abs_count: None,
+        };
+        assert_eq!(
+            Position::from_response(response("0:03:58", "0:01:07.5")),
+            Position::new(67_500, 238_000)
+        );
+        assert_eq!(
+            Position::from_response(response("-1:-1:-1", "NOT_IMPLEMENTED")),
+            Position::new(0, 0)
+        );
     }
 
     #[test]
@@ -16,7 +16,10 @@ pub use handles::{WatchHandle, WatchMode};
 
 // Re-export type aliases for all property handles
 pub use handles::{
-    BassHandle, CurrentTrackHandle, GroupMembershipHandle, GroupMuteHandle,
-    GroupVolumeChangeableHandle, GroupVolumeHandle, LoudnessHandle, MuteHandle,
-    PlaybackStateHandle, PositionHandle, TrebleHandle, VolumeHandle,
+    AudioOutputHandle, BassHandle, ButtonLockHandle, CurrentPlayModeHandle, CurrentTrackHandle,
+    GroupCompositionHandle, GroupMembershipHandle, GroupMuteHandle, GroupVolumeChangeableHandle,
+    GroupVolumeHandle, LoudnessHandle, MuteHandle, OutputFixedHandle, PlaybackStateHandle,
+    PositionHandle, PresetsHandle, StatusLightHandle, SubEnabledHandle, SubGainHandle,
+    SurroundEnabledHandle, SurroundLevelHandle, SurroundModeHandle, TransportActionsHandle,
+    TrebleHandle, VolumeHandle,
 };
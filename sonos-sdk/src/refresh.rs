@@ -0,0 +1,276 @@
+//! Bringing the whole store back in line with the speakers
+//!
+//! See [`SonosSystem::refresh_all()`](crate::SonosSystem::refresh_all).
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+use serde::Serialize;
+use sonos_api::{ApiError, Service, SonosClient};
+use sonos_state::{
+    Bass, CurrentTrack, DynamicValue, Loudness, Mute, PlaybackState, Position, Property, SpeakerId,
+    StateManager, Treble, Volume,
+};
+
+use crate::property::Fetchable;
+use crate::{SdkError, Speaker};
+
+/// Speakers [`SonosSystem::refresh_all()`](crate::SonosSystem::refresh_all)
+/// reads at the same time
+pub const DEFAULT_REFRESH_PARALLELISM: usize = 4;
+
+/// Properties a refresh reads, in the order they are reported
+const REFRESHED_KEYS: [&str; 8] = [
+    Volume::KEY,
+    Mute::KEY,
+    Bass::KEY,
+    Treble::KEY,
+    Loudness::KEY,
+    PlaybackState::KEY,
+    Position::KEY,
+    CurrentTrack::KEY,
+];
+
+/// What [`SonosSystem::refresh_all()`](crate::SonosSystem::refresh_all)
+/// found out of date
+///
+/// A store kept current by events has nothing to correct, apart from the
+/// position of a playing track, which Sonos doesn't send events for.
+/// Corrections showing up refresh after refresh point to lost events.
+///
+/// ```rust,ignore
+/// let report = system.refresh_all();
+/// for speaker in &report.speakers {
+///     for fix in &speaker.corrected {
+///         println!("{}: {} {:?} -> {:?}", speaker.name, fix.key, fix.before, fix.after);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RefreshReport {
+    /// Whether the topology of every household was fetched and applied
+    pub topology: bool,
+    /// One entry per speaker, ordered by ID
+    pub speakers: Vec<SpeakerRefresh>,
+}
+
+impl RefreshReport {
+    /// Values corrected across all speakers
+    pub fn corrections(&self) -> usize {
+        self.speakers.iter().map(|s| s.corrected.len()).sum()
+    }
+
+    /// Speakers with at least one failed read
+    pub fn failed(&self) -> impl Iterator<Item = &SpeakerRefresh> {
+        self.speakers.iter().filter(|s| !s.failures.is_empty())
+    }
+
+    /// The entry of `speaker_id`
+    pub fn speaker(&self, speaker_id: &SpeakerId) -> Option<&SpeakerRefresh> {
+        self.speakers.iter().find(|s| s.speaker_id == *speaker_id)
+    }
+}
+
+/// One speaker's part of a [`RefreshReport`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpeakerRefresh {
+    pub speaker_id: SpeakerId,
+    pub name: String,
+    /// Values that differed from the store, now corrected
+    pub corrected: Vec<Correction>,
+    /// Reads that failed; the values they cover were left as they were
+    pub failures: Vec<RefreshFailure>,
+}
+
+impl SpeakerRefresh {
+    /// Whether the key's value was corrected
+    pub fn corrected(&self, key: &str) -> bool {
+        self.corrected.iter().any(|c| c.key == key)
+    }
+}
+
+/// A value a refresh found out of date
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Correction {
+    /// Property key, as in [`ChangeEvent::property_key`](sonos_state::ChangeEvent::property_key)
+    pub key: &'static str,
+    /// Value the store held
+    pub before: Option<DynamicValue>,
+    /// Value the speaker reported
+    pub after: Option<DynamicValue>,
+}
+
+/// A read that failed during a refresh
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RefreshFailure {
+    /// UPnP action, e.g. `GetVolume`
+    pub action: &'static str,
+    pub error: String,
+}
+
+/// Read and apply each speaker, `parallelism` speakers at a time
+pub(crate) fn refresh_speakers(
+    client: &SonosClient,
+    state_manager: &StateManager,
+    speakers: &[Speaker],
+    parallelism: usize,
+) -> Vec<SpeakerRefresh> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(speakers.len()));
+    thread::scope(|scope| {
+        for _ in 0..parallelism.clamp(1, speakers.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(speaker) = speakers.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let refresh = refresh_speaker(client, state_manager, speaker);
+                    results
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push(refresh);
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap_or_else(PoisonError::into_inner);
+    results.sort_by(|a, b| a.speaker_id.as_str().cmp(b.speaker_id.as_str()));
+    results
+}
+
+/// Read `P` from the speaker at `addr`
+fn read<P: Fetchable>(client: &SonosClient, addr: &str) -> Result<P, SdkError> {
+    let response = client.execute_enhanced(addr, P::build_operation()?)?;
+    Ok(P::from_response(response))
+}
+
+/// Read position and track, which come from the same response
+fn read_position_info(
+    client: &SonosClient,
+    addr: &str,
+) -> Result<(Position, CurrentTrack), SdkError> {
+    let operation = <Position as Fetchable>::build_operation()?;
+    let response = client.execute_enhanced(addr, operation)?;
+    Ok((
+        Position::from_response(response.clone()),
+        CurrentTrack::from_response(response),
+    ))
+}
+
+/// The value of a read, or `None` with the failure noted; actions the model
+/// doesn't implement (EQ on some models) are skipped without a failure
+fn noted<T>(
+    failures: &mut Vec<RefreshFailure>,
+    action: &'static str,
+    result: Result<T, SdkError>,
+) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(SdkError::ApiError(ApiError::NotSupported(_))) => None,
+        Err(e) => {
+            failures.push(RefreshFailure {
+                action,
+                error: e.to_string(),
+            });
+            None
+        }
+    }
+}
+
+fn refresh_speaker(
+    client: &SonosClient,
+    state_manager: &StateManager,
+    speaker: &Speaker,
+) -> SpeakerRefresh {
+    let id = &speaker.id;
+    let addr = state_manager
+        .get_speaker_addr(id)
+        .unwrap_or_else(|| speaker.addr());
+    let host = addr.to_string();
+    let mut failures = Vec::new();
+
+    let volume = noted(&mut failures, "GetVolume", read::<Volume>(client, &host));
+    let mute = noted(&mut failures, "GetMute", read::<Mute>(client, &host));
+    let bass = noted(&mut failures, "GetBass", read::<Bass>(client, &host));
+    let treble = noted(&mut failures, "GetTreble", read::<Treble>(client, &host));
+    let loudness = noted(
+        &mut failures,
+        "GetLoudness",
+        read::<Loudness>(client, &host),
+    );
+    // Members play what their coordinator does, which is read there
+    let coordinator = state_manager
+        .resolve_subscription_target(id, addr, Service::AVTransport)
+        .0
+        == *id;
+    let (playback, position_info) = if coordinator {
+        (
+            noted(
+                &mut failures,
+                "GetTransportInfo",
+                read::<PlaybackState>(client, &host),
+            ),
+            noted(
+                &mut failures,
+                "GetPositionInfo",
+                read_position_info(client, &host),
+            ),
+        )
+    } else {
+        (None, None)
+    };
+
+    let before: Vec<_> = REFRESHED_KEYS
+        .iter()
+        .map(|key| (*key, state_manager.get_property_dynamic(id, key)))
+        .collect();
+    let changed = state_manager.apply_fetched(id, |batch| {
+        if let Some(volume) = volume {
+            batch.set(volume);
+        }
+        if let Some(mute) = mute {
+            batch.set(mute);
+        }
+        if let Some(bass) = bass {
+            batch.set(bass);
+        }
+        if let Some(treble) = treble {
+            batch.set(treble);
+        }
+        if let Some(loudness) = loudness {
+            batch.set(loudness);
+        }
+        if let Some(playback) = playback {
+            batch.set(playback);
+        }
+        if let Some((position, track)) = position_info {
+            batch.set(position);
+            batch.set(track);
+        }
+    });
+    let corrected: Vec<_> = before
+        .into_iter()
+        .filter(|(key, _)| changed.contains(key))
+        .map(|(key, before)| Correction {
+            key,
+            before,
+            after: state_manager.get_property_dynamic(id, key),
+        })
+        .collect();
+
+    if !corrected.is_empty() {
+        tracing::debug!("Refresh corrected {} value(s) of {}", corrected.len(), id);
+    }
+    for failure in &failures {
+        tracing::warn!(
+            "Refresh of {}: {} failed: {}",
+            id,
+            failure.action,
+            failure.error
+        );
+    }
+    SpeakerRefresh {
+        speaker_id: id.clone(),
+        name: speaker.name.clone(),
+        corrected,
+        failures,
+    }
+}
@@ -0,0 +1,379 @@
+//! Everything scheduled to happen in the household
+//!
+//! See [`SonosSystem::scheduled_overview()`](crate::SonosSystem::scheduled_overview).
+
+use std::fmt;
+use std::thread::{self, ScopedJoinHandle};
+use std::time::Duration;
+
+use chrono::{Datelike, Days, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use sonos_api::services::alarm_clock::{Alarm, Recurrence};
+use sonos_state::{GroupId, SpeakerId};
+
+use crate::{SdkError, Speaker};
+
+/// Sleep timers, alarms and autoplay settings across the household
+///
+/// Times are the host's local time, which Sonos alarms assume too. Entries
+/// from a query that failed are missing; `partial` is set and `failures`
+/// says which.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScheduleOverview {
+    /// When the overview was taken; next alarm times count from here
+    pub generated_at: NaiveDateTime,
+    /// Running sleep timers, soonest first
+    pub sleep_timers: Vec<SleepTimerEntry>,
+    /// Enabled alarms, soonest first
+    pub alarms: Vec<AlarmEntry>,
+    /// Speakers whose line-in autoplays
+    pub autoplay: Vec<AutoplayEntry>,
+    /// Whether any query failed
+    pub partial: bool,
+    pub failures: Vec<ScheduleFailure>,
+}
+
+impl ScheduleOverview {
+    /// The enabled alarm that fires first
+    pub fn next_alarm(&self) -> Option<&AlarmEntry> {
+        self.alarms.iter().find(|alarm| alarm.next_fire.is_some())
+    }
+}
+
+/// A group's running sleep timer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SleepTimerEntry {
+    /// Coordinator that reported it
+    pub source: SpeakerId,
+    pub group_id: GroupId,
+    pub remaining_secs: u64,
+    /// When playback stops
+    pub ends_at: NaiveDateTime,
+}
+
+/// An enabled alarm
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlarmEntry {
+    /// Speaker that listed it
+    pub source: SpeakerId,
+    pub id: String,
+    /// Room the alarm plays in
+    pub room: SpeakerId,
+    /// Local time of day, `HH:MM:SS`
+    pub start_time: String,
+    pub recurrence: Recurrence,
+    /// How long it plays, `HH:MM:SS`
+    pub duration: String,
+    pub volume: u8,
+    pub program_uri: String,
+    pub include_linked_zones: bool,
+    /// When it fires next; `None` if the start time doesn't parse or it
+    /// fires on no day
+    pub next_fire: Option<NaiveDateTime>,
+}
+
+/// A speaker whose line-in starts playing in `room` when it gets a signal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AutoplayEntry {
+    pub source: SpeakerId,
+    pub room: SpeakerId,
+    pub volume: u8,
+}
+
+/// What a failed query asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleQuery {
+    SleepTimer,
+    Alarms,
+    Autoplay,
+}
+
+impl fmt::Display for ScheduleQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScheduleQuery::SleepTimer => "sleep timer",
+            ScheduleQuery::Alarms => "alarms",
+            ScheduleQuery::Autoplay => "autoplay",
+        })
+    }
+}
+
+/// A query of the overview that failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScheduleFailure {
+    pub source: SpeakerId,
+    pub query: ScheduleQuery,
+    pub error: String,
+}
+
+/// First time after `after` that an alarm starting at `start` fires
+///
+/// Works on local wall-clock time without a time zone, so an alarm on a
+/// day that skips or repeats its start time comes out at the time as
+/// written. `None` for a recurrence without days.
+pub fn next_occurrence(
+    recurrence: Recurrence,
+    start: NaiveTime,
+    after: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    // A week and a day covers a weekly alarm whose time today has passed
+    (0..=7)
+        .filter_map(|days| after.date().checked_add_days(Days::new(days)))
+        .filter(|date| recurrence.fires_on(date.weekday().num_days_from_sunday() as u8))
+        .map(|date| date.and_time(start))
+        .find(|at| *at > after)
+}
+
+/// Remaining sleep timer as `GetRemainingSleepTimerDuration` reports it
+/// (`H:MM:SS`); `None` when no timer runs
+fn parse_remaining(remaining: &str) -> Option<Duration> {
+    let mut parts = remaining.trim().split(':').map(|part| part.parse::<u64>());
+    let (Some(Ok(h)), Some(Ok(m)), Some(Ok(s)), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    Some(Duration::from_secs(h * 3600 + m * 60 + s)).filter(|d| !d.is_zero())
+}
+
+fn alarm_entry(source: &SpeakerId, alarm: Alarm, now: NaiveDateTime) -> AlarmEntry {
+    let next_fire = NaiveTime::parse_from_str(&alarm.start_time, "%H:%M:%S")
+        .ok()
+        .and_then(|start| next_occurrence(alarm.recurrence, start, now));
+    AlarmEntry {
+        source: source.clone(),
+        room: SpeakerId::new(alarm.room_uuid),
+        id: alarm.id,
+        start_time: alarm.start_time,
+        recurrence: alarm.recurrence,
+        duration: alarm.duration,
+        volume: alarm.volume,
+        program_uri: alarm.program_uri,
+        include_linked_zones: alarm.include_linked_zones,
+        next_fire,
+    }
+}
+
+/// Query every group's coordinator for its sleep timer, the first one for
+/// the household's alarms and every speaker for autoplay, all at once
+/// A query's result, with a panic in its thread reported as a failure of
+/// that query rather than of the whole overview
+fn joined<T>(handle: ScopedJoinHandle<'_, Result<T, SdkError>>) -> Result<T, SdkError> {
+    handle
+        .join()
+        .unwrap_or_else(|_| Err(SdkError::FetchFailed("schedule query panicked".to_string())))
+}
+
+pub(crate) fn gather(
+    coordinators: Vec<(GroupId, Speaker)>,
+    speakers: Vec<Speaker>,
+    now: NaiveDateTime,
+) -> ScheduleOverview {
+    let mut overview = ScheduleOverview {
+        generated_at: now,
+        sleep_timers: Vec::new(),
+        alarms: Vec::new(),
+        autoplay: Vec::new(),
+        partial: false,
+        failures: Vec::new(),
+    };
+    let mut failures = Vec::new();
+    let mut fail = |source: &SpeakerId, query: ScheduleQuery, error: SdkError| {
+        tracing::debug!("{query} query to {} failed: {error}", source.as_str());
+        failures.push(ScheduleFailure {
+            source: source.clone(),
+            query,
+            error: error.to_string(),
+        });
+    };
+
+    let (timers, alarms, autoplay) = thread::scope(|s| {
+        let timers: Vec<_> = coordinators
+            .iter()
+            .map(|(group_id, speaker)| {
+                (
+                    group_id,
+                    speaker,
+                    s.spawn(|| speaker.get_remaining_sleep_timer()),
+                )
+            })
+            .collect();
+        let alarms = coordinators
+            .first()
+            .map(|(_, speaker)| (speaker, s.spawn(|| speaker.list_alarms())));
+        let autoplay: Vec<_> = speakers
+            .iter()
+            .map(|speaker| {
+                let room = s.spawn(|| {
+                    let Some(room) = speaker.get_autoplay_room()? else {
+                        return Ok(None);
+                    };
+                    Ok::<_, SdkError>(Some((room, speaker.get_autoplay_volume()?)))
+                });
+                (speaker, room)
+            })
+            .collect();
+
+        (
+            timers
+                .into_iter()
+                .map(|(group_id, speaker, handle)| (group_id, speaker, joined(handle)))
+                .collect::<Vec<_>>(),
+            alarms.map(|(speaker, handle)| (speaker, joined(handle))),
+            autoplay
+                .into_iter()
+                .map(|(speaker, handle)| (speaker, joined(handle)))
+                .collect::<Vec<_>>(),
+        )
+    });
+
+    for (group_id, speaker, result) in timers {
+        match result {
+            Ok(response) => {
+                if let Some(remaining) = parse_remaining(&response.remaining_sleep_timer_duration) {
+                    overview.sleep_timers.push(SleepTimerEntry {
+                        source: speaker.id.clone(),
+                        group_id: group_id.clone(),
+                        remaining_secs: remaining.as_secs(),
+                        ends_at: now + remaining,
+                    });
+                }
+            }
+            Err(error) => fail(&speaker.id, ScheduleQuery::SleepTimer, error),
+        }
+    }
+    match alarms {
+        Some((speaker, Ok(response))) => overview.alarms.extend(
+            response
+                .alarms
+                .into_iter()
+                .filter(|alarm| alarm.enabled)
+                .map(|alarm| alarm_entry(&speaker.id, alarm, now)),
+        ),
+        Some((speaker, Err(error))) => fail(&speaker.id, ScheduleQuery::Alarms, error),
+        None => {}
+    }
+    for (speaker, result) in autoplay {
+        match result {
+            Ok(Some((room, volume))) => overview.autoplay.push(AutoplayEntry {
+                source: speaker.id.clone(),
+                room,
+                volume,
+            }),
+            Ok(None) => {}
+            Err(error) => fail(&speaker.id, ScheduleQuery::Autoplay, error),
+        }
+    }
+
+    overview.partial = !failures.is_empty();
+    overview.failures = failures;
+    overview.sleep_timers.sort_by_key(|timer| timer.ends_at);
+    // Alarms that never fire go last
+    overview
+        .alarms
+        .sort_by_key(|alarm| (alarm.next_fire.is_none(), alarm.next_fire));
+    overview
+        .autoplay
+        .sort_by(|a, b| a.source.as_str().cmp(b.source.as_str()));
+    overview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panicked_query_is_a_failure() {
+        let (ok, panicked) = thread::scope(|s| {
+            let ok = s.spawn(|| Ok::<_, SdkError>(7));
+            let panicked = s.spawn(|| -> Result<u32, SdkError> { panic!("bad response") });
+            (joined(ok), joined(panicked))
+        });
+
+        assert_eq!(ok.unwrap(), 7);
+        assert!(matches!(panicked, Err(SdkError::FetchFailed(_))));
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn next(recurrence: &str, start: &str, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        next_occurrence(
+            Recurrence::parse(recurrence).unwrap(),
+            NaiveTime::parse_from_str(start, "%H:%M:%S").unwrap(),
+            after,
+        )
+    }
+
+    #[test]
+    fn test_next_occurrence() {
+        // 2026-10-16 is a Friday
+        let friday_evening = at("2026-10-16", "20:00:00");
+        assert_eq!(
+            next("ONCE", "21:30:00", friday_evening),
+            Some(at("2026-10-16", "21:30:00"))
+        );
+        assert_eq!(
+            next("ONCE", "07:00:00", friday_evening),
+            Some(at("2026-10-17", "07:00:00"))
+        );
+        assert_eq!(
+            next("DAILY", "07:00:00", friday_evening),
+            Some(at("2026-10-17", "07:00:00"))
+        );
+        // Over the weekend
+        assert_eq!(
+            next("WEEKDAYS", "07:00:00", friday_evening),
+            Some(at("2026-10-19", "07:00:00"))
+        );
+        assert_eq!(
+            next("WEEKENDS", "09:00:00", friday_evening),
+            Some(at("2026-10-17", "09:00:00"))
+        );
+        // Monday and Wednesday
+        assert_eq!(
+            next("ON_13", "06:30:00", friday_evening),
+            Some(at("2026-10-19", "06:30:00"))
+        );
+        // Only on Fridays, and this one has passed: a week later
+        assert_eq!(
+            next("ON_5", "19:00:00", friday_evening),
+            Some(at("2026-10-23", "19:00:00"))
+        );
+        // Exactly now counts as fired
+        assert_eq!(
+            next("DAILY", "20:00:00", friday_evening),
+            Some(at("2026-10-17", "20:00:00"))
+        );
+        // Across a month and year end
+        assert_eq!(
+            next("ON_0", "08:00:00", at("2026-12-31", "12:00:00")),
+            Some(at("2027-01-03", "08:00:00"))
+        );
+        assert_eq!(
+            next("WEEKDAYS", "00:00:00", at("2028-02-28", "23:59:59")),
+            Some(at("2028-02-29", "00:00:00"))
+        );
+        // Wall-clock arithmetic: the same local time on either side of a
+        // daylight saving change
+        assert_eq!(
+            next("DAILY", "02:30:00", at("2026-03-28", "03:00:00")),
+            Some(at("2026-03-29", "02:30:00"))
+        );
+        assert_eq!(
+            next_occurrence(Recurrence::On(0), NaiveTime::MIN, friday_evening),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_remaining() {
+        assert_eq!(parse_remaining("0:29:59"), Some(Duration::from_secs(1799)));
+        assert_eq!(parse_remaining("1:00:00"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_remaining(""), None);
+        assert_eq!(parse_remaining("0:00:00"), None);
+        assert_eq!(parse_remaining("soon"), None);
+    }
+}
@@ -0,0 +1,164 @@
+//! What a speaker is playing from
+//!
+//! See [`Speaker::playback_source()`](crate::Speaker::playback_source).
+
+use sonos_state::SpeakerId;
+
+/// Where a speaker's audio comes from, read from its transport URI
+///
+/// The URI is `CurrentURI` of `GetMediaInfo`: the queue, stream or input the
+/// speaker was set to, not the track playing from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaybackSource {
+    /// Nothing loaded
+    Idle,
+    /// A queue (`x-rincon-queue:`), usually the speaker's own
+    Queue { owner: SpeakerId },
+    /// A group member playing whatever its coordinator plays (`x-rincon:`)
+    Grouped { coordinator: SpeakerId },
+    /// The analog line-in of `source`, possibly another speaker
+    /// (`x-rincon-stream:`)
+    LineIn { source: SpeakerId },
+    /// The TV input of a home theater speaker (`x-sonos-htastream:`)
+    Tv { source: SpeakerId },
+    /// Internet radio or another live stream (`x-sonosapi-stream:`,
+    /// `x-sonosapi-radio:`, `x-sonosapi-hls:`, `x-rincon-mp3radio:`,
+    /// `hls-radio:`, `aac:`)
+    Radio { uri: String },
+    /// Controlled from an app through AirPlay or a streaming service's own
+    /// protocol, such as Spotify Connect (`x-sonos-vli:`)
+    DirectControl { uri: String },
+    /// A single track or a URI of any other scheme
+    Other { uri: String },
+}
+
+/// Schemes of radio and live streams
+const RADIO_SCHEMES: [&str; 6] = [
+    "x-sonosapi-stream:",
+    "x-sonosapi-radio:",
+    "x-sonosapi-hls:",
+    "x-rincon-mp3radio:",
+    "hls-radio:",
+    "aac:",
+];
+
+impl PlaybackSource {
+    /// Classify a transport URI
+    pub fn from_uri(uri: &str) -> Self {
+        let uri = uri.trim();
+        // The speaker ID runs to the first `#` (queue instance) or `:`
+        // (home theater input name)
+        let speaker = |rest: &str| SpeakerId::new(rest.split(['#', ':']).next().unwrap_or(rest));
+        if uri.is_empty() {
+            PlaybackSource::Idle
+        } else if let Some(rest) = uri.strip_prefix("x-rincon-queue:") {
+            PlaybackSource::Queue {
+                owner: speaker(rest),
+            }
+        } else if let Some(rest) = uri.strip_prefix("x-rincon-stream:") {
+            PlaybackSource::LineIn {
+                source: speaker(rest),
+            }
+        } else if let Some(rest) = uri.strip_prefix("x-sonos-htastream:") {
+            PlaybackSource::Tv {
+                source: speaker(rest),
+            }
+        } else if let Some(rest) = uri.strip_prefix("x-rincon:") {
+            PlaybackSource::Grouped {
+                coordinator: speaker(rest),
+            }
+        } else if uri.starts_with("x-sonos-vli:") {
+            PlaybackSource::DirectControl {
+                uri: uri.to_string(),
+            }
+        } else if RADIO_SCHEMES.iter().any(|scheme| uri.starts_with(scheme)) {
+            PlaybackSource::Radio {
+                uri: uri.to_string(),
+            }
+        } else {
+            PlaybackSource::Other {
+                uri: uri.to_string(),
+            }
+        }
+    }
+
+    /// Whether the speaker follows another speaker rather than playing a
+    /// source of its own
+    pub fn is_grouped(&self) -> bool {
+        matches!(self, PlaybackSource::Grouped { .. })
+    }
+
+    /// Whether the source is a live input (line-in or TV), which has no
+    /// queue, track length or position
+    pub fn is_input(&self) -> bool {
+        matches!(
+            self,
+            PlaybackSource::LineIn { .. } | PlaybackSource::Tv { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_uri() {
+        let id = SpeakerId::new;
+        for (uri, source) in [
+            ("", PlaybackSource::Idle),
+            (
+                "x-rincon-queue:RINCON_000E58A0123401400#0",
+                PlaybackSource::Queue {
+                    owner: id("RINCON_000E58A0123401400"),
+                },
+            ),
+            (
+                "x-rincon:RINCON_DEN",
+                PlaybackSource::Grouped {
+                    coordinator: id("RINCON_DEN"),
+                },
+            ),
+            (
+                "x-rincon-stream:RINCON_HALL",
+                PlaybackSource::LineIn {
+                    source: id("RINCON_HALL"),
+                },
+            ),
+            (
+                "x-sonos-htastream:RINCON_BEAM:spdif",
+                PlaybackSource::Tv {
+                    source: id("RINCON_BEAM"),
+                },
+            ),
+        ] {
+            assert_eq!(PlaybackSource::from_uri(uri), source, "{uri}");
+        }
+
+        for uri in [
+            "x-sonosapi-stream:s24861?sid=254&flags=8224&sn=0",
+            "x-sonosapi-radio:ST%3aplaylist?sid=236",
+            "x-rincon-mp3radio://stream.example.com/live",
+            "aac://stream.example.com/live.aac",
+        ] {
+            assert!(
+                matches!(PlaybackSource::from_uri(uri), PlaybackSource::Radio { .. }),
+                "{uri}"
+            );
+        }
+        assert!(matches!(
+            PlaybackSource::from_uri("x-sonos-vli:RINCON_DEN:1,airplay:abc"),
+            PlaybackSource::DirectControl { .. }
+        ));
+        assert_eq!(
+            PlaybackSource::from_uri("x-file-cifs://nas/music/song.flac"),
+            PlaybackSource::Other {
+                uri: "x-file-cifs://nas/music/song.flac".to_string()
+            }
+        );
+
+        assert!(PlaybackSource::from_uri("x-rincon:RINCON_DEN").is_grouped());
+        assert!(PlaybackSource::from_uri("x-sonos-htastream:RINCON_BEAM:spdif").is_input());
+        assert!(!PlaybackSource::Idle.is_input());
+    }
+}
@@ -10,27 +10,42 @@
 //! command silently, the cache may be stale until the next UPnP event corrects it.
 //! Use `speaker.volume.watch()` for authoritative real-time state.
 
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
 
-use sonos_api::SonosClient;
+use sonos_api::{ApiError, Service, SoapFault, SonosClient};
 use sonos_discovery::Device;
-use sonos_state::{Bass, Loudness, Mute, PlaybackState, SpeakerId, StateManager, Treble, Volume};
+use sonos_state::{
+    property::SonosProperty, Bass, ButtonLock, CurrentPlayMode, Loudness, Mute, PlaybackState,
+    Presets, Property, SpeakerId, StateManager, StatusLight, SubEnabled, SubGain, SurroundEnabled,
+    SurroundLevel, SurroundMode, TransportActions, Treble, Volume,
+};
 
-use crate::Group;
+use crate::eq::{EqField, EqOutcome, EqResult, EqSettings, EqValue};
+use crate::{FetchCoalescer, Group, PlaybackSource};
 
 use sonos_api::operation::{ComposableOperation, UPnPOperation, ValidationError};
 use sonos_api::services::{
+    alarm_clock::{self, Alarm, ListAlarmsResponse},
     av_transport::{
         self, AddURIToQueueResponse, BecomeCoordinatorOfStandaloneGroupResponse,
         CreateSavedQueueResponse, GetCrossfadeModeResponse, GetCurrentTransportActionsResponse,
         GetDeviceCapabilitiesResponse, GetMediaInfoResponse,
         GetRemainingSleepTimerDurationResponse, GetRunningAlarmPropertiesResponse,
         GetTransportSettingsResponse, RemoveTrackRangeFromQueueResponse, SaveQueueResponse,
+        Station,
+    },
+    connection_manager::{
+        self, parse_protocol_info_list, sink_supports_uri, uri_content_formats, ProtocolInfo,
     },
+    content_directory::{self, Favorite, Playlist},
+    device_properties::{self, GetZoneAttributesResponse},
     rendering_control::{self, SetRelativeVolumeResponse},
 };
 
+use crate::intercept::{send_write, Sent};
+use crate::toggle::toggle;
 use crate::SdkError;
 
 /// Seek target for the `seek()` method
@@ -67,40 +82,64 @@ impl SeekTarget {
     }
 }
 
+impl From<Duration> for SeekTarget {
+    /// An absolute position, whole seconds only
+    fn from(position: Duration) -> Self {
+        let secs = position.as_secs();
+        SeekTarget::Time(format!(
+            "{}:{:02}:{:02}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        ))
+    }
+}
+
 /// Play mode for the `set_play_mode()` method
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PlayMode {
-    /// Normal sequential playback
-    Normal,
-    /// Repeat all tracks
-    RepeatAll,
-    /// Repeat current track
-    RepeatOne,
-    /// Shuffle without repeat
-    ShuffleNoRepeat,
-    /// Shuffle with repeat
-    Shuffle,
-    /// Shuffle and repeat current track
-    ShuffleRepeatOne,
+pub use sonos_api::services::av_transport::PlayMode;
+
+/// How transport controls treat the speaker's currently allowed actions
+///
+/// Sonos reports which transport actions are valid right now (see
+/// [`Speaker::available_actions()`]). Sending one that isn't, such as `Pause`
+/// on a radio stream, fails with UPnP error 701. The check uses the cached
+/// `transport_actions` value only; when nothing is cached every action is attempted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PreCheck {
+    /// Send every command without checking (default)
+    #[default]
+    Off,
+    /// Send the command; if it fails and the action wasn't allowed, return
+    /// [`SdkError::NotAvailable`] wrapping the device error
+    Advisory,
+    /// Return [`SdkError::NotAvailable`] without any network I/O when the
+    /// action isn't allowed
+    Enforce,
 }
 
-impl std::fmt::Display for PlayMode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            PlayMode::Normal => write!(f, "NORMAL"),
-            PlayMode::RepeatAll => write!(f, "REPEAT_ALL"),
-            PlayMode::RepeatOne => write!(f, "REPEAT_ONE"),
-            PlayMode::ShuffleNoRepeat => write!(f, "SHUFFLE_NOREPEAT"),
-            PlayMode::Shuffle => write!(f, "SHUFFLE"),
-            PlayMode::ShuffleRepeatOne => write!(f, "SHUFFLE_REPEAT_ONE"),
-        }
-    }
+/// How URI commands treat formats the speaker doesn't list as playable
+///
+/// The check compares the URI's scheme and file extension with the sink
+/// list from [`Speaker::supported_protocols()`]. URIs it can't judge, such as
+/// streams without an extension or Sonos schemes missing from the list, are
+/// always sent, as they are when the list can't be fetched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProtocolCheck {
+    /// Send every URI without checking (default)
+    #[default]
+    Off,
+    /// Log a warning and send the URI anyway
+    Warn,
+    /// Return [`SdkError::UnsupportedMedia`] without sending
+    Reject,
 }
 
 use crate::property::{
-    BassHandle, CurrentTrackHandle, GroupMembershipHandle, LoudnessHandle, MuteHandle,
-    PlaybackStateHandle, PositionHandle, PropertyHandle, SpeakerContext, TrebleHandle,
-    VolumeHandle,
+    AudioOutputHandle, BassHandle, ButtonLockHandle, CurrentPlayModeHandle, CurrentTrackHandle,
+    GroupMembershipHandle, LoudnessHandle, MuteHandle, OutputFixedHandle, PlaybackStateHandle,
+    PositionHandle, PresetsHandle, PropertyHandle, SpeakerContext, StatusLightHandle,
+    SubEnabledHandle, SubGainHandle, SurroundEnabledHandle, SurroundLevelHandle,
+    SurroundModeHandle, TransportActionsHandle, TrebleHandle, VolumeHandle,
 };
 
 /// Speaker handle with property access
@@ -128,6 +167,8 @@ pub struct Speaker {
     pub name: String,
     /// IP address of the speaker
     pub ip: IpAddr,
+    /// UPnP port of the speaker (1400 unless discovery reported otherwise)
+    pub port: u16,
     /// Model name of the speaker (e.g., "Sonos One", "Sonos Beam")
     pub model_name: String,
 
@@ -144,6 +185,35 @@ pub struct Speaker {
     pub treble: TrebleHandle,
     /// Loudness compensation setting
     pub loudness: LoudnessHandle,
+    /// Line-out volume fixed (Port, Connect)
+    pub output_fixed: OutputFixedHandle,
+    /// EQ presets the speaker can recall with [`select_preset()`](Self::select_preset)
+    pub presets: PresetsHandle,
+
+    // ========================================================================
+    // Home-theater properties (read and written on the bond primary)
+    // ========================================================================
+    /// Bonded Sub on/off
+    pub sub_enabled: SubEnabledHandle,
+    /// Bonded Sub level (-15 to +15)
+    pub sub_gain: SubGainHandle,
+    /// Bonded surrounds on/off
+    pub surround_enabled: SurroundEnabledHandle,
+    /// Surround music playback mode (Ambient/Full)
+    pub surround_mode: SurroundModeHandle,
+    /// Surround TV level (-15 to +15)
+    pub surround_level: SurroundLevelHandle,
+
+    // ========================================================================
+    // DeviceProperties properties
+    // ========================================================================
+    /// Buttons/touch controls lock (true = locked)
+    pub button_lock: ButtonLockHandle,
+    /// White status light (true = on)
+    pub status_light: StatusLightHandle,
+    /// Where a portable's audio is going (Speaker/Headphone/Bluetooth/LineOut);
+    /// `NotSupported` on other models
+    pub audio_output: AudioOutputHandle,
 
     // ========================================================================
     // AVTransport properties
@@ -154,6 +224,10 @@ pub struct Speaker {
     pub position: PositionHandle,
     /// Current track information (title, artist, album, etc.)
     pub current_track: CurrentTrackHandle,
+    /// Transport actions currently allowed (Play, Pause, Next, ...)
+    pub transport_actions: TransportActionsHandle,
+    /// Queue play mode (shuffle and repeat); written with `play_mode.set()`
+    pub play_mode: CurrentPlayModeHandle,
 
     // ========================================================================
     // ZoneGroupTopology properties
@@ -163,6 +237,8 @@ pub struct Speaker {
 
     // Internal context shared with property handles
     context: Arc<SpeakerContext>,
+    precheck: PreCheck,
+    protocol_check: ProtocolCheck,
 }
 
 impl Speaker {
@@ -200,7 +276,7 @@ impl Speaker {
         Ok(Self::new(
             SpeakerId::new(&device.id),
             name,
-            ip,
+            SocketAddr::new(ip, device.port),
             device.model_name.clone(),
             state_manager,
             api_client,
@@ -210,21 +286,47 @@ impl Speaker {
     /// Create a new Speaker handle
     ///
     /// For most use cases, prefer [`Speaker::from_device()`] which handles
-    /// IP parsing and extracts fields from a Device struct.
+    /// IP parsing and extracts fields from a Device struct. `addr` carries
+    /// the port, so speakers sharing an IP stay distinct.
     pub fn new(
         id: SpeakerId,
         name: String,
-        ip: IpAddr,
+        addr: SocketAddr,
         model_name: String,
         state_manager: Arc<StateManager>,
         api_client: SonosClient,
     ) -> Self {
-        let context = SpeakerContext::new(id.clone(), ip, state_manager, api_client);
+        let context = SpeakerContext::new(id.clone(), addr, state_manager, api_client);
+        Self::from_context(id, name, model_name, context)
+    }
+
+    /// Create a Speaker whose fetches are coalesced through `fetches`
+    pub(crate) fn with_fetches(
+        id: SpeakerId,
+        name: String,
+        addr: SocketAddr,
+        model_name: String,
+        state_manager: Arc<StateManager>,
+        api_client: SonosClient,
+        fetches: Arc<FetchCoalescer>,
+    ) -> Self {
+        let context =
+            SpeakerContext::with_fetches(id.clone(), addr, state_manager, api_client, fetches);
+        Self::from_context(id, name, model_name, context)
+    }
 
+    fn from_context(
+        id: SpeakerId,
+        name: String,
+        model_name: String,
+        context: Arc<SpeakerContext>,
+    ) -> Self {
+        let addr = context.speaker_addr;
         Self {
             id,
             name,
-            ip,
+            ip: addr.ip(),
+            port: addr.port(),
             model_name,
             // RenderingControl properties
             volume: PropertyHandle::new(Arc::clone(&context)),
@@ -232,14 +334,89 @@ impl Speaker {
             bass: PropertyHandle::new(Arc::clone(&context)),
             treble: PropertyHandle::new(Arc::clone(&context)),
             loudness: PropertyHandle::new(Arc::clone(&context)),
+            output_fixed: PropertyHandle::new(Arc::clone(&context)),
+            presets: PropertyHandle::new(Arc::clone(&context)),
+            // Home-theater properties
+            sub_enabled: PropertyHandle::new(Arc::clone(&context)),
+            sub_gain: PropertyHandle::new(Arc::clone(&context)),
+            surround_enabled: PropertyHandle::new(Arc::clone(&context)),
+            surround_mode: PropertyHandle::new(Arc::clone(&context)),
+            surround_level: PropertyHandle::new(Arc::clone(&context)),
+            // DeviceProperties properties
+            button_lock: PropertyHandle::new(Arc::clone(&context)),
+            status_light: PropertyHandle::new(Arc::clone(&context)),
+            audio_output: PropertyHandle::new(Arc::clone(&context)),
             // AVTransport properties
             playback_state: PropertyHandle::new(Arc::clone(&context)),
             position: PropertyHandle::new(Arc::clone(&context)),
             current_track: PropertyHandle::new(Arc::clone(&context)),
+            transport_actions: PropertyHandle::new(Arc::clone(&context)),
+            play_mode: PropertyHandle::new(Arc::clone(&context)),
             // ZoneGroupTopology properties
             group_membership: PropertyHandle::new(Arc::clone(&context)),
             // Internal
             context,
+            precheck: PreCheck::Off,
+            protocol_check: ProtocolCheck::Off,
+        }
+    }
+
+    /// Address (IP and port) the speaker is reached at
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port)
+    }
+
+    /// Return a handle that checks allowed transport actions before
+    /// `play()`, `pause()`, `next()`, `previous()` and `seek()`
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let kitchen = sonos.speaker("Kitchen").unwrap().with_precheck(PreCheck::Enforce);
+    /// if let Err(SdkError::NotAvailable { available, .. }) = kitchen.pause() {
+    ///     println!("pause not possible right now; allowed: {available:?}");
+    /// }
+    /// ```
+    pub fn with_precheck(mut self, precheck: PreCheck) -> Self {
+        self.precheck = precheck;
+        self
+    }
+
+    /// Return a handle that checks URIs against the speaker's supported
+    /// formats before `set_av_transport_uri()`, `set_next_av_transport_uri()`
+    /// and `add_uri_to_queue()`
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let kitchen = sonos.speaker("Kitchen").unwrap().with_protocol_check(ProtocolCheck::Reject);
+    /// if let Err(SdkError::UnsupportedMedia { content_type, .. }) =
+    ///     kitchen.set_av_transport_uri("http://nas/track.opus.mka", "")
+    /// {
+    ///     println!("Kitchen can't play {content_type}");
+    /// }
+    /// ```
+    pub fn with_protocol_check(mut self, protocol_check: ProtocolCheck) -> Self {
+        self.protocol_check = protocol_check;
+        self
+    }
+
+    /// Get the formats this speaker can render (sync)
+    ///
+    /// Fetches the sink list via ConnectionManager `GetProtocolInfo` on first
+    /// call; later calls, from any handle on this speaker, reuse it.
+    pub fn supported_protocols(&self) -> Result<Vec<ProtocolInfo>, SdkError> {
+        self.sink_protocols().map(<[ProtocolInfo]>::to_vec)
+    }
+
+    /// Get the transport actions currently allowed (sync)
+    ///
+    /// Returns the cached value when available, otherwise fetches it via
+    /// `GetCurrentTransportActions`. Useful for greying out UI controls.
+    pub fn available_actions(&self) -> Result<TransportActions, SdkError> {
+        match self.transport_actions.get() {
+            Some(actions) => Ok(actions),
+            None => self.transport_actions.fetch(),
         }
     }
 
@@ -269,6 +446,7 @@ impl Speaker {
             info,
             Arc::clone(&self.context.state_manager),
             self.context.api_client.clone(),
+            Arc::clone(&self.context.fetches),
         )
     }
 
@@ -276,7 +454,7 @@ impl Speaker {
     // Private helpers
     // ========================================================================
 
-    /// Execute a UPnP operation against this speaker
+    /// Execute a read-only UPnP operation against this speaker
     fn exec<Op: UPnPOperation>(
         &self,
         operation: Result<ComposableOperation<Op>, ValidationError>,
@@ -284,10 +462,150 @@ impl Speaker {
         let op = operation?;
         self.context
             .api_client
-            .execute_enhanced(&self.context.speaker_ip.to_string(), op)
+            .execute_enhanced(&self.context.speaker_addr.to_string(), op)
             .map_err(SdkError::ApiError)
     }
 
+    /// Send a mutating operation to this speaker through the write
+    /// interceptors
+    ///
+    /// A transport command the speaker refuses as a member of another
+    /// coordinator's group fails with [`SdkError::NotCoordinator`].
+    /// AVTransport's 701 also means the transition isn't available, so it is
+    /// only read that way when topology names another coordinator.
+    fn send<Op: UPnPOperation>(
+        &self,
+        operation: ComposableOperation<Op>,
+    ) -> Result<Sent<Op::Response>, SdkError> {
+        let speaker_id = &self.context.speaker_id;
+        let source = match send_write(
+            &self.context.state_manager,
+            &self.context.api_client,
+            speaker_id,
+            self.context.speaker_addr,
+            operation,
+        ) {
+            Err(SdkError::ApiError(e))
+                if Op::SERVICE == Service::AVTransport
+                    && e.upnp_error().is_some_and(|c| c.is_not_coordinator()) =>
+            {
+                e
+            }
+            result => return result,
+        };
+        let coordinator_hint = self
+            .context
+            .state_manager
+            .get_group_for_speaker(speaker_id)
+            .map(|group| group.coordinator_id)
+            .filter(|id| id != speaker_id);
+        Err(match coordinator_hint {
+            Some(hint) => SdkError::NotCoordinator {
+                speaker_id: speaker_id.clone(),
+                coordinator_hint: Some(hint),
+                source,
+            },
+            None => SdkError::ApiError(source),
+        })
+    }
+
+    /// [`send()`](Self::send) a mutating operation, returning the response
+    fn write<Op: UPnPOperation>(
+        &self,
+        operation: Result<ComposableOperation<Op>, ValidationError>,
+    ) -> Result<Op::Response, SdkError> {
+        let sent = self.send(operation?)?;
+        Ok(sent.response)
+    }
+
+    /// [`write()`](Self::write), then cache `value` as this speaker's new
+    /// state unless what was sent differs from what was asked for
+    fn write_cached<Op: UPnPOperation, P: SonosProperty>(
+        &self,
+        operation: Result<ComposableOperation<Op>, ValidationError>,
+        value: P,
+    ) -> Result<Op::Response, SdkError> {
+        let sent = self.send(operation?)?;
+        if !sent.modified {
+            self.context
+                .state_manager
+                .apply_local_write(&self.context.speaker_id, value);
+        }
+        Ok(sent.response)
+    }
+
+    /// The cached sink list, fetching it on first use
+    fn sink_protocols(&self) -> Result<&[ProtocolInfo], SdkError> {
+        if let Some(protocols) = self.context.protocols.get() {
+            return Ok(protocols);
+        }
+        let info = self.exec(connection_manager::get_protocol_info().build())?;
+        Ok(self
+            .context
+            .protocols
+            .get_or_init(|| parse_protocol_info_list(&info.sink)))
+    }
+
+    /// Check `uri` against the sink list per the configured [`ProtocolCheck`]
+    fn check_uri(&self, uri: &str) -> Result<(), SdkError> {
+        if self.protocol_check == ProtocolCheck::Off {
+            return Ok(());
+        }
+        let sinks = match self.sink_protocols() {
+            Ok(sinks) => sinks,
+            Err(e) => {
+                tracing::debug!("protocol check skipped for {uri}: {e}");
+                return Ok(());
+            }
+        };
+        if sink_supports_uri(sinks, uri) != Some(false) {
+            return Ok(());
+        }
+        let content_type = uri_content_formats(uri)
+            .and_then(|formats| formats.first())
+            .copied()
+            .unwrap_or_default();
+        match self.protocol_check {
+            ProtocolCheck::Reject => Err(SdkError::UnsupportedMedia {
+                uri: uri.to_string(),
+                content_type: content_type.to_string(),
+            }),
+            _ => {
+                tracing::warn!(
+                    "{} doesn't list {content_type} as playable; sending {uri} anyway",
+                    self.name
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Run a transport command subject to the configured [`PreCheck`]
+    fn gated<T>(
+        &self,
+        action: &str,
+        command: impl FnOnce() -> Result<T, SdkError>,
+    ) -> Result<T, SdkError> {
+        let disallowed = match self.precheck {
+            PreCheck::Off => None,
+            PreCheck::Advisory | PreCheck::Enforce => {
+                self.transport_actions.get().filter(|a| !a.allows(action))
+            }
+        };
+        let Some(allowed) = disallowed else {
+            return command();
+        };
+        let not_available = |source| SdkError::NotAvailable {
+            action: action.to_string(),
+            available: allowed.0.clone(),
+            source,
+        };
+        match self.precheck {
+            PreCheck::Enforce => Err(not_available(None)),
+            _ => command().map_err(|e| not_available(Some(Box::new(e)))),
+        }
+    }
+
     // ========================================================================
     // AVTransport — Basic playback
     // ========================================================================
@@ -296,10 +614,12 @@ impl Speaker {
     ///
     /// Updates the state cache to `PlaybackState::Playing` on success.
     pub fn play(&self) -> Result<(), SdkError> {
-        self.exec(av_transport::play("1".to_string()).build())?;
-        self.context
-            .state_manager
-            .set_property(&self.context.speaker_id, PlaybackState::Playing);
+        self.gated("Play", || {
+            self.write_cached(
+                av_transport::play("1".to_string()).build(),
+                PlaybackState::Playing,
+            )
+        })?;
         Ok(())
     }
 
@@ -307,33 +627,55 @@ impl Speaker {
     ///
     /// Updates the state cache to `PlaybackState::Paused` on success.
     pub fn pause(&self) -> Result<(), SdkError> {
-        self.exec(av_transport::pause().build())?;
-        self.context
-            .state_manager
-            .set_property(&self.context.speaker_id, PlaybackState::Paused);
+        self.gated("Pause", || {
+            self.write_cached(av_transport::pause().build(), PlaybackState::Paused)
+        })?;
         Ok(())
     }
 
+    /// Pause if playing, otherwise play, and return the state the speaker
+    /// confirmed
+    ///
+    /// Starts from the cached playback state while it is watched, or a
+    /// fetched one otherwise; `Transitioning` counts as playing. Playback
+    /// is read back after the write and, if another controller's change
+    /// undid it, toggled once more from there. See
+    /// [`MuteHandle`](crate::property::MuteHandle)`::toggle()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let state = speaker.toggle_playback()?;
+    /// println!("Now {state:?}");
+    /// ```
+    pub fn toggle_playback(&self) -> Result<PlaybackState, SdkError> {
+        let baseline = self.playback_state.fresh()?;
+        toggle(
+            PlaybackState::KEY,
+            baseline,
+            |state| matches!(state, PlaybackState::Playing | PlaybackState::Transitioning),
+            |play| if play { self.play() } else { self.pause() },
+            || self.playback_state.read_back(),
+        )
+    }
+
     /// Stop playback
     ///
     /// Updates the state cache to `PlaybackState::Stopped` on success.
     pub fn stop(&self) -> Result<(), SdkError> {
-        self.exec(av_transport::stop().build())?;
-        self.context
-            .state_manager
-            .set_property(&self.context.speaker_id, PlaybackState::Stopped);
+        self.write_cached(av_transport::stop().build(), PlaybackState::Stopped)?;
         Ok(())
     }
 
     /// Skip to next track
     pub fn next(&self) -> Result<(), SdkError> {
-        self.exec(av_transport::next().build())?;
+        self.gated("Next", || self.write(av_transport::next().build()))?;
         Ok(())
     }
 
     /// Skip to previous track
     pub fn previous(&self) -> Result<(), SdkError> {
-        self.exec(av_transport::previous().build())?;
+        self.gated("Previous", || self.write(av_transport::previous().build()))?;
         Ok(())
     }
 
@@ -349,9 +691,16 @@ impl Speaker {
     /// speaker.seek(SeekTarget::Time("0:02:30".into()))?;  // Seek to 2:30
     /// speaker.seek(SeekTarget::Track(3))?;                 // Seek to track 3
     /// speaker.seek(SeekTarget::Delta("+0:00:30".into()))?; // Skip forward 30s
+    /// speaker.seek(Duration::from_secs(150))?;             // Also 2:30
     /// ```
-    pub fn seek(&self, target: SeekTarget) -> Result<(), SdkError> {
-        self.exec(av_transport::seek(target.unit().to_string(), target.target()).build())?;
+    ///
+    /// Malformed times (not `h:mm:ss`) and track 0 fail validation without a
+    /// request.
+    pub fn seek(&self, target: impl Into<SeekTarget>) -> Result<(), SdkError> {
+        let target = target.into();
+        self.gated("Seek", || {
+            self.write(av_transport::seek(target.unit().to_string(), target.target()).build())
+        })?;
         Ok(())
     }
 
@@ -360,16 +709,22 @@ impl Speaker {
     // ========================================================================
 
     /// Set the current transport URI
+    ///
+    /// Subject to the configured [`ProtocolCheck`].
     pub fn set_av_transport_uri(&self, uri: &str, metadata: &str) -> Result<(), SdkError> {
-        self.exec(
+        self.check_uri(uri)?;
+        self.write(
             av_transport::set_av_transport_uri(uri.to_string(), metadata.to_string()).build(),
         )?;
         Ok(())
     }
 
     /// Set the next transport URI (for gapless playback)
+    ///
+    /// Subject to the configured [`ProtocolCheck`].
     pub fn set_next_av_transport_uri(&self, uri: &str, metadata: &str) -> Result<(), SdkError> {
-        self.exec(
+        self.check_uri(uri)?;
+        self.write(
             av_transport::set_next_av_transport_uri(uri.to_string(), metadata.to_string()).build(),
         )?;
         Ok(())
@@ -389,6 +744,17 @@ impl Speaker {
         self.exec(av_transport::get_transport_settings().build())
     }
 
+    /// What the speaker is playing from: its queue, another speaker's
+    /// stream, an input, radio, or an app in direct control
+    ///
+    /// Sends `GetMediaInfo` and classifies its `CurrentURI`.
+    pub fn playback_source(&self) -> Result<PlaybackSource, SdkError> {
+        let info = self.get_media_info()?;
+        Ok(PlaybackSource::from_uri(
+            info.current_uri.as_deref().unwrap_or_default(),
+        ))
+    }
+
     /// Get currently available transport actions
     pub fn get_current_transport_actions(
         &self,
@@ -402,6 +768,8 @@ impl Speaker {
 
     /// Set play mode
     ///
+    /// The cached `play_mode` holds the new mode once the speaker accepts it.
+    ///
     /// # Example
     ///
     /// ```rust,ignore
@@ -409,7 +777,10 @@ impl Speaker {
     /// speaker.set_play_mode(PlayMode::RepeatAll)?;
     /// ```
     pub fn set_play_mode(&self, mode: PlayMode) -> Result<(), SdkError> {
-        self.exec(av_transport::set_play_mode(mode.to_string()).build())?;
+        self.write_cached(
+            av_transport::set_play_mode(mode).build(),
+            CurrentPlayMode(mode),
+        )?;
         Ok(())
     }
 
@@ -420,7 +791,7 @@ impl Speaker {
 
     /// Set crossfade mode
     pub fn set_crossfade_mode(&self, enabled: bool) -> Result<(), SdkError> {
-        self.exec(av_transport::set_crossfade_mode(enabled).build())?;
+        self.write(av_transport::set_crossfade_mode(enabled).build())?;
         Ok(())
     }
 
@@ -430,7 +801,7 @@ impl Speaker {
 
     /// Configure sleep timer (e.g., `"01:00:00"` for 1 hour, `""` to cancel)
     pub fn configure_sleep_timer(&self, duration: &str) -> Result<(), SdkError> {
-        self.exec(av_transport::configure_sleep_timer(duration.to_string()).build())?;
+        self.write(av_transport::configure_sleep_timer(duration.to_string()).build())?;
         Ok(())
     }
 
@@ -446,11 +817,115 @@ impl Speaker {
         self.exec(av_transport::get_remaining_sleep_timer_duration().build())
     }
 
+    // ========================================================================
+    // AlarmClock
+    // ========================================================================
+
+    /// List the household's alarms
+    ///
+    /// Alarms are household-wide: any speaker answers with all of them,
+    /// whichever room they play in.
+    pub fn list_alarms(&self) -> Result<ListAlarmsResponse, SdkError> {
+        self.exec(alarm_clock::list_alarms().build())
+    }
+
+    /// Replace an alarm's settings with `alarm`'s
+    ///
+    /// The speaker replaces every setting, so edit an alarm from
+    /// [`list_alarms()`](Self::list_alarms) rather than building one.
+    pub fn update_alarm(&self, alarm: &Alarm) -> Result<(), SdkError> {
+        self.write(alarm_clock::update_alarm(alarm.clone()).build())
+    }
+
+    /// Switch alarm `id` on or off, keeping its other settings
+    ///
+    /// Reads the alarm first; returns [`SdkError::AlarmNotFound`] without
+    /// writing when the household has no alarm `id`.
+    pub fn set_alarm_enabled(&self, id: &str, enabled: bool) -> Result<(), SdkError> {
+        let alarm = self
+            .list_alarms()?
+            .alarms
+            .into_iter()
+            .find(|alarm| alarm.id == id)
+            .ok_or_else(|| SdkError::AlarmNotFound(id.to_string()))?;
+        self.write(alarm_clock::set_alarm_enabled(&alarm, enabled).build())
+    }
+
+    // ========================================================================
+    // ContentDirectory — Favorites and playlists
+    // ========================================================================
+
+    /// List the household's Sonos Favorites, in the order the Sonos app
+    /// shows them
+    ///
+    /// Favorites are household-wide: any speaker answers with all of them.
+    pub fn favorites(&self) -> Result<Vec<Favorite>, SdkError> {
+        content_directory::list_favorites(
+            &self.context.api_client,
+            &self.context.speaker_addr.to_string(),
+        )
+        .map_err(SdkError::ApiError)
+    }
+
+    /// List the household's Sonos playlists (saved queues)
+    pub fn playlists(&self) -> Result<Vec<Playlist>, SdkError> {
+        content_directory::list_playlists(
+            &self.context.api_client,
+            &self.context.speaker_addr.to_string(),
+        )
+        .map_err(SdkError::ApiError)
+    }
+
+    /// Play a Sonos Favorite
+    ///
+    /// Stations and tracks become the transport URI. Albums and service
+    /// playlists ([`Favorite::plays_from_queue()`]) replace the queue, which
+    /// then plays from the start. A favorite without a URI, such as a
+    /// shortcut, is refused without sending anything.
+    pub fn play_favorite(&self, favorite: &Favorite) -> Result<(), SdkError> {
+        if favorite.uri.is_empty() {
+            return Err(SdkError::InvalidOperation(format!(
+                "favorite {} has no URI to play",
+                favorite.title
+            )));
+        }
+        if favorite.plays_from_queue() {
+            self.play_from_queue(&favorite.uri, &favorite.metadata)
+        } else {
+            self.set_av_transport_uri(&favorite.uri, &favorite.metadata)?;
+            self.play()
+        }
+    }
+
+    /// Replace the queue with a Sonos playlist and play it from the start
+    pub fn play_playlist(&self, playlist: &Playlist) -> Result<(), SdkError> {
+        self.play_from_queue(&playlist.uri, &playlist.metadata)
+    }
+
+    /// Play a radio station of a music service, such as TuneIn
+    ///
+    /// Sets the station's stream URI and metadata as the transport URI, then
+    /// plays; the queue is left as it was.
+    pub fn play_station(&self, station: &Station) -> Result<(), SdkError> {
+        self.set_av_transport_uri(&station.uri(), &station.metadata())?;
+        self.play()
+    }
+
+    /// Replace the queue with `uri` and play this speaker's queue
+    fn play_from_queue(&self, uri: &str, metadata: &str) -> Result<(), SdkError> {
+        self.remove_all_tracks_from_queue()?;
+        self.add_uri_to_queue(uri, metadata, 0, false)?;
+        self.set_av_transport_uri(&format!("x-rincon-queue:{}#0", self.id), "")?;
+        self.play()
+    }
+
     // ========================================================================
     // AVTransport — Queue operations
     // ========================================================================
 
     /// Add a URI to the queue
+    ///
+    /// Subject to the configured [`ProtocolCheck`].
     pub fn add_uri_to_queue(
         &self,
         uri: &str,
@@ -458,7 +933,8 @@ impl Speaker {
         position: u32,
         enqueue_as_next: bool,
     ) -> Result<AddURIToQueueResponse, SdkError> {
-        self.exec(
+        self.check_uri(uri)?;
+        self.write(
             av_transport::add_uri_to_queue(
                 uri.to_string(),
                 metadata.to_string(),
@@ -471,19 +947,21 @@ impl Speaker {
 
     /// Remove a track from the queue
     pub fn remove_track_from_queue(&self, object_id: &str, update_id: u32) -> Result<(), SdkError> {
-        self.exec(av_transport::remove_track_from_queue(object_id.to_string(), update_id).build())?;
+        self.write(
+            av_transport::remove_track_from_queue(object_id.to_string(), update_id).build(),
+        )?;
         Ok(())
     }
 
     /// Remove all tracks from the queue
     pub fn remove_all_tracks_from_queue(&self) -> Result<(), SdkError> {
-        self.exec(av_transport::remove_all_tracks_from_queue().build())?;
+        self.write(av_transport::remove_all_tracks_from_queue().build())?;
         Ok(())
     }
 
     /// Save the current queue as a Sonos playlist
     pub fn save_queue(&self, title: &str, object_id: &str) -> Result<SaveQueueResponse, SdkError> {
-        self.exec(av_transport::save_queue(title.to_string(), object_id.to_string()).build())
+        self.write(av_transport::save_queue(title.to_string(), object_id.to_string()).build())
     }
 
     /// Create a new saved queue (playlist) with a URI
@@ -493,7 +971,7 @@ impl Speaker {
         uri: &str,
         metadata: &str,
     ) -> Result<CreateSavedQueueResponse, SdkError> {
-        self.exec(
+        self.write(
             av_transport::create_saved_queue(
                 title.to_string(),
                 uri.to_string(),
@@ -510,7 +988,7 @@ impl Speaker {
         starting_index: u32,
         number_of_tracks: u32,
     ) -> Result<RemoveTrackRangeFromQueueResponse, SdkError> {
-        self.exec(
+        self.write(
             av_transport::remove_track_range_from_queue(
                 update_id,
                 starting_index,
@@ -522,7 +1000,7 @@ impl Speaker {
 
     /// Backup the current queue
     pub fn backup_queue(&self) -> Result<(), SdkError> {
-        self.exec(av_transport::backup_queue().build())?;
+        self.write(av_transport::backup_queue().build())?;
         Ok(())
     }
 
@@ -541,7 +1019,7 @@ impl Speaker {
 
     /// Snooze the currently running alarm
     pub fn snooze_alarm(&self, duration: &str) -> Result<(), SdkError> {
-        self.exec(av_transport::snooze_alarm(duration.to_string()).build())?;
+        self.write(av_transport::snooze_alarm(duration.to_string()).build())?;
         Ok(())
     }
 
@@ -560,7 +1038,7 @@ impl Speaker {
     pub fn become_standalone(
         &self,
     ) -> Result<BecomeCoordinatorOfStandaloneGroupResponse, SdkError> {
-        self.exec(av_transport::become_coordinator_of_standalone_group().build())
+        self.write(av_transport::become_coordinator_of_standalone_group().build())
     }
 
     /// Delegate group coordination to another speaker
@@ -569,7 +1047,7 @@ impl Speaker {
         new_coordinator: &SpeakerId,
         rejoin_group: bool,
     ) -> Result<(), SdkError> {
-        self.exec(
+        self.write(
             av_transport::delegate_group_coordination_to(
                 new_coordinator.as_str().to_string(),
                 rejoin_group,
@@ -603,10 +1081,10 @@ impl Speaker {
     ///
     /// Updates the state cache to the new `Volume` on success.
     pub fn set_volume(&self, volume: u8) -> Result<(), SdkError> {
-        self.exec(rendering_control::set_volume("Master".to_string(), volume).build())?;
-        self.context
-            .state_manager
-            .set_property(&self.context.speaker_id, Volume(volume));
+        self.write_cached(
+            rendering_control::set_volume("Master".to_string(), volume).build(),
+            Volume(volume),
+        )?;
         Ok(())
     }
 
@@ -617,12 +1095,12 @@ impl Speaker {
         &self,
         adjustment: i8,
     ) -> Result<SetRelativeVolumeResponse, SdkError> {
-        let response = self.exec(
+        let response = self.write(
             rendering_control::set_relative_volume("Master".to_string(), adjustment).build(),
         )?;
         self.context
             .state_manager
-            .set_property(&self.context.speaker_id, Volume(response.new_volume));
+            .apply_local_write(&self.context.speaker_id, Volume(response.new_volume));
         Ok(response)
     }
 
@@ -630,37 +1108,313 @@ impl Speaker {
     ///
     /// Updates the state cache to the new `Mute` value on success.
     pub fn set_mute(&self, muted: bool) -> Result<(), SdkError> {
-        self.exec(rendering_control::set_mute("Master".to_string(), muted).build())?;
-        self.context
-            .state_manager
-            .set_property(&self.context.speaker_id, Mute(muted));
+        self.write_cached(
+            rendering_control::set_mute("Master".to_string(), muted).build(),
+            Mute(muted),
+        )?;
         Ok(())
     }
 
     /// Set bass EQ level (-10 to +10)
     pub fn set_bass(&self, level: i8) -> Result<(), SdkError> {
-        self.exec(rendering_control::set_bass(level).build())?;
-        self.context
-            .state_manager
-            .set_property(&self.context.speaker_id, Bass(level));
+        self.write_cached(rendering_control::set_bass(level).build(), Bass(level))?;
         Ok(())
     }
 
     /// Set treble EQ level (-10 to +10)
     pub fn set_treble(&self, level: i8) -> Result<(), SdkError> {
-        self.exec(rendering_control::set_treble(level).build())?;
-        self.context
-            .state_manager
-            .set_property(&self.context.speaker_id, Treble(level));
+        self.write_cached(rendering_control::set_treble(level).build(), Treble(level))?;
         Ok(())
     }
 
     /// Set loudness compensation
     pub fn set_loudness(&self, enabled: bool) -> Result<(), SdkError> {
-        self.exec(rendering_control::set_loudness("Master".to_string(), enabled).build())?;
+        self.write_cached(
+            rendering_control::set_loudness("Master".to_string(), enabled).build(),
+            Loudness(enabled),
+        )?;
+        Ok(())
+    }
+
+    /// Recall an EQ preset by name
+    ///
+    /// [`FACTORY_DEFAULTS_PRESET`](crate::FACTORY_DEFAULTS_PRESET) resets
+    /// bass, treble and loudness. When `presets` is cached the name is checked
+    /// against it without any network I/O; otherwise the device decides.
+    /// Either way an unknown name is [`SdkError::InvalidPreset`]. The new EQ
+    /// values arrive with the speaker's next RenderingControl event.
+    pub fn select_preset(&self, name: &str) -> Result<(), SdkError> {
+        let known = self.presets.get();
+        let invalid = |available: Option<Presets>| SdkError::InvalidPreset {
+            name: name.to_string(),
+            available: available.map(|p| p.0).unwrap_or_default(),
+        };
+        if known
+            .as_ref()
+            .is_some_and(|presets| !presets.contains(name))
+        {
+            return Err(invalid(known));
+        }
+        match self.write(rendering_control::select_preset(name.to_string()).build()) {
+            Err(SdkError::ApiError(ApiError::SoapFault {
+                fault: SoapFault {
+                    code: 701 | 702, ..
+                },
+                ..
+            })) => Err(invalid(known)),
+            result => result,
+        }
+    }
+
+    /// Apply several EQ settings as one action
+    ///
+    /// Every value is validated, and the values being replaced are read
+    /// (from the cache, or the device when nothing is cached), before
+    /// anything is sent; if either fails the error is returned and the
+    /// speaker is untouched. Fields are then written in order: bass, treble,
+    /// loudness. When one fails the rest are skipped and those already
+    /// written are put back, best effort, in reverse order.
+    ///
+    /// The cache is updated in one batch, so watchers of several of these
+    /// properties see a single [`ChangeEvent::BATCH_KEY`](crate::ChangeEvent::BATCH_KEY)
+    /// event. The returned [`EqResult`] has the outcome of each field.
+    pub fn apply_eq(&self, settings: EqSettings) -> Result<EqResult, SdkError> {
+        self.dry_run_eq(&settings)?;
+        let values = settings.values();
+        let previous = values
+            .iter()
+            .map(|value| self.current_eq(value.field()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut outcomes = Vec::with_capacity(values.len());
+        let mut cached = Vec::new();
+        let mut failed = false;
+        for value in &values {
+            if failed {
+                outcomes.push(EqOutcome::Skipped);
+                continue;
+            }
+            match self.send_eq_value(*value) {
+                Ok(modified) => {
+                    if !modified {
+                        cached.push(*value);
+                    }
+                    outcomes.push(EqOutcome::Applied);
+                }
+                Err(e) => {
+                    failed = true;
+                    outcomes.push(EqOutcome::Failed(e));
+                }
+            }
+        }
+        if failed {
+            for (outcome, previous) in outcomes.iter_mut().zip(&previous).rev() {
+                if !matches!(outcome, EqOutcome::Applied) {
+                    continue;
+                }
+                *outcome = match self.send_eq_value(*previous) {
+                    Ok(_) => {
+                        cached.retain(|v| v.field() != previous.field());
+                        EqOutcome::RolledBack
+                    }
+                    Err(e) => EqOutcome::RollbackFailed(e),
+                };
+            }
+        }
+
+        self.context
+            .state_manager
+            .apply_local_writes(&self.context.speaker_id, |batch| {
+                for value in cached {
+                    match value {
+                        EqValue::Bass(level) => batch.set(Bass(level)),
+                        EqValue::Treble(level) => batch.set(Treble(level)),
+                        EqValue::Loudness(enabled) => batch.set(Loudness(enabled)),
+                    }
+                }
+            });
+        Ok(EqResult {
+            fields: values.iter().map(EqValue::field).zip(outcomes).collect(),
+        })
+    }
+
+    /// Validate `settings` as [`apply_eq()`](Self::apply_eq) does, without
+    /// sending or caching anything
+    pub fn dry_run_eq(&self, settings: &EqSettings) -> Result<(), SdkError> {
+        for value in settings.values() {
+            value.validate()?;
+        }
+        Ok(())
+    }
+
+    /// The value `field` has now, from the cache or else the device
+    fn current_eq(&self, field: EqField) -> Result<EqValue, SdkError> {
+        Ok(match field {
+            EqField::Bass => EqValue::Bass(match self.bass.get() {
+                Some(Bass(level)) => level,
+                None => self.bass.fetch()?.0,
+            }),
+            EqField::Treble => EqValue::Treble(match self.treble.get() {
+                Some(Treble(level)) => level,
+                None => self.treble.fetch()?.0,
+            }),
+            EqField::Loudness => EqValue::Loudness(match self.loudness.get() {
+                Some(Loudness(enabled)) => enabled,
+                None => self.loudness.fetch()?.0,
+            }),
+        })
+    }
+
+    /// Send one EQ write; `true` if an interceptor changed what was sent
+    fn send_eq_value(&self, value: EqValue) -> Result<bool, SdkError> {
+        match value {
+            EqValue::Bass(level) => self.send_modified(rendering_control::set_bass(level).build()),
+            EqValue::Treble(level) => {
+                self.send_modified(rendering_control::set_treble(level).build())
+            }
+            EqValue::Loudness(enabled) => self.send_modified(
+                rendering_control::set_loudness("Master".to_string(), enabled).build(),
+            ),
+        }
+    }
+
+    /// [`write()`](Self::write), reporting whether an interceptor changed
+    /// the request instead of the response
+    fn send_modified<Op: UPnPOperation>(
+        &self,
+        operation: Result<ComposableOperation<Op>, ValidationError>,
+    ) -> Result<bool, SdkError> {
+        let sent = self.send(operation?)?;
+        Ok(sent.modified)
+    }
+
+    /// Lock or unlock the speaker's buttons/touch controls
+    ///
+    /// Models without a button lock return [`sonos_api::ApiError::NotSupported`].
+    pub fn set_button_lock(&self, locked: bool) -> Result<(), SdkError> {
+        self.write_cached(
+            device_properties::set_button_lock_state(locked).build(),
+            ButtonLock(locked),
+        )?;
+        Ok(())
+    }
+
+    /// Room name and icon the speaker reports
+    pub fn zone_attributes(&self) -> Result<GetZoneAttributesResponse, SdkError> {
+        self.exec(device_properties::get_zone_attributes().build())
+    }
+
+    /// Rename the speaker's room
+    ///
+    /// The room keeps its icon. The new name is applied to the state store
+    /// straight away rather than waiting for the DeviceProperties event, so
+    /// [`SonosSystem::speaker()`](crate::SonosSystem::speaker) finds the
+    /// speaker under it once this returns. This handle keeps its old `name`.
+    /// Empty names are rejected before anything is sent.
+    pub fn rename(&self, name: &str) -> Result<(), SdkError> {
+        // Validate before asking for the icon to keep
+        device_properties::set_zone_attributes(name.to_string(), String::new(), String::new())
+            .build()?;
+        let current = self.zone_attributes()?;
+        self.write(
+            device_properties::set_zone_attributes(
+                name.to_string(),
+                current.current_icon,
+                current.current_configuration,
+            )
+            .build(),
+        )?;
         self.context
             .state_manager
-            .set_property(&self.context.speaker_id, Loudness(enabled));
+            .set_room_name(&self.context.speaker_id, name.trim());
+        Ok(())
+    }
+
+    /// Whether the speaker's status LED is on
+    pub fn led_state(&self) -> Result<bool, SdkError> {
+        let response = self.exec(device_properties::get_led_state().build())?;
+        Ok(response.current_led_state)
+    }
+
+    /// Turn the speaker's status LED on or off
+    ///
+    /// The new state is applied to [`status_light`](Self::status_light)
+    /// straight away; the confirming DeviceProperties event isn't reported
+    /// as a second change.
+    pub fn set_led(&self, on: bool) -> Result<(), SdkError> {
+        self.write_cached(
+            device_properties::set_led_state(on).build(),
+            StatusLight(on),
+        )?;
+        Ok(())
+    }
+
+    /// Speaker ID of the room this speaker's line-in autoplays in, or
+    /// `None` when autoplay is off
+    pub fn get_autoplay_room(&self) -> Result<Option<SpeakerId>, SdkError> {
+        let response =
+            self.exec(device_properties::get_autoplay_room_uuid(String::new()).build())?;
+        Ok(Some(response.room_uuid)
+            .filter(|room| !room.is_empty())
+            .map(SpeakerId::new))
+    }
+
+    /// Volume (0-100) this speaker's line-in autoplays at
+    pub fn get_autoplay_volume(&self) -> Result<u8, SdkError> {
+        let response = self.exec(device_properties::get_autoplay_volume(String::new()).build())?;
+        Ok(response.current_volume)
+    }
+
+    /// Enable or disable the bonded Sub
+    ///
+    /// Like the other home-theater setters, this is sent to the bond primary
+    /// when called on a satellite.
+    pub fn set_sub_enabled(&self, enabled: bool) -> Result<(), SdkError> {
+        self.set_eq("SubEnable", enabled as i16, SubEnabled(enabled))
+    }
+
+    /// Set the bonded Sub level (-15 to +15)
+    pub fn set_sub_gain(&self, level: i8) -> Result<(), SdkError> {
+        self.set_eq("SubGain", level.into(), SubGain(level))
+    }
+
+    /// Enable or disable the bonded surrounds
+    pub fn set_surround_enabled(&self, enabled: bool) -> Result<(), SdkError> {
+        self.set_eq("SurroundEnable", enabled as i16, SurroundEnabled(enabled))
+    }
+
+    /// Set how the surrounds play music
+    pub fn set_surround_mode(&self, mode: SurroundMode) -> Result<(), SdkError> {
+        self.set_eq("SurroundMode", mode.eq_value(), mode)
+    }
+
+    /// Set the surround TV level (-15 to +15)
+    pub fn set_surround_level(&self, level: i8) -> Result<(), SdkError> {
+        self.set_eq("SurroundLevel", level.into(), SurroundLevel(level))
+    }
+
+    /// Send a `SetEQ` to the speaker holding `P` (the bond primary for
+    /// satellites) and cache `value` there
+    fn set_eq<P: SonosProperty>(
+        &self,
+        eq_type: &str,
+        desired: i16,
+        value: P,
+    ) -> Result<(), SdkError> {
+        let operation = rendering_control::set_eq(eq_type.to_string(), desired).build()?;
+        let (owner_id, owner_addr) = self.context.property_owner::<P>()?;
+        let sent = send_write(
+            &self.context.state_manager,
+            &self.context.api_client,
+            &owner_id,
+            owner_addr,
+            operation,
+        )?;
+        if !sent.modified {
+            self.context
+                .state_manager
+                .apply_local_write(&owner_id, value);
+        }
         Ok(())
     }
 }
@@ -679,6 +1433,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
         let state_manager = Arc::new(manager);
@@ -687,13 +1443,45 @@ mod tests {
         Speaker::new(
             SpeakerId::new("RINCON_TEST123"),
             "Test Speaker".to_string(),
-            "192.168.1.100".parse().unwrap(),
+            "192.168.1.100:1400".parse().unwrap(),
             "Sonos One".to_string(),
             state_manager,
             api_client,
         )
     }
 
+    #[test]
+    fn test_enforce_precheck_short_circuits() {
+        let speaker = create_test_speaker().with_precheck(PreCheck::Enforce);
+        speaker
+            .context
+            .state_manager
+            .set_property(&speaker.id, TransportActions::parse("Play, Stop"));
+
+        // No speaker at this address — only a short-circuit returns NotAvailable
+        let result = speaker.pause();
+        match result {
+            Err(SdkError::NotAvailable {
+                action,
+                available,
+                source,
+            }) => {
+                assert_eq!(action, "Pause");
+                assert_eq!(available, vec!["Play", "Stop"]);
+                assert!(source.is_none());
+            }
+            other => panic!("expected NotAvailable, got {other:?}"),
+        }
+        assert!(matches!(
+            speaker.seek(SeekTarget::Track(1)),
+            Err(SdkError::NotAvailable { .. })
+        ));
+        assert_eq!(
+            speaker.available_actions().unwrap().actions(),
+            ["Play", "Stop"]
+        );
+    }
+
     #[test]
     fn test_set_volume_rejects_invalid() {
         let speaker = create_test_speaker();
@@ -715,6 +1503,100 @@ mod tests {
         assert!(matches!(result, Err(SdkError::ValidationFailed(_))));
     }
 
+    #[test]
+    fn test_set_sub_gain_rejects_invalid() {
+        let speaker = create_test_speaker();
+        let result = speaker.set_sub_gain(16);
+        assert!(matches!(result, Err(SdkError::ValidationFailed(_))));
+    }
+
+    #[test]
+    fn test_seek_targets() {
+        assert_eq!(
+            SeekTarget::from(Duration::from_millis(150_900)),
+            SeekTarget::Time("0:02:30".into())
+        );
+        assert_eq!(
+            SeekTarget::from(Duration::from_secs(36_061)),
+            SeekTarget::Time("10:01:01".into())
+        );
+
+        let speaker = create_test_speaker();
+        for target in [
+            SeekTarget::Time("2:30".into()),
+            SeekTarget::Delta("+0:0:30".into()),
+            SeekTarget::Track(0),
+        ] {
+            assert!(matches!(
+                speaker.seek(target),
+                Err(SdkError::ValidationFailed(_))
+            ));
+        }
+    }
+
+    /// An Arc (primary) bonded with a Sub, returning a handle for the Sub
+    fn create_bonded_sub(bonded: bool) -> (Speaker, SpeakerId) {
+        let manager = StateManager::new().unwrap();
+        let device = |id: &str, ip: &str| Device {
+            id: id.to_string(),
+            name: "Living Room".to_string(),
+            room_name: "Living Room".to_string(),
+            ip_address: ip.to_string(),
+            port: 1400,
+            model_name: "Sonos Arc".to_string(),
+            household_id: None,
+            secure: false,
+        };
+        manager
+            .add_devices(vec![
+                device("RINCON_ARC", "192.168.1.100"),
+                device("RINCON_SUB", "192.168.1.101"),
+            ])
+            .unwrap();
+        let (arc, sub) = (SpeakerId::new("RINCON_ARC"), SpeakerId::new("RINCON_SUB"));
+        manager.set_satellite_ids(vec![sub.clone()]);
+        if bonded {
+            manager.set_bonds(vec![(arc.clone(), vec![sub.clone()])]);
+        }
+        let speaker = Speaker::new(
+            sub,
+            "Living Room".to_string(),
+            "192.168.1.101:1400".parse().unwrap(),
+            "Sonos Sub".to_string(),
+            Arc::new(manager),
+            SonosClient::new(),
+        );
+        (speaker, arc)
+    }
+
+    #[test]
+    fn test_home_theater_settings_redirect_to_bond_primary() {
+        let (sub, arc) = create_bonded_sub(true);
+        assert_eq!(
+            sub.context.property_owner::<SubGain>().unwrap(),
+            (arc.clone(), "192.168.1.100:1400".parse().unwrap())
+        );
+        sub.context.state_manager.set_property(&arc, SubGain(-4));
+        assert_eq!(sub.sub_gain.get(), Some(SubGain(-4)));
+
+        // Ordinary properties stay on the satellite itself
+        assert_eq!(sub.context.property_owner::<Volume>().unwrap().0, sub.id);
+    }
+
+    #[test]
+    fn test_home_theater_settings_reject_satellite_without_primary() {
+        let (sub, _) = create_bonded_sub(false);
+        assert!(matches!(
+            sub.set_surround_mode(SurroundMode::Full),
+            Err(SdkError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            sub.sub_enabled.fetch(),
+            Err(SdkError::InvalidOperation(_))
+        ));
+        assert_eq!(sub.surround_level.get(), None);
+    }
+
     #[test]
     fn test_speaker_action_methods_exist() {
         // Compile-time assertion that all method signatures are correct
@@ -784,8 +1666,10 @@ mod tests {
             name: speaker.name.clone(),
             room_name: speaker.name.clone(),
             ip_address: speaker.ip.to_string(),
-            port: 1400,
+            port: speaker.port,
             model_name: speaker.model_name.clone(),
+            household_id: None,
+            secure: false,
         }];
         state_manager.add_devices(devices).unwrap();
 
@@ -795,6 +1679,12 @@ mod tests {
             vec![speaker.id.clone()],
         );
 
-        crate::Group::from_info(group_info, state_manager, SonosClient::new()).unwrap()
+        crate::Group::from_info(
+            group_info,
+            state_manager,
+            SonosClient::new(),
+            Default::default(),
+        )
+        .unwrap()
     }
 }
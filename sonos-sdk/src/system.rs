@@ -2,19 +2,81 @@
 //!
 //! Provides a sync-first, DOM-like API for controlling Sonos devices.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex, RwLock};
-use std::time::Duration;
-
-use sonos_api::SonosClient;
-use sonos_discovery::{self, Device};
-use sonos_event_manager::SonosEventManager;
+use std::sync::{mpsc, Arc, Mutex, OnceLock, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sonos_api::clock::{SharedClock, SystemClock};
+use sonos_api::rate_limit::{TokenLevels, WriteRateLimit};
+use sonos_api::services::content_directory::{Favorite, Playlist};
+use sonos_api::services::zone_group_topology::state::ZoneGroupTopologyState;
+use sonos_api::{DeviceTransport, EventingConnection, Service, SonosClient};
+use sonos_discovery::{self, Device, DeviceEvent};
+use sonos_event_manager::{BrokerConfig, SonosEventManager};
 #[cfg(feature = "test-support")]
 use sonos_state::GroupInfo;
-use sonos_state::{EventInitFn, GroupId, SpeakerId, StateManager, Topology};
+use sonos_state::{
+    ChangeEvent, CurrentTrack, EventInitFn, GroupId, InterceptDecision, ListeningEvents,
+    ListeningRules, RoutingStats, ShutdownReport, SimulatedChange, SpeakerId, SpeakerInfo,
+    StateManager, SuspendPolicy, Topology, WriteRequest,
+};
+
+use crate::activity::{self, ActivityConfig, ActivityFeed};
+use crate::art::{self, ArtCache, ArtHandle};
+use crate::auto_subscribe::{self, AutoSubscribe, AutoSubscribeOptions, Presence};
+use crate::compat::{self, CompatibilityReport, DeviceCompatibility, Quirk};
+use crate::connect::{ConnectOptions, ConnectProgress, ConnectReport, DeviceReadiness, Readiness};
+use crate::household::{self, Household, HouseholdSelection};
+use crate::refresh::{self, RefreshReport};
+use crate::schedule::{self, ScheduleOverview};
+use crate::{cache, FetchCoalescer, Group, SdkError, Speaker};
+
+/// Reach devices that discovery reports as HTTPS-only over HTTPS, unless a
+/// transport was set for them already (e.g. a pinned certificate from
+/// [`ConnectOptions::device_transport()`])
+///
+/// The port comes along because topology may later report the plain HTTP
+/// one for the same speaker.
+fn secure_transports(devices: &[Device], api_client: &SonosClient) {
+    for device in devices {
+        if device.secure && api_client.device_transport(&device.ip_address).is_none() {
+            tracing::debug!("{} only speaks HTTPS, on port {}", device.id, device.port);
+            api_client.set_device_transport(
+                &device.ip_address,
+                DeviceTransport::https().with_port(device.port),
+            );
+        }
+    }
+}
 
-use crate::{cache, Group, SdkError, Speaker};
+/// Send subscription requests to models with
+/// [`Quirk::CloseEventingConnections`] over fresh connections, unless their
+/// transport already says how
+fn eventing_transports(devices: &[Device], api_client: &SonosClient) {
+    for device in devices {
+        if !compat::closes_eventing_connections(&device.model_name) {
+            continue;
+        }
+        let transport = api_client
+            .device_transport(&device.ip_address)
+            .unwrap_or_default();
+        if transport.eventing.is_none() {
+            tracing::debug!(
+                "{} ({}) gets fresh eventing connections",
+                device.id,
+                device.model_name
+            );
+            api_client.set_device_transport(
+                &device.ip_address,
+                transport.with_eventing_connection(EventingConnection::Close),
+            );
+        }
+    }
+}
 
 /// Compute the display name for a device.
 ///
@@ -28,18 +90,210 @@ fn display_name(device: &Device) -> String {
     }
 }
 
+/// Name index: display name → every speaker registered under that name.
+///
+/// Each entry is sorted by `SpeakerId` so lookups on a shared name are
+/// deterministic. More than one entry means the room name is ambiguous.
+type SpeakerIndex = HashMap<String, Vec<Speaker>>;
+
+/// Speakers that share the same room name.
+///
+/// Usually left behind by a factory reset, where a replacement device is
+/// set up under the old room name. Reported by [`SonosSystem::name_collisions()`]
+/// and announced on [`SonosSystem::iter()`] as a [`ChangeEvent`](sonos_state::ChangeEvent)
+/// with `property_key == NameCollision::EVENT_KEY` for each affected speaker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameCollision {
+    /// The shared room name
+    pub name: String,
+    /// IDs of the speakers using that name, sorted
+    pub speaker_ids: Vec<SpeakerId>,
+}
+
+impl NameCollision {
+    /// Property key of the diagnostic change event emitted when a speaker
+    /// enters or leaves a name collision
+    pub const EVENT_KEY: &'static str = "name_collision";
+}
+
 /// Find a speaker by name with case-insensitive fallback.
 ///
 /// Tries an exact O(1) HashMap lookup first, then falls back to
-/// case-insensitive iteration (O(n), typically n < 50).
-fn find_speaker_by_name(speakers: &HashMap<String, Speaker>, name: &str) -> Option<Speaker> {
-    if let Some(speaker) = speakers.get(name) {
+/// case-insensitive iteration (O(n), typically n < 50), and finally to the
+/// disambiguated labels produced by [`disambiguated_names()`].
+/// When a name is shared, the speaker with the lowest `SpeakerId` wins.
+fn find_speaker_by_name(speakers: &SpeakerIndex, name: &str) -> Option<Speaker> {
+    if let Some(speaker) = speakers.get(name).and_then(|list| list.first()) {
+        return Some(speaker.clone());
+    }
+    if let Some(speaker) = speakers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, list)| list.first())
+    {
         return Some(speaker.clone());
     }
     speakers
         .values()
-        .find(|s| s.name.eq_ignore_ascii_case(name))
-        .cloned()
+        .filter(|list| list.len() > 1)
+        .find_map(|list| {
+            disambiguated_names(list)
+                .into_iter()
+                .position(|label| label.eq_ignore_ascii_case(name))
+                .map(|i| list[i].clone())
+        })
+}
+
+/// Compute unique display labels for speakers sharing a name.
+///
+/// A lone speaker keeps its name. Duplicates are suffixed with the model
+/// ("Bedroom (One)", "Bedroom (Play:1)"), or with the last four characters
+/// of the ID when the models match too.
+fn disambiguated_names(list: &[Speaker]) -> Vec<String> {
+    if list.len() < 2 {
+        return list.iter().map(|s| s.name.clone()).collect();
+    }
+    let short_model = |s: &Speaker| -> String {
+        s.model_name
+            .strip_prefix("Sonos ")
+            .unwrap_or(&s.model_name)
+            .to_string()
+    };
+    list.iter()
+        .map(|s| {
+            let model = short_model(s);
+            let unique_model = list.iter().filter(|o| short_model(o) == model).count() == 1;
+            let suffix = if unique_model && !model.is_empty() {
+                model
+            } else {
+                let id = s.id.as_str();
+                id.char_indices()
+                    .rev()
+                    .nth(3)
+                    .map_or(id, |(i, _)| &id[i..])
+                    .to_string()
+            };
+            format!("{} ({})", s.name, suffix)
+        })
+        .collect()
+}
+
+/// List every shared name in the index, sorted by name.
+fn find_collisions(speakers: &SpeakerIndex) -> Vec<NameCollision> {
+    let mut collisions: Vec<NameCollision> = speakers
+        .iter()
+        .filter(|(_, list)| list.len() > 1)
+        .map(|(name, list)| NameCollision {
+            name: name.clone(),
+            speaker_ids: list.iter().map(|s| s.id.clone()).collect(),
+        })
+        .collect();
+    collisions.sort_by(|a, b| a.name.cmp(&b.name));
+    collisions
+}
+
+/// Change the speaker name index and report name collisions.
+///
+/// `update` runs under the index's write lock, so concurrent updates
+/// (discoveries, removals, renames) each see the other's result. Satellite
+/// speakers known from topology are dropped afterwards. Every speaker
+/// whose collision status changed (newly ambiguous or no longer ambiguous)
+/// gets a [`NameCollision::EVENT_KEY`] change event on [`SonosSystem::iter()`].
+fn update_index(
+    speakers: &RwLock<SpeakerIndex>,
+    state_manager: &StateManager,
+    update: impl FnOnce(&mut SpeakerIndex),
+) {
+    let colliding_ids = |index: &SpeakerIndex| -> HashSet<SpeakerId> {
+        find_collisions(index)
+            .into_iter()
+            .flat_map(|c| c.speaker_ids)
+            .collect()
+    };
+    let satellite_ids = state_manager.get_satellite_ids();
+
+    let (new_collisions, now_colliding, was_colliding) = {
+        let Ok(mut speakers) = speakers.write() else {
+            return;
+        };
+        let was_colliding = colliding_ids(&speakers);
+        update(&mut speakers);
+        if !satellite_ids.is_empty() {
+            for list in speakers.values_mut() {
+                list.retain(|speaker| !satellite_ids.contains(&speaker.id));
+            }
+            speakers.retain(|_name, list| !list.is_empty());
+            tracing::debug!("Filtered {} satellite speakers", satellite_ids.len());
+        }
+        (
+            find_collisions(&speakers),
+            colliding_ids(&speakers),
+            was_colliding,
+        )
+    };
+
+    for collision in &new_collisions {
+        tracing::warn!(
+            "duplicate speaker name \"{}\" shared by {:?}",
+            collision.name,
+            collision
+                .speaker_ids
+                .iter()
+                .map(SpeakerId::as_str)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    for speaker_id in now_colliding.symmetric_difference(&was_colliding) {
+        state_manager.emit_change(ChangeEvent::new(
+            speaker_id.clone(),
+            NameCollision::EVENT_KEY,
+            Service::ZoneGroupTopology,
+        ));
+    }
+}
+
+/// Move a speaker's handle to the entry for its new room name
+fn rename_in_index(index: &mut SpeakerIndex, speaker_id: &SpeakerId, name: &str) {
+    let mut moved = None;
+    for list in index.values_mut() {
+        if let Some(pos) = list.iter().position(|s| s.id == *speaker_id) {
+            moved = Some(list.remove(pos));
+        }
+    }
+    index.retain(|_name, list| !list.is_empty());
+    let Some(mut speaker) = moved else {
+        return;
+    };
+    speaker.name = name.to_string();
+    let list = index.entry(speaker.name.clone()).or_default();
+    list.push(speaker);
+    list.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+}
+
+/// Re-key speakers in `speakers` when their room is renamed.
+///
+/// Follows the [`SpeakerInfo::RENAME_EVENT_KEY`] events the state manager
+/// emits for [`Speaker::rename()`] and for `ZoneName` changes made
+/// elsewhere. Runs on the thread that applied the rename, so the index has
+/// the new name when `rename()` returns. Holds only weak references.
+fn observe_renames(speakers: &Arc<RwLock<SpeakerIndex>>, state_manager: &Arc<StateManager>) {
+    let speakers = Arc::downgrade(speakers);
+    let manager = Arc::downgrade(state_manager);
+    state_manager.add_change_observer(Arc::new(move |event: &ChangeEvent| {
+        if event.property_key != SpeakerInfo::RENAME_EVENT_KEY {
+            return;
+        }
+        let (Some(speakers), Some(manager)) = (speakers.upgrade(), manager.upgrade()) else {
+            return;
+        };
+        let Some(info) = manager.speaker_info(&event.speaker_id) else {
+            return;
+        };
+        update_index(&speakers, &manager, |index| {
+            rename_in_index(index, &event.speaker_id, &info.room_name)
+        });
+    }));
 }
 
 /// Main system entry point - provides DOM-like API
@@ -84,15 +338,45 @@ pub struct SonosSystem {
     /// API client for direct operations
     api_client: SonosClient,
 
-    /// Speaker handles by name
-    speakers: RwLock<HashMap<String, Speaker>>,
+    /// Speaker handles indexed by name (shared names hold several speakers);
+    /// shared with the rename observer
+    speakers: Arc<RwLock<SpeakerIndex>>,
 
     /// Timestamp of last rediscovery attempt (seconds since UNIX_EPOCH, 0 = never)
     last_rediscovery: AtomicU64,
+
+    /// Watch handles held on behalf of the caller by `connect()` and
+    /// `auto_subscribe()`, per speaker
+    profile_watches: Mutex<HashMap<SpeakerId, Vec<Box<dyn Send>>>>,
+
+    /// Speakers reported lost by `auto_subscribe()` and not yet found again
+    offline: RwLock<HashSet<SpeakerId>>,
+
+    /// Album art cache shared by every speaker
+    art: Arc<ArtCache>,
+
+    /// Coalesces property fetches across every speaker and group handle
+    fetches: Arc<FetchCoalescer>,
+
+    /// Households seen when the system was built, largest first
+    households: Vec<Household>,
+
+    /// Household this system is scoped to; `None` when unscoped
+    household: Option<String>,
+
+    /// Activity log, once `activity_feed()` installed it; shared with the
+    /// broker's protocol observer
+    activity: Arc<OnceLock<ActivityFeed>>,
 }
 
 const REDISCOVERY_COOLDOWN_SECS: u64 = 30;
 
+/// Time budget for re-fetching state in [`SonosSystem::resume()`]
+const RESUME_REFRESH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Results of [`SonosSystem::spawn_topology_fetch()`], tagged with the speaker IP
+type TopologyReceiver = mpsc::Receiver<(String, sonos_api::Result<ZoneGroupTopologyState>)>;
+
 impl SonosSystem {
     /// Create a new SonosSystem with cache-first device discovery (sync)
     ///
@@ -103,14 +387,56 @@ impl SonosSystem {
     /// 4. If no cache exists, run SSDP discovery
     /// 5. If no devices found anywhere, return `Err(SdkError::DiscoveryFailed)`
     pub fn new() -> Result<Self, SdkError> {
-        let devices = match cache::load() {
+        let devices = Self::load_devices(true, Duration::from_secs(3));
+        if devices.is_empty() {
+            return Err(SdkError::DiscoveryFailed(
+                "no Sonos devices found on the network".to_string(),
+            ));
+        }
+
+        Self::from_discovered_devices(devices)
+    }
+
+    /// Like [`new()`](Self::new), scoped to the household with this ID
+    ///
+    /// `new()` picks the household with the most devices; use this when the
+    /// LAN carries several and [`households()`](Self::households) showed the
+    /// one you want. Returns [`SdkError::HouseholdNotFound`] when no
+    /// discovered device belongs to it.
+    pub fn with_household(id: impl Into<String>) -> Result<Self, SdkError> {
+        let devices = Self::load_devices(true, Duration::from_secs(3));
+        if devices.is_empty() {
+            return Err(SdkError::DiscoveryFailed(
+                "no Sonos devices found on the network".to_string(),
+            ));
+        }
+
+        let system = Self::assemble(
+            devices,
+            HouseholdSelection::Id(id.into()),
+            Arc::new(SystemClock),
+            None,
+        )?;
+        system.ensure_topology();
+        system.publish_speakers();
+        Ok(system)
+    }
+
+    /// Load devices from the discovery cache and/or SSDP.
+    ///
+    /// With `use_cache`, a fresh cache is used directly and a stale one is
+    /// the fallback when SSDP finds nothing. Returns an empty list when
+    /// nothing was found.
+    fn load_devices(use_cache: bool, timeout: Duration) -> Vec<Device> {
+        let cached = if use_cache { cache::load() } else { None };
+        match cached {
             Some(cached) if !cache::is_stale(&cached) => {
                 // Fresh cache — use directly
                 cached.devices
             }
             Some(cached) => {
                 // Stale cache — try SSDP, fall back to stale data
-                let fresh = sonos_discovery::get_with_timeout(Duration::from_secs(3));
+                let fresh = sonos_discovery::get_with_timeout(timeout);
                 if fresh.is_empty() {
                     tracing::warn!("Cache is stale and SSDP found no devices; using stale cache");
                     cached.devices
@@ -123,20 +449,274 @@ impl SonosSystem {
             }
             None => {
                 // No cache — full SSDP discovery
-                let fresh = sonos_discovery::get_with_timeout(Duration::from_secs(3));
-                if fresh.is_empty() {
-                    return Err(SdkError::DiscoveryFailed(
-                        "no Sonos devices found on the network".to_string(),
-                    ));
-                }
-                if let Err(e) = cache::save(&fresh) {
-                    tracing::warn!("Failed to save discovery cache: {}", e);
+                let fresh = sonos_discovery::get_with_timeout(timeout);
+                if !fresh.is_empty() {
+                    if let Err(e) = cache::save(&fresh) {
+                        tracing::warn!("Failed to save discovery cache: {}", e);
+                    }
                 }
                 fresh
             }
+        }
+    }
+
+    /// Discover, register, prefetch and subscribe in one call (sync)
+    ///
+    /// Quick-start alternative to [`new()`](Self::new). After discovery it
+    /// prefetches volume, mute, playback state and current track on every
+    /// speaker, then watches the NowPlaying profile (playback state, current
+    /// track, volume). The system holds those watch handles, so
+    /// [`iter()`](Self::iter) receives events without further setup.
+    ///
+    /// Per-device failures do not fail the call: the speaker stays
+    /// registered and is marked [`Readiness::Degraded`] in the returned
+    /// [`ConnectReport`]. Work still outstanding when the deadline passes is
+    /// abandoned the same way. Only finding no devices at all is an error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let (sonos, report) = SonosSystem::connect(ConnectOptions::default())?;
+    /// for device in report.degraded() {
+    ///     eprintln!("{} not ready: {:?}", device.name, device.readiness);
+    /// }
+    /// ```
+    pub fn connect(options: ConnectOptions) -> Result<(Self, ConnectReport), SdkError> {
+        let started = Instant::now();
+        let deadline = started + options.deadline;
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+
+        // Before any request: the household lookup may already need them
+        let api_client = SonosClient::new();
+        for (ip, transport) in &options.transports {
+            api_client.set_device_transport(ip, transport.clone());
+        }
+
+        options.report(ConnectProgress::DiscoveryStarted);
+        let discover = || {
+            Self::load_devices(
+                options.use_cache,
+                options.discovery_timeout.min(remaining()),
+            )
         };
+        #[cfg(feature = "test-support")]
+        let devices = options.devices.clone().unwrap_or_else(discover);
+        #[cfg(not(feature = "test-support"))]
+        let devices = discover();
+        if devices.is_empty() {
+            return Err(SdkError::DiscoveryFailed(
+                "no Sonos devices found on the network".to_string(),
+            ));
+        }
+        options.report(ConnectProgress::DeviceFound(devices.len()));
+
+        // Topology and prefetch run side by side so one slow device
+        // cannot starve the other; subscriptions wait for both.
+        let system = Self::assemble(
+            devices,
+            options.household.clone(),
+            Arc::clone(&options.clock),
+            options.stable_id_file.clone(),
+        )?;
+        let topology = system.spawn_topology_fetch();
+        let mut failures = if options.prefetch {
+            Self::prefetch_within(&system.speakers(), deadline, &options)
+        } else {
+            HashMap::new()
+        };
+        system.await_topology(topology, deadline);
+        system.publish_speakers();
+
+        let mut speakers = system.speakers();
+        speakers.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| a.id.as_str().cmp(b.id.as_str()))
+        });
+
+        if options.subscribe {
+            system.subscribe_now_playing(&speakers, deadline, &options, &mut failures);
+        }
 
-        Self::from_discovered_devices(devices)
+        let devices = speakers
+            .iter()
+            .map(|speaker| DeviceReadiness {
+                speaker_id: speaker.id.clone(),
+                name: speaker.name.clone(),
+                readiness: match failures.remove(&speaker.id) {
+                    Some(reason) => Readiness::Degraded(reason),
+                    None => Readiness::Ready,
+                },
+            })
+            .collect();
+
+        options.report(ConnectProgress::Ready);
+        let report = ConnectReport {
+            devices,
+            elapsed: started.elapsed(),
+        };
+        Ok((system, report))
+    }
+
+    /// Start fetching topology from every speaker in parallel.
+    fn spawn_topology_fetch(&self) -> TopologyReceiver {
+        let (tx, rx) = mpsc::channel();
+        for speaker in self.speakers() {
+            let tx = tx.clone();
+            let client = self.api_client.clone();
+            thread::spawn(move || {
+                let addr = speaker.addr().to_string();
+                let result = sonos_api::services::zone_group_topology::state::poll(&client, &addr);
+                let _ = tx.send((addr, result));
+            });
+        }
+        rx
+    }
+
+    /// Apply the first topology of each household that arrives before `deadline`.
+    fn await_topology(&self, rx: TopologyReceiver, deadline: Instant) {
+        let households = self.households_by_addr();
+        let mut pending: HashSet<Option<String>> = households.values().cloned().collect();
+        while let Ok((ip, result)) =
+            rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            let household = households.get(&ip).cloned().flatten();
+            if !pending.contains(&household) {
+                continue;
+            }
+            match result {
+                Ok(state) => {
+                    self.apply_topology(&state);
+                    pending.remove(&household);
+                    if pending.is_empty() {
+                        return;
+                    }
+                }
+                Err(e) => tracing::debug!("Topology fetch failed for {}: {}", ip, e),
+            }
+        }
+        tracing::warn!("No topology before deadline");
+    }
+
+    /// Household of each registered speaker, keyed by `ip:port`
+    fn households_by_addr(&self) -> HashMap<String, Option<String>> {
+        self.state_manager
+            .speaker_infos()
+            .into_iter()
+            .map(|info| (info.socket_addr().to_string(), info.household_id))
+            .collect()
+    }
+
+    /// Prefetch the basic property set on every speaker in parallel.
+    ///
+    /// Returns the failure reason for each speaker that errored or did not
+    /// finish before `deadline`.
+    fn prefetch_within(
+        speakers: &[Speaker],
+        deadline: Instant,
+        options: &ConnectOptions,
+    ) -> HashMap<SpeakerId, String> {
+        let (tx, rx) = mpsc::channel();
+        for speaker in speakers {
+            let tx = tx.clone();
+            let speaker = speaker.clone();
+            thread::spawn(move || {
+                let result = (|| -> Result<(), SdkError> {
+                    speaker.volume.fetch()?;
+                    speaker.mute.fetch()?;
+                    speaker.playback_state.fetch()?;
+                    speaker.current_track.fetch()?;
+                    Ok(())
+                })();
+                let _ = tx.send((speaker.id.clone(), result));
+            });
+        }
+        drop(tx);
+
+        let total = speakers.len();
+        let mut pending: HashSet<SpeakerId> = speakers.iter().map(|s| s.id.clone()).collect();
+        let mut failures = HashMap::new();
+        while !pending.is_empty() {
+            let Ok((speaker_id, result)) =
+                rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            else {
+                break;
+            };
+            pending.remove(&speaker_id);
+            if let Err(e) = result {
+                failures.insert(speaker_id, format!("prefetch failed: {e}"));
+            }
+            options.report(ConnectProgress::Prefetching {
+                done: total - pending.len(),
+                total,
+            });
+        }
+        for speaker_id in pending {
+            failures.insert(
+                speaker_id,
+                "prefetch did not finish before the deadline".to_string(),
+            );
+        }
+        failures
+    }
+
+    /// Watch the NowPlaying profile on every speaker not already degraded.
+    ///
+    /// `watch()` only queues the UPnP subscription, so this runs inline.
+    fn subscribe_now_playing(
+        &self,
+        speakers: &[Speaker],
+        deadline: Instant,
+        options: &ConnectOptions,
+        failures: &mut HashMap<SpeakerId, String>,
+    ) {
+        let ready: Vec<&Speaker> = speakers
+            .iter()
+            .filter(|s| !failures.contains_key(&s.id))
+            .collect();
+        let total = ready.len();
+        for (i, speaker) in ready.into_iter().enumerate() {
+            if Instant::now() >= deadline {
+                failures.insert(
+                    speaker.id.clone(),
+                    "deadline passed before subscribing".to_string(),
+                );
+                continue;
+            }
+            if let Err(e) = self.watch_profile(speaker) {
+                failures.insert(speaker.id.clone(), format!("subscription failed: {e}"));
+            }
+            options.report(ConnectProgress::Subscribing { done: i + 1, total });
+        }
+    }
+
+    /// Watch the NowPlaying profile on one speaker unless already held.
+    pub(crate) fn watch_profile(&self, speaker: &Speaker) -> Result<(), SdkError> {
+        let mut watches = self
+            .profile_watches
+            .lock()
+            .map_err(|_| SdkError::LockPoisoned)?;
+        if watches.contains_key(&speaker.id) {
+            return Ok(());
+        }
+        let handles: Vec<Box<dyn Send>> = vec![
+            Box::new(speaker.playback_state.watch()?),
+            Box::new(speaker.current_track.watch()?),
+            Box::new(speaker.volume.watch()?),
+        ];
+        watches.insert(speaker.id.clone(), handles);
+        Ok(())
+    }
+
+    /// Drop the profile watches held for a speaker; returns whether any were held
+    pub(crate) fn unwatch_profile(&self, speaker_id: &SpeakerId) -> bool {
+        // Dropped outside the lock; each handle starts its own grace period
+        let handles = self
+            .profile_watches
+            .lock()
+            .ok()
+            .and_then(|mut watches| watches.remove(speaker_id));
+        handles.is_some()
     }
 
     /// Create a new SonosSystem from pre-discovered devices (sync)
@@ -159,19 +739,71 @@ impl SonosSystem {
     }
 
     fn from_devices_inner(devices: Vec<Device>) -> Result<Self, SdkError> {
+        let system = Self::assemble(
+            devices,
+            HouseholdSelection::default(),
+            Arc::new(SystemClock),
+            None,
+        )?;
+
+        // 5. Prefetch topology before any subscriptions can start.
+        //    This ensures group structure is known when the first AVTransport
+        //    events arrive, so PerCoordinator suppression/propagation works
+        //    from the very first event.
+        system.ensure_topology();
+
+        system.publish_speakers();
+        Ok(system)
+    }
+
+    /// Register devices and build the system.
+    ///
+    /// The only network access is `GetHouseholdID` for devices that
+    /// discovery left without a household, and only when the selection
+    /// needs it.
+    fn assemble(
+        mut devices: Vec<Device>,
+        selection: HouseholdSelection,
+        clock: SharedClock,
+        stable_id_file: Option<PathBuf>,
+    ) -> Result<Self, SdkError> {
+        let api_client = SonosClient::new().with_clock(Arc::clone(&clock));
+
+        // 0. Keep one household's devices
+        secure_transports(&devices, &api_client);
+        eventing_transports(&devices, &api_client);
+        household::resolve_missing(&mut devices, &api_client, &selection);
+        let households = household::detect(&devices);
+        let scope = household::select(&households, &selection)?;
+        devices.retain(|device| household::in_scope(device, scope.as_deref()));
+        if households.len() > 1 {
+            tracing::info!(
+                "{} households on the network; using {}",
+                households.len(),
+                scope.as_deref().unwrap_or("all of them")
+            );
+        }
+
         // 1. Create shared state FIRST — no event manager yet (lazy init)
-        let state_manager = Arc::new(StateManager::new().map_err(SdkError::StateError)?);
+        let state_manager = Arc::new(
+            StateManager::builder()
+                .clock(Arc::clone(&clock))
+                .build()
+                .map_err(SdkError::StateError)?,
+        );
+        state_manager.set_household(scope.clone());
         state_manager
             .add_devices(devices.clone())
             .map_err(SdkError::StateError)?;
 
-        let api_client = SonosClient::new();
         let event_manager: Arc<Mutex<Option<Arc<SonosEventManager>>>> = Arc::new(Mutex::new(None));
+        let activity = Arc::new(OnceLock::new());
 
         // 2. Build init closure and store on StateManager (single source of truth)
         let init_fn: EventInitFn = {
             let em_mutex = Arc::clone(&event_manager);
             let sm = Arc::clone(&state_manager);
+            let observer = activity::protocol_observer(Arc::clone(&activity));
             Arc::new(
                 move || -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
                     let mut guard = em_mutex.lock().map_err(|_| SdkError::LockPoisoned)?;
@@ -182,7 +814,13 @@ impl SonosSystem {
                         return Ok(());
                     }
                     tracing::info!("Lazy-initializing event manager (first watch() call)");
-                    let em = Arc::new(SonosEventManager::new().map_err(|e| {
+                    let mut config = BrokerConfig::default()
+                        .with_clock(Arc::clone(&clock))
+                        .with_protocol_observer(observer.clone());
+                    if let Some(path) = &stable_id_file {
+                        config = config.with_stable_id_file(path);
+                    }
+                    let em = Arc::new(SonosEventManager::with_config(config).map_err(|e| {
                         tracing::error!("Failed to create SonosEventManager: {}", e);
                         SdkError::EventManager(e.to_string())
                     })?);
@@ -198,45 +836,57 @@ impl SonosSystem {
         state_manager.set_event_init(init_fn);
 
         // 3. Build speakers (init fn is on StateManager — no per-speaker threading needed)
-        let speakers = Self::build_speakers(&devices, &state_manager, &api_client)?;
+        let fetches = Arc::new(FetchCoalescer::new());
+        let speakers = Self::build_speakers(&devices, &state_manager, &api_client, &fetches)?;
+
+        let speakers = Arc::new(RwLock::new(speakers));
+        observe_renames(&speakers, &state_manager);
+
+        let art = Arc::new(ArtCache::new(api_client.clone()));
+        art::observe_track_changes(&art, &state_manager);
 
         // 4. Assemble struct from the SAME Arcs
-        let system = Self {
+        Ok(Self {
             state_manager,
             event_manager: Arc::try_unwrap(event_manager).unwrap_or_else(|arc| {
                 let inner = arc.lock().unwrap().clone();
                 Mutex::new(inner)
             }),
             api_client,
-            speakers: RwLock::new(speakers),
+            speakers,
             last_rediscovery: AtomicU64::new(0),
-        };
-
-        // 5. Prefetch topology before any subscriptions can start.
-        //    This ensures group structure is known when the first AVTransport
-        //    events arrive, so PerCoordinator suppression/propagation works
-        //    from the very first event.
-        system.ensure_topology();
-
-        // 6. Filter satellite speakers (surrounds/subs marked Invisible="1")
-        let satellite_ids = system.state_manager.get_satellite_ids();
-        if !satellite_ids.is_empty() {
-            if let Ok(mut speakers) = system.speakers.write() {
-                speakers.retain(|_name, speaker| !satellite_ids.contains(&speaker.id));
-            }
-            tracing::debug!("Filtered {} satellite speakers", satellite_ids.len());
-        }
+            profile_watches: Mutex::new(HashMap::new()),
+            offline: RwLock::new(HashSet::new()),
+            art,
+            fetches,
+            households,
+            household: scope,
+            activity,
+        })
+    }
 
-        // 7. Refresh Speaker handle IPs from state store (topology may have updated them)
-        if let Ok(mut speakers) = system.speakers.write() {
-            for speaker in speakers.values_mut() {
-                if let Some(info) = system.state_manager.speaker_info(&speaker.id) {
+    /// Publish the name index built by [`assemble()`](Self::assemble).
+    fn publish_speakers(&self) {
+        // 6. Runs after topology so satellite speakers (surrounds/subs marked
+        //    Invisible="1", which share the main speaker's room name) are
+        //    filtered before collisions are reported. Installed from empty so
+        //    every collision is announced; the system isn't shared yet.
+        let speakers = self
+            .speakers
+            .write()
+            .map(|mut s| std::mem::take(&mut *s))
+            .unwrap_or_default();
+        self.install_speakers(speakers);
+
+        // 7. Refresh Speaker handle addresses from state store (topology may have updated them)
+        if let Ok(mut speakers) = self.speakers.write() {
+            for speaker in speakers.values_mut().flatten() {
+                if let Some(info) = self.state_manager.speaker_info(&speaker.id) {
                     speaker.ip = info.ip_address;
+                    speaker.port = info.port;
                 }
             }
         }
-
-        Ok(system)
     }
 
     /// Create a test SonosSystem with named speakers and no network access.
@@ -266,9 +916,18 @@ impl SonosSystem {
                 ip_address: format!("192.168.1.{}", 100 + i),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             })
             .collect();
 
+        Self::from_devices_offline(devices)
+    }
+
+    /// Build an in-memory system from devices without topology fetch or
+    /// event manager wiring. Backs `with_speakers()` and SDK unit tests.
+    #[cfg(any(test, feature = "test-support"))]
+    fn from_devices_offline(devices: Vec<Device>) -> Self {
         let state_manager =
             Arc::new(StateManager::new().expect("StateManager::new() should not fail"));
 
@@ -277,16 +936,32 @@ impl SonosSystem {
             .expect("add_devices should not fail with valid test data");
 
         let api_client = SonosClient::new();
-        let speakers = Self::build_speakers(&devices, &state_manager, &api_client)
+        let fetches = Arc::new(FetchCoalescer::new());
+        let speakers = Self::build_speakers(&devices, &state_manager, &api_client, &fetches)
             .expect("build_speakers should not fail with valid test data");
 
-        Self {
+        let index = Arc::new(RwLock::new(HashMap::new()));
+        observe_renames(&index, &state_manager);
+
+        let art = Arc::new(ArtCache::new(api_client.clone()));
+        art::observe_track_changes(&art, &state_manager);
+
+        let system = Self {
             state_manager,
             event_manager: Mutex::new(None),
             api_client,
-            speakers: RwLock::new(speakers),
+            speakers: index,
             last_rediscovery: AtomicU64::new(0),
-        }
+            profile_watches: Mutex::new(HashMap::new()),
+            offline: RwLock::new(HashSet::new()),
+            art,
+            fetches,
+            households: household::detect(&devices),
+            household: None,
+            activity: Arc::new(OnceLock::new()),
+        };
+        system.install_speakers(speakers);
+        system
     }
 
     /// Create a test SonosSystem with speakers AND group topology.
@@ -321,13 +996,20 @@ impl SonosSystem {
         system
     }
 
-    /// Build Speaker handles from a list of devices.
+    /// Build the speaker name index from a list of devices.
+    ///
+    /// Devices sharing a display name are kept side by side (sorted by ID)
+    /// rather than overwriting each other; a repeated ID replaces the
+    /// earlier entry.
     fn build_speakers(
         devices: &[Device],
         state_manager: &Arc<StateManager>,
         api_client: &SonosClient,
-    ) -> Result<HashMap<String, Speaker>, SdkError> {
-        let mut speakers = HashMap::new();
+        fetches: &Arc<FetchCoalescer>,
+    ) -> Result<SpeakerIndex, SdkError> {
+        secure_transports(devices, api_client);
+        eventing_transports(devices, api_client);
+        let mut speakers: SpeakerIndex = HashMap::new();
         for device in devices {
             let speaker_id = SpeakerId::new(&device.id);
             let ip = device
@@ -336,31 +1018,47 @@ impl SonosSystem {
                 .map_err(|_| SdkError::InvalidIpAddress)?;
 
             let name = display_name(device);
-            let speaker = Speaker::new(
+            let speaker = Speaker::with_fetches(
                 speaker_id,
                 name.clone(),
-                ip,
+                SocketAddr::new(ip, device.port),
                 device.model_name.clone(),
                 Arc::clone(state_manager),
                 api_client.clone(),
+                Arc::clone(fetches),
             );
 
-            if speakers.contains_key(&name) {
-                tracing::warn!(
-                    "duplicate speaker name \"{}\", keeping last discovered",
-                    name
-                );
-            }
-            speakers.insert(name, speaker);
+            let list = speakers.entry(name).or_default();
+            list.retain(|s| s.id != speaker.id);
+            list.push(speaker);
+        }
+        for list in speakers.values_mut() {
+            list.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
         }
         Ok(speakers)
     }
 
+    /// Replace the speaker name index and report name collisions.
+    fn install_speakers(&self, speakers: SpeakerIndex) {
+        self.update_speakers(|index| *index = speakers);
+    }
+
+    /// Change the speaker name index and report name collisions; see
+    /// [`update_index()`]
+    fn update_speakers(&self, update: impl FnOnce(&mut SpeakerIndex)) {
+        update_index(&self.speakers, &self.state_manager, update);
+    }
+
     /// Get speaker by name (sync)
     ///
     /// If the speaker isn't in the current map, triggers an SSDP
     /// rediscovery (rate-limited to once per 30s) before returning `None`.
     ///
+    /// When several speakers share the name, the one with the lowest
+    /// `SpeakerId` is returned. Use [`speakers_by_name()`](Self::speakers_by_name)
+    /// to get all of them, or pass a disambiguated label from
+    /// [`speaker_names()`](Self::speaker_names) such as `"Bedroom (Play:1)"`.
+    ///
     /// # Example
     ///
     /// ```rust,ignore
@@ -380,6 +1078,32 @@ impl SonosSystem {
         find_speaker_by_name(&speakers, name)
     }
 
+    /// Get every speaker registered under a name (sync)
+    ///
+    /// Matches case-insensitively and returns speakers sorted by ID.
+    /// More than one result means the room name is ambiguous; see
+    /// [`name_collisions()`](Self::name_collisions).
+    pub fn speakers_by_name(&self, name: &str) -> Vec<Speaker> {
+        let Ok(speakers) = self.speakers.read() else {
+            return Vec::new();
+        };
+        let mut matches: Vec<Speaker> = speakers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .flat_map(|(_, list)| list.iter().cloned())
+            .collect();
+        matches.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+        matches
+    }
+
+    /// Get all room names currently shared by more than one speaker (sync)
+    pub fn name_collisions(&self) -> Vec<NameCollision> {
+        self.speakers
+            .read()
+            .map(|s| find_collisions(&s))
+            .unwrap_or_default()
+    }
+
     /// Get speaker by name (sync)
     #[deprecated(since = "0.2.0", note = "renamed to `speaker()`")]
     pub fn get_speaker_by_name(&self, name: &str) -> Option<Speaker> {
@@ -398,71 +1122,700 @@ impl SonosSystem {
         }
         self.last_rediscovery.store(now, Ordering::Relaxed);
 
-        // 1. SSDP runs WITHOUT holding any lock (3s)
-        tracing::info!("speaker '{}' not found, running auto-rediscovery...", name);
-        let devices = sonos_discovery::get_with_timeout(Duration::from_secs(3));
-        if devices.is_empty() {
-            return;
-        }
+        // 1. SSDP runs WITHOUT holding any lock (3s)
+        tracing::info!("speaker '{}' not found, running auto-rediscovery...", name);
+        let devices = sonos_discovery::get_with_timeout(Duration::from_secs(3));
+        if devices.is_empty() {
+            return;
+        }
+
+        // 2. Register devices with state manager (required for property tracking)
+        let scoped: Vec<Device> = devices
+            .iter()
+            .filter(|device| self.in_household(device))
+            .cloned()
+            .collect();
+        if let Err(e) = self.state_manager.add_devices(scoped.clone()) {
+            tracing::warn!("Failed to register rediscovered devices: {}", e);
+            return;
+        }
+
+        // 3. Build new Speaker handles (no lock needed)
+        let new_speakers = match Self::build_speakers(
+            &scoped,
+            &self.state_manager,
+            &self.api_client,
+            &self.fetches,
+        ) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!("Failed to build speakers from rediscovery: {}", e);
+                return;
+            }
+        };
+
+        // 4. Acquire write lock BRIEFLY for map swap only
+        self.install_speakers(new_speakers);
+
+        // 5. Save cache (non-fatal on failure)
+        if let Err(e) = cache::save(&devices) {
+            tracing::warn!("Failed to save discovery cache: {}", e);
+        }
+    }
+
+    /// Whether a discovered device belongs to this system's household
+    pub(crate) fn in_household(&self, device: &Device) -> bool {
+        household::in_scope(device, self.household.as_deref())
+    }
+
+    /// Register a newly discovered device and add it to the name index.
+    ///
+    /// Announces the speaker with a [`Presence::EVENT_KEY`] change event.
+    pub(crate) fn register_device(&self, device: &Device) -> Result<Speaker, SdkError> {
+        self.state_manager
+            .add_devices(vec![device.clone()])
+            .map_err(SdkError::StateError)?;
+        let built = Self::build_speakers(
+            std::slice::from_ref(device),
+            &self.state_manager,
+            &self.api_client,
+            &self.fetches,
+        )?;
+        self.update_speakers(|index| {
+            for (name, list) in built {
+                let entry = index.entry(name).or_default();
+                entry.extend(list);
+                entry.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+            }
+        });
+        let speaker_id = SpeakerId::new(&device.id);
+        self.state_manager.emit_change(ChangeEvent::new(
+            speaker_id.clone(),
+            Presence::EVENT_KEY,
+            Service::ZoneGroupTopology,
+        ));
+        self.speaker_by_id(&speaker_id)
+            .ok_or_else(|| SdkError::SpeakerNotFound(speaker_id.as_str().to_string()))
+    }
+
+    /// Move a known speaker to a new address.
+    ///
+    /// Updates the state store, rebuilds the speaker handle and moves any
+    /// profile watches to the new address. Returns the new handle, or `None`
+    /// if the speaker is unknown or already at `addr`.
+    pub(crate) fn migrate_speaker(
+        &self,
+        speaker_id: &SpeakerId,
+        addr: SocketAddr,
+    ) -> Option<Speaker> {
+        let old = self.speaker_by_id(speaker_id)?;
+        if old.addr() == addr {
+            return None;
+        }
+        self.state_manager.update_speaker_addr(speaker_id, addr);
+        let speaker = Speaker::with_fetches(
+            speaker_id.clone(),
+            old.name.clone(),
+            addr,
+            old.model_name.clone(),
+            Arc::clone(&self.state_manager),
+            self.api_client.clone(),
+            Arc::clone(&self.fetches),
+        );
+        if let Ok(mut speakers) = self.speakers.write() {
+            for slot in speakers.values_mut().flatten() {
+                if slot.id == *speaker_id {
+                    *slot = speaker.clone();
+                }
+            }
+        }
+        if self.unwatch_profile(speaker_id) {
+            if let Err(e) = self.watch_profile(&speaker) {
+                tracing::warn!("re-subscribing {} at {} failed: {}", speaker_id, addr, e);
+            }
+        }
+        self.state_manager.emit_change(ChangeEvent::new(
+            speaker_id.clone(),
+            Presence::ADDRESS_EVENT_KEY,
+            Service::ZoneGroupTopology,
+        ));
+        Some(speaker)
+    }
+
+    /// Record a presence change; emits a [`Presence::EVENT_KEY`] change
+    /// event and returns `true` if it differs from the current one
+    pub(crate) fn set_presence(&self, speaker_id: &SpeakerId, presence: Presence) -> bool {
+        let Ok(mut offline) = self.offline.write() else {
+            return false;
+        };
+        let changed = match presence {
+            Presence::Online => offline.remove(speaker_id),
+            Presence::Offline => offline.insert(speaker_id.clone()),
+        };
+        drop(offline);
+        if changed {
+            self.state_manager.emit_change(ChangeEvent::new(
+                speaker_id.clone(),
+                Presence::EVENT_KEY,
+                Service::ZoneGroupTopology,
+            ));
+        }
+        changed
+    }
+
+    /// Prefetch the basic property set on one speaker within `timeout`
+    pub(crate) fn prefetch_speaker(&self, speaker: &Speaker, timeout: Duration) {
+        let failures = Self::prefetch_within(
+            std::slice::from_ref(speaker),
+            Instant::now() + timeout,
+            &ConnectOptions::default(),
+        );
+        for (speaker_id, reason) in failures {
+            tracing::warn!("{}: {}", speaker_id, reason);
+        }
+    }
+
+    /// Whether a speaker is online, as tracked by [`auto_subscribe()`](Self::auto_subscribe)
+    ///
+    /// Registered speakers count as online until a [`DeviceEvent::Lost`]
+    /// for them arrives. Returns `None` for unknown speakers.
+    pub fn presence(&self, speaker_id: &SpeakerId) -> Option<Presence> {
+        self.speaker_by_id(speaker_id)?;
+        let offline = self.offline.read().ok()?.contains(speaker_id);
+        Some(if offline {
+            Presence::Offline
+        } else {
+            Presence::Online
+        })
+    }
+
+    /// Remove a speaker from the system
+    ///
+    /// Its handle leaves the name index and its subscriptions are released,
+    /// even while watches still hold them (see
+    /// [`StateManager::remove_speaker()`]). Events still in flight from it
+    /// are dropped instead of bringing it back; a later discovery registers
+    /// it again, and watch handles still held resume. Announces the removal with a [`Presence::EVENT_KEY`] change
+    /// event. Returns `false` if the speaker is unknown.
+    pub fn remove_speaker(&self, speaker_id: &SpeakerId) -> bool {
+        self.unwatch_profile(speaker_id);
+        if !self.state_manager.remove_speaker(speaker_id) {
+            return false;
+        }
+        self.update_speakers(|index| {
+            for list in index.values_mut() {
+                list.retain(|speaker| speaker.id != *speaker_id);
+            }
+            index.retain(|_name, list| !list.is_empty());
+        });
+        if let Ok(mut offline) = self.offline.write() {
+            offline.remove(speaker_id);
+        }
+        self.state_manager.emit_change(ChangeEvent::new(
+            speaker_id.clone(),
+            Presence::EVENT_KEY,
+            Service::ZoneGroupTopology,
+        ));
+        true
+    }
+
+    /// Follow a continuous discovery source and keep speakers subscribed.
+    ///
+    /// Runs on a background thread until the returned handle is dropped or
+    /// the system goes away:
+    ///
+    /// - `Found` for an unknown speaker registers it, prefetches the basic
+    ///   property set and watches the NowPlaying profile. A known speaker
+    ///   that was offline comes back online and is refreshed.
+    /// - `Lost` marks the speaker offline and, after the configured grace
+    ///   period, drops its profile watches.
+    /// - `Updated` moves a known speaker, and its watches, to the new address.
+    ///
+    /// Presence changes and moves are announced on [`iter()`](Self::iter)
+    /// with [`Presence::EVENT_KEY`] and [`Presence::ADDRESS_EVENT_KEY`].
+    /// A speaker that keeps coming back within the flap window has its
+    /// returns held back with exponential backoff; the latest event is
+    /// applied when the backoff ends.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let system = Arc::new(SonosSystem::new()?);
+    /// let _auto = system.auto_subscribe(watcher, AutoSubscribeOptions::default());
+    /// for event in system.iter() {
+    ///     if event.property_key == Presence::EVENT_KEY {
+    ///         println!("{} is {:?}", event.speaker_id, system.presence(&event.speaker_id));
+    ///     }
+    /// }
+    /// ```
+    pub fn auto_subscribe<I>(
+        self: &Arc<Self>,
+        events: I,
+        options: AutoSubscribeOptions,
+    ) -> AutoSubscribe
+    where
+        I: IntoIterator<Item = DeviceEvent>,
+        I::IntoIter: Send + 'static,
+    {
+        auto_subscribe::spawn(
+            Arc::downgrade(self),
+            events.into_iter(),
+            options,
+            self.state_manager.clock(),
+        )
+    }
+
+    /// Get all speakers (sync)
+    pub fn speakers(&self) -> Vec<Speaker> {
+        self.speakers
+            .read()
+            .map(|s| s.values().flatten().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get speaker by ID (sync)
+    pub fn speaker_by_id(&self, speaker_id: &SpeakerId) -> Option<Speaker> {
+        let speakers = self.speakers.read().ok()?;
+        speakers
+            .values()
+            .flatten()
+            .find(|s| s.id == *speaker_id)
+            .cloned()
+    }
+
+    /// Get speaker by ID (sync)
+    #[deprecated(since = "0.2.0", note = "renamed to `speaker_by_id()`")]
+    pub fn get_speaker_by_id(&self, speaker_id: &SpeakerId) -> Option<Speaker> {
+        self.speaker_by_id(speaker_id)
+    }
+
+    /// Get all speaker names (sync)
+    ///
+    /// Names shared by several speakers are suffixed so every entry is
+    /// unique, e.g. `"Bedroom (One)"` and `"Bedroom (Play:1)"`. Each
+    /// returned name resolves to its speaker via [`speaker()`](Self::speaker).
+    pub fn speaker_names(&self) -> Vec<String> {
+        self.speakers
+            .read()
+            .map(|s| {
+                s.values()
+                    .flat_map(|list| disambiguated_names(list))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Build the SDK version and device compatibility matrix (sync)
+    ///
+    /// Lists every registered device, bonded satellites included, with its
+    /// model, firmware version, software generation and the quirks the SDK
+    /// applies to it. Firmware and generation come from topology and read
+    /// `"unknown"` / `None` until it has been fetched.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let report = sonos.compatibility_report();
+    /// println!("{report}");
+    /// let json = serde_json::to_string_pretty(&report)?;
+    /// ```
+    pub fn compatibility_report(&self) -> CompatibilityReport {
+        let mut labels: HashMap<SpeakerId, String> = self
+            .speakers
+            .read()
+            .map(|speakers| {
+                speakers
+                    .values()
+                    .filter(|list| list.len() > 1)
+                    .flat_map(|list| {
+                        list.iter()
+                            .map(|s| s.id.clone())
+                            .zip(disambiguated_names(list))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let satellite_ids = self.state_manager.get_satellite_ids();
+
+        let mut devices: Vec<DeviceCompatibility> = self
+            .state_manager
+            .speaker_infos()
+            .into_iter()
+            .map(|info| {
+                let mut quirks = Vec::new();
+                if satellite_ids.contains(&info.id) {
+                    quirks.push(Quirk::BondedSatellite {
+                        primary: self.state_manager.bond_primary(&info.id),
+                    });
+                }
+                if let Some(label) = labels.remove(&info.id) {
+                    quirks.push(Quirk::NameCollision { label });
+                }
+                if compat::closes_eventing_connections(&info.model_name) {
+                    quirks.push(Quirk::CloseEventingConnections);
+                }
+                DeviceCompatibility::new(info, quirks)
+            })
+            .collect();
+        devices.sort_by(|a, b| {
+            (&a.name, a.speaker_id.as_str()).cmp(&(&b.name, b.speaker_id.as_str()))
+        });
+
+        CompatibilityReport {
+            build: compat::build_info(),
+            devices,
+        }
+    }
+
+    /// Sleep timers, enabled alarms and autoplay settings across the
+    /// household, queried all at once
+    ///
+    /// Every group's coordinator is asked for its sleep timer, the first
+    /// coordinator for the household's alarms, and every speaker for its
+    /// line-in autoplay. A query that fails leaves its entries out and is
+    /// listed in [`ScheduleOverview::failures`]. Alarm times are evaluated
+    /// in the host's local time on the system clock.
+    ///
+    /// ```rust,ignore
+    /// let overview = system.scheduled_overview();
+    /// if let Some(alarm) = overview.next_alarm() {
+    ///     println!("next alarm {:?} in {}", alarm.next_fire, alarm.room.as_str());
+    /// }
+    /// println!("{}", serde_json::to_string(&overview)?);
+    /// ```
+    pub fn scheduled_overview(&self) -> ScheduleOverview {
+        let coordinators = self
+            .groups()
+            .into_iter()
+            .filter_map(|group| Some((group.id.clone(), group.coordinator()?)))
+            .collect();
+        let now = chrono::DateTime::<chrono::Local>::from(self.state_manager.clock().wall())
+            .naive_local();
+        schedule::gather(coordinators, self.speakers(), now)
+    }
+
+    /// Scrolling log of subscription, NOTIFY and write activity
+    ///
+    /// The first call installs the feed with [`ActivityConfig::default()`]:
+    /// a protocol observer on the event broker and a write interceptor,
+    /// which records writes as the interceptors registered before it leave
+    /// them. Nothing earlier is recorded. Later calls return the same feed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let feed = system.activity_feed();
+    /// let mut cursor = feed.cursor();
+    /// for event in system.iter() {
+    ///     if event.property_key == ActivityFeed::EVENT_KEY {
+    ///         let page = feed.since(cursor);
+    ///         cursor = page.next;
+    ///         for entry in page.entries {
+    ///             println!("{:?} {}", entry.severity, entry.summary);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn activity_feed(&self) -> ActivityFeed {
+        self.activity_feed_with(ActivityConfig::default())
+    }
+
+    /// [`activity_feed()`](Self::activity_feed) with its size and redaction
+    ///
+    /// `config` only applies to the call that installs the feed.
+    pub fn activity_feed_with(&self, config: ActivityConfig) -> ActivityFeed {
+        if let Some(feed) = self.activity.get() {
+            if feed.config() != config {
+                tracing::debug!("Activity feed already installed; ignoring {config:?}");
+            }
+            return feed.clone();
+        }
+        self.activity
+            .get_or_init(|| {
+                let feed = ActivityFeed::new(config);
+                feed.attach(&self.state_manager);
+                self.state_manager
+                    .add_write_interceptor(activity::write_recorder(feed.clone()));
+                feed
+            })
+            .clone()
+    }
+
+    /// Started, qualified and abandoned tracks of every group, for
+    /// scrobbling
+    ///
+    /// The first call installs the tracker with [`ListeningRules::default()`];
+    /// each call returns a receiver of the events from then on, also
+    /// announced on [`iter()`](Self::iter) with
+    /// `property_key == ListeningEvent::EVENT_KEY`. The tracker follows the
+    /// playback state, track and position of coordinators whose
+    /// `playback_state`, `current_track` and `position` are watched.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let den = system.speaker("Den").unwrap();
+    /// let _watches = (
+    ///     den.playback_state.watch()?,
+    ///     den.current_track.watch()?,
+    ///     den.position.watch()?,
+    /// );
+    /// for event in system.listening_events() {
+    ///     if let ListeningEvent::TrackQualified { track, .. } = event {
+    ///         println!("scrobble {}", track.display());
+    ///     }
+    /// }
+    /// ```
+    pub fn listening_events(&self) -> ListeningEvents {
+        self.state_manager.listening_events()
+    }
+
+    /// [`listening_events()`](Self::listening_events) with the rules a track
+    /// qualifies by
+    ///
+    /// `rules` only apply to the call that installs the tracker.
+    pub fn listening_events_with(&self, rules: ListeningRules) -> ListeningEvents {
+        self.state_manager.listening_events_with(rules)
+    }
+
+    /// Get the state manager for advanced usage
+    pub fn state_manager(&self) -> &Arc<StateManager> {
+        &self.state_manager
+    }
+
+    /// How group commands were routed to coordinators: waits for a
+    /// reshaped group to settle, topology refreshes and reroutes after a
+    /// speaker refused a command as no longer coordinating
+    ///
+    /// The settle delay is set with
+    /// `state_manager().coordinator_resolver().set_settle_delay()`.
+    pub fn routing_stats(&self) -> RoutingStats {
+        self.state_manager.coordinator_resolver().stats()
+    }
+
+    /// Report a synthetic change as if a speaker had sent it
+    ///
+    /// For building and testing UIs without speakers: the change becomes
+    /// the event a device would send and goes through the same decoding as
+    /// live events, so handles, groups and [`iter()`](Self::iter) see it as
+    /// they would on a real system. Pair with `with_speakers()` (requires
+    /// the `test-support` feature); see [`simulation`](crate::simulation)
+    /// for the scenario builders and scripted playback. Returns `false` if
+    /// the event was skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let system = SonosSystem::with_speakers(&["Kitchen", "Den"]);
+    /// let kitchen = system.speaker("Kitchen").unwrap();
+    /// for change in SimulatedChange::volume_sweep(&kitchen.id, 10, 30) {
+    ///     system.simulate(change)?;
+    /// }
+    /// ```
+    pub fn simulate(&self, change: SimulatedChange) -> Result<bool, SdkError> {
+        self.state_manager
+            .simulate(&change)
+            .map_err(SdkError::StateError)
+    }
+
+    /// Suspend all Sonos activity, e.g. when the machine sleeps or the app
+    /// is backgrounded.
+    ///
+    /// Renewals and polling stop; with [`SuspendPolicy::Unsubscribe`] every
+    /// subscription is cancelled too. Watches stay in place. Idempotent and
+    /// safe to call from any thread; returns `false` if already suspended.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // OS sleep hook
+    /// system.suspend(SuspendPolicy::Unsubscribe)?;
+    /// // OS wake hook
+    /// system.resume()?;
+    /// ```
+    pub fn suspend(&self, policy: SuspendPolicy) -> Result<bool, SdkError> {
+        self.state_manager
+            .suspend(policy)
+            .map_err(SdkError::StateError)
+    }
+
+    /// Resume after [`suspend()`](Self::suspend) and refresh all state.
+    ///
+    /// Re-subscribes every watched service, re-fetches topology plus volume,
+    /// mute, playback state and current track on every speaker, then emits a
+    /// single [`ChangeEvent`](sonos_state::ChangeEvent) with
+    /// [`RerenderScope::Full`](sonos_state::RerenderScope::Full) on
+    /// [`iter()`](Self::iter). Speakers that do not answer within 5s keep
+    /// their last known values. Returns `false` if not suspended.
+    pub fn resume(&self) -> Result<bool, SdkError> {
+        self.state_manager
+            .resume_with(|| {
+                // Values fetched just before sleeping are not fresh
+                self.fetches.forget_recent();
+                let deadline = Instant::now() + RESUME_REFRESH_TIMEOUT;
+                let topology = self.spawn_topology_fetch();
+                let failures =
+                    Self::prefetch_within(&self.speakers(), deadline, &ConnectOptions::default());
+                self.await_topology(topology, deadline);
+                for (speaker_id, reason) in failures {
+                    tracing::warn!("resume: {} not refreshed: {}", speaker_id, reason);
+                }
+            })
+            .map_err(SdkError::StateError)
+    }
+
+    /// Make the store match the speakers, e.g. after a reconnect or a gap
+    /// in events
+    ///
+    /// Fetches the topology once per household, then reads volume, mute,
+    /// EQ, and on coordinators playback state, position and track from
+    /// every speaker, [`DEFAULT_REFRESH_PARALLELISM`](crate::DEFAULT_REFRESH_PARALLELISM)
+    /// speakers at a time. Each speaker's values are stored together:
+    /// watchers see at most one event per speaker, and none for values that
+    /// already matched. A failed read leaves its values as they were and is
+    /// listed in the report with the corrections.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let report = system.refresh_all();
+    /// if report.corrections() > 0 {
+    ///     tracing::warn!("store was stale: {:?}", report);
+    /// }
+    /// ```
+    pub fn refresh_all(&self) -> RefreshReport {
+        self.refresh_all_with(refresh::DEFAULT_REFRESH_PARALLELISM)
+    }
+
+    /// [`refresh_all()`](Self::refresh_all), reading up to `parallelism`
+    /// speakers at a time
+    pub fn refresh_all_with(&self, parallelism: usize) -> RefreshReport {
+        let topology = self.fetch_topology();
+        let speakers = refresh::refresh_speakers(
+            &self.api_client,
+            &self.state_manager,
+            &self.speakers(),
+            parallelism,
+        );
+        RefreshReport { topology, speakers }
+    }
 
-        // 2. Register devices with state manager (required for property tracking)
-        if let Err(e) = self.state_manager.add_devices(devices.clone()) {
-            tracing::warn!("Failed to register rediscovered devices: {}", e);
-            return;
-        }
+    /// Shut down without losing events the speakers already sent
+    ///
+    /// Cancels every subscription, decodes the events received up to that
+    /// point into the store, flushes persistence sinks, then stops the
+    /// background threads. Decoding is cut off at `timeout`; the report says
+    /// how many events were drained and how many abandoned. Reads and control
+    /// commands keep working; later watches fall back to polling.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// system.group("Living Room").unwrap().pause()?;
+    /// let report = system.shutdown(Duration::from_secs(2))?;
+    /// assert_eq!(report.events_abandoned, 0);
+    /// ```
+    pub fn shutdown(&self, timeout: Duration) -> Result<ShutdownReport, SdkError> {
+        let report = self
+            .state_manager
+            .shutdown(timeout)
+            .map_err(SdkError::StateError)?;
+        // Released after the drain, so their grace periods have nothing to do
+        let held = self
+            .profile_watches
+            .lock()
+            .map(|mut watches| std::mem::take(&mut *watches));
+        drop(held);
+        Ok(report)
+    }
 
-        // 3. Build new Speaker handles (no lock needed)
-        let new_speakers =
-            match Self::build_speakers(&devices, &self.state_manager, &self.api_client) {
-                Ok(s) => s,
-                Err(e) => {
-                    tracing::warn!("Failed to build speakers from rediscovery: {}", e);
-                    return;
-                }
-            };
+    /// Get the album art for a track, downloading it on a cache miss
+    ///
+    /// Art is cached in memory under a normalized key, so the same cover
+    /// served by different speakers or with a fresh token is fetched once.
+    /// Relative `/getaa` URIs are resolved against a registered speaker.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if let Some(track) = speaker.current_track.get() {
+    ///     let art = system.album_art(&track)?;
+    ///     println!("{:?} {:?}", art.content_type(), art.dimensions());
+    /// }
+    /// ```
+    pub fn album_art(&self, track: &CurrentTrack) -> Result<ArtHandle, SdkError> {
+        let speaker_addr = self
+            .state_manager
+            .speaker_infos()
+            .first()
+            .map(|info| info.socket_addr())
+            .ok_or_else(|| SdkError::FetchFailed("no speakers registered".to_string()))?;
+        self.art.get_for_track(track, speaker_addr)
+    }
 
-        // 4. Acquire write lock BRIEFLY for map swap only
-        if let Ok(mut map) = self.speakers.write() {
-            *map = new_speakers;
-        }
+    /// List the household's Sonos Favorites, read from any registered
+    /// speaker
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let favorites = system.favorites()?;
+    /// if let Some(radio) = favorites.iter().find(|f| f.title == "BBC Radio 4") {
+    ///     system.speaker("Kitchen").unwrap().play_favorite(radio)?;
+    /// }
+    /// ```
+    pub fn favorites(&self) -> Result<Vec<Favorite>, SdkError> {
+        self.any_speaker()?.favorites()
+    }
 
-        // 5. Save cache (non-fatal on failure)
-        if let Err(e) = cache::save(&devices) {
-            tracing::warn!("Failed to save discovery cache: {}", e);
-        }
+    /// List the household's Sonos playlists, read from any registered
+    /// speaker
+    pub fn playlists(&self) -> Result<Vec<Playlist>, SdkError> {
+        self.any_speaker()?.playlists()
     }
 
-    /// Get all speakers (sync)
-    pub fn speakers(&self) -> Vec<Speaker> {
-        self.speakers
-            .read()
-            .map(|s| s.values().cloned().collect())
-            .unwrap_or_default()
+    /// A registered speaker to ask household-wide questions
+    fn any_speaker(&self) -> Result<Speaker, SdkError> {
+        self.speakers()
+            .into_iter()
+            .next()
+            .ok_or_else(|| SdkError::FetchFailed("no speakers registered".to_string()))
     }
 
-    /// Get speaker by ID (sync)
-    pub fn speaker_by_id(&self, speaker_id: &SpeakerId) -> Option<Speaker> {
-        let speakers = self.speakers.read().ok()?;
-        speakers.values().find(|s| s.id == *speaker_id).cloned()
+    /// Get the album art cache, e.g. to set its size or enable prefetching
+    pub fn album_art_cache(&self) -> &ArtCache {
+        &self.art
     }
 
-    /// Get speaker by ID (sync)
-    #[deprecated(since = "0.2.0", note = "renamed to `speaker_by_id()`")]
-    pub fn get_speaker_by_id(&self, speaker_id: &SpeakerId) -> Option<Speaker> {
-        self.speaker_by_id(speaker_id)
+    /// Get the fetch coalescer, e.g. to tune its window or read its counters
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// system.fetch_coalescer().set_window(Duration::from_millis(500));
+    /// let stats = system.fetch_coalescer().stats();
+    /// println!("{} of {} fetches saved a request", stats.saved(), stats.fetches);
+    /// ```
+    pub fn fetch_coalescer(&self) -> &FetchCoalescer {
+        &self.fetches
     }
 
-    /// Get all speaker names (sync)
-    pub fn speaker_names(&self) -> Vec<String> {
-        self.speakers
-            .read()
-            .map(|s| s.keys().cloned().collect())
-            .unwrap_or_default()
+    /// Households seen when the system was built, largest first
+    ///
+    /// Lists every household on the network, including ones this system is
+    /// not scoped to, for selection UIs. Pass an ID to
+    /// [`with_household()`](Self::with_household) or
+    /// [`ConnectOptions::household()`] to switch.
+    pub fn households(&self) -> &[Household] {
+        &self.households
     }
 
-    /// Get the state manager for advanced usage
-    pub fn state_manager(&self) -> &Arc<StateManager> {
-        &self.state_manager
+    /// ID of the household this system manages
+    ///
+    /// `None` when built with [`HouseholdSelection::All`] or when no device
+    /// reported a household.
+    pub fn household_id(&self) -> Option<&str> {
+        self.household.as_deref()
     }
 
     /// Get a blocking iterator over property change events
@@ -485,64 +1838,198 @@ impl SonosSystem {
         self.state_manager.iter()
     }
 
+    // ========================================================================
+    // Middleware
+    // ========================================================================
+
+    /// Register a callback consulted before every write a speaker or group
+    /// handle sends
+    ///
+    /// Interceptors run in registration order on the calling thread. A
+    /// `Deny` fails the call with [`SdkError::WriteDenied`] without sending
+    /// anything; a `Modify` sends the rewritten arguments, and the state
+    /// cache then waits for the speaker's event instead of caching the
+    /// requested value.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use sonos_sdk::{InterceptDecision, WriteRequest};
+    ///
+    /// // Parental control: never louder than 60
+    /// system.add_write_interceptor(|request: &WriteRequest| {
+    ///     match request.arg("DesiredVolume").and_then(|v| v.parse::<u8>().ok()) {
+    ///         Some(v) if v > 60 => InterceptDecision::Modify(request.clone().with_arg("DesiredVolume", "60")),
+    ///         _ => InterceptDecision::Allow,
+    ///     }
+    /// });
+    /// ```
+    pub fn add_write_interceptor(
+        &self,
+        interceptor: impl Fn(&WriteRequest) -> InterceptDecision + Send + Sync + 'static,
+    ) {
+        self.state_manager
+            .add_write_interceptor(Arc::new(interceptor));
+    }
+
+    /// Register a callback that rewrites or drops change events before
+    /// [`iter()`](Self::iter) and change observers see them
+    ///
+    /// Middleware runs in registration order on the thread that produced
+    /// the change; returning `None` drops the event.
+    pub fn add_change_middleware(
+        &self,
+        middleware: impl Fn(ChangeEvent) -> Option<ChangeEvent> + Send + Sync + 'static,
+    ) {
+        self.state_manager
+            .add_change_middleware(Arc::new(middleware));
+    }
+
+    /// Limit how fast speaker and group handles send writes; `None` lifts
+    /// the limit
+    ///
+    /// Reads and subscriptions aren't limited. The limit applies after the
+    /// write interceptors, so denied writes take no token, and starts with
+    /// full buckets. Writes waiting under the old limit keep their place.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use std::time::Duration;
+    /// use sonos_sdk::{ExceedPolicy, RateLimit, WriteRateLimit};
+    ///
+    /// // 5 writes a second per speaker, bursts of 10; volume drags send
+    /// // only their latest value
+    /// system.set_write_rate_limit(Some(
+    ///     WriteRateLimit::new(ExceedPolicy::Coalesce { max_wait: Duration::from_secs(2) })
+    ///         .per_device(RateLimit::new(5.0, 10)),
+    /// ));
+    /// ```
+    pub fn set_write_rate_limit(&self, limit: Option<WriteRateLimit>) {
+        self.api_client.write_limiter().set_limit(limit);
+    }
+
+    /// Tokens left in the write rate limit's buckets
+    pub fn write_token_levels(&self) -> TokenLevels {
+        self.api_client.write_limiter().levels()
+    }
+
     // ========================================================================
     // Topology Fetch
     // ========================================================================
 
     /// Ensure group topology has been fetched.
     ///
-    /// Tries all known speaker IPs sequentially until one responds with topology.
-    /// Topology data is identical from any speaker, so first success wins.
-    /// Also refreshes speaker IPs and records satellite IDs from the topology.
+    /// Tries all known speaker addresses sequentially until one responds with topology.
+    /// Topology data is identical from any speaker of a household, so first
+    /// success wins; a system spanning several households asks one speaker
+    /// of each. Also refreshes speaker addresses and records satellite IDs
+    /// from the topology.
     fn ensure_topology(&self) {
-        if self.state_manager.group_count() > 0 {
-            return;
+        if self.state_manager.group_count() == 0 {
+            self.fetch_topology();
         }
+    }
 
-        let speaker_ips: Vec<String> = {
+    /// Fetch and apply the topology of each household from the first of its
+    /// speakers that responds; `false` if some household got none
+    fn fetch_topology(&self) -> bool {
+        let speaker_addrs: Vec<String> = {
             let speakers = match self.speakers.read() {
                 Ok(s) => s,
-                Err(_) => return,
+                Err(_) => return false,
             };
-            speakers.values().map(|s| s.ip.to_string()).collect()
+            speakers
+                .values()
+                .flatten()
+                .map(|s| s.addr().to_string())
+                .collect()
         };
+        let households = self.households_by_addr();
+        let mut pending: HashSet<Option<String>> = households.values().cloned().collect();
 
-        for speaker_ip in &speaker_ips {
-            let topology_state = match sonos_api::services::zone_group_topology::state::poll(
+        for speaker_addr in &speaker_addrs {
+            let household = households.get(speaker_addr).cloned().flatten();
+            if !pending.contains(&household) {
+                continue;
+            }
+            match sonos_api::services::zone_group_topology::state::poll(
                 &self.api_client,
-                speaker_ip,
+                speaker_addr,
             ) {
-                Ok(state) => state,
+                Ok(state) => {
+                    self.apply_topology(&state);
+                    pending.remove(&household);
+                    if pending.is_empty() {
+                        return true;
+                    }
+                }
                 Err(e) => {
-                    tracing::debug!("Topology fetch failed for {}: {}", speaker_ip, e);
-                    continue;
+                    tracing::debug!("Topology fetch failed for {}: {}", speaker_addr, e);
                 }
-            };
-
-            let topology_changes = sonos_state::decode_topology_event(&topology_state);
-
-            // Apply IP updates from topology before initializing groups
-            for (speaker_id, new_ip) in &topology_changes.speaker_ips {
-                self.state_manager.update_speaker_ip(speaker_id, *new_ip);
             }
+        }
+
+        tracing::warn!("fetch_topology: no speakers responded");
+        false
+    }
 
-            // Build topology with existing speaker data and freshly fetched groups
-            let topology =
-                Topology::new(self.state_manager.speaker_infos(), topology_changes.groups);
-            self.state_manager.initialize(topology);
+    /// Initialize groups, memberships, speaker addresses, firmware versions, satellite IDs
+    /// and bonds from a topology snapshot.
+    fn apply_topology(&self, topology_state: &ZoneGroupTopologyState) {
+        let topology_changes = sonos_state::decode_topology_event(topology_state);
+
+        // Households this snapshot speaks for
+        let infos = self.state_manager.speaker_infos();
+        let household_of = |id: &SpeakerId| {
+            infos
+                .iter()
+                .find(|info| &info.id == id)
+                .and_then(|info| info.household_id.clone())
+        };
+        let reported: HashSet<Option<String>> = topology_changes
+            .memberships
+            .iter()
+            .map(|(id, _)| household_of(id))
+            .collect();
 
-            // Store satellite IDs for later filtering
+        // Apply address updates from topology before initializing groups
+        for (speaker_id, new_addr) in &topology_changes.speaker_addrs {
             self.state_manager
-                .set_satellite_ids(topology_changes.satellite_ids);
+                .update_speaker_addr(speaker_id, *new_addr);
+        }
 
-            tracing::debug!(
-                "Fetched zone group topology on-demand ({} groups)",
-                self.state_manager.group_count()
-            );
-            return;
+        // Build topology with existing speaker data and freshly fetched groups
+        let topology = Topology::new(self.state_manager.speaker_infos(), topology_changes.groups);
+        self.state_manager.initialize(topology);
+        // So `group_membership.get()` answers without waiting for a
+        // ZoneGroupTopology event
+        for (speaker_id, membership) in topology_changes.memberships {
+            self.state_manager.set_property(&speaker_id, membership);
+        }
+
+        // Store satellite IDs for later filtering, and which primary each is
+        // bonded to. Bonds of other households are not in this snapshot; keep them.
+        let mut satellite_ids = topology_changes.satellite_ids;
+        let mut bonds = topology_changes.bonds;
+        for info in &infos {
+            let household = info.household_id.clone();
+            if info.satellites.is_empty() || household.is_none() || reported.contains(&household) {
+                continue;
+            }
+            satellite_ids.extend(info.satellites.iter().cloned());
+            bonds.push((info.id.clone(), info.satellites.clone()));
         }
+        self.state_manager.set_satellite_ids(satellite_ids);
+        self.state_manager.set_bonds(bonds);
+        self.state_manager
+            .set_software_versions(topology_changes.software);
+        self.state_manager.set_boot_seqs(topology_changes.boot_seqs);
 
-        tracing::warn!("ensure_topology: no speakers responded");
+        tracing::debug!(
+            "Fetched zone group topology on-demand ({} groups)",
+            self.state_manager.group_count()
+        );
     }
 
     // ========================================================================
@@ -574,6 +2061,7 @@ impl SonosSystem {
                     info,
                     Arc::clone(&self.state_manager),
                     self.api_client.clone(),
+                    Arc::clone(&self.fetches),
                 )
             })
             .collect()
@@ -597,6 +2085,7 @@ impl SonosSystem {
             info,
             Arc::clone(&self.state_manager),
             self.api_client.clone(),
+            Arc::clone(&self.fetches),
         )
     }
 
@@ -629,6 +2118,7 @@ impl SonosSystem {
             info,
             Arc::clone(&self.state_manager),
             self.api_client.clone(),
+            Arc::clone(&self.fetches),
         )
     }
 
@@ -671,6 +2161,7 @@ impl SonosSystem {
                     info,
                     Arc::clone(&self.state_manager),
                     self.api_client.clone(),
+                    Arc::clone(&self.fetches),
                 )
             })
     }
@@ -722,11 +2213,55 @@ impl SonosSystem {
 
         Ok(crate::group::GroupChangeResult { succeeded, failed })
     }
+
+    /// Move `speaker` into the group `with` is in
+    ///
+    /// The group's coordinator is looked up in the current [`Topology`], so
+    /// `with` may be any member. `speaker` is sent `SetAVTransportURI` with
+    /// `x-rincon:{coordinator}` and leaves the group it was in, if any;
+    /// [`Speaker::leave_group()`] undoes it. The checks are those of
+    /// [`Group::add_speaker()`], and a speaker already in the group fails
+    /// with [`SdkError::AlreadyInGroup`] without sending anything.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let kitchen = system.speaker("Kitchen").unwrap();
+    /// let living_room = system.speaker("Living Room").unwrap();
+    /// system.group_with(&kitchen, &living_room)?;
+    /// ```
+    pub fn group_with(&self, speaker: &Speaker, with: &Speaker) -> Result<(), SdkError> {
+        self.ensure_topology();
+        let topology = self
+            .state_manager
+            .get_system_property::<Topology>()
+            .unwrap_or_else(Topology::empty);
+        let info = topology
+            .groups
+            .into_iter()
+            .find(|group| group.member_ids.contains(&with.id))
+            .ok_or_else(|| SdkError::SpeakerNotFound(with.id.as_str().to_string()))?;
+        if info.member_ids.contains(&speaker.id) && info.coordinator_id != speaker.id {
+            return Err(SdkError::AlreadyInGroup {
+                speaker_id: speaker.id.clone(),
+                group_id: info.id,
+            });
+        }
+        let group = Group::from_info(
+            info,
+            Arc::clone(&self.state_manager),
+            self.api_client.clone(),
+            Arc::clone(&self.fetches),
+        )
+        .ok_or_else(|| SdkError::SpeakerNotFound(with.id.as_str().to_string()))?;
+        group.add_speaker(speaker)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::BuildInfo;
     use sonos_state::GroupInfo;
 
     /// Create a test SonosSystem with the given devices
@@ -748,6 +2283,8 @@ mod tests {
                 ip_address: "192.168.1.100".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
             Device {
                 id: "RINCON_222".to_string(),
@@ -756,6 +2293,8 @@ mod tests {
                 ip_address: "192.168.1.101".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
         ];
 
@@ -796,6 +2335,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = create_test_system(devices).unwrap();
@@ -814,6 +2355,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = create_test_system(devices).unwrap();
@@ -844,6 +2387,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = create_test_system(devices).unwrap();
@@ -864,6 +2409,8 @@ mod tests {
                 ip_address: "192.168.1.100".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
             Device {
                 id: "RINCON_222".to_string(),
@@ -872,6 +2419,8 @@ mod tests {
                 ip_address: "192.168.1.101".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
         ];
 
@@ -912,6 +2461,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = create_test_system(devices).unwrap();
@@ -931,6 +2482,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = create_test_system(devices).unwrap();
@@ -979,6 +2532,8 @@ mod tests {
                 ip_address: "192.168.1.100".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
             Device {
                 id: "RINCON_222".to_string(),
@@ -987,6 +2542,8 @@ mod tests {
                 ip_address: "192.168.1.101".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
         ];
 
@@ -1034,6 +2591,8 @@ mod tests {
                 ip_address: "192.168.1.100".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
             Device {
                 id: "RINCON_222".to_string(),
@@ -1042,6 +2601,8 @@ mod tests {
                 ip_address: "192.168.1.101".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
         ];
 
@@ -1074,6 +2635,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         };
         assert_eq!(display_name(&device), "Kitchen");
     }
@@ -1087,6 +2650,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         };
         assert_eq!(
             display_name(&device),
@@ -1100,10 +2665,92 @@ mod tests {
             ip_address: "192.168.1.101".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         };
         assert_eq!(display_name(&device_empty), "192.168.1.101 - Sonos One");
     }
 
+    #[test]
+    fn test_secure_devices_get_https_unless_configured() {
+        let device = |id: &str, ip: &str, port: u16, secure: bool| Device {
+            id: id.to_string(),
+            name: id.to_string(),
+            room_name: id.to_string(),
+            ip_address: ip.to_string(),
+            port,
+            model_name: "Era 100".to_string(),
+            household_id: None,
+            secure,
+        };
+        let api_client = SonosClient::new();
+        let pinned = DeviceTransport::https().pinned(sonos_api::CertFingerprint::of_der(b"cert"));
+        api_client.set_device_transport("192.0.2.11", pinned.clone());
+
+        secure_transports(
+            &[
+                device("RINCON_A", "192.0.2.10", 1443, true),
+                device("RINCON_B", "192.0.2.11", 1443, true),
+                device("RINCON_C", "192.0.2.12", 1400, false),
+            ],
+            &api_client,
+        );
+        assert_eq!(
+            api_client.device_transport("192.0.2.10"),
+            Some(DeviceTransport::https())
+        );
+        assert_eq!(api_client.device_transport("192.0.2.11"), Some(pinned));
+        assert_eq!(api_client.device_transport("192.0.2.12"), None);
+    }
+
+    #[test]
+    fn test_old_zoneplayers_close_eventing_connections() {
+        let device = |ip: &str, model: &str, secure: bool| Device {
+            id: format!("RINCON_{ip}"),
+            name: model.to_string(),
+            room_name: model.to_string(),
+            ip_address: ip.to_string(),
+            port: 1400,
+            model_name: model.to_string(),
+            household_id: None,
+            secure,
+        };
+        let api_client = SonosClient::new().with_eventing_connection(EventingConnection::KeepAlive);
+        let kept = DeviceTransport::http().with_eventing_connection(EventingConnection::KeepAlive);
+        api_client.set_device_transport("192.0.2.22", kept.clone());
+
+        let devices = [
+            device("192.0.2.20", "Sonos ZP90", false),
+            device("192.0.2.21", "zp120", true),
+            device("192.0.2.22", "Sonos ZP100", false),
+            device("192.0.2.23", "Sonos One", false),
+        ];
+        secure_transports(&devices, &api_client);
+        eventing_transports(&devices, &api_client);
+
+        let close = |transport: DeviceTransport| {
+            Some(transport.with_eventing_connection(EventingConnection::Close))
+        };
+        assert_eq!(
+            api_client.device_transport("192.0.2.20"),
+            close(DeviceTransport::http())
+        );
+        assert_eq!(
+            api_client.device_transport("192.0.2.21"),
+            close(DeviceTransport::https().with_port(1400))
+        );
+        assert_eq!(api_client.device_transport("192.0.2.22"), Some(kept));
+        assert_eq!(api_client.device_transport("192.0.2.23"), None);
+        assert_eq!(
+            api_client.eventing_connection("192.0.2.20"),
+            EventingConnection::Close
+        );
+        assert_eq!(
+            api_client.eventing_connection("192.0.2.23"),
+            EventingConnection::KeepAlive
+        );
+    }
+
     #[test]
     fn test_speaker_lookup_case_insensitive() {
         let devices = vec![Device {
@@ -1113,6 +2760,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         let system = create_test_system(devices).unwrap();
         assert!(system.speaker("Kitchen").is_some());
@@ -1130,6 +2779,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = create_test_system(devices).unwrap();
@@ -1152,6 +2803,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = create_test_system(devices).unwrap();
@@ -1171,4 +2824,247 @@ mod tests {
         assert!(system.group("LIVING ROOM").is_some());
         assert!(system.group("Nonexistent").is_none());
     }
+
+    fn bedroom_device(id: &str, model_name: &str, ip: &str) -> Device {
+        Device {
+            id: id.to_string(),
+            name: "Bedroom".to_string(),
+            room_name: "Bedroom".to_string(),
+            ip_address: ip.to_string(),
+            port: 1400,
+            model_name: model_name.to_string(),
+            household_id: None,
+            secure: false,
+        }
+    }
+
+    fn duplicate_bedrooms() -> Vec<Device> {
+        vec![
+            bedroom_device("RINCON_BBB2", "Sonos Play:1", "192.168.1.101"),
+            bedroom_device("RINCON_AAA1", "Sonos One", "192.168.1.100"),
+        ]
+    }
+
+    #[test]
+    fn test_duplicate_names_are_all_registered() {
+        let system = SonosSystem::from_devices_offline(duplicate_bedrooms());
+
+        assert_eq!(system.speakers().len(), 2);
+        let matches = system.speakers_by_name("bedroom");
+        let ids: Vec<_> = matches.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["RINCON_AAA1", "RINCON_BBB2"]);
+
+        // Ambiguous lookup deterministically picks the lowest ID
+        assert_eq!(
+            system.speaker("Bedroom").unwrap().id.as_str(),
+            "RINCON_AAA1"
+        );
+
+        assert_eq!(
+            system.name_collisions(),
+            vec![NameCollision {
+                name: "Bedroom".to_string(),
+                speaker_ids: vec![SpeakerId::new("RINCON_AAA1"), SpeakerId::new("RINCON_BBB2")],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_names_are_suffixed() {
+        let system = SonosSystem::from_devices_offline(duplicate_bedrooms());
+
+        let mut names = system.speaker_names();
+        names.sort();
+        assert_eq!(names, vec!["Bedroom (One)", "Bedroom (Play:1)"]);
+        assert_eq!(
+            system.speaker("Bedroom (Play:1)").unwrap().id.as_str(),
+            "RINCON_BBB2"
+        );
+
+        // Same model falls back to the last four characters of the ID
+        let system = SonosSystem::from_devices_offline(vec![
+            bedroom_device("RINCON_AAA1", "Sonos One", "192.168.1.100"),
+            bedroom_device("RINCON_BBB2", "Sonos One", "192.168.1.101"),
+        ]);
+        let mut names = system.speaker_names();
+        names.sort();
+        assert_eq!(names, vec!["Bedroom (AAA1)", "Bedroom (BBB2)"]);
+    }
+
+    #[test]
+    fn test_id_suffix_is_taken_by_characters() {
+        // The last four bytes of the ID split a three-byte character
+        let system = SonosSystem::from_devices_offline(vec![
+            bedroom_device("RINCON_1€€€", "Sonos One", "192.168.1.100"),
+            bedroom_device("RINCON_AAA1", "Sonos One", "192.168.1.101"),
+        ]);
+        let mut names = system.speaker_names();
+        names.sort();
+        assert_eq!(names, vec!["Bedroom (1€€€)", "Bedroom (AAA1)"]);
+    }
+
+    #[test]
+    fn test_name_collision_emits_change_events() {
+        let system = SonosSystem::from_devices_offline(duplicate_bedrooms());
+
+        let events: Vec<_> = system.iter().try_iter().collect();
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|e| e.property_key == NameCollision::EVENT_KEY));
+    }
+
+    #[test]
+    fn test_rename_clears_name_collision() {
+        let system = SonosSystem::from_devices_offline(duplicate_bedrooms());
+        let _ = system.iter().try_iter().count();
+
+        // The speaker reports the new name itself, as a ZoneName event
+        let renamed = system
+            .simulate(SimulatedChange::RoomName {
+                speaker: SpeakerId::new("RINCON_BBB2"),
+                name: "Guest Room".to_string(),
+            })
+            .unwrap();
+        assert!(renamed);
+
+        assert!(system.name_collisions().is_empty());
+        assert_eq!(
+            system.speaker("Bedroom").unwrap().id.as_str(),
+            "RINCON_AAA1"
+        );
+        assert_eq!(
+            system.speaker("Guest Room").unwrap().id.as_str(),
+            "RINCON_BBB2"
+        );
+
+        // Both speakers left the collision, so both get a change event
+        let events: Vec<_> = system.iter().try_iter().collect();
+        let mut collisions: Vec<_> = events
+            .iter()
+            .filter(|event| event.property_key == NameCollision::EVENT_KEY)
+            .map(|event| event.speaker_id.as_str())
+            .collect();
+        collisions.sort_unstable();
+        assert_eq!(collisions, ["RINCON_AAA1", "RINCON_BBB2"]);
+        assert!(events
+            .iter()
+            .any(|event| event.property_key == SpeakerInfo::RENAME_EVENT_KEY));
+    }
+
+    #[test]
+    fn test_concurrent_registrations_keep_every_speaker() {
+        let system = SonosSystem::from_devices_offline(duplicate_bedrooms());
+        let devices: Vec<Device> = (0..16)
+            .map(|i| Device {
+                id: format!("RINCON_NEW{i:02}"),
+                name: format!("Room {i}"),
+                room_name: format!("Room {i}"),
+                ip_address: format!("192.168.1.{}", 110 + i),
+                port: 1400,
+                model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
+            })
+            .collect();
+
+        std::thread::scope(|scope| {
+            for device in &devices {
+                scope.spawn(|| system.register_device(device).unwrap());
+            }
+            scope.spawn(|| system.remove_speaker(&SpeakerId::new("RINCON_BBB2")));
+        });
+
+        assert_eq!(system.speakers().len(), 17);
+        for device in &devices {
+            assert!(system.speaker(&device.room_name).is_some(), "{}", device.id);
+        }
+        assert!(system.name_collisions().is_empty());
+    }
+
+    #[test]
+    fn test_compatibility_report_covers_every_device_and_quirk() {
+        let mut devices = duplicate_bedrooms();
+        devices.push(Device {
+            id: "RINCON_ARC".to_string(),
+            name: "Living Room".to_string(),
+            room_name: "Living Room".to_string(),
+            ip_address: "192.168.1.102".to_string(),
+            port: 1400,
+            model_name: "Sonos Arc".to_string(),
+            household_id: None,
+            secure: false,
+        });
+        devices.push(Device {
+            id: "RINCON_SUB".to_string(),
+            name: "Sub".to_string(),
+            room_name: "Sub".to_string(),
+            ip_address: "192.168.1.103".to_string(),
+            port: 1400,
+            model_name: "Sonos Sub".to_string(),
+            household_id: None,
+            secure: false,
+        });
+        let system = SonosSystem::from_devices_offline(devices);
+        let (arc, sub) = (SpeakerId::new("RINCON_ARC"), SpeakerId::new("RINCON_SUB"));
+        system.state_manager.set_satellite_ids(vec![sub.clone()]);
+        system
+            .state_manager
+            .set_bonds(vec![(arc.clone(), vec![sub])]);
+        system.state_manager.set_software_versions(vec![
+            (arc, "85.0-64200".to_string(), Some(2)),
+            (
+                SpeakerId::new("RINCON_AAA1"),
+                "57.3-77280".to_string(),
+                Some(1),
+            ),
+        ]);
+
+        let mut report = system.compatibility_report();
+        assert_eq!(report.build, compat::build_info());
+        report.build = BuildInfo {
+            sdk_version: "1.2.3",
+            crates: vec![],
+        };
+
+        assert_eq!(
+            report.to_string(),
+            "sonos-sdk 1.2.3\n\
+             \n\
+             NAME         MODEL         FIRMWARE    GEN  ID           QUIRKS\n\
+             Bedroom      Sonos One     57.3-77280  S1   RINCON_AAA1  shared name, use \"Bedroom (One)\"\n\
+             Bedroom      Sonos Play:1  unknown     ?    RINCON_BBB2  shared name, use \"Bedroom (Play:1)\"\n\
+             Living Room  Sonos Arc     85.0-64200  S2   RINCON_ARC   -\n\
+             Sub          Sonos Sub     unknown     ?    RINCON_SUB   satellite of RINCON_ARC\n"
+        );
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn test_simulate_drives_handles_and_groups() {
+        let system = SonosSystem::with_groups(&["Kitchen", "Den"]);
+        let kitchen = system.speaker("Kitchen").unwrap();
+        let den = system.speaker("Den").unwrap();
+
+        for change in SimulatedChange::volume_sweep(&kitchen.id, 10, 14) {
+            assert!(system.simulate(change).unwrap());
+        }
+        assert_eq!(kitchen.volume.get(), Some(crate::Volume(14)));
+
+        system
+            .simulate(SimulatedChange::group_formation(
+                &kitchen.id,
+                [den.id.clone()],
+            ))
+            .unwrap();
+        let groups = system.groups();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].members().len(), 2);
+
+        let unknown = SimulatedChange::Volume {
+            speaker: SpeakerId::new("RINCON_NOWHERE"),
+            volume: 1,
+        };
+        assert!(system.simulate(unknown).is_err());
+    }
 }
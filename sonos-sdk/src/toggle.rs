@@ -0,0 +1,100 @@
+//! Toggles that survive concurrent changes
+//!
+//! A toggle is a read followed by a write of the opposite value, and
+//! another controller can act in between. The outcome is therefore read
+//! back from the speaker after the write. If it shows the state the toggle
+//! started from, a concurrent change undid the write; the toggle then
+//! writes once more from that corrected baseline and reports whatever the
+//! speaker confirms.
+
+use std::fmt;
+
+use crate::SdkError;
+
+/// Flip a two-state property starting from `baseline`
+///
+/// `is_on` projects a value onto the two states, `write` sends the wanted
+/// state (recording the local write so its echo is not reported as
+/// external), and `read_back` reads the speaker, bypassing the cache.
+/// Returns the state the speaker confirmed last.
+pub(crate) fn toggle<T: fmt::Debug>(
+    key: &str,
+    baseline: T,
+    is_on: impl Fn(&T) -> bool,
+    write: impl Fn(bool) -> Result<(), SdkError>,
+    read_back: impl Fn() -> Result<T, SdkError>,
+) -> Result<T, SdkError> {
+    let before = is_on(&baseline);
+    write(!before)?;
+    let observed = read_back()?;
+    if is_on(&observed) != before {
+        return Ok(observed);
+    }
+
+    tracing::debug!("{key} toggle raced with another change (now {observed:?}); writing again");
+    write(!is_on(&observed))?;
+    read_back()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    /// A device whose state another controller may flip after each write
+    struct Device {
+        on: Cell<bool>,
+        interference: RefCell<Vec<bool>>,
+        writes: Cell<usize>,
+    }
+
+    impl Device {
+        fn new(on: bool, interference: Vec<bool>) -> Self {
+            Self {
+                on: Cell::new(on),
+                interference: RefCell::new(interference),
+                writes: Cell::new(0),
+            }
+        }
+
+        fn toggle(&self) -> bool {
+            toggle(
+                "test",
+                self.on.get(),
+                |on| *on,
+                |wanted| {
+                    self.writes.set(self.writes.get() + 1);
+                    self.on.set(wanted);
+                    if let Some(external) = self.interference.borrow_mut().pop() {
+                        self.on.set(external);
+                    }
+                    Ok(())
+                },
+                || Ok(self.on.get()),
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_uncontested_toggle_writes_once() {
+        let device = Device::new(false, Vec::new());
+        assert!(device.toggle());
+        assert_eq!(device.writes.get(), 1);
+    }
+
+    #[test]
+    fn test_undone_toggle_is_written_again() {
+        // Another controller flips it back right after the first write
+        let device = Device::new(false, vec![false]);
+        assert!(device.toggle());
+        assert_eq!(device.writes.get(), 2);
+    }
+
+    #[test]
+    fn test_retries_only_once() {
+        let device = Device::new(true, vec![true, true]);
+        assert!(device.toggle());
+        assert_eq!(device.writes.get(), 2);
+    }
+}
@@ -0,0 +1,140 @@
+//! Activity feed fed by a scripted mock
+//!
+//! Mocks on loopback ports picked by the OS: one is watched, changes
+//! volume a second into its timeline and is written to; another only takes
+//! writes, to fill a small ring. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test activity
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{
+    ActivityCategory, ActivityConfig, ActivityCursor, ActivityFeed, ActivitySeverity, ChangeEvent,
+    SpeakerId, Volume,
+};
+
+use common::{system_for, wait_for};
+
+#[test]
+fn test_feed_records_scripted_activity_in_order() {
+    let mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new()
+            .with_volume(20)
+            .at(Duration::from_secs(1), Action::SetVolume(35)),
+    );
+    let system = system_for(mock.addr(), "Den");
+    let den = system.speaker("Den").unwrap();
+
+    let announced = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&announced);
+    system
+        .state_manager()
+        .add_change_observer(Arc::new(move |event: &ChangeEvent| {
+            if event.property_key == ActivityFeed::EVENT_KEY {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        }));
+    let feed = system.activity_feed();
+
+    let _volume = den.volume.watch().unwrap();
+    wait_for("the subscription", || mock.live_subscriptions() == 1);
+    wait_for("the SUBSCRIBE entry", || {
+        feed.recent(10)
+            .iter()
+            .any(|e| e.category == ActivityCategory::Subscription)
+    });
+    mock.advance(Duration::from_secs(1));
+    wait_for("the NOTIFY", || den.volume.get() == Some(Volume(35)));
+    den.set_volume(30).unwrap();
+
+    let page = feed.since(ActivityCursor::default());
+    assert_eq!(page.missed, 0);
+    let categories: Vec<_> = page.entries.iter().map(|e| e.category).collect();
+    assert_eq!(categories.first(), Some(&ActivityCategory::Subscription));
+    assert_eq!(categories.last(), Some(&ActivityCategory::Write));
+    assert!(categories[1..categories.len() - 1]
+        .iter()
+        .all(|c| *c == ActivityCategory::Notify));
+    assert!(categories.len() >= 3, "{categories:?}");
+
+    let subscribe = &page.entries[0];
+    assert!(
+        subscribe
+            .summary
+            .starts_with("SUBSCRIBE RenderingControl …"),
+        "{}",
+        subscribe.summary
+    );
+    let write = page.entries.last().unwrap();
+    assert_eq!(write.summary, "SetVolume Channel=Master, DesiredVolume=30");
+    for entry in &page.entries {
+        assert_eq!(entry.speaker, Some(SpeakerId::new("RINCON_DEN")));
+        assert_eq!(entry.severity, ActivitySeverity::Info);
+        assert!(!entry.summary.contains("127.0.0.1"), "{}", entry.summary);
+    }
+    let seqs: Vec<_> = page.entries.iter().map(|e| e.seq).collect();
+    assert_eq!(seqs, (1..=seqs.len() as u64).collect::<Vec<_>>());
+
+    // One announcement per read, however many entries follow it
+    let before = announced.load(Ordering::SeqCst);
+    assert!(before >= 1);
+    den.set_volume(31).unwrap();
+    den.set_volume(32).unwrap();
+    assert_eq!(announced.load(Ordering::SeqCst), before + 1);
+    let page = feed.since(page.next);
+    assert_eq!(page.entries.len(), 2);
+    den.set_volume(33).unwrap();
+    assert_eq!(announced.load(Ordering::SeqCst), before + 2);
+}
+
+#[test]
+fn test_ring_bound_and_cursor_pagination() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(20));
+    let system = system_for(mock.addr(), "Den");
+    let den = system.speaker("Den").unwrap();
+    let feed = system.activity_feed_with(ActivityConfig::default().with_capacity(4));
+    // Installed once; a second config is ignored
+    assert_eq!(system.activity_feed().config().capacity, 4);
+
+    let mut volume = 0;
+    let mut write = |count: usize| {
+        for _ in 0..count {
+            volume += 1;
+            den.set_volume(volume).unwrap();
+        }
+    };
+
+    // Read between bursts that fit the ring: every entry exactly once
+    let mut cursor = feed.cursor();
+    let mut read = Vec::new();
+    for burst in [3, 0, 4, 1, 2] {
+        write(burst);
+        let page = feed.since(cursor);
+        assert_eq!(page.missed, 0);
+        read.extend(page.entries.iter().map(|e| e.seq));
+        cursor = page.next;
+    }
+    assert_eq!(read, (1..=10).collect::<Vec<_>>());
+
+    // A burst larger than the ring: the oldest are reported missed
+    write(6);
+    assert_eq!(feed.recent(10).len(), 4);
+    let page = feed.since(cursor);
+    assert_eq!(page.missed, 2);
+    let seqs: Vec<_> = page.entries.iter().map(|e| e.seq).collect();
+    assert_eq!(seqs, [13, 14, 15, 16]);
+    assert_eq!(
+        page.entries.last().unwrap().summary,
+        "SetVolume Channel=Master, DesiredVolume=16"
+    );
+    assert_eq!(page.next, page.entries.last().unwrap().cursor());
+}
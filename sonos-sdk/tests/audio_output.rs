@@ -0,0 +1,124 @@
+//! Where a portable speaker's audio is going
+//!
+//! Mock Roams on loopback serve the Bluetooth status page with Bluetooth
+//! active, headphones plugged in, and neither; the mock One has no audio
+//! output and must be refused without asking it. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test audio_output
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use sonos_api::Service;
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{AudioOutput, SdkError, SonosSystem, WatchMode};
+
+use common::{device, model_device, wait_for};
+
+const BLUETOOTH_ACTIVE: &str = "<BluetoothStatus><Enabled>1</Enabled><Connected>1</Connected>\
+     <Active>1</Active><DeviceName>Pixel 8</DeviceName></BluetoothStatus>";
+const BLUETOOTH_IDLE: &str =
+    "<BluetoothStatus><Enabled>1</Enabled><Connected>0</Connected><Active>0</Active></BluetoothStatus>";
+
+#[test]
+fn test_audio_output_on_portables_and_unsupported_models() {
+    let patio_mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new().with_bluetooth_status(BLUETOOTH_ACTIVE),
+    );
+    let trail_mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new()
+            .with_bluetooth_status(BLUETOOTH_IDLE)
+            .with_headphone_connected(true),
+    );
+    let porch_mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new().with_bluetooth_status(BLUETOOTH_IDLE),
+    );
+    let kitchen_mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system = SonosSystem::from_discovered_devices(vec![
+        model_device("RINCON_PATIO", "Patio", patio_mock.addr(), "Sonos Roam 2"),
+        model_device("RINCON_TRAIL", "Trail", trail_mock.addr(), "Sonos Move"),
+        model_device("RINCON_PORCH", "Porch", porch_mock.addr(), "Roam"),
+        device("RINCON_KITCHEN", "Kitchen", kitchen_mock.addr()),
+    ])
+    .unwrap();
+
+    let patio = system.speaker("Patio").unwrap();
+    assert_eq!(patio.audio_output.fetch().unwrap(), AudioOutput::Bluetooth);
+    assert_eq!(patio.audio_output.get(), Some(AudioOutput::Bluetooth));
+    // Bluetooth wins, so headphones weren't asked about
+    assert!(!patio_mock
+        .actions()
+        .contains(&"GetHeadphoneConnected".to_string()));
+
+    let trail = system.speaker("Trail").unwrap();
+    assert_eq!(trail.audio_output.fetch().unwrap(), AudioOutput::Headphone);
+    // No GetHeadphoneConnected on this model (401): the speaker is playing
+    let porch = system.speaker("Porch").unwrap();
+    assert_eq!(porch.audio_output.fetch().unwrap(), AudioOutput::Speaker);
+
+    // Unsupported is a clean error before anything is sent, not a timeout
+    let kitchen = system.speaker("Kitchen").unwrap();
+    let started = Instant::now();
+    for result in [
+        kitchen.audio_output.fetch().map(|_| ()),
+        kitchen.audio_output.watch().map(|_| ()),
+    ] {
+        assert!(matches!(
+            result,
+            Err(SdkError::NotSupported {
+                property: "audio_output",
+                ref model_name,
+            }) if model_name == "Sonos One"
+        ));
+    }
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(kitchen_mock
+        .user_agents()
+        .iter()
+        .all(|(method, _)| method != "GET"));
+    assert!(!kitchen_mock
+        .actions()
+        .contains(&"GetHeadphoneConnected".to_string()));
+    assert_eq!(kitchen.audio_output.get(), None);
+}
+
+#[test]
+fn test_watch_follows_device_properties_events() {
+    let mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new().with_bluetooth_status(BLUETOOTH_IDLE),
+    );
+    let system = SonosSystem::from_discovered_devices(vec![model_device(
+        "RINCON_DECK",
+        "Deck",
+        mock.addr(),
+        "Sonos Roam",
+    )])
+    .unwrap();
+    let deck = system.speaker("Deck").unwrap();
+
+    let watch = deck.audio_output.watch().unwrap();
+    assert_eq!(watch.mode(), WatchMode::Events);
+    wait_for("subscription", || mock.live_subscriptions() == 1);
+
+    for (wire, output) in [
+        ("BLUETOOTH", AudioOutput::Bluetooth),
+        ("LINE_OUT", AudioOutput::LineOut),
+        ("SPEAKER", AudioOutput::Speaker),
+    ] {
+        mock.perform(Action::Notify {
+            service: Service::DeviceProperties,
+            body: format!(
+                r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><AudioOutput>{wire}</AudioOutput></e:property></e:propertyset>"#
+            ),
+        });
+        wait_for(wire, || deck.audio_output.get() == Some(output));
+    }
+}
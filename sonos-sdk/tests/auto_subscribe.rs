@@ -0,0 +1,110 @@
+//! `SonosSystem::auto_subscribe()` driven by a scripted discovery sequence
+//!
+//! Loopback mocks stand in for a speaker already known at startup, a
+//! speaker that appears later and the address it moves to. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test auto_subscribe
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{AutoSubscribeOptions, DeviceEvent, Presence, SonosSystem, SpeakerId, Volume};
+
+use common::{device, wait_for};
+
+#[test]
+fn test_scripted_discovery_sequence() {
+    let home = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(10));
+    let patio = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(70));
+    let moved = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(70));
+    let system = Arc::new(
+        SonosSystem::from_discovered_devices(vec![device("RINCON_HOME", "Home", home.addr())])
+            .unwrap(),
+    );
+    let patio_id = SpeakerId::new("RINCON_PATIO");
+
+    let lifecycle = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&lifecycle);
+    system
+        .state_manager()
+        .add_change_observer(Arc::new(move |event| {
+            if event.speaker_id.as_str() == "RINCON_PATIO" {
+                recorded.lock().unwrap().push(event.property_key);
+            }
+        }));
+    let events_for_patio = |key: &str| {
+        lifecycle
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|k| **k == key)
+            .count()
+    };
+
+    let (script, events) = mpsc::channel();
+    let _auto = system.auto_subscribe(
+        events,
+        AutoSubscribeOptions::default()
+            .teardown_after(Some(Duration::from_millis(150)))
+            .backoff(Duration::from_millis(400), Duration::from_secs(2))
+            .prefetch_timeout(Duration::from_secs(2)),
+    );
+
+    // A new speaker is registered, prefetched and subscribed
+    script
+        .send(DeviceEvent::Found(device(
+            "RINCON_PATIO",
+            "Patio",
+            patio.addr(),
+        )))
+        .unwrap();
+    wait_for("patio subscriptions", || patio.subscriptions() > 0);
+    let speaker = system.speaker("Patio").unwrap();
+    assert_eq!(speaker.volume.get(), Some(Volume(70)));
+    assert_eq!(system.presence(&patio_id), Some(Presence::Online));
+    let subscribed = patio.subscriptions();
+
+    // Losing it marks it offline, then tears down its subscriptions
+    script
+        .send(DeviceEvent::Lost("RINCON_PATIO".into()))
+        .unwrap();
+    wait_for("teardown", || patio.unsubscriptions() == subscribed);
+    assert_eq!(system.presence(&patio_id), Some(Presence::Offline));
+
+    // Flap: the first return goes through, the second waits out the backoff
+    let flap_started = Instant::now();
+    for event in [
+        DeviceEvent::Found(device("RINCON_PATIO", "Patio", patio.addr())),
+        DeviceEvent::Lost("RINCON_PATIO".into()),
+        DeviceEvent::Found(device("RINCON_PATIO", "Patio", patio.addr())),
+    ] {
+        script.send(event).unwrap();
+    }
+    wait_for("flap to settle", || {
+        system.presence(&patio_id) == Some(Presence::Online)
+            && events_for_patio(Presence::EVENT_KEY) == 5
+    });
+    assert!(flap_started.elapsed() >= Duration::from_millis(400));
+    wait_for("patio resubscribed", || patio.subscriptions() > subscribed);
+
+    // Moving it migrates the store entry and its subscriptions
+    script
+        .send(DeviceEvent::Updated(device(
+            "RINCON_PATIO",
+            "Patio",
+            moved.addr(),
+        )))
+        .unwrap();
+    wait_for("moved subscriptions", || moved.subscriptions() > 0);
+    let info = system.state_manager().speaker_info(&patio_id).unwrap();
+    assert_eq!(info.socket_addr(), moved.addr());
+    assert_eq!(system.speaker("Patio").unwrap().port, moved.addr().port());
+    assert_eq!(events_for_patio(Presence::ADDRESS_EVENT_KEY), 1);
+    assert_eq!(events_for_patio(Presence::EVENT_KEY), 5);
+}
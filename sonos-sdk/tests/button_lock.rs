@@ -0,0 +1,45 @@
+//! Locking the buttons/touch controls
+//!
+//! One loopback mock supports the button lock; the other stands in for an
+//! older model that faults with UPnP error 401. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test button_lock
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_api::ApiError;
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{SdkError, SonosSystem};
+
+use common::device;
+
+#[test]
+fn test_button_lock_round_trip_and_unsupported_models() {
+    let kids = MockDevice::start("127.0.0.1:0", Scenario::new().with_button_lock(false));
+    let legacy = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system = SonosSystem::from_discovered_devices(vec![
+        device("RINCON_KIDS", "Kids Room", kids.addr()),
+        device("RINCON_LEGACY", "Garage", legacy.addr()),
+    ])
+    .unwrap();
+
+    let kids = system.speaker("Kids Room").unwrap();
+    assert!(!kids.button_lock.fetch().unwrap().is_locked());
+    kids.set_button_lock(true).unwrap();
+    assert!(kids.button_lock.get().unwrap().is_locked());
+    assert!(kids.button_lock.fetch().unwrap().is_locked());
+
+    let garage = system.speaker("Garage").unwrap();
+    assert!(matches!(
+        garage.button_lock.fetch(),
+        Err(SdkError::ApiError(ApiError::NotSupported(401)))
+    ));
+    assert!(matches!(
+        garage.set_button_lock(true),
+        Err(SdkError::ApiError(ApiError::NotSupported(401)))
+    ));
+    assert_eq!(garage.button_lock.get(), None);
+}
@@ -0,0 +1,85 @@
+//! Fixtures shared by the mock-backed integration tests
+//!
+//! Every test binary compiles its own copy and uses only part of it.
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sonos_sdk::mock::MockDevice;
+use sonos_sdk::sonos_discovery::Device;
+use sonos_sdk::SonosSystem;
+
+/// Poll `condition` until it holds, failing the test after 5s
+pub fn wait_for(what: &str, condition: impl Fn() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "timed out waiting for {what}"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// A Sonos One in room `name`, as discovery reports it
+pub fn device(id: &str, name: &str, addr: SocketAddr) -> Device {
+    model_device(id, name, addr, "Sonos One")
+}
+
+/// Like [`device()`], for another model
+pub fn model_device(id: &str, name: &str, addr: SocketAddr, model_name: &str) -> Device {
+    Device {
+        id: id.to_string(),
+        name: name.to_string(),
+        room_name: name.to_string(),
+        ip_address: addr.ip().to_string(),
+        port: addr.port(),
+        model_name: model_name.to_string(),
+        household_id: None,
+        secure: false,
+    }
+}
+
+/// One speaker per mock, with ID `RINCON_<ROOM>`
+pub fn room_devices(rooms: &[(&str, &MockDevice)]) -> Vec<Device> {
+    rooms
+        .iter()
+        .map(|(room, mock)| {
+            device(
+                &format!("RINCON_{}", room.to_uppercase()),
+                room,
+                mock.addr(),
+            )
+        })
+        .collect()
+}
+
+/// A system of the single speaker in room `name` at `addr`
+pub fn system_for(addr: SocketAddr, name: &str) -> SonosSystem {
+    SonosSystem::from_discovered_devices(vec![device(
+        &format!("RINCON_{}", name.to_uppercase()),
+        name,
+        addr,
+    )])
+    .unwrap()
+}
+
+/// The topology entry of room `room`, ID `RINCON_<ROOM>`, at `addr`
+pub fn member(room: &str, addr: SocketAddr) -> String {
+    format!(
+        r#"<ZoneGroupMember UUID="RINCON_{}" Location="http://{addr}/xml/device_description.xml" ZoneName="{room}" BootSeq="7"/>"#,
+        room.to_uppercase()
+    )
+}
+
+/// How many times `mock` was sent `action`
+pub fn count(mock: &MockDevice, action: &str) -> usize {
+    mock.actions().iter().filter(|a| *a == action).count()
+}
+
+/// Actions after the first `start`, e.g. the ones sent while connecting
+pub fn actions_since(mock: &MockDevice, start: usize) -> Vec<String> {
+    mock.actions().split_off(start)
+}
@@ -0,0 +1,61 @@
+//! `SonosSystem::connect()` against a local mock speaker
+//!
+//! One loopback mock answers SOAP requests; a second accepts connections
+//! but never replies, standing in for an unreachable device. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test connect
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sonos_sdk::mock::{Behavior, MockDevice, Scenario};
+use sonos_sdk::{ConnectOptions, ConnectProgress, Readiness, SonosSystem, Volume};
+
+use common::device;
+
+#[test]
+fn test_connect_degrades_unreachable_device_within_deadline() {
+    let good = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let hung = MockDevice::start("127.0.0.1:0", Scenario::new().then(Behavior::Hang));
+
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&progress);
+    let deadline = Duration::from_secs(2);
+    let options = ConnectOptions::default()
+        .devices(vec![
+            device("RINCON_GOOD", "Kitchen", good.addr()),
+            device("RINCON_HUNG", "Attic", hung.addr()),
+        ])
+        .deadline(deadline)
+        .subscribe(false)
+        .on_progress(move |p| seen.lock().unwrap().push(p));
+
+    let started = Instant::now();
+    let (system, report) = SonosSystem::connect(options).expect("partial failure must not fail");
+    assert!(started.elapsed() < deadline + Duration::from_millis(500));
+
+    let degraded: Vec<_> = report.degraded().collect();
+    assert_eq!(degraded.len(), 1);
+    assert_eq!(degraded[0].speaker_id.as_str(), "RINCON_HUNG");
+    assert!(matches!(degraded[0].readiness, Readiness::Degraded(_)));
+    assert_eq!(report.devices.len(), 2);
+
+    let kitchen = system.speaker("Kitchen").unwrap();
+    assert_eq!(kitchen.volume.get(), Some(Volume(25)));
+    kitchen
+        .set_volume(30)
+        .expect("healthy speaker stays usable");
+    assert_eq!(kitchen.volume.get(), Some(Volume(30)));
+    assert!(system.speaker("Attic").is_some());
+
+    let progress = progress.lock().unwrap();
+    assert_eq!(progress.first(), Some(&ConnectProgress::DiscoveryStarted));
+    assert!(progress.contains(&ConnectProgress::DeviceFound(2)));
+    assert!(progress.contains(&ConnectProgress::Prefetching { done: 1, total: 2 }));
+    assert_eq!(progress.last(), Some(&ConnectProgress::Ready));
+}
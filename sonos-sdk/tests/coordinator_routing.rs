@@ -0,0 +1,145 @@
+//! Group commands while the group is being reshaped
+//!
+//! Loopback mocks `Den` and `Hall` serve a topology where Hall coordinates
+//! Den, and Den (playing `x-rincon:RINCON_HALL`) faults transport commands
+//! with 701 like a member does. The store is made to believe otherwise with
+//! simulated topology events. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test coordinator_routing
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{SdkError, SimulatedChange, SonosSystem, SpeakerId};
+
+use common::{count, member, room_devices};
+
+struct Lan {
+    system: SonosSystem,
+    den: MockDevice,
+    hall: MockDevice,
+}
+
+/// `Den` and `Hall`, grouped under `coordinator` as the devices see it
+fn start_lan(coordinator: &str) -> Lan {
+    let scenario = |id: &str| {
+        if id == coordinator {
+            Scenario::new()
+        } else {
+            Scenario::new().with_transport_uri(format!("x-rincon:{coordinator}"))
+        }
+    };
+    let den = MockDevice::start("127.0.0.1:0", scenario("RINCON_DEN"));
+    let hall = MockDevice::start("127.0.0.1:0", scenario("RINCON_HALL"));
+    let topology = format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="{coordinator}" ID="{coordinator}:1">{}{}</ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        member("Den", den.addr()),
+        member("Hall", hall.addr())
+    );
+    den.set_zone_group_state(topology.clone());
+    hall.set_zone_group_state(topology);
+    let devices = room_devices(&[("Den", &den), ("Hall", &hall)]);
+    let system = SonosSystem::from_discovered_devices(devices).unwrap();
+    // Load the devices' topology before the tests contradict it
+    assert!(system
+        .group_for_speaker(&SpeakerId::new("RINCON_DEN"))
+        .is_some());
+    Lan { system, den, hall }
+}
+
+fn den() -> SpeakerId {
+    SpeakerId::new("RINCON_DEN")
+}
+
+fn hall() -> SpeakerId {
+    SpeakerId::new("RINCON_HALL")
+}
+
+#[test]
+fn test_refused_command_is_rerouted_after_refresh() {
+    let lan = start_lan("RINCON_HALL");
+    let resolver = lan.system.state_manager().coordinator_resolver();
+    resolver.set_settle_delay(Duration::ZERO);
+
+    // The store still has Den coordinating when the play is sent
+    lan.system
+        .simulate(SimulatedChange::Group {
+            coordinator: den(),
+            members: vec![hall()],
+        })
+        .unwrap();
+    let group = lan.system.group_for_speaker(&den()).unwrap();
+    assert_eq!(group.coordinator_id, den());
+
+    group.play().unwrap();
+    assert_eq!(count(&lan.den, "Play"), 1);
+    assert_eq!(count(&lan.hall, "Play"), 1);
+    let stats = lan.system.routing_stats();
+    assert_eq!(stats.refreshes, 1);
+    assert_eq!(stats.reroutes, 1);
+    assert_eq!(
+        lan.system.group_for_speaker(&den()).unwrap().coordinator_id,
+        hall()
+    );
+
+    // The refreshed store routes straight to Hall
+    group.pause().unwrap();
+    assert_eq!(count(&lan.den, "Pause"), 0);
+    assert_eq!(count(&lan.hall, "Pause"), 1);
+    assert_eq!(lan.system.routing_stats().reroutes, 1);
+}
+
+#[test]
+fn test_route_waits_for_regroup_to_settle() {
+    let lan = start_lan("RINCON_HALL");
+
+    // An interim event names Den; the final one arrives shortly after
+    lan.system
+        .simulate(SimulatedChange::Group {
+            coordinator: den(),
+            members: vec![hall()],
+        })
+        .unwrap();
+    let group = lan.system.group_for_speaker(&den()).unwrap();
+    assert_eq!(group.coordinator_id, den());
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(100));
+            lan.system
+                .simulate(SimulatedChange::Group {
+                    coordinator: hall(),
+                    members: vec![den()],
+                })
+                .unwrap();
+        });
+        group.play().unwrap();
+    });
+
+    assert_eq!(count(&lan.den, "Play"), 0);
+    assert_eq!(count(&lan.hall, "Play"), 1);
+    let stats = lan.system.routing_stats();
+    assert_eq!(stats.settle_waits, 1);
+    assert_eq!(stats.refreshes, 0);
+    assert_eq!(stats.reroutes, 0);
+}
+
+#[test]
+fn test_member_refusing_transport_command_names_coordinator() {
+    let lan = start_lan("RINCON_HALL");
+    let den_speaker = lan.system.speaker("Den").unwrap();
+
+    let err = den_speaker.play().unwrap_err();
+    assert!(
+        matches!(&err, SdkError::NotCoordinator { speaker_id, coordinator_hint: Some(hint), .. }
+            if *speaker_id == den() && *hint == hall()),
+        "{err:?}"
+    );
+    assert_eq!(count(&lan.den, "Play"), 1);
+    assert_eq!(count(&lan.hall, "Play"), 0);
+}
@@ -0,0 +1,161 @@
+//! EQ presets applied with `Speaker::apply_eq()`
+//!
+//! Loopback mocks: one accepts every write, one fails the second, one
+//! takes single-property writes. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test eq
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{
+    Bass, ChangeEvent, EqField, EqOutcome, EqSettings, InterceptDecision, Loudness, RerenderScope,
+    SdkError, SonosSystem, Speaker, Treble,
+};
+
+use common::device;
+
+/// Cache the current EQ and watch it, so the preset's events are the only ones
+fn watch_eq(system: &SonosSystem, speaker: &Speaker) {
+    speaker.bass.fetch().unwrap();
+    speaker.treble.fetch().unwrap();
+    speaker.loudness.fetch().unwrap();
+    for key in ["bass", "treble", "loudness"] {
+        system.state_manager().register_watch(&speaker.id, key);
+    }
+}
+
+/// Record every write as `"Action value"`
+fn log_writes(system: &SonosSystem) -> Arc<Mutex<Vec<String>>> {
+    let writes = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&writes);
+    system.add_write_interceptor(move |request| {
+        let value = request.args().last().map(|(_, v)| v.as_str());
+        log.lock().unwrap().push(format!(
+            "{} {}",
+            request.action(),
+            value.unwrap_or_default()
+        ));
+        InterceptDecision::Allow
+    });
+    writes
+}
+
+const PRESET: EqSettings = EqSettings {
+    bass: Some(-2),
+    treble: Some(1),
+    loudness: Some(true),
+};
+
+#[test]
+fn test_preset_is_validated_then_applied_as_one_change() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system =
+        SonosSystem::from_discovered_devices(vec![device("RINCON_EQ_OK", "Lounge", mock.addr())])
+            .unwrap();
+    let lounge = system.speaker("Lounge").unwrap();
+    watch_eq(&system, &lounge);
+    let iter = system.iter();
+
+    // An out-of-range field stops the whole preset before anything is sent
+    let invalid = EqSettings {
+        treble: Some(11),
+        ..PRESET
+    };
+    let requests = mock.requests();
+    assert!(matches!(
+        lounge.dry_run_eq(&invalid),
+        Err(SdkError::ValidationFailed(_))
+    ));
+    assert!(matches!(
+        lounge.apply_eq(invalid),
+        Err(SdkError::ValidationFailed(_))
+    ));
+    lounge.dry_run_eq(&PRESET).unwrap();
+    assert_eq!(mock.requests(), requests);
+
+    let result = lounge.apply_eq(PRESET).unwrap();
+    assert!(result.is_success());
+    assert_eq!(mock.eq(), (-2, 1, true));
+    assert_eq!(lounge.bass.get(), Some(Bass(-2)));
+
+    let events: Vec<ChangeEvent> = iter.try_iter().collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].property_key, ChangeEvent::BATCH_KEY);
+    assert_eq!(events[0].batch, ["bass", "treble", "loudness"]);
+    assert_eq!(events[0].rerender_scope(), RerenderScope::Speaker);
+}
+
+#[test]
+fn test_failed_field_rolls_back_earlier_ones() {
+    // Startup and the three setup reads take four requests; the preset's
+    // SetBass goes through and its SetTreble fails
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().respond(5).status(500, 1));
+    let system =
+        SonosSystem::from_discovered_devices(vec![device("RINCON_EQ_FAIL", "Study", mock.addr())])
+            .unwrap();
+    let study = system.speaker("Study").unwrap();
+    watch_eq(&system, &study);
+    let iter = system.iter();
+    let writes = log_writes(&system);
+    assert_eq!(mock.requests(), 4);
+
+    let result = study.apply_eq(PRESET).unwrap();
+    assert!(!result.is_success());
+    assert!(matches!(
+        result.outcome(EqField::Bass),
+        Some(EqOutcome::RolledBack)
+    ));
+    assert!(matches!(
+        result.outcome(EqField::Treble),
+        Some(EqOutcome::Failed(SdkError::ApiError(_)))
+    ));
+    assert!(matches!(
+        result.outcome(EqField::Loudness),
+        Some(EqOutcome::Skipped)
+    ));
+    assert_eq!(
+        *writes.lock().unwrap(),
+        ["SetBass -2", "SetTreble 1", "SetBass 0"]
+    );
+
+    assert_eq!(mock.eq(), (0, 0, false));
+    assert_eq!(study.bass.get(), Some(Bass(0)));
+    assert_eq!(iter.try_iter().count(), 0);
+}
+
+#[test]
+fn test_property_handles_set_and_fetch() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system =
+        SonosSystem::from_discovered_devices(vec![device("RINCON_EQ_SET", "Den", mock.addr())])
+            .unwrap();
+    let den = system.speaker("Den").unwrap();
+
+    den.bass.set(-4).unwrap();
+    den.treble.set(7).unwrap();
+    den.loudness.set(true).unwrap();
+    assert_eq!(mock.eq(), (-4, 7, true));
+    assert_eq!(den.treble.get(), Some(Treble(7)));
+
+    // Out of range levels never reach the speaker
+    let requests = mock.requests();
+    assert!(matches!(
+        den.bass.set(11),
+        Err(SdkError::ValidationFailed(_))
+    ));
+    assert!(matches!(
+        den.treble.set(-11),
+        Err(SdkError::ValidationFailed(_))
+    ));
+    assert_eq!(mock.requests(), requests);
+
+    assert_eq!(den.bass.fetch().unwrap(), Bass(-4));
+    assert_eq!(den.treble.fetch().unwrap(), Treble(7));
+    assert_eq!(den.loudness.fetch().unwrap(), Loudness(true));
+}
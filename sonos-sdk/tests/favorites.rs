@@ -0,0 +1,174 @@
+//! Listing and playing Sonos Favorites, playlists and radio stations
+//!
+//! A single loopback mock `Den` serving the Browse results recorded in the
+//! sonos-api fixtures. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test favorites
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{SdkError, SonosSystem, Station};
+
+use common::{actions_since, system_for};
+
+/// The DIDL-Lite `Result` of a recorded Browse response
+fn browse_result(envelope: &str) -> String {
+    let envelope = sonos_api::Element::parse(envelope.as_bytes()).unwrap();
+    envelope
+        .get_child("Body")
+        .and_then(|body| body.get_child("BrowseResponse"))
+        .and_then(|response| response.get_child("Result"))
+        .and_then(|result| result.get_text())
+        .unwrap()
+        .into_owned()
+}
+
+fn start_den() -> (SonosSystem, MockDevice) {
+    let scenario = Scenario::new()
+        .with_queue(["Already queued"])
+        .with_browse_result(
+            "FV:2",
+            browse_result(include_str!(
+                "../../sonos-api/tests/fixtures/browse_favorites_response.xml"
+            )),
+        )
+        .with_browse_result(
+            "SQ:",
+            browse_result(include_str!(
+                "../../sonos-api/tests/fixtures/browse_saved_queues_response.xml"
+            )),
+        );
+    let mock = MockDevice::start("127.0.0.1:0", scenario);
+    (system_for(mock.addr(), "Den"), mock)
+}
+
+#[test]
+fn test_favorites_and_playlists_are_listed_in_order() {
+    let (system, _mock) = start_den();
+
+    let favorites = system.favorites().unwrap();
+    let listed: Vec<_> = favorites
+        .iter()
+        .map(|f| (f.ordinal, f.title.as_str()))
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            (0, "Blue Lines"),
+            (1, "BBC Radio 4 & World"),
+            (2, "Teardrop")
+        ]
+    );
+    assert_eq!(favorites[1].description.as_deref(), Some("TuneIn"));
+
+    let playlists = system.playlists().unwrap();
+    let titles: Vec<_> = playlists.iter().map(|p| p.title.as_str()).collect();
+    assert_eq!(titles, ["Dinner Party", "Morning Coffee"]);
+}
+
+#[test]
+fn test_play_favorite_station_sets_the_transport_uri() {
+    let (system, mock) = start_den();
+    let den = system.speaker("Den").unwrap();
+    let radio = system
+        .favorites()
+        .unwrap()
+        .into_iter()
+        .find(|f| f.description.as_deref() == Some("TuneIn"))
+        .unwrap();
+
+    let start = mock.actions().len();
+    den.play_favorite(&radio).unwrap();
+    assert_eq!(actions_since(&mock, start), ["SetAVTransportURI", "Play"]);
+    let (_, body) = mock
+        .calls()
+        .into_iter()
+        .find(|(action, _)| action == "SetAVTransportURI")
+        .unwrap();
+    assert!(body.contains(
+        "<CurrentURI>x-sonosapi-stream:s25419?sid=254&amp;flags=8224&amp;sn=0</CurrentURI>"
+    ));
+    // r:resMD is sent as the metadata, escaped once more
+    assert!(body.contains("<CurrentURIMetaData>&lt;DIDL-Lite "));
+    assert!(body.contains("BBC Radio 4 &amp;amp; World"));
+    assert_eq!(mock.queue(), ["Already queued"]);
+}
+
+#[test]
+fn test_play_station_matches_its_favorite() {
+    let (system, mock) = start_den();
+    let den = system.speaker("Den").unwrap();
+    let radio = system.favorites().unwrap().remove(1);
+
+    let start = mock.actions().len();
+    den.play_station(&Station::tunein("s25419", "BBC Radio 4 & World"))
+        .unwrap();
+    den.play_favorite(&radio).unwrap();
+    assert_eq!(
+        actions_since(&mock, start),
+        ["SetAVTransportURI", "Play", "SetAVTransportURI", "Play"]
+    );
+    let bodies: Vec<_> = mock
+        .calls()
+        .into_iter()
+        .filter(|(action, _)| action == "SetAVTransportURI")
+        .map(|(_, body)| body)
+        .collect();
+    assert_eq!(bodies[0], bodies[1]);
+}
+
+#[test]
+fn test_play_favorite_album_and_playlist_replace_the_queue() {
+    let (system, mock) = start_den();
+    let den = system.speaker("Den").unwrap();
+    let album = &system.favorites().unwrap()[0];
+    assert!(album.plays_from_queue());
+
+    let start = mock.actions().len();
+    den.play_favorite(album).unwrap();
+    assert_eq!(
+        actions_since(&mock, start),
+        [
+            "RemoveAllTracksFromQueue",
+            "AddURIToQueue",
+            "SetAVTransportURI",
+            "Play"
+        ]
+    );
+    assert_eq!(mock.queue(), ["Blue Lines"]);
+    let (_, set_uri) = mock
+        .calls()
+        .into_iter()
+        .rfind(|(action, _)| action == "SetAVTransportURI")
+        .unwrap();
+    assert!(set_uri.contains("<CurrentURI>x-rincon-queue:RINCON_DEN#0</CurrentURI>"));
+
+    let dinner = &system.playlists().unwrap()[0];
+    den.play_playlist(dinner).unwrap();
+    assert_eq!(mock.queue(), ["Dinner Party"]);
+    let (_, add) = mock
+        .calls()
+        .into_iter()
+        .rfind(|(action, _)| action == "AddURIToQueue")
+        .unwrap();
+    assert!(add.contains("<EnqueuedURI>file:///jffs/settings/savedqueues.rsq#3</EnqueuedURI>"));
+}
+
+#[test]
+fn test_favorite_without_uri_is_refused() {
+    let (system, mock) = start_den();
+    let den = system.speaker("Den").unwrap();
+    let mut shortcut = system.favorites().unwrap().remove(0);
+    shortcut.uri.clear();
+
+    let start = mock.actions().len();
+    assert!(matches!(
+        den.play_favorite(&shortcut),
+        Err(SdkError::InvalidOperation(_))
+    ));
+    assert!(actions_since(&mock, start).is_empty());
+}
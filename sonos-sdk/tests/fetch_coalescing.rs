@@ -0,0 +1,96 @@
+//! Concurrent `fetch()` calls sharing one request
+//!
+//! A loopback mock holds the first GetVolume until every caller has joined
+//! it. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test fetch_coalescing
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use sonos_sdk::mock::{Action, Behavior, MockDevice, Scenario};
+use sonos_sdk::{Mute, SdkError, SonosSystem, Speaker, Volume};
+
+use common::{system_for, wait_for};
+
+/// Start `n` volume fetches and wait until all but the leader joined it
+fn spawn_fetches(
+    system: &SonosSystem,
+    speaker: &Speaker,
+    n: u64,
+) -> Vec<thread::JoinHandle<Result<Volume, SdkError>>> {
+    let before = system.fetch_coalescer().stats().joined;
+    let fetches = (0..n)
+        .map(|_| {
+            let speaker = speaker.clone();
+            thread::spawn(move || speaker.volume.fetch())
+        })
+        .collect();
+    wait_for("fetches to join", || {
+        system.fetch_coalescer().stats().joined == before + n - 1
+    });
+    fetches
+}
+
+#[test]
+fn test_concurrent_fetches_share_one_request() {
+    // Startup takes one request; the first GetVolume after it is held
+    let mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new()
+            .with_volume(37)
+            .respond(1)
+            .delay(Duration::from_secs(1)),
+    );
+    let system = system_for(mock.addr(), "Den");
+    let den = system.speaker("Den").unwrap();
+    let requests = mock.requests();
+
+    let fetches = spawn_fetches(&system, &den, 20);
+    // Other properties do not queue behind the held volume request
+    assert_eq!(den.mute.fetch().unwrap(), Mute(false));
+    mock.advance(Duration::from_secs(1));
+
+    for fetch in fetches {
+        assert_eq!(fetch.join().unwrap().unwrap(), Volume(37));
+    }
+    assert_eq!(mock.requests(), requests + 2, "one GetVolume, one GetMute");
+
+    // A repeat right after the flight is answered from the cache
+    assert_eq!(den.volume.fetch().unwrap(), Volume(37));
+    assert_eq!(mock.requests(), requests + 2);
+    let stats = system.fetch_coalescer().stats();
+    assert_eq!(stats.requests, 2);
+    assert_eq!(stats.saved(), 20);
+
+    // Without the window every fetch outside a flight goes to the speaker
+    system.fetch_coalescer().set_window(Duration::ZERO);
+    den.volume.fetch().unwrap();
+    assert_eq!(mock.requests(), requests + 3);
+}
+
+#[test]
+fn test_failed_flight_fails_every_waiter() {
+    let mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new().respond(1).step(Behavior::Hang, 1),
+    );
+    let system = system_for(mock.addr(), "Loft");
+    let loft = system.speaker("Loft").unwrap();
+
+    let fetches = spawn_fetches(&system, &loft, 20);
+    mock.perform(Action::Reboot);
+
+    for fetch in fetches {
+        assert!(fetch.join().unwrap().is_err());
+    }
+    assert_eq!(system.fetch_coalescer().stats().requests, 1);
+    // Failures are not cached: the next fetch tries again
+    assert_eq!(loft.volume.fetch().unwrap(), Volume(25));
+    assert_eq!(loft.volume.get(), Some(Volume(25)));
+}
@@ -0,0 +1,387 @@
+//! Golden tests: recorded household sessions replayed through the full
+//! event pipeline
+//!
+//! Each `tests/golden/sessions/<name>.json` holds the speakers of one
+//! household (IDs and addresses anonymized), the topology it starts in and
+//! the NOTIFY bodies it sent, in order, with the speaker, service and time
+//! of each. The harness starts a mock per speaker on its recorded loopback
+//! address, on a free port, and points the recorded topology and bodies at
+//! those ports. All mocks share one `ManualClock`. It watches a fixed set
+//! of properties on every speaker and subscribes each speaker to the
+//! services it sends, then moves the clock to each event and has the
+//! speaker's mock deliver it, so it takes the production path: callback
+//! server, broker, decoders, state store. Minutes of recorded time replay
+//! in a few seconds.
+//!
+//! Every `ChangeEvent` the replay emits is written to a transcript with
+//! its origin and the value read back, step by step, followed by the final
+//! value of each watched property and the groups. The transcript must
+//! match `tests/golden/snapshots/<name>.snap`; on a mismatch the test
+//! prints a line diff. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test golden
+//! ```
+//!
+//! When a change is meant to alter the pipeline's output, rewrite the
+//! snapshots and review their diff like any other code change:
+//!
+//! ```bash
+//! GOLDEN_UPDATE=1 cargo test -p sonos-sdk --features test-support --test golden
+//! git diff sonos-sdk/tests/golden/snapshots
+//! ```
+//!
+//! A new session needs a distinct loopback address per speaker, written
+//! as `<ip>:1400` in its topology and bodies, and a `#[test]` below;
+//! its first run with `GOLDEN_UPDATE=1` writes the snapshot.
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::fmt::{Debug, Write as _};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Deserialize;
+use sonos_api::clock::ManualClock;
+use sonos_api::Service;
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{ChangeEvent, ChangeIterator, SonosSystem, Speaker};
+
+use common::{device, wait_for};
+
+/// How long the pipeline must stay silent before a step counts as done;
+/// longer than the window that coalesces external volume steps
+const QUIET: Duration = Duration::from_millis(600);
+
+/// Properties watched on every speaker, in transcript order
+const WATCHED: [&str; 7] = [
+    "volume",
+    "mute",
+    "playback_state",
+    "position",
+    "current_track",
+    "transport_actions",
+    "group_membership",
+];
+
+#[derive(Deserialize)]
+struct Session {
+    description: String,
+    speakers: Vec<SessionSpeaker>,
+    /// `ZoneGroupState` every speaker reports at the start
+    topology: String,
+    events: Vec<SessionEvent>,
+}
+
+#[derive(Deserialize)]
+struct SessionSpeaker {
+    id: String,
+    name: String,
+    ip: String,
+}
+
+#[derive(Deserialize)]
+struct SessionEvent {
+    at_ms: u64,
+    /// Room name of the sender
+    speaker: String,
+    service: Service,
+    /// `<e:propertyset>` body, exactly as received
+    body: String,
+}
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// Replay a session and compare its transcript with the snapshot
+fn check(name: &str) {
+    let path = golden_dir().join("sessions").join(format!("{name}.json"));
+    let json =
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {e}", path.display()));
+    let session: Session =
+        serde_json::from_str(&json).unwrap_or_else(|e| panic!("parsing {}: {e}", path.display()));
+    let actual = replay(name, &session);
+
+    let snapshot = golden_dir().join("snapshots").join(format!("{name}.snap"));
+    if std::env::var_os("GOLDEN_UPDATE").is_some() {
+        fs::write(&snapshot, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&snapshot).unwrap_or_else(|_| {
+        panic!(
+            "no snapshot at {}; run with GOLDEN_UPDATE=1 to create it",
+            snapshot.display()
+        )
+    });
+    if expected != actual {
+        panic!(
+            "session {name} no longer matches {} (- expected, + actual); \
+             if the change is intended, rerun with GOLDEN_UPDATE=1\n\n{}",
+            snapshot.display(),
+            diff(&expected, &actual)
+        );
+    }
+}
+
+fn replay(name: &str, session: &Session) -> String {
+    let clock = ManualClock::new();
+    let mut last = Duration::ZERO;
+    let mocks: Vec<MockDevice> = session
+        .speakers
+        .iter()
+        .map(|speaker| {
+            MockDevice::start_with_clock(
+                &format!("{}:0", speaker.ip),
+                Scenario::new(),
+                clock.clone(),
+            )
+        })
+        .collect();
+    // The recording names each speaker at `<ip>:1400`; its mock has another port
+    let localize = |text: &str| {
+        session
+            .speakers
+            .iter()
+            .zip(&mocks)
+            .fold(text.to_string(), |text, (speaker, mock)| {
+                text.replace(&format!("{}:1400", speaker.ip), &mock.addr().to_string())
+            })
+    };
+    let topology = localize(&session.topology);
+    for mock in &mocks {
+        mock.set_zone_group_state(topology.clone());
+    }
+    let system = SonosSystem::from_discovered_devices(
+        session
+            .speakers
+            .iter()
+            .zip(&mocks)
+            .map(|(s, mock)| device(&s.id, &s.name, mock.addr()))
+            .collect(),
+    )
+    .unwrap();
+    // Load the starting topology before watches report their initial values
+    system.groups();
+
+    let changes = system.iter();
+    let speakers: Vec<Speaker> = session
+        .speakers
+        .iter()
+        .map(|s| system.speaker(&s.name).unwrap())
+        .collect();
+    let _watches: Vec<_> = speakers
+        .iter()
+        .map(|s| {
+            (
+                s.volume.watch().unwrap(),
+                s.mute.watch().unwrap(),
+                s.playback_state.watch().unwrap(),
+                s.position.watch().unwrap(),
+                s.current_track.watch().unwrap(),
+                s.transport_actions.watch().unwrap(),
+                s.group_membership.watch().unwrap(),
+            )
+        })
+        .collect();
+
+    // Watches subscribe a member's AVTransport on its coordinator; the
+    // recording has NOTIFYs from members too, so subscribe every sender
+    let mut senders: Vec<(usize, Service)> = Vec::new();
+    for event in &session.events {
+        let sender = (speaker_index(session, &event.speaker), event.service);
+        if !senders.contains(&sender) {
+            senders.push(sender);
+        }
+    }
+    let event_manager = system.state_manager().event_manager().unwrap();
+    let _subscriptions: Vec<_> = senders
+        .iter()
+        .map(|&(i, service)| {
+            let speaker = &speakers[i];
+            event_manager
+                .acquire_watch(&speaker.id, own_key(service), speaker.addr(), service)
+                .unwrap()
+        })
+        .collect();
+    for &(i, service) in &senders {
+        wait_for(
+            &format!("{} to accept a {service:?} subscription", speakers[i].name),
+            || !mocks[i].sids(service).is_empty(),
+        );
+    }
+
+    let mut out = String::new();
+    writeln!(out, "# {name}: {}", session.description).unwrap();
+    writeln!(out, "\n## watch").unwrap();
+    drain(&system, &changes, &mut out);
+
+    for event in &session.events {
+        let at = Duration::from_millis(event.at_ms);
+        assert!(
+            at > last,
+            "{name}: events must be in strictly increasing at_ms order"
+        );
+        let mock = &mocks[speaker_index(session, &event.speaker)];
+        mock.advance(at - mock.elapsed());
+        mock.perform(Action::Notify {
+            service: event.service,
+            body: localize(&event.body),
+        });
+        last = at;
+        writeln!(
+            out,
+            "\n## {}ms {} {:?}",
+            event.at_ms, event.speaker, event.service
+        )
+        .unwrap();
+        drain(&system, &changes, &mut out);
+    }
+
+    writeln!(out, "\n## final state").unwrap();
+    for speaker in &speakers {
+        writeln!(out, "{}", speaker.name).unwrap();
+        for key in WATCHED {
+            writeln!(out, "  {key} = {}", value(speaker, key)).unwrap();
+        }
+    }
+    writeln!(out, "\n## groups").unwrap();
+    let mut groups: Vec<String> = system
+        .groups()
+        .iter()
+        .map(|group| {
+            let coordinator = group.coordinator().map(|c| c.name).unwrap_or_default();
+            let mut members: Vec<String> = group.members().into_iter().map(|m| m.name).collect();
+            members.sort();
+            format!("{coordinator}: {}", members.join(", "))
+        })
+        .collect();
+    groups.sort();
+    for group in groups {
+        writeln!(out, "{group}").unwrap();
+    }
+    out
+}
+
+fn speaker_index(session: &Session, name: &str) -> usize {
+    session
+        .speakers
+        .iter()
+        .position(|s| s.name == name)
+        .unwrap_or_else(|| panic!("event from unknown speaker {name:?}"))
+}
+
+/// A speaker-scoped property the speaker's own subscription to `service`
+/// feeds
+fn own_key(service: Service) -> &'static str {
+    match service {
+        Service::RenderingControl => "volume",
+        Service::AVTransport => "playback_state",
+        Service::ZoneGroupTopology => "group_membership",
+        other => panic!("sessions can't replay {other:?} yet"),
+    }
+}
+
+/// Write the changes emitted until the pipeline goes quiet
+fn drain(system: &SonosSystem, changes: &ChangeIterator, out: &mut String) {
+    let mut quiet = true;
+    while let Some(event) = changes.recv_timeout(QUIET) {
+        quiet = false;
+        writeln!(out, "{}", describe(system, &event)).unwrap();
+    }
+    if quiet {
+        writeln!(out, "(no changes)").unwrap();
+    }
+}
+
+fn describe(system: &SonosSystem, event: &ChangeEvent) -> String {
+    let Some(speaker) = system.speaker_by_id(&event.speaker_id) else {
+        return format!(
+            "{} {} {:?}",
+            event.speaker_id, event.property_key, event.origin
+        );
+    };
+    let coalesced = if event.coalesced > 1 {
+        format!(" x{}", event.coalesced)
+    } else {
+        String::new()
+    };
+    format!(
+        "{} {} {:?}{coalesced} = {}",
+        speaker.name,
+        event.property_key,
+        event.origin,
+        value(&speaker, event.property_key)
+    )
+}
+
+fn value(speaker: &Speaker, key: &str) -> String {
+    fn show<T: Debug>(value: Option<T>) -> String {
+        value.map_or_else(|| "unset".to_string(), |v| format!("{v:?}"))
+    }
+    match key {
+        "volume" => show(speaker.volume.get()),
+        "mute" => show(speaker.mute.get()),
+        "playback_state" => show(speaker.playback_state.get()),
+        "position" => show(speaker.position.get()),
+        "current_track" => show(speaker.current_track.get()),
+        "transport_actions" => show(speaker.transport_actions.get()),
+        "group_membership" => show(speaker.group_membership.get()),
+        _ => "?".to_string(),
+    }
+}
+
+/// Line diff of two transcripts, changed lines marked `-` / `+`
+fn diff(expected: &str, actual: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+    // lcs[i][j]: longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = String::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            writeln!(out, "  {}", a[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            writeln!(out, "- {}", a[i]).unwrap();
+            i += 1;
+        } else {
+            writeln!(out, "+ {}", b[j]).unwrap();
+            j += 1;
+        }
+    }
+    out
+}
+
+#[test]
+fn test_normal_playback() {
+    check("normal_playback");
+}
+
+#[test]
+fn test_grouping() {
+    check("grouping");
+}
+
+#[test]
+fn test_radio() {
+    check("radio");
+}
+
+#[test]
+fn test_coordinator_handoff() {
+    check("coordinator_handoff");
+}
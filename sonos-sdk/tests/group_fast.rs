@@ -0,0 +1,111 @@
+//! Fast group assembly through GroupManagement `AddMember`
+//!
+//! Loopback mocks: `Den` coordinates (and answers `AddMember`), `Hall`
+//! joins it. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test group_fast
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_api::ApiError;
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{SdkError, SonosSystem};
+
+use common::{actions_since, member, room_devices};
+
+struct Lan {
+    system: SonosSystem,
+    den: MockDevice,
+    hall: MockDevice,
+}
+
+/// `Den` and `Hall`, grouped or each standalone
+fn start_lan(grouped: bool) -> Lan {
+    let den = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new().with_coordinator_id("RINCON_DEN"),
+    );
+    let hall = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let (den_member, hall_member) = (member("Den", den.addr()), member("Hall", hall.addr()));
+    let groups = if grouped {
+        format!(
+            r#"<ZoneGroup Coordinator="RINCON_DEN" ID="RINCON_DEN:1">{den_member}{hall_member}</ZoneGroup>"#
+        )
+    } else {
+        format!(
+            r#"<ZoneGroup Coordinator="RINCON_DEN" ID="RINCON_DEN:1">{den_member}</ZoneGroup><ZoneGroup Coordinator="RINCON_HALL" ID="RINCON_HALL:1">{hall_member}</ZoneGroup>"#
+        )
+    };
+    let topology = format!("<ZoneGroupState><ZoneGroups>{groups}</ZoneGroups></ZoneGroupState>");
+    den.set_zone_group_state(topology.clone());
+    hall.set_zone_group_state(topology);
+    let devices = room_devices(&[("Den", &den), ("Hall", &hall)]);
+    let system = SonosSystem::from_discovered_devices(devices).unwrap();
+    Lan { system, den, hall }
+}
+
+#[test]
+fn test_add_member_fast_follows_handshake() {
+    let lan = start_lan(false);
+    let den = lan.system.speaker("Den").unwrap();
+    let hall = lan.system.speaker("Hall").unwrap();
+    let group = lan.system.group_for_speaker(&den.id).unwrap();
+    let (den_start, hall_start) = (lan.den.actions().len(), lan.hall.actions().len());
+
+    let joined = group.add_member_fast(&hall).unwrap();
+
+    assert_eq!(joined.coordinator_id(), Some("RINCON_DEN"));
+    assert_eq!(joined.queue_owner_id().as_deref(), Some("RINCON_DEN"));
+    assert_eq!(actions_since(&lan.den, den_start), ["AddMember"]);
+    assert_eq!(actions_since(&lan.hall, hall_start), ["SetAVTransportURI"]);
+    assert_eq!(lan.den.members(), ["RINCON_HALL"]);
+
+    let calls = lan.den.calls();
+    let (_, add_member) = calls.last().unwrap();
+    assert!(add_member.contains("<MemberID>RINCON_HALL</MemberID>"));
+    assert!(add_member.contains("<BootSeq>7</BootSeq>"), "{add_member}");
+    let calls = lan.hall.calls();
+    let (_, set_uri) = calls.last().unwrap();
+    assert!(set_uri.contains("<CurrentURI>x-rincon:RINCON_DEN</CurrentURI>"));
+}
+
+#[test]
+fn test_member_already_in_group_is_refused_without_sending() {
+    let lan = start_lan(true);
+    let den = lan.system.speaker("Den").unwrap();
+    let hall = lan.system.speaker("Hall").unwrap();
+    let group = lan.system.group_for_speaker(&den.id).unwrap();
+    let (den_start, hall_start) = (lan.den.actions().len(), lan.hall.actions().len());
+
+    let err = group.add_member_fast(&hall).unwrap_err();
+
+    assert!(
+        matches!(&err, SdkError::AlreadyInGroup { speaker_id, .. } if *speaker_id == hall.id),
+        "{err:?}"
+    );
+    assert!(actions_since(&lan.den, den_start).is_empty());
+    assert!(actions_since(&lan.hall, hall_start).is_empty());
+}
+
+#[test]
+fn test_device_refusal_stops_before_member() {
+    let lan = start_lan(false);
+    let den = lan.system.speaker("Den").unwrap();
+    let hall = lan.system.speaker("Hall").unwrap();
+    let group = lan.system.group_for_speaker(&den.id).unwrap();
+    group.add_member_fast(&hall).unwrap();
+    let (den_start, hall_start) = (lan.den.actions().len(), lan.hall.actions().len());
+
+    // The handle predates the join, so only the coordinator knows
+    let err = group.add_member_fast(&hall).unwrap_err();
+
+    assert!(
+        matches!(&err, SdkError::ApiError(ApiError::SoapFault { code, .. }) if code.is_invalid_args()),
+        "{err:?}"
+    );
+    assert_eq!(actions_since(&lan.den, den_start), ["AddMember"]);
+    assert!(actions_since(&lan.hall, hall_start).is_empty());
+}
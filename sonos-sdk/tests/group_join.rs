@@ -0,0 +1,123 @@
+//! Moving speakers between groups
+//!
+//! Loopback mocks: `Den` coordinates `Hall` (playing
+//! `x-rincon:RINCON_DEN`), `Patio` is on its own. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test group_join
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{SdkError, SonosSystem};
+
+use common::{actions_since, member, room_devices};
+
+struct Lan {
+    system: SonosSystem,
+    den: MockDevice,
+    hall: MockDevice,
+    patio: MockDevice,
+}
+
+/// `Den`, `Hall` and `Patio`
+fn start_lan() -> Lan {
+    let den = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let hall = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new().with_transport_uri("x-rincon:RINCON_DEN"),
+    );
+    let patio = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let topology = format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_DEN" ID="RINCON_DEN:1">{}{}</ZoneGroup><ZoneGroup Coordinator="RINCON_PATIO" ID="RINCON_PATIO:1">{}</ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        member("Den", den.addr()),
+        member("Hall", hall.addr()),
+        member("Patio", patio.addr())
+    );
+    for mock in [&den, &hall, &patio] {
+        mock.set_zone_group_state(topology.clone());
+    }
+    let devices = room_devices(&[("Den", &den), ("Hall", &hall), ("Patio", &patio)]);
+    let system = SonosSystem::from_discovered_devices(devices).unwrap();
+    Lan {
+        system,
+        den,
+        hall,
+        patio,
+    }
+}
+
+fn last_body(mock: &MockDevice) -> String {
+    mock.calls().pop().unwrap().1
+}
+
+#[test]
+fn test_grouped_speaker_moves_to_another_group() {
+    let lan = start_lan();
+    let hall = lan.system.speaker("Hall").unwrap();
+    let patio = lan.system.speaker("Patio").unwrap();
+    let den_start = lan.den.actions().len();
+    let hall_start = lan.hall.actions().len();
+
+    lan.system.group_with(&hall, &patio).unwrap();
+
+    assert_eq!(actions_since(&lan.hall, hall_start), ["SetAVTransportURI"]);
+    assert!(last_body(&lan.hall).contains("<CurrentURI>x-rincon:RINCON_PATIO</CurrentURI>"));
+    // The group Hall leaves is not asked anything
+    assert!(actions_since(&lan.den, den_start).is_empty());
+}
+
+#[test]
+fn test_joining_a_member_resolves_its_coordinator() {
+    let lan = start_lan();
+    let hall = lan.system.speaker("Hall").unwrap();
+    let patio = lan.system.speaker("Patio").unwrap();
+    let patio_start = lan.patio.actions().len();
+
+    lan.system.group_with(&patio, &hall).unwrap();
+
+    assert_eq!(
+        actions_since(&lan.patio, patio_start),
+        ["SetAVTransportURI"]
+    );
+    assert!(last_body(&lan.patio).contains("<CurrentURI>x-rincon:RINCON_DEN</CurrentURI>"));
+}
+
+#[test]
+fn test_refusals_send_nothing() {
+    let lan = start_lan();
+    let den = lan.system.speaker("Den").unwrap();
+    let hall = lan.system.speaker("Hall").unwrap();
+    let (den_start, hall_start) = (lan.den.actions().len(), lan.hall.actions().len());
+
+    let err = lan.system.group_with(&hall, &den).unwrap_err();
+    assert!(
+        matches!(&err, SdkError::AlreadyInGroup { speaker_id, .. } if *speaker_id == hall.id),
+        "{err:?}"
+    );
+    let err = lan.system.group_with(&den, &hall).unwrap_err();
+    assert!(matches!(err, SdkError::InvalidOperation(_)), "{err:?}");
+
+    assert!(actions_since(&lan.den, den_start).is_empty());
+    assert!(actions_since(&lan.hall, hall_start).is_empty());
+}
+
+#[test]
+fn test_leave_group_makes_member_standalone() {
+    let lan = start_lan();
+    let hall = lan.system.speaker("Hall").unwrap();
+    let hall_start = lan.hall.actions().len();
+
+    let left = hall.leave_group().unwrap();
+
+    assert_eq!(left.delegated_group_coordinator_id, "RINCON_DEN");
+    assert_eq!(
+        actions_since(&lan.hall, hall_start),
+        ["BecomeCoordinatorOfStandaloneGroup"]
+    );
+    // No longer a member, so transport commands are accepted again
+    hall.pause().unwrap();
+    assert_eq!(lan.hall.transport_state(), "PAUSED_PLAYBACK");
+}
@@ -0,0 +1,162 @@
+//! Group volume and mute through the coordinator
+//!
+//! Loopback mocks `Den` and `Hall` are grouped, and only the mock given a
+//! group volume answers GroupRenderingControl; the other faults with 701
+//! like a member does. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test group_volume
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_api::services::group_rendering_control;
+use sonos_api::{ApiError, SonosClient};
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{Group, GroupMute, GroupVolume, SdkError, SimulatedChange, SonosSystem, SpeakerId};
+
+use common::{count, member, room_devices};
+
+struct Lan {
+    system: SonosSystem,
+    den: MockDevice,
+    hall: MockDevice,
+}
+
+/// `Den` coordinating `Hall`; `coordinator` is the mock that accepts
+/// GroupRenderingControl, starting at volume 30
+fn start_lan(coordinator: &str) -> Lan {
+    let scenario = |room: &str| {
+        if room == coordinator {
+            Scenario::new().with_group_volume(30)
+        } else {
+            Scenario::new()
+        }
+    };
+    let den = MockDevice::start("127.0.0.1:0", scenario("Den"));
+    let hall = MockDevice::start("127.0.0.1:0", scenario("Hall"));
+    let topology = format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_DEN" ID="RINCON_DEN:1">{}{}</ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        member("Den", den.addr()),
+        member("Hall", hall.addr())
+    );
+    den.set_zone_group_state(topology.clone());
+    hall.set_zone_group_state(topology);
+    let devices = room_devices(&[("Den", &den), ("Hall", &hall)]);
+    let system = SonosSystem::from_discovered_devices(devices).unwrap();
+    Lan { system, den, hall }
+}
+
+fn den_group(system: &SonosSystem) -> Group {
+    system
+        .group_for_speaker(&SpeakerId::new("RINCON_DEN"))
+        .unwrap()
+}
+
+#[test]
+fn test_group_volume_and_mute_handles() {
+    let lan = start_lan("Den");
+    let group = den_group(&lan.system);
+    assert_eq!(group.coordinator_id, SpeakerId::new("RINCON_DEN"));
+
+    assert_eq!(group.volume.fetch().unwrap(), GroupVolume(30));
+    group.volume.snapshot().unwrap();
+    group.volume.set(45).unwrap();
+    assert_eq!(group.volume.get(), Some(GroupVolume(45)));
+    assert_eq!(group.volume.set_relative(-5).unwrap(), GroupVolume(40));
+    assert_eq!(group.volume.get(), Some(GroupVolume(40)));
+    assert_eq!(group.volume.fetch().unwrap(), GroupVolume(40));
+
+    group.mute.set(true).unwrap();
+    assert_eq!(group.mute.get(), Some(GroupMute(true)));
+    assert_eq!(group.mute.fetch().unwrap(), GroupMute(true));
+
+    assert!(matches!(
+        group.volume.set(101),
+        Err(SdkError::ValidationFailed(_))
+    ));
+    assert_eq!(count(&lan.den, "SnapshotGroupVolume"), 1);
+    assert_eq!(count(&lan.den, "SetGroupVolume"), 1);
+    assert!(lan
+        .hall
+        .actions()
+        .iter()
+        .all(|a| !a.contains("GroupVolume") && !a.contains("GroupMute")));
+}
+
+#[test]
+fn test_member_faults_as_not_coordinator() {
+    let lan = start_lan("Den");
+    let client = SonosClient::new();
+    let operation = group_rendering_control::get_group_volume().build().unwrap();
+    assert!(matches!(
+        client.execute_enhanced(&lan.hall.addr().to_string(), operation),
+        Err(ApiError::NotCoordinator)
+    ));
+    // The group's handles never go to a member
+    let group = den_group(&lan.system);
+    assert_eq!(group.volume.fetch().unwrap(), GroupVolume(30));
+    assert_eq!(count(&lan.hall, "GetGroupVolume"), 1);
+}
+
+#[test]
+fn test_writes_follow_coordinator_handoff() {
+    let lan = start_lan("Hall");
+    let group = den_group(&lan.system);
+
+    // Topology still says Den coordinates, so there is nowhere to redirect
+    assert!(matches!(
+        group.volume.set(50),
+        Err(SdkError::NotCoordinator {
+            speaker_id,
+            coordinator_hint: None,
+            source: ApiError::NotCoordinator,
+        }) if speaker_id.as_str() == "RINCON_DEN"
+    ));
+    assert_eq!(group.volume.get(), None);
+
+    // Hall takes over; the handle made before the handoff routes to it
+    lan.system
+        .simulate(SimulatedChange::Group {
+            coordinator: SpeakerId::new("RINCON_HALL"),
+            members: vec![SpeakerId::new("RINCON_DEN")],
+        })
+        .unwrap();
+    group.volume.set(50).unwrap();
+    assert_eq!(group.volume.get(), Some(GroupVolume(50)));
+    assert_eq!(group.volume.fetch().unwrap(), GroupVolume(50));
+    group.set_mute(true).unwrap();
+    assert_eq!(count(&lan.den, "SetGroupVolume"), 1);
+    assert_eq!(count(&lan.hall, "SetGroupVolume"), 1);
+    assert_eq!(count(&lan.hall, "SetGroupMute"), 1);
+}
+
+#[test]
+fn test_refused_redirect_hints_at_coordinator() {
+    // Neither accepts, and each names the other as coordinator
+    let lan = start_lan("");
+    let group = |coordinator: &str| {
+        format!(
+            r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_{}" ID="RINCON_DEN:1">{}{}</ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+            coordinator.to_uppercase(),
+            member("Den", lan.den.addr()),
+            member("Hall", lan.hall.addr())
+        )
+    };
+    lan.den.set_zone_group_state(group("Hall"));
+    lan.hall.set_zone_group_state(group("Den"));
+
+    let err = den_group(&lan.system).volume.set(50).unwrap_err();
+    assert!(
+        matches!(&err, SdkError::NotCoordinator { speaker_id, coordinator_hint: Some(hint), .. }
+            if speaker_id.as_str() == "RINCON_HALL" && hint.as_str() == "RINCON_DEN"),
+        "{err:?}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "RINCON_HALL is not its group's coordinator; try RINCON_DEN"
+    );
+    assert_eq!(count(&lan.den, "SetGroupVolume"), 1);
+    assert_eq!(count(&lan.hall, "SetGroupVolume"), 1);
+}
@@ -0,0 +1,165 @@
+//! Two Sonos households on one LAN
+//!
+//! Loopback mocks play three speakers of `Sonos_A` and two of the
+//! neighbouring `Sonos_B`; one of the latter only reveals its household
+//! through `GetHouseholdID`. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test household
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::sonos_discovery::Device;
+use sonos_sdk::{ConnectOptions, HouseholdSelection, SdkError, SonosSystem, SpeakerId};
+
+use common::device;
+
+/// (room, household, household reported by discovery)
+const SPEAKERS: [(&str, &str, bool); 5] = [
+    ("Den", "Sonos_A", true),
+    ("Hall", "Sonos_A", true),
+    ("Study", "Sonos_A", true),
+    ("Loft", "Sonos_B", true),
+    ("Attic", "Sonos_B", false),
+];
+
+struct Lan {
+    devices: Vec<Device>,
+    mocks: Vec<MockDevice>,
+}
+
+impl Lan {
+    fn mock(&self, room: &str) -> &MockDevice {
+        let i = SPEAKERS.iter().position(|s| s.0 == room).unwrap();
+        &self.mocks[i]
+    }
+}
+
+/// Every room standalone; each speaker reports its own household's groups
+fn topology(mocks: &[MockDevice], household: &str) -> String {
+    let groups: String = SPEAKERS
+        .iter()
+        .zip(mocks)
+        .filter(|(s, _)| s.1 == household)
+        .map(|((room, _, _), mock)| {
+            let uuid = format!("RINCON_{}", room.to_uppercase());
+            format!(
+                r#"<ZoneGroup Coordinator="{uuid}" ID="{uuid}:1"><ZoneGroupMember UUID="{uuid}" Location="http://{}/xml/device_description.xml" ZoneName="{room}"/></ZoneGroup>"#,
+                mock.addr()
+            )
+        })
+        .collect();
+    format!("<ZoneGroupState><ZoneGroups>{groups}</ZoneGroups></ZoneGroupState>")
+}
+
+fn start_lan() -> Lan {
+    let mocks: Vec<MockDevice> = SPEAKERS
+        .iter()
+        .map(|(_, household, _)| {
+            MockDevice::start("127.0.0.1:0", Scenario::new().with_household(*household))
+        })
+        .collect();
+    for ((_, household, _), mock) in SPEAKERS.iter().zip(&mocks) {
+        mock.set_zone_group_state(topology(&mocks, household));
+    }
+    let devices = SPEAKERS
+        .iter()
+        .zip(&mocks)
+        .map(|((room, household, reported), mock)| Device {
+            household_id: reported.then(|| household.to_string()),
+            ..device(
+                &format!("RINCON_{}", room.to_uppercase()),
+                room,
+                mock.addr(),
+            )
+        })
+        .collect();
+    Lan { devices, mocks }
+}
+
+fn names(system: &SonosSystem) -> Vec<String> {
+    let mut names = system.speaker_names();
+    names.sort();
+    names
+}
+
+fn connect(lan: &Lan, household: HouseholdSelection) -> Result<SonosSystem, SdkError> {
+    let options = ConnectOptions::default()
+        .devices(lan.devices.clone())
+        .household(household)
+        .prefetch(false)
+        .subscribe(false);
+    SonosSystem::connect(options).map(|(system, _)| system)
+}
+
+#[test]
+fn test_household_with_most_devices_is_selected() {
+    let lan = start_lan();
+    let system = SonosSystem::from_discovered_devices(lan.devices.clone()).unwrap();
+
+    assert_eq!(system.household_id(), Some("Sonos_A"));
+    assert_eq!(names(&system), ["Den", "Hall", "Study"]);
+    assert_eq!(system.groups().len(), 3);
+
+    let households = system.households();
+    assert_eq!(households.len(), 2);
+    assert_eq!(households[0].id, "Sonos_A");
+    assert_eq!(households[1].room_names, ["Attic", "Loft"]);
+    assert_eq!(households[1].device_count(), 2);
+
+    // The neighbours were asked for their household and nothing else
+    assert_eq!(lan.mock("Attic").requests(), 1);
+    assert_eq!(lan.mock("Loft").requests(), 0);
+}
+
+#[test]
+fn test_explicit_household_is_selected() {
+    let lan = start_lan();
+    let system = connect(&lan, HouseholdSelection::Id("Sonos_B".to_string())).unwrap();
+
+    assert_eq!(system.household_id(), Some("Sonos_B"));
+    assert_eq!(names(&system), ["Attic", "Loft"]);
+    assert!(system
+        .speaker_by_id(&SpeakerId::new("RINCON_DEN"))
+        .is_none());
+    assert_eq!(lan.mock("Den").requests(), 0);
+
+    let unknown = connect(&lan, HouseholdSelection::Id("Sonos_Z".to_string()));
+    assert!(matches!(unknown, Err(SdkError::HouseholdNotFound(id)) if id == "Sonos_Z"));
+}
+
+#[test]
+fn test_grouping_across_households_is_refused() {
+    let lan = start_lan();
+    let system = connect(&lan, HouseholdSelection::All).unwrap();
+    assert_eq!(system.household_id(), None);
+    assert_eq!(names(&system), ["Attic", "Den", "Hall", "Loft", "Study"]);
+    // Both households' topologies were fetched
+    assert_eq!(system.groups().len(), 5);
+    // No GetHouseholdID: Attic's one request is its topology fetch
+    assert_eq!(lan.mock("Attic").requests(), 1);
+
+    let den = system.speaker("Den").unwrap();
+    let hall = system.speaker("Hall").unwrap();
+    let loft = system.speaker("Loft").unwrap();
+    let loft_requests = lan.mock("Loft").requests();
+
+    let result = system.create_group(&den, &[&hall, &loft]).unwrap();
+    assert_eq!(result.succeeded, std::slice::from_ref(&hall.id));
+    assert_eq!(result.failed.len(), 1);
+    assert!(matches!(
+        &result.failed[0].1,
+        SdkError::CrossHousehold { speaker_id, speaker_household, group_household }
+            if speaker_id == &loft.id && speaker_household == "Sonos_B" && group_household == "Sonos_A"
+    ));
+
+    let den_group = system.group_for_speaker(&den.id).unwrap();
+    assert!(matches!(
+        loft.join_group(&den_group),
+        Err(SdkError::CrossHousehold { .. })
+    ));
+    assert_eq!(lan.mock("Loft").requests(), loft_requests, "nothing sent");
+}
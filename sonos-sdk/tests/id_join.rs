@@ -0,0 +1,101 @@
+//! Discovery and topology name the same speaker differently
+//!
+//! Device descriptions carry `uuid:RINCON_…` UDNs while ZoneGroupState uses
+//! bare `RINCON_…` UUIDs. Loopback mocks serve one topology;
+//! every discovered speaker must land in its group. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test id_join
+//! ```
+#![cfg(feature = "test-support")]
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::sonos_discovery::device::DeviceDescription;
+use sonos_sdk::sonos_discovery::Device;
+use sonos_sdk::{SonosSystem, SpeakerId};
+
+const ROOMS: [(&str, &str); 3] = [
+    ("RINCON_LIVING01400", "Living Room"),
+    ("RINCON_KITCHEN01400", "Kitchen"),
+    ("RINCON_OFFICE01400", "Office"),
+];
+
+/// Living Room and Kitchen grouped, Office on its own
+fn topology(mocks: &[MockDevice]) -> String {
+    let member = |(uuid, room): (&str, &str), mock: &MockDevice| {
+        format!(
+            r#"<ZoneGroupMember UUID="{uuid}" Location="http://{}/xml/device_description.xml" ZoneName="{room}"/>"#,
+            mock.addr()
+        )
+    };
+    let [living, kitchen, office] = ROOMS;
+    format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="{}" ID="{}:1">{}{}</ZoneGroup><ZoneGroup Coordinator="{}" ID="{}:2">{}</ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        living.0,
+        living.0,
+        member(living, &mocks[0]),
+        member(kitchen, &mocks[1]),
+        office.0,
+        office.0,
+        member(office, &mocks[2]),
+    )
+}
+
+/// What SSDP discovery hands over, UDN prefix and all
+fn discovered((uuid, room): (&str, &str), mock: &MockDevice) -> Device {
+    let xml = format!(
+        r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:ZonePlayer:1</deviceType>
+    <friendlyName>{room}</friendlyName>
+    <manufacturer>Sonos, Inc.</manufacturer>
+    <modelName>Sonos One</modelName>
+    <UDN>uuid:{uuid}</UDN>
+    <roomName>{room}</roomName>
+  </device>
+</root>"#
+    );
+    let device = DeviceDescription::from_xml(&xml)
+        .unwrap()
+        .to_device(mock.addr().ip().to_string());
+    Device {
+        port: mock.addr().port(),
+        ..device
+    }
+}
+
+#[test]
+fn test_discovered_speakers_join_topology() {
+    let mocks: Vec<MockDevice> = ROOMS
+        .iter()
+        .map(|_| MockDevice::start("127.0.0.1:0", Scenario::new()))
+        .collect();
+    for mock in &mocks {
+        mock.set_zone_group_state(topology(&mocks));
+    }
+    let devices: Vec<Device> = ROOMS
+        .into_iter()
+        .zip(&mocks)
+        .map(|(room, mock)| discovered(room, mock))
+        .collect();
+    assert!(devices.iter().all(|d| d.id.starts_with("uuid:")));
+
+    let system = SonosSystem::from_discovered_devices(devices).unwrap();
+    let groups = system.groups();
+    assert_eq!(groups.len(), 2);
+
+    for (uuid, room) in ROOMS {
+        let speaker = system.speaker(room).unwrap();
+        assert_eq!(speaker.id, SpeakerId::new(uuid));
+        assert_eq!(speaker.id.as_str(), uuid);
+        let group = speaker.group().expect("speaker orphaned from its group");
+        assert!(group.members().iter().any(|m| m.id == speaker.id));
+    }
+
+    let living = system.speaker("Living Room").unwrap();
+    let kitchen = system.speaker("Kitchen").unwrap();
+    let group = system.group_for_speaker(&kitchen.id).unwrap();
+    assert!(group.is_coordinator(&living.id));
+    assert_eq!(group.members().len(), 2);
+}
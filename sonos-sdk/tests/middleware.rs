@@ -0,0 +1,116 @@
+//! Write interceptors and change middleware registered on `SonosSystem`
+//!
+//! A loopback mock records what actually reaches the wire. Run
+//! with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test middleware
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use sonos_api::Service;
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{
+    ChangeEvent, ChangeOrigin, InterceptDecision, SdkError, SpeakerId, Volume, WriteRequest,
+};
+
+use common::system_for;
+
+/// Caps volume at 60 and refuses anything above 90 outright
+fn volume_cap(request: &WriteRequest) -> InterceptDecision {
+    let volume = request
+        .arg("DesiredVolume")
+        .and_then(|v| v.parse::<u8>().ok());
+    match volume {
+        Some(v) if v > 90 => InterceptDecision::Deny(format!("volume {v} is too loud")),
+        Some(v) if v > 60 => {
+            InterceptDecision::Modify(request.clone().with_arg("DesiredVolume", "60"))
+        }
+        _ => InterceptDecision::Allow,
+    }
+}
+
+#[test]
+fn test_volume_cap_interceptor() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(20));
+    let system = system_for(mock.addr(), "Den");
+    let den = system.speaker("Den").unwrap();
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&seen);
+    system.add_write_interceptor(move |request| {
+        log.lock().unwrap().push(request.action());
+        InterceptDecision::Allow
+    });
+    system.add_write_interceptor(volume_cap);
+
+    // Denied: nothing is sent and the caller learns why
+    let requests = mock.requests();
+    match den.set_volume(95) {
+        Err(SdkError::WriteDenied {
+            speaker_id,
+            action,
+            reason,
+        }) => {
+            assert_eq!(speaker_id, SpeakerId::new("RINCON_DEN"));
+            assert_eq!(action, "SetVolume");
+            assert_eq!(reason, "volume 95 is too loud");
+        }
+        other => panic!("expected WriteDenied, got {other:?}"),
+    }
+    assert_eq!(mock.requests(), requests);
+    assert_eq!(mock.volume(), 20);
+
+    // Modified: the capped value goes out and the requested one isn't cached
+    den.set_volume(75).unwrap();
+    assert_eq!(mock.volume(), 60);
+    assert_ne!(den.volume.get(), Some(Volume(75)));
+
+    // Allowed: sent and cached as usual
+    den.set_volume(30).unwrap();
+    assert_eq!(mock.volume(), 30);
+    assert_eq!(den.volume.get(), Some(Volume(30)));
+
+    // Every write went past the logging interceptor first, reads did not
+    den.get_media_info().ok();
+    assert_eq!(*seen.lock().unwrap(), ["SetVolume"; 3]);
+}
+
+#[test]
+fn test_change_middleware_rewrites_before_delivery() {
+    let system = system_for("127.0.0.1:1400".parse().unwrap(), "Den");
+    let manager = system.state_manager();
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&observed);
+    manager.add_change_observer(Arc::new(move |event: &ChangeEvent| {
+        recorded
+            .lock()
+            .unwrap()
+            .push((event.property_key, event.origin));
+    }));
+
+    // Drop mute changes, then mark what is left as external
+    system.add_change_middleware(|event| (event.property_key != "mute").then_some(event));
+    system.add_change_middleware(|event| Some(event.with_origin(ChangeOrigin::External)));
+
+    let den = SpeakerId::new("RINCON_DEN");
+    manager.emit_change(ChangeEvent::new(
+        den.clone(),
+        "mute",
+        Service::RenderingControl,
+    ));
+    manager.emit_change(ChangeEvent::new(den, "volume", Service::RenderingControl));
+
+    assert_eq!(
+        *observed.lock().unwrap(),
+        [("volume", ChangeOrigin::External)]
+    );
+    let iter = system.iter();
+    let delivered: Vec<_> = iter.try_iter().map(|e| e.property_key).collect();
+    assert_eq!(delivered, ["volume"]);
+}
@@ -0,0 +1,60 @@
+//! Shuffle and repeat through the `play_mode` handle
+//!
+//! One loopback mock remembers the play mode it was last given and
+//! events `CurrentPlayMode` changes made by another controller. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test play_mode
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_api::Service;
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{CurrentPlayMode, PlayMode, SonosSystem, WatchMode};
+
+use common::{device, wait_for};
+
+#[test]
+fn test_play_mode_fetch_set_and_events() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system =
+        SonosSystem::from_discovered_devices(vec![device("RINCON_DEN", "Den", mock.addr())])
+            .unwrap();
+    let den = system.speaker("Den").unwrap();
+
+    assert_eq!(den.play_mode.get(), None);
+    let mode = den.play_mode.fetch().unwrap().mode();
+    assert_eq!(mode, PlayMode::Normal);
+
+    // Toggling shuffle keeps the repeat setting
+    den.play_mode.set(PlayMode::RepeatAll).unwrap();
+    den.play_mode
+        .set(PlayMode::RepeatAll.with_shuffle(true))
+        .unwrap();
+    assert_eq!(
+        den.play_mode.get(),
+        Some(CurrentPlayMode(PlayMode::Shuffle))
+    );
+    assert_eq!(
+        den.get_transport_settings().unwrap().mode(),
+        Some(PlayMode::Shuffle)
+    );
+    den.set_play_mode(PlayMode::RepeatOne).unwrap();
+    assert_eq!(
+        den.play_mode.get(),
+        Some(CurrentPlayMode(PlayMode::RepeatOne))
+    );
+
+    let watch = den.play_mode.watch().unwrap();
+    assert_eq!(watch.mode(), WatchMode::Events);
+    wait_for("subscription", || mock.live_subscriptions() == 1);
+    mock.perform(Action::Notify {
+        service: Service::AVTransport,
+        body: r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"&gt;&lt;InstanceID val="0"&gt;&lt;CurrentPlayMode val="SHUFFLE_NOREPEAT"/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#.to_string(),
+    });
+    wait_for("evented play mode", || {
+        den.play_mode.get() == Some(CurrentPlayMode(PlayMode::ShuffleNoRepeat))
+    });
+}
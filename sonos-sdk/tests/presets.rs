@@ -0,0 +1,53 @@
+//! EQ preset recall with `Speaker::select_preset()`
+//!
+//! A loopback mock, which knows only `FactoryDefaults`. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test presets
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{SdkError, SonosSystem, FACTORY_DEFAULTS_PRESET};
+
+use common::device;
+
+#[test]
+fn test_unknown_presets_are_rejected_by_cache_or_device() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system =
+        SonosSystem::from_discovered_devices(vec![device("RINCON_PRESETS", "Den", mock.addr())])
+            .unwrap();
+    let den = system.speaker("Den").unwrap();
+
+    // Nothing cached: the device's 701 fault comes back typed
+    let requests = mock.requests();
+    match den.select_preset("Jazz") {
+        Err(SdkError::InvalidPreset { name, available }) => {
+            assert_eq!(name, "Jazz");
+            assert!(available.is_empty());
+        }
+        other => panic!("expected InvalidPreset, got {other:?}"),
+    }
+    assert_eq!(mock.requests(), requests + 1);
+
+    // With the list cached, an unknown name never reaches the device
+    assert_eq!(
+        den.presets.fetch().unwrap().names(),
+        [FACTORY_DEFAULTS_PRESET]
+    );
+    let requests = mock.requests();
+    match den.select_preset("Jazz") {
+        Err(SdkError::InvalidPreset { available, .. }) => {
+            assert_eq!(available, [FACTORY_DEFAULTS_PRESET]);
+        }
+        other => panic!("expected InvalidPreset, got {other:?}"),
+    }
+    assert_eq!(mock.requests(), requests);
+
+    den.set_bass(4).unwrap();
+    den.select_preset(FACTORY_DEFAULTS_PRESET).unwrap();
+    assert_eq!(mock.eq(), (0, 0, true));
+}
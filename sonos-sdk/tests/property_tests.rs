@@ -3,7 +3,7 @@
 //! These tests validate correctness properties using property-based testing.
 
 use proptest::prelude::*;
-use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use sonos_api::SonosClient;
@@ -31,6 +31,8 @@ fn create_test_state_manager(
         ip_address: ip,
         port: 1400,
         model_name: "Sonos One".to_string(),
+        household_id: None,
+        secure: false,
     }];
     manager.add_devices(devices).unwrap();
     Arc::new(manager)
@@ -59,9 +61,9 @@ fn create_test_context(
     state_manager: Arc<StateManager>,
 ) -> Arc<SpeakerContext> {
     let speaker_id_obj = SpeakerId::new(speaker_id.as_ref());
-    let speaker_ip: IpAddr = ip.as_ref().parse().unwrap();
+    let speaker_addr = SocketAddr::new(ip.as_ref().parse().unwrap(), 1400);
     let api_client = SonosClient::new();
-    SpeakerContext::new(speaker_id_obj, speaker_ip, state_manager, api_client)
+    SpeakerContext::new(speaker_id_obj, speaker_addr, state_manager, api_client)
 }
 
 // ============================================================================
@@ -253,14 +255,14 @@ proptest! {
     ) {
         let state_manager = create_test_state_manager(&speaker_id, &ip);
         let speaker_id_obj = SpeakerId::new(&speaker_id);
-        let speaker_ip: IpAddr = ip.parse().unwrap();
+        let speaker_addr = SocketAddr::new(ip.parse().unwrap(), 1400);
         let api_client = SonosClient::new();
 
         // Create a speaker
         let speaker = Speaker::new(
             speaker_id_obj.clone(),
             format!("Test Speaker {speaker_id}"),
-            speaker_ip,
+            speaker_addr,
             "Sonos One".to_string(),
             Arc::clone(&state_manager),
             api_client,
@@ -328,14 +330,14 @@ proptest! {
     ) {
         let state_manager = create_test_state_manager(&speaker_id, &ip);
         let speaker_id_obj = SpeakerId::new(&speaker_id);
-        let speaker_ip: IpAddr = ip.parse().unwrap();
+        let speaker_addr = SocketAddr::new(ip.parse().unwrap(), 1400);
         let api_client = SonosClient::new();
 
         // Create a speaker and set initial volume
         let speaker = Speaker::new(
             speaker_id_obj.clone(),
             format!("Test Speaker {speaker_id}"),
-            speaker_ip,
+            speaker_addr,
             "Sonos One".to_string(),
             Arc::clone(&state_manager),
             api_client,
@@ -658,6 +660,8 @@ proptest! {
             ip_address: ip.clone(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = SonosSystem::from_discovered_devices(devices).unwrap();
@@ -699,6 +703,8 @@ proptest! {
             ip_address: ip.clone(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = SonosSystem::from_discovered_devices(devices).unwrap();
@@ -736,6 +742,8 @@ proptest! {
             ip_address: ip.clone(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = SonosSystem::from_discovered_devices(devices).unwrap();
@@ -777,6 +785,8 @@ proptest! {
             ip_address: ip.clone(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         let system = SonosSystem::from_discovered_devices(devices).unwrap();
@@ -815,6 +825,8 @@ proptest! {
                 ip_address: format!("192.168.1.{}", 100 + i),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             })
             .collect();
 
@@ -862,6 +874,8 @@ proptest! {
                 ip_address: format!("192.168.1.{}", 100 + i),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             })
             .collect();
 
@@ -928,6 +942,8 @@ proptest! {
             ip_address: ip.clone(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         state_manager.add_devices(devices).unwrap();
 
@@ -1003,6 +1019,8 @@ proptest! {
                 ip_address: ip.clone(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             })
             .collect();
         state_manager.add_devices(devices).unwrap();
@@ -1076,6 +1094,8 @@ proptest! {
                 ip_address: ip,
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             });
 
             all_groups.push(GroupInfo::new(
@@ -1280,6 +1300,8 @@ proptest! {
                 ip_address: ip.clone(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             })
             .collect();
         state_manager.add_devices(devices).unwrap();
@@ -1368,6 +1390,8 @@ proptest! {
                 ip_address: ip.clone(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             })
             .collect();
         state_manager.add_devices(devices).unwrap();
@@ -1448,6 +1472,8 @@ proptest! {
             ip_address: ip.clone(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         state_manager.add_devices(devices).unwrap();
 
@@ -1518,6 +1544,8 @@ proptest! {
                 ip_address: ip.clone(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             })
             .collect();
         state_manager.add_devices(devices).unwrap();
@@ -1633,6 +1661,8 @@ proptest! {
                 ip_address: ip,
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             });
 
             groups.push(GroupInfo::new(
@@ -1707,6 +1737,8 @@ proptest! {
                 ip_address: ip,
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             });
 
             groups.push(GroupInfo::new(
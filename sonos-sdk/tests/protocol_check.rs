@@ -0,0 +1,106 @@
+//! URI pre-flight checks against `Speaker::supported_protocols()`
+//!
+//! Two loopback mocks: one whose sink list takes MP3 and FLAC over HTTP,
+//! and one with no sink list. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test protocol_check
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{ProtocolCheck, SdkError, SonosSystem};
+
+use common::device;
+
+const SINK: &str = "http-get:*:audio/mpeg:*,http-get:*:audio/flac:*,x-rincon-mp3radio:*:*:*";
+
+#[test]
+fn test_protocol_check_modes() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_protocol_info(SINK));
+    let system = SonosSystem::from_discovered_devices(vec![device(
+        "RINCON_PROTOCOLS",
+        "Study",
+        mock.addr(),
+    )])
+    .unwrap();
+    let study = system.speaker("Study").unwrap();
+
+    // Off by default: nothing is fetched or checked
+    let requests = mock.requests();
+    study.set_av_transport_uri("http://nas/a.wav", "").unwrap();
+    assert_eq!(mock.requests(), requests + 1);
+
+    // The sink list is fetched once and shared by every handle
+    let protocols = study.supported_protocols().unwrap();
+    assert_eq!(protocols.len(), 3);
+    assert_eq!(protocols[1].content_format, "audio/flac");
+    let requests = mock.requests();
+    assert_eq!(
+        system
+            .speaker("Study")
+            .unwrap()
+            .supported_protocols()
+            .unwrap(),
+        protocols
+    );
+    assert_eq!(mock.requests(), requests);
+
+    // Reject: an unlisted format never reaches the device
+    let strict = study.clone().with_protocol_check(ProtocolCheck::Reject);
+    let requests = mock.requests();
+    match strict.set_av_transport_uri("http://nas/a.wav?token=1", "") {
+        Err(SdkError::UnsupportedMedia { uri, content_type }) => {
+            assert_eq!(uri, "http://nas/a.wav?token=1");
+            assert_eq!(content_type, "audio/wav");
+        }
+        other => panic!("expected UnsupportedMedia, got {other:?}"),
+    }
+    assert!(matches!(
+        strict.add_uri_to_queue("http://nas/a.ogg", "", 0, false),
+        Err(SdkError::UnsupportedMedia { .. })
+    ));
+    assert_eq!(mock.requests(), requests);
+
+    // Listed formats and URIs the list can't judge are sent
+    strict
+        .set_av_transport_uri("http://nas/a.flac", "")
+        .unwrap();
+    strict
+        .set_av_transport_uri("http://radio/live", "")
+        .unwrap();
+    strict
+        .set_av_transport_uri("x-sonosapi-stream:s1234?sid=254", "")
+        .unwrap();
+    assert_eq!(mock.requests(), requests + 3);
+
+    // Warn: the URI is sent anyway
+    let lenient = study.with_protocol_check(ProtocolCheck::Warn);
+    lenient
+        .set_av_transport_uri("http://nas/a.wav", "")
+        .unwrap();
+    assert_eq!(mock.requests(), requests + 4);
+}
+
+#[test]
+fn test_protocol_check_without_sink_list_sends() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system = SonosSystem::from_discovered_devices(vec![device(
+        "RINCON_NO_PROTOCOLS",
+        "Attic",
+        mock.addr(),
+    )])
+    .unwrap();
+    let attic = system
+        .speaker("Attic")
+        .unwrap()
+        .with_protocol_check(ProtocolCheck::Reject);
+
+    assert!(attic.supported_protocols().is_err());
+    let requests = mock.requests();
+    attic.set_av_transport_uri("http://nas/a.wav", "").unwrap();
+    // One failed GetProtocolInfo, then the command itself
+    assert_eq!(mock.requests(), requests + 2);
+}
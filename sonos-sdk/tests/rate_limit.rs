@@ -0,0 +1,156 @@
+//! Write rate limiting on `SonosSystem`
+//!
+//! A loopback mock counts the writes that reach the wire while the
+//! system's `ManualClock` refills the token buckets. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test rate_limit
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{
+    ConnectOptions, ExceedPolicy, ManualClock, RateLimit, SdkError, SonosSystem, Volume,
+    WriteRateLimit,
+};
+
+use common::{count, device, wait_for};
+
+/// A system of the single speaker "Den" at `mock`, on `clock`
+fn den_system(mock: &MockDevice, clock: &ManualClock) -> SonosSystem {
+    let (system, _) = SonosSystem::connect(
+        ConnectOptions::default()
+            .devices(vec![device("RINCON_DEN", "Den", mock.addr())])
+            .prefetch(false)
+            .subscribe(false)
+            .clock(Arc::new(clock.clone())),
+    )
+    .unwrap();
+    system
+}
+
+/// Den's token level, negative once writes are queued for later tokens
+fn level(system: &SonosSystem, mock: &MockDevice) -> f64 {
+    system.write_token_levels().devices[&mock.addr().to_string()]
+}
+
+fn retry_after(result: Result<(), SdkError>) -> Duration {
+    match result {
+        Err(SdkError::RateLimited {
+            action,
+            retry_after,
+            ..
+        }) => {
+            assert_eq!(action, "SetVolume");
+            retry_after
+        }
+        other => panic!("expected RateLimited, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_reject_sends_the_burst_then_reports_retry_after() {
+    let clock = ManualClock::new();
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(20));
+    let system = den_system(&mock, &clock);
+    let den = system.speaker("Den").unwrap();
+    system.set_write_rate_limit(Some(
+        WriteRateLimit::new(ExceedPolicy::Reject).per_device(RateLimit::new(1.0, 2)),
+    ));
+
+    den.set_volume(21).unwrap();
+    den.set_volume(22).unwrap();
+    assert_eq!(retry_after(den.set_volume(23)), Duration::from_secs(1));
+    assert_eq!(retry_after(den.set_volume(24)), Duration::from_secs(1));
+    assert_eq!(count(&mock, "SetVolume"), 2);
+    assert_eq!(mock.volume(), 22);
+    assert_eq!(den.volume.get(), Some(Volume(22)));
+
+    clock.advance(Duration::from_millis(500));
+    assert_eq!(retry_after(den.set_volume(25)), Duration::from_millis(500));
+    clock.advance(Duration::from_millis(500));
+    den.set_volume(26).unwrap();
+    assert_eq!(count(&mock, "SetVolume"), 3);
+    assert_eq!(mock.volume(), 26);
+
+    // Reads are never limited
+    den.volume.fetch().unwrap();
+}
+
+#[test]
+fn test_queue_sends_in_order_within_max_wait() {
+    let clock = ManualClock::new();
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(20));
+    let system = den_system(&mock, &clock);
+    let den = system.speaker("Den").unwrap();
+    system.set_write_rate_limit(Some(
+        WriteRateLimit::new(ExceedPolicy::Queue {
+            max_wait: Duration::from_secs(2),
+        })
+        .per_device(RateLimit::new(1.0, 1)),
+    ));
+
+    den.set_volume(10).unwrap();
+    thread::scope(|scope| {
+        let second = scope.spawn(|| den.set_volume(30));
+        wait_for("the first queued write", || level(&system, &mock) < -0.5);
+        let third = scope.spawn(|| den.set_volume(40));
+        wait_for("the second queued write", || level(&system, &mock) < -1.5);
+
+        // A third queued write would wait 3s, past max_wait
+        assert_eq!(retry_after(den.set_volume(50)), Duration::from_secs(1));
+        assert_eq!(count(&mock, "SetVolume"), 1);
+
+        clock.advance(Duration::from_secs(1));
+        second.join().unwrap().unwrap();
+        assert_eq!(mock.volume(), 30);
+        assert_eq!(count(&mock, "SetVolume"), 2);
+
+        clock.advance(Duration::from_secs(1));
+        third.join().unwrap().unwrap();
+        assert_eq!(mock.volume(), 40);
+        assert_eq!(count(&mock, "SetVolume"), 3);
+    });
+}
+
+#[test]
+fn test_coalesce_sends_only_the_latest_queued_volume() {
+    let clock = ManualClock::new();
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(20));
+    let system = den_system(&mock, &clock);
+    let den = system.speaker("Den").unwrap();
+    system.set_write_rate_limit(Some(
+        WriteRateLimit::new(ExceedPolicy::Coalesce {
+            max_wait: Duration::from_secs(5),
+        })
+        .per_device(RateLimit::new(1.0, 1)),
+    ));
+
+    den.set_volume(10).unwrap();
+    let den = &den;
+    thread::scope(|scope| {
+        let mut writes = vec![scope.spawn(|| den.set_volume(15))];
+        wait_for("the queued write", || level(&system, &mock) < -0.5);
+        for volume in [25, 35, 45] {
+            writes.push(scope.spawn(move || den.set_volume(volume)));
+            // Merge in order
+            thread::sleep(Duration::from_millis(20));
+        }
+        clock.advance(Duration::from_secs(1));
+        for write in writes {
+            write.join().unwrap().unwrap();
+        }
+    });
+
+    assert_eq!(count(&mock, "SetVolume"), 2);
+    assert_eq!(mock.volume(), 45);
+    // Superseded values never reached the speaker and aren't cached
+    assert_eq!(den.volume.get(), Some(Volume(45)));
+    assert!(level(&system, &mock) < 0.5);
+}
@@ -0,0 +1,105 @@
+//! `SonosSystem::refresh_all()` against a store partly out of date
+//!
+//! Loopback mocks `Den` (coordinating `Kitchen`) and `Kitchen` answer the
+//! reads; `Attic` is registered at an address nothing listens on. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test refresh
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::net::TcpListener;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{ChangeEvent, Mute, PlaybackState, SonosSystem, SpeakerId, Volume};
+use sonos_state::{DynamicValue, Property};
+
+use common::{count, device, member, room_devices};
+
+#[test]
+fn test_refresh_corrects_only_stale_values() {
+    let den = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let kitchen = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new()
+            .with_volume(40)
+            .with_transport_uri("x-rincon:RINCON_DEN"),
+    );
+    let topology = format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_DEN" ID="RINCON_DEN:1">{}{}</ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        member("Den", den.addr()),
+        member("Kitchen", kitchen.addr())
+    );
+    den.set_zone_group_state(topology.clone());
+    kitchen.set_zone_group_state(topology);
+    let unreachable = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let mut devices = room_devices(&[("Den", &den), ("Kitchen", &kitchen)]);
+    devices.push(device("RINCON_ATTIC", "Attic", unreachable));
+    let system = SonosSystem::from_discovered_devices(devices).unwrap();
+    let (den_id, kitchen_id) = (
+        SpeakerId::new("RINCON_DEN"),
+        SpeakerId::new("RINCON_KITCHEN"),
+    );
+    let state = system.state_manager();
+
+    // An empty store: everything read is a correction
+    let first = system.refresh_all();
+    assert!(first.topology);
+    assert_eq!(first.speaker(&den_id).unwrap().corrected.len(), 8);
+    // Kitchen's transport is read at Den
+    assert_eq!(first.speaker(&kitchen_id).unwrap().corrected.len(), 5);
+    assert_eq!(count(&kitchen, "GetPositionInfo"), 0);
+    let attic = first.speaker(&SpeakerId::new("RINCON_ATTIC")).unwrap();
+    assert!(attic.corrected.is_empty());
+    assert_eq!(attic.failures.len(), 7, "{:?}", attic.failures);
+    assert_eq!(first.failed().count(), 1);
+
+    for (id, key) in [
+        (&den_id, Volume::KEY),
+        (&den_id, Mute::KEY),
+        (&den_id, PlaybackState::KEY),
+        (&kitchen_id, Volume::KEY),
+    ] {
+        state.register_watch(id, key);
+    }
+    let changes = system.iter();
+
+    // Accurate: no changes at all
+    let accurate = system.refresh_all();
+    assert_eq!(accurate.corrections(), 0);
+    assert!(changes.try_recv().is_none());
+
+    // Stale mute and playback on Den, stale volume on Kitchen
+    state.set_property(&den_id, Mute(true));
+    state.set_property(&den_id, PlaybackState::Paused);
+    state.set_property(&kitchen_id, Volume(10));
+    while changes.try_recv().is_some() {}
+
+    let report = system.refresh_all_with(1);
+    assert_eq!(report.corrections(), 3);
+    let den_fixes = &report.speaker(&den_id).unwrap().corrected;
+    assert_eq!(
+        den_fixes.iter().map(|c| c.key).collect::<Vec<_>>(),
+        [Mute::KEY, PlaybackState::KEY]
+    );
+    assert_eq!(den_fixes[0].before, Some(DynamicValue::Bool(true)));
+    assert_eq!(den_fixes[0].after, Some(DynamicValue::Bool(false)));
+    let kitchen_fix = &report.speaker(&kitchen_id).unwrap().corrected[0];
+    assert_eq!(kitchen_fix.before, Some(DynamicValue::Int(10)));
+    assert_eq!(kitchen_fix.after, Some(DynamicValue::Int(40)));
+    assert!(!report.speaker(&den_id).unwrap().corrected(Volume::KEY));
+
+    // One event per speaker
+    let mut events: Vec<ChangeEvent> = changes.try_iter().collect();
+    events.sort_by(|a, b| a.speaker_id.as_str().cmp(b.speaker_id.as_str()));
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].speaker_id, den_id);
+    assert_eq!(events[0].batch, [Mute::KEY, PlaybackState::KEY]);
+    assert_eq!(events[1].property_key, Volume::KEY);
+    assert_eq!(state.get_property::<Volume>(&kitchen_id), Some(Volume(40)));
+}
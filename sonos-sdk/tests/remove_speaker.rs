@@ -0,0 +1,106 @@
+//! Removing a speaker with a watch and an event still in flight
+//!
+//! A loopback mock is watched, then removed; the event it sent
+//! just before must neither bring it back nor reach the store. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test remove_speaker
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{ChangeOrigin, ConnectOptions, SimulatedChange, SonosSystem, SpeakerId, Volume};
+use sonos_state::Property;
+
+use common::{device, wait_for};
+
+#[test]
+fn test_removed_speaker_is_unsubscribed_and_not_resurrected() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(25));
+    mock.set_zone_group_state(format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_ATTIC" ID="RINCON_ATTIC:1"><ZoneGroupMember UUID="RINCON_ATTIC" Location="http://{}/xml/device_description.xml" ZoneName="Attic"/></ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        mock.addr()
+    ));
+    let (system, _) = SonosSystem::connect(
+        ConnectOptions::default()
+            .devices(vec![device("RINCON_ATTIC", "Attic", mock.addr())])
+            .prefetch(false)
+            .subscribe(false),
+    )
+    .unwrap();
+    let attic_id = SpeakerId::new("RINCON_ATTIC");
+    let attic = system.speaker_by_id(&attic_id).unwrap();
+
+    let watch = attic.volume.watch().unwrap();
+    wait_for("subscription", || mock.subscriptions() > 0);
+    let in_flight = SimulatedChange::Volume {
+        speaker: attic_id.clone(),
+        volume: 60,
+    }
+    .to_event(system.state_manager())
+    .unwrap();
+
+    // The watch still holds the subscription, yet removal releases it
+    assert!(system.remove_speaker(&attic_id));
+    wait_for("unsubscribe", || mock.unsubscriptions() > 0);
+    assert!(!system.remove_speaker(&attic_id));
+
+    let state = system.state_manager();
+    assert!(!state.inject_event(&in_flight));
+    assert!(system.speaker_by_id(&attic_id).is_none());
+    assert!(state.speaker_info(&attic_id).is_none());
+    assert_eq!(state.get_property::<Volume>(&attic_id), None);
+    assert_eq!(state.dropped_for_removed(), 1);
+
+    // The orphaned watch lets go without a second unsubscribe
+    drop(watch);
+    thread::sleep(Duration::from_millis(1500));
+    assert_eq!(mock.unsubscriptions(), 1);
+}
+
+#[test]
+fn test_watch_handle_resumes_when_speaker_returns() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(25));
+    let (system, _) = SonosSystem::connect(
+        ConnectOptions::default()
+            .devices(vec![device("RINCON_ATTIC", "Attic", mock.addr())])
+            .prefetch(false)
+            .subscribe(false),
+    )
+    .unwrap();
+    let attic_id = SpeakerId::new("RINCON_ATTIC");
+    let attic = system.speaker_by_id(&attic_id).unwrap();
+    let state = system.state_manager();
+
+    let _watch = attic.volume.watch().unwrap();
+    wait_for("subscription", || mock.subscriptions() == 1);
+    assert!(system.remove_speaker(&attic_id));
+    wait_for("unsubscribe", || mock.unsubscriptions() == 1);
+
+    // The handle held across the removal takes its subscription up again
+    let changes = system.iter();
+    changes.try_iter().for_each(drop);
+    let next_volume = || loop {
+        let event = changes
+            .recv_timeout(Duration::from_secs(5))
+            .expect("volume event");
+        if event.property_key == Volume::KEY {
+            return event;
+        }
+    };
+    state
+        .add_devices(vec![device("RINCON_ATTIC", "Attic", mock.addr())])
+        .unwrap();
+    wait_for("re-subscription", || mock.subscriptions() == 2);
+    assert!(state.is_watched(&attic_id, Volume::KEY));
+    assert_eq!(next_volume().origin, ChangeOrigin::Initial);
+
+    mock.perform(Action::SetVolume(40));
+    next_volume();
+    assert_eq!(state.get_property::<Volume>(&attic_id), Some(Volume(40)));
+}
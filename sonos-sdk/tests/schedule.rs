@@ -0,0 +1,149 @@
+//! Household schedule overview across several speakers
+//!
+//! Loopback mocks: `Den` coordinates `Hall`, `Loft` plays alone.
+//! `Den` runs a sleep timer, `Hall` autoplays into `Den`, and `Loft` has no
+//! sleep timer action, so its query fails. Alarms are switched on and off
+//! through a mock that rewrites its alarm list. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test schedule
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use chrono::{NaiveDateTime, NaiveTime};
+use sonos_api::services::alarm_clock::{Alarm, Recurrence};
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{next_occurrence, ScheduleQuery, SdkError, SonosSystem, SpeakerId};
+
+use common::{count, member, room_devices, system_for};
+
+const ALARMS: &str = r#"<Alarms><Alarm ID="4" StartTime="07:00:00" Duration="02:00:00" Recurrence="WEEKDAYS" Enabled="1" RoomUUID="RINCON_DEN" ProgramURI="x-rincon-buzzer:0" ProgramMetaData="" PlayMode="SHUFFLE_NOREPEAT" Volume="25" IncludeLinkedZones="1"/><Alarm ID="9" StartTime="09:30:00" Duration="01:00:00" Recurrence="ON_06" Enabled="0" RoomUUID="RINCON_LOFT" ProgramURI="" ProgramMetaData="" PlayMode="NORMAL" Volume="10" IncludeLinkedZones="0"/></Alarms>"#;
+
+#[test]
+fn test_scheduled_overview_degrades_to_partial_results() {
+    let den = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new()
+            .with_sleep_timer("0:30:00")
+            .with_alarm_list(ALARMS),
+    );
+    let hall = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new().with_autoplay("RINCON_DEN", 30),
+    );
+    let loft = MockDevice::start("127.0.0.1:0", Scenario::new().with_alarm_list(ALARMS));
+    let topology = format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_DEN" ID="RINCON_DEN:1">{}{}</ZoneGroup><ZoneGroup Coordinator="RINCON_LOFT" ID="RINCON_LOFT:1">{}</ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        member("Den", den.addr()),
+        member("Hall", hall.addr()),
+        member("Loft", loft.addr())
+    );
+    let rooms = [("Den", &den), ("Hall", &hall), ("Loft", &loft)];
+    for (_, mock) in rooms {
+        mock.set_zone_group_state(topology.clone());
+    }
+    let devices = room_devices(&rooms);
+    let system = SonosSystem::from_discovered_devices(devices).unwrap();
+
+    let overview = system.scheduled_overview();
+    let now = overview.generated_at;
+
+    assert_eq!(overview.sleep_timers.len(), 1);
+    let timer = &overview.sleep_timers[0];
+    assert_eq!(timer.source, SpeakerId::new("RINCON_DEN"));
+    assert_eq!(timer.group_id.as_str(), "RINCON_DEN:1");
+    assert_eq!(timer.remaining_secs, 1800);
+    assert_eq!(timer.ends_at, now + chrono::Duration::seconds(1800));
+
+    // Only the enabled alarm, with its next weekday morning
+    assert_eq!(overview.alarms.len(), 1);
+    let alarm = &overview.alarms[0];
+    assert_eq!(
+        (alarm.id.as_str(), alarm.room.as_str()),
+        ("4", "RINCON_DEN")
+    );
+    assert!(alarm.include_linked_zones);
+    assert_eq!(
+        alarm.next_fire,
+        next_occurrence(
+            Recurrence::Weekdays,
+            NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            now
+        )
+    );
+    assert!(alarm.next_fire.unwrap() > now);
+    assert_eq!(overview.next_alarm(), Some(alarm));
+
+    assert_eq!(overview.autoplay.len(), 1);
+    let autoplay = &overview.autoplay[0];
+    assert_eq!(autoplay.source, SpeakerId::new("RINCON_HALL"));
+    assert_eq!(
+        (autoplay.room.as_str(), autoplay.volume),
+        ("RINCON_DEN", 30)
+    );
+
+    // Loft's sleep timer query failed; everything else still came through
+    assert!(overview.partial);
+    assert_eq!(overview.failures.len(), 1);
+    let failure = &overview.failures[0];
+    assert_eq!(failure.source, SpeakerId::new("RINCON_LOFT"));
+    assert_eq!(failure.query, ScheduleQuery::SleepTimer);
+    assert!(!failure.error.is_empty());
+
+    let json = serde_json::to_value(&overview).unwrap();
+    let generated_at = json["generated_at"].as_str().unwrap();
+    assert_eq!(
+        generated_at.parse::<NaiveDateTime>().unwrap(),
+        overview.generated_at
+    );
+    assert_eq!(json["partial"], true);
+    assert_eq!(json["sleep_timers"][0]["source"], "RINCON_DEN");
+    assert_eq!(json["sleep_timers"][0]["group_id"], "RINCON_DEN:1");
+    assert_eq!(json["sleep_timers"][0]["remaining_secs"], 1800);
+    assert_eq!(json["alarms"][0]["recurrence"], "WEEKDAYS");
+    assert_eq!(json["alarms"][0]["start_time"], "07:00:00");
+    assert!(json["alarms"][0]["next_fire"].is_string());
+    assert_eq!(json["autoplay"][0]["room"], "RINCON_DEN");
+    assert_eq!(json["failures"][0]["query"], "sleep_timer");
+    assert_eq!(json["failures"][0]["source"], "RINCON_LOFT");
+}
+
+#[test]
+fn test_set_alarm_enabled_keeps_other_settings() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_alarm_list(ALARMS));
+    let system = system_for(mock.addr(), "Den");
+    let den = system.speaker("Den").unwrap();
+    let before = den.list_alarms().unwrap().alarms;
+
+    den.set_alarm_enabled("9", true).unwrap();
+    den.set_alarm_enabled("4", false).unwrap();
+    let after = den.list_alarms().unwrap().alarms;
+    assert_eq!(
+        after.iter().map(|a| a.enabled).collect::<Vec<_>>(),
+        [false, true]
+    );
+    for (before, after) in before.iter().zip(&after) {
+        assert_eq!(
+            Alarm {
+                enabled: before.enabled,
+                ..after.clone()
+            },
+            *before
+        );
+    }
+
+    let mut louder = after[1].clone();
+    louder.volume = 40;
+    den.update_alarm(&louder).unwrap();
+    assert_eq!(den.list_alarms().unwrap().alarms[1], louder);
+
+    // Unknown IDs are refused after the read, before anything is written
+    let updates = count(&mock, "UpdateAlarm");
+    assert!(matches!(
+        den.set_alarm_enabled("99", true),
+        Err(SdkError::AlarmNotFound(id)) if id == "99"
+    ));
+    assert_eq!(count(&mock, "UpdateAlarm"), updates);
+}
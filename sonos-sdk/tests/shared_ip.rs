@@ -0,0 +1,47 @@
+//! Two speakers behind one IP on different ports
+//!
+//! Two loopback mocks on 127.0.0.1, each on a port of its own, stand in
+//! for devices behind a port-forwarding bridge. Reads, writes and subscriptions must each reach
+//! the right port, and neither speaker's state may leak into the other's.
+//! Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test shared_ip
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{SonosSystem, Volume};
+
+use common::{device, wait_for};
+
+#[test]
+fn test_speakers_sharing_an_ip_stay_separate() {
+    let office = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(10));
+    let patio = MockDevice::start("127.0.0.1:0", Scenario::new().with_volume(70));
+    let system = SonosSystem::from_discovered_devices(vec![
+        device("RINCON_OFFICE", "Office", office.addr()),
+        device("RINCON_PATIO", "Patio", patio.addr()),
+    ])
+    .unwrap();
+    let office_speaker = system.speaker("Office").unwrap();
+    let patio_speaker = system.speaker("Patio").unwrap();
+    assert_eq!(office.addr().ip(), patio.addr().ip());
+    assert_eq!(patio_speaker.addr(), patio.addr());
+
+    assert_eq!(office_speaker.volume.fetch().unwrap(), Volume(10));
+    assert_eq!(patio_speaker.volume.fetch().unwrap(), Volume(70));
+
+    patio_speaker.set_volume(55).unwrap();
+    assert_eq!((office.volume(), patio.volume()), (10, 55));
+    assert_eq!(office_speaker.volume.get(), Some(Volume(10)));
+    assert_eq!(patio_speaker.volume.get(), Some(Volume(55)));
+
+    let _office_volume = office_speaker.volume.watch().unwrap();
+    let _patio_volume = patio_speaker.volume.watch().unwrap();
+    wait_for("one subscription per port", || {
+        office.subscriptions() == 1 && patio.subscriptions() == 1
+    });
+}
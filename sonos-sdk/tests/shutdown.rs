@@ -0,0 +1,127 @@
+//! `SonosSystem::shutdown()` draining events that race with it
+//!
+//! Loopback mock speakers send NOTIFY bursts right
+//! before shutdown; the drain must decode them into the store (and the
+//! persistence sink) unless its deadline cuts it short. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test shutdown
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sonos_api::Service;
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{ChangeEvent, Mute, Volume, WatchMode};
+use sonos_state::{DynamicValue, PersistedChange, PersistenceConfig, PersistenceSink, SinkError};
+
+use common::{system_for, wait_for};
+
+fn mute_notify(muted: bool) -> Action {
+    Action::Notify {
+        service: Service::RenderingControl,
+        body: format!(
+            r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/RCS/&quot;&gt;&lt;InstanceID val=&quot;0&quot;&gt;&lt;Mute channel=&quot;Master&quot; val=&quot;{}&quot;/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#,
+            u8::from(muted)
+        ),
+    }
+}
+
+struct MemorySink(Arc<Mutex<Vec<PersistedChange>>>);
+
+impl PersistenceSink for MemorySink {
+    fn write_batch(&mut self, batch: &[PersistedChange]) -> Result<(), SinkError> {
+        self.0.lock().unwrap().extend_from_slice(batch);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_shutdown_drains_events_racing_with_it() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system = system_for(mock.addr(), "Study");
+    let study = system.speaker("Study").unwrap();
+
+    let persisted = Arc::new(Mutex::new(Vec::new()));
+    // Long interval: only the shutdown flush writes anything
+    system.state_manager().add_persistence_sink(
+        Box::new(MemorySink(Arc::clone(&persisted))),
+        PersistenceConfig::default().flush_interval(Duration::from_secs(60)),
+    );
+
+    // Decoding lags behind the speaker by 20ms a mute change
+    system
+        .state_manager()
+        .add_change_observer(Arc::new(|event: &ChangeEvent| {
+            if event.property_key == "mute" {
+                thread::sleep(Duration::from_millis(20));
+            }
+        }));
+    assert_eq!(study.volume.fetch().unwrap(), Volume(25));
+    let _volume = study.volume.watch().unwrap();
+    let _mute = study.mute.watch().unwrap();
+    wait_for("subscription", || mock.live_subscriptions() == 1);
+
+    // The speaker's last burst is still being decoded when shutdown starts
+    for i in 0..11 {
+        mock.perform(mute_notify(i % 2 == 0));
+    }
+    mock.perform(Action::SetVolume(40));
+    let report = system.shutdown(Duration::from_secs(5)).unwrap();
+
+    assert!(report.events_drained > 0);
+    assert_eq!(report.events_abandoned, 0);
+    assert!(report.persistence_flushed);
+    assert_eq!(study.mute.get(), Some(Mute(true)));
+    assert_eq!(study.volume.get(), Some(Volume(40)));
+    // The volume step was still being coalesced; shutdown emitted it
+    let persisted = persisted.lock().unwrap();
+    let last = |key| {
+        persisted
+            .iter()
+            .rev()
+            .find(|c| c.property_key == key)
+            .unwrap()
+    };
+    assert_eq!(last("mute").value, DynamicValue::Bool(true));
+    assert_eq!(last("volume").value, DynamicValue::Int(40));
+    assert_eq!(mock.live_subscriptions(), 0, "unsubscribed");
+    // No new subscriptions: later watches fall back to polling
+    assert_eq!(study.bass.watch().unwrap().mode(), WatchMode::Polling);
+    assert_eq!(mock.subscriptions(), 1);
+}
+
+#[test]
+fn test_shutdown_deadline_bounds_slow_decoding() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system = system_for(mock.addr(), "Attic");
+    let attic = system.speaker("Attic").unwrap();
+
+    // Every mute change costs the decoder 100ms
+    system
+        .state_manager()
+        .add_change_observer(Arc::new(|event: &ChangeEvent| {
+            if event.property_key == "mute" {
+                thread::sleep(Duration::from_millis(100));
+            }
+        }));
+    let _mute = attic.mute.watch().unwrap();
+    wait_for("subscription", || mock.live_subscriptions() == 1);
+
+    for i in 0..20 {
+        mock.perform(mute_notify(i % 2 == 0));
+    }
+    let started = Instant::now();
+    let report = system.shutdown(Duration::from_millis(300)).unwrap();
+
+    // Decoding everything would take about 2s
+    assert!(started.elapsed() < Duration::from_millis(1500));
+    assert!(report.events_abandoned > 0);
+    assert!(report.events_drained + report.events_abandoned <= 20);
+    assert_eq!(mock.live_subscriptions(), 0);
+}
@@ -0,0 +1,64 @@
+//! Playback source and play mode read back from a mock
+//!
+//! One loopback mock remembers the transport URI and play mode it was
+//! last given. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test source
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{PlayMode, PlaybackSource, SonosSystem, SpeakerId};
+
+use common::model_device;
+
+#[test]
+fn test_playback_source_and_play_mode() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let system = SonosSystem::from_discovered_devices(vec![model_device(
+        "RINCON_BEAM",
+        "Den",
+        mock.addr(),
+        "Sonos Beam",
+    )])
+    .unwrap();
+    let den = system.speaker("Den").unwrap();
+
+    assert_eq!(den.playback_source().unwrap(), PlaybackSource::Idle);
+
+    for (uri, source) in [
+        (
+            "x-rincon-queue:RINCON_BEAM#0",
+            PlaybackSource::Queue {
+                owner: SpeakerId::new("RINCON_BEAM"),
+            },
+        ),
+        (
+            "x-sonos-htastream:RINCON_BEAM:spdif",
+            PlaybackSource::Tv {
+                source: SpeakerId::new("RINCON_BEAM"),
+            },
+        ),
+        (
+            "x-rincon:RINCON_ARC",
+            PlaybackSource::Grouped {
+                coordinator: SpeakerId::new("RINCON_ARC"),
+            },
+        ),
+    ] {
+        den.set_av_transport_uri(uri, "").unwrap();
+        assert_eq!(den.playback_source().unwrap(), source, "{uri}");
+    }
+
+    assert_eq!(
+        den.get_transport_settings().unwrap().mode(),
+        Some(PlayMode::Normal)
+    );
+    den.set_play_mode(PlayMode::ShuffleRepeatOne).unwrap();
+    let settings = den.get_transport_settings().unwrap();
+    assert_eq!(settings.play_mode, "SHUFFLE_REPEAT_ONE");
+    assert_eq!(settings.mode(), Some(PlayMode::ShuffleRepeatOne));
+}
@@ -0,0 +1,64 @@
+//! Status light and button lock kept current by DeviceProperties events
+//!
+//! A loopback mock `Den` notifies `LEDState` and `ButtonLockState` changes,
+//! both for its own echo of a write and for changes made elsewhere. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test status_light
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::time::Duration;
+
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{ButtonLock, ChangeEvent, ChangeOrigin, StatusLight, WatchMode};
+
+use common::{system_for, wait_for};
+
+fn changes(events: &[ChangeEvent], key: &str) -> Vec<ChangeOrigin> {
+    events
+        .iter()
+        .filter(|e| e.property_key == key && e.origin != ChangeOrigin::Initial)
+        .map(|e| e.origin)
+        .collect()
+}
+
+#[test]
+fn test_status_light_write_echo_and_external_changes() {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_button_lock(false));
+    let system = system_for(mock.addr(), "Den");
+    let den = system.speaker("Den").unwrap();
+    let iter = system.iter();
+
+    let watch = den.status_light.watch().unwrap();
+    assert_eq!(watch.mode(), WatchMode::Events);
+    let _lock_watch = den.button_lock.watch().unwrap();
+    wait_for("subscription", || mock.live_subscriptions() == 1);
+
+    // SDK write: stored at once, and the confirming event changes nothing
+    den.set_led(false).unwrap();
+    assert!(!mock.led());
+    assert_eq!(den.status_light.get(), Some(StatusLight(false)));
+    // The device confirms it once it has answered
+    mock.perform(Action::SetStatusLight(false));
+
+    // Events arrive in order, so once this lands the echo has been decoded
+    mock.perform(Action::SetButtonLock(true));
+    wait_for("button lock", || {
+        den.button_lock.get() == Some(ButtonLock(true))
+    });
+    let events: Vec<_> = iter.timeout_iter(Duration::from_millis(100)).collect();
+    assert_eq!(changes(&events, "status_light"), [ChangeOrigin::LocalWrite]);
+    assert_eq!(changes(&events, "button_lock"), [ChangeOrigin::External]);
+
+    // External change: event, store, watcher
+    mock.perform(Action::SetStatusLight(true));
+    let event = iter
+        .timeout_iter(Duration::from_secs(5))
+        .find(|e| e.property_key == "status_light")
+        .expect("status light change");
+    assert_eq!(event.origin, ChangeOrigin::External);
+    assert_eq!(den.status_light.get(), Some(StatusLight(true)));
+}
@@ -0,0 +1,86 @@
+//! `SonosSystem::suspend()` / `resume()` around a mock speaker restart
+//!
+//! A loopback mock answers SOAP calls and SUBSCRIBE / renewal /
+//! UNSUBSCRIBE, tracking which SIDs are live. Its scenario reboots it an
+//! hour in, forgetting every SID and coming back at a new volume, like a
+//! real device losing power while the host sleeps. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test suspend
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{ChangeEvent, RerenderScope, SonosSystem, SuspendPolicy, Volume};
+
+use common::{device, wait_for};
+
+#[test]
+fn test_suspend_resume_across_device_restart() {
+    let hour = Duration::from_secs(3600);
+    let mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new()
+            .at(hour, Action::Reboot)
+            .at(hour, Action::SetVolume(40)),
+    );
+    let system =
+        SonosSystem::from_discovered_devices(vec![device("RINCON_DEN", "Den", mock.addr())])
+            .unwrap();
+    let den = system.speaker("Den").unwrap();
+
+    let full_refreshes = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&full_refreshes);
+    system
+        .state_manager()
+        .add_change_observer(Arc::new(move |event: &ChangeEvent| {
+            if event.rerender_scope() == RerenderScope::Full {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        }));
+
+    assert_eq!(den.volume.fetch().unwrap(), Volume(25));
+    let _volume = den.volume.watch().unwrap();
+    let _playback = den.playback_state.watch().unwrap();
+    wait_for("both services subscribed", || {
+        mock.live_subscriptions() == 2
+    });
+
+    // Keep subscriptions, then lose them to a device restart while asleep
+    assert!(system.suspend(SuspendPolicy::KeepSubscriptions).unwrap());
+    assert!(!system.suspend(SuspendPolicy::KeepSubscriptions).unwrap());
+    mock.advance(hour);
+
+    // Wake hooks may fire more than once, from any thread
+    let resumed: usize = thread::scope(|scope| {
+        let workers: Vec<_> = (0..4).map(|_| scope.spawn(|| system.resume())).collect();
+        workers
+            .into_iter()
+            .map(|w| w.join().unwrap().unwrap() as usize)
+            .sum()
+    });
+    assert_eq!(resumed, 1);
+    assert_eq!(den.volume.get(), Some(Volume(40)), "state refreshed");
+    assert_eq!(
+        mock.live_subscriptions(),
+        2,
+        "lapsed SIDs replaced, not duplicated"
+    );
+    assert_eq!(mock.subscriptions(), 4);
+    assert_eq!(full_refreshes.load(Ordering::SeqCst), 1);
+
+    // Unsubscribe policy releases the device's subscriptions immediately
+    assert!(system.suspend(SuspendPolicy::Unsubscribe).unwrap());
+    assert_eq!(mock.live_subscriptions(), 0);
+    assert!(system.resume().unwrap());
+    assert_eq!(mock.live_subscriptions(), 2);
+    assert_eq!(mock.subscriptions(), 6);
+    assert_eq!(full_refreshes.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,71 @@
+//! Mute and play/pause toggles racing another controller
+//!
+//! Loopback mocks play the other controller: its change lands
+//! either between the toggle's read and write (through a write
+//! interceptor) or right after the write (a scenario reaction). Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test toggle
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use sonos_sdk::mock::{Action, MockDevice, Scenario};
+use sonos_sdk::{InterceptDecision, Mute, PlaybackState};
+
+use common::{count, system_for};
+
+#[test]
+fn test_mute_toggle_rewrites_when_another_controller_undoes_it() {
+    // The other controller unmutes right after the first SetMute
+    let mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new()
+            .with_mute(false)
+            .after_action("SetMute", Action::SetMute(false)),
+    );
+    let system = system_for(mock.addr(), "Loft");
+    let loft = system.speaker("Loft").unwrap();
+
+    assert_eq!(loft.mute.toggle().unwrap(), Mute(true));
+    assert!(mock.mute());
+    assert_eq!(count(&mock, "SetMute"), 2);
+    assert_eq!(loft.mute.get(), Some(Mute(true)));
+
+    // Uncontested, one write does it
+    assert_eq!(loft.mute.toggle().unwrap(), Mute(false));
+    assert_eq!(count(&mock, "SetMute"), 3);
+}
+
+#[test]
+fn test_playback_toggle_converges_on_intent() {
+    let mock = MockDevice::start(
+        "127.0.0.1:0",
+        Scenario::new()
+            .with_transport_state("PLAYING")
+            .after_action("Play", Action::SetTransportState("PAUSED_PLAYBACK".into())),
+    );
+    let system = system_for(mock.addr(), "Loft");
+    let loft = system.speaker("Loft").unwrap();
+
+    // The other controller pauses between our read and our write: pausing
+    // again still leaves it paused, as intended
+    let raced = AtomicBool::new(false);
+    let other = mock.clone();
+    system.add_write_interceptor(move |_| {
+        if !raced.swap(true, Ordering::SeqCst) {
+            other.perform(Action::SetTransportState("PAUSED_PLAYBACK".into()));
+        }
+        InterceptDecision::Allow
+    });
+    assert_eq!(loft.toggle_playback().unwrap(), PlaybackState::Paused);
+    assert_eq!(count(&mock, "Pause"), 1);
+
+    // The other controller pauses right after our Play; we play again
+    assert_eq!(loft.toggle_playback().unwrap(), PlaybackState::Playing);
+    assert_eq!(mock.transport_state(), "PLAYING");
+    assert_eq!(count(&mock, "Play"), 2);
+}
@@ -0,0 +1,86 @@
+//! Renewal, staleness polling and unwatch linger on one virtual clock
+//!
+//! A loopback mock shares a `ManualClock` with the system, so a
+//! half-hour subscription lifetime runs in about a second. The clock is
+//! stepped in small increments with short real pauses so the background
+//! loops observe each step. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test virtual_time
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{ConnectOptions, ManualClock, SonosSystem};
+
+use common::{device, wait_for};
+
+/// Advance `clock` by `total` in `step`s, letting the loops catch up
+fn step(clock: &ManualClock, total: Duration, step: Duration) {
+    let mut elapsed = Duration::ZERO;
+    while elapsed < total {
+        clock.advance(step);
+        elapsed += step;
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn gets(mock: &MockDevice) -> usize {
+    mock.actions()
+        .iter()
+        .filter(|action| *action == "GetVolume")
+        .count()
+}
+
+#[test]
+fn test_renewal_staleness_and_linger_follow_the_clock() {
+    let clock = ManualClock::new();
+    let mock = MockDevice::start_with_clock(
+        "127.0.0.1:0",
+        Scenario::new().with_volume(25),
+        clock.clone(),
+    );
+    mock.set_zone_group_state(format!(
+        r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_STUDY" ID="RINCON_STUDY:1"><ZoneGroupMember UUID="RINCON_STUDY" Location="http://{}/xml/device_description.xml" ZoneName="Study"/></ZoneGroup></ZoneGroups></ZoneGroupState>"#,
+        mock.addr()
+    ));
+    let (system, _) = SonosSystem::connect(
+        ConnectOptions::default()
+            .devices(vec![device("RINCON_STUDY", "Study", mock.addr())])
+            .prefetch(false)
+            .subscribe(false)
+            .clock(Arc::new(clock.clone())),
+    )
+    .unwrap();
+    let study = system.speaker("Study").unwrap();
+
+    let watch = study.volume.watch().unwrap();
+    wait_for("subscription", || mock.subscriptions() > 0);
+
+    // Real time alone neither renews nor polls
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(mock.renewals(), 0);
+    assert_eq!(gets(&mock), 0);
+
+    // The mock never sends NOTIFY, so the event timeout starts polling
+    step(&clock, Duration::from_secs(60), Duration::from_secs(5));
+    wait_for("staleness polling", || gets(&mock) > 0);
+
+    // The granted 1800s lifetime reaches the renewal threshold
+    step(&clock, Duration::from_secs(1500), Duration::from_secs(10));
+    wait_for("renewal", || mock.renewals() > 0);
+    assert_eq!(mock.unsubscriptions(), 0);
+
+    // Dropping the last watch lingers for the grace period on the clock
+    drop(watch);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(mock.unsubscriptions(), 0);
+    clock.advance(Duration::from_secs(1));
+    wait_for("linger to expire", || mock.unsubscriptions() > 0);
+}
@@ -0,0 +1,185 @@
+//! Hostile XML against every network-facing parser
+//!
+//! A DTD with an external entity, a nesting bomb and an attribute flood go
+//! through SOAP responses, NOTIFY bodies, DIDL-Lite and device descriptions.
+//! Each must be refused as an `XmlViolation`, not a parse error, and without
+//! the memory a parser would spend building it; a counting allocator tracks
+//! the calling thread's peak. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test xml_hardening
+//! ```
+#![cfg(feature = "test-support")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+
+use sonos_api::events::xml_utils::{self, DidlLite};
+use sonos_api::events::EventProcessor;
+use sonos_api::services::rendering_control;
+use sonos_api::{ApiError, Service, SonosClient, XmlViolation};
+use sonos_sdk::sonos_discovery::device::DeviceDescription;
+use sonos_sdk::sonos_discovery::DiscoveryError;
+
+/// Counts the live bytes allocated by each thread and their peak
+struct Tracking;
+
+thread_local! {
+    static LIVE: Cell<usize> = const { Cell::new(0) };
+    static PEAK: Cell<usize> = const { Cell::new(0) };
+}
+
+fn grow(bytes: usize) {
+    let _ = LIVE.try_with(|live| {
+        live.set(live.get() + bytes);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+    });
+}
+
+fn shrink(bytes: usize) {
+    let _ = LIVE.try_with(|live| live.set(live.get().saturating_sub(bytes)));
+}
+
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            shrink(layout.size());
+            grow(new_size);
+        }
+        new
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Tracking = Tracking;
+
+/// Run `f`, asserting this thread's allocations never grew past `limit`
+fn within<R>(limit: usize, f: impl FnOnce() -> R) -> R {
+    let base = LIVE.with(Cell::get);
+    PEAK.with(|peak| peak.set(base));
+    let result = f();
+    let growth = PEAK.with(Cell::get) - base;
+    assert!(growth <= limit, "grew by {growth} bytes, limit {limit}");
+    result
+}
+
+/// Generous for a scan, far short of building the document
+fn budget(payload: &str) -> usize {
+    3 * payload.len() + 256 * 1024
+}
+
+/// Whether a rejection is the one a payload must raise
+type Expected = fn(&XmlViolation) -> bool;
+
+/// The three hostile documents with a `root` element, and the violation each
+/// must raise
+fn payloads(root: &str) -> [(String, Expected); 3] {
+    let dtd = format!(
+        r#"<?xml version="1.0"?><!DOCTYPE r [<!ENTITY x SYSTEM "file:///etc/passwd">]><{root}>&x;</{root}>"#
+    );
+    let bomb = format!(
+        "<{root}>{}{}</{root}>",
+        "<a>".repeat(200_000),
+        "</a>".repeat(200_000)
+    );
+    let flood: String = (0..50_000).map(|i| format!(r#" a{i}="x""#)).collect();
+    let flood = format!("<{root}{flood}/>");
+    [
+        (dtd, |v| matches!(v, XmlViolation::Doctype)),
+        (bomb, |v| matches!(v, XmlViolation::TooDeep { .. })),
+        (flood, |v| {
+            matches!(v, XmlViolation::AttributeTooLarge { .. })
+        }),
+    ]
+}
+
+/// Serve `body` as the answer to one HTTP request
+fn respond_once(body: String) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut chunk = [0; 4096];
+        // The SOAP request is small; read its head and body before replying
+        while !String::from_utf8_lossy(&request).contains("</s:Envelope>") {
+            let n = stream.read(&mut chunk).unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&chunk[..n]);
+        }
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        let _ = stream.write_all(head.as_bytes());
+        let _ = stream.write_all(body.as_bytes());
+    });
+    addr
+}
+
+#[test]
+fn test_soap_responses_are_refused() {
+    let client = SonosClient::new();
+    for (payload, expected) in payloads("s:Envelope") {
+        let limit = budget(&payload);
+        let addr = respond_once(payload);
+        let op = rendering_control::get_volume("Master".to_string())
+            .build()
+            .unwrap();
+        let result = within(limit, || client.execute_enhanced(&addr.to_string(), op));
+        assert!(
+            matches!(&result, Err(ApiError::XmlRejected(v)) if expected(v)),
+            "{result:?}"
+        );
+    }
+}
+
+#[test]
+fn test_notify_bodies_are_refused() {
+    let processor = EventProcessor::new();
+    let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    for (payload, expected) in payloads("e:propertyset") {
+        for service in [Service::AVTransport, Service::RenderingControl] {
+            let result = within(budget(&payload), || {
+                processor.process_upnp_event(ip, service, "uuid:sub".to_string(), &payload)
+            });
+            assert!(matches!(&result, Err(ApiError::XmlRejected(v)) if expected(v)));
+        }
+        let last_change = within(budget(&payload), || xml_utils::last_change(&payload));
+        assert!(matches!(&last_change, Err(ApiError::XmlRejected(v)) if expected(v)));
+    }
+}
+
+#[test]
+fn test_didl_lite_is_refused() {
+    for (payload, expected) in payloads("DIDL-Lite") {
+        let result = within(budget(&payload), || DidlLite::from_xml(&payload));
+        assert!(matches!(&result, Err(ApiError::XmlRejected(v)) if expected(v)));
+    }
+}
+
+#[test]
+fn test_device_descriptions_are_refused() {
+    for (payload, expected) in payloads("root") {
+        let result = within(budget(&payload), || DeviceDescription::from_xml(&payload));
+        assert!(matches!(&result, Err(DiscoveryError::XmlRejected(v)) if expected(v)));
+    }
+}
@@ -0,0 +1,76 @@
+//! Renaming rooms and switching the status LED
+//!
+//! A single loopback mock `Den` serving its zone attributes. Run with:
+//!
+//! ```bash
+//! cargo test -p sonos-sdk --features test-support --test zone_attributes
+//! ```
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use sonos_sdk::mock::{MockDevice, Scenario};
+use sonos_sdk::{SdkError, SonosSystem, SpeakerId};
+
+use common::device;
+
+fn start_den() -> (SonosSystem, MockDevice) {
+    let mock = MockDevice::start("127.0.0.1:0", Scenario::new().with_zone_name("Den"));
+    let system =
+        SonosSystem::from_discovered_devices(vec![device("RINCON_DEN", "Den", mock.addr())])
+            .unwrap();
+    (system, mock)
+}
+
+#[test]
+fn test_rename_keeps_icon_and_updates_store() {
+    let (system, mock) = start_den();
+    let den = system.speaker("Den").unwrap();
+
+    let attributes = den.zone_attributes().unwrap();
+    assert_eq!(attributes.current_zone_name, "Den");
+
+    den.rename("Study & Den").unwrap();
+
+    assert_eq!(mock.zone_name().as_deref(), Some("Study & Den"));
+    let body = mock.calls().pop().unwrap().1;
+    assert!(body.contains("<DesiredZoneName>Study &amp; Den</DesiredZoneName>"));
+    assert!(body.contains(&format!(
+        "<DesiredIcon>{}</DesiredIcon>",
+        attributes.current_icon
+    )));
+    let info = system
+        .state_manager()
+        .speaker_info(&SpeakerId::new("RINCON_DEN"))
+        .unwrap();
+    assert_eq!(info.room_name, "Study & Den");
+
+    // The system's name lookup follows
+    assert_eq!(system.speaker("Study & Den").unwrap().id, den.id);
+    assert_eq!(system.speaker("Study & Den").unwrap().name, "Study & Den");
+    assert!(system.speakers_by_name("Den").is_empty());
+}
+
+#[test]
+fn test_blank_rename_sends_nothing() {
+    let (system, mock) = start_den();
+    let den = system.speaker("Den").unwrap();
+    let start = mock.actions().len();
+
+    let err = den.rename("  ").unwrap_err();
+
+    assert!(matches!(err, SdkError::ValidationFailed(_)), "{err:?}");
+    assert_eq!(mock.actions().len(), start);
+    assert_eq!(mock.zone_name().as_deref(), Some("Den"));
+}
+
+#[test]
+fn test_led_round_trip() {
+    let (system, mock) = start_den();
+    let den = system.speaker("Den").unwrap();
+
+    assert!(den.led_state().unwrap());
+    den.set_led(false).unwrap();
+    assert!(!mock.led());
+    assert!(!den.led_state().unwrap());
+}
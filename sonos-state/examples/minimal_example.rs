@@ -49,8 +49,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  Topology initialized");
 
     let speaker_id = SpeakerId::new(&devices[0].id);
-    let speaker_ip: std::net::IpAddr = devices[0].ip_address.parse()?;
-    println!("Using speaker: {} ({})", devices[0].name, speaker_ip);
+    let speaker_addr = std::net::SocketAddr::new(devices[0].ip_address.parse()?, devices[0].port);
+    println!("Using speaker: {} ({})", devices[0].name, speaker_addr);
 
     println!("\n4. Getting current values (from cache)...");
     if let Some(vol) = manager.get_property::<Volume>(&speaker_id) {
@@ -63,12 +63,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Watch speaker volume: register watch + subscribe to RenderingControl
     manager.register_watch(&speaker_id, Volume::KEY);
-    event_manager.ensure_service_subscribed(speaker_ip, Volume::SERVICE)?;
+    event_manager.ensure_service_subscribed(speaker_addr, Volume::SERVICE)?;
     println!("  Watching speaker volume (RenderingControl)");
 
     // Watch group volume: register watch + subscribe to GroupRenderingControl
     manager.register_watch(&speaker_id, GroupVolume::KEY);
-    event_manager.ensure_service_subscribed(speaker_ip, GroupVolume::SERVICE)?;
+    event_manager.ensure_service_subscribed(speaker_addr, GroupVolume::SERVICE)?;
     println!("  Watching group volume (GroupRenderingControl)");
 
     println!("  (Change volume within 30s to see events)");
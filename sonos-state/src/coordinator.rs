@@ -0,0 +1,237 @@
+//! Choosing the speaker a group command goes to
+//!
+//! Commands for a group (transport controls, group volume) must reach its
+//! coordinator, which the store knows from the last ZoneGroupTopology event.
+//! While a group is being reshaped devices send several of those in quick
+//! succession, and a command routed on the first may reach a speaker that
+//! just stopped coordinating. [`CoordinatorResolver::route()`] waits until a
+//! speaker's group has been quiet for the settle delay before picking its
+//! coordinator. Each route carries the topology generation it was read
+//! from, so a command the speaker refuses can tell whether the store has
+//! moved on since.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use sonos_api::clock::SharedClock;
+
+use crate::model::SpeakerId;
+use crate::state::StateStore;
+
+/// How long a group must go without topology changes before commands are
+/// routed to its coordinator
+pub const DEFAULT_SETTLE_DELAY: Duration = Duration::from_millis(250);
+
+/// Most settle delays one route waits through while its group keeps
+/// changing
+const MAX_SETTLE_ROUNDS: u32 = 4;
+
+/// Where a group command is sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoordinatorRoute {
+    /// The group's coordinator
+    pub coordinator_id: SpeakerId,
+    /// Its address
+    pub addr: SocketAddr,
+    /// [`StateManager::topology_generation()`](crate::StateManager::topology_generation)
+    /// the route was read at
+    pub generation: u64,
+}
+
+/// Counters of a [`CoordinatorResolver`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoutingStats {
+    /// Routes delayed because their group changed within the settle delay
+    pub settle_waits: u64,
+    /// Topology refreshes after a speaker refused a command as not the
+    /// coordinator
+    pub refreshes: u64,
+    /// Commands sent again to another coordinator after such a refusal
+    pub reroutes: u64,
+}
+
+/// Resolves group commands to coordinators, shared by a
+/// [`StateManager`](crate::StateManager)
+///
+/// Obtained via [`StateManager::coordinator_resolver()`](crate::StateManager::coordinator_resolver).
+pub struct CoordinatorResolver {
+    store: Arc<RwLock<StateStore>>,
+    clock: SharedClock,
+    settle_delay: Mutex<Duration>,
+    settle_waits: AtomicU64,
+    refreshes: AtomicU64,
+    reroutes: AtomicU64,
+}
+
+impl CoordinatorResolver {
+    pub(crate) fn new(store: Arc<RwLock<StateStore>>, clock: SharedClock) -> Self {
+        Self {
+            store,
+            clock,
+            settle_delay: Mutex::new(DEFAULT_SETTLE_DELAY),
+            settle_waits: AtomicU64::new(0),
+            refreshes: AtomicU64::new(0),
+            reroutes: AtomicU64::new(0),
+        }
+    }
+
+    /// How long a group must be quiet before [`route()`](Self::route) picks
+    /// its coordinator
+    pub fn settle_delay(&self) -> Duration {
+        *self
+            .settle_delay
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Change the settle delay; `Duration::ZERO` routes without waiting
+    pub fn set_settle_delay(&self, delay: Duration) {
+        *self
+            .settle_delay
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = delay;
+    }
+
+    /// Coordinator of `speaker_id`'s group as the store has it now
+    ///
+    /// `None` if the speaker is in no known group or its coordinator has no
+    /// address.
+    pub fn current(&self, speaker_id: &SpeakerId) -> Option<CoordinatorRoute> {
+        let store = self.store.read();
+        let coordinator_id = store
+            .get_group_for_speaker(speaker_id)?
+            .coordinator_id
+            .clone();
+        let addr = store.speakers.get(&coordinator_id)?.socket_addr();
+        Some(CoordinatorRoute {
+            coordinator_id,
+            addr,
+            generation: store.topology_generation,
+        })
+    }
+
+    /// Coordinator of `speaker_id`'s group, once the group has gone the
+    /// settle delay without a topology change
+    ///
+    /// Blocks while the group is changing, up to a few settle delays if
+    /// changes keep arriving.
+    pub fn route(&self, speaker_id: &SpeakerId) -> Option<CoordinatorRoute> {
+        let delay = self.settle_delay();
+        for round in 0..MAX_SETTLE_ROUNDS {
+            let Some(changed) = self.store.read().regrouped_at.get(speaker_id).copied() else {
+                break;
+            };
+            let quiet = self.clock.now().saturating_duration_since(changed);
+            if quiet >= delay {
+                break;
+            }
+            if round == 0 {
+                self.settle_waits.fetch_add(1, Ordering::Relaxed);
+            }
+            tracing::debug!(
+                "Group of {} changed {:?} ago; waiting for it to settle",
+                speaker_id.as_str(),
+                quiet
+            );
+            self.clock.sleep(delay - quiet);
+        }
+        self.current(speaker_id)
+    }
+
+    /// Count a topology refresh made because a command was refused
+    pub fn note_refresh(&self) {
+        self.refreshes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a command sent again to another coordinator
+    pub fn note_reroute(&self) {
+        self.reroutes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counters since the state manager was built
+    pub fn stats(&self) -> RoutingStats {
+        RoutingStats {
+            settle_waits: self.settle_waits.load(Ordering::Relaxed),
+            refreshes: self.refreshes.load(Ordering::Relaxed),
+            reroutes: self.reroutes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GroupId, SpeakerInfo};
+    use crate::property::GroupInfo;
+    use sonos_api::clock::{Clock, ManualClock};
+    use std::thread;
+
+    fn speaker(id: &str, port: u16) -> SpeakerInfo {
+        SpeakerInfo {
+            id: SpeakerId::new(id),
+            name: id.to_string(),
+            room_name: id.to_string(),
+            ip_address: "127.0.0.1".parse().unwrap(),
+            port,
+            model_name: "Sonos One".to_string(),
+            software_version: "unknown".to_string(),
+            software_generation: None,
+            boot_seq: 0,
+            satellites: vec![],
+            household_id: None,
+        }
+    }
+
+    #[test]
+    fn test_route_waits_for_recent_regroup() {
+        let clock = ManualClock::new();
+        let mut store = StateStore::new();
+        store.create_entity(speaker("RINCON_A", 1400));
+        store.create_entity(speaker("RINCON_B", 1401));
+        store.add_group(GroupInfo::new(
+            GroupId::new("RINCON_B:1"),
+            SpeakerId::new("RINCON_B"),
+            vec![SpeakerId::new("RINCON_B"), SpeakerId::new("RINCON_A")],
+        ));
+        store.topology_generation = 3;
+        let store = Arc::new(RwLock::new(store));
+        let resolver = CoordinatorResolver::new(Arc::clone(&store), Arc::new(clock.clone()));
+        let a = SpeakerId::new("RINCON_A");
+
+        // Never regrouped: routed at once
+        let route = resolver.route(&a).unwrap();
+        assert_eq!(route.coordinator_id, SpeakerId::new("RINCON_B"));
+        assert_eq!(route.addr, "127.0.0.1:1401".parse().unwrap());
+        assert_eq!(route.generation, 3);
+        assert_eq!(resolver.stats().settle_waits, 0);
+
+        // Regrouped just now: the route waits out the rest of the delay
+        store.write().regrouped_at.insert(a.clone(), clock.now());
+        clock.advance(Duration::from_millis(100));
+        let waiting = {
+            let resolver = Arc::new(resolver);
+            let routing = Arc::clone(&resolver);
+            let handle = thread::spawn(move || routing.route(&SpeakerId::new("RINCON_A")));
+            while clock.pending() == 0 {
+                thread::sleep(Duration::from_millis(1));
+            }
+            assert_eq!(
+                clock.next_deadline(),
+                Some(clock.now() + Duration::from_millis(150))
+            );
+            clock.advance(Duration::from_millis(150));
+            assert!(handle.join().unwrap().is_some());
+            resolver
+        };
+        assert_eq!(waiting.stats().settle_waits, 1);
+        assert_eq!(waiting.current(&SpeakerId::new("RINCON_C")), None);
+
+        waiting.set_settle_delay(Duration::ZERO);
+        store.write().regrouped_at.insert(a.clone(), clock.now());
+        assert!(waiting.route(&a).is_some());
+        assert_eq!(waiting.stats().settle_waits, 1);
+    }
+}
@@ -2,19 +2,30 @@
 //!
 //! This module decodes raw events from sonos-stream into typed property
 //! changes that can be applied to the StateStore.
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::string_slice
+)]
 
 use sonos_api::Service;
 use sonos_stream::events::{
-    AVTransportState, EnrichedEvent, EventData, GroupRenderingControlState, RenderingControlState,
-    ZoneGroupTopologyState,
+    AVTransportState, DevicePropertiesEvent, EnrichedEvent, EventData, GroupRenderingControlState,
+    RenderingControlState, ZoneGroupTopologyState,
 };
 
-use std::net::IpAddr;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
 
 use crate::model::{GroupId, SpeakerId};
+use crate::origin::ChangeOrigin;
 use crate::property::{
-    Bass, CurrentTrack, GroupInfo, GroupMembership, GroupMute, GroupVolume, GroupVolumeChangeable,
-    Loudness, Mute, PlaybackState, Position, Treble, Volume,
+    AudioOutput, Bass, ButtonLock, CurrentPlayMode, CurrentTrack, GroupInfo, GroupMembership,
+    GroupMute, GroupVolume, GroupVolumeChangeable, Loudness, Mute, OutputFixed, PlaybackState,
+    Position, Presets, StatusLight, SubEnabled, SubGain, SurroundEnabled, SurroundLevel,
+    SurroundMode, TransportActions, Treble, Volume,
 };
 use crate::state::StateStore;
 
@@ -25,6 +36,9 @@ pub struct DecodedChanges {
     pub speaker_id: SpeakerId,
     /// List of property changes
     pub changes: Vec<PropertyChange>,
+    /// Room name reported by a DeviceProperties event, kept on the
+    /// speaker's [`SpeakerInfo`](crate::SpeakerInfo) rather than as a property
+    pub room_name: Option<String>,
 }
 
 /// Changes extracted from a ZoneGroupTopology event
@@ -40,10 +54,14 @@ pub struct TopologyChanges {
     pub memberships: Vec<(SpeakerId, GroupMembership)>,
     /// Boot sequence numbers per speaker (for GroupManagement AddMember)
     pub boot_seqs: Vec<(SpeakerId, u32)>,
-    /// Current IPs extracted from topology location URLs
-    pub speaker_ips: Vec<(SpeakerId, IpAddr)>,
+    /// Firmware version and software generation per speaker that reports them
+    pub software: Vec<(SpeakerId, String, Option<u8>)>,
+    /// Current addresses (IP and port) extracted from topology location URLs
+    pub speaker_addrs: Vec<(SpeakerId, SocketAddr)>,
     /// Speakers marked Invisible="1" (satellites: surrounds, subs)
     pub satellite_ids: Vec<SpeakerId>,
+    /// Home-theater bonds: (primary, its invisible satellites)
+    pub bonds: Vec<(SpeakerId, Vec<SpeakerId>)>,
 }
 
 /// A single property change
@@ -54,9 +72,21 @@ pub enum PropertyChange {
     Bass(Bass),
     Treble(Treble),
     Loudness(Loudness),
+    OutputFixed(OutputFixed),
+    Presets(Presets),
+    SubEnabled(SubEnabled),
+    SubGain(SubGain),
+    SurroundEnabled(SurroundEnabled),
+    SurroundMode(SurroundMode),
+    SurroundLevel(SurroundLevel),
+    ButtonLock(ButtonLock),
+    StatusLight(StatusLight),
+    AudioOutput(AudioOutput),
     PlaybackState(PlaybackState),
     Position(Position),
     CurrentTrack(CurrentTrack),
+    TransportActions(TransportActions),
+    CurrentPlayMode(CurrentPlayMode),
     GroupMembership(GroupMembership),
     GroupVolume(GroupVolume),
     GroupMute(GroupMute),
@@ -70,36 +100,53 @@ impl PropertyChange {
     /// group-scoped properties resolve speaker→group and store in `group_props`.
     ///
     /// Returns `true` if the value actually changed.
-    pub fn apply(&self, store: &mut StateStore, speaker_id: &SpeakerId) -> bool {
+    pub fn apply(
+        &self,
+        store: &mut StateStore,
+        speaker_id: &SpeakerId,
+        origin: ChangeOrigin,
+    ) -> bool {
         match self {
             // Speaker-scoped properties
-            PropertyChange::Volume(v) => store.set(speaker_id, v.clone()),
-            PropertyChange::Mute(v) => store.set(speaker_id, v.clone()),
-            PropertyChange::Bass(v) => store.set(speaker_id, v.clone()),
-            PropertyChange::Treble(v) => store.set(speaker_id, v.clone()),
-            PropertyChange::Loudness(v) => store.set(speaker_id, v.clone()),
-            PropertyChange::PlaybackState(v) => store.set(speaker_id, v.clone()),
-            PropertyChange::Position(v) => store.set(speaker_id, v.clone()),
-            PropertyChange::CurrentTrack(v) => store.set(speaker_id, v.clone()),
-            PropertyChange::GroupMembership(v) => store.set(speaker_id, v.clone()),
+            PropertyChange::Volume(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::Mute(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::Bass(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::Treble(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::Loudness(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::OutputFixed(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::Presets(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::SubEnabled(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::SubGain(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::SurroundEnabled(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::SurroundMode(v) => store.set_tracked(speaker_id, *v, origin),
+            PropertyChange::SurroundLevel(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::ButtonLock(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::StatusLight(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::AudioOutput(v) => store.set_tracked(speaker_id, *v, origin),
+            PropertyChange::PlaybackState(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::Position(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::CurrentTrack(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::TransportActions(v) => store.set_tracked(speaker_id, v.clone(), origin),
+            PropertyChange::CurrentPlayMode(v) => store.set_tracked(speaker_id, *v, origin),
+            PropertyChange::GroupMembership(v) => store.set_tracked(speaker_id, v.clone(), origin),
             // Group-scoped properties: resolve speaker→group, store in group_props
             PropertyChange::GroupVolume(v) => {
                 if let Some(group_id) = store.speaker_to_group.get(speaker_id).cloned() {
-                    store.set_group(&group_id, v.clone())
+                    store.set_group_tracked(&group_id, v.clone(), origin)
                 } else {
                     false
                 }
             }
             PropertyChange::GroupMute(v) => {
                 if let Some(group_id) = store.speaker_to_group.get(speaker_id).cloned() {
-                    store.set_group(&group_id, v.clone())
+                    store.set_group_tracked(&group_id, v.clone(), origin)
                 } else {
                     false
                 }
             }
             PropertyChange::GroupVolumeChangeable(v) => {
                 if let Some(group_id) = store.speaker_to_group.get(speaker_id).cloned() {
-                    store.set_group(&group_id, v.clone())
+                    store.set_group_tracked(&group_id, v.clone(), origin)
                 } else {
                     false
                 }
@@ -116,9 +163,21 @@ impl PropertyChange {
             PropertyChange::Bass(_) => Bass::KEY,
             PropertyChange::Treble(_) => Treble::KEY,
             PropertyChange::Loudness(_) => Loudness::KEY,
+            PropertyChange::OutputFixed(_) => OutputFixed::KEY,
+            PropertyChange::Presets(_) => Presets::KEY,
+            PropertyChange::SubEnabled(_) => SubEnabled::KEY,
+            PropertyChange::SubGain(_) => SubGain::KEY,
+            PropertyChange::SurroundEnabled(_) => SurroundEnabled::KEY,
+            PropertyChange::SurroundMode(_) => SurroundMode::KEY,
+            PropertyChange::SurroundLevel(_) => SurroundLevel::KEY,
+            PropertyChange::ButtonLock(_) => ButtonLock::KEY,
+            PropertyChange::StatusLight(_) => StatusLight::KEY,
+            PropertyChange::AudioOutput(_) => AudioOutput::KEY,
             PropertyChange::PlaybackState(_) => PlaybackState::KEY,
             PropertyChange::Position(_) => Position::KEY,
             PropertyChange::CurrentTrack(_) => CurrentTrack::KEY,
+            PropertyChange::TransportActions(_) => TransportActions::KEY,
+            PropertyChange::CurrentPlayMode(_) => CurrentPlayMode::KEY,
             PropertyChange::GroupMembership(_) => GroupMembership::KEY,
             PropertyChange::GroupVolume(_) => GroupVolume::KEY,
             PropertyChange::GroupMute(_) => GroupMute::KEY,
@@ -135,9 +194,21 @@ impl PropertyChange {
             PropertyChange::Bass(_) => Bass::SCOPE,
             PropertyChange::Treble(_) => Treble::SCOPE,
             PropertyChange::Loudness(_) => Loudness::SCOPE,
+            PropertyChange::OutputFixed(_) => OutputFixed::SCOPE,
+            PropertyChange::Presets(_) => Presets::SCOPE,
+            PropertyChange::SubEnabled(_) => SubEnabled::SCOPE,
+            PropertyChange::SubGain(_) => SubGain::SCOPE,
+            PropertyChange::SurroundEnabled(_) => SurroundEnabled::SCOPE,
+            PropertyChange::SurroundMode(_) => SurroundMode::SCOPE,
+            PropertyChange::SurroundLevel(_) => SurroundLevel::SCOPE,
+            PropertyChange::ButtonLock(_) => ButtonLock::SCOPE,
+            PropertyChange::StatusLight(_) => StatusLight::SCOPE,
+            PropertyChange::AudioOutput(_) => AudioOutput::SCOPE,
             PropertyChange::PlaybackState(_) => PlaybackState::SCOPE,
             PropertyChange::Position(_) => Position::SCOPE,
             PropertyChange::CurrentTrack(_) => CurrentTrack::SCOPE,
+            PropertyChange::TransportActions(_) => TransportActions::SCOPE,
+            PropertyChange::CurrentPlayMode(_) => CurrentPlayMode::SCOPE,
             PropertyChange::GroupMembership(_) => GroupMembership::SCOPE,
             PropertyChange::GroupVolume(_) => GroupVolume::SCOPE,
             PropertyChange::GroupMute(_) => GroupMute::SCOPE,
@@ -154,9 +225,21 @@ impl PropertyChange {
             PropertyChange::Bass(_) => Bass::SERVICE,
             PropertyChange::Treble(_) => Treble::SERVICE,
             PropertyChange::Loudness(_) => Loudness::SERVICE,
+            PropertyChange::OutputFixed(_) => OutputFixed::SERVICE,
+            PropertyChange::Presets(_) => Presets::SERVICE,
+            PropertyChange::SubEnabled(_) => SubEnabled::SERVICE,
+            PropertyChange::SubGain(_) => SubGain::SERVICE,
+            PropertyChange::SurroundEnabled(_) => SurroundEnabled::SERVICE,
+            PropertyChange::SurroundMode(_) => SurroundMode::SERVICE,
+            PropertyChange::SurroundLevel(_) => SurroundLevel::SERVICE,
+            PropertyChange::ButtonLock(_) => ButtonLock::SERVICE,
+            PropertyChange::StatusLight(_) => StatusLight::SERVICE,
+            PropertyChange::AudioOutput(_) => AudioOutput::SERVICE,
             PropertyChange::PlaybackState(_) => PlaybackState::SERVICE,
             PropertyChange::Position(_) => Position::SERVICE,
             PropertyChange::CurrentTrack(_) => CurrentTrack::SERVICE,
+            PropertyChange::TransportActions(_) => TransportActions::SERVICE,
+            PropertyChange::CurrentPlayMode(_) => CurrentPlayMode::SERVICE,
             PropertyChange::GroupMembership(_) => GroupMembership::SERVICE,
             PropertyChange::GroupVolume(_) => GroupVolume::SERVICE,
             PropertyChange::GroupMute(_) => GroupMute::SERVICE,
@@ -171,16 +254,27 @@ pub fn decode_event(event: &EnrichedEvent, speaker_id: SpeakerId) -> DecodedChan
         EventData::RenderingControl(rc) => decode_rendering_control(rc),
         EventData::AVTransport(avt) => decode_av_transport(avt),
         EventData::ZoneGroupTopology(zgt) => decode_topology(zgt),
-        EventData::DeviceProperties(_) => vec![],
+        EventData::DeviceProperties(dp) => decode_device_properties(dp),
         // GroupManagement is action-only; group changes surface via ZoneGroupTopology events.
         // No user-facing properties to decode.
         EventData::GroupManagement(_) => vec![],
         EventData::GroupRenderingControl(grc) => decode_group_rendering_control(grc),
     };
 
+    let room_name = match &event.event_data {
+        EventData::DeviceProperties(dp) => dp
+            .zone_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string),
+        _ => None,
+    };
+
     DecodedChanges {
         speaker_id,
         changes,
+        room_name,
     }
 }
 
@@ -221,6 +315,32 @@ fn decode_rendering_control(event: &RenderingControlState) -> Vec<PropertyChange
         changes.push(PropertyChange::Loudness(Loudness(loudness)));
     }
 
+    if let Some(v) = &event.output_fixed {
+        changes.push(PropertyChange::OutputFixed(OutputFixed(v == "1")));
+    }
+    if let Some(list) = &event.preset_name_list {
+        changes.push(PropertyChange::Presets(Presets::parse(list)));
+    }
+
+    // Home-theater EQ (only present on bond primaries)
+    if let Some(v) = &event.sub_enabled {
+        changes.push(PropertyChange::SubEnabled(SubEnabled(v == "1")));
+    }
+    if let Some(gain) = event.sub_gain.as_deref().and_then(|v| v.parse().ok()) {
+        changes.push(PropertyChange::SubGain(SubGain::new(gain)));
+    }
+    if let Some(v) = &event.surround_enabled {
+        changes.push(PropertyChange::SurroundEnabled(SurroundEnabled(v == "1")));
+    }
+    if let Some(mode) = event.surround_mode.as_deref().and_then(|v| v.parse().ok()) {
+        changes.push(PropertyChange::SurroundMode(SurroundMode::from_eq_value(
+            mode,
+        )));
+    }
+    if let Some(level) = event.surround_level.as_deref().and_then(|v| v.parse().ok()) {
+        changes.push(PropertyChange::SurroundLevel(SurroundLevel::new(level)));
+    }
+
     changes
 }
 
@@ -267,6 +387,22 @@ fn decode_av_transport(event: &AVTransportState) -> Vec<PropertyChange> {
         changes.push(PropertyChange::CurrentTrack(track));
     }
 
+    // TransportActions
+    if let Some(actions) = &event.transport_actions {
+        changes.push(PropertyChange::TransportActions(TransportActions::parse(
+            actions,
+        )));
+    }
+
+    // Unknown modes are dropped rather than guessed
+    if let Some(mode) = event
+        .play_mode
+        .as_deref()
+        .and_then(CurrentPlayMode::from_wire)
+    {
+        changes.push(PropertyChange::CurrentPlayMode(mode));
+    }
+
     changes
 }
 
@@ -301,6 +437,26 @@ fn decode_group_rendering_control(event: &GroupRenderingControlState) -> Vec<Pro
     changes
 }
 
+/// Decode DeviceProperties event data
+fn decode_device_properties(event: &DevicePropertiesEvent) -> Vec<PropertyChange> {
+    let button_lock = event
+        .button_lock
+        .map(|locked| PropertyChange::ButtonLock(ButtonLock(locked)));
+    let status_light = event
+        .status_light
+        .map(|on| PropertyChange::StatusLight(StatusLight(on)));
+    let audio_output = event
+        .audio_output
+        .as_deref()
+        .and_then(AudioOutput::from_wire)
+        .map(PropertyChange::AudioOutput);
+    button_lock
+        .into_iter()
+        .chain(status_light)
+        .chain(audio_output)
+        .collect()
+}
+
 /// Decode a ZoneGroupTopology event into TopologyChanges
 ///
 /// This extracts group information and speaker memberships from the topology event.
@@ -315,8 +471,10 @@ pub fn decode_topology_event(event: &ZoneGroupTopologyState) -> TopologyChanges
     let mut groups = Vec::new();
     let mut memberships = Vec::new();
     let mut boot_seqs = Vec::new();
-    let mut speaker_ips = Vec::new();
+    let mut software = Vec::new();
+    let mut speaker_addrs = Vec::new();
     let mut satellite_ids = Vec::new();
+    let mut bonds = Vec::new();
 
     for zone_group in &event.zone_groups {
         let group_id = GroupId::new(&zone_group.id);
@@ -341,20 +499,32 @@ pub fn decode_topology_event(event: &ZoneGroupTopologyState) -> TopologyChanges
             let membership = GroupMembership::new(group_id.clone(), is_coordinator);
             memberships.push((speaker_id.clone(), membership));
             boot_seqs.push((speaker_id.clone(), member.boot_seq));
+            if !member.software_version.is_empty() {
+                software.push((
+                    speaker_id.clone(),
+                    member.software_version.clone(),
+                    member.software_generation,
+                ));
+            }
 
-            if let Some(ip) = extract_ip_from_location(&member.location) {
-                speaker_ips.push((speaker_id, ip));
+            if let Some(addr) = extract_addr_from_location(&member.location) {
+                speaker_addrs.push((speaker_id.clone(), addr));
             }
 
+            let mut bonded = Vec::new();
             for sat in &member.satellites {
                 if sat.invisible == "1" {
                     let sat_id = SpeakerId::new(&sat.uuid);
                     satellite_ids.push(sat_id.clone());
-                    if let Some(ip) = extract_ip_from_location(&sat.location) {
-                        speaker_ips.push((sat_id, ip));
+                    bonded.push(sat_id.clone());
+                    if let Some(addr) = extract_addr_from_location(&sat.location) {
+                        speaker_addrs.push((sat_id, addr));
                     }
                 }
             }
+            if !bonded.is_empty() {
+                bonds.push((speaker_id.clone(), bonded));
+            }
         }
     }
 
@@ -362,16 +532,64 @@ pub fn decode_topology_event(event: &ZoneGroupTopologyState) -> TopologyChanges
         groups,
         memberships,
         boot_seqs,
-        speaker_ips,
+        software,
+        speaker_addrs,
         satellite_ids,
+        bonds,
     }
 }
 
-fn extract_ip_from_location(location: &str) -> Option<IpAddr> {
+/// Fingerprint of what [`decode_topology_event`] reads from an event
+///
+/// Groups and their members are sorted first, so the same topology sent in
+/// another order gives the same fingerprint, while a different coordinator,
+/// member, address, boot sequence, firmware or bond gives a different one.
+/// Attributes the decoder ignores (zone names, network details, channel
+/// maps, vanished devices) are left out. Sonos re-sends the topology on
+/// every renewal and many unrelated attribute changes; an event whose
+/// fingerprint matches the last one applied can be skipped undecoded.
+pub fn topology_fingerprint(event: &ZoneGroupTopologyState) -> u64 {
+    let mut groups: Vec<_> = event.zone_groups.iter().collect();
+    groups.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut hasher = DefaultHasher::new();
+    groups.len().hash(&mut hasher);
+    for group in groups {
+        (&group.id, &group.coordinator).hash(&mut hasher);
+        let mut members: Vec<_> = group.members.iter().collect();
+        members.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+        members.len().hash(&mut hasher);
+        for member in members {
+            (
+                &member.uuid,
+                &member.location,
+                member.boot_seq,
+                &member.software_version,
+                member.software_generation,
+            )
+                .hash(&mut hasher);
+            let mut satellites: Vec<_> = member
+                .satellites
+                .iter()
+                .filter(|sat| sat.invisible == "1")
+                .map(|sat| (&sat.uuid, &sat.location))
+                .collect();
+            satellites.sort();
+            satellites.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Device address from a location URL, defaulting to port 1400 when the
+/// URL has none
+fn extract_addr_from_location(location: &str) -> Option<SocketAddr> {
     let url_part = location.strip_prefix("http://")?;
     let host_port = url_part.split('/').next()?;
-    let host = host_port.split(':').next()?;
-    host.parse().ok()
+    match host_port.parse() {
+        Ok(addr) => Some(addr),
+        Err(_) => Some(SocketAddr::new(host_port.parse().ok()?, 1400)),
+    }
 }
 
 /// Parse duration string (HH:MM:SS or H:MM:SS) to milliseconds
@@ -383,23 +601,7 @@ fn parse_duration_ms(duration: Option<&str>) -> Option<u64> {
         return None;
     }
 
-    let parts: Vec<&str> = d.split(':').collect();
-    if parts.len() != 3 {
-        return None;
-    }
-
-    let hours: u64 = parts[0].parse().ok()?;
-    let minutes: u64 = parts[1].parse().ok()?;
-
-    // Handle potential milliseconds in seconds part (HH:MM:SS.mmm)
-    let seconds_parts: Vec<&str> = parts[2].split('.').collect();
-    let seconds: u64 = seconds_parts[0].parse().ok()?;
-    let millis: u64 = seconds_parts
-        .get(1)
-        .and_then(|m| m.parse().ok())
-        .unwrap_or(0);
-
-    Some((hours * 3600 + minutes * 60 + seconds) * 1000 + millis)
+    Position::parse_time_to_ms(d)
 }
 
 /// Parse DIDL-Lite track metadata XML
@@ -431,10 +633,8 @@ pub fn extract_xml_element(xml: &str, element: &str) -> Option<String> {
     let start_tag = format!("<{element}>");
     let end_tag = format!("</{element}>");
 
-    let start_idx = xml.find(&start_tag)? + start_tag.len();
-    let end_idx = xml[start_idx..].find(&end_tag)? + start_idx;
-
-    let content = &xml[start_idx..end_idx];
+    let (_, rest) = xml.split_once(&start_tag)?;
+    let (content, _) = rest.split_once(&end_tag)?;
 
     // Unescape basic XML entities
     let unescaped = content
@@ -455,6 +655,23 @@ pub fn extract_xml_element(xml: &str, element: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    /// A registered speaker to decode into
+    fn entity(id: &SpeakerId) -> crate::model::SpeakerInfo {
+        crate::model::SpeakerInfo {
+            id: id.clone(),
+            name: "Den".to_string(),
+            room_name: "Den".to_string(),
+            ip_address: "192.168.1.50".parse().unwrap(),
+            port: 1400,
+            model_name: "Test".to_string(),
+            software_version: "1.0".to_string(),
+            software_generation: None,
+            boot_seq: 0,
+            satellites: vec![],
+            household_id: None,
+        }
+    }
+
     #[test]
     fn test_parse_duration_ms() {
         assert_eq!(parse_duration_ms(Some("0:00:00")), Some(0));
@@ -465,6 +682,8 @@ mod tests {
         assert_eq!(parse_duration_ms(Some("NOT_IMPLEMENTED")), None);
         assert_eq!(parse_duration_ms(None), None);
         assert_eq!(parse_duration_ms(Some("")), None);
+        // Found by fuzz_parse_duration: used to overflow
+        assert_eq!(parse_duration_ms(Some("5124100000000:0:0")), None);
     }
 
     #[test]
@@ -483,34 +702,46 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_ip_from_location_valid() {
-        let ip = extract_ip_from_location("http://192.168.4.200:1400/xml/device_description.xml");
-        assert_eq!(ip, Some("192.168.4.200".parse().unwrap()));
+    fn test_extract_addr_from_location_valid() {
+        let addr =
+            extract_addr_from_location("http://192.168.4.200:1400/xml/device_description.xml");
+        assert_eq!(addr, Some("192.168.4.200:1400".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_extract_addr_from_location_no_path() {
+        let addr = extract_addr_from_location("http://10.0.0.1:1400");
+        assert_eq!(addr, Some("10.0.0.1:1400".parse().unwrap()));
     }
 
     #[test]
-    fn test_extract_ip_from_location_no_path() {
-        let ip = extract_ip_from_location("http://10.0.0.1:1400");
-        assert_eq!(ip, Some("10.0.0.1".parse().unwrap()));
+    fn test_extract_addr_from_location_keeps_port() {
+        let addr = extract_addr_from_location("http://10.0.0.1:1401/xml");
+        assert_eq!(addr, Some("10.0.0.1:1401".parse().unwrap()));
+        let addr = extract_addr_from_location("http://10.0.0.1/xml");
+        assert_eq!(addr, Some("10.0.0.1:1400".parse().unwrap()));
     }
 
     #[test]
-    fn test_extract_ip_from_location_missing_prefix() {
-        assert_eq!(extract_ip_from_location("192.168.1.1:1400/xml"), None);
+    fn test_extract_addr_from_location_missing_prefix() {
+        assert_eq!(extract_addr_from_location("192.168.1.1:1400/xml"), None);
     }
 
     #[test]
-    fn test_extract_ip_from_location_empty() {
-        assert_eq!(extract_ip_from_location(""), None);
+    fn test_extract_addr_from_location_empty() {
+        assert_eq!(extract_addr_from_location(""), None);
     }
 
     #[test]
-    fn test_extract_ip_from_location_malformed() {
-        assert_eq!(extract_ip_from_location("http://not-an-ip:1400/xml"), None);
+    fn test_extract_addr_from_location_malformed() {
+        assert_eq!(
+            extract_addr_from_location("http://not-an-ip:1400/xml"),
+            None
+        );
     }
 
     #[test]
-    fn test_decode_topology_extracts_ips_and_satellites() {
+    fn test_decode_topology_extracts_addrs_and_satellites() {
         use sonos_stream::events::{
             NetworkInfo, SatelliteInfo, ZoneGroupInfo, ZoneGroupMemberInfo, ZoneGroupTopologyState,
         };
@@ -524,6 +755,7 @@ mod tests {
                     location: "http://192.168.4.100:1400/xml/device_description.xml".to_string(),
                     zone_name: "Living Room".to_string(),
                     software_version: "56.0".to_string(),
+                    software_generation: None,
                     boot_seq: 42,
                     network_info: NetworkInfo::default(),
                     satellites: vec![SatelliteInfo {
@@ -541,24 +773,31 @@ mod tests {
 
         let changes = decode_topology_event(&event);
 
-        assert_eq!(changes.speaker_ips.len(), 2);
+        assert_eq!(changes.speaker_addrs.len(), 2);
         assert_eq!(
-            changes.speaker_ips[0],
+            changes.speaker_addrs[0],
             (
                 SpeakerId::new("RINCON_MAIN"),
-                "192.168.4.100".parse().unwrap()
+                "192.168.4.100:1400".parse().unwrap()
             )
         );
         assert_eq!(
-            changes.speaker_ips[1],
+            changes.speaker_addrs[1],
             (
                 SpeakerId::new("RINCON_SAT"),
-                "192.168.4.101".parse().unwrap()
+                "192.168.4.101:1400".parse().unwrap()
             )
         );
 
         assert_eq!(changes.satellite_ids.len(), 1);
         assert_eq!(changes.satellite_ids[0], SpeakerId::new("RINCON_SAT"));
+        assert_eq!(
+            changes.bonds,
+            vec![(
+                SpeakerId::new("RINCON_MAIN"),
+                vec![SpeakerId::new("RINCON_SAT")]
+            )]
+        );
     }
 
     #[test]
@@ -575,6 +814,7 @@ mod tests {
             rf_mute: None,
             balance: None,
             other_channels: std::collections::HashMap::new(),
+            ..Default::default()
         };
 
         let changes = decode_rendering_control(&event);
@@ -596,6 +836,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decode_rendering_control_arc_with_sub() {
+        // LastChange from an Arc bonded with a Sub (trimmed)
+        let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/RCS/&quot;&gt;&lt;InstanceID val=&quot;0&quot;&gt;&lt;Volume channel=&quot;Master&quot; val=&quot;22&quot;/&gt;&lt;SubGain val=&quot;-4&quot;/&gt;&lt;SubEnabled val=&quot;1&quot;/&gt;&lt;SurroundLevel val=&quot;3&quot;/&gt;&lt;SurroundEnabled val=&quot;1&quot;/&gt;&lt;SurroundMode val=&quot;1&quot;/&gt;&lt;MusicSurroundLevel val=&quot;0&quot;/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#;
+        let event = sonos_api::services::rendering_control::RenderingControlEvent::from_xml(xml)
+            .unwrap()
+            .into_state();
+
+        let changes = decode_rendering_control(&event);
+
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, PropertyChange::Volume(Volume(22)))));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, PropertyChange::SubEnabled(SubEnabled(true)))));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, PropertyChange::SubGain(SubGain(-4)))));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, PropertyChange::SurroundEnabled(SurroundEnabled(true)))));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, PropertyChange::SurroundMode(SurroundMode::Full))));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, PropertyChange::SurroundLevel(SurroundLevel(3)))));
+    }
+
+    #[test]
+    fn test_decode_rendering_control_presets() {
+        // Initial LastChange from a Port with line-out fixed (trimmed)
+        let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/RCS/&quot;&gt;&lt;InstanceID val=&quot;0&quot;&gt;&lt;Volume channel=&quot;Master&quot; val=&quot;100&quot;/&gt;&lt;OutputFixed val=&quot;1&quot;/&gt;&lt;PresetNameList val=&quot;FactoryDefaults, Night&quot;/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#;
+        let event = sonos_api::services::rendering_control::RenderingControlEvent::from_xml(xml)
+            .unwrap()
+            .into_state();
+
+        let changes = decode_rendering_control(&event);
+
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, PropertyChange::OutputFixed(OutputFixed(true)))));
+        assert!(changes.iter().any(|c| matches!(
+            c,
+            PropertyChange::Presets(p) if p.names() == ["FactoryDefaults", "Night"]
+        )));
+    }
+
     #[test]
     fn test_decode_av_transport() {
         let event = AVTransportState {
@@ -613,6 +902,7 @@ mod tests {
             next_track_uri: None,
             next_track_metadata: None,
             queue_length: None,
+            transport_actions: None,
         };
 
         let changes = decode_av_transport(&event);
@@ -627,6 +917,150 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decode_av_transport_actions_from_last_change() {
+        let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0">
+            <e:property>
+                <LastChange>&lt;Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"&gt;
+                    &lt;InstanceID val="0"&gt;
+                        &lt;TransportState val="PLAYING"/&gt;
+                        &lt;CurrentTransportActions val="Set, Stop,Play"/&gt;
+                    &lt;/InstanceID&gt;
+                &lt;/Event&gt;</LastChange>
+            </e:property>
+        </e:propertyset>"#;
+        let event = sonos_api::services::av_transport::AVTransportEvent::from_xml(xml)
+            .unwrap()
+            .into_state();
+
+        let mut store = StateStore::new();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        store.create_entity(entity(&speaker_id));
+        for change in decode_av_transport(&event) {
+            change.apply(&mut store, &speaker_id, ChangeOrigin::Unknown);
+        }
+
+        let actions = store.get::<TransportActions>(&speaker_id).unwrap();
+        assert_eq!(actions.actions(), ["Set", "Stop", "Play"]);
+        assert!(!actions.allows("Pause"));
+    }
+
+    #[test]
+    fn test_decode_av_transport_play_mode_from_last_change() {
+        let event = |mode: &str| {
+            sonos_api::services::av_transport::AVTransportEvent::from_xml(&format!(
+                r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"&gt;&lt;InstanceID val="0"&gt;&lt;CurrentPlayMode val="{mode}"/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#
+            ))
+            .unwrap()
+            .into_state()
+        };
+
+        let mut store = StateStore::new();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        store.create_entity(entity(&speaker_id));
+        for change in decode_av_transport(&event("SHUFFLE_NOREPEAT")) {
+            change.apply(&mut store, &speaker_id, ChangeOrigin::Unknown);
+        }
+        let mode = store.get::<CurrentPlayMode>(&speaker_id).unwrap();
+        assert_eq!(
+            mode.mode(),
+            sonos_api::services::av_transport::PlayMode::ShuffleNoRepeat
+        );
+        assert!(mode.is_shuffle());
+
+        assert!(decode_av_transport(&event("SIDEWAYS"))
+            .iter()
+            .all(|c| !matches!(c, PropertyChange::CurrentPlayMode(_))));
+    }
+
+    #[test]
+    fn test_decode_device_properties_toggles_button_lock() {
+        let event = |state: &str| -> DevicePropertiesEvent {
+            let xml = format!(
+                r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><ZoneName>Kids Room</ZoneName></e:property><e:property><ButtonLockState>{state}</ButtonLockState></e:property></e:propertyset>"#
+            );
+            sonos_api::services::device_properties::DevicePropertiesEvent::from_xml(&xml)
+                .unwrap()
+                .into_state()
+                .into()
+        };
+
+        let mut store = StateStore::new();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        store.create_entity(entity(&speaker_id));
+        for (state, locked) in [("On", true), ("Off", false), ("on", true)] {
+            let changes = decode_device_properties(&event(state));
+            assert_eq!(changes.len(), 1);
+            assert!(changes[0].apply(&mut store, &speaker_id, ChangeOrigin::Unknown));
+            assert_eq!(
+                store.get::<ButtonLock>(&speaker_id),
+                Some(ButtonLock(locked))
+            );
+        }
+        assert!(decode_device_properties(&event("Jammed")).is_empty());
+    }
+
+    #[test]
+    fn test_decode_device_properties_status_light() {
+        let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LEDState>Off</LEDState></e:property><e:property><ButtonLockState>On</ButtonLockState></e:property></e:propertyset>"#;
+        let event: DevicePropertiesEvent =
+            sonos_api::services::device_properties::DevicePropertiesEvent::from_xml(xml)
+                .unwrap()
+                .into_state()
+                .into();
+
+        let mut store = StateStore::new();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        store.create_entity(entity(&speaker_id));
+        let changes = decode_device_properties(&event);
+        assert_eq!(changes.len(), 2);
+        for change in changes {
+            assert!(change.apply(&mut store, &speaker_id, ChangeOrigin::Unknown));
+        }
+        assert_eq!(
+            store.get::<StatusLight>(&speaker_id),
+            Some(StatusLight(false))
+        );
+        assert_eq!(store.get::<ButtonLock>(&speaker_id), Some(ButtonLock(true)));
+    }
+
+    #[test]
+    fn test_decode_device_properties_audio_output() {
+        let event = |xml: &str| -> DevicePropertiesEvent {
+            sonos_api::services::device_properties::DevicePropertiesEvent::from_xml(xml)
+                .unwrap()
+                .into_state()
+                .into()
+        };
+        let mut store = StateStore::new();
+        let speaker_id = SpeakerId::new("RINCON_ROAM");
+        store.create_entity(entity(&speaker_id));
+
+        let roam = event(
+            r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><ZoneName>Patio</ZoneName></e:property><e:property><AudioOutput>BLUETOOTH</AudioOutput></e:property></e:propertyset>"#,
+        );
+        for change in decode_device_properties(&roam) {
+            change.apply(&mut store, &speaker_id, ChangeOrigin::Unknown);
+        }
+        assert_eq!(
+            store.get::<AudioOutput>(&speaker_id),
+            Some(AudioOutput::Bluetooth)
+        );
+
+        // A One never reports an output, and an unknown value is dropped
+        let one = event(
+            r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><ZoneName>Kitchen</ZoneName></e:property><e:property><ButtonLockState>Off</ButtonLockState></e:property></e:propertyset>"#,
+        );
+        let changes = decode_device_properties(&one);
+        assert!(!changes
+            .iter()
+            .any(|c| matches!(c, PropertyChange::AudioOutput(_))));
+        let garbled = event(
+            r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><AudioOutput>HDMI</AudioOutput></e:property></e:propertyset>"#,
+        );
+        assert!(decode_device_properties(&garbled).is_empty());
+    }
+
     #[test]
     fn test_decode_group_rendering_control() {
         let event = GroupRenderingControlState {
@@ -767,6 +1201,7 @@ mod tests {
             location: "http://192.168.1.100:1400/xml/device_description.xml".to_string(),
             zone_name: zone_name.to_string(),
             software_version: "79.1-56030".to_string(),
+            software_generation: None,
             boot_seq,
             network_info: NetworkInfo {
                 wireless_mode: "0".to_string(),
@@ -779,6 +1214,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_topology_fingerprint_ignores_order_only() {
+        let group = |coordinator: &str, members: &[&str]| ZoneGroupInfo {
+            coordinator: coordinator.to_string(),
+            id: format!("{coordinator}:1"),
+            members: members
+                .iter()
+                .map(|uuid| make_member(uuid, "Room"))
+                .collect(),
+        };
+        let topology = |zone_groups| ZoneGroupTopologyState {
+            zone_groups,
+            vanished_devices: vec![],
+        };
+        let fingerprint = topology_fingerprint(&topology(vec![
+            group("RINCON_A", &["RINCON_A", "RINCON_B"]),
+            group("RINCON_C", &["RINCON_C"]),
+        ]));
+
+        let reordered = topology(vec![
+            group("RINCON_C", &["RINCON_C"]),
+            group("RINCON_A", &["RINCON_B", "RINCON_A"]),
+        ]);
+        assert_eq!(topology_fingerprint(&reordered), fingerprint);
+
+        let mut renamed = reordered.clone();
+        renamed.zone_groups[0].members[0].zone_name = "Den".to_string();
+        assert_eq!(topology_fingerprint(&renamed), fingerprint);
+
+        let mut handoff = reordered.clone();
+        handoff.zone_groups[1].coordinator = "RINCON_B".to_string();
+        assert_ne!(topology_fingerprint(&handoff), fingerprint);
+
+        let left = topology(vec![
+            group("RINCON_A", &["RINCON_A"]),
+            group("RINCON_C", &["RINCON_C", "RINCON_B"]),
+        ]);
+        assert_ne!(topology_fingerprint(&left), fingerprint);
+    }
+
     #[test]
     fn test_decode_topology_single_group_one_speaker() {
         // Single speaker in a standalone group
@@ -997,6 +1472,44 @@ mod tests {
         assert_eq!(result.boot_seqs.len(), 1);
         assert_eq!(result.boot_seqs[0].1, 0);
     }
+
+    #[test]
+    fn test_decode_topology_extracts_software_versions() {
+        let mut s2 = make_member("RINCON_222222222222", "Kitchen");
+        s2.software_generation = Some(2);
+        let mut unreported = make_member("RINCON_333333333333", "Office");
+        unreported.software_version.clear();
+        let event = ZoneGroupTopologyState {
+            zone_groups: vec![ZoneGroupInfo {
+                coordinator: "RINCON_111111111111".to_string(),
+                id: "RINCON_111111111111:0".to_string(),
+                members: vec![
+                    make_member("RINCON_111111111111", "Living Room"),
+                    s2,
+                    unreported,
+                ],
+            }],
+            vanished_devices: vec![],
+        };
+
+        let result = decode_topology_event(&event);
+
+        assert_eq!(
+            result.software,
+            vec![
+                (
+                    SpeakerId::new("RINCON_111111111111"),
+                    "79.1-56030".to_string(),
+                    None
+                ),
+                (
+                    SpeakerId::new("RINCON_222222222222"),
+                    "79.1-56030".to_string(),
+                    Some(2)
+                ),
+            ]
+        );
+    }
 }
 
 // ============================================================================
@@ -1022,6 +1535,7 @@ mod property_tests {
                 location: "http://192.168.1.100:1400/xml/device_description.xml".to_string(),
                 zone_name: zone_name.trim().to_string(),
                 software_version: "79.1-56030".to_string(),
+                software_generation: None,
                 boot_seq: 0,
                 network_info: NetworkInfo {
                     wireless_mode: "0".to_string(),
@@ -1171,4 +1685,58 @@ mod property_tests {
             }
         }
     }
+
+    /// Strategy for topology built from arbitrary device strings, where
+    /// coordinators needn't be members and groups may be empty
+    fn hostile_topology_strategy() -> impl Strategy<Value = ZoneGroupTopologyState> {
+        let member =
+            ("\\PC{0,12}", "\\PC{0,48}", "\\PC{0,12}").prop_map(|(uuid, location, zone_name)| {
+                ZoneGroupMemberInfo {
+                    uuid,
+                    location,
+                    zone_name,
+                    software_version: String::new(),
+                    software_generation: None,
+                    boot_seq: 0,
+                    network_info: NetworkInfo {
+                        wireless_mode: String::new(),
+                        wifi_enabled: String::new(),
+                        eth_link: String::new(),
+                        channel_freq: String::new(),
+                        behind_wifi_extender: String::new(),
+                    },
+                    satellites: vec![],
+                }
+            });
+        let group = ("\\PC{0,12}", proptest::collection::vec(member, 0..=3)).prop_map(
+            |(coordinator, members)| ZoneGroupInfo {
+                id: format!("{coordinator}:0"),
+                coordinator,
+                members,
+            },
+        );
+        proptest::collection::vec(group, 0..=3).prop_map(|zone_groups| ZoneGroupTopologyState {
+            zone_groups,
+            vanished_devices: vec![],
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn fuzz_decode_topology(event in hostile_topology_strategy()) {
+            decode_topology_event(&event);
+        }
+
+        #[test]
+        fn fuzz_parse_duration(duration in "[0-9]{1,24}:[0-9]{1,24}:[0-9]{1,24}(\\.[0-9]{1,24})?|\\PC{0,24}") {
+            parse_duration_ms(Some(&duration));
+        }
+
+        #[test]
+        fn fuzz_parse_track_metadata(metadata in "(<dc:title>|</dc:title>|<upnp:album>|&amp;|\\PC){0,16}") {
+            parse_track_metadata(Some(&metadata));
+        }
+    }
 }
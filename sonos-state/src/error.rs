@@ -46,6 +46,19 @@ pub enum StateError {
 
     /// Lock poisoned (internal mutex error)
     LockPoisoned,
+
+    /// No built-in property has this key
+    UnknownProperty {
+        key: String,
+        /// Keys of the built-in properties
+        valid_keys: Vec<&'static str>,
+    },
+
+    /// The property can't be set by value
+    ReadOnlyProperty(&'static str),
+
+    /// A dynamic value doesn't fit the property's schema
+    InvalidValue { key: &'static str, reason: String },
 }
 
 impl fmt::Display for StateError {
@@ -66,6 +79,17 @@ impl fmt::Display for StateError {
             StateError::SubscriptionFailed(msg) => write!(f, "Subscription failed: {msg}"),
             StateError::InvalidIpAddress(ip) => write!(f, "Invalid IP address: {ip}"),
             StateError::LockPoisoned => write!(f, "Internal lock poisoned"),
+            StateError::UnknownProperty { key, valid_keys } => {
+                write!(
+                    f,
+                    "Unknown property: {key} (expected one of: {})",
+                    valid_keys.join(", ")
+                )
+            }
+            StateError::ReadOnlyProperty(key) => write!(f, "Property is read-only: {key}"),
+            StateError::InvalidValue { key, reason } => {
+                write!(f, "Invalid value for {key}: {reason}")
+            }
         }
     }
 }
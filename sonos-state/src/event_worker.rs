@@ -3,160 +3,446 @@
 //! This module provides a background thread that consumes events from the
 //! SonosEventManager and applies them to the StateStore.
 
-use std::collections::HashSet;
-use std::net::IpAddr;
-use std::sync::{mpsc, Arc};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
+use std::time::Instant;
 
-use parking_lot::RwLock;
+use arc_swap::ArcSwap;
+use parking_lot::{Mutex, RwLock};
 
 use sonos_api::Service;
 use sonos_event_manager::SonosEventManager;
-use sonos_stream::events::EventData;
+use sonos_stream::chaos::ChaosHook;
+use sonos_stream::events::{EnrichedEvent, EventData, ZoneGroupTopologyState};
 
 use sonos_api::ServiceScope;
 
-use crate::decoder::{decode_event, decode_topology_event, PropertyChange, TopologyChanges};
-use crate::model::SpeakerId;
-use crate::property::{GroupMembership, Property, Scope};
-use crate::state::{ChangeEvent, StateStore};
+use crate::decoder::{
+    decode_event, decode_topology_event, topology_fingerprint, PropertyChange, TopologyChanges,
+};
+use crate::model::{GroupId, SpeakerId, SpeakerInfo};
+use crate::origin::{ChangeOrigin, OriginTracker};
+use crate::property::{
+    GroupComposition, GroupInfo, GroupList, GroupMembership, PlaybackState, Property, Scope,
+    Topology,
+};
+use crate::state::{ChangeEvent, ChangeSink, StateStore};
+use crate::transition::TransitionMonitor;
+
+/// Shutdown bookkeeping shared by `StateManager::shutdown()` and the worker
+#[derive(Default)]
+pub(crate) struct Drain {
+    /// Set once shutdown starts
+    deadline: Mutex<Option<Instant>>,
+    /// Events received after shutdown started and decoded in time
+    drained: AtomicUsize,
+    /// Events received but never decoded
+    abandoned: AtomicUsize,
+}
 
-/// Spawns the state event worker thread
+impl Drain {
+    /// Start draining; `false` if shutdown had already started
+    pub(crate) fn begin(&self, deadline: Instant) -> bool {
+        let mut current = self.deadline.lock();
+        if current.is_some() {
+            return false;
+        }
+        *current = Some(deadline);
+        true
+    }
+
+    pub(crate) fn is_started(&self) -> bool {
+        self.deadline.lock().is_some()
+    }
+
+    /// Account for an event the worker just received, returning whether to
+    /// decode it. Past the deadline the event is abandoned and the worker
+    /// stops.
+    fn admit(&self) -> bool {
+        match *self.deadline.lock() {
+            None => true,
+            Some(deadline) if Instant::now() < deadline => {
+                self.drained.fetch_add(1, Ordering::SeqCst);
+                true
+            }
+            Some(_) => {
+                self.abandoned.fetch_add(1, Ordering::SeqCst);
+                false
+            }
+        }
+    }
+
+    /// Count events left in the queue when the worker stopped
+    pub(crate) fn abandon(&self, count: usize) {
+        self.abandoned.fetch_add(count, Ordering::SeqCst);
+    }
+
+    /// `(drained, abandoned)`
+    pub(crate) fn counts(&self) -> (usize, usize) {
+        (
+            self.drained.load(Ordering::SeqCst),
+            self.abandoned.load(Ordering::SeqCst),
+        )
+    }
+}
+
+/// Events held during startup, beyond which the oldest are dropped
+const HELD_EVENTS_LIMIT: usize = 256;
+
+/// Where events from addresses with no registered speaker go
 ///
-/// This worker:
-/// - Consumes events from SonosEventManager's iterator
-/// - Decodes them into typed property changes
-/// - Applies changes to the StateStore
-/// - Emits ChangeEvents for watched properties
-pub(crate) fn spawn_state_event_worker(
-    event_manager: Arc<SonosEventManager>,
-    store: Arc<RwLock<StateStore>>,
-    watched: Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
-    event_tx: mpsc::Sender<ChangeEvent>,
-    ip_to_speaker: Arc<RwLock<std::collections::HashMap<IpAddr, SpeakerId>>>,
-) -> JoinHandle<()> {
-    thread::spawn(move || {
-        tracing::info!("State event worker started, waiting for events...");
+/// During startup (until the first `StateManager::initialize()`) they are
+/// held and replayed once their speaker registers. Events for a removed
+/// speaker are dropped and counted. Anything else is dropped with a warning.
+pub(crate) struct Routing {
+    /// Held events; `None` once startup has ended
+    held: Mutex<Option<VecDeque<EnrichedEvent>>>,
+    /// Addresses of removed speakers, until something registers there again
+    removed: RwLock<HashMap<SocketAddr, SpeakerId>>,
+    /// Events dropped because their speaker was removed
+    dropped_for_removed: AtomicUsize,
+}
+
+impl Routing {
+    pub(crate) fn new() -> Self {
+        Self {
+            held: Mutex::new(Some(VecDeque::new())),
+            removed: RwLock::new(HashMap::new()),
+            dropped_for_removed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Route events from `addr` to `speaker_id` as a removed speaker's
+    pub(crate) fn mark_removed(&self, addr: SocketAddr, speaker_id: SpeakerId) {
+        self.removed.write().insert(addr, speaker_id);
+    }
+
+    /// Forget removals at addresses a speaker registered at again
+    pub(crate) fn mark_registered(&self, addrs: impl IntoIterator<Item = SocketAddr>) {
+        let mut removed = self.removed.write();
+        for addr in addrs {
+            removed.remove(&addr);
+        }
+    }
+
+    pub(crate) fn dropped_for_removed(&self) -> usize {
+        self.dropped_for_removed.load(Ordering::SeqCst)
+    }
+
+    /// Stop holding events, dropping those no speaker claimed
+    pub(crate) fn end_startup(&self) {
+        if let Some(held) = self.held.lock().take() {
+            if !held.is_empty() {
+                tracing::debug!(
+                    "Startup over, dropping {} events from unregistered speakers",
+                    held.len()
+                );
+            }
+        }
+    }
+
+    /// Hold `event` if still starting up
+    fn hold(&self, event: &EnrichedEvent) -> bool {
+        let mut held = self.held.lock();
+        let Some(queue) = held.as_mut() else {
+            return false;
+        };
+        if queue.len() == HELD_EVENTS_LIMIT {
+            queue.pop_front();
+        }
+        queue.push_back(event.clone());
+        true
+    }
+}
+
+/// Everything decoding an event touches
+///
+/// The worker runs every event from the event manager through
+/// [`process()`](Self::process); injected events take the same path.
+#[derive(Clone)]
+pub(crate) struct EventPipeline {
+    pub(crate) store: Arc<RwLock<StateStore>>,
+    pub(crate) watched: Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
+    pub(crate) event_tx: ChangeSink,
+    pub(crate) origins: Arc<OriginTracker>,
+    pub(crate) transitions: Arc<TransitionMonitor>,
+    pub(crate) addr_to_speaker: Arc<ArcSwap<std::collections::HashMap<SocketAddr, SpeakerId>>>,
+    pub(crate) routing: Arc<Routing>,
+    pub(crate) chaos: ChaosHook,
+}
+
+impl EventPipeline {
+    /// Decode one event and apply it to the store
+    ///
+    /// - Decodes it into typed property changes
+    /// - Applies changes to the StateStore
+    /// - Emits ChangeEvents for watched properties, attributed by `origins`
+    /// - Settles pending `transitions` checks of speakers whose playback
+    ///   state it reported
+    ///
+    /// Returns `false` for events that are skipped: from an unknown speaker,
+    /// from a group member for a service its coordinator owns, or a
+    /// topology identical to the last one.
+    pub(crate) fn process(&self, event: &EnrichedEvent) -> bool {
+        tracing::debug!(
+            "Received event from {} for service {:?}",
+            event.speaker_addr,
+            event.service
+        );
+
+        // Handle ZoneGroupTopology events specially - they affect all speakers
+        if let EventData::ZoneGroupTopology(ref zgt_event) = event.event_data {
+            return apply_topology_event(
+                &self.store,
+                &self.watched,
+                &self.event_tx,
+                &self.addr_to_speaker,
+                zgt_event,
+            );
+        }
+
+        // Look up speaker_id from address for non-topology events
+        let speaker_id = {
+            let addr_map = self.addr_to_speaker.load();
 
-        // Consume events from event manager (blocking)
-        for event in event_manager.iter() {
             tracing::debug!(
-                "Received event from {} for service {:?}",
-                event.speaker_ip,
-                event.service
+                "addr_to_speaker map has {} entries: {:?}",
+                addr_map.len(),
+                addr_map.keys().collect::<Vec<_>>()
             );
 
-            // Handle ZoneGroupTopology events specially - they affect all speakers
-            if let EventData::ZoneGroupTopology(ref zgt_event) = event.event_data {
-                tracing::debug!("Processing ZoneGroupTopology event");
-                let topology_changes = decode_topology_event(zgt_event);
-                apply_topology_changes(
-                    &store,
-                    &watched,
-                    &event_tx,
-                    &ip_to_speaker,
-                    topology_changes,
-                );
-                continue;
+            match addr_map.get(&event.speaker_addr) {
+                Some(id) => id.clone(),
+                None => {
+                    self.route_unregistered(event);
+                    return false;
+                }
             }
+        };
+
+        tracing::debug!(
+            "Mapped {} to speaker_id {}",
+            event.speaker_addr,
+            speaker_id.as_str()
+        );
 
-            // Look up speaker_id from IP for non-topology events
-            let speaker_id = {
-                let ip_map = ip_to_speaker.read();
+        // For PerCoordinator services (e.g. AVTransport), skip events from
+        // non-coordinator speakers. Their events carry empty/default values
+        // because the coordinator owns playback state for the whole group.
+        // The coordinator's events will be propagated to members below.
+        if event.service.scope() == ServiceScope::PerCoordinator {
+            let is_coordinator = {
+                let s = self.store.read();
+                // If no group info exists yet, treat as coordinator (safe default)
+                s.speaker_to_group
+                    .get(&speaker_id)
+                    .and_then(|gid| s.groups.get(gid))
+                    .map(|group| group.coordinator_id == speaker_id)
+                    .unwrap_or(true)
+            };
 
+            if !is_coordinator {
                 tracing::debug!(
-                    "ip_to_speaker map has {} entries: {:?}",
-                    ip_map.len(),
-                    ip_map.keys().collect::<Vec<_>>()
+                    "Skipping PerCoordinator {:?} event from non-coordinator {}",
+                    event.service,
+                    speaker_id.as_str()
                 );
+                return false;
+            }
+        }
 
-                match ip_map.get(&event.speaker_ip) {
-                    Some(id) => id.clone(),
-                    None => {
-                        tracing::warn!(
-                            "Received event from unknown speaker IP: {} (not in ip_to_speaker map)",
-                            event.speaker_ip
-                        );
-                        continue;
-                    }
-                }
-            };
+        // Decode event
+        if let Some(delay) = self.chaos.decode_delay() {
+            thread::sleep(delay);
+        }
+        let decoded = decode_event(event, speaker_id.clone());
+        tracing::debug!(
+            "Decoded {} property changes from event",
+            decoded.changes.len()
+        );
 
-            tracing::debug!(
-                "Mapped IP {} to speaker_id {}",
-                event.speaker_ip,
-                speaker_id.as_str()
+        // Apply changes to the originating speaker (coordinator)
+        apply_speaker_changes(
+            &self.store,
+            &self.watched,
+            &self.origins,
+            &self.transitions,
+            &speaker_id,
+            &decoded.changes,
+        );
+
+        if let Some(room_name) = &decoded.room_name {
+            apply_room_name(
+                &self.store,
+                &self.watched,
+                &self.event_tx,
+                &speaker_id,
+                room_name,
             );
+        }
 
-            // For PerCoordinator services (e.g. AVTransport), skip events from
-            // non-coordinator speakers. Their events carry empty/default values
-            // because the coordinator owns playback state for the whole group.
-            // The coordinator's events will be propagated to members below.
-            if event.service.scope() == ServiceScope::PerCoordinator {
-                let is_coordinator = {
-                    let s = store.read();
-                    // If no group info exists yet, treat as coordinator (safe default)
-                    s.speaker_to_group
-                        .get(&speaker_id)
-                        .and_then(|gid| s.groups.get(gid))
-                        .map(|group| group.coordinator_id == speaker_id)
-                        .unwrap_or(true)
-                };
-
-                if !is_coordinator {
-                    tracing::debug!(
-                        "Skipping PerCoordinator {:?} event from non-coordinator {}",
-                        event.service,
-                        speaker_id.as_str()
-                    );
-                    continue;
-                }
+        // For PerCoordinator services, notify group members who are watching
+        // these properties. No data is copied — members read the coordinator's
+        // value at read time via get_resolved().
+        if event.service.scope() == ServiceScope::PerCoordinator {
+            let members = {
+                let s = self.store.read();
+                resolve_group_members(&s, &speaker_id)
+            };
+            if !members.is_empty() {
+                notify_group_members(&self.watched, &self.event_tx, &members, &decoded.changes);
             }
+        }
+        true
+    }
+}
 
-            // Decode event
-            let decoded = decode_event(&event, speaker_id.clone());
+impl EventPipeline {
+    /// Hold, count or drop an event from an address with no speaker; see
+    /// [`Routing`]
+    fn route_unregistered(&self, event: &EnrichedEvent) {
+        let removed = self
+            .routing
+            .removed
+            .read()
+            .get(&event.speaker_addr)
+            .cloned();
+        if let Some(speaker_id) = removed {
+            self.routing
+                .dropped_for_removed
+                .fetch_add(1, Ordering::SeqCst);
             tracing::debug!(
-                "Decoded {} property changes from event",
-                decoded.changes.len()
+                "Dropping {:?} event for removed speaker {} at {}",
+                event.service,
+                speaker_id.as_str(),
+                event.speaker_addr
             );
+        } else if self.routing.hold(event) {
+            tracing::debug!(
+                "Holding {:?} event from {} until its speaker registers",
+                event.service,
+                event.speaker_addr
+            );
+        } else {
+            tracing::warn!(
+                "Received event from unknown speaker: {} (not in addr_to_speaker map)",
+                event.speaker_addr
+            );
+        }
+    }
 
-            // Apply changes to the originating speaker (coordinator)
-            for change in &decoded.changes {
-                tracing::debug!("Applying change: {:?}", change);
-                apply_property_change(&store, &watched, &event_tx, &speaker_id, change);
-            }
+    /// Process the held events whose speaker has registered since,
+    /// returning how many were applied
+    pub(crate) fn replay_held(&self) -> usize {
+        let ready: VecDeque<EnrichedEvent> = {
+            let mut held = self.routing.held.lock();
+            let Some(queue) = held.as_mut() else {
+                return 0;
+            };
+            let known = self.addr_to_speaker.load();
+            let (ready, waiting) = queue
+                .drain(..)
+                .partition(|event| known.contains_key(&event.speaker_addr));
+            *queue = waiting;
+            ready
+        };
+        ready.iter().filter(|event| self.process(event)).count()
+    }
+}
 
-            // For PerCoordinator services, notify group members who are watching
-            // these properties. No data is copied — members read the coordinator's
-            // value at read time via get_resolved().
-            if event.service.scope() == ServiceScope::PerCoordinator {
-                let members = {
-                    let s = store.read();
-                    resolve_group_members(&s, &speaker_id)
-                };
-                if !members.is_empty() {
-                    notify_group_members(&watched, &event_tx, &members, &decoded.changes);
-                }
+/// Spawns the state event worker thread
+///
+/// This worker consumes events from SonosEventManager's iterator and runs
+/// each through `pipeline`. Once `drain` has started, it stops at its
+/// deadline.
+pub(crate) fn spawn_state_event_worker(
+    event_manager: Arc<SonosEventManager>,
+    pipeline: EventPipeline,
+    drain: Arc<Drain>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        tracing::info!("State event worker started, waiting for events...");
+
+        // Consume events from event manager (blocking)
+        for event in event_manager.iter() {
+            if !drain.admit() {
+                tracing::warn!("Shutdown deadline passed, abandoning undecoded events");
+                break;
             }
+            pipeline.process(&event);
         }
 
         tracing::info!("State event worker stopped");
     })
 }
 
+/// Apply an event's decoded changes to the speaker that sent it
+///
+/// An event reporting the playback state settles any pending check of a
+/// local `Transitioning` write.
+pub(crate) fn apply_speaker_changes(
+    store: &Arc<RwLock<StateStore>>,
+    watched: &Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
+    origins: &OriginTracker,
+    transitions: &TransitionMonitor,
+    speaker_id: &SpeakerId,
+    changes: &[PropertyChange],
+) {
+    for change in changes {
+        tracing::debug!("Applying change: {:?}", change);
+        apply_property_change(store, watched, origins, speaker_id, change);
+    }
+    if changes
+        .iter()
+        .any(|change| change.key() == PlaybackState::KEY)
+    {
+        transitions.cancel(speaker_id);
+    }
+}
+
+/// Apply a ZoneGroupTopology event unless it repeats the last one applied
+///
+/// Returns whether the event was decoded and applied.
+fn apply_topology_event(
+    store: &Arc<RwLock<StateStore>>,
+    watched: &Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
+    event_tx: &ChangeSink,
+    addr_to_speaker: &Arc<ArcSwap<std::collections::HashMap<SocketAddr, SpeakerId>>>,
+    event: &ZoneGroupTopologyState,
+) -> bool {
+    let fingerprint = topology_fingerprint(event);
+    if store.read().topology_fingerprint == Some(fingerprint) {
+        tracing::debug!("ZoneGroupTopology unchanged, skipping");
+        return false;
+    }
+
+    tracing::debug!("Processing ZoneGroupTopology event");
+    let topology_changes = decode_topology_event(event);
+    apply_topology_changes(store, watched, event_tx, addr_to_speaker, topology_changes);
+    store.write().topology_fingerprint = Some(fingerprint);
+    true
+}
+
 /// Apply topology changes from a ZoneGroupTopology event
 ///
 /// This function:
-/// 1. Clears existing groups from the store
-/// 2. Adds new groups from the TopologyChanges
-/// 3. Updates GroupMembership for each speaker
-/// 4. Updates boot_seq, speaker IPs, and satellite IDs
-/// 5. Emits change events for watched GroupMembership properties
+/// 1. Removes groups that are gone or whose coordinator or members changed
+/// 2. Adds the new and changed groups, keeping unchanged groups (and their
+///    group properties) as they are
+/// 3. Updates GroupMembership for the speakers of added groups
+/// 4. Updates boot_seq, firmware versions, speaker IPs, and satellite IDs
+/// 5. Rebuilds the system-scoped GroupList and Topology
+/// 6. Emits change events for the watched GroupMembership, GroupComposition
+///    (keyed on the coordinator of each added or removed group), GroupList
+///    and Topology properties that changed
 fn apply_topology_changes(
     store: &Arc<RwLock<StateStore>>,
     watched: &Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
-    event_tx: &mpsc::Sender<ChangeEvent>,
-    ip_to_speaker: &Arc<RwLock<std::collections::HashMap<IpAddr, SpeakerId>>>,
+    event_tx: &ChangeSink,
+    addr_to_speaker: &Arc<ArcSwap<std::collections::HashMap<SocketAddr, SpeakerId>>>,
     changes: TopologyChanges,
 ) {
     tracing::debug!(
@@ -166,26 +452,82 @@ fn apply_topology_changes(
     );
 
     // Apply all changes within a single write lock
-    let (membership_changes, ip_updates) = {
+    let (membership_changes, composition_changes, system_changes, addr_updates) = {
         let mut store = store.write();
 
-        // 1. Clear existing groups
-        store.clear_groups();
+        // 1. Remove groups that are gone or changed
+        let (unchanged, added): (Vec<GroupInfo>, Vec<GroupInfo>) =
+            changes.groups.into_iter().partition(|group| {
+                store
+                    .groups
+                    .get(&group.id)
+                    .is_some_and(|old| same_group(old, group))
+            });
+        // A topology event describes one household; groups of another
+        // household sharing the store are not stale just because it omits them
+        let households: HashSet<Option<String>> = unchanged
+            .iter()
+            .chain(&added)
+            .map(|group| group_household(&store, group))
+            .collect();
+        let scoped = !households.is_empty() && !households.contains(&None);
+        let stale: Vec<GroupId> = store
+            .groups
+            .values()
+            .filter(|old| !unchanged.iter().any(|group| group.id == old.id))
+            .filter(|old| {
+                !scoped || {
+                    let household = group_household(&store, old);
+                    household.is_none() || households.contains(&household)
+                }
+            })
+            .map(|old| old.id.clone())
+            .collect();
+        let mut recomposed = HashSet::new();
+        let mut regrouped = HashSet::new();
+        for group_id in &stale {
+            if let Some(old) = store.groups.get(group_id) {
+                recomposed.insert(old.coordinator_id.clone());
+                regrouped.extend(old.member_ids.iter().cloned());
+            }
+            store.remove_group(group_id);
+        }
 
-        // 2. Add new groups
-        for group in changes.groups {
+        // 2. Add new and changed groups
+        let mut added_members = HashSet::new();
+        for group in added {
             tracing::debug!(
                 "Adding group {} with {} members",
                 group.id.as_str(),
                 group.member_ids.len()
             );
-            store.add_group(group);
+            added_members.extend(group.member_ids.iter().cloned());
+            let coordinator_id = group.coordinator_id.clone();
+            if store.add_group(group) {
+                recomposed.insert(coordinator_id);
+            }
+        }
+
+        // Stamp everyone whose group changed, so commands to them wait for
+        // the rest of the reshuffle
+        if !stale.is_empty() || !added_members.is_empty() {
+            store.topology_generation += 1;
+            let now = event_tx.now();
+            for speaker_id in regrouped.iter().chain(&added_members) {
+                store.regrouped_at.insert(speaker_id.clone(), now);
+            }
         }
 
-        // 3. Update GroupMembership for each speaker and track which ones changed
+        // 3. Update GroupMembership for speakers whose group changed (or
+        //    that have none yet) and track which ones changed
         let mut changed_memberships = Vec::new();
         for (speaker_id, membership) in changes.memberships {
-            let changed = store.set(&speaker_id, membership);
+            if !added_members.contains(&speaker_id)
+                && store.get::<GroupMembership>(&speaker_id).is_some()
+            {
+                continue;
+            }
+            let changed = store.set_tracked(&speaker_id, membership, ChangeOrigin::Unknown);
             changed_memberships.push((speaker_id, changed));
         }
 
@@ -195,34 +537,54 @@ fn apply_topology_changes(
                 speaker.boot_seq = boot_seq;
             }
         }
+        store.set_software_versions(changes.software);
 
-        // 5. Apply IP updates from topology location URLs
-        let mut changed_ips = Vec::new();
-        for (speaker_id, new_ip) in &changes.speaker_ips {
-            if let Some(old_ip) = store.update_speaker_ip_address(speaker_id, *new_ip) {
+        // 5. Apply address updates from topology location URLs
+        let mut changed_addrs = Vec::new();
+        for (speaker_id, new_addr) in &changes.speaker_addrs {
+            if let Some(old_addr) = store.update_speaker_address(speaker_id, *new_addr) {
                 tracing::info!(
-                    "Speaker {} IP changed: {} -> {}",
+                    "Speaker {} address changed: {} -> {}",
                     speaker_id.as_str(),
-                    old_ip,
-                    new_ip
+                    old_addr,
+                    new_addr
                 );
-                changed_ips.push((old_ip, *new_ip, speaker_id.clone()));
+                changed_addrs.push((old_addr, *new_addr, speaker_id.clone()));
             }
         }
 
-        // 6. Store satellite IDs
+        // 6. Store satellite IDs and home-theater bonds
         store.satellite_ids = changes.satellite_ids.into_iter().collect();
-
-        (changed_memberships, changed_ips)
+        store.set_bonds(changes.bonds);
+
+        // 7. Rebuild the system-wide views
+        let (list_changed, topology_changed) = store.refresh_topology_properties();
+        let system_changes: Vec<&'static str> = [
+            (GroupList::KEY, list_changed),
+            (Topology::KEY, topology_changed),
+        ]
+        .into_iter()
+        .filter_map(|(key, changed)| changed.then_some(key))
+        .collect();
+
+        (
+            changed_memberships,
+            recomposed,
+            system_changes,
+            changed_addrs,
+        )
     };
 
-    // Update ip_to_speaker reverse map (outside store lock)
-    if !ip_updates.is_empty() {
-        let mut map = ip_to_speaker.write();
-        for (old_ip, new_ip, speaker_id) in ip_updates {
-            map.remove(&old_ip);
-            map.insert(new_ip, speaker_id);
-        }
+    // Update addr_to_speaker reverse map (outside store lock)
+    if !addr_updates.is_empty() {
+        addr_to_speaker.rcu(|map| {
+            let mut map = map.as_ref().clone();
+            for (old_addr, new_addr, speaker_id) in &addr_updates {
+                map.remove(old_addr);
+                map.insert(*new_addr, speaker_id.clone());
+            }
+            map
+        });
     }
 
     // Emit change events for watched properties (outside write locks)
@@ -234,13 +596,86 @@ fn apply_topology_changes(
                 "GroupMembership changed for {}, emitting event",
                 speaker_id.as_str()
             );
-            let _ = event_tx.send(ChangeEvent::new(
+            event_tx.send(ChangeEvent::new(
                 speaker_id,
                 GroupMembership::KEY,
                 Service::ZoneGroupTopology,
             ));
         }
     }
+
+    for coordinator_id in composition_changes {
+        if watched_set.contains(&(coordinator_id.clone(), GroupComposition::KEY)) {
+            event_tx.send(ChangeEvent::new(
+                coordinator_id,
+                GroupComposition::KEY,
+                Service::ZoneGroupTopology,
+            ));
+        }
+    }
+
+    let system_id = ChangeEvent::system_id();
+    for key in system_changes {
+        if watched_set.contains(&(system_id.clone(), key)) {
+            event_tx.send(ChangeEvent::new(
+                system_id.clone(),
+                key,
+                Service::ZoneGroupTopology,
+            ));
+        }
+    }
+}
+
+/// Rename a speaker and rebuild [`Topology`], which lists it, returning
+/// whether the name changed
+///
+/// Announces the rename with a [`SpeakerInfo::RENAME_EVENT_KEY`] change
+/// event, so name lookups built on the store can follow it.
+pub(crate) fn apply_room_name(
+    store: &Arc<RwLock<StateStore>>,
+    watched: &Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
+    event_tx: &ChangeSink,
+    speaker_id: &SpeakerId,
+    room_name: &str,
+) -> bool {
+    let topology_changed = {
+        let mut store = store.write();
+        if !store.rename_speaker(speaker_id, room_name) {
+            return false;
+        }
+        store.refresh_topology_properties().1
+    };
+    tracing::info!("Speaker {} renamed to {:?}", speaker_id.as_str(), room_name);
+    event_tx.send(ChangeEvent::new(
+        speaker_id.clone(),
+        SpeakerInfo::RENAME_EVENT_KEY,
+        Service::DeviceProperties,
+    ));
+    let system_id = ChangeEvent::system_id();
+    if topology_changed && watched.read().contains(&(system_id.clone(), Topology::KEY)) {
+        event_tx.send(ChangeEvent::new(
+            system_id,
+            Topology::KEY,
+            Service::DeviceProperties,
+        ));
+    }
+    true
+}
+
+/// Household of a group's coordinator, as reported by discovery
+fn group_household(store: &StateStore, group: &GroupInfo) -> Option<String> {
+    store
+        .speakers
+        .get(&group.coordinator_id)
+        .and_then(|speaker| speaker.household_id.clone())
+}
+
+/// Whether two groups have the same ID, coordinator and members, in any order
+fn same_group(a: &GroupInfo, b: &GroupInfo) -> bool {
+    a.id == b.id
+        && a.coordinator_id == b.coordinator_id
+        && a.member_ids.len() == b.member_ids.len()
+        && a.member_ids.iter().all(|id| b.member_ids.contains(id))
 }
 
 /// Resolve the non-coordinator group members for the given coordinator speaker.
@@ -271,7 +706,7 @@ fn resolve_group_members(store: &StateStore, speaker_id: &SpeakerId) -> Vec<Spea
 /// read the coordinator's value at read time via `StateStore::get_resolved()`.
 fn notify_group_members(
     watched: &Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
-    event_tx: &mpsc::Sender<ChangeEvent>,
+    event_tx: &ChangeSink,
     members: &[SpeakerId],
     changes: &[PropertyChange],
 ) {
@@ -286,8 +721,7 @@ fn notify_group_members(
                         member_id.as_str(),
                         key
                     );
-                    let _ =
-                        event_tx.send(ChangeEvent::new(member_id.clone(), key, change.service()));
+                    event_tx.send(ChangeEvent::new(member_id.clone(), key, change.service()));
                 }
             }
         }
@@ -295,19 +729,24 @@ fn notify_group_members(
 }
 
 /// Apply a single property change to the store
+///
+/// The change is attributed before the store is touched, so the echo of a
+/// local write consumes its expectation even when the value is unchanged
+/// (and no event is emitted).
 fn apply_property_change(
     store: &Arc<RwLock<StateStore>>,
     watched: &Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
-    event_tx: &mpsc::Sender<ChangeEvent>,
+    origins: &OriginTracker,
     speaker_id: &SpeakerId,
     change: &PropertyChange,
 ) {
     let key = change.key();
     let service = change.service();
+    let origin = origins.attribute(speaker_id, key);
 
     let changed = {
         let mut store = store.write();
-        change.apply(&mut store, speaker_id)
+        change.apply(&mut store, speaker_id, origin)
     };
 
     if changed {
@@ -319,7 +758,7 @@ fn apply_property_change(
                 key,
                 speaker_id.as_str()
             );
-            let _ = event_tx.send(ChangeEvent::new(speaker_id.clone(), key, service));
+            origins.emit(ChangeEvent::new(speaker_id.clone(), key, service).with_origin(origin));
         }
     }
 }
@@ -327,22 +766,22 @@ fn apply_property_change(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::GroupId;
-    use crate::property::{GroupInfo, Property, Volume};
+    use crate::property::{GroupVolume, Volume};
     use sonos_api::Service;
+    use sonos_stream::events::types::{NetworkInfo, ZoneGroupInfo, ZoneGroupMemberInfo};
 
     #[test]
     fn test_apply_property_change_volume() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = OriginTracker::channel();
 
         let speaker_id = SpeakerId::new("test-speaker");
 
         // Add speaker to store first
         {
             let mut s = store.write();
-            s.add_speaker(crate::model::SpeakerInfo {
+            s.create_entity(crate::model::SpeakerInfo {
                 id: speaker_id.clone(),
                 name: "Test".to_string(),
                 room_name: "Test".to_string(),
@@ -350,8 +789,10 @@ mod tests {
                 port: 1400,
                 model_name: "Test".to_string(),
                 software_version: "1.0".to_string(),
+                software_generation: None,
                 boot_seq: 0,
                 satellites: vec![],
+                household_id: None,
             });
         }
 
@@ -376,14 +817,14 @@ mod tests {
     fn test_apply_property_change_with_watch() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = OriginTracker::channel();
 
         let speaker_id = SpeakerId::new("test-speaker");
 
         // Add speaker to store
         {
             let mut s = store.write();
-            s.add_speaker(crate::model::SpeakerInfo {
+            s.create_entity(crate::model::SpeakerInfo {
                 id: speaker_id.clone(),
                 name: "Test".to_string(),
                 room_name: "Test".to_string(),
@@ -391,8 +832,10 @@ mod tests {
                 port: 1400,
                 model_name: "Test".to_string(),
                 software_version: "1.0".to_string(),
+                software_generation: None,
                 boot_seq: 0,
                 satellites: vec![],
+                household_id: None,
             });
         }
 
@@ -432,8 +875,10 @@ mod tests {
             port: 1400,
             model_name: "Test".to_string(),
             software_version: "1.0".to_string(),
+            software_generation: None,
             boot_seq: 0,
             satellites: vec![],
+            household_id: None,
         }
     }
 
@@ -441,7 +886,7 @@ mod tests {
     fn test_apply_property_change_group_volume() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, _rx) = mpsc::channel();
+        let (tx, _rx) = OriginTracker::channel();
 
         let speaker_id = SpeakerId::new("RINCON_111");
         let group_id = GroupId::new("RINCON_111:1");
@@ -449,7 +894,7 @@ mod tests {
         // Add speaker and group to store
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_111",
                 "Living Room",
                 "192.168.1.101",
@@ -480,14 +925,14 @@ mod tests {
     fn test_apply_property_change_group_volume_no_group() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, _rx) = mpsc::channel();
+        let (tx, _rx) = OriginTracker::channel();
 
         let speaker_id = SpeakerId::new("RINCON_111");
 
         // Add speaker but no group
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_111",
                 "Living Room",
                 "192.168.1.101",
@@ -512,17 +957,17 @@ mod tests {
     fn test_apply_topology_changes_updates_groups() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, _rx) = mpsc::channel();
+        let (tx, _rx) = ChangeSink::channel();
 
         // Add speakers to store
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_111",
                 "Living Room",
                 "192.168.1.101",
             ));
-            s.add_speaker(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
+            s.create_entity(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
         }
 
         // Create topology changes with one group containing both speakers
@@ -547,12 +992,14 @@ mod tests {
                 ),
             ],
             boot_seqs: vec![],
-            speaker_ips: vec![],
+            software: vec![],
+            speaker_addrs: vec![],
             satellite_ids: vec![],
+            bonds: vec![],
         };
 
-        let ip_to_speaker = Arc::new(RwLock::new(std::collections::HashMap::new()));
-        apply_topology_changes(&store, &watched, &tx, &ip_to_speaker, changes);
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(std::collections::HashMap::new()));
+        apply_topology_changes(&store, &watched, &tx, &addr_to_speaker, changes);
 
         // Verify groups are updated
         let s = store.read();
@@ -569,17 +1016,17 @@ mod tests {
     fn test_apply_topology_changes_updates_group_membership() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, _rx) = mpsc::channel();
+        let (tx, _rx) = ChangeSink::channel();
 
         // Add speakers to store
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_111",
                 "Living Room",
                 "192.168.1.101",
             ));
-            s.add_speaker(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
+            s.create_entity(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
         }
 
         let group_id = GroupId::new("RINCON_111:1");
@@ -603,12 +1050,14 @@ mod tests {
                 ),
             ],
             boot_seqs: vec![],
-            speaker_ips: vec![],
+            software: vec![],
+            speaker_addrs: vec![],
             satellite_ids: vec![],
+            bonds: vec![],
         };
 
-        let ip_to_speaker = Arc::new(RwLock::new(std::collections::HashMap::new()));
-        apply_topology_changes(&store, &watched, &tx, &ip_to_speaker, changes);
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(std::collections::HashMap::new()));
+        apply_topology_changes(&store, &watched, &tx, &addr_to_speaker, changes);
 
         // Verify GroupMembership is updated for each speaker
         let s = store.read();
@@ -630,7 +1079,7 @@ mod tests {
     fn test_apply_topology_changes_emits_events_for_watched_properties() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = ChangeSink::channel();
 
         let speaker1 = SpeakerId::new("RINCON_111");
         let speaker2 = SpeakerId::new("RINCON_222");
@@ -638,12 +1087,12 @@ mod tests {
         // Add speakers to store
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_111",
                 "Living Room",
                 "192.168.1.101",
             ));
-            s.add_speaker(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
+            s.create_entity(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
         }
 
         // Watch GroupMembership for speaker1 only
@@ -671,12 +1120,14 @@ mod tests {
                 ),
             ],
             boot_seqs: vec![],
-            speaker_ips: vec![],
+            software: vec![],
+            speaker_addrs: vec![],
             satellite_ids: vec![],
+            bonds: vec![],
         };
 
-        let ip_to_speaker = Arc::new(RwLock::new(std::collections::HashMap::new()));
-        apply_topology_changes(&store, &watched, &tx, &ip_to_speaker, changes);
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(std::collections::HashMap::new()));
+        apply_topology_changes(&store, &watched, &tx, &addr_to_speaker, changes);
 
         // Should receive event for speaker1 (watched) but not speaker2 (not watched)
         let event = rx.try_recv().unwrap();
@@ -692,7 +1143,7 @@ mod tests {
     fn test_apply_topology_changes_clears_old_groups() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, _rx) = mpsc::channel();
+        let (tx, _rx) = ChangeSink::channel();
 
         let speaker1 = SpeakerId::new("RINCON_111");
         let speaker2 = SpeakerId::new("RINCON_222");
@@ -700,12 +1151,12 @@ mod tests {
         // Add speakers and an initial group
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_111",
                 "Living Room",
                 "192.168.1.101",
             ));
-            s.add_speaker(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
+            s.create_entity(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
 
             // Add initial group
             let old_group_id = GroupId::new("OLD_GROUP:1");
@@ -742,12 +1193,14 @@ mod tests {
                 ),
             ],
             boot_seqs: vec![],
-            speaker_ips: vec![],
+            software: vec![],
+            speaker_addrs: vec![],
             satellite_ids: vec![],
+            bonds: vec![],
         };
 
-        let ip_to_speaker = Arc::new(RwLock::new(std::collections::HashMap::new()));
-        apply_topology_changes(&store, &watched, &tx, &ip_to_speaker, changes);
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(std::collections::HashMap::new()));
+        apply_topology_changes(&store, &watched, &tx, &addr_to_speaker, changes);
 
         // Verify old group is gone, new group exists
         let s = store.read();
@@ -760,7 +1213,7 @@ mod tests {
     fn test_apply_topology_changes_updates_speaker_to_group_mapping() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, _rx) = mpsc::channel();
+        let (tx, _rx) = ChangeSink::channel();
 
         let speaker1 = SpeakerId::new("RINCON_111");
         let speaker2 = SpeakerId::new("RINCON_222");
@@ -768,12 +1221,12 @@ mod tests {
         // Add speakers
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_111",
                 "Living Room",
                 "192.168.1.101",
             ));
-            s.add_speaker(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
+            s.create_entity(make_speaker_info("RINCON_222", "Kitchen", "192.168.1.102"));
         }
 
         let group_id = GroupId::new("RINCON_111:1");
@@ -795,12 +1248,14 @@ mod tests {
                 ),
             ],
             boot_seqs: vec![],
-            speaker_ips: vec![],
+            software: vec![],
+            speaker_addrs: vec![],
             satellite_ids: vec![],
+            bonds: vec![],
         };
 
-        let ip_to_speaker = Arc::new(RwLock::new(std::collections::HashMap::new()));
-        apply_topology_changes(&store, &watched, &tx, &ip_to_speaker, changes);
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(std::collections::HashMap::new()));
+        apply_topology_changes(&store, &watched, &tx, &addr_to_speaker, changes);
 
         // Verify speaker_to_group mapping is updated
         let s = store.read();
@@ -812,7 +1267,7 @@ mod tests {
     fn test_apply_topology_changes_no_event_when_membership_unchanged() {
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = ChangeSink::channel();
 
         let speaker1 = SpeakerId::new("RINCON_111");
         let group_id = GroupId::new("RINCON_111:1");
@@ -820,7 +1275,7 @@ mod tests {
         // Add speaker and set initial membership
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_111",
                 "Living Room",
                 "192.168.1.101",
@@ -846,17 +1301,277 @@ mod tests {
                 GroupMembership::new(group_id.clone(), true),
             )],
             boot_seqs: vec![],
-            speaker_ips: vec![],
+            software: vec![],
+            speaker_addrs: vec![],
             satellite_ids: vec![],
+            bonds: vec![],
         };
 
-        let ip_to_speaker = Arc::new(RwLock::new(std::collections::HashMap::new()));
-        apply_topology_changes(&store, &watched, &tx, &ip_to_speaker, changes);
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(std::collections::HashMap::new()));
+        apply_topology_changes(&store, &watched, &tx, &addr_to_speaker, changes);
 
         // No event should be emitted since membership didn't change
         assert!(rx.try_recv().is_err());
     }
 
+    /// A topology event; each group is (coordinator, members) with ID
+    /// `{coordinator}:{n}`
+    fn zgt_event(groups: &[(&str, u32, &[&str])]) -> ZoneGroupTopologyState {
+        let member = |uuid: &str| ZoneGroupMemberInfo {
+            uuid: uuid.to_string(),
+            location: format!("http://192.168.1.{}:1400/xml", &uuid[uuid.len() - 3..]),
+            zone_name: uuid.to_string(),
+            software_version: "1.0".to_string(),
+            software_generation: None,
+            boot_seq: 1,
+            network_info: NetworkInfo {
+                wireless_mode: "0".to_string(),
+                wifi_enabled: "1".to_string(),
+                eth_link: "1".to_string(),
+                channel_freq: "2412".to_string(),
+                behind_wifi_extender: "0".to_string(),
+            },
+            satellites: vec![],
+        };
+        ZoneGroupTopologyState {
+            zone_groups: groups
+                .iter()
+                .map(|(coordinator, n, members)| ZoneGroupInfo {
+                    coordinator: coordinator.to_string(),
+                    id: format!("{coordinator}:{n}"),
+                    members: members.iter().map(|uuid| member(uuid)).collect(),
+                })
+                .collect(),
+            vanished_devices: vec![],
+        }
+    }
+
+    /// A store with speakers 101, 102 and 103 and every membership watched
+    #[allow(clippy::type_complexity)]
+    fn topology_fixture() -> (
+        Arc<RwLock<StateStore>>,
+        Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
+        Arc<ArcSwap<std::collections::HashMap<SocketAddr, SpeakerId>>>,
+    ) {
+        let store = Arc::new(RwLock::new(StateStore::new()));
+        let watched = Arc::new(RwLock::new(HashSet::new()));
+        for n in ["101", "102", "103"] {
+            let id = format!("RINCON_{n}");
+            store
+                .write()
+                .create_entity(make_speaker_info(&id, &id, &format!("192.168.1.{n}")));
+            watched
+                .write()
+                .insert((SpeakerId::new(&id), GroupMembership::KEY));
+        }
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(std::collections::HashMap::new()));
+        (store, watched, addr_to_speaker)
+    }
+
+    #[test]
+    fn test_apply_topology_event_skips_reordered_repeat() {
+        let (store, watched, addrs) = topology_fixture();
+        let (tx, rx) = ChangeSink::channel();
+        let first = zgt_event(&[
+            ("RINCON_101", 1, &["RINCON_101", "RINCON_102"]),
+            ("RINCON_103", 1, &["RINCON_103"]),
+        ]);
+        assert!(apply_topology_event(&store, &watched, &tx, &addrs, &first));
+        assert_eq!(rx.try_iter().count(), 3);
+
+        // Markers any write would overwrite
+        let group = GroupId::new("RINCON_101:1");
+        {
+            let mut s = store.write();
+            s.set_group(&group, GroupVolume(40));
+            s.speakers
+                .get_mut(&SpeakerId::new("RINCON_102"))
+                .unwrap()
+                .boot_seq = 99;
+        }
+
+        let reordered = zgt_event(&[
+            ("RINCON_103", 1, &["RINCON_103"]),
+            ("RINCON_101", 1, &["RINCON_102", "RINCON_101"]),
+        ]);
+        assert!(!apply_topology_event(
+            &store, &watched, &tx, &addrs, &reordered
+        ));
+
+        let s = store.read();
+        assert_eq!(s.get_group::<GroupVolume>(&group), Some(GroupVolume(40)));
+        assert_eq!(s.speakers[&SpeakerId::new("RINCON_102")].boot_seq, 99);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_apply_topology_event_touches_only_regrouped_speakers() {
+        let (store, watched, addrs) = topology_fixture();
+        let (tx, rx) = ChangeSink::channel();
+        let standalone = zgt_event(&[
+            ("RINCON_101", 1, &["RINCON_101"]),
+            ("RINCON_102", 1, &["RINCON_102"]),
+            ("RINCON_103", 1, &["RINCON_103"]),
+        ]);
+        assert!(apply_topology_event(
+            &store,
+            &watched,
+            &tx,
+            &addrs,
+            &standalone
+        ));
+        rx.try_iter().for_each(drop);
+
+        // 103's group and membership stay put: a write would overwrite these
+        let untouched = SpeakerId::new("RINCON_103");
+        let untouched_group = GroupId::new("RINCON_103:1");
+        let marker = GroupMembership::new(untouched_group.clone(), false);
+        {
+            let mut s = store.write();
+            s.set_group(&untouched_group, GroupVolume(40));
+            s.set(&untouched, marker.clone());
+        }
+
+        // 102 joins 101
+        let joined = zgt_event(&[
+            ("RINCON_101", 2, &["RINCON_101", "RINCON_102"]),
+            ("RINCON_103", 1, &["RINCON_103"]),
+        ]);
+        assert!(apply_topology_event(&store, &watched, &tx, &addrs, &joined));
+
+        let mut changed: Vec<String> = rx
+            .try_iter()
+            .map(|e| e.speaker_id.as_str().to_string())
+            .collect();
+        changed.sort();
+        assert_eq!(changed, ["RINCON_101", "RINCON_102"]);
+
+        let s = store.read();
+        assert_eq!(s.groups.len(), 2);
+        assert_eq!(
+            s.get::<GroupMembership>(&SpeakerId::new("RINCON_102")),
+            Some(GroupMembership::new(GroupId::new("RINCON_101:2"), false))
+        );
+        assert_eq!(s.get::<GroupMembership>(&untouched), Some(marker));
+        assert_eq!(
+            s.get_group::<GroupVolume>(&untouched_group),
+            Some(GroupVolume(40))
+        );
+    }
+
+    #[test]
+    fn test_apply_topology_event_keeps_other_household_groups() {
+        let (store, watched, addrs) = topology_fixture();
+        let (tx, _rx) = ChangeSink::channel();
+        {
+            let mut s = store.write();
+            for (n, household) in [("101", "Sonos_A"), ("102", "Sonos_A"), ("103", "Sonos_B")] {
+                let id = SpeakerId::new(format!("RINCON_{n}"));
+                s.speakers.get_mut(&id).unwrap().household_id = Some(household.to_string());
+            }
+        }
+        let household_b = zgt_event(&[("RINCON_103", 1, &["RINCON_103"])]);
+        assert!(apply_topology_event(
+            &store,
+            &watched,
+            &tx,
+            &addrs,
+            &household_b
+        ));
+
+        // Household A's speaker only reports its own groups
+        let household_a = zgt_event(&[("RINCON_101", 1, &["RINCON_101", "RINCON_102"])]);
+        assert!(apply_topology_event(
+            &store,
+            &watched,
+            &tx,
+            &addrs,
+            &household_a
+        ));
+
+        let s = store.read();
+        assert_eq!(s.groups.len(), 2);
+        assert!(s.groups.contains_key(&GroupId::new("RINCON_103:1")));
+    }
+
+    #[test]
+    fn test_granular_topology_properties_fire_only_when_affected() {
+        let (store, watched, addrs) = topology_fixture();
+        let (tx, rx) = ChangeSink::channel();
+        {
+            let mut w = watched.write();
+            for n in ["101", "102", "103"] {
+                w.insert((SpeakerId::new(format!("RINCON_{n}")), GroupComposition::KEY));
+            }
+            w.insert((ChangeEvent::system_id(), GroupList::KEY));
+            w.insert((ChangeEvent::system_id(), Topology::KEY));
+        }
+        let changed = |rx: &std::sync::mpsc::Receiver<ChangeEvent>| {
+            let mut keys: Vec<(String, &'static str)> = rx
+                .try_iter()
+                .map(|e| (e.speaker_id.as_str().to_string(), e.property_key))
+                .collect();
+            keys.sort();
+            keys
+        };
+        let groups: &[(&str, u32, &[&str])] = &[
+            ("RINCON_101", 1, &["RINCON_101"]),
+            ("RINCON_102", 1, &["RINCON_102"]),
+            ("RINCON_103", 1, &["RINCON_103"]),
+        ];
+        assert!(apply_topology_event(
+            &store,
+            &watched,
+            &tx,
+            &addrs,
+            &zgt_event(groups)
+        ));
+        rx.try_iter().for_each(drop);
+
+        // A Wi-Fi attribute alone doesn't reach the decoder
+        let mut wifi = zgt_event(groups);
+        wifi.zone_groups[0].members[0].network_info.channel_freq = "5180".to_string();
+        assert!(!apply_topology_event(&store, &watched, &tx, &addrs, &wifi));
+        assert!(changed(&rx).is_empty());
+
+        // A reboot changes the full Topology, but no group
+        let mut rebooted = zgt_event(groups);
+        rebooted.zone_groups[2].members[0].boot_seq = 2;
+        assert!(apply_topology_event(
+            &store, &watched, &tx, &addrs, &rebooted
+        ));
+        assert_eq!(changed(&rx), [(String::new(), Topology::KEY)]);
+
+        // 102 joins 101: 103's group is untouched
+        let mut joined = zgt_event(&[
+            ("RINCON_101", 2, &["RINCON_101", "RINCON_102"]),
+            ("RINCON_103", 1, &["RINCON_103"]),
+        ]);
+        joined.zone_groups[1].members[0].boot_seq = 2;
+        assert!(apply_topology_event(&store, &watched, &tx, &addrs, &joined));
+        assert_eq!(
+            changed(&rx),
+            [
+                (String::new(), GroupList::KEY),
+                (String::new(), Topology::KEY),
+                ("RINCON_101".to_string(), GroupComposition::KEY),
+                ("RINCON_101".to_string(), GroupMembership::KEY),
+                ("RINCON_102".to_string(), GroupComposition::KEY),
+                ("RINCON_102".to_string(), GroupMembership::KEY),
+            ]
+        );
+
+        let s = store.read();
+        assert_eq!(
+            s.get_group::<GroupComposition>(&GroupId::new("RINCON_101:2")),
+            Some(GroupComposition::new(
+                SpeakerId::new("RINCON_101"),
+                vec![SpeakerId::new("RINCON_102"), SpeakerId::new("RINCON_101")]
+            ))
+        );
+        assert_eq!(s.get_system::<GroupList>().map(|l| l.len()), Some(2));
+    }
+
     // ========================================================================
     // PerCoordinator Read-Time Resolution Tests
     // ========================================================================
@@ -867,7 +1582,7 @@ mod tests {
 
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = OriginTracker::channel();
 
         let coordinator = SpeakerId::new("RINCON_COORD");
         let member = SpeakerId::new("RINCON_MEMBER");
@@ -876,12 +1591,12 @@ mod tests {
         // Add speakers and group
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_COORD",
                 "Bedroom",
                 "192.168.1.101",
             ));
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_MEMBER",
                 "Kitchen",
                 "192.168.1.102",
@@ -913,7 +1628,7 @@ mod tests {
             let s = store.read();
             resolve_group_members(&s, &coordinator)
         };
-        notify_group_members(&watched, &tx, &members, &changes);
+        notify_group_members(&watched, tx.sink(), &members, &changes);
 
         // Both coordinator and member should have received ChangeEvents
         let event1 = rx.try_recv().unwrap();
@@ -947,7 +1662,7 @@ mod tests {
 
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = OriginTracker::channel();
 
         let speaker = SpeakerId::new("RINCON_STANDALONE");
         let group_id = GroupId::new("RINCON_STANDALONE:1");
@@ -955,7 +1670,7 @@ mod tests {
         // Add standalone speaker (single-member group)
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_STANDALONE",
                 "Bedroom",
                 "192.168.1.101",
@@ -998,7 +1713,7 @@ mod tests {
         // notify group members even when a group exists.
         let store = Arc::new(RwLock::new(StateStore::new()));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = OriginTracker::channel();
 
         let coordinator = SpeakerId::new("RINCON_COORD");
         let member = SpeakerId::new("RINCON_MEMBER");
@@ -1007,12 +1722,12 @@ mod tests {
         // Add speakers and group
         {
             let mut s = store.write();
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_COORD",
                 "Bedroom",
                 "192.168.1.101",
             ));
-            s.add_speaker(make_speaker_info(
+            s.create_entity(make_speaker_info(
                 "RINCON_MEMBER",
                 "Kitchen",
                 "192.168.1.102",
@@ -1067,12 +1782,12 @@ mod tests {
         let member = SpeakerId::new("RINCON_MEMBER");
         let group_id = GroupId::new("RINCON_COORD:1");
 
-        store.add_speaker(make_speaker_info(
+        store.create_entity(make_speaker_info(
             "RINCON_COORD",
             "Bedroom",
             "192.168.1.101",
         ));
-        store.add_speaker(make_speaker_info(
+        store.create_entity(make_speaker_info(
             "RINCON_MEMBER",
             "Kitchen",
             "192.168.1.102",
@@ -1093,7 +1808,7 @@ mod tests {
         use crate::property::PlaybackState;
 
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = ChangeSink::channel();
 
         let member_watched = SpeakerId::new("RINCON_WATCHED");
         let member_unwatched = SpeakerId::new("RINCON_UNWATCHED");
@@ -1118,4 +1833,91 @@ mod tests {
         // No event for the unwatched member
         assert!(rx.try_recv().is_err());
     }
+
+    // ========================================================================
+    // Change origin
+    // ========================================================================
+
+    fn watched_volume(speaker_id: &SpeakerId) -> Arc<RwLock<HashSet<(SpeakerId, &'static str)>>> {
+        Arc::new(RwLock::new(HashSet::from([(
+            speaker_id.clone(),
+            Volume::KEY,
+        )])))
+    }
+
+    #[test]
+    fn test_local_write_echo_is_suppressed() {
+        use crate::origin::ChangeOrigin;
+
+        let store = Arc::new(RwLock::new(StateStore::new()));
+        let speaker_id = SpeakerId::new("RINCON_A");
+        store
+            .write()
+            .create_entity(make_speaker_info("RINCON_A", "Den", "192.168.1.50"));
+        let watched = watched_volume(&speaker_id);
+        let (origins, rx) = OriginTracker::channel();
+
+        // What StateManager::apply_local_write() does after SetVolume(30)
+        origins.expect_write(&speaker_id, Volume::KEY);
+        store.write().set(&speaker_id, Volume(30));
+
+        let echo = PropertyChange::Volume(Volume(30));
+        apply_property_change(&store, &watched, &origins, &speaker_id, &echo);
+        assert!(rx.try_recv().is_err(), "echo must not surface");
+
+        // The echo used up the expectation; the next step is someone else's
+        let step = PropertyChange::Volume(Volume(32));
+        apply_property_change(&store, &watched, &origins, &speaker_id, &step);
+        assert_eq!(rx.try_recv().unwrap().origin, ChangeOrigin::External);
+    }
+
+    #[test]
+    fn test_unexpected_event_is_external() {
+        use crate::origin::ChangeOrigin;
+
+        let store = Arc::new(RwLock::new(StateStore::new()));
+        let speaker_id = SpeakerId::new("RINCON_A");
+        store
+            .write()
+            .create_entity(make_speaker_info("RINCON_A", "Den", "192.168.1.50"));
+        let watched = watched_volume(&speaker_id);
+        let (origins, rx) = OriginTracker::channel();
+
+        let change = PropertyChange::Volume(Volume(12));
+        apply_property_change(&store, &watched, &origins, &speaker_id, &change);
+
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.origin, ChangeOrigin::External);
+        assert_eq!(event.coalesced, 1);
+    }
+
+    #[test]
+    fn test_external_volume_burst_is_coalesced() {
+        use crate::origin::{ChangeOrigin, DEFAULT_EXPECTATION_WINDOW};
+        use std::time::Duration;
+
+        let store = Arc::new(RwLock::new(StateStore::new()));
+        let speaker_id = SpeakerId::new("RINCON_A");
+        store
+            .write()
+            .create_entity(make_speaker_info("RINCON_A", "Den", "192.168.1.50"));
+        let watched = watched_volume(&speaker_id);
+        let (sink, rx) = ChangeSink::channel();
+        let origins =
+            OriginTracker::new(sink, DEFAULT_EXPECTATION_WINDOW, Duration::from_millis(100));
+
+        // Five presses of the volume-up button
+        for level in 21..=25 {
+            let change = PropertyChange::Volume(Volume(level));
+            apply_property_change(&store, &watched, &origins, &speaker_id, &change);
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(rx.try_recv().is_err(), "held until the burst goes quiet");
+
+        let event = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(event.origin, ChangeOrigin::External);
+        assert_eq!(event.coalesced, 5);
+        assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+        assert_eq!(store.read().get::<Volume>(&speaker_id), Some(Volume(25)));
+    }
 }
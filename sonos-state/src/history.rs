@@ -0,0 +1,218 @@
+//! Bounded change history
+//!
+//! When enabled with [`StateManagerBuilder::history_size()`], the store keeps
+//! the last N property changes with the values before and after, for
+//! activity feeds and simple undo. Off by default: at size 0 nothing is
+//! cloned or kept.
+//!
+//! [`StateManagerBuilder::history_size()`]: crate::StateManagerBuilder::history_size
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use sonos_api::clock::{SharedClock, SystemClock};
+use sonos_api::Service;
+
+use crate::model::SpeakerId;
+use crate::origin::ChangeOrigin;
+use crate::property::{Property, SonosProperty};
+
+type Value = Arc<dyn Any + Send + Sync>;
+
+/// One recorded property change
+#[derive(Clone)]
+pub struct HistoryEntry {
+    /// Position in the history, one higher for each change recorded
+    pub seq: u64,
+    /// Speaker that changed (the coordinator, for group properties)
+    pub speaker_id: SpeakerId,
+    /// Property key that changed
+    pub property_key: &'static str,
+    /// Service the property belongs to
+    pub service: Service,
+    /// When the change was applied
+    pub timestamp: Instant,
+    /// Who caused the change
+    pub origin: ChangeOrigin,
+    before: Option<Value>,
+    after: Value,
+    /// Set once undone, so the next undo reaches further back
+    undone: bool,
+}
+
+impl HistoryEntry {
+    /// Value before the change, or `None` if the property had no value yet
+    /// (or `P` is not this entry's property)
+    pub fn before<P: Property>(&self) -> Option<P> {
+        self.before.as_ref()?.downcast_ref::<P>().cloned()
+    }
+
+    /// Value after the change, or `None` if `P` is not this entry's property
+    pub fn after<P: Property>(&self) -> Option<P> {
+        self.after.downcast_ref::<P>().cloned()
+    }
+}
+
+impl fmt::Debug for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HistoryEntry")
+            .field("seq", &self.seq)
+            .field("speaker_id", &self.speaker_id)
+            .field("property_key", &self.property_key)
+            .field("timestamp", &self.timestamp)
+            .field("origin", &self.origin)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Selects entries from [`StateManager::history()`](crate::StateManager::history)
+///
+/// Results are oldest first. To page through them, set a `limit` and pass
+/// the last `seq` of each page to [`after()`](Self::after) for the next.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    speaker_id: Option<SpeakerId>,
+    property_key: Option<&'static str>,
+    since: Option<Instant>,
+    until: Option<Instant>,
+    after_seq: Option<u64>,
+    limit: Option<usize>,
+}
+
+impl HistoryFilter {
+    /// Match every entry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only changes to `speaker_id`
+    pub fn speaker(mut self, speaker_id: &SpeakerId) -> Self {
+        self.speaker_id = Some(speaker_id.clone());
+        self
+    }
+
+    /// Only changes to property `P`
+    pub fn property<P: Property>(mut self) -> Self {
+        self.property_key = Some(P::KEY);
+        self
+    }
+
+    /// Only changes at or after `time`
+    pub fn since(mut self, time: Instant) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    /// Only changes before `time`
+    pub fn until(mut self, time: Instant) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    /// Only entries with a `seq` greater than `seq`
+    pub fn after(mut self, seq: u64) -> Self {
+        self.after_seq = Some(seq);
+        self
+    }
+
+    /// Return at most `limit` entries
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.speaker_id
+            .as_ref()
+            .map_or(true, |id| *id == entry.speaker_id)
+            && self
+                .property_key
+                .map_or(true, |key| key == entry.property_key)
+            && self.since.map_or(true, |t| entry.timestamp >= t)
+            && self.until.map_or(true, |t| entry.timestamp < t)
+            && self.after_seq.map_or(true, |seq| entry.seq > seq)
+    }
+}
+
+/// The last `capacity` changes, oldest first
+pub(crate) struct ChangeHistory {
+    capacity: usize,
+    next_seq: u64,
+    entries: VecDeque<HistoryEntry>,
+    /// Timestamps entries
+    clock: SharedClock,
+}
+
+impl ChangeHistory {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_seq: 0,
+            entries: VecDeque::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Timestamp entries with `clock` instead of the system clock
+    pub(crate) fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub(crate) fn record<P: SonosProperty>(
+        &mut self,
+        speaker_id: &SpeakerId,
+        origin: ChangeOrigin,
+        before: Option<P>,
+        after: P,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.next_seq += 1;
+        self.entries.push_back(HistoryEntry {
+            seq: self.next_seq,
+            speaker_id: speaker_id.clone(),
+            property_key: P::KEY,
+            service: P::SERVICE,
+            timestamp: self.clock.now(),
+            origin,
+            before: before.map(|v| Arc::new(v) as Value),
+            after: Arc::new(after),
+            undone: false,
+        });
+    }
+
+    pub(crate) fn query(&self, filter: &HistoryFilter) -> Vec<HistoryEntry> {
+        self.entries
+            .iter()
+            .filter(|e| filter.matches(e))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// Mark the newest undoable change to `P` on `speaker_id` as undone and
+    /// return the value it replaced. Restores themselves are skipped.
+    pub(crate) fn take_undo<P: SonosProperty>(&mut self, speaker_id: &SpeakerId) -> Option<P> {
+        let entry = self.entries.iter_mut().rev().find(|e| {
+            !e.undone
+                && e.origin != ChangeOrigin::Restore
+                && e.property_key == P::KEY
+                && e.speaker_id == *speaker_id
+        })?;
+        let before = entry.before::<P>()?;
+        entry.undone = true;
+        Some(before)
+    }
+}
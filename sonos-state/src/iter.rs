@@ -153,6 +153,7 @@ impl<'a> Iterator for TimeoutIter<'a> {
 mod tests {
     use super::*;
     use crate::model::SpeakerId;
+    use crate::origin::ChangeOrigin;
     use sonos_api::Service;
     use std::thread;
     use std::time::Instant;
@@ -163,6 +164,9 @@ mod tests {
             property_key: "volume",
             service: Service::RenderingControl,
             timestamp: Instant::now(),
+            origin: ChangeOrigin::Unknown,
+            coalesced: 1,
+            batch: Vec::new(),
         }
     }
 
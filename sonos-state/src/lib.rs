@@ -67,9 +67,19 @@ pub mod decoder;
 pub(crate) mod event_worker;
 
 // Sync-first API
+pub mod coordinator;
+pub mod history;
 pub mod iter;
+pub mod listening;
+pub mod middleware;
+pub mod origin;
+pub mod persistence;
+pub mod schema;
+pub mod simulation;
+pub mod snapshot;
 pub mod speaker;
 pub mod state;
+pub mod transition;
 
 // Error types
 pub mod error;
@@ -79,15 +89,59 @@ pub mod error;
 // ============================================================================
 
 // State manager
-pub use state::{ChangeEvent, EventInitFn, StateManager, StateManagerBuilder};
+pub use state::{
+    ChangeEvent, ChangeObserver, EventInitFn, RerenderScope, ShutdownReport, StateManager,
+    StateManagerBuilder, WriteBatch,
+};
+
+// Suspend policy for StateManager::suspend()
+pub use sonos_event_manager::SuspendPolicy;
+
+// Coordinator routing for group commands
+pub use coordinator::{CoordinatorResolver, CoordinatorRoute, RoutingStats, DEFAULT_SETTLE_DELAY};
+
+// Write interception and change middleware
+pub use middleware::{ChangeMiddleware, InterceptDecision, WriteInterceptor, WriteRequest};
+
+// Change attribution
+pub use origin::{ChangeOrigin, OriginClassifier};
+
+// Change history
+pub use history::{HistoryEntry, HistoryFilter};
+
+// Change persistence
+pub use persistence::{
+    NdjsonFileSink, PersistedChange, PersistenceConfig, PersistenceHandle, PersistenceSink,
+    SinkError,
+};
+
+// Property schemas and dynamic access
+pub use schema::{
+    DynamicUpdate, DynamicValue, DynamicWatcher, PropertySchema, ValueKind, WatchStatus,
+};
+
+// Listening sessions
+pub use listening::{ListeningEvent, ListeningEvents, ListeningRules};
+
+// Store snapshots and diffs
+pub use snapshot::{
+    DiffOptions, Presence, PropertyDiff, SpeakerDiff, SpeakerSnapshot, StoreDiff, StoreSnapshot,
+    ValueDiff,
+};
+
+// Synthetic events for development without speakers
+pub use simulation::{Journal, JournalEntry, SimulatedChange, SimulatedTrack};
 
 // Change iterator
 pub use iter::ChangeIterator;
 
 // Properties
 pub use property::{
-    Bass, CurrentTrack, GroupInfo, GroupMembership, GroupMute, GroupVolume, GroupVolumeChangeable,
-    Loudness, Mute, PlaybackState, Position, Property, Scope, Topology, Treble, Volume,
+    AudioOutput, Bass, ButtonLock, CurrentPlayMode, CurrentTrack, GroupComposition, GroupInfo,
+    GroupList, GroupMembership, GroupMute, GroupVolume, GroupVolumeChangeable, Loudness, Mute,
+    OutputFixed, PlaybackState, Position, Presets, Property, Scope, StatusLight, SubEnabled,
+    SubGain, SurroundEnabled, SurroundLevel, SurroundMode, Topology, TransportActions, Treble,
+    Volume,
 };
 
 // Model types
@@ -95,8 +149,8 @@ pub use model::{GroupId, SpeakerId, SpeakerInfo};
 
 // Event decoder
 pub use decoder::{
-    decode_event, decode_topology_event, parse_track_metadata, DecodedChanges, PropertyChange,
-    TopologyChanges,
+    decode_event, decode_topology_event, parse_track_metadata, topology_fingerprint,
+    DecodedChanges, PropertyChange, TopologyChanges,
 };
 
 // Error types
@@ -110,8 +164,11 @@ pub use error::{Result, StateError};
 pub mod prelude {
     // Properties
     pub use crate::property::{
-        Bass, CurrentTrack, GroupMembership, GroupMute, GroupVolume, GroupVolumeChangeable,
-        Loudness, Mute, PlaybackState, Position, Property, Scope, Topology, Treble, Volume,
+        AudioOutput, Bass, ButtonLock, CurrentPlayMode, CurrentTrack, GroupComposition, GroupList,
+        GroupMembership, GroupMute, GroupVolume, GroupVolumeChangeable, Loudness, Mute,
+        OutputFixed, PlaybackState, Position, Presets, Property, Scope, StatusLight, SubEnabled,
+        SubGain, SurroundEnabled, SurroundLevel, SurroundMode, Topology, TransportActions, Treble,
+        Volume,
     };
 
     // Model types
@@ -119,6 +176,7 @@ pub mod prelude {
 
     // State management
     pub use crate::iter::ChangeIterator;
+    pub use crate::origin::ChangeOrigin;
     pub use crate::state::{ChangeEvent, StateManager};
 
     // Error types
@@ -0,0 +1,793 @@
+//! Listening sessions, for scrobbling
+//!
+//! [`StateManager::listening_events()`] installs a tracker that follows the
+//! [`PlaybackState`], [`CurrentTrack`] and [`Position`] of every group
+//! coordinator and reports, per coordinator, when a track starts, when it
+//! has been listened to long enough to count, when playback left it before
+//! that, and when playback stops. Off until the first call.
+//!
+//! Play time accumulates on the manager's clock while the coordinator is
+//! `Playing`, so pauses don't count and a seek, which only moves the
+//! position, can't count twice. The position is interpolated over that
+//! play time between reports; a report back at the start of a track that
+//! already qualified (repeat one, or a restart) begins a new listen.
+//!
+//! The tracker only sees changes that reach `iter()`: watch the three
+//! properties on the coordinators to follow.
+//!
+//! [`StateManager::listening_events()`]: crate::StateManager::listening_events
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::task::{Context, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use parking_lot::RwLock;
+use sonos_api::clock::SharedClock;
+use sonos_api::Service;
+
+use crate::model::SpeakerId;
+use crate::property::{CurrentTrack, PlaybackState, Position, Property, SonosProperty};
+use crate::state::{ChangeEvent, ChangeSink, StateStore};
+
+/// How close to the start a position report must be to restart a track
+/// that already qualified
+const RESTART_WINDOW: Duration = Duration::from_secs(3);
+
+/// When a track counts as listened to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListeningRules {
+    /// Share of a track's duration that qualifies it
+    pub fraction: f64,
+    /// Play time that qualifies a track whatever its duration
+    pub max_time: Duration,
+    /// Play time that qualifies a stream with no duration, such as a radio
+    /// station's current song
+    pub stream_time: Duration,
+}
+
+impl Default for ListeningRules {
+    /// Half the track or 4 minutes, whichever comes first; 30 seconds of a
+    /// stream
+    fn default() -> Self {
+        Self {
+            fraction: 0.5,
+            max_time: Duration::from_secs(240),
+            stream_time: Duration::from_secs(30),
+        }
+    }
+}
+
+/// What happened to a coordinator's listening session
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListeningEvent {
+    /// The coordinator started playing `track`
+    TrackStarted {
+        coordinator: SpeakerId,
+        track: CurrentTrack,
+    },
+    /// `track` has played long enough to count; `accumulated` is its play
+    /// time when that was noticed, at least the threshold
+    TrackQualified {
+        coordinator: SpeakerId,
+        track: CurrentTrack,
+        accumulated: Duration,
+    },
+    /// Playback left `track`, or stopped, before it qualified
+    TrackAbandoned {
+        coordinator: SpeakerId,
+        track: CurrentTrack,
+        accumulated: Duration,
+    },
+    /// Playback stopped; `track` is the last one and `accumulated` the play
+    /// time of the whole session
+    SessionEnded {
+        coordinator: SpeakerId,
+        track: CurrentTrack,
+        accumulated: Duration,
+    },
+}
+
+impl ListeningEvent {
+    /// Property key of the change event announcing each listening event
+    pub const EVENT_KEY: &'static str = "listening";
+
+    /// Coordinator of the group whose session this is
+    pub fn coordinator(&self) -> &SpeakerId {
+        match self {
+            Self::TrackStarted { coordinator, .. }
+            | Self::TrackQualified { coordinator, .. }
+            | Self::TrackAbandoned { coordinator, .. }
+            | Self::SessionEnded { coordinator, .. } => coordinator,
+        }
+    }
+
+    /// Track the event is about
+    pub fn track(&self) -> &CurrentTrack {
+        match self {
+            Self::TrackStarted { track, .. }
+            | Self::TrackQualified { track, .. }
+            | Self::TrackAbandoned { track, .. }
+            | Self::SessionEnded { track, .. } => track,
+        }
+    }
+}
+
+/// Listening events, in the order they happened
+///
+/// Created by [`StateManager::listening_events()`](crate::StateManager::listening_events).
+/// All methods block on the calling thread, as
+/// [`ChangeIterator`](crate::ChangeIterator)'s do; it is also a blocking
+/// iterator.
+pub struct ListeningEvents {
+    rx: mpsc::Receiver<ListeningEvent>,
+}
+
+impl ListeningEvents {
+    /// Block until the next event
+    ///
+    /// Returns `None` once the state manager is gone.
+    pub fn recv(&self) -> Option<ListeningEvent> {
+        self.rx.recv().ok()
+    }
+
+    /// Block until the next event or the timeout
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<ListeningEvent> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    /// Take the next event if one is waiting
+    pub fn try_recv(&self) -> Option<ListeningEvent> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Iterator for ListeningEvents {
+    type Item = ListeningEvent;
+
+    fn next(&mut self) -> Option<ListeningEvent> {
+        self.recv()
+    }
+}
+
+/// One changed input of a coordinator's session
+enum Input {
+    State(PlaybackState),
+    Track(Option<CurrentTrack>),
+    Position(Position),
+}
+
+/// One track's listen
+struct Listen {
+    track: CurrentTrack,
+    /// `None` for a stream
+    duration: Option<Duration>,
+    accumulated: Duration,
+    /// Interpolated playback position
+    position: Duration,
+    started: bool,
+    qualified: bool,
+}
+
+impl Listen {
+    fn new(track: CurrentTrack, duration: Option<Duration>) -> Self {
+        Self {
+            track,
+            duration,
+            accumulated: Duration::ZERO,
+            position: Duration::ZERO,
+            started: false,
+            qualified: false,
+        }
+    }
+
+    fn threshold(&self, rules: &ListeningRules) -> Duration {
+        match self.duration {
+            Some(duration) => duration.mul_f64(rules.fraction).min(rules.max_time),
+            None => rules.stream_time,
+        }
+    }
+}
+
+/// A coordinator's listening session
+struct Session {
+    coordinator: SpeakerId,
+    listen: Option<Listen>,
+    /// Set while `Playing`, moved forward as play time is counted
+    playing_since: Option<Instant>,
+    /// Play time since the session started
+    total: Duration,
+    /// A track started since the last stop
+    active: bool,
+}
+
+impl Session {
+    fn new(coordinator: SpeakerId) -> Self {
+        Self {
+            coordinator,
+            listen: None,
+            playing_since: None,
+            total: Duration::ZERO,
+            active: false,
+        }
+    }
+
+    /// Count the play time up to `now`, reporting a track that qualified
+    /// meanwhile
+    fn advance(&mut self, rules: &ListeningRules, now: Instant, events: &mut Vec<ListeningEvent>) {
+        let Some(since) = self.playing_since else {
+            return;
+        };
+        let played = now.saturating_duration_since(since);
+        self.playing_since = Some(now);
+        self.total += played;
+        let Some(listen) = self.listen.as_mut() else {
+            return;
+        };
+        listen.accumulated += played;
+        listen.position += played;
+        let threshold = listen.threshold(rules);
+        if listen.started && !listen.qualified && listen.accumulated >= threshold {
+            listen.qualified = true;
+            events.push(ListeningEvent::TrackQualified {
+                coordinator: self.coordinator.clone(),
+                track: listen.track.clone(),
+                accumulated: listen.accumulated,
+            });
+        }
+    }
+
+    /// When the current track qualifies if playback goes on
+    fn deadline(&self, rules: &ListeningRules) -> Option<Instant> {
+        let since = self.playing_since?;
+        let listen = self.listen.as_ref().filter(|l| l.started && !l.qualified)?;
+        Some(since + listen.threshold(rules).saturating_sub(listen.accumulated))
+    }
+
+    fn apply(
+        &mut self,
+        rules: &ListeningRules,
+        now: Instant,
+        input: Input,
+        events: &mut Vec<ListeningEvent>,
+    ) {
+        self.advance(rules, now, events);
+        match input {
+            Input::State(state) => {
+                if state.is_playing() {
+                    self.playing_since.get_or_insert(now);
+                } else {
+                    self.playing_since = None;
+                }
+                if state.is_stopped() {
+                    self.end(events);
+                }
+            }
+            Input::Track(track) => {
+                let track = track.filter(|t| !t.is_empty());
+                let same = match (&self.listen, &track) {
+                    (Some(listen), Some(track)) => listen.track.same_identity(track),
+                    (None, None) => true,
+                    _ => false,
+                };
+                if !same {
+                    self.leave(events);
+                    // The position that comes with a new track sets its duration
+                    self.listen = track.map(|track| Listen::new(track, None));
+                }
+            }
+            Input::Position(position) => {
+                if let Some(listen) = self.listen.as_mut() {
+                    let reported = Duration::from_millis(position.position_ms);
+                    listen.duration = (position.duration_ms > 0)
+                        .then(|| Duration::from_millis(position.duration_ms));
+                    if listen.qualified
+                        && reported < RESTART_WINDOW
+                        && listen.position >= reported + RESTART_WINDOW
+                    {
+                        let restarted = Listen::new(listen.track.clone(), listen.duration);
+                        *listen = restarted;
+                    }
+                    listen.position = reported;
+                }
+            }
+        }
+        if self.playing_since.is_some() {
+            if let Some(listen) = self.listen.as_mut().filter(|l| !l.started) {
+                listen.started = true;
+                self.active = true;
+                events.push(ListeningEvent::TrackStarted {
+                    coordinator: self.coordinator.clone(),
+                    track: listen.track.clone(),
+                });
+            }
+        }
+    }
+
+    /// Report the current track as abandoned unless it qualified
+    fn leave(&mut self, events: &mut Vec<ListeningEvent>) {
+        if let Some(listen) = self.listen.as_ref().filter(|l| l.started && !l.qualified) {
+            events.push(ListeningEvent::TrackAbandoned {
+                coordinator: self.coordinator.clone(),
+                track: listen.track.clone(),
+                accumulated: listen.accumulated,
+            });
+        }
+    }
+
+    /// Close the session; the track stays, to start again on play
+    fn end(&mut self, events: &mut Vec<ListeningEvent>) {
+        self.leave(events);
+        if let (true, Some(listen)) = (self.active, self.listen.as_ref()) {
+            events.push(ListeningEvent::SessionEnded {
+                coordinator: self.coordinator.clone(),
+                track: listen.track.clone(),
+                accumulated: self.total,
+            });
+        }
+        if let Some(listen) = self.listen.as_mut() {
+            *listen = Listen::new(listen.track.clone(), listen.duration);
+        }
+        self.total = Duration::ZERO;
+        self.active = false;
+    }
+}
+
+#[derive(Default)]
+struct Sessions {
+    by_coordinator: HashMap<SpeakerId, Session>,
+    /// Events waiting to be delivered, in order
+    outbox: VecDeque<ListeningEvent>,
+    subscribers: Vec<mpsc::Sender<ListeningEvent>>,
+    /// The qualification thread, while it runs
+    runner: Option<Thread>,
+}
+
+/// Wakes the qualification thread when its timer fires
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Follows the listening sessions of one state manager
+pub(crate) struct ListeningTracker {
+    rules: ListeningRules,
+    clock: SharedClock,
+    store: Arc<RwLock<StateStore>>,
+    sink: ChangeSink,
+    sessions: Mutex<Sessions>,
+    /// Held by the thread delivering the outbox
+    delivering: Mutex<()>,
+}
+
+impl ListeningTracker {
+    pub(crate) fn new(
+        rules: ListeningRules,
+        clock: SharedClock,
+        store: Arc<RwLock<StateStore>>,
+        sink: ChangeSink,
+    ) -> Self {
+        Self {
+            rules,
+            clock,
+            store,
+            sink,
+            sessions: Mutex::new(Sessions::default()),
+            delivering: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn rules(&self) -> &ListeningRules {
+        &self.rules
+    }
+
+    fn sessions(&self) -> MutexGuard<'_, Sessions> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A new receiver of every later event
+    pub(crate) fn subscribe(&self) -> ListeningEvents {
+        let (tx, rx) = mpsc::channel();
+        self.sessions().subscribers.push(tx);
+        ListeningEvents { rx }
+    }
+
+    /// Feed a change event; anything but a coordinator's playback state,
+    /// track or position is ignored
+    pub(crate) fn observe(self: &Arc<Self>, event: &ChangeEvent) {
+        let keys: &[&'static str] = if event.property_key == ChangeEvent::BATCH_KEY {
+            &event.batch
+        } else {
+            std::slice::from_ref(&event.property_key)
+        };
+        let tracked = [PlaybackState::KEY, CurrentTrack::KEY, Position::KEY];
+        if !keys.iter().any(|key| tracked.contains(key)) {
+            return;
+        }
+        let inputs: Vec<Input> = {
+            let store = self.store.read();
+            if store.resolve_coordinator(&event.speaker_id) != event.speaker_id {
+                return;
+            }
+            let id = &event.speaker_id;
+            keys.iter()
+                .filter_map(|key| match *key {
+                    PlaybackState::KEY => store.get::<PlaybackState>(id).map(Input::State),
+                    CurrentTrack::KEY => Some(Input::Track(store.get::<CurrentTrack>(id))),
+                    Position::KEY => store.get::<Position>(id).map(Input::Position),
+                    _ => None,
+                })
+                .collect()
+        };
+        if inputs.is_empty() {
+            return;
+        }
+
+        let now = self.clock.now();
+        let mut sessions = self.sessions();
+        let Sessions {
+            by_coordinator,
+            outbox,
+            ..
+        } = &mut *sessions;
+        let session = by_coordinator
+            .entry(event.speaker_id.clone())
+            .or_insert_with(|| Session::new(event.speaker_id.clone()));
+        let mut events = Vec::new();
+        for input in inputs {
+            session.apply(&self.rules, now, input, &mut events);
+        }
+        outbox.extend(events);
+        if session.deadline(&self.rules).is_some() {
+            match &sessions.runner {
+                Some(runner) => runner.unpark(),
+                None => {
+                    let tracker = Arc::downgrade(self);
+                    let runner = thread::spawn(move || Self::run(tracker));
+                    sessions.runner = Some(runner.thread().clone());
+                }
+            }
+        }
+        drop(sessions);
+        self.deliver();
+    }
+
+    /// Qualification thread: counts play time while any track is on its
+    /// way to qualifying, and until the tracker is dropped
+    ///
+    /// Parks until the clock's timer for the nearest deadline fires, or
+    /// [`observe()`](Self::observe) or the tracker's drop unparks it.
+    fn run(tracker: Weak<Self>) {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        while let Some(tracker) = tracker.upgrade() {
+            let mut sessions = tracker.sessions();
+            let now = tracker.clock.now();
+            let Some(deadline) = sessions
+                .by_coordinator
+                .values()
+                .filter_map(|session| session.deadline(&tracker.rules))
+                .min()
+            else {
+                sessions.runner = None;
+                return;
+            };
+            if deadline > now {
+                drop(sessions);
+                let mut timer = pin!(tracker.clock.timer(deadline));
+                if timer.as_mut().poll(&mut cx).is_pending() {
+                    drop(tracker);
+                    thread::park();
+                }
+                continue;
+            }
+            let mut events = Vec::new();
+            for session in sessions.by_coordinator.values_mut() {
+                if session.deadline(&tracker.rules).is_some_and(|d| d <= now) {
+                    session.advance(&tracker.rules, now, &mut events);
+                }
+            }
+            sessions.outbox.extend(events);
+            drop(sessions);
+            tracker.deliver();
+        }
+    }
+
+    /// Send the outbox to the subscribers and announce each event on the
+    /// change stream
+    ///
+    /// One thread delivers at a time, so events keep their order; an event
+    /// queued meanwhile, even by an observer of one being delivered, goes
+    /// out with the rest.
+    fn deliver(&self) {
+        loop {
+            let Ok(delivering) = self.delivering.try_lock() else {
+                return;
+            };
+            while let Some(event) = self.next_delivery() {
+                self.sink.send(ChangeEvent::new(
+                    event.coordinator().clone(),
+                    ListeningEvent::EVENT_KEY,
+                    Service::AVTransport,
+                ));
+            }
+            drop(delivering);
+            // Queued after the last check, by a thread that found us delivering
+            if self.sessions().outbox.is_empty() {
+                return;
+            }
+        }
+    }
+
+    /// Take the next event from the outbox and hand it to the subscribers
+    fn next_delivery(&self) -> Option<ListeningEvent> {
+        let mut sessions = self.sessions();
+        let event = sessions.outbox.pop_front()?;
+        sessions
+            .subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        Some(event)
+    }
+}
+
+impl Drop for ListeningTracker {
+    /// Lets the qualification thread see the tracker is gone
+    fn drop(&mut self) {
+        if let Some(runner) = &self.sessions().runner {
+            runner.unpark();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StateManager;
+    use sonos_api::clock::ManualClock;
+
+    const DEN: &str = "RINCON_DEN";
+
+    fn manager(clock: &ManualClock) -> StateManager {
+        let manager = StateManager::builder()
+            .clock(Arc::new(clock.clone()))
+            .build()
+            .unwrap();
+        manager
+            .add_devices(vec![sonos_discovery::Device {
+                id: DEN.to_string(),
+                name: "Den".to_string(),
+                room_name: "Den".to_string(),
+                ip_address: "192.168.1.100".to_string(),
+                port: 1400,
+                model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
+            }])
+            .unwrap();
+        let den = SpeakerId::new(DEN);
+        for key in [PlaybackState::KEY, CurrentTrack::KEY, Position::KEY] {
+            manager.register_watch(&den, key);
+        }
+        manager
+    }
+
+    fn track(title: &str) -> CurrentTrack {
+        CurrentTrack {
+            title: Some(title.to_string()),
+            artist: Some("The Beatles".to_string()),
+            album: None,
+            album_art_uri: None,
+            uri: Some(format!("x-file-cifs://nas/{title}.flac")),
+        }
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    /// Scripts one coordinator's changes on the manual clock
+    struct Script {
+        manager: StateManager,
+        clock: ManualClock,
+        events: ListeningEvents,
+        den: SpeakerId,
+    }
+
+    impl Script {
+        fn new() -> Self {
+            Self::with_rules(ListeningRules::default())
+        }
+
+        fn with_rules(rules: ListeningRules) -> Self {
+            let clock = ManualClock::new();
+            let manager = manager(&clock);
+            let events = manager.listening_events_with(rules);
+            Self {
+                manager,
+                clock,
+                events,
+                den: SpeakerId::new(DEN),
+            }
+        }
+
+        fn play_track(&self, title: &str, duration: u64) {
+            self.manager.set_property(&self.den, track(title));
+            self.manager
+                .set_property(&self.den, Position::new(0, duration * 1000));
+            self.state(PlaybackState::Playing);
+        }
+
+        fn state(&self, state: PlaybackState) {
+            self.manager.set_property(&self.den, state);
+        }
+
+        fn seek(&self, position: u64, duration: u64) {
+            self.manager
+                .set_property(&self.den, Position::new(position * 1000, duration * 1000));
+        }
+
+        fn advance(&self, by: Duration) {
+            self.clock.advance(by);
+        }
+
+        fn started(&self, title: &str) -> ListeningEvent {
+            ListeningEvent::TrackStarted {
+                coordinator: self.den.clone(),
+                track: track(title),
+            }
+        }
+
+        fn qualified(&self, title: &str, accumulated: Duration) -> ListeningEvent {
+            ListeningEvent::TrackQualified {
+                coordinator: self.den.clone(),
+                track: track(title),
+                accumulated,
+            }
+        }
+
+        fn abandoned(&self, title: &str, accumulated: Duration) -> ListeningEvent {
+            ListeningEvent::TrackAbandoned {
+                coordinator: self.den.clone(),
+                track: track(title),
+                accumulated,
+            }
+        }
+
+        fn ended(&self, title: &str, accumulated: Duration) -> ListeningEvent {
+            ListeningEvent::SessionEnded {
+                coordinator: self.den.clone(),
+                track: track(title),
+                accumulated,
+            }
+        }
+
+        /// Events so far, waiting briefly for the qualification thread
+        fn take(&self) -> Vec<ListeningEvent> {
+            let mut events = Vec::new();
+            while let Some(event) = self.events.recv_timeout(Duration::from_millis(200)) {
+                events.push(event);
+            }
+            events
+        }
+    }
+
+    #[test]
+    fn test_normal_listen_qualifies_at_half_the_track() {
+        let s = Script::new();
+        s.play_track("Something", 200);
+        s.advance(secs(99));
+        assert_eq!(s.take(), [s.started("Something")]);
+
+        s.advance(secs(1));
+        assert_eq!(s.take(), [s.qualified("Something", secs(100))]);
+
+        s.advance(secs(100));
+        s.play_track("Octopus's Garden", 170);
+        s.advance(secs(20));
+        s.state(PlaybackState::Stopped);
+        assert_eq!(
+            s.take(),
+            [
+                s.started("Octopus's Garden"),
+                s.abandoned("Octopus's Garden", secs(20)),
+                s.ended("Octopus's Garden", secs(220)),
+            ]
+        );
+
+        // Each event was announced on the change stream too
+        let announced = s
+            .manager
+            .iter()
+            .try_iter()
+            .filter(|e| e.property_key == ListeningEvent::EVENT_KEY)
+            .count();
+        assert_eq!(announced, 5);
+    }
+
+    #[test]
+    fn test_pauses_do_not_count_toward_qualifying() {
+        let s = Script::new();
+        s.play_track("Hey Jude", 600);
+        // 4 minutes is less than half of 10
+        for _ in 0..3 {
+            s.advance(secs(60));
+            s.state(PlaybackState::Paused);
+            s.advance(secs(600));
+            s.state(PlaybackState::Playing);
+        }
+        assert_eq!(s.take(), [s.started("Hey Jude")]);
+
+        s.advance(secs(60));
+        assert_eq!(s.take(), [s.qualified("Hey Jude", secs(240))]);
+        s.state(PlaybackState::Stopped);
+        assert_eq!(s.take(), [s.ended("Hey Jude", secs(240))]);
+    }
+
+    #[test]
+    fn test_skip_at_forty_percent_abandons_the_track() {
+        let s = Script::new();
+        s.play_track("Let It Be", 250);
+        s.advance(secs(100));
+        s.play_track("Yesterday", 125);
+        assert_eq!(
+            s.take(),
+            [
+                s.started("Let It Be"),
+                s.abandoned("Let It Be", secs(100)),
+                s.started("Yesterday"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_seeks_move_the_position_but_not_the_play_time() {
+        let s = Script::new();
+        s.play_track("Come Together", 260);
+        s.advance(secs(30));
+        // Skipping ahead to the last seconds doesn't qualify the track
+        s.seek(250, 260);
+        s.advance(secs(10));
+        assert_eq!(s.take(), [s.started("Come Together")]);
+
+        // Still counted by play time alone: 130s in all
+        s.seek(10, 260);
+        s.advance(secs(90));
+        assert_eq!(s.take(), [s.qualified("Come Together", secs(130))]);
+
+        // Back to the start of a qualified track: a new listen
+        s.advance(secs(100));
+        s.seek(0, 260);
+        assert_eq!(s.take(), [s.started("Come Together")]);
+    }
+
+    #[test]
+    fn test_radio_streams_qualify_on_play_time_alone() {
+        let s = Script::with_rules(ListeningRules {
+            stream_time: secs(60),
+            ..ListeningRules::default()
+        });
+        // A stream reports no duration
+        s.play_track("Station Ident", 0);
+        s.advance(secs(20));
+        s.play_track("Here Comes the Sun", 0);
+        s.advance(secs(90));
+        s.state(PlaybackState::Stopped);
+        assert_eq!(
+            s.take(),
+            [
+                s.started("Station Ident"),
+                s.abandoned("Station Ident", secs(20)),
+                s.started("Here Comes the Sun"),
+                // Counted when the clock jumped past the 60s needed
+                s.qualified("Here Comes the Sun", secs(90)),
+                s.ended("Here Comes the Sun", secs(110)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Application middleware for writes and change events
+//!
+//! Write interceptors run before the SDK sends a mutating SOAP action and
+//! can let it through, veto it or rewrite its arguments (logging, parental
+//! volume caps). Change middleware runs before observers and `iter()` see a
+//! [`ChangeEvent`] and can rewrite or drop it.
+//!
+//! Both are ordered: each registration sees the output of the previous one.
+//! With nothing registered, the check is a single atomic load.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use sonos_api::Service;
+
+use crate::model::SpeakerId;
+use crate::state::ChangeEvent;
+
+/// A mutating SOAP action about to be sent to a speaker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteRequest {
+    speaker_id: SpeakerId,
+    service: Service,
+    action: &'static str,
+    args: Vec<(String, String)>,
+}
+
+impl WriteRequest {
+    pub fn new(
+        speaker_id: SpeakerId,
+        service: Service,
+        action: &'static str,
+        args: Vec<(String, String)>,
+    ) -> Self {
+        Self {
+            speaker_id,
+            service,
+            action,
+            args,
+        }
+    }
+
+    /// Speaker the action is sent to (the coordinator for group writes, the
+    /// bond primary for home-theater settings)
+    pub fn speaker_id(&self) -> &SpeakerId {
+        &self.speaker_id
+    }
+
+    pub fn service(&self) -> Service {
+        self.service
+    }
+
+    /// SOAP action name, e.g. `"SetVolume"`
+    pub fn action(&self) -> &'static str {
+        self.action
+    }
+
+    /// Action arguments in wire order, values unescaped
+    pub fn args(&self) -> &[(String, String)] {
+        &self.args
+    }
+
+    /// Value of the argument `name`, e.g. `"DesiredVolume"`
+    pub fn arg(&self, name: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Replace the value of `name`, or append it if the action has no such
+    /// argument
+    pub fn with_arg(mut self, name: &str, value: impl Into<String>) -> Self {
+        let value = value.into();
+        match self.args.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => self.args.push((name.to_string(), value)),
+        }
+        self
+    }
+}
+
+/// What a [`WriteInterceptor`] does with a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterceptDecision {
+    /// Send the request as it is
+    Allow,
+    /// Don't send it; the caller gets an error carrying the reason
+    Deny(String),
+    /// Send these arguments instead. Only the arguments of the replacement
+    /// are used: the action and target stay those of the original request.
+    Modify(WriteRequest),
+}
+
+/// Callback consulted before every write the SDK sends.
+///
+/// Runs on the calling thread, possibly concurrently with itself.
+pub type WriteInterceptor = Arc<dyn Fn(&WriteRequest) -> InterceptDecision + Send + Sync>;
+
+/// Callback that rewrites a change event, or drops it by returning `None`.
+///
+/// Runs on the thread that produced the change (usually the event worker).
+pub type ChangeMiddleware = Arc<dyn Fn(ChangeEvent) -> Option<ChangeEvent> + Send + Sync>;
+
+/// Ordered registrations whose empty check takes no lock
+pub(crate) struct Chain<T> {
+    installed: AtomicBool,
+    entries: RwLock<Vec<T>>,
+}
+
+impl<T: Clone> Chain<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            installed: AtomicBool::new(false),
+            entries: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn push(&self, entry: T) {
+        self.entries.write().push(entry);
+        self.installed.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.installed.load(Ordering::Acquire)
+    }
+
+    /// Registrations in order, copied out so callbacks run without the lock
+    /// held and may register more middleware
+    fn snapshot(&self) -> Vec<T> {
+        self.entries.read().clone()
+    }
+}
+
+impl Chain<WriteInterceptor> {
+    /// Run `request` through every interceptor
+    ///
+    /// Stops at the first `Deny`. Returns `Modify` with the final request if
+    /// any interceptor rewrote it, `Allow` otherwise.
+    pub(crate) fn intercept(&self, request: WriteRequest) -> InterceptDecision {
+        if self.is_empty() {
+            return InterceptDecision::Allow;
+        }
+        let mut current = request;
+        let mut modified = false;
+        for interceptor in self.snapshot() {
+            match interceptor(&current) {
+                InterceptDecision::Allow => {}
+                InterceptDecision::Deny(reason) => return InterceptDecision::Deny(reason),
+                InterceptDecision::Modify(replacement) => {
+                    current.args = replacement.args;
+                    modified = true;
+                }
+            }
+        }
+        if modified {
+            InterceptDecision::Modify(current)
+        } else {
+            InterceptDecision::Allow
+        }
+    }
+}
+
+impl Chain<ChangeMiddleware> {
+    /// Run `event` through every middleware; `None` once one drops it
+    pub(crate) fn apply(&self, event: ChangeEvent) -> Option<ChangeEvent> {
+        if self.is_empty() {
+            return Some(event);
+        }
+        self.snapshot()
+            .iter()
+            .try_fold(event, |event, middleware| middleware(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn set_volume(volume: u8) -> WriteRequest {
+        WriteRequest::new(
+            SpeakerId::new("RINCON_1"),
+            Service::RenderingControl,
+            "SetVolume",
+            vec![
+                ("InstanceID".into(), "0".into()),
+                ("DesiredVolume".into(), volume.to_string()),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_interceptors_run_in_order_until_denied() {
+        let chain: Chain<WriteInterceptor> = Chain::new();
+        assert_eq!(chain.intercept(set_volume(90)), InterceptDecision::Allow);
+
+        chain.push(Arc::new(|r: &WriteRequest| {
+            InterceptDecision::Modify(r.clone().with_arg("DesiredVolume", "50"))
+        }));
+        chain.push(Arc::new(|r: &WriteRequest| match r.arg("DesiredVolume") {
+            Some("50") => InterceptDecision::Allow,
+            _ => InterceptDecision::Deny("not capped".into()),
+        }));
+        assert_eq!(
+            chain.intercept(set_volume(90)),
+            InterceptDecision::Modify(set_volume(50))
+        );
+
+        chain.push(Arc::new(|_: &WriteRequest| {
+            InterceptDecision::Deny("read-only".into())
+        }));
+        assert_eq!(
+            chain.intercept(set_volume(90)),
+            InterceptDecision::Deny("read-only".into())
+        );
+    }
+
+    #[test]
+    fn test_change_middleware_rewrites_and_drops() {
+        let chain: Chain<ChangeMiddleware> = Chain::new();
+        let event = || {
+            ChangeEvent::new(
+                SpeakerId::new("RINCON_1"),
+                "volume",
+                Service::RenderingControl,
+            )
+        };
+
+        chain.push(Arc::new(|mut e: ChangeEvent| {
+            e.coalesced = 7;
+            Some(e)
+        }));
+        assert_eq!(chain.apply(event()).unwrap().coalesced, 7);
+
+        chain.push(Arc::new(|_| None));
+        assert!(chain.apply(event()).is_none());
+    }
+
+    #[test]
+    fn test_empty_chain_takes_no_lock() {
+        let chain: Chain<ChangeMiddleware> = Chain::new();
+        let writer = chain.entries.write();
+        let (tx, rx) = mpsc::channel();
+        thread::scope(|s| {
+            s.spawn(|| {
+                let event = ChangeEvent::new(
+                    SpeakerId::new("RINCON_1"),
+                    "mute",
+                    Service::RenderingControl,
+                );
+                tx.send(chain.apply(event).is_some()).unwrap();
+            });
+            let passed = rx.recv_timeout(Duration::from_secs(2));
+            drop(writer);
+            assert_eq!(passed, Ok(true));
+        });
+    }
+}
@@ -2,7 +2,7 @@
 
 use super::SpeakerId;
 use serde::{Deserialize, Serialize};
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// Information about a Sonos speaker device
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -21,13 +21,23 @@ pub struct Speaker {
     pub model_name: String,
     /// Software/firmware version
     pub software_version: String,
+    /// Software generation (1 = S1, 2 = S2), once reported by topology
+    #[serde(default)]
+    pub software_generation: Option<u8>,
     /// Boot sequence number from topology events (used by GroupManagement AddMember)
     pub boot_seq: u32,
     /// Satellite speaker IDs (for home theater setups)
     pub satellites: Vec<SpeakerId>,
+    /// Household the speaker belongs to, when discovery reported one
+    #[serde(default)]
+    pub household_id: Option<String>,
 }
 
 impl Speaker {
+    /// Property key of the change event emitted, whether watched or not,
+    /// when a speaker's room is renamed
+    pub const RENAME_EVENT_KEY: &'static str = "room_name";
+
     /// Get the speaker ID
     pub fn get_id(&self) -> &SpeakerId {
         &self.id
@@ -35,7 +45,12 @@ impl Speaker {
 
     /// Get the full address (ip:port) for this speaker
     pub fn address(&self) -> String {
-        format!("{}:{}", self.ip_address, self.port)
+        self.socket_addr().to_string()
+    }
+
+    /// Get the speaker's socket address
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip_address, self.port)
     }
 }
 
@@ -52,8 +67,10 @@ mod tests {
             port: 1400,
             model_name: "Sonos One".to_string(),
             software_version: "56.0-76060".to_string(),
+            software_generation: None,
             boot_seq: 0,
             satellites: vec![],
+            household_id: None,
         }
     }
 
@@ -0,0 +1,356 @@
+//! Change origin inference
+//!
+//! UPnP events don't say who caused a change. The SDK records an
+//! expectation whenever it writes a value; an event that matches a recent
+//! expectation is attributed to that write, and anything else is assumed to
+//! come from outside this process (another app, or the speaker's buttons).
+//!
+//! External volume steps arriving in quick succession (a held button) are
+//! merged into one [`ChangeEvent`] whose `coalesced` count says how many
+//! steps it covers.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::model::SpeakerId;
+use crate::property::{GroupMute, GroupVolume, Mute, Property, Volume};
+use crate::state::{ChangeEvent, ChangeSink};
+
+/// Who caused a [`ChangeEvent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChangeOrigin {
+    /// Not attributed: fetches, topology updates and diagnostics
+    #[default]
+    Unknown,
+    /// A write made through this SDK, or the device's echo of it
+    LocalWrite,
+    /// A member speaker following a group volume/mute write made through
+    /// this SDK
+    GroupOperation,
+    /// An event no local write accounts for: another controller or the
+    /// speaker's physical buttons
+    External,
+    /// A previous value put back by [`StateManager::undo_last()`](crate::StateManager::undo_last)
+    Restore,
+    /// A local `Transitioning` write no event confirmed in time, corrected
+    /// from the device or reverted (see
+    /// [`StateManagerBuilder::transition_timeout()`](crate::StateManagerBuilder::transition_timeout))
+    Reconciled,
+    /// Not a change: the one notification a new watch delivers for the
+    /// property's current value, which may be unset (see
+    /// [`StateManager::begin_watch()`](crate::StateManager::begin_watch))
+    Initial,
+}
+
+/// Callback that refines the inferred origin of an event-driven change.
+///
+/// Receives the event with its inferred `origin` and returns the origin to
+/// report. Runs on the event worker thread.
+pub type OriginClassifier = Arc<dyn Fn(&ChangeEvent) -> ChangeOrigin + Send + Sync>;
+
+/// How long a local write claims matching events
+pub const DEFAULT_EXPECTATION_WINDOW: Duration = Duration::from_secs(2);
+
+/// Quiet period that ends a burst of external volume steps
+pub const DEFAULT_COALESCE_WINDOW: Duration = Duration::from_millis(400);
+
+/// Keys affected by a group volume/mute operation
+const GROUP_OPERATION_KEYS: [&str; 4] = [Volume::KEY, Mute::KEY, GroupVolume::KEY, GroupMute::KEY];
+
+/// Keys whose external changes are coalesced
+const COALESCED_KEYS: [&str; 2] = [Volume::KEY, GroupVolume::KEY];
+
+struct Expectation {
+    speaker_id: SpeakerId,
+    /// `None` matches any of [`GROUP_OPERATION_KEYS`]
+    key: Option<&'static str>,
+    origin: ChangeOrigin,
+    expires: Instant,
+}
+
+struct Burst {
+    event: ChangeEvent,
+    last_step: Instant,
+}
+
+/// Attributes event-driven changes and coalesces external volume bursts
+pub(crate) struct OriginTracker {
+    sink: ChangeSink,
+    expectations: Mutex<Vec<Expectation>>,
+    classifier: RwLock<Option<OriginClassifier>>,
+    expectation_window: Duration,
+    coalesce_window: Duration,
+    bursts: Arc<Mutex<HashMap<(SpeakerId, &'static str), Burst>>>,
+    flushing: Arc<AtomicBool>,
+}
+
+impl OriginTracker {
+    pub(crate) fn new(
+        sink: ChangeSink,
+        expectation_window: Duration,
+        coalesce_window: Duration,
+    ) -> Self {
+        Self {
+            sink,
+            expectations: Mutex::new(Vec::new()),
+            classifier: RwLock::new(None),
+            expectation_window,
+            coalesce_window,
+            bursts: Arc::new(Mutex::new(HashMap::new())),
+            flushing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Create a tracker that never coalesces, with its receiving end, for
+    /// worker tests
+    #[cfg(test)]
+    pub(crate) fn channel() -> (Self, std::sync::mpsc::Receiver<ChangeEvent>) {
+        let (sink, rx) = ChangeSink::channel();
+        (
+            Self::new(sink, DEFAULT_EXPECTATION_WINDOW, Duration::ZERO),
+            rx,
+        )
+    }
+
+    #[cfg(test)]
+    pub(crate) fn sink(&self) -> &ChangeSink {
+        &self.sink
+    }
+
+    pub(crate) fn set_classifier(&self, classifier: Option<OriginClassifier>) {
+        *self.classifier.write() = classifier;
+    }
+
+    /// Expect an echo of a local write to `key` on `speaker_id`
+    pub(crate) fn expect_write(&self, speaker_id: &SpeakerId, key: &'static str) {
+        self.expect(speaker_id.clone(), Some(key), ChangeOrigin::LocalWrite);
+    }
+
+    /// Expect volume/mute events from every speaker in a group after a
+    /// group-level write
+    pub(crate) fn expect_group_operation(&self, speaker_ids: &[SpeakerId]) {
+        for id in speaker_ids {
+            self.expect(id.clone(), None, ChangeOrigin::GroupOperation);
+        }
+    }
+
+    fn expect(&self, speaker_id: SpeakerId, key: Option<&'static str>, origin: ChangeOrigin) {
+        let expires = Instant::now() + self.expectation_window;
+        if let Ok(mut expectations) = self.expectations.lock() {
+            expectations.retain(|e| e.expires > Instant::now());
+            expectations.push(Expectation {
+                speaker_id,
+                key,
+                origin,
+                expires,
+            });
+        }
+    }
+
+    /// Infer the origin of an event-driven update to `key` on `speaker_id`.
+    ///
+    /// Call for every decoded property, changed or not: a matching local
+    /// write expectation is consumed by its echo even when the echo carries
+    /// the value already in the store.
+    pub(crate) fn attribute(&self, speaker_id: &SpeakerId, key: &'static str) -> ChangeOrigin {
+        let Ok(mut expectations) = self.expectations.lock() else {
+            return ChangeOrigin::External;
+        };
+        let now = Instant::now();
+        expectations.retain(|e| e.expires > now);
+
+        let matches = |e: &Expectation| {
+            e.speaker_id == *speaker_id
+                && match e.key {
+                    Some(k) => k == key,
+                    None => GROUP_OPERATION_KEYS.contains(&key),
+                }
+        };
+        if let Some(pos) = expectations
+            .iter()
+            .position(|e| e.origin == ChangeOrigin::LocalWrite && matches(e))
+        {
+            return expectations.remove(pos).origin;
+        }
+        if expectations.iter().any(matches) {
+            return ChangeOrigin::GroupOperation;
+        }
+        ChangeOrigin::External
+    }
+
+    /// Emit an event-driven change after classification and coalescing
+    pub(crate) fn emit(&self, mut event: ChangeEvent) {
+        event.timestamp = self.sink.now();
+        if let Some(classifier) = self.classifier.read().as_ref() {
+            event.origin = classifier(&event);
+        }
+
+        let coalesce = event.origin == ChangeOrigin::External
+            && COALESCED_KEYS.contains(&event.property_key)
+            && !self.coalesce_window.is_zero();
+        if !coalesce {
+            self.sink.send_stamped(event);
+            return;
+        }
+
+        if let Ok(mut bursts) = self.bursts.lock() {
+            let key = (event.speaker_id.clone(), event.property_key);
+            match bursts.get_mut(&key) {
+                Some(burst) => {
+                    burst.event.coalesced += 1;
+                    burst.event.timestamp = event.timestamp;
+                    burst.last_step = Instant::now();
+                }
+                None => {
+                    bursts.insert(
+                        key,
+                        Burst {
+                            event,
+                            last_step: Instant::now(),
+                        },
+                    );
+                }
+            }
+        }
+        self.spawn_flusher();
+    }
+
+    /// Emit every pending burst now rather than once it goes quiet
+    pub(crate) fn flush_bursts(&self) {
+        let ready: Vec<ChangeEvent> = match self.bursts.lock() {
+            Ok(mut bursts) => bursts.drain().map(|(_, burst)| burst.event).collect(),
+            Err(_) => return,
+        };
+        for event in ready {
+            self.sink.send_stamped(event);
+        }
+    }
+
+    /// Start the thread that emits bursts once they go quiet, unless it is
+    /// already running. It exits when no bursts remain.
+    fn spawn_flusher(&self) {
+        if self.flushing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let bursts = Arc::clone(&self.bursts);
+        let flushing = Arc::clone(&self.flushing);
+        let sink = self.sink.clone();
+        let window = self.coalesce_window;
+
+        thread::spawn(move || loop {
+            let (ready, next_due) = {
+                let Ok(mut bursts) = bursts.lock() else {
+                    flushing.store(false, Ordering::SeqCst);
+                    return;
+                };
+                let now = Instant::now();
+                let done: Vec<_> = bursts
+                    .iter()
+                    .filter(|(_, b)| now.duration_since(b.last_step) >= window)
+                    .map(|(k, _)| k.clone())
+                    .collect();
+                let ready: Vec<_> = done
+                    .into_iter()
+                    .filter_map(|k| bursts.remove(&k))
+                    .map(|b| b.event)
+                    .collect();
+                let next_due = bursts.values().map(|b| b.last_step + window).min();
+                if next_due.is_none() {
+                    // Clear under the lock so a concurrent emit() either sees
+                    // this burst map or starts a new flusher
+                    flushing.store(false, Ordering::SeqCst);
+                }
+                (ready, next_due)
+            };
+
+            for event in ready {
+                sink.send_stamped(event);
+            }
+            match next_due {
+                Some(due) => thread::sleep(due.saturating_duration_since(Instant::now())),
+                None => return,
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sonos_api::Service;
+
+    #[test]
+    fn test_local_write_claims_one_echo() {
+        let (tracker, _rx) = OriginTracker::channel();
+        let speaker = SpeakerId::new("RINCON_A");
+        tracker.expect_write(&speaker, Volume::KEY);
+
+        assert_eq!(
+            tracker.attribute(&speaker, Mute::KEY),
+            ChangeOrigin::External
+        );
+        assert_eq!(
+            tracker.attribute(&speaker, Volume::KEY),
+            ChangeOrigin::LocalWrite
+        );
+        assert_eq!(
+            tracker.attribute(&speaker, Volume::KEY),
+            ChangeOrigin::External
+        );
+    }
+
+    #[test]
+    fn test_group_operation_covers_members() {
+        let (tracker, _rx) = OriginTracker::channel();
+        let (a, b) = (SpeakerId::new("RINCON_A"), SpeakerId::new("RINCON_B"));
+        tracker.expect_group_operation(&[a.clone(), b.clone()]);
+
+        assert_eq!(
+            tracker.attribute(&b, Volume::KEY),
+            ChangeOrigin::GroupOperation
+        );
+        assert_eq!(
+            tracker.attribute(&b, Volume::KEY),
+            ChangeOrigin::GroupOperation
+        );
+        assert_eq!(tracker.attribute(&a, "bass"), ChangeOrigin::External);
+    }
+
+    #[test]
+    fn test_classifier_refines_origin() {
+        let (tracker, rx) = OriginTracker::channel();
+        tracker.set_classifier(Some(Arc::new(|_: &ChangeEvent| ChangeOrigin::Unknown)));
+        let event = ChangeEvent::new(
+            SpeakerId::new("RINCON_A"),
+            Volume::KEY,
+            Service::RenderingControl,
+        )
+        .with_origin(ChangeOrigin::External);
+        tracker.emit(event);
+
+        assert_eq!(rx.try_recv().unwrap().origin, ChangeOrigin::Unknown);
+    }
+
+    #[test]
+    fn test_flush_bursts_emits_without_waiting() {
+        let (sink, rx) = ChangeSink::channel();
+        let tracker = OriginTracker::new(sink, DEFAULT_EXPECTATION_WINDOW, Duration::from_secs(60));
+        let speaker = SpeakerId::new("RINCON_A");
+        for _ in 0..3 {
+            let step = ChangeEvent::new(speaker.clone(), Volume::KEY, Service::RenderingControl)
+                .with_origin(ChangeOrigin::External);
+            tracker.emit(step);
+        }
+        assert!(rx.try_recv().is_err(), "held for the coalesce window");
+
+        tracker.flush_bursts();
+        assert_eq!(rx.try_recv().unwrap().coalesced, 3);
+        assert!(rx.try_recv().is_err());
+    }
+}
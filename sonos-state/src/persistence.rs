@@ -0,0 +1,613 @@
+//! Writing property changes to an external store
+//!
+//! A [`PersistenceSink`] registered with
+//! [`StateManager::add_persistence_sink()`](crate::StateManager::add_persistence_sink)
+//! receives every change to a property with a [`PropertySchema`](crate::PropertySchema),
+//! value included, in batches on a thread of its own. Slow sinks only grow
+//! that thread's queue; they never hold up event processing or `iter()`.
+//!
+//! Delivery is at-least-once: a batch leaves the queue only when the sink
+//! accepts it, and a failed batch is retried, unchanged, at the next flush.
+//! Changes are written in the order they happened, so the order per speaker
+//! holds across batches. [`NdjsonFileSink`] is a ready-made sink writing
+//! newline-delimited JSON, and [`StateManager::replay()`](crate::StateManager::replay)
+//! loads such a record back into a store.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::model::SpeakerId;
+use crate::origin::ChangeOrigin;
+use crate::schema::{self, DynamicValue};
+use crate::state::{ChangeEvent, StateStore};
+
+/// Error a sink reports for a batch it couldn't write
+pub type SinkError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Callback told about every failed write; the batch will be retried
+pub type SinkErrorHandler = Arc<dyn Fn(&SinkError) + Send + Sync>;
+
+/// Destination for recorded changes, such as a time-series database
+pub trait PersistenceSink: Send {
+    /// Write one batch, oldest change first
+    ///
+    /// On error the same batch is offered again at the next flush, so a
+    /// sink that fails part-way may see some changes twice.
+    fn write_batch(&mut self, batch: &[PersistedChange]) -> Result<(), SinkError>;
+}
+
+/// A property change with the value it changed to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(into = "StoredChange")]
+pub struct PersistedChange {
+    /// Speaker that changed (the coordinator, for group properties)
+    pub speaker_id: SpeakerId,
+    pub property_key: &'static str,
+    pub origin: ChangeOrigin,
+    /// Wall-clock time the change was recorded
+    pub timestamp: SystemTime,
+    pub value: DynamicValue,
+}
+
+/// How a sink's changes are batched
+#[derive(Clone)]
+pub struct PersistenceConfig {
+    flush_interval: Duration,
+    batch_size: usize,
+    on_error: Option<SinkErrorHandler>,
+}
+
+impl Default for PersistenceConfig {
+    fn default() -> Self {
+        Self {
+            flush_interval: Duration::from_secs(1),
+            batch_size: 100,
+            on_error: None,
+        }
+    }
+}
+
+impl PersistenceConfig {
+    /// Longest a change waits before it is written (default 1s); also the
+    /// delay before a failed batch is retried
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Most changes per batch (default 100); a full batch is written
+    /// without waiting for the interval
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// Called on the sink's thread whenever a batch fails
+    pub fn on_error(mut self, handler: impl Fn(&SinkError) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Arc::new(handler));
+        self
+    }
+}
+
+impl fmt::Debug for PersistenceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistenceConfig")
+            .field("flush_interval", &self.flush_interval)
+            .field("batch_size", &self.batch_size)
+            .field("on_error", &self.on_error.is_some())
+            .finish()
+    }
+}
+
+enum Message {
+    Change(PersistedChange),
+    /// Write everything queued, then answer whether it all went through
+    Flush(mpsc::Sender<bool>),
+}
+
+/// Handle to a registered sink
+///
+/// The sink runs for as long as the `StateManager` does; dropping the
+/// handle doesn't stop it. When the manager goes away the sink's queue is
+/// written one last time.
+#[derive(Debug, Clone)]
+pub struct PersistenceHandle {
+    tx: mpsc::Sender<Message>,
+}
+
+impl PersistenceHandle {
+    /// Write every change recorded so far, waiting for the sink
+    ///
+    /// Returns `false` if a batch failed (it stays queued for retry).
+    pub fn flush(&self) -> bool {
+        let (done_tx, done_rx) = mpsc::channel();
+        self.tx.send(Message::Flush(done_tx)).is_ok() && done_rx.recv().unwrap_or(false)
+    }
+}
+
+/// Turns change events into [`PersistedChange`]s for one sink
+pub(crate) struct Recorder {
+    tx: mpsc::Sender<Message>,
+    store: Arc<RwLock<StateStore>>,
+}
+
+impl Recorder {
+    /// Queue the value behind `event`
+    ///
+    /// Runs as a change observer, after the store was updated. Initial
+    /// notifications aren't changes and are skipped; a full refresh records
+    /// every value, since the refresh itself emitted no per-property events,
+    /// and a batch every key it lists.
+    pub(crate) fn record(&self, event: &ChangeEvent) {
+        if event.origin == ChangeOrigin::Initial {
+            return;
+        }
+        let timestamp = SystemTime::now();
+        let store = self.store.read();
+        let changes: Vec<PersistedChange> = if event.property_key == ChangeEvent::FULL_REFRESH_KEY {
+            let mut speakers: Vec<&SpeakerId> = store.speakers.keys().collect();
+            speakers.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            speakers
+                .into_iter()
+                .flat_map(|speaker_id| {
+                    schema::entries().filter_map(|entry| {
+                        Some(PersistedChange {
+                            speaker_id: speaker_id.clone(),
+                            property_key: entry.schema.key,
+                            origin: event.origin,
+                            timestamp,
+                            value: (entry.get)(&store, speaker_id)?,
+                        })
+                    })
+                })
+                .collect()
+        } else {
+            let keys = match event.batch.as_slice() {
+                [] => std::slice::from_ref(&event.property_key),
+                keys => keys,
+            };
+            keys.iter()
+                .filter_map(|key| {
+                    let entry = schema::lookup(key)?;
+                    Some(PersistedChange {
+                        speaker_id: event.speaker_id.clone(),
+                        property_key: entry.schema.key,
+                        origin: event.origin,
+                        timestamp,
+                        value: (entry.get)(&store, &event.speaker_id)?,
+                    })
+                })
+                .collect()
+        };
+        drop(store);
+        for change in changes {
+            // The sink thread only stops once every sender is gone
+            let _ = self.tx.send(Message::Change(change));
+        }
+    }
+}
+
+/// Start the thread feeding `sink`
+pub(crate) fn spawn(
+    sink: Box<dyn PersistenceSink>,
+    config: PersistenceConfig,
+    store: Arc<RwLock<StateStore>>,
+) -> (PersistenceHandle, Recorder) {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || Writer::new(sink, config).run(rx));
+    (PersistenceHandle { tx: tx.clone() }, Recorder { tx, store })
+}
+
+struct Writer {
+    sink: Box<dyn PersistenceSink>,
+    config: PersistenceConfig,
+    queue: VecDeque<PersistedChange>,
+    /// A batch failed; wait for the next tick before trying again
+    backing_off: bool,
+}
+
+impl Writer {
+    fn new(sink: Box<dyn PersistenceSink>, config: PersistenceConfig) -> Self {
+        Self {
+            sink,
+            config,
+            queue: VecDeque::new(),
+            backing_off: false,
+        }
+    }
+
+    fn run(mut self, rx: mpsc::Receiver<Message>) {
+        let mut next_tick = Instant::now() + self.config.flush_interval;
+        loop {
+            let wait = next_tick.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(wait) {
+                Ok(Message::Change(change)) => {
+                    self.queue.push_back(change);
+                    if !self.backing_off && self.queue.len() >= self.config.batch_size {
+                        self.write(false);
+                    }
+                }
+                Ok(Message::Flush(done)) => {
+                    let _ = done.send(self.write(true));
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    self.write(true);
+                    next_tick = Instant::now() + self.config.flush_interval;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.write(true);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Write full batches, plus the partial tail if `all`; stops at the
+    /// first failure. Returns whether everything asked for was written.
+    fn write(&mut self, all: bool) -> bool {
+        self.backing_off = false;
+        while self.queue.len() >= self.config.batch_size || (all && !self.queue.is_empty()) {
+            let len = self.queue.len().min(self.config.batch_size);
+            let batch = &self.queue.make_contiguous()[..len];
+            if let Err(e) = self.sink.write_batch(batch) {
+                tracing::warn!("persistence sink failed, retrying later: {}", e);
+                if let Some(on_error) = &self.config.on_error {
+                    on_error(&e);
+                }
+                self.backing_off = true;
+                return false;
+            }
+            self.queue.drain(..len);
+        }
+        true
+    }
+}
+
+/// Serialized form of a [`PersistedChange`]
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredChange {
+    speaker_id: SpeakerId,
+    property_key: String,
+    origin: ChangeOrigin,
+    /// Milliseconds since the Unix epoch
+    timestamp_ms: u64,
+    value: StoredValue,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum StoredValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+    Struct(serde_json::Map<String, serde_json::Value>),
+}
+
+impl From<PersistedChange> for StoredChange {
+    fn from(change: PersistedChange) -> Self {
+        let timestamp_ms = change
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        Self {
+            speaker_id: change.speaker_id,
+            property_key: change.property_key.to_string(),
+            origin: change.origin,
+            timestamp_ms,
+            value: change.value.into(),
+        }
+    }
+}
+
+impl From<DynamicValue> for StoredValue {
+    fn from(value: DynamicValue) -> Self {
+        match value {
+            DynamicValue::Bool(b) => Self::Bool(b),
+            DynamicValue::Int(i) => Self::Int(i),
+            DynamicValue::String(s) => Self::String(s),
+            DynamicValue::Struct(fields) => Self::Struct(
+                fields
+                    .into_iter()
+                    .filter_map(|(name, value)| {
+                        let value = match value {
+                            DynamicValue::Bool(b) => b.into(),
+                            DynamicValue::Int(i) => i.into(),
+                            DynamicValue::String(s) => s.into(),
+                            DynamicValue::Struct(_) => return None,
+                        };
+                        Some((name.to_string(), value))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// By hand: a derive would borrow `property_key` from the input
+impl<'de> Deserialize<'de> for PersistedChange {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        StoredChange::deserialize(deserializer)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl TryFrom<StoredChange> for PersistedChange {
+    type Error = String;
+
+    fn try_from(stored: StoredChange) -> Result<Self, String> {
+        let entry = schema::lookup(&stored.property_key)
+            .ok_or_else(|| format!("unknown property {:?}", stored.property_key))?;
+        let value = match stored.value {
+            StoredValue::Bool(b) => DynamicValue::Bool(b),
+            StoredValue::Int(i) => DynamicValue::Int(i),
+            StoredValue::String(s) => DynamicValue::String(s),
+            // Field names come back as the schema's, in the schema's order
+            StoredValue::Struct(mut fields) => {
+                let names = match entry.schema.value_kind {
+                    schema::ValueKind::Struct { fields } => fields,
+                    _ => &[],
+                };
+                DynamicValue::Struct(
+                    names
+                        .iter()
+                        .filter_map(|name| {
+                            let value = match fields.remove(*name)? {
+                                serde_json::Value::Bool(b) => DynamicValue::Bool(b),
+                                serde_json::Value::Number(n) => DynamicValue::Int(n.as_i64()?),
+                                serde_json::Value::String(s) => DynamicValue::String(s),
+                                _ => return None,
+                            };
+                            Some((*name, value))
+                        })
+                        .collect(),
+                )
+            }
+        };
+        Ok(Self {
+            speaker_id: stored.speaker_id,
+            property_key: entry.schema.key,
+            origin: stored.origin,
+            timestamp: UNIX_EPOCH + Duration::from_millis(stored.timestamp_ms),
+            value,
+        })
+    }
+}
+
+/// Sink writing one JSON object per line, rotating by size
+///
+/// Once `path` would grow past `max_bytes` it is renamed to `path.1` (the
+/// previous `path.1` to `path.2`, and so on) and a new file is started;
+/// `keep` rotated files are kept.
+pub struct NdjsonFileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: BufWriter<File>,
+    len: u64,
+}
+
+impl NdjsonFileSink {
+    /// Append to `path`, creating it if needed
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            keep,
+            file: BufWriter::new(file),
+            len,
+        })
+    }
+
+    /// Read back every change under `path`, oldest rotated file first
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<PersistedChange>> {
+        let path = path.as_ref();
+        let mut files: Vec<PathBuf> = (1..)
+            .map(|n| rotated(path, n))
+            .take_while(|p| p.exists())
+            .collect();
+        files.reverse();
+        files.push(path.to_path_buf());
+
+        let mut changes = Vec::new();
+        for file in files.iter().filter(|p| p.exists()) {
+            for line in BufReader::new(File::open(file)?).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                changes.push(serde_json::from_str(&line).map_err(io::Error::other)?);
+            }
+        }
+        Ok(changes)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = rotated(&self.path, n);
+                if from.exists() {
+                    fs::rename(from, rotated(&self.path, n + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated(&self.path, 1))?;
+        }
+        self.file = BufWriter::new(File::create(&self.path)?);
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl PersistenceSink for NdjsonFileSink {
+    fn write_batch(&mut self, batch: &[PersistedChange]) -> Result<(), SinkError> {
+        for change in batch {
+            let mut line = serde_json::to_vec(change)?;
+            line.push(b'\n');
+            if self.len > 0 && self.len + line.len() as u64 > self.max_bytes {
+                self.rotate()?;
+            }
+            self.file.write_all(&line)?;
+            self.len += line.len() as u64;
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{Bass, Mute, Property, Volume};
+    use crate::StateManager;
+    use std::sync::Mutex;
+
+    fn manager_with_speaker() -> (StateManager, SpeakerId) {
+        let manager = StateManager::new().unwrap();
+        manager
+            .add_devices(vec![sonos_discovery::Device {
+                id: "RINCON_DEN".to_string(),
+                name: "Den".to_string(),
+                room_name: "Den".to_string(),
+                ip_address: "192.168.1.100".to_string(),
+                port: 1400,
+                model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
+            }])
+            .unwrap();
+        let den = SpeakerId::new("RINCON_DEN");
+        for key in [Volume::KEY, Mute::KEY, Bass::KEY] {
+            manager.register_watch(&den, key);
+        }
+        (manager, den)
+    }
+
+    /// Records each batch's volumes; optionally slow or failing
+    #[derive(Clone, Default)]
+    struct Recording {
+        batches: Arc<Mutex<Vec<Vec<DynamicValue>>>>,
+        delay: Duration,
+        failures: Arc<Mutex<usize>>,
+    }
+
+    impl PersistenceSink for Recording {
+        fn write_batch(&mut self, batch: &[PersistedChange]) -> Result<(), SinkError> {
+            thread::sleep(self.delay);
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err("store unavailable".into());
+            }
+            let values = batch.iter().map(|c| c.value.clone()).collect();
+            self.batches.lock().unwrap().push(values);
+            Ok(())
+        }
+    }
+
+    fn volumes(range: std::ops::Range<u8>) -> Vec<DynamicValue> {
+        range.map(|v| DynamicValue::Int(v.into())).collect()
+    }
+
+    #[test]
+    fn test_slow_sink_does_not_stall_changes() {
+        let (manager, den) = manager_with_speaker();
+        let sink = Recording {
+            delay: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let config = PersistenceConfig::default().batch_size(1);
+        let handle = manager.add_persistence_sink(Box::new(sink.clone()), config);
+
+        let start = Instant::now();
+        for v in 0..20 {
+            manager.set_property(&den, Volume(v));
+        }
+        assert!(start.elapsed() < Duration::from_millis(200));
+        assert_eq!(manager.iter().try_iter().count(), 20);
+
+        assert!(handle.flush());
+        let written: Vec<_> = sink.batches.lock().unwrap().concat();
+        assert_eq!(written, volumes(0..20));
+    }
+
+    #[test]
+    fn test_batches_follow_config_and_retry() {
+        let (manager, den) = manager_with_speaker();
+        let sink = Recording::default();
+        let errors = Arc::new(Mutex::new(0));
+        let seen = Arc::clone(&errors);
+        let config = PersistenceConfig::default()
+            .batch_size(3)
+            .flush_interval(Duration::from_secs(3600))
+            .on_error(move |_| *seen.lock().unwrap() += 1);
+        let handle = manager.add_persistence_sink(Box::new(sink.clone()), config);
+
+        for v in 0..7 {
+            manager.set_property(&den, Volume(v));
+        }
+        assert!(handle.flush());
+        let sizes: Vec<_> = sink.batches.lock().unwrap().iter().map(Vec::len).collect();
+        assert_eq!(sizes, [3, 3, 1]);
+
+        // A failed batch stays queued and goes out whole on the next flush
+        *sink.failures.lock().unwrap() = 1;
+        manager.set_property(&den, Volume(50));
+        assert!(!handle.flush());
+        assert_eq!(*errors.lock().unwrap(), 1);
+        assert!(handle.flush());
+        let last = sink.batches.lock().unwrap().last().cloned();
+        assert_eq!(last, Some(volumes(50..51)));
+    }
+
+    #[test]
+    fn test_file_sink_replays_into_same_state() {
+        let dir = std::env::temp_dir().join(format!("sonos-persistence-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("changes.ndjson");
+
+        let (manager, den) = manager_with_speaker();
+        let sink = NdjsonFileSink::open(&path, 512, 8).unwrap();
+        let handle = manager.add_persistence_sink(Box::new(sink), PersistenceConfig::default());
+        for v in 0..10 {
+            manager.set_property(&den, Volume(v * 10));
+            manager.set_property(&den, Mute(v % 2 == 0));
+            manager.set_property(&den, Bass(v as i8 - 5));
+        }
+        assert!(handle.flush());
+        assert!(rotated(&path, 1).exists(), "log should have rotated");
+
+        let changes = NdjsonFileSink::read(&path).unwrap();
+        assert_eq!(changes.len(), 30);
+        let (replayed, _) = manager_with_speaker();
+        assert_eq!(replayed.replay(changes), 30);
+        for key in [Volume::KEY, Mute::KEY, Bass::KEY] {
+            assert_eq!(
+                replayed.get_property_dynamic(&den, key),
+                manager.get_property_dynamic(&den, key)
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
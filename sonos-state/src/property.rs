@@ -7,6 +7,7 @@
 //! - Can be watched for changes
 
 use serde::{Deserialize, Serialize};
+use sonos_api::services::av_transport::PlayMode;
 use sonos_api::Service;
 
 use crate::model::{GroupId, SpeakerInfo};
@@ -58,6 +59,34 @@ pub trait SonosProperty: Property {
     /// Used for subscription hints - to know which services need subscriptions
     /// when this property is being watched.
     const SERVICE: Service;
+
+    /// Whether this property lives on the home-theater bond primary.
+    ///
+    /// Satellites (Sub, surrounds) don't hold these settings themselves;
+    /// the SDK reads and writes them on the speaker they are bonded to.
+    const BOND_PRIMARY: bool = false;
+
+    /// Whether speakers of this model have this property
+    ///
+    /// The SDK refuses to watch or fetch it on other models, without sending
+    /// anything, instead of waiting on a value that never comes.
+    fn supported_by(_model_name: &str) -> bool {
+        true
+    }
+
+    /// Fields that change without the value meaning anything different
+    ///
+    /// Store diffs leave a change confined to these out unless asked, and
+    /// the listening tracker doesn't count it as a new track; live change
+    /// detection still reports it. Listed by their
+    /// [`DynamicValue`](crate::DynamicValue) field names.
+    const VOLATILE_FIELDS: &'static [&'static str] = &[];
+
+    /// Whether `other` differs from `self` only in
+    /// [`VOLATILE_FIELDS`](Self::VOLATILE_FIELDS)
+    fn same_identity(&self, other: &Self) -> bool {
+        self == other
+    }
 }
 
 // ============================================================================
@@ -179,6 +208,187 @@ impl Loudness {
     }
 }
 
+/// Whether line-out volume is fixed (Port, Connect); volume writes fail when set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputFixed(pub bool);
+
+impl Property for OutputFixed {
+    const KEY: &'static str = "output_fixed";
+}
+
+impl SonosProperty for OutputFixed {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::RenderingControl;
+}
+
+impl OutputFixed {
+    pub fn is_fixed(&self) -> bool {
+        self.0
+    }
+}
+
+/// EQ presets the speaker can recall
+///
+/// Reported as a comma-separated `PresetNameList`, both by `ListPresets` and
+/// in LastChange. Every speaker has `FactoryDefaults`, which resets EQ.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Presets(pub Vec<String>);
+
+impl Property for Presets {
+    const KEY: &'static str = "presets";
+}
+
+impl SonosProperty for Presets {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::RenderingControl;
+}
+
+impl Presets {
+    /// Parse the comma-separated preset list, ignoring stray whitespace and empty entries
+    pub fn parse(list: &str) -> Self {
+        Self(sonos_api::services::rendering_control::parse_preset_name_list(list))
+    }
+
+    /// Check whether a preset name is listed (exact match, as the device compares)
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.iter().any(|p| p == name)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.0
+    }
+}
+
+// ============================================================================
+// Home-theater Properties (RenderingControl EQ on the bond primary)
+// ============================================================================
+
+/// Whether the bonded Sub is enabled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubEnabled(pub bool);
+
+impl Property for SubEnabled {
+    const KEY: &'static str = "sub_enabled";
+}
+
+impl SonosProperty for SubEnabled {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::RenderingControl;
+    const BOND_PRIMARY: bool = true;
+}
+
+impl SubEnabled {
+    pub fn is_enabled(&self) -> bool {
+        self.0
+    }
+}
+
+/// Sub level (-15 to +15)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubGain(pub i8);
+
+impl Property for SubGain {
+    const KEY: &'static str = "sub_gain";
+}
+
+impl SonosProperty for SubGain {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::RenderingControl;
+    const BOND_PRIMARY: bool = true;
+}
+
+impl SubGain {
+    pub fn new(value: i8) -> Self {
+        Self(value.clamp(-15, 15))
+    }
+
+    pub fn value(&self) -> i8 {
+        self.0
+    }
+}
+
+/// Whether bonded surround speakers are enabled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurroundEnabled(pub bool);
+
+impl Property for SurroundEnabled {
+    const KEY: &'static str = "surround_enabled";
+}
+
+impl SonosProperty for SurroundEnabled {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::RenderingControl;
+    const BOND_PRIMARY: bool = true;
+}
+
+impl SurroundEnabled {
+    pub fn is_enabled(&self) -> bool {
+        self.0
+    }
+}
+
+/// How surrounds play music
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SurroundMode {
+    /// Subtle, ambient surround sound (EQ value 0)
+    Ambient,
+    /// Full-range surround sound (EQ value 1)
+    Full,
+}
+
+impl Property for SurroundMode {
+    const KEY: &'static str = "surround_mode";
+}
+
+impl SonosProperty for SurroundMode {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::RenderingControl;
+    const BOND_PRIMARY: bool = true;
+}
+
+impl SurroundMode {
+    /// Parse the `SurroundMode` EQ value
+    pub fn from_eq_value(value: i16) -> Self {
+        if value == 1 {
+            Self::Full
+        } else {
+            Self::Ambient
+        }
+    }
+
+    /// The `SurroundMode` EQ value
+    pub fn eq_value(&self) -> i16 {
+        match self {
+            Self::Ambient => 0,
+            Self::Full => 1,
+        }
+    }
+}
+
+/// TV surround level (-15 to +15)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurroundLevel(pub i8);
+
+impl Property for SurroundLevel {
+    const KEY: &'static str = "surround_level";
+}
+
+impl SonosProperty for SurroundLevel {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::RenderingControl;
+    const BOND_PRIMARY: bool = true;
+}
+
+impl SurroundLevel {
+    pub fn new(value: i8) -> Self {
+        Self(value.clamp(-15, 15))
+    }
+
+    pub fn value(&self) -> i8 {
+        self.0
+    }
+}
+
 // ============================================================================
 // Group-scoped Properties (from GroupRenderingControl)
 // ============================================================================
@@ -252,6 +462,115 @@ impl GroupVolumeChangeable {
     }
 }
 
+// ============================================================================
+// Speaker-scoped Properties (from DeviceProperties)
+// ============================================================================
+
+/// Whether the speaker's buttons/touch controls are locked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ButtonLock(pub bool);
+
+impl Property for ButtonLock {
+    const KEY: &'static str = "button_lock";
+}
+
+impl SonosProperty for ButtonLock {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::DeviceProperties;
+}
+
+impl ButtonLock {
+    pub fn new(locked: bool) -> Self {
+        Self(locked)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.0
+    }
+}
+
+/// Whether the speaker's white status light is on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusLight(pub bool);
+
+impl Property for StatusLight {
+    const KEY: &'static str = "status_light";
+}
+
+impl SonosProperty for StatusLight {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::DeviceProperties;
+}
+
+impl StatusLight {
+    pub fn new(on: bool) -> Self {
+        Self(on)
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.0
+    }
+}
+
+/// Where a portable speaker's audio is going
+///
+/// Only portable models (Roam, Move) report this; the SDK returns
+/// `NotSupported` for the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AudioOutput {
+    /// The speaker's own driver
+    Speaker,
+    /// Wired headphones
+    Headphone,
+    /// A connected Bluetooth device
+    Bluetooth,
+    /// Line-out
+    LineOut,
+}
+
+impl Property for AudioOutput {
+    const KEY: &'static str = "audio_output";
+}
+
+impl SonosProperty for AudioOutput {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::DeviceProperties;
+
+    fn supported_by(model_name: &str) -> bool {
+        sonos_api::services::device_properties::supports_audio_output(model_name)
+    }
+}
+
+impl AudioOutput {
+    /// Parse an `AudioOutput` value (`SPEAKER`, `HEADPHONE`, `BLUETOOTH`,
+    /// `LINE_OUT`), in any case
+    pub fn from_wire(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "SPEAKER" => Some(Self::Speaker),
+            "HEADPHONE" => Some(Self::Headphone),
+            "BLUETOOTH" => Some(Self::Bluetooth),
+            "LINE_OUT" => Some(Self::LineOut),
+            _ => None,
+        }
+    }
+
+    /// The `AudioOutput` value
+    pub fn wire(&self) -> &'static str {
+        use sonos_api::services::device_properties as dp;
+        match self {
+            Self::Speaker => dp::AUDIO_OUTPUT_SPEAKER,
+            Self::Headphone => dp::AUDIO_OUTPUT_HEADPHONE,
+            Self::Bluetooth => dp::AUDIO_OUTPUT_BLUETOOTH,
+            Self::LineOut => dp::AUDIO_OUTPUT_LINE_OUT,
+        }
+    }
+
+    /// Whether the speaker itself is silent
+    pub fn is_external(&self) -> bool {
+        !matches!(self, Self::Speaker)
+    }
+}
+
 // ============================================================================
 // Speaker-scoped Properties (from AVTransport)
 // ============================================================================
@@ -336,26 +655,26 @@ impl Position {
 
     /// Parse time string (HH:MM:SS or HH:MM:SS.mmm) to milliseconds
     pub fn parse_time_to_ms(time_str: &str) -> Option<u64> {
-        if !time_str.contains(':') {
+        let mut parts = time_str.split(':');
+        let (Some(hours), Some(minutes), Some(seconds), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
             return None;
-        }
-
-        let parts: Vec<&str> = time_str.split(':').collect();
-        if parts.len() != 3 {
-            return None;
-        }
-
-        let hours: u64 = parts[0].parse().ok()?;
-        let minutes: u64 = parts[1].parse().ok()?;
-
-        let seconds_parts: Vec<&str> = parts[2].split('.').collect();
-        let seconds: u64 = seconds_parts[0].parse().ok()?;
-        let millis: u64 = seconds_parts
-            .get(1)
-            .and_then(|m| m.parse().ok())
-            .unwrap_or(0);
+        };
 
-        Some((hours * 3600 + minutes * 60 + seconds) * 1000 + millis)
+        let hours: u64 = hours.parse().ok()?;
+        let minutes: u64 = minutes.parse().ok()?;
+        let (seconds, millis) = seconds.split_once('.').unwrap_or((seconds, ""));
+        let seconds: u64 = seconds.parse().ok()?;
+        let millis: u64 = millis.parse().unwrap_or(0);
+
+        // Checked: the string comes from the device, and "99999999999999:00:00" must not panic
+        hours
+            .checked_mul(3600)?
+            .checked_add(minutes.checked_mul(60)?)?
+            .checked_add(seconds)?
+            .checked_mul(1000)?
+            .checked_add(millis)
     }
 }
 
@@ -373,9 +692,19 @@ impl Property for CurrentTrack {
     const KEY: &'static str = "current_track";
 }
 
+/// The album art URI carries a session token that the speaker reissues
+/// while the track plays
 impl SonosProperty for CurrentTrack {
     const SCOPE: Scope = Scope::Speaker;
     const SERVICE: Service = Service::AVTransport;
+    const VOLATILE_FIELDS: &'static [&'static str] = &["album_art_uri"];
+
+    fn same_identity(&self, other: &Self) -> bool {
+        self.title == other.title
+            && self.artist == other.artist
+            && self.album == other.album
+            && self.uri == other.uri
+    }
 }
 
 impl CurrentTrack {
@@ -411,6 +740,77 @@ impl Default for CurrentTrack {
     }
 }
 
+/// Transport actions the speaker currently accepts
+///
+/// Sonos reports these as a comma-separated list (e.g. `"Play, Stop, Next"`),
+/// both from `GetCurrentTransportActions` and the `CurrentTransportActions`
+/// LastChange variable. A radio stream typically omits `Pause`, `Next` and `Seek`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransportActions(pub Vec<String>);
+
+impl Property for TransportActions {
+    const KEY: &'static str = "transport_actions";
+}
+
+impl SonosProperty for TransportActions {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::AVTransport;
+}
+
+impl TransportActions {
+    /// Parse the comma-separated action list, ignoring stray whitespace and empty entries
+    pub fn parse(actions: &str) -> Self {
+        Self(
+            actions
+                .split(',')
+                .map(str::trim)
+                .filter(|a| !a.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    /// Check whether an action (e.g. `"Pause"`) is currently allowed (case-insensitive)
+    pub fn allows(&self, action: &str) -> bool {
+        self.0.iter().any(|a| a.eq_ignore_ascii_case(action))
+    }
+
+    pub fn actions(&self) -> &[String] {
+        &self.0
+    }
+}
+
+/// Queue play mode (shuffle and repeat)
+///
+/// Evented as `CurrentPlayMode` in AVTransport LastChange and read with
+/// `GetTransportSettings`; written with `SetPlayMode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentPlayMode(pub PlayMode);
+
+impl Property for CurrentPlayMode {
+    const KEY: &'static str = "play_mode";
+}
+
+impl SonosProperty for CurrentPlayMode {
+    const SCOPE: Scope = Scope::Speaker;
+    const SERVICE: Service = Service::AVTransport;
+}
+
+impl CurrentPlayMode {
+    /// Parse the wire value, `None` for a mode this crate doesn't know
+    pub fn from_wire(value: &str) -> Option<Self> {
+        PlayMode::parse(value).map(Self)
+    }
+
+    pub fn mode(&self) -> PlayMode {
+        self.0
+    }
+
+    pub fn is_shuffle(&self) -> bool {
+        self.0.is_shuffle()
+    }
+}
+
 /// Speaker's group membership
 ///
 /// Every speaker is always in a group - a single speaker forms a group of one.
@@ -442,11 +842,100 @@ impl GroupMembership {
     }
 }
 
+// ============================================================================
+// Group-scoped Properties (from ZoneGroupTopology)
+// ============================================================================
+
+/// A group's coordinator and members
+///
+/// Derived from topology and only rewritten when the group forms or is
+/// reshaped, so watchers don't wake for topology attributes they don't use.
+/// Members are sorted by ID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupComposition {
+    pub coordinator_id: crate::model::SpeakerId,
+    pub member_ids: Vec<crate::model::SpeakerId>,
+}
+
+impl Property for GroupComposition {
+    const KEY: &'static str = "group_composition";
+}
+
+impl SonosProperty for GroupComposition {
+    const SCOPE: Scope = Scope::Group;
+    const SERVICE: Service = Service::ZoneGroupTopology;
+}
+
+impl GroupComposition {
+    pub fn new(
+        coordinator_id: crate::model::SpeakerId,
+        mut member_ids: Vec<crate::model::SpeakerId>,
+    ) -> Self {
+        member_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        Self {
+            coordinator_id,
+            member_ids,
+        }
+    }
+
+    /// Composition of a topology group
+    pub fn of(group: &GroupInfo) -> Self {
+        Self::new(group.coordinator_id.clone(), group.member_ids.clone())
+    }
+
+    pub fn contains(&self, speaker_id: &crate::model::SpeakerId) -> bool {
+        self.member_ids.contains(speaker_id)
+    }
+
+    pub fn is_standalone(&self) -> bool {
+        self.member_ids.len() == 1
+    }
+}
+
 // ============================================================================
 // System-scoped Properties
 // ============================================================================
 
+/// IDs of every current group, sorted
+///
+/// Derived from topology and only rewritten when a group appears or goes
+/// away, so a group list view doesn't redraw for changes inside groups.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupList(pub Vec<GroupId>);
+
+impl Property for GroupList {
+    const KEY: &'static str = "group_list";
+}
+
+impl SonosProperty for GroupList {
+    const SCOPE: Scope = Scope::System;
+    const SERVICE: Service = Service::ZoneGroupTopology;
+}
+
+impl GroupList {
+    pub fn new(mut group_ids: Vec<GroupId>) -> Self {
+        group_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        Self(group_ids)
+    }
+
+    pub fn group_ids(&self) -> &[GroupId] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 /// System-wide topology of all speakers and groups
+///
+/// Replaced whenever the applied topology changes at all. Watch
+/// [`GroupMembership`], [`GroupComposition`] or [`GroupList`] instead to only
+/// hear about the part a view shows.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Topology {
     pub speakers: Vec<SpeakerInfo>,
@@ -530,6 +1019,18 @@ mod tests {
         assert_eq!(Volume::new(0).value(), 0);
     }
 
+    #[test]
+    fn test_transport_actions_parsing() {
+        let actions = TransportActions::parse(" Play,Stop ,  Next,, X_DLNA_SeekTime ");
+        assert_eq!(
+            actions.actions(),
+            ["Play", "Stop", "Next", "X_DLNA_SeekTime"]
+        );
+        assert!(actions.allows("play"));
+        assert!(!actions.allows("Pause"));
+        assert!(TransportActions::parse("").actions().is_empty());
+    }
+
     #[test]
     fn test_bass_clamping() {
         assert_eq!(Bass::new(0).value(), 0);
@@ -574,6 +1075,7 @@ mod tests {
         assert_eq!(Position::parse_time_to_ms("0:03:45"), Some(225_000));
         assert_eq!(Position::parse_time_to_ms("0:03:45.500"), Some(225_500));
         assert_eq!(Position::parse_time_to_ms("NOT_IMPLEMENTED"), None);
+        assert_eq!(Position::parse_time_to_ms("99999999999999999:00:00"), None);
     }
 
     #[test]
@@ -0,0 +1,1086 @@
+//! Property schemas and dynamically typed access
+//!
+//! Generic tools (settings inspectors, HTTP bridges) can't name property
+//! types at compile time. Every built-in speaker- and group-scoped property
+//! is listed here with its [`PropertySchema`], and
+//! [`StateManager::get_property_dynamic()`] /
+//! [`StateManager::set_property_dynamic()`] dispatch by key to the typed
+//! accessors. Dynamic writes are checked against the schema first, and
+//! [`StateManager::watch_dynamic()`] returns a [`DynamicWatcher`] that reports
+//! changes as [`DynamicValue`]s.
+//!
+//! The system-scoped `Topology` and `GroupList` are not listed: they aren't
+//! addressed by speaker.
+//!
+//! [`StateManager::get_property_dynamic()`]: crate::StateManager::get_property_dynamic
+//! [`StateManager::set_property_dynamic()`]: crate::StateManager::set_property_dynamic
+//! [`StateManager::watch_dynamic()`]: crate::StateManager::watch_dynamic
+
+use std::fmt;
+use std::future::Future;
+use std::sync::{mpsc, Arc, Weak};
+use std::task::{Poll, Waker};
+use std::time::{Duration, Instant};
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Serialize, Serializer};
+use sonos_api::Service;
+
+use crate::model::SpeakerId;
+use crate::origin::ChangeOrigin;
+use crate::persistence::StoredValue;
+use crate::property::{
+    AudioOutput, Bass, ButtonLock, CurrentPlayMode, CurrentTrack, GroupComposition,
+    GroupMembership, GroupMute, GroupVolume, GroupVolumeChangeable, Loudness, Mute, OutputFixed,
+    PlaybackState, Position, Presets, Scope, SonosProperty, StatusLight, SubEnabled, SubGain,
+    SurroundEnabled, SurroundLevel, SurroundMode, TransportActions, Treble, Volume,
+};
+use crate::state::{ChangeEvent, StateManager, StateStore};
+use crate::{Result, StateError};
+
+/// Shape and constraints of a property's value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Bool,
+    /// Integer within `min..=max`
+    Int {
+        min: i64,
+        max: i64,
+    },
+    /// One of the listed names
+    Enum {
+        variants: &'static [&'static str],
+    },
+    String,
+    /// Several named fields, read as [`DynamicValue::Struct`]
+    Struct {
+        fields: &'static [&'static str],
+    },
+}
+
+impl ValueKind {
+    /// Check that `value` fits this kind and its constraints
+    pub fn check(&self, value: &DynamicValue) -> std::result::Result<(), String> {
+        match (self, value) {
+            (ValueKind::Bool, DynamicValue::Bool(_))
+            | (ValueKind::String, DynamicValue::String(_))
+            | (ValueKind::Struct { .. }, DynamicValue::Struct(_)) => Ok(()),
+            (ValueKind::Int { min, max }, DynamicValue::Int(v)) => {
+                if (*min..=*max).contains(v) {
+                    Ok(())
+                } else {
+                    Err(format!("{v} is outside {min}..={max}"))
+                }
+            }
+            (ValueKind::Enum { variants }, DynamicValue::String(s)) => {
+                if variants.contains(&s.as_str()) {
+                    Ok(())
+                } else {
+                    Err(format!("{s:?} is not one of {variants:?}"))
+                }
+            }
+            (kind, value) => Err(format!("expected {kind:?}, got {value}")),
+        }
+    }
+}
+
+/// Static description of one property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertySchema {
+    /// Property key, as in [`Property::KEY`](crate::Property::KEY)
+    pub key: &'static str,
+    /// Human-readable name for UIs
+    pub display_name: &'static str,
+    pub value_kind: ValueKind,
+    /// UPnP service whose events feed this property
+    pub source_service: Service,
+    pub scope: Scope,
+    /// Whether [`StateManager::set_property_dynamic()`] accepts it
+    pub writable: bool,
+    /// Struct fields whose changes alone are not significant, from
+    /// [`SonosProperty::VOLATILE_FIELDS`]
+    pub volatile_fields: &'static [&'static str],
+}
+
+impl PropertySchema {
+    const fn of<P: SonosProperty>(
+        display_name: &'static str,
+        value_kind: ValueKind,
+        writable: bool,
+    ) -> Self {
+        Self {
+            key: P::KEY,
+            display_name,
+            value_kind,
+            source_service: P::SERVICE,
+            scope: P::SCOPE,
+            writable,
+            volatile_fields: P::VOLATILE_FIELDS,
+        }
+    }
+
+    /// Whether `before` and `after` differ only in
+    /// [`volatile_fields`](Self::volatile_fields), the dynamic counterpart
+    /// of [`SonosProperty::same_identity()`]
+    pub fn same_identity(&self, before: &DynamicValue, after: &DynamicValue) -> bool {
+        match (before, after) {
+            (DynamicValue::Struct(a), DynamicValue::Struct(b)) => {
+                let significant = |fields: &Vec<(&'static str, DynamicValue)>| {
+                    fields
+                        .iter()
+                        .filter(|(name, _)| !self.volatile_fields.contains(name))
+                        .cloned()
+                        .collect::<Vec<_>>()
+                };
+                significant(a) == significant(b)
+            }
+            _ => before == after,
+        }
+    }
+}
+
+/// A property value without its static type
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    Bool(bool),
+    Int(i64),
+    /// Strings and enum variant names
+    String(String),
+    /// Named fields; optional fields that are unset are left out
+    Struct(Vec<(&'static str, DynamicValue)>),
+}
+
+impl fmt::Display for DynamicValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicValue::Bool(b) => write!(f, "Bool({b})"),
+            DynamicValue::Int(i) => write!(f, "Int({i})"),
+            DynamicValue::String(s) => write!(f, "String({s:?})"),
+            DynamicValue::Struct(_) => write!(f, "Struct"),
+        }
+    }
+}
+
+/// As JSON scalars, or an object of a struct's fields
+impl Serialize for DynamicValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        StoredValue::from(self.clone()).serialize(serializer)
+    }
+}
+
+/// Conversion between a property type and [`DynamicValue`]
+trait DynamicProperty: SonosProperty {
+    const SCHEMA: PropertySchema;
+
+    fn to_dynamic(&self) -> DynamicValue;
+
+    /// Build a value that already passed [`ValueKind::check()`]; `None` for
+    /// read-only properties
+    fn from_dynamic(_value: &DynamicValue) -> Option<Self> {
+        None
+    }
+}
+
+macro_rules! bool_property {
+    ($ty:ident, $name:literal, writable: $writable:literal) => {
+        impl DynamicProperty for $ty {
+            const SCHEMA: PropertySchema =
+                PropertySchema::of::<$ty>($name, ValueKind::Bool, $writable);
+
+            fn to_dynamic(&self) -> DynamicValue {
+                DynamicValue::Bool(self.0)
+            }
+
+            fn from_dynamic(value: &DynamicValue) -> Option<Self> {
+                match value {
+                    DynamicValue::Bool(b) => Some(Self(*b)),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+macro_rules! int_property {
+    ($ty:ident, $name:literal, $min:literal..=$max:literal) => {
+        impl DynamicProperty for $ty {
+            const SCHEMA: PropertySchema = PropertySchema::of::<$ty>(
+                $name,
+                ValueKind::Int {
+                    min: $min,
+                    max: $max,
+                },
+                true,
+            );
+
+            fn to_dynamic(&self) -> DynamicValue {
+                DynamicValue::Int(self.0.into())
+            }
+
+            fn from_dynamic(value: &DynamicValue) -> Option<Self> {
+                match value {
+                    DynamicValue::Int(i) => (*i).try_into().ok().map(Self),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+int_property!(Volume, "Volume", 0..=100);
+bool_property!(Mute, "Mute", writable: true);
+int_property!(Bass, "Bass", -10..=10);
+int_property!(Treble, "Treble", -10..=10);
+bool_property!(Loudness, "Loudness", writable: true);
+bool_property!(OutputFixed, "Fixed Volume", writable: false);
+bool_property!(SubEnabled, "Sub", writable: true);
+int_property!(SubGain, "Sub Level", -15..=15);
+bool_property!(SurroundEnabled, "Surrounds", writable: true);
+int_property!(SurroundLevel, "Surround Level", -15..=15);
+bool_property!(ButtonLock, "Button Lock", writable: true);
+bool_property!(StatusLight, "Status Light", writable: true);
+int_property!(GroupVolume, "Group Volume", 0..=100);
+bool_property!(GroupMute, "Group Mute", writable: true);
+bool_property!(GroupVolumeChangeable, "Group Volume Changeable", writable: false);
+
+impl DynamicProperty for SurroundMode {
+    const SCHEMA: PropertySchema = PropertySchema::of::<Self>(
+        "Surround Music Playback",
+        ValueKind::Enum {
+            variants: &["Ambient", "Full"],
+        },
+        true,
+    );
+
+    fn to_dynamic(&self) -> DynamicValue {
+        DynamicValue::String(format!("{self:?}"))
+    }
+
+    fn from_dynamic(value: &DynamicValue) -> Option<Self> {
+        match value {
+            DynamicValue::String(s) if s == "Ambient" => Some(Self::Ambient),
+            DynamicValue::String(s) if s == "Full" => Some(Self::Full),
+            _ => None,
+        }
+    }
+}
+
+/// Read-only: the output follows what's plugged in or paired
+impl DynamicProperty for AudioOutput {
+    const SCHEMA: PropertySchema = PropertySchema::of::<Self>(
+        "Audio Output",
+        ValueKind::Enum {
+            variants: &["Speaker", "Headphone", "Bluetooth", "LineOut"],
+        },
+        false,
+    );
+
+    fn to_dynamic(&self) -> DynamicValue {
+        DynamicValue::String(format!("{self:?}"))
+    }
+
+    fn from_dynamic(value: &DynamicValue) -> Option<Self> {
+        match value {
+            DynamicValue::String(s) => match s.as_str() {
+                "Speaker" => Some(Self::Speaker),
+                "Headphone" => Some(Self::Headphone),
+                "Bluetooth" => Some(Self::Bluetooth),
+                "LineOut" => Some(Self::LineOut),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Read-only: playback is changed with transport commands, not by value
+impl DynamicProperty for PlaybackState {
+    const SCHEMA: PropertySchema = PropertySchema::of::<Self>(
+        "Playback State",
+        ValueKind::Enum {
+            variants: &["Playing", "Paused", "Stopped", "Transitioning"],
+        },
+        false,
+    );
+
+    fn to_dynamic(&self) -> DynamicValue {
+        DynamicValue::String(format!("{self:?}"))
+    }
+}
+
+impl DynamicProperty for Position {
+    const SCHEMA: PropertySchema = PropertySchema::of::<Self>(
+        "Position",
+        ValueKind::Struct {
+            fields: &["position_ms", "duration_ms"],
+        },
+        false,
+    );
+
+    fn to_dynamic(&self) -> DynamicValue {
+        let ms = |v: u64| DynamicValue::Int(v.try_into().unwrap_or(i64::MAX));
+        DynamicValue::Struct(vec![
+            ("position_ms", ms(self.position_ms)),
+            ("duration_ms", ms(self.duration_ms)),
+        ])
+    }
+}
+
+impl DynamicProperty for CurrentTrack {
+    const SCHEMA: PropertySchema = PropertySchema::of::<Self>(
+        "Current Track",
+        ValueKind::Struct {
+            fields: &["title", "artist", "album", "album_art_uri", "uri"],
+        },
+        false,
+    );
+
+    fn to_dynamic(&self) -> DynamicValue {
+        let fields = [
+            ("title", &self.title),
+            ("artist", &self.artist),
+            ("album", &self.album),
+            ("album_art_uri", &self.album_art_uri),
+            ("uri", &self.uri),
+        ];
+        DynamicValue::Struct(
+            fields
+                .into_iter()
+                .filter_map(|(name, v)| Some((name, DynamicValue::String(v.clone()?))))
+                .collect(),
+        )
+    }
+}
+
+/// Comma-separated, as the device reports it; recalled with `SelectPreset`
+impl DynamicProperty for Presets {
+    const SCHEMA: PropertySchema =
+        PropertySchema::of::<Self>("EQ Presets", ValueKind::String, false);
+
+    fn to_dynamic(&self) -> DynamicValue {
+        DynamicValue::String(self.names().join(","))
+    }
+}
+
+/// Wire values, as `SetPlayMode` takes them
+impl DynamicProperty for CurrentPlayMode {
+    const SCHEMA: PropertySchema = PropertySchema::of::<Self>(
+        "Play Mode",
+        ValueKind::Enum {
+            variants: &[
+                "NORMAL",
+                "REPEAT_ALL",
+                "REPEAT_ONE",
+                "SHUFFLE_NOREPEAT",
+                "SHUFFLE",
+                "SHUFFLE_REPEAT_ONE",
+            ],
+        },
+        true,
+    );
+
+    fn to_dynamic(&self) -> DynamicValue {
+        DynamicValue::String(self.0.to_string())
+    }
+
+    fn from_dynamic(value: &DynamicValue) -> Option<Self> {
+        match value {
+            DynamicValue::String(s) => Self::from_wire(s),
+            _ => None,
+        }
+    }
+}
+
+/// Comma-separated, as the device reports it
+impl DynamicProperty for TransportActions {
+    const SCHEMA: PropertySchema =
+        PropertySchema::of::<Self>("Available Actions", ValueKind::String, false);
+
+    fn to_dynamic(&self) -> DynamicValue {
+        DynamicValue::String(self.actions().join(","))
+    }
+}
+
+impl DynamicProperty for GroupMembership {
+    const SCHEMA: PropertySchema = PropertySchema::of::<Self>(
+        "Group Membership",
+        ValueKind::Struct {
+            fields: &["group_id", "is_coordinator"],
+        },
+        false,
+    );
+
+    fn to_dynamic(&self) -> DynamicValue {
+        DynamicValue::Struct(vec![
+            (
+                "group_id",
+                DynamicValue::String(self.group_id.as_str().to_string()),
+            ),
+            ("is_coordinator", DynamicValue::Bool(self.is_coordinator)),
+        ])
+    }
+}
+
+/// Members are comma-separated
+impl DynamicProperty for GroupComposition {
+    const SCHEMA: PropertySchema = PropertySchema::of::<Self>(
+        "Group Members",
+        ValueKind::Struct {
+            fields: &["coordinator_id", "member_ids"],
+        },
+        false,
+    );
+
+    fn to_dynamic(&self) -> DynamicValue {
+        let members: Vec<&str> = self.member_ids.iter().map(|id| id.as_str()).collect();
+        DynamicValue::Struct(vec![
+            (
+                "coordinator_id",
+                DynamicValue::String(self.coordinator_id.as_str().to_string()),
+            ),
+            ("member_ids", DynamicValue::String(members.join(","))),
+        ])
+    }
+}
+
+/// A schema with its typed accessors
+pub(crate) struct Entry {
+    pub(crate) schema: PropertySchema,
+    pub(crate) get: fn(&StateStore, &SpeakerId) -> Option<DynamicValue>,
+    pub(crate) set: fn(&StateManager, &SpeakerId, &DynamicValue) -> Result<()>,
+}
+
+const fn entry<P: DynamicProperty>() -> Entry {
+    Entry {
+        schema: P::SCHEMA,
+        get: get_dynamic::<P>,
+        set: set_dynamic::<P>,
+    }
+}
+
+static REGISTRY: [Entry; 25] = [
+    entry::<Volume>(),
+    entry::<Mute>(),
+    entry::<Bass>(),
+    entry::<Treble>(),
+    entry::<Loudness>(),
+    entry::<OutputFixed>(),
+    entry::<Presets>(),
+    entry::<SubEnabled>(),
+    entry::<SubGain>(),
+    entry::<SurroundEnabled>(),
+    entry::<SurroundMode>(),
+    entry::<SurroundLevel>(),
+    entry::<ButtonLock>(),
+    entry::<StatusLight>(),
+    entry::<AudioOutput>(),
+    entry::<GroupVolume>(),
+    entry::<GroupMute>(),
+    entry::<GroupVolumeChangeable>(),
+    entry::<PlaybackState>(),
+    entry::<Position>(),
+    entry::<CurrentTrack>(),
+    entry::<TransportActions>(),
+    entry::<CurrentPlayMode>(),
+    entry::<GroupMembership>(),
+    entry::<GroupComposition>(),
+];
+
+pub(crate) fn entries() -> impl Iterator<Item = &'static Entry> {
+    REGISTRY.iter()
+}
+
+pub(crate) fn schemas() -> impl Iterator<Item = &'static PropertySchema> {
+    entries().map(|e| &e.schema)
+}
+
+pub(crate) fn lookup(key: &str) -> Option<&'static Entry> {
+    REGISTRY.iter().find(|e| e.schema.key == key)
+}
+
+pub(crate) fn unknown_property(key: &str) -> StateError {
+    StateError::UnknownProperty {
+        key: key.to_string(),
+        valid_keys: schemas().map(|s| s.key).collect(),
+    }
+}
+
+/// Group-scoped values are read from the speaker's current group
+fn get_dynamic<P: DynamicProperty>(
+    store: &StateStore,
+    speaker_id: &SpeakerId,
+) -> Option<DynamicValue> {
+    let value = if P::SCOPE == Scope::Group {
+        let group_id = store.speaker_to_group.get(speaker_id)?;
+        store.get_group::<P>(group_id)?
+    } else {
+        store.get_resolved::<P>(speaker_id)?
+    };
+    Some(value.to_dynamic())
+}
+
+fn set_dynamic<P: DynamicProperty>(
+    manager: &StateManager,
+    speaker_id: &SpeakerId,
+    value: &DynamicValue,
+) -> Result<()> {
+    if !P::SCHEMA.writable {
+        return Err(StateError::ReadOnlyProperty(P::KEY));
+    }
+    let invalid = |reason: String| StateError::InvalidValue {
+        key: P::KEY,
+        reason,
+    };
+    P::SCHEMA.value_kind.check(value).map_err(invalid)?;
+    let value = P::from_dynamic(value).ok_or_else(|| invalid(format!("{value} is unsupported")))?;
+
+    if P::SCOPE == Scope::Group {
+        let group = manager
+            .get_group_for_speaker(speaker_id)
+            .ok_or_else(|| invalid("speaker is not in a known group".to_string()))?;
+        manager.set_group_property(&group.id, value);
+    } else {
+        manager.set_property(speaker_id, value);
+    }
+    Ok(())
+}
+
+/// One change seen by a [`DynamicWatcher`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicUpdate {
+    pub speaker_id: SpeakerId,
+    pub property_key: &'static str,
+    /// Value when the update was taken from the watcher, like the typed
+    /// `get_property()` after an `iter()` event; `None` if unset
+    pub value: Option<DynamicValue>,
+    pub origin: ChangeOrigin,
+    /// When the change occurred, as in [`ChangeEvent::timestamp`]
+    pub timestamp: Instant,
+}
+
+/// What [`DynamicWatcher::wait_or_status()`] returns
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchStatus {
+    /// The property changed, or the watcher was re-bound to its returning
+    /// speaker ([`ChangeOrigin::Initial`])
+    Changed(DynamicUpdate),
+    /// The speaker was removed; the watcher stays bound to its ID and
+    /// resumes if a speaker with that ID is added again
+    SpeakerGone,
+}
+
+/// Message from the change sink to a [`DynamicWatcher`]
+pub(crate) enum Tapped {
+    Change(ChangeEvent),
+    /// The watched speaker was removed
+    Gone,
+}
+
+/// Where the change sink delivers a [`DynamicWatcher`]'s events
+#[derive(Clone)]
+pub(crate) struct WatchTap {
+    speaker_id: SpeakerId,
+    entry: &'static Entry,
+    tx: mpsc::Sender<Tapped>,
+    /// Task awaiting [`DynamicWatcher::wait_or_status_async()`], if any
+    waker: Arc<Mutex<Option<Waker>>>,
+    /// Dead once the watcher is dropped
+    alive: Weak<()>,
+}
+
+impl WatchTap {
+    /// Whether the event changed the watched property, alone or in a batch
+    pub(crate) fn matches(&self, event: &ChangeEvent) -> bool {
+        let key = self.entry.schema.key;
+        event.speaker_id == self.speaker_id
+            && (event.property_key == key || event.batch.contains(&key))
+    }
+
+    /// Deliver the event; `false` once the watcher is gone
+    pub(crate) fn send(&self, event: ChangeEvent) -> bool {
+        self.deliver(Tapped::Change(event))
+    }
+
+    /// Tell the watcher its speaker was removed; `false` once it is gone
+    pub(crate) fn send_gone(&self) -> bool {
+        self.deliver(Tapped::Gone)
+    }
+
+    fn deliver(&self, tapped: Tapped) -> bool {
+        let sent = self.tx.send(tapped).is_ok();
+        self.wake();
+        sent
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = self.waker.lock().take() {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn speaker_id(&self) -> &SpeakerId {
+        &self.speaker_id
+    }
+
+    pub(crate) fn schema(&self) -> PropertySchema {
+        self.entry.schema
+    }
+
+    /// Whether the watcher still exists
+    pub(crate) fn is_alive(&self) -> bool {
+        self.alive.strong_count() > 0
+    }
+}
+
+impl Drop for WatchTap {
+    /// Lets an awaiting watcher see the sink disconnect; spurious for all
+    /// but the last copy, which the watcher tolerates
+    fn drop(&mut self) {
+        self.wake();
+    }
+}
+
+/// Changes to one property of one speaker, as [`DynamicValue`]s
+///
+/// Created by [`StateManager::watch_dynamic()`]. Except for
+/// [`wait_or_status_async()`](Self::wait_or_status_async), methods block
+/// on the calling thread, as [`ChangeIterator`](crate::ChangeIterator)'s
+/// do; the watcher is also a blocking iterator.
+///
+/// The watcher belongs to the speaker ID, not to one registration of the
+/// speaker. While the speaker is removed, [`recv()`](Self::recv) keeps
+/// waiting and [`wait_or_status()`](Self::wait_or_status) reports
+/// [`WatchStatus::SpeakerGone`]. When a speaker with the same ID is added
+/// again, the watch is registered and subscribed anew and the watcher gets a
+/// [`ChangeOrigin::Initial`] update with the fresh state.
+pub struct DynamicWatcher {
+    speaker_id: SpeakerId,
+    entry: &'static Entry,
+    store: Arc<RwLock<StateStore>>,
+    rx: mpsc::Receiver<Tapped>,
+    waker: Arc<Mutex<Option<Waker>>>,
+    _alive: Arc<()>,
+}
+
+impl DynamicWatcher {
+    pub(crate) fn new(
+        speaker_id: SpeakerId,
+        entry: &'static Entry,
+        store: Arc<RwLock<StateStore>>,
+    ) -> (WatchTap, Self) {
+        let (tx, rx) = mpsc::channel();
+        let alive = Arc::new(());
+        let waker = Arc::new(Mutex::new(None));
+        let tap = WatchTap {
+            speaker_id: speaker_id.clone(),
+            entry,
+            tx,
+            waker: Arc::clone(&waker),
+            alive: Arc::downgrade(&alive),
+        };
+        let watcher = Self {
+            speaker_id,
+            entry,
+            store,
+            rx,
+            waker,
+            _alive: alive,
+        };
+        (tap, watcher)
+    }
+
+    pub fn speaker_id(&self) -> &SpeakerId {
+        &self.speaker_id
+    }
+
+    /// Schema of the watched property
+    pub fn schema(&self) -> PropertySchema {
+        self.entry.schema
+    }
+
+    /// Current value, without waiting for a change
+    pub fn current(&self) -> Option<DynamicValue> {
+        (self.entry.get)(&self.store.read(), &self.speaker_id)
+    }
+
+    /// Whether the watched speaker is currently registered
+    pub fn is_present(&self) -> bool {
+        self.store.read().speakers.contains_key(&self.speaker_id)
+    }
+
+    /// Block until the next change
+    ///
+    /// Keeps waiting while the speaker is removed. Returns `None` once the
+    /// state manager is gone.
+    pub fn recv(&self) -> Option<DynamicUpdate> {
+        loop {
+            if let Tapped::Change(event) = self.rx.recv().ok()? {
+                return Some(self.update(event));
+            }
+        }
+    }
+
+    /// Block until the next change or the timeout
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<DynamicUpdate> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if let Tapped::Change(event) = self.rx.recv_timeout(left).ok()? {
+                return Some(self.update(event));
+            }
+        }
+    }
+
+    /// Take the next change if one is waiting
+    pub fn try_recv(&self) -> Option<DynamicUpdate> {
+        loop {
+            if let Tapped::Change(event) = self.rx.try_recv().ok()? {
+                return Some(self.update(event));
+            }
+        }
+    }
+
+    /// Like [`recv()`](Self::recv), but report a removed speaker instead of
+    /// waiting for it to return
+    ///
+    /// Changes sent before the removal come first. While the speaker is
+    /// absent every call returns [`WatchStatus::SpeakerGone`] at once; wait
+    /// for its return with [`recv()`](Self::recv). Returns `None` once the
+    /// state manager is gone.
+    pub fn wait_or_status(&self) -> Option<WatchStatus> {
+        let next = match self.rx.try_recv() {
+            Ok(tapped) => tapped,
+            Err(mpsc::TryRecvError::Disconnected) => return None,
+            Err(mpsc::TryRecvError::Empty) if !self.is_present() => {
+                return Some(WatchStatus::SpeakerGone)
+            }
+            Err(mpsc::TryRecvError::Empty) => self.rx.recv().ok()?,
+        };
+        Some(self.status(next))
+    }
+
+    /// [`wait_or_status()`](Self::wait_or_status) for async code
+    ///
+    /// Waits without blocking a thread, on any executor.
+    pub fn wait_or_status_async(&mut self) -> impl Future<Output = Option<WatchStatus>> + '_ {
+        std::future::poll_fn(move |cx| {
+            let next = match self.rx.try_recv() {
+                Ok(tapped) => tapped,
+                Err(mpsc::TryRecvError::Disconnected) => return Poll::Ready(None),
+                Err(mpsc::TryRecvError::Empty) if !self.is_present() => {
+                    return Poll::Ready(Some(WatchStatus::SpeakerGone))
+                }
+                Err(mpsc::TryRecvError::Empty) => {
+                    *self.waker.lock() = Some(cx.waker().clone());
+                    // Again, so a change sent before the waker was set
+                    // isn't missed
+                    match self.rx.try_recv() {
+                        Ok(tapped) => tapped,
+                        Err(mpsc::TryRecvError::Disconnected) => return Poll::Ready(None),
+                        Err(mpsc::TryRecvError::Empty) => return Poll::Pending,
+                    }
+                }
+            };
+            Poll::Ready(Some(self.status(next)))
+        })
+    }
+
+    fn status(&self, tapped: Tapped) -> WatchStatus {
+        match tapped {
+            Tapped::Change(event) => WatchStatus::Changed(self.update(event)),
+            Tapped::Gone => WatchStatus::SpeakerGone,
+        }
+    }
+
+    fn update(&self, event: ChangeEvent) -> DynamicUpdate {
+        DynamicUpdate {
+            speaker_id: self.speaker_id.clone(),
+            property_key: self.entry.schema.key,
+            value: self.current(),
+            origin: event.origin,
+            timestamp: event.timestamp,
+        }
+    }
+}
+
+impl Iterator for DynamicWatcher {
+    type Item = DynamicUpdate;
+
+    fn next(&mut self) -> Option<DynamicUpdate> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::Property;
+    use std::collections::HashSet;
+
+    fn den() -> sonos_discovery::Device {
+        sonos_discovery::Device {
+            id: "RINCON_DEN".to_string(),
+            name: "Den".to_string(),
+            room_name: "Den".to_string(),
+            ip_address: "192.168.1.100".to_string(),
+            port: 1400,
+            model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
+        }
+    }
+
+    fn manager_with_speaker() -> (StateManager, SpeakerId) {
+        let manager = StateManager::new().unwrap();
+        manager.add_devices(vec![den()]).unwrap();
+        (manager, SpeakerId::new("RINCON_DEN"))
+    }
+
+    #[test]
+    fn test_registry_lists_schemas() {
+        let manager = StateManager::new().unwrap();
+        let schemas = manager.property_schemas();
+        let keys: HashSet<_> = schemas.iter().map(|s| s.key).collect();
+        assert_eq!(keys.len(), schemas.len(), "keys are unique");
+        assert!(keys.contains(CurrentTrack::KEY));
+
+        let volume = manager.property_schema(Volume::KEY).unwrap();
+        assert_eq!(volume.value_kind, ValueKind::Int { min: 0, max: 100 });
+        assert_eq!(volume.source_service, Service::RenderingControl);
+        assert!(volume.writable);
+        assert!(
+            !manager
+                .property_schema(PlaybackState::KEY)
+                .unwrap()
+                .writable
+        );
+    }
+
+    #[test]
+    fn test_dynamic_round_trip() {
+        let (manager, den) = manager_with_speaker();
+        assert_eq!(manager.get_property_dynamic(&den, Volume::KEY), None);
+
+        manager
+            .set_property_dynamic(&den, Volume::KEY, DynamicValue::Int(30))
+            .unwrap();
+        manager
+            .set_property_dynamic(&den, Mute::KEY, DynamicValue::Bool(true))
+            .unwrap();
+
+        assert_eq!(manager.get_property::<Volume>(&den), Some(Volume(30)));
+        assert_eq!(
+            manager.get_property_dynamic(&den, Volume::KEY),
+            Some(DynamicValue::Int(30))
+        );
+        assert_eq!(
+            manager.get_property_dynamic(&den, Mute::KEY),
+            Some(DynamicValue::Bool(true))
+        );
+    }
+
+    #[test]
+    fn test_dynamic_set_enforces_schema() {
+        let (manager, den) = manager_with_speaker();
+        let set = |key: &str, value| manager.set_property_dynamic(&den, key, value);
+
+        assert!(matches!(
+            set(Volume::KEY, DynamicValue::Int(101)),
+            Err(StateError::InvalidValue { key: "volume", .. })
+        ));
+        assert!(matches!(
+            set(Volume::KEY, DynamicValue::Bool(true)),
+            Err(StateError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            set(SurroundMode::KEY, DynamicValue::String("Loud".to_string())),
+            Err(StateError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            set(
+                PlaybackState::KEY,
+                DynamicValue::String("Playing".to_string())
+            ),
+            Err(StateError::ReadOnlyProperty("playback_state"))
+        ));
+        assert!(matches!(
+            set("nope", DynamicValue::Int(1)),
+            Err(StateError::UnknownProperty { .. })
+        ));
+        assert!(matches!(
+            manager.set_property_dynamic(
+                &SpeakerId::new("RINCON_X"),
+                "mute",
+                DynamicValue::Bool(true)
+            ),
+            Err(StateError::SpeakerNotFound(_))
+        ));
+        assert_eq!(manager.get_property::<Volume>(&den), None);
+    }
+
+    #[test]
+    fn test_watch_dynamic_reports_values() {
+        let (manager, den) = manager_with_speaker();
+        manager.set_property(&den, Mute(false));
+
+        let volume = manager.watch_dynamic(&den, Volume::KEY).unwrap();
+        let mute = manager.watch_dynamic(&den, Mute::KEY).unwrap();
+        let position = manager.watch_dynamic(&den, Position::KEY).unwrap();
+        let timeout = Duration::from_millis(100);
+
+        let initial = volume.recv_timeout(timeout).unwrap();
+        assert_eq!(initial.origin, ChangeOrigin::Initial);
+        assert_eq!(initial.value, None);
+        let initial = mute.recv_timeout(timeout).unwrap();
+        assert_eq!(initial.value, Some(DynamicValue::Bool(false)));
+        assert!(position.recv_timeout(timeout).unwrap().value.is_none());
+
+        manager.set_property(&den, Volume(25));
+        manager.set_property(&den, Mute(true));
+        manager.set_property(
+            &den,
+            Position {
+                position_ms: 1_000,
+                duration_ms: 180_000,
+            },
+        );
+
+        let update = volume.recv_timeout(timeout).unwrap();
+        assert_eq!(update.property_key, Volume::KEY);
+        assert_eq!(update.value, Some(DynamicValue::Int(25)));
+        assert_eq!(
+            mute.recv_timeout(timeout).unwrap().value,
+            Some(DynamicValue::Bool(true))
+        );
+        assert_eq!(
+            position.recv_timeout(timeout).unwrap().value,
+            Some(DynamicValue::Struct(vec![
+                ("position_ms", DynamicValue::Int(1_000)),
+                ("duration_ms", DynamicValue::Int(180_000)),
+            ]))
+        );
+        assert!(volume.try_recv().is_none(), "one update per change");
+        assert_eq!(volume.schema().key, Volume::KEY);
+    }
+
+    #[test]
+    fn test_watch_dynamic_survives_removal() {
+        let (manager, den_id) = manager_with_speaker();
+        let timeout = Duration::from_millis(100);
+        let volume = manager.watch_dynamic(&den_id, Volume::KEY).unwrap();
+        assert!(volume.recv_timeout(timeout).is_some());
+        manager.set_property(&den_id, Volume(30));
+
+        assert!(manager.remove_speaker(&den_id));
+        assert!(!volume.is_present());
+        // The change sent before the removal comes first
+        let Some(WatchStatus::Changed(update)) = volume.wait_or_status() else {
+            panic!("change lost");
+        };
+        assert_eq!(update.value, None, "read after the removal");
+        assert_eq!(volume.wait_or_status(), Some(WatchStatus::SpeakerGone));
+        assert_eq!(volume.wait_or_status(), Some(WatchStatus::SpeakerGone));
+        assert!(volume.recv_timeout(timeout).is_none());
+
+        manager.add_devices(vec![den()]).unwrap();
+        assert!(volume.is_present());
+        let Some(WatchStatus::Changed(rebound)) = volume.wait_or_status() else {
+            panic!("no re-bind notification");
+        };
+        assert_eq!(rebound.origin, ChangeOrigin::Initial);
+        assert_eq!(rebound.value, None, "fresh state");
+        assert!(volume.try_recv().is_none());
+
+        manager.set_property(&den_id, Volume(12));
+        assert_eq!(
+            volume.recv_timeout(timeout).unwrap().value,
+            Some(DynamicValue::Int(12))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_or_status_async_wakes_on_change() {
+        let (manager, den_id) = manager_with_speaker();
+        let mut volume = manager.watch_dynamic(&den_id, Volume::KEY).unwrap();
+        assert!(volume.try_recv().is_some());
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            manager.set_property(&den_id, Volume(40));
+            manager
+        });
+        let Some(WatchStatus::Changed(update)) = volume.wait_or_status_async().await else {
+            panic!("no change");
+        };
+        assert_eq!(update.value, Some(DynamicValue::Int(40)));
+
+        let manager = writer.join().unwrap();
+        assert!(manager.remove_speaker(&SpeakerId::new("RINCON_DEN")));
+        assert_eq!(
+            volume.wait_or_status_async().await,
+            Some(WatchStatus::SpeakerGone)
+        );
+    }
+
+    #[test]
+    fn test_watch_dynamic_unknown_key() {
+        let (manager, den) = manager_with_speaker();
+        let Err(err) = manager.watch_dynamic(&den, "loudness_level") else {
+            panic!("unknown key accepted");
+        };
+        match &err {
+            StateError::UnknownProperty { key, valid_keys } => {
+                assert_eq!(key, "loudness_level");
+                assert_eq!(valid_keys.len(), manager.property_schemas().len());
+                assert!(valid_keys.contains(&Volume::KEY));
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        assert!(err.to_string().contains("volume"));
+        assert!(matches!(
+            manager.watch_dynamic(&SpeakerId::new("RINCON_X"), Volume::KEY),
+            Err(StateError::SpeakerNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_watch_dynamic_matches_typed_watch() {
+        let (manager, den) = manager_with_speaker();
+        let iter = manager.iter();
+        let timeout = Duration::from_millis(100);
+
+        manager
+            .watch_property_with_subscription::<Volume>(&den)
+            .unwrap();
+        let typed_initial = iter.recv_timeout(timeout).unwrap();
+        assert_eq!(typed_initial.origin, ChangeOrigin::Initial);
+
+        // Already watched: the dynamic watcher still gets its initial
+        // update, and iter() doesn't get a second one
+        let dynamic = manager.watch_dynamic(&den, Volume::KEY).unwrap();
+        assert_eq!(
+            dynamic.recv_timeout(timeout).unwrap().origin,
+            ChangeOrigin::Initial
+        );
+        assert!(iter.try_recv().is_none());
+
+        manager.set_property(&den, Volume(10));
+        let typed = iter.recv_timeout(timeout).unwrap();
+        let update = dynamic.recv_timeout(timeout).unwrap();
+        assert_eq!(update.timestamp, typed.timestamp);
+        assert_eq!(update.origin, typed.origin);
+
+        // No change, no notification on either
+        manager.set_property(&den, Volume(10));
+        assert!(iter.try_recv().is_none());
+        assert!(dynamic.try_recv().is_none());
+
+        // Other properties and speakers don't reach the watcher
+        manager.set_property(&den, Mute(true));
+        assert!(dynamic.try_recv().is_none());
+
+        drop(dynamic);
+        manager.set_property(&den, Volume(11));
+        assert!(iter.recv_timeout(timeout).is_some());
+    }
+}
@@ -0,0 +1,877 @@
+//! Synthetic events for developing without speakers
+//!
+//! A [`SimulatedChange`] describes something a speaker could report — a
+//! volume step, a new track, a group forming — and turns into the
+//! [`EnrichedEvent`] a real device would send for it.
+//! [`StateManager::simulate()`] feeds that event through the same decoding
+//! as live events, so derived properties, group propagation and change
+//! origins behave as they do against hardware. Only the speakers must be
+//! known to the manager, e.g. added with
+//! [`add_devices()`](StateManager::add_devices).
+//!
+//! A [`Journal`] strings events together with their timing and plays them
+//! back at any speed, from code or from an NDJSON file.
+//!
+//! This is for app development and tests; the `mock` module of `sonos-api`
+//! covers protocol-level testing.
+//!
+//! ```rust,ignore
+//! use sonos_state::simulation::{SimulatedChange, SimulatedTrack};
+//!
+//! let track = SimulatedTrack::new("Blue in Green").artist("Miles Davis");
+//! for change in SimulatedChange::track_change(&speaker_id, track) {
+//!     manager.simulate(&change)?;
+//! }
+//! for change in SimulatedChange::volume_sweep(&speaker_id, 10, 40) {
+//!     manager.simulate(&change)?;
+//! }
+//! ```
+
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sonos_api::operation::xml_escape;
+use sonos_api::services::av_transport::AVTransportState;
+use sonos_api::services::device_properties::DevicePropertiesState;
+use sonos_api::services::rendering_control::RenderingControlState;
+use sonos_api::Service;
+use sonos_stream::events::{
+    EnrichedEvent, EventData, EventSource, SatelliteInfo, ZoneGroupInfo, ZoneGroupMemberInfo,
+    ZoneGroupTopologyState,
+};
+use sonos_stream::registry::RegistrationId;
+
+use crate::model::{SpeakerId, SpeakerInfo};
+use crate::property::PlaybackState;
+use crate::state::StateManager;
+use crate::{Result, StateError};
+
+/// Subscription ID carried by simulated events
+pub const SIMULATED_SUBSCRIPTION_ID: &str = "uuid:simulated";
+
+/// A track to report as playing
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedTrack {
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_art_uri: Option<String>,
+    pub uri: String,
+    pub duration: Duration,
+}
+
+impl SimulatedTrack {
+    /// A three-minute track with only a title
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            artist: None,
+            album: None,
+            album_art_uri: None,
+            uri: "x-file-cifs://simulated/track.flac".to_string(),
+            duration: Duration::from_secs(180),
+        }
+    }
+
+    pub fn artist(mut self, artist: impl Into<String>) -> Self {
+        self.artist = Some(artist.into());
+        self
+    }
+
+    pub fn album(mut self, album: impl Into<String>) -> Self {
+        self.album = Some(album.into());
+        self
+    }
+
+    pub fn album_art_uri(mut self, uri: impl Into<String>) -> Self {
+        self.album_art_uri = Some(uri.into());
+        self
+    }
+
+    pub fn uri(mut self, uri: impl Into<String>) -> Self {
+        self.uri = uri.into();
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// DIDL-Lite metadata as Sonos sends it in `CurrentTrackMetaData`
+    fn didl(&self) -> String {
+        let mut item = format!(
+            "<upnp:class>object.item.audioItem.musicTrack</upnp:class><dc:title>{}</dc:title>",
+            xml_escape(&self.title)
+        );
+        if let Some(artist) = &self.artist {
+            item.push_str(&format!("<dc:creator>{}</dc:creator>", xml_escape(artist)));
+        }
+        if let Some(album) = &self.album {
+            item.push_str(&format!("<upnp:album>{}</upnp:album>", xml_escape(album)));
+        }
+        if let Some(art) = &self.album_art_uri {
+            item.push_str(&format!(
+                "<upnp:albumArtURI>{}</upnp:albumArtURI>",
+                xml_escape(art)
+            ));
+        }
+        format!(
+            r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="-1" parentID="-1" restricted="true">{item}</item></DIDL-Lite>"#
+        )
+    }
+}
+
+/// Something a speaker can report, as an event
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulatedChange {
+    /// Master volume, from RenderingControl
+    Volume { speaker: SpeakerId, volume: u8 },
+    /// Master mute, from RenderingControl
+    Mute { speaker: SpeakerId, muted: bool },
+    /// Transport state, from AVTransport
+    Playback {
+        speaker: SpeakerId,
+        state: PlaybackState,
+    },
+    /// A track starting to play, from AVTransport
+    Track {
+        speaker: SpeakerId,
+        track: SimulatedTrack,
+    },
+    /// `members` joining `coordinator`'s group, from ZoneGroupTopology
+    ///
+    /// Speakers leaving their groups leave the rest of those groups in
+    /// place, except where the coordinator left: its former members become
+    /// standalone.
+    Group {
+        coordinator: SpeakerId,
+        members: Vec<SpeakerId>,
+    },
+    /// The room being renamed, from DeviceProperties
+    RoomName { speaker: SpeakerId, name: String },
+}
+
+impl SimulatedChange {
+    /// One volume step at a time from `from` to `to`, both included
+    pub fn volume_sweep(speaker: &SpeakerId, from: u8, to: u8) -> Vec<Self> {
+        let steps: Vec<u8> = if from <= to {
+            (from..=to).collect()
+        } else {
+            (to..=from).rev().collect()
+        };
+        steps
+            .into_iter()
+            .map(|volume| Self::Volume {
+                speaker: speaker.clone(),
+                volume,
+            })
+            .collect()
+    }
+
+    /// The transport briefly transitioning, then `track` playing
+    pub fn track_change(speaker: &SpeakerId, track: SimulatedTrack) -> Vec<Self> {
+        vec![
+            Self::Playback {
+                speaker: speaker.clone(),
+                state: PlaybackState::Transitioning,
+            },
+            Self::Track {
+                speaker: speaker.clone(),
+                track,
+            },
+        ]
+    }
+
+    /// `members` joining `coordinator`'s group
+    pub fn group_formation(
+        coordinator: &SpeakerId,
+        members: impl IntoIterator<Item = SpeakerId>,
+    ) -> Self {
+        Self::Group {
+            coordinator: coordinator.clone(),
+            members: members.into_iter().collect(),
+        }
+    }
+
+    /// The event a device would send for this change
+    ///
+    /// AVTransport changes come from the speaker's group coordinator, as
+    /// they do on a real system. Fails with
+    /// [`SpeakerNotFound`](StateError::SpeakerNotFound) if a speaker isn't
+    /// known to `manager`.
+    pub fn to_event(&self, manager: &StateManager) -> Result<EnrichedEvent> {
+        match self {
+            Self::Volume { speaker, volume } => {
+                let state = RenderingControlState {
+                    master_volume: Some(volume.to_string()),
+                    ..Default::default()
+                };
+                let addr = speaker_info(manager, speaker)?.socket_addr();
+                Ok(event(
+                    addr,
+                    Service::RenderingControl,
+                    EventData::RenderingControl(state),
+                ))
+            }
+            Self::Mute { speaker, muted } => {
+                let state = RenderingControlState {
+                    master_mute: Some(if *muted { "1" } else { "0" }.to_string()),
+                    ..Default::default()
+                };
+                let addr = speaker_info(manager, speaker)?.socket_addr();
+                Ok(event(
+                    addr,
+                    Service::RenderingControl,
+                    EventData::RenderingControl(state),
+                ))
+            }
+            Self::Playback { speaker, state } => {
+                let transport_state = match state {
+                    PlaybackState::Playing => "PLAYING",
+                    PlaybackState::Paused => "PAUSED_PLAYBACK",
+                    PlaybackState::Stopped => "STOPPED",
+                    PlaybackState::Transitioning => "TRANSITIONING",
+                };
+                let mut av = av_transport_state();
+                av.transport_state = Some(transport_state.to_string());
+                av_transport_event(manager, speaker, av)
+            }
+            Self::Track { speaker, track } => {
+                let mut av = av_transport_state();
+                av.transport_state = Some("PLAYING".to_string());
+                av.current_track_uri = Some(track.uri.clone());
+                av.track_duration = Some(format_duration(track.duration));
+                av.track_metadata = Some(track.didl());
+                av_transport_event(manager, speaker, av)
+            }
+            Self::Group {
+                coordinator,
+                members,
+            } => topology_event(manager, coordinator, members),
+            Self::RoomName { speaker, name } => {
+                let state = DevicePropertiesState {
+                    zone_name: Some(name.clone()),
+                    ..Default::default()
+                };
+                let addr = speaker_info(manager, speaker)?.socket_addr();
+                Ok(event(
+                    addr,
+                    Service::DeviceProperties,
+                    EventData::DeviceProperties(state.into()),
+                ))
+            }
+        }
+    }
+}
+
+fn speaker_info(manager: &StateManager, speaker: &SpeakerId) -> Result<SpeakerInfo> {
+    manager
+        .speaker_info(speaker)
+        .ok_or_else(|| StateError::SpeakerNotFound(speaker.clone()))
+}
+
+fn event(addr: std::net::SocketAddr, service: Service, data: EventData) -> EnrichedEvent {
+    EnrichedEvent::new(
+        RegistrationId::new(0),
+        addr,
+        service,
+        EventSource::UPnPNotification {
+            subscription_id: SIMULATED_SUBSCRIPTION_ID.to_string(),
+        },
+        data,
+    )
+}
+
+fn av_transport_event(
+    manager: &StateManager,
+    speaker: &SpeakerId,
+    state: AVTransportState,
+) -> Result<EnrichedEvent> {
+    speaker_info(manager, speaker)?;
+    let coordinator = manager
+        .get_group_for_speaker(speaker)
+        .map(|group| group.coordinator_id)
+        .unwrap_or_else(|| speaker.clone());
+    let addr = speaker_info(manager, &coordinator)?.socket_addr();
+    Ok(event(
+        addr,
+        Service::AVTransport,
+        EventData::AVTransport(state),
+    ))
+}
+
+fn av_transport_state() -> AVTransportState {
+    AVTransportState {
+        transport_state: None,
+        transport_status: None,
+        speed: None,
+        current_track_uri: None,
+        track_duration: None,
+        track_metadata: None,
+        rel_time: None,
+        abs_time: None,
+        rel_count: None,
+        abs_count: None,
+        play_mode: None,
+        next_track_uri: None,
+        next_track_metadata: None,
+        queue_length: None,
+        transport_actions: None,
+    }
+}
+
+/// `H:MM:SS`, as in `CurrentTrackDuration`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// The full topology with `members` moved into `coordinator`'s group
+fn topology_event(
+    manager: &StateManager,
+    coordinator: &SpeakerId,
+    members: &[SpeakerId],
+) -> Result<EnrichedEvent> {
+    let mut grouped = vec![coordinator.clone()];
+    for member in members {
+        if !grouped.contains(member) {
+            grouped.push(member.clone());
+        }
+    }
+    let infos = grouped
+        .iter()
+        .map(|id| speaker_info(manager, id))
+        .collect::<Result<Vec<_>>>()?;
+
+    let satellites = manager.get_satellite_ids();
+    let mut groups = manager.groups();
+    let mut layout: Vec<(String, SpeakerId, Vec<SpeakerId>)> = Vec::new();
+
+    // The new group keeps the coordinator's group ID if it led one
+    let id = groups
+        .iter()
+        .find(|g| g.coordinator_id == *coordinator)
+        .map(|g| g.id.as_str().to_string())
+        .unwrap_or_else(|| format!("{}:0", coordinator.as_str()));
+    layout.push((id, coordinator.clone(), grouped.clone()));
+
+    groups.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    for group in groups {
+        let rest: Vec<SpeakerId> = group
+            .member_ids
+            .iter()
+            .filter(|id| !grouped.contains(id) && !satellites.contains(id))
+            .cloned()
+            .collect();
+        if rest.contains(&group.coordinator_id) {
+            layout.push((
+                group.id.as_str().to_string(),
+                group.coordinator_id.clone(),
+                rest,
+            ));
+        } else {
+            layout.extend(
+                rest.into_iter()
+                    .map(|id| (format!("{}:0", id.as_str()), id.clone(), vec![id])),
+            );
+        }
+    }
+
+    // Speakers in no group yet are standalone
+    let mut speakers = manager.speaker_infos();
+    speakers.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    for speaker in &speakers {
+        let placed = layout.iter().any(|(_, _, m)| m.contains(&speaker.id));
+        if !placed && !satellites.contains(&speaker.id) {
+            layout.push((
+                format!("{}:0", speaker.id.as_str()),
+                speaker.id.clone(),
+                vec![speaker.id.clone()],
+            ));
+        }
+    }
+
+    let zone_groups = layout
+        .into_iter()
+        .map(|(id, coordinator, member_ids)| ZoneGroupInfo {
+            coordinator: coordinator.as_str().to_string(),
+            id,
+            members: member_ids
+                .iter()
+                .filter_map(|id| manager.speaker_info(id))
+                .map(|info| member_info(manager, &info))
+                .collect(),
+        })
+        .collect();
+
+    let data = EventData::ZoneGroupTopology(ZoneGroupTopologyState {
+        zone_groups,
+        vanished_devices: vec![],
+    });
+    Ok(event(
+        infos[0].socket_addr(),
+        Service::ZoneGroupTopology,
+        data,
+    ))
+}
+
+fn location(info: &SpeakerInfo) -> String {
+    format!("http://{}/xml/device_description.xml", info.socket_addr())
+}
+
+fn member_info(manager: &StateManager, info: &SpeakerInfo) -> ZoneGroupMemberInfo {
+    ZoneGroupMemberInfo {
+        uuid: info.id.as_str().to_string(),
+        location: location(info),
+        zone_name: info.room_name.clone(),
+        software_version: info.software_version.clone(),
+        software_generation: info.software_generation,
+        boot_seq: info.boot_seq,
+        network_info: Default::default(),
+        satellites: info
+            .satellites
+            .iter()
+            .filter_map(|id| manager.speaker_info(id))
+            .map(|sat| SatelliteInfo {
+                uuid: sat.id.as_str().to_string(),
+                location: location(&sat),
+                zone_name: sat.room_name.clone(),
+                ht_sat_chan_map_set: String::new(),
+                invisible: "1".to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// An event and when it happened, relative to the start of its journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Milliseconds since the start of the journal
+    pub at_ms: u64,
+    pub event: EnrichedEvent,
+}
+
+/// A timed sequence of events for scripted playback
+///
+/// Stored as NDJSON, one [`JournalEntry`] per line.
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `event`, happening `at` after the start
+    pub fn push(&mut self, at: Duration, event: EnrichedEvent) {
+        self.entries.push(JournalEntry {
+            at_ms: at.as_millis() as u64,
+            event,
+        });
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Read a journal written by [`write_ndjson()`](Self::write_ndjson);
+    /// blank lines are skipped
+    pub fn from_ndjson(reader: impl BufRead) -> Result<Self> {
+        let mut entries = Vec::new();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| StateError::Parse(format!("line {}: {e}", n + 1)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line)
+                .map_err(|e| StateError::Parse(format!("line {}: {e}", n + 1)))?;
+            entries.push(entry);
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn write_ndjson(&self, mut writer: impl Write) -> io::Result<()> {
+        for entry in &self.entries {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()
+    }
+
+    /// Inject every event into `manager` in order, returning how many were
+    /// applied
+    ///
+    /// Waits between events as the journal's timing says, divided by
+    /// `speed`: 2.0 plays twice as fast. A `speed` that is zero, negative
+    /// or not finite plays without waiting. Blocks until done.
+    pub fn play(&self, manager: &StateManager, speed: f64) -> usize {
+        let mut entries: Vec<&JournalEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|entry| entry.at_ms);
+        let mut at_ms = 0;
+        let mut applied = 0;
+        for entry in entries {
+            if speed.is_finite() && speed > 0.0 && entry.at_ms > at_ms {
+                let wait = (entry.at_ms - at_ms) as f64 / speed;
+                thread::sleep(Duration::from_secs_f64(wait / 1000.0));
+            }
+            at_ms = entry.at_ms;
+            if manager.inject_event(&entry.event) {
+                applied += 1;
+            }
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{
+        CurrentTrack, GroupMembership, Mute, Position, Property, Topology, Volume,
+    };
+    use crate::state::ChangeEvent;
+    use sonos_api::services::zone_group_topology::events::parse_zone_group_state_xml;
+
+    const DEN: &str = "RINCON_DEN";
+    const KITCHEN: &str = "RINCON_KITCHEN";
+    const WATCHED: [&str; 8] = [
+        Volume::KEY,
+        Mute::KEY,
+        PlaybackState::KEY,
+        CurrentTrack::KEY,
+        Position::KEY,
+        GroupMembership::KEY,
+        crate::property::GroupComposition::KEY,
+        Topology::KEY,
+    ];
+
+    fn manager() -> StateManager {
+        let manager = StateManager::new().unwrap();
+        let device = |id: &str, name: &str, ip: &str| sonos_discovery::Device {
+            id: id.to_string(),
+            name: name.to_string(),
+            room_name: name.to_string(),
+            ip_address: ip.to_string(),
+            port: 1400,
+            model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
+        };
+        manager
+            .add_devices(vec![
+                device(DEN, "Den", "192.168.1.100"),
+                device(KITCHEN, "Kitchen", "192.168.1.101"),
+            ])
+            .unwrap();
+        for id in [DEN, KITCHEN] {
+            for key in WATCHED {
+                manager.register_watch(&SpeakerId::new(id), key);
+            }
+        }
+        manager.iter().try_iter().for_each(drop);
+        manager
+    }
+
+    fn fixture_event(ip: &str, service: Service, data: EventData) -> EnrichedEvent {
+        event(format!("{ip}:1400").parse().unwrap(), service, data)
+    }
+
+    /// LastChange NOTIFY body around `inner`, as a device sends it
+    fn last_change(namespace: &str, inner: &str) -> String {
+        let event = format!(
+            r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/{namespace}/"><InstanceID val="0">{inner}</InstanceID></Event>"#
+        );
+        format!(
+            r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>{}</LastChange></e:property></e:propertyset>"#,
+            xml_escape(&event)
+        )
+    }
+
+    fn rendering_control_fixture(inner: &str) -> EnrichedEvent {
+        let xml = last_change("RCS", inner);
+        let state = sonos_api::services::rendering_control::RenderingControlEvent::from_xml(&xml)
+            .unwrap()
+            .into_state();
+        fixture_event(
+            "192.168.1.100",
+            Service::RenderingControl,
+            EventData::RenderingControl(state),
+        )
+    }
+
+    fn av_transport_fixture(inner: &str) -> EnrichedEvent {
+        let xml = last_change("AVT", inner);
+        let state = sonos_api::services::av_transport::AVTransportEvent::from_xml(&xml)
+            .unwrap()
+            .into_state();
+        fixture_event(
+            "192.168.1.100",
+            Service::AVTransport,
+            EventData::AVTransport(state),
+        )
+    }
+
+    fn topology_fixture(groups: &str) -> EnrichedEvent {
+        let xml = format!("<ZoneGroupState><ZoneGroups>{groups}</ZoneGroups></ZoneGroupState>");
+        let state = ZoneGroupTopologyState {
+            zone_groups: parse_zone_group_state_xml(&xml).unwrap(),
+            vanished_devices: vec![],
+        };
+        fixture_event(
+            "192.168.1.100",
+            Service::ZoneGroupTopology,
+            EventData::ZoneGroupTopology(state),
+        )
+    }
+
+    fn member(id: &str, ip: &str, name: &str) -> String {
+        format!(
+            r#"<ZoneGroupMember UUID="{id}" Location="http://{ip}:1400/xml/device_description.xml" ZoneName="{name}" BootSeq="0"/>"#
+        )
+    }
+
+    fn changes(manager: &StateManager) -> Vec<(SpeakerId, &'static str, crate::ChangeOrigin)> {
+        manager
+            .iter()
+            .try_iter()
+            .map(|e: ChangeEvent| (e.speaker_id, e.property_key, e.origin))
+            .collect()
+    }
+
+    fn assert_same_state(simulated: &StateManager, real: &StateManager) {
+        for id in [DEN, KITCHEN] {
+            let id = SpeakerId::new(id);
+            assert_eq!(
+                simulated.get_property::<Volume>(&id),
+                real.get_property::<Volume>(&id)
+            );
+            assert_eq!(
+                simulated.get_property::<Mute>(&id),
+                real.get_property::<Mute>(&id)
+            );
+            assert_eq!(
+                simulated.get_property::<PlaybackState>(&id),
+                real.get_property::<PlaybackState>(&id)
+            );
+            assert_eq!(
+                simulated.get_property::<CurrentTrack>(&id),
+                real.get_property::<CurrentTrack>(&id)
+            );
+            assert_eq!(
+                simulated.get_property::<Position>(&id),
+                real.get_property::<Position>(&id)
+            );
+            assert_eq!(
+                simulated.get_group_for_speaker(&id),
+                real.get_group_for_speaker(&id)
+            );
+        }
+    }
+
+    #[test]
+    fn test_volume_and_mute_match_fixture() {
+        let (simulated, real) = (manager(), manager());
+        let den = SpeakerId::new(DEN);
+
+        simulated
+            .simulate(&SimulatedChange::Volume {
+                speaker: den.clone(),
+                volume: 35,
+            })
+            .unwrap();
+        simulated
+            .simulate(&SimulatedChange::Mute {
+                speaker: den.clone(),
+                muted: true,
+            })
+            .unwrap();
+        real.inject_event(&rendering_control_fixture(
+            r#"<Volume channel="Master" val="35"/>"#,
+        ));
+        real.inject_event(&rendering_control_fixture(
+            r#"<Mute channel="Master" val="1"/>"#,
+        ));
+
+        assert_eq!(simulated.get_property::<Volume>(&den), Some(Volume(35)));
+        assert_same_state(&simulated, &real);
+        let events = changes(&simulated);
+        assert!(!events.is_empty());
+        assert_eq!(events, changes(&real));
+    }
+
+    #[test]
+    fn test_volume_sweep_steps_through_every_level() {
+        let den = SpeakerId::new(DEN);
+        let sweep = SimulatedChange::volume_sweep(&den, 12, 9);
+        let volumes: Vec<u8> = sweep
+            .iter()
+            .map(|change| match change {
+                SimulatedChange::Volume { volume, .. } => *volume,
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect();
+        assert_eq!(volumes, [12, 11, 10, 9]);
+
+        let manager = manager();
+        for change in &sweep {
+            assert!(manager.simulate(change).unwrap());
+        }
+        assert_eq!(manager.get_property::<Volume>(&den), Some(Volume(9)));
+    }
+
+    #[test]
+    fn test_track_change_matches_fixture() {
+        let (simulated, real) = (manager(), manager());
+        let den = SpeakerId::new(DEN);
+        let track = SimulatedTrack::new("Tom & Jerry <Live>")
+            .artist("The Cartoons")
+            .album("Saturday Morning")
+            .album_art_uri("/getaa?s=1&u=x")
+            .uri("x-file-cifs://nas/music/track.flac")
+            .duration(Duration::from_secs(210));
+
+        for change in SimulatedChange::track_change(&den, track) {
+            assert!(simulated.simulate(&change).unwrap());
+        }
+
+        let didl = r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="-1" parentID="-1" restricted="true"><upnp:class>object.item.audioItem.musicTrack</upnp:class><dc:title>Tom &amp; Jerry &lt;Live&gt;</dc:title><dc:creator>The Cartoons</dc:creator><upnp:album>Saturday Morning</upnp:album><upnp:albumArtURI>/getaa?s=1&amp;u=x</upnp:albumArtURI></item></DIDL-Lite>"#;
+        real.inject_event(&av_transport_fixture(
+            r#"<TransportState val="TRANSITIONING"/>"#,
+        ));
+        real.inject_event(&av_transport_fixture(&format!(
+            r#"<TransportState val="PLAYING"/><CurrentTrackURI val="x-file-cifs://nas/music/track.flac"/><CurrentTrackDuration val="0:03:30"/><CurrentTrackMetaData val="{}"/>"#,
+            xml_escape(didl)
+        )));
+
+        let current = simulated.get_property::<CurrentTrack>(&den).unwrap();
+        assert_eq!(current.title.as_deref(), Some("Tom & Jerry <Live>"));
+        assert_eq!(current.album_art_uri.as_deref(), Some("/getaa?s=1&u=x"));
+        assert_eq!(
+            simulated.get_property::<PlaybackState>(&den),
+            Some(PlaybackState::Playing)
+        );
+        assert_same_state(&simulated, &real);
+        let events = changes(&simulated);
+        assert!(!events.is_empty());
+        assert_eq!(events, changes(&real));
+    }
+
+    #[test]
+    fn test_group_formation_matches_fixture() {
+        let (simulated, real) = (manager(), manager());
+        let (den, kitchen) = (SpeakerId::new(DEN), SpeakerId::new(KITCHEN));
+        let standalone = topology_fixture(&format!(
+            r#"<ZoneGroup Coordinator="{DEN}" ID="{DEN}:1">{}</ZoneGroup><ZoneGroup Coordinator="{KITCHEN}" ID="{KITCHEN}:1">{}</ZoneGroup>"#,
+            member(DEN, "192.168.1.100", "Den"),
+            member(KITCHEN, "192.168.1.101", "Kitchen"),
+        ));
+        for manager in [&simulated, &real] {
+            assert!(manager.inject_event(&standalone));
+            manager.iter().try_iter().for_each(drop);
+        }
+
+        assert!(simulated
+            .simulate(&SimulatedChange::group_formation(&den, [kitchen.clone()]))
+            .unwrap());
+        real.inject_event(&topology_fixture(&format!(
+            r#"<ZoneGroup Coordinator="{DEN}" ID="{DEN}:1">{}{}</ZoneGroup>"#,
+            member(DEN, "192.168.1.100", "Den"),
+            member(KITCHEN, "192.168.1.101", "Kitchen"),
+        )));
+
+        let group = simulated.get_group_for_speaker(&kitchen).unwrap();
+        assert_eq!(group.coordinator_id, den);
+        assert_eq!(group.member_ids, [den.clone(), kitchen.clone()]);
+        assert_same_state(&simulated, &real);
+        assert_eq!(simulated.groups().len(), real.groups().len());
+
+        // Playback on the member is reported by its coordinator
+        simulated
+            .simulate(&SimulatedChange::Playback {
+                speaker: kitchen.clone(),
+                state: PlaybackState::Paused,
+            })
+            .unwrap();
+        assert_eq!(
+            simulated.get_property::<PlaybackState>(&den),
+            Some(PlaybackState::Paused)
+        );
+    }
+
+    #[test]
+    fn test_room_name_matches_fixture() {
+        let (simulated, real) = (manager(), manager());
+        let den = SpeakerId::new(DEN);
+
+        assert!(simulated
+            .simulate(&SimulatedChange::RoomName {
+                speaker: den.clone(),
+                name: "Study".to_string(),
+            })
+            .unwrap());
+        let xml = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><ZoneName>Study</ZoneName></e:property></e:propertyset>"#;
+        let state = sonos_api::services::device_properties::DevicePropertiesEvent::from_xml(xml)
+            .unwrap()
+            .into_state();
+        assert!(real.inject_event(&fixture_event(
+            "192.168.1.100",
+            Service::DeviceProperties,
+            EventData::DeviceProperties(state.into()),
+        )));
+
+        assert_eq!(simulated.speaker_info(&den).unwrap().room_name, "Study");
+        assert_eq!(simulated.speaker_info(&den), real.speaker_info(&den));
+        assert_eq!(changes(&simulated), changes(&real));
+    }
+
+    #[test]
+    fn test_unknown_speaker_is_an_error() {
+        let manager = manager();
+        let change = SimulatedChange::Volume {
+            speaker: SpeakerId::new("RINCON_NOWHERE"),
+            volume: 10,
+        };
+        assert!(matches!(
+            manager.simulate(&change),
+            Err(StateError::SpeakerNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_journal_round_trips_and_plays() {
+        let source = manager();
+        let den = SpeakerId::new(DEN);
+        let mut journal = Journal::new();
+        for (i, change) in SimulatedChange::volume_sweep(&den, 20, 24)
+            .iter()
+            .enumerate()
+        {
+            let at = Duration::from_millis(i as u64 * 500);
+            journal.push(at, change.to_event(&source).unwrap());
+        }
+
+        let mut ndjson = Vec::new();
+        journal.write_ndjson(&mut ndjson).unwrap();
+        let read = Journal::from_ndjson(ndjson.as_slice()).unwrap();
+        assert_eq!(read.entries().len(), 5);
+        assert_eq!(read.entries()[4].at_ms, 2000);
+
+        // 2 s of journal at 100x takes about 20 ms
+        let manager = manager();
+        let started = std::time::Instant::now();
+        assert_eq!(read.play(&manager, 100.0), 5);
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(manager.get_property::<Volume>(&den), Some(Volume(24)));
+
+        assert!(matches!(
+            Journal::from_ndjson("{not json}\n".as_bytes()),
+            Err(StateError::Parse(_))
+        ));
+    }
+}
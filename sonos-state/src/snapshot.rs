@@ -0,0 +1,454 @@
+//! Point-in-time copies of the store and what changed between two of them
+//!
+//! [`StateManager::snapshot()`] reads every built-in speaker- and
+//! group-scoped property of every speaker through the schema registry, as
+//! [`DynamicValue`]s. [`StateStore::diff()`] compares two snapshots speaker
+//! by speaker: properties added, removed and changed, and speakers that
+//! appeared or went away. Changes confined to a property's
+//! [`volatile_fields`](crate::PropertySchema::volatile_fields) are left out,
+//! as live change detection leaves them out, unless
+//! [`DiffOptions::include_volatile()`] asks for them.
+//!
+//! A [`StoreDiff`] prints as a report grouped by speaker and serializes for
+//! CI artifacts.
+//!
+//! [`StateManager::snapshot()`]: crate::StateManager::snapshot
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::model::SpeakerId;
+use crate::schema::{self, DynamicValue};
+use crate::state::StateStore;
+
+/// Every speaker's property values at one moment
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StoreSnapshot {
+    /// Ordered by speaker ID
+    pub speakers: Vec<SpeakerSnapshot>,
+}
+
+/// One speaker's property values
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpeakerSnapshot {
+    pub id: SpeakerId,
+    /// Room name
+    pub name: String,
+    /// By property key; properties with no value yet are left out
+    pub properties: BTreeMap<&'static str, DynamicValue>,
+}
+
+impl StoreSnapshot {
+    pub(crate) fn capture(store: &StateStore) -> Self {
+        let mut speakers: Vec<SpeakerSnapshot> = store
+            .speakers
+            .values()
+            .map(|speaker| SpeakerSnapshot {
+                id: speaker.id.clone(),
+                name: speaker.room_name.clone(),
+                properties: schema::entries()
+                    .filter_map(|entry| Some((entry.schema.key, (entry.get)(store, &speaker.id)?)))
+                    .collect(),
+            })
+            .collect();
+        speakers.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+        Self { speakers }
+    }
+
+    /// The snapshot of speaker `id`, if it was known
+    pub fn speaker(&self, id: &SpeakerId) -> Option<&SpeakerSnapshot> {
+        self.speakers.iter().find(|s| &s.id == id)
+    }
+}
+
+/// What [`StateStore::diff_with()`] reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    include_volatile: bool,
+}
+
+impl DiffOptions {
+    /// Also report changes confined to volatile fields, marked as such
+    /// (default: left out)
+    pub fn include_volatile(mut self, include: bool) -> Self {
+        self.include_volatile = include;
+        self
+    }
+}
+
+/// Differences between two [`StoreSnapshot`]s
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StoreDiff {
+    /// Speakers with at least one difference, ordered by speaker ID
+    pub speakers: Vec<SpeakerDiff>,
+}
+
+/// Differences on one speaker
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpeakerDiff {
+    pub id: SpeakerId,
+    /// Room name, from the later snapshot when the speaker is in both
+    pub name: String,
+    pub presence: Presence,
+    /// Ordered by property key
+    pub properties: Vec<PropertyDiff>,
+}
+
+/// Whether a speaker is in both snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Presence {
+    /// Only in the later snapshot; every property is [`ValueDiff::Added`]
+    Added,
+    /// Only in the earlier snapshot; every property is [`ValueDiff::Removed`]
+    Removed,
+    Both,
+}
+
+/// One property's difference
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PropertyDiff {
+    pub key: &'static str,
+    #[serde(flatten)]
+    pub change: ValueDiff,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValueDiff {
+    Added {
+        after: DynamicValue,
+    },
+    Removed {
+        before: DynamicValue,
+    },
+    Changed {
+        before: DynamicValue,
+        after: DynamicValue,
+        /// Only volatile fields differ; reported only with
+        /// [`DiffOptions::include_volatile()`]
+        volatile: bool,
+    },
+}
+
+impl StoreDiff {
+    /// Whether the snapshots had no reported differences
+    pub fn is_empty(&self) -> bool {
+        self.speakers.is_empty()
+    }
+
+    /// The differences on speaker `id`, if it had any
+    pub fn speaker(&self, id: &SpeakerId) -> Option<&SpeakerDiff> {
+        self.speakers.iter().find(|s| &s.id == id)
+    }
+}
+
+impl SpeakerDiff {
+    /// The difference in property `key`, if it had one
+    pub fn property(&self, key: &str) -> Option<&ValueDiff> {
+        self.properties
+            .iter()
+            .find(|p| p.key == key)
+            .map(|p| &p.change)
+    }
+}
+
+impl StateStore {
+    /// What changed from `before` to `after`, leaving out changes confined
+    /// to volatile fields
+    pub fn diff(before: &StoreSnapshot, after: &StoreSnapshot) -> StoreDiff {
+        Self::diff_with(before, after, DiffOptions::default())
+    }
+
+    /// Like [`diff()`](Self::diff), with `options`
+    pub fn diff_with(
+        before: &StoreSnapshot,
+        after: &StoreSnapshot,
+        options: DiffOptions,
+    ) -> StoreDiff {
+        let mut ids: Vec<&SpeakerId> = before
+            .speakers
+            .iter()
+            .chain(&after.speakers)
+            .map(|s| &s.id)
+            .collect();
+        ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        ids.dedup();
+
+        let speakers = ids
+            .into_iter()
+            .filter_map(|id| {
+                let (earlier, later) = (before.speaker(id), after.speaker(id));
+                let (name, presence) = match (earlier, later) {
+                    (_, Some(later)) if earlier.is_some() => (&later.name, Presence::Both),
+                    (_, Some(later)) => (&later.name, Presence::Added),
+                    (Some(earlier), None) => (&earlier.name, Presence::Removed),
+                    (None, None) => return None,
+                };
+                let empty = BTreeMap::new();
+                let properties = diff_properties(
+                    earlier.map_or(&empty, |s| &s.properties),
+                    later.map_or(&empty, |s| &s.properties),
+                    options,
+                );
+                (presence != Presence::Both || !properties.is_empty()).then(|| SpeakerDiff {
+                    id: id.clone(),
+                    name: name.clone(),
+                    presence,
+                    properties,
+                })
+            })
+            .collect();
+        StoreDiff { speakers }
+    }
+}
+
+fn diff_properties(
+    before: &BTreeMap<&'static str, DynamicValue>,
+    after: &BTreeMap<&'static str, DynamicValue>,
+    options: DiffOptions,
+) -> Vec<PropertyDiff> {
+    let mut keys: Vec<&'static str> = before.keys().chain(after.keys()).copied().collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|key| {
+            let change = match (before.get(key), after.get(key)) {
+                (None, Some(after)) => ValueDiff::Added {
+                    after: after.clone(),
+                },
+                (Some(before), None) => ValueDiff::Removed {
+                    before: before.clone(),
+                },
+                (Some(before), Some(after)) if before != after => {
+                    let volatile = schema::lookup(key)
+                        .is_some_and(|entry| entry.schema.same_identity(before, after));
+                    if volatile && !options.include_volatile {
+                        return None;
+                    }
+                    ValueDiff::Changed {
+                        before: before.clone(),
+                        after: after.clone(),
+                        volatile,
+                    }
+                }
+                _ => return None,
+            };
+            Some(PropertyDiff { key, change })
+        })
+        .collect()
+}
+
+/// Values as written in a report: bare scalars, quoted strings and
+/// `{field: value}` structs
+struct Rendered<'a>(&'a DynamicValue);
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            DynamicValue::Bool(b) => write!(f, "{b}"),
+            DynamicValue::Int(i) => write!(f, "{i}"),
+            DynamicValue::String(s) => write!(f, "{s:?}"),
+            DynamicValue::Struct(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {}", Rendered(value))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// One block per speaker, a line per property: `+` added, `-` removed,
+/// `~` changed
+impl fmt::Display for StoreDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for speaker in &self.speakers {
+            write!(f, "{} ({})", speaker.name, speaker.id)?;
+            match speaker.presence {
+                Presence::Added => writeln!(f, ", added")?,
+                Presence::Removed => writeln!(f, ", removed")?,
+                Presence::Both => writeln!(f)?,
+            }
+            for property in &speaker.properties {
+                match &property.change {
+                    ValueDiff::Added { after } => {
+                        writeln!(f, "  + {}: {}", property.key, Rendered(after))?
+                    }
+                    ValueDiff::Removed { before } => {
+                        writeln!(f, "  - {}: {}", property.key, Rendered(before))?
+                    }
+                    ValueDiff::Changed {
+                        before,
+                        after,
+                        volatile,
+                    } => {
+                        write!(
+                            f,
+                            "  ~ {}: {} -> {}",
+                            property.key,
+                            Rendered(before),
+                            Rendered(after)
+                        )?;
+                        if *volatile {
+                            write!(f, " (volatile)")?;
+                        }
+                        writeln!(f)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{CurrentTrack, Mute, Property, Volume};
+    use crate::StateManager;
+
+    fn device(id: &str, room: &str, ip: &str) -> sonos_discovery::Device {
+        sonos_discovery::Device {
+            id: id.to_string(),
+            name: room.to_string(),
+            room_name: room.to_string(),
+            ip_address: ip.to_string(),
+            port: 1400,
+            model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
+        }
+    }
+
+    fn track(art: &str) -> CurrentTrack {
+        CurrentTrack {
+            title: Some("Hey Jude".to_string()),
+            artist: Some("The Beatles".to_string()),
+            album: None,
+            album_art_uri: Some(format!("/getaa?s=1&token={art}")),
+            uri: Some("x-sonos-spotify:track1".to_string()),
+        }
+    }
+
+    /// Den's volume changes, its mute goes away, its album art token is
+    /// reissued and Kitchen appears
+    fn snapshots() -> (StoreSnapshot, StoreSnapshot) {
+        let manager = StateManager::new().unwrap();
+        manager
+            .add_devices(vec![device("RINCON_DEN", "Den", "192.168.1.100")])
+            .unwrap();
+        let den = SpeakerId::new("RINCON_DEN");
+        manager.set_property(&den, Volume(20));
+        manager.set_property(&den, Mute(false));
+        manager.set_property(&den, track("a1"));
+        let before = manager.snapshot();
+
+        manager.set_property(&den, Volume(35));
+        manager.set_property(&den, track("b2"));
+        manager
+            .add_devices(vec![device("RINCON_KITCHEN", "Kitchen", "192.168.1.101")])
+            .unwrap();
+        manager.set_property(&SpeakerId::new("RINCON_KITCHEN"), Volume(10));
+        let mut after = manager.snapshot();
+        after.speakers[0].properties.remove(Mute::KEY);
+        (before, after)
+    }
+
+    #[test]
+    fn test_diff_reports_changes_removals_and_new_speakers() {
+        let (before, after) = snapshots();
+        assert_eq!(after.speakers.len(), 2);
+        let diff = StateStore::diff(&before, &after);
+
+        let den = diff.speaker(&SpeakerId::new("RINCON_DEN")).unwrap();
+        assert_eq!(den.presence, Presence::Both);
+        assert_eq!(
+            den.property(Volume::KEY),
+            Some(&ValueDiff::Changed {
+                before: DynamicValue::Int(20),
+                after: DynamicValue::Int(35),
+                volatile: false,
+            })
+        );
+        assert_eq!(
+            den.property(Mute::KEY),
+            Some(&ValueDiff::Removed {
+                before: DynamicValue::Bool(false)
+            })
+        );
+        assert_eq!(den.property(CurrentTrack::KEY), None, "art token only");
+        assert_eq!(den.properties.len(), 2);
+
+        let kitchen = diff.speaker(&SpeakerId::new("RINCON_KITCHEN")).unwrap();
+        assert_eq!(kitchen.presence, Presence::Added);
+        assert_eq!(kitchen.name, "Kitchen");
+        assert_eq!(
+            kitchen.property(Volume::KEY),
+            Some(&ValueDiff::Added {
+                after: DynamicValue::Int(10)
+            })
+        );
+
+        assert!(StateStore::diff(&after, &after).is_empty());
+        let gone = StateStore::diff(&after, &before);
+        assert_eq!(
+            gone.speaker(&SpeakerId::new("RINCON_KITCHEN"))
+                .unwrap()
+                .presence,
+            Presence::Removed
+        );
+    }
+
+    #[test]
+    fn test_volatile_changes_are_opt_in() {
+        let (before, after) = snapshots();
+        let diff = StateStore::diff_with(
+            &before,
+            &after,
+            DiffOptions::default().include_volatile(true),
+        );
+        let den = diff.speaker(&SpeakerId::new("RINCON_DEN")).unwrap();
+        match den.property(CurrentTrack::KEY) {
+            Some(ValueDiff::Changed { volatile, .. }) => assert!(volatile),
+            other => panic!("expected a volatile change, got {other:?}"),
+        }
+        assert_eq!(den.properties.len(), 3);
+
+        let report = diff.to_string();
+        assert!(report.starts_with("Den (RINCON_DEN)\n"), "{report}");
+        assert!(report.contains("  ~ volume: 20 -> 35\n"), "{report}");
+        assert!(report.contains("  - mute: false\n"), "{report}");
+        assert!(report.contains("token=b2\", uri: "), "{report}");
+        assert!(report.contains("(volatile)\n"), "{report}");
+        assert!(report.contains("Kitchen (RINCON_KITCHEN), added\n  + volume: 10\n"));
+    }
+
+    #[test]
+    fn test_diff_serializes_for_ci() {
+        let (before, after) = snapshots();
+        let json = serde_json::to_value(StateStore::diff(&before, &after)).unwrap();
+        assert_eq!(
+            json["speakers"][0],
+            serde_json::json!({
+                "id": "RINCON_DEN",
+                "name": "Den",
+                "presence": "both",
+                "properties": [
+                    {"key": "mute", "kind": "removed", "before": false},
+                    {"key": "volume", "kind": "changed", "before": 20, "after": 35, "volatile": false},
+                ],
+            })
+        );
+        assert_eq!(json["speakers"][1]["presence"], "added");
+    }
+}
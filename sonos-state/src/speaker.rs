@@ -17,8 +17,10 @@ mod tests {
             port: 1400,
             model_name: "Sonos One".to_string(),
             software_version: "1.0".to_string(),
+            software_generation: None,
             boot_seq: 0,
             satellites: vec![],
+            household_id: None,
         }
     }
 
@@ -27,22 +27,49 @@
 
 use std::any::{Any, TypeId};
 use std::collections::{HashMap, HashSet};
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Mutex, OnceLock};
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
+use arc_swap::ArcSwap;
 use parking_lot::RwLock;
 
-use sonos_api::{Service, ServiceScope};
+use sonos_api::clock::{SharedClock, SystemClock};
+use sonos_api::{Service, ServiceScope, SonosClient};
 use sonos_discovery::Device;
-use sonos_event_manager::{SonosEventManager, WatchRegistry};
+use sonos_event_manager::{EventManagerError, SonosEventManager, SuspendPolicy, WatchRegistry};
+use sonos_stream::chaos::ChaosHook;
+use sonos_stream::events::{EnrichedEvent, EventData, EventSource, ZoneGroupTopologyState};
+use sonos_stream::registry::RegistrationId;
 use tracing::info;
 
-use crate::event_worker::spawn_state_event_worker;
+use crate::coordinator::{CoordinatorResolver, DEFAULT_SETTLE_DELAY};
+use crate::event_worker::{
+    apply_room_name, spawn_state_event_worker, Drain, EventPipeline, Routing,
+};
+use crate::history::{ChangeHistory, HistoryEntry, HistoryFilter};
 use crate::iter::ChangeIterator;
+use crate::listening::{ListeningEvents, ListeningRules, ListeningTracker};
+use crate::middleware::{
+    Chain, ChangeMiddleware, InterceptDecision, WriteInterceptor, WriteRequest,
+};
 use crate::model::{GroupId, SpeakerId, SpeakerInfo};
-use crate::property::{GroupInfo, Property, Scope, SonosProperty, Topology};
+use crate::origin::{
+    ChangeOrigin, OriginClassifier, OriginTracker, DEFAULT_COALESCE_WINDOW,
+    DEFAULT_EXPECTATION_WINDOW,
+};
+use crate::persistence::{
+    self, PersistedChange, PersistenceConfig, PersistenceHandle, PersistenceSink,
+};
+use crate::property::{
+    GroupComposition, GroupInfo, GroupList, PlaybackState, Property, Scope, SonosProperty, Topology,
+};
+use crate::schema::{self, DynamicValue, DynamicWatcher, PropertySchema, WatchTap};
+use crate::simulation::SimulatedChange;
+use crate::snapshot::StoreSnapshot;
+use crate::transition::{TransitionMonitor, DEFAULT_TRANSITION_TIMEOUT};
 use crate::{Result, StateError};
 
 /// Closure type for lazy event manager initialization.
@@ -67,19 +94,179 @@ pub struct ChangeEvent {
     pub property_key: &'static str,
     /// Service the property belongs to
     pub service: Service,
-    /// When the change occurred
+    /// When the change occurred (the latest step, if coalesced)
     pub timestamp: Instant,
+    /// Who caused the change
+    pub origin: ChangeOrigin,
+    /// Number of changes merged into this event; above 1 only for bursts
+    /// of external volume steps and batches
+    pub coalesced: u32,
+    /// Keys stored together, for [`BATCH_KEY`](Self::BATCH_KEY) events;
+    /// empty otherwise
+    pub batch: Vec<&'static str>,
 }
 
 impl ChangeEvent {
+    /// Property key of the system-wide event emitted after a full state
+    /// refresh (see [`StateManager::resume()`])
+    pub const FULL_REFRESH_KEY: &'static str = "full_refresh";
+
+    /// Property key of an event for several properties of one speaker
+    /// written together (see [`StateManager::apply_local_writes()`])
+    pub const BATCH_KEY: &'static str = "batch";
+
+    /// Property key of the diagnostic event emitted when a local
+    /// `Transitioning` write went unconfirmed and its verification fetch
+    /// failed (see [`StateManagerBuilder::transition_timeout()`])
+    pub const TRANSITION_TIMEOUT_KEY: &'static str = "transition_timeout";
+
     pub fn new(speaker_id: SpeakerId, property_key: &'static str, service: Service) -> Self {
         Self {
             speaker_id,
             property_key,
             service,
             timestamp: Instant::now(),
+            origin: ChangeOrigin::Unknown,
+            coalesced: 1,
+            batch: Vec::new(),
+        }
+    }
+
+    /// Event for `keys` of `speaker_id` changing together
+    pub fn batch(speaker_id: SpeakerId, keys: Vec<&'static str>, service: Service) -> Self {
+        Self {
+            coalesced: keys.len() as u32,
+            batch: keys,
+            ..Self::new(speaker_id, Self::BATCH_KEY, service)
         }
     }
+
+    /// Set the event's origin
+    pub fn with_origin(mut self, origin: ChangeOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// The ID system-scoped properties are watched and reported under: not
+    /// tied to a speaker, so empty
+    pub fn system_id() -> SpeakerId {
+        SpeakerId::new("")
+    }
+
+    /// Event telling consumers that any value may have changed.
+    ///
+    /// Not tied to a speaker: `speaker_id` is empty.
+    pub fn full_refresh() -> Self {
+        Self::new(
+            Self::system_id(),
+            Self::FULL_REFRESH_KEY,
+            Service::ZoneGroupTopology,
+        )
+    }
+
+    /// How much of a UI this event invalidates
+    pub fn rerender_scope(&self) -> RerenderScope {
+        if self.property_key == Self::FULL_REFRESH_KEY {
+            RerenderScope::Full
+        } else if self.property_key == Self::BATCH_KEY {
+            RerenderScope::Speaker
+        } else {
+            RerenderScope::Property
+        }
+    }
+}
+
+/// What a consumer should redraw for a [`ChangeEvent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RerenderScope {
+    /// Only `property_key` on `speaker_id` changed
+    Property,
+    /// The properties in `batch` on `speaker_id` changed
+    Speaker,
+    /// All state was refreshed; redraw everything
+    Full,
+}
+
+/// Callback notified of every change event sent to `iter()`.
+///
+/// Runs synchronously on the thread that produced the change (usually the
+/// event worker), so it must return quickly; hand slow work to another thread.
+pub type ChangeObserver = Arc<dyn Fn(&ChangeEvent) + Send + Sync>;
+
+/// Sending half of the change stream.
+///
+/// Passes the event through change middleware, notifies registered
+/// observers and dynamic watchers, then forwards it to `iter()`.
+#[derive(Clone)]
+pub(crate) struct ChangeSink {
+    tx: mpsc::Sender<ChangeEvent>,
+    middleware: Arc<Chain<ChangeMiddleware>>,
+    observers: Arc<RwLock<Vec<ChangeObserver>>>,
+    /// Channels of live [`DynamicWatcher`]s; dropped watchers are pruned on
+    /// the next event they match
+    taps: Arc<RwLock<Vec<WatchTap>>>,
+    /// Drops events while a full refresh is rewriting the store
+    muted: Arc<AtomicBool>,
+    /// Stamps every event sent
+    clock: SharedClock,
+}
+
+impl ChangeSink {
+    pub(crate) fn new(tx: mpsc::Sender<ChangeEvent>) -> Self {
+        Self {
+            tx,
+            middleware: Arc::new(Chain::new()),
+            observers: Arc::new(RwLock::new(Vec::new())),
+            taps: Arc::new(RwLock::new(Vec::new())),
+            muted: Arc::new(AtomicBool::new(false)),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Stamp events with `clock` instead of the system clock
+    pub(crate) fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Current time on the sink's clock
+    pub(crate) fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// Create a sink with its receiving end, for worker tests
+    #[cfg(test)]
+    pub(crate) fn channel() -> (Self, mpsc::Receiver<ChangeEvent>) {
+        let (tx, rx) = mpsc::channel();
+        (Self::new(tx), rx)
+    }
+
+    /// Stamp `event` with the current time and publish it
+    pub(crate) fn send(&self, mut event: ChangeEvent) {
+        event.timestamp = self.clock.now();
+        self.send_stamped(event);
+    }
+
+    /// Publish an event that already carries its timestamp, such as a
+    /// coalesced burst stamped at its latest step
+    pub(crate) fn send_stamped(&self, event: ChangeEvent) {
+        if self.muted.load(Ordering::SeqCst) {
+            return;
+        }
+        let Some(event) = self.middleware.apply(event) else {
+            return;
+        };
+        // Recursive: an observer may emit a change of its own
+        for observer in self.observers.read_recursive().iter() {
+            observer(&event);
+        }
+        if !self.taps.read().is_empty() {
+            self.taps
+                .write()
+                .retain(|tap| !tap.matches(&event) || tap.send(event.clone()));
+        }
+        let _ = self.tx.send(event);
+    }
 }
 
 // ============================================================================
@@ -91,7 +278,7 @@ pub struct StateStore {
     /// Speaker metadata
     pub(crate) speakers: HashMap<SpeakerId, SpeakerInfo>,
     /// IP to speaker ID mapping
-    pub(crate) ip_to_speaker: HashMap<IpAddr, SpeakerId>,
+    pub(crate) addr_to_speaker: HashMap<SocketAddr, SpeakerId>,
     /// Property values: (speaker_id, property_key) -> type-erased value
     pub(crate) speaker_props: HashMap<SpeakerId, PropertyBag>,
     /// Group metadata
@@ -104,33 +291,77 @@ pub struct StateStore {
     pub(crate) speaker_to_group: HashMap<SpeakerId, GroupId>,
     /// Satellite speaker IDs (Invisible="1") from topology
     pub(crate) satellite_ids: HashSet<SpeakerId>,
+    /// [`topology_fingerprint`](crate::decoder::topology_fingerprint) of the
+    /// last topology event applied
+    pub(crate) topology_fingerprint: Option<u64>,
+    /// Bumped each time the group layout changes
+    pub(crate) topology_generation: u64,
+    /// When each speaker last changed groups, for
+    /// [`CoordinatorResolver`](crate::coordinator::CoordinatorResolver)
+    pub(crate) regrouped_at: HashMap<SpeakerId, Instant>,
+    /// Recent changes, when enabled
+    pub(crate) history: ChangeHistory,
 }
 
 impl StateStore {
     pub(crate) fn new() -> Self {
         Self {
             speakers: HashMap::new(),
-            ip_to_speaker: HashMap::new(),
+            addr_to_speaker: HashMap::new(),
             speaker_props: HashMap::new(),
             groups: HashMap::new(),
             group_props: HashMap::new(),
             system_props: PropertyBag::new(),
             speaker_to_group: HashMap::new(),
             satellite_ids: HashSet::new(),
+            topology_fingerprint: None,
+            topology_generation: 0,
+            regrouped_at: HashMap::new(),
+            history: ChangeHistory::new(0),
         }
     }
 
-    pub(crate) fn add_speaker(&mut self, speaker: SpeakerInfo) {
+    /// Register a speaker, creating the entity its properties are stored on
+    ///
+    /// The only way a speaker enters the store: property writes for a
+    /// speaker without an entity are ignored, so a late event cannot bring
+    /// back a removed speaker.
+    pub(crate) fn create_entity(&mut self, speaker: SpeakerInfo) {
         let id = speaker.id.clone();
-        let ip = speaker.ip_address;
-        self.ip_to_speaker.insert(ip, id.clone());
+        self.addr_to_speaker
+            .insert(speaker.socket_addr(), id.clone());
         self.speakers.insert(id.clone(), speaker);
         self.speaker_props
             .entry(id)
             .or_insert_with(PropertyBag::new);
     }
 
-    fn speaker(&self, id: &SpeakerId) -> Option<&SpeakerInfo> {
+    /// Remove a speaker with its properties and address mapping
+    pub(crate) fn remove_entity(&mut self, id: &SpeakerId) -> Option<SpeakerInfo> {
+        let info = self.speakers.remove(id)?;
+        self.addr_to_speaker.retain(|_, speaker| speaker != id);
+        self.speaker_props.remove(id);
+        self.speaker_to_group.remove(id);
+        self.satellite_ids.remove(id);
+        self.regrouped_at.remove(id);
+        Some(info)
+    }
+
+    /// Give a speaker a new room (and friendly) name, returning whether it
+    /// changed
+    pub(crate) fn rename_speaker(&mut self, id: &SpeakerId, room_name: &str) -> bool {
+        let Some(speaker) = self.speakers.get_mut(id) else {
+            return false;
+        };
+        if speaker.room_name == room_name && speaker.name == room_name {
+            return false;
+        }
+        speaker.room_name = room_name.to_string();
+        speaker.name = room_name.to_string();
+        true
+    }
+
+    pub(crate) fn speaker(&self, id: &SpeakerId) -> Option<&SpeakerInfo> {
         self.speakers.get(id)
     }
 
@@ -138,14 +369,34 @@ impl StateStore {
         self.speakers.values().cloned().collect()
     }
 
-    pub(crate) fn add_group(&mut self, group: GroupInfo) {
+    /// Add or replace a group, returning whether its [`GroupComposition`]
+    /// changed
+    pub(crate) fn add_group(&mut self, group: GroupInfo) -> bool {
         let id = group.id.clone();
         // Update speaker_to_group mapping for all members
         for member_id in &group.member_ids {
             self.speaker_to_group.insert(member_id.clone(), id.clone());
         }
+        let composition = GroupComposition::of(&group);
         self.groups.insert(id.clone(), group);
-        self.group_props.entry(id).or_insert_with(PropertyBag::new);
+        self.group_props
+            .entry(id)
+            .or_insert_with(PropertyBag::new)
+            .set(composition)
+    }
+
+    /// Rebuild the system-scoped [`GroupList`] and [`Topology`] from the
+    /// current speakers and groups, returning which of them changed
+    pub(crate) fn refresh_topology_properties(&mut self) -> (bool, bool) {
+        let group_list = GroupList::new(self.groups.keys().cloned().collect());
+        let mut speakers = self.speakers();
+        speakers.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+        let mut groups: Vec<GroupInfo> = self.groups.values().cloned().collect();
+        groups.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+        (
+            self.set_system(group_list),
+            self.set_system(Topology::new(speakers, groups)),
+        )
     }
 
     /// Get the group a speaker belongs to
@@ -155,13 +406,13 @@ impl StateStore {
         self.groups.get(group_id)
     }
 
-    /// Clear all groups and speaker_to_group mappings
+    /// Remove a group with its properties and the mappings pointing at it
     ///
-    /// Used when processing topology updates to replace all group data
-    pub(crate) fn clear_groups(&mut self) {
-        self.groups.clear();
-        self.group_props.clear();
-        self.speaker_to_group.clear();
+    /// Used when a topology update drops or reshapes the group.
+    pub(crate) fn remove_group(&mut self, group_id: &GroupId) {
+        self.groups.remove(group_id);
+        self.group_props.remove(group_id);
+        self.speaker_to_group.retain(|_, id| id != group_id);
     }
 
     /// Resolve the coordinator speaker for the given speaker.
@@ -194,17 +445,20 @@ impl StateStore {
         }
     }
 
-    #[cfg_attr(not(test), allow(dead_code))]
     pub(crate) fn get<P: Property>(&self, speaker_id: &SpeakerId) -> Option<P> {
         self.speaker_props.get(speaker_id)?.get::<P>()
     }
 
+    /// Store a speaker property; `false` if unchanged or the speaker has
+    /// no entity (see [`create_entity()`](Self::create_entity))
     pub(crate) fn set<P: Property>(&mut self, speaker_id: &SpeakerId, value: P) -> bool {
-        let bag = self
-            .speaker_props
-            .entry(speaker_id.clone())
-            .or_insert_with(PropertyBag::new);
-        bag.set(value)
+        match self.speaker_props.get_mut(speaker_id) {
+            Some(bag) => bag.set(value),
+            None => {
+                tracing::debug!("Ignoring {} for unregistered {}", P::KEY, speaker_id);
+                false
+            }
+        }
     }
 
     pub(crate) fn get_group<P: Property>(&self, group_id: &GroupId) -> Option<P> {
@@ -219,26 +473,103 @@ impl StateStore {
         bag.set(value)
     }
 
+    /// Set a speaker property, recording the change in history
+    pub(crate) fn set_tracked<P: SonosProperty>(
+        &mut self,
+        speaker_id: &SpeakerId,
+        value: P,
+        origin: ChangeOrigin,
+    ) -> bool {
+        if !self.history.is_enabled() {
+            return self.set(speaker_id, value);
+        }
+        let before = self.get::<P>(speaker_id);
+        let changed = self.set(speaker_id, value.clone());
+        if changed {
+            self.history.record(speaker_id, origin, before, value);
+        }
+        changed
+    }
+
+    /// Set a group property, recording the change in history under the
+    /// group's coordinator
+    pub(crate) fn set_group_tracked<P: SonosProperty>(
+        &mut self,
+        group_id: &GroupId,
+        value: P,
+        origin: ChangeOrigin,
+    ) -> bool {
+        if !self.history.is_enabled() {
+            return self.set_group(group_id, value);
+        }
+        let before = self.get_group::<P>(group_id);
+        let changed = self.set_group(group_id, value.clone());
+        if let (true, Some(group)) = (changed, self.groups.get(group_id)) {
+            let coordinator_id = group.coordinator_id.clone();
+            self.history.record(&coordinator_id, origin, before, value);
+        }
+        changed
+    }
+
+    pub(crate) fn get_system<P: Property>(&self) -> Option<P> {
+        self.system_props.get::<P>()
+    }
+
     fn set_system<P: Property>(&mut self, value: P) -> bool {
         self.system_props.set(value)
     }
 
-    /// Update a speaker's IP address in the store. Returns the old IP if changed.
-    pub(crate) fn update_speaker_ip_address(
+    /// Update a speaker's address in the store. Returns the old address if changed.
+    pub(crate) fn update_speaker_address(
         &mut self,
         speaker_id: &SpeakerId,
-        new_ip: IpAddr,
-    ) -> Option<IpAddr> {
+        new_addr: SocketAddr,
+    ) -> Option<SocketAddr> {
         if let Some(info) = self.speakers.get_mut(speaker_id) {
-            let old_ip = info.ip_address;
-            if old_ip != new_ip {
-                info.ip_address = new_ip;
-                return Some(old_ip);
+            let old_addr = info.socket_addr();
+            if old_addr != new_addr {
+                info.ip_address = new_addr.ip();
+                info.port = new_addr.port();
+                return Some(old_addr);
             }
         }
         None
     }
 
+    /// Record firmware versions and software generations reported by
+    /// topology. Speakers not yet registered are skipped.
+    pub(crate) fn set_software_versions(&mut self, software: Vec<(SpeakerId, String, Option<u8>)>) {
+        for (speaker_id, version, generation) in software {
+            if let Some(info) = self.speakers.get_mut(&speaker_id) {
+                info.software_version = version;
+                if generation.is_some() {
+                    info.software_generation = generation;
+                }
+            }
+        }
+    }
+
+    /// Replace every speaker's bonded satellites with `bonds`
+    /// (primary, satellites). Primaries not yet registered are skipped.
+    pub(crate) fn set_bonds(&mut self, bonds: Vec<(SpeakerId, Vec<SpeakerId>)>) {
+        for info in self.speakers.values_mut() {
+            info.satellites.clear();
+        }
+        for (primary, satellites) in bonds {
+            if let Some(info) = self.speakers.get_mut(&primary) {
+                info.satellites = satellites;
+            }
+        }
+    }
+
+    /// The home-theater primary a satellite is bonded to
+    fn bond_primary(&self, speaker_id: &SpeakerId) -> Option<SpeakerId> {
+        self.speakers
+            .values()
+            .find(|info| info.satellites.contains(speaker_id))
+            .map(|info| info.id.clone())
+    }
+
     fn is_empty(&self) -> bool {
         self.speakers.is_empty()
     }
@@ -292,10 +623,54 @@ impl PropertyBag {
     }
 }
 
+// ============================================================================
+// WriteBatch - several values of one speaker reported as one change
+// ============================================================================
+
+/// Values stored by [`StateManager::apply_local_writes()`] and
+/// [`StateManager::apply_fetched()`]
+pub struct WriteBatch<'a> {
+    store: &'a mut StateStore,
+    origins: &'a OriginTracker,
+    speaker_id: &'a SpeakerId,
+    origin: ChangeOrigin,
+    changed: Vec<(&'static str, Service)>,
+    playback_set: bool,
+}
+
+impl WriteBatch<'_> {
+    /// Store `value`: the result of a write made through the SDK, or a value
+    /// read from the speaker
+    pub fn set<P: SonosProperty>(&mut self, value: P) {
+        if self.origin == ChangeOrigin::LocalWrite {
+            self.origins.expect_write(self.speaker_id, P::KEY);
+        }
+        self.playback_set |= P::KEY == PlaybackState::KEY;
+        if self
+            .store
+            .set_tracked::<P>(self.speaker_id, value, self.origin)
+        {
+            self.changed.push((P::KEY, P::SERVICE));
+        }
+    }
+}
+
 // ============================================================================
 // StateManager - main entry point
 // ============================================================================
 
+/// What [`StateManager::shutdown()`] got through before its deadline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Events received before the pipeline closed and decoded into the store
+    /// during shutdown
+    pub events_drained: usize,
+    /// Events received but still undecoded when the deadline passed
+    pub events_abandoned: usize,
+    /// Whether every persistence sink wrote all it had recorded
+    pub persistence_flushed: bool,
+}
+
 /// Core state manager with sync-first API
 ///
 /// All public methods are synchronous. Background event processing
@@ -308,19 +683,29 @@ pub struct StateManager {
     watched: Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
 
     /// IP to speaker ID mapping (for event worker)
-    ip_to_speaker: Arc<RwLock<HashMap<IpAddr, SpeakerId>>>,
+    ///
+    /// Read for every event and changed only when speakers are added or
+    /// move: a copy-on-write snapshot that readers load without locking
+    addr_to_speaker: Arc<ArcSwap<HashMap<SocketAddr, SpeakerId>>>,
 
     /// Event manager (set-once via OnceLock — enables live events)
     event_manager: OnceLock<Arc<SonosEventManager>>,
 
-    /// Channel for sending change events to iter()
-    event_tx: mpsc::Sender<ChangeEvent>,
+    /// Channel for sending change events to iter() and observers
+    event_tx: ChangeSink,
 
     /// Receiver for iter() - wrapped in `Arc<Mutex>` for cloning
     event_rx: Arc<Mutex<mpsc::Receiver<ChangeEvent>>>,
 
-    /// Background event processor handle (lazily spawned)
-    _worker: Mutex<Option<JoinHandle<()>>>,
+    /// Background event processor handle (lazily spawned), joined by
+    /// [`shutdown()`](Self::shutdown)
+    worker: Arc<Mutex<Option<JoinHandle<()>>>>,
+
+    /// Shutdown progress, shared with the event worker
+    drain: Arc<Drain>,
+
+    /// Registered persistence sinks, flushed on shutdown
+    persistence: Arc<RwLock<Vec<PersistenceHandle>>>,
 
     /// Cleanup timeout for subscriptions
     cleanup_timeout: Duration,
@@ -332,12 +717,55 @@ pub struct StateManager {
     /// Lazy event manager initialization closure (set-once).
     /// Called by watch() to trigger event manager creation on first use.
     event_init: OnceLock<EventInitFn>,
+
+    /// Active suspend policy; the lock serializes suspend/resume
+    suspension: Arc<Mutex<Option<SuspendPolicy>>>,
+
+    /// Attributes event-driven changes to local writes or external sources
+    origins: Arc<OriginTracker>,
+
+    /// Consulted by the SDK before it sends a write
+    write_interceptors: Arc<Chain<WriteInterceptor>>,
+
+    /// Household that [`add_devices()`](Self::add_devices) admits; `None` admits all
+    household: Arc<RwLock<Option<String>>>,
+
+    /// Verifies local `Transitioning` writes no event confirms
+    transitions: Arc<TransitionMonitor>,
+
+    /// Time source of timestamps and timers; see [`StateManagerBuilder::clock()`]
+    clock: SharedClock,
+
+    /// Handling of events from addresses with no registered speaker
+    routing: Arc<Routing>,
+
+    /// Picks the coordinator group commands are sent to
+    coordinators: Arc<CoordinatorResolver>,
+
+    /// Listening session tracker, installed by the first
+    /// [`listening_events()`](Self::listening_events)
+    listening: Arc<OnceLock<Arc<ListeningTracker>>>,
+
+    /// Watches of removed speakers, taken up again if they are re-added
+    dormant: Arc<RwLock<HashMap<SpeakerId, DormantWatches>>>,
+
+    /// Latency injected before decoding by chaos testing
+    chaos: ChaosHook,
 }
 
 // ============================================================================
 // StateWatchRegistry - WatchRegistry impl for SonosEventManager
 // ============================================================================
 
+/// What a removed speaker was watched for; see
+/// [`StateManager::remove_speaker()`]
+struct DormantWatches {
+    /// Address its subscriptions were released from
+    addr: SocketAddr,
+    /// Property keys watched when it was removed
+    keys: Vec<&'static str>,
+}
+
 /// Lightweight WatchRegistry implementation wired into the event manager.
 ///
 /// Separated from StateManager because `mpsc::Sender` is `!Sync`,
@@ -345,7 +773,7 @@ pub struct StateManager {
 /// This struct holds only the Arc-wrapped fields needed for watch management.
 struct StateWatchRegistry {
     watched: Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
-    ip_to_speaker: Arc<RwLock<HashMap<IpAddr, SpeakerId>>>,
+    addr_to_speaker: Arc<ArcSwap<HashMap<SocketAddr, SpeakerId>>>,
     key_to_service: Arc<RwLock<HashMap<&'static str, Service>>>,
 }
 
@@ -355,14 +783,14 @@ impl WatchRegistry for StateWatchRegistry {
         self.key_to_service.write().insert(key, service);
     }
 
-    fn unregister_watches_for_service(&self, ip: IpAddr, service: Service) {
-        // 1. Resolve IP → SpeakerId
-        let speaker_id = match self.ip_to_speaker.read().get(&ip).cloned() {
+    fn unregister_watches_for_service(&self, addr: SocketAddr, service: Service) {
+        // 1. Resolve address → SpeakerId
+        let speaker_id = match self.addr_to_speaker.load().get(&addr).cloned() {
             Some(id) => id,
             None => {
                 tracing::warn!(
-                    "unregister_watches_for_service: no speaker found for IP {}",
-                    ip
+                    "unregister_watches_for_service: no speaker found at {}",
+                    addr
                 );
                 return;
             }
@@ -411,15 +839,32 @@ impl StateManager {
     /// manager.add_devices(devices)?;
     /// ```
     pub fn add_devices(&self, devices: Vec<Device>) -> Result<()> {
+        let household = self.household.read().clone();
         let mut store = self.store.write();
-        let mut ip_map = self.ip_to_speaker.write();
+        let mut addrs = Vec::new();
+        let mut added = Vec::new();
+        let mut result = Ok(());
 
         for device in devices {
+            if let (Some(scope), Some(theirs)) = (&household, &device.household_id) {
+                if scope != theirs {
+                    tracing::debug!(
+                        "Skipping {} from household {} (scoped to {})",
+                        device.id,
+                        theirs,
+                        scope
+                    );
+                    continue;
+                }
+            }
             let speaker_id = SpeakerId::new(&device.id);
-            let ip: IpAddr = device
-                .ip_address
-                .parse()
-                .map_err(|_| StateError::InvalidIpAddress(device.ip_address.clone()))?;
+            if !store.speakers.contains_key(&speaker_id) {
+                added.push(speaker_id.clone());
+            }
+            let Ok(ip) = device.ip_address.parse::<IpAddr>() else {
+                result = Err(StateError::InvalidIpAddress(device.ip_address.clone()));
+                break;
+            };
 
             let friendly_name = if device.room_name.is_empty() || device.room_name == "Unknown" {
                 device.name.clone()
@@ -435,24 +880,37 @@ impl StateManager {
                 port: device.port,
                 model_name: device.model_name.clone(),
                 software_version: "unknown".to_string(),
+                software_generation: None,
                 boot_seq: 0,
                 satellites: vec![],
+                household_id: device.household_id.clone(),
             };
 
-            // Update ip_to_speaker mapping
-            ip_map.insert(ip, speaker_id.clone());
+            // Update addr_to_speaker mapping
+            let addr = info.socket_addr();
+            addrs.push((addr, speaker_id.clone()));
             tracing::debug!(
-                "Added speaker {} at IP {} to ip_to_speaker map",
+                "Added speaker {} at {} to addr_to_speaker map",
                 speaker_id.as_str(),
-                ip
+                addr
             );
 
-            store.add_speaker(info);
+            store.create_entity(info);
         }
 
-        // Also add devices to event manager if present
+        // Publish the new addresses, including those added before a bad one
+        self.addr_to_speaker.rcu(|map| {
+            let mut map = HashMap::clone(map);
+            map.extend(addrs.iter().cloned());
+            map
+        });
         drop(store);
-        drop(ip_map);
+        self.routing
+            .mark_registered(addrs.iter().map(|(addr, _)| *addr));
+        self.pipeline().replay_held();
+        result?;
+
+        // Also add devices to event manager if present
 
         if let Some(em) = self.event_manager.get() {
             let devices_for_em: Vec<_> = self
@@ -465,6 +923,8 @@ impl StateManager {
                     ip_address: info.ip_address.to_string(),
                     port: info.port,
                     model_name: info.model_name.clone(),
+                    household_id: info.household_id.clone(),
+                    secure: false,
                 })
                 .collect();
 
@@ -473,9 +933,98 @@ impl StateManager {
             }
         }
 
+        self.rebind_watchers(&added);
         Ok(())
     }
 
+    /// Watch again the properties of speakers that were removed and have
+    /// now been added back
+    ///
+    /// The holds of every watch still alive (typed watches, their handles,
+    /// [`DynamicWatcher`]s) are moved to the speaker's address through
+    /// [`SonosEventManager::restore_device()`], which subscribes again.
+    /// Every property watched then sends one [`ChangeOrigin::Initial`]
+    /// update for the fresh state.
+    fn rebind_watchers(&self, added: &[SpeakerId]) {
+        let taps: Vec<WatchTap> = {
+            let mut taps = self.event_tx.taps.write();
+            taps.retain(WatchTap::is_alive);
+            taps.iter()
+                .filter(|tap| added.contains(tap.speaker_id()))
+                .cloned()
+                .collect()
+        };
+        for speaker_id in added {
+            let Some(dormant) = self.dormant.write().remove(speaker_id) else {
+                continue;
+            };
+            let mut keys = dormant.keys;
+            // Keys whose holds all went while it was away stay unwatched
+            if let (Some(em), Some(addr)) =
+                (self.event_manager.get(), self.get_speaker_addr(speaker_id))
+            {
+                match em.restore_device(dormant.addr, addr) {
+                    Ok(services) => keys.retain(|key| {
+                        self.watch_service(key)
+                            .is_some_and(|service| services.contains(&service))
+                    }),
+                    Err(e) => tracing::warn!(
+                        "Failed to restore subscriptions of {}: {}",
+                        speaker_id.as_str(),
+                        e
+                    ),
+                }
+            }
+            let bound = taps.iter().filter(|tap| tap.speaker_id() == speaker_id);
+            keys.extend(bound.map(|tap| tap.schema().key));
+            keys.sort_unstable();
+            keys.dedup();
+
+            for key in keys {
+                let Some(service) = self.watch_service(key) else {
+                    continue;
+                };
+                tracing::debug!("Re-binding {} watch of {}", key, speaker_id.as_str());
+                // Already watched again (by a new typed watch, say): notify
+                // the dynamic watchers alone, as watch_dynamic() does
+                if !self.begin_watch(speaker_id, key, service) {
+                    let initial = ChangeEvent::new(speaker_id.clone(), key, service)
+                        .with_origin(ChangeOrigin::Initial);
+                    for tap in taps.iter().filter(|tap| tap.matches(&initial)) {
+                        tap.send(initial.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Service a watched property key is fed by
+    fn watch_service(&self, key: &str) -> Option<Service> {
+        if let Some(service) = self.key_to_service.read().get(key) {
+            return Some(*service);
+        }
+        schema::lookup(key).map(|entry| entry.schema.source_service)
+    }
+
+    /// Limit [`add_devices()`](Self::add_devices) to one household
+    ///
+    /// Devices reporting a different household are skipped; devices without
+    /// a household ID are still admitted. Speakers already in the store are
+    /// kept. `None` removes the limit.
+    pub fn set_household(&self, household: Option<String>) {
+        *self.household.write() = household;
+    }
+
+    /// Household this manager is scoped to, if any
+    pub fn household(&self) -> Option<String> {
+        self.household.read().clone()
+    }
+
+    /// Clock this manager was built with
+    pub fn clock(&self) -> SharedClock {
+        Arc::clone(&self.clock)
+    }
+
     /// Get all speaker info
     pub fn speaker_infos(&self) -> Vec<SpeakerInfo> {
         self.store.read().speakers()
@@ -486,26 +1035,61 @@ impl StateManager {
         self.store.read().speaker(speaker_id).cloned()
     }
 
+    /// Record a speaker's new room name, e.g. after renaming it
+    ///
+    /// Updates its [`SpeakerInfo`] `room_name` and `name` and the
+    /// [`Topology`] property, as a DeviceProperties event with a new
+    /// `ZoneName` does, and emits a [`SpeakerInfo::RENAME_EVENT_KEY`] change
+    /// event. Returns `false` if the speaker is unknown or already has that
+    /// name.
+    pub fn set_room_name(&self, speaker_id: &SpeakerId, room_name: &str) -> bool {
+        apply_room_name(
+            &self.store,
+            &self.watched,
+            &self.event_tx,
+            speaker_id,
+            room_name,
+        )
+    }
+
     /// Get speaker IP by ID
     pub fn get_speaker_ip(&self, speaker_id: &SpeakerId) -> Option<IpAddr> {
         self.store.read().speaker(speaker_id).map(|s| s.ip_address)
     }
 
+    /// Get speaker address (IP and port) by ID
+    pub fn get_speaker_addr(&self, speaker_id: &SpeakerId) -> Option<SocketAddr> {
+        self.store
+            .read()
+            .speaker(speaker_id)
+            .map(|s| s.socket_addr())
+    }
+
     /// Get boot_seq for a speaker (used by GroupManagement AddMember)
     pub fn get_boot_seq(&self, speaker_id: &SpeakerId) -> Option<u32> {
         self.store.read().speaker(speaker_id).map(|s| s.boot_seq)
     }
 
-    /// Update a speaker's IP address in both the store and the reverse map.
+    /// Update a speaker's IP address, keeping its port
     pub fn update_speaker_ip(&self, speaker_id: &SpeakerId, new_ip: IpAddr) {
-        let old_ip = {
+        if let Some(addr) = self.get_speaker_addr(speaker_id) {
+            self.update_speaker_addr(speaker_id, SocketAddr::new(new_ip, addr.port()));
+        }
+    }
+
+    /// Update a speaker's address in both the store and the reverse map.
+    pub fn update_speaker_addr(&self, speaker_id: &SpeakerId, new_addr: SocketAddr) {
+        let old_addr = {
             let mut store = self.store.write();
-            store.update_speaker_ip_address(speaker_id, new_ip)
+            store.update_speaker_address(speaker_id, new_addr)
         };
-        if let Some(old_ip) = old_ip {
-            let mut map = self.ip_to_speaker.write();
-            map.remove(&old_ip);
-            map.insert(new_ip, speaker_id.clone());
+        if let Some(old_addr) = old_addr {
+            self.addr_to_speaker.rcu(|map| {
+                let mut map = HashMap::clone(map);
+                map.remove(&old_addr);
+                map.insert(new_addr, speaker_id.clone());
+                map
+            });
         }
     }
 
@@ -519,6 +1103,36 @@ impl StateManager {
         self.store.write().satellite_ids = ids.into_iter().collect();
     }
 
+    /// Store firmware versions and software generations from topology data.
+    pub fn set_software_versions(&self, software: Vec<(SpeakerId, String, Option<u8>)>) {
+        self.store.write().set_software_versions(software);
+    }
+
+    /// Store boot sequences (speaker, `BootSeq`) from topology data, for
+    /// GroupManagement `AddMember`
+    pub fn set_boot_seqs(&self, boot_seqs: Vec<(SpeakerId, u32)>) {
+        let mut store = self.store.write();
+        for (speaker_id, boot_seq) in boot_seqs {
+            if let Some(speaker) = store.speakers.get_mut(&speaker_id) {
+                speaker.boot_seq = boot_seq;
+            }
+        }
+    }
+
+    /// Store home-theater bonds (primary, satellites) from topology data.
+    ///
+    /// Each primary's [`SpeakerInfo::satellites`] is replaced; speakers not
+    /// listed end up with no satellites.
+    pub fn set_bonds(&self, bonds: Vec<(SpeakerId, Vec<SpeakerId>)>) {
+        self.store.write().set_bonds(bonds);
+    }
+
+    /// Get the home-theater primary `speaker_id` is bonded to, if it is a
+    /// satellite of a known primary.
+    pub fn bond_primary(&self, speaker_id: &SpeakerId) -> Option<SpeakerId> {
+        self.store.read().bond_primary(speaker_id)
+    }
+
     /// Create a blocking iterator over change events
     ///
     /// Only emits events for properties that have been watched.
@@ -551,6 +1165,15 @@ impl StateManager {
         self.store.read().get_group::<P>(group_id)
     }
 
+    /// Get current system property value, such as [`GroupList`] (sync, no
+    /// subscription)
+    ///
+    /// System properties are watched and reported under
+    /// [`ChangeEvent::system_id()`].
+    pub fn get_system_property<P: Property>(&self) -> Option<P> {
+        self.store.read().get_system::<P>()
+    }
+
     /// Set a property value
     ///
     /// Updates the property value in the store and emits a change event
@@ -558,14 +1181,282 @@ impl StateManager {
     pub fn set_property<P: SonosProperty>(&self, speaker_id: &SpeakerId, value: P) {
         let changed = {
             let mut store = self.store.write();
-            store.set::<P>(speaker_id, value)
+            store.set_tracked::<P>(speaker_id, value, ChangeOrigin::Unknown)
         };
+        if P::KEY == PlaybackState::KEY {
+            self.transitions.cancel(speaker_id);
+        }
+
+        if changed {
+            self.maybe_emit_change(speaker_id, P::KEY, P::SERVICE, ChangeOrigin::Unknown);
+        }
+    }
 
+    /// Store the result of a write made through the SDK
+    ///
+    /// Like [`set_property()`](Self::set_property), but the change event is
+    /// marked [`ChangeOrigin::LocalWrite`] and the device's echo of the write
+    /// is not reported as an external change.
+    ///
+    /// A [`PlaybackState::Transitioning`] write is verified if no event
+    /// confirms it in time (see [`StateManagerBuilder::transition_timeout()`]).
+    pub fn apply_local_write<P: SonosProperty>(&self, speaker_id: &SpeakerId, value: P) {
+        self.origins.expect_write(speaker_id, P::KEY);
+        let (changed, playback) = {
+            let mut store = self.store.write();
+            let previous = store.get::<PlaybackState>(speaker_id);
+            let changed = store.set_tracked::<P>(speaker_id, value, ChangeOrigin::LocalWrite);
+            let playback = (P::KEY == PlaybackState::KEY)
+                .then(|| {
+                    store
+                        .get::<PlaybackState>(speaker_id)
+                        .map(|v| (previous, v))
+                })
+                .flatten();
+            (changed, playback)
+        };
+        if let Some((previous, value)) = playback {
+            self.transitions.local_write(speaker_id, previous, &value);
+        }
         if changed {
-            self.maybe_emit_change(speaker_id, P::KEY, P::SERVICE);
+            self.maybe_emit_change(speaker_id, P::KEY, P::SERVICE, ChangeOrigin::LocalWrite);
+        }
+    }
+
+    /// Store the result of a group-level write made through the SDK
+    ///
+    /// The coordinator's echo is treated as in
+    /// [`apply_local_write()`](Self::apply_local_write); the volume and mute
+    /// events members send as they follow the group are marked
+    /// [`ChangeOrigin::GroupOperation`].
+    pub fn apply_local_group_write<P: SonosProperty>(&self, group_id: &GroupId, value: P) {
+        let coordinator_id = {
+            let mut store = self.store.write();
+            let group = store.groups.get(group_id).cloned();
+            if let Some(group) = &group {
+                self.origins.expect_write(&group.coordinator_id, P::KEY);
+                self.origins.expect_group_operation(&group.member_ids);
+            }
+            if !store.set_group_tracked::<P>(group_id, value, ChangeOrigin::LocalWrite) {
+                return;
+            }
+            group.map(|g| g.coordinator_id)
+        };
+
+        if let Some(coordinator_id) = coordinator_id {
+            self.maybe_emit_change(
+                &coordinator_id,
+                P::KEY,
+                P::SERVICE,
+                ChangeOrigin::LocalWrite,
+            );
         }
     }
 
+    /// Store the results of several writes to one speaker made as one
+    /// logical action
+    ///
+    /// Each value is stored as by [`apply_local_write()`](Self::apply_local_write),
+    /// but watchers see a single event: the changed property's own if only
+    /// one watched property changed, otherwise a
+    /// [`ChangeEvent::BATCH_KEY`] event listing them.
+    pub fn apply_local_writes(
+        &self,
+        speaker_id: &SpeakerId,
+        writes: impl FnOnce(&mut WriteBatch<'_>),
+    ) {
+        self.apply_batch(speaker_id, ChangeOrigin::LocalWrite, writes);
+    }
+
+    /// Store several values read from one speaker as one update
+    ///
+    /// Each value is stored as by [`set_property()`](Self::set_property);
+    /// watchers see a single event as with
+    /// [`apply_local_writes()`](Self::apply_local_writes), and none if every
+    /// value matched the store. Returns the keys of the values that changed,
+    /// watched or not.
+    pub fn apply_fetched(
+        &self,
+        speaker_id: &SpeakerId,
+        values: impl FnOnce(&mut WriteBatch<'_>),
+    ) -> Vec<&'static str> {
+        self.apply_batch(speaker_id, ChangeOrigin::Unknown, values)
+    }
+
+    fn apply_batch(
+        &self,
+        speaker_id: &SpeakerId,
+        origin: ChangeOrigin,
+        values: impl FnOnce(&mut WriteBatch<'_>),
+    ) -> Vec<&'static str> {
+        let (changed, playback_set) = {
+            let mut store = self.store.write();
+            let mut batch = WriteBatch {
+                store: &mut store,
+                origins: &self.origins,
+                speaker_id,
+                origin,
+                changed: Vec::new(),
+                playback_set: false,
+            };
+            values(&mut batch);
+            (batch.changed, batch.playback_set)
+        };
+        if playback_set && origin != ChangeOrigin::LocalWrite {
+            self.transitions.cancel(speaker_id);
+        }
+        let keys = changed.iter().map(|(key, _)| *key).collect();
+
+        let watched: Vec<(&'static str, Service)> = {
+            let watched = self.watched.read();
+            changed
+                .into_iter()
+                .filter(|(key, _)| watched.contains(&(speaker_id.clone(), *key)))
+                .collect()
+        };
+        let event = match watched.as_slice() {
+            [] => return keys,
+            [(key, service)] => ChangeEvent::new(speaker_id.clone(), key, *service),
+            [(_, service), ..] => ChangeEvent::batch(
+                speaker_id.clone(),
+                watched.iter().map(|(key, _)| *key).collect(),
+                *service,
+            ),
+        };
+        self.event_tx.send(event.with_origin(origin));
+        keys
+    }
+
+    /// Recorded changes matching `filter`, oldest first
+    ///
+    /// Empty unless enabled with
+    /// [`StateManagerBuilder::history_size()`].
+    pub fn history(&self, filter: &HistoryFilter) -> Vec<HistoryEntry> {
+        self.store.read().history.query(filter)
+    }
+
+    /// Put back the value `P` had before its latest change on `speaker_id`
+    ///
+    /// The value is stored as by [`set_property()`](Self::set_property) and
+    /// the change is reported with [`ChangeOrigin::Restore`]. Calling again
+    /// undoes the change before that. Only the cached state changes: write
+    /// the returned value to the speaker to make it stick. Group properties
+    /// are recorded under the group's coordinator.
+    ///
+    /// Returns `None` if history is disabled or has no earlier value.
+    pub fn undo_last<P: SonosProperty>(&self, speaker_id: &SpeakerId) -> Option<P> {
+        let (value, changed) = {
+            let mut store = self.store.write();
+            let value = store.history.take_undo::<P>(speaker_id)?;
+            let changed = if P::SCOPE == Scope::Group {
+                let group_id = store.speaker_to_group.get(speaker_id).cloned()?;
+                store.set_group_tracked(&group_id, value.clone(), ChangeOrigin::Restore)
+            } else {
+                store.set_tracked(speaker_id, value.clone(), ChangeOrigin::Restore)
+            };
+            (value, changed)
+        };
+
+        if changed {
+            self.maybe_emit_change(speaker_id, P::KEY, P::SERVICE, ChangeOrigin::Restore);
+        }
+        Some(value)
+    }
+
+    /// Schemas of the built-in speaker- and group-scoped properties
+    pub fn property_schemas(&self) -> Vec<PropertySchema> {
+        schema::schemas().copied().collect()
+    }
+
+    /// Schema for the property with `key`, if it is a built-in one
+    pub fn property_schema(&self, key: &str) -> Option<PropertySchema> {
+        schema::lookup(key).map(|e| e.schema)
+    }
+
+    /// Get a property's current value by key, without naming its type
+    ///
+    /// Group-scoped properties are read from the speaker's group. Returns
+    /// `None` for unknown keys and properties with no value yet.
+    pub fn get_property_dynamic(&self, speaker_id: &SpeakerId, key: &str) -> Option<DynamicValue> {
+        (schema::lookup(key)?.get)(&self.store.read(), speaker_id)
+    }
+
+    /// Every speaker's current property values, for comparing later with
+    /// [`StateStore::diff()`]
+    pub fn snapshot(&self) -> StoreSnapshot {
+        StoreSnapshot::capture(&self.store.read())
+    }
+
+    /// Set a property by key, without naming its type
+    ///
+    /// The value must fit the property's [`PropertySchema`]: writes to
+    /// read-only properties, values of the wrong kind and integers out of
+    /// range are rejected before anything is stored. Stores the value as
+    /// [`set_property()`](Self::set_property) does; it is not sent to the
+    /// speaker.
+    pub fn set_property_dynamic(
+        &self,
+        speaker_id: &SpeakerId,
+        key: &str,
+        value: DynamicValue,
+    ) -> Result<()> {
+        let entry = schema::lookup(key).ok_or_else(|| schema::unknown_property(key))?;
+        if self.speaker_info(speaker_id).is_none() {
+            return Err(StateError::SpeakerNotFound(speaker_id.clone()));
+        }
+        (entry.set)(self, speaker_id, &value)
+    }
+
+    /// Watch a property by key, without naming its type
+    ///
+    /// Registers the watch and subscribes as
+    /// [`watch_property_with_subscription()`](Self::watch_property_with_subscription)
+    /// does. The returned watcher hears about the same changes `iter()`
+    /// would, at the same time, each with the value it changed to. Its first
+    /// update is always a [`ChangeOrigin::Initial`] one carrying the current
+    /// value, even when the property was already watched.
+    ///
+    /// Dropping the watcher stops its updates but leaves the property
+    /// watched; release it with [`unwatch_dynamic()`](Self::unwatch_dynamic).
+    pub fn watch_dynamic(&self, speaker_id: &SpeakerId, key: &str) -> Result<DynamicWatcher> {
+        let entry = schema::lookup(key).ok_or_else(|| schema::unknown_property(key))?;
+        if self.speaker_info(speaker_id).is_none() {
+            return Err(StateError::SpeakerNotFound(speaker_id.clone()));
+        }
+        let key = entry.schema.key;
+        let service = entry.schema.source_service;
+
+        // Tap first, so the initial notification can't slip past it
+        let (tap, watcher) =
+            DynamicWatcher::new(speaker_id.clone(), entry, Arc::clone(&self.store));
+        self.event_tx.taps.write().push(tap.clone());
+        if !self.begin_watch(speaker_id, key, service) {
+            tap.send(
+                ChangeEvent::new(speaker_id.clone(), key, service)
+                    .with_origin(ChangeOrigin::Initial),
+            );
+        }
+        self.subscribe_service(speaker_id, service);
+        Ok(watcher)
+    }
+
+    /// Unwatch a property by key and release its subscription
+    pub fn unwatch_dynamic(&self, speaker_id: &SpeakerId, key: &str) -> Result<()> {
+        let entry = schema::lookup(key).ok_or_else(|| schema::unknown_property(key))?;
+        self.unregister_watch(speaker_id, entry.schema.key);
+        self.release_service(speaker_id, entry.schema.source_service);
+        Ok(())
+    }
+
+    /// Install a callback that refines the inferred origin of event-driven
+    /// changes, or remove it with `None`
+    ///
+    /// Without a callback, an event that matches no recent local write is
+    /// [`ChangeOrigin::External`].
+    pub fn set_origin_classifier(&self, classifier: Option<OriginClassifier>) {
+        self.origins.set_classifier(classifier);
+    }
+
     /// Set a group property value
     ///
     /// Updates the group property value in the store and emits a change event
@@ -574,7 +1465,7 @@ impl StateManager {
     pub fn set_group_property<P: SonosProperty>(&self, group_id: &GroupId, value: P) {
         let coordinator_id = {
             let mut store = self.store.write();
-            let changed = store.set_group::<P>(group_id, value);
+            let changed = store.set_group_tracked::<P>(group_id, value, ChangeOrigin::Unknown);
             if !changed {
                 return;
             }
@@ -582,17 +1473,48 @@ impl StateManager {
         };
 
         if let Some(coordinator_id) = coordinator_id {
-            self.maybe_emit_change(&coordinator_id, P::KEY, P::SERVICE);
+            self.maybe_emit_change(&coordinator_id, P::KEY, P::SERVICE, ChangeOrigin::Unknown);
         }
     }
 
-    /// Register a property as watched (called by PropertyHandle::watch)
+    /// Register a property as watched, without an initial notification
     pub fn register_watch(&self, speaker_id: &SpeakerId, property_key: &'static str) {
         self.watched
             .write()
             .insert((speaker_id.clone(), property_key));
     }
 
+    /// Start watching a property, delivering its initial notification
+    ///
+    /// If the property wasn't watched, registers it and sends one
+    /// [`ChangeOrigin::Initial`] event for it before returning, so the
+    /// watcher hears about the current value (read it with `get_property()`;
+    /// it may be unset) and then only about real changes. Returns `false`
+    /// and sends nothing if the property was already watched, including by
+    /// a handle still in its grace period. Concurrent callers race on the
+    /// registration, so exactly one of them delivers the notification.
+    ///
+    /// Unlike [`register_watch()`](Self::register_watch), which registers
+    /// silently, this is what every watch path goes through.
+    pub fn begin_watch(
+        &self,
+        speaker_id: &SpeakerId,
+        property_key: &'static str,
+        service: Service,
+    ) -> bool {
+        let first = self
+            .watched
+            .write()
+            .insert((speaker_id.clone(), property_key));
+        if first {
+            self.event_tx.send(
+                ChangeEvent::new(speaker_id.clone(), property_key, service)
+                    .with_origin(ChangeOrigin::Initial),
+            );
+        }
+        first
+    }
+
     /// Unregister a property watch
     pub fn unregister_watch(&self, speaker_id: &SpeakerId, property_key: &'static str) {
         self.watched
@@ -603,7 +1525,8 @@ impl StateManager {
     /// Watch a property with automatic UPnP subscription (recommended API)
     ///
     /// This is the preferred method for watching properties as it:
-    /// 1. Registers the property for change notifications
+    /// 1. Registers the property for change notifications (see
+    ///    [`begin_watch()`](Self::begin_watch))
     /// 2. Subscribes to the UPnP service via the event manager
     ///
     /// Returns the current cached value if available.
@@ -612,38 +1535,45 @@ impl StateManager {
         speaker_id: &SpeakerId,
     ) -> Result<Option<P>> {
         // Register for change notifications
-        self.register_watch(speaker_id, P::KEY);
+        self.begin_watch(speaker_id, P::KEY, P::SERVICE);
 
         // Subscribe via event manager if available
+        self.subscribe_service(speaker_id, P::SERVICE);
+
+        Ok(self.get_property::<P>(speaker_id))
+    }
+
+    /// Unwatch a property and release UPnP subscription
+    pub fn unwatch_property_with_subscription<P: SonosProperty>(&self, speaker_id: &SpeakerId) {
+        // Unregister from change notifications
+        self.unregister_watch(speaker_id, P::KEY);
+
+        // Release subscription via event manager if available
+        self.release_service(speaker_id, P::SERVICE);
+    }
+
+    fn subscribe_service(&self, speaker_id: &SpeakerId, service: Service) {
         if let Some(em) = self.event_manager.get() {
-            // Get speaker IP from store
-            if let Some(ip) = self.get_speaker_ip(speaker_id) {
-                if let Err(e) = em.ensure_service_subscribed(ip, P::SERVICE) {
+            if let Some(addr) = self.get_speaker_addr(speaker_id) {
+                if let Err(e) = em.ensure_service_subscribed(addr, service) {
                     tracing::warn!(
                         "Failed to subscribe to {:?} for {}: {}",
-                        P::SERVICE,
+                        service,
                         speaker_id.as_str(),
                         e
                     );
                 }
             }
         }
-
-        Ok(self.get_property::<P>(speaker_id))
     }
 
-    /// Unwatch a property and release UPnP subscription
-    pub fn unwatch_property_with_subscription<P: SonosProperty>(&self, speaker_id: &SpeakerId) {
-        // Unregister from change notifications
-        self.unregister_watch(speaker_id, P::KEY);
-
-        // Release subscription via event manager if available
+    fn release_service(&self, speaker_id: &SpeakerId, service: Service) {
         if let Some(em) = self.event_manager.get() {
-            if let Some(ip) = self.get_speaker_ip(speaker_id) {
-                if let Err(e) = em.release_service_subscription(ip, P::SERVICE) {
+            if let Some(addr) = self.get_speaker_addr(speaker_id) {
+                if let Err(e) = em.release_service_subscription(addr, service) {
                     tracing::warn!(
                         "Failed to unsubscribe from {:?} for {}: {}",
-                        P::SERVICE,
+                        service,
                         speaker_id.as_str(),
                         e
                     );
@@ -659,12 +1589,188 @@ impl StateManager {
             .contains(&(speaker_id.clone(), property_key))
     }
 
+    /// Emit a change event regardless of watch registration
+    ///
+    /// Used for diagnostics that don't correspond to a watchable property
+    /// (e.g. the SDK reporting speakers that share a room name).
+    pub fn emit_change(&self, event: ChangeEvent) {
+        self.event_tx.send(event);
+    }
+
+    /// Register a callback invoked for every change event sent to `iter()`
+    ///
+    /// Observers see the same events as `iter()` (watched properties plus
+    /// diagnostics) without consuming them. They run on the producing thread,
+    /// so keep them fast.
+    pub fn add_change_observer(&self, observer: ChangeObserver) {
+        self.event_tx.observers.write().push(observer);
+    }
+
+    /// Register middleware that rewrites or drops change events
+    ///
+    /// Middleware runs in registration order before observers and `iter()`
+    /// see an event, each receiving the previous one's output; an event
+    /// dropped by one (`None`) goes no further. It runs on the producing
+    /// thread, so keep it fast.
+    pub fn add_change_middleware(&self, middleware: ChangeMiddleware) {
+        self.event_tx.middleware.push(middleware);
+    }
+
+    /// Started, qualified and abandoned tracks of every group coordinator,
+    /// for scrobbling
+    ///
+    /// The first call installs the tracker with [`ListeningRules::default()`]
+    /// (see [`listening`](crate::listening)); each call returns a receiver of
+    /// the events from then on. Every event is also announced on `iter()` as
+    /// a [`ChangeEvent`] for the coordinator with
+    /// `property_key == ListeningEvent::EVENT_KEY`. Only watched changes
+    /// reach the tracker: watch `playback_state`, `current_track` and
+    /// `position` on the coordinators to follow.
+    pub fn listening_events(&self) -> ListeningEvents {
+        self.listening_events_with(ListeningRules::default())
+    }
+
+    /// [`listening_events()`](Self::listening_events) with the rules a track
+    /// qualifies by
+    ///
+    /// `rules` only apply to the call that installs the tracker.
+    pub fn listening_events_with(&self, rules: ListeningRules) -> ListeningEvents {
+        let tracker = self.listening.get_or_init(|| {
+            let tracker = Arc::new(ListeningTracker::new(
+                rules.clone(),
+                Arc::clone(&self.clock),
+                Arc::clone(&self.store),
+                self.event_tx.clone(),
+            ));
+            let observed = Arc::downgrade(&tracker);
+            self.add_change_observer(Arc::new(move |event| {
+                if let Some(tracker) = observed.upgrade() {
+                    tracker.observe(event);
+                }
+            }));
+            tracker
+        });
+        if *tracker.rules() != rules {
+            tracing::debug!("Listening tracker already installed; ignoring {rules:?}");
+        }
+        tracker.subscribe()
+    }
+
+    /// Record changes to a [`PersistenceSink`] on a thread of its own
+    ///
+    /// The sink gets the changes `iter()` sees (watched properties, after
+    /// middleware) that have a [`PropertySchema`], each with the value it
+    /// changed to, batched as `config` says. A full refresh records every
+    /// current value. Initial watch notifications aren't recorded.
+    pub fn add_persistence_sink(
+        &self,
+        sink: Box<dyn PersistenceSink>,
+        config: PersistenceConfig,
+    ) -> PersistenceHandle {
+        let (handle, recorder) = persistence::spawn(sink, config, Arc::clone(&self.store));
+        self.add_change_observer(Arc::new(move |event: &ChangeEvent| recorder.record(event)));
+        self.persistence.write().push(handle.clone());
+        handle
+    }
+
+    /// Apply recorded changes in order, returning how many were applied
+    ///
+    /// Each change goes through [`set_property_dynamic()`](Self::set_property_dynamic),
+    /// so only writable properties of known speakers are restored; the rest
+    /// are skipped.
+    pub fn replay(&self, changes: impl IntoIterator<Item = PersistedChange>) -> usize {
+        changes
+            .into_iter()
+            .filter(|change| {
+                self.set_property_dynamic(
+                    &change.speaker_id,
+                    change.property_key,
+                    change.value.clone(),
+                )
+                .is_ok()
+            })
+            .count()
+    }
+
+    /// Resolver of group commands to coordinators
+    pub fn coordinator_resolver(&self) -> &CoordinatorResolver {
+        &self.coordinators
+    }
+
+    /// Counter bumped each time the group layout changes
+    ///
+    /// Lets a caller tell whether the topology was updated since it read a
+    /// [`CoordinatorRoute`](crate::CoordinatorRoute).
+    pub fn topology_generation(&self) -> u64 {
+        self.store.read().topology_generation
+    }
+
+    /// Apply a ZoneGroupTopology state fetched from `addr`
+    ///
+    /// For callers that poll the topology themselves, e.g. after a speaker
+    /// refused a command because it no longer coordinates. Processed like a
+    /// polled event; returns `false` if `addr` belongs to no known speaker.
+    pub fn refresh_topology(&self, addr: SocketAddr, state: ZoneGroupTopologyState) -> bool {
+        let event = EnrichedEvent::new(
+            RegistrationId::new(0),
+            addr,
+            Service::ZoneGroupTopology,
+            EventSource::PollingDetection {
+                poll_interval: Duration::ZERO,
+            },
+            EventData::ZoneGroupTopology(state),
+        );
+        self.inject_event(&event)
+    }
+
+    /// Run a synthetic event through the same decoding as device events
+    ///
+    /// Intended for development and tests without speakers: the event is
+    /// decoded, applied and attributed exactly as if the event manager had
+    /// delivered it, so derived properties, group propagation and change
+    /// origins behave as they would live. Processing happens on the calling
+    /// thread. Returns `false` if the event was skipped, e.g. because its
+    /// address belongs to no known speaker.
+    pub fn inject_event(&self, event: &EnrichedEvent) -> bool {
+        self.pipeline().process(event)
+    }
+
+    /// Inject the event that would report `change`
+    ///
+    /// See [`simulation`](crate::simulation) for the builders.
+    pub fn simulate(&self, change: &SimulatedChange) -> Result<bool> {
+        let event = change.to_event(self)?;
+        Ok(self.inject_event(&event))
+    }
+
+    /// Register an interceptor consulted before every write the SDK sends
+    ///
+    /// Interceptors run in registration order, each seeing the request as
+    /// rewritten by the previous ones; the first `Deny` wins.
+    pub fn add_write_interceptor(&self, interceptor: WriteInterceptor) {
+        self.write_interceptors.push(interceptor);
+    }
+
+    /// Whether any write interceptor is registered (a lock-free check)
+    pub fn has_write_interceptors(&self) -> bool {
+        !self.write_interceptors.is_empty()
+    }
+
+    /// Run `request` through the registered write interceptors
+    ///
+    /// Returns `Deny` from the first interceptor that vetoes it, `Modify`
+    /// with the final request if any rewrote it, and `Allow` otherwise.
+    pub fn intercept_write(&self, request: WriteRequest) -> InterceptDecision {
+        self.write_interceptors.intercept(request)
+    }
+
     /// Emit a change event if the property is being watched
     fn maybe_emit_change(
         &self,
         speaker_id: &SpeakerId,
         property_key: &'static str,
         service: Service,
+        origin: ChangeOrigin,
     ) {
         let is_watched = self
             .watched
@@ -672,21 +1778,98 @@ impl StateManager {
             .contains(&(speaker_id.clone(), property_key));
 
         if is_watched {
-            let event = ChangeEvent::new(speaker_id.clone(), property_key, service);
-            let _ = self.event_tx.send(event);
+            let event =
+                ChangeEvent::new(speaker_id.clone(), property_key, service).with_origin(origin);
+            self.event_tx.send(event);
         }
     }
 
     /// Initialize from topology data
+    ///
+    /// The first call ends startup: events held because their speaker
+    /// hadn't registered yet are replayed, and from then on events from
+    /// unregistered addresses are dropped.
     pub fn initialize(&self, topology: Topology) {
-        let mut store = self.store.write();
-        for speaker in &topology.speakers {
-            store.add_speaker(speaker.clone());
+        {
+            let mut store = self.store.write();
+            for speaker in &topology.speakers {
+                store.create_entity(speaker.clone());
+            }
+            for group in &topology.groups {
+                store.add_group(group.clone());
+            }
+            // Groups no longer come from the last event, so don't skip its repeat
+            store.topology_fingerprint = None;
+            store.topology_generation += 1;
+            let group_list = GroupList::new(store.groups.keys().cloned().collect());
+            store.set_system(group_list);
+            store.set_system(topology);
+        }
+        self.pipeline().replay_held();
+        self.routing.end_startup();
+    }
+
+    /// Remove a speaker from the system
+    ///
+    /// Its subscriptions are released through the event manager (even
+    /// while watches still hold them), its watches and pending transition
+    /// checks are discarded and its entity is removed with its properties.
+    /// Events still in flight from its address are then dropped and counted
+    /// in [`dropped_for_removed()`](Self::dropped_for_removed) rather than
+    /// bringing it back; only registering it again does. A
+    /// [`DynamicWatcher`] of the speaker is told it is gone. Watches still
+    /// held when it is registered again, of any kind, are taken up again
+    /// with their subscriptions. Returns `false` if the speaker wasn't
+    /// registered.
+    pub fn remove_speaker(&self, speaker_id: &SpeakerId) -> bool {
+        let Some(addr) = self.get_speaker_addr(speaker_id) else {
+            return false;
+        };
+        let keys: Vec<&'static str> = self
+            .watched
+            .read()
+            .iter()
+            .filter(|(id, _)| id == speaker_id)
+            .map(|(_, key)| *key)
+            .collect();
+        // Before the address is forgotten, so its watches still resolve
+        if let Some(em) = self.event_manager.get() {
+            if let Err(e) = em.release_device(addr) {
+                tracing::warn!(
+                    "Failed to release subscriptions of {}: {}",
+                    speaker_id.as_str(),
+                    e
+                );
+            }
         }
-        for group in &topology.groups {
-            store.add_group(group.clone());
+        if self.store.write().remove_entity(speaker_id).is_none() {
+            return false;
         }
-        store.set_system(topology);
+        self.routing.mark_removed(addr, speaker_id.clone());
+        self.addr_to_speaker.rcu(|map| {
+            let mut map = HashMap::clone(map);
+            map.retain(|_, id| id != speaker_id);
+            map
+        });
+        self.watched.write().retain(|(id, _)| id != speaker_id);
+        self.dormant
+            .write()
+            .insert(speaker_id.clone(), DormantWatches { addr, keys });
+        // Dynamic watchers stay bound to the ID; see rebind_watchers()
+        self.event_tx
+            .taps
+            .write()
+            .retain(|tap| tap.speaker_id() != speaker_id || tap.send_gone());
+        self.transitions.cancel(speaker_id);
+        tracing::debug!("Removed speaker {} at {}", speaker_id.as_str(), addr);
+        true
+    }
+
+    /// Events dropped because their speaker had been removed
+    ///
+    /// See [`remove_speaker()`](Self::remove_speaker).
+    pub fn dropped_for_removed(&self) -> usize {
+        self.routing.dropped_for_removed()
     }
 
     /// Check if initialized with any speakers
@@ -728,7 +1911,7 @@ impl StateManager {
 
     /// Resolve the subscription target for a PerCoordinator service.
     ///
-    /// For PerCoordinator services, returns the coordinator's `(SpeakerId, IpAddr)`
+    /// For PerCoordinator services, returns the coordinator's `(SpeakerId, SocketAddr)`
     /// so the SDK can route UPnP subscriptions to the coordinator speaker.
     /// Falls back to the speaker itself if no group data exists.
     ///
@@ -736,23 +1919,23 @@ impl StateManager {
     pub fn resolve_subscription_target(
         &self,
         speaker_id: &SpeakerId,
-        speaker_ip: IpAddr,
+        speaker_addr: SocketAddr,
         service: Service,
-    ) -> (SpeakerId, IpAddr) {
+    ) -> (SpeakerId, SocketAddr) {
         if service.scope() == ServiceScope::PerCoordinator {
             let store = self.store.read();
             let coordinator_id = store.resolve_coordinator(speaker_id);
             if coordinator_id == *speaker_id {
-                (speaker_id.clone(), speaker_ip)
+                (speaker_id.clone(), speaker_addr)
             } else {
-                let coord_ip = store
+                let coord_addr = store
                     .speaker(&coordinator_id)
-                    .map(|s| s.ip_address)
-                    .unwrap_or(speaker_ip);
-                (coordinator_id, coord_ip)
+                    .map(|s| s.socket_addr())
+                    .unwrap_or(speaker_addr);
+                (coordinator_id, coord_addr)
             }
         } else {
-            (speaker_id.clone(), speaker_ip)
+            (speaker_id.clone(), speaker_addr)
         }
     }
 
@@ -770,6 +1953,11 @@ impl StateManager {
     /// Can only be called once — subsequent calls are no-ops.
     pub fn set_event_manager(&self, em: Arc<SonosEventManager>) -> Result<()> {
         tracing::debug!("StateManager::set_event_manager called");
+        if self.drain.is_started() {
+            return Err(StateError::InitializationFailed(
+                "StateManager has been shut down".to_string(),
+            ));
+        }
         if self.event_manager.set(Arc::clone(&em)).is_err() {
             tracing::debug!("Event manager already set — no-op");
             return Ok(()); // Already set — no-op
@@ -778,7 +1966,7 @@ impl StateManager {
         // Wire this StateManager as the WatchRegistry
         em.set_watch_registry(Arc::new(StateWatchRegistry {
             watched: Arc::clone(&self.watched),
-            ip_to_speaker: Arc::clone(&self.ip_to_speaker),
+            addr_to_speaker: Arc::clone(&self.addr_to_speaker),
             key_to_service: Arc::clone(&self.key_to_service),
         }));
 
@@ -793,31 +1981,160 @@ impl StateManager {
                 ip_address: info.ip_address.to_string(),
                 port: info.port,
                 model_name: info.model_name.clone(),
+                household_id: None,
+                secure: false,
             })
             .collect();
 
-        if let Err(e) = em.add_devices(devices_for_em) {
-            tracing::warn!(
-                "Failed to add devices to event manager during lazy init: {}",
-                e
-            );
+        if let Err(e) = em.add_devices(devices_for_em) {
+            tracing::warn!(
+                "Failed to add devices to event manager during lazy init: {}",
+                e
+            );
+        }
+
+        // Spawn event worker thread
+        let worker = spawn_state_event_worker(em, self.pipeline(), Arc::clone(&self.drain));
+        info!("StateManager event worker started (lazy init)");
+
+        if let Ok(mut w) = self.worker.lock() {
+            *w = Some(worker);
+        }
+
+        Ok(())
+    }
+
+    /// Suspend the event pipeline, e.g. from an OS sleep hook.
+    ///
+    /// Stops renewals and polling; [`SuspendPolicy::Unsubscribe`] also
+    /// cancels subscriptions. Watches stay registered. Safe to call from any
+    /// thread; returns `false` if already suspended.
+    pub fn suspend(&self, policy: SuspendPolicy) -> Result<bool> {
+        let mut suspension = self
+            .suspension
+            .lock()
+            .map_err(|_| StateError::LockPoisoned)?;
+        if suspension.is_some() {
+            return Ok(false);
+        }
+        if let Some(em) = self.event_manager.get() {
+            em.suspend(policy)
+                .map_err(|e| StateError::SubscriptionFailed(e.to_string()))?;
+        }
+        *suspension = Some(policy);
+        Ok(true)
+    }
+
+    /// Resume after [`suspend()`](Self::suspend) and emit one
+    /// [`ChangeEvent::full_refresh()`].
+    ///
+    /// Equivalent to `resume_with(|| {})`.
+    pub fn resume(&self) -> Result<bool> {
+        self.resume_with(|| {})
+    }
+
+    /// Resume, run `refresh` to re-fetch state, then emit a single
+    /// [`ChangeEvent::full_refresh()`].
+    ///
+    /// Subscriptions are re-established before `refresh` runs. Per-property
+    /// change events are muted while it runs, so consumers see one
+    /// [`RerenderScope::Full`] event once the store is consistent instead of
+    /// a burst of partial updates. Returns `false` (without calling
+    /// `refresh`) if not suspended.
+    pub fn resume_with(&self, refresh: impl FnOnce()) -> Result<bool> {
+        let mut suspension = self
+            .suspension
+            .lock()
+            .map_err(|_| StateError::LockPoisoned)?;
+        if suspension.is_none() {
+            return Ok(false);
+        }
+        if let Some(em) = self.event_manager.get() {
+            em.resume()
+                .map_err(|e| StateError::SubscriptionFailed(e.to_string()))?;
         }
+        *suspension = None;
 
-        // Spawn event worker thread
-        let worker = spawn_state_event_worker(
-            em,
-            Arc::clone(&self.store),
-            Arc::clone(&self.watched),
-            self.event_tx.clone(),
-            Arc::clone(&self.ip_to_speaker),
-        );
-        info!("StateManager event worker started (lazy init)");
+        self.event_tx.muted.store(true, Ordering::SeqCst);
+        refresh();
+        self.event_tx.muted.store(false, Ordering::SeqCst);
+        self.event_tx.send(ChangeEvent::full_refresh());
+        Ok(true)
+    }
 
-        if let Ok(mut w) = self._worker.lock() {
-            *w = Some(worker);
+    /// Returns true between [`suspend()`](Self::suspend) and a resume
+    pub fn is_suspended(&self) -> bool {
+        self.suspension.lock().map(|s| s.is_some()).unwrap_or(false)
+    }
+
+    /// Shut the event pipeline down without losing events already received
+    ///
+    /// Runs in order: new watches are refused and every subscription is
+    /// cancelled, so devices stop sending; events already received are
+    /// decoded into the store; pending volume bursts are emitted and every
+    /// persistence sink is flushed; then the worker threads exit. Decoding
+    /// stops at `timeout`, and what is left is reported as abandoned.
+    ///
+    /// Only the first call drains; later calls just flush persistence
+    /// again. Once shutdown starts the event manager refuses new watches.
+    pub fn shutdown(&self, timeout: Duration) -> Result<ShutdownReport> {
+        let deadline = Instant::now() + timeout;
+        if !self.drain.begin(deadline) {
+            return Ok(ShutdownReport {
+                persistence_flushed: self.flush_persistence(),
+                ..ShutdownReport::default()
+            });
         }
 
-        Ok(())
+        if let Some(em) = self.event_manager.get() {
+            // Once the drain has closed the event stream the worker exits on
+            // its own, after the last event or at the deadline
+            let closed = match em.drain(deadline) {
+                Ok(forwarded) => {
+                    tracing::debug!("Event manager drained, {} events forwarded", forwarded);
+                    true
+                }
+                Err(EventManagerError::WorkerDisconnected) => true,
+                Err(e) => {
+                    tracing::warn!("Event manager did not drain: {}", e);
+                    false
+                }
+            };
+            let worker = self.worker.lock().ok().and_then(|mut w| w.take());
+            if let (true, Some(worker)) = (closed, worker) {
+                if worker.join().is_err() {
+                    tracing::warn!("State event worker panicked during shutdown");
+                }
+            }
+            self.drain.abandon(em.iter().try_iter().count());
+            em.shutdown();
+        }
+
+        self.origins.flush_bursts();
+        self.transitions.clear();
+        let persistence_flushed = self.flush_persistence();
+        let (events_drained, events_abandoned) = self.drain.counts();
+        info!(
+            "StateManager shut down: {} events drained, {} abandoned",
+            events_drained, events_abandoned
+        );
+        Ok(ShutdownReport {
+            events_drained,
+            events_abandoned,
+            persistence_flushed,
+        })
+    }
+
+    /// Flush every persistence sink, returning whether all succeeded
+    fn flush_persistence(&self) -> bool {
+        // Not all(): every sink gets flushed, even after one fails
+        let failed = self
+            .persistence
+            .read()
+            .iter()
+            .filter(|handle| !handle.flush())
+            .count();
+        failed == 0
     }
 
     /// Set the lazy event manager initialization closure.
@@ -835,6 +2152,20 @@ impl StateManager {
     pub fn event_init(&self) -> Option<&EventInitFn> {
         self.event_init.get()
     }
+
+    /// The decoding state shared by the event worker and [`inject_event()`](Self::inject_event)
+    fn pipeline(&self) -> EventPipeline {
+        EventPipeline {
+            store: Arc::clone(&self.store),
+            watched: Arc::clone(&self.watched),
+            event_tx: self.event_tx.clone(),
+            origins: Arc::clone(&self.origins),
+            transitions: Arc::clone(&self.transitions),
+            addr_to_speaker: Arc::clone(&self.addr_to_speaker),
+            routing: Arc::clone(&self.routing),
+            chaos: self.chaos.clone(),
+        }
+    }
 }
 
 impl Clone for StateManager {
@@ -850,14 +2181,27 @@ impl Clone for StateManager {
         Self {
             store: Arc::clone(&self.store),
             watched: Arc::clone(&self.watched),
-            ip_to_speaker: Arc::clone(&self.ip_to_speaker),
+            addr_to_speaker: Arc::clone(&self.addr_to_speaker),
             event_manager,
             event_tx: self.event_tx.clone(),
             event_rx: Arc::clone(&self.event_rx),
-            _worker: Mutex::new(None),
+            worker: Arc::clone(&self.worker),
+            drain: Arc::clone(&self.drain),
+            persistence: Arc::clone(&self.persistence),
             cleanup_timeout: self.cleanup_timeout,
             key_to_service: Arc::clone(&self.key_to_service),
             event_init,
+            suspension: Arc::clone(&self.suspension),
+            origins: Arc::clone(&self.origins),
+            write_interceptors: Arc::clone(&self.write_interceptors),
+            household: Arc::clone(&self.household),
+            transitions: Arc::clone(&self.transitions),
+            clock: Arc::clone(&self.clock),
+            routing: Arc::clone(&self.routing),
+            coordinators: Arc::clone(&self.coordinators),
+            listening: Arc::clone(&self.listening),
+            dormant: Arc::clone(&self.dormant),
+            chaos: self.chaos.clone(),
         }
     }
 }
@@ -870,6 +2214,14 @@ impl Clone for StateManager {
 pub struct StateManagerBuilder {
     cleanup_timeout: Duration,
     event_manager: Option<Arc<SonosEventManager>>,
+    expectation_window: Duration,
+    coalesce_window: Duration,
+    history_size: usize,
+    transition_timeout: Duration,
+    clock: SharedClock,
+    api_client: Option<SonosClient>,
+    settle_delay: Duration,
+    chaos: ChaosHook,
 }
 
 impl Default for StateManagerBuilder {
@@ -877,6 +2229,14 @@ impl Default for StateManagerBuilder {
         Self {
             cleanup_timeout: Duration::from_secs(5),
             event_manager: None,
+            expectation_window: DEFAULT_EXPECTATION_WINDOW,
+            coalesce_window: DEFAULT_COALESCE_WINDOW,
+            history_size: 0,
+            transition_timeout: DEFAULT_TRANSITION_TIMEOUT,
+            clock: Arc::new(SystemClock),
+            api_client: None,
+            settle_delay: DEFAULT_SETTLE_DELAY,
+            chaos: ChaosHook::default(),
         }
     }
 }
@@ -888,6 +2248,68 @@ impl StateManagerBuilder {
         self
     }
 
+    /// How long after a local write a matching event is attributed to it
+    /// rather than reported as [`ChangeOrigin::External`] (default 2s)
+    pub fn expectation_window(mut self, window: Duration) -> Self {
+        self.expectation_window = window;
+        self
+    }
+
+    /// Quiet period that ends a burst of external volume steps (default
+    /// 400ms); `Duration::ZERO` reports every step individually
+    pub fn coalesce_window(mut self, window: Duration) -> Self {
+        self.coalesce_window = window;
+        self
+    }
+
+    /// Keep the last `size` property changes for
+    /// [`StateManager::history()`] and [`StateManager::undo_last()`]
+    /// (default 0: no history)
+    pub fn history_size(mut self, size: usize) -> Self {
+        self.history_size = size;
+        self
+    }
+
+    /// How long a local [`PlaybackState::Transitioning`] write may go
+    /// without an AVTransport event before `GetTransportInfo` is fetched to
+    /// reconcile it (default 5s); `Duration::ZERO` disables the check
+    ///
+    /// The corrected value is reported with [`ChangeOrigin::Reconciled`].
+    /// If the fetch fails, the last confirmed state is put back and a
+    /// [`ChangeEvent::TRANSITION_TIMEOUT_KEY`] event is emitted.
+    pub fn transition_timeout(mut self, timeout: Duration) -> Self {
+        self.transition_timeout = timeout;
+        self
+    }
+
+    /// Time source for change event and history timestamps and the
+    /// transition timeout (default: the system clock)
+    ///
+    /// Share one [`ManualClock`](sonos_api::clock::ManualClock) with the
+    /// event manager's `BrokerConfig::with_clock()` to move both through
+    /// virtual time in tests.
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Client for the transition verification fetch (default
+    /// `SonosClient::new()`)
+    pub fn api_client(mut self, client: SonosClient) -> Self {
+        self.api_client = Some(client);
+        self
+    }
+
+    /// How long a group must go without topology changes before group
+    /// commands are routed to its coordinator (default 250ms);
+    /// `Duration::ZERO` routes on the latest topology at once
+    ///
+    /// See [`CoordinatorResolver`].
+    pub fn settle_delay(mut self, delay: Duration) -> Self {
+        self.settle_delay = delay;
+        self
+    }
+
     /// Set the event manager for live event processing
     ///
     /// When an event manager is provided, the StateManager will:
@@ -899,17 +2321,48 @@ impl StateManagerBuilder {
         self
     }
 
+    /// Add the decode latency `controller` sets before each event is decoded
+    #[cfg(feature = "chaos-testing")]
+    pub fn chaos(mut self, controller: &sonos_stream::ChaosController) -> Self {
+        self.chaos = controller.hook();
+        self
+    }
+
     /// Build the StateManager
     pub fn build(self) -> Result<StateManager> {
-        let (event_tx, event_rx) = mpsc::channel();
+        let (tx, event_rx) = mpsc::channel();
+        let event_tx = ChangeSink::new(tx).with_clock(Arc::clone(&self.clock));
+        let origins = Arc::new(OriginTracker::new(
+            event_tx.clone(),
+            self.expectation_window,
+            self.coalesce_window,
+        ));
 
-        let store = Arc::new(RwLock::new(StateStore::new()));
+        let mut store = StateStore::new();
+        store.history = ChangeHistory::new(self.history_size).with_clock(Arc::clone(&self.clock));
+        let store = Arc::new(RwLock::new(store));
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let ip_to_speaker = Arc::new(RwLock::new(HashMap::new()));
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(HashMap::new()));
         let key_to_service = Arc::new(RwLock::new(HashMap::new()));
 
+        let routing = Arc::new(Routing::new());
+        let coordinators = Arc::new(CoordinatorResolver::new(
+            Arc::clone(&store),
+            Arc::clone(&self.clock),
+        ));
+        coordinators.set_settle_delay(self.settle_delay);
+        let transitions = Arc::new(TransitionMonitor::new(
+            self.transition_timeout,
+            Arc::clone(&self.clock),
+            self.api_client.unwrap_or_default(),
+            Arc::clone(&store),
+            Arc::clone(&watched),
+            event_tx.clone(),
+        ));
+
         let event_manager_lock = OnceLock::new();
         let mut worker = None;
+        let drain = Arc::new(Drain::default());
 
         // If event_manager provided at build time, wire it up eagerly
         if let Some(em) = self.event_manager {
@@ -918,17 +2371,21 @@ impl StateManagerBuilder {
             // Wire WatchRegistry
             em.set_watch_registry(Arc::new(StateWatchRegistry {
                 watched: Arc::clone(&watched),
-                ip_to_speaker: Arc::clone(&ip_to_speaker),
+                addr_to_speaker: Arc::clone(&addr_to_speaker),
                 key_to_service: Arc::clone(&key_to_service),
             }));
 
-            let worker_handle = spawn_state_event_worker(
-                em,
-                Arc::clone(&store),
-                Arc::clone(&watched),
-                event_tx.clone(),
-                Arc::clone(&ip_to_speaker),
-            );
+            let pipeline = EventPipeline {
+                store: Arc::clone(&store),
+                watched: Arc::clone(&watched),
+                event_tx: event_tx.clone(),
+                origins: Arc::clone(&origins),
+                transitions: Arc::clone(&transitions),
+                addr_to_speaker: Arc::clone(&addr_to_speaker),
+                routing: Arc::clone(&routing),
+                chaos: self.chaos.clone(),
+            };
+            let worker_handle = spawn_state_event_worker(em, pipeline, Arc::clone(&drain));
             info!("StateManager event worker started");
             worker = Some(worker_handle);
         }
@@ -936,14 +2393,27 @@ impl StateManagerBuilder {
         let manager = StateManager {
             store,
             watched,
-            ip_to_speaker,
+            addr_to_speaker,
             event_manager: event_manager_lock,
             event_tx,
             event_rx: Arc::new(Mutex::new(event_rx)),
-            _worker: Mutex::new(worker),
+            worker: Arc::new(Mutex::new(worker)),
+            drain,
+            persistence: Arc::new(RwLock::new(Vec::new())),
             cleanup_timeout: self.cleanup_timeout,
             key_to_service,
             event_init: OnceLock::new(),
+            suspension: Arc::new(Mutex::new(None)),
+            origins,
+            write_interceptors: Arc::new(Chain::new()),
+            household: Arc::new(RwLock::new(None)),
+            transitions,
+            clock: self.clock,
+            routing,
+            coordinators,
+            listening: Arc::new(OnceLock::new()),
+            dormant: Arc::new(RwLock::new(HashMap::new())),
+            chaos: self.chaos,
         };
 
         info!("StateManager created (sync-first mode)");
@@ -954,9 +2424,28 @@ impl StateManagerBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::property::{GroupVolume, PlaybackState, Volume};
+    use crate::property::{CurrentTrack, GroupVolume, Mute, PlaybackState, Volume};
     use sonos_api::Service;
 
+    /// Register speakers `ids`, each on its own address
+    fn register(manager: &StateManager, ids: &[&str]) {
+        let devices = ids
+            .iter()
+            .zip(100..)
+            .map(|(id, n)| Device {
+                id: id.to_string(),
+                name: id.to_string(),
+                room_name: id.to_string(),
+                ip_address: format!("192.168.1.{n}"),
+                port: 1400,
+                model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
+            })
+            .collect();
+        manager.add_devices(devices).unwrap();
+    }
+
     #[test]
     fn test_state_manager_creation() {
         let manager = StateManager::new().unwrap();
@@ -975,12 +2464,45 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
 
         manager.add_devices(devices).unwrap();
         assert_eq!(manager.speaker_count(), 1);
     }
 
+    #[test]
+    fn test_household_scope_skips_other_households() {
+        let manager = StateManager::new().unwrap();
+        manager.set_household(Some("Sonos_A".to_string()));
+        let device = |id: &str, household: Option<&str>| Device {
+            id: id.to_string(),
+            name: id.to_string(),
+            room_name: id.to_string(),
+            ip_address: "192.168.1.100".to_string(),
+            port: 1400,
+            model_name: "Sonos One".to_string(),
+            household_id: household.map(str::to_string),
+            secure: false,
+        };
+
+        manager
+            .add_devices(vec![
+                device("RINCON_A", Some("Sonos_A")),
+                device("RINCON_B", Some("Sonos_B")),
+                device("RINCON_C", None),
+            ])
+            .unwrap();
+        let mut ids: Vec<_> = manager
+            .speaker_infos()
+            .into_iter()
+            .map(|info| info.id.as_str().to_string())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, ["RINCON_A", "RINCON_C"]);
+    }
+
     #[test]
     fn test_property_storage() {
         let manager = StateManager::new().unwrap();
@@ -992,6 +2514,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1019,6 +2543,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1036,6 +2562,21 @@ mod tests {
         assert!(!manager.is_watched(&speaker_id, "volume"));
     }
 
+    #[test]
+    fn test_begin_watch_announces_once() {
+        let manager = StateManager::new().unwrap();
+        let speaker_id = SpeakerId::new("RINCON_123");
+
+        assert!(manager.begin_watch(&speaker_id, "volume", Service::RenderingControl));
+        assert!(!manager.begin_watch(&speaker_id, "volume", Service::RenderingControl));
+        assert!(manager.is_watched(&speaker_id, "volume"));
+
+        let events: Vec<_> = manager.iter().try_iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].origin, ChangeOrigin::Initial);
+        assert_eq!(events[0].property_key, "volume");
+    }
+
     #[test]
     fn test_change_event_emission() {
         let manager = StateManager::new().unwrap();
@@ -1047,6 +2588,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1068,6 +2611,230 @@ mod tests {
         assert_eq!(event.property_key, "volume");
     }
 
+    #[test]
+    fn test_change_observer_sees_events_without_consuming() {
+        let manager = StateManager::new().unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        manager.add_change_observer(Arc::new(move |e: &ChangeEvent| {
+            sink.lock().unwrap().push(e.property_key);
+        }));
+
+        let speaker_id = SpeakerId::new("RINCON_123");
+        register(&manager, &["RINCON_123"]);
+        manager.register_watch(&speaker_id, "volume");
+        manager.set_property(&speaker_id, Volume::new(40));
+        // Unwatched properties reach neither observers nor iter()
+        manager.set_property(&speaker_id, Mute::new(true));
+
+        assert_eq!(*seen.lock().unwrap(), vec!["volume"]);
+        assert_eq!(manager.iter().try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_apply_local_writes_emits_one_event() {
+        use crate::property::{Bass, Loudness, Treble};
+
+        let manager = StateManager::new().unwrap();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        register(&manager, &["RINCON_123"]);
+        manager.register_watch(&speaker_id, "bass");
+        manager.register_watch(&speaker_id, "treble");
+
+        manager.apply_local_writes(&speaker_id, |batch| {
+            batch.set(Bass(-2));
+            batch.set(Treble(1));
+            batch.set(Loudness(true));
+        });
+        let events: Vec<_> = manager.iter().try_iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].property_key, ChangeEvent::BATCH_KEY);
+        assert_eq!(events[0].batch, ["bass", "treble"]);
+        assert_eq!(events[0].coalesced, 2);
+        assert_eq!(events[0].origin, ChangeOrigin::LocalWrite);
+        assert_eq!(events[0].rerender_scope(), RerenderScope::Speaker);
+        assert_eq!(
+            manager.get_property::<Loudness>(&speaker_id),
+            Some(Loudness(true))
+        );
+
+        // Only one watched property changes: its own event
+        manager.apply_local_writes(&speaker_id, |batch| {
+            batch.set(Bass(-2));
+            batch.set(Treble(3));
+        });
+        let events: Vec<_> = manager.iter().try_iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].property_key, "treble");
+        assert!(events[0].batch.is_empty());
+    }
+
+    #[test]
+    fn test_apply_fetched_reports_only_differences() {
+        use crate::property::Bass;
+
+        let manager = StateManager::new().unwrap();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        register(&manager, &["RINCON_123"]);
+        manager.register_watch(&speaker_id, "volume");
+        manager.register_watch(&speaker_id, "mute");
+        manager.set_property(&speaker_id, Volume::new(20));
+        manager.set_property(&speaker_id, Mute::new(false));
+        manager.iter().try_iter().count();
+
+        let changed = manager.apply_fetched(&speaker_id, |batch| {
+            batch.set(Volume::new(20));
+            batch.set(Mute::new(false));
+        });
+        assert!(changed.is_empty());
+        assert_eq!(manager.iter().try_iter().count(), 0);
+
+        let changed = manager.apply_fetched(&speaker_id, |batch| {
+            batch.set(Volume::new(35));
+            batch.set(Mute::new(true));
+            batch.set(Bass(4));
+        });
+        assert_eq!(changed, ["volume", "mute", "bass"]);
+        let events: Vec<_> = manager.iter().try_iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].batch, ["volume", "mute"]);
+        assert_eq!(events[0].origin, ChangeOrigin::Unknown);
+    }
+
+    #[test]
+    fn test_album_art_only_change_is_reported() {
+        let manager = StateManager::new().unwrap();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        register(&manager, &["RINCON_123"]);
+        manager.register_watch(&speaker_id, CurrentTrack::KEY);
+        let track = |art: Option<&str>| CurrentTrack {
+            title: Some("Hey Jude".to_string()),
+            artist: None,
+            album: None,
+            album_art_uri: art.map(str::to_string),
+            uri: None,
+        };
+
+        manager.set_property(&speaker_id, track(None));
+        assert_eq!(manager.iter().try_iter().count(), 1);
+
+        // Art fetched after the track started, then its token reissued
+        manager.set_property(&speaker_id, track(Some("/getaa?token=1")));
+        assert_eq!(manager.iter().try_iter().count(), 1);
+        manager.set_property(&speaker_id, track(Some("/getaa?token=2")));
+        assert_eq!(manager.iter().try_iter().count(), 1);
+        assert_eq!(
+            manager.get_property::<CurrentTrack>(&speaker_id),
+            Some(track(Some("/getaa?token=2")))
+        );
+    }
+
+    #[test]
+    fn test_resume_emits_single_full_refresh() {
+        let manager = StateManager::new().unwrap();
+        let speaker_id = SpeakerId::new("RINCON_123");
+        register(&manager, &["RINCON_123"]);
+        manager.register_watch(&speaker_id, "volume");
+
+        assert!(!manager.resume().unwrap());
+        assert!(manager.suspend(SuspendPolicy::KeepSubscriptions).unwrap());
+        assert!(!manager.suspend(SuspendPolicy::Unsubscribe).unwrap());
+        assert!(manager.is_suspended());
+
+        let resumed = manager
+            .resume_with(|| {
+                manager.set_property(&speaker_id, Volume::new(10));
+                manager.set_property(&speaker_id, Volume::new(20));
+            })
+            .unwrap();
+        assert!(resumed);
+        assert!(!manager.is_suspended());
+        assert!(!manager.resume().unwrap());
+
+        let events: Vec<_> = manager.iter().try_iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rerender_scope(), RerenderScope::Full);
+        assert_eq!(
+            manager.get_property::<Volume>(&speaker_id),
+            Some(Volume::new(20))
+        );
+    }
+
+    #[test]
+    fn test_events_and_history_are_stamped_on_manager_clock() {
+        let clock = sonos_api::clock::ManualClock::new();
+        let manager = StateManager::builder()
+            .clock(Arc::new(clock.clone()))
+            .history_size(4)
+            .build()
+            .unwrap();
+        let den = SpeakerId::new("RINCON_DEN");
+        register(&manager, &["RINCON_DEN"]);
+        manager.register_watch(&den, "volume");
+        let start = sonos_api::Clock::now(&clock);
+
+        manager.set_property(&den, Volume::new(10));
+        clock.advance(Duration::from_secs(90));
+        manager.set_property(&den, Volume::new(20));
+
+        let stamps: Vec<_> = manager.iter().try_iter().map(|e| e.timestamp).collect();
+        assert_eq!(stamps, [start, start + Duration::from_secs(90)]);
+        let history = manager.history(&HistoryFilter::new());
+        assert_eq!(
+            history[1].timestamp - history[0].timestamp,
+            Duration::from_secs(90)
+        );
+    }
+
+    #[test]
+    fn test_history_query_and_undo() {
+        assert!(StateManager::new()
+            .unwrap()
+            .history(&HistoryFilter::new())
+            .is_empty());
+
+        let manager = StateManager::builder().history_size(8).build().unwrap();
+        let (den, kitchen) = (SpeakerId::new("RINCON_DEN"), SpeakerId::new("RINCON_KIT"));
+        register(&manager, &["RINCON_DEN", "RINCON_KIT"]);
+        manager.register_watch(&den, "volume");
+        for v in 1..=10 {
+            manager.set_property(&den, Volume::new(v));
+        }
+        manager.set_property(&kitchen, Volume::new(30));
+        manager.apply_local_write(&den, Mute::new(true));
+
+        // Bounded: the two oldest changes were dropped
+        let all = manager.history(&HistoryFilter::new());
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0].after::<Volume>(), Some(Volume::new(5)));
+        assert_eq!(all[7].origin, ChangeOrigin::LocalWrite);
+
+        let volumes = HistoryFilter::new().speaker(&den).property::<Volume>();
+        let page = manager.history(&volumes.clone().limit(4));
+        assert_eq!(page.len(), 4);
+        let rest = manager.history(&volumes.after(page[3].seq));
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[1].before::<Volume>(), Some(Volume::new(9)));
+
+        let since = all[6].timestamp;
+        assert!(manager
+            .history(&HistoryFilter::new().since(since))
+            .iter()
+            .all(|e| e.seq >= all[6].seq));
+
+        manager.iter().try_iter().for_each(drop);
+        assert_eq!(manager.undo_last::<Volume>(&den), Some(Volume::new(9)));
+        assert_eq!(manager.get_property::<Volume>(&den), Some(Volume::new(9)));
+        let event = manager.iter().try_iter().next().unwrap();
+        assert_eq!(event.origin, ChangeOrigin::Restore);
+
+        // The restore is recorded; the next undo reaches further back
+        let latest = manager.history(&HistoryFilter::new().property::<Volume>());
+        assert_eq!(latest.last().unwrap().origin, ChangeOrigin::Restore);
+        assert_eq!(manager.undo_last::<Volume>(&den), Some(Volume::new(8)));
+        assert_eq!(manager.undo_last::<Mute>(&kitchen), None);
+    }
+
     #[test]
     fn test_set_group_property_emits_change_event() {
         let manager = StateManager::new().unwrap();
@@ -1079,6 +2846,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1123,6 +2892,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1228,7 +2999,7 @@ mod tests {
     }
 
     #[test]
-    fn test_clear_groups_removes_all_group_data() {
+    fn test_remove_group_removes_all_group_data() {
         let mut store = StateStore::new();
 
         let speaker1 = SpeakerId::new("RINCON_111");
@@ -1247,8 +3018,8 @@ mod tests {
         assert!(!store.groups.is_empty());
         assert!(!store.speaker_to_group.is_empty());
 
-        // Clear groups
-        store.clear_groups();
+        // Remove the group
+        store.remove_group(&group_id);
 
         // Verify all group data is cleared
         assert!(store.groups.is_empty());
@@ -1257,7 +3028,7 @@ mod tests {
     }
 
     #[test]
-    fn test_clear_groups_then_add_new_groups() {
+    fn test_remove_group_then_add_new_groups() {
         let mut store = StateStore::new();
 
         // Add initial group
@@ -1266,8 +3037,8 @@ mod tests {
         let group1 = GroupInfo::new(group1_id.clone(), speaker1.clone(), vec![speaker1.clone()]);
         store.add_group(group1);
 
-        // Clear and add new group
-        store.clear_groups();
+        // Remove and add new group
+        store.remove_group(&group1_id);
 
         let speaker2 = SpeakerId::new("RINCON_222");
         let group2_id = GroupId::new("RINCON_222:1");
@@ -1300,6 +3071,8 @@ mod tests {
                 ip_address: "192.168.1.100".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
             Device {
                 id: "RINCON_222".to_string(),
@@ -1308,6 +3081,8 @@ mod tests {
                 ip_address: "192.168.1.101".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
         ];
         manager.add_devices(devices).unwrap();
@@ -1363,6 +3138,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1403,6 +3180,8 @@ mod tests {
                 ip_address: "192.168.1.100".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
             Device {
                 id: "RINCON_222".to_string(),
@@ -1411,6 +3190,8 @@ mod tests {
                 ip_address: "192.168.1.101".to_string(),
                 port: 1400,
                 model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
             },
         ];
         manager.add_devices(devices).unwrap();
@@ -1460,6 +3241,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1509,6 +3292,8 @@ mod tests {
             ip_address: "192.168.1.100".to_string(),
             port: 1400,
             model_name: "Sonos One".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1518,6 +3303,36 @@ mod tests {
         assert_eq!(manager.get_boot_seq(&speaker_id), Some(0));
     }
 
+    #[test]
+    fn test_set_boot_seqs_skips_unknown_speakers() {
+        let manager = StateManager::new().unwrap();
+        manager
+            .add_devices(vec![Device {
+                id: "RINCON_123".to_string(),
+                name: "Living Room".to_string(),
+                room_name: "Living Room".to_string(),
+                ip_address: "192.168.1.100".to_string(),
+                port: 1400,
+                model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
+            }])
+            .unwrap();
+
+        manager.set_boot_seqs(vec![
+            (SpeakerId::new("RINCON_123"), 42),
+            (SpeakerId::new("RINCON_999"), 7),
+        ]);
+
+        assert_eq!(
+            manager.get_boot_seq(&SpeakerId::new("RINCON_123")),
+            Some(42)
+        );
+        assert!(manager
+            .get_boot_seq(&SpeakerId::new("RINCON_999"))
+            .is_none());
+    }
+
     // ========================================================================
     // StateWatchRegistry Tests
     // ========================================================================
@@ -1525,16 +3340,16 @@ mod tests {
     #[test]
     fn test_state_watch_registry_register_and_unregister() {
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let ip_to_speaker = Arc::new(RwLock::new(HashMap::new()));
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(HashMap::new()));
         let key_to_service = Arc::new(RwLock::new(HashMap::new()));
 
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let speaker_id = SpeakerId::new("RINCON_123");
-        ip_to_speaker.write().insert(ip, speaker_id.clone());
+        addr_to_speaker.store(Arc::new(HashMap::from([(ip, speaker_id.clone())])));
 
         let registry = StateWatchRegistry {
             watched: Arc::clone(&watched),
-            ip_to_speaker: Arc::clone(&ip_to_speaker),
+            addr_to_speaker: Arc::clone(&addr_to_speaker),
             key_to_service: Arc::clone(&key_to_service),
         };
 
@@ -1558,14 +3373,14 @@ mod tests {
     #[test]
     fn test_state_watch_registry_unknown_ip_is_noop() {
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let ip_to_speaker = Arc::new(RwLock::new(HashMap::new()));
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(HashMap::new()));
         let key_to_service = Arc::new(RwLock::new(HashMap::new()));
 
         let speaker_id = SpeakerId::new("RINCON_123");
 
         let registry = StateWatchRegistry {
             watched: Arc::clone(&watched),
-            ip_to_speaker,
+            addr_to_speaker,
             key_to_service: Arc::clone(&key_to_service),
         };
 
@@ -1576,7 +3391,7 @@ mod tests {
             .insert("volume", Service::RenderingControl);
 
         // Unregister for an unknown IP — should be a no-op
-        let unknown_ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let unknown_ip: SocketAddr = "10.0.0.1:1400".parse().unwrap();
         registry.unregister_watches_for_service(unknown_ip, Service::RenderingControl);
 
         // Watch should still be there
@@ -1586,20 +3401,23 @@ mod tests {
     #[test]
     fn test_state_watch_registry_only_removes_matching_speaker() {
         let watched = Arc::new(RwLock::new(HashSet::new()));
-        let ip_to_speaker = Arc::new(RwLock::new(HashMap::new()));
+        let addr_to_speaker = Arc::new(ArcSwap::from_pointee(HashMap::new()));
         let key_to_service = Arc::new(RwLock::new(HashMap::new()));
 
-        let ip1: IpAddr = "192.168.1.100".parse().unwrap();
-        let ip2: IpAddr = "192.168.1.101".parse().unwrap();
+        // Same IP, different ports
+        let ip1: SocketAddr = "192.168.1.100:1400".parse().unwrap();
+        let ip2: SocketAddr = "192.168.1.100:1401".parse().unwrap();
         let speaker1 = SpeakerId::new("RINCON_111");
         let speaker2 = SpeakerId::new("RINCON_222");
 
-        ip_to_speaker.write().insert(ip1, speaker1.clone());
-        ip_to_speaker.write().insert(ip2, speaker2.clone());
+        addr_to_speaker.store(Arc::new(HashMap::from([
+            (ip1, speaker1.clone()),
+            (ip2, speaker2.clone()),
+        ])));
 
         let registry = StateWatchRegistry {
             watched: Arc::clone(&watched),
-            ip_to_speaker,
+            addr_to_speaker,
             key_to_service: Arc::clone(&key_to_service),
         };
 
@@ -1628,7 +3446,7 @@ mod tests {
         let speaker = SpeakerId::new("RINCON_111");
         let group_id = GroupId::new("RINCON_111:1");
 
-        store.add_speaker(SpeakerInfo {
+        store.create_entity(SpeakerInfo {
             id: speaker.clone(),
             name: "Living Room".to_string(),
             room_name: "Living Room".to_string(),
@@ -1636,8 +3454,10 @@ mod tests {
             port: 1400,
             model_name: "Test".to_string(),
             software_version: "1.0".to_string(),
+            software_generation: None,
             boot_seq: 0,
             satellites: vec![],
+            household_id: None,
         });
         store.add_group(GroupInfo::new(
             group_id,
@@ -1691,7 +3511,7 @@ mod tests {
         let member = SpeakerId::new("RINCON_MEMBER");
         let group_id = GroupId::new("RINCON_COORD:1");
 
-        store.add_speaker(SpeakerInfo {
+        store.create_entity(SpeakerInfo {
             id: coordinator.clone(),
             name: "Coord".to_string(),
             room_name: "Coord".to_string(),
@@ -1699,10 +3519,12 @@ mod tests {
             port: 1400,
             model_name: "Test".to_string(),
             software_version: "1.0".to_string(),
+            software_generation: None,
             boot_seq: 0,
             satellites: vec![],
+            household_id: None,
         });
-        store.add_speaker(SpeakerInfo {
+        store.create_entity(SpeakerInfo {
             id: member.clone(),
             name: "Member".to_string(),
             room_name: "Member".to_string(),
@@ -1710,8 +3532,10 @@ mod tests {
             port: 1400,
             model_name: "Test".to_string(),
             software_version: "1.0".to_string(),
+            software_generation: None,
             boot_seq: 0,
             satellites: vec![],
+            household_id: None,
         });
         store.add_group(GroupInfo::new(
             group_id,
@@ -1739,7 +3563,7 @@ mod tests {
         let member = SpeakerId::new("RINCON_MEMBER");
         let group_id = GroupId::new("RINCON_COORD:1");
 
-        store.add_speaker(SpeakerInfo {
+        store.create_entity(SpeakerInfo {
             id: coordinator.clone(),
             name: "Coord".to_string(),
             room_name: "Coord".to_string(),
@@ -1747,10 +3571,12 @@ mod tests {
             port: 1400,
             model_name: "Test".to_string(),
             software_version: "1.0".to_string(),
+            software_generation: None,
             boot_seq: 0,
             satellites: vec![],
+            household_id: None,
         });
-        store.add_speaker(SpeakerInfo {
+        store.create_entity(SpeakerInfo {
             id: member.clone(),
             name: "Member".to_string(),
             room_name: "Member".to_string(),
@@ -1758,8 +3584,10 @@ mod tests {
             port: 1400,
             model_name: "Test".to_string(),
             software_version: "1.0".to_string(),
+            software_generation: None,
             boot_seq: 0,
             satellites: vec![],
+            household_id: None,
         });
         store.add_group(GroupInfo::new(
             group_id,
@@ -1790,6 +3618,8 @@ mod tests {
             ip_address: "192.168.4.198".to_string(),
             port: 1400,
             model_name: "Roam 2".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1806,10 +3636,13 @@ mod tests {
         // Verify forward map updated
         assert_eq!(manager.get_speaker_ip(&speaker_id), Some(new_ip));
 
-        // Verify reverse map updated (old IP removed, new IP present)
-        let ip_map = manager.ip_to_speaker.read();
-        assert!(!ip_map.contains_key(&old_ip));
-        assert_eq!(ip_map.get(&new_ip), Some(&speaker_id));
+        // Verify reverse map updated (old address removed, new one present)
+        let addr_map = manager.addr_to_speaker.load();
+        assert!(!addr_map.contains_key(&SocketAddr::new(old_ip, 1400)));
+        assert_eq!(
+            addr_map.get(&SocketAddr::new(new_ip, 1400)),
+            Some(&speaker_id)
+        );
     }
 
     #[test]
@@ -1823,6 +3656,8 @@ mod tests {
             ip_address: "192.168.4.198".to_string(),
             port: 1400,
             model_name: "Roam 2".to_string(),
+            household_id: None,
+            secure: false,
         }];
         manager.add_devices(devices).unwrap();
 
@@ -1848,4 +3683,294 @@ mod tests {
         assert!(stored.contains(&SpeakerId::new("RINCON_SAT1")));
         assert!(stored.contains(&SpeakerId::new("RINCON_SAT2")));
     }
+
+    #[test]
+    fn test_bond_primary() {
+        let manager = StateManager::new().unwrap();
+        manager
+            .add_devices(vec![Device {
+                id: "RINCON_ARC".to_string(),
+                name: "Living Room".to_string(),
+                room_name: "Living Room".to_string(),
+                ip_address: "192.168.1.100".to_string(),
+                port: 1400,
+                model_name: "Sonos Arc".to_string(),
+                household_id: None,
+                secure: false,
+            }])
+            .unwrap();
+        let (arc, sub) = (SpeakerId::new("RINCON_ARC"), SpeakerId::new("RINCON_SUB"));
+
+        manager.set_bonds(vec![(arc.clone(), vec![sub.clone()])]);
+        assert_eq!(manager.bond_primary(&sub), Some(arc.clone()));
+        assert_eq!(manager.bond_primary(&arc), None);
+
+        // A later topology without the bond releases the satellite
+        manager.set_bonds(vec![]);
+        assert_eq!(manager.bond_primary(&sub), None);
+    }
+
+    /// A manager on a manual clock watching the playback state of one
+    /// speaker at `ip`, last confirmed as paused
+    fn transition_fixture(
+        addr: std::net::SocketAddr,
+    ) -> (StateManager, SpeakerId, sonos_api::clock::ManualClock) {
+        let clock = sonos_api::clock::ManualClock::new();
+        let manager = StateManager::builder()
+            .clock(Arc::new(clock.clone()))
+            .build()
+            .unwrap();
+        manager
+            .add_devices(vec![Device {
+                id: "RINCON_TRANSITION".to_string(),
+                name: "Den".to_string(),
+                room_name: "Den".to_string(),
+                ip_address: addr.ip().to_string(),
+                port: addr.port(),
+                model_name: "Sonos One".to_string(),
+                household_id: None,
+                secure: false,
+            }])
+            .unwrap();
+        let speaker_id = SpeakerId::new("RINCON_TRANSITION");
+        manager.register_watch(&speaker_id, PlaybackState::KEY);
+        manager.set_property(&speaker_id, PlaybackState::Paused);
+        manager.apply_local_write(&speaker_id, PlaybackState::Transitioning);
+        let iter = manager.iter();
+        assert_eq!(iter.try_iter().count(), 2);
+        (manager, speaker_id, clock)
+    }
+
+    #[test]
+    fn test_transition_confirmed_by_event_is_not_fetched() {
+        use crate::decoder::PropertyChange;
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let (manager, speaker_id, clock) = transition_fixture(mock.addr());
+        assert!(manager.transitions.is_pending(&speaker_id));
+
+        crate::event_worker::apply_speaker_changes(
+            &manager.store,
+            &manager.watched,
+            &manager.origins,
+            &manager.transitions,
+            &speaker_id,
+            &[PropertyChange::PlaybackState(PlaybackState::Playing)],
+        );
+        assert!(!manager.transitions.is_pending(&speaker_id));
+
+        clock.advance(DEFAULT_TRANSITION_TIMEOUT * 2);
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(mock.requests(), 0);
+        assert_eq!(
+            manager.get_property::<PlaybackState>(&speaker_id),
+            Some(PlaybackState::Playing)
+        );
+    }
+
+    #[test]
+    fn test_unconfirmed_transition_is_reconciled_from_device() {
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let mock = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let (manager, speaker_id, clock) = transition_fixture(mock.addr());
+        let iter = manager.iter();
+
+        // Nothing happens before the timeout
+        clock.advance(DEFAULT_TRANSITION_TIMEOUT - Duration::from_millis(1));
+        assert!(iter.recv_timeout(Duration::from_millis(100)).is_none());
+        assert_eq!(mock.requests(), 0);
+
+        clock.advance(Duration::from_millis(1));
+        let event = iter.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event.property_key, PlaybackState::KEY);
+        assert_eq!(event.origin, ChangeOrigin::Reconciled);
+        assert_eq!(mock.requests(), 1);
+        // The mock reports PLAYING
+        assert_eq!(
+            manager.get_property::<PlaybackState>(&speaker_id),
+            Some(PlaybackState::Playing)
+        );
+        assert!(!manager.transitions.is_pending(&speaker_id));
+    }
+
+    #[test]
+    fn test_unconfirmed_transition_reverts_when_fetch_fails() {
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let mock = MockDevice::start("127.0.0.1:0", Scenario::new().status(500, 1));
+        let (manager, speaker_id, clock) = transition_fixture(mock.addr());
+        let iter = manager.iter();
+
+        // A second write restarts the timeout but keeps the confirmed state
+        clock.advance(DEFAULT_TRANSITION_TIMEOUT / 2);
+        manager.apply_local_write(&speaker_id, PlaybackState::Transitioning);
+        clock.advance(DEFAULT_TRANSITION_TIMEOUT / 2);
+        assert!(iter.recv_timeout(Duration::from_millis(100)).is_none());
+
+        clock.advance(DEFAULT_TRANSITION_TIMEOUT / 2);
+        let reverted = iter.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(reverted.property_key, PlaybackState::KEY);
+        assert_eq!(reverted.origin, ChangeOrigin::Reconciled);
+        let diagnostic = iter.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(diagnostic.property_key, ChangeEvent::TRANSITION_TIMEOUT_KEY);
+        assert_eq!(diagnostic.speaker_id, speaker_id);
+        assert_eq!(diagnostic.origin, ChangeOrigin::Reconciled);
+        assert_eq!(mock.requests(), 1);
+        assert_eq!(
+            manager.get_property::<PlaybackState>(&speaker_id),
+            Some(PlaybackState::Paused)
+        );
+    }
+
+    #[test]
+    fn test_events_for_removed_speaker_are_dropped() {
+        let manager = StateManager::new().unwrap();
+        register(&manager, &["RINCON_A"]);
+        let speaker_id = SpeakerId::new("RINCON_A");
+        // Captured before removal, as if already in flight
+        let in_flight = SimulatedChange::Volume {
+            speaker: speaker_id.clone(),
+            volume: 30,
+        }
+        .to_event(&manager)
+        .unwrap();
+
+        assert!(manager.remove_speaker(&speaker_id));
+        assert!(!manager.remove_speaker(&speaker_id));
+
+        assert!(!manager.inject_event(&in_flight));
+        assert!(manager.speaker_info(&speaker_id).is_none());
+        assert_eq!(manager.get_property::<Volume>(&speaker_id), None);
+        assert_eq!(manager.speaker_count(), 0);
+        assert_eq!(manager.dropped_for_removed(), 1);
+
+        // Registering it again is what brings it back
+        register(&manager, &["RINCON_A"]);
+        assert!(manager.inject_event(&in_flight));
+        assert_eq!(
+            manager.get_property::<Volume>(&speaker_id),
+            Some(Volume(30))
+        );
+        assert_eq!(manager.dropped_for_removed(), 1);
+    }
+
+    #[test]
+    fn test_zone_name_event_renames_speaker() {
+        use sonos_api::services::device_properties::DevicePropertiesState;
+        use sonos_stream::events::EventData;
+
+        let manager = StateManager::new().unwrap();
+        register(&manager, &["RINCON_A"]);
+        manager.initialize(Topology::new(manager.speaker_infos(), vec![]));
+        let speaker_id = SpeakerId::new("RINCON_A");
+        let event = |zone_name: &str| {
+            EnrichedEvent::new(
+                RegistrationId::new(0),
+                manager.get_speaker_addr(&speaker_id).unwrap(),
+                Service::DeviceProperties,
+                EventSource::PollingDetection {
+                    poll_interval: Duration::ZERO,
+                },
+                EventData::DeviceProperties(
+                    DevicePropertiesState {
+                        zone_name: Some(zone_name.to_string()),
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+            )
+        };
+
+        assert!(manager.inject_event(&event(" Kids Room ")));
+        let info = manager.speaker_info(&speaker_id).unwrap();
+        assert_eq!(info.room_name, "Kids Room");
+        assert_eq!(info.name, "Kids Room");
+        let topology = manager.get_system_property::<Topology>().unwrap();
+        assert_eq!(topology.speakers[0].room_name, "Kids Room");
+
+        // A blank name is ignored; renaming locally to the same name is a no-op
+        manager.inject_event(&event(""));
+        assert_eq!(
+            manager.speaker_info(&speaker_id).unwrap().room_name,
+            "Kids Room"
+        );
+        assert!(!manager.set_room_name(&speaker_id, "Kids Room"));
+        assert!(manager.set_room_name(&speaker_id, "Study"));
+        assert_eq!(manager.speaker_info(&speaker_id).unwrap().name, "Study");
+        assert!(!manager.set_room_name(&SpeakerId::new("RINCON_B"), "Study"));
+
+        // Each rename is announced though nothing is watched
+        let renames = manager
+            .iter()
+            .try_iter()
+            .filter(|e| e.property_key == SpeakerInfo::RENAME_EVENT_KEY)
+            .count();
+        assert_eq!(renames, 2);
+    }
+
+    #[test]
+    fn test_startup_events_are_held_until_registration() {
+        let source = StateManager::new().unwrap();
+        register(&source, &["RINCON_A"]);
+        let speaker_id = SpeakerId::new("RINCON_A");
+        let event = SimulatedChange::Volume {
+            speaker: speaker_id.clone(),
+            volume: 12,
+        }
+        .to_event(&source)
+        .unwrap();
+
+        // Arrives before the speaker registers, so nothing is created yet
+        let manager = StateManager::new().unwrap();
+        assert!(!manager.inject_event(&event));
+        assert_eq!(manager.speaker_count(), 0);
+
+        register(&manager, &["RINCON_A"]);
+        assert_eq!(
+            manager.get_property::<Volume>(&speaker_id),
+            Some(Volume(12))
+        );
+
+        // After startup, events from unregistered addresses are dropped
+        manager.initialize(Topology::new(vec![], vec![]));
+        let stranger = StateManager::new().unwrap();
+        register(&stranger, &["RINCON_A", "RINCON_B"]);
+        let late = SimulatedChange::Volume {
+            speaker: SpeakerId::new("RINCON_B"),
+            volume: 40,
+        }
+        .to_event(&stranger)
+        .unwrap();
+        assert!(!manager.inject_event(&late));
+        register(&manager, &["RINCON_A", "RINCON_B"]);
+        assert_eq!(
+            manager.get_property::<Volume>(&SpeakerId::new("RINCON_B")),
+            None
+        );
+        assert_eq!(manager.dropped_for_removed(), 0);
+    }
+
+    #[cfg(feature = "chaos-testing")]
+    #[test]
+    fn test_chaos_delays_decoding() {
+        use crate::simulation::SimulatedChange;
+
+        let chaos = sonos_stream::ChaosController::new(1);
+        let manager = StateManager::builder().chaos(&chaos).build().unwrap();
+        register(&manager, &["RINCON_1"]);
+        let speaker = SpeakerId::new("RINCON_1");
+        let change = &SimulatedChange::volume_sweep(&speaker, 10, 11)[0];
+
+        chaos.decode_latency(Duration::from_millis(30));
+        let started = std::time::Instant::now();
+        assert!(manager.simulate(change).unwrap());
+        assert!(started.elapsed() >= Duration::from_millis(30));
+        assert_eq!(chaos.stats().decodes_delayed, 1);
+
+        chaos.clear();
+        assert!(manager.simulate(change).unwrap());
+        assert_eq!(chaos.stats().decodes_delayed, 1);
+    }
 }
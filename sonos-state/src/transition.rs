@@ -0,0 +1,263 @@
+//! Verification of unconfirmed `Transitioning` writes
+//!
+//! A local write of [`PlaybackState::Transitioning`] is normally replaced by
+//! the device's next AVTransport event. When the command failed silently no
+//! event comes, and the state would read `Transitioning` forever. Each such
+//! write schedules a check instead: unless an event for the speaker's
+//! playback state arrives first, `GetTransportInfo` is fetched once the
+//! timeout passes and the store is reconciled with the answer. If the fetch
+//! fails too, the last confirmed state is put back and a
+//! [`ChangeEvent::TRANSITION_TIMEOUT_KEY`] event reports it.
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::RwLock;
+use sonos_api::clock::SharedClock;
+use sonos_api::services::av_transport;
+use sonos_api::{Service, SonosClient};
+
+use crate::model::SpeakerId;
+use crate::origin::ChangeOrigin;
+use crate::property::{PlaybackState, Property};
+use crate::state::{ChangeEvent, ChangeSink, StateStore};
+
+/// How long a local `Transitioning` write may go unconfirmed
+pub const DEFAULT_TRANSITION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest the verifier sleeps between clock reads; the clock may be a
+/// manual one that moves without waking anybody
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+struct Pending {
+    deadline: Instant,
+    /// Playback state before the first unconfirmed write, put back if the
+    /// check fails
+    confirmed: Option<PlaybackState>,
+    /// Distinguishes this check from a later one for the same speaker
+    check: u64,
+}
+
+#[derive(Default)]
+struct Checks {
+    pending: HashMap<SpeakerId, Pending>,
+    next_check: u64,
+    /// Whether the verifier thread is running
+    running: bool,
+}
+
+/// Schedules and runs the checks for one state manager
+pub(crate) struct TransitionMonitor {
+    timeout: Duration,
+    clock: SharedClock,
+    client: SonosClient,
+    store: Arc<RwLock<StateStore>>,
+    watched: Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
+    sink: ChangeSink,
+    checks: Mutex<Checks>,
+    wake: Condvar,
+}
+
+impl TransitionMonitor {
+    pub(crate) fn new(
+        timeout: Duration,
+        clock: SharedClock,
+        client: SonosClient,
+        store: Arc<RwLock<StateStore>>,
+        watched: Arc<RwLock<HashSet<(SpeakerId, &'static str)>>>,
+        sink: ChangeSink,
+    ) -> Self {
+        Self {
+            timeout,
+            clock,
+            client,
+            store,
+            watched,
+            sink,
+            checks: Mutex::new(Checks::default()),
+            wake: Condvar::new(),
+        }
+    }
+
+    fn checks(&self) -> std::sync::MutexGuard<'_, Checks> {
+        self.checks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record a local write of `value`, replacing `previous`
+    ///
+    /// `Transitioning` (re)starts the speaker's check; any other value
+    /// settles it.
+    pub(crate) fn local_write(
+        self: &Arc<Self>,
+        speaker_id: &SpeakerId,
+        previous: Option<PlaybackState>,
+        value: &PlaybackState,
+    ) {
+        if *value != PlaybackState::Transitioning {
+            self.cancel(speaker_id);
+            return;
+        }
+        if self.timeout.is_zero() {
+            return;
+        }
+        let deadline = self.clock.now() + self.timeout;
+        let mut checks = self.checks();
+        checks.next_check += 1;
+        let check = checks.next_check;
+        let confirmed = match checks.pending.remove(speaker_id) {
+            Some(earlier) => earlier.confirmed,
+            None => previous.filter(|p| *p != PlaybackState::Transitioning),
+        };
+        checks.pending.insert(
+            speaker_id.clone(),
+            Pending {
+                deadline,
+                confirmed,
+                check,
+            },
+        );
+        if checks.running {
+            self.wake.notify_all();
+        } else {
+            checks.running = true;
+            let monitor = Arc::clone(self);
+            thread::spawn(move || monitor.run());
+        }
+    }
+
+    /// Drop the speaker's check: its playback state was confirmed by an
+    /// event or a fetch
+    pub(crate) fn cancel(&self, speaker_id: &SpeakerId) {
+        if self.checks().pending.remove(speaker_id).is_some() {
+            self.wake.notify_all();
+        }
+    }
+
+    /// Drop every check, for shutdown
+    pub(crate) fn clear(&self) {
+        self.checks().pending.clear();
+        self.wake.notify_all();
+    }
+
+    /// Whether a check is scheduled or running for the speaker
+    #[cfg(test)]
+    pub(crate) fn is_pending(&self, speaker_id: &SpeakerId) -> bool {
+        self.checks().pending.contains_key(speaker_id)
+    }
+
+    /// Verifier thread: runs due checks until none are left
+    fn run(&self) {
+        let mut checks = self.checks();
+        loop {
+            let now = self.clock.now();
+            let Some(deadline) = checks.pending.values().map(|p| p.deadline).min() else {
+                checks.running = false;
+                return;
+            };
+            if deadline > now {
+                let wait = (deadline - now).min(POLL_INTERVAL);
+                checks = self
+                    .wake
+                    .wait_timeout(checks, wait)
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0;
+                continue;
+            }
+            let due: Vec<(SpeakerId, u64)> = checks
+                .pending
+                .iter()
+                .filter(|(_, p)| p.deadline <= now)
+                .map(|(id, p)| (id.clone(), p.check))
+                .collect();
+            drop(checks);
+            for (speaker_id, check) in due {
+                self.verify(&speaker_id, check);
+            }
+            checks = self.checks();
+        }
+    }
+
+    /// Fetch the speaker's transport state and reconcile the store
+    fn verify(&self, speaker_id: &SpeakerId, check: u64) {
+        let addr = self
+            .store
+            .read()
+            .speaker(speaker_id)
+            .map(|s| s.socket_addr());
+        let fetched = match addr {
+            Some(addr) => self.fetch(addr),
+            None => Err("speaker unknown".to_string()),
+        };
+
+        // An event or newer write while the fetch ran takes precedence
+        let confirmed = {
+            let mut checks = self.checks();
+            match checks.pending.get(speaker_id) {
+                Some(p) if p.check == check => {}
+                _ => return,
+            }
+            checks.pending.remove(speaker_id).and_then(|p| p.confirmed)
+        };
+
+        match fetched {
+            Ok(state) => {
+                tracing::debug!(
+                    "Transitioning on {} unconfirmed, device reports {:?}",
+                    speaker_id.as_str(),
+                    state
+                );
+                self.store_reconciled(speaker_id, state);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Transitioning on {} unconfirmed and GetTransportInfo failed: {}; \
+                     reverting to {:?}",
+                    speaker_id.as_str(),
+                    e,
+                    confirmed
+                );
+                if let Some(state) = confirmed {
+                    self.store_reconciled(speaker_id, state);
+                }
+                self.sink.send(
+                    ChangeEvent::new(
+                        speaker_id.clone(),
+                        ChangeEvent::TRANSITION_TIMEOUT_KEY,
+                        Service::AVTransport,
+                    )
+                    .with_origin(ChangeOrigin::Reconciled),
+                );
+            }
+        }
+    }
+
+    fn fetch(&self, addr: SocketAddr) -> Result<PlaybackState, String> {
+        let op = av_transport::get_transport_info_operation()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let response = self
+            .client
+            .execute_enhanced(&addr.to_string(), op)
+            .map_err(|e| e.to_string())?;
+        Ok(PlaybackState::from_transport_state(
+            &response.current_transport_state,
+        ))
+    }
+
+    fn store_reconciled(&self, speaker_id: &SpeakerId, state: PlaybackState) {
+        let changed = self
+            .store
+            .write()
+            .set_tracked(speaker_id, state, ChangeOrigin::Reconciled);
+        let key = PlaybackState::KEY;
+        if changed && self.watched.read().contains(&(speaker_id.clone(), key)) {
+            self.sink.send(
+                ChangeEvent::new(speaker_id.clone(), key, Service::AVTransport)
+                    .with_origin(ChangeOrigin::Reconciled),
+            );
+        }
+    }
+}
@@ -6,7 +6,7 @@
 
 use sonos_api::Service;
 use sonos_stream::{BrokerConfig, EventBroker, EventData};
-use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::time::Duration;
 use tokio::time::timeout;
 
@@ -20,7 +20,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut broker = EventBroker::new(config).await?;
 
     // Example device IP - replace with your actual Sonos device
-    let device_ip: IpAddr = "192.168.1.100".parse()?;
+    let device_ip: SocketAddr = "192.168.1.100:1400".parse()?;
 
     println!("\n📋 Registering Sonos services...");
 
@@ -68,7 +68,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 );
                 println!(
                     "   Device: {} | Service: {:?} | Source: {}",
-                    event.speaker_ip,
+                    event.speaker_addr,
                     event.service,
                     format_event_source(&event.event_source)
                 );
@@ -76,24 +76,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 // Process different event types with async operations
                 match event.event_data {
                     EventData::AVTransport(transport_event) => {
-                        handle_transport_event_async(event.speaker_ip, transport_event).await;
+                        handle_transport_event_async(event.speaker_addr, transport_event).await;
                     }
                     EventData::RenderingControl(volume_event) => {
-                        handle_volume_event_async(event.speaker_ip, volume_event).await;
+                        handle_volume_event_async(event.speaker_addr, volume_event).await;
                     }
                     EventData::ZoneGroupTopology(topology) => {
-                        handle_topology_change_async(event.speaker_ip, topology).await;
+                        handle_topology_change_async(event.speaker_addr, topology).await;
                     }
                     EventData::DeviceProperties(device_event) => {
-                        handle_device_properties_async(event.speaker_ip, device_event).await;
+                        handle_device_properties_async(event.speaker_addr, device_event).await;
                     }
                     EventData::GroupManagement(gm_event) => {
-                        handle_group_management_async(event.speaker_ip, gm_event).await;
+                        handle_group_management_async(event.speaker_addr, gm_event).await;
                     }
                     EventData::GroupRenderingControl(grc_event) => {
                         println!(
                             "🔊 Group rendering control from {}: volume={:?}, mute={:?}",
-                            event.speaker_ip, grc_event.group_volume, grc_event.group_mute
+                            event.speaker_addr, grc_event.group_volume, grc_event.group_mute
                         );
                     }
                 }
@@ -133,7 +133,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 /// Handle transport events asynchronously
 async fn handle_transport_event_async(
-    device_ip: IpAddr,
+    device_ip: SocketAddr,
     transport_event: sonos_stream::events::types::AVTransportState,
 ) {
     println!("🎵 Processing transport event asynchronously...");
@@ -176,7 +176,7 @@ async fn handle_transport_event_async(
 
 /// Handle volume events asynchronously
 async fn handle_volume_event_async(
-    device_ip: IpAddr,
+    device_ip: SocketAddr,
     volume_event: sonos_stream::events::types::RenderingControlState,
 ) {
     println!("🔊 Processing volume event asynchronously...");
@@ -217,7 +217,7 @@ async fn handle_volume_event_async(
 
 /// Handle topology changes asynchronously
 async fn handle_topology_change_async(
-    device_ip: IpAddr,
+    device_ip: SocketAddr,
     topology: sonos_stream::events::types::ZoneGroupTopologyState,
 ) {
     println!("🏠 Processing topology change asynchronously...");
@@ -288,7 +288,7 @@ async fn handle_topology_change_async(
 
 /// Handle device properties events asynchronously
 async fn handle_device_properties_async(
-    device_ip: IpAddr,
+    device_ip: SocketAddr,
     device_event: sonos_stream::events::types::DevicePropertiesEvent,
 ) {
     println!("⚙️  Processing device properties event asynchronously...");
@@ -320,7 +320,7 @@ async fn handle_device_properties_async(
 
 /// Handle group management events asynchronously
 async fn handle_group_management_async(
-    device_ip: IpAddr,
+    device_ip: SocketAddr,
     gm_event: sonos_stream::events::types::GroupManagementState,
 ) {
     println!("🔗 Processing group management event asynchronously...");
@@ -357,7 +357,7 @@ async fn demonstrate_async_features(events: &mut sonos_stream::events::iterator:
         Ok(Some(event)) => {
             println!(
                 "  📨 Found buffered event: {} {:?}",
-                event.speaker_ip, event.service
+                event.speaker_addr, event.service
             );
         }
         Ok(None) => {
@@ -373,7 +373,7 @@ async fn demonstrate_async_features(events: &mut sonos_stream::events::iterator:
         Ok(Some(event)) => {
             println!(
                 "  📨 Received event within timeout: {} {:?}",
-                event.speaker_ip, event.service
+                event.speaker_addr, event.service
             );
         }
         Ok(None) => {
@@ -394,14 +394,14 @@ async fn demonstrate_async_features(events: &mut sonos_stream::events::iterator:
 }
 
 /// Simulate sending a notification to an external service
-async fn simulate_external_notification(event_type: &str, device_ip: IpAddr) {
+async fn simulate_external_notification(event_type: &str, device_ip: SocketAddr) {
     // Simulate async operation (e.g., HTTP request to webhook)
     tokio::time::sleep(Duration::from_millis(50)).await;
     println!("   📤 Sent '{event_type}' notification for device {device_ip}");
 }
 
 /// Simulate updating track information in external service
-async fn simulate_track_update(device_ip: IpAddr, track_uri: &str) {
+async fn simulate_track_update(device_ip: SocketAddr, track_uri: &str) {
     // Simulate async database update
     tokio::time::sleep(Duration::from_millis(100)).await;
     println!("   💾 Updated track database: {device_ip} -> {track_uri}");
@@ -409,7 +409,7 @@ async fn simulate_track_update(device_ip: IpAddr, track_uri: &str) {
 
 /// Simulate topology database update
 async fn simulate_topology_update(
-    device_ip: IpAddr,
+    device_ip: SocketAddr,
     topology: &sonos_stream::events::types::ZoneGroupTopologyState,
 ) {
     // Simulate async database update
@@ -423,7 +423,7 @@ async fn simulate_topology_update(
 
 /// Simulate device properties database update
 async fn simulate_device_update(
-    device_ip: IpAddr,
+    device_ip: SocketAddr,
     device_event: &sonos_stream::events::types::DevicePropertiesEvent,
 ) {
     // Simulate async database update
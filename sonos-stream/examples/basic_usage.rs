@@ -12,7 +12,7 @@ use sonos_api::services::av_transport::{
 use sonos_api::services::rendering_control::{GetVolumeOperation, GetVolumeOperationRequest};
 use sonos_api::{OperationBuilder, Service, SonosClient};
 use sonos_stream::{BrokerConfig, EventBroker, EventData, PollingReason};
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// Local transport state maintained by the consumer
 #[derive(Debug, Clone)]
@@ -73,7 +73,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .iter()
         .find(|d| d.model_name.contains("Playbar") || d.model_name.contains("Amp"))
         .unwrap_or(&devices[0]);
-    let device_ip: IpAddr = selected_device.ip_address.parse()?;
+    let device_ip = SocketAddr::new(
+        selected_device.ip_address.parse::<IpAddr>()?,
+        selected_device.port,
+    );
 
     println!(
         "\n🎯 Using device: {} ({}) at {}",
@@ -155,7 +158,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!(
             "📨 Event #{} received from {} ({})",
             event_count,
-            event.speaker_ip,
+            event.speaker_addr,
             format_event_source(&event.event_source)
         );
 
@@ -315,7 +318,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// This demonstrates how consumers should handle initial state population
 async fn query_initial_transport_state(
     client: &SonosClient,
-    device_ip: &IpAddr,
+    device_ip: &SocketAddr,
 ) -> Result<LocalTransportState, Box<dyn std::error::Error>> {
     // Get transport info using proper operation API
     let request = GetTransportInfoOperationRequest { instance_id: 0 };
@@ -334,7 +337,7 @@ async fn query_initial_transport_state(
 /// Query initial volume state directly from the device
 async fn query_initial_volume_state(
     client: &SonosClient,
-    device_ip: &IpAddr,
+    device_ip: &SocketAddr,
 ) -> Result<LocalVolumeState, Box<dyn std::error::Error>> {
     // Get volume using proper operation API
     let request = GetVolumeOperationRequest {
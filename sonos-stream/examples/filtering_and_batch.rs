@@ -12,7 +12,7 @@ use sonos_stream::{
     EventData, RegistrationResult,
 };
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::time::Duration;
 
 #[tokio::main]
@@ -24,8 +24,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut broker = EventBroker::new(BrokerConfig::resource_efficient()).await?;
 
     // Set up multiple devices and services for filtering demonstration
-    let device1: IpAddr = "192.168.1.100".parse()?;
-    let _device2: IpAddr = "192.168.1.101".parse()?; // Second device if available
+    let device1: SocketAddr = "192.168.1.100:1400".parse()?;
+    let _device2: SocketAddr = "192.168.1.101:1400".parse()?; // Second device if available
 
     println!("\n📋 Registering multiple services for filtering demonstration...");
 
@@ -115,7 +115,7 @@ async fn demonstrate_source_filtering(
             "📡 UPnP Event {}: {} from {} ({})",
             upnp_count,
             format_event_data(&event.event_data),
-            event.speaker_ip,
+            event.speaker_addr,
             format_event_source(&event.event_source)
         );
     }
@@ -205,7 +205,7 @@ async fn demonstrate_batch_processing(
         let mut devices_affected = std::collections::HashSet::new();
 
         for (i, event) in batch.iter().enumerate() {
-            devices_affected.insert(event.speaker_ip);
+            devices_affected.insert(event.speaker_addr);
 
             match &event.event_data {
                 EventData::AVTransport(_) => {
@@ -213,7 +213,7 @@ async fn demonstrate_batch_processing(
                     println!(
                         "   {}. 🎵 Transport event from {} ({})",
                         i + 1,
-                        event.speaker_ip,
+                        event.speaker_addr,
                         format_event_source(&event.event_source)
                     );
                 }
@@ -222,7 +222,7 @@ async fn demonstrate_batch_processing(
                     println!(
                         "   {}. 🔊 Volume event from {} ({})",
                         i + 1,
-                        event.speaker_ip,
+                        event.speaker_addr,
                         format_event_source(&event.event_source)
                     );
                 }
@@ -230,7 +230,7 @@ async fn demonstrate_batch_processing(
                     println!(
                         "   {}. 🏠 Topology event from {} ({} groups, {})",
                         i + 1,
-                        event.speaker_ip,
+                        event.speaker_addr,
                         topology.zone_groups.len(),
                         format_event_source(&event.event_source)
                     );
@@ -239,7 +239,7 @@ async fn demonstrate_batch_processing(
                     println!(
                         "   {}. ⚙️  Device properties event from {} ({})",
                         i + 1,
-                        event.speaker_ip,
+                        event.speaker_addr,
                         format_event_source(&event.event_source)
                     );
                 }
@@ -247,7 +247,7 @@ async fn demonstrate_batch_processing(
                     println!(
                         "   {}. 🔗 Group management event from {} ({})",
                         i + 1,
-                        event.speaker_ip,
+                        event.speaker_addr,
                         format_event_source(&event.event_source)
                     );
                 }
@@ -255,7 +255,7 @@ async fn demonstrate_batch_processing(
                     println!(
                         "   {}. 🔊 Group rendering control event from {} ({})",
                         i + 1,
-                        event.speaker_ip,
+                        event.speaker_addr,
                         format_event_source(&event.event_source)
                     );
                 }
@@ -297,7 +297,7 @@ async fn demonstrate_peek_functionality(
     match events.peek().await {
         Some(peeked_event) => {
             println!("👁️ Peeked at next event:");
-            println!("   Device: {}", peeked_event.speaker_ip);
+            println!("   Device: {}", peeked_event.speaker_addr);
             println!("   Service: {:?}", peeked_event.service);
             println!(
                 "   Source: {}",
@@ -322,7 +322,7 @@ async fn demonstrate_peek_functionality(
             println!("✅ Successfully consumed the same event");
             // We need to get the peeked registration ID from above, but since we're outside
             // the match now, let's just confirm we got an event
-            println!("   Consumed event from: {}", consumed_event.speaker_ip);
+            println!("   Consumed event from: {}", consumed_event.speaker_addr);
         }
         None => println!("❓ Event stream ended"),
     }
@@ -363,18 +363,18 @@ async fn demonstrate_multi_device_coordination(
     while start.elapsed() < Duration::from_secs(5) {
         match tokio::time::timeout(Duration::from_millis(300), events.next_async()).await {
             Ok(Some(event)) => {
-                let device_count = events_per_device.entry(event.speaker_ip).or_insert(0);
+                let device_count = events_per_device.entry(event.speaker_addr).or_insert(0);
                 *device_count += 1;
 
                 println!(
                     "📡 Event from {}: {:?} (total from this device: {})",
-                    event.speaker_ip, event.service, device_count
+                    event.speaker_addr, event.service, device_count
                 );
 
                 // Track device state changes
                 if let EventData::AVTransport(transport_event) = &event.event_data {
                     if let Some(ref state) = transport_event.transport_state {
-                        device_states.insert(event.speaker_ip, Some(state.clone()));
+                        device_states.insert(event.speaker_addr, Some(state.clone()));
 
                         // Check for synchronized playback
                         if device_states.len() > 1 {
@@ -9,7 +9,7 @@ use sonos_api::Service;
 use sonos_stream::{
     events::types::EventSource, BrokerConfig, EventBroker, EventData, PollingReason,
 };
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 
 #[tokio::main]
@@ -99,10 +99,10 @@ async fn test_firewall_scenario(
 
     // Register services and analyze the results
     let transport_reg = broker
-        .register_speaker_service(device_ip, Service::AVTransport)
+        .register_speaker_service(SocketAddr::new(device_ip, 1400), Service::AVTransport)
         .await?;
     let volume_reg = broker
-        .register_speaker_service(device_ip, Service::RenderingControl)
+        .register_speaker_service(SocketAddr::new(device_ip, 1400), Service::RenderingControl)
         .await?;
 
     // Analyze and report on the registration results
@@ -237,7 +237,7 @@ async fn monitor_events(
                         upnp_events += 1;
                         println!(
                             "    📡 UPnP Event #{}: {} {:?}",
-                            event_count, event.speaker_ip, event.service
+                            event_count, event.speaker_addr, event.service
                         );
                     }
                     EventSource::PollingDetection { poll_interval } => {
@@ -245,7 +245,7 @@ async fn monitor_events(
                         println!(
                             "    🔄 Polling Event #{}: {} {:?} ({}s interval)",
                             event_count,
-                            event.speaker_ip,
+                            event.speaker_addr,
                             event.service,
                             poll_interval.as_secs()
                         );
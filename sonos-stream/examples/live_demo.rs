@@ -51,9 +51,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Register every service on every speaker
     for device in &devices {
-        let ip = device.ip_address.parse()?;
+        let addr = std::net::SocketAddr::new(device.ip_address.parse()?, device.port);
         for &svc in SERVICES {
-            let reg = broker.register_speaker_service(ip, svc).await?;
+            let reg = broker.register_speaker_service(addr, svc).await?;
             let mode = if reg.polling_reason.is_some() {
                 "polling"
             } else {
@@ -63,10 +63,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // Build a name lookup: ip -> speaker name
-    let names: std::collections::HashMap<std::net::IpAddr, String> = devices
+    // Build a name lookup: address -> speaker name
+    let names: std::collections::HashMap<std::net::SocketAddr, String> = devices
         .iter()
-        .filter_map(|d| d.ip_address.parse().ok().map(|ip| (ip, d.name.clone())))
+        .filter_map(|d| {
+            let ip = d.ip_address.parse().ok()?;
+            Some((std::net::SocketAddr::new(ip, d.port), d.name.clone()))
+        })
         .collect();
 
     println!();
@@ -83,7 +86,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             Ok(Some(event)) => {
                 count += 1;
                 let speaker = names
-                    .get(&event.speaker_ip)
+                    .get(&event.speaker_addr)
                     .map(String::as_str)
                     .unwrap_or("unknown");
                 let source = match &event.event_source {
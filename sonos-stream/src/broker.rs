@@ -4,25 +4,35 @@
 //! the primary user interface for the sonos-stream crate. It coordinates subscription
 //! management, event processing, polling, and firewall detection.
 
-use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, Mutex};
 use tracing::{debug, error, info, warn};
 
 use callback_server::{
-    CallbackServer, FirewallDetectionConfig, FirewallDetectionCoordinator, FirewallStatus,
+    CallbackServer, ConnectionLimits, FirewallDetectionConfig, FirewallDetectionCoordinator,
+    FirewallStatus,
 };
-use sonos_api::Service;
+use sonos_api::{GroupId, Service};
 
 use crate::config::BrokerConfig;
-use crate::error::{BrokerError, BrokerResult};
-use crate::events::{iterator::EventIterator, processor::EventProcessor, types::EnrichedEvent};
-use crate::polling::scheduler::PollingScheduler;
+use crate::diagnostics::{ProtocolDiagnostics, ProtocolHistory};
+use crate::error::{BrokerError, BrokerResult, SubscriptionError};
+use crate::events::{
+    iterator::EventIterator,
+    processor::EventProcessor,
+    types::{EnrichedEvent, ZoneGroupTopologyState},
+};
+use crate::group::{self, Coordinator, GroupSubscriptionEvent, GroupTag, GroupTags};
+use crate::polling::scheduler::{PollingHealthEvent, PollingScheduler, TaskHealth};
 use crate::registry::{RegistrationId, SpeakerServicePair, SpeakerServiceRegistry};
+use crate::stable_id::StableIds;
 use crate::subscription::{
     event_detector::{EventDetector, PollingAction, PollingRequest},
-    manager::SubscriptionManager,
+    manager::{ManagedSubscriptionWrapper, SubscriptionManager},
+    registry::{DeliveryMode, SubscriptionInfo},
 };
 
 /// Result type for registration operations with enhanced feedback
@@ -68,6 +78,26 @@ impl std::fmt::Display for PollingReason {
     }
 }
 
+/// What [`EventBroker::suspend()`] does with live UPnP subscriptions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuspendPolicy {
+    /// Stop renewing and let subscriptions lapse on the device. Resume renews
+    /// any that are still alive and re-subscribes the rest.
+    KeepSubscriptions,
+    /// Unsubscribe everything now so devices stop sending NOTIFYs. Resume
+    /// re-subscribes every registration.
+    Unsubscribe,
+}
+
+/// A group subscription and the registration currently serving it
+struct GroupSubscription {
+    coordinator: Coordinator,
+    registration_id: RegistrationId,
+    /// False when the coordinator already had this registration of its own;
+    /// it is then left in place when the group subscription moves or ends
+    owned: bool,
+}
+
 /// Main EventBroker that coordinates all components
 pub struct EventBroker {
     /// Speaker/service registration registry
@@ -114,6 +144,31 @@ pub struct EventBroker {
 
     /// Polling request channel receiver (taken during background processing startup)
     polling_request_receiver: Option<mpsc::UnboundedReceiver<PollingRequest>>,
+
+    /// Active suspend policy; the lock serializes suspend/resume
+    suspension: Mutex<Option<SuspendPolicy>>,
+
+    /// Set while suspended so the renewal task skips its checks
+    renewals_paused: Arc<AtomicBool>,
+
+    /// Last UPnP `BOOTID` seen for each device, to notice reboots
+    boot_seqs: Mutex<HashMap<SocketAddr, u32>>,
+
+    /// Group coordinators in the last topology observed
+    group_coordinators: Mutex<HashMap<GroupId, Coordinator>>,
+
+    /// Group subscriptions; the lock serializes subscribing and migration
+    group_subscriptions: Mutex<HashMap<(GroupId, Service), GroupSubscription>>,
+
+    /// Groups of the registrations serving group subscriptions, read by the
+    /// event processor and polling scheduler to tag events
+    group_tags: Arc<GroupTags>,
+
+    /// Lifecycle events of group subscriptions
+    group_events: broadcast::Sender<GroupSubscriptionEvent>,
+
+    /// Stable IDs of speaker/service pairs, by speaker UUID
+    stable_ids: Arc<StableIds>,
 }
 
 /// Get the local IP address that can be reached by devices on the network
@@ -162,8 +217,31 @@ impl EventBroker {
         })?;
         let server_url = format!("http://{}:{}", local_ip, callback_server.port());
 
+        let stable_ids = Arc::new(match &config.stable_id_file {
+            Some(path) => StableIds::open(path),
+            None => StableIds::disabled(),
+        });
+
         // Initialize subscription manager with correct callback URL
-        let subscription_manager = Arc::new(SubscriptionManager::new(server_url.clone()));
+        let mut diagnostics = ProtocolDiagnostics::new(
+            config.protocol_history_size,
+            config.protocol_history_redaction,
+        )
+        .with_stable_ids(Arc::clone(&stable_ids));
+        if let Some(observer) = config.protocol_observer.clone() {
+            diagnostics = diagnostics.with_observer(observer);
+        }
+        let diagnostics = Arc::new(diagnostics);
+        let mut subscription_manager =
+            SubscriptionManager::with_diagnostics(server_url.clone(), diagnostics)
+                .with_clock(Arc::clone(&config.clock))
+                .with_stable_ids(Arc::clone(&stable_ids))
+                .with_chaos(config.chaos.clone())
+                .with_eventing_connection(config.eventing_connection);
+        if let Some(directory) = config.subscription_directory.clone() {
+            subscription_manager = subscription_manager.with_subscription_directory(directory);
+        }
+        let subscription_manager = Arc::new(subscription_manager);
 
         // Initialize firewall detection coordinator if enabled
         let firewall_coordinator = if config.enable_proactive_firewall_detection {
@@ -186,22 +264,6 @@ impl EventBroker {
             None
         };
 
-        // Initialize event processor with the correct subscription manager and firewall coordinator
-        let event_processor = Arc::new(EventProcessor::new(
-            Arc::clone(&subscription_manager),
-            event_sender.clone(),
-            firewall_coordinator.clone(),
-        ));
-
-        // Initialize polling scheduler
-        let polling_scheduler = Arc::new(PollingScheduler::new(
-            event_sender.clone(),
-            config.base_polling_interval,
-            config.max_polling_interval,
-            config.adaptive_polling,
-            config.max_concurrent_polls,
-        ));
-
         // Create polling request channel (sender kept alive for EventDetector)
         let (polling_request_sender, polling_request_receiver) = mpsc::unbounded_channel();
 
@@ -212,8 +274,38 @@ impl EventBroker {
             event_detector.set_firewall_coordinator(Arc::clone(coordinator));
         }
         event_detector.set_polling_request_sender(polling_request_sender);
+        event_detector.set_clock(Arc::clone(&config.clock));
         let event_detector = Arc::new(event_detector);
 
+        // Initialize event processor with the correct subscription manager and firewall coordinator
+        let group_tags = Arc::new(GroupTags::default());
+        let event_processor = Arc::new(
+            EventProcessor::new(
+                Arc::clone(&subscription_manager),
+                event_sender.clone(),
+                firewall_coordinator.clone(),
+            )
+            .with_group_tags(Arc::clone(&group_tags))
+            .with_stable_ids(Arc::clone(&stable_ids))
+            .with_event_detector(Arc::clone(&event_detector))
+            .with_chaos(config.chaos.clone()),
+        );
+
+        // Initialize polling scheduler
+        let polling_scheduler = Arc::new(
+            PollingScheduler::new(
+                event_sender.clone(),
+                config.base_polling_interval,
+                config.max_polling_interval,
+                config.adaptive_polling,
+                config.max_concurrent_polls,
+            )
+            .with_watchdog(config.polling_watchdog.clone())
+            .with_clock(Arc::clone(&config.clock))
+            .with_group_tags(Arc::clone(&group_tags))
+            .with_stable_ids(Arc::clone(&stable_ids)),
+        );
+
         let mut broker = Self {
             registry,
             subscription_manager,
@@ -230,6 +322,14 @@ impl EventBroker {
             upnp_receiver: Some(upnp_receiver),
             event_router: Some(event_router),
             polling_request_receiver: Some(polling_request_receiver),
+            suspension: Mutex::new(None),
+            renewals_paused: Arc::new(AtomicBool::new(false)),
+            boot_seqs: Mutex::new(HashMap::new()),
+            group_coordinators: Mutex::new(HashMap::new()),
+            group_subscriptions: Mutex::new(HashMap::new()),
+            group_tags,
+            group_events: broadcast::channel(64).0,
+            stable_ids,
         };
 
         // Start background processing
@@ -245,9 +345,14 @@ impl EventBroker {
         config: &BrokerConfig,
         event_sender: mpsc::UnboundedSender<callback_server::router::NotificationPayload>,
     ) -> BrokerResult<Arc<CallbackServer>> {
-        let server = CallbackServer::new(config.callback_port_range, event_sender)
-            .await
-            .map_err(|e| BrokerError::CallbackServer(e.to_string()))?;
+        let server = CallbackServer::with_options(
+            config.callback_port_range,
+            event_sender,
+            ConnectionLimits::default(),
+            config.callback_port_selection.clone(),
+        )
+        .await
+        .map_err(|e| BrokerError::CallbackServer(e.to_string()))?;
 
         Ok(Arc::new(server))
     }
@@ -261,7 +366,7 @@ impl EventBroker {
         // Count how many are for this device IP
         let existing_count = registered_pairs
             .iter()
-            .filter(|(_, pair)| pair.speaker_ip == device_ip)
+            .filter(|(_, pair)| pair.speaker_addr.ip() == device_ip)
             .count();
 
         // If there are no existing pairs for this device, it will be the first
@@ -315,7 +420,7 @@ impl EventBroker {
                 match request.action {
                     PollingAction::Start => {
                         debug!(
-                            speaker_ip = %request.speaker_service_pair.speaker_ip,
+                            speaker_addr = %request.speaker_service_pair.speaker_addr,
                             service = ?request.speaker_service_pair.service,
                             reason = ?request.reason,
                             registration_id = %request.registration_id,
@@ -331,24 +436,20 @@ impl EventBroker {
                         {
                             error!(
                                 registration_id = %request.registration_id,
-                                speaker_ip = %request.speaker_service_pair.speaker_ip,
+                                speaker_addr = %request.speaker_service_pair.speaker_addr,
                                 service = ?request.speaker_service_pair.service,
                                 error = %e,
                                 "Failed to start polling"
                             );
                         } else {
                             // Mark polling as active in subscription
-                            if let Some(subscription) = subscription_manager
-                                .get_subscription(request.registration_id)
-                                .await
-                            {
-                                subscription.set_polling_active(true);
-                            }
+                            subscription_manager
+                                .set_delivery(request.registration_id, DeliveryMode::Polling);
                         }
                     }
                     PollingAction::Stop => {
                         debug!(
-                            speaker_ip = %request.speaker_service_pair.speaker_ip,
+                            speaker_addr = %request.speaker_service_pair.speaker_addr,
                             service = ?request.speaker_service_pair.service,
                             registration_id = %request.registration_id,
                             "Stopping polling for speaker service"
@@ -360,19 +461,15 @@ impl EventBroker {
                         {
                             error!(
                                 registration_id = %request.registration_id,
-                                speaker_ip = %request.speaker_service_pair.speaker_ip,
+                                speaker_addr = %request.speaker_service_pair.speaker_addr,
                                 service = ?request.speaker_service_pair.service,
                                 error = %e,
                                 "Failed to stop polling"
                             );
                         } else {
                             // Mark polling as inactive in subscription
-                            if let Some(subscription) = subscription_manager
-                                .get_subscription(request.registration_id)
-                                .await
-                            {
-                                subscription.set_polling_active(false);
-                            }
+                            subscription_manager
+                                .set_delivery(request.registration_id, DeliveryMode::Events);
                         }
                     }
                 }
@@ -388,14 +485,36 @@ impl EventBroker {
     async fn start_subscription_renewal_monitoring(&mut self) {
         let subscription_manager = Arc::clone(&self.subscription_manager);
         let renewal_threshold = self.config.renewal_threshold;
+        let renewals_paused = Arc::clone(&self.renewals_paused);
+        let event_router = self.event_router.clone();
+        let clock = Arc::clone(&self.config.clock);
 
         let task = tokio::spawn(async move {
             info!("Starting subscription renewal monitoring");
 
-            let mut interval = tokio::time::interval(renewal_threshold / 2); // Check twice as often as threshold
+            let period = renewal_threshold / 2; // Check twice as often as threshold
+            let mut next_check = clock.now();
 
             loop {
-                interval.tick().await;
+                clock.timer(next_check).await;
+                next_check = clock.now() + period;
+                let gap = subscription_manager.note_renewal_check(period).await;
+                if renewals_paused.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                // After a sleep, local expiry says nothing about what the
+                // device still holds: renew everything instead of trusting it
+                if let Some(gap) = gap {
+                    warn!(gap = ?gap, "Renewal check ran late (host asleep?), revalidating subscriptions");
+                    for (old_sid, new_sid) in subscription_manager.revalidate_all().await {
+                        if let Some(router) = &event_router {
+                            router.unregister(&old_sid).await;
+                            router.register(new_sid).await;
+                        }
+                    }
+                    continue;
+                }
 
                 match subscription_manager.check_renewals().await {
                     Ok(renewed_count) => {
@@ -410,6 +529,12 @@ impl EventBroker {
                         );
                     }
                 }
+                for (old_sid, new_sid) in subscription_manager.take_replaced() {
+                    if let Some(router) = &event_router {
+                        router.unregister(&old_sid).await;
+                        router.register(new_sid).await;
+                    }
+                }
             }
         });
 
@@ -419,18 +544,18 @@ impl EventBroker {
     /// Register a speaker/service pair for event streaming
     pub async fn register_speaker_service(
         &self,
-        speaker_ip: IpAddr,
+        speaker_addr: SocketAddr,
         service: Service,
     ) -> BrokerResult<RegistrationResult> {
         debug!(
-            speaker_ip = %speaker_ip,
+            speaker_addr = %speaker_addr,
             service = ?service,
             "Registering speaker service"
         );
 
         // Check for duplicates and register
-        let registration_id = self.registry.register(speaker_ip, service).await?;
-        let was_duplicate = self.registry.is_registered(speaker_ip, service).await;
+        let registration_id = self.registry.register(speaker_addr, service).await?;
+        let was_duplicate = self.registry.is_registered(speaker_addr, service).await;
 
         if was_duplicate {
             debug!(
@@ -439,7 +564,21 @@ impl EventBroker {
             );
         }
 
-        let pair = SpeakerServicePair::new(speaker_ip, service);
+        // While suspended, only record the registration; resume() subscribes it
+        if self.suspension.lock().await.is_some() {
+            debug!(
+                registration_id = %registration_id,
+                "Broker suspended, deferring subscription until resume"
+            );
+            return Ok(RegistrationResult {
+                registration_id,
+                firewall_status: FirewallStatus::Unknown,
+                polling_reason: None,
+                was_duplicate,
+            });
+        }
+
+        let pair = SpeakerServicePair::new(speaker_addr, service);
 
         let mut polling_reason = None;
         let firewall_status;
@@ -448,7 +587,7 @@ impl EventBroker {
             // Force polling mode: skip UPnP subscription entirely, go straight to polling
             debug!(
                 registration_id = %registration_id,
-                speaker_ip = %speaker_ip,
+                speaker_addr = %speaker_addr,
                 service = ?service,
                 "Force polling mode: skipping UPnP subscription"
             );
@@ -477,18 +616,20 @@ impl EventBroker {
             // Normal mode: attempt UPnP subscription with firewall detection
 
             // Check if this is the first subscription for this device
-            let is_first_for_device = self.is_first_subscription_for_device(speaker_ip).await;
+            let is_first_for_device = self
+                .is_first_subscription_for_device(speaker_addr.ip())
+                .await;
 
             // Get or trigger firewall detection for this device
             firewall_status = if let Some(coordinator) = &self.firewall_coordinator {
                 if is_first_for_device {
                     debug!(
-                        speaker_ip = %speaker_ip,
+                        speaker_addr = %speaker_addr,
                         "First subscription for device, triggering firewall detection"
                     );
-                    coordinator.on_first_subscription(speaker_ip).await
+                    coordinator.on_first_subscription(speaker_addr.ip()).await
                 } else {
-                    coordinator.get_device_status(speaker_ip).await
+                    coordinator.get_device_status(speaker_addr.ip()).await
                 }
             } else {
                 FirewallStatus::Unknown
@@ -517,6 +658,7 @@ impl EventBroker {
                             "Registered subscription with EventRouter"
                         );
                     }
+                    self.resubscribe_retired().await;
 
                     // Register with event detector for timeout monitoring
                     self.event_detector
@@ -543,7 +685,8 @@ impl EventBroker {
                                 "Failed to start immediate polling"
                             );
                         } else {
-                            subscription.set_polling_active(true);
+                            self.subscription_manager
+                                .set_delivery(registration_id, DeliveryMode::Polling);
                             debug!(
                                 registration_id = %registration_id,
                                 reason = ?request.reason,
@@ -552,6 +695,13 @@ impl EventBroker {
                         }
                     }
                 }
+                Err(e @ SubscriptionError::AlreadySubscribedExternally { .. }) => {
+                    // Polling alongside the other subscription would deliver
+                    // its events twice too
+                    warn!(registration_id = %registration_id, error = %e, "Speaker/service pair is subscribed elsewhere");
+                    let _ = self.registry.unregister(registration_id).await;
+                    return Err(e.into());
+                }
                 Err(e) => {
                     error!(
                         registration_id = %registration_id,
@@ -645,7 +795,7 @@ impl EventBroker {
         let removed_pair = self.registry.unregister(registration_id).await?;
 
         debug!(
-            speaker_ip = %pair.speaker_ip,
+            speaker_addr = %pair.speaker_addr,
             service = ?pair.service,
             registration_id = %registration_id,
             "Unregistration completed"
@@ -654,6 +804,497 @@ impl EventBroker {
         Ok(removed_pair)
     }
 
+    /// Suspend all event activity, e.g. when the host goes to sleep.
+    ///
+    /// Stops renewals, polling and event-timeout monitoring. With
+    /// [`SuspendPolicy::Unsubscribe`] every subscription is also cancelled.
+    /// Registrations are kept so [`resume()`](Self::resume) can restore them.
+    ///
+    /// Returns `false` if the broker was already suspended.
+    pub async fn suspend(&self, policy: SuspendPolicy) -> BrokerResult<bool> {
+        let mut suspension = self.suspension.lock().await;
+        if suspension.is_some() {
+            return Ok(false);
+        }
+        info!(policy = ?policy, "Suspending EventBroker");
+
+        self.renewals_paused.store(true, Ordering::Relaxed);
+        if let Err(e) = self.polling_scheduler.shutdown_all().await {
+            warn!(error = %e, "Error stopping polling during suspend");
+        }
+
+        for (registration_id, _pair) in self.registry.list_registrations().await {
+            self.event_detector
+                .unregister_subscription(registration_id)
+                .await;
+            if let Some(subscription) = self
+                .subscription_manager
+                .get_subscription(registration_id)
+                .await
+            {
+                self.subscription_manager
+                    .set_delivery(registration_id, DeliveryMode::Events);
+                if policy == SuspendPolicy::Unsubscribe {
+                    self.drop_subscription(registration_id, &subscription).await;
+                }
+            }
+        }
+
+        *suspension = Some(policy);
+        Ok(true)
+    }
+
+    /// Resume after [`suspend()`](Self::suspend).
+    ///
+    /// Every registration ends up with exactly one live subscription: kept
+    /// subscriptions are renewed, and any that fail to renew (expired, or the
+    /// device restarted) are replaced. Registrations that cannot subscribe
+    /// fall back to polling, as in
+    /// [`register_speaker_service()`](Self::register_speaker_service).
+    ///
+    /// Returns `false` if the broker was not suspended.
+    pub async fn resume(&self) -> BrokerResult<bool> {
+        let mut suspension = self.suspension.lock().await;
+        let Some(policy) = suspension.take() else {
+            return Ok(false);
+        };
+        info!(policy = ?policy, "Resuming EventBroker");
+
+        for (registration_id, pair) in self.registry.list_registrations().await {
+            if self.config.force_polling_mode {
+                if let Err(e) = self
+                    .polling_scheduler
+                    .start_polling(registration_id, pair.clone())
+                    .await
+                {
+                    error!(registration_id = %registration_id, error = %e, "Failed to restart forced polling");
+                }
+                continue;
+            }
+
+            if let Some(subscription) = self
+                .subscription_manager
+                .get_subscription(registration_id)
+                .await
+            {
+                if self
+                    .subscription_manager
+                    .renew_subscription(&subscription)
+                    .await
+                    .is_ok()
+                {
+                    debug!(registration_id = %registration_id, "Kept subscription across suspend");
+                    self.event_detector
+                        .register_subscription(registration_id, pair)
+                        .await;
+                    continue;
+                }
+                debug!(registration_id = %registration_id, "Subscription lapsed during suspend, replacing");
+                self.drop_subscription(registration_id, &subscription).await;
+            }
+            self.resubscribe(registration_id, pair).await;
+        }
+
+        self.renewals_paused.store(false, Ordering::Relaxed);
+        Ok(true)
+    }
+
+    /// Returns true while the broker is suspended
+    pub async fn is_suspended(&self) -> bool {
+        self.suspension.lock().await.is_some()
+    }
+
+    /// Cancel a subscription and stop routing its SID (best effort)
+    async fn drop_subscription(
+        &self,
+        registration_id: RegistrationId,
+        subscription: &ManagedSubscriptionWrapper,
+    ) {
+        if let Some(router) = &self.event_router {
+            router.unregister(subscription.subscription_id()).await;
+        }
+        if let Err(e) = self
+            .subscription_manager
+            .remove_subscription(registration_id)
+            .await
+        {
+            debug!(registration_id = %registration_id, error = %e, "Unsubscribe failed (device may have dropped it already)");
+        }
+    }
+
+    /// Create a fresh subscription for an existing registration, falling
+    /// back to polling if the device refuses it.
+    async fn resubscribe(&self, registration_id: RegistrationId, pair: SpeakerServicePair) {
+        match self
+            .subscription_manager
+            .create_subscription(registration_id, pair.clone())
+            .await
+        {
+            Ok(subscription) => {
+                if let Some(router) = &self.event_router {
+                    router
+                        .register(subscription.subscription_id().to_string())
+                        .await;
+                }
+                self.resubscribe_retired().await;
+                self.event_detector
+                    .register_subscription(registration_id, pair.clone())
+                    .await;
+                if self
+                    .event_detector
+                    .evaluate_firewall_status(registration_id, &pair)
+                    .await
+                    .is_some()
+                    && self
+                        .polling_scheduler
+                        .start_polling(registration_id, pair)
+                        .await
+                        .is_ok()
+                {
+                    self.subscription_manager
+                        .set_delivery(registration_id, DeliveryMode::Polling);
+                }
+            }
+            Err(e) => {
+                warn!(
+                    registration_id = %registration_id,
+                    error = %e,
+                    "Failed to re-subscribe, falling back to polling"
+                );
+                if let Err(e) = self
+                    .polling_scheduler
+                    .start_polling(registration_id, pair)
+                    .await
+                {
+                    error!(registration_id = %registration_id, error = %e, "Failed to start fallback polling");
+                }
+            }
+        }
+    }
+
+    /// Resubscribe registrations whose SID their device reissued to a new
+    /// subscription (see [`SubscriptionManager::take_retired()`])
+    ///
+    /// The SID is unregistered from the router once, for the retired holder;
+    /// the new holder keeps its own registration of it.
+    async fn resubscribe_retired(&self) {
+        loop {
+            let retired = self.subscription_manager.take_retired();
+            if retired.is_empty() {
+                return;
+            }
+            for record in retired {
+                let stale = record.subscription();
+                if let Some(router) = &self.event_router {
+                    router.unregister(stale.subscription_id()).await;
+                }
+                let registration_id = stale.registration_id();
+                if self.registry.get_pair(registration_id).await.is_none() {
+                    continue;
+                }
+                if record.delivery() == DeliveryMode::Polling {
+                    let _ = self.polling_scheduler.stop_polling(registration_id).await;
+                }
+                // resubscribe() drains retirements of its own; boxed because
+                // that makes it recursive
+                Box::pin(self.resubscribe(registration_id, stale.speaker_service_pair().clone()))
+                    .await;
+            }
+        }
+    }
+
+    /// Note the UPnP `BOOTID` a device reported, e.g. in an SSDP response or
+    /// its topology
+    ///
+    /// The first value seen for a device is only recorded. A different value
+    /// later means the device rebooted and forgot every subscription, and
+    /// may reissue their SIDs to new ones: all its subscriptions are dropped
+    /// without UNSUBSCRIBE and replaced (on [`resume()`](Self::resume) when
+    /// suspended).
+    ///
+    /// Returns `true` when a reboot was detected.
+    pub async fn observe_boot_seq(&self, device: SocketAddr, boot_seq: u32) -> bool {
+        let previous = self.boot_seqs.lock().await.insert(device, boot_seq);
+        if previous.map_or(true, |previous| previous == boot_seq) {
+            return false;
+        }
+        info!(
+            device = %device,
+            previous = ?previous,
+            boot_seq,
+            "Device rebooted, replacing its subscriptions"
+        );
+
+        let suspension = self.suspension.lock().await;
+        for record in self.subscription_manager.retire_device(device) {
+            let stale = record.subscription();
+            if let Some(router) = &self.event_router {
+                router.unregister(stale.subscription_id()).await;
+            }
+            if suspension.is_some() {
+                continue;
+            }
+            let registration_id = stale.registration_id();
+            if record.delivery() == DeliveryMode::Polling {
+                let _ = self.polling_scheduler.stop_polling(registration_id).await;
+            }
+            if let Some(pair) = self.registry.get_pair(registration_id).await {
+                self.resubscribe(registration_id, pair).await;
+            }
+        }
+        true
+    }
+
+    /// Subscribe to `service` on whichever speaker coordinates `group_id`,
+    /// following coordinator handoffs
+    ///
+    /// The coordinator comes from `BrokerConfig::group_resolver` or, failing
+    /// that, the last topology passed to
+    /// [`observe_topology()`](Self::observe_topology). Events carry the group
+    /// in [`EnrichedEvent::group`]. When the coordinator changes, the old one
+    /// is unsubscribed, the new one subscribed, and
+    /// [`GroupSubscriptionEvent::Migrated`] is sent to
+    /// [`group_events()`](Self::group_events).
+    ///
+    /// Subscribing to the same group and service again returns the existing
+    /// registration.
+    pub async fn subscribe_group(
+        &self,
+        group_id: &GroupId,
+        service: Service,
+    ) -> BrokerResult<RegistrationResult> {
+        let mut groups = self.group_subscriptions.lock().await;
+        let key = (group_id.clone(), service);
+        if let Some(existing) = groups.get(&key) {
+            return Ok(RegistrationResult {
+                registration_id: existing.registration_id,
+                firewall_status: self
+                    .get_device_firewall_status(existing.coordinator.addr.ip())
+                    .await,
+                polling_reason: None,
+                was_duplicate: true,
+            });
+        }
+
+        let coordinator = self
+            .resolve_coordinator(group_id)
+            .await
+            .ok_or_else(|| BrokerError::UnknownGroup(group_id.clone()))?;
+        debug!(
+            group_id = %group_id,
+            service = ?service,
+            coordinator = %coordinator.id,
+            "Subscribing to group"
+        );
+        let (subscription, result) = self
+            .subscribe_coordinator(group_id, service, coordinator)
+            .await?;
+        groups.insert(key, subscription);
+        Ok(result)
+    }
+
+    /// End a subscription made with [`subscribe_group()`](Self::subscribe_group)
+    ///
+    /// Returns `false` if there was none.
+    pub async fn unsubscribe_group(
+        &self,
+        group_id: &GroupId,
+        service: Service,
+    ) -> BrokerResult<bool> {
+        let mut groups = self.group_subscriptions.lock().await;
+        let Some(subscription) = groups.remove(&(group_id.clone(), service)) else {
+            return Ok(false);
+        };
+        self.release_group_registration(&subscription).await?;
+        Ok(true)
+    }
+
+    /// Note that the speaker with UUID `uuid` (`RINCON_...`) is at `addr`
+    ///
+    /// Stable IDs are kept per speaker UUID, so pairs at `addr` have none
+    /// until its speaker is identified, here or by
+    /// [`observe_topology()`](Self::observe_topology).
+    pub fn identify_speaker(&self, addr: SocketAddr, uuid: &str) {
+        self.stable_ids.identify(addr, uuid);
+    }
+
+    /// Note the group coordinators and speaker addresses in a
+    /// ZoneGroupTopology state and move group subscriptions whose
+    /// coordinator changed
+    ///
+    /// A group missing from the topology keeps its subscription. Returns how
+    /// many subscriptions were moved.
+    pub async fn observe_topology(&self, topology: &ZoneGroupTopologyState) -> usize {
+        for (uuid, addr) in group::members(topology) {
+            self.stable_ids.identify(addr, uuid);
+        }
+        *self.group_coordinators.lock().await = group::coordinators(topology);
+        self.refresh_groups().await
+    }
+
+    /// Resolve the coordinator of every group subscription again and move
+    /// those whose coordinator changed, e.g. when `BrokerConfig::group_resolver`
+    /// answers differently
+    ///
+    /// A failed move is retried by the next call. Returns how many
+    /// subscriptions were moved.
+    pub async fn refresh_groups(&self) -> usize {
+        let mut groups = self.group_subscriptions.lock().await;
+        let mut migrated = 0;
+        for ((group_id, service), subscription) in groups.iter_mut() {
+            let Some(coordinator) = self.resolve_coordinator(group_id).await else {
+                continue;
+            };
+            if coordinator != subscription.coordinator
+                && self
+                    .migrate_group(group_id, *service, subscription, coordinator)
+                    .await
+            {
+                migrated += 1;
+            }
+        }
+        migrated
+    }
+
+    /// Receive lifecycle events of group subscriptions, such as a move to a
+    /// new coordinator
+    pub fn group_events(&self) -> broadcast::Receiver<GroupSubscriptionEvent> {
+        self.group_events.subscribe()
+    }
+
+    /// Current coordinator of a group: the configured resolver's answer, or
+    /// else the observed topology's
+    async fn resolve_coordinator(&self, group_id: &GroupId) -> Option<Coordinator> {
+        let resolved = self
+            .config
+            .group_resolver
+            .as_ref()
+            .and_then(|resolver| resolver.resolve(group_id));
+        match resolved {
+            Some(coordinator) => Some(coordinator),
+            None => self.group_coordinators.lock().await.get(group_id).cloned(),
+        }
+    }
+
+    /// Register `service` on a group's coordinator
+    ///
+    /// The registration is tagged with the group before subscribing, so the
+    /// initial event carries it. A registration the coordinator already had
+    /// is shared rather than subscribed twice.
+    async fn subscribe_coordinator(
+        &self,
+        group_id: &GroupId,
+        service: Service,
+        coordinator: Coordinator,
+    ) -> BrokerResult<(GroupSubscription, RegistrationResult)> {
+        let owned = !self.registry.is_registered(coordinator.addr, service).await;
+        let registration_id = self.registry.register(coordinator.addr, service).await?;
+        self.group_tags.set(
+            registration_id,
+            GroupTag {
+                group_id: group_id.clone(),
+                coordinator: coordinator.id.clone(),
+            },
+        );
+
+        let result = if owned {
+            self.register_speaker_service(coordinator.addr, service)
+                .await
+                .inspect_err(|_| self.group_tags.remove(registration_id))?
+        } else {
+            RegistrationResult {
+                registration_id,
+                firewall_status: self.get_device_firewall_status(coordinator.addr.ip()).await,
+                polling_reason: None,
+                was_duplicate: true,
+            }
+        };
+        let subscription = GroupSubscription {
+            coordinator,
+            registration_id,
+            owned,
+        };
+        Ok((subscription, result))
+    }
+
+    /// Stop tagging a group subscription's registration and remove it if the
+    /// group subscription created it
+    async fn release_group_registration(
+        &self,
+        subscription: &GroupSubscription,
+    ) -> BrokerResult<()> {
+        self.group_tags.remove(subscription.registration_id);
+        if subscription.owned {
+            self.unregister_speaker_service(subscription.registration_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Move a group subscription to a new coordinator: unsubscribe the old
+    /// one, then subscribe the new one. Returns whether it succeeded.
+    async fn migrate_group(
+        &self,
+        group_id: &GroupId,
+        service: Service,
+        subscription: &mut GroupSubscription,
+        to: Coordinator,
+    ) -> bool {
+        let from = subscription.coordinator.clone();
+        info!(
+            group_id = %group_id,
+            service = ?service,
+            from = %from.id,
+            to = %to.id,
+            "Group coordinator changed, moving subscription"
+        );
+
+        if let Err(e) = self.release_group_registration(subscription).await {
+            warn!(group_id = %group_id, error = %e, "Failed to unsubscribe previous coordinator");
+        }
+        // Should the new subscription fail, there is nothing left to release
+        subscription.owned = false;
+        let event = match self
+            .subscribe_coordinator(group_id, service, to.clone())
+            .await
+        {
+            Ok((next, _)) => {
+                let registration_id = next.registration_id;
+                *subscription = next;
+                GroupSubscriptionEvent::Migrated {
+                    group_id: group_id.clone(),
+                    service,
+                    from,
+                    to,
+                    registration_id,
+                }
+            }
+            Err(e) => {
+                warn!(group_id = %group_id, service = ?service, error = %e, "Failed to move group subscription");
+                GroupSubscriptionEvent::MigrationFailed {
+                    group_id: group_id.clone(),
+                    service,
+                    from,
+                    to,
+                    error: e.to_string(),
+                }
+            }
+        };
+        let migrated = matches!(event, GroupSubscriptionEvent::Migrated { .. });
+        let _ = self.group_events.send(event);
+        migrated
+    }
+
+    /// Turn every NOTIFY the callback server has already acknowledged into an
+    /// event, returning how many were queued
+    ///
+    /// Call before unregistering during shutdown: a notification processed
+    /// after its subscription is removed no longer resolves to a speaker and
+    /// is dropped.
+    pub async fn flush_notifications(&self) -> usize {
+        self.event_processor.flush_upnp().await
+    }
+
     /// Get an event iterator for consuming events
     /// This consumes the broker's event receiver, so it can only be called once
     pub fn event_iterator(&mut self) -> BrokerResult<EventIterator> {
@@ -685,6 +1326,43 @@ impl EventBroker {
         }
     }
 
+    /// Health of every active polling task: last run and success, consecutive
+    /// failures, average poll duration and suspension
+    pub async fn polling_health(&self) -> Vec<TaskHealth> {
+        self.polling_scheduler.task_health().await
+    }
+
+    /// Receive an event whenever a polling task is suspended after repeated
+    /// failures, or resumes
+    pub fn polling_health_events(&self) -> broadcast::Receiver<PollingHealthEvent> {
+        self.polling_scheduler.health_events()
+    }
+
+    /// Recent SUBSCRIBE exchanges and NOTIFY receipts for a speaker/service
+    ///
+    /// Returns `None` when nothing was recorded, including when
+    /// `BrokerConfig::protocol_history_size` is 0 (the default).
+    pub fn protocol_history(
+        &self,
+        speaker_addr: SocketAddr,
+        service: Service,
+    ) -> Option<ProtocolHistory> {
+        self.subscription_manager
+            .diagnostics()
+            .history(speaker_addr, service)
+    }
+
+    /// State, metadata and stable ID of every UPnP subscription
+    pub fn subscriptions(&self) -> Vec<SubscriptionInfo> {
+        self.subscription_manager.subscription_info()
+    }
+
+    /// All recorded protocol history as JSON for attaching to bug reports,
+    /// redacted per `BrokerConfig::protocol_history_redaction`
+    pub fn protocol_history_json(&self) -> String {
+        self.subscription_manager.diagnostics().to_json()
+    }
+
     /// Get current firewall status (returns Unknown since status is now per-device)
     pub async fn firewall_status(&self) -> FirewallStatus {
         // Since firewall status is now per-device, this method returns Unknown
@@ -742,6 +1420,12 @@ impl EventBroker {
         // Clear registry
         self.registry.clear().await;
 
+        // Finish writing new stable IDs
+        let stable_ids = self.stable_ids;
+        if let Err(e) = tokio::task::spawn_blocking(move || stable_ids.flush()).await {
+            warn!(error = %e, "Error writing stable subscription IDs");
+        }
+
         info!("EventBroker shutdown complete");
 
         Ok(())
@@ -822,4 +1506,508 @@ mod tests {
         assert_eq!(PollingReason::NetworkIssues.to_string(), "network issues");
         assert_eq!(PollingReason::ForcedPolling.to_string(), "forced polling");
     }
+
+    /// A broker on the device's clock, recording protocol history
+    async fn mock_broker(device: &sonos_api::mock::MockDevice) -> EventBroker {
+        let mut config = BrokerConfig::no_firewall_detection().with_clock(Arc::new(device.clock()));
+        config.protocol_history_size = 16;
+        EventBroker::new(config).await.unwrap()
+    }
+
+    /// Next event delivered by NOTIFY, skipping any from polling
+    async fn next_notified(events: &mut EventIterator) -> EnrichedEvent {
+        loop {
+            let event = events
+                .next_timeout(std::time::Duration::from_secs(5))
+                .await
+                .unwrap()
+                .unwrap();
+            if matches!(
+                event.event_source,
+                crate::EventSource::UPnPNotification { .. }
+            ) {
+                return event;
+            }
+        }
+    }
+
+    fn transport_notify() -> sonos_api::mock::Action {
+        sonos_api::mock::Action::Notify {
+            service: Service::AVTransport,
+            body: r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/AVT/&quot;&gt;&lt;InstanceID val=&quot;0&quot;&gt;&lt;TransportState val=&quot;PLAYING&quot;/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#.to_string(),
+        }
+    }
+
+    /// Services of the active subscriptions to `addr`, sorted, one entry
+    /// per subscription
+    async fn active_services(broker: &EventBroker, addr: SocketAddr) -> Vec<String> {
+        let mut services: Vec<_> = broker
+            .subscription_manager
+            .list_subscriptions()
+            .await
+            .iter()
+            .filter(|s| s.speaker_service_pair().speaker_addr == addr)
+            .map(|s| format!("{:?}", s.speaker_service_pair().service))
+            .collect();
+        services.sort();
+        services
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_arriving_events_keep_registration_off_polling() {
+        use crate::subscription::DeliveryMode;
+        use sonos_api::mock::{MockDevice, Scenario};
+        use std::time::Duration;
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let config = BrokerConfig::no_firewall_detection()
+            .with_clock(Arc::new(device.clock()))
+            .with_event_timeout(Duration::from_secs(10));
+        let broker = EventBroker::new(config).await.unwrap();
+        broker
+            .register_speaker_service(device.addr(), Service::AVTransport)
+            .await
+            .unwrap();
+
+        // An event every 5s for four event timeouts, each second observed
+        // by the detector's monitor
+        for _ in 0..8 {
+            device.perform(transport_notify());
+            broker.flush_notifications().await;
+            for _ in 0..5 {
+                device.advance(Duration::from_secs(1));
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            broker.subscriptions()[0].metadata.delivery,
+            DeliveryMode::Events
+        );
+        broker.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reissued_sid_retires_stale_subscription() {
+        use crate::diagnostics::ExchangeKind;
+        use sonos_api::mock::{Action, MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().recycle_sids());
+        let addr = device.addr();
+        let mut broker = mock_broker(&device).await;
+        let mut events = broker.event_iterator().unwrap();
+
+        let transport = broker
+            .register_speaker_service(addr, Service::AVTransport)
+            .await
+            .unwrap()
+            .registration_id;
+        let rendering = broker
+            .register_speaker_service(addr, Service::RenderingControl)
+            .await
+            .unwrap()
+            .registration_id;
+        assert_eq!(device.sids(Service::AVTransport), ["uuid:mock-1"]);
+
+        // After the reboot, the replacement RenderingControl subscription is
+        // granted the SID AVTransport had
+        device.perform(Action::Reboot);
+        broker.unregister_speaker_service(rendering).await.unwrap();
+        let rendering = broker
+            .register_speaker_service(addr, Service::RenderingControl)
+            .await
+            .unwrap()
+            .registration_id;
+        assert_eq!(device.sids(Service::RenderingControl), ["uuid:mock-1"]);
+        assert_eq!(device.sids(Service::AVTransport), ["uuid:mock-2"]);
+        assert_eq!(device.live_subscriptions(), 2);
+        assert_eq!(
+            active_services(&broker, addr).await,
+            ["AVTransport", "RenderingControl"]
+        );
+        let history = broker.protocol_history(addr, Service::AVTransport).unwrap();
+        let retire = history
+            .exchanges
+            .iter()
+            .find(|e| e.kind == ExchangeKind::Retire)
+            .unwrap();
+        assert_eq!(retire.sid.as_deref(), Some("uuid:mock-1"));
+
+        // Each service's events reach its own registration
+        device.perform(Action::SetVolume(40));
+        let event = next_notified(&mut events).await;
+        assert_eq!(
+            (event.registration_id, event.service),
+            (rendering, Service::RenderingControl)
+        );
+        device.perform(transport_notify());
+        let event = next_notified(&mut events).await;
+        assert_eq!(
+            (event.registration_id, event.service),
+            (transport, Service::AVTransport)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stable_id_survives_restart() {
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let addr = device.addr();
+        let dir = std::env::temp_dir().join(format!("sonos-stable-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("stable-ids.json");
+
+        // SID and stable ID of the first notified event of a fresh broker
+        let run = || async {
+            let config = BrokerConfig::no_firewall_detection()
+                .with_clock(Arc::new(device.clock()))
+                .with_stable_id_file(&path);
+            let mut broker = EventBroker::new(config).await.unwrap();
+            let mut events = broker.event_iterator().unwrap();
+            broker.identify_speaker(addr, "RINCON_DEN");
+            broker
+                .register_speaker_service(addr, Service::AVTransport)
+                .await
+                .unwrap();
+            device.perform(transport_notify());
+            let event = next_notified(&mut events).await;
+            let info = broker.subscriptions();
+            assert_eq!(info[0].stable_id, event.stable_id);
+            broker.shutdown().await.unwrap();
+            let crate::EventSource::UPnPNotification { subscription_id } = event.event_source
+            else {
+                unreachable!()
+            };
+            (subscription_id, event.stable_id.unwrap())
+        };
+
+        let (first_sid, stable_id) = run().await;
+        let (second_sid, restarted) = run().await;
+        assert_ne!(first_sid, second_sid);
+        assert_eq!(restarted, stable_id);
+
+        // Without the file a new ID is generated, then kept
+        std::fs::remove_file(&path).unwrap();
+        let (_, regenerated) = run().await;
+        assert_ne!(regenerated, stable_id);
+        assert_eq!(run().await.1, regenerated);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_boot_seq_change_replaces_device_subscriptions() {
+        use sonos_api::mock::{Action, MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().recycle_sids());
+        let addr = device.addr();
+        let mut broker = mock_broker(&device).await;
+        let mut events = broker.event_iterator().unwrap();
+        let transport = broker
+            .register_speaker_service(addr, Service::AVTransport)
+            .await
+            .unwrap()
+            .registration_id;
+        let rendering = broker
+            .register_speaker_service(addr, Service::RenderingControl)
+            .await
+            .unwrap()
+            .registration_id;
+
+        assert!(!broker.observe_boot_seq(addr, 7).await);
+        device.perform(Action::Reboot);
+        assert!(!broker.observe_boot_seq(addr, 7).await);
+        assert!(broker.observe_boot_seq(addr, 8).await);
+
+        // Replaced without UNSUBSCRIBEs the device would refuse
+        assert_eq!(device.subscriptions(), 4);
+        assert_eq!(device.unsubscriptions(), 0);
+        assert_eq!(device.live_subscriptions(), 2);
+        assert_eq!(
+            active_services(&broker, addr).await,
+            ["AVTransport", "RenderingControl"]
+        );
+
+        device.perform(Action::SetVolume(40));
+        let event = next_notified(&mut events).await;
+        assert_eq!(
+            (event.registration_id, event.service),
+            (rendering, Service::RenderingControl)
+        );
+        device.perform(transport_notify());
+        let event = next_notified(&mut events).await;
+        assert_eq!(
+            (event.registration_id, event.service),
+            (transport, Service::AVTransport)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_boot_seqs_of_two_devices_on_one_ip_are_kept_apart() {
+        use sonos_api::mock::{Action, MockDevice, Scenario};
+
+        let first = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let second = MockDevice::start_with_clock("127.0.0.1:0", Scenario::new(), first.clock());
+        assert_eq!(first.addr().ip(), second.addr().ip());
+        let broker = mock_broker(&first).await;
+        for device in [&first, &second] {
+            broker
+                .register_speaker_service(device.addr(), Service::AVTransport)
+                .await
+                .unwrap();
+        }
+
+        for _ in 0..2 {
+            assert!(!broker.observe_boot_seq(first.addr(), 7).await);
+            assert!(!broker.observe_boot_seq(second.addr(), 3).await);
+        }
+
+        // Only the device that rebooted is resubscribed
+        first.perform(Action::Reboot);
+        assert!(broker.observe_boot_seq(first.addr(), 8).await);
+        assert_eq!(first.subscriptions(), 2);
+        assert_eq!(second.subscriptions(), 1);
+        assert_eq!(second.unsubscriptions(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_same_sid_on_two_devices_routes_by_sender() {
+        use sonos_api::mock::{Action, MockDevice, Scenario};
+
+        // NOTIFYs are told apart by their sender's IP, so each device needs
+        // a host of its own
+        let kitchen = MockDevice::start("127.0.0.41:0", Scenario::new().recycle_sids());
+        let den = MockDevice::start_with_clock(
+            "127.0.0.42:0",
+            Scenario::new().recycle_sids(),
+            kitchen.clock(),
+        );
+        let mut broker = mock_broker(&kitchen).await;
+        let mut events = broker.event_iterator().unwrap();
+        let mut registrations = Vec::new();
+        for device in [&kitchen, &den] {
+            let registration = broker
+                .register_speaker_service(device.addr(), Service::RenderingControl)
+                .await
+                .unwrap()
+                .registration_id;
+            assert_eq!(device.sids(Service::RenderingControl), ["uuid:mock-1"]);
+            registrations.push(registration);
+        }
+
+        for (device, registration) in [&den, &kitchen, &den].into_iter().zip([1, 0, 1]) {
+            device.perform(Action::SetVolume(40));
+            let event = next_notified(&mut events).await;
+            assert_eq!(event.registration_id, registrations[registration]);
+            assert_eq!(event.speaker_addr, device.addr());
+        }
+
+        // The kitchen letting go of the SID doesn't stop the den's events
+        broker
+            .unregister_speaker_service(registrations[0])
+            .await
+            .unwrap();
+        den.perform(Action::SetVolume(30));
+        let event = next_notified(&mut events).await;
+        assert_eq!(event.registration_id, registrations[1]);
+    }
+
+    /// One group, `RINCON_DEN:1`, of `members` coordinated by `coordinator`
+    fn group_topology(coordinator: &str, members: &[(&str, SocketAddr)]) -> ZoneGroupTopologyState {
+        use crate::events::types::{NetworkInfo, ZoneGroupInfo, ZoneGroupMemberInfo};
+
+        ZoneGroupTopologyState {
+            zone_groups: vec![ZoneGroupInfo {
+                coordinator: coordinator.to_string(),
+                id: "RINCON_DEN:1".to_string(),
+                members: members
+                    .iter()
+                    .map(|(uuid, addr)| ZoneGroupMemberInfo {
+                        uuid: uuid.to_string(),
+                        location: format!("http://{addr}/xml/device_description.xml"),
+                        zone_name: uuid.to_string(),
+                        software_version: String::new(),
+                        software_generation: None,
+                        boot_seq: 1,
+                        network_info: NetworkInfo::default(),
+                        satellites: vec![],
+                    })
+                    .collect(),
+            }],
+            vanished_devices: vec![],
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_group_subscription_follows_coordinator_handoff() {
+        use crate::diagnostics::{ExchangeKind, ProtocolObserver, ProtocolRecord};
+        use sonos_api::mock::{MockDevice, Scenario};
+        use sonos_api::SpeakerId;
+
+        let den = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let hall = MockDevice::start_with_clock("127.0.0.1:0", Scenario::new(), den.clock());
+        let members = [("RINCON_DEN", den.addr()), ("RINCON_HALL", hall.addr())];
+        let group = GroupId::new("RINCON_DEN:1");
+        let tag = |coordinator: &str| GroupTag {
+            group_id: group.clone(),
+            coordinator: SpeakerId::new(coordinator),
+        };
+
+        let exchanges = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&exchanges);
+        let config = BrokerConfig::no_firewall_detection()
+            .with_clock(Arc::new(den.clock()))
+            .with_protocol_observer(ProtocolObserver::new(move |pair, record| {
+                if let ProtocolRecord::Exchange(exchange) = record {
+                    recorded
+                        .lock()
+                        .unwrap()
+                        .push((pair.speaker_addr, exchange.kind));
+                }
+            }));
+        let mut broker = EventBroker::new(config).await.unwrap();
+        let mut events = broker.event_iterator().unwrap();
+        let mut lifecycle = broker.group_events();
+
+        assert!(matches!(
+            broker.subscribe_group(&group, Service::AVTransport).await,
+            Err(BrokerError::UnknownGroup(_))
+        ));
+        let topology = group_topology("RINCON_DEN", &members);
+        assert_eq!(broker.observe_topology(&topology).await, 0);
+        let first = broker
+            .subscribe_group(&group, Service::AVTransport)
+            .await
+            .unwrap();
+        let again = broker
+            .subscribe_group(&group, Service::AVTransport)
+            .await
+            .unwrap();
+        assert_eq!(again.registration_id, first.registration_id);
+        assert!(again.was_duplicate);
+        assert_eq!(
+            (den.live_subscriptions(), hall.live_subscriptions()),
+            (1, 0)
+        );
+
+        den.perform(transport_notify());
+        let event = next_notified(&mut events).await;
+        assert_eq!(event.speaker_addr, den.addr());
+        assert_eq!(event.group, Some(tag("RINCON_DEN")));
+
+        // Coordination handed to the hall: the den is unsubscribed, then the
+        // hall subscribed
+        let topology = group_topology("RINCON_HALL", &members);
+        assert_eq!(broker.observe_topology(&topology).await, 1);
+        assert_eq!(
+            (den.live_subscriptions(), hall.live_subscriptions()),
+            (0, 1)
+        );
+        assert_eq!(
+            *exchanges.lock().unwrap(),
+            [
+                (den.addr(), ExchangeKind::Subscribe),
+                (den.addr(), ExchangeKind::Unsubscribe),
+                (hall.addr(), ExchangeKind::Subscribe),
+            ]
+        );
+        let GroupSubscriptionEvent::Migrated {
+            group_id,
+            service,
+            from,
+            to,
+            registration_id,
+        } = lifecycle.try_recv().unwrap()
+        else {
+            panic!("expected a migration");
+        };
+        assert_eq!((group_id, service), (group.clone(), Service::AVTransport));
+        assert_eq!((from.id.as_str(), from.addr), ("RINCON_DEN", den.addr()));
+        assert_eq!((to.id.as_str(), to.addr), ("RINCON_HALL", hall.addr()));
+        assert_ne!(registration_id, first.registration_id);
+
+        // Events keep coming, from the hall, under the same group
+        hall.perform(transport_notify());
+        let event = next_notified(&mut events).await;
+        assert_eq!(
+            (event.registration_id, event.speaker_addr),
+            (registration_id, hall.addr())
+        );
+        assert_eq!(event.group, Some(tag("RINCON_HALL")));
+
+        // The same topology again moves nothing
+        assert_eq!(broker.observe_topology(&topology).await, 0);
+        assert!(lifecycle.try_recv().is_err());
+
+        assert!(broker
+            .unsubscribe_group(&group, Service::AVTransport)
+            .await
+            .unwrap());
+        assert_eq!(hall.live_subscriptions(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_group_subscription_shares_existing_registration() {
+        use crate::group::{Coordinator, GroupResolver};
+        use sonos_api::mock::{MockDevice, Scenario};
+        use sonos_api::SpeakerId;
+
+        let den = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let hall = MockDevice::start_with_clock("127.0.0.1:0", Scenario::new(), den.clock());
+        let group = GroupId::new("RINCON_DEN:1");
+        let coordinator = Arc::new(std::sync::Mutex::new(Coordinator {
+            id: SpeakerId::new("RINCON_DEN"),
+            addr: den.addr(),
+        }));
+        let resolved = Arc::clone(&coordinator);
+        let config = BrokerConfig::no_firewall_detection()
+            .with_clock(Arc::new(den.clock()))
+            .with_group_resolver(GroupResolver::new(move |_| {
+                Some(resolved.lock().unwrap().clone())
+            }));
+        let mut broker = EventBroker::new(config).await.unwrap();
+        let mut events = broker.event_iterator().unwrap();
+
+        // The hall has a registration of its own
+        let own = broker
+            .register_speaker_service(hall.addr(), Service::AVTransport)
+            .await
+            .unwrap()
+            .registration_id;
+        broker
+            .subscribe_group(&group, Service::AVTransport)
+            .await
+            .unwrap();
+        assert_eq!(den.live_subscriptions(), 1);
+
+        // The resolver names the hall: its registration is shared, not
+        // subscribed twice
+        *coordinator.lock().unwrap() = Coordinator {
+            id: SpeakerId::new("RINCON_HALL"),
+            addr: hall.addr(),
+        };
+        assert_eq!(broker.refresh_groups().await, 1);
+        assert_eq!(den.live_subscriptions(), 0);
+        assert_eq!((hall.subscriptions(), hall.live_subscriptions()), (1, 1));
+        hall.perform(transport_notify());
+        let event = next_notified(&mut events).await;
+        assert_eq!(event.registration_id, own);
+        assert_eq!(
+            event.group.map(|tag| tag.coordinator),
+            Some(SpeakerId::new("RINCON_HALL"))
+        );
+
+        // Ending the group subscription leaves the hall's registration alone
+        assert!(broker
+            .unsubscribe_group(&group, Service::AVTransport)
+            .await
+            .unwrap());
+        assert!(!broker
+            .unsubscribe_group(&group, Service::AVTransport)
+            .await
+            .unwrap());
+        assert_eq!(hall.live_subscriptions(), 1);
+        hall.perform(transport_notify());
+        let event = next_notified(&mut events).await;
+        assert_eq!((event.registration_id, event.group), (own, None));
+    }
 }
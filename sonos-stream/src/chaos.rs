@@ -0,0 +1,302 @@
+//! Fault injection for resilience tests
+//!
+//! With the `chaos-testing` feature, a `ChaosController` handed to
+//! `BrokerConfig::with_chaos()` (and to sonos-state's
+//! `StateManagerBuilder::chaos()`) makes the SDK's own internals misbehave:
+//!
+//! - NOTIFYs from the callback server are dropped, delayed or duplicated
+//!   before the event processor sees them
+//! - The next N subscription renewals fail without reaching the device
+//! - sonos-state's decoder waits before decoding each event
+//!
+//! Which events are hit is drawn from a generator seeded by the
+//! controller's seed, so a run with the same seed and the same events
+//! misbehaves the same way.
+//!
+//! Each injection point holds a [`ChaosHook`]. Without the feature it is a
+//! zero-sized type whose checks are constant no-ops.
+
+use std::time::Duration;
+
+#[cfg(feature = "chaos-testing")]
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// What happens to one event at the callback adapter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFate {
+    Deliver,
+    Drop,
+    Delay(Duration),
+    Duplicate,
+}
+
+/// Fault injection state of one injection point: a no-op unless the
+/// `chaos-testing` feature is enabled and a controller is attached
+#[derive(Debug, Clone, Default)]
+pub struct ChaosHook {
+    #[cfg(feature = "chaos-testing")]
+    controller: Option<ChaosController>,
+}
+
+#[cfg(not(feature = "chaos-testing"))]
+impl ChaosHook {
+    #[inline(always)]
+    pub fn event_fate(&self) -> EventFate {
+        EventFate::Deliver
+    }
+
+    #[inline(always)]
+    pub fn fail_renewal(&self) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    pub fn decode_delay(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(feature = "chaos-testing")]
+impl ChaosHook {
+    /// Fate of the next event from the callback server
+    pub fn event_fate(&self) -> EventFate {
+        self.controller
+            .as_ref()
+            .map_or(EventFate::Deliver, ChaosController::event_fate)
+    }
+
+    /// Whether the renewal about to be sent should fail instead
+    pub fn fail_renewal(&self) -> bool {
+        self.controller
+            .as_ref()
+            .is_some_and(ChaosController::fail_renewal)
+    }
+
+    /// Latency to add before decoding the next event
+    pub fn decode_delay(&self) -> Option<Duration> {
+        self.controller
+            .as_ref()
+            .and_then(ChaosController::decode_delay)
+    }
+}
+
+#[cfg(feature = "chaos-testing")]
+impl From<ChaosController> for ChaosHook {
+    fn from(controller: ChaosController) -> Self {
+        Self {
+            controller: Some(controller),
+        }
+    }
+}
+
+/// Faults injected so far
+#[cfg(feature = "chaos-testing")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    pub events_dropped: u64,
+    pub events_delayed: u64,
+    pub events_duplicated: u64,
+    pub renewals_failed: u64,
+    pub decodes_delayed: u64,
+}
+
+/// Handle controlling the faults injected into the components it was
+/// given to; clones control the same faults
+///
+/// Every fault starts off. Event fractions are probabilities in `0.0..=1.0`
+/// drawn for each event, in the order drop, duplicate, delay.
+#[cfg(feature = "chaos-testing")]
+#[derive(Debug, Clone)]
+pub struct ChaosController {
+    inner: Arc<Mutex<ChaosState>>,
+}
+
+#[cfg(feature = "chaos-testing")]
+#[derive(Debug, Default)]
+struct ChaosState {
+    seed: u64,
+    rng: u64,
+    drop_events: f64,
+    duplicate_events: f64,
+    delay_events: f64,
+    event_delay: Duration,
+    failing_renewals: u32,
+    decode_latency: Option<Duration>,
+    stats: ChaosStats,
+}
+
+#[cfg(feature = "chaos-testing")]
+impl ChaosState {
+    /// SplitMix64, mapped to `0.0..1.0`
+    fn roll(&mut self) -> f64 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (z ^ (z >> 31)) as f64 / (u64::MAX as f64 + 1.0)
+    }
+}
+
+#[cfg(feature = "chaos-testing")]
+impl ChaosController {
+    /// A controller injecting nothing yet, drawing from `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ChaosState {
+                seed,
+                rng: seed,
+                ..ChaosState::default()
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ChaosState> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.lock().seed
+    }
+
+    /// Drop `fraction` of the events from the callback server
+    pub fn drop_events(&self, fraction: f64) -> &Self {
+        self.lock().drop_events = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Deliver `fraction` of the events twice
+    pub fn duplicate_events(&self, fraction: f64) -> &Self {
+        self.lock().duplicate_events = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Hold `fraction` of the events back for `delay`, stalling the ones
+    /// queued behind them
+    pub fn delay_events(&self, fraction: f64, delay: Duration) -> &Self {
+        let mut state = self.lock();
+        state.delay_events = fraction.clamp(0.0, 1.0);
+        state.event_delay = delay;
+        self
+    }
+
+    /// Fail the next `count` renewals without sending them
+    pub fn fail_renewals(&self, count: u32) -> &Self {
+        self.lock().failing_renewals = count;
+        self
+    }
+
+    /// Wait `latency` before decoding each event; `Duration::ZERO` turns
+    /// it off
+    pub fn decode_latency(&self, latency: Duration) -> &Self {
+        self.lock().decode_latency = (!latency.is_zero()).then_some(latency);
+        self
+    }
+
+    /// Stop injecting faults, keeping the stats and the generator's position
+    pub fn clear(&self) -> &Self {
+        let mut state = self.lock();
+        *state = ChaosState {
+            seed: state.seed,
+            rng: state.rng,
+            stats: state.stats,
+            ..ChaosState::default()
+        };
+        self
+    }
+
+    pub fn stats(&self) -> ChaosStats {
+        self.lock().stats
+    }
+
+    /// Hook for an injection point, controlled by this handle
+    pub fn hook(&self) -> ChaosHook {
+        ChaosHook::from(self.clone())
+    }
+
+    fn event_fate(&self) -> EventFate {
+        let mut state = self.lock();
+        let total = state.drop_events + state.duplicate_events + state.delay_events;
+        if total == 0.0 {
+            return EventFate::Deliver;
+        }
+        let roll = state.roll();
+        if roll < state.drop_events {
+            state.stats.events_dropped += 1;
+            EventFate::Drop
+        } else if roll < state.drop_events + state.duplicate_events {
+            state.stats.events_duplicated += 1;
+            EventFate::Duplicate
+        } else if roll < total {
+            state.stats.events_delayed += 1;
+            EventFate::Delay(state.event_delay)
+        } else {
+            EventFate::Deliver
+        }
+    }
+
+    fn fail_renewal(&self) -> bool {
+        let mut state = self.lock();
+        if state.failing_renewals == 0 {
+            return false;
+        }
+        state.failing_renewals -= 1;
+        state.stats.renewals_failed += 1;
+        true
+    }
+
+    fn decode_delay(&self) -> Option<Duration> {
+        let mut state = self.lock();
+        let latency = state.decode_latency?;
+        state.stats.decodes_delayed += 1;
+        Some(latency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "chaos-testing"))]
+    #[test]
+    fn test_disabled_hook_is_zero_sized_no_op() {
+        assert_eq!(std::mem::size_of::<ChaosHook>(), 0);
+        let hook = ChaosHook::default();
+        assert_eq!(hook.event_fate(), EventFate::Deliver);
+        assert!(!hook.fail_renewal());
+        assert_eq!(hook.decode_delay(), None);
+    }
+
+    #[cfg(feature = "chaos-testing")]
+    #[test]
+    fn test_controller_is_reproducible_from_its_seed() {
+        let fates = |seed| {
+            let chaos = ChaosController::new(seed);
+            chaos
+                .drop_events(0.3)
+                .duplicate_events(0.2)
+                .delay_events(0.2, Duration::from_millis(5));
+            let hook = chaos.hook();
+            let fates: Vec<_> = (0..64).map(|_| hook.event_fate()).collect();
+            (fates, chaos.stats())
+        };
+        let (first, stats) = fates(42);
+        assert_eq!(fates(42), (first.clone(), stats));
+        assert_ne!(fates(43).0, first);
+        assert!(stats.events_dropped > 0 && stats.events_duplicated > 0);
+        assert!(stats.events_delayed > 0);
+
+        let chaos = ChaosController::new(1);
+        let hook = chaos.hook();
+        assert!(!hook.fail_renewal());
+        chaos.fail_renewals(2);
+        assert!(hook.fail_renewal() && hook.fail_renewal());
+        assert!(!hook.fail_renewal());
+        chaos.decode_latency(Duration::from_millis(3));
+        assert_eq!(hook.decode_delay(), Some(Duration::from_millis(3)));
+        chaos.clear();
+        assert_eq!(hook.decode_delay(), None);
+        assert_eq!(hook.event_fate(), EventFate::Deliver);
+        assert_eq!(chaos.stats().renewals_failed, 2);
+        assert_eq!(ChaosHook::default().event_fate(), EventFate::Deliver);
+    }
+}
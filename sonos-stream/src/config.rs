@@ -4,8 +4,19 @@
 //! of the EventBroker, including firewall detection, polling intervals,
 //! and event processing settings.
 
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
+use callback_server::PortSelection;
+use sonos_api::{EventingConnection, SharedClock, SubscriptionDirectory, SystemClock};
+
+use crate::chaos::ChaosHook;
+use crate::diagnostics::{ProtocolObserver, RedactionPolicy};
+use crate::events::spillover::SpilloverConfig;
+use crate::group::GroupResolver;
+use crate::polling::scheduler::WatchdogConfig;
+
 /// Configuration for the EventBroker
 ///
 /// This struct controls all aspects of the event broker's behavior, from
@@ -16,6 +27,12 @@ pub struct BrokerConfig {
     /// Default: (3400, 3500)
     pub callback_port_range: (u16, u16),
 
+    /// Order the callback server tries the range's ports in, and how many
+    /// it binds at once
+    /// Default: `PortSelection::default()` (in order, after the port the
+    /// process bound last)
+    pub callback_port_selection: PortSelection,
+
     /// Timeout for detecting event failures (fallback after proactive detection)
     /// Default: 30 seconds
     pub event_timeout: Duration,
@@ -76,12 +93,69 @@ pub struct BrokerConfig {
     /// Simulates a firewall that blocks all callback traffic. Useful for testing.
     /// Default: false
     pub force_polling_mode: bool,
+
+    /// Number of SUBSCRIBE exchanges, and NOTIFY receipts per subscription,
+    /// kept per speaker/service for [`EventBroker::protocol_history()`](crate::EventBroker::protocol_history).
+    /// 0 disables recording.
+    /// Default: 0
+    pub protocol_history_size: usize,
+
+    /// How speaker IPs and SIDs are written in the protocol history JSON dump
+    /// Default: `RedactionPolicy::Identifiers`
+    pub protocol_history_redaction: RedactionPolicy,
+
+    /// Called with every SUBSCRIBE / renewal / UNSUBSCRIBE and NOTIFY
+    /// receipt as it's recorded, independent of `protocol_history_size`
+    /// Default: None
+    pub protocol_observer: Option<ProtocolObserver>,
+
+    /// Time source for subscription expiry, detecting host sleep, and the
+    /// renewal, polling and event-timeout timers
+    /// Default: the system clock
+    pub clock: SharedClock,
+
+    /// Spill events to disk when their consumer falls behind, instead of
+    /// buffering them in memory without bound. Applied by the sync bridge in
+    /// sonos-event-manager.
+    /// Default: None (disabled)
+    pub spillover: Option<SpilloverConfig>,
+
+    /// Per-poll timeout and suspension of polling tasks that keep failing
+    /// Default: `WatchdogConfig::default()`
+    pub polling_watchdog: WatchdogConfig,
+
+    /// How SUBSCRIBE, renewal and UNSUBSCRIBE requests use connections, for
+    /// devices whose transport doesn't say otherwise
+    /// Default: `EventingConnection::Close` (a fresh connection per request)
+    pub eventing_connection: EventingConnection,
+
+    /// Finds group coordinators for group subscriptions, ahead of the
+    /// topology passed to `EventBroker::observe_topology()`
+    /// Default: None
+    pub group_resolver: Option<GroupResolver>,
+
+    /// Subscriptions made elsewhere in the process, adopted or reported as
+    /// `SubscriptionError::AlreadySubscribedExternally` instead of being
+    /// subscribed to twice
+    /// Default: None (disabled)
+    pub subscription_directory: Option<SubscriptionDirectory>,
+
+    /// File mapping each speaker/service pair to a stable ID that survives
+    /// restarts and SID changes, set as `EnrichedEvent::stable_id`
+    /// Default: None (no stable IDs)
+    pub stable_id_file: Option<PathBuf>,
+
+    /// Faults injected into event delivery and renewals; a no-op unless
+    /// the `chaos-testing` feature is enabled
+    /// Default: none injected
+    pub chaos: ChaosHook,
 }
 
 impl Default for BrokerConfig {
     fn default() -> Self {
         Self {
             callback_port_range: (3400, 3500),
+            callback_port_selection: PortSelection::default(),
             event_timeout: Duration::from_secs(30),
             polling_activation_delay: Duration::from_secs(5),
             base_polling_interval: Duration::from_secs(5),
@@ -97,6 +171,17 @@ impl Default for BrokerConfig {
             adaptive_polling: true,
             renewal_threshold: Duration::from_secs(300), // 5 minutes
             force_polling_mode: false,
+            protocol_history_size: 0,
+            protocol_history_redaction: RedactionPolicy::Identifiers,
+            protocol_observer: None,
+            clock: Arc::new(SystemClock),
+            spillover: None,
+            polling_watchdog: WatchdogConfig::default(),
+            eventing_connection: EventingConnection::Close,
+            group_resolver: None,
+            subscription_directory: None,
+            stable_id_file: None,
+            chaos: ChaosHook::default(),
         }
     }
 }
@@ -195,6 +280,22 @@ impl BrokerConfig {
             ));
         }
 
+        if self.polling_watchdog.poll_timeout == Duration::ZERO
+            || self.polling_watchdog.failure_threshold == 0
+        {
+            return Err(crate::BrokerError::Configuration(
+                "Polling watchdog timeout and failure threshold must be greater than 0".to_string(),
+            ));
+        }
+
+        if let Some(spillover) = &self.spillover {
+            if spillover.memory_capacity == 0 || spillover.spill_batch == 0 {
+                return Err(crate::BrokerError::Configuration(
+                    "Spillover memory capacity and batch size must be greater than 0".to_string(),
+                ));
+            }
+        }
+
         Ok(())
     }
 
@@ -204,6 +305,13 @@ impl BrokerConfig {
         self
     }
 
+    /// Start the callback server's port scan at a random port of the range,
+    /// so processes sharing the range rarely try the same ports
+    pub fn with_randomized_callback_port(mut self) -> Self {
+        self.callback_port_selection.randomize = true;
+        self
+    }
+
     pub fn with_polling_interval(mut self, base: Duration, max: Duration) -> Self {
         self.base_polling_interval = base;
         self.max_polling_interval = max;
@@ -229,6 +337,53 @@ impl BrokerConfig {
         self.force_polling_mode = enabled;
         self
     }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_spillover(mut self, spillover: SpilloverConfig) -> Self {
+        self.spillover = Some(spillover);
+        self
+    }
+
+    pub fn with_polling_watchdog(mut self, watchdog: WatchdogConfig) -> Self {
+        self.polling_watchdog = watchdog;
+        self
+    }
+
+    pub fn with_eventing_connection(mut self, eventing: EventingConnection) -> Self {
+        self.eventing_connection = eventing;
+        self
+    }
+
+    pub fn with_protocol_observer(mut self, observer: ProtocolObserver) -> Self {
+        self.protocol_observer = Some(observer);
+        self
+    }
+
+    pub fn with_group_resolver(mut self, resolver: GroupResolver) -> Self {
+        self.group_resolver = Some(resolver);
+        self
+    }
+
+    pub fn with_subscription_directory(mut self, directory: SubscriptionDirectory) -> Self {
+        self.subscription_directory = Some(directory);
+        self
+    }
+
+    pub fn with_stable_id_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.stable_id_file = Some(path.into());
+        self
+    }
+
+    /// Inject the faults `controller` sets into this broker
+    #[cfg(feature = "chaos-testing")]
+    pub fn with_chaos(mut self, controller: &crate::chaos::ChaosController) -> Self {
+        self.chaos = controller.hook();
+        self
+    }
 }
 
 #[cfg(test)]
@@ -259,6 +414,12 @@ mod tests {
             ..Default::default()
         };
         assert!(invalid_polling.validate().is_err());
+
+        let invalid_watchdog = BrokerConfig::default().with_polling_watchdog(WatchdogConfig {
+            failure_threshold: 0,
+            ..Default::default()
+        });
+        assert!(invalid_watchdog.validate().is_err());
     }
 
     #[test]
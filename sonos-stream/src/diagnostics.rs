@@ -0,0 +1,470 @@
+//! Wire-level subscription diagnostics
+//!
+//! Keeps the recent protocol history for each speaker/service pair: the last
+//! N SUBSCRIBE / renewal / UNSUBSCRIBE exchanges and the last N NOTIFY
+//! receipts per subscription. Entries are small structured summaries meant
+//! for bug reports, not tracing spans. A [`ProtocolObserver`] sees each
+//! entry as it's recorded. With a capacity of 0 (the default) and no
+//! observer, recording returns before building anything.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use sonos_api::Service;
+
+use crate::registry::SpeakerServicePair;
+use crate::stable_id::StableIds;
+
+/// Oldest subscriptions' NOTIFY receipts are dropped beyond this many SIDs per pair
+const MAX_SIDS_PER_PAIR: usize = 4;
+
+/// How identifiers are written in [`ProtocolDiagnostics::to_json()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionPolicy {
+    /// Replace speaker IPs and keep only the last 4 characters of SIDs
+    #[default]
+    Identifiers,
+    /// Write everything verbatim
+    Off,
+}
+
+/// Subscription request type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExchangeKind {
+    Subscribe,
+    Renew,
+    Unsubscribe,
+    /// Dropped locally without a request, because the device reissued the
+    /// SID or rebooted; `error` says which
+    Retire,
+}
+
+/// One subscription request and its result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubscriptionExchange {
+    /// Milliseconds since the Unix epoch
+    pub at_ms: u64,
+    pub kind: ExchangeKind,
+    /// SID sent or granted; `None` when an initial SUBSCRIBE failed
+    pub sid: Option<String>,
+    /// The pair's ID across restarts, when stable IDs are enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
+    /// Timeout granted by the device, in seconds
+    pub granted_timeout_secs: Option<u32>,
+    /// Error for a failed request
+    pub error: Option<String>,
+}
+
+/// What the broker did with a NOTIFY
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyOutcome {
+    /// Parsed and forwarded to the event stream
+    Delivered,
+    /// The body could not be parsed
+    ParseFailed,
+    /// The callback server couldn't decompress the body
+    Undecodable,
+    /// Parsed, but the event stream was closed
+    ChannelClosed,
+}
+
+/// One NOTIFY received for a subscription
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NotifyReceipt {
+    /// Milliseconds since the Unix epoch
+    pub at_ms: u64,
+    /// `SEQ` header, if the device sent one
+    pub seq: Option<u32>,
+    /// Body size in bytes
+    pub size: usize,
+    pub outcome: NotifyOutcome,
+}
+
+/// Recent protocol history for one speaker/service pair, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProtocolHistory {
+    /// The pair's ID across restarts, when stable IDs are enabled
+    pub stable_id: Option<String>,
+    pub exchanges: Vec<SubscriptionExchange>,
+    /// NOTIFY receipts by SID
+    pub notifies: Vec<(String, Vec<NotifyReceipt>)>,
+}
+
+/// An entry as it's recorded, handed to a [`ProtocolObserver`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtocolRecord {
+    Exchange(SubscriptionExchange),
+    Notify {
+        sid: String,
+        /// The pair's ID across restarts, when stable IDs are enabled
+        stable_id: Option<String>,
+        receipt: NotifyReceipt,
+    },
+}
+
+/// Callback handed every exchange and NOTIFY receipt as it's recorded,
+/// unredacted, whatever the history size
+///
+/// Runs on the broker's task, so it must return quickly.
+#[derive(Clone)]
+pub struct ProtocolObserver(Arc<ObserverFn>);
+
+type ObserverFn = dyn Fn(&SpeakerServicePair, &ProtocolRecord) + Send + Sync;
+
+impl ProtocolObserver {
+    pub fn new(
+        observer: impl Fn(&SpeakerServicePair, &ProtocolRecord) + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(observer))
+    }
+}
+
+impl fmt::Debug for ProtocolObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProtocolObserver")
+    }
+}
+
+#[derive(Debug, Default)]
+struct PairHistory {
+    exchanges: VecDeque<SubscriptionExchange>,
+    /// Ordered by first receipt so the oldest SID is evicted first
+    notifies: VecDeque<(String, VecDeque<NotifyReceipt>)>,
+}
+
+/// Bounded per speaker/service protocol history
+#[derive(Debug, Default)]
+pub struct ProtocolDiagnostics {
+    capacity: usize,
+    redaction: RedactionPolicy,
+    observer: Option<ProtocolObserver>,
+    pairs: Mutex<HashMap<SpeakerServicePair, PairHistory>>,
+    /// Stable IDs recorded next to SIDs
+    stable_ids: Arc<StableIds>,
+    /// Entries built, to prove the disabled path does no work
+    #[cfg(test)]
+    built: std::sync::atomic::AtomicUsize,
+}
+
+impl ProtocolDiagnostics {
+    /// Keep up to `capacity` exchanges, and receipts per SID; 0 disables recording
+    pub fn new(capacity: usize, redaction: RedactionPolicy) -> Self {
+        Self {
+            capacity,
+            redaction,
+            ..Self::default()
+        }
+    }
+
+    /// Also hand every entry to `observer`, even with a capacity of 0
+    pub fn with_observer(mut self, observer: ProtocolObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Record each pair's stable ID from `stable_ids` alongside its SIDs
+    pub fn with_stable_ids(mut self, stable_ids: Arc<StableIds>) -> Self {
+        self.stable_ids = stable_ids;
+        self
+    }
+
+    /// Whether anything is being recorded or observed
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0 || self.observer.is_some()
+    }
+
+    /// Hand `record` to the observer; `false` when there's no history to
+    /// store it in
+    fn observe(&self, pair: &SpeakerServicePair, record: impl FnOnce() -> ProtocolRecord) -> bool {
+        if let Some(observer) = &self.observer {
+            (observer.0)(pair, &record());
+        }
+        self.capacity > 0
+    }
+
+    /// Record a subscription request and its result
+    pub fn record_exchange(
+        &self,
+        pair: &SpeakerServicePair,
+        kind: ExchangeKind,
+        sid: Option<&str>,
+        result: Result<u32, &dyn std::fmt::Display>,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.count_built();
+        let (granted_timeout_secs, error) = match result {
+            Ok(timeout) => (Some(timeout), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let exchange = SubscriptionExchange {
+            at_ms: now_ms(),
+            kind,
+            sid: sid.map(str::to_string),
+            stable_id: self.stable_ids.id(pair.speaker_addr, pair.service),
+            granted_timeout_secs,
+            error,
+        };
+        if !self.observe(pair, || ProtocolRecord::Exchange(exchange.clone())) {
+            return;
+        }
+
+        let Ok(mut pairs) = self.pairs.lock() else {
+            return;
+        };
+        let history = pairs.entry(pair.clone()).or_default();
+        if history.exchanges.len() == self.capacity {
+            history.exchanges.pop_front();
+        }
+        history.exchanges.push_back(exchange);
+    }
+
+    /// Record a NOTIFY receipt
+    pub fn record_notify(
+        &self,
+        pair: &SpeakerServicePair,
+        sid: &str,
+        seq: Option<u32>,
+        size: usize,
+        outcome: NotifyOutcome,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.count_built();
+        let receipt = NotifyReceipt {
+            at_ms: now_ms(),
+            seq,
+            size,
+            outcome,
+        };
+        let record = || ProtocolRecord::Notify {
+            sid: sid.to_string(),
+            stable_id: self.stable_ids.id(pair.speaker_addr, pair.service),
+            receipt: receipt.clone(),
+        };
+        if !self.observe(pair, record) {
+            return;
+        }
+
+        let Ok(mut pairs) = self.pairs.lock() else {
+            return;
+        };
+        let history = pairs.entry(pair.clone()).or_default();
+        let receipts = match history.notifies.iter().position(|(s, _)| s == sid) {
+            Some(pos) => &mut history.notifies[pos].1,
+            None => {
+                if history.notifies.len() == MAX_SIDS_PER_PAIR {
+                    history.notifies.pop_front();
+                }
+                history
+                    .notifies
+                    .push_back((sid.to_string(), VecDeque::new()));
+                &mut history.notifies.back_mut().expect("just pushed").1
+            }
+        };
+        if receipts.len() == self.capacity {
+            receipts.pop_front();
+        }
+        receipts.push_back(receipt);
+    }
+
+    /// History for one speaker/service pair, or `None` if nothing was recorded
+    pub fn history(&self, speaker_addr: SocketAddr, service: Service) -> Option<ProtocolHistory> {
+        let pairs = self.pairs.lock().ok()?;
+        let history = pairs.get(&SpeakerServicePair::new(speaker_addr, service))?;
+        Some(ProtocolHistory {
+            stable_id: self.stable_ids.id(speaker_addr, service),
+            exchanges: history.exchanges.iter().cloned().collect(),
+            notifies: history
+                .notifies
+                .iter()
+                .map(|(sid, receipts)| (sid.clone(), receipts.iter().cloned().collect()))
+                .collect(),
+        })
+    }
+
+    /// All recorded history as a JSON array, redacted per the configured policy
+    pub fn to_json(&self) -> String {
+        let Ok(pairs) = self.pairs.lock() else {
+            return "[]".to_string();
+        };
+        let mut entries: Vec<_> = pairs.iter().collect();
+        entries.sort_by_key(|(pair, _)| (pair.speaker_addr, format!("{:?}", pair.service)));
+
+        let mut speakers: Vec<_> = entries.iter().map(|(pair, _)| pair.speaker_addr).collect();
+        speakers.dedup();
+
+        let dump: Vec<_> = entries
+            .into_iter()
+            .map(|(pair, history)| {
+                let i = speakers
+                    .iter()
+                    .position(|ip| *ip == pair.speaker_addr)
+                    .unwrap_or_default();
+                let redact = |text: &str| match self.redaction {
+                    RedactionPolicy::Off => text.to_string(),
+                    RedactionPolicy::Identifiers => {
+                        text.replace(&pair.speaker_addr.ip().to_string(), &format!("speaker-{i}"))
+                    }
+                };
+                let exchanges: Vec<_> = history
+                    .exchanges
+                    .iter()
+                    .map(|e| SubscriptionExchange {
+                        sid: e.sid.as_deref().map(|sid| self.redact_sid(sid)),
+                        error: e.error.as_deref().map(redact),
+                        ..e.clone()
+                    })
+                    .collect();
+                let notifies: Vec<_> = history
+                    .notifies
+                    .iter()
+                    .map(|(sid, receipts)| (self.redact_sid(sid), receipts))
+                    .collect();
+                serde_json::json!({
+                    "speaker": redact(&pair.speaker_addr.to_string()),
+                    "service": format!("{:?}", pair.service),
+                    "stable_id": self.stable_ids.id(pair.speaker_addr, pair.service),
+                    "exchanges": exchanges,
+                    "notifies": notifies,
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&dump).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    fn redact_sid(&self, sid: &str) -> String {
+        match self.redaction {
+            RedactionPolicy::Off => sid.to_string(),
+            RedactionPolicy::Identifiers => {
+                let tail = sid.len().saturating_sub(4);
+                format!("…{}", sid.get(tail..).unwrap_or_default())
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn built(&self) -> usize {
+        self.built.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn count_built(&self) {
+        #[cfg(test)]
+        self.built.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair() -> SpeakerServicePair {
+        SpeakerServicePair::new(
+            "192.168.1.50:1400".parse().unwrap(),
+            Service::RenderingControl,
+        )
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_ordered() {
+        let diagnostics = ProtocolDiagnostics::new(2, RedactionPolicy::Off);
+        for seq in 0..5 {
+            diagnostics.record_notify(&pair(), "uuid:a", Some(seq), 10, NotifyOutcome::Delivered);
+        }
+        for sid in ["uuid:b", "uuid:c", "uuid:d", "uuid:e"] {
+            diagnostics.record_notify(&pair(), sid, Some(0), 10, NotifyOutcome::Delivered);
+        }
+
+        let history = diagnostics
+            .history(pair().speaker_addr, Service::RenderingControl)
+            .unwrap();
+        let sids: Vec<_> = history.notifies.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(sids, ["uuid:b", "uuid:c", "uuid:d", "uuid:e"]);
+
+        diagnostics.record_notify(&pair(), "uuid:b", Some(1), 10, NotifyOutcome::Delivered);
+        diagnostics.record_notify(&pair(), "uuid:b", Some(2), 10, NotifyOutcome::Delivered);
+        let history = diagnostics
+            .history(pair().speaker_addr, Service::RenderingControl)
+            .unwrap();
+        let seqs: Vec<_> = history.notifies[0].1.iter().map(|r| r.seq).collect();
+        assert_eq!(seqs, [Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn test_disabled_builds_nothing() {
+        let diagnostics = ProtocolDiagnostics::default();
+        diagnostics.record_exchange(&pair(), ExchangeKind::Subscribe, Some("uuid:a"), Ok(1800));
+        diagnostics.record_notify(&pair(), "uuid:a", Some(0), 10, NotifyOutcome::Delivered);
+
+        assert_eq!(diagnostics.built(), 0);
+        assert!(diagnostics
+            .history(pair().speaker_addr, Service::RenderingControl)
+            .is_none());
+    }
+
+    #[test]
+    fn test_observer_sees_entries_without_history() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let diagnostics = ProtocolDiagnostics::default().with_observer(ProtocolObserver::new(
+            move |pair, record| sink.lock().unwrap().push((pair.clone(), record.clone())),
+        ));
+        diagnostics.record_exchange(&pair(), ExchangeKind::Subscribe, Some("uuid:a"), Ok(1800));
+        diagnostics.record_notify(&pair(), "uuid:a", Some(0), 10, NotifyOutcome::Delivered);
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert!(seen.iter().all(|(p, _)| *p == pair()));
+        match &seen[0].1 {
+            ProtocolRecord::Exchange(exchange) => {
+                assert_eq!(exchange.kind, ExchangeKind::Subscribe);
+                assert_eq!(exchange.sid.as_deref(), Some("uuid:a"));
+                assert_eq!(exchange.granted_timeout_secs, Some(1800));
+            }
+            other => panic!("expected an exchange, got {other:?}"),
+        }
+        match &seen[1].1 {
+            ProtocolRecord::Notify { sid, receipt, .. } => {
+                assert_eq!((sid.as_str(), receipt.seq), ("uuid:a", Some(0)));
+            }
+            other => panic!("expected a receipt, got {other:?}"),
+        }
+        assert!(diagnostics
+            .history(pair().speaker_addr, Service::RenderingControl)
+            .is_none());
+    }
+
+    #[test]
+    fn test_json_redacts_identifiers() {
+        let diagnostics = ProtocolDiagnostics::new(4, RedactionPolicy::Identifiers);
+        let error = "connection to 192.168.1.50:1400 refused";
+        diagnostics.record_exchange(
+            &pair(),
+            ExchangeKind::Renew,
+            Some("uuid:RINCON_1234"),
+            Err(&error),
+        );
+
+        let json = diagnostics.to_json();
+        assert!(!json.contains("192.168.1.50"), "{json}");
+        assert!(!json.contains("RINCON"), "{json}");
+        assert!(json.contains("speaker-0:1400 refused"), "{json}");
+        assert!(json.contains("…1234"), "{json}");
+    }
+}
@@ -3,7 +3,7 @@
 //! This module defines all error types used throughout the crate, providing
 //! clear error messages and proper error chaining.
 
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// Main error type for the EventBroker
 #[derive(Debug, thiserror::Error)]
@@ -29,6 +29,9 @@ pub enum BrokerError {
     #[error("Firewall detection error: {0}")]
     FirewallDetection(String),
 
+    #[error("No coordinator known for group {0}")]
+    UnknownGroup(sonos_api::GroupId),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -39,9 +42,9 @@ pub enum BrokerError {
 /// Errors related to speaker/service registry operations
 #[derive(Debug, thiserror::Error)]
 pub enum RegistryError {
-    #[error("Speaker/service pair already registered: {speaker_ip} {service:?}")]
+    #[error("Speaker/service pair already registered: {speaker_addr} {service:?}")]
     DuplicateRegistration {
-        speaker_ip: IpAddr,
+        speaker_addr: SocketAddr,
         service: sonos_api::Service,
     },
 
@@ -78,6 +81,42 @@ pub enum SubscriptionError {
 
     #[error("Invalid subscription state")]
     InvalidState,
+
+    #[error("Subscription state error: {0}")]
+    State(#[from] SubscriptionStateError),
+
+    #[error(
+        "{service:?} on {speaker_addr} is already subscribed by {} with callback {callback_url}",
+        owner.as_deref().unwrap_or("another component")
+    )]
+    AlreadySubscribedExternally {
+        speaker_addr: SocketAddr,
+        service: sonos_api::Service,
+        /// Tag of the component holding the subscription, if it gave one
+        owner: Option<String>,
+        /// Where that subscription's events go
+        callback_url: String,
+    },
+}
+
+/// Transitions the subscription registry refused
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SubscriptionStateError {
+    #[error("Registration {0} already has a subscription")]
+    AlreadySubscribed(crate::RegistrationId),
+
+    #[error("Registration {0} has no subscription")]
+    NotSubscribed(crate::RegistrationId),
+
+    #[error("Subscription of registration {0} was replaced")]
+    Superseded(crate::RegistrationId),
+
+    #[error("Can't {action} registration {registration_id}'s subscription while {state:?}")]
+    InvalidTransition {
+        registration_id: crate::RegistrationId,
+        state: crate::subscription::SubscriptionState,
+        action: &'static str,
+    },
 }
 
 /// Errors related to polling operations
@@ -101,6 +140,9 @@ pub enum PollingError {
     #[error("Too many consecutive errors: {error_count}")]
     TooManyErrors { error_count: u32 },
 
+    #[error("Poll timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
     #[error("SOAP client error: {0}")]
     SoapClient(String),
 }
@@ -111,6 +153,11 @@ pub enum EventProcessingError {
     #[error("Event parsing failed: {0}")]
     Parsing(String),
 
+    /// The NOTIFY body was refused before parsing, for a DTD or for breaking
+    /// the [`XmlLimits`](sonos_api::XmlLimits)
+    #[error("Event XML rejected: {0}")]
+    XmlRejected(sonos_api::XmlViolation),
+
     #[error("Event enrichment failed: {0}")]
     Enrichment(String),
 
@@ -151,7 +198,7 @@ mod tests {
         assert!(err.to_string().contains("Configuration error"));
 
         let registry_err = RegistryError::DuplicateRegistration {
-            speaker_ip: "192.168.1.100".parse().unwrap(),
+            speaker_addr: "192.168.1.100:1400".parse().unwrap(),
             service: sonos_api::Service::AVTransport,
         };
         assert!(registry_err.to_string().contains("already registered"));
@@ -401,7 +401,7 @@ mod tests {
     fn create_test_event(registration_id: RegistrationId) -> EnrichedEvent {
         EnrichedEvent {
             registration_id,
-            speaker_ip: "192.168.1.100".parse().unwrap(),
+            speaker_addr: "192.168.1.100:1400".parse().unwrap(),
             service: sonos_api::Service::AVTransport,
             event_source: EventSource::UPnPNotification {
                 subscription_id: "test-sid".to_string(),
@@ -422,7 +422,10 @@ mod tests {
                 next_track_uri: None,
                 next_track_metadata: None,
                 queue_length: None,
+                transport_actions: None,
             }),
+            group: None,
+            stable_id: None,
         }
     }
 
@@ -6,10 +6,12 @@
 
 pub mod iterator;
 pub mod processor;
+pub mod spillover;
 pub mod types;
 
 pub use iterator::{EventIterator, SyncEventIterator};
 pub use processor::EventProcessor;
+pub use spillover::{SpilloverConfig, SpilloverMetrics, SpilloverQueue};
 pub use types::{
     // Re-export sonos-api state types for convenience
     AVTransportState,
@@ -3,20 +3,43 @@
 //! This processor replaces the old service-specific processing logic with
 //! a simple delegation to the sonos-api EventProcessor.
 
-use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot, RwLock};
 use tracing::{debug, error, info, trace, warn};
 
 use callback_server::{
     router::{EventRouter, NotificationPayload},
     FirewallDetectionCoordinator,
 };
-use sonos_api::events::EventProcessor as ApiEventProcessor;
+use sonos_api::events::{xml_utils, EventProcessor as ApiEventProcessor};
+use sonos_api::services::av_transport::AVTransportEventRef;
 
+use crate::chaos::{ChaosHook, EventFate};
+use crate::diagnostics::NotifyOutcome;
 use crate::error::{EventProcessingError, EventProcessingResult};
 use crate::events::types::{EnrichedEvent, EventData, EventSource};
+use crate::group::GroupTags;
+use crate::stable_id::StableIds;
+use crate::subscription::event_detector::EventDetector;
 use crate::subscription::manager::SubscriptionManager;
 
+fn api_error(e: sonos_api::ApiError) -> EventProcessingError {
+    match e {
+        sonos_api::ApiError::XmlRejected(violation) => EventProcessingError::XmlRejected(violation),
+        e => EventProcessingError::Parsing(format!("API processing failed: {e}")),
+    }
+}
+
+/// Parse an AVTransport NOTIFY body straight into its state
+///
+/// Values are borrowed from the `LastChange` document and copied once, into
+/// the state; the owned `AVTransportEvent` would copy each of them twice.
+fn parse_av_transport(xml: &str) -> EventProcessingResult<EventData> {
+    let last_change = xml_utils::last_change(xml).map_err(api_error)?;
+    let event = AVTransportEventRef::parse(&last_change).map_err(api_error)?;
+    Ok(EventData::AVTransport(event.to_state()))
+}
+
 /// Simplified event processor that delegates to sonos-api event framework
 pub struct EventProcessor {
     /// The sonos-api event processor that handles service-specific parsing
@@ -33,6 +56,24 @@ pub struct EventProcessor {
 
     /// Firewall detection coordinator for event arrival notifications
     firewall_coordinator: Option<Arc<FirewallDetectionCoordinator>>,
+
+    /// Flush requests, answered by the UPnP processing loop
+    flush_tx: mpsc::UnboundedSender<oneshot::Sender<usize>>,
+
+    /// Taken by [`start_upnp_processing()`](Self::start_upnp_processing)
+    flush_rx: Mutex<Option<mpsc::UnboundedReceiver<oneshot::Sender<usize>>>>,
+
+    /// Groups of registrations serving group subscriptions
+    group_tags: Arc<GroupTags>,
+
+    /// Stable IDs set on events
+    stable_ids: Arc<StableIds>,
+
+    /// Told of every event, so its silence timer restarts
+    event_detector: Option<Arc<EventDetector>>,
+
+    /// Faults injected between the callback server and processing
+    chaos: ChaosHook,
 }
 
 impl EventProcessor {
@@ -42,15 +83,46 @@ impl EventProcessor {
         event_sender: mpsc::UnboundedSender<EnrichedEvent>,
         firewall_coordinator: Option<Arc<FirewallDetectionCoordinator>>,
     ) -> Self {
+        let (flush_tx, flush_rx) = mpsc::unbounded_channel();
         Self {
             api_processor: ApiEventProcessor::with_default_parsers(),
             subscription_manager,
             event_sender,
             stats: Arc::new(RwLock::new(EventProcessorStats::new())),
             firewall_coordinator,
+            flush_tx,
+            flush_rx: Mutex::new(Some(flush_rx)),
+            group_tags: Arc::default(),
+            stable_ids: Arc::default(),
+            event_detector: None,
+            chaos: ChaosHook::default(),
         }
     }
 
+    /// Tag events with the group their registration serves
+    pub(crate) fn with_group_tags(mut self, group_tags: Arc<GroupTags>) -> Self {
+        self.group_tags = group_tags;
+        self
+    }
+
+    /// Set each event's stable ID from `stable_ids`
+    pub(crate) fn with_stable_ids(mut self, stable_ids: Arc<StableIds>) -> Self {
+        self.stable_ids = stable_ids;
+        self
+    }
+
+    /// Tell `event_detector` of every event received
+    pub(crate) fn with_event_detector(mut self, event_detector: Arc<EventDetector>) -> Self {
+        self.event_detector = Some(event_detector);
+        self
+    }
+
+    /// Drop, delay or duplicate events as `chaos` decides
+    pub(crate) fn with_chaos(mut self, chaos: ChaosHook) -> Self {
+        self.chaos = chaos;
+        self
+    }
+
     /// Process a UPnP notification payload from the callback server
     pub async fn process_upnp_notification(
         &self,
@@ -62,10 +134,10 @@ impl EventProcessor {
             stats.upnp_events_received += 1;
         }
 
-        // Look up subscription by SID
+        // Look up subscription by sending device and SID
         let subscription_wrapper = self
             .subscription_manager
-            .get_subscription_by_sid(&payload.subscription_id)
+            .get_subscription_for(payload.sender, &payload.subscription_id)
             .await
             .ok_or_else(|| {
                 EventProcessingError::Enrichment(format!(
@@ -79,52 +151,82 @@ impl EventProcessor {
         let registration_id = subscription_wrapper.registration_id();
 
         // Record that we received an event for this subscription
-        subscription_wrapper.record_event_received().await;
         self.subscription_manager
-            .record_event_received(&payload.subscription_id)
-            .await;
+            .record_event(registration_id, payload.seq);
+        if let Some(detector) = &self.event_detector {
+            detector.record_event(registration_id).await;
+        }
 
         // Notify firewall coordinator that an event was received
         if let Some(coordinator) = &self.firewall_coordinator {
-            coordinator.on_event_received(pair.speaker_ip).await;
+            coordinator.on_event_received(pair.speaker_addr.ip()).await;
+        }
+
+        let diagnostics = self.subscription_manager.diagnostics();
+        let (seq, size) = (payload.seq, payload.event_xml.len());
+        let record = |sid: &str, outcome| diagnostics.record_notify(pair, sid, seq, size, outcome);
+
+        // The callback server routes bodies it couldn't decompress so they
+        // surface here rather than as a parse failure on compressed bytes
+        if let Some(e) = &payload.decode_error {
+            record(&payload.subscription_id, NotifyOutcome::Undecodable);
+            return Err(EventProcessingError::Parsing(format!(
+                "Undecodable NOTIFY body: {e}"
+            )));
+        }
+
+        // Parse the event using sonos-api event processor. AVTransport, the
+        // busiest service, is read in place instead of into an owned event.
+        let event_data = match pair.service {
+            sonos_api::Service::AVTransport => parse_av_transport(&payload.event_xml),
+            service => self
+                .api_processor
+                .process_upnp_event(
+                    pair.speaker_addr.ip(),
+                    service,
+                    payload.subscription_id.clone(),
+                    &payload.event_xml,
+                )
+                .map_err(api_error)
+                // Convert from sonos-api enriched event to sonos-stream compatible format
+                .and_then(|api_event| self.convert_api_event_data(&service, api_event.event_data)),
         }
+        .inspect_err(|_| record(&payload.subscription_id, NotifyOutcome::ParseFailed))?;
 
-        // Parse the event using sonos-api event processor
-        let api_enriched_event = self
-            .api_processor
-            .process_upnp_event(
-                pair.speaker_ip, // speaker_ip is already an IpAddr
-                pair.service,
-                payload.subscription_id.clone(),
-                &payload.event_xml,
-            )
-            .map_err(|e| EventProcessingError::Parsing(format!("API processing failed: {e}")))?;
-
-        // Convert from sonos-api enriched event to sonos-stream compatible format
-        let event_data =
-            self.convert_api_event_data(&pair.service, api_enriched_event.event_data)?;
+        // The SID moves into the event; keep a copy only when recording
+        let sid = diagnostics
+            .is_enabled()
+            .then(|| payload.subscription_id.clone());
 
         // Create enriched event compatible with existing sonos-stream code
-        let enriched_event = EnrichedEvent::new(
+        let mut enriched_event = EnrichedEvent::new(
             registration_id,
-            pair.speaker_ip,
+            pair.speaker_addr,
             pair.service,
             EventSource::UPnPNotification {
                 subscription_id: payload.subscription_id,
             },
             event_data,
         );
+        enriched_event.group = self.group_tags.get(registration_id);
+        enriched_event.stable_id = self.stable_ids.id(pair.speaker_addr, pair.service);
 
         // Send enriched event
         debug!(
-            speaker_ip = %enriched_event.speaker_ip,
+            speaker_addr = %enriched_event.speaker_addr,
             service = ?enriched_event.service,
             event_source = ?enriched_event.event_source,
             "Routing event to EventIterator channel"
         );
-        self.event_sender
-            .send(enriched_event)
-            .map_err(|_| EventProcessingError::ChannelClosed)?;
+        let sent = self.event_sender.send(enriched_event);
+        if let Some(sid) = sid {
+            let outcome = match sent {
+                Ok(()) => NotifyOutcome::Delivered,
+                Err(_) => NotifyOutcome::ChannelClosed,
+            };
+            record(&sid, outcome);
+        }
+        sent.map_err(|_| EventProcessingError::ChannelClosed)?;
 
         // Update success stats
         {
@@ -145,7 +247,7 @@ impl EventProcessor {
 
         // Send the event (it's already enriched)
         debug!(
-            speaker_ip = %event.speaker_ip,
+            speaker_addr = %event.speaker_addr,
             service = ?event.service,
             event_source = ?event.event_source,
             "Routing polling event to EventIterator channel"
@@ -173,7 +275,7 @@ impl EventProcessor {
 
         // Send the event (it's already enriched)
         debug!(
-            speaker_ip = %event.speaker_ip,
+            speaker_addr = %event.speaker_addr,
             service = ?event.service,
             event_source = ?event.event_source,
             "Routing resync event to EventIterator channel"
@@ -247,6 +349,25 @@ impl EventProcessor {
                     })?;
                 Ok(EventData::GroupManagement(event.into_state()))
             }
+            sonos_api::Service::DeviceProperties => {
+                let event = api_event_data
+                    .downcast::<sonos_api::services::device_properties::DevicePropertiesEvent>()
+                    .map_err(|_| {
+                        EventProcessingError::Parsing(
+                            "Failed to downcast DeviceProperties event".to_string(),
+                        )
+                    })?;
+                Ok(EventData::DeviceProperties(event.into_state().into()))
+            }
+            sonos_api::Service::ConnectionManager => Err(EventProcessingError::Parsing(
+                "ConnectionManager events are not supported".to_string(),
+            )),
+            sonos_api::Service::AlarmClock => Err(EventProcessingError::Parsing(
+                "AlarmClock events are not supported".to_string(),
+            )),
+            sonos_api::Service::ContentDirectory => Err(EventProcessingError::Parsing(
+                "ContentDirectory events are not supported".to_string(),
+            )),
         }
     }
 
@@ -257,40 +378,28 @@ impl EventProcessor {
     ) {
         info!("Starting UPnP event processing using sonos-api framework");
 
+        let mut flush_rx = self.flush_rx.lock().ok().and_then(|mut rx| rx.take());
         let mut event_count = 0;
         loop {
             tokio::select! {
                 maybe_payload = upnp_receiver.recv() => {
                     match maybe_payload {
-                        Some(payload) => {
-                            event_count += 1;
-                            debug!(
-                                event_count,
-                                subscription_id = %payload.subscription_id,
-                                "Processing UPnP event"
-                            );
-
-                            match self.process_upnp_notification(payload).await {
-                                Ok(()) => {
-                                    trace!(event_count, "UPnP event processed successfully");
-                                }
-                                Err(e) => {
-                                    error!(
-                                        event_count,
-                                        error = %e,
-                                        "Failed to process UPnP event"
-                                    );
-                                    let mut stats = self.stats.write().await;
-                                    stats.processing_errors += 1;
-                                }
-                            }
-                        }
+                        Some(payload) => self.deliver(payload, &mut event_count).await,
                         None => {
                             warn!("UPnP receiver channel closed");
                             break;
                         }
                     }
                 }
+                Some(reply) = next_flush(&mut flush_rx) => {
+                    let mut flushed = 0;
+                    while let Ok(payload) = upnp_receiver.try_recv() {
+                        flushed += 1;
+                        self.deliver(payload, &mut event_count).await;
+                    }
+                    debug!(flushed, "Flushed queued UPnP events");
+                    let _ = reply.send(flushed);
+                }
                 _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => {
                     trace!(
                         events_processed = event_count,
@@ -303,6 +412,67 @@ impl EventProcessor {
         info!("UPnP event processing stopped");
     }
 
+    /// Hand a payload from the callback server to processing, unless chaos
+    /// testing drops, delays or duplicates it
+    async fn deliver(&self, payload: NotificationPayload, event_count: &mut usize) {
+        match self.chaos.event_fate() {
+            EventFate::Deliver => {}
+            EventFate::Drop => {
+                debug!(sid = %payload.subscription_id, "Chaos: dropped event");
+                return;
+            }
+            EventFate::Delay(delay) => tokio::time::sleep(delay).await,
+            EventFate::Duplicate => {
+                *event_count += 1;
+                self.handle_upnp_payload(payload.clone(), *event_count)
+                    .await;
+            }
+        }
+        *event_count += 1;
+        self.handle_upnp_payload(payload, *event_count).await;
+    }
+
+    /// Process every notification the callback server has already queued,
+    /// returning how many there were
+    ///
+    /// The callback server queues a NOTIFY before acknowledging it, so once
+    /// this returns every acknowledged notification is in the event stream
+    /// (or was rejected). Returns 0 if UPnP processing hasn't started.
+    pub async fn flush_upnp(&self) -> usize {
+        let started = self.flush_rx.lock().is_ok_and(|rx| rx.is_none());
+        if !started {
+            return 0;
+        }
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if self.flush_tx.send(reply_tx).is_err() {
+            return 0;
+        }
+        reply_rx.await.unwrap_or(0)
+    }
+
+    async fn handle_upnp_payload(&self, payload: NotificationPayload, event_count: usize) {
+        debug!(
+            event_count,
+            subscription_id = %payload.subscription_id,
+            "Processing UPnP event"
+        );
+
+        match self.process_upnp_notification(payload).await {
+            Ok(()) => {
+                trace!(event_count, "UPnP event processed successfully");
+            }
+            Err(e) => {
+                error!(
+                    event_count,
+                    error = %e,
+                    "Failed to process UPnP event"
+                );
+                let mut stats = self.stats.write().await;
+                stats.processing_errors += 1;
+            }
+        }
+    }
+
     /// Start processing polling events
     pub async fn start_polling_processing(
         &self,
@@ -451,9 +621,20 @@ pub async fn create_integrated_event_router(
     (router, upnp_receiver)
 }
 
+/// Next flush request, or never once the receiver is gone
+async fn next_flush(
+    flush_rx: &mut Option<mpsc::UnboundedReceiver<oneshot::Sender<usize>>>,
+) -> Option<oneshot::Sender<usize>> {
+    match flush_rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sonos_api::mock::{Action, MockDevice, Scenario};
 
     #[test]
     fn test_event_processor_creation() {
@@ -464,7 +645,7 @@ mod tests {
         let processor = EventProcessor::new(subscription_manager, event_sender, None);
 
         // Should have the supported services from sonos-api
-        assert_eq!(processor.supported_services().len(), 5); // AVTransport, RenderingControl, GroupRenderingControl, ZoneGroupTopology, GroupManagement
+        assert_eq!(processor.supported_services().len(), 6); // AVTransport, RenderingControl, GroupRenderingControl, ZoneGroupTopology, GroupManagement, DeviceProperties
         assert!(processor.is_service_supported(&sonos_api::Service::AVTransport));
         assert!(processor.is_service_supported(&sonos_api::Service::RenderingControl));
         assert!(processor.is_service_supported(&sonos_api::Service::GroupRenderingControl));
@@ -485,4 +666,137 @@ mod tests {
         assert_eq!(stats.total_events_received(), 0);
         assert_eq!(stats.success_rate(), 1.0);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_hostile_notify_is_rejected_apart_from_parse_failures() {
+        use crate::registry::{RegistrationId, SpeakerServicePair};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let manager = Arc::new(SubscriptionManager::new(
+            "http://127.0.0.1:3400".to_string(),
+        ));
+        let (event_sender, _event_receiver) = mpsc::unbounded_channel();
+        let processor = EventProcessor::new(Arc::clone(&manager), event_sender, None);
+        let doctype = r#"<!DOCTYPE e [<!ENTITY x SYSTEM "file:///etc/passwd">]><e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0">&x;</e:propertyset>"#;
+
+        for (id, service) in [
+            (1, sonos_api::Service::AVTransport),
+            (2, sonos_api::Service::RenderingControl),
+        ] {
+            let pair = SpeakerServicePair::new(device.addr(), service);
+            let wrapper = manager
+                .create_subscription(RegistrationId::new(id), pair)
+                .await
+                .unwrap();
+            let result = processor
+                .process_upnp_notification(NotificationPayload {
+                    subscription_id: wrapper.subscription_id().to_string(),
+                    event_xml: doctype.to_string(),
+                    seq: Some(0),
+                    content_encoding: None,
+                    decode_error: None,
+                    sender: None,
+                })
+                .await;
+            assert!(matches!(
+                result,
+                Err(EventProcessingError::XmlRejected(
+                    sonos_api::XmlViolation::Doctype
+                ))
+            ));
+        }
+    }
+
+    /// Subscribe, renew, receive a good, a malformed and an undecodable
+    /// NOTIFY, then fail a renewal once the device has dropped the subscription
+    async fn run_scripted_sequence(
+        diagnostics: Arc<crate::diagnostics::ProtocolDiagnostics>,
+        device: &MockDevice,
+    ) {
+        use crate::registry::{RegistrationId, SpeakerServicePair};
+
+        let manager = Arc::new(SubscriptionManager::with_diagnostics(
+            "http://127.0.0.1:3400".to_string(),
+            diagnostics,
+        ));
+        let pair = SpeakerServicePair::new(device.addr(), sonos_api::Service::RenderingControl);
+        let wrapper = manager
+            .create_subscription(RegistrationId::new(1), pair)
+            .await
+            .unwrap();
+        wrapper.renew().await.unwrap();
+
+        let (event_sender, _event_receiver) = mpsc::unbounded_channel();
+        let processor = EventProcessor::new(Arc::clone(&manager), event_sender, None);
+        let good = r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/RCS/&quot;&gt;&lt;InstanceID val=&quot;0&quot;&gt;&lt;Volume channel=&quot;Master&quot; val=&quot;30&quot;/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#;
+        let brotli = callback_server::DecodeError::UnsupportedEncoding("br".to_string());
+        for (seq, xml, decode_error) in [
+            (0, good, None),
+            (1, "<not-an-event", None),
+            (2, "", Some(brotli)),
+        ] {
+            let _ = processor
+                .process_upnp_notification(NotificationPayload {
+                    subscription_id: wrapper.subscription_id().to_string(),
+                    event_xml: xml.to_string(),
+                    seq: Some(seq),
+                    content_encoding: None,
+                    decode_error,
+                    sender: None,
+                })
+                .await;
+        }
+
+        device.perform(Action::Expire(sonos_api::Service::RenderingControl));
+        assert!(wrapper.renew().await.is_err());
+        assert!(wrapper.renew().await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_protocol_history_records_scripted_sequence() {
+        use crate::diagnostics::{
+            ExchangeKind, NotifyOutcome, ProtocolDiagnostics, RedactionPolicy,
+        };
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let addr = device.addr();
+        let service = sonos_api::Service::RenderingControl;
+
+        let diagnostics = Arc::new(ProtocolDiagnostics::new(3, RedactionPolicy::Off));
+        run_scripted_sequence(Arc::clone(&diagnostics), &device).await;
+
+        let history = diagnostics.history(addr, service).unwrap();
+        // Bounded to 3: the initial SUBSCRIBE was evicted by the last renewal
+        let kinds: Vec<_> = history.exchanges.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [ExchangeKind::Renew; 3]);
+        assert_eq!(history.exchanges[0].granted_timeout_secs, Some(1800));
+        assert!(history.exchanges[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("412"));
+        assert!(history
+            .exchanges
+            .windows(2)
+            .all(|w| w[0].at_ms <= w[1].at_ms));
+
+        let (sid, receipts) = &history.notifies[0];
+        assert_eq!(history.exchanges[0].sid.as_ref(), Some(sid));
+        let summary: Vec<_> = receipts.iter().map(|r| (r.seq, r.outcome)).collect();
+        assert_eq!(
+            summary,
+            [
+                (Some(0), NotifyOutcome::Delivered),
+                (Some(1), NotifyOutcome::ParseFailed),
+                (Some(2), NotifyOutcome::Undecodable)
+            ]
+        );
+        assert!(diagnostics.to_json().contains("\"renew\""));
+
+        // Disabled (the default): the same traffic builds nothing
+        let disabled = Arc::new(ProtocolDiagnostics::default());
+        run_scripted_sequence(Arc::clone(&disabled), &device).await;
+        assert_eq!(disabled.built(), 0);
+        assert!(disabled.history(addr, service).is_none());
+    }
 }
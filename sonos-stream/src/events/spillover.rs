@@ -0,0 +1,521 @@
+//! Disk spillover between the event stream and a slow consumer
+//!
+//! [`SpilloverQueue`] is an opt-in stand-in for the unbounded channel that
+//! carries [`EnrichedEvent`]s to their consumer. Up to `memory_capacity`
+//! events are queued in memory. Past that, events wait in an overflow batch
+//! where a newer event for a registration is collapsed into its queued one,
+//! and every full batch is appended to a temp file as JSON lines. Once memory
+//! is empty the consumer reads the file back, and the file is truncated when
+//! fully drained.
+//!
+//! The queue is read memory → file → overflow batch, and an event is only
+//! ever collapsed into the latest queued event of its own registration, so
+//! each registration's events stay in order. When the file reaches
+//! `max_file_bytes` the batch stays in memory; collapsing bounds it to one
+//! event per registration.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::events::types::EnrichedEvent;
+use crate::registry::RegistrationId;
+
+/// Configuration for a [`SpilloverQueue`]
+#[derive(Debug, Clone)]
+pub struct SpilloverConfig {
+    /// Events held in memory before overflow starts
+    /// Default: 256
+    pub memory_capacity: usize,
+
+    /// Overflow events collected (and collapsed) per append to the spill file
+    /// Default: 32
+    pub spill_batch: usize,
+
+    /// Size cap of the spill file in bytes
+    /// Default: 16 MiB
+    pub max_file_bytes: u64,
+
+    /// Directory the spill file is created in
+    /// Default: `std::env::temp_dir()`
+    pub directory: PathBuf,
+}
+
+impl Default for SpilloverConfig {
+    fn default() -> Self {
+        Self {
+            memory_capacity: 256,
+            spill_batch: 32,
+            max_file_bytes: 16 * 1024 * 1024,
+            directory: std::env::temp_dir(),
+        }
+    }
+}
+
+impl SpilloverConfig {
+    pub fn with_memory_capacity(mut self, events: usize) -> Self {
+        self.memory_capacity = events;
+        self
+    }
+
+    pub fn with_spill_batch(mut self, events: usize) -> Self {
+        self.spill_batch = events;
+        self
+    }
+
+    pub fn with_max_file_bytes(mut self, bytes: u64) -> Self {
+        self.max_file_bytes = bytes;
+        self
+    }
+
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = directory.into();
+        self
+    }
+}
+
+/// Counters for a [`SpilloverQueue`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpilloverMetrics {
+    /// Events appended to the spill file
+    pub spilled: u64,
+    /// Events read back from the spill file
+    pub drained: u64,
+    /// Events merged into a queued event for the same registration
+    pub collapsed: u64,
+    /// Current size of the spill file in bytes
+    pub file_bytes: u64,
+    /// Largest size the spill file has reached
+    pub peak_file_bytes: u64,
+}
+
+/// Event queue that spills to disk instead of growing without bound
+///
+/// `push()` never blocks on the consumer; `recv()` blocks until an event is
+/// available or the queue is closed and empty.
+pub struct SpilloverQueue {
+    config: SpilloverConfig,
+    state: Mutex<QueueState>,
+    ready: Condvar,
+}
+
+struct QueueState {
+    memory: VecDeque<EnrichedEvent>,
+    overflow: Vec<EnrichedEvent>,
+    file: Option<SpillFile>,
+    /// Events per registration waiting in the spill file
+    on_disk: HashMap<RegistrationId, usize>,
+    metrics: SpilloverMetrics,
+    closed: bool,
+}
+
+struct SpillFile {
+    path: PathBuf,
+    writer: File,
+    reader: BufReader<File>,
+    /// Records written but not read back yet
+    unread: usize,
+}
+
+impl SpillFile {
+    fn create(directory: &Path) -> io::Result<Self> {
+        let path = directory.join(format!("sonos-spill-{}.jsonl", uuid::Uuid::new_v4()));
+        let writer = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)?;
+        let reader = BufReader::new(File::open(&path)?);
+        Ok(Self {
+            path,
+            writer,
+            reader,
+            unread: 0,
+        })
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl SpilloverQueue {
+    /// Create an empty queue; the spill file is only created on first overflow
+    pub fn new(config: SpilloverConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(QueueState {
+                memory: VecDeque::new(),
+                overflow: Vec::new(),
+                file: None,
+                on_disk: HashMap::new(),
+                metrics: SpilloverMetrics::default(),
+                closed: false,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+
+    /// Queue an event. Returns `false` if the queue has been closed.
+    pub fn push(&self, event: EnrichedEvent) -> bool {
+        let mut state = self.lock();
+        if state.closed {
+            return false;
+        }
+        if state.is_backlogged() || state.memory.len() >= self.config.memory_capacity {
+            state.overflow(event, &self.config);
+        } else {
+            state.memory.push_back(event);
+        }
+        drop(state);
+        self.ready.notify_one();
+        true
+    }
+
+    /// Block until an event is available
+    ///
+    /// Returns `None` once the queue is closed and everything queued before
+    /// that has been received.
+    pub fn recv(&self) -> Option<EnrichedEvent> {
+        let mut state = self.lock();
+        loop {
+            if let Some(event) = state.take(self.config.memory_capacity) {
+                return Some(event);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .ready
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Block until an event is available or `timeout` expires
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<EnrichedEvent> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        loop {
+            if let Some(event) = state.take(self.config.memory_capacity) {
+                return Some(event);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if state.closed || remaining.is_zero() {
+                return None;
+            }
+            state = self
+                .ready
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Receive an event without blocking
+    pub fn try_recv(&self) -> Option<EnrichedEvent> {
+        self.lock().take(self.config.memory_capacity)
+    }
+
+    /// Stop accepting events; queued events can still be received
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.ready.notify_all();
+    }
+
+    pub fn metrics(&self) -> SpilloverMetrics {
+        self.lock().metrics
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl QueueState {
+    /// Whether earlier events are waiting outside memory
+    fn is_backlogged(&self) -> bool {
+        !self.overflow.is_empty() || self.file.as_ref().is_some_and(|f| f.unread > 0)
+    }
+
+    fn overflow(&mut self, event: EnrichedEvent, config: &SpilloverConfig) {
+        let id = event.registration_id;
+        // The latest queued event for this registration is in the overflow
+        // batch if it has one there, otherwise in memory unless some are on disk
+        let latest = match self
+            .overflow
+            .iter_mut()
+            .rev()
+            .find(|e| e.registration_id == id)
+        {
+            Some(queued) => Some(queued),
+            None if !self.on_disk.contains_key(&id) => self
+                .memory
+                .iter_mut()
+                .rev()
+                .find(|e| e.registration_id == id),
+            None => None,
+        };
+        if let Some(queued) = latest {
+            collapse(queued, event);
+            self.metrics.collapsed += 1;
+            return;
+        }
+
+        self.overflow.push(event);
+        if self.overflow.len() >= config.spill_batch {
+            self.spill(config);
+        }
+    }
+
+    /// Append as much of the overflow batch as fits under the size cap
+    fn spill(&mut self, config: &SpilloverConfig) {
+        if self.file.is_none() {
+            match SpillFile::create(&config.directory) {
+                Ok(file) => self.file = Some(file),
+                Err(e) => {
+                    tracing::warn!(
+                        "Cannot create event spill file in {}: {}",
+                        config.directory.display(),
+                        e
+                    );
+                    return;
+                }
+            }
+        }
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+
+        let mut buf = Vec::new();
+        let mut count = 0;
+        for event in &self.overflow {
+            let Ok(mut line) = serde_json::to_vec(event) else {
+                break;
+            };
+            line.push(b'\n');
+            if self.metrics.file_bytes + (buf.len() + line.len()) as u64 > config.max_file_bytes {
+                tracing::debug!("Event spill file is full; holding overflow in memory");
+                break;
+            }
+            buf.extend_from_slice(&line);
+            count += 1;
+        }
+        if count == 0 {
+            return;
+        }
+        if let Err(e) = file.writer.write_all(&buf) {
+            tracing::warn!("Failed to write event spill file: {}", e);
+            let _ = file.writer.set_len(self.metrics.file_bytes);
+            return;
+        }
+
+        for event in self.overflow.drain(..count) {
+            *self.on_disk.entry(event.registration_id).or_default() += 1;
+        }
+        file.unread += count;
+        self.metrics.spilled += count as u64;
+        self.metrics.file_bytes += buf.len() as u64;
+        self.metrics.peak_file_bytes = self.metrics.peak_file_bytes.max(self.metrics.file_bytes);
+    }
+
+    fn take(&mut self, memory_capacity: usize) -> Option<EnrichedEvent> {
+        if self.memory.is_empty() {
+            self.refill(memory_capacity);
+        }
+        self.memory.pop_front()
+    }
+
+    /// Refill memory from the spill file, or from the overflow batch once the
+    /// file is drained
+    fn refill(&mut self, memory_capacity: usize) {
+        let Some(file) = self.file.as_mut().filter(|f| f.unread > 0) else {
+            self.memory.extend(self.overflow.drain(..));
+            return;
+        };
+
+        while file.unread > 0 && self.memory.len() < memory_capacity.max(1) {
+            let mut line = String::new();
+            if !matches!(file.reader.read_line(&mut line), Ok(n) if n > 0) {
+                tracing::warn!(
+                    "Event spill file ended early; dropping {} events",
+                    file.unread
+                );
+                file.unread = 0;
+                break;
+            }
+            file.unread -= 1;
+            match serde_json::from_str::<EnrichedEvent>(&line) {
+                Ok(event) => {
+                    if let Some(count) = self.on_disk.get_mut(&event.registration_id) {
+                        *count -= 1;
+                        if *count == 0 {
+                            self.on_disk.remove(&event.registration_id);
+                        }
+                    }
+                    self.metrics.drained += 1;
+                    self.memory.push_back(event);
+                }
+                Err(e) => tracing::warn!("Dropping unreadable spilled event: {}", e),
+            }
+        }
+
+        if file.unread == 0 {
+            if let Err(e) = file
+                .writer
+                .set_len(0)
+                .and_then(|_| file.reader.seek(SeekFrom::Start(0)).map(drop))
+            {
+                tracing::warn!("Failed to truncate event spill file: {}", e);
+                self.file = None;
+            }
+            self.on_disk.clear();
+            self.metrics.file_bytes = 0;
+        }
+    }
+}
+
+/// Merge `newer` into the queued event of the same registration
+///
+/// LastChange events only carry the variables that changed, so replacing the
+/// queued event outright could lose a field it set. The newer envelope and
+/// every field it sets win; fields it leaves unset keep the queued value.
+fn collapse(queued: &mut EnrichedEvent, newer: EnrichedEvent) {
+    let older = std::mem::replace(queued, newer);
+    let (Ok(Value::Object(mut merged)), Ok(Value::Object(previous))) = (
+        serde_json::to_value(&queued.event_data),
+        serde_json::to_value(&older.event_data),
+    ) else {
+        return;
+    };
+    // Externally tagged: {"Variant": {fields}}
+    for (variant, fields) in merged.iter_mut() {
+        if let (Value::Object(fields), Some(Value::Object(old_fields))) =
+            (fields, previous.get(variant))
+        {
+            for (name, value) in old_fields {
+                let field = fields.entry(name.clone()).or_insert(Value::Null);
+                if field.is_null() {
+                    *field = value.clone();
+                }
+            }
+        }
+    }
+    if let Ok(data) = serde_json::from_value(Value::Object(merged)) {
+        queued.event_data = data;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::types::{EventData, EventSource, RenderingControlState};
+    use std::sync::Arc;
+    use std::thread;
+
+    fn event(registration: u64, volume: Option<u32>, mute: Option<&str>) -> EnrichedEvent {
+        EnrichedEvent::new(
+            RegistrationId::new(registration),
+            "192.168.1.100:1400".parse().unwrap(),
+            sonos_api::Service::RenderingControl,
+            EventSource::UPnPNotification {
+                subscription_id: format!("uuid:{registration}"),
+            },
+            EventData::RenderingControl(RenderingControlState {
+                master_volume: volume.map(|v| v.to_string()),
+                master_mute: mute.map(str::to_string),
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn fields(event: &EnrichedEvent) -> (Option<u32>, Option<String>) {
+        match &event.event_data {
+            EventData::RenderingControl(state) => (
+                state.master_volume.as_ref().map(|v| v.parse().unwrap()),
+                state.master_mute.clone(),
+            ),
+            other => panic!("unexpected event data {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_collapsing_keeps_fields_the_newer_event_leaves_unset() {
+        let queue = SpilloverQueue::new(SpilloverConfig::default().with_memory_capacity(1));
+        queue.push(event(1, Some(10), None));
+        queue.push(event(1, None, Some("1")));
+        queue.push(event(1, Some(12), None));
+
+        let collapsed = queue.try_recv().unwrap();
+        assert_eq!(fields(&collapsed), (Some(12), Some("1".to_string())));
+        assert!(queue.try_recv().is_none());
+        assert_eq!(queue.metrics().collapsed, 2);
+    }
+
+    #[test]
+    fn test_burst_spills_and_drains_in_order_for_throttled_consumer() {
+        const REGISTRATIONS: u32 = 40;
+        const EVENTS: u32 = 2000;
+        const CAP: u64 = 4 * 1024;
+        let dir = std::env::temp_dir().join(format!("sonos-spill-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let queue = Arc::new(SpilloverQueue::new(
+            SpilloverConfig::default()
+                .with_memory_capacity(8)
+                .with_spill_batch(4)
+                .with_max_file_bytes(CAP)
+                .with_directory(&dir),
+        ));
+
+        // Fake decoder: slow for the first 50 events, then full speed
+        let decoder = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                let mut seen = Vec::new();
+                while let Some(event) = queue.recv() {
+                    if seen.len() < 50 {
+                        thread::sleep(Duration::from_millis(2));
+                    }
+                    seen.push((event.registration_id.as_u64(), fields(&event).0.unwrap()));
+                }
+                seen
+            })
+        };
+        for seq in 0..EVENTS {
+            queue.push(event(u64::from(seq % REGISTRATIONS), Some(seq), None));
+        }
+        queue.close();
+        let seen = decoder.join().unwrap();
+
+        let mut last = HashMap::new();
+        for (registration, seq) in &seen {
+            if let Some(previous) = last.insert(*registration, *seq) {
+                assert!(
+                    *seq > previous,
+                    "registration {registration} went backwards"
+                );
+            }
+        }
+        for registration in 0..REGISTRATIONS {
+            let newest = EVENTS - REGISTRATIONS + registration;
+            assert_eq!(last[&u64::from(registration)], newest);
+        }
+
+        let metrics = queue.metrics();
+        assert!(metrics.spilled > 0 && metrics.collapsed > 0);
+        assert_eq!(metrics.drained, metrics.spilled);
+        assert!(metrics.peak_file_bytes <= CAP);
+        assert_eq!(metrics.file_bytes, 0);
+        assert_eq!(seen.len() as u64 + metrics.collapsed, u64::from(EVENTS));
+
+        drop(queue);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0, "spill file removed");
+        fs::remove_dir(&dir).unwrap();
+    }
+}
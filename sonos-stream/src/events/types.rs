@@ -5,13 +5,15 @@
 //! structs live in sonos-api; sonos-stream wraps them in EventData for transport.
 
 use serde::{Deserialize, Serialize};
-use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::time::{Duration, SystemTime};
 
+use crate::group::GroupTag;
 use crate::registry::RegistrationId;
 
 // Re-export sonos-api state types for convenience
 pub use sonos_api::services::av_transport::state::AVTransportState;
+pub use sonos_api::services::device_properties::state::DevicePropertiesState;
 pub use sonos_api::services::group_management::state::GroupManagementState;
 pub use sonos_api::services::group_rendering_control::state::GroupRenderingControlState;
 pub use sonos_api::services::rendering_control::state::RenderingControlState;
@@ -23,13 +25,13 @@ pub use sonos_api::services::zone_group_topology::events::{
 };
 
 /// An enriched event that includes context and source information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnrichedEvent {
     /// Registration ID this event belongs to
     pub registration_id: RegistrationId,
 
-    /// IP address of the speaker that generated this event
-    pub speaker_ip: IpAddr,
+    /// Address (IP and port) of the speaker that generated this event
+    pub speaker_addr: SocketAddr,
 
     /// UPnP service that generated this event
     pub service: sonos_api::Service,
@@ -42,30 +44,42 @@ pub struct EnrichedEvent {
 
     /// The actual event data
     pub event_data: EventData,
+
+    /// Group this event was delivered for, when it comes from a group
+    /// subscription
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<GroupTag>,
+
+    /// ID of the speaker/service pair that stays the same across restarts
+    /// and SID changes, when `BrokerConfig::stable_id_file` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
 }
 
 impl EnrichedEvent {
     /// Create a new enriched event
     pub fn new(
         registration_id: RegistrationId,
-        speaker_ip: IpAddr,
+        speaker_addr: SocketAddr,
         service: sonos_api::Service,
         event_source: EventSource,
         event_data: EventData,
     ) -> Self {
         Self {
             registration_id,
-            speaker_ip,
+            speaker_addr,
             service,
             event_source,
             timestamp: SystemTime::now(),
             event_data,
+            group: None,
+            stable_id: None,
         }
     }
 }
 
 /// Source of an event - indicates whether it came from UPnP events or polling
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EventSource {
     /// Event came from a UPnP NOTIFY message
     UPnPNotification {
@@ -85,7 +99,7 @@ pub enum EventSource {
 /// Variants reference canonical State types from sonos-api.
 /// Both UPnP events (via `into_state()`) and polling (via `poll()`)
 /// produce the same State types, ensuring parity.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EventData {
     /// AVTransport service state
     AVTransport(AVTransportState),
@@ -93,7 +107,7 @@ pub enum EventData {
     /// RenderingControl service state
     RenderingControl(RenderingControlState),
 
-    /// DeviceProperties service event (no sonos-api State type yet)
+    /// DeviceProperties service event
     DeviceProperties(DevicePropertiesEvent),
 
     /// ZoneGroupTopology service state
@@ -112,12 +126,7 @@ impl EventData {
         match self {
             EventData::AVTransport(_) => sonos_api::Service::AVTransport,
             EventData::RenderingControl(_) => sonos_api::Service::RenderingControl,
-            EventData::DeviceProperties(_) => {
-                // FIXME: DeviceProperties needs its own Service variant in sonos-api.
-                // Using ZoneGroupTopology as fallback could cause misrouted events
-                // if DeviceProperties polling is ever added to the scheduler.
-                sonos_api::Service::ZoneGroupTopology
-            }
+            EventData::DeviceProperties(_) => sonos_api::Service::DeviceProperties,
             EventData::ZoneGroupTopology(_) => sonos_api::Service::ZoneGroupTopology,
             EventData::GroupManagement(_) => sonos_api::Service::GroupManagement,
             EventData::GroupRenderingControl(_) => sonos_api::Service::GroupRenderingControl,
@@ -125,7 +134,8 @@ impl EventData {
     }
 }
 
-// DeviceProperties event types — kept here since there's no sonos-api State type yet
+// DeviceProperties event type — a superset of the sonos-api State type, which
+// doesn't carry the model and version fields
 
 /// Complete DeviceProperties event data containing all device property information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,10 +164,42 @@ pub struct DevicePropertiesEvent {
     /// Device hardware version
     pub hardware_version: Option<String>,
 
+    /// Whether the buttons/touch controls are locked
+    #[serde(default)]
+    pub button_lock: Option<bool>,
+
+    /// Whether the white status light is on
+    #[serde(default)]
+    pub status_light: Option<bool>,
+
+    /// Where a portable speaker's audio is going (`SPEAKER`, `HEADPHONE`,
+    /// `BLUETOOTH` or `LINE_OUT`)
+    #[serde(default)]
+    pub audio_output: Option<String>,
+
     /// Additional device properties (extensible)
     pub additional_properties: std::collections::HashMap<String, String>,
 }
 
+impl From<DevicePropertiesState> for DevicePropertiesEvent {
+    fn from(state: DevicePropertiesState) -> Self {
+        Self {
+            zone_name: state.zone_name,
+            zone_icon: state.icon,
+            configuration: state.configuration,
+            capabilities: None,
+            software_version: None,
+            model_name: None,
+            display_version: None,
+            hardware_version: None,
+            button_lock: state.button_lock,
+            status_light: state.status_light,
+            audio_output: state.audio_output,
+            additional_properties: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,7 +207,7 @@ mod tests {
     #[test]
     fn test_enriched_event_creation() {
         let reg_id = RegistrationId::new(1);
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let service = sonos_api::Service::AVTransport;
         let source = EventSource::UPnPNotification {
             subscription_id: "uuid:123".to_string(),
@@ -185,12 +227,13 @@ mod tests {
             next_track_uri: None,
             next_track_metadata: None,
             queue_length: None,
+            transport_actions: None,
         });
 
         let event = EnrichedEvent::new(reg_id, ip, service, source, data);
 
         assert_eq!(event.registration_id, reg_id);
-        assert_eq!(event.speaker_ip, ip);
+        assert_eq!(event.speaker_addr, ip);
         assert_eq!(event.service, service);
     }
 
@@ -211,6 +254,7 @@ mod tests {
             next_track_uri: None,
             next_track_metadata: None,
             queue_length: None,
+            transport_actions: None,
         });
         assert_eq!(av_event.service_type(), sonos_api::Service::AVTransport);
 
@@ -225,7 +269,7 @@ mod tests {
             treble: None,
             loudness: None,
             balance: None,
-            other_channels: std::collections::HashMap::new(),
+            ..Default::default()
         });
         assert_eq!(
             rc_event.service_type(),
@@ -0,0 +1,237 @@
+//! Group-scoped subscriptions
+//!
+//! AVTransport and GroupRenderingControl belong to a group's coordinator,
+//! which changes when coordination is handed off.
+//! [`EventBroker::subscribe_group()`](crate::EventBroker::subscribe_group)
+//! subscribes to whichever speaker coordinates a group and moves the
+//! subscription when that changes. These are the supporting types: how a
+//! coordinator is found, how events are tagged, and the lifecycle events of
+//! a migration.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use sonos_api::services::zone_group_topology::state::ZoneGroupTopologyState;
+use sonos_api::{GroupId, Service, SpeakerId};
+
+use crate::registry::RegistrationId;
+
+/// The speaker coordinating a group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coordinator {
+    pub id: SpeakerId,
+    pub addr: SocketAddr,
+}
+
+/// Looks up a group's current coordinator, for applications that track
+/// topology themselves
+///
+/// Consulted before the topology the broker has observed. Runs on the
+/// broker's task, so it must return quickly.
+#[derive(Clone)]
+pub struct GroupResolver(Arc<ResolverFn>);
+
+type ResolverFn = dyn Fn(&GroupId) -> Option<Coordinator> + Send + Sync;
+
+impl GroupResolver {
+    pub fn new(resolver: impl Fn(&GroupId) -> Option<Coordinator> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(resolver))
+    }
+
+    pub fn resolve(&self, group_id: &GroupId) -> Option<Coordinator> {
+        (self.0)(group_id)
+    }
+}
+
+impl fmt::Debug for GroupResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("GroupResolver")
+    }
+}
+
+/// Group an event was delivered for, set on events of a group subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupTag {
+    /// Stays the same across coordinator handoffs
+    pub group_id: GroupId,
+    /// Coordinator the event came from
+    pub coordinator: SpeakerId,
+}
+
+/// Lifecycle of a group subscription, from
+/// [`EventBroker::group_events()`](crate::EventBroker::group_events)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupSubscriptionEvent {
+    /// The coordinator changed and the subscription moved to the new one.
+    /// Its initial event carries the full state, so nothing that changed
+    /// during the move is lost.
+    Migrated {
+        group_id: GroupId,
+        service: Service,
+        from: Coordinator,
+        to: Coordinator,
+        registration_id: RegistrationId,
+    },
+    /// The coordinator changed but subscribing to (or polling) the new one
+    /// failed; the next topology change retries
+    MigrationFailed {
+        group_id: GroupId,
+        service: Service,
+        from: Coordinator,
+        to: Coordinator,
+        error: String,
+    },
+}
+
+/// Coordinators of the groups in the last topology observed
+pub(crate) fn coordinators(topology: &ZoneGroupTopologyState) -> HashMap<GroupId, Coordinator> {
+    topology
+        .zone_groups
+        .iter()
+        .filter_map(|group| {
+            let member = group
+                .members
+                .iter()
+                .find(|member| member.uuid == group.coordinator)?;
+            let addr = location_addr(&member.location)?;
+            Some((
+                GroupId::new(&group.id),
+                Coordinator {
+                    id: SpeakerId::new(&group.coordinator),
+                    addr,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// UUID and address of every speaker in the topology
+pub(crate) fn members(
+    topology: &ZoneGroupTopologyState,
+) -> impl Iterator<Item = (&str, SocketAddr)> {
+    topology
+        .zone_groups
+        .iter()
+        .flat_map(|group| &group.members)
+        .filter_map(|member| Some((member.uuid.as_str(), location_addr(&member.location)?)))
+}
+
+/// Address of a speaker from its device description URL
+/// (`http://192.168.1.10:1400/xml/device_description.xml`)
+fn location_addr(location: &str) -> Option<SocketAddr> {
+    location
+        .strip_prefix("http://")?
+        .split('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Group tags by registration, read as events are created
+#[derive(Debug, Default)]
+pub(crate) struct GroupTags(ArcSwap<HashMap<RegistrationId, GroupTag>>);
+
+impl GroupTags {
+    pub(crate) fn get(&self, registration_id: RegistrationId) -> Option<GroupTag> {
+        self.0.load().get(&registration_id).cloned()
+    }
+
+    pub(crate) fn set(&self, registration_id: RegistrationId, tag: GroupTag) {
+        self.0.rcu(|tags| {
+            let mut tags = HashMap::clone(tags);
+            tags.insert(registration_id, tag.clone());
+            tags
+        });
+    }
+
+    pub(crate) fn remove(&self, registration_id: RegistrationId) {
+        self.0.rcu(|tags| {
+            let mut tags = HashMap::clone(tags);
+            tags.remove(&registration_id);
+            tags
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sonos_api::services::zone_group_topology::events::{
+        NetworkInfo, ZoneGroupInfo, ZoneGroupMemberInfo,
+    };
+
+    fn member(uuid: &str, location: &str) -> ZoneGroupMemberInfo {
+        ZoneGroupMemberInfo {
+            uuid: uuid.to_string(),
+            location: location.to_string(),
+            zone_name: uuid.to_string(),
+            software_version: String::new(),
+            software_generation: None,
+            boot_seq: 1,
+            network_info: NetworkInfo::default(),
+            satellites: vec![],
+        }
+    }
+
+    #[test]
+    fn test_coordinators_from_topology() {
+        let topology = ZoneGroupTopologyState {
+            zone_groups: vec![
+                ZoneGroupInfo {
+                    coordinator: "RINCON_B".to_string(),
+                    id: "RINCON_A:7".to_string(),
+                    members: vec![
+                        member(
+                            "RINCON_A",
+                            "http://192.168.1.10:1400/xml/device_description.xml",
+                        ),
+                        member(
+                            "RINCON_B",
+                            "http://192.168.1.11:1400/xml/device_description.xml",
+                        ),
+                    ],
+                },
+                // Coordinator missing from its members, or unreachable
+                ZoneGroupInfo {
+                    coordinator: "RINCON_C".to_string(),
+                    id: "RINCON_C:1".to_string(),
+                    members: vec![member("RINCON_D", "http://192.168.1.13:1400/")],
+                },
+                ZoneGroupInfo {
+                    coordinator: "RINCON_E".to_string(),
+                    id: "RINCON_E:1".to_string(),
+                    members: vec![member("RINCON_E", "")],
+                },
+            ],
+            vanished_devices: vec![],
+        };
+
+        let coordinators = coordinators(&topology);
+        assert_eq!(coordinators.len(), 1);
+        assert_eq!(
+            coordinators[&GroupId::new("RINCON_A:7")],
+            Coordinator {
+                id: SpeakerId::new("RINCON_B"),
+                addr: "192.168.1.11:1400".parse().unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_group_tags() {
+        let tags = GroupTags::default();
+        let tag = GroupTag {
+            group_id: GroupId::new("RINCON_A:7"),
+            coordinator: SpeakerId::new("RINCON_A"),
+        };
+        tags.set(RegistrationId::new(1), tag.clone());
+        assert_eq!(tags.get(RegistrationId::new(1)), Some(tag));
+        assert_eq!(tags.get(RegistrationId::new(2)), None);
+        tags.remove(RegistrationId::new(1));
+        assert_eq!(tags.get(RegistrationId::new(1)), None);
+    }
+}
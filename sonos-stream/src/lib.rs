@@ -26,7 +26,7 @@
 //! let mut broker = EventBroker::new(BrokerConfig::default()).await?;
 //!
 //! // Register speakers with automatic duplicate protection
-//! let reg1 = broker.register_speaker_service("192.168.1.100".parse()?, Service::AVTransport).await?;
+//! let reg1 = broker.register_speaker_service("192.168.1.100:1400".parse()?, Service::AVTransport).await?;
 //!
 //! // Process events with optimal sync iterator for state management
 //! let mut events = broker.event_iterator();
@@ -44,26 +44,41 @@
 //! - [`subscription`] - Integration with SonosClient's ManagedSubscription lifecycle
 //! - [`polling`] - Intelligent polling system with service-specific strategies
 //! - [`events`] - Event processing, enrichment, and iterator interfaces
+//! - [`SpilloverQueue`] - Opt-in disk spillover for consumers that fall behind
+//! - [`diagnostics`] - Bounded SUBSCRIBE/NOTIFY history for bug reports
+//! - [`stable_id`] - Subscription identities kept across restarts
 
 pub mod broker;
+pub mod chaos;
 pub mod config;
+pub mod diagnostics;
 pub mod error;
 pub mod events;
+pub mod group;
 pub mod polling;
 pub mod registry;
+pub mod stable_id;
 pub mod subscription;
 
 // Re-export main types for easy access
-pub use broker::{EventBroker, PollingReason, RegistrationResult};
+pub use broker::{EventBroker, PollingReason, RegistrationResult, SuspendPolicy};
+#[cfg(feature = "chaos-testing")]
+pub use chaos::{ChaosController, ChaosStats};
 pub use config::BrokerConfig;
+pub use diagnostics::{ProtocolHistory, ProtocolObserver, ProtocolRecord, RedactionPolicy};
 pub use error::{BrokerError, PollingError, RegistryError, SubscriptionError};
 pub use events::iterator::EventIterator;
+pub use events::spillover::{SpilloverConfig, SpilloverMetrics, SpilloverQueue};
 pub use events::types::{EnrichedEvent, EventData, EventSource};
+pub use group::{Coordinator, GroupResolver, GroupSubscriptionEvent, GroupTag};
+pub use polling::{PollingHealthEvent, ResumeReason, TaskHealth, WatchdogConfig};
 pub use registry::{RegistrationId, SpeakerServicePair};
+pub use stable_id::StableIds;
 
 // Re-export types from dependencies that users commonly need
 pub use callback_server::firewall_detection::FirewallStatus;
-pub use sonos_api::Service;
+pub use callback_server::PortSelection;
+pub use sonos_api::{EventingConnection, Service, SubscriptionDirectory};
 
 #[cfg(test)]
 mod tests {
@@ -77,7 +92,7 @@ mod tests {
         // Test that we can create RegistrationId and SpeakerServicePair
         let _reg_id = RegistrationId::new(1);
         let _pair = SpeakerServicePair {
-            speaker_ip: "192.168.1.100".parse().unwrap(),
+            speaker_addr: "192.168.1.100:1400".parse().unwrap(),
             service: Service::AVTransport,
         };
     }
@@ -7,5 +7,7 @@
 pub mod scheduler;
 pub mod strategies;
 
-pub use scheduler::{PollingScheduler, PollingTask};
+pub use scheduler::{
+    PollingHealthEvent, PollingScheduler, PollingTask, ResumeReason, TaskHealth, WatchdogConfig,
+};
 pub use strategies::{AVTransportPoller, DeviceStatePoller, RenderingControlPoller, ServicePoller};
@@ -2,22 +2,200 @@
 //!
 //! This module provides intelligent polling task management with support for
 //! adaptive intervals, graceful shutdown, and coordination with the event system.
+//!
+//! Each task runs in its own spawned future, so a slow device only delays
+//! its own polls. A watchdog bounds every poll with a timeout, tracks each
+//! task's [`TaskHealth`], and suspends a task that keeps failing.
+//!
+//! Intervals, back-off and cool-downs are waited out on the scheduler's
+//! [`Clock`](sonos_api::clock::Clock), so a manual clock drives them in
+//! tests. Poll timeouts stay in real time: they bound network I/O.
 
+use sonos_api::clock::{SharedClock, SystemClock};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
-use tokio::sync::{mpsc, RwLock};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, Notify, RwLock};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
 use crate::error::{PollingError, PollingResult};
 use crate::events::types::{EnrichedEvent, EventSource};
+use crate::group::GroupTags;
 use crate::polling::strategies::DeviceStatePoller;
 use crate::registry::{RegistrationId, SpeakerServicePair};
+use crate::stable_id::StableIds;
+
+/// Limits the polling watchdog enforces on every task
+///
+/// The SOAP client has its own connect and read timeouts, but a poll makes
+/// several requests, so a half-dead device can hold one for far longer.
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    /// Longest a single poll may take before it counts as failed
+    /// Default: 30 seconds
+    pub poll_timeout: Duration,
+
+    /// Consecutive failures (errors or timeouts) that suspend a task
+    /// Default: 5
+    pub failure_threshold: u32,
+
+    /// How long a suspended task waits before polling again, unless its
+    /// device is reported reachable first
+    /// Default: 60 seconds
+    pub suspend_cooldown: Duration,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            poll_timeout: Duration::from_secs(30),
+            failure_threshold: 5,
+            suspend_cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Health of one polling task, as tracked by the watchdog
+#[derive(Debug, Clone)]
+pub struct TaskHealth {
+    pub registration_id: RegistrationId,
+    pub speaker_service_pair: SpeakerServicePair,
+    /// When the last poll started
+    pub last_run: Option<Instant>,
+    /// When the last successful poll started
+    pub last_success: Option<Instant>,
+    /// Failures since the last success; reset on resumption
+    pub consecutive_failures: u32,
+    /// Polls started, successful or not
+    pub polls: u64,
+    /// Polls cut off by [`WatchdogConfig::poll_timeout`]
+    pub timeouts: u64,
+    /// Set while suspended: when the cool-down ends
+    pub suspended_until: Option<Instant>,
+    total_duration: Duration,
+}
+
+impl TaskHealth {
+    fn new(registration_id: RegistrationId, speaker_service_pair: SpeakerServicePair) -> Self {
+        Self {
+            registration_id,
+            speaker_service_pair,
+            last_run: None,
+            last_success: None,
+            consecutive_failures: 0,
+            polls: 0,
+            timeouts: 0,
+            suspended_until: None,
+            total_duration: Duration::ZERO,
+        }
+    }
+
+    /// Mean time a poll took, timed-out ones counted at the timeout
+    pub fn average_duration(&self) -> Option<Duration> {
+        u32::try_from(self.polls)
+            .ok()
+            .filter(|polls| *polls > 0)
+            .map(|polls| self.total_duration / polls)
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended_until.is_some()
+    }
+}
+
+/// Why a suspended task started polling again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeReason {
+    /// [`WatchdogConfig::suspend_cooldown`] passed
+    CooldownElapsed,
+    /// Another poll of the device recovered, or
+    /// [`PollingScheduler::resume_device()`] was called
+    DeviceReachable,
+}
+
+/// Suspension and resumption of a polling task
+#[derive(Debug, Clone)]
+pub enum PollingHealthEvent {
+    Suspended {
+        registration_id: RegistrationId,
+        speaker_service_pair: SpeakerServicePair,
+        consecutive_failures: u32,
+        cooldown: Duration,
+    },
+    Resumed {
+        registration_id: RegistrationId,
+        speaker_service_pair: SpeakerServicePair,
+        reason: ResumeReason,
+    },
+}
+
+/// Health record and wake-up signal of one task, shared with the scheduler
+struct TaskMonitor {
+    health: Mutex<TaskHealth>,
+    /// Wakes the task from any wait: shutdown, or resumption while suspended
+    wake: Notify,
+    clock: SharedClock,
+}
+
+impl TaskMonitor {
+    fn health(&self) -> MutexGuard<'_, TaskHealth> {
+        self.health.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sleep for `duration`; `false` if woken early
+    async fn sleep(&self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = self.clock.timer(self.clock.now() + duration) => true,
+            _ = self.wake.notified() => false,
+        }
+    }
+}
+
+/// Per-task health shared by every task of a scheduler
+struct Watchdog {
+    config: WatchdogConfig,
+    clock: SharedClock,
+    monitors: Mutex<HashMap<RegistrationId, Arc<TaskMonitor>>>,
+    events: broadcast::Sender<PollingHealthEvent>,
+}
+
+impl Watchdog {
+    fn new(config: WatchdogConfig, clock: SharedClock) -> Self {
+        Self {
+            config,
+            clock,
+            monitors: Mutex::new(HashMap::new()),
+            events: broadcast::channel(64).0,
+        }
+    }
+
+    fn monitors(&self) -> MutexGuard<'_, HashMap<RegistrationId, Arc<TaskMonitor>>> {
+        self.monitors.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Wake the suspended tasks polling `device_ip`, returning how many
+    fn resume_device(&self, device_ip: IpAddr) -> usize {
+        let mut woken = 0;
+        for monitor in self.monitors().values() {
+            let health = monitor.health();
+            if health.is_suspended() && health.speaker_service_pair.speaker_addr.ip() == device_ip {
+                monitor.wake.notify_one();
+                woken += 1;
+            }
+        }
+        woken
+    }
+
+    fn emit(&self, event: PollingHealthEvent) {
+        // No receivers is fine
+        let _ = self.events.send(event);
+    }
+}
 
 /// A single polling task with state management
-#[derive(Debug)]
 pub struct PollingTask {
     /// Registration ID this task is polling for
     registration_id: RegistrationId,
@@ -35,18 +213,30 @@ pub struct PollingTask {
     shutdown_signal: Arc<AtomicBool>,
 
     /// When this task was started
-    started_at: SystemTime,
+    started_at: Instant,
+
+    /// Health record, shared with the watchdog
+    monitor: Arc<TaskMonitor>,
 
-    /// Number of consecutive errors
-    error_count: Arc<RwLock<u32>>,
+    /// Removes the monitor when the task shuts down
+    watchdog: Arc<Watchdog>,
+}
 
-    /// Total number of polls performed
-    poll_count: Arc<RwLock<u64>>,
+impl std::fmt::Debug for PollingTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PollingTask")
+            .field("registration_id", &self.registration_id)
+            .field("speaker_service_pair", &self.speaker_service_pair)
+            .field("current_interval", &self.current_interval)
+            .field("started_at", &self.started_at)
+            .finish_non_exhaustive()
+    }
 }
 
 impl PollingTask {
     /// Create and start a new polling task
-    pub fn start(
+    #[allow(clippy::too_many_arguments)]
+    fn start(
         registration_id: RegistrationId,
         speaker_service_pair: SpeakerServicePair,
         initial_interval: Duration,
@@ -54,17 +244,29 @@ impl PollingTask {
         adaptive_polling: bool,
         device_poller: Arc<DeviceStatePoller>,
         event_sender: mpsc::UnboundedSender<EnrichedEvent>,
+        group_tags: Arc<GroupTags>,
+        stable_id: Option<String>,
+        watchdog: Arc<Watchdog>,
     ) -> Self {
         let shutdown_signal = Arc::new(AtomicBool::new(false));
-        let error_count = Arc::new(RwLock::new(0));
-        let poll_count = Arc::new(RwLock::new(0));
+        let monitor = Arc::new(TaskMonitor {
+            health: Mutex::new(TaskHealth::new(
+                registration_id,
+                speaker_service_pair.clone(),
+            )),
+            wake: Notify::new(),
+            clock: Arc::clone(&watchdog.clock),
+        });
+        watchdog
+            .monitors()
+            .insert(registration_id, Arc::clone(&monitor));
 
         // Clone for the task
         let task_registration_id = registration_id;
         let task_pair = speaker_service_pair.clone();
         let task_shutdown_signal = Arc::clone(&shutdown_signal);
-        let task_error_count = Arc::clone(&error_count);
-        let task_poll_count = Arc::clone(&poll_count);
+        let task_monitor = Arc::clone(&monitor);
+        let task_watchdog = Arc::clone(&watchdog);
 
         let task_handle = tokio::spawn(async move {
             Self::polling_loop(
@@ -75,9 +277,11 @@ impl PollingTask {
                 adaptive_polling,
                 device_poller,
                 event_sender,
+                group_tags,
+                stable_id,
                 task_shutdown_signal,
-                task_error_count,
-                task_poll_count,
+                task_monitor,
+                task_watchdog,
             )
             .await;
         });
@@ -88,13 +292,17 @@ impl PollingTask {
             current_interval: initial_interval,
             task_handle,
             shutdown_signal,
-            started_at: SystemTime::now(),
-            error_count,
-            poll_count,
+            started_at: monitor.clock.now(),
+            monitor,
+            watchdog,
         }
     }
 
     /// Main polling loop
+    ///
+    /// Every poll is bounded by the watchdog's `poll_timeout`. After
+    /// `failure_threshold` consecutive failures the task suspends itself
+    /// for `suspend_cooldown`, or until its device is reported reachable.
     #[allow(clippy::too_many_arguments)]
     async fn polling_loop(
         registration_id: RegistrationId,
@@ -104,17 +312,21 @@ impl PollingTask {
         adaptive_polling: bool,
         device_poller: Arc<DeviceStatePoller>,
         event_sender: mpsc::UnboundedSender<EnrichedEvent>,
+        group_tags: Arc<GroupTags>,
+        stable_id: Option<String>,
         shutdown_signal: Arc<AtomicBool>,
-        error_count: Arc<RwLock<u32>>,
-        poll_count: Arc<RwLock<u64>>,
+        monitor: Arc<TaskMonitor>,
+        watchdog: Arc<Watchdog>,
     ) {
         info!(
-            speaker_ip = %pair.speaker_ip,
+            speaker_addr = %pair.speaker_addr,
             service = ?pair.service,
             ?current_interval,
             "Starting polling task"
         );
 
+        let config = &watchdog.config;
+
         // Track last state locally within the loop
         let mut last_state: Option<String> = None;
 
@@ -122,7 +334,7 @@ impl PollingTask {
             // Check for shutdown signal
             if shutdown_signal.load(Ordering::Relaxed) {
                 info!(
-                    speaker_ip = %pair.speaker_ip,
+                    speaker_addr = %pair.speaker_addr,
                     service = ?pair.service,
                     "Polling task shutting down"
                 );
@@ -130,21 +342,38 @@ impl PollingTask {
             }
 
             // Sleep for the current interval
-            tokio::time::sleep(current_interval).await;
+            monitor.sleep(current_interval).await;
+            if shutdown_signal.load(Ordering::Relaxed) {
+                continue;
+            }
 
-            // Increment poll count
+            // Poll the device state, independently of the SOAP client's timeouts
+            let started = monitor.clock.now();
+            let timing = Instant::now();
+            monitor.health().last_run = Some(started);
+            let result = match tokio::time::timeout(
+                config.poll_timeout,
+                device_poller.poll_device_state(&pair),
+            )
+            .await
             {
-                let mut count = poll_count.write().await;
-                *count += 1;
-            }
+                Ok(result) => result,
+                Err(_) => Err(PollingError::Timeout(config.poll_timeout)),
+            };
 
-            // Poll the device state
-            match device_poller.poll_device_state(&pair).await {
+            match result {
                 Ok(current_state) => {
-                    // Reset error count on success
-                    {
-                        let mut errors = error_count.write().await;
-                        *errors = 0;
+                    let recovered = {
+                        let mut health = monitor.health();
+                        health.polls += 1;
+                        health.total_duration += timing.elapsed();
+                        health.last_success = Some(started);
+                        std::mem::take(&mut health.consecutive_failures) > 0
+                    };
+                    // The device answers again: its other suspended tasks
+                    // needn't wait out their cool-down
+                    if recovered {
+                        watchdog.resume_device(pair.speaker_addr.ip());
                     }
 
                     // Check for state changes (compare without cloning)
@@ -156,7 +385,7 @@ impl PollingTask {
 
                     if state_changed {
                         debug!(
-                            speaker_ip = %pair.speaker_ip,
+                            speaker_addr = %pair.speaker_addr,
                             service = ?pair.service,
                             "State change detected"
                         );
@@ -164,19 +393,21 @@ impl PollingTask {
                         // Convert JSON snapshot to EventData and emit full-state event
                         match device_poller.state_to_event_data(&pair.service, &current_state) {
                             Ok(event_data) => {
-                                let enriched_event = EnrichedEvent::new(
+                                let mut enriched_event = EnrichedEvent::new(
                                     registration_id,
-                                    pair.speaker_ip,
+                                    pair.speaker_addr,
                                     pair.service,
                                     EventSource::PollingDetection {
                                         poll_interval: current_interval,
                                     },
                                     event_data,
                                 );
+                                enriched_event.group = group_tags.get(registration_id);
+                                enriched_event.stable_id = stable_id.clone();
 
                                 if event_sender.send(enriched_event).is_err() {
                                     error!(
-                                        speaker_ip = %pair.speaker_ip,
+                                        speaker_addr = %pair.speaker_addr,
                                         service = ?pair.service,
                                         "Failed to send polling event — channel closed"
                                     );
@@ -185,7 +416,7 @@ impl PollingTask {
                             }
                             Err(e) => {
                                 warn!(
-                                    speaker_ip = %pair.speaker_ip,
+                                    speaker_addr = %pair.speaker_addr,
                                     service = ?pair.service,
                                     error = %e,
                                     "Failed to convert state to event data"
@@ -198,61 +429,113 @@ impl PollingTask {
                             current_interval = Self::calculate_adaptive_interval(
                                 current_interval,
                                 max_interval,
-                                SystemTime::now(),
+                                Instant::now(),
                             );
                         }
                     }
                 }
                 Err(e) => {
-                    // Increment error count
                     let error_count_value = {
-                        let mut errors = error_count.write().await;
-                        *errors += 1;
-                        *errors
+                        let mut health = monitor.health();
+                        health.polls += 1;
+                        health.total_duration += timing.elapsed();
+                        if matches!(e, PollingError::Timeout(_)) {
+                            health.timeouts += 1;
+                        }
+                        health.consecutive_failures += 1;
+                        health.consecutive_failures
                     };
 
                     warn!(
-                        speaker_ip = %pair.speaker_ip,
+                        speaker_addr = %pair.speaker_addr,
                         service = ?pair.service,
                         attempt = error_count_value,
                         error = %e,
                         "Polling error"
                     );
 
-                    // Use exponential backoff for errors
-                    if error_count_value >= 5 {
-                        error!(
-                            speaker_ip = %pair.speaker_ip,
-                            service = ?pair.service,
-                            "Too many consecutive errors, stopping polling"
-                        );
-                        break;
+                    if error_count_value >= config.failure_threshold {
+                        Self::suspend(
+                            registration_id,
+                            &pair,
+                            &monitor,
+                            &watchdog,
+                            error_count_value,
+                        )
+                        .await;
+                        continue;
                     }
 
                     // Exponential backoff up to max interval
                     let backoff_interval = current_interval * (2_u32.pow(error_count_value.min(6)));
                     let capped_interval = backoff_interval.min(max_interval);
-                    tokio::time::sleep(capped_interval).await;
+                    monitor.sleep(capped_interval).await;
                 }
             }
         }
 
         info!(
-            speaker_ip = %pair.speaker_ip,
+            speaker_addr = %pair.speaker_addr,
             service = ?pair.service,
             "Polling task ended"
         );
     }
 
+    /// Wait out a suspension, then clear the failure count
+    async fn suspend(
+        registration_id: RegistrationId,
+        pair: &SpeakerServicePair,
+        monitor: &TaskMonitor,
+        watchdog: &Watchdog,
+        consecutive_failures: u32,
+    ) {
+        let cooldown = watchdog.config.suspend_cooldown;
+        error!(
+            speaker_addr = %pair.speaker_addr,
+            service = ?pair.service,
+            consecutive_failures,
+            ?cooldown,
+            "Too many consecutive errors, suspending polling"
+        );
+        monitor.health().suspended_until = Some(monitor.clock.now() + cooldown);
+        watchdog.emit(PollingHealthEvent::Suspended {
+            registration_id,
+            speaker_service_pair: pair.clone(),
+            consecutive_failures,
+            cooldown,
+        });
+
+        let reason = if monitor.sleep(cooldown).await {
+            ResumeReason::CooldownElapsed
+        } else {
+            ResumeReason::DeviceReachable
+        };
+
+        {
+            let mut health = monitor.health();
+            health.suspended_until = None;
+            health.consecutive_failures = 0;
+        }
+        info!(
+            speaker_addr = %pair.speaker_addr,
+            service = ?pair.service,
+            ?reason,
+            "Resuming polling"
+        );
+        watchdog.emit(PollingHealthEvent::Resumed {
+            registration_id,
+            speaker_service_pair: pair.clone(),
+            reason,
+        });
+    }
+
     /// Calculate adaptive polling interval based on recent activity
     fn calculate_adaptive_interval(
         current_interval: Duration,
         max_interval: Duration,
-        last_change_time: SystemTime,
+        last_change_time: Instant,
     ) -> Duration {
-        let time_since_change = SystemTime::now()
-            .duration_since(last_change_time)
-            .unwrap_or(Duration::ZERO);
+        let time_since_change = last_change_time.elapsed();
 
         if time_since_change < Duration::from_secs(30) {
             // Recent activity - poll faster
@@ -285,10 +568,17 @@ impl PollingTask {
         !self.task_handle.is_finished()
     }
 
+    /// Current health record of this task
+    pub fn health(&self) -> TaskHealth {
+        self.monitor.health().clone()
+    }
+
     /// Get task statistics
     pub async fn stats(&self) -> PollingTaskStats {
-        let error_count = *self.error_count.read().await;
-        let poll_count = *self.poll_count.read().await;
+        let (error_count, poll_count) = {
+            let health = self.monitor.health();
+            (health.consecutive_failures, health.polls)
+        };
 
         PollingTaskStats {
             registration_id: self.registration_id,
@@ -303,8 +593,10 @@ impl PollingTask {
 
     /// Request graceful shutdown of this polling task
     pub async fn shutdown(self) -> PollingResult<()> {
-        // Signal shutdown
+        // Signal shutdown, waking the task from any sleep or suspension
         self.shutdown_signal.store(true, Ordering::Relaxed);
+        self.monitor.wake.notify_one();
+        self.watchdog.monitors().remove(&self.registration_id);
 
         // Wait for task to complete
         match self.task_handle.await {
@@ -322,7 +614,7 @@ pub struct PollingTaskStats {
     pub registration_id: RegistrationId,
     pub speaker_service_pair: SpeakerServicePair,
     pub current_interval: Duration,
-    pub started_at: SystemTime,
+    pub started_at: Instant,
     pub error_count: u32,
     pub poll_count: u64,
     pub is_running: bool,
@@ -350,6 +642,15 @@ pub struct PollingScheduler {
 
     /// Maximum number of concurrent polling tasks
     max_concurrent_tasks: usize,
+
+    /// Per-task health, timeouts and suspension
+    watchdog: Arc<Watchdog>,
+
+    /// Groups of registrations serving group subscriptions
+    group_tags: Arc<GroupTags>,
+
+    /// Stable IDs set on events
+    stable_ids: Arc<StableIds>,
 }
 
 impl PollingScheduler {
@@ -369,9 +670,46 @@ impl PollingScheduler {
             max_interval,
             adaptive_polling,
             max_concurrent_tasks,
+            watchdog: Arc::new(Watchdog::new(
+                WatchdogConfig::default(),
+                Arc::new(SystemClock),
+            )),
+            group_tags: Arc::default(),
+            stable_ids: Arc::default(),
         }
     }
 
+    /// Use `config` for the watchdog of tasks started from now on
+    pub fn with_watchdog(mut self, config: WatchdogConfig) -> Self {
+        self.watchdog = Arc::new(Watchdog::new(config, Arc::clone(&self.watchdog.clock)));
+        self
+    }
+
+    /// Wait out intervals, back-off and cool-downs of tasks started from now
+    /// on on `clock` (default: the system clock)
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.watchdog = Arc::new(Watchdog::new(self.watchdog.config.clone(), clock));
+        self
+    }
+
+    /// Tag events with the group their registration serves
+    pub(crate) fn with_group_tags(mut self, group_tags: Arc<GroupTags>) -> Self {
+        self.group_tags = group_tags;
+        self
+    }
+
+    /// Set each event's stable ID from `stable_ids`
+    pub(crate) fn with_stable_ids(mut self, stable_ids: Arc<StableIds>) -> Self {
+        self.stable_ids = stable_ids;
+        self
+    }
+
+    /// Poll through `device_poller` instead of the default strategies
+    pub fn with_device_poller(mut self, device_poller: DeviceStatePoller) -> Self {
+        self.device_poller = Arc::new(device_poller);
+        self
+    }
+
     /// Start polling for a speaker/service pair
     pub async fn start_polling(
         &self,
@@ -401,12 +739,15 @@ impl PollingScheduler {
             self.adaptive_polling,
             Arc::clone(&self.device_poller),
             self.event_sender.clone(),
+            Arc::clone(&self.group_tags),
+            self.stable_ids.id(pair.speaker_addr, pair.service),
+            Arc::clone(&self.watchdog),
         );
 
         tasks.insert(registration_id, task);
 
         info!(
-            speaker_ip = %pair.speaker_ip,
+            speaker_addr = %pair.speaker_addr,
             service = ?pair.service,
             "Started polling"
         );
@@ -424,7 +765,7 @@ impl PollingScheduler {
             task.shutdown().await?;
 
             info!(
-                speaker_ip = %pair.speaker_ip,
+                speaker_addr = %pair.speaker_addr,
                 service = ?pair.service,
                 "Stopped polling"
             );
@@ -439,6 +780,24 @@ impl PollingScheduler {
         tasks.contains_key(&registration_id)
     }
 
+    /// Health of every active polling task
+    pub async fn task_health(&self) -> Vec<TaskHealth> {
+        let tasks = self.active_tasks.read().await;
+        tasks.values().map(PollingTask::health).collect()
+    }
+
+    /// Receive an event whenever a task is suspended or resumed
+    pub fn health_events(&self) -> broadcast::Receiver<PollingHealthEvent> {
+        self.watchdog.events.subscribe()
+    }
+
+    /// Resume the suspended tasks polling `device_ip` now, for when
+    /// something else shows the device is reachable again. Returns how many
+    /// were woken.
+    pub fn resume_device(&self, device_ip: IpAddr) -> usize {
+        self.watchdog.resume_device(device_ip)
+    }
+
     /// Get statistics for all active polling tasks
     pub async fn stats(&self) -> PollingSchedulerStats {
         let tasks = self.active_tasks.read().await;
@@ -508,7 +867,7 @@ impl std::fmt::Display for PollingSchedulerStats {
                     f,
                     "    {}: {} {:?} (interval: {:?}, polls: {}, errors: {})",
                     stat.registration_id,
-                    stat.speaker_service_pair.speaker_ip,
+                    stat.speaker_service_pair.speaker_addr,
                     stat.speaker_service_pair.service,
                     stat.current_interval,
                     stat.poll_count,
@@ -524,6 +883,7 @@ impl std::fmt::Display for PollingSchedulerStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::net::SocketAddr;
     use tokio::sync::mpsc;
 
     #[tokio::test]
@@ -556,7 +916,7 @@ mod tests {
 
         let registration_id = RegistrationId::new(1);
         let pair = SpeakerServicePair::new(
-            "192.168.1.100".parse().unwrap(),
+            "192.168.1.100:1400".parse().unwrap(),
             sonos_api::Service::AVTransport,
         );
 
@@ -576,15 +936,197 @@ mod tests {
     fn test_adaptive_interval_calculation() {
         let current = Duration::from_secs(5);
         let max = Duration::from_secs(30);
-        let recent_change = SystemTime::now() - Duration::from_secs(10);
+        let recent_change = Instant::now() - Duration::from_secs(10);
 
         let new_interval = PollingTask::calculate_adaptive_interval(current, max, recent_change);
         // Should decrease interval for recent activity
         assert!(new_interval <= current);
 
-        let old_change = SystemTime::now() - Duration::from_secs(400);
+        let old_change = Instant::now() - Duration::from_secs(400);
         let new_interval = PollingTask::calculate_adaptive_interval(current, max, old_change);
         // Should increase interval for old activity
         assert!(new_interval >= current);
     }
+
+    /// GroupManagement poller whose devices hang while listed in `down`
+    struct MockDevices {
+        down: Arc<Mutex<Vec<IpAddr>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::polling::ServicePoller for MockDevices {
+        async fn poll_state(
+            &self,
+            _client: &sonos_api::SonosClient,
+            pair: &SpeakerServicePair,
+        ) -> PollingResult<String> {
+            let hangs = self.down.lock().unwrap().contains(&pair.speaker_addr.ip());
+            if hangs {
+                std::future::pending::<()>().await;
+            }
+            Ok("{}".to_string())
+        }
+
+        fn state_to_event_data(
+            &self,
+            json_state: &str,
+        ) -> PollingResult<crate::events::types::EventData> {
+            crate::polling::strategies::GroupManagementPoller.state_to_event_data(json_state)
+        }
+
+        fn service_type(&self) -> sonos_api::Service {
+            sonos_api::Service::GroupManagement
+        }
+    }
+
+    const HEALTHY: &str = "192.168.1.10:1400";
+    const HANGING: &str = "192.168.1.20:1400";
+
+    /// Scheduler polling [`MockDevices`], with the list of devices down and
+    /// the receiver that keeps its tasks running
+    fn mock_scheduler(
+        watchdog: WatchdogConfig,
+    ) -> (
+        PollingScheduler,
+        Arc<Mutex<Vec<IpAddr>>>,
+        mpsc::UnboundedReceiver<EnrichedEvent>,
+    ) {
+        let down = Arc::new(Mutex::new(Vec::new()));
+        let (event_sender, event_receiver) = mpsc::unbounded_channel();
+        let scheduler = PollingScheduler::new(
+            event_sender,
+            Duration::from_millis(20),
+            Duration::from_millis(100),
+            false,
+            5,
+        )
+        .with_watchdog(watchdog)
+        .with_device_poller(DeviceStatePoller::new().with_poller(Box::new(MockDevices {
+            down: Arc::clone(&down),
+        })));
+        (scheduler, down, event_receiver)
+    }
+
+    async fn start(scheduler: &PollingScheduler, id: u64, addr: &str) -> RegistrationId {
+        let registration_id = RegistrationId::new(id);
+        let pair =
+            SpeakerServicePair::new(addr.parse().unwrap(), sonos_api::Service::GroupManagement);
+        scheduler
+            .start_polling(registration_id, pair)
+            .await
+            .unwrap();
+        registration_id
+    }
+
+    async fn health_of(scheduler: &PollingScheduler, id: RegistrationId) -> TaskHealth {
+        scheduler
+            .task_health()
+            .await
+            .into_iter()
+            .find(|h| h.registration_id == id)
+            .unwrap()
+    }
+
+    async fn next_event(
+        events: &mut broadcast::Receiver<PollingHealthEvent>,
+    ) -> PollingHealthEvent {
+        tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("no health event")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_hanging_device_does_not_delay_healthy_one() {
+        let (scheduler, down, _polled) = mock_scheduler(WatchdogConfig {
+            poll_timeout: Duration::from_millis(200),
+            failure_threshold: 100,
+            suspend_cooldown: Duration::from_secs(60),
+        });
+        down.lock()
+            .unwrap()
+            .push(HANGING.parse::<SocketAddr>().unwrap().ip());
+        let hanging = start(&scheduler, 1, HANGING).await;
+        let healthy = start(&scheduler, 2, HEALTHY).await;
+
+        tokio::time::sleep(Duration::from_millis(600)).await;
+
+        // 20ms interval over 600ms: ~30 polls if nothing gets in the way
+        let health = health_of(&scheduler, healthy).await;
+        assert!(
+            health.polls >= 15,
+            "healthy device polled {} times",
+            health.polls
+        );
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.last_success.is_some());
+        assert!(health.average_duration().unwrap() < Duration::from_millis(50));
+
+        let health = health_of(&scheduler, hanging).await;
+        assert!(health.timeouts >= 1);
+        assert_eq!(health.consecutive_failures as u64, health.timeouts);
+        assert!(health.last_success.is_none());
+        assert!(health.average_duration().unwrap() >= Duration::from_millis(200));
+
+        scheduler.shutdown_all().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failing_task_suspends_and_resumes() {
+        let (scheduler, down, _polled) = mock_scheduler(WatchdogConfig {
+            poll_timeout: Duration::from_millis(30),
+            failure_threshold: 2,
+            suspend_cooldown: Duration::from_millis(300),
+        });
+        let ip = HANGING.parse::<SocketAddr>().unwrap().ip();
+        down.lock().unwrap().push(ip);
+        let mut events = scheduler.health_events();
+        let id = start(&scheduler, 1, HANGING).await;
+
+        let PollingHealthEvent::Suspended {
+            registration_id,
+            consecutive_failures,
+            ..
+        } = next_event(&mut events).await
+        else {
+            panic!("expected suspension");
+        };
+        assert_eq!(registration_id, id);
+        assert_eq!(consecutive_failures, 2);
+        let health = health_of(&scheduler, id).await;
+        assert!(health.is_suspended());
+        let polls = health.polls;
+
+        // Still down: the cool-down runs out and it fails its way back
+        assert!(matches!(
+            next_event(&mut events).await,
+            PollingHealthEvent::Resumed {
+                reason: ResumeReason::CooldownElapsed,
+                ..
+            }
+        ));
+        assert!(matches!(
+            next_event(&mut events).await,
+            PollingHealthEvent::Suspended { .. }
+        ));
+        assert_eq!(health_of(&scheduler, id).await.polls, polls + 2);
+
+        // Back up: told so, it resumes before the cool-down
+        down.lock().unwrap().clear();
+        assert_eq!(scheduler.resume_device(ip), 1);
+        assert!(matches!(
+            next_event(&mut events).await,
+            PollingHealthEvent::Resumed {
+                reason: ResumeReason::DeviceReachable,
+                ..
+            }
+        ));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let health = health_of(&scheduler, id).await;
+        assert!(!health.is_suspended());
+        assert!(health.last_success.is_some());
+        assert_eq!(health.consecutive_failures, 0);
+
+        scheduler.shutdown_all().await.unwrap();
+    }
 }
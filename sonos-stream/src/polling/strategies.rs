@@ -47,7 +47,7 @@ impl ServicePoller for AVTransportPoller {
         pair: &SpeakerServicePair,
     ) -> PollingResult<String> {
         let client = client.clone();
-        let ip = pair.speaker_ip.to_string();
+        let ip = pair.speaker_addr.to_string();
 
         let state = tokio::task::spawn_blocking(move || {
             sonos_api::services::av_transport::state::poll(&client, &ip)
@@ -86,7 +86,7 @@ impl ServicePoller for RenderingControlPoller {
         pair: &SpeakerServicePair,
     ) -> PollingResult<String> {
         let client = client.clone();
-        let ip = pair.speaker_ip.to_string();
+        let ip = pair.speaker_addr.to_string();
 
         let state = tokio::task::spawn_blocking(move || {
             sonos_api::services::rendering_control::state::poll(&client, &ip)
@@ -127,7 +127,7 @@ impl ServicePoller for ZoneGroupTopologyPoller {
         pair: &SpeakerServicePair,
     ) -> PollingResult<String> {
         let client = client.clone();
-        let ip = pair.speaker_ip.to_string();
+        let ip = pair.speaker_addr.to_string();
 
         let state = tokio::task::spawn_blocking(move || {
             sonos_api::services::zone_group_topology::state::poll(&client, &ip)
@@ -206,7 +206,7 @@ impl ServicePoller for GroupRenderingControlPoller {
         pair: &SpeakerServicePair,
     ) -> PollingResult<String> {
         let client = client.clone();
-        let ip = pair.speaker_ip.to_string();
+        let ip = pair.speaker_addr.to_string();
 
         let state = tokio::task::spawn_blocking(move || {
             sonos_api::services::group_rendering_control::state::poll(&client, &ip)
@@ -234,6 +234,48 @@ impl ServicePoller for GroupRenderingControlPoller {
     }
 }
 
+/// Polling strategy for DeviceProperties service.
+///
+/// Delegates to `sonos_api::services::device_properties::state::poll()`, which
+/// only reads the button lock.
+pub struct DevicePropertiesPoller;
+
+#[async_trait]
+impl ServicePoller for DevicePropertiesPoller {
+    async fn poll_state(
+        &self,
+        client: &SonosClient,
+        pair: &SpeakerServicePair,
+    ) -> PollingResult<String> {
+        let client = client.clone();
+        let ip = pair.speaker_addr.to_string();
+
+        let state = tokio::task::spawn_blocking(move || {
+            sonos_api::services::device_properties::state::poll(&client, &ip)
+        })
+        .await
+        .map_err(|e| PollingError::Network(format!("Polling task panicked: {e}")))?
+        .map_err(|e| PollingError::Network(e.to_string()))?;
+
+        serde_json::to_string(&state)
+            .map_err(|e| PollingError::StateParsing(format!("Failed to serialize state: {e}")))
+    }
+
+    fn state_to_event_data(&self, json_state: &str) -> PollingResult<EventData> {
+        let state: sonos_api::services::device_properties::state::DevicePropertiesState =
+            serde_json::from_str(json_state).map_err(|e| {
+                PollingError::StateParsing(format!(
+                    "Failed to deserialize DeviceProperties state: {e}"
+                ))
+            })?;
+        Ok(EventData::DeviceProperties(state.into()))
+    }
+
+    fn service_type(&self) -> Service {
+        Service::DeviceProperties
+    }
+}
+
 /// Main device state poller that coordinates different service strategies
 pub struct DeviceStatePoller {
     /// Service-specific polling strategies
@@ -264,6 +306,7 @@ impl DeviceStatePoller {
             Service::GroupRenderingControl,
             Box::new(GroupRenderingControlPoller),
         );
+        service_pollers.insert(Service::DeviceProperties, Box::new(DevicePropertiesPoller));
 
         Self {
             service_pollers,
@@ -271,6 +314,12 @@ impl DeviceStatePoller {
         }
     }
 
+    /// Replace the strategy for the service `poller` handles
+    pub fn with_poller(mut self, poller: Box<dyn ServicePoller>) -> Self {
+        self.service_pollers.insert(poller.service_type(), poller);
+        self
+    }
+
     /// Poll device state for a specific speaker/service pair
     pub async fn poll_device_state(&self, pair: &SpeakerServicePair) -> PollingResult<String> {
         match self.service_pollers.get(&pair.service) {
@@ -340,12 +389,13 @@ mod tests {
         let poller = DeviceStatePoller::new();
         let stats = poller.stats();
 
-        assert_eq!(stats.total_pollers, 5);
+        assert_eq!(stats.total_pollers, 6);
         assert!(poller.is_service_supported(&Service::AVTransport));
         assert!(poller.is_service_supported(&Service::RenderingControl));
         assert!(poller.is_service_supported(&Service::ZoneGroupTopology));
         assert!(poller.is_service_supported(&Service::GroupManagement));
         assert!(poller.is_service_supported(&Service::GroupRenderingControl));
+        assert!(poller.is_service_supported(&Service::DeviceProperties));
     }
 
     #[test]
@@ -372,8 +422,10 @@ mod tests {
     #[tokio::test]
     async fn test_group_management_poller_returns_stable_state() {
         let poller = GroupManagementPoller;
-        let pair =
-            SpeakerServicePair::new("192.168.1.100".parse().unwrap(), Service::GroupManagement);
+        let pair = SpeakerServicePair::new(
+            "192.168.1.100:1400".parse().unwrap(),
+            Service::GroupManagement,
+        );
 
         let state1 = poller.poll_state(&SonosClient::new(), &pair).await.unwrap();
         let state2 = poller.poll_state(&SonosClient::new(), &pair).await.unwrap();
@@ -401,6 +453,7 @@ mod tests {
             next_track_uri: None,
             next_track_metadata: None,
             queue_length: None,
+            transport_actions: None,
         };
         let json = serde_json::to_string(&avt_state).unwrap();
         let event_data = poller
@@ -426,7 +479,7 @@ mod tests {
             rf_volume: None,
             lf_mute: None,
             rf_mute: None,
-            other_channels: std::collections::HashMap::new(),
+            ..Default::default()
         };
         let json = serde_json::to_string(&rc_state).unwrap();
         let event_data = poller
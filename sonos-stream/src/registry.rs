@@ -4,16 +4,17 @@
 //! pairs, ensuring that duplicate registrations are prevented and providing
 //! efficient lookup capabilities.
 
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::{Arc, Mutex, PoisonError};
 
 use crate::error::{RegistryError, RegistryResult};
 
 /// Unique identifier for a speaker/service registration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct RegistrationId(u64);
 
 impl RegistrationId {
@@ -37,17 +38,17 @@ impl std::fmt::Display for RegistrationId {
 /// A speaker and service type pair
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct SpeakerServicePair {
-    /// IP address of the speaker
-    pub speaker_ip: IpAddr,
+    /// Address (IP and port) of the speaker
+    pub speaker_addr: SocketAddr,
     /// UPnP service type
     pub service: sonos_api::Service,
 }
 
 impl SpeakerServicePair {
     /// Create a new SpeakerServicePair
-    pub fn new(speaker_ip: IpAddr, service: sonos_api::Service) -> Self {
+    pub fn new(speaker_addr: SocketAddr, service: sonos_api::Service) -> Self {
         Self {
-            speaker_ip,
+            speaker_addr,
             service,
         }
     }
@@ -55,21 +56,36 @@ impl SpeakerServicePair {
 
 impl std::fmt::Display for SpeakerServicePair {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{:?}", self.speaker_ip, self.service)
+        write!(f, "{}:{:?}", self.speaker_addr, self.service)
     }
 }
 
+/// Both directions of the registry, swapped in together so a reader never
+/// sees one updated without the other
+#[derive(Clone, Default)]
+struct Registrations {
+    /// Mapping from registration ID to speaker/service pair
+    by_id: HashMap<RegistrationId, SpeakerServicePair>,
+
+    /// Reverse mapping from speaker/service pair to registration ID for duplicate detection
+    by_pair: HashMap<SpeakerServicePair, RegistrationId>,
+}
+
 /// Thread-safe registry for speaker/service pairs with duplicate protection
 ///
 /// This registry maintains bidirectional mappings between registration IDs and
 /// speaker/service pairs, allowing for efficient lookups in both directions
 /// while preventing duplicate registrations.
+///
+/// Lookups far outnumber registrations, so the mappings are a copy-on-write
+/// snapshot: readers load it without locking, and writers clone, modify and
+/// swap it under `write_lock`.
 pub struct SpeakerServiceRegistry {
-    /// Mapping from registration ID to speaker/service pair
-    registrations: Arc<RwLock<HashMap<RegistrationId, SpeakerServicePair>>>,
+    /// Current mappings
+    registrations: ArcSwap<Registrations>,
 
-    /// Reverse mapping from speaker/service pair to registration ID for duplicate detection
-    pair_to_registration: Arc<RwLock<HashMap<SpeakerServicePair, RegistrationId>>>,
+    /// Serializes writers, so none of them swaps over another's change
+    write_lock: Mutex<()>,
 
     /// Atomic counter for generating unique registration IDs
     next_id: Arc<AtomicU64>,
@@ -82,8 +98,8 @@ impl SpeakerServiceRegistry {
     /// Create a new registry with the specified maximum registrations
     pub fn new(max_registrations: usize) -> Self {
         Self {
-            registrations: Arc::new(RwLock::new(HashMap::new())),
-            pair_to_registration: Arc::new(RwLock::new(HashMap::new())),
+            registrations: ArcSwap::from_pointee(Registrations::default()),
+            write_lock: Mutex::new(()),
             next_id: Arc::new(AtomicU64::new(1)),
             max_registrations,
         }
@@ -95,7 +111,7 @@ impl SpeakerServiceRegistry {
     /// If the pair is new, creates a new registration ID.
     ///
     /// # Arguments
-    /// * `speaker_ip` - IP address of the speaker
+    /// * `speaker_addr` - Address (IP and port) of the speaker
     /// * `service` - UPnP service type
     ///
     /// # Returns
@@ -103,30 +119,30 @@ impl SpeakerServiceRegistry {
     /// * `Err(RegistryError)` - If registration fails
     pub async fn register(
         &self,
-        speaker_ip: IpAddr,
+        speaker_addr: SocketAddr,
         service: sonos_api::Service,
     ) -> RegistryResult<RegistrationId> {
-        let pair = SpeakerServicePair::new(speaker_ip, service);
+        let pair = SpeakerServicePair::new(speaker_addr, service);
 
-        // First check if this pair is already registered (read lock)
-        {
-            let pair_lookup = self.pair_to_registration.read().await;
-            if let Some(existing_id) = pair_lookup.get(&pair) {
-                return Ok(*existing_id);
-            }
+        // First check if this pair is already registered (no lock)
+        if let Some(existing_id) = self.registrations.load().by_pair.get(&pair) {
+            return Ok(*existing_id);
         }
 
-        // Need to register new pair (write locks)
-        let mut registrations = self.registrations.write().await;
-        let mut pair_lookup = self.pair_to_registration.write().await;
+        // Need to register new pair
+        let _writer = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut registrations = Registrations::clone(&self.registrations.load());
 
-        // Double-check in case another task registered it between locks
-        if let Some(existing_id) = pair_lookup.get(&pair) {
+        // Double-check in case another task registered it in the meantime
+        if let Some(existing_id) = registrations.by_pair.get(&pair) {
             return Ok(*existing_id);
         }
 
         // Check registration limit
-        if registrations.len() >= self.max_registrations {
+        if registrations.by_id.len() >= self.max_registrations {
             return Err(RegistryError::RegistryFull {
                 max_registrations: self.max_registrations,
             });
@@ -136,8 +152,9 @@ impl SpeakerServiceRegistry {
         let registration_id = RegistrationId::new(self.next_id.fetch_add(1, Ordering::Relaxed));
 
         // Insert into both mappings
-        registrations.insert(registration_id, pair.clone());
-        pair_lookup.insert(pair, registration_id);
+        registrations.by_id.insert(registration_id, pair.clone());
+        registrations.by_pair.insert(pair, registration_id);
+        self.registrations.store(Arc::new(registrations));
 
         Ok(registration_id)
     }
@@ -154,16 +171,21 @@ impl SpeakerServiceRegistry {
         &self,
         registration_id: RegistrationId,
     ) -> RegistryResult<SpeakerServicePair> {
-        let mut registrations = self.registrations.write().await;
-        let mut pair_lookup = self.pair_to_registration.write().await;
+        let _writer = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut registrations = Registrations::clone(&self.registrations.load());
 
         // Remove from primary mapping
         let pair = registrations
+            .by_id
             .remove(&registration_id)
             .ok_or(RegistryError::NotFound(registration_id))?;
 
         // Remove from reverse mapping
-        pair_lookup.remove(&pair);
+        registrations.by_pair.remove(&pair);
+        self.registrations.store(Arc::new(registrations));
 
         Ok(pair)
     }
@@ -171,21 +193,24 @@ impl SpeakerServiceRegistry {
     /// Check if a speaker/service pair is already registered
     ///
     /// # Arguments
-    /// * `speaker_ip` - IP address of the speaker
+    /// * `speaker_addr` - Address (IP and port) of the speaker
     /// * `service` - UPnP service type
     ///
     /// # Returns
     /// * `true` if the pair is registered, `false` otherwise
-    pub async fn is_registered(&self, speaker_ip: IpAddr, service: sonos_api::Service) -> bool {
-        let pair = SpeakerServicePair::new(speaker_ip, service);
-        let pair_lookup = self.pair_to_registration.read().await;
-        pair_lookup.contains_key(&pair)
+    pub async fn is_registered(
+        &self,
+        speaker_addr: SocketAddr,
+        service: sonos_api::Service,
+    ) -> bool {
+        let pair = SpeakerServicePair::new(speaker_addr, service);
+        self.registrations.load().by_pair.contains_key(&pair)
     }
 
     /// Get the registration ID for a speaker/service pair
     ///
     /// # Arguments
-    /// * `speaker_ip` - IP address of the speaker
+    /// * `speaker_addr` - Address (IP and port) of the speaker
     /// * `service` - UPnP service type
     ///
     /// # Returns
@@ -193,12 +218,11 @@ impl SpeakerServiceRegistry {
     /// * `None` if the pair is not registered
     pub async fn get_registration_id(
         &self,
-        speaker_ip: IpAddr,
+        speaker_addr: SocketAddr,
         service: sonos_api::Service,
     ) -> Option<RegistrationId> {
-        let pair = SpeakerServicePair::new(speaker_ip, service);
-        let pair_lookup = self.pair_to_registration.read().await;
-        pair_lookup.get(&pair).copied()
+        let pair = SpeakerServicePair::new(speaker_addr, service);
+        self.registrations.load().by_pair.get(&pair).copied()
     }
 
     /// Get the speaker/service pair for a registration ID
@@ -210,8 +234,11 @@ impl SpeakerServiceRegistry {
     /// * `Some(SpeakerServicePair)` if the registration ID is found
     /// * `None` if the registration ID is not found
     pub async fn get_pair(&self, registration_id: RegistrationId) -> Option<SpeakerServicePair> {
-        let registrations = self.registrations.read().await;
-        registrations.get(&registration_id).cloned()
+        self.registrations
+            .load()
+            .by_id
+            .get(&registration_id)
+            .cloned()
     }
 
     /// List all current registrations
@@ -219,8 +246,9 @@ impl SpeakerServiceRegistry {
     /// # Returns
     /// A vector of (RegistrationId, SpeakerServicePair) tuples
     pub async fn list_registrations(&self) -> Vec<(RegistrationId, SpeakerServicePair)> {
-        let registrations = self.registrations.read().await;
-        registrations
+        self.registrations
+            .load()
+            .by_id
             .iter()
             .map(|(id, pair)| (*id, pair.clone()))
             .collect()
@@ -228,8 +256,7 @@ impl SpeakerServiceRegistry {
 
     /// Get the number of current registrations
     pub async fn count(&self) -> usize {
-        let registrations = self.registrations.read().await;
-        registrations.len()
+        self.registrations.load().by_id.len()
     }
 
     /// Get the maximum number of registrations allowed
@@ -239,20 +266,21 @@ impl SpeakerServiceRegistry {
 
     /// Clear all registrations (useful for testing or shutdown)
     pub async fn clear(&self) {
-        let mut registrations = self.registrations.write().await;
-        let mut pair_lookup = self.pair_to_registration.write().await;
-        registrations.clear();
-        pair_lookup.clear();
+        let _writer = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.registrations.store(Arc::default());
     }
 
     /// Get statistics about the registry
     pub async fn stats(&self) -> RegistryStats {
-        let registrations = self.registrations.read().await;
-        let count = registrations.len();
+        let registrations = self.registrations.load();
+        let count = registrations.by_id.len();
 
         // Count services
         let mut service_counts = HashMap::new();
-        for pair in registrations.values() {
+        for pair in registrations.by_id.values() {
             *service_counts.entry(pair.service).or_insert(0) += 1;
         }
 
@@ -295,7 +323,7 @@ mod tests {
     #[tokio::test]
     async fn test_registration_basic() {
         let registry = SpeakerServiceRegistry::new(100);
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let service = sonos_api::Service::AVTransport;
 
         // First registration should succeed
@@ -312,7 +340,7 @@ mod tests {
     #[tokio::test]
     async fn test_duplicate_detection() {
         let registry = SpeakerServiceRegistry::new(100);
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let service = sonos_api::Service::AVTransport;
 
         let reg_id1 = registry.register(ip, service).await.unwrap();
@@ -325,7 +353,7 @@ mod tests {
     #[tokio::test]
     async fn test_different_services() {
         let registry = SpeakerServiceRegistry::new(100);
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
 
         let av_reg = registry
             .register(ip, sonos_api::Service::AVTransport)
@@ -340,17 +368,34 @@ mod tests {
         assert_eq!(registry.count().await, 2);
     }
 
+    #[tokio::test]
+    async fn test_same_ip_different_ports() {
+        let registry = SpeakerServiceRegistry::new(100);
+        let service = sonos_api::Service::AVTransport;
+        let first: SocketAddr = "192.168.1.100:1400".parse().unwrap();
+        let second: SocketAddr = "192.168.1.100:1401".parse().unwrap();
+
+        let first_reg = registry.register(first, service).await.unwrap();
+        let second_reg = registry.register(second, service).await.unwrap();
+
+        assert_ne!(first_reg, second_reg);
+        assert_eq!(
+            registry.get_pair(second_reg).await.unwrap().speaker_addr,
+            second
+        );
+    }
+
     #[tokio::test]
     async fn test_unregistration() {
         let registry = SpeakerServiceRegistry::new(100);
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let service = sonos_api::Service::AVTransport;
 
         let reg_id = registry.register(ip, service).await.unwrap();
         assert_eq!(registry.count().await, 1);
 
         let pair = registry.unregister(reg_id).await.unwrap();
-        assert_eq!(pair.speaker_ip, ip);
+        assert_eq!(pair.speaker_addr, ip);
         assert_eq!(pair.service, service);
         assert_eq!(registry.count().await, 0);
         assert!(!registry.is_registered(ip, service).await);
@@ -359,9 +404,9 @@ mod tests {
     #[tokio::test]
     async fn test_registration_limit() {
         let registry = SpeakerServiceRegistry::new(2);
-        let ip1: IpAddr = "192.168.1.100".parse().unwrap();
-        let ip2: IpAddr = "192.168.1.101".parse().unwrap();
-        let ip3: IpAddr = "192.168.1.102".parse().unwrap();
+        let ip1: SocketAddr = "192.168.1.100:1400".parse().unwrap();
+        let ip2: SocketAddr = "192.168.1.101:1400".parse().unwrap();
+        let ip3: SocketAddr = "192.168.1.102:1400".parse().unwrap();
         let service = sonos_api::Service::AVTransport;
 
         // First two should succeed
@@ -376,7 +421,7 @@ mod tests {
     #[tokio::test]
     async fn test_lookups() {
         let registry = SpeakerServiceRegistry::new(100);
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let service = sonos_api::Service::AVTransport;
 
         let reg_id = registry.register(ip, service).await.unwrap();
@@ -404,7 +449,7 @@ mod tests {
     #[tokio::test]
     async fn test_list_and_stats() {
         let registry = SpeakerServiceRegistry::new(100);
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
 
         let _av_reg = registry
             .register(ip, sonos_api::Service::AVTransport)
@@ -437,7 +482,7 @@ mod tests {
     #[tokio::test]
     async fn test_concurrent_access() {
         let registry = Arc::new(SpeakerServiceRegistry::new(100));
-        let ip: IpAddr = "192.168.1.100".parse().unwrap();
+        let ip: SocketAddr = "192.168.1.100:1400".parse().unwrap();
         let service = sonos_api::Service::AVTransport;
 
         // Simulate concurrent registration attempts
@@ -466,4 +511,97 @@ mod tests {
         // Should only have one registration
         assert_eq!(registry.count().await, 1);
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_snapshots_are_never_torn() {
+        let registry = Arc::new(SpeakerServiceRegistry::new(1000));
+        let service = sonos_api::Service::AVTransport;
+
+        let churn = {
+            let registry = Arc::clone(&registry);
+            tokio::spawn(async move {
+                for n in 0..2000u32 {
+                    let addr = SocketAddr::from(([192, 168, 2, (n % 50) as u8], 1400));
+                    let id = registry.register(addr, service).await.unwrap();
+                    if n % 3 != 0 {
+                        registry.unregister(id).await.unwrap();
+                    }
+                }
+            })
+        };
+
+        // Every snapshot maps each registration both ways
+        while !churn.is_finished() {
+            let snapshot = registry.registrations.load();
+            assert_eq!(snapshot.by_id.len(), snapshot.by_pair.len());
+            for (id, pair) in &snapshot.by_id {
+                assert_eq!(snapshot.by_pair.get(pair), Some(id));
+            }
+            tokio::task::yield_now().await;
+        }
+        churn.await.unwrap();
+    }
+
+    /// Lookups per second while registrations churn
+    ///
+    /// Run with `cargo test -p sonos-sdk-stream --release --lib
+    /// bench_lookups_under_churn -- --ignored --nocapture`.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore]
+    async fn bench_lookups_under_churn() {
+        let registry = Arc::new(SpeakerServiceRegistry::new(1000));
+        let service = sonos_api::Service::AVTransport;
+        let stable: Vec<SocketAddr> = (0..32)
+            .map(|n| SocketAddr::from(([192, 168, 1, n], 1400)))
+            .collect();
+        for addr in &stable {
+            registry.register(*addr, service).await.unwrap();
+        }
+
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let churn = {
+            let (registry, done) = (Arc::clone(&registry), Arc::clone(&done));
+            tokio::spawn(async move {
+                let mut n = 0u16;
+                while !done.load(Ordering::Relaxed) {
+                    let addr = SocketAddr::from(([192, 168, 2, (n % 200) as u8], 1400));
+                    let id = registry.register(addr, service).await.unwrap();
+                    registry.unregister(id).await.unwrap();
+                    n = n.wrapping_add(1);
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+
+        let run_for = std::time::Duration::from_secs(2);
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let (registry, stable) = (Arc::clone(&registry), stable.clone());
+                tokio::spawn(async move {
+                    let started = std::time::Instant::now();
+                    let mut lookups = 0u64;
+                    while started.elapsed() < run_for {
+                        for addr in &stable {
+                            let id = registry.get_registration_id(*addr, service).await.unwrap();
+                            assert!(registry.get_pair(id).await.is_some());
+                            lookups += 1;
+                        }
+                    }
+                    lookups
+                })
+            })
+            .collect();
+
+        let mut lookups = 0;
+        for reader in readers {
+            lookups += reader.await.unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        churn.await.unwrap();
+
+        println!(
+            "{:.0} lookups/s under churn",
+            lookups as f64 / run_for.as_secs_f64()
+        );
+    }
 }
@@ -0,0 +1,264 @@
+//! Subscription identities that survive restarts
+//!
+//! A device hands out a new SID on every SUBSCRIBE, so after a restart or a
+//! resubscription nothing ties the new subscription to the old one.
+//! [`StableIds`] maps each speaker/service pair to a locally generated UUID
+//! and keeps the mapping in a JSON file, so the same pair gets the same ID
+//! every time the broker starts.
+//!
+//! Pairs are keyed by the speaker's UUID (`RINCON_...`), not its address,
+//! so an ID survives DHCP handing the speaker a new one. An address maps to
+//! a UUID once [`StableIds::identify()`] was told about it; until then its
+//! pairs have no stable ID.
+//!
+//! The file is rewritten whole through a temporary file and a rename, so a
+//! crash leaves the old mapping or the new one. Inside a Tokio runtime the
+//! write runs on the blocking pool, never on the caller's task. A file that
+//! can't be read or parsed is logged and replaced; entries that are missing
+//! or not strings get new IDs, written back with the next new pair.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use sonos_api::Service;
+use tracing::warn;
+
+/// Stable IDs of speaker/service pairs, kept in a file
+#[derive(Debug, Default)]
+pub struct StableIds {
+    /// `None` when disabled: no pair has a stable ID
+    file: Option<Arc<IdFile>>,
+    /// Speaker UUID at each address
+    speakers: Mutex<HashMap<SocketAddr, String>>,
+}
+
+/// The mapping and the file it is written to
+#[derive(Debug)]
+struct IdFile {
+    path: PathBuf,
+    ids: Mutex<BTreeMap<String, String>>,
+    /// Set when `ids` has entries the file lacks
+    dirty: AtomicBool,
+    /// Held while writing, so writes land in the order they were taken
+    writing: Mutex<()>,
+}
+
+impl IdFile {
+    /// Write the mapping if it changed since the last write
+    fn write_if_dirty(&self) {
+        let _writing = self.writing.lock().unwrap_or_else(PoisonError::into_inner);
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let ids = self
+            .ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if let Err(e) = store(&self.path, &ids) {
+            warn!(path = %self.path.display(), error = %e, "Failed to write stable subscription IDs");
+        }
+    }
+}
+
+impl StableIds {
+    /// Disabled: [`id()`](Self::id) is always `None`
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// IDs kept in `path`, loading those already there
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let ids = load(&path);
+        Self {
+            file: Some(Arc::new(IdFile {
+                path,
+                ids: Mutex::new(ids),
+                dirty: AtomicBool::new(false),
+                writing: Mutex::new(()),
+            })),
+            speakers: Mutex::default(),
+        }
+    }
+
+    /// File the IDs are kept in, if enabled
+    pub fn path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|file| file.path.as_path())
+    }
+
+    /// Note that the speaker with UUID `uuid` is at `addr`
+    ///
+    /// A `uuid:` prefix is ignored. A later call for the same address
+    /// replaces the UUID.
+    pub fn identify(&self, addr: SocketAddr, uuid: &str) {
+        let uuid = uuid.strip_prefix("uuid:").unwrap_or(uuid);
+        self.speakers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(addr, uuid.to_string());
+    }
+
+    /// Stable ID of `service` on the speaker at `addr`, generated the first
+    /// time it's asked for
+    ///
+    /// `None` when disabled or if the speaker at `addr` hasn't been
+    /// [identified](Self::identify). A new ID is written to the file in the
+    /// background.
+    pub fn id(&self, addr: SocketAddr, service: Service) -> Option<String> {
+        let file = self.file.as_ref()?;
+        let uuid = self
+            .speakers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&addr)?
+            .clone();
+        let key = format!("{uuid}/{}", service.name());
+        let id = {
+            let mut ids = file.ids.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(id) = ids.get(&key) {
+                return Some(id.clone());
+            }
+            let id = uuid::Uuid::new_v4().to_string();
+            ids.insert(key, id.clone());
+            id
+        };
+        file.dirty.store(true, Ordering::SeqCst);
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                let file = Arc::clone(file);
+                runtime.spawn_blocking(move || file.write_if_dirty());
+            }
+            Err(_) => file.write_if_dirty(),
+        }
+        Some(id)
+    }
+
+    /// Write IDs not yet in the file now, blocking until done
+    pub fn flush(&self) {
+        if let Some(file) = &self.file {
+            file.write_if_dirty();
+        }
+    }
+}
+
+/// The mapping in `path`, keeping only well-formed entries
+fn load(path: &Path) -> BTreeMap<String, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return BTreeMap::new(),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "Failed to read stable subscription IDs");
+            return BTreeMap::new();
+        }
+    };
+    match serde_json::from_str::<BTreeMap<String, serde_json::Value>>(&text) {
+        Ok(entries) => entries
+            .into_iter()
+            .filter_map(|(key, id)| match id {
+                serde_json::Value::String(id) if !id.is_empty() => Some((key, id)),
+                _ => None,
+            })
+            .collect(),
+        Err(e) => {
+            warn!(
+                path = %path.display(),
+                error = %e,
+                "Stable subscription ID file is corrupt, regenerating"
+            );
+            BTreeMap::new()
+        }
+    }
+}
+
+/// Replace `path` with `ids` atomically
+fn store(path: &Path, ids: &BTreeMap<String, String>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(serde_json::to_string_pretty(ids)?.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_ids_survive_reopen_and_corruption() {
+        let dir = std::env::temp_dir().join(format!("sonos-stable-ids-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("ids.json");
+        let addr: SocketAddr = "192.168.1.10:1400".parse().unwrap();
+        let open = |path: &Path| {
+            let ids = StableIds::open(path);
+            ids.identify(addr, "uuid:RINCON_DEN");
+            ids
+        };
+        assert_eq!(StableIds::disabled().id(addr, Service::AVTransport), None);
+
+        let ids = StableIds::open(&path);
+        assert_eq!(ids.id(addr, Service::AVTransport), None, "unidentified");
+        ids.identify(addr, "RINCON_DEN");
+        let transport = ids.id(addr, Service::AVTransport).unwrap();
+        let rendering = ids.id(addr, Service::RenderingControl).unwrap();
+        assert_ne!(transport, rendering);
+        assert_eq!(ids.id(addr, Service::AVTransport).unwrap(), transport);
+
+        let reopened = open(&path);
+        assert_eq!(reopened.id(addr, Service::AVTransport).unwrap(), transport);
+
+        // A damaged entry is regenerated; the others are kept
+        let mut entries: BTreeMap<String, serde_json::Value> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        entries.insert("RINCON_DEN/AVTransport".to_string(), serde_json::json!(7));
+        fs::write(&path, serde_json::to_string(&entries).unwrap()).unwrap();
+        let repaired = open(&path);
+        assert_eq!(
+            repaired.id(addr, Service::RenderingControl).unwrap(),
+            rendering
+        );
+        let regenerated = repaired.id(addr, Service::AVTransport).unwrap();
+        assert_ne!(regenerated, transport);
+        assert_eq!(
+            open(&path).id(addr, Service::AVTransport).unwrap(),
+            regenerated
+        );
+
+        // An unreadable file starts over
+        fs::write(&path, "{not json").unwrap();
+        let fresh = open(&path);
+        assert_ne!(
+            fresh.id(addr, Service::RenderingControl).unwrap(),
+            rendering
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stable_id_follows_the_speaker_to_a_new_address() {
+        let dir = std::env::temp_dir().join(format!("sonos-stable-ids-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("ids.json");
+        let before: SocketAddr = "192.168.1.10:1400".parse().unwrap();
+        let after: SocketAddr = "192.168.1.23:1443".parse().unwrap();
+
+        let ids = StableIds::open(&path);
+        ids.identify(before, "RINCON_DEN");
+        let id = ids.id(before, Service::AVTransport).unwrap();
+        ids.flush();
+
+        let reopened = StableIds::open(&path);
+        reopened.identify(after, "RINCON_DEN");
+        assert_eq!(reopened.id(after, Service::AVTransport), Some(id));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -10,6 +10,7 @@ use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, RwLock};
 
 use callback_server::{FirewallDetectionCoordinator, FirewallStatus};
+use sonos_api::clock::{SharedClock, SystemClock};
 use tracing::debug;
 
 use crate::broker::PollingReason;
@@ -38,6 +39,9 @@ pub struct EventDetector {
 
     /// Sender for requesting polling activation
     polling_request_sender: Option<mpsc::UnboundedSender<PollingRequest>>,
+
+    /// Measures event silence and paces the monitoring checks
+    clock: SharedClock,
 }
 
 /// Request to activate or deactivate polling for a registration
@@ -64,9 +68,16 @@ impl EventDetector {
             polling_activation_delay,
             firewall_coordinator: None,
             polling_request_sender: None,
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Measure event silence on `clock` instead of the system clock (must
+    /// be called during initialization)
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = clock;
+    }
+
     /// Set the firewall coordinator (must be called during initialization)
     pub fn set_firewall_coordinator(&mut self, coordinator: Arc<FirewallDetectionCoordinator>) {
         self.firewall_coordinator = Some(coordinator);
@@ -81,7 +92,7 @@ impl EventDetector {
     pub async fn record_event(&self, registration_id: RegistrationId) {
         let mut registrations = self.registrations.write().await;
         if let Some(reg) = registrations.get_mut(&registration_id) {
-            reg.last_event_time = Instant::now();
+            reg.last_event_time = self.clock.now();
         }
     }
 
@@ -90,7 +101,7 @@ impl EventDetector {
         let registrations = self.registrations.read().await;
         registrations
             .get(&registration_id)
-            .map(|reg| reg.last_event_time.elapsed() > self.event_timeout)
+            .map(|reg| self.clock.now().duration_since(reg.last_event_time) > self.event_timeout)
             .unwrap_or(false)
     }
 
@@ -99,7 +110,10 @@ impl EventDetector {
         let registrations = self.registrations.read().await;
         registrations
             .get(&registration_id)
-            .map(|reg| reg.last_event_time.elapsed() <= self.polling_activation_delay)
+            .map(|reg| {
+                self.clock.now().duration_since(reg.last_event_time)
+                    <= self.polling_activation_delay
+            })
             .unwrap_or(false)
     }
 
@@ -111,7 +125,7 @@ impl EventDetector {
     ) -> Option<PollingRequest> {
         if let Some(firewall_coordinator) = &self.firewall_coordinator {
             let status = firewall_coordinator
-                .get_device_status(pair.speaker_ip)
+                .get_device_status(pair.speaker_addr.ip())
                 .await;
 
             match status {
@@ -154,16 +168,17 @@ impl EventDetector {
         let registrations = Arc::clone(&self.registrations);
         let event_timeout = self.event_timeout;
         let polling_request_sender = self.polling_request_sender.clone();
+        let clock = Arc::clone(&self.clock);
 
         let check_interval = (event_timeout / 3).max(Duration::from_secs(1));
 
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(check_interval);
-
+            // The first check runs straight away
+            let mut next_check = clock.now();
             loop {
-                interval.tick().await;
-
-                let now = Instant::now();
+                clock.timer(next_check).await;
+                let now = clock.now();
+                next_check = now + check_interval;
 
                 // Snapshot registration IDs and check timeouts in a single lock
                 let timed_out: Vec<(RegistrationId, SpeakerServicePair)> = {
@@ -214,7 +229,7 @@ impl EventDetector {
         registrations.insert(
             registration_id,
             MonitoredRegistration {
-                last_event_time: Instant::now(),
+                last_event_time: self.clock.now(),
                 pair,
                 polling_activated: false,
             },
@@ -232,7 +247,7 @@ impl EventDetector {
         let registrations = self.registrations.read().await;
         let total_monitored = registrations.len();
 
-        let now = Instant::now();
+        let now = self.clock.now();
         let mut timeout_count = 0;
         let mut recent_events_count = 0;
 
@@ -298,7 +313,7 @@ mod tests {
 
         let registration_id = RegistrationId::new(1);
         let pair = SpeakerServicePair::new(
-            "192.168.1.100".parse().unwrap(),
+            "192.168.1.100:1400".parse().unwrap(),
             sonos_api::Service::AVTransport,
         );
 
@@ -319,7 +334,7 @@ mod tests {
 
         let registration_id = RegistrationId::new(1);
         let pair = SpeakerServicePair::new(
-            "192.168.1.100".parse().unwrap(),
+            "192.168.1.100:1400".parse().unwrap(),
             sonos_api::Service::AVTransport,
         );
 
@@ -342,7 +357,7 @@ mod tests {
 
         let registration_id = RegistrationId::new(1);
         let pair = SpeakerServicePair::new(
-            "192.168.1.100".parse().unwrap(),
+            "192.168.1.100:1400".parse().unwrap(),
             sonos_api::Service::AVTransport,
         );
 
@@ -354,7 +369,7 @@ mod tests {
         // Verify it's stored
         let regs = detector.registrations.read().await;
         assert!(regs.contains_key(&registration_id));
-        assert_eq!(regs[&registration_id].pair.speaker_ip, pair.speaker_ip);
+        assert_eq!(regs[&registration_id].pair.speaker_addr, pair.speaker_addr);
         drop(regs);
 
         // Unregister is a single remove
@@ -377,7 +392,7 @@ mod tests {
 
         let registration_id = RegistrationId::new(42);
         let pair = SpeakerServicePair::new(
-            "192.168.1.100".parse().unwrap(),
+            "192.168.1.100:1400".parse().unwrap(),
             sonos_api::Service::RenderingControl,
         );
 
@@ -406,7 +421,7 @@ mod tests {
         );
         let request = request.unwrap().expect("Channel should have a message");
         assert_eq!(request.registration_id, registration_id);
-        assert_eq!(request.speaker_service_pair.speaker_ip, pair.speaker_ip);
+        assert_eq!(request.speaker_service_pair.speaker_addr, pair.speaker_addr);
         assert!(matches!(request.action, PollingAction::Start));
         assert_eq!(request.reason, PollingReason::EventTimeout);
     }
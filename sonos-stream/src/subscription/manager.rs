@@ -4,16 +4,33 @@
 //! ManagedSubscription system and coordinating with the callback server for event routing.
 
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::SystemTime;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{Mutex, RwLock};
 
 use callback_server::firewall_detection::FirewallStatus;
-use sonos_api::{ManagedSubscription, Service, SonosClient};
-
-use crate::error::{SubscriptionError, SubscriptionResult};
+use sonos_api::{
+    EventingConnection, ManagedSubscription, Service, SharedClock, SonosClient,
+    SubscriptionDirectory, SystemClock,
+};
+
+use crate::chaos::ChaosHook;
+use crate::diagnostics::{ExchangeKind, ProtocolDiagnostics};
+use crate::error::{SubscriptionError, SubscriptionResult, SubscriptionStateError};
 use crate::registry::{RegistrationId, SpeakerServicePair};
+use crate::stable_id::StableIds;
+use crate::subscription::registry::{
+    DeliveryMode, RegistryEntry, SubscriptionInfo, SubscriptionMetadata, SubscriptionRecord,
+    SubscriptionRegistry,
+};
+
+/// How far past schedule a renewal check may run before the host is assumed
+/// to have slept
+const SLEEP_GAP_TOLERANCE: Duration = Duration::from_secs(60);
+
+/// Owner tag on the subscriptions the manager creates
+pub const SUBSCRIPTION_OWNER: &str = "sonos-stream";
 
 /// Wrapper around ManagedSubscription with additional context for event streaming
 #[derive(Debug)]
@@ -27,17 +44,14 @@ pub struct ManagedSubscriptionWrapper {
     /// Speaker/service pair for this subscription
     speaker_service_pair: SpeakerServicePair,
 
-    /// Timestamp of the last event received for this subscription
-    last_event_time: Arc<Mutex<Option<SystemTime>>>,
+    /// Protocol history that renewals and unsubscribes are recorded to
+    diagnostics: Option<Arc<ProtocolDiagnostics>>,
 
-    /// Whether polling is currently active for this subscription
-    is_polling_active: Arc<AtomicBool>,
+    /// Held while talking to the device; see [`SubscriptionManager`]
+    device_lane: Arc<Mutex<()>>,
 
-    /// Creation timestamp
-    created_at: SystemTime,
-
-    /// Number of renewal attempts
-    renewal_count: Arc<Mutex<u32>>,
+    /// The registration's metadata, once registered
+    metadata: OnceLock<Arc<std::sync::Mutex<SubscriptionMetadata>>>,
 }
 
 impl ManagedSubscriptionWrapper {
@@ -48,13 +62,38 @@ impl ManagedSubscriptionWrapper {
         speaker_service_pair: SpeakerServicePair,
     ) -> Self {
         Self {
-            subscription,
             registration_id,
             speaker_service_pair,
-            last_event_time: Arc::new(Mutex::new(None)),
-            is_polling_active: Arc::new(AtomicBool::new(false)),
-            created_at: SystemTime::now(),
-            renewal_count: Arc::new(Mutex::new(0)),
+            diagnostics: None,
+            device_lane: Arc::default(),
+            metadata: OnceLock::new(),
+            subscription,
+        }
+    }
+
+    /// Share `lane` with the device's other subscriptions
+    pub(crate) fn with_device_lane(mut self, lane: Arc<Mutex<()>>) -> Self {
+        self.device_lane = lane;
+        self
+    }
+
+    /// Record renewals and unsubscribes to `diagnostics`
+    pub(crate) fn with_diagnostics(mut self, diagnostics: Arc<ProtocolDiagnostics>) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    fn record(&self, kind: ExchangeKind, result: &Result<(), sonos_api::ApiError>) {
+        if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.record_exchange(
+                &self.speaker_service_pair,
+                kind,
+                Some(self.subscription.subscription_id()),
+                match result {
+                    Ok(()) => Ok(self.subscription.timeout_seconds()),
+                    Err(e) => Err(e),
+                },
+            );
         }
     }
 
@@ -84,61 +123,111 @@ impl ManagedSubscriptionWrapper {
     }
 
     /// Renew the subscription
+    ///
+    /// Fails with [`SubscriptionError::Expired`] if the device no longer
+    /// knows the SID (HTTP 412).
     pub async fn renew(&self) -> SubscriptionResult<()> {
-        self.subscription
-            .renew()
-            .map_err(|e| SubscriptionError::RenewalFailed(e.to_string()))?;
-
-        // Increment renewal count
-        let mut count = self.renewal_count.lock().await;
-        *count += 1;
-
-        Ok(())
+        let lane = self.device_lane.lock().await;
+        // A lapsed SID surfaces as an error; the caller replaces the
+        // subscription itself, as events are routed by SID
+        let result = self.subscription.renew_in_place();
+        drop(lane);
+        self.record(ExchangeKind::Renew, &result);
+        result.map_err(|e| match e {
+            sonos_api::ApiError::SubscriptionUnknown(_) => SubscriptionError::Expired,
+            e => SubscriptionError::RenewalFailed(e.to_string()),
+        })
     }
 
     /// Unsubscribe and clean up
     pub async fn unsubscribe(&self) -> SubscriptionResult<()> {
-        self.subscription
-            .unsubscribe()
-            .map_err(|e| SubscriptionError::NetworkError(e.to_string()))?;
+        let lane = self.device_lane.lock().await;
+        let result = self.subscription.unsubscribe();
+        drop(lane);
+        self.record(ExchangeKind::Unsubscribe, &result);
+        result.map_err(|e| SubscriptionError::NetworkError(e.to_string()))?;
         Ok(())
     }
 
+    /// Run `f` on the registration's metadata; `None` until registered
+    fn with_metadata<T>(&self, f: impl FnOnce(&mut SubscriptionMetadata) -> T) -> Option<T> {
+        let metadata = self.metadata.get()?;
+        Some(f(&mut metadata
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)))
+    }
+
     /// Record that an event was received for this subscription
     pub async fn record_event_received(&self) {
-        let mut last_event_time = self.last_event_time.lock().await;
-        *last_event_time = Some(SystemTime::now());
+        let now = self.subscription.clock().now();
+        self.with_metadata(|meta| meta.last_event_at = Some(now));
     }
 
     /// Get the time of the last event received
-    pub async fn last_event_time(&self) -> Option<SystemTime> {
-        let last_event_time = self.last_event_time.lock().await;
-        *last_event_time
+    pub async fn last_event_time(&self) -> Option<Instant> {
+        self.with_metadata(|meta| meta.last_event_at).flatten()
+    }
+
+    /// Time since the last event received
+    pub async fn last_event_age(&self) -> Option<Duration> {
+        let last_event_time = self.last_event_time().await?;
+        Some(
+            self.subscription
+                .clock()
+                .now()
+                .saturating_duration_since(last_event_time),
+        )
     }
 
     /// Set whether polling is active for this subscription
     pub fn set_polling_active(&self, active: bool) {
-        self.is_polling_active.store(active, Ordering::Relaxed);
+        let delivery = if active {
+            DeliveryMode::Polling
+        } else {
+            DeliveryMode::Events
+        };
+        self.with_metadata(|meta| meta.delivery = delivery);
     }
 
     /// Check if polling is active for this subscription
     pub fn is_polling_active(&self) -> bool {
-        self.is_polling_active.load(Ordering::Relaxed)
-    }
-
-    /// Get creation timestamp
-    pub fn created_at(&self) -> SystemTime {
-        self.created_at
+        self.with_metadata(|meta| meta.delivery == DeliveryMode::Polling)
+            .unwrap_or(false)
     }
 
     /// Get renewal count
     pub async fn renewal_count(&self) -> u32 {
-        let count = self.renewal_count.lock().await;
-        *count
+        self.with_metadata(|meta| meta.renewals).unwrap_or(0)
+    }
+}
+
+impl RegistryEntry for ManagedSubscriptionWrapper {
+    fn registration_id(&self) -> RegistrationId {
+        self.registration_id
+    }
+
+    fn speaker_service_pair(&self) -> &SpeakerServicePair {
+        &self.speaker_service_pair
+    }
+
+    fn subscription_id(&self) -> &str {
+        self.subscription.subscription_id()
+    }
+
+    fn detach(&self) {
+        self.subscription.detach();
+    }
+
+    fn attach_metadata(&self, metadata: Arc<std::sync::Mutex<SubscriptionMetadata>>) {
+        let _ = self.metadata.set(metadata);
     }
 }
 
 /// Manages subscriptions for registered speaker/service pairs
+///
+/// SUBSCRIBE, renewal and UNSUBSCRIBE requests to one device are sent one
+/// at a time, so they reuse the HTTP agent's keep-alive connection to it
+/// instead of each opening a connection of their own.
 pub struct SubscriptionManager {
     /// SonosClient for creating and managing subscriptions
     sonos_client: SonosClient,
@@ -146,24 +235,140 @@ pub struct SubscriptionManager {
     /// Callback URL for UPnP event notifications
     callback_url: String,
 
-    /// Active subscriptions indexed by registration ID
-    active_subscriptions: Arc<RwLock<HashMap<RegistrationId, Arc<ManagedSubscriptionWrapper>>>>,
+    /// Active subscriptions indexed by registration ID, with their state
+    /// and metadata
+    subscriptions: SubscriptionRegistry<ManagedSubscriptionWrapper>,
 
     /// Current firewall status (shared with other components)
     firewall_status: Arc<RwLock<FirewallStatus>>,
+
+    /// Wire-level SUBSCRIBE/NOTIFY history (disabled unless configured)
+    diagnostics: Arc<ProtocolDiagnostics>,
+
+    /// Time source for subscription expiry and sleep detection
+    clock: SharedClock,
+
+    /// Monotonic and wall time of the previous renewal check
+    last_renewal_check: Mutex<Option<(Instant, SystemTime)>>,
+
+    /// One lock per device, held for each subscription request to it
+    device_lanes: std::sync::Mutex<HashMap<SocketAddr, Arc<Mutex<()>>>>,
+
+    /// Where other components in the process register their subscriptions
+    /// (disabled unless configured)
+    directory: Option<SubscriptionDirectory>,
+
+    /// Stable IDs of registrations (disabled unless configured)
+    stable_ids: Arc<StableIds>,
+
+    /// Renewals failed on purpose by chaos testing
+    chaos: ChaosHook,
+
+    /// `(old_sid, new_sid)` of subscriptions replaced by `check_renewals()`
+    replaced: std::sync::Mutex<Vec<(String, String)>>,
 }
 
+/// A subscription's registry record
+pub type ManagedSubscriptionRecord = Arc<SubscriptionRecord<ManagedSubscriptionWrapper>>;
+
 impl SubscriptionManager {
     /// Create a new SubscriptionManager
     pub fn new(callback_url: String) -> Self {
+        Self::with_diagnostics(callback_url, Arc::new(ProtocolDiagnostics::default()))
+    }
+
+    /// Create a SubscriptionManager that records protocol history to `diagnostics`
+    pub fn with_diagnostics(callback_url: String, diagnostics: Arc<ProtocolDiagnostics>) -> Self {
+        let clock: SharedClock = Arc::new(SystemClock);
         Self {
             sonos_client: SonosClient::new(),
             callback_url,
-            active_subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            subscriptions: SubscriptionRegistry::new(Arc::clone(&clock)),
             firewall_status: Arc::new(RwLock::new(FirewallStatus::Unknown)),
+            diagnostics,
+            clock,
+            last_renewal_check: Mutex::new(None),
+            device_lanes: std::sync::Mutex::new(HashMap::new()),
+            directory: None,
+            stable_ids: Arc::default(),
+            chaos: ChaosHook::default(),
+            replaced: std::sync::Mutex::default(),
         }
     }
 
+    /// Measure subscription expiry and renewal gaps on `clock`
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.sonos_client = self.sonos_client.clone().with_clock(Arc::clone(&clock));
+        self.subscriptions = SubscriptionRegistry::new(Arc::clone(&clock))
+            .with_stable_ids(Arc::clone(&self.stable_ids));
+        self.clock = clock;
+        self
+    }
+
+    /// Give each registration its stable ID from `stable_ids`
+    pub fn with_stable_ids(mut self, stable_ids: Arc<StableIds>) -> Self {
+        self.subscriptions = SubscriptionRegistry::new(Arc::clone(&self.clock))
+            .with_stable_ids(Arc::clone(&stable_ids));
+        self.stable_ids = stable_ids;
+        self
+    }
+
+    /// Fail renewals as `chaos` decides, without sending them
+    pub(crate) fn with_chaos(mut self, chaos: ChaosHook) -> Self {
+        self.chaos = chaos;
+        self
+    }
+
+    /// Use connections for subscription requests as `eventing` says, for
+    /// devices whose transport doesn't say otherwise
+    pub fn with_eventing_connection(mut self, eventing: EventingConnection) -> Self {
+        self.sonos_client = self.sonos_client.clone().with_eventing_connection(eventing);
+        self
+    }
+
+    /// Look for an existing subscription in `directory` before subscribing
+    ///
+    /// One with this manager's callback URL is adopted: the manager renews
+    /// it, and unsubscribes it when its registration is removed. One with
+    /// another callback URL fails the subscription with
+    /// [`SubscriptionError::AlreadySubscribedExternally`] instead of
+    /// subscribing a second time. The manager's own subscriptions are
+    /// registered in `directory` too, tagged [`SUBSCRIPTION_OWNER`].
+    pub fn with_subscription_directory(mut self, directory: SubscriptionDirectory) -> Self {
+        self.sonos_client = self
+            .sonos_client
+            .clone()
+            .with_subscription_directory(directory.clone())
+            .with_subscription_owner(SUBSCRIPTION_OWNER);
+        self.directory = Some(directory);
+        self
+    }
+
+    /// Protocol history shared with the event processor
+    pub fn diagnostics(&self) -> &Arc<ProtocolDiagnostics> {
+        &self.diagnostics
+    }
+
+    /// The subscriptions with their state and metadata
+    pub fn registry(&self) -> &SubscriptionRegistry<ManagedSubscriptionWrapper> {
+        &self.subscriptions
+    }
+
+    /// TCP connections opened to `speaker_addr` so far, by subscription
+    /// traffic and any SOAP calls sharing the HTTP agent
+    pub fn connections_opened(&self, speaker_addr: SocketAddr) -> u64 {
+        self.sonos_client
+            .connections_opened(&speaker_addr.to_string())
+    }
+
+    fn device_lane(&self, speaker_addr: SocketAddr) -> Arc<Mutex<()>> {
+        let mut lanes = self
+            .device_lanes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(lanes.entry(speaker_addr).or_default())
+    }
+
     /// Set the firewall status (called by firewall detection system)
     pub async fn set_firewall_status(&self, status: FirewallStatus) {
         let mut current_status = self.firewall_status.write().await;
@@ -176,50 +381,185 @@ impl SubscriptionManager {
         *status
     }
 
-    /// Create a subscription for a speaker/service pair
+    /// Create the subscription of a speaker/service pair's registration
+    ///
+    /// Fails if the registration already has one.
     pub async fn create_subscription(
         &self,
         registration_id: RegistrationId,
         pair: SpeakerServicePair,
+    ) -> SubscriptionResult<Arc<ManagedSubscriptionWrapper>> {
+        // Checked before subscribing to spare the device a request;
+        // insert_new() enforces it
+        if self.subscriptions.get(registration_id).is_some() {
+            return Err(SubscriptionStateError::AlreadySubscribed(registration_id).into());
+        }
+        let wrapper = match self.adopt_existing(registration_id, &pair)? {
+            Some(adopted) => adopted,
+            None => self.subscribe(registration_id, pair).await?,
+        };
+        self.subscriptions.insert_new(Arc::clone(&wrapper))?;
+        Ok(wrapper)
+    }
+
+    /// The directory's subscription to a registration's speaker/service
+    /// pair, wrapped for the registration if it sends events to this
+    /// manager's callback URL
+    ///
+    /// `None` without a directory, or when the pair has no live subscription
+    /// this manager doesn't already track.
+    fn adopt_existing(
+        &self,
+        registration_id: RegistrationId,
+        pair: &SpeakerServicePair,
+    ) -> SubscriptionResult<Option<Arc<ManagedSubscriptionWrapper>>> {
+        let Some(existing) = self
+            .directory
+            .as_ref()
+            .and_then(|directory| directory.find(&pair.speaker_addr.to_string(), pair.service))
+        else {
+            return Ok(None);
+        };
+        if !self
+            .subscriptions
+            .with_sid(existing.subscription_id())
+            .is_empty()
+        {
+            return Ok(None);
+        }
+        if existing.callback_url() != self.callback_url {
+            return Err(SubscriptionError::AlreadySubscribedExternally {
+                speaker_addr: pair.speaker_addr,
+                service: pair.service,
+                owner: existing.owner().map(str::to_string),
+                callback_url: existing.callback_url().to_string(),
+            });
+        }
+
+        tracing::info!(
+            speaker_addr = %pair.speaker_addr,
+            service = ?pair.service,
+            subscription_id = %existing.subscription_id(),
+            owner = existing.owner().unwrap_or_default(),
+            "Adopted existing subscription"
+        );
+        Ok(Some(Arc::new(
+            ManagedSubscriptionWrapper::new(existing, registration_id, pair.clone())
+                .with_diagnostics(Arc::clone(&self.diagnostics))
+                .with_device_lane(self.device_lane(pair.speaker_addr)),
+        )))
+    }
+
+    /// Subscribe a registration on its device, without registering the
+    /// subscription
+    async fn subscribe(
+        &self,
+        registration_id: RegistrationId,
+        pair: SpeakerServicePair,
     ) -> SubscriptionResult<Arc<ManagedSubscriptionWrapper>> {
         // Convert Service to the format expected by SonosClient (no conversion needed since we're using the same enum)
         let service = pair.service;
 
         // Create the subscription using SonosClient
-        let subscription = self
-            .sonos_client
-            .subscribe(&pair.speaker_ip.to_string(), service, &self.callback_url)
-            .map_err(|e| SubscriptionError::CreationFailed(e.to_string()))?;
+        let lane = self.device_lane(pair.speaker_addr);
+        let guard = lane.lock().await;
+        let result = self.sonos_client.subscribe(
+            &pair.speaker_addr.to_string(),
+            service,
+            &self.callback_url,
+        );
+        drop(guard);
+        match &result {
+            Ok(subscription) => self.diagnostics.record_exchange(
+                &pair,
+                ExchangeKind::Subscribe,
+                Some(subscription.subscription_id()),
+                Ok(subscription.timeout_seconds()),
+            ),
+            Err(e) => {
+                self.diagnostics
+                    .record_exchange(&pair, ExchangeKind::Subscribe, None, Err(e))
+            }
+        }
+        let subscription = result.map_err(|e| SubscriptionError::CreationFailed(e.to_string()))?;
 
         // Wrap it with our additional context
-        let wrapper = Arc::new(ManagedSubscriptionWrapper::new(
-            subscription,
-            registration_id,
-            pair,
-        ));
+        Ok(Arc::new(
+            ManagedSubscriptionWrapper::new(subscription, registration_id, pair)
+                .with_diagnostics(Arc::clone(&self.diagnostics))
+                .with_device_lane(lane),
+        ))
+    }
+
+    /// Replace `current` with a fresh subscription for its registration,
+    /// keeping the registration's metadata
+    ///
+    /// Returns the fresh subscription. Fails if `current` is no longer the
+    /// registration's subscription by the time the device granted it.
+    async fn replace_subscription(
+        &self,
+        current: &ManagedSubscriptionWrapper,
+    ) -> SubscriptionResult<Arc<ManagedSubscriptionWrapper>> {
+        let fresh = self
+            .subscribe(
+                current.registration_id,
+                current.speaker_service_pair.clone(),
+            )
+            .await?;
+        self.subscriptions.migrate(Arc::clone(&fresh))?;
+        Ok(fresh)
+    }
 
-        // Store in our active subscriptions
-        let mut subscriptions = self.active_subscriptions.write().await;
-        subscriptions.insert(registration_id, Arc::clone(&wrapper));
+    /// Subscriptions retired since the last call because their device
+    /// reissued their SID to another subscription
+    ///
+    /// They are no longer active and must not be unsubscribed (that would
+    /// cancel the new holder of the SID); the caller resubscribes them.
+    pub fn take_retired(&self) -> Vec<ManagedSubscriptionRecord> {
+        let retired = self.subscriptions.take_retired();
+        for record in &retired {
+            let stale = record.subscription();
+            tracing::warn!(
+                "{} reissued SID {}; retiring its {:?} subscription",
+                stale.speaker_service_pair.speaker_addr,
+                stale.subscription_id(),
+                stale.speaker_service_pair.service
+            );
+            self.diagnostics.record_exchange(
+                &stale.speaker_service_pair,
+                ExchangeKind::Retire,
+                Some(stale.subscription_id()),
+                Err(&"SID reissued to another subscription"),
+            );
+        }
+        retired
+    }
 
-        Ok(wrapper)
+    /// Drop every subscription to the device at `addr` without
+    /// unsubscribing, because it rebooted and no longer knows their SIDs
+    ///
+    /// Returns the dropped subscriptions for the caller to resubscribe.
+    pub fn retire_device(&self, addr: SocketAddr) -> Vec<ManagedSubscriptionRecord> {
+        let retired = self.subscriptions.retire_device(addr);
+        for record in &retired {
+            let wrapper = record.subscription();
+            self.diagnostics.record_exchange(
+                &wrapper.speaker_service_pair,
+                ExchangeKind::Retire,
+                Some(wrapper.subscription_id()),
+                Err(&"device rebooted"),
+            );
+        }
+        retired
     }
 
-    /// Remove a subscription
+    /// Remove a subscription and unsubscribe it
     pub async fn remove_subscription(
         &self,
         registration_id: RegistrationId,
     ) -> SubscriptionResult<()> {
-        let mut subscriptions = self.active_subscriptions.write().await;
-
-        if let Some(wrapper) = subscriptions.remove(&registration_id) {
-            // Unsubscribe from the UPnP service
-            wrapper.unsubscribe().await?;
-        } else {
-            return Err(SubscriptionError::InvalidState);
-        }
-
-        Ok(())
+        let record = self.subscriptions.remove(registration_id)?;
+        record.subscription().unsubscribe().await
     }
 
     /// Get a subscription by registration ID
@@ -227,8 +567,9 @@ impl SubscriptionManager {
         &self,
         registration_id: RegistrationId,
     ) -> Option<Arc<ManagedSubscriptionWrapper>> {
-        let subscriptions = self.active_subscriptions.read().await;
-        subscriptions.get(&registration_id).cloned()
+        self.subscriptions
+            .get(registration_id)
+            .map(|record| Arc::clone(record.subscription()))
     }
 
     /// Get subscription by UPnP subscription ID (for event routing)
@@ -236,39 +577,115 @@ impl SubscriptionManager {
         &self,
         subscription_id: &str,
     ) -> Option<Arc<ManagedSubscriptionWrapper>> {
-        let subscriptions = self.active_subscriptions.read().await;
-        subscriptions
-            .values()
-            .find(|wrapper| wrapper.subscription_id() == subscription_id)
-            .cloned()
+        self.subscriptions
+            .with_sid(subscription_id)
+            .first()
+            .map(|record| Arc::clone(record.subscription()))
+    }
+
+    /// Get the subscription a NOTIFY from `sender` with this SID belongs to
+    ///
+    /// SIDs are only unique per device, so the sending device picks between
+    /// subscriptions sharing one. Without a sender, or when no subscription
+    /// to the sender has the SID (the NOTIFY came through a proxy or another
+    /// interface), the SID alone decides if it's unambiguous.
+    pub async fn get_subscription_for(
+        &self,
+        sender: Option<IpAddr>,
+        subscription_id: &str,
+    ) -> Option<Arc<ManagedSubscriptionWrapper>> {
+        let matching = self.subscriptions.with_sid(subscription_id);
+        let from_sender = sender.and_then(|ip| {
+            matching
+                .iter()
+                .find(|record| record.subscription().speaker_service_pair.speaker_addr.ip() == ip)
+        });
+        match (from_sender, matching.as_slice()) {
+            (Some(record), _) | (None, [record]) => Some(Arc::clone(record.subscription())),
+            _ => None,
+        }
     }
 
     /// List all active subscriptions
     pub async fn list_subscriptions(&self) -> Vec<Arc<ManagedSubscriptionWrapper>> {
-        let subscriptions = self.active_subscriptions.read().await;
-        subscriptions.values().cloned().collect()
+        self.subscriptions
+            .records()
+            .iter()
+            .map(|record| Arc::clone(record.subscription()))
+            .collect()
+    }
+
+    /// Renew a registered subscription
+    ///
+    /// Fails without a request if it was replaced or removed, or another
+    /// renewal of it is in flight.
+    pub async fn renew_subscription(
+        &self,
+        wrapper: &Arc<ManagedSubscriptionWrapper>,
+    ) -> SubscriptionResult<()> {
+        self.subscriptions.begin_renewal(wrapper)?;
+        let result = if self.chaos.fail_renewal() {
+            Err(SubscriptionError::RenewalFailed(
+                "failed by chaos testing".to_string(),
+            ))
+        } else {
+            wrapper.renew().await
+        };
+        // Replaced or removed meanwhile: the outcome no longer matters
+        let _ = self.subscriptions.end_renewal(wrapper, result.is_ok());
+        result
     }
 
     /// Check for subscriptions that need renewal and renew them
+    ///
+    /// A subscription the device no longer knows is replaced with a fresh
+    /// one; collect those with [`take_replaced()`](Self::take_replaced).
+    /// Only renewals count towards the returned number.
     pub async fn check_renewals(&self) -> SubscriptionResult<usize> {
-        let subscriptions = self.active_subscriptions.read().await;
         let mut renewed_count = 0;
 
-        for wrapper in subscriptions.values() {
+        for wrapper in self.list_subscriptions().await {
             if wrapper.needs_renewal() {
-                match wrapper.renew().await {
+                match self.renew_subscription(&wrapper).await {
                     Ok(()) => {
                         renewed_count += 1;
                         eprintln!(
                             "✅ Renewed subscription for {} {:?}",
-                            wrapper.speaker_service_pair.speaker_ip,
+                            wrapper.speaker_service_pair.speaker_addr,
                             wrapper.speaker_service_pair.service
                         );
                     }
+                    Err(SubscriptionError::Expired) => {
+                        // The device already dropped the SID: don't UNSUBSCRIBE it
+                        wrapper.detach();
+                        let old_sid = wrapper.subscription_id().to_string();
+                        match self.replace_subscription(&wrapper).await {
+                            Ok(fresh) => {
+                                tracing::warn!(
+                                    speaker = %wrapper.speaker_service_pair.speaker_addr,
+                                    service = ?wrapper.speaker_service_pair.service,
+                                    sid = %old_sid,
+                                    "Device forgot subscription; replaced it"
+                                );
+                                self.replaced
+                                    .lock()
+                                    .unwrap_or_else(PoisonError::into_inner)
+                                    .push((old_sid, fresh.subscription_id().to_string()));
+                            }
+                            Err(e) => {
+                                tracing::warn!(
+                                    speaker = %wrapper.speaker_service_pair.speaker_addr,
+                                    service = ?wrapper.speaker_service_pair.service,
+                                    error = %e,
+                                    "Failed to replace forgotten subscription"
+                                );
+                            }
+                        }
+                    }
                     Err(e) => {
                         eprintln!(
                             "❌ Failed to renew subscription for {} {:?}: {}",
-                            wrapper.speaker_service_pair.speaker_ip,
+                            wrapper.speaker_service_pair.speaker_addr,
                             wrapper.speaker_service_pair.service,
                             e
                         );
@@ -281,33 +698,139 @@ impl SubscriptionManager {
         Ok(renewed_count)
     }
 
-    /// Record that an event was received for a subscription
-    pub async fn record_event_received(&self, subscription_id: &str) {
-        if let Some(wrapper) = self.get_subscription_by_sid(subscription_id).await {
-            wrapper.record_event_received().await;
+    /// `(old_sid, new_sid)` of the subscriptions `check_renewals()` replaced
+    /// since the last call, so the caller can re-route their events
+    pub fn take_replaced(&self) -> Vec<(String, String)> {
+        std::mem::take(&mut *self.replaced.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Note that a renewal check is running, `expected` after the previous one.
+    ///
+    /// Returns the actual time since the previous check when it overshoots
+    /// `expected` by more than a minute. That means the host was most likely
+    /// asleep, and devices may have dropped subscriptions that still look
+    /// valid locally, or kept ones that look expired.
+    pub async fn note_renewal_check(&self, expected: Duration) -> Option<Duration> {
+        let now = (self.clock.now(), self.clock.wall());
+        let (previous, previous_wall) = self.last_renewal_check.lock().await.replace(now)?;
+
+        // The monotonic clock stops during sleep on most platforms; wall time
+        // doesn't. A backwards wall-clock step is ignored.
+        let gap = now
+            .0
+            .saturating_duration_since(previous)
+            .max(now.1.duration_since(previous_wall).unwrap_or_default());
+        (gap > expected + SLEEP_GAP_TOLERANCE).then_some(gap)
+    }
+
+    /// Renew every subscription now, replacing any the device refuses to
+    /// renew (it expired or forgot the SID) with a fresh one.
+    ///
+    /// Returns `(old_sid, new_sid)` for each replaced subscription so the
+    /// caller can re-route its events. That includes subscriptions retired
+    /// because a replacement was granted their SID.
+    pub async fn revalidate_all(&self) -> Vec<(String, String)> {
+        let mut replaced = Vec::new();
+
+        for wrapper in self.list_subscriptions().await {
+            // Retired or replaced while earlier ones were being renewed
+            let current = self.get_subscription(wrapper.registration_id).await;
+            if !current.is_some_and(|current| Arc::ptr_eq(&current, &wrapper)) {
+                continue;
+            }
+            if self.renew_subscription(&wrapper).await.is_ok() {
+                continue;
+            }
+            let old_sid = wrapper.subscription_id().to_string();
+            match self.replace_subscription(&wrapper).await {
+                Ok(fresh) => {
+                    replaced.push((old_sid, fresh.subscription_id().to_string()));
+                }
+                Err(e) => {
+                    eprintln!(
+                        "❌ Failed to replace subscription for {} {:?}: {}",
+                        wrapper.speaker_service_pair.speaker_addr,
+                        wrapper.speaker_service_pair.service,
+                        e
+                    );
+                }
+            }
+        }
+
+        loop {
+            let retired = self.take_retired();
+            if retired.is_empty() {
+                break;
+            }
+            for record in retired {
+                let stale = record.subscription();
+                let old_sid = stale.subscription_id().to_string();
+                match self
+                    .create_subscription(stale.registration_id, stale.speaker_service_pair.clone())
+                    .await
+                {
+                    Ok(fresh) => {
+                        self.set_delivery(stale.registration_id, record.delivery());
+                        replaced.push((old_sid, fresh.subscription_id().to_string()));
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "❌ Failed to replace retired subscription for {} {:?}: {}",
+                            stale.speaker_service_pair.speaker_addr,
+                            stale.speaker_service_pair.service,
+                            e
+                        );
+                    }
+                }
+            }
         }
+
+        replaced
+    }
+
+    /// State, metadata and stable ID of every subscription
+    pub fn subscription_info(&self) -> Vec<SubscriptionInfo> {
+        self.subscriptions.snapshot()
+    }
+
+    /// Record that an event with this `SEQ` was received for a registration
+    pub fn record_event(&self, registration_id: RegistrationId, seq: Option<u32>) {
+        self.subscriptions.record_event(registration_id, seq);
+    }
+
+    /// Time since the last event of a registration
+    pub fn last_event_age(&self, registration_id: RegistrationId) -> Option<Duration> {
+        self.subscriptions.last_event_age(registration_id)
+    }
+
+    /// Set whether a registration's changes arrive as events or by polling
+    pub fn set_delivery(&self, registration_id: RegistrationId, delivery: DeliveryMode) {
+        self.subscriptions.set_delivery(registration_id, delivery);
     }
 
     /// Get statistics about managed subscriptions
     pub async fn stats(&self) -> SubscriptionStats {
-        let subscriptions = self.active_subscriptions.read().await;
-        let total_count = subscriptions.len();
+        let snapshot = self.subscriptions.snapshot();
+        let total_count = snapshot.len();
         let firewall_status = *self.firewall_status.read().await;
 
         let mut service_counts = HashMap::new();
         let mut polling_count = 0;
         let mut renewal_count = 0;
+        let mut connections_opened = HashMap::new();
 
-        for wrapper in subscriptions.values() {
-            *service_counts
-                .entry(wrapper.speaker_service_pair.service)
-                .or_insert(0) += 1;
+        for info in &snapshot {
+            let addr = info.pair.speaker_addr;
+            connections_opened
+                .entry(addr)
+                .or_insert_with(|| self.connections_opened(addr));
+            *service_counts.entry(info.pair.service).or_insert(0) += 1;
 
-            if wrapper.is_polling_active() {
+            if info.metadata.delivery == DeliveryMode::Polling {
                 polling_count += 1;
             }
 
-            renewal_count += wrapper.renewal_count().await;
+            renewal_count += info.metadata.renewals;
         }
 
         SubscriptionStats {
@@ -315,15 +838,16 @@ impl SubscriptionManager {
             service_breakdown: service_counts,
             polling_active_count: polling_count,
             total_renewals: renewal_count,
+            connections_opened,
             firewall_status,
         }
     }
 
     /// Shutdown all subscriptions
     pub async fn shutdown(&self) -> SubscriptionResult<()> {
-        let mut subscriptions = self.active_subscriptions.write().await;
-
-        for (registration_id, wrapper) in subscriptions.drain() {
+        for record in self.subscriptions.drain() {
+            let wrapper = record.subscription();
+            let registration_id = wrapper.registration_id;
             match wrapper.unsubscribe().await {
                 Ok(()) => {
                     eprintln!("✅ Unsubscribed {registration_id}");
@@ -345,6 +869,8 @@ pub struct SubscriptionStats {
     pub service_breakdown: HashMap<Service, usize>,
     pub polling_active_count: usize,
     pub total_renewals: u32,
+    /// TCP connections opened to each subscribed device
+    pub connections_opened: HashMap<SocketAddr, u64>,
     pub firewall_status: FirewallStatus,
 }
 
@@ -355,6 +881,10 @@ impl std::fmt::Display for SubscriptionStats {
         writeln!(f, "  Firewall status: {:?}", self.firewall_status)?;
         writeln!(f, "  Polling active: {}", self.polling_active_count)?;
         writeln!(f, "  Total renewals: {}", self.total_renewals)?;
+        writeln!(f, "  Connections opened:")?;
+        for (addr, count) in &self.connections_opened {
+            writeln!(f, "    {addr}: {count}")?;
+        }
         writeln!(f, "  Service breakdown:")?;
         for (service, count) in &self.service_breakdown {
             writeln!(f, "    {service:?}: {count}")?;
@@ -372,10 +902,11 @@ mod tests {
         // Note: We can't easily test ManagedSubscription creation without actual devices
         // So we'll test the basic wrapper functionality that doesn't require network calls
         let _reg_id = RegistrationId::new(1);
-        let pair = SpeakerServicePair::new("192.168.1.100".parse().unwrap(), Service::AVTransport);
+        let pair =
+            SpeakerServicePair::new("192.168.1.100:1400".parse().unwrap(), Service::AVTransport);
 
         // Basic tests for the pair functionality
-        assert_eq!(pair.speaker_ip.to_string(), "192.168.1.100");
+        assert_eq!(pair.speaker_addr.to_string(), "192.168.1.100:1400");
         assert_eq!(pair.service, Service::AVTransport);
     }
 
@@ -403,4 +934,320 @@ mod tests {
         assert_eq!(stats.polling_active_count, 0);
         assert_eq!(stats.firewall_status, FirewallStatus::Unknown);
     }
+
+    #[tokio::test]
+    async fn test_renewals_reuse_one_connection_per_device() {
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new().keep_alive());
+        let addr = device.addr();
+        let manager = SubscriptionManager::new("http://127.0.0.1:3400/callback".to_string())
+            .with_clock(Arc::new(device.clock()))
+            .with_eventing_connection(EventingConnection::KeepAlive);
+        let services = [
+            Service::AVTransport,
+            Service::RenderingControl,
+            Service::ZoneGroupTopology,
+        ];
+        for (id, service) in services.into_iter().enumerate() {
+            manager
+                .create_subscription(
+                    RegistrationId::new(id as u64 + 1),
+                    SpeakerServicePair::new(addr, service),
+                )
+                .await
+                .unwrap();
+        }
+
+        // Two full renewal cycles of the 1800s subscriptions
+        for _ in 0..2 {
+            device.advance(Duration::from_secs(1750));
+            assert_eq!(manager.check_renewals().await.unwrap(), 3);
+        }
+
+        assert_eq!(device.subscriptions(), 3);
+        assert_eq!(device.renewals(), 6);
+        assert_eq!(device.connections(), 1);
+        assert_eq!(manager.connections_opened(addr), 1);
+        assert_eq!(manager.stats().await.connections_opened[&addr], 1);
+    }
+
+    #[cfg(feature = "chaos-testing")]
+    #[tokio::test]
+    async fn test_chaos_fails_next_renewals_without_sending_them() {
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let chaos = crate::ChaosController::new(1);
+        let manager = SubscriptionManager::new("http://127.0.0.1:3400/callback".to_string())
+            .with_clock(Arc::new(device.clock()))
+            .with_chaos(chaos.hook());
+        let pair = SpeakerServicePair::new(device.addr(), Service::AVTransport);
+        manager
+            .create_subscription(RegistrationId::new(1), pair)
+            .await
+            .unwrap();
+
+        chaos.fail_renewals(2);
+        device.advance(Duration::from_secs(1750));
+        assert_eq!(manager.check_renewals().await.unwrap(), 0);
+        assert_eq!(manager.check_renewals().await.unwrap(), 0);
+        assert_eq!(device.renewals(), 0);
+        assert_eq!(manager.check_renewals().await.unwrap(), 1);
+        assert_eq!(device.renewals(), 1);
+        assert_eq!(chaos.stats().renewals_failed, 2);
+    }
+
+    #[tokio::test]
+    async fn test_clock_jump_revalidates_subscriptions() {
+        use sonos_api::mock::{Action, MockDevice, Scenario};
+
+        // The device forgets RenderingControl SIDs an hour into the sleep
+        let period = Duration::from_secs(150);
+        let device = MockDevice::start(
+            "127.0.0.1:0",
+            Scenario::new().at(
+                period + Duration::from_secs(3600),
+                Action::Expire(Service::RenderingControl),
+            ),
+        );
+        let manager = SubscriptionManager::new("http://127.0.0.1:3400/callback".to_string())
+            .with_clock(Arc::new(device.clock()));
+        let pair = |service| SpeakerServicePair::new(device.addr(), service);
+        let kept = manager
+            .create_subscription(RegistrationId::new(1), pair(Service::AVTransport))
+            .await
+            .unwrap();
+        let lost = manager
+            .create_subscription(RegistrationId::new(2), pair(Service::RenderingControl))
+            .await
+            .unwrap();
+        kept.record_event_received().await;
+
+        assert_eq!(manager.note_renewal_check(period).await, None);
+        device.advance(period);
+        assert_eq!(manager.note_renewal_check(period).await, None);
+
+        // The host sleeps for two hours
+        device.advance(Duration::from_secs(2 * 3600));
+        assert!(device.sids(Service::RenderingControl).is_empty());
+        assert_eq!(
+            manager.note_renewal_check(period).await,
+            Some(Duration::from_secs(2 * 3600))
+        );
+        assert_eq!(
+            kept.last_event_age().await,
+            Some(period + Duration::from_secs(2 * 3600))
+        );
+
+        let replaced = manager.revalidate_all().await;
+        assert_eq!(replaced.len(), 1);
+        assert_eq!(replaced[0].0, lost.subscription_id());
+        assert!(kept.is_active());
+        assert_eq!(kept.renewal_count().await, 1);
+
+        let fresh = manager
+            .get_subscription(RegistrationId::new(2))
+            .await
+            .unwrap();
+        assert_eq!(fresh.subscription_id(), replaced[0].1);
+        assert!(fresh.is_active());
+    }
+
+    #[tokio::test]
+    async fn test_renewal_check_replaces_forgotten_subscription() {
+        use sonos_api::mock::{Action, MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let manager = SubscriptionManager::new("http://127.0.0.1:3400/callback".to_string())
+            .with_clock(Arc::new(device.clock()));
+        let lost = manager
+            .create_subscription(
+                RegistrationId::new(1),
+                SpeakerServicePair::new(device.addr(), Service::RenderingControl),
+            )
+            .await
+            .unwrap();
+
+        device.perform(Action::Expire(Service::RenderingControl));
+        device.advance(Duration::from_secs(1750));
+        assert_eq!(manager.check_renewals().await.unwrap(), 0);
+
+        let replaced = manager.take_replaced();
+        let fresh = manager
+            .get_subscription(RegistrationId::new(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            replaced,
+            [(
+                lost.subscription_id().to_string(),
+                fresh.subscription_id().to_string()
+            )]
+        );
+        assert_eq!(
+            device.sids(Service::RenderingControl),
+            [fresh.subscription_id()]
+        );
+        assert!(manager.take_replaced().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wrapper_shares_registration_metadata() {
+        use sonos_api::mock::{Action, MockDevice, Scenario};
+
+        let device = MockDevice::start(
+            "127.0.0.1:0",
+            Scenario::new().at(
+                Duration::from_secs(60),
+                Action::Expire(Service::RenderingControl),
+            ),
+        );
+        let manager = SubscriptionManager::new("http://127.0.0.1:3400/callback".to_string())
+            .with_clock(Arc::new(device.clock()));
+        let id = RegistrationId::new(1);
+        let first = manager
+            .create_subscription(
+                id,
+                SpeakerServicePair::new(device.addr(), Service::RenderingControl),
+            )
+            .await
+            .unwrap();
+
+        manager.record_event(id, Some(0));
+        first.set_polling_active(true);
+        assert!(first.last_event_time().await.is_some());
+        assert_eq!(
+            manager.registry().get(id).unwrap().delivery(),
+            DeliveryMode::Polling
+        );
+
+        // The replacement picks up where the lost subscription left off
+        device.advance(Duration::from_secs(120));
+        assert_eq!(manager.revalidate_all().await.len(), 1);
+        let fresh = manager.get_subscription(id).await.unwrap();
+        assert_eq!(manager.registry().get(id).unwrap().metadata().migrations, 1);
+        assert_eq!(fresh.last_event_age().await, Some(Duration::from_secs(120)));
+        assert!(fresh.is_polling_active());
+    }
+
+    /// A subscription another component made to AVTransport on `device`,
+    /// registered in `directory`
+    fn external_subscription(
+        device: &sonos_api::mock::MockDevice,
+        directory: &SubscriptionDirectory,
+        callback_url: &str,
+    ) -> ManagedSubscription {
+        SonosClient::new()
+            .with_clock(Arc::new(device.clock()))
+            .with_subscription_directory(directory.clone())
+            .with_subscription_owner("legacy-app")
+            .subscribe(
+                &device.addr().to_string(),
+                Service::AVTransport,
+                callback_url,
+            )
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_directory_subscription_with_same_callback_is_adopted() {
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let directory = SubscriptionDirectory::new();
+        let external = external_subscription(&device, &directory, "http://127.0.0.1:3400/callback");
+        let manager = SubscriptionManager::new("http://127.0.0.1:3400/callback".to_string())
+            .with_clock(Arc::new(device.clock()))
+            .with_subscription_directory(directory.clone());
+
+        let adopted = manager
+            .create_subscription(
+                RegistrationId::new(1),
+                SpeakerServicePair::new(device.addr(), Service::AVTransport),
+            )
+            .await
+            .unwrap();
+        assert_eq!(adopted.subscription_id(), external.subscription_id());
+        assert_eq!(device.subscriptions(), 1);
+        assert!(manager
+            .get_subscription_by_sid(external.subscription_id())
+            .await
+            .is_some());
+
+        // The manager renews it from here on
+        device.advance(Duration::from_secs(1750));
+        assert_eq!(manager.check_renewals().await.unwrap(), 1);
+        assert_eq!(device.renewals(), 1);
+        assert!(external.is_active());
+
+        // Its own subscriptions are found by others, tagged with the manager
+        manager
+            .create_subscription(
+                RegistrationId::new(2),
+                SpeakerServicePair::new(device.addr(), Service::RenderingControl),
+            )
+            .await
+            .unwrap();
+        let own = directory
+            .find(&device.addr().to_string(), Service::RenderingControl)
+            .unwrap();
+        assert_eq!(own.owner(), Some(SUBSCRIPTION_OWNER));
+    }
+
+    #[tokio::test]
+    async fn test_directory_subscription_with_other_callback_conflicts() {
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let directory = SubscriptionDirectory::new();
+        let _external = external_subscription(&device, &directory, "http://127.0.0.1:3999/legacy");
+        let manager = SubscriptionManager::new("http://127.0.0.1:3400/callback".to_string())
+            .with_clock(Arc::new(device.clock()))
+            .with_subscription_directory(directory);
+
+        let result = manager
+            .create_subscription(
+                RegistrationId::new(1),
+                SpeakerServicePair::new(device.addr(), Service::AVTransport),
+            )
+            .await;
+        match result {
+            Err(SubscriptionError::AlreadySubscribedExternally {
+                speaker_addr,
+                service,
+                owner,
+                callback_url,
+            }) => {
+                assert_eq!(speaker_addr, device.addr());
+                assert_eq!(service, Service::AVTransport);
+                assert_eq!(owner.as_deref(), Some("legacy-app"));
+                assert_eq!(callback_url, "http://127.0.0.1:3999/legacy");
+            }
+            other => panic!("expected a conflict, got {other:?}"),
+        }
+        assert_eq!(device.subscriptions(), 1);
+        assert!(manager.list_subscriptions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_without_directory_subscriptions_are_independent() {
+        use sonos_api::mock::{MockDevice, Scenario};
+
+        let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+        let directory = SubscriptionDirectory::new();
+        let external = external_subscription(&device, &directory, "http://127.0.0.1:3999/legacy");
+        let manager = SubscriptionManager::new("http://127.0.0.1:3400/callback".to_string())
+            .with_clock(Arc::new(device.clock()));
+
+        let own = manager
+            .create_subscription(
+                RegistrationId::new(1),
+                SpeakerServicePair::new(device.addr(), Service::AVTransport),
+            )
+            .await
+            .unwrap();
+        assert_ne!(own.subscription_id(), external.subscription_id());
+        assert_eq!(device.subscriptions(), 2);
+    }
 }
@@ -6,6 +6,11 @@
 
 pub mod event_detector;
 pub mod manager;
+pub mod registry;
 
 pub use event_detector::EventDetector;
-pub use manager::{ManagedSubscriptionWrapper, SubscriptionManager};
+pub use manager::{ManagedSubscriptionRecord, ManagedSubscriptionWrapper, SubscriptionManager};
+pub use registry::{
+    DeliveryMode, RegistryEntry, SubscriptionInfo, SubscriptionMetadata, SubscriptionRecord,
+    SubscriptionRegistry, SubscriptionState,
+};
@@ -0,0 +1,903 @@
+//! Bookkeeping of active subscriptions
+//!
+//! [`SubscriptionRegistry`] owns the map from registration to its live UPnP
+//! subscription and is the only way to change it. Each entry is a
+//! [`SubscriptionRecord`]: the subscription, where it is in its lifecycle
+//! ([`SubscriptionState`]) and metadata that outlives it
+//! ([`SubscriptionMetadata`]: event SEQ, timings, delivery mode), which
+//! carries over when a registration migrates to a fresh subscription.
+//!
+//! Transitions are checked: a registration holds at most one
+//! subscription, only an active subscription starts a renewal, and a
+//! renewal ends on the record that started it. The registry doesn't talk
+//! to devices; [`SubscriptionManager`](super::SubscriptionManager) does
+//! that and records the outcome here.
+
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use sonos_api::{SharedClock, SystemClock};
+
+use crate::error::SubscriptionStateError;
+use crate::registry::{RegistrationId, SpeakerServicePair};
+use crate::stable_id::StableIds;
+
+/// What the registry needs from a subscription
+pub trait RegistryEntry: Send + Sync {
+    /// Registration the subscription belongs to
+    fn registration_id(&self) -> RegistrationId;
+
+    /// Device and service subscribed to
+    fn speaker_service_pair(&self) -> &SpeakerServicePair;
+
+    /// UPnP SID the device assigned
+    fn subscription_id(&self) -> &str;
+
+    /// Don't unsubscribe when dropped: the device no longer knows the SID,
+    /// or gave it to another subscription
+    fn detach(&self);
+
+    /// Called once the subscription is registered, with its registration's
+    /// metadata, shared with the records it migrates from and to
+    fn attach_metadata(&self, _metadata: Arc<Mutex<SubscriptionMetadata>>) {}
+}
+
+/// Where a subscription is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionState {
+    /// Receiving events
+    Active,
+    /// A renewal request is in flight
+    Renewing,
+    /// Replaced by a fresh subscription for the same registration
+    Superseded,
+    /// Dropped without UNSUBSCRIBE because the device reissued its SID or
+    /// rebooted
+    Retired,
+    /// Removed from the registry
+    Removed,
+}
+
+/// How a registration's changes currently reach the broker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeliveryMode {
+    /// UPnP NOTIFY events
+    #[default]
+    Events,
+    /// Polling, because events don't arrive
+    Polling,
+}
+
+/// Per-registration metadata, kept across migrations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionMetadata {
+    /// When the registration first subscribed
+    pub created_at: Instant,
+    /// When the last event arrived
+    pub last_event_at: Option<Instant>,
+    /// `SEQ` header of the last event, if it had one
+    pub last_seq: Option<u32>,
+    /// Events whose `SEQ` skipped ahead of the previous one
+    pub seq_gaps: u32,
+    /// When the last successful renewal finished
+    pub last_renewed_at: Option<Instant>,
+    /// Successful renewals
+    pub renewals: u32,
+    /// Times the registration moved to a fresh subscription
+    pub migrations: u32,
+    /// How changes are delivered
+    pub delivery: DeliveryMode,
+}
+
+impl SubscriptionMetadata {
+    fn new(created_at: Instant) -> Self {
+        Self {
+            created_at,
+            last_event_at: None,
+            last_seq: None,
+            seq_gaps: 0,
+            last_renewed_at: None,
+            renewals: 0,
+            migrations: 0,
+            delivery: DeliveryMode::Events,
+        }
+    }
+}
+
+/// A subscription with its state and metadata
+#[derive(Debug)]
+pub struct SubscriptionRecord<S> {
+    subscription: Arc<S>,
+    state: Mutex<SubscriptionState>,
+    /// Shared with the records this one migrated from or to
+    meta: Arc<Mutex<SubscriptionMetadata>>,
+    /// The registration's ID across restarts, carried over on migration
+    stable_id: Option<String>,
+}
+
+impl<S: RegistryEntry> SubscriptionRecord<S> {
+    fn new(
+        subscription: Arc<S>,
+        meta: Arc<Mutex<SubscriptionMetadata>>,
+        stable_id: Option<String>,
+    ) -> Self {
+        subscription.attach_metadata(Arc::clone(&meta));
+        Self {
+            subscription,
+            state: Mutex::new(SubscriptionState::Active),
+            meta,
+            stable_id,
+        }
+    }
+
+    /// The subscription
+    pub fn subscription(&self) -> &Arc<S> {
+        &self.subscription
+    }
+
+    /// Current lifecycle state
+    pub fn state(&self) -> SubscriptionState {
+        *self.lock_state()
+    }
+
+    /// Copy of the metadata
+    pub fn metadata(&self) -> SubscriptionMetadata {
+        self.lock_meta().clone()
+    }
+
+    /// ID of the speaker/service pair across restarts, when stable IDs are
+    /// enabled
+    pub fn stable_id(&self) -> Option<&str> {
+        self.stable_id.as_deref()
+    }
+
+    /// How changes are delivered
+    pub fn delivery(&self) -> DeliveryMode {
+        self.lock_meta().delivery
+    }
+
+    /// Point-in-time view for introspection
+    pub fn info(&self) -> SubscriptionInfo {
+        SubscriptionInfo {
+            registration_id: self.subscription.registration_id(),
+            pair: self.subscription.speaker_service_pair().clone(),
+            subscription_id: self.subscription.subscription_id().to_string(),
+            stable_id: self.stable_id.clone(),
+            state: self.state(),
+            metadata: self.metadata(),
+        }
+    }
+
+    fn owns(&self, subscription: &Arc<S>) -> bool {
+        Arc::ptr_eq(&self.subscription, subscription)
+    }
+
+    /// Move from one of `from` to `to`, or fail naming `action`
+    fn transition(
+        &self,
+        from: &[SubscriptionState],
+        to: SubscriptionState,
+        action: &'static str,
+    ) -> Result<(), SubscriptionStateError> {
+        let mut state = self.lock_state();
+        if !from.contains(&state) {
+            return Err(SubscriptionStateError::InvalidTransition {
+                registration_id: self.subscription.registration_id(),
+                state: *state,
+                action,
+            });
+        }
+        *state = to;
+        Ok(())
+    }
+
+    /// Leave the registry for good in `to`
+    fn finish(&self, to: SubscriptionState) {
+        *self.lock_state() = to;
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, SubscriptionState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_meta(&self) -> MutexGuard<'_, SubscriptionMetadata> {
+        self.meta.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Snapshot of one record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionInfo {
+    pub registration_id: RegistrationId,
+    pub pair: SpeakerServicePair,
+    pub subscription_id: String,
+    /// Stays the same across restarts and SID changes; `None` unless
+    /// `BrokerConfig::stable_id_file` is set
+    pub stable_id: Option<String>,
+    pub state: SubscriptionState,
+    pub metadata: SubscriptionMetadata,
+}
+
+type Records<S> = HashMap<RegistrationId, Arc<SubscriptionRecord<S>>>;
+
+/// The active subscription of each registration
+///
+/// Reads load a copy-on-write snapshot without locking, since they happen
+/// on every NOTIFY; changes are serialised so each checks and applies its
+/// precondition atomically.
+pub struct SubscriptionRegistry<S> {
+    records: ArcSwap<Records<S>>,
+
+    /// Held by every change of `records`
+    writer: Mutex<()>,
+
+    /// Records retired because their device reissued their SID, waiting to
+    /// be resubscribed
+    retired: Mutex<Vec<Arc<SubscriptionRecord<S>>>>,
+
+    /// Time source for the metadata
+    clock: SharedClock,
+
+    /// Stable IDs given to new registrations
+    stable_ids: Arc<StableIds>,
+}
+
+impl<S: RegistryEntry> Default for SubscriptionRegistry<S> {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock))
+    }
+}
+
+impl<S: RegistryEntry> SubscriptionRegistry<S> {
+    /// An empty registry timing its metadata on `clock`
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            records: ArcSwap::from_pointee(HashMap::new()),
+            writer: Mutex::new(()),
+            retired: Mutex::new(Vec::new()),
+            clock,
+            stable_ids: Arc::default(),
+        }
+    }
+
+    /// Give each registration its stable ID from `stable_ids`
+    pub fn with_stable_ids(mut self, stable_ids: Arc<StableIds>) -> Self {
+        self.stable_ids = stable_ids;
+        self
+    }
+
+    /// Add the first subscription of a registration
+    ///
+    /// Fails if the registration already has one; replace it with
+    /// [`migrate()`](Self::migrate). Other subscriptions to the same device
+    /// holding the new SID are retired (see [`take_retired()`](Self::take_retired)).
+    pub fn insert_new(
+        &self,
+        subscription: Arc<S>,
+    ) -> Result<Arc<SubscriptionRecord<S>>, SubscriptionStateError> {
+        let registration_id = subscription.registration_id();
+        let _writer = self.lock_writer();
+        if self.records.load().contains_key(&registration_id) {
+            return Err(SubscriptionStateError::AlreadySubscribed(registration_id));
+        }
+        let meta = Arc::new(Mutex::new(SubscriptionMetadata::new(self.clock.now())));
+        let pair = subscription.speaker_service_pair();
+        let stable_id = self.stable_ids.id(pair.speaker_addr, pair.service);
+        let record = Arc::new(SubscriptionRecord::new(subscription, meta, stable_id));
+        self.store(Arc::clone(&record));
+        Ok(record)
+    }
+
+    /// Replace a registration's subscription with `fresh`, keeping its
+    /// metadata
+    ///
+    /// Returns the superseded record. Its subscription is detached if the
+    /// device handed its SID to `fresh`; otherwise dropping it unsubscribes.
+    pub fn migrate(
+        &self,
+        fresh: Arc<S>,
+    ) -> Result<Arc<SubscriptionRecord<S>>, SubscriptionStateError> {
+        let registration_id = fresh.registration_id();
+        let _writer = self.lock_writer();
+        let previous = self
+            .records
+            .load()
+            .get(&registration_id)
+            .cloned()
+            .ok_or(SubscriptionStateError::NotSubscribed(registration_id))?;
+        if previous.owns(&fresh) {
+            return Err(SubscriptionStateError::InvalidTransition {
+                registration_id,
+                state: previous.state(),
+                action: "migrate to itself",
+            });
+        }
+        previous.transition(
+            &[SubscriptionState::Active, SubscriptionState::Renewing],
+            SubscriptionState::Superseded,
+            "migrate",
+        )?;
+        if reissued(previous.subscription.as_ref(), fresh.as_ref()) {
+            previous.subscription.detach();
+        }
+        previous.lock_meta().migrations += 1;
+        let record = Arc::new(SubscriptionRecord::new(
+            fresh,
+            Arc::clone(&previous.meta),
+            previous.stable_id.clone(),
+        ));
+        self.store(record);
+        Ok(previous)
+    }
+
+    /// Store `record`, retiring subscriptions its SID was reissued from
+    ///
+    /// A device that reboots can hand out a SID it gave an older
+    /// subscription; that one is dead on the device, so it's retired rather
+    /// than left to steal the new events, and detached so dropping it
+    /// doesn't cancel the new holder. Callers hold the writer lock.
+    fn store(&self, record: Arc<SubscriptionRecord<S>>) {
+        let registration_id = record.subscription.registration_id();
+        let mut records = Records::clone(&self.records.load());
+        let stale: Vec<_> = records
+            .iter()
+            .filter(|(id, other)| {
+                **id != registration_id
+                    && reissued(other.subscription.as_ref(), record.subscription.as_ref())
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        let mut retired = Vec::new();
+        for id in stale {
+            if let Some(other) = records.remove(&id) {
+                other.subscription.detach();
+                other.finish(SubscriptionState::Retired);
+                retired.push(other);
+            }
+        }
+        records.insert(registration_id, record);
+        self.records.store(Arc::new(records));
+        self.lock_retired().extend(retired);
+    }
+
+    /// Start renewing `subscription`
+    ///
+    /// Fails unless it is the registration's current subscription and
+    /// active, so one renewal runs at a time.
+    pub fn begin_renewal(&self, subscription: &Arc<S>) -> Result<(), SubscriptionStateError> {
+        self.current(subscription)?.transition(
+            &[SubscriptionState::Active],
+            SubscriptionState::Renewing,
+            "begin renewal",
+        )
+    }
+
+    /// Finish a renewal started with [`begin_renewal()`](Self::begin_renewal)
+    ///
+    /// Either way the subscription is active again; a successful renewal
+    /// is counted. Fails if the subscription was replaced or dropped
+    /// meanwhile.
+    pub fn end_renewal(
+        &self,
+        subscription: &Arc<S>,
+        renewed: bool,
+    ) -> Result<(), SubscriptionStateError> {
+        let record = self.current(subscription)?;
+        record.transition(
+            &[SubscriptionState::Renewing],
+            SubscriptionState::Active,
+            "end renewal",
+        )?;
+        if renewed {
+            let mut meta = record.lock_meta();
+            meta.renewals += 1;
+            meta.last_renewed_at = Some(self.clock.now());
+        }
+        Ok(())
+    }
+
+    /// Remove a registration's subscription, returning its final record
+    pub fn remove(
+        &self,
+        registration_id: RegistrationId,
+    ) -> Result<Arc<SubscriptionRecord<S>>, SubscriptionStateError> {
+        let _writer = self.lock_writer();
+        let mut records = Records::clone(&self.records.load());
+        let record = records
+            .remove(&registration_id)
+            .ok_or(SubscriptionStateError::NotSubscribed(registration_id))?;
+        self.records.store(Arc::new(records));
+        record.finish(SubscriptionState::Removed);
+        Ok(record)
+    }
+
+    /// Remove every subscription, returning their final records
+    pub fn drain(&self) -> Vec<Arc<SubscriptionRecord<S>>> {
+        let _writer = self.lock_writer();
+        let records = self.records.swap(Arc::default());
+        records
+            .values()
+            .map(|record| {
+                record.finish(SubscriptionState::Removed);
+                Arc::clone(record)
+            })
+            .collect()
+    }
+
+    /// Drop every subscription to the device at `addr` without
+    /// unsubscribing, because it rebooted and no longer knows their SIDs
+    ///
+    /// Returns the retired records.
+    pub fn retire_device(&self, addr: SocketAddr) -> Vec<Arc<SubscriptionRecord<S>>> {
+        let _writer = self.lock_writer();
+        let mut records = Records::clone(&self.records.load());
+        let on_device: Vec<_> = records
+            .iter()
+            .filter(|(_, record)| record.subscription.speaker_service_pair().speaker_addr == addr)
+            .map(|(id, _)| *id)
+            .collect();
+        let retired: Vec<_> = on_device
+            .into_iter()
+            .filter_map(|id| records.remove(&id))
+            .collect();
+        self.records.store(Arc::new(records));
+        for record in &retired {
+            record.subscription.detach();
+            record.finish(SubscriptionState::Retired);
+        }
+        retired
+    }
+
+    /// Records retired since the last call because their device reissued
+    /// their SID to another subscription
+    pub fn take_retired(&self) -> Vec<Arc<SubscriptionRecord<S>>> {
+        std::mem::take(&mut *self.lock_retired())
+    }
+
+    /// The record of a registration
+    pub fn get(&self, registration_id: RegistrationId) -> Option<Arc<SubscriptionRecord<S>>> {
+        self.records.load().get(&registration_id).cloned()
+    }
+
+    /// The records whose subscription has this SID
+    pub fn with_sid(&self, subscription_id: &str) -> Vec<Arc<SubscriptionRecord<S>>> {
+        self.records
+            .load()
+            .values()
+            .filter(|record| record.subscription.subscription_id() == subscription_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Every record
+    pub fn records(&self) -> Vec<Arc<SubscriptionRecord<S>>> {
+        self.records.load().values().cloned().collect()
+    }
+
+    /// Every record's state and metadata
+    pub fn snapshot(&self) -> Vec<SubscriptionInfo> {
+        self.records
+            .load()
+            .values()
+            .map(|record| record.info())
+            .collect()
+    }
+
+    /// Number of registrations with a subscription
+    pub fn len(&self) -> usize {
+        self.records.load().len()
+    }
+
+    /// Whether no registration has a subscription
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Note an event for a registration, with its `SEQ` header
+    ///
+    /// Returns `false` if the registration has no subscription.
+    pub fn record_event(&self, registration_id: RegistrationId, seq: Option<u32>) -> bool {
+        let Some(record) = self.get(registration_id) else {
+            return false;
+        };
+        let mut meta = record.lock_meta();
+        meta.last_event_at = Some(self.clock.now());
+        if let (Some(previous), Some(seq)) = (meta.last_seq, seq) {
+            if seq > previous.wrapping_add(1) {
+                meta.seq_gaps += 1;
+            }
+        }
+        if seq.is_some() {
+            meta.last_seq = seq;
+        }
+        true
+    }
+
+    /// Time since a registration's last event
+    pub fn last_event_age(&self, registration_id: RegistrationId) -> Option<Duration> {
+        let last_event_at = self.get(registration_id)?.lock_meta().last_event_at?;
+        Some(self.clock.now().saturating_duration_since(last_event_at))
+    }
+
+    /// Set how a registration's changes are delivered
+    ///
+    /// Returns `false` if the registration has no subscription.
+    pub fn set_delivery(&self, registration_id: RegistrationId, delivery: DeliveryMode) -> bool {
+        let Some(record) = self.get(registration_id) else {
+            return false;
+        };
+        record.lock_meta().delivery = delivery;
+        true
+    }
+
+    /// The record if `subscription` is still its registration's current one
+    fn current(
+        &self,
+        subscription: &Arc<S>,
+    ) -> Result<Arc<SubscriptionRecord<S>>, SubscriptionStateError> {
+        let registration_id = subscription.registration_id();
+        let record = self
+            .get(registration_id)
+            .ok_or(SubscriptionStateError::NotSubscribed(registration_id))?;
+        if !record.owns(subscription) {
+            return Err(SubscriptionStateError::Superseded(registration_id));
+        }
+        Ok(record)
+    }
+
+    fn lock_writer(&self) -> MutexGuard<'_, ()> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_retired(&self) -> MutexGuard<'_, Vec<Arc<SubscriptionRecord<S>>>> {
+        self.retired.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Whether the device handed `fresh` the SID `other` had
+fn reissued<S: RegistryEntry>(other: &S, fresh: &S) -> bool {
+    other.speaker_service_pair().speaker_addr == fresh.speaker_service_pair().speaker_addr
+        && other.subscription_id() == fresh.subscription_id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sonos_api::Service;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[derive(Debug)]
+    struct FakeSubscription {
+        registration_id: RegistrationId,
+        pair: SpeakerServicePair,
+        sid: String,
+        detached: AtomicBool,
+    }
+
+    impl RegistryEntry for FakeSubscription {
+        fn registration_id(&self) -> RegistrationId {
+            self.registration_id
+        }
+
+        fn speaker_service_pair(&self) -> &SpeakerServicePair {
+            &self.pair
+        }
+
+        fn subscription_id(&self) -> &str {
+            &self.sid
+        }
+
+        fn detach(&self) {
+            self.detached.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn subscription(id: u64, addr: &str, service: Service, sid: &str) -> Arc<FakeSubscription> {
+        Arc::new(FakeSubscription {
+            registration_id: RegistrationId::new(id),
+            pair: SpeakerServicePair::new(addr.parse().unwrap(), service),
+            sid: sid.to_string(),
+            detached: AtomicBool::new(false),
+        })
+    }
+
+    fn registry() -> SubscriptionRegistry<FakeSubscription> {
+        SubscriptionRegistry::default()
+    }
+
+    const LIVING_ROOM: &str = "192.168.1.10:1400";
+    const KITCHEN: &str = "192.168.1.11:1400";
+
+    #[test]
+    fn test_insert_new_rejects_second_subscription() {
+        let registry = registry();
+        let first = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        registry.insert_new(Arc::clone(&first)).unwrap();
+
+        let second = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:b");
+        assert_eq!(
+            registry.insert_new(second).unwrap_err(),
+            SubscriptionStateError::AlreadySubscribed(RegistrationId::new(1))
+        );
+        let current = registry.get(RegistrationId::new(1)).unwrap();
+        assert!(Arc::ptr_eq(current.subscription(), &first));
+        assert_eq!(current.state(), SubscriptionState::Active);
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_renewal_runs_one_at_a_time() {
+        let registry = registry();
+        let sub = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        registry.insert_new(Arc::clone(&sub)).unwrap();
+
+        registry.begin_renewal(&sub).unwrap();
+        assert!(matches!(
+            registry.begin_renewal(&sub),
+            Err(SubscriptionStateError::InvalidTransition {
+                state: SubscriptionState::Renewing,
+                ..
+            })
+        ));
+        registry.end_renewal(&sub, true).unwrap();
+        assert!(matches!(
+            registry.end_renewal(&sub, true),
+            Err(SubscriptionStateError::InvalidTransition {
+                state: SubscriptionState::Active,
+                ..
+            })
+        ));
+
+        // A failed renewal isn't counted, but the subscription is usable again
+        registry.begin_renewal(&sub).unwrap();
+        registry.end_renewal(&sub, false).unwrap();
+
+        let record = registry.get(RegistrationId::new(1)).unwrap();
+        assert_eq!(record.state(), SubscriptionState::Active);
+        assert_eq!(record.metadata().renewals, 1);
+        assert!(record.metadata().last_renewed_at.is_some());
+    }
+
+    #[test]
+    fn test_renewal_of_replaced_subscription_is_rejected() {
+        let registry = registry();
+        let old = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        registry.insert_new(Arc::clone(&old)).unwrap();
+        registry.begin_renewal(&old).unwrap();
+
+        // Replaced while its renewal is in flight
+        let fresh = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:b");
+        let superseded = registry.migrate(Arc::clone(&fresh)).unwrap();
+        assert_eq!(superseded.state(), SubscriptionState::Superseded);
+        assert_eq!(
+            registry.end_renewal(&old, true).unwrap_err(),
+            SubscriptionStateError::Superseded(RegistrationId::new(1))
+        );
+        assert_eq!(
+            registry.begin_renewal(&old).unwrap_err(),
+            SubscriptionStateError::Superseded(RegistrationId::new(1))
+        );
+        assert_eq!(
+            registry
+                .get(RegistrationId::new(1))
+                .unwrap()
+                .metadata()
+                .renewals,
+            0
+        );
+
+        registry.remove(RegistrationId::new(1)).unwrap();
+        assert_eq!(
+            registry.begin_renewal(&fresh).unwrap_err(),
+            SubscriptionStateError::NotSubscribed(RegistrationId::new(1))
+        );
+    }
+
+    #[test]
+    fn test_migration_keeps_metadata() {
+        let registry = registry();
+        let old = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        registry.insert_new(Arc::clone(&old)).unwrap();
+        registry.set_delivery(RegistrationId::new(1), DeliveryMode::Polling);
+        registry.record_event(RegistrationId::new(1), Some(4));
+        registry.begin_renewal(&old).unwrap();
+        registry.end_renewal(&old, true).unwrap();
+        let created_at = registry
+            .get(RegistrationId::new(1))
+            .unwrap()
+            .metadata()
+            .created_at;
+
+        let fresh = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:b");
+        let superseded = registry.migrate(Arc::clone(&fresh)).unwrap();
+        assert!(Arc::ptr_eq(superseded.subscription(), &old));
+        // A different SID, so dropping the old one may unsubscribe it
+        assert!(!old.detached.load(Ordering::SeqCst));
+
+        let record = registry.get(RegistrationId::new(1)).unwrap();
+        assert!(Arc::ptr_eq(record.subscription(), &fresh));
+        assert_eq!(record.state(), SubscriptionState::Active);
+        let meta = record.metadata();
+        assert_eq!(meta.created_at, created_at);
+        assert_eq!(meta.delivery, DeliveryMode::Polling);
+        assert_eq!(meta.last_seq, Some(4));
+        assert_eq!(meta.renewals, 1);
+        assert_eq!(meta.migrations, 1);
+    }
+
+    #[test]
+    fn test_migration_needs_a_current_subscription() {
+        let registry = registry();
+        let sub = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        assert_eq!(
+            registry.migrate(Arc::clone(&sub)).unwrap_err(),
+            SubscriptionStateError::NotSubscribed(RegistrationId::new(1))
+        );
+
+        registry.insert_new(Arc::clone(&sub)).unwrap();
+        assert!(matches!(
+            registry.migrate(Arc::clone(&sub)),
+            Err(SubscriptionStateError::InvalidTransition { .. })
+        ));
+        assert_eq!(
+            registry.get(RegistrationId::new(1)).unwrap().state(),
+            SubscriptionState::Active
+        );
+    }
+
+    #[test]
+    fn test_migration_to_same_sid_detaches_old_subscription() {
+        let registry = registry();
+        let old = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        registry.insert_new(Arc::clone(&old)).unwrap();
+
+        let fresh = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        registry.migrate(fresh).unwrap();
+        assert!(old.detached.load(Ordering::SeqCst));
+        assert!(registry.take_retired().is_empty());
+    }
+
+    #[test]
+    fn test_reissued_sid_retires_other_registration() {
+        let registry = registry();
+        let rendering = subscription(1, LIVING_ROOM, Service::RenderingControl, "uuid:a");
+        let kitchen = subscription(2, KITCHEN, Service::AVTransport, "uuid:a");
+        registry.insert_new(Arc::clone(&rendering)).unwrap();
+        registry.insert_new(Arc::clone(&kitchen)).unwrap();
+
+        // The living room rebooted and gave its old SID to a new subscription
+        let transport = subscription(3, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        registry.insert_new(transport).unwrap();
+
+        let retired = registry.take_retired();
+        assert_eq!(retired.len(), 1);
+        assert!(Arc::ptr_eq(retired[0].subscription(), &rendering));
+        assert_eq!(retired[0].state(), SubscriptionState::Retired);
+        assert!(rendering.detached.load(Ordering::SeqCst));
+        assert!(registry.get(RegistrationId::new(1)).is_none());
+
+        // SIDs are per device: the kitchen's stays
+        assert!(!kitchen.detached.load(Ordering::SeqCst));
+        assert_eq!(registry.with_sid("uuid:a").len(), 2);
+        assert!(registry.take_retired().is_empty());
+    }
+
+    #[test]
+    fn test_retire_device_detaches_its_subscriptions() {
+        let registry = registry();
+        let transport = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        let rendering = subscription(2, LIVING_ROOM, Service::RenderingControl, "uuid:b");
+        let kitchen = subscription(3, KITCHEN, Service::AVTransport, "uuid:c");
+        for sub in [&transport, &rendering, &kitchen] {
+            registry.insert_new(Arc::clone(sub)).unwrap();
+        }
+        registry.set_delivery(RegistrationId::new(2), DeliveryMode::Polling);
+
+        let mut retired = registry.retire_device(LIVING_ROOM.parse().unwrap());
+        retired.sort_by_key(|record| record.subscription().sid.clone());
+        assert_eq!(retired.len(), 2);
+        assert!(retired
+            .iter()
+            .all(|record| record.state() == SubscriptionState::Retired));
+        assert_eq!(retired[1].delivery(), DeliveryMode::Polling);
+        assert!(transport.detached.load(Ordering::SeqCst));
+        assert!(rendering.detached.load(Ordering::SeqCst));
+        assert!(!kitchen.detached.load(Ordering::SeqCst));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_returns_final_record() {
+        let registry = registry();
+        let sub = subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a");
+        registry.insert_new(Arc::clone(&sub)).unwrap();
+        registry.record_event(RegistrationId::new(1), Some(0));
+
+        let record = registry.remove(RegistrationId::new(1)).unwrap();
+        assert!(Arc::ptr_eq(record.subscription(), &sub));
+        assert_eq!(record.state(), SubscriptionState::Removed);
+        assert_eq!(record.metadata().last_seq, Some(0));
+        assert!(registry.is_empty());
+        assert_eq!(
+            registry.remove(RegistrationId::new(1)).unwrap_err(),
+            SubscriptionStateError::NotSubscribed(RegistrationId::new(1))
+        );
+        assert!(!registry.record_event(RegistrationId::new(1), Some(1)));
+        assert!(!registry.set_delivery(RegistrationId::new(1), DeliveryMode::Polling));
+
+        // The registration can subscribe afresh
+        registry.insert_new(sub).unwrap();
+        assert_eq!(
+            registry
+                .get(RegistrationId::new(1))
+                .unwrap()
+                .metadata()
+                .last_seq,
+            None
+        );
+    }
+
+    #[test]
+    fn test_drain_removes_everything() {
+        let registry = registry();
+        registry
+            .insert_new(subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a"))
+            .unwrap();
+        registry
+            .insert_new(subscription(2, KITCHEN, Service::AVTransport, "uuid:b"))
+            .unwrap();
+
+        let drained = registry.drain();
+        assert_eq!(drained.len(), 2);
+        assert!(drained
+            .iter()
+            .all(|record| record.state() == SubscriptionState::Removed));
+        assert!(registry.is_empty());
+        assert!(registry.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_event_seq_tracking() {
+        let registry = registry();
+        registry
+            .insert_new(subscription(1, LIVING_ROOM, Service::AVTransport, "uuid:a"))
+            .unwrap();
+        let id = RegistrationId::new(1);
+        assert_eq!(registry.last_event_age(id), None);
+
+        for seq in [Some(0), Some(1), None, Some(2), Some(5), Some(6)] {
+            assert!(registry.record_event(id, seq));
+        }
+        let meta = registry.get(id).unwrap().metadata();
+        assert_eq!(meta.last_seq, Some(6));
+        assert_eq!(meta.seq_gaps, 1);
+        assert!(meta.last_event_at.is_some());
+        assert!(registry.last_event_age(id).is_some());
+    }
+
+    #[test]
+    fn test_snapshot_reflects_state_and_metadata() {
+        let registry = registry();
+        let sub = subscription(7, LIVING_ROOM, Service::ZoneGroupTopology, "uuid:z");
+        registry.insert_new(Arc::clone(&sub)).unwrap();
+        registry.begin_renewal(&sub).unwrap();
+        registry.set_delivery(RegistrationId::new(7), DeliveryMode::Polling);
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        let info = &snapshot[0];
+        assert_eq!(info.registration_id, RegistrationId::new(7));
+        assert_eq!(info.pair.service, Service::ZoneGroupTopology);
+        assert_eq!(info.subscription_id, "uuid:z");
+        assert_eq!(info.state, SubscriptionState::Renewing);
+        assert_eq!(info.metadata.delivery, DeliveryMode::Polling);
+
+        // Snapshots are copies
+        registry.end_renewal(&sub, true).unwrap();
+        assert_eq!(info.state, SubscriptionState::Renewing);
+        assert_eq!(registry.snapshot()[0].metadata.renewals, 1);
+    }
+}
@@ -0,0 +1,112 @@
+//! Chaos tests: faults injected into the broker's own internals must be
+//! caught by its missed-event detection and its event-silence watchdog
+//!
+//! Run with `cargo test -p sonos-sdk-stream --features chaos-testing`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sonos_api::mock::{Action, MockDevice, Scenario};
+use sonos_stream::chaos::EventFate;
+use sonos_stream::subscription::{DeliveryMode, SubscriptionInfo};
+use sonos_stream::{BrokerConfig, ChaosController, EventBroker, Service};
+
+fn transport_notify() -> Action {
+    Action::Notify {
+        service: Service::AVTransport,
+        body: r#"<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0"><e:property><LastChange>&lt;Event xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/AVT/&quot;&gt;&lt;InstanceID val=&quot;0&quot;&gt;&lt;TransportState val=&quot;PLAYING&quot;/&gt;&lt;/InstanceID&gt;&lt;/Event&gt;</LastChange></e:property></e:propertyset>"#.to_string(),
+    }
+}
+
+/// A broker on the device's clock, subscribed to its AVTransport
+async fn subscribed_broker(
+    device: &MockDevice,
+    chaos: &ChaosController,
+    event_timeout: Duration,
+) -> EventBroker {
+    let config = BrokerConfig::no_firewall_detection()
+        .with_clock(Arc::new(device.clock()))
+        .with_event_timeout(event_timeout)
+        .with_chaos(chaos);
+    let broker = EventBroker::new(config).await.unwrap();
+    broker
+        .register_speaker_service(device.addr(), Service::AVTransport)
+        .await
+        .unwrap();
+    broker
+}
+
+fn subscription(broker: &EventBroker) -> SubscriptionInfo {
+    broker.subscriptions().remove(0)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dropped_events_are_detected_as_seq_gaps() {
+    let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let chaos = ChaosController::new(7);
+    let broker = subscribed_broker(&device, &chaos, Duration::from_secs(3600)).await;
+
+    chaos.drop_events(0.3);
+    for _ in 0..20 {
+        device.perform(transport_notify());
+    }
+    broker.flush_notifications().await;
+
+    // The same seed picks the same events: every run of drops after the
+    // first delivered event is one gap
+    let replay = ChaosController::new(7);
+    replay.drop_events(0.3);
+    let hook = replay.hook();
+    let fates: Vec<_> = (0..20).map(|_| hook.event_fate()).collect();
+    let first = fates.iter().position(|f| *f == EventFate::Deliver).unwrap();
+    let expected_gaps = fates[first..]
+        .windows(2)
+        .filter(|w| w[0] == EventFate::Drop && w[1] == EventFate::Deliver)
+        .count();
+
+    let dropped = chaos.stats().events_dropped;
+    assert_eq!(dropped, replay.stats().events_dropped);
+    assert!(dropped > 0 && expected_gaps > 0);
+    assert_eq!(
+        subscription(&broker).metadata.seq_gaps as usize,
+        expected_gaps
+    );
+    broker.shutdown().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn watchdog_falls_back_to_polling_when_events_go_missing() {
+    let device = MockDevice::start("127.0.0.1:0", Scenario::new());
+    let chaos = ChaosController::new(7);
+    let broker = subscribed_broker(&device, &chaos, Duration::from_secs(10)).await;
+
+    // Events every 5s keep the subscription on events well past the timeout
+    for _ in 0..4 {
+        device.perform(transport_notify());
+        broker.flush_notifications().await;
+        device.advance(Duration::from_secs(5));
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        subscription(&broker).metadata.delivery,
+        DeliveryMode::Events
+    );
+
+    // The device keeps notifying, but nothing reaches the processor
+    chaos.drop_events(1.0);
+    for _ in 0..4 {
+        device.perform(transport_notify());
+        broker.flush_notifications().await;
+        device.advance(Duration::from_secs(5));
+    }
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while subscription(&broker).metadata.delivery != DeliveryMode::Polling {
+        assert!(
+            Instant::now() < deadline,
+            "watchdog never switched to polling"
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(chaos.stats().events_dropped, 4);
+    broker.shutdown().await.unwrap();
+}